@@ -0,0 +1,140 @@
+//! HTTP account API.
+//!
+//! Wraps the server's `/api/register`, `/api/login` and `/api/refresh`
+//! endpoints and builds the matching `/ws` URL for [`Connection`](crate::Connection).
+
+use message::UserId;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::{Connection, reject_tls};
+use crate::error::ClientError;
+
+/// Account credentials returned by register, login and refresh.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+  /// Account user ID.
+  pub user_id: UserId,
+  /// JWT token for the `TokenAuth` signaling message.
+  pub token: String,
+  /// Single-use token for [`ApiClient::refresh`].
+  #[serde(default)]
+  pub refresh_token: Option<String>,
+  /// Lifetime of `token` in seconds.
+  #[serde(default)]
+  pub expires_in: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct AuthRequest<'a> {
+  username: &'a str,
+  password: &'a str,
+}
+
+#[derive(Serialize)]
+struct RefreshRequest<'a> {
+  refresh_token: &'a str,
+}
+
+#[derive(Deserialize)]
+struct AuthErrorBody {
+  error: String,
+}
+
+/// Client for the server's HTTP API.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+  base_url: String,
+  http: reqwest::Client,
+}
+
+impl ApiClient {
+  /// Create a client for the server at `base_url` (e.g. `http://127.0.0.1:3000`).
+  pub fn new(base_url: impl Into<String>) -> Self {
+    let base_url = base_url.into().trim_end_matches('/').to_string();
+    Self {
+      base_url,
+      http: reqwest::Client::new(),
+    }
+  }
+
+  /// Server base URL without a trailing slash.
+  pub fn base_url(&self) -> &str {
+    &self.base_url
+  }
+
+  /// WebSocket signaling URL derived from the base URL.
+  ///
+  /// `http` maps to `ws` and `https` to `wss`; any other scheme is kept.
+  pub fn ws_url(&self) -> String {
+    let ws_base = if let Some(rest) = self.base_url.strip_prefix("https://") {
+      format!("wss://{rest}")
+    } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+      format!("ws://{rest}")
+    } else {
+      self.base_url.clone()
+    };
+    format!("{ws_base}/ws")
+  }
+
+  /// Register a new account.
+  pub async fn register(&self, username: &str, password: &str) -> Result<Credentials, ClientError> {
+    self
+      .post_auth("/api/register", &AuthRequest { username, password })
+      .await
+  }
+
+  /// Log in to an existing account.
+  ///
+  /// Starts a new session; other devices stay signed in unless the
+  /// account is at the server's session limit, which ends the least
+  /// recently used one.
+  pub async fn login(&self, username: &str, password: &str) -> Result<Credentials, ClientError> {
+    self
+      .post_auth("/api/login", &AuthRequest { username, password })
+      .await
+  }
+
+  /// Exchange a refresh token for new credentials.
+  ///
+  /// The presented refresh token is consumed; use the one in the
+  /// returned credentials next time. Replaying a consumed token ends
+  /// the session on the server.
+  pub async fn refresh(&self, refresh_token: &str) -> Result<Credentials, ClientError> {
+    self
+      .post_auth("/api/refresh", &RefreshRequest { refresh_token })
+      .await
+  }
+
+  /// Open a signaling connection and authenticate it with `token`.
+  pub async fn connect(&self, token: &str) -> Result<Connection, ClientError> {
+    let mut connection = Connection::connect(&self.ws_url()).await?;
+    connection.authenticate(token).await?;
+    Ok(connection)
+  }
+
+  async fn post_auth(&self, path: &str, body: &impl Serialize) -> Result<Credentials, ClientError> {
+    reject_tls(&self.base_url)?;
+    let response = self
+      .http
+      .post(format!("{}{path}", self.base_url))
+      .json(body)
+      .send()
+      .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+      let message = match response.json::<AuthErrorBody>().await {
+        Ok(body) => body.error,
+        Err(_) => status.canonical_reason().unwrap_or_default().to_string(),
+      };
+      return Err(ClientError::Api {
+        status: status.as_u16(),
+        message,
+      });
+    }
+    Ok(response.json().await?)
+  }
+}
+
+#[cfg(test)]
+mod tests;
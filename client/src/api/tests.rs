@@ -0,0 +1,53 @@
+use super::*;
+
+#[test]
+fn test_base_url_trailing_slash_is_trimmed() {
+  let client = ApiClient::new("http://127.0.0.1:3000/");
+  assert_eq!(client.base_url(), "http://127.0.0.1:3000");
+}
+
+#[test]
+fn test_ws_url_from_http() {
+  let client = ApiClient::new("http://127.0.0.1:3000");
+  assert_eq!(client.ws_url(), "ws://127.0.0.1:3000/ws");
+}
+
+#[test]
+fn test_ws_url_from_https() {
+  let client = ApiClient::new("https://chat.example.com");
+  assert_eq!(client.ws_url(), "wss://chat.example.com/ws");
+}
+
+#[test]
+fn test_ws_url_keeps_ws_scheme() {
+  let client = ApiClient::new("ws://localhost:3000");
+  assert_eq!(client.ws_url(), "ws://localhost:3000/ws");
+}
+
+#[test]
+fn test_credentials_parse_server_response() {
+  let user_id = UserId::new();
+  let body = format!(r#"{{"user_id":"{user_id}","token":"abc"}}"#);
+  let credentials: Credentials = serde_json::from_str(&body).unwrap();
+  assert_eq!(credentials.user_id, user_id);
+  assert_eq!(credentials.token, "abc");
+}
+
+#[test]
+fn test_credentials_parse_refresh_fields() {
+  let user_id = UserId::new();
+  let body =
+    format!(r#"{{"user_id":"{user_id}","token":"abc","refresh_token":"def","expires_in":900}}"#);
+  let credentials: Credentials = serde_json::from_str(&body).unwrap();
+  assert_eq!(credentials.refresh_token.as_deref(), Some("def"));
+  assert_eq!(credentials.expires_in, Some(900));
+}
+
+#[tokio::test]
+async fn test_https_base_url_is_rejected() {
+  let client = ApiClient::new("https://chat.example.com");
+  let err = client.login("alice", "password").await.unwrap_err();
+  assert!(matches!(err, ClientError::TlsUnsupported(_)), "{err}");
+  let err = client.connect("token").await.unwrap_err();
+  assert!(matches!(err, ClientError::TlsUnsupported(_)), "{err}");
+}
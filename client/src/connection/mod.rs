@@ -0,0 +1,378 @@
+//! WebSocket signaling connection.
+//!
+//! A [`Connection`] wraps one `/ws` socket and speaks the binary frame
+//! protocol: every [`SignalingMessage`] is bitcode-encoded and wrapped
+//! in a [`MessageFrame`] tagged with its discriminator.
+//!
+//! Heartbeats are answered transparently: the server sends `Ping` on its
+//! heartbeat interval and drops clients that stay silent past the
+//! timeout, so [`Connection::recv`] replies with `Pong` and hides the
+//! `Ping` unless auto-pong is switched off.
+//!
+//! Request helpers ([`Connection::authenticate`], [`Connection::join_room`],
+//! ...) wait for the matching response. Unrelated messages that arrive in
+//! the meantime are buffered and returned by later `recv` calls, so event
+//! consumers never lose broadcasts.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use message::error::MessageError;
+use message::frame::{MessageFrame, decode_frame, encode_frame};
+use message::signaling::{
+  AuthSuccess, ConnectionInvite, CreateRoom, InviteAccepted, InviteDeclined, JoinRoom, LeaveRoom,
+  Ping, Pong, RoomCreated, RoomInvite, RoomInviteResponse, RoomJoined, SignalingMessage, TokenAuth,
+};
+use message::{RoomId, UserId};
+use tokio::net::TcpStream;
+use tokio::time::{Instant, sleep, timeout};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+use crate::error::ClientError;
+
+/// Underlying WebSocket stream type.
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How long request helpers wait for the server's response.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Quiet window after which [`Connection::drain`] stops reading.
+const DRAIN_IDLE: Duration = Duration::from_millis(50);
+
+/// Encode a signaling message into a binary frame.
+pub fn encode_signaling(msg: &SignalingMessage) -> Result<Vec<u8>, MessageError> {
+  let frame = MessageFrame::new(msg.discriminator(), bitcode::encode(msg));
+  encode_frame(&frame)
+}
+
+/// Decode a binary frame into a signaling message.
+pub fn decode_signaling(bytes: &[u8]) -> Result<SignalingMessage, MessageError> {
+  let frame = decode_frame(bytes)?;
+  bitcode::decode(&frame.payload)
+    .map_err(|e| MessageError::Deserialization(format!("Failed to decode signaling message: {e}")))
+}
+
+/// An open signaling connection.
+pub struct Connection {
+  stream: WsStream,
+  pending: VecDeque<SignalingMessage>,
+  user_id: Option<UserId>,
+  auto_pong: bool,
+}
+
+impl std::fmt::Debug for Connection {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Connection")
+      .field("user_id", &self.user_id)
+      .field("pending", &self.pending.len())
+      .field("auto_pong", &self.auto_pong)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Connection {
+  /// Open an unauthenticated connection to a `ws://` URL.
+  pub async fn connect(url: &str) -> Result<Self, ClientError> {
+    reject_tls(url)?;
+    let (stream, _) = connect_async(url).await?;
+    Ok(Self {
+      stream,
+      pending: VecDeque::new(),
+      user_id: None,
+      auto_pong: true,
+    })
+  }
+
+  /// Enable or disable automatic `Pong` replies (enabled by default).
+  ///
+  /// When disabled, server `Ping` messages are returned from
+  /// [`recv`](Self::recv) and the caller is responsible for answering.
+  /// Enabling it also drops any `Ping` already buffered while waiting
+  /// for a response, since the next heartbeat is answered automatically.
+  pub fn set_auto_pong(&mut self, enabled: bool) {
+    self.auto_pong = enabled;
+    if enabled {
+      self
+        .pending
+        .retain(|msg| !matches!(msg, SignalingMessage::Ping(_)));
+    }
+  }
+
+  /// User ID from the last successful [`authenticate`](Self::authenticate).
+  pub fn user_id(&self) -> Option<&UserId> {
+    self.user_id.as_ref()
+  }
+
+  /// Send a signaling message.
+  pub async fn send(&mut self, msg: &SignalingMessage) -> Result<(), ClientError> {
+    let encoded = encode_signaling(msg)?;
+    self.stream.send(Message::Binary(encoded.into())).await?;
+    Ok(())
+  }
+
+  /// Receive the next signaling message.
+  ///
+  /// Returns [`ClientError::Closed`] once the server closes the socket.
+  pub async fn recv(&mut self) -> Result<SignalingMessage, ClientError> {
+    match self.pending.pop_front() {
+      Some(msg) => Ok(msg),
+      None => self.recv_from_socket().await,
+    }
+  }
+
+  /// Receive the next message, or `None` if nothing arrives within `wait`.
+  pub async fn recv_timeout(
+    &mut self,
+    wait: Duration,
+  ) -> Result<Option<SignalingMessage>, ClientError> {
+    match timeout(wait, self.recv()).await {
+      Ok(result) => result.map(Some),
+      Err(_) => Ok(None),
+    }
+  }
+
+  /// Receive messages until one satisfies `matches`, discarding the rest.
+  ///
+  /// Returns [`ClientError::Timeout`] if no match arrives within `wait`.
+  pub async fn recv_matching<F>(
+    &mut self,
+    wait: Duration,
+    mut matches: F,
+  ) -> Result<SignalingMessage, ClientError>
+  where
+    F: FnMut(&SignalingMessage) -> bool,
+  {
+    let deadline = Instant::now() + wait;
+    loop {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      match self.recv_timeout(remaining).await? {
+        Some(msg) if matches(&msg) => return Ok(msg),
+        Some(_) => {}
+        None => return Err(ClientError::Timeout),
+      }
+    }
+  }
+
+  /// Wait `wait`, then discard everything received until the socket has
+  /// been quiet for a short window. Returns the discarded messages.
+  pub async fn drain(&mut self, wait: Duration) -> Vec<SignalingMessage> {
+    sleep(wait).await;
+    let mut drained: Vec<_> = self.pending.drain(..).collect();
+    while let Ok(Some(msg)) = self.recv_timeout(DRAIN_IDLE).await {
+      drained.push(msg);
+    }
+    drained
+  }
+
+  /// Authenticate with a JWT token from register or login.
+  pub async fn authenticate(&mut self, token: &str) -> Result<AuthSuccess, ClientError> {
+    self
+      .send(&SignalingMessage::TokenAuth(TokenAuth {
+        token: token.to_string(),
+        device: None,
+      }))
+      .await?;
+    let reply = self
+      .request_reply(|msg| {
+        matches!(
+          msg,
+          SignalingMessage::AuthSuccess(_) | SignalingMessage::AuthFailure(_)
+        )
+      })
+      .await?;
+    match reply {
+      SignalingMessage::AuthSuccess(success) => {
+        self.user_id = Some(success.user_id.clone());
+        Ok(success)
+      }
+      SignalingMessage::AuthFailure(failure) => Err(ClientError::AuthFailed(failure.reason)),
+      _ => unreachable!("filtered by request_reply"),
+    }
+  }
+
+  /// Send an application-level heartbeat `Ping`.
+  pub async fn ping(&mut self) -> Result<(), ClientError> {
+    self.send(&SignalingMessage::Ping(Ping)).await
+  }
+
+  /// Create a room; the creator joins it as owner.
+  pub async fn create_room(&mut self, request: CreateRoom) -> Result<RoomCreated, ClientError> {
+    self.send(&SignalingMessage::CreateRoom(request)).await?;
+    match self
+      .request_reply(|msg| matches!(msg, SignalingMessage::RoomCreated(_)))
+      .await?
+    {
+      SignalingMessage::RoomCreated(created) => Ok(created),
+      _ => unreachable!("filtered by request_reply"),
+    }
+  }
+
+  /// Join a room, with its password if it has one.
+  pub async fn join_room(
+    &mut self,
+    room_id: RoomId,
+    password: Option<String>,
+  ) -> Result<RoomJoined, ClientError> {
+    self
+      .send(&SignalingMessage::JoinRoom(JoinRoom { room_id, password }))
+      .await?;
+    match self
+      .request_reply(|msg| matches!(msg, SignalingMessage::RoomJoined(_)))
+      .await?
+    {
+      SignalingMessage::RoomJoined(joined) => Ok(joined),
+      _ => unreachable!("filtered by request_reply"),
+    }
+  }
+
+  /// Leave a room.
+  pub async fn leave_room(&mut self, room_id: RoomId) -> Result<(), ClientError> {
+    self
+      .send(&SignalingMessage::LeaveRoom(LeaveRoom { room_id }))
+      .await
+  }
+
+  /// Invite another user to a peer connection.
+  pub async fn invite(&mut self, to: UserId, note: Option<String>) -> Result<(), ClientError> {
+    let from = self.require_user_id()?;
+    self
+      .send(&SignalingMessage::ConnectionInvite(ConnectionInvite {
+        from,
+        to,
+        note,
+      }))
+      .await
+  }
+
+  /// Accept a connection invite from `inviter`.
+  pub async fn accept_invite(&mut self, inviter: UserId) -> Result<(), ClientError> {
+    let from = self.require_user_id()?;
+    self
+      .send(&SignalingMessage::InviteAccepted(InviteAccepted {
+        from,
+        to: inviter,
+      }))
+      .await
+  }
+
+  /// Decline a connection invite from `inviter`.
+  pub async fn decline_invite(&mut self, inviter: UserId) -> Result<(), ClientError> {
+    let from = self.require_user_id()?;
+    self
+      .send(&SignalingMessage::InviteDeclined(InviteDeclined {
+        from,
+        to: inviter,
+      }))
+      .await
+  }
+
+  /// Invite another user into a room.
+  pub async fn invite_to_room(
+    &mut self,
+    room_id: RoomId,
+    to: UserId,
+    note: String,
+  ) -> Result<(), ClientError> {
+    let from = self.require_user_id()?;
+    self
+      .send(&SignalingMessage::RoomInvite(RoomInvite {
+        room_id,
+        from,
+        to,
+        note,
+      }))
+      .await
+  }
+
+  /// Answer a room invite from `inviter`.
+  pub async fn respond_to_room_invite(
+    &mut self,
+    room_id: RoomId,
+    inviter: UserId,
+    accepted: bool,
+  ) -> Result<(), ClientError> {
+    self
+      .send(&SignalingMessage::RoomInviteResponse(RoomInviteResponse {
+        room_id,
+        to: inviter,
+        accepted,
+      }))
+      .await
+  }
+
+  /// Close the connection gracefully.
+  pub async fn close(mut self) -> Result<(), ClientError> {
+    self.stream.close(None).await?;
+    Ok(())
+  }
+
+  fn require_user_id(&self) -> Result<UserId, ClientError> {
+    self
+      .user_id
+      .clone()
+      .ok_or_else(|| ClientError::AuthFailed("connection is not authenticated".to_string()))
+  }
+
+  /// Wait for the reply matching `is_reply`, surfacing `ErrorResponse` as
+  /// an error and buffering everything else for later `recv` calls.
+  async fn request_reply<F>(&mut self, is_reply: F) -> Result<SignalingMessage, ClientError>
+  where
+    F: Fn(&SignalingMessage) -> bool,
+  {
+    let mut skipped = Vec::new();
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let result = loop {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      let msg = match timeout(remaining, self.recv_from_socket()).await {
+        Ok(Ok(msg)) => msg,
+        Ok(Err(e)) => break Err(e),
+        Err(_) => break Err(ClientError::Timeout),
+      };
+      if is_reply(&msg) {
+        break Ok(msg);
+      }
+      if let SignalingMessage::ErrorResponse(error) = msg {
+        break Err(ClientError::Server(Box::new(error)));
+      }
+      skipped.push(msg);
+    };
+    self.pending.extend(skipped);
+    result
+  }
+
+  /// Read the next message off the socket, answering heartbeats when
+  /// auto-pong is enabled. Bypasses the pending buffer.
+  async fn recv_from_socket(&mut self) -> Result<SignalingMessage, ClientError> {
+    loop {
+      let msg = match self.stream.next().await {
+        Some(Ok(Message::Binary(data))) => decode_signaling(&data)?,
+        Some(Ok(Message::Close(_))) | None => return Err(ClientError::Closed),
+        Some(Ok(other)) => {
+          tracing::debug!(?other, "Ignoring non-binary WebSocket frame");
+          continue;
+        }
+        Some(Err(e)) => return Err(e.into()),
+      };
+      if self.auto_pong && matches!(msg, SignalingMessage::Ping(_)) {
+        self.send(&SignalingMessage::Pong(Pong)).await?;
+        continue;
+      }
+      return Ok(msg);
+    }
+  }
+}
+
+/// Refuse `https://` and `wss://` URLs up front, since neither the HTTP
+/// client nor the WebSocket connector is built with TLS support.
+pub(crate) fn reject_tls(url: &str) -> Result<(), ClientError> {
+  let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+  if scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("wss") {
+    return Err(ClientError::TlsUnsupported(url.to_string()));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests;
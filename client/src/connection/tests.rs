@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn test_signaling_roundtrip() {
+  let msg = SignalingMessage::JoinRoom(JoinRoom {
+    room_id: RoomId::new(),
+    password: Some("secret".to_string()),
+  });
+  let encoded = encode_signaling(&msg).unwrap();
+  assert_eq!(decode_signaling(&encoded).unwrap(), msg);
+}
+
+#[test]
+fn test_encoded_frame_carries_discriminator() {
+  let msg = SignalingMessage::Ping(Ping);
+  let encoded = encode_signaling(&msg).unwrap();
+  let frame = decode_frame(&encoded).unwrap();
+  assert_eq!(frame.message_type, msg.discriminator());
+}
+
+#[test]
+fn test_decode_rejects_garbage() {
+  assert!(decode_signaling(b"not a frame").is_err());
+}
+
+#[tokio::test]
+async fn test_wss_url_is_rejected() {
+  for url in ["wss://chat.example.com/ws", "WSS://chat.example.com/ws"] {
+    let err = Connection::connect(url).await.unwrap_err();
+    assert!(matches!(err, ClientError::TlsUnsupported(_)), "{err}");
+  }
+}
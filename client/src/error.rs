@@ -0,0 +1,51 @@
+//! Client error type.
+
+use message::ErrorResponse;
+use message::error::MessageError;
+use thiserror::Error;
+
+/// Errors returned by the HTTP API client and the signaling connection.
+#[derive(Debug, Error)]
+pub enum ClientError {
+  /// HTTP transport failure (connection refused, bad body, ...).
+  #[error("HTTP request failed: {0}")]
+  Http(#[from] reqwest::Error),
+
+  /// The server rejected an HTTP API call.
+  #[error("server returned {status}: {message}")]
+  Api {
+    /// HTTP status code.
+    status: u16,
+    /// Error message from the response body.
+    message: String,
+  },
+
+  /// The URL needs TLS (`https://` or `wss://`), which this client is
+  /// built without. Put it behind a plain-text endpoint instead.
+  #[error("TLS is not supported, use an http:// or ws:// URL: {0}")]
+  TlsUnsupported(String),
+
+  /// WebSocket transport failure.
+  #[error("WebSocket error: {0}")]
+  WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+  /// A frame could not be encoded or decoded.
+  #[error("protocol error: {0}")]
+  Protocol(#[from] MessageError),
+
+  /// The server refused the token.
+  #[error("authentication failed: {0}")]
+  AuthFailed(String),
+
+  /// The server answered a request with an error response.
+  #[error("server error {}: {}", .0.code, .0.message)]
+  Server(Box<ErrorResponse>),
+
+  /// The server closed the connection.
+  #[error("connection closed")]
+  Closed,
+
+  /// No matching message arrived in time.
+  #[error("timed out waiting for the server")]
+  Timeout,
+}
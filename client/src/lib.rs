@@ -0,0 +1,30 @@
+//! # Chat Client
+//!
+//! Headless native client for the WebRTC Chat signaling server.
+//!
+//! This crate provides:
+//! - HTTP account flows (`/api/register`, `/api/login`) via [`ApiClient`]
+//! - A `/ws` signaling [`Connection`] speaking the binary frame protocol
+//!   from the `message` crate, with token auth, heartbeat replies, room
+//!   join and invite helpers
+//!
+//! It is used by the `client` CLI binary, by bots and load tests, and by
+//! the server's integration tests. It is built without TLS: `https://`
+//! and `wss://` URLs are refused with [`ClientError::TlsUnsupported`].
+
+#![warn(missing_docs)]
+#![warn(rust_2018_idioms)]
+#![warn(unreachable_pub)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::missing_errors_doc)]
+
+pub mod api;
+pub mod connection;
+pub mod error;
+
+pub use api::{ApiClient, Credentials};
+pub use connection::Connection;
+pub use error::ClientError;
@@ -0,0 +1,186 @@
+//! # WebRTC Chat CLI
+//!
+//! Headless command-line client for scripting the signaling server.
+//! Results and events are printed to stdout as JSON lines; logs go to
+//! stderr (filtered by `RUST_LOG`).
+
+#![warn(missing_docs)]
+#![warn(rust_2018_idioms)]
+#![warn(unreachable_pub)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::missing_errors_doc)]
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use client::connection::REQUEST_TIMEOUT;
+use client::{ApiClient, ClientError, Connection};
+use message::RoomId;
+use message::signaling::SignalingMessage;
+use serde::Serialize;
+use tracing_subscriber::EnvFilter;
+
+/// Command-line arguments.
+#[derive(Debug, Parser)]
+#[command(name = "client", version, about = "Headless WebRTC Chat client")]
+struct Cli {
+  /// Server base URL.
+  #[arg(
+    long,
+    env = "CHAT_SERVER",
+    default_value = "http://127.0.0.1:3000",
+    global = true
+  )]
+  server: String,
+
+  /// Session token from `register` or `login`.
+  #[arg(long, env = "CHAT_TOKEN", global = true, hide_env_values = true)]
+  token: Option<String>,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+/// Top-level commands.
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Create an account and print its credentials.
+  Register(AccountArgs),
+  /// Log in and print fresh credentials.
+  Login(AccountArgs),
+  /// Exchange a refresh token for fresh credentials.
+  Refresh {
+    /// Refresh token from `register`, `login` or a previous `refresh`.
+    #[arg(env = "CHAT_REFRESH_TOKEN", hide_env_values = true)]
+    refresh_token: String,
+  },
+  /// Room list commands.
+  Rooms {
+    #[command(subcommand)]
+    command: RoomsCommand,
+  },
+  /// Single-room commands.
+  Room {
+    #[command(subcommand)]
+    command: RoomCommand,
+  },
+  /// Event streaming commands.
+  Watch {
+    #[command(subcommand)]
+    command: WatchCommand,
+  },
+}
+
+/// Username and password for account commands.
+#[derive(Debug, clap::Args)]
+struct AccountArgs {
+  /// Account username.
+  username: String,
+  /// Account password.
+  #[arg(long, env = "CHAT_PASSWORD", hide_env_values = true)]
+  password: String,
+}
+
+/// `rooms` subcommands.
+#[derive(Debug, Subcommand)]
+enum RoomsCommand {
+  /// Print every room on the server.
+  List,
+}
+
+/// `room` subcommands.
+#[derive(Debug, Subcommand)]
+enum RoomCommand {
+  /// Join a room, print the join result, then stream events until Ctrl-C.
+  Join {
+    /// Room ID.
+    room_id: RoomId,
+    /// Room password, if the room has one.
+    #[arg(long)]
+    password: Option<String>,
+  },
+}
+
+/// `watch` subcommands.
+#[derive(Debug, Subcommand)]
+enum WatchCommand {
+  /// Stream every signaling message until Ctrl-C or disconnect.
+  Events,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  tracing_subscriber::fmt()
+    .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+    .with_writer(std::io::stderr)
+    .init();
+
+  let cli = Cli::parse();
+  let api = ApiClient::new(cli.server);
+
+  match cli.command {
+    Command::Register(args) => print_json(&api.register(&args.username, &args.password).await?),
+    Command::Login(args) => print_json(&api.login(&args.username, &args.password).await?),
+    Command::Refresh { refresh_token } => print_json(&api.refresh(&refresh_token).await?),
+    Command::Rooms {
+      command: RoomsCommand::List,
+    } => {
+      let mut conn = connect(&api, cli.token.as_deref()).await?;
+      let update = conn
+        .recv_matching(REQUEST_TIMEOUT, |msg| {
+          matches!(msg, SignalingMessage::RoomListUpdate(_))
+        })
+        .await?;
+      if let SignalingMessage::RoomListUpdate(update) = update {
+        for room in &update.rooms {
+          print_json(room)?;
+        }
+      }
+      conn.close().await?;
+      Ok(())
+    }
+    Command::Room {
+      command: RoomCommand::Join { room_id, password },
+    } => {
+      let mut conn = connect(&api, cli.token.as_deref()).await?;
+      print_json(&conn.join_room(room_id, password).await?)?;
+      stream_events(&mut conn).await
+    }
+    Command::Watch {
+      command: WatchCommand::Events,
+    } => {
+      let mut conn = connect(&api, cli.token.as_deref()).await?;
+      stream_events(&mut conn).await
+    }
+  }
+}
+
+/// Open an authenticated signaling connection.
+async fn connect(api: &ApiClient, token: Option<&str>) -> anyhow::Result<Connection> {
+  let token = token.context("a token is required: pass --token or set CHAT_TOKEN")?;
+  Ok(api.connect(token).await?)
+}
+
+/// Print every received message until Ctrl-C or the server disconnects.
+async fn stream_events(conn: &mut Connection) -> anyhow::Result<()> {
+  let ctrl_c = tokio::signal::ctrl_c();
+  tokio::pin!(ctrl_c);
+  loop {
+    tokio::select! {
+      _ = &mut ctrl_c => return Ok(()),
+      msg = conn.recv() => match msg {
+        Ok(msg) => print_json(&msg)?,
+        Err(ClientError::Closed) => return Ok(()),
+        Err(e) => return Err(e.into()),
+      },
+    }
+  }
+}
+
+/// Print a value as one JSON line on stdout.
+fn print_json<T: Serialize>(value: &T) -> anyhow::Result<()> {
+  println!("{}", serde_json::to_string(value)?);
+  Ok(())
+}
@@ -1,7 +1,8 @@
 //! Authentication service and context.
 //!
 //! Provides HTTP-based registration and login, JWT token persistence,
-//! and automatic token recovery on page refresh.
+//! refresh-token renewal of short-lived access tokens, and automatic
+//! token recovery on page refresh.
 
 mod jwt;
 mod service;
@@ -9,7 +10,8 @@ mod token;
 mod types;
 mod utils;
 
-pub(crate) use token::{KEY_USER_ID, KEY_USERNAME};
+pub(crate) use service::refresh_auth;
+pub(crate) use token::{KEY_USER_ID, KEY_USERNAME, has_refresh_token};
 pub use token::{
   clear_auth_storage, load_active_call, load_active_room_id, load_auth_from_storage,
   load_avatar_from_storage, save_active_call, save_active_room_id, save_auth_to_storage,
@@ -27,7 +29,9 @@ pub use jwt::is_jwt_expired;
 #[cfg(test)]
 pub(crate) use jwt::is_payload_expired;
 #[cfg(test)]
-pub(crate) use types::{AuthErrorResponse, AuthResponse, LoginRequest, RegisterRequest};
+pub(crate) use types::{
+  AuthErrorResponse, AuthResponse, LoginRequest, RefreshRequest, RegisterRequest,
+};
 
 #[cfg(test)]
 mod tests;
@@ -1,6 +1,7 @@
 //! Auth HTTP service.
 //!
-//! Provides HTTP-based registration, login, and token recovery logic.
+//! Provides HTTP-based registration, login, token refresh and token
+//! recovery logic.
 
 use leptos::prelude::*;
 use message::UserId;
@@ -11,8 +12,13 @@ use wasm_bindgen_futures::spawn_local;
 use crate::state::{AppState, AuthState};
 
 use super::jwt::is_jwt_expired;
-use super::token::{clear_auth_storage, load_auth_from_storage, save_auth_to_storage};
-use super::types::{AuthErrorResponse, AuthResponse, AuthResult, LoginRequest, RegisterRequest};
+use super::token::{
+  clear_auth_storage, has_refresh_token, load_auth_from_storage, load_refresh_token,
+  save_auth_to_storage, save_refresh_token,
+};
+use super::types::{
+  AuthErrorResponse, AuthResponse, AuthResult, LoginRequest, RefreshRequest, RegisterRequest,
+};
 use super::utils::format_js_error;
 
 /// Get the base URL for auth API requests.
@@ -64,6 +70,22 @@ pub fn login(
   );
 }
 
+/// Renew an expired access token with the stored refresh token.
+///
+/// The refresh token is single-use: on success both the new access
+/// token and the rotated refresh token are persisted, and the updated
+/// `auth` is returned. The caller decides what to do on failure
+/// (usually falling back to the login page).
+pub(crate) async fn refresh_auth(http_url: &str, mut auth: AuthState) -> Result<AuthState, String> {
+  let refresh_token = load_refresh_token().ok_or("No refresh token")?;
+  let url = format!("{}/api/refresh", http_url);
+  let response = send_http_request(&url, &RefreshRequest { refresh_token }).await?;
+  auth.token = response.token;
+  save_refresh_token(response.refresh_token.as_deref());
+  save_auth_to_storage(&auth);
+  Ok(auth)
+}
+
 /// Attempt to recover auth state from localStorage and reconnect.
 ///
 /// Called on app startup. If a valid token is found in localStorage,
@@ -71,6 +93,11 @@ pub fn login(
 /// setting the auth state and connecting the signaling client (which
 /// will send `TokenAuth` to verify the token with the server).
 ///
+/// Access tokens are short-lived, so an expired token is renewed with
+/// the stored refresh token first; recovery then finishes
+/// asynchronously and only falls through to the login page if the
+/// refresh is rejected.
+///
 /// If `signaling.connect()` fails synchronously (e.g. malformed ws URL,
 /// browser CSP block), the auth state is rolled back and storage is
 /// cleared so the UI falls through to the login page instead of
@@ -78,10 +105,35 @@ pub fn login(
 pub fn try_recover_auth(app_state: AppState) -> bool {
   if let Some(auth) = load_auth_from_storage() {
     // Quick local check: if the JWT has expired, skip the connect
-    // attempt and go straight to the login page.
+    // attempt and either renew it or go straight to the login page.
     if is_jwt_expired(&auth.token) {
-      clear_auth_storage();
-      return false;
+      if !has_refresh_token() {
+        clear_auth_storage();
+        return false;
+      }
+      // Capture context-dependent values while the reactive owner is
+      // still in scope (see `send_auth_request`).
+      let http_url = auth_base_url();
+      let client = crate::signaling::use_signaling_client();
+      spawn_local(async move {
+        match refresh_auth(&http_url, auth).await {
+          Ok(auth) => {
+            app_state.auth.set(Some(auth));
+            if client.connect().is_err() {
+              clear_auth_storage();
+              app_state.auth.set(None);
+            }
+          }
+          Err(e) => {
+            web_sys::console::warn_1(&wasm_bindgen::JsValue::from_str(&format!(
+              "[auth] Token refresh failed: {}",
+              e
+            )));
+            clear_auth_storage();
+          }
+        }
+      });
+      return true;
     }
     app_state.auth.set(Some(auth));
     let client = crate::signaling::use_signaling_client();
@@ -144,6 +196,7 @@ fn send_auth_request<T: Serialize + 'static>(
         };
 
         save_auth_to_storage(&auth);
+        save_refresh_token(response.refresh_token.as_deref());
         app_state.auth.set(Some(auth));
 
         // If the signaling connection cannot even be created (malformed
@@ -80,6 +80,30 @@ fn test_auth_response_deserialization() {
   assert_eq!(response.token, "jwt-token-123");
 }
 
+#[test]
+fn test_auth_response_with_refresh_token() {
+  let json = r#"{"user_id":"550e8400-e29b-41d4-a716-446655440000","token":"jwt","refresh_token":"rt-1","expires_in":900}"#;
+  let response: AuthResponse = serde_json::from_str(json).expect("Should deserialize");
+  assert_eq!(response.token, "jwt");
+  assert_eq!(response.refresh_token.as_deref(), Some("rt-1"));
+}
+
+#[test]
+fn test_auth_response_without_refresh_token() {
+  let json = r#"{"user_id":"550e8400-e29b-41d4-a716-446655440000","token":"jwt"}"#;
+  let response: AuthResponse = serde_json::from_str(json).expect("Should deserialize");
+  assert!(response.refresh_token.is_none());
+}
+
+#[test]
+fn test_refresh_request_serialization() {
+  let request = RefreshRequest {
+    refresh_token: "rt-1".to_string(),
+  };
+  let json = serde_json::to_string(&request).unwrap();
+  assert_eq!(json, r#"{"refresh_token":"rt-1"}"#);
+}
+
 #[test]
 fn test_auth_response_deserialization_invalid_json() {
   let json = r#"{"invalid": true}"#;
@@ -9,6 +9,9 @@ use message::UserId;
 
 /// localStorage keys for auth persistence.
 const KEY_TOKEN: &str = "auth_token";
+/// localStorage key for the single-use refresh token that renews
+/// `auth_token` once the short-lived access token expires.
+const KEY_REFRESH_TOKEN: &str = "auth_refresh_token";
 pub(crate) const KEY_USER_ID: &str = "auth_user_id";
 pub(crate) const KEY_USERNAME: &str = "auth_username";
 const KEY_NICKNAME: &str = "auth_nickname";
@@ -67,6 +70,25 @@ pub fn save_auth_to_storage(auth: &AuthState) {
   utils::remove_from_local_storage("nickname");
 }
 
+/// Save (or, with `None`, clear) the refresh token.
+pub(crate) fn save_refresh_token(token: Option<&str>) {
+  match token {
+    Some(token) => utils::save_to_local_storage(KEY_REFRESH_TOKEN, token),
+    None => utils::remove_from_local_storage(KEY_REFRESH_TOKEN),
+  }
+}
+
+/// Load the refresh token, if one is stored.
+pub(crate) fn load_refresh_token() -> Option<String> {
+  utils::load_from_local_storage(KEY_REFRESH_TOKEN).filter(|token| !token.is_empty())
+}
+
+/// Whether an expired access token can be renewed without logging in.
+#[must_use]
+pub(crate) fn has_refresh_token() -> bool {
+  load_refresh_token().is_some()
+}
+
 /// Load auth state from localStorage.
 ///
 /// Returns `None` if any required field is missing or invalid.
@@ -167,6 +189,7 @@ pub fn load_active_call() -> Option<String> {
 /// starts clean (Req 10.9.34).
 pub fn clear_auth_storage() {
   utils::remove_from_local_storage(KEY_TOKEN);
+  utils::remove_from_local_storage(KEY_REFRESH_TOKEN);
   utils::remove_from_local_storage(KEY_USER_ID);
   utils::remove_from_local_storage(KEY_USERNAME);
   utils::remove_from_local_storage(KEY_NICKNAME);
@@ -26,11 +26,20 @@ pub(crate) struct LoginRequest {
   pub password: String,
 }
 
+/// Token refresh request payload.
+#[derive(Debug, Serialize)]
+pub(crate) struct RefreshRequest {
+  pub refresh_token: String,
+}
+
 /// Auth API response.
 #[derive(Debug, Deserialize)]
 pub(crate) struct AuthResponse {
   pub user_id: String,
   pub token: String,
+  /// Single-use token for `/api/refresh`. Absent on older servers.
+  #[serde(default)]
+  pub refresh_token: Option<String>,
 }
 
 /// Auth API error response.
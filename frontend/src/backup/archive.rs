@@ -0,0 +1,200 @@
+//! Creating and restoring backups in the browser.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use js_sys::{Array, ArrayBuffer, Uint8Array};
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, BlobPropertyBag, IdbDatabase};
+
+use super::format::{
+  BackgroundImageEntry, BackupError, BackupFile, BackupHeader, BackupPayload, PREFERENCE_KEYS,
+  RestoreSummary, SALT_LEN, dedupe_records, missing_flags, newer_avatars, validate_passphrase,
+};
+use crate::devices::add_linked_conversations;
+use crate::devices::link::{EXPORT_PAGE_SIZE, ExportCursor, LinkedConversation};
+use crate::persistence::record::conversation_key;
+use crate::persistence::schema::{KEY_USER_BG_DARK, KEY_USER_BG_LIGHT};
+use crate::persistence::store::{
+  get_background_image, list_avatars, list_conv_flags, put_avatar, put_background_image,
+  put_conv_flags,
+};
+use crate::persistence::{PersistenceManager, try_use_persistence_manager};
+use crate::settings::SettingsState;
+use crate::state::AppState;
+use crate::utils;
+use crate::webrtc::BackupCipher;
+
+/// Collect local history, flags, avatars, background images and
+/// settings, and seal them under `passphrase`. Returns the backup
+/// file as JSON text.
+pub async fn create_backup(
+  passphrase: &str,
+  settings: SettingsState,
+  app_state: AppState,
+) -> Result<String, BackupError> {
+  validate_passphrase(passphrase)?;
+  let pm = persistence()?;
+  let db = pm.db().await.map_err(storage)?;
+
+  let mut messages = Vec::new();
+  let mut cursor = ExportCursor::default();
+  while !cursor.is_done() {
+    let page = pm
+      .export_page(cursor.from_ts(), EXPORT_PAGE_SIZE)
+      .await
+      .map_err(storage)?;
+    messages.extend(cursor.advance(page, EXPORT_PAGE_SIZE));
+  }
+
+  let payload = BackupPayload {
+    app_version: env!("CARGO_PKG_VERSION").to_string(),
+    settings: settings.signal().get_untracked(),
+    preferences: PREFERENCE_KEYS
+      .iter()
+      .filter_map(|key| utils::load_from_local_storage(key).map(|v| ((*key).to_string(), v)))
+      .collect(),
+    conversations: app_state.conversations.with_untracked(|list| {
+      list
+        .iter()
+        .map(|c| LinkedConversation {
+          key: conversation_key(&c.id),
+          display_name: c.display_name.clone(),
+        })
+        .collect()
+    }),
+    conversation_flags: list_conv_flags(&db).await.map_err(js_storage)?,
+    avatars: list_avatars(&db).await.map_err(js_storage)?,
+    background_images: read_background_images(&db).await?,
+    messages,
+  };
+  let plaintext = serde_json::to_vec(&payload).map_err(|e| BackupError::Codec(e.to_string()))?;
+
+  let salt = BackupCipher::random_salt(SALT_LEN).map_err(BackupError::Crypto)?;
+  let header = BackupHeader::new(&salt, chrono::Utc::now().to_rfc3339());
+  let cipher = BackupCipher::derive(passphrase, &salt, header.kdf.iterations)
+    .await
+    .map_err(BackupError::Crypto)?;
+  let (iv, ciphertext) = cipher
+    .seal(&plaintext, &header.binding())
+    .await
+    .map_err(BackupError::Crypto)?;
+  Ok(BackupFile::new(header, &iv, &ciphertext).to_json())
+}
+
+/// Decrypt a backup file and merge it into local storage.
+///
+/// Messages are deduplicated by `MessageId` and never overwrite a copy
+/// this device already has; conversation flags only fill in rows that
+/// are missing; avatars are taken when newer. Settings, preferences
+/// and background images are replaced by the backup's. The search
+/// index is rebuilt afterwards.
+pub async fn restore_backup(
+  text: &str,
+  passphrase: &str,
+  settings: SettingsState,
+  app_state: AppState,
+) -> Result<RestoreSummary, BackupError> {
+  let file = BackupFile::parse(text)?;
+  let salt = file.header.salt()?;
+  let (iv, ciphertext) = file.sealed()?;
+  let cipher = BackupCipher::derive(passphrase, &salt, file.header.kdf.iterations)
+    .await
+    .map_err(BackupError::Crypto)?;
+  let plaintext = cipher
+    .open(&iv, &ciphertext, &file.header.binding())
+    .await
+    .map_err(|_| BackupError::Decrypt)?;
+  let mut payload: BackupPayload =
+    serde_json::from_slice(&plaintext).map_err(|e| BackupError::Codec(e.to_string()))?;
+  payload.retain_known_entries();
+
+  let pm = persistence()?;
+  let db = pm.db().await.map_err(storage)?;
+
+  let mut summary = RestoreSummary::default();
+  let records = dedupe_records(std::mem::take(&mut payload.messages));
+  for chunk in records.chunks(EXPORT_PAGE_SIZE) {
+    summary.messages += pm.import_records(chunk.to_vec()).await.map_err(storage)?;
+  }
+
+  let local_flags = list_conv_flags(&db).await.map_err(js_storage)?;
+  for row in missing_flags(&local_flags, payload.conversation_flags) {
+    put_conv_flags(&db, &row).await.map_err(js_storage)?;
+  }
+  let local_avatars = list_avatars(&db).await.map_err(js_storage)?;
+  for avatar in newer_avatars(&local_avatars, payload.avatars) {
+    put_avatar(&db, &avatar).await.map_err(js_storage)?;
+  }
+  for image in &payload.background_images {
+    write_background_image(&db, image).await?;
+  }
+
+  settings.update(|s| *s = payload.settings);
+  for (key, value) in &payload.preferences {
+    utils::save_to_local_storage(key, value);
+  }
+  if let Some(theme) = payload.preferences.get("settings_theme") {
+    app_state.theme.set(theme.clone());
+  }
+
+  summary.conversations = add_linked_conversations(app_state, payload.conversations);
+  app_state.reconcile_conv_flags_from_idb();
+  pm.rebuild_index_streaming().await.map_err(storage)?;
+  Ok(summary)
+}
+
+/// Both background slots, skipping empty ones.
+async fn read_background_images(
+  db: &IdbDatabase,
+) -> Result<Vec<BackgroundImageEntry>, BackupError> {
+  let mut out = Vec::new();
+  for key in [KEY_USER_BG_LIGHT, KEY_USER_BG_DARK] {
+    let Some(blob) = get_background_image(db, key).await.map_err(js_storage)? else {
+      continue;
+    };
+    let buffer = JsFuture::from(blob.array_buffer())
+      .await
+      .map_err(|e| BackupError::Storage(format!("{e:?}")))?;
+    let buffer: ArrayBuffer = buffer
+      .dyn_into()
+      .map_err(|_| BackupError::Storage("blob did not yield an ArrayBuffer".to_string()))?;
+    out.push(BackgroundImageEntry {
+      key: key.to_string(),
+      mime: blob.type_(),
+      data: BASE64.encode(Uint8Array::new(&buffer).to_vec()),
+    });
+  }
+  Ok(out)
+}
+
+async fn write_background_image(
+  db: &IdbDatabase,
+  image: &BackgroundImageEntry,
+) -> Result<(), BackupError> {
+  let bytes = BASE64
+    .decode(&image.data)
+    .map_err(|e| BackupError::Codec(format!("background image: {e}")))?;
+  let options = BlobPropertyBag::new();
+  options.set_type(&image.mime);
+  let parts = Array::of1(&Uint8Array::from(bytes.as_slice()));
+  let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+    .map_err(|e| BackupError::Storage(format!("{e:?}")))?;
+  put_background_image(db, &image.key, &blob)
+    .await
+    .map_err(js_storage)
+}
+
+fn persistence() -> Result<PersistenceManager, BackupError> {
+  try_use_persistence_manager()
+    .ok_or_else(|| BackupError::Storage("persistence is not available".to_string()))
+}
+
+fn storage(err: crate::persistence::manager::PersistError) -> BackupError {
+  BackupError::Storage(err.to_string())
+}
+
+fn js_storage(err: wasm_bindgen::JsValue) -> BackupError {
+  BackupError::Storage(format!("{err:?}"))
+}
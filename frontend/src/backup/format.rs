@@ -0,0 +1,372 @@
+//! Backup file format.
+//!
+//! A backup file is one JSON object: a plaintext [`BackupHeader`]
+//! followed by the base64 IV and ciphertext of a [`BackupPayload`].
+//! The header's JSON encoding is bound into AES-GCM as additional
+//! data, so editing the KDF parameters or the schema version breaks
+//! authentication rather than silently changing how the file is read.
+//!
+//! Everything in this module is pure so it can be unit-tested
+//! natively; the browser side lives in `archive`.
+
+use crate::devices::link::LinkedConversation;
+use crate::persistence::schema::DB_VERSION;
+use crate::persistence::{AvatarEntry, ConvFlagsEntry, MessageRecord};
+use crate::settings::UserSettings;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use message::MessageId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Value of [`BackupHeader::format`]; tells a backup apart from the
+/// plaintext JSON export.
+pub const BACKUP_FORMAT: &str = "webrtc-e2ee-chat-backup";
+
+/// Current backup file version. Bump when the header or payload
+/// changes incompatibly.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Key derivation function written into new headers.
+pub const KDF_PBKDF2_SHA256: &str = "PBKDF2-SHA256";
+
+/// PBKDF2 iterations for new backups (OWASP 2023 guidance for
+/// PBKDF2-HMAC-SHA256).
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Lowest iteration count accepted on restore. Anything below is
+/// either corrupt or was written to make brute-forcing cheap.
+pub const MIN_PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Highest iteration count accepted on restore, so a crafted file
+/// cannot hang the tab in key derivation.
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/// Salt length in bytes.
+pub const SALT_LEN: usize = 16;
+
+/// Shortest passphrase accepted when creating a backup.
+pub const MIN_PASSPHRASE_CHARS: usize = 8;
+
+/// localStorage preferences carried next to [`UserSettings`]. Theme,
+/// locale, theater overlay and the blacklist each own their storage
+/// key outside `settings_user`.
+pub const PREFERENCE_KEYS: &[&str] = &[
+  "settings_theme",
+  "settings_locale",
+  "settings_theater_overlay",
+  crate::blacklist::STORAGE_KEY,
+];
+
+/// Why a backup could not be created or restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupError {
+  /// The passphrase is shorter than [`MIN_PASSPHRASE_CHARS`].
+  WeakPassphrase,
+  /// The file is not a backup (e.g. a plaintext export).
+  NotABackup,
+  /// The file was written by a newer backup format.
+  UnsupportedVersion(u32),
+  /// The file was written against a newer `IndexedDB` schema than
+  /// this build knows.
+  NewerSchema(u32),
+  /// Wrong passphrase, or the file was modified.
+  Decrypt,
+  /// Web Crypto failed.
+  Crypto(String),
+  /// Reading or writing `IndexedDB` failed.
+  Storage(String),
+  /// The file or payload is malformed.
+  Codec(String),
+}
+
+impl std::fmt::Display for BackupError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::WeakPassphrase => write!(
+        f,
+        "passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"
+      ),
+      Self::NotABackup => f.write_str("not an encrypted backup file"),
+      Self::UnsupportedVersion(v) => write!(f, "unsupported backup version {v}"),
+      Self::NewerSchema(v) => write!(
+        f,
+        "backup uses database version {v}, this app supports up to {DB_VERSION}"
+      ),
+      Self::Decrypt => f.write_str("wrong passphrase or corrupted backup"),
+      Self::Crypto(msg) => write!(f, "crypto error: {msg}"),
+      Self::Storage(msg) => write!(f, "storage error: {msg}"),
+      Self::Codec(msg) => write!(f, "codec error: {msg}"),
+    }
+  }
+}
+
+impl std::error::Error for BackupError {}
+
+/// Check a passphrase chosen for a new backup.
+///
+/// # Errors
+/// Returns [`BackupError::WeakPassphrase`] when it is too short.
+pub fn validate_passphrase(passphrase: &str) -> Result<(), BackupError> {
+  if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+    return Err(BackupError::WeakPassphrase);
+  }
+  Ok(())
+}
+
+/// Key derivation parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+  /// Always [`KDF_PBKDF2_SHA256`] for now.
+  pub algorithm: String,
+  /// PBKDF2 iteration count.
+  pub iterations: u32,
+  /// Base64 salt.
+  pub salt: String,
+}
+
+/// Plaintext part of a backup file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupHeader {
+  /// Always [`BACKUP_FORMAT`].
+  pub format: String,
+  /// Backup file version.
+  pub version: u32,
+  /// [`DB_VERSION`] of the app that wrote the backup.
+  pub db_version: u32,
+  /// RFC 3339 creation timestamp.
+  pub created_at: String,
+  /// How the key was derived from the passphrase.
+  pub kdf: KdfParams,
+}
+
+impl BackupHeader {
+  /// Header for a new backup with the given random `salt`.
+  #[must_use]
+  pub fn new(salt: &[u8], created_at: String) -> Self {
+    Self {
+      format: BACKUP_FORMAT.to_string(),
+      version: BACKUP_VERSION,
+      db_version: DB_VERSION,
+      created_at,
+      kdf: KdfParams {
+        algorithm: KDF_PBKDF2_SHA256.to_string(),
+        iterations: PBKDF2_ITERATIONS,
+        salt: BASE64.encode(salt),
+      },
+    }
+  }
+
+  /// Additional data bound into the AES-GCM seal.
+  #[must_use]
+  pub fn binding(&self) -> Vec<u8> {
+    serde_json::to_vec(self).unwrap_or_default()
+  }
+
+  /// Decoded salt.
+  ///
+  /// # Errors
+  /// Returns [`BackupError::Codec`] if the salt is not base64.
+  pub fn salt(&self) -> Result<Vec<u8>, BackupError> {
+    BASE64
+      .decode(&self.kdf.salt)
+      .map_err(|e| BackupError::Codec(format!("salt: {e}")))
+  }
+
+  /// Check that this build can read the backup.
+  ///
+  /// # Errors
+  /// Returns the reason the backup cannot be restored here.
+  pub fn check_compatible(&self) -> Result<(), BackupError> {
+    if self.format != BACKUP_FORMAT {
+      return Err(BackupError::NotABackup);
+    }
+    if self.version == 0 || self.version > BACKUP_VERSION {
+      return Err(BackupError::UnsupportedVersion(self.version));
+    }
+    if self.db_version > DB_VERSION {
+      return Err(BackupError::NewerSchema(self.db_version));
+    }
+    if self.kdf.algorithm != KDF_PBKDF2_SHA256
+      || !(MIN_PBKDF2_ITERATIONS..=MAX_PBKDF2_ITERATIONS).contains(&self.kdf.iterations)
+    {
+      return Err(BackupError::Codec(format!(
+        "unsupported key derivation {} x{}",
+        self.kdf.algorithm, self.kdf.iterations
+      )));
+    }
+    Ok(())
+  }
+}
+
+/// A backup file as written to disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+  /// Plaintext header.
+  #[serde(flatten)]
+  pub header: BackupHeader,
+  /// Base64 AES-GCM IV.
+  pub iv: String,
+  /// Base64 AES-GCM ciphertext of the JSON [`BackupPayload`].
+  pub ciphertext: String,
+}
+
+impl BackupFile {
+  /// Wrap a sealed payload.
+  #[must_use]
+  pub fn new(header: BackupHeader, iv: &[u8], ciphertext: &[u8]) -> Self {
+    Self {
+      header,
+      iv: BASE64.encode(iv),
+      ciphertext: BASE64.encode(ciphertext),
+    }
+  }
+
+  /// Parse and version-check a backup file.
+  ///
+  /// # Errors
+  /// Returns [`BackupError::NotABackup`] for anything that is not a
+  /// backup, or the reason [`BackupHeader::check_compatible`] gives.
+  pub fn parse(text: &str) -> Result<Self, BackupError> {
+    let file: Self = serde_json::from_str(text).map_err(|_| BackupError::NotABackup)?;
+    file.header.check_compatible()?;
+    Ok(file)
+  }
+
+  /// Decoded IV and ciphertext.
+  ///
+  /// # Errors
+  /// Returns [`BackupError::Codec`] if either is not base64.
+  pub fn sealed(&self) -> Result<(Vec<u8>, Vec<u8>), BackupError> {
+    let iv = BASE64
+      .decode(&self.iv)
+      .map_err(|e| BackupError::Codec(format!("iv: {e}")))?;
+    let ciphertext = BASE64
+      .decode(&self.ciphertext)
+      .map_err(|e| BackupError::Codec(format!("ciphertext: {e}")))?;
+    Ok((iv, ciphertext))
+  }
+
+  /// Pretty-printed JSON for download.
+  #[must_use]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap_or_default()
+  }
+}
+
+/// One blob of the `background_image` store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackgroundImageEntry {
+  /// Store key (`user_bg_light` / `user_bg_dark`).
+  pub key: String,
+  /// Blob MIME type.
+  pub mime: String,
+  /// Base64 blob bytes.
+  pub data: String,
+}
+
+/// Everything a backup restores, encrypted as one JSON document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupPayload {
+  /// App version that wrote the backup.
+  pub app_version: String,
+  /// The `settings_user` snapshot.
+  pub settings: UserSettings,
+  /// Values of [`PREFERENCE_KEYS`] present at backup time.
+  #[serde(default)]
+  pub preferences: BTreeMap<String, String>,
+  /// Sidebar conversations, so restored history has somewhere to
+  /// show up.
+  #[serde(default)]
+  pub conversations: Vec<LinkedConversation>,
+  /// Pin / mute / archive rows.
+  #[serde(default)]
+  pub conversation_flags: Vec<ConvFlagsEntry>,
+  /// Cached avatars.
+  #[serde(default)]
+  pub avatars: Vec<AvatarEntry>,
+  /// Custom background images.
+  #[serde(default)]
+  pub background_images: Vec<BackgroundImageEntry>,
+  /// Every stored message, oldest first.
+  #[serde(default)]
+  pub messages: Vec<MessageRecord>,
+}
+
+impl BackupPayload {
+  /// Drop preferences outside [`PREFERENCE_KEYS`] and background
+  /// images outside the canonical slots, so a crafted backup cannot
+  /// write arbitrary localStorage keys (auth tokens included).
+  pub fn retain_known_entries(&mut self) {
+    self
+      .preferences
+      .retain(|key, _| PREFERENCE_KEYS.contains(&key.as_str()));
+    self
+      .background_images
+      .retain(|bg| crate::persistence::schema::is_canonical_background_key(&bg.key));
+  }
+}
+
+/// Records worth importing: valid [`MessageId`]s only, each id once.
+/// On duplicates the most recently edited copy wins.
+#[must_use]
+pub fn dedupe_records(records: Vec<MessageRecord>) -> Vec<MessageRecord> {
+  let mut position: HashMap<MessageId, usize> = HashMap::with_capacity(records.len());
+  let mut out: Vec<MessageRecord> = Vec::with_capacity(records.len());
+  for record in records {
+    let Ok(id) = record.message_id.parse::<MessageId>() else {
+      continue;
+    };
+    if let Some(&at) = position.get(&id) {
+      if edit_revision(&record) > edit_revision(&out[at]) {
+        out[at] = record;
+      }
+    } else {
+      position.insert(id, out.len());
+      out.push(record);
+    }
+  }
+  out
+}
+
+fn edit_revision(record: &MessageRecord) -> u32 {
+  record.edit.as_ref().map_or(0, |e| e.revision)
+}
+
+/// Flag rows from the backup for conversations this device has no
+/// row for. Local rows are newer than any backup of them.
+#[must_use]
+pub fn missing_flags(local: &[ConvFlagsEntry], backup: Vec<ConvFlagsEntry>) -> Vec<ConvFlagsEntry> {
+  let known: HashSet<&str> = local.iter().map(|f| f.conversation_id.as_str()).collect();
+  backup
+    .into_iter()
+    .filter(|f| !known.contains(f.conversation_id.as_str()))
+    .collect()
+}
+
+/// Avatars from the backup that are missing locally or newer than the
+/// cached copy.
+#[must_use]
+pub fn newer_avatars(local: &[AvatarEntry], backup: Vec<AvatarEntry>) -> Vec<AvatarEntry> {
+  let cached: HashMap<&str, i64> = local
+    .iter()
+    .map(|a| (a.user_id.as_str(), a.cached_at_ms))
+    .collect();
+  backup
+    .into_iter()
+    .filter(|a| {
+      cached
+        .get(a.user_id.as_str())
+        .is_none_or(|&at| a.cached_at_ms > at)
+    })
+    .collect()
+}
+
+/// What a restore changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+  /// Messages added (already-present ones are not counted).
+  pub messages: usize,
+  /// Conversations added to the sidebar.
+  pub conversations: usize,
+}
@@ -0,0 +1,30 @@
+//! Encrypted backup and restore of local chat data.
+//!
+//! The plaintext export in [`crate::settings::ExportPayload`] is for
+//! reading; a backup is for getting the data back. It covers every
+//! stored message, conversation flags, cached avatars, custom
+//! background images and settings, sealed with AES-256-GCM under a key
+//! derived from a passphrase (PBKDF2-SHA256 via Web Crypto).
+//!
+//! * [`format`] — pure pieces: the file header and its version checks
+//!   against [`crate::persistence::schema::DB_VERSION`], the payload,
+//!   and the merge rules applied on restore.
+//! * `archive` — [`create_backup`] / [`restore_backup`]: reading and
+//!   writing `IndexedDB`, localStorage and the live settings (WASM
+//!   only). Key derivation lives in [`crate::webrtc::BackupCipher`].
+//!
+//! Restore merges rather than replaces: messages are deduplicated by
+//! `MessageId`, so restoring the same backup twice, or restoring onto
+//! a device that kept part of its history, adds only what is missing.
+//! The `search_index` store is rebuilt once the messages are in.
+
+#[cfg(target_arch = "wasm32")]
+mod archive;
+pub mod format;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(target_arch = "wasm32")]
+pub use archive::{create_backup, restore_backup};
+pub use format::{BackupError, RestoreSummary};
@@ -0,0 +1,206 @@
+use super::format::{
+  BACKUP_FORMAT, BACKUP_VERSION, BackgroundImageEntry, BackupError, BackupFile, BackupHeader,
+  BackupPayload, MAX_PBKDF2_ITERATIONS, PBKDF2_ITERATIONS, dedupe_records, missing_flags,
+  newer_avatars, validate_passphrase,
+};
+use crate::persistence::record::{ContentRecord, EditRecord, StatusRecord};
+use crate::persistence::schema::{DB_VERSION, KEY_USER_BG_DARK};
+use crate::persistence::{AvatarEntry, ConvFlagsEntry, MessageRecord};
+use crate::settings::UserSettings;
+use std::collections::BTreeMap;
+
+const ID_A: &str = "00000000-0000-0000-0000-00000000000a";
+const ID_B: &str = "00000000-0000-0000-0000-00000000000b";
+
+fn record(id: &str, ts: i64, text: &str) -> MessageRecord {
+  MessageRecord {
+    message_id: id.to_string(),
+    conversation: "d:11111111-1111-1111-1111-111111111111".to_string(),
+    timestamp_ms: ts,
+    sender: "11111111-1111-1111-1111-111111111111".to_string(),
+    sender_name: "S".to_string(),
+    outgoing: false,
+    status: StatusRecord::Received,
+    reply_to: None,
+    thread_root: None,
+    read_by: Vec::new(),
+    reactions: BTreeMap::new(),
+    mentions_me: false,
+    content: ContentRecord::Text {
+      text: text.to_string(),
+    },
+    edit: None,
+  }
+}
+
+fn flags(conversation_id: &str, pinned: bool) -> ConvFlagsEntry {
+  ConvFlagsEntry {
+    conversation_id: conversation_id.to_string(),
+    pinned,
+    pinned_at_ms: None,
+    muted: false,
+    archived: false,
+  }
+}
+
+fn avatar(user_id: &str, cached_at_ms: i64) -> AvatarEntry {
+  AvatarEntry {
+    user_id: user_id.to_string(),
+    data_uri: format!("data:image/png;base64,{cached_at_ms}"),
+    cached_at_ms,
+  }
+}
+
+fn header() -> BackupHeader {
+  BackupHeader::new(&[7; 16], "2026-01-01T00:00:00Z".to_string())
+}
+
+// ── Header & versioning ──
+
+#[test]
+fn new_header_targets_current_schema() {
+  let h = header();
+  assert_eq!(h.format, BACKUP_FORMAT);
+  assert_eq!(h.version, BACKUP_VERSION);
+  assert_eq!(h.db_version, DB_VERSION);
+  assert_eq!(h.kdf.iterations, PBKDF2_ITERATIONS);
+  assert_eq!(h.salt().unwrap(), vec![7; 16]);
+  assert!(h.check_compatible().is_ok());
+}
+
+#[test]
+fn file_roundtrips_and_binding_is_stable() {
+  let file = BackupFile::new(header(), &[1; 12], &[2, 3, 4]);
+  let parsed = BackupFile::parse(&file.to_json()).unwrap();
+  assert_eq!(parsed, file);
+  assert_eq!(parsed.header.binding(), file.header.binding());
+  assert_eq!(parsed.sealed().unwrap(), (vec![1; 12], vec![2, 3, 4]));
+}
+
+#[test]
+fn parse_rejects_other_json_and_newer_versions() {
+  assert_eq!(
+    BackupFile::parse(r#"{"exported_at":"x","settings":{}}"#),
+    Err(BackupError::NotABackup)
+  );
+  assert_eq!(BackupFile::parse("not json"), Err(BackupError::NotABackup));
+
+  let mut newer_schema = BackupFile::new(header(), &[1; 12], &[2]);
+  newer_schema.header.db_version = DB_VERSION + 1;
+  assert_eq!(
+    BackupFile::parse(&newer_schema.to_json()),
+    Err(BackupError::NewerSchema(DB_VERSION + 1))
+  );
+
+  let mut newer_format = BackupFile::new(header(), &[1; 12], &[2]);
+  newer_format.header.version = BACKUP_VERSION + 1;
+  assert_eq!(
+    BackupFile::parse(&newer_format.to_json()),
+    Err(BackupError::UnsupportedVersion(BACKUP_VERSION + 1))
+  );
+}
+
+#[test]
+fn older_schema_is_accepted_but_extreme_kdf_is_not() {
+  let mut older = header();
+  older.db_version = 1;
+  assert!(older.check_compatible().is_ok());
+
+  let mut cheap = header();
+  cheap.kdf.iterations = 1;
+  assert!(matches!(
+    cheap.check_compatible(),
+    Err(BackupError::Codec(_))
+  ));
+
+  let mut slow = header();
+  slow.kdf.iterations = MAX_PBKDF2_ITERATIONS + 1;
+  assert!(matches!(
+    slow.check_compatible(),
+    Err(BackupError::Codec(_))
+  ));
+}
+
+#[test]
+fn short_passphrases_are_rejected() {
+  assert_eq!(
+    validate_passphrase("short"),
+    Err(BackupError::WeakPassphrase)
+  );
+  assert!(validate_passphrase("correct horse").is_ok());
+}
+
+// ── Payload ──
+
+#[test]
+fn payload_drops_unknown_preferences_and_background_keys() {
+  let mut payload = BackupPayload {
+    app_version: "0.1.0".to_string(),
+    settings: UserSettings::default(),
+    preferences: BTreeMap::from([
+      ("settings_theme".to_string(), "dark".to_string()),
+      ("auth_token".to_string(), "stolen".to_string()),
+    ]),
+    conversations: Vec::new(),
+    conversation_flags: Vec::new(),
+    avatars: Vec::new(),
+    background_images: vec![
+      BackgroundImageEntry {
+        key: KEY_USER_BG_DARK.to_string(),
+        mime: "image/webp".to_string(),
+        data: String::new(),
+      },
+      BackgroundImageEntry {
+        key: "other".to_string(),
+        mime: "image/webp".to_string(),
+        data: String::new(),
+      },
+    ],
+    messages: vec![record(ID_A, 1, "hi")],
+  };
+  payload.retain_known_entries();
+  assert_eq!(payload.preferences.len(), 1);
+  assert!(payload.preferences.contains_key("settings_theme"));
+  assert_eq!(payload.background_images.len(), 1);
+
+  let json = serde_json::to_string(&payload).unwrap();
+  let back: BackupPayload = serde_json::from_str(&json).unwrap();
+  assert_eq!(back, payload);
+}
+
+// ── Merge rules ──
+
+#[test]
+fn dedupe_keeps_one_copy_per_message_id() {
+  let mut edited = record(ID_A, 1, "edited");
+  edited.edit = Some(EditRecord {
+    revision: 2,
+    edited_at_ms: 5,
+    previous: Vec::new(),
+  });
+  let out = dedupe_records(vec![
+    record(ID_A, 1, "original"),
+    record(ID_B, 2, "other"),
+    record("not-a-uuid", 3, "bad"),
+    edited.clone(),
+    record(ID_A, 1, "stale"),
+  ]);
+  assert_eq!(out, vec![edited, record(ID_B, 2, "other")]);
+}
+
+#[test]
+fn flags_only_fill_missing_rows() {
+  let local = vec![flags("a", false)];
+  let out = missing_flags(&local, vec![flags("a", true), flags("b", true)]);
+  assert_eq!(out, vec![flags("b", true)]);
+}
+
+#[test]
+fn avatars_are_taken_when_newer_or_missing() {
+  let local = vec![avatar("u1", 10), avatar("u2", 10)];
+  let out = newer_avatars(
+    &local,
+    vec![avatar("u1", 5), avatar("u2", 20), avatar("u3", 1)],
+  );
+  assert_eq!(out, vec![avatar("u2", 20), avatar("u3", 1)]);
+}
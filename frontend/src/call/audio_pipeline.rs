@@ -0,0 +1,150 @@
+//! Web Audio glue for the outbound microphone [`DspChain`].
+//!
+//! ```text
+//!   mic track ─► MediaStreamAudioSourceNode ─► ScriptProcessorNode ─► MediaStreamAudioDestinationNode ─► processed track
+//!                                               (DspChain::process)
+//! ```
+//!
+//! The processed track replaces the raw microphone track in the local
+//! stream, so everything downstream (peer senders, recording, VAD)
+//! sees cleaned-up audio without knowing the pipeline exists.
+//!
+//! `ScriptProcessorNode` is deprecated in favour of `AudioWorklet`, but
+//! a worklet runs in its own global scope and would need a second
+//! instance of the WASM module plus a JS loader shim. The processor
+//! node runs our Rust on the main thread instead, at the cost of a
+//! [`BUFFER_SIZE`]-sample block of extra latency (≈ 21 ms at 48 kHz)
+//! and some sensitivity to main-thread stalls.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{
+  AudioContext, AudioProcessingEvent, MediaStream, MediaStreamAudioDestinationNode,
+  MediaStreamAudioSourceNode, MediaStreamTrack, ScriptProcessorNode,
+};
+
+use super::dsp::{DspChain, DspConfig};
+use super::media;
+
+/// `ScriptProcessorNode` block size. Must be a power of two between
+/// 256 and 16384; 1024 keeps the callback rate (~47 Hz at 48 kHz) low
+/// enough for the main thread without adding much latency.
+pub const BUFFER_SIZE: u32 = 1024;
+
+/// A running microphone processing graph.
+///
+/// Owns the raw microphone track: [`Self::close`] stops it, since once
+/// the processed track is installed nothing else holds a reference to
+/// the original.
+pub struct AudioPipeline {
+  ctx: AudioContext,
+  source: MediaStreamAudioSourceNode,
+  processor: ScriptProcessorNode,
+  _destination: MediaStreamAudioDestinationNode,
+  _on_process: Closure<dyn FnMut(AudioProcessingEvent)>,
+  chain: Rc<RefCell<DspChain>>,
+  raw_track: MediaStreamTrack,
+}
+
+impl std::fmt::Debug for AudioPipeline {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("AudioPipeline")
+      .field("config", &self.chain.borrow().config())
+      .finish_non_exhaustive()
+  }
+}
+
+impl AudioPipeline {
+  /// Route the first audio track of `raw` through a new [`DspChain`].
+  ///
+  /// Returns the pipeline together with a stream holding the processed
+  /// audio track followed by `raw`'s video tracks, ready to be used as
+  /// the local stream.
+  ///
+  /// # Errors
+  /// Returns `Err` when `raw` has no audio track or the browser cannot
+  /// build the Web Audio graph. The caller should then fall back to the
+  /// unprocessed stream.
+  pub fn attach(raw: &MediaStream, config: DspConfig) -> Result<(Self, MediaStream), String> {
+    let raw_track = media::first_audio_track(raw).ok_or("Stream has no audio track")?;
+
+    let ctx = AudioContext::new().map_err(|e| format!("AudioContext failed: {e:?}"))?;
+    let mic_only = MediaStream::new().map_err(|e| format!("MediaStream failed: {e:?}"))?;
+    mic_only.add_track(&raw_track);
+    let source = ctx
+      .create_media_stream_source(&mic_only)
+      .map_err(|e| format!("createMediaStreamSource failed: {e:?}"))?;
+    let processor = ctx
+      .create_script_processor_with_buffer_size_and_number_of_input_channels_and_number_of_output_channels(
+        BUFFER_SIZE,
+        1,
+        1,
+      )
+      .map_err(|e| format!("createScriptProcessor failed: {e:?}"))?;
+    let destination = ctx
+      .create_media_stream_destination()
+      .map_err(|e| format!("createMediaStreamDestination failed: {e:?}"))?;
+
+    let chain = Rc::new(RefCell::new(DspChain::new(ctx.sample_rate(), config)));
+    let chain_for_cb = chain.clone();
+    let mut output = vec![0.0f32; BUFFER_SIZE as usize];
+    let on_process =
+      Closure::<dyn FnMut(AudioProcessingEvent)>::new(move |event: AudioProcessingEvent| {
+        let (Ok(input_buffer), Ok(output_buffer)) = (event.input_buffer(), event.output_buffer())
+        else {
+          return;
+        };
+        let Ok(input) = input_buffer.get_channel_data(0) else {
+          return;
+        };
+        output.resize(input.len(), 0.0);
+        chain_for_cb.borrow_mut().process(&input, &mut output);
+        let _ = output_buffer.copy_to_channel(&output, 0);
+      });
+    processor.set_onaudioprocess(Some(on_process.as_ref().unchecked_ref()));
+
+    source
+      .connect_with_audio_node(&processor)
+      .map_err(|e| format!("source.connect(processor) failed: {e:?}"))?;
+    processor
+      .connect_with_audio_node(&destination)
+      .map_err(|e| format!("processor.connect(destination) failed: {e:?}"))?;
+
+    let processed = destination.stream();
+    for track in raw.get_video_tracks().iter() {
+      if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+        processed.add_track(&track);
+      }
+    }
+
+    Ok((
+      Self {
+        ctx,
+        source,
+        processor,
+        _destination: destination,
+        _on_process: on_process,
+        chain,
+        raw_track,
+      },
+      processed,
+    ))
+  }
+
+  /// Switch processing stages on or off without rebuilding the graph.
+  pub fn set_config(&self, config: DspConfig) {
+    self.chain.borrow_mut().set_config(config);
+  }
+
+  /// Tear the graph down and stop the raw microphone track.
+  pub fn close(self) {
+    self.processor.set_onaudioprocess(None);
+    let _ = self.source.disconnect();
+    let _ = self.processor.disconnect();
+    self.raw_track.stop();
+    let _ = self.ctx.close();
+  }
+}
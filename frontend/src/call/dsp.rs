@@ -0,0 +1,168 @@
+//! Outbound microphone processing, in pure Rust.
+//!
+//! The chain runs three switchable stages on every
+//! [`HOP_SIZE`]-sample frame, in this order:
+//!
+//! 1. [`NoiseSuppressor`] — RNNoise-style spectral suppression of
+//!    stationary background noise (fans, hum, keyboard bed).
+//! 2. [`NoiseGate`] — attenuates everything between phrases, opened
+//!    and closed by the chain's [`VoiceActivity`] verdict.
+//! 3. [`AutoGain`] — levels speech towards a fixed loudness and
+//!    limits peaks.
+//!
+//! Nothing in this module touches `web_sys`; the Web Audio glue lives
+//! in [`super::audio_pipeline`], which feeds the chain from a
+//! `ScriptProcessorNode`. Keeping the DSP free of browser types is what
+//! lets `tests.rs` run it natively against the synthetic reference
+//! recordings in `fixtures.rs`.
+//!
+//! The chain accepts blocks of any length and always returns the same
+//! number of samples, delayed by a constant [`LATENCY_SAMPLES`]. The
+//! latency does not change when stages are toggled, so switching a
+//! stage mid-call is glitch-free.
+
+mod activity;
+mod agc;
+mod denoise;
+mod fft;
+mod gate;
+
+#[cfg(test)]
+mod fixtures;
+
+use std::collections::VecDeque;
+
+pub use activity::{VoiceActivity, rms_db};
+pub use agc::{AutoGain, MAX_GAIN_DB, PEAK_LIMIT, TARGET_DB};
+pub use denoise::{FFT_SIZE, GAIN_FLOOR, HOP_SIZE, NoiseSuppressor};
+pub use fft::{Complex, Fft};
+pub use gate::{GATE_FLOOR, NoiseGate};
+
+/// Total delay between a sample entering [`DspChain::process`] and
+/// leaving it: one hop of input buffering plus one hop of STFT overlap.
+pub const LATENCY_SAMPLES: usize = 2 * HOP_SIZE;
+
+/// Which stages of the chain are active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DspConfig {
+  /// Spectral noise suppression.
+  pub noise_suppression: bool,
+  /// VAD-driven noise gate.
+  pub noise_gate: bool,
+  /// Automatic gain control.
+  pub auto_gain: bool,
+}
+
+impl DspConfig {
+  /// Every stage switched on.
+  pub const ALL: Self = Self {
+    noise_suppression: true,
+    noise_gate: true,
+    auto_gain: true,
+  };
+
+  /// Every stage switched off (pure delay line).
+  pub const NONE: Self = Self {
+    noise_suppression: false,
+    noise_gate: false,
+    auto_gain: false,
+  };
+}
+
+impl Default for DspConfig {
+  fn default() -> Self {
+    Self::ALL
+  }
+}
+
+/// Streaming processing chain for one mono microphone signal.
+#[derive(Debug, Clone)]
+pub struct DspChain {
+  config: DspConfig,
+  denoiser: NoiseSuppressor,
+  activity: VoiceActivity,
+  gate: NoiseGate,
+  agc: AutoGain,
+  /// Input samples waiting for a full hop.
+  pending: Vec<f32>,
+  /// Processed samples waiting to be handed out.
+  ready: VecDeque<f32>,
+  hop: Vec<f32>,
+}
+
+impl DspChain {
+  /// Build a chain for `sample_rate` Hz mono input.
+  #[must_use]
+  pub fn new(sample_rate: f32, config: DspConfig) -> Self {
+    // Pre-filling one hop of silence guarantees `process` can always
+    // return as many samples as it was given.
+    let mut ready = VecDeque::with_capacity(4 * HOP_SIZE);
+    ready.extend(std::iter::repeat_n(0.0, HOP_SIZE));
+    Self {
+      config,
+      denoiser: NoiseSuppressor::new(sample_rate),
+      activity: VoiceActivity::new(sample_rate, HOP_SIZE),
+      gate: NoiseGate::new(sample_rate),
+      agc: AutoGain::new(sample_rate, HOP_SIZE),
+      pending: Vec::with_capacity(HOP_SIZE),
+      ready,
+      hop: vec![0.0; HOP_SIZE],
+    }
+  }
+
+  /// Active stages.
+  #[must_use]
+  pub const fn config(&self) -> DspConfig {
+    self.config
+  }
+
+  /// Switch stages on or off; takes effect from the next hop.
+  pub fn set_config(&mut self, config: DspConfig) {
+    self.config = config;
+  }
+
+  /// Whether the chain currently hears speech.
+  #[must_use]
+  pub const fn is_speaking(&self) -> bool {
+    self.activity.is_speaking()
+  }
+
+  /// Process `input` into `output` (same length), delayed by
+  /// [`LATENCY_SAMPLES`]. Non-finite input samples are treated as
+  /// silence so one bad buffer cannot poison the filter state.
+  ///
+  /// # Panics
+  /// Panics when `input` and `output` differ in length.
+  pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+    assert_eq!(input.len(), output.len(), "DSP blocks must match");
+    for &sample in input {
+      self
+        .pending
+        .push(if sample.is_finite() { sample } else { 0.0 });
+      if self.pending.len() == HOP_SIZE {
+        self.run_hop();
+      }
+    }
+    for slot in output.iter_mut() {
+      *slot = self.ready.pop_front().unwrap_or(0.0);
+    }
+  }
+
+  fn run_hop(&mut self) {
+    let config = self.config;
+    self
+      .denoiser
+      .process_hop(&self.pending, &mut self.hop, config.noise_suppression);
+    self.pending.clear();
+    let speaking = self.activity.update(&self.hop);
+    let voiced = self.activity.is_voiced();
+    self
+      .gate
+      .process(&mut self.hop, speaking, config.noise_gate);
+    self.agc.process(&mut self.hop, voiced, config.auto_gain);
+    self.ready.extend(self.hop.iter().copied());
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,112 @@
+//! Frame-level voice-activity decision for the DSP chain.
+//!
+//! Same idea as the `AnalyserNode`-based [`crate::call::VoiceActivityDetector`]
+//! — an energy threshold plus a hang-over window so pauses between
+//! words do not flicker — but computed in Rust on the samples the chain
+//! already holds, and relative to an adaptive noise floor instead of a
+//! fixed threshold, so it keeps working after the noise suppressor has
+//! changed the level of the background.
+
+/// Level above the tracked noise floor that counts as speech.
+const SPEECH_MARGIN_DB: f32 = 9.0;
+
+/// Absolute level below which a frame is never speech.
+const SILENCE_DB: f32 = -65.0;
+
+/// Upward drift of the noise floor per frame (≈ 5 dB/s at a 256-sample
+/// hop and 48 kHz): slow enough that a phrase does not become the new
+/// floor, fast enough to learn a louder room within a few seconds.
+const FLOOR_RISE_DB: f32 = 0.03;
+
+/// Hang-over after the last speech frame, in seconds.
+const HANG_OVER_SECS: f32 = 0.2;
+
+/// Level reported for digital silence.
+const MIN_DB: f32 = -120.0;
+
+/// RMS level of `samples` in dBFS.
+#[must_use]
+pub fn rms_db(samples: &[f32]) -> f32 {
+  if samples.is_empty() {
+    return MIN_DB;
+  }
+  let mean_sq = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+  if mean_sq <= 0.0 {
+    MIN_DB
+  } else {
+    (10.0 * mean_sq.log10()).max(MIN_DB)
+  }
+}
+
+/// Adaptive energy VAD with hang-over.
+#[derive(Debug, Clone)]
+pub struct VoiceActivity {
+  floor_db: Option<f32>,
+  hang_over_frames: u32,
+  hang_over: u32,
+  voiced: bool,
+  speaking: bool,
+}
+
+impl VoiceActivity {
+  /// Build a detector for frames of `frame_len` samples at
+  /// `sample_rate` Hz.
+  #[must_use]
+  pub fn new(sample_rate: f32, frame_len: usize) -> Self {
+    let frames_per_sec = sample_rate / frame_len as f32;
+    Self {
+      floor_db: None,
+      hang_over_frames: (HANG_OVER_SECS * frames_per_sec).ceil() as u32,
+      hang_over: 0,
+      voiced: false,
+      speaking: false,
+    }
+  }
+
+  /// Feed one frame and return the updated verdict.
+  pub fn update(&mut self, frame: &[f32]) -> bool {
+    let level = rms_db(frame);
+    // Digital silence (pipeline start-up, a muted device) says nothing
+    // about the room and would pin the floor far below the real noise.
+    if level <= SILENCE_DB {
+      self.voiced = false;
+      if self.hang_over > 0 {
+        self.hang_over -= 1;
+      } else {
+        self.speaking = false;
+      }
+      return self.speaking;
+    }
+    let floor = match self.floor_db {
+      Some(floor) if level < floor => level,
+      Some(floor) => floor + FLOOR_RISE_DB,
+      None => level,
+    };
+    self.floor_db = Some(floor);
+
+    self.voiced = level > floor + SPEECH_MARGIN_DB;
+    if self.voiced {
+      self.hang_over = self.hang_over_frames;
+      self.speaking = true;
+    } else if self.hang_over > 0 {
+      self.hang_over -= 1;
+    } else {
+      self.speaking = false;
+    }
+    self.speaking
+  }
+
+  /// Last verdict, including the hang-over.
+  #[must_use]
+  pub const fn is_speaking(&self) -> bool {
+    self.speaking
+  }
+
+  /// Whether the last frame itself was above the speech threshold,
+  /// ignoring the hang-over. Level measurements (AGC) use this so the
+  /// quiet tail of a phrase is not mistaken for quiet speech.
+  #[must_use]
+  pub const fn is_voiced(&self) -> bool {
+    self.voiced
+  }
+}
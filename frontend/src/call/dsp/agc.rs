@@ -0,0 +1,126 @@
+//! Automatic gain control.
+//!
+//! Tracks the speech level (only on frames the VAD marks as voiced, so
+//! pauses never get amplified into audible noise) and steers a gain
+//! towards [`TARGET_DB`]. Gain drops quickly and rises slowly, is
+//! capped at [`MAX_GAIN_DB`], and every frame is additionally limited
+//! so no sample leaves the stage above [`PEAK_LIMIT`].
+
+/// Target speech level in dBFS.
+pub const TARGET_DB: f32 = -20.0;
+
+/// Largest boost the AGC will apply.
+pub const MAX_GAIN_DB: f32 = 24.0;
+
+/// Strongest cut the AGC will apply.
+const MIN_GAIN_DB: f32 = -12.0;
+
+/// Highest absolute sample value allowed out of the stage (≈ −1 dBFS).
+pub const PEAK_LIMIT: f32 = 0.89;
+
+/// Time constant of the speech-level follower when the level rises,
+/// in seconds.
+const LEVEL_RISE_SECS: f32 = 0.1;
+
+/// Time constant of the speech-level follower when the level falls,
+/// in seconds. Slower than the rise so the soft edges of syllables do
+/// not drag the estimate down.
+const LEVEL_FALL_SECS: f32 = 1.5;
+
+/// Time constant for reducing gain, in seconds.
+const DECAY_SECS: f32 = 0.05;
+
+/// Time constant for increasing gain, in seconds.
+const GROW_SECS: f32 = 0.8;
+
+fn db_to_linear(db: f32) -> f32 {
+  10f32.powf(db / 20.0)
+}
+
+/// Frame-based AGC with a peak limiter.
+#[derive(Debug, Clone)]
+pub struct AutoGain {
+  /// Smoothed speech level in dBFS; `None` until the first voiced
+  /// frame, which seeds it directly so the first phrase is already
+  /// levelled.
+  level_db: Option<f32>,
+  gain: f32,
+  rise_coeff: f32,
+  fall_coeff: f32,
+  decay_coeff: f32,
+  grow_coeff: f32,
+}
+
+impl AutoGain {
+  /// Build an AGC for frames of `frame_len` samples at `sample_rate`
+  /// Hz. Starts at unity gain.
+  #[must_use]
+  pub fn new(sample_rate: f32, frame_len: usize) -> Self {
+    let frame_rate = sample_rate / frame_len as f32;
+    Self {
+      level_db: None,
+      gain: 1.0,
+      rise_coeff: super::gate::one_pole(LEVEL_RISE_SECS, frame_rate),
+      fall_coeff: super::gate::one_pole(LEVEL_FALL_SECS, frame_rate),
+      decay_coeff: super::gate::one_pole(DECAY_SECS, frame_rate),
+      grow_coeff: super::gate::one_pole(GROW_SECS, frame_rate),
+    }
+  }
+
+  /// Apply the AGC to `frame` in place. `voiced` gates level
+  /// tracking; with `enabled == false` the stage passes audio through
+  /// and resets to unity gain.
+  pub fn process(&mut self, frame: &mut [f32], voiced: bool, enabled: bool) {
+    if !enabled {
+      self.gain = 1.0;
+      self.level_db = None;
+      return;
+    }
+
+    let start = self.gain;
+    let mut target = self.gain;
+    if voiced {
+      let level = super::activity::rms_db(frame);
+      let smoothed = self.level_db.map_or(level, |prev| {
+        let coeff = if level > prev {
+          self.rise_coeff
+        } else {
+          self.fall_coeff
+        };
+        level + coeff * (prev - level)
+      });
+      self.level_db = Some(smoothed);
+      let wanted = db_to_linear((TARGET_DB - smoothed).clamp(MIN_GAIN_DB, MAX_GAIN_DB));
+      let coeff = if wanted < self.gain {
+        self.decay_coeff
+      } else {
+        self.grow_coeff
+      };
+      target = wanted + coeff * (self.gain - wanted);
+    }
+
+    // Limiter: neither end of the per-frame ramp may push the frame's
+    // peak over the ceiling.
+    let peak = frame.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    let ceiling = if peak > 0.0 {
+      PEAK_LIMIT / peak
+    } else {
+      f32::INFINITY
+    };
+    let start = start.min(ceiling);
+    let end = target.min(ceiling);
+    self.gain = target;
+
+    // Ramp across the frame to avoid zipper noise.
+    let step = (end - start) / frame.len().max(1) as f32;
+    for (i, sample) in frame.iter_mut().enumerate() {
+      *sample *= start + step * (i + 1) as f32;
+    }
+  }
+
+  /// Current gain (linear).
+  #[must_use]
+  pub const fn gain(&self) -> f32 {
+    self.gain
+  }
+}
@@ -0,0 +1,258 @@
+//! Spectral noise suppression.
+//!
+//! Modelled on RNNoise's signal path — a 50 %-overlap STFT, per-band
+//! gains on a Bark-like band layout, and linear interpolation of those
+//! gains across FFT bins — but with the recurrent network replaced by
+//! a classic estimator so it needs no trained weights:
+//!
+//! * the noise power of every band is tracked with a minimum follower
+//!   (falls quickly, creeps up slowly, and only adapts freely while
+//!   the band does not look like speech);
+//! * the gain is a Wiener gain on the decision-directed a-priori SNR
+//!   (Ephraim–Malah), which keeps "musical noise" low;
+//! * gains never drop below [`GAIN_FLOOR`] so the residual noise stays
+//!   natural instead of pumping in and out of digital silence.
+//!
+//! Analysis and synthesis both use a square-root periodic Hann window,
+//! so with every gain at 1.0 the output is the input delayed by
+//! [`HOP_SIZE`] samples, bit-for-bit up to float rounding. The stage
+//! relies on that when it is switched off: the STFT keeps running
+//! (and the noise estimate keeps learning) with unity gains, so
+//! toggling it mid-call neither shifts the latency nor starts from an
+//! untrained estimate.
+
+use super::fft::{Complex, Fft};
+
+/// STFT frame length in samples (≈ 10.7 ms at 48 kHz).
+pub const FFT_SIZE: usize = 512;
+
+/// STFT hop in samples (50 % overlap).
+pub const HOP_SIZE: usize = FFT_SIZE / 2;
+
+/// Band edges in Hz, following RNNoise's 5 ms band layout.
+const BAND_EDGES_HZ: [f32; 22] = [
+  0.0, 200.0, 400.0, 600.0, 800.0, 1000.0, 1200.0, 1400.0, 1600.0, 2000.0, 2400.0, 2800.0, 3200.0,
+  4000.0, 4800.0, 5600.0, 6800.0, 8000.0, 9600.0, 12000.0, 15600.0, 20000.0,
+];
+
+/// Lowest gain applied to a band (−20 dB).
+pub const GAIN_FLOOR: f32 = 0.1;
+
+/// Decision-directed smoothing of the a-priori SNR.
+const PRIOR_SNR_SMOOTHING: f32 = 0.98;
+
+/// Number of initial frames averaged into the first noise estimate
+/// (≈ 270 ms at 48 kHz). Calls almost always start with the user
+/// listening, so the opening frames are a good noise sample.
+const NOISE_INIT_FRAMES: u32 = 50;
+
+/// Per-frame weight of a new observation while the band looks like
+/// noise.
+const NOISE_ADAPT: f32 = 0.05;
+
+/// A band is treated as speech (and the noise estimate is only allowed
+/// to creep) when its power exceeds the estimate by this factor.
+const SPEECH_POWER_RATIO: f32 = 4.0;
+
+/// Per-frame multiplicative creep of the noise estimate during speech,
+/// so a permanent rise in background level is eventually learnt
+/// (≈ +2 dB/s at 48 kHz).
+const NOISE_CREEP: f32 = 1.0025;
+
+/// Tiny power floor that keeps the SNR maths finite on digital silence.
+const POWER_EPSILON: f32 = 1e-12;
+
+/// Streaming STFT noise suppressor working on [`HOP_SIZE`] frames.
+#[derive(Debug, Clone)]
+pub struct NoiseSuppressor {
+  fft: Fft,
+  window: Vec<f32>,
+  /// Last [`FFT_SIZE`] input samples.
+  analysis: Vec<f32>,
+  /// Overlap-add accumulator.
+  overlap: Vec<f32>,
+  spectrum: Vec<Complex>,
+  /// First bin of every band, plus a final sentinel (`FFT_SIZE / 2 + 1`).
+  band_start: Vec<usize>,
+  /// Centre bin of every band, used to interpolate gains.
+  band_centre: Vec<f32>,
+  band_power: Vec<f32>,
+  noise: Vec<f32>,
+  prev_gain: Vec<f32>,
+  prev_post_snr: Vec<f32>,
+  band_gain: Vec<f32>,
+  bin_gain: Vec<f32>,
+  frames: u32,
+}
+
+impl NoiseSuppressor {
+  /// Build a suppressor for `sample_rate` Hz input.
+  #[must_use]
+  pub fn new(sample_rate: f32) -> Self {
+    let half = FFT_SIZE / 2;
+    let window = (0..FFT_SIZE)
+      .map(|n| {
+        let phase = 2.0 * std::f32::consts::PI * n as f32 / FFT_SIZE as f32;
+        (0.5 - 0.5 * phase.cos()).sqrt()
+      })
+      .collect();
+
+    // Map the Hz layout onto bins; at low sample rates several edges
+    // collapse onto the Nyquist bin, so keep only strictly increasing
+    // starts.
+    let mut band_start: Vec<usize> = Vec::with_capacity(BAND_EDGES_HZ.len());
+    for hz in &BAND_EDGES_HZ[..BAND_EDGES_HZ.len() - 1] {
+      let bin = ((hz * FFT_SIZE as f32 / sample_rate).round() as usize).min(half);
+      if band_start.last().is_none_or(|&last| bin > last) {
+        band_start.push(bin);
+      }
+    }
+    band_start.push(half + 1);
+    let bands = band_start.len() - 1;
+    let band_centre = band_start
+      .windows(2)
+      .map(|w| (w[0] + w[1] - 1) as f32 / 2.0)
+      .collect();
+
+    Self {
+      fft: Fft::new(FFT_SIZE),
+      window,
+      analysis: vec![0.0; FFT_SIZE],
+      overlap: vec![0.0; FFT_SIZE],
+      spectrum: vec![Complex::default(); FFT_SIZE],
+      band_start,
+      band_centre,
+      band_power: vec![0.0; bands],
+      noise: vec![0.0; bands],
+      prev_gain: vec![1.0; bands],
+      prev_post_snr: vec![1.0; bands],
+      band_gain: vec![1.0; bands],
+      bin_gain: vec![1.0; half + 1],
+      frames: 0,
+    }
+  }
+
+  /// Process one hop. `input` and `output` must both hold
+  /// [`HOP_SIZE`] samples; `output` lags `input` by one hop.
+  ///
+  /// With `suppress == false` the noise estimate still updates but
+  /// every gain is 1.0, so the audio passes through unchanged.
+  pub fn process_hop(&mut self, input: &[f32], output: &mut [f32], suppress: bool) {
+    debug_assert_eq!(input.len(), HOP_SIZE);
+    debug_assert_eq!(output.len(), HOP_SIZE);
+
+    self.analysis.copy_within(HOP_SIZE.., 0);
+    self.analysis[FFT_SIZE - HOP_SIZE..].copy_from_slice(input);
+    for ((slot, &x), &w) in self
+      .spectrum
+      .iter_mut()
+      .zip(&self.analysis)
+      .zip(&self.window)
+    {
+      *slot = Complex::new(x * w, 0.0);
+    }
+    self.fft.forward(&mut self.spectrum);
+
+    self.measure_bands();
+    self.update_noise();
+    self.compute_gains(suppress);
+    self.apply_gains();
+
+    self.fft.inverse(&mut self.spectrum);
+    for ((acc, c), &w) in self
+      .overlap
+      .iter_mut()
+      .zip(&self.spectrum)
+      .zip(&self.window)
+    {
+      *acc += c.re * w;
+    }
+    output.copy_from_slice(&self.overlap[..HOP_SIZE]);
+    self.overlap.copy_within(HOP_SIZE.., 0);
+    self.overlap[FFT_SIZE - HOP_SIZE..].fill(0.0);
+    self.frames = self.frames.saturating_add(1);
+  }
+
+  /// Current per-band gains (for diagnostics and tests).
+  #[must_use]
+  pub fn band_gains(&self) -> &[f32] {
+    &self.band_gain
+  }
+
+  fn measure_bands(&mut self) {
+    for (b, power) in self.band_power.iter_mut().enumerate() {
+      let bins = self.band_start[b]..self.band_start[b + 1];
+      let count = bins.len().max(1) as f32;
+      *power = self.spectrum[bins]
+        .iter()
+        .map(|c| c.norm_sqr())
+        .sum::<f32>()
+        / count;
+    }
+  }
+
+  fn update_noise(&mut self) {
+    if self.frames < NOISE_INIT_FRAMES {
+      // Running mean over the opening frames.
+      let k = 1.0 / (self.frames + 1) as f32;
+      for (noise, &power) in self.noise.iter_mut().zip(&self.band_power) {
+        *noise += k * (power - *noise);
+      }
+      return;
+    }
+    for (noise, &power) in self.noise.iter_mut().zip(&self.band_power) {
+      if power < *noise {
+        // Follow drops quickly: the minimum is the best noise cue.
+        *noise = 0.5 * (*noise + power);
+      } else if power < SPEECH_POWER_RATIO * *noise {
+        *noise += NOISE_ADAPT * (power - *noise);
+      } else {
+        *noise *= NOISE_CREEP;
+      }
+    }
+  }
+
+  fn compute_gains(&mut self, suppress: bool) {
+    for b in 0..self.band_gain.len() {
+      let noise = self.noise[b].max(POWER_EPSILON);
+      let post_snr = self.band_power[b] / noise;
+      let prior_snr = PRIOR_SNR_SMOOTHING * self.prev_gain[b].powi(2) * self.prev_post_snr[b]
+        + (1.0 - PRIOR_SNR_SMOOTHING) * (post_snr - 1.0).max(0.0);
+      let gain = (prior_snr / (1.0 + prior_snr)).clamp(GAIN_FLOOR, 1.0);
+      self.prev_gain[b] = gain;
+      self.prev_post_snr[b] = post_snr;
+      self.band_gain[b] = if suppress { gain } else { 1.0 };
+    }
+
+    // Interpolate linearly between band centres (RNNoise's
+    // `interp_band_gain`), holding the outermost gains flat.
+    let last = self.band_gain.len() - 1;
+    let mut band = 0;
+    for (k, slot) in self.bin_gain.iter_mut().enumerate() {
+      let bin = k as f32;
+      while band < last && bin >= self.band_centre[band + 1] {
+        band += 1;
+      }
+      *slot = if bin <= self.band_centre[0] {
+        self.band_gain[0]
+      } else if band == last {
+        self.band_gain[last]
+      } else {
+        let (c0, c1) = (self.band_centre[band], self.band_centre[band + 1]);
+        let t = (bin - c0) / (c1 - c0);
+        self.band_gain[band] + t * (self.band_gain[band + 1] - self.band_gain[band])
+      };
+    }
+  }
+
+  fn apply_gains(&mut self) {
+    let half = FFT_SIZE / 2;
+    for k in 0..=half {
+      let g = self.bin_gain[k];
+      self.spectrum[k] = self.spectrum[k].scale(g);
+      if k != 0 && k != half {
+        self.spectrum[FFT_SIZE - k] = self.spectrum[FFT_SIZE - k].scale(g);
+      }
+    }
+  }
+}
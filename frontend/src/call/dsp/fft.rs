@@ -0,0 +1,125 @@
+//! Minimal in-place radix-2 complex FFT.
+//!
+//! Only power-of-two sizes are supported, which is all the STFT in
+//! [`super::denoise`] needs. Twiddle factors are pre-computed once per
+//! size so the per-frame cost is just the butterflies.
+
+use std::f32::consts::PI;
+
+/// A complex sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+  pub re: f32,
+  pub im: f32,
+}
+
+impl Complex {
+  #[must_use]
+  pub const fn new(re: f32, im: f32) -> Self {
+    Self { re, im }
+  }
+
+  /// Squared magnitude (`re² + im²`).
+  #[must_use]
+  pub fn norm_sqr(self) -> f32 {
+    self.re * self.re + self.im * self.im
+  }
+
+  /// Scale both components by a real factor.
+  #[must_use]
+  pub fn scale(self, k: f32) -> Self {
+    Self::new(self.re * k, self.im * k)
+  }
+
+  fn mul(self, other: Self) -> Self {
+    Self::new(
+      self.re * other.re - self.im * other.im,
+      self.re * other.im + self.im * other.re,
+    )
+  }
+}
+
+/// Pre-planned FFT of a fixed power-of-two size.
+#[derive(Debug, Clone)]
+pub struct Fft {
+  size: usize,
+  /// `e^{-2πik/N}` for `k` in `0..N/2`.
+  twiddles: Vec<Complex>,
+}
+
+impl Fft {
+  /// Plan an FFT of `size` points.
+  ///
+  /// # Panics
+  /// Panics when `size` is not a power of two.
+  #[must_use]
+  pub fn new(size: usize) -> Self {
+    assert!(size.is_power_of_two(), "FFT size must be a power of two");
+    let twiddles = (0..size / 2)
+      .map(|k| {
+        let angle = -2.0 * PI * k as f32 / size as f32;
+        Complex::new(angle.cos(), angle.sin())
+      })
+      .collect();
+    Self { size, twiddles }
+  }
+
+  /// Number of points.
+  #[must_use]
+  pub const fn size(&self) -> usize {
+    self.size
+  }
+
+  /// Forward transform, in place. Unnormalised.
+  pub fn forward(&self, buf: &mut [Complex]) {
+    self.transform(buf, false);
+  }
+
+  /// Inverse transform, in place. Normalised by `1/N`, so
+  /// `inverse(forward(x)) == x`.
+  pub fn inverse(&self, buf: &mut [Complex]) {
+    self.transform(buf, true);
+    let k = 1.0 / self.size as f32;
+    for c in buf.iter_mut() {
+      *c = c.scale(k);
+    }
+  }
+
+  fn transform(&self, buf: &mut [Complex], inverse: bool) {
+    let n = self.size;
+    assert_eq!(buf.len(), n, "FFT buffer length must match the plan");
+
+    // Bit-reversal permutation.
+    let mut j = 0;
+    for i in 1..n {
+      let mut bit = n >> 1;
+      while j & bit != 0 {
+        j ^= bit;
+        bit >>= 1;
+      }
+      j |= bit;
+      if i < j {
+        buf.swap(i, j);
+      }
+    }
+
+    // Iterative Cooley-Tukey butterflies.
+    let mut len = 2;
+    while len <= n {
+      let stride = n / len;
+      for start in (0..n).step_by(len) {
+        for k in 0..len / 2 {
+          let mut w = self.twiddles[k * stride];
+          if inverse {
+            w.im = -w.im;
+          }
+          let a = buf[start + k];
+          let b = buf[start + k + len / 2].mul(w);
+          buf[start + k] = Complex::new(a.re + b.re, a.im + b.im);
+          buf[start + k + len / 2] = Complex::new(a.re - b.re, a.im - b.im);
+        }
+      }
+      len <<= 1;
+    }
+  }
+}
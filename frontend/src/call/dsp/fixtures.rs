@@ -0,0 +1,128 @@
+//! Reference audio for the DSP tests.
+//!
+//! Fixtures are synthesised deterministically rather than checked in
+//! as WAV files: a seeded generator produces the same samples on every
+//! run and platform, and the clean / noise components stay available
+//! separately so tests can measure exactly what each stage did.
+//!
+//! Every fixture opens with [`LEAD_IN_SECS`] of background only (the
+//! "user is listening" part of a call), followed by alternating
+//! voiced syllables and short pauses.
+
+use std::f32::consts::PI;
+
+/// Sample rate of every fixture.
+pub const SAMPLE_RATE: f32 = 48_000.0;
+
+/// Background-only lead-in.
+pub const LEAD_IN_SECS: f32 = 1.0;
+
+/// Length of one voiced syllable.
+pub const SYLLABLE_SECS: f32 = 0.25;
+
+/// Pause after each syllable.
+pub const PAUSE_SECS: f32 = 0.25;
+
+/// Deterministic 32-bit LCG (Numerical Recipes constants).
+#[derive(Debug, Clone)]
+pub struct Lcg(u32);
+
+impl Lcg {
+  pub const fn new(seed: u32) -> Self {
+    Self(seed)
+  }
+
+  /// Uniform sample in `[-1, 1)`.
+  pub fn next_bipolar(&mut self) -> f32 {
+    self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+    (self.0 >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+  }
+}
+
+/// A reference recording split into its parts.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+  /// Speech only.
+  pub clean: Vec<f32>,
+  /// Background only.
+  pub noise: Vec<f32>,
+  /// `clean + noise`, what the microphone would capture.
+  pub mix: Vec<f32>,
+  /// `[start, end)` sample ranges of the voiced syllables.
+  pub syllables: Vec<(usize, usize)>,
+}
+
+impl Fixture {
+  /// Samples of the lead-in (background only).
+  pub fn lead_in(&self) -> std::ops::Range<usize> {
+    0..secs(LEAD_IN_SECS)
+  }
+}
+
+/// Seconds to samples.
+pub fn secs(s: f32) -> usize {
+  (s * SAMPLE_RATE) as usize
+}
+
+/// Voiced, speech-like signal: a 20-harmonic source with 1/k roll-off,
+/// gentle pitch glide and vibrato, shaped by a raised-cosine syllable
+/// envelope. `peak` is the envelope maximum.
+pub fn speech(syllables: usize, peak: f32) -> (Vec<f32>, Vec<(usize, usize)>) {
+  let syllable = secs(SYLLABLE_SECS);
+  let pause = secs(PAUSE_SECS);
+  let total = secs(LEAD_IN_SECS) + syllables * (syllable + pause);
+  let mut out = vec![0.0; total];
+  let mut ranges = Vec::with_capacity(syllables);
+  let mut phase = 0.0f32;
+  for s in 0..syllables {
+    let start = secs(LEAD_IN_SECS) + s * (syllable + pause);
+    ranges.push((start, start + syllable));
+    let base_f0 = 120.0 + 20.0 * (s % 3) as f32;
+    for i in 0..syllable {
+      let t = i as f32 / SAMPLE_RATE;
+      let f0 =
+        base_f0 * (1.0 + 0.1 * t / SYLLABLE_SECS) * (1.0 + 0.01 * (2.0 * PI * 5.0 * t).sin());
+      phase += 2.0 * PI * f0 / SAMPLE_RATE;
+      let env = 0.5 - 0.5 * (2.0 * PI * i as f32 / syllable as f32).cos();
+      let voiced: f32 = (1..=20).map(|k| (k as f32 * phase).sin() / k as f32).sum();
+      out[start + i] = peak * env * voiced / 2.0;
+    }
+  }
+  (out, ranges)
+}
+
+/// White noise at `rms` (linear) for `len` samples.
+pub fn white_noise(len: usize, rms: f32, seed: u32) -> Vec<f32> {
+  // A uniform distribution on [-1, 1) has an RMS of 1/√3.
+  let scale = rms * 3f32.sqrt();
+  let mut rng = Lcg::new(seed);
+  (0..len).map(|_| scale * rng.next_bipolar()).collect()
+}
+
+/// Mains-style hum: 50 Hz plus odd harmonics at `rms` (linear).
+pub fn hum(len: usize, rms: f32) -> Vec<f32> {
+  let norm = (0.5f32 * (1.0 + 0.25 + 0.0625)).sqrt();
+  (0..len)
+    .map(|i| {
+      let w = 2.0 * PI * 50.0 * i as f32 / SAMPLE_RATE;
+      rms / norm * (w.sin() + 0.5 * (3.0 * w).sin() + 0.25 * (5.0 * w).sin())
+    })
+    .collect()
+}
+
+/// Speech at `speech_peak` mixed with `noise`.
+pub fn noisy_speech(
+  syllables: usize,
+  speech_peak: f32,
+  noise: impl Fn(usize) -> Vec<f32>,
+) -> Fixture {
+  let (clean, syllables) = speech(syllables, speech_peak);
+  let noise = noise(clean.len());
+  let mix = clean.iter().zip(&noise).map(|(c, n)| c + n).collect();
+  Fixture {
+    clean,
+    noise,
+    mix,
+    syllables,
+  }
+}
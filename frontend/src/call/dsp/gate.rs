@@ -0,0 +1,62 @@
+//! Noise gate driven by the chain's voice-activity verdict.
+//!
+//! Instead of opening on a level threshold of its own, the gate
+//! follows [`super::activity::VoiceActivity`]: open while the user is
+//! speaking (including the VAD hang-over), closed to [`GATE_FLOOR`]
+//! otherwise. A fast attack and a slower release keep word onsets and
+//! tails intact without clicks.
+
+/// Gain while the gate is closed (−30 dB).
+pub const GATE_FLOOR: f32 = 0.031_6;
+
+/// Time to open, in seconds.
+const ATTACK_SECS: f32 = 0.005;
+
+/// Time to close, in seconds.
+const RELEASE_SECS: f32 = 0.08;
+
+/// Per-sample smoothed gate.
+#[derive(Debug, Clone)]
+pub struct NoiseGate {
+  gain: f32,
+  attack: f32,
+  release: f32,
+}
+
+impl NoiseGate {
+  /// Build a gate for `sample_rate` Hz input. Starts open.
+  #[must_use]
+  pub fn new(sample_rate: f32) -> Self {
+    Self {
+      gain: 1.0,
+      attack: one_pole(ATTACK_SECS, sample_rate),
+      release: one_pole(RELEASE_SECS, sample_rate),
+    }
+  }
+
+  /// Apply the gate to `frame` in place. `open` is the VAD verdict;
+  /// with `enabled == false` the gate ramps fully open and stays there.
+  pub fn process(&mut self, frame: &mut [f32], open: bool, enabled: bool) {
+    let target = if open || !enabled { 1.0 } else { GATE_FLOOR };
+    let coeff = if target > self.gain {
+      self.attack
+    } else {
+      self.release
+    };
+    for sample in frame {
+      self.gain = target + coeff * (self.gain - target);
+      *sample *= self.gain;
+    }
+  }
+
+  /// Current gain (for diagnostics and tests).
+  #[must_use]
+  pub const fn gain(&self) -> f32 {
+    self.gain
+  }
+}
+
+/// One-pole smoothing coefficient for a time constant of `secs`.
+pub(super) fn one_pole(secs: f32, sample_rate: f32) -> f32 {
+  (-1.0 / (secs * sample_rate)).exp()
+}
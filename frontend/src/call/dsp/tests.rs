@@ -0,0 +1,424 @@
+use super::fixtures::{self, Fixture, SAMPLE_RATE, secs};
+use super::*;
+
+/// Run `input` through a fresh chain in 1024-sample blocks (the
+/// `ScriptProcessorNode` buffer size) and return the output realigned
+/// with the input.
+fn run(config: DspConfig, input: &[f32]) -> Vec<f32> {
+  run_blocks(config, input, 1024)
+}
+
+fn run_blocks(config: DspConfig, input: &[f32], block: usize) -> Vec<f32> {
+  let mut chain = DspChain::new(SAMPLE_RATE, config);
+  let mut padded = input.to_vec();
+  padded.extend(std::iter::repeat_n(0.0, LATENCY_SAMPLES));
+  let mut out = vec![0.0; padded.len()];
+  for (i, o) in padded.chunks(block).zip(out.chunks_mut(block)) {
+    chain.process(i, o);
+  }
+  out.drain(..LATENCY_SAMPLES);
+  out
+}
+
+fn rms(samples: &[f32]) -> f32 {
+  (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn db(ratio: f32) -> f32 {
+  20.0 * ratio.log10()
+}
+
+/// Background-only part of a fixture's lead-in, skipping the
+/// noise-estimate warm-up.
+fn background(f: &Fixture) -> std::ops::Range<usize> {
+  secs(0.4)..f.lead_in().end
+}
+
+fn concat(samples: &[f32], ranges: &[std::ops::Range<usize>]) -> Vec<f32> {
+  ranges
+    .iter()
+    .flat_map(|r| samples[r.clone()].iter().copied())
+    .collect()
+}
+
+fn syllable_cores(f: &Fixture) -> Vec<std::ops::Range<usize>> {
+  // The middle half of every syllable, where the envelope is high.
+  f.syllables
+    .iter()
+    .map(|&(s, e)| {
+      let q = (e - s) / 4;
+      s + q..e - q
+    })
+    .collect()
+}
+
+fn white_fixture(syllables: usize, speech_peak: f32, noise_rms: f32) -> Fixture {
+  fixtures::noisy_speech(syllables, speech_peak, |len| {
+    fixtures::white_noise(len, noise_rms, 0x5EED)
+  })
+}
+
+// ── FFT ──────────────────────────────────────────────────────────────
+
+#[test]
+fn fft_round_trip_restores_signal() {
+  let fft = Fft::new(64);
+  let mut rng = fixtures::Lcg::new(7);
+  let original: Vec<Complex> = (0..64)
+    .map(|_| Complex::new(rng.next_bipolar(), rng.next_bipolar()))
+    .collect();
+  let mut buf = original.clone();
+  fft.forward(&mut buf);
+  fft.inverse(&mut buf);
+  for (a, b) in buf.iter().zip(&original) {
+    assert!((a.re - b.re).abs() < 1e-5 && (a.im - b.im).abs() < 1e-5);
+  }
+}
+
+#[test]
+fn fft_of_cosine_peaks_at_its_bin() {
+  let n = 256;
+  let fft = Fft::new(n);
+  let mut buf: Vec<Complex> = (0..n)
+    .map(|i| {
+      let w = 2.0 * std::f32::consts::PI * 10.0 * i as f32 / n as f32;
+      Complex::new(w.cos(), 0.0)
+    })
+    .collect();
+  fft.forward(&mut buf);
+  // A unit cosine splits into two conjugate bins of magnitude N/2.
+  assert!((buf[10].norm_sqr().sqrt() - n as f32 / 2.0).abs() < 1e-2);
+  assert!((buf[n - 10].norm_sqr().sqrt() - n as f32 / 2.0).abs() < 1e-2);
+  assert!(buf[11].norm_sqr().sqrt() < 1e-2);
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn fft_rejects_non_power_of_two() {
+  let _ = Fft::new(480);
+}
+
+// ── Chain plumbing ───────────────────────────────────────────────────
+
+#[test]
+fn all_stages_off_is_a_pure_delay() {
+  let input = fixtures::white_noise(secs(0.5), 0.1, 1);
+  let output = run(DspConfig::NONE, &input);
+  for (i, (a, b)) in input.iter().zip(&output).enumerate() {
+    assert!((a - b).abs() < 1e-4, "sample {i}: {a} vs {b}");
+  }
+}
+
+#[test]
+fn output_does_not_depend_on_block_size() {
+  let f = white_fixture(3, 0.3, 0.01);
+  let reference = run_blocks(DspConfig::ALL, &f.mix, 1024);
+  for block in [128, 441, 4096] {
+    let other = run_blocks(DspConfig::ALL, &f.mix, block);
+    assert_eq!(reference, other, "block size {block}");
+  }
+}
+
+#[test]
+fn process_returns_as_many_samples_as_given() {
+  let mut chain = DspChain::new(SAMPLE_RATE, DspConfig::ALL);
+  for len in [1, 7, 255, 256, 257, 1000] {
+    let input = vec![0.1; len];
+    let mut output = vec![f32::NAN; len];
+    chain.process(&input, &mut output);
+    assert!(output.iter().all(|s| s.is_finite()));
+  }
+}
+
+#[test]
+fn non_finite_input_is_treated_as_silence() {
+  let mut input = fixtures::white_noise(secs(0.2), 0.05, 3);
+  input[100] = f32::NAN;
+  input[2000] = f32::INFINITY;
+  let output = run(DspConfig::ALL, &input);
+  assert!(output.iter().all(|s| s.is_finite()));
+}
+
+#[test]
+fn toggling_stages_mid_stream_stays_finite_and_bounded() {
+  let f = white_fixture(6, 0.5, 0.02);
+  let mut chain = DspChain::new(SAMPLE_RATE, DspConfig::ALL);
+  let mut out = vec![0.0; f.mix.len()];
+  let configs = [
+    DspConfig::NONE,
+    DspConfig::ALL,
+    DspConfig {
+      noise_gate: false,
+      ..DspConfig::ALL
+    },
+    DspConfig {
+      auto_gain: false,
+      ..DspConfig::NONE
+    },
+  ];
+  for (n, (i, o)) in f.mix.chunks(1024).zip(out.chunks_mut(1024)).enumerate() {
+    chain.set_config(configs[n % configs.len()]);
+    chain.process(i, o);
+  }
+  assert!(out.iter().all(|s| s.is_finite() && s.abs() <= 1.0));
+}
+
+#[test]
+fn chain_works_at_16_khz() {
+  let mut chain = DspChain::new(16_000.0, DspConfig::ALL);
+  let input = fixtures::white_noise(16_000, 0.05, 9);
+  let mut output = vec![0.0; input.len()];
+  chain.process(&input, &mut output);
+  assert!(output.iter().all(|s| s.is_finite()));
+}
+
+// ── Noise suppression ────────────────────────────────────────────────
+
+#[test]
+fn suppression_cuts_white_noise_background_by_10_db() {
+  let f = white_fixture(6, 0.3, 0.02);
+  let config = DspConfig {
+    noise_suppression: true,
+    ..DspConfig::NONE
+  };
+  let out = run(config, &f.mix);
+  let bg = background(&f);
+  let reduction = db(rms(&f.mix[bg.clone()]) / rms(&out[bg]));
+  assert!(reduction >= 10.0, "background reduced by {reduction:.1} dB");
+}
+
+#[test]
+fn suppression_improves_speech_snr() {
+  let f = white_fixture(6, 0.3, 0.02);
+  let config = DspConfig {
+    noise_suppression: true,
+    ..DspConfig::NONE
+  };
+  let out = run(config, &f.mix);
+  let cores = syllable_cores(&f);
+  let clean = concat(&f.clean, &cores);
+  let before: Vec<f32> = concat(&f.noise, &cores);
+  let after: Vec<f32> = concat(&out, &cores)
+    .iter()
+    .zip(&clean)
+    .map(|(o, c)| o - c)
+    .collect();
+  let snr_in = db(rms(&clean) / rms(&before));
+  let snr_out = db(rms(&clean) / rms(&after));
+  assert!(
+    snr_out - snr_in >= 3.0,
+    "SNR {snr_in:.1} dB → {snr_out:.1} dB"
+  );
+}
+
+#[test]
+fn suppression_removes_hum() {
+  let f = fixtures::noisy_speech(4, 0.3, |len| fixtures::hum(len, 0.02));
+  let config = DspConfig {
+    noise_suppression: true,
+    ..DspConfig::NONE
+  };
+  let out = run(config, &f.mix);
+  let bg = background(&f);
+  let reduction = db(rms(&f.mix[bg.clone()]) / rms(&out[bg]));
+  assert!(reduction >= 10.0, "hum reduced by {reduction:.1} dB");
+}
+
+#[test]
+fn suppression_gains_stay_within_floor_and_unity() {
+  let f = white_fixture(2, 0.3, 0.02);
+  let mut denoiser = NoiseSuppressor::new(SAMPLE_RATE);
+  let mut out = vec![0.0; HOP_SIZE];
+  for hop in f.mix.chunks_exact(HOP_SIZE) {
+    denoiser.process_hop(hop, &mut out, true);
+    assert!(
+      denoiser
+        .band_gains()
+        .iter()
+        .all(|g| (GAIN_FLOOR..=1.0).contains(g))
+    );
+  }
+}
+
+// ── Voice activity ───────────────────────────────────────────────────
+
+fn vad_verdicts(samples: &[f32]) -> Vec<bool> {
+  let mut vad = VoiceActivity::new(SAMPLE_RATE, HOP_SIZE);
+  samples
+    .chunks_exact(HOP_SIZE)
+    .map(|hop| vad.update(hop))
+    .collect()
+}
+
+#[test]
+fn vad_flags_syllables_and_ignores_background() {
+  let f = white_fixture(4, 0.3, 0.01);
+  let verdicts = vad_verdicts(&f.mix);
+  for range in syllable_cores(&f) {
+    let hops = range.start / HOP_SIZE..range.end / HOP_SIZE;
+    assert!(verdicts[hops].iter().all(|&v| v), "missed a syllable");
+  }
+  let lead = secs(0.3) / HOP_SIZE..f.lead_in().end / HOP_SIZE;
+  assert!(
+    verdicts[lead].iter().all(|&v| !v),
+    "noise flagged as speech"
+  );
+}
+
+#[test]
+fn vad_hang_over_bridges_short_pauses_only() {
+  let f = white_fixture(2, 0.3, 0.01);
+  let verdicts = vad_verdicts(&f.mix);
+  let (_, first_end) = f.syllables[0];
+  // 100 ms after the syllable: still held open.
+  assert!(verdicts[(first_end + secs(0.1)) / HOP_SIZE]);
+  // Well after the second syllable: released.
+  let (_, last_end) = f.syllables[1];
+  assert!(!verdicts[(last_end + secs(0.24)) / HOP_SIZE]);
+}
+
+#[test]
+fn rms_db_of_full_scale_square_is_zero() {
+  let square: Vec<f32> = (0..100)
+    .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+    .collect();
+  assert!(rms_db(&square).abs() < 1e-4);
+  assert!(rms_db(&[0.0; 16]) <= -119.0);
+  assert!(rms_db(&[]) <= -119.0);
+}
+
+// ── Noise gate ───────────────────────────────────────────────────────
+
+#[test]
+fn gate_attenuates_background_and_keeps_speech() {
+  let f = white_fixture(4, 0.3, 0.01);
+  let config = DspConfig {
+    noise_gate: true,
+    ..DspConfig::NONE
+  };
+  let out = run(config, &f.mix);
+  let bg = background(&f);
+  let background_cut = db(rms(&f.mix[bg.clone()]) / rms(&out[bg]));
+  assert!(
+    background_cut >= 25.0,
+    "background cut {background_cut:.1} dB"
+  );
+
+  let cores = syllable_cores(&f);
+  let speech_change = db(rms(&concat(&out, &cores)) / rms(&concat(&f.mix, &cores)));
+  assert!(
+    speech_change.abs() < 0.5,
+    "speech changed by {speech_change:.2} dB"
+  );
+}
+
+#[test]
+fn disabled_gate_opens_fully() {
+  let mut gate = NoiseGate::new(SAMPLE_RATE);
+  let mut frame = vec![0.5; HOP_SIZE];
+  for _ in 0..200 {
+    gate.process(&mut frame, false, true);
+  }
+  assert!((gate.gain() - GATE_FLOOR).abs() < 1e-3);
+  for _ in 0..100 {
+    gate.process(&mut frame, false, false);
+  }
+  assert!((gate.gain() - 1.0).abs() < 1e-3);
+}
+
+// ── Automatic gain ───────────────────────────────────────────────────
+
+fn agc_only() -> DspConfig {
+  DspConfig {
+    auto_gain: true,
+    ..DspConfig::NONE
+  }
+}
+
+/// Level of the last three syllables, in dBFS.
+fn settled_level(f: &Fixture, out: &[f32]) -> f32 {
+  let cores = syllable_cores(f);
+  20.0 * rms(&concat(out, &cores[cores.len() - 3..])).log10()
+}
+
+#[test]
+fn agc_raises_quiet_speech_towards_target() {
+  let f = white_fixture(12, 0.02, 0.0005);
+  let input_level = settled_level(&f, &f.mix);
+  let out = run(agc_only(), &f.mix);
+  let level = settled_level(&f, &out);
+  assert!(input_level < TARGET_DB - 10.0);
+  assert!(
+    (level - TARGET_DB).abs() < 3.0,
+    "settled at {level:.1} dBFS"
+  );
+}
+
+#[test]
+fn agc_lowers_loud_speech_towards_target() {
+  let f = white_fixture(12, 0.8, 0.001);
+  let out = run(agc_only(), &f.mix);
+  let level = settled_level(&f, &out);
+  assert!(
+    (level - TARGET_DB).abs() < 3.0,
+    "settled at {level:.1} dBFS"
+  );
+}
+
+#[test]
+fn agc_boost_is_capped() {
+  let f = white_fixture(12, 0.001, 0.00001);
+  let out = run(agc_only(), &f.mix);
+  let gain = settled_level(&f, &out) - settled_level(&f, &f.mix);
+  assert!(gain <= MAX_GAIN_DB + 0.5, "boost {gain:.1} dB");
+}
+
+#[test]
+fn agc_limits_peaks() {
+  let f = white_fixture(6, 1.5, 0.001);
+  let out = run(agc_only(), &f.mix);
+  let peak = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+  assert!(peak <= PEAK_LIMIT + 1e-4, "peak {peak}");
+}
+
+#[test]
+fn agc_does_not_amplify_background_before_speech() {
+  let f = white_fixture(4, 0.02, 0.001);
+  let out = run(agc_only(), &f.mix);
+  let lead = f.lead_in();
+  let change = db(rms(&out[lead.clone()]) / rms(&f.mix[lead]));
+  assert!(change.abs() < 0.5, "background changed by {change:.2} dB");
+}
+
+#[test]
+fn disabled_agc_resets_to_unity() {
+  let mut agc = AutoGain::new(SAMPLE_RATE, HOP_SIZE);
+  let mut quiet = vec![0.001; HOP_SIZE];
+  for _ in 0..200 {
+    agc.process(&mut quiet, true, true);
+    quiet.fill(0.001);
+  }
+  assert!(agc.gain() > 1.0);
+  agc.process(&mut quiet, true, false);
+  assert!((agc.gain() - 1.0).abs() < f32::EPSILON);
+  assert!(quiet.iter().all(|&s| (s - 0.001).abs() < f32::EPSILON));
+}
+
+// ── Full chain ───────────────────────────────────────────────────────
+
+#[test]
+fn full_chain_cleans_and_levels_noisy_quiet_speech() {
+  let f = white_fixture(12, 0.05, 0.005);
+  let out = run(DspConfig::ALL, &f.mix);
+  let bg = background(&f);
+  let background_cut = db(rms(&f.mix[bg.clone()]) / rms(&out[bg]));
+  assert!(
+    background_cut >= 20.0,
+    "background cut {background_cut:.1} dB"
+  );
+  let level = settled_level(&f, &out);
+  assert!(
+    (level - TARGET_DB).abs() < 4.0,
+    "settled at {level:.1} dBFS"
+  );
+}
@@ -289,6 +289,9 @@ impl CallManager {
       if let Some(old) = self.signals.local_stream.get_untracked() {
         media::stop_stream(&old);
       }
+      // The display capture carries no microphone, so the mic
+      // processing graph has nothing left to feed.
+      self.release_audio_pipeline();
       self.signals.local_stream.set(Some(display.clone()));
       self.signals.local_media.update(|s| s.screen_sharing = true);
 
@@ -351,7 +354,7 @@ impl CallManager {
   /// sending `addTrack` earlier would pollute the mesh SDP for
   /// peers who never ultimately participate in the call.
   pub(super) fn install_local_stream(&self, media_type: MediaType, stream: MediaStream) {
-    self.prepare_local_stream(media_type, stream.clone());
+    let stream = self.prepare_local_stream(media_type, stream);
     self.publish_to_peers(&stream);
   }
 
@@ -359,15 +362,66 @@ impl CallManager {
   /// pushing any track to peer connections. Used by the `Inviting`
   /// path so the caller sees their own preview while waiting for the
   /// callee to accept.
-  pub(super) fn prepare_local_stream(&self, media_type: MediaType, stream: MediaStream) {
+  ///
+  /// The microphone track is routed through the outbound
+  /// [`AudioPipeline`] first; the returned stream is the one actually
+  /// installed (processed audio plus the original video tracks).
+  pub(super) fn prepare_local_stream(
+    &self,
+    media_type: MediaType,
+    stream: MediaStream,
+  ) -> MediaStream {
+    let stream = self.process_outbound_audio(stream);
     self
       .signals
       .local_media
       .set(LocalMediaState::initial_for(media_type));
-    self.signals.local_stream.set(Some(stream));
+    self.signals.local_stream.set(Some(stream.clone()));
     // Reset the video profile to the baseline when a fresh stream is
     // installed.
     self.signals.self_video_profile.set(VideoProfile::HIGH);
+    stream
+  }
+
+  /// Swap the raw microphone track of `raw` for the output of a fresh
+  /// [`AudioPipeline`] configured from the persisted settings. Falls
+  /// back to `raw` unchanged when it has no audio or the Web Audio
+  /// graph cannot be built, so a processing failure never costs the
+  /// user their microphone.
+  fn process_outbound_audio(&self, raw: MediaStream) -> MediaStream {
+    self.release_audio_pipeline();
+    if media::first_audio_track(&raw).is_none() {
+      return raw;
+    }
+    let config = media::dsp_config(crate::settings::load_snapshot().audio_processing);
+    match AudioPipeline::attach(&raw, config) {
+      Ok((pipeline, processed)) => {
+        self.inner.borrow_mut().audio_pipeline = Some(pipeline);
+        processed
+      }
+      Err(e) => {
+        web_sys::console::warn_1(&format!("[call] Audio processing unavailable: {e}").into());
+        raw
+      }
+    }
+  }
+
+  /// Close the outbound [`AudioPipeline`], stopping the raw microphone
+  /// track it owns. No-op when none is running.
+  pub(super) fn release_audio_pipeline(&self) {
+    let pipeline = self.inner.borrow_mut().audio_pipeline.take();
+    if let Some(pipeline) = pipeline {
+      pipeline.close();
+    }
+  }
+
+  /// Switch outbound audio processing stages on or off. Applies to the
+  /// running pipeline immediately; later calls pick the new values up
+  /// from the persisted settings.
+  pub fn set_audio_processing(&self, config: DspConfig) {
+    if let Some(pipeline) = self.inner.borrow().audio_pipeline.as_ref() {
+      pipeline.set_config(config);
+    }
   }
 
   /// Publish the currently-installed local stream to every connected
@@ -382,6 +436,8 @@ impl CallManager {
   }
 
   pub(super) fn tear_down_local_media(&self) {
+    // Finalise any running recording before its inputs disappear.
+    self.finish_recording_on_teardown();
     // Detach published senders before stopping tracks so the browser
     // release indicator clears promptly on the remote side.
     if let Some(webrtc) = self.webrtc.borrow().as_ref() {
@@ -390,6 +446,7 @@ impl CallManager {
     if let Some(stream) = self.signals.local_stream.get_untracked() {
       media::stop_stream(&stream);
     }
+    self.release_audio_pipeline();
     self.signals.local_stream.set(None);
     self.signals.local_media.set(LocalMediaState::off());
     self.signals.participants.update(HashMap::clear);
@@ -17,17 +17,19 @@
 //! * `lifecycle.rs` — `initiate_call` / `accept_call` / `decline_call`
 //!   / `end_call`.
 //! * `media_ops.rs` — `toggle_mute` / `toggle_camera` /
-//!   `toggle_screen_share` / PiP / `apply_video_profile` / internal
-//!   media helpers.
+//!   `toggle_screen_share` / PiP / `apply_video_profile` / outbound
+//!   audio processing / internal media helpers.
 //! * `peer_events.rs` — incoming signaling handlers, peer connection
 //!   events, DataChannel state broadcasts, refresh recovery.
 //! * `timers.rs` — `arm_*`/`cancel_*` timer helpers.
 //! * `persistence.rs` — localStorage serialisation.
+//! * `recording.rs` — local call recording and its consent broadcast.
 
 mod lifecycle;
 mod media_ops;
 mod peer_events;
 mod persistence;
+mod recording;
 mod timers;
 
 pub use persistence::load_persisted;
@@ -47,13 +49,15 @@ use crate::state::AppState;
 use crate::utils::{IntervalHandle, TimeoutHandle};
 use crate::webrtc::WebRtcManager;
 
+use super::audio_pipeline::AudioPipeline;
+use super::dsp::DspConfig;
 use super::media;
 use super::stats::{
   QualityAction, QualityController, STATS_POLL_INTERVAL_MS, parse_stats_report, quality_rank,
 };
 use super::types::{
-  CallEndReason, CallPhase, CallState, LocalMediaState, NetworkStatsSample, PersistedCallState,
-  VideoProfile,
+  CallEndReason, CallPhase, CallRecording, CallState, LocalMediaState, NetworkStatsSample,
+  PersistedCallState, VideoProfile,
 };
 use super::vad::VoiceActivityDetector;
 
@@ -93,6 +97,9 @@ pub struct RemoteParticipant {
   /// Whether the participant is currently reconnecting (driven by
   /// `ReconnectingState` broadcasts — Req 10.5.24).
   pub reconnecting: bool,
+  /// Whether the participant is recording the call (driven by
+  /// `CallRecordingState` broadcasts).
+  pub recording: bool,
 }
 
 impl RemoteParticipant {
@@ -105,6 +112,7 @@ impl RemoteParticipant {
       mic_enabled: true,
       camera_enabled: true,
       reconnecting: false,
+      recording: false,
     }
   }
 }
@@ -138,6 +146,11 @@ pub struct CallSignals {
   /// Most-recent network stats sample per peer, so the UI can display
   /// RTT/loss details on hover (Req 14.10).
   pub network_stats: RwSignal<HashMap<UserId, NetworkStatsSample>>,
+  /// Whether the local user is recording the call.
+  pub recording: RwSignal<bool>,
+  /// The most recent finished recording, until the user saves, sends
+  /// or discards it.
+  pub last_recording: RwSignal<Option<CallRecording>>,
 }
 
 impl CallSignals {
@@ -154,6 +167,8 @@ impl CallSignals {
       recovery_prompt: RwSignal::new(None),
       self_video_profile: RwSignal::new(VideoProfile::HIGH),
       network_stats: RwSignal::new(HashMap::new()),
+      recording: RwSignal::new(false),
+      last_recording: RwSignal::new(None),
     }
   }
 }
@@ -225,6 +240,14 @@ pub(super) struct Inner {
   /// emit the "Network quality restored" toast exactly once per
   /// recovery edge (Req 14.10.5).
   pub(super) was_poor: bool,
+  /// The recording in progress, if any.
+  pub(super) recording: Option<recording::ActiveRecording>,
+  /// Blob backing [`CallSignals::last_recording`], kept so the file can
+  /// be re-wrapped for sending after the recorder is gone.
+  pub(super) recording_blob: Option<web_sys::Blob>,
+  /// Outbound microphone processing graph feeding the local stream's
+  /// audio track; replaced whenever a fresh capture is installed.
+  pub(super) audio_pipeline: Option<AudioPipeline>,
 }
 
 impl CallManager {
@@ -249,6 +272,9 @@ impl CallManager {
         screen_share_switching: Cell::new(false),
         last_poor_toast_ms: None,
         was_poor: false,
+        recording: None,
+        recording_blob: None,
+        audio_pipeline: None,
       })),
     }
   }
@@ -298,6 +324,12 @@ impl CallManager {
     webrtc.set_on_reconnecting_state(move |peer_id, state| {
       manager_for_reconnect.on_remote_reconnecting(peer_id, state);
     });
+    // Surface peers' recording state so everyone knows when the call
+    // is being recorded.
+    let manager_for_recording = self.clone();
+    webrtc.set_on_call_recording_state(move |peer_id, state| {
+      manager_for_recording.on_remote_recording(peer_id, state);
+    });
     *self.webrtc.borrow_mut() = Some(webrtc);
   }
 
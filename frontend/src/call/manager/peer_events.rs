@@ -238,6 +238,10 @@ impl CallManager {
     ) {
       return;
     }
+    // Late joiners must learn that the call is being recorded.
+    if self.signals.recording.get_untracked() {
+      self.broadcast_recording_state();
+    }
     let Some(stream) = self.signals.local_stream.get_untracked() else {
       return;
     };
@@ -308,6 +312,20 @@ impl CallManager {
     });
   }
 
+  /// Update the remote participant's recording flag in response to a
+  /// `CallRecordingState` DataChannel broadcast.
+  pub fn on_remote_recording(&self, peer: UserId, state: message::datachannel::CallRecordingState) {
+    if !self.is_call_live() {
+      return;
+    }
+    self.signals.participants.update(|map| {
+      let entry = map
+        .entry(peer.clone())
+        .or_insert_with(|| RemoteParticipant::new(peer.clone()));
+      entry.recording = state.recording;
+    });
+  }
+
   /// True while the call is in a state that should accept remote
   /// participant updates (`Active` or `Inviting`). Used by the
   /// DataChannel-driven callbacks to reject late-arriving broadcasts
@@ -0,0 +1,185 @@
+//! Local call recording: start / stop, the consent broadcast, and the
+//! save / send / discard hand-off for the finished file.
+//!
+//! The recording itself is produced entirely on this device by
+//! [`CallRecorder`]; the only thing that goes on the wire is a
+//! `CallRecordingState` frame, broadcast whenever recording starts or
+//! stops and re-sent to peers that connect mid-recording, so every
+//! participant knows they are being recorded.
+
+use std::rc::Rc;
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use super::*;
+use crate::call::recorder::{CallRecorder, RecordingTile, recording_filename};
+use crate::call::types::CallRecording;
+
+/// A recording in progress, together with the room it belongs to (the
+/// call state may already have moved on by the time it is finished).
+pub(super) struct ActiveRecording {
+  pub(super) recorder: CallRecorder,
+  pub(super) room_id: RoomId,
+}
+
+impl CallManager {
+  /// Start recording the active call.
+  ///
+  /// # Errors
+  /// Returns `Err` when no call is active, a recording is already
+  /// running, or the browser cannot build the recording pipeline.
+  pub fn start_recording(&self) -> Result<(), String> {
+    let CallState::Active { room_id, .. } = self.signals.call_state.get_untracked() else {
+      return Err("No active call".to_string());
+    };
+    if self.inner.borrow().recording.is_some() {
+      return Err("Already recording".to_string());
+    }
+
+    let manager = self.clone();
+    let recorder = CallRecorder::start(Rc::new(move || manager.recording_tiles()))?;
+    self.inner.borrow_mut().recording = Some(ActiveRecording { recorder, room_id });
+    self.signals.recording.set(true);
+    self.broadcast_recording_state();
+    Ok(())
+  }
+
+  /// Stop the running recording and publish the result through
+  /// [`CallSignals::last_recording`]. No-op when nothing is recording.
+  pub async fn stop_recording(&self) {
+    let Some(ActiveRecording { recorder, room_id }) = self.inner.borrow_mut().recording.take()
+    else {
+      return;
+    };
+    let started_at =
+      chrono::DateTime::<chrono::Utc>::from_timestamp_millis(recorder.started_at_ms() as i64)
+        .unwrap_or_default();
+    self.signals.recording.set(false);
+    self.broadcast_recording_state();
+
+    let Some(finished) = recorder.finish().await else {
+      return;
+    };
+    let Ok(object_url) = web_sys::Url::create_object_url_with_blob(&finished.blob) else {
+      return;
+    };
+    // A newer recording replaces any one the user has not acted on.
+    self.discard_recording();
+    self.inner.borrow_mut().recording_blob = Some(finished.blob.clone());
+    self.signals.last_recording.set(Some(CallRecording {
+      room_id,
+      filename: recording_filename(started_at),
+      mime_type: finished.mime_type,
+      size: finished.blob.size() as u64,
+      duration_secs: finished.duration_secs,
+      object_url,
+    }));
+  }
+
+  /// Start recording when idle, stop when recording.
+  pub async fn toggle_recording(&self) -> Result<(), String> {
+    if self.signals.recording.get_untracked() {
+      self.stop_recording().await;
+      Ok(())
+    } else {
+      self.start_recording()
+    }
+  }
+
+  /// Download the finished recording to the local disk and dismiss it.
+  pub fn save_recording(&self) {
+    let Some(recording) = self.signals.last_recording.get_untracked() else {
+      return;
+    };
+    crate::utils::trigger_url_download(&recording.filename, &recording.object_url);
+    self.inner.borrow_mut().recording_blob = None;
+    self.signals.last_recording.set(None);
+    // Same delayed revoke as `trigger_download`: Firefox starts the
+    // download asynchronously and needs the URL a little longer.
+    let _ = crate::utils::set_timeout_once(5_000, move || {
+      let _ = web_sys::Url::revoke_object_url(&recording.object_url);
+    });
+  }
+
+  /// Wrap the finished recording in a `File` so the UI can offer it
+  /// through the regular file-transfer pipeline.
+  #[must_use]
+  pub fn recording_file(&self) -> Option<web_sys::File> {
+    let recording = self.signals.last_recording.get_untracked()?;
+    let blob = self.inner.borrow().recording_blob.clone()?;
+    let parts = js_sys::Array::of1(&blob);
+    let bag = web_sys::FilePropertyBag::new();
+    bag.set_type(&recording.mime_type);
+    web_sys::File::new_with_blob_sequence_and_options(&parts, &recording.filename, &bag).ok()
+  }
+
+  /// Drop the finished recording and release its blob URL.
+  pub fn discard_recording(&self) {
+    if let Some(recording) = self.signals.last_recording.get_untracked() {
+      let _ = web_sys::Url::revoke_object_url(&recording.object_url);
+    }
+    self.inner.borrow_mut().recording_blob = None;
+    self.signals.last_recording.set(None);
+  }
+
+  /// Finish a recording that is still running when the call ends, so
+  /// the user can still save what was captured.
+  pub(super) fn finish_recording_on_teardown(&self) {
+    match self.inner.borrow_mut().recording.as_mut() {
+      // Freeze the canvas now: the participant list is about to be
+      // cleared and the flush below only runs on a later tick.
+      Some(active) => active.recorder.stop_frames(),
+      None => return,
+    }
+    let manager = self.clone();
+    spawn_local(async move {
+      manager.stop_recording().await;
+    });
+  }
+
+  /// Tell every connected peer whether we are recording.
+  pub(super) fn broadcast_recording_state(&self) {
+    let webrtc = self.webrtc.borrow().clone();
+    let Some(webrtc) = webrtc else {
+      return;
+    };
+    let msg = message::datachannel::DataChannelMessage::CallRecordingState(
+      message::datachannel::CallRecordingState {
+        recording: self.signals.recording.get_untracked(),
+      },
+    );
+    webrtc.broadcast_data_channel_message(&msg);
+  }
+
+  /// Snapshot of the tiles to composite, in the same order as the
+  /// on-screen grid: the local tile first, then remote participants
+  /// sorted by user id.
+  fn recording_tiles(&self) -> Vec<RecordingTile> {
+    let local = RecordingTile {
+      label: self
+        .app_state
+        .auth
+        .with_untracked(|auth| auth.as_ref().map(|a| a.nickname.clone()))
+        .unwrap_or_default(),
+      stream: self.signals.local_stream.get_untracked(),
+    };
+    let mut remote: Vec<RemoteParticipant> = self
+      .signals
+      .participants
+      .with_untracked(|map| map.values().cloned().collect());
+    remote.sort_by_key(|p| p.user_id.to_string());
+    let names = self.app_state.online_users.get_untracked();
+    std::iter::once(local)
+      .chain(remote.into_iter().map(|p| {
+        RecordingTile {
+          label: names
+            .iter()
+            .find(|u| u.user_id == p.user_id)
+            .map_or_else(|| p.user_id.to_string(), |u| u.nickname.clone()),
+          stream: p.stream,
+        }
+      }))
+      .collect()
+  }
+}
@@ -118,6 +118,7 @@ fn remote_participant_defaults_have_media_enabled() {
   assert!(p.camera_enabled);
   assert!(!p.screen_sharing);
   assert!(!p.reconnecting);
+  assert!(!p.recording);
   assert!(!p.speaking);
 }
 
@@ -15,8 +15,9 @@ use web_sys::{
   MediaStreamConstraints, MediaStreamTrack, MediaTrackConstraints,
 };
 
+use super::dsp::DspConfig;
 use super::types::VideoProfile;
-use crate::settings::{UserSettings, VideoQualityPref, load_snapshot};
+use crate::settings::{AudioProcessingPrefs, UserSettings, VideoQualityPref, load_snapshot};
 
 /// Translate the user's persisted [`VideoQualityPref`] into a
 /// concrete [`VideoProfile`]. The runtime quality controller may
@@ -36,6 +37,17 @@ pub fn baseline_video_profile(pref: VideoQualityPref) -> VideoProfile {
   }
 }
 
+/// Translate the user's persisted [`AudioProcessingPrefs`] into the
+/// stage switches of the outbound [`DspConfig`].
+#[must_use]
+pub const fn dsp_config(prefs: AudioProcessingPrefs) -> DspConfig {
+  DspConfig {
+    noise_suppression: prefs.noise_suppression,
+    noise_gate: prefs.noise_gate,
+    auto_gain: prefs.auto_gain,
+  }
+}
+
 /// Apply the user's preferred audio-input device id to the audio
 /// constraints, when one is configured. No-op for `None`.
 fn apply_audio_device(audio: &MediaTrackConstraints, settings: &UserSettings) {
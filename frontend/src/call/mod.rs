@@ -5,8 +5,9 @@
 //! ringing, or in an active call; the local camera/microphone/display
 //! capture pipeline; network-quality monitoring with a downgrade and
 //! hysteresis-based recovery state machine; voice-activity detection
-//! for the active-speaker indicator; and localStorage-backed refresh
-//! recovery.
+//! for the active-speaker indicator; outbound microphone processing
+//! (noise suppression, noise gate, AGC); and localStorage-backed
+//! refresh recovery.
 //!
 //! ## Layering
 //!
@@ -18,15 +19,20 @@
 //!                      ├── media.rs   (getUserMedia / getDisplayMedia)
 //!                      ├── stats.rs   (quality hysteresis)
 //!                      ├── vad.rs     (voice activity detection)
+//!                      ├── audio_pipeline.rs (mic ─► dsp.rs ─► peers)
+//!                      ├── recorder.rs (local call recording)
 //!                      └── types.rs   (CallState, LocalMediaState, ...)
 //! ```
 //!
 //! The UI only ever talks to the `CallManager`. The manager, in turn,
 //! delegates to the three collaborators wired up at bootstrap.
 
+mod audio_pipeline;
+pub mod dsp;
 mod manager;
 mod media;
 mod notifier;
+mod recorder;
 mod stats;
 mod types;
 mod vad;
@@ -34,17 +40,20 @@ mod vad;
 #[cfg(all(test, target_arch = "wasm32"))]
 mod wasm_tests;
 
+pub use audio_pipeline::AudioPipeline;
+pub use dsp::{DspChain, DspConfig};
 pub use manager::{CallManager, CallSignals, INVITE_TIMEOUT_MS, RemoteParticipant, load_persisted};
 pub use media::{
   acquire_display_stream, acquire_user_media, acquire_video_only_stream, apply_speaker_settings,
-  attach_stream_to_video, baseline_video_profile, capture_stream_from_video,
+  attach_stream_to_video, baseline_video_profile, capture_stream_from_video, dsp_config,
   exit_picture_in_picture, first_audio_track, first_video_track, request_picture_in_picture,
   retarget_video_track, stop_stream,
 };
+pub use recorder::{CallRecorder, FinishedRecording, RecordingTile, TileRect, grid_layout};
 pub use stats::{QualityAction, QualityController, STATS_POLL_INTERVAL_MS, parse_stats_report};
 pub use types::{
-  CallEndReason, CallPhase, CallState, ConnectionType, LocalMediaState, NetworkStatsSample,
-  PersistedCallState, VideoProfile,
+  CallEndReason, CallPhase, CallRecording, CallState, ConnectionType, LocalMediaState,
+  NetworkStatsSample, PersistedCallState, VideoProfile,
 };
 pub use vad::VoiceActivityDetector;
 
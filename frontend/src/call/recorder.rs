@@ -0,0 +1,553 @@
+//! Local call recording.
+//!
+//! The recorder never touches the wire: it rebuilds the call grid on
+//! an off-screen `<canvas>` (one tile per participant, in the same
+//! order as the on-screen grid), mixes every participant's audio
+//! track into a single Web Audio destination, and feeds the canvas
+//! video track plus the mixed audio track into a `MediaRecorder`
+//! producing WebM.
+//!
+//! Each remote stream is attached to a private, muted `<video>`
+//! element owned by the recorder, so recording keeps working while
+//! the call view is minimised or re-rendered. Tiles and audio sources
+//! are re-synchronised on every frame tick, so participants joining,
+//! leaving or toggling their camera mid-recording show up without
+//! restarting the recorder.
+//!
+//! The frame ticker runs on `setInterval`, which browsers throttle in
+//! background tabs; the recording stays valid but drops to roughly
+//! one frame per second while the tab is hidden.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::Array;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+  AudioContext, Blob, BlobEvent, BlobPropertyBag, CanvasRenderingContext2d, HtmlCanvasElement,
+  HtmlVideoElement, MediaRecorder, MediaRecorderOptions, MediaStream,
+  MediaStreamAudioDestinationNode, MediaStreamAudioSourceNode, MediaStreamTrack,
+};
+
+use crate::utils::{IntervalHandle, set_interval};
+
+/// Width of the composited recording, in pixels.
+pub const RECORDING_WIDTH: u32 = 1280;
+
+/// Height of the composited recording, in pixels.
+pub const RECORDING_HEIGHT: u32 = 720;
+
+/// Target frame rate of the composited recording.
+pub const RECORDING_FPS: u32 = 30;
+
+/// Gap between tiles on the recording canvas, in pixels.
+const TILE_GAP: f64 = 8.0;
+
+/// Container formats tried in order; the first one
+/// `MediaRecorder.isTypeSupported` accepts wins.
+pub const RECORDING_MIME_CANDIDATES: &[&str] = &[
+  "video/webm;codecs=vp9,opus",
+  "video/webm;codecs=vp8,opus",
+  "video/webm",
+];
+
+/// Target video bitrate handed to `MediaRecorder`.
+const VIDEO_BITS_PER_SECOND: u32 = 2_500_000;
+
+/// Time slice passed to `MediaRecorder.start` so chunks are flushed
+/// periodically instead of buffering the whole call in the encoder.
+const CHUNK_SLICE_MS: i32 = 1_000;
+
+/// One participant tile to composite.
+#[derive(Debug, Clone)]
+pub struct RecordingTile {
+  /// Name drawn in the tile footer.
+  pub label: String,
+  /// The participant's media, if any has arrived yet.
+  pub stream: Option<MediaStream>,
+}
+
+/// Placement of a tile (or of a letter-boxed frame inside a tile) on
+/// the recording canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileRect {
+  /// Left edge, in canvas pixels.
+  pub x: f64,
+  /// Top edge, in canvas pixels.
+  pub y: f64,
+  /// Width, in canvas pixels.
+  pub width: f64,
+  /// Height, in canvas pixels.
+  pub height: f64,
+}
+
+/// Lay `count` tiles out on a `width` × `height` canvas.
+///
+/// Mirrors the on-screen grid: the column count is the smallest
+/// square that fits every tile, every cell has the same size, and a
+/// partially filled last row is centred.
+#[must_use]
+pub fn grid_layout(count: usize, width: f64, height: f64) -> Vec<TileRect> {
+  if count == 0 {
+    return Vec::new();
+  }
+  let mut cols = 1;
+  while cols * cols < count {
+    cols += 1;
+  }
+  let rows = count.div_ceil(cols);
+  let cols_f = cols as f64;
+  let rows_f = rows as f64;
+  let cell_w = (width - TILE_GAP * (cols_f + 1.0)) / cols_f;
+  let cell_h = (height - TILE_GAP * (rows_f + 1.0)) / rows_f;
+
+  (0..count)
+    .map(|i| {
+      let row = i / cols;
+      let col = i % cols;
+      let in_row = if row + 1 == rows {
+        count - row * cols
+      } else {
+        cols
+      };
+      let row_width = in_row as f64 * cell_w + (in_row as f64 - 1.0) * TILE_GAP;
+      let row_x = (width - row_width) / 2.0;
+      TileRect {
+        x: row_x + col as f64 * (cell_w + TILE_GAP),
+        y: TILE_GAP + row as f64 * (cell_h + TILE_GAP),
+        width: cell_w,
+        height: cell_h,
+      }
+    })
+    .collect()
+}
+
+/// Fit a `source_w` × `source_h` frame inside `cell` without cropping,
+/// centring it on the leftover axis.
+#[must_use]
+pub fn contain_rect(source_w: f64, source_h: f64, cell: TileRect) -> TileRect {
+  if source_w <= 0.0 || source_h <= 0.0 {
+    return cell;
+  }
+  let scale = (cell.width / source_w).min(cell.height / source_h);
+  let width = source_w * scale;
+  let height = source_h * scale;
+  TileRect {
+    x: cell.x + (cell.width - width) / 2.0,
+    y: cell.y + (cell.height - height) / 2.0,
+    width,
+    height,
+  }
+}
+
+/// Build a filename like `call-recording-2026-05-06-142501.webm`.
+#[must_use]
+pub fn recording_filename(started_at: chrono::DateTime<chrono::Utc>) -> String {
+  format!(
+    "call-recording-{}.webm",
+    started_at.format("%Y-%m-%d-%H%M%S")
+  )
+}
+
+/// Pick the first container format the browser can record.
+fn pick_mime_type() -> Option<&'static str> {
+  RECORDING_MIME_CANDIDATES
+    .iter()
+    .copied()
+    .find(|mime| MediaRecorder::is_type_supported(mime))
+}
+
+/// Finished recording, ready to be saved or sent.
+#[derive(Debug, Clone)]
+pub struct FinishedRecording {
+  /// The WebM payload.
+  pub blob: Blob,
+  /// MIME type the recorder actually produced.
+  pub mime_type: String,
+  /// Wall-clock length of the recording.
+  pub duration_secs: u64,
+}
+
+/// Canvas + audio graph shared between the recorder and its frame
+/// ticker.
+struct Compositor {
+  ctx: CanvasRenderingContext2d,
+  audio_ctx: AudioContext,
+  destination: MediaStreamAudioDestinationNode,
+  /// Private `<video>` elements keyed by stream id.
+  videos: HashMap<String, HtmlVideoElement>,
+  /// Audio sources keyed by track id.
+  audio: HashMap<String, MediaStreamAudioSourceNode>,
+}
+
+impl Compositor {
+  /// Bring the private `<video>` elements and audio sources in line
+  /// with the current tile set.
+  fn sync(&mut self, tiles: &[RecordingTile]) {
+    let streams: Vec<&MediaStream> = tiles.iter().filter_map(|t| t.stream.as_ref()).collect();
+
+    let live_videos: Vec<String> = streams.iter().map(|s| s.id()).collect();
+    self.videos.retain(|id, video| {
+      let keep = live_videos.contains(id);
+      if !keep {
+        video.set_src_object(None);
+      }
+      keep
+    });
+    for stream in &streams {
+      if self.videos.contains_key(&stream.id()) {
+        continue;
+      }
+      if let Some(video) = hidden_video(stream) {
+        self.videos.insert(stream.id(), video);
+      }
+    }
+
+    let tracks: Vec<MediaStreamTrack> = streams
+      .iter()
+      .flat_map(|s| s.get_audio_tracks().iter().collect::<Vec<_>>())
+      .filter_map(|t| t.dyn_into::<MediaStreamTrack>().ok())
+      .filter(|t| t.ready_state() == web_sys::MediaStreamTrackState::Live)
+      .collect();
+    let live_tracks: Vec<String> = tracks.iter().map(MediaStreamTrack::id).collect();
+    self.audio.retain(|id, source| {
+      let keep = live_tracks.contains(id);
+      if !keep {
+        let _ = source.disconnect();
+      }
+      keep
+    });
+    for track in tracks {
+      if self.audio.contains_key(&track.id()) {
+        continue;
+      }
+      // One source per track: a `MediaStreamAudioSourceNode` only
+      // follows the tracks present when it was created, so a stream
+      // that gains its audio track later would otherwise stay silent.
+      let Ok(single) = MediaStream::new_with_tracks(&Array::of1(&track)) else {
+        continue;
+      };
+      let Ok(source) = self.audio_ctx.create_media_stream_source(&single) else {
+        continue;
+      };
+      if source.connect_with_audio_node(&self.destination).is_ok() {
+        self.audio.insert(track.id(), source);
+      }
+    }
+  }
+
+  /// Paint one frame.
+  fn draw(&self, tiles: &[RecordingTile]) {
+    let width = f64::from(RECORDING_WIDTH);
+    let height = f64::from(RECORDING_HEIGHT);
+    let ctx = &self.ctx;
+    ctx.set_fill_style_str("#0f172a");
+    ctx.fill_rect(0.0, 0.0, width, height);
+
+    for (tile, cell) in tiles.iter().zip(grid_layout(tiles.len(), width, height)) {
+      ctx.set_fill_style_str("#1e293b");
+      ctx.fill_rect(cell.x, cell.y, cell.width, cell.height);
+
+      let video = tile
+        .stream
+        .as_ref()
+        .and_then(|s| self.videos.get(&s.id()))
+        .filter(|v| v.video_width() > 0 && has_live_video(v));
+      if let Some(video) = video {
+        let frame = contain_rect(
+          f64::from(video.video_width()),
+          f64::from(video.video_height()),
+          cell,
+        );
+        let _ = ctx.draw_image_with_html_video_element_and_dw_and_dh(
+          video,
+          frame.x,
+          frame.y,
+          frame.width,
+          frame.height,
+        );
+      } else {
+        draw_placeholder(ctx, &tile.label, cell);
+      }
+      draw_label(ctx, &tile.label, cell);
+    }
+  }
+
+  fn close(&mut self) {
+    for video in self.videos.values() {
+      video.set_src_object(None);
+    }
+    self.videos.clear();
+    for source in self.audio.values() {
+      let _ = source.disconnect();
+    }
+    self.audio.clear();
+    let _ = self.audio_ctx.close();
+  }
+}
+
+/// Whether the element's stream still carries an enabled, live video
+/// track (a disabled camera keeps rendering its last frame otherwise).
+fn has_live_video(video: &HtmlVideoElement) -> bool {
+  let Some(stream) = video.src_object() else {
+    return false;
+  };
+  stream
+    .get_video_tracks()
+    .iter()
+    .filter_map(|t| t.dyn_into::<MediaStreamTrack>().ok())
+    .any(|t| t.enabled() && t.ready_state() == web_sys::MediaStreamTrackState::Live)
+}
+
+/// Create a detached, muted `<video>` element playing `stream`.
+fn hidden_video(stream: &MediaStream) -> Option<HtmlVideoElement> {
+  let video: HtmlVideoElement = web_sys::window()?
+    .document()?
+    .create_element("video")
+    .ok()?
+    .dyn_into()
+    .ok()?;
+  video.set_muted(true);
+  video.set_autoplay(true);
+  let _ = video.set_attribute("playsinline", "");
+  video.set_src_object(Some(stream));
+  if let Ok(promise) = video.play() {
+    wasm_bindgen_futures::spawn_local(async move {
+      let _ = JsFuture::from(promise).await;
+    });
+  }
+  Some(video)
+}
+
+/// Draw the initial-letter avatar used when a tile has no video.
+fn draw_placeholder(ctx: &CanvasRenderingContext2d, label: &str, cell: TileRect) {
+  let radius = cell.width.min(cell.height) * 0.18;
+  let cx = cell.x + cell.width / 2.0;
+  let cy = cell.y + cell.height / 2.0;
+  ctx.set_fill_style_str("#334155");
+  ctx.begin_path();
+  let _ = ctx.arc(cx, cy, radius, 0.0, std::f64::consts::TAU);
+  ctx.fill();
+  let initial: String = label
+    .chars()
+    .next()
+    .map(|c| c.to_uppercase().collect())
+    .unwrap_or_default();
+  ctx.set_fill_style_str("#e2e8f0");
+  ctx.set_font(&format!("600 {}px sans-serif", (radius * 0.9).round()));
+  ctx.set_text_align("center");
+  ctx.set_text_baseline("middle");
+  let _ = ctx.fill_text(&initial, cx, cy);
+}
+
+/// Draw the participant name in the bottom-left corner of a tile.
+fn draw_label(ctx: &CanvasRenderingContext2d, label: &str, cell: TileRect) {
+  const PADDING: f64 = 8.0;
+  const FONT_PX: f64 = 16.0;
+  ctx.set_font(&format!("500 {FONT_PX}px sans-serif"));
+  ctx.set_text_align("left");
+  ctx.set_text_baseline("bottom");
+  let text_w = ctx.measure_text(label).map_or(0.0, |m| m.width());
+  ctx.set_fill_style_str("rgba(15, 23, 42, 0.6)");
+  ctx.fill_rect(
+    cell.x + PADDING,
+    cell.y + cell.height - PADDING - FONT_PX - 8.0,
+    text_w + 12.0,
+    FONT_PX + 8.0,
+  );
+  ctx.set_fill_style_str("#f8fafc");
+  let _ = ctx.fill_text(
+    label,
+    cell.x + PADDING + 6.0,
+    cell.y + cell.height - PADDING - 4.0,
+  );
+}
+
+/// Callback producing the current tile set, in grid order.
+pub type TileSource = Rc<dyn Fn() -> Vec<RecordingTile>>;
+
+/// A running call recording.
+pub struct CallRecorder {
+  recorder: MediaRecorder,
+  mime_type: String,
+  compositor: Rc<RefCell<Compositor>>,
+  chunks: Rc<RefCell<Vec<Blob>>>,
+  /// Tracks of the recorder's own input stream (canvas + mix); stopped
+  /// when the recording ends.
+  input: MediaStream,
+  started_at_ms: f64,
+  /// Frame ticker; dropping it stops compositing.
+  ticker: Option<IntervalHandle>,
+  _on_data: Closure<dyn FnMut(BlobEvent)>,
+}
+
+impl CallRecorder {
+  /// Build the compositor and start recording.
+  ///
+  /// # Errors
+  /// Returns `Err` when the browser lacks `MediaRecorder` WebM support
+  /// or any part of the canvas / Web Audio pipeline cannot be built.
+  pub fn start(tiles: TileSource) -> Result<Self, String> {
+    let mime_type = pick_mime_type().ok_or("WebM recording is not supported")?;
+
+    let document = web_sys::window()
+      .and_then(|w| w.document())
+      .ok_or("no document")?;
+    let canvas: HtmlCanvasElement = document
+      .create_element("canvas")
+      .map_err(|e| format!("{e:?}"))?
+      .dyn_into()
+      .map_err(|_| "canvas element unavailable")?;
+    canvas.set_width(RECORDING_WIDTH);
+    canvas.set_height(RECORDING_HEIGHT);
+    let ctx: CanvasRenderingContext2d = canvas
+      .get_context("2d")
+      .map_err(|e| format!("{e:?}"))?
+      .ok_or("2d context unavailable")?
+      .dyn_into()
+      .map_err(|_| "2d context unavailable")?;
+
+    let audio_ctx = AudioContext::new().map_err(|e| format!("{e:?}"))?;
+    let destination = audio_ctx
+      .create_media_stream_destination()
+      .map_err(|e| format!("{e:?}"))?;
+
+    let compositor = Rc::new(RefCell::new(Compositor {
+      ctx,
+      audio_ctx,
+      destination,
+      videos: HashMap::new(),
+      audio: HashMap::new(),
+    }));
+    {
+      let initial = tiles();
+      let mut c = compositor.borrow_mut();
+      c.sync(&initial);
+      c.draw(&initial);
+    }
+
+    let canvas_stream = canvas
+      .capture_stream_with_frame_request_rate(f64::from(RECORDING_FPS))
+      .map_err(|e| format!("{e:?}"))?;
+    let input = MediaStream::new().map_err(|e| format!("{e:?}"))?;
+    for track in canvas_stream.get_video_tracks().iter().chain(
+      compositor
+        .borrow()
+        .destination
+        .stream()
+        .get_audio_tracks()
+        .iter(),
+    ) {
+      if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+        input.add_track(&track);
+      }
+    }
+
+    let options = MediaRecorderOptions::new();
+    options.set_mime_type(mime_type);
+    options.set_video_bits_per_second(VIDEO_BITS_PER_SECOND);
+    let recorder =
+      MediaRecorder::new_with_media_stream_and_media_recorder_options(&input, &options)
+        .map_err(|e| format!("{e:?}"))?;
+
+    let chunks: Rc<RefCell<Vec<Blob>>> = Rc::new(RefCell::new(Vec::new()));
+    let chunks_for_data = chunks.clone();
+    let on_data = Closure::wrap(Box::new(move |ev: BlobEvent| {
+      if let Some(data) = ev.data()
+        && data.size() > 0.0
+      {
+        chunks_for_data.borrow_mut().push(data);
+      }
+    }) as Box<dyn FnMut(BlobEvent)>);
+    recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+    recorder
+      .start_with_time_slice(CHUNK_SLICE_MS)
+      .map_err(|e| format!("{e:?}"))?;
+
+    let compositor_for_tick = compositor.clone();
+    let frame_ms = i32::try_from(1_000 / RECORDING_FPS).unwrap_or(33);
+    let ticker = set_interval(frame_ms, move || {
+      let current = tiles();
+      let mut c = compositor_for_tick.borrow_mut();
+      c.sync(&current);
+      c.draw(&current);
+    });
+
+    Ok(Self {
+      recorder,
+      mime_type: mime_type.to_string(),
+      compositor,
+      chunks,
+      input,
+      started_at_ms: js_sys::Date::now(),
+      ticker,
+      _on_data: on_data,
+    })
+  }
+
+  /// Wall-clock time the recording started, in Unix milliseconds.
+  #[must_use]
+  pub const fn started_at_ms(&self) -> f64 {
+    self.started_at_ms
+  }
+
+  /// Stop compositing new frames; the recorder keeps the last one
+  /// until [`Self::finish`] flushes it.
+  pub fn stop_frames(&mut self) {
+    if let Some(ticker) = self.ticker.take() {
+      ticker.cancel();
+    }
+  }
+
+  /// Stop recording, wait for the encoder to flush, and release the
+  /// canvas / audio pipeline.
+  ///
+  /// Returns `None` when nothing was captured.
+  pub async fn finish(mut self) -> Option<FinishedRecording> {
+    self.stop_frames();
+
+    if self.recorder.state() != web_sys::RecordingState::Inactive {
+      let (tx, rx) = futures::channel::oneshot::channel::<()>();
+      let on_stop = Closure::once(move |_ev: web_sys::Event| {
+        let _ = tx.send(());
+      });
+      self
+        .recorder
+        .set_onstop(Some(on_stop.as_ref().unchecked_ref()));
+      if self.recorder.stop().is_ok() {
+        // The final `dataavailable` fires before `stop`, so every
+        // chunk is in `self.chunks` once this resolves.
+        let _ = rx.await;
+      }
+      self.recorder.set_onstop(None);
+    }
+
+    let tracks = self.input.get_tracks();
+    for track in tracks.iter() {
+      if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+        track.stop();
+      }
+    }
+    self.compositor.borrow_mut().close();
+
+    let parts: Array = self.chunks.borrow_mut().drain(..).collect();
+    if parts.length() == 0 {
+      return None;
+    }
+    let bag = BlobPropertyBag::new();
+    bag.set_type(&self.mime_type);
+    let blob = Blob::new_with_blob_sequence_and_options(&parts, &bag).ok()?;
+    let elapsed_ms = (js_sys::Date::now() - self.started_at_ms).max(0.0);
+    Some(FinishedRecording {
+      blob,
+      mime_type: self.mime_type.clone(),
+      duration_secs: (elapsed_ms / 1_000.0).round() as u64,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests;
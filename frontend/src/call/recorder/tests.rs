@@ -0,0 +1,119 @@
+use super::*;
+
+const W: f64 = 1280.0;
+const H: f64 = 720.0;
+
+#[test]
+fn single_tile_fills_canvas_minus_gap() {
+  let rects = grid_layout(1, W, H);
+  assert_eq!(rects.len(), 1);
+  let r = rects[0];
+  assert!((r.x - TILE_GAP).abs() < f64::EPSILON);
+  assert!((r.y - TILE_GAP).abs() < f64::EPSILON);
+  assert!((r.width - (W - 2.0 * TILE_GAP)).abs() < 1e-9);
+  assert!((r.height - (H - 2.0 * TILE_GAP)).abs() < 1e-9);
+}
+
+#[test]
+fn grid_uses_smallest_square_column_count() {
+  // 2 → 2x1, 4 → 2x2, 5 → 3x2, 9 → 3x3.
+  for (count, cols, rows) in [(2, 2, 1), (4, 2, 2), (5, 3, 2), (9, 3, 3)] {
+    let rects = grid_layout(count, W, H);
+    let distinct_rows = {
+      let mut ys: Vec<i64> = rects.iter().map(|r| r.y.round() as i64).collect();
+      ys.dedup();
+      ys.len()
+    };
+    assert_eq!(distinct_rows, rows, "rows for {count}");
+    let expected_w = (W - TILE_GAP * (cols as f64 + 1.0)) / cols as f64;
+    assert!(
+      (rects[0].width - expected_w).abs() < 1e-9,
+      "width for {count}"
+    );
+  }
+}
+
+#[test]
+fn partial_last_row_is_centred() {
+  // Three tiles: two on the first row, one centred below.
+  let rects = grid_layout(3, W, H);
+  let last = rects[2];
+  let centre = last.x + last.width / 2.0;
+  assert!((centre - W / 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn tiles_stay_inside_canvas() {
+  for count in 1..=8 {
+    for r in grid_layout(count, W, H) {
+      assert!(r.x >= 0.0 && r.y >= 0.0);
+      assert!(r.x + r.width <= W + 1e-9);
+      assert!(r.y + r.height <= H + 1e-9);
+    }
+  }
+}
+
+#[test]
+fn empty_grid_has_no_tiles() {
+  assert!(grid_layout(0, W, H).is_empty());
+}
+
+#[test]
+fn contain_rect_letterboxes_wide_source() {
+  let cell = TileRect {
+    x: 0.0,
+    y: 0.0,
+    width: 400.0,
+    height: 400.0,
+  };
+  let fit = contain_rect(1600.0, 900.0, cell);
+  assert!((fit.width - 400.0).abs() < 1e-9);
+  assert!((fit.height - 225.0).abs() < 1e-9);
+  assert!((fit.y - 87.5).abs() < 1e-9);
+}
+
+#[test]
+fn contain_rect_pillarboxes_tall_source() {
+  let cell = TileRect {
+    x: 10.0,
+    y: 10.0,
+    width: 320.0,
+    height: 180.0,
+  };
+  let fit = contain_rect(720.0, 1280.0, cell);
+  assert!((fit.height - 180.0).abs() < 1e-9);
+  assert!(fit.width < 320.0);
+  assert!((fit.x + fit.width / 2.0 - 170.0).abs() < 1e-9);
+}
+
+#[test]
+fn contain_rect_ignores_unknown_source_size() {
+  let cell = TileRect {
+    x: 1.0,
+    y: 2.0,
+    width: 3.0,
+    height: 4.0,
+  };
+  assert_eq!(contain_rect(0.0, 0.0, cell), cell);
+}
+
+#[test]
+fn recording_filename_is_timestamped_webm() {
+  let at = chrono::DateTime::parse_from_rfc3339("2026-05-06T14:25:01Z")
+    .unwrap()
+    .with_timezone(&chrono::Utc);
+  assert_eq!(
+    recording_filename(at),
+    "call-recording-2026-05-06-142501.webm"
+  );
+}
+
+#[test]
+fn mime_candidates_end_with_plain_webm_fallback() {
+  assert_eq!(RECORDING_MIME_CANDIDATES.last(), Some(&"video/webm"));
+  assert!(
+    RECORDING_MIME_CANDIDATES
+      .iter()
+      .all(|m| m.starts_with("video/webm"))
+  );
+}
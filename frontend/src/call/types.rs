@@ -380,5 +380,24 @@ impl VideoProfile {
   }
 }
 
+/// A finished local call recording, waiting for the user to save,
+/// send or discard it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallRecording {
+  /// Room the call took place in; the destination conversation when
+  /// the recording is sent as a file.
+  pub room_id: RoomId,
+  /// Suggested filename.
+  pub filename: String,
+  /// MIME type the recorder produced.
+  pub mime_type: String,
+  /// Size of the recording in bytes.
+  pub size: u64,
+  /// Length of the recording in seconds.
+  pub duration_secs: u64,
+  /// Blob URL backing the save / preview actions.
+  pub object_url: String,
+}
+
 #[cfg(test)]
 mod tests;
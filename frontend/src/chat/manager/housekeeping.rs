@@ -1,9 +1,13 @@
 //! Housekeeping tick — runs the 1 Hz timer that processes ACK retries,
 //! flushes read-receipt batches, and expires stale typing indicators.
+//! Direct messages that exhaust their retries are handed to the server
+//! mailbox when one is offered, and only fail otherwise.
 
 use super::ChatManager;
+use super::mailbox::deposit_via_mailbox;
 use super::wire::send_wire_out;
 use crate::chat::models::MessageStatus;
+use crate::state::ConversationId;
 use crate::utils::set_interval;
 use leptos::prelude::*;
 use message::datachannel::{DataChannelMessage, MessageRead};
@@ -22,16 +26,29 @@ impl ChatManager {
     let handle = set_interval(1_000, move || {
       let Some(inner) = weak.upgrade() else { return };
       let now = chrono::Utc::now().timestamp_millis();
-      let (retries, expired_ids, read_batches) = {
+      let (retries, expired_ids, expired_direct, read_batches) = {
         let mut guard = inner.borrow_mut();
-        let (retries, expired_ids) = guard.process_ack_ticks(now);
+        let (retries, expired_ids, expired_direct) = guard.process_ack_ticks(now);
         let read_batches = guard.read_batcher.drain_ready(now);
         guard.expire_stale_typing(now);
-        (retries, expired_ids, read_batches)
+        (retries, expired_ids, expired_direct, read_batches)
       };
 
+      // Offer expired direct messages to the mailbox before failing them.
+      let mut deposited = Vec::new();
+      let webrtc_clone = webrtc.borrow().as_ref().cloned();
+      if let Some(mgr) = &webrtc_clone {
+        for (conv, id, wire) in expired_direct {
+          if let ConversationId::Direct(peer) = &conv
+            && deposit_via_mailbox(mgr, &inner, peer, id, wire)
+          {
+            deposited.push(id);
+          }
+        }
+      }
+
       // Apply expired states to the UI.
-      for id in &expired_ids {
+      for id in expired_ids.iter().filter(|id| !deposited.contains(id)) {
         let conv = inner.borrow().index.get(id).cloned();
         if let Some(conv) = conv
           && let Some(state) = inner.borrow().conversations.get(&conv).copied()
@@ -63,7 +80,6 @@ impl ChatManager {
       }
 
       // Resend retries via the WebRTC manager.
-      let webrtc_clone = webrtc.borrow().as_ref().cloned();
       if let Some(mgr) = webrtc_clone {
         for (conv, _id, wire) in &retries {
           send_wire_out(&mgr, &app_state, conv, wire.clone());
@@ -7,8 +7,8 @@ use crate::state::ConversationId;
 use chrono::Utc;
 use leptos::prelude::*;
 use message::datachannel::{
-  AckStatus, DataChannelMessage, MessageAck, MessageReaction, MessageRead, MessageRevoke,
-  ReactionAction, TypingIndicator,
+  AckStatus, DataChannelMessage, MessageAck, MessageEdit, MessageReaction, MessageRead,
+  MessageRevoke, ReactionAction, TypingIndicator,
 };
 use message::{MessageId, UserId};
 use std::collections::HashMap;
@@ -45,6 +45,17 @@ impl ChatManager {
       state.unread.update(|n| *n = n.saturating_add(1));
     }
 
+    // Thread replies also count against their thread unless the user
+    // is looking at it right now.
+    if let Some(root) = msg.thread_root
+      && !(active && state.open_thread.get_untracked() == Some(root))
+    {
+      state.thread_unread.update(|map| {
+        let n = map.entry(root).or_insert(0);
+        *n = n.saturating_add(1);
+      });
+    }
+
     let preview = preview_for(&msg);
     let ts = msg.timestamp_ms;
     self.app_state.conversations.update(|list| {
@@ -193,6 +204,36 @@ impl ChatManager {
     });
   }
 
+  /// Apply an incoming `MessageEdit`.
+  ///
+  /// Only the original sender's edits are honoured; stale or duplicate
+  /// revisions are ignored by [`ChatMessage::apply_edit`].
+  pub fn apply_edit(&self, sender: UserId, edit: &MessageEdit) {
+    let (conv, state) = {
+      let inner = self.inner.borrow();
+      let Some(conv) = inner.index.get(&edit.message_id).cloned() else {
+        return;
+      };
+      match inner.conversations.get(&conv).copied() {
+        Some(s) => (conv, s),
+        None => return,
+      }
+    };
+
+    let edited_at_ms = crate::chat::routing::nanos_to_ms(edit.timestamp_nanos);
+    let mut applied = false;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == edit.message_id)
+        && m.sender == sender
+      {
+        applied = m.apply_edit(edit.content.clone(), edit.revision, edited_at_ms);
+      }
+    });
+    if applied {
+      self.persist_updated(&conv, edit.message_id);
+    }
+  }
+
   /// Apply an incoming `MessageReaction`.
   pub fn apply_reaction(&self, user: UserId, reaction: &MessageReaction) {
     let state = {
@@ -0,0 +1,83 @@
+//! Offline mailbox fallback — hands direct messages that cannot reach
+//! their recipient to the server mailbox and applies the delivery
+//! receipts that come back.
+
+use super::{ChatManager, Inner, now_ms_to_nanos};
+use crate::chat::models::MessageStatus;
+use crate::state::ConversationId;
+use crate::webrtc::WebRtcManager;
+use chrono::Utc;
+use leptos::prelude::*;
+use message::datachannel::{AckStatus, DataChannelMessage, MessageAck};
+use message::signaling::MailboxDelivered;
+use message::{MessageId, UserId};
+use std::cell::RefCell;
+
+impl ChatManager {
+  /// Deposit a direct message in `peer`'s server mailbox. Returns
+  /// `false` when there is no WebRTC manager or the server offers no
+  /// mailbox.
+  pub(super) fn deposit_to_mailbox(
+    &self,
+    peer: &UserId,
+    id: MessageId,
+    wire: DataChannelMessage,
+  ) -> bool {
+    let Some(mgr) = self.webrtc.borrow().as_ref().cloned() else {
+      return false;
+    };
+    deposit_via_mailbox(&mgr, &self.inner, peer, id, wire)
+  }
+
+  /// Apply a mailbox delivery receipt: the recipient drained these
+  /// messages, which counts as a `MessageAck { status: Received }`.
+  pub fn apply_mailbox_delivered(&self, delivered: &MailboxDelivered) {
+    let timestamp_nanos = now_ms_to_nanos(Utc::now().timestamp_millis());
+    for id in &delivered.message_ids {
+      self.apply_ack(
+        delivered.recipient.clone(),
+        &MessageAck {
+          message_id: *id,
+          status: AckStatus::Received,
+          timestamp_nanos,
+        },
+      );
+      self.inner.borrow_mut().retry_payloads.remove(id);
+    }
+  }
+}
+
+/// Hand a direct message to the server mailbox instead of failing it.
+///
+/// The message stays `Sent` until the recipient drains it. Its wire
+/// payload is kept so the retry button still works if the deposit is
+/// rejected. Returns `false` (and does nothing) when the server offers
+/// no mailbox.
+pub(super) fn deposit_via_mailbox(
+  mgr: &WebRtcManager,
+  inner: &RefCell<Inner>,
+  peer: &UserId,
+  id: MessageId,
+  wire: DataChannelMessage,
+) -> bool {
+  if !mgr.mailbox_enabled() {
+    return false;
+  }
+  let state = {
+    let mut inner = inner.borrow_mut();
+    inner.retry_payloads.insert(id, wire.clone());
+    inner
+      .conversations
+      .get(&ConversationId::Direct(peer.clone()))
+      .copied()
+  };
+  if let Some(state) = state {
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == id) {
+        m.status = MessageStatus::Sent;
+      }
+    });
+  }
+  mgr.deposit_to_mailbox(peer.clone(), id, wire);
+  true
+}
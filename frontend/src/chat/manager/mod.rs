@@ -7,9 +7,12 @@
 //!   ancillary signals.
 //! * Provides mutation APIs (`push_incoming`, `push_outgoing`,
 //!   `apply_ack`, `apply_revoke`, `apply_reaction`, `mark_read`).
+//! * Tracks the open thread and per-thread unread counts
+//!   (`open_thread`, `close_thread`, `reply_in_thread`).
 //! * Dispatches outbound `DataChannel` messages via the `WebRtcManager`.
 //! * Runs a 1 Hz housekeeping tick that flushes the read-receipt
-//!   batcher and processes ACK retries.
+//!   batcher and processes ACK retries. Direct messages whose retries
+//!   run out go to the server mailbox when one is offered.
 //!
 //! All interior mutability is via `Rc<RefCell<_>>` because the
 //! application is single-threaded WASM. The type gets `Send + Sync`
@@ -17,8 +20,11 @@
 
 mod housekeeping;
 mod inbound;
+mod mailbox;
 mod outbound;
 mod persistence_bridge;
+mod polls;
+mod threads;
 mod wire;
 
 use crate::chat::ack_queue::{AckQueue, TickResult};
@@ -42,6 +48,9 @@ const TYPING_TIMEOUT_MS: i64 = 5_000;
 /// Rate-limit window for outbound `TypingIndicator` events (Req 4.5.x).
 const TYPING_RATE_LIMIT_MS: i64 = 3_000;
 
+/// A wire message together with its conversation and id.
+pub(crate) type WirePayload = (ConversationId, MessageId, DataChannelMessage);
+
 /// Reactive per-conversation state.
 #[derive(Debug, Clone, Copy)]
 pub struct ChatConversationState {
@@ -55,6 +64,11 @@ pub struct ChatConversationState {
   /// Last-seen id used to render the "new messages" divider
   /// (Req 4.10.x).
   pub last_seen: RwSignal<Option<MessageId>>,
+  /// Root of the thread shown in the thread panel, if any.
+  pub open_thread: RwSignal<Option<MessageId>>,
+  /// Unread reply count per thread root. Entries are removed when the
+  /// thread is opened.
+  pub thread_unread: RwSignal<HashMap<MessageId, u32>>,
 }
 
 impl ChatConversationState {
@@ -67,6 +81,8 @@ impl ChatConversationState {
       unread: RwSignal::new(0),
       typing: RwSignal::new(Vec::new()),
       last_seen: RwSignal::new(None),
+      open_thread: RwSignal::new(None),
+      thread_unread: RwSignal::new(HashMap::new()),
     }
   }
 }
@@ -133,19 +149,19 @@ impl Inner {
     }
   }
 
-  /// Process the ACK queue tick. Returns `(retries, expired_ids)` where
-  /// each retry is `(conversation, message_id, wire_payload)` and
-  /// `expired_ids` contains messages whose retries were exhausted.
+  /// Process the ACK queue tick. Returns `(retries, expired_ids,
+  /// expired_direct)` where each retry is `(conversation, message_id,
+  /// wire_payload)`, `expired_ids` contains messages whose retries were
+  /// exhausted and `expired_direct` carries the payloads of the expired
+  /// direct messages so they can fall back to the mailbox.
   pub(crate) fn process_ack_ticks(
     &mut self,
     now: i64,
-  ) -> (
-    Vec<(ConversationId, MessageId, DataChannelMessage)>,
-    Vec<MessageId>,
-  ) {
+  ) -> (Vec<WirePayload>, Vec<MessageId>, Vec<WirePayload>) {
     let ticks = self.ack_queue.tick(now);
     let mut retries = Vec::new();
     let mut expired_ids = Vec::new();
+    let mut expired_direct = Vec::new();
     for (id, result) in &ticks {
       match result {
         TickResult::Retry => {
@@ -159,12 +175,17 @@ impl Inner {
         }
         TickResult::Expired => {
           expired_ids.push(*id);
-          self.retry_payloads.remove(id);
+          let wire = self.retry_payloads.remove(id);
+          if let Some(conv @ ConversationId::Direct(_)) = self.index.get(id).cloned()
+            && let Some(wire) = wire
+          {
+            expired_direct.push((conv, *id, wire));
+          }
         }
         TickResult::Idle => {}
       }
     }
-    (retries, expired_ids)
+    (retries, expired_ids, expired_direct)
   }
 
   /// Remove stale peer-typing indicators that have exceeded the timeout
@@ -320,6 +341,7 @@ pub fn preview_for(msg: &ChatMessage) -> String {
       let preview = crate::chat::markdown::to_plain_text(content);
       format!("[Forwarded] {preview}")
     }
+    MessageContent::Poll(poll) => format!("[Poll] {}", poll.question),
     MessageContent::Revoked => "[Revoked]".to_string(),
   }
 }
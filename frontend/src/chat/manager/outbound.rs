@@ -1,5 +1,5 @@
 //! Outbound message dispatch — send text, sticker, voice, image,
-//! forward, revoke, reaction, and resend.
+//! forward, revoke, edit, reaction, and resend.
 
 use super::{ChatManager, ImagePayload, now_ms_to_nanos, preview_for};
 use crate::chat::models::{
@@ -13,8 +13,8 @@ use leptos::prelude::*;
 use message::MessageId;
 use message::UserId;
 use message::datachannel::{
-  ChatImage, ChatSticker, ChatText, ChatVoice, DataChannelMessage, ForwardMessage, MessageReaction,
-  MessageRevoke, ReactionAction,
+  ChatImage, ChatSticker, ChatText, ChatVoice, DataChannelMessage, ForwardMessage, MessageEdit,
+  MessageReaction, MessageRevoke, ReactionAction,
 };
 use std::collections::BTreeMap;
 
@@ -28,6 +28,29 @@ impl ChatManager {
     conv: ConversationId,
     content: String,
     reply_to: Option<ReplySnippet>,
+  ) -> Option<MessageId> {
+    self.send_text_in(conv, content, reply_to, None)
+  }
+
+  /// Send a text reply inside the thread rooted at `root`.
+  ///
+  /// Behaves like [`Self::send_text`] but tags the message with
+  /// `thread_root` so receivers file it under the thread.
+  pub fn reply_in_thread(
+    &self,
+    conv: ConversationId,
+    root: MessageId,
+    content: String,
+  ) -> Option<MessageId> {
+    self.send_text_in(conv, content, None, Some(root))
+  }
+
+  fn send_text_in(
+    &self,
+    conv: ConversationId,
+    content: String,
+    reply_to: Option<ReplySnippet>,
+    thread_root: Option<MessageId>,
   ) -> Option<MessageId> {
     let trimmed = content.trim().to_string();
     if trimmed.is_empty() || trimmed.chars().count() > MAX_TEXT_LENGTH {
@@ -47,10 +70,12 @@ impl ChatManager {
       outgoing: true,
       status: MessageStatus::Sending,
       reply_to: reply_to.clone(),
+      thread_root,
       read_by: Vec::new(),
       reactions: BTreeMap::new(),
       mentions_me: false,
       counted_unread: false,
+      edit: None,
     };
     self.push_outgoing(conv.clone(), ui_msg);
 
@@ -58,6 +83,7 @@ impl ChatManager {
       message_id: id,
       content: trimmed.clone(),
       reply_to: reply_to.map(|r| r.message_id),
+      thread_root,
       timestamp_nanos: now_ms_to_nanos(now_ms),
       room_id: conv.room_id(),
       mentions: crate::chat::mention::extract_user_ids(&trimmed, self),
@@ -90,10 +116,12 @@ impl ChatManager {
       outgoing: true,
       status: MessageStatus::Sending,
       reply_to: None,
+      thread_root: None,
       read_by: Vec::new(),
       reactions: BTreeMap::new(),
       mentions_me: false,
       counted_unread: false,
+      edit: None,
     };
     self.push_outgoing(conv.clone(), ui_msg);
 
@@ -102,6 +130,7 @@ impl ChatManager {
       pack_id,
       sticker_id,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: now_ms_to_nanos(now_ms),
       room_id: conv.room_id(),
     });
@@ -139,10 +168,12 @@ impl ChatManager {
       outgoing: true,
       status: MessageStatus::Sending,
       reply_to: None,
+      thread_root: None,
       read_by: Vec::new(),
       reactions: BTreeMap::new(),
       mentions_me: false,
       counted_unread: false,
+      edit: None,
     };
     self.push_outgoing(conv.clone(), ui_msg);
 
@@ -152,6 +183,7 @@ impl ChatManager {
       duration_ms,
       waveform,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: now_ms_to_nanos(now_ms),
       room_id: conv.room_id(),
     });
@@ -180,10 +212,12 @@ impl ChatManager {
       outgoing: true,
       status: MessageStatus::Sending,
       reply_to: None,
+      thread_root: None,
       read_by: Vec::new(),
       reactions: BTreeMap::new(),
       mentions_me: false,
       counted_unread: false,
+      edit: None,
     };
     self.push_outgoing(conv.clone(), ui_msg);
 
@@ -194,6 +228,7 @@ impl ChatManager {
       width: payload.width,
       height: payload.height,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: now_ms_to_nanos(now_ms),
       room_id: conv.room_id(),
     });
@@ -220,6 +255,7 @@ impl ChatManager {
       | MessageContent::Voice(_)
       | MessageContent::Image(_)
       | MessageContent::File(_)
+      | MessageContent::Poll(_)
       | MessageContent::Revoked => return None,
     };
 
@@ -240,10 +276,12 @@ impl ChatManager {
       outgoing: true,
       status: MessageStatus::Sending,
       reply_to: None,
+      thread_root: None,
       read_by: Vec::new(),
       reactions: BTreeMap::new(),
       mentions_me: false,
       counted_unread: false,
+      edit: None,
     };
     self.push_outgoing(target_conv.clone(), ui_msg);
 
@@ -305,6 +343,49 @@ impl ChatManager {
     applied
   }
 
+  /// Edit the text of an outbound message.
+  ///
+  /// Returns `true` when the edit was applied locally and a
+  /// `MessageEdit` was queued for sending. Rejects non-text and inbound
+  /// messages, and content that is empty, over the length cap or
+  /// identical to the current text.
+  pub fn edit_message(&self, conv: ConversationId, id: MessageId, content: String) -> bool {
+    let trimmed = content.trim().to_string();
+    if trimmed.is_empty() || trimmed.chars().count() > MAX_TEXT_LENGTH {
+      return false;
+    }
+    let Some(state) = self.inner.borrow().conversations.get(&conv).copied() else {
+      return false;
+    };
+    let now = Utc::now().timestamp_millis();
+
+    let mut revision = None;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == id)
+        && m.can_edit()
+        && !matches!(&m.content, MessageContent::Text(current) if *current == trimmed)
+      {
+        let next = m.revision() + 1;
+        if m.apply_edit(trimmed.clone(), next, now) {
+          revision = Some(next);
+        }
+      }
+    });
+    let Some(revision) = revision else {
+      return false;
+    };
+    self.persist_updated(&conv, id);
+
+    let wire = DataChannelMessage::MessageEdit(MessageEdit {
+      message_id: id,
+      content: trimmed,
+      revision,
+      timestamp_nanos: now_ms_to_nanos(now),
+    });
+    self.send_out(&conv, wire);
+    true
+  }
+
   /// Toggle an emoji reaction on a message.
   ///
   /// Returns `true` when the reaction state changed (and a DataChannel
@@ -443,6 +524,17 @@ impl ChatManager {
           });
         }
       } else {
+        // A peer that is not even signed in may stay away for a long
+        // time: hand the message to the server mailbox when one exists.
+        if let ConversationId::Direct(peer) = &conv {
+          let online = self
+            .app_state
+            .online_users
+            .with_untracked(|users| users.iter().any(|u| &u.user_id == peer));
+          if !online && self.deposit_to_mailbox(peer, id, wire.clone()) {
+            return;
+          }
+        }
         // Buffer the message for replay when the peer reconnects
         // instead of immediately marking as Failed. Cap the buffer at
         // 50 entries to prevent unbounded memory growth — evict the
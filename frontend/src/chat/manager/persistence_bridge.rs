@@ -7,7 +7,7 @@ use super::ChatManager;
 use crate::persistence::PersistenceManager;
 use crate::state::ConversationId;
 #[cfg(target_arch = "wasm32")]
-use leptos::prelude::{GetUntracked, Update};
+use leptos::prelude::{GetUntracked, Update, WithUntracked};
 use message::MessageId;
 #[cfg(target_arch = "wasm32")]
 use message::UserId;
@@ -80,6 +80,27 @@ impl ChatManager {
     }
   }
 
+  /// Re-save a message that was mutated in place (e.g. edited) so the
+  /// stored record matches what the UI shows.
+  #[cfg(target_arch = "wasm32")]
+  pub(super) fn persist_updated(&self, conv: &ConversationId, id: MessageId) {
+    let Some(pm) = self.get_persistence() else {
+      return;
+    };
+    let Some(state) = self.inner.borrow().conversations.get(conv).copied() else {
+      return;
+    };
+    state.messages.with_untracked(|list| {
+      if let Some(m) = list.iter().find(|m| m.id == id) {
+        pm.persist_message(conv, m);
+      }
+    });
+  }
+
+  /// No-op on native builds — IndexedDB is not available.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(super) fn persist_updated(&self, _conv: &ConversationId, _id: MessageId) {}
+
   /// Load the most recent messages for `conv` from IndexedDB and
   /// populate the reactive signal. Called when the user switches
   /// conversations (Req 11.2).
@@ -138,6 +159,41 @@ impl ChatManager {
   #[cfg(not(target_arch = "wasm32"))]
   pub fn load_history(&self, _conv: ConversationId) {}
 
+  /// Pull the stored replies of the thread rooted at `root` into the
+  /// conversation list. Replies older than the loaded history window
+  /// are otherwise only in IndexedDB, so the thread panel would show a
+  /// partial thread.
+  #[cfg(target_arch = "wasm32")]
+  pub(super) fn load_thread(&self, conv: ConversationId, root: MessageId) {
+    let Some(pm) = self.get_persistence() else {
+      return;
+    };
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      match pm.load_thread(&root).await {
+        Ok(replies) if !replies.is_empty() => {
+          let state = this.conversation_state(&conv);
+          let mut added = Vec::new();
+          state.messages.update(|list| {
+            added = crate::chat::threads::merge_loaded(list, replies);
+          });
+          let mut inner = this.inner.borrow_mut();
+          for id in added {
+            inner.index.insert(id, conv.clone());
+          }
+        }
+        Ok(_) => {}
+        Err(e) => {
+          web_sys::console::warn_1(&format!("[chat] load_thread failed for {root}: {e}").into());
+        }
+      }
+    });
+  }
+
+  /// No-op on native builds — IndexedDB is not available.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(super) fn load_thread(&self, _conv: ConversationId, _root: MessageId) {}
+
   /// Load older messages before `before_ts` and prepend them to the
   /// conversation state. Returns the count of messages loaded (0 if
   /// no more). Used by the infinite-scroll handler in the message
@@ -167,10 +223,10 @@ impl ChatManager {
               inner.index.insert(m.id, conv_clone.clone());
             }
           }
+          // Merge rather than prepend: thread replies pulled in by
+          // `load_thread` may already sit anywhere in the list.
           state.messages.update(|list| {
-            let mut combined = older;
-            combined.append(list);
-            *list = combined;
+            crate::chat::threads::merge_loaded(list, older);
           });
           on_done(count);
         }
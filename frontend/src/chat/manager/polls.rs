@@ -0,0 +1,186 @@
+//! Polls — creating polls, casting and reconciling votes, and closing.
+//!
+//! Tallies are never sent over the wire: each client folds the
+//! `PollVote` / `PollClose` frames it receives into the poll message's
+//! [`crate::chat::polls::PollState`] and persists the result with the
+//! message.
+
+use super::{ChatConversationState, ChatManager, now_ms_to_nanos};
+use crate::chat::models::{ChatMessage, MessageContent, MessageStatus};
+use crate::chat::polls::{PollDraft, PollState, counted_at_ms};
+use crate::chat::routing::nanos_to_ms;
+use crate::state::ConversationId;
+use chrono::Utc;
+use leptos::prelude::*;
+use message::datachannel::{ChatPoll, DataChannelMessage, PollClose, PollVote};
+use message::{MessageId, UserId};
+use std::collections::BTreeMap;
+
+impl ChatManager {
+  /// Post a poll into `conv`.
+  ///
+  /// Returns the id of the poll message, or `None` when the draft does
+  /// not normalise to a valid poll (see [`PollDraft::normalized`]).
+  pub fn send_poll(&self, conv: ConversationId, draft: &PollDraft) -> Option<MessageId> {
+    let now_ms = Utc::now().timestamp_millis();
+    let draft = draft.normalized(now_ms)?;
+    let sender = self.app_state.current_user_id()?;
+    let sender_name = self.current_nickname().unwrap_or_default();
+    let id = MessageId::new();
+
+    let wire = ChatPoll {
+      message_id: id,
+      question: draft.question,
+      options: draft.options,
+      multiple_choice: draft.multiple_choice,
+      anonymous: draft.anonymous,
+      deadline_nanos: draft.deadline_ms.map(now_ms_to_nanos),
+      reply_to: None,
+      thread_root: None,
+      timestamp_nanos: now_ms_to_nanos(now_ms),
+      room_id: conv.room_id(),
+    };
+
+    let ui_msg = ChatMessage {
+      id,
+      sender,
+      sender_name,
+      content: MessageContent::Poll(PollState::from_wire(&wire)),
+      timestamp_ms: now_ms,
+      outgoing: true,
+      status: MessageStatus::Sending,
+      reply_to: None,
+      thread_root: None,
+      read_by: Vec::new(),
+      reactions: BTreeMap::new(),
+      mentions_me: false,
+      counted_unread: false,
+      edit: None,
+    };
+    self.push_outgoing(conv.clone(), ui_msg);
+    self.dispatch_and_track(conv, id, DataChannelMessage::ChatPoll(wire));
+    Some(id)
+  }
+
+  /// Cast (or, with empty `choices`, retract) the local user's vote on
+  /// `poll_id`.
+  ///
+  /// Returns `true` when the ballot was recorded locally and a
+  /// `PollVote` was queued for sending.
+  pub fn vote_poll(&self, poll_id: MessageId, choices: Vec<u32>) -> bool {
+    let Some(voter) = self.app_state.current_user_id() else {
+      return false;
+    };
+    let Some((conv, state)) = self.poll_location(&poll_id) else {
+      return false;
+    };
+    let now = Utc::now().timestamp_millis();
+
+    let mut applied = false;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == poll_id) {
+        applied = m.apply_poll_vote(voter, choices.clone(), now);
+      }
+    });
+    if !applied {
+      return false;
+    }
+    self.persist_updated(&conv, poll_id);
+
+    let wire = DataChannelMessage::PollVote(PollVote {
+      poll_id,
+      choices,
+      timestamp_nanos: now_ms_to_nanos(now),
+    });
+    self.send_out(&conv, wire);
+    true
+  }
+
+  /// Close a poll the local user created.
+  ///
+  /// Returns `true` when the poll was closed locally and a `PollClose`
+  /// was queued for sending.
+  pub fn close_poll(&self, poll_id: MessageId) -> bool {
+    let Some(me) = self.app_state.current_user_id() else {
+      return false;
+    };
+    let Some((conv, state)) = self.poll_location(&poll_id) else {
+      return false;
+    };
+    let now = Utc::now().timestamp_millis();
+
+    let mut applied = false;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == poll_id) {
+        applied = m.close_poll(&me, now);
+      }
+    });
+    if !applied {
+      return false;
+    }
+    self.persist_updated(&conv, poll_id);
+
+    let wire = DataChannelMessage::PollClose(PollClose {
+      poll_id,
+      timestamp_nanos: now_ms_to_nanos(now),
+    });
+    self.send_out(&conv, wire);
+    true
+  }
+
+  /// Apply an incoming `PollVote` from `voter`, received in `conv`.
+  ///
+  /// Ignored unless the poll lives in `conv`, so a peer cannot vote in
+  /// a conversation they are not part of by naming its poll.
+  pub fn apply_poll_vote(&self, conv: &ConversationId, voter: UserId, vote: &PollVote) {
+    let Some(state) = self.poll_in(conv, &vote.poll_id) else {
+      return;
+    };
+    let cast_at_ms = counted_at_ms(
+      nanos_to_ms(vote.timestamp_nanos),
+      Utc::now().timestamp_millis(),
+    );
+    let mut applied = false;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == vote.poll_id) {
+        applied = m.apply_poll_vote(voter, vote.choices.clone(), cast_at_ms);
+      }
+    });
+    if applied {
+      self.persist_updated(conv, vote.poll_id);
+    }
+  }
+
+  /// Apply an incoming `PollClose`, received in `conv`. Only honoured
+  /// when `sender` created the poll and the poll lives in `conv`.
+  pub fn apply_poll_close(&self, conv: &ConversationId, sender: UserId, close: &PollClose) {
+    let Some(state) = self.poll_in(conv, &close.poll_id) else {
+      return;
+    };
+    let closed_at_ms = nanos_to_ms(close.timestamp_nanos);
+    let mut applied = false;
+    state.messages.update(|list| {
+      if let Some(m) = list.iter_mut().find(|m| m.id == close.poll_id) {
+        applied = m.close_poll(&sender, closed_at_ms);
+      }
+    });
+    if applied {
+      self.persist_updated(conv, close.poll_id);
+    }
+  }
+
+  /// Conversation holding `poll_id`, if it is loaded.
+  fn poll_location(&self, poll_id: &MessageId) -> Option<(ConversationId, ChatConversationState)> {
+    let inner = self.inner.borrow();
+    let conv = inner.index.get(poll_id).cloned()?;
+    let state = inner.conversations.get(&conv).copied()?;
+    Some((conv, state))
+  }
+
+  /// State of the conversation holding `poll_id`, when that is `conv`.
+  fn poll_in(&self, conv: &ConversationId, poll_id: &MessageId) -> Option<ChatConversationState> {
+    self
+      .poll_location(poll_id)
+      .and_then(|(at, state)| (&at == conv).then_some(state))
+  }
+}
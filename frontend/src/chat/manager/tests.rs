@@ -51,10 +51,12 @@ fn make_msg(content: MessageContent) -> ChatMessage {
     outgoing: true,
     status: MessageStatus::Sent,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -120,6 +122,20 @@ fn preview_for_forwarded() {
   assert!(preview.contains("forwarded text"));
 }
 
+#[test]
+fn preview_for_poll() {
+  let msg = make_msg(MessageContent::Poll(crate::chat::polls::PollState {
+    question: "Lunch?".to_string(),
+    options: vec!["Pizza".to_string(), "Sushi".to_string()],
+    multiple_choice: false,
+    anonymous: false,
+    deadline_ms: None,
+    ballots: BTreeMap::new(),
+    closed_at_ms: None,
+  }));
+  assert_eq!(preview_for(&msg), "[Poll] Lunch?");
+}
+
 #[test]
 fn preview_for_revoked() {
   let msg = make_msg(MessageContent::Revoked);
@@ -131,9 +147,10 @@ fn preview_for_revoked() {
 #[test]
 fn process_ack_ticks_returns_empty_on_idle() {
   let mut inner = Inner::new();
-  let (retries, expired) = inner.process_ack_ticks(1_000);
+  let (retries, expired, expired_direct) = inner.process_ack_ticks(1_000);
   assert!(retries.is_empty());
   assert!(expired.is_empty());
+  assert!(expired_direct.is_empty());
 }
 
 #[test]
@@ -153,7 +170,7 @@ fn process_ack_ticks_extracts_expired_after_72h() {
   let now_ms =
     chrono::Utc::now().timestamp_millis() + crate::chat::ack_queue::config::ACK_EXPIRY_MS + 1;
 
-  let (_retries, expired) = inner.process_ack_ticks(now_ms);
+  let (_retries, expired, _) = inner.process_ack_ticks(now_ms);
 
   // The entry should be classified as Expired.
   assert!(
@@ -171,6 +188,7 @@ fn process_ack_ticks_removes_retry_payload_on_expiry() {
     message_id: id,
     content: "hello".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 0,
     room_id: None,
     mentions: vec![],
@@ -189,7 +207,7 @@ fn process_ack_ticks_removes_retry_payload_on_expiry() {
   let now_ms =
     chrono::Utc::now().timestamp_millis() + crate::chat::ack_queue::config::ACK_EXPIRY_MS + 1;
 
-  let (_retries, expired) = inner.process_ack_ticks(now_ms);
+  let (_retries, expired, expired_direct) = inner.process_ack_ticks(now_ms);
 
   // Entry should be expired and its retry_payload cleaned up.
   assert!(expired.contains(&id));
@@ -197,6 +215,37 @@ fn process_ack_ticks_removes_retry_payload_on_expiry() {
     !inner.retry_payloads.contains_key(&id),
     "Expired entry payload should be removed"
   );
+  // The direct payload is handed back for the mailbox fallback.
+  assert_eq!(expired_direct.len(), 1);
+  assert_eq!(expired_direct[0].1, id);
+}
+
+#[test]
+fn process_ack_ticks_does_not_offer_room_payloads_to_mailbox() {
+  let mut inner = Inner::new();
+  let id = MessageId::new();
+  let room_id = message::RoomId::new();
+  let wire = DataChannelMessage::ChatText(message::datachannel::ChatText {
+    message_id: id,
+    content: "hello".to_string(),
+    reply_to: None,
+    thread_root: None,
+    timestamp_nanos: 0,
+    room_id: Some(room_id.clone()),
+    mentions: vec![],
+  });
+  inner.index.insert(id, ConversationId::Room(room_id));
+  inner.retry_payloads.insert(id, wire);
+  inner
+    .ack_queue
+    .track(id, "conv".to_string(), vec![UserId::from(1u64)]);
+
+  let now_ms =
+    chrono::Utc::now().timestamp_millis() + crate::chat::ack_queue::config::ACK_EXPIRY_MS + 1;
+  let (_retries, expired, expired_direct) = inner.process_ack_ticks(now_ms);
+
+  assert!(expired.contains(&id));
+  assert!(expired_direct.is_empty());
 }
 
 #[test]
@@ -213,7 +262,7 @@ fn process_ack_ticks_skips_retry_without_index() {
   // entry the retry is silently skipped.
   let base_time = chrono::Utc::now().timestamp_millis();
   let retry_time = base_time + crate::chat::ack_queue::config::INITIAL_BACKOFF_MS + 1;
-  let (retries, _expired) = inner.process_ack_ticks(retry_time);
+  let (retries, _expired, _) = inner.process_ack_ticks(retry_time);
 
   // No retry should be emitted because the index is missing.
   for (_, retry_id, _) in &retries {
@@ -0,0 +1,37 @@
+//! Thread panel state — which thread is open and how many of its
+//! replies are unread.
+
+use super::ChatManager;
+use crate::state::ConversationId;
+use leptos::prelude::*;
+use message::MessageId;
+
+impl ChatManager {
+  /// Open the thread rooted at `root` in `conv`.
+  ///
+  /// Clears the thread's unread count and pulls any replies that are
+  /// only in IndexedDB (older than the loaded history window) into the
+  /// conversation list.
+  pub fn open_thread(&self, conv: ConversationId, root: MessageId) {
+    let state = self.conversation_state(&conv);
+    state.open_thread.set(Some(root));
+    state.thread_unread.update(|map| {
+      map.remove(&root);
+    });
+    self.load_thread(conv, root);
+  }
+
+  /// Close the thread panel for `conv`.
+  pub fn close_thread(&self, conv: &ConversationId) {
+    self.conversation_state(conv).open_thread.set(None);
+  }
+
+  /// Unread reply count for the thread rooted at `root` (reactive).
+  #[must_use]
+  pub fn thread_unread(&self, conv: &ConversationId, root: &MessageId) -> u32 {
+    self
+      .conversation_state(conv)
+      .thread_unread
+      .with(|map| map.get(root).copied().unwrap_or(0))
+  }
+}
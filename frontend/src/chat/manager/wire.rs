@@ -68,17 +68,35 @@ impl ChatManager {
 }
 
 /// Encode and send a wire message to all peers in the conversation.
+///
+/// Direct messages are encrypted pairwise. Room messages are encrypted
+/// once under the local sender key for the room and the same frame is
+/// sent to every member (see `WebRtcManager::send_room_message`).
 pub(crate) fn send_wire_out(
   mgr: &WebRtcManager,
   app_state: &AppState,
   conv: &ConversationId,
   wire: DataChannelMessage,
 ) {
-  let targets: Vec<UserId> = match conv {
-    ConversationId::Direct(peer) => vec![peer.clone()],
+  let mgr = mgr.clone();
+  match conv {
+    ConversationId::Direct(peer) => {
+      let peer = peer.clone();
+      wasm_bindgen_futures::spawn_local(async move {
+        if let Err(e) = mgr
+          .send_encrypted_data_channel_message(peer.clone(), &wire)
+          .await
+        {
+          web_sys::console::warn_1(
+            &format!("[chat] send_encrypted_data_channel_message failed for peer {peer}: {e}")
+              .into(),
+          );
+        }
+      });
+    }
     ConversationId::Room(room_id) => {
       let me = app_state.current_user_id();
-      app_state
+      let targets: Vec<UserId> = app_state
         .room_members
         .get_untracked()
         .get(room_id)
@@ -90,21 +108,18 @@ pub(crate) fn send_wire_out(
             .filter(|uid| mgr.has_encryption_key(uid))
             .collect()
         })
-        .unwrap_or_default()
-    }
-  };
-  for peer in targets {
-    let mgr = mgr.clone();
-    let wire = wire.clone();
-    wasm_bindgen_futures::spawn_local(async move {
-      if let Err(e) = mgr
-        .send_encrypted_data_channel_message(peer.clone(), &wire)
-        .await
-      {
-        web_sys::console::warn_1(
-          &format!("[chat] send_encrypted_data_channel_message failed for peer {peer}: {e}").into(),
-        );
+        .unwrap_or_default();
+      if targets.is_empty() {
+        return;
       }
-    });
+      let room_id = room_id.clone();
+      wasm_bindgen_futures::spawn_local(async move {
+        if let Err(e) = mgr.send_room_message(&room_id, &targets, &wire).await {
+          web_sys::console::warn_1(
+            &format!("[chat] send_room_message failed for room {room_id}: {e}").into(),
+          );
+        }
+      });
+    }
   }
 }
@@ -1,16 +1,50 @@
 //! Minimal Markdown renderer with built-in XSS filtering.
 //!
 //! Implemented by hand (rather than pulling a heavyweight crate) so the
-//! WASM bundle size stays small and the allow-list is explicit. Supports:
+//! WASM bundle size stays small and the allow-list is explicit. Block
+//! constructs are recognised line by line:
 //!
-//! * Bold: `**text**`
-//! * Italic: `*text*`
-//! * Inline code: `` `text` ``
 //! * Fenced code blocks: ```` ```lang\ncode\n``` ````
-//! * Autolinks: bare `http(s)://...` URLs are wrapped in `<a>` tags.
+//! * Headings: `# title` … `###### title`
+//! * Quotes: `> text` (nested blocks allowed inside)
+//! * Lists: `- item`, `* item`, `+ item`, `1. step` / `1) step`;
+//!   indenting a line continues or nests under the item above
+//! * Tables: a `| a | b |` header, a `|---|:---:|` delimiter row, then
+//!   body rows
+//!
+//! Inline spans (see `inline`):
+//!
+//! * Bold `**text**`, italic `*text*`, strikethrough `~~text~~`
+//! * Inline code: `` `text` ``
+//! * Spoilers: `||text||`, hidden until focused or hovered
+//! * Masked links `[label](https://…)` and bare `http(s)://…` autolinks
+//! * Backslash escapes: `\*` renders a literal `*`
 //!
-//! Everything else is HTML-escaped. The output is always safe to inject
-//! via `inner_html`.
+//! Any other text is HTML-escaped, and line breaks inside a paragraph
+//! become `<br />`. The only elements ever emitted are `strong`, `em`,
+//! `del`, `code`, `pre`, `a`, `br`, `span`, `div`, `h1`–`h6`,
+//! `blockquote`, `ul`, `ol`, `li`, `table`, `thead`, `tbody`, `tr`, `th`
+//! and `td`. Attributes are limited to `href` / `title` (only ever an
+//! escaped `http(s)` URL), fixed `target` / `rel` / `class` /
+//! `tabindex` values, and a numeric `start` on `ol`. The output is
+//! always safe to inject via `inner_html`.
+
+mod inline;
+
+use std::fmt::Write as _;
+
+use inline::{parse_masked_link, render_inline, utf8_char_len};
+
+/// Deepest nesting of quotes, list items and inline spans that is still
+/// rendered as markup. Anything deeper falls back to escaped text, which
+/// keeps the recursion bounded for hostile input.
+const MAX_DEPTH: usize = 8;
+
+/// Columns a tab counts for when measuring list indentation.
+const TAB_WIDTH: usize = 4;
+
+/// Stand-in for spoiler text in plain-text previews and notifications.
+pub const SPOILER_MASK: &str = "▒▒▒";
 
 /// Render Markdown source to sanitised HTML.
 #[must_use]
@@ -19,178 +53,357 @@ pub fn render(source: &str) -> String {
   if source.is_empty() {
     return String::new();
   }
-
-  // Split fenced code blocks first so their contents are rendered as
-  // plain text (no further markdown is applied inside them).
+  let lines: Vec<&str> = source.lines().collect();
   let mut out = String::with_capacity(source.len() + 16);
-  let mut remainder = source;
-  while let Some(idx) = remainder.find("```") {
-    let (head, tail) = remainder.split_at(idx);
-    out.push_str(&render_inline(head));
-
-    // Skip opening ```
-    let after_open = &tail[3..];
-    if let Some(close) = after_open.find("```") {
-      // Strip optional language tag on the opening fence.
-      let mut fenced = &after_open[..close];
-      if let Some(nl) = fenced.find('\n') {
-        fenced = &fenced[nl + 1..];
-      }
-      out.push_str("<pre><code>");
-      out.push_str(&escape_html(fenced));
-      out.push_str("</code></pre>");
-      remainder = &after_open[close + 3..];
+  render_blocks(&lines, 0, &mut out);
+  out
+}
+
+/// Column alignment of a table, from its delimiter row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+  None,
+  Left,
+  Center,
+  Right,
+}
+
+/// List marker at the start of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ListMarker {
+  /// Columns of indentation before the marker.
+  indent: usize,
+  /// Start number for ordered lists, `None` for bullets.
+  start: Option<u32>,
+  /// Byte offset where the item text begins.
+  content: usize,
+}
+
+fn render_blocks(lines: &[&str], depth: usize, out: &mut String) {
+  let mut paragraph: Vec<&str> = Vec::new();
+  let mut i = 0;
+  while i < lines.len() {
+    let consumed = render_block(lines, i, depth, &mut paragraph, out);
+    if consumed == 0 {
+      paragraph.push(lines[i]);
+      i += 1;
     } else {
-      // Unterminated fence -> treat the remaining text as inline.
-      out.push_str(&render_inline(&remainder[idx..]));
-      return out;
+      i += consumed;
     }
   }
-  out.push_str(&render_inline(remainder));
-  out
+  flush_paragraph(&mut paragraph, depth, out);
 }
 
-fn render_inline(source: &str) -> String {
-  if source.is_empty() {
-    return String::new();
+/// Render the block starting at `lines[i]`, if there is one, and return
+/// how many lines it took. A pending paragraph is flushed first.
+fn render_block(
+  lines: &[&str],
+  i: usize,
+  depth: usize,
+  paragraph: &mut Vec<&str>,
+  out: &mut String,
+) -> usize {
+  let line = lines[i];
+  let trimmed = line.trim_start();
+
+  if trimmed.starts_with("```")
+    && let Some(close) = lines[i + 1..]
+      .iter()
+      .position(|l| l.trim_start().starts_with("```"))
+  {
+    flush_paragraph(paragraph, depth, out);
+    out.push_str("<pre><code>");
+    for code in &lines[i + 1..=i + close] {
+      out.push_str(&escape_html(code));
+      out.push('\n');
+    }
+    out.push_str("</code></pre>");
+    return close + 2;
   }
 
-  // Strategy: walk the input character-by-character and emit an output
-  // string. We handle bold/italic/inline-code/autolinks via a small
-  // pushdown automaton; anything unmatched is HTML-escaped.
+  if let Some((level, text)) = heading(trimmed) {
+    flush_paragraph(paragraph, depth, out);
+    let _ = write!(out, "<h{level}>");
+    out.push_str(&render_inline(text, depth + 1));
+    let _ = write!(out, "</h{level}>");
+    return 1;
+  }
 
-  let bytes = source.as_bytes();
-  let mut out = String::with_capacity(source.len() + 8);
-  let mut i = 0;
+  if depth >= MAX_DEPTH {
+    return 0;
+  }
 
-  while i < bytes.len() {
-    let c = bytes[i];
-    match c {
-      b'`' => {
-        // Inline code spans.
-        if let Some(end) = find_unescaped(bytes, i + 1, b'`') {
-          let inner = &source[i + 1..end];
-          out.push_str("<code>");
-          out.push_str(&escape_html(inner));
-          out.push_str("</code>");
-          i = end + 1;
-          continue;
-        }
+  if trimmed.starts_with('>') {
+    flush_paragraph(paragraph, depth, out);
+    let quoted: Vec<&str> = lines[i..]
+      .iter()
+      .map_while(|l| l.trim_start().strip_prefix('>'))
+      .map(|l| l.strip_prefix(' ').unwrap_or(l))
+      .collect();
+    out.push_str("<blockquote>");
+    render_blocks(&quoted, depth + 1, out);
+    out.push_str("</blockquote>");
+    return quoted.len();
+  }
+
+  // Like CommonMark, only a list starting at 1 may interrupt a
+  // paragraph, so "born in\n1990. It was…" stays prose.
+  let fresh = paragraph.iter().all(|l| l.trim().is_empty());
+  if let Some(marker) = list_marker(line).filter(|m| fresh || matches!(m.start, None | Some(1))) {
+    flush_paragraph(paragraph, depth, out);
+    return render_list(lines, i, marker, depth, out);
+  }
+
+  if line.contains('|')
+    && let Some(aligns) = lines.get(i + 1).and_then(|l| delimiter_row(l))
+  {
+    let header = split_row(line);
+    if header.len() == aligns.len() {
+      flush_paragraph(paragraph, depth, out);
+      return render_table(lines, i, &header, &aligns, depth, out);
+    }
+  }
+
+  0
+}
+
+/// Emit the collected paragraph lines, dropping blank lines at either
+/// end so they do not turn into stray `<br />`s around blocks.
+fn flush_paragraph(paragraph: &mut Vec<&str>, depth: usize, out: &mut String) {
+  let start = paragraph.iter().position(|l| !l.trim().is_empty());
+  let end = paragraph.iter().rposition(|l| !l.trim().is_empty());
+  if let (Some(start), Some(end)) = (start, end) {
+    out.push_str(&render_inline(&paragraph[start..=end].join("\n"), depth));
+  }
+  paragraph.clear();
+}
+
+/// `# text` → `(1, "text")`. Closing `#`s are dropped.
+fn heading(trimmed: &str) -> Option<(usize, &str)> {
+  let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+  if !(1..=6).contains(&level) {
+    return None;
+  }
+  let rest = &trimmed[level..];
+  if !rest.starts_with([' ', '\t']) {
+    return None;
+  }
+  let text = rest.trim().trim_end_matches('#').trim_end();
+  (!text.is_empty()).then_some((level, text))
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+  let ws = line.len() - line.trim_start_matches([' ', '\t']).len();
+  let indent = columns(&line[..ws]);
+  let rest = &line[ws..];
+  let (start, marker_len) = match rest.as_bytes().first()? {
+    b'-' | b'*' | b'+' => (None, 1),
+    b'0'..=b'9' => {
+      let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+      if digits > 9 || !matches!(rest.as_bytes().get(digits), Some(b'.' | b')')) {
+        return None;
       }
-      b'*' => {
-        // `**` -> bold; `*` -> italic.
-        let is_double = bytes.get(i + 1) == Some(&b'*');
-        if is_double {
-          if let Some(end) = find_pattern(bytes, i + 2, b"**") {
-            let inner = &source[i + 2..end];
-            out.push_str("<strong>");
-            out.push_str(&render_inline(inner));
-            out.push_str("</strong>");
-            i = end + 2;
-            continue;
-          }
-        } else if let Some(end) = find_unescaped(bytes, i + 1, b'*') {
-          let inner = &source[i + 1..end];
-          out.push_str("<em>");
-          out.push_str(&render_inline(inner));
-          out.push_str("</em>");
-          i = end + 1;
+      (Some(rest[..digits].parse().ok()?), digits + 1)
+    }
+    _ => return None,
+  };
+  let after = &rest[marker_len..];
+  if !after.starts_with([' ', '\t']) || after.trim().is_empty() {
+    return None;
+  }
+  let content = ws + marker_len + 1;
+  Some(ListMarker {
+    indent,
+    start,
+    content,
+  })
+}
+
+fn render_list(
+  lines: &[&str],
+  first: usize,
+  marker: ListMarker,
+  depth: usize,
+  out: &mut String,
+) -> usize {
+  let sibling = |line: &str| {
+    list_marker(line)
+      .filter(|m| m.indent == marker.indent && m.start.is_some() == marker.start.is_some())
+  };
+  let content_width = columns(&lines[first][..marker.content]);
+
+  let mut items: Vec<Vec<&str>> = Vec::new();
+  let mut i = first;
+  while i < lines.len() {
+    let line = lines[i];
+    if let Some(m) = sibling(line) {
+      items.push(vec![&line[m.content..]]);
+      i += 1;
+      continue;
+    }
+    if line.trim().is_empty() {
+      // A blank line only keeps the list going when the next item follows.
+      let next = lines[i..].iter().position(|l| !l.trim().is_empty());
+      match next.map(|n| i + n) {
+        Some(n) if sibling(lines[n]).is_some() => {
+          i = n;
           continue;
         }
+        _ => break,
       }
-      b'h' | b'H' if bytes[i..].starts_with(b"http://") || bytes[i..].starts_with(b"https://") => {
-        let end = scan_url(bytes, i);
-        let url = &source[i..end];
-        out.push_str("<a href=\"");
-        out.push_str(&escape_attr(url));
-        out.push_str("\" target=\"_blank\" rel=\"noopener noreferrer\">");
-        out.push_str(&escape_html(url));
-        out.push_str("</a>");
-        i = end;
-        continue;
-      }
-      b'\n' => {
-        out.push_str("<br />");
-        i += 1;
-        continue;
-      }
-      _ => {}
     }
-    // Default: escape the next UTF-8 character.
-    let ch_len = utf8_char_len(bytes, i);
-    let slice = &source[i..i + ch_len];
-    out.push_str(&escape_html(slice));
-    i += ch_len;
+    let ws = line.len() - line.trim_start_matches([' ', '\t']).len();
+    if columns(&line[..ws]) <= marker.indent {
+      break;
+    }
+    if let Some(item) = items.last_mut() {
+      item.push(strip_columns(line, content_width));
+    }
+    i += 1;
   }
-  out
-}
 
-fn utf8_char_len(bytes: &[u8], idx: usize) -> usize {
-  let b = bytes[idx];
-  // ASCII (b < 0x80) or UTF-8 continuation byte (0x80..=0xBF) both
-  // occupy a single byte from the indexer's point of view.
-  if b < 0xC0 {
-    1
-  } else if b < 0xE0 {
-    2
-  } else if b < 0xF0 {
-    3
+  match marker.start {
+    Some(1) => out.push_str("<ol>"),
+    Some(n) => {
+      let _ = write!(out, "<ol start=\"{n}\">");
+    }
+    None => out.push_str("<ul>"),
+  }
+  for item in &items {
+    out.push_str("<li>");
+    render_blocks(item, depth + 1, out);
+    out.push_str("</li>");
+  }
+  out.push_str(if marker.start.is_some() {
+    "</ol>"
   } else {
-    4
+    "</ul>"
+  });
+  i - first
+}
+
+fn columns(whitespace: &str) -> usize {
+  whitespace
+    .bytes()
+    .map(|b| if b == b'\t' { TAB_WIDTH } else { 1 })
+    .sum()
+}
+
+/// Remove up to `width` columns of leading whitespace.
+fn strip_columns(line: &str, width: usize) -> &str {
+  let mut seen = 0;
+  for (idx, b) in line.bytes().enumerate() {
+    if seen >= width || !matches!(b, b' ' | b'\t') {
+      return &line[idx..];
+    }
+    seen += if b == b'\t' { TAB_WIDTH } else { 1 };
   }
-  .min(bytes.len() - idx)
+  ""
 }
 
-fn find_unescaped(bytes: &[u8], from: usize, needle: u8) -> Option<usize> {
-  let mut i = from;
+/// Cells of a table row, without the optional outer pipes. `\|` stays
+/// inside its cell.
+fn split_row(line: &str) -> Vec<&str> {
+  let trimmed = line.trim();
+  let trimmed = trimmed.strip_prefix('|').unwrap_or(trimmed);
+  let trimmed = match trimmed.strip_suffix('|') {
+    Some(t) if !t.ends_with('\\') => t,
+    _ => trimmed,
+  };
+  let bytes = trimmed.as_bytes();
+  let mut cells = Vec::new();
+  let mut start = 0;
+  let mut i = 0;
   while i < bytes.len() {
-    if bytes[i] == b'\\' && i + 1 < bytes.len() {
-      i += 2;
-      continue;
-    }
-    if bytes[i] == needle {
-      return Some(i);
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'|' => {
+        cells.push(trimmed[start..i].trim());
+        start = i + 1;
+      }
+      _ => {}
     }
     i += 1;
   }
-  None
+  cells.push(trimmed[start..].trim());
+  cells
 }
 
-fn find_pattern(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
-  if needle.is_empty() || from > bytes.len() {
+/// `|---|:--:|--:|` → column alignments, or `None` if `line` is not a
+/// delimiter row.
+fn delimiter_row(line: &str) -> Option<Vec<Align>> {
+  if !line.contains('|') || !line.contains('-') {
     return None;
   }
-  let mut i = from;
-  while i + needle.len() <= bytes.len() {
-    if &bytes[i..i + needle.len()] == needle {
-      return Some(i);
-    }
-    i += 1;
-  }
-  None
+  split_row(line)
+    .into_iter()
+    .map(|cell| {
+      let left = cell.starts_with(':');
+      let right = cell.ends_with(':');
+      let dashes = cell.trim_matches(':');
+      if dashes.is_empty() || !dashes.bytes().all(|b| b == b'-') {
+        return None;
+      }
+      Some(match (left, right) {
+        (true, true) => Align::Center,
+        (true, false) => Align::Left,
+        (false, true) => Align::Right,
+        (false, false) => Align::None,
+      })
+    })
+    .collect()
 }
 
-fn scan_url(bytes: &[u8], from: usize) -> usize {
-  let mut j = from;
-  while j < bytes.len() {
-    let b = bytes[j];
-    // Stop on whitespace or common trailing punctuation.
-    if b == b' ' || b == b'\n' || b == b'\t' || b == b')' || b == b'"' || b == b'<' || b == b'>' {
-      break;
-    }
-    j += 1;
-  }
-  // Trim trailing punctuation that is usually not part of the URL.
-  while j > from {
-    let last = bytes[j - 1];
-    if last == b'.' || last == b',' || last == b';' || last == b':' || last == b'!' || last == b'?'
-    {
-      j -= 1;
-    } else {
-      break;
+fn render_table(
+  lines: &[&str],
+  first: usize,
+  header: &[&str],
+  aligns: &[Align],
+  depth: usize,
+  out: &mut String,
+) -> usize {
+  let rows: Vec<Vec<&str>> = lines[first + 2..]
+    .iter()
+    .take_while(|l| !l.trim().is_empty() && l.contains('|'))
+    .map(|l| split_row(l))
+    .collect();
+
+  out.push_str("<div class=\"md-table\"><table><thead><tr>");
+  for (cell, align) in header.iter().zip(aligns) {
+    push_cell(out, "th", cell, *align, depth);
+  }
+  out.push_str("</tr></thead>");
+  if !rows.is_empty() {
+    out.push_str("<tbody>");
+    for row in &rows {
+      out.push_str("<tr>");
+      for (col, align) in aligns.iter().enumerate() {
+        push_cell(
+          out,
+          "td",
+          row.get(col).copied().unwrap_or(""),
+          *align,
+          depth,
+        );
+      }
+      out.push_str("</tr>");
     }
+    out.push_str("</tbody>");
   }
-  j
+  out.push_str("</table></div>");
+  rows.len() + 2
+}
+
+fn push_cell(out: &mut String, tag: &str, text: &str, align: Align, depth: usize) {
+  let class = match align {
+    Align::None => "",
+    Align::Left => " class=\"md-align-left\"",
+    Align::Center => " class=\"md-align-center\"",
+    Align::Right => " class=\"md-align-right\"",
+  };
+  let _ = write!(out, "<{tag}{class}>");
+  out.push_str(&render_inline(text, depth + 1));
+  let _ = write!(out, "</{tag}>");
 }
 
 /// Escape HTML special characters so user input cannot inject tags or
@@ -220,21 +433,59 @@ fn escape_attr(input: &str) -> String {
 }
 
 /// Remove all Markdown formatting and return a plain-text preview.
+///
+/// List markers, table delimiter rows and link targets are dropped, and
+/// spoilers are replaced by [`SPOILER_MASK`] so previews and
+/// notifications do not give them away.
 #[must_use]
 pub fn to_plain_text(source: &str) -> String {
+  let mut joined = String::with_capacity(source.len());
+  for line in source.lines() {
+    if delimiter_row(line).is_some() {
+      continue;
+    }
+    let text = list_marker(line).map_or(line, |m| &line[m.content..]);
+    joined.push_str(text);
+    joined.push(' ');
+  }
+  plain_inline(&joined)
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn plain_inline(source: &str) -> String {
   let mut out = String::with_capacity(source.len());
   let bytes = source.as_bytes();
   let mut i = 0;
   while i < bytes.len() {
-    let c = bytes[i];
-    match c {
+    match bytes[i] {
       b'*' | b'`' | b'\\' | b'#' | b'>' => {
         i += 1;
       }
-      b'\n' => {
+      b'|' if bytes.get(i + 1) == Some(&b'|') => match source[i + 2..].find("||") {
+        Some(end) => {
+          out.push_str(SPOILER_MASK);
+          i += end + 4;
+        }
+        None => i += 2,
+      },
+      b'|' => {
         out.push(' ');
         i += 1;
       }
+      b'~' if bytes.get(i + 1) == Some(&b'~') => {
+        i += 2;
+      }
+      b'[' => {
+        if let Some(link) = parse_masked_link(source, i) {
+          out.push_str(&plain_inline(link.label));
+          i = link.end;
+        } else {
+          out.push('[');
+          i += 1;
+        }
+      }
       _ => {
         let len = utf8_char_len(bytes, i);
         out.push_str(&source[i..i + len]);
@@ -242,7 +493,7 @@ pub fn to_plain_text(source: &str) -> String {
       }
     }
   }
-  out.trim().to_string()
+  out
 }
 
 #[cfg(test)]
@@ -0,0 +1,264 @@
+//! Inline spans: emphasis, strikethrough, code, spoilers and links.
+//!
+//! A small pushdown walk over the bytes of one paragraph, heading or
+//! table cell. Delimiters are only ever matched at ASCII positions, so
+//! every slice taken lands on a UTF-8 boundary.
+
+use super::{MAX_DEPTH, escape_attr, escape_html};
+
+/// Paired delimiters and the markup they become. `**` is tried before
+/// `*` so bold wins over two italics.
+const PAIRS: [(&[u8], &str, &str); 4] = [
+  (b"**", "<strong>", "</strong>"),
+  (b"*", "<em>", "</em>"),
+  (b"~~", "<del>", "</del>"),
+  (
+    b"||",
+    "<span class=\"md-spoiler\" tabindex=\"0\">",
+    "</span>",
+  ),
+];
+
+/// A `[label](url)` link whose target passed [`is_web_url`].
+pub(super) struct MaskedLink<'a> {
+  pub label: &'a str,
+  pub url: &'a str,
+  /// Byte offset just past the closing `)`.
+  pub end: usize,
+}
+
+/// Render inline Markdown to sanitised HTML.
+pub(super) fn render_inline(source: &str, depth: usize) -> String {
+  render_spans(source, depth, true)
+}
+
+/// `links` is off inside a link label so anchors never nest.
+fn render_spans(source: &str, depth: usize, links: bool) -> String {
+  if source.is_empty() {
+    return String::new();
+  }
+  if depth > MAX_DEPTH {
+    return escape_html(source).replace('\n', "<br />");
+  }
+
+  let bytes = source.as_bytes();
+  let mut out = String::with_capacity(source.len() + 8);
+  let mut i = 0;
+
+  while i < bytes.len() {
+    if let Some((delim, end, open, close)) = paired_span(bytes, i) {
+      out.push_str(open);
+      out.push_str(&render_spans(
+        &source[i + delim.len()..end],
+        depth + 1,
+        links,
+      ));
+      out.push_str(close);
+      i = end + delim.len();
+      continue;
+    }
+    let next = bytes.get(i + 1).copied();
+    match bytes[i] {
+      b'\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+        out.push_str(&escape_html(&source[i + 1..i + 2]));
+        i += 2;
+        continue;
+      }
+      b'`' => {
+        i = push_code_span(&mut out, source, i);
+        continue;
+      }
+      b'[' if links => {
+        if let Some(link) = parse_masked_link(source, i) {
+          out.push_str("<a href=\"");
+          out.push_str(&escape_attr(link.url));
+          // The title shows the real target on hover, since the label
+          // can say anything.
+          out.push_str("\" title=\"");
+          out.push_str(&escape_attr(link.url));
+          out.push_str("\" target=\"_blank\" rel=\"noopener noreferrer\">");
+          out.push_str(&render_spans(link.label, depth + 1, false));
+          out.push_str("</a>");
+          i = link.end;
+          continue;
+        }
+      }
+      b'h' | b'H'
+        if links && (bytes[i..].starts_with(b"http://") || bytes[i..].starts_with(b"https://")) =>
+      {
+        let end = scan_url(bytes, i);
+        let url = &source[i..end];
+        out.push_str("<a href=\"");
+        out.push_str(&escape_attr(url));
+        out.push_str("\" target=\"_blank\" rel=\"noopener noreferrer\">");
+        out.push_str(&escape_html(url));
+        out.push_str("</a>");
+        i = end;
+        continue;
+      }
+      b'\n' => {
+        out.push_str("<br />");
+        i += 1;
+        continue;
+      }
+      _ => {}
+    }
+    // Default: escape the next UTF-8 character.
+    let ch_len = utf8_char_len(bytes, i);
+    let slice = &source[i..i + ch_len];
+    out.push_str(&escape_html(slice));
+    i += ch_len;
+  }
+  out
+}
+
+/// A run of N backticks is closed by the next run of exactly N;
+/// backslashes inside code are literal. Returns the offset after the
+/// span, or after the run when it is never closed.
+fn push_code_span(out: &mut String, source: &str, at: usize) -> usize {
+  let bytes = source.as_bytes();
+  let run = bytes[at..].iter().take_while(|&&b| b == b'`').count();
+  let Some(end) = find_backtick_run(bytes, at + run, run) else {
+    out.push_str(&source[at..at + run]);
+    return at + run;
+  };
+  let inner = &source[at + run..end];
+  let inner = match inner.strip_prefix(' ').and_then(|s| s.strip_suffix(' ')) {
+    Some(padded) if !padded.trim().is_empty() => padded,
+    _ => inner,
+  };
+  out.push_str("<code>");
+  out.push_str(&escape_html(inner));
+  out.push_str("</code>");
+  end + run
+}
+
+/// The first delimiter pair opening at `at` that is closed later on,
+/// with the offset of its closing delimiter.
+fn paired_span(
+  bytes: &[u8],
+  at: usize,
+) -> Option<(&'static [u8], usize, &'static str, &'static str)> {
+  PAIRS.iter().find_map(|&(delim, open, close)| {
+    if !bytes[at..].starts_with(delim) {
+      return None;
+    }
+    find_closing(bytes, at + delim.len(), delim).map(|end| (delim, end, open, close))
+  })
+}
+
+/// Parse `[label](url)` at `at`. The label may not contain another `[`
+/// or a line break, and the URL must be `http(s)`; anything else is not
+/// a link and renders as text.
+pub(super) fn parse_masked_link(source: &str, at: usize) -> Option<MaskedLink<'_>> {
+  let bytes = source.as_bytes();
+  let mut i = at + 1;
+  let close = loop {
+    match bytes.get(i)? {
+      b'\\' => i += 2,
+      b'[' | b'\n' => return None,
+      b']' => break i,
+      _ => i += 1,
+    }
+  };
+  if close == at + 1 || bytes.get(close + 1) != Some(&b'(') {
+    return None;
+  }
+  let url_start = close + 2;
+  let url_end = url_start + source[url_start..].find(|c: char| c == ')' || c.is_whitespace())?;
+  if bytes[url_end] != b')' {
+    return None;
+  }
+  let url = &source[url_start..url_end];
+  if !is_web_url(url) {
+    return None;
+  }
+  Some(MaskedLink {
+    label: &source[at + 1..close],
+    url,
+    end: url_end + 1,
+  })
+}
+
+/// Only `http://` and `https://` targets become links, so `javascript:`,
+/// `data:` and friends never reach an `href`.
+fn is_web_url(url: &str) -> bool {
+  ["http://", "https://"].iter().any(|scheme| {
+    url.len() > scheme.len()
+      && url
+        .get(..scheme.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+  })
+}
+
+pub(super) fn utf8_char_len(bytes: &[u8], idx: usize) -> usize {
+  let b = bytes[idx];
+  // ASCII (b < 0x80) or UTF-8 continuation byte (0x80..=0xBF) both
+  // occupy a single byte from the indexer's point of view.
+  if b < 0xC0 {
+    1
+  } else if b < 0xE0 {
+    2
+  } else if b < 0xF0 {
+    3
+  } else {
+    4
+  }
+  .min(bytes.len() - idx)
+}
+
+/// First unescaped `needle` at or after `from` that closes a non-empty
+/// span.
+fn find_closing(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+  let mut i = from;
+  while i + needle.len() <= bytes.len() {
+    if bytes[i] == b'\\' {
+      i += 2;
+      continue;
+    }
+    if &bytes[i..i + needle.len()] == needle {
+      return (i > from).then_some(i);
+    }
+    i += 1;
+  }
+  None
+}
+
+fn find_backtick_run(bytes: &[u8], from: usize, run: usize) -> Option<usize> {
+  let mut i = from;
+  while i < bytes.len() {
+    if bytes[i] == b'`' {
+      let len = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+      if len == run {
+        return Some(i);
+      }
+      i += len;
+    } else {
+      i += 1;
+    }
+  }
+  None
+}
+
+fn scan_url(bytes: &[u8], from: usize) -> usize {
+  let mut j = from;
+  while j < bytes.len() {
+    let b = bytes[j];
+    // Stop on whitespace or common trailing punctuation.
+    if b == b' ' || b == b'\n' || b == b'\t' || b == b')' || b == b'"' || b == b'<' || b == b'>' {
+      break;
+    }
+    j += 1;
+  }
+  // Trim trailing punctuation that is usually not part of the URL.
+  while j > from {
+    let last = bytes[j - 1];
+    if last == b'.' || last == b',' || last == b';' || last == b':' || last == b'!' || last == b'?'
+    {
+      j -= 1;
+    } else {
+      break;
+    }
+  }
+  j
+}
@@ -52,3 +52,166 @@ fn plain_text_strips_markers() {
   assert_eq!(to_plain_text("**hello**"), "hello");
   assert_eq!(to_plain_text("a\nb"), "a b");
 }
+
+// ── Block constructs ──
+
+#[test]
+fn plain_paragraphs_keep_line_breaks() {
+  assert_eq!(render("a\nb"), "a<br />b");
+  assert_eq!(render("a\n\nb"), "a<br /><br />b");
+}
+
+#[test]
+fn bullet_and_numbered_lists() {
+  assert_eq!(
+    render("- one\n- **two**"),
+    "<ul><li>one</li><li><strong>two</strong></li></ul>"
+  );
+  assert_eq!(render("1. a\n2. b"), "<ol><li>a</li><li>b</li></ol>");
+  assert_eq!(
+    render("3) c\n4) d"),
+    "<ol start=\"3\"><li>c</li><li>d</li></ol>"
+  );
+  assert_eq!(
+    render("Steps:\n1. a\n\n2. b\ndone"),
+    "Steps:<ol><li>a</li><li>b</li></ol>done"
+  );
+}
+
+#[test]
+fn indented_lines_nest_under_list_items() {
+  assert_eq!(
+    render("- a\n  - b\n    more\n- c"),
+    "<ul><li>a<ul><li>b<br />more</li></ul></li><li>c</li></ul>"
+  );
+}
+
+#[test]
+fn list_lookalikes_stay_text() {
+  assert_eq!(render("born in\n1990. It was"), "born in<br />1990. It was");
+  assert_eq!(render("-"), "-");
+  assert_eq!(render("-5 degrees"), "-5 degrees");
+  assert_eq!(render("*not a list*"), "<em>not a list</em>");
+}
+
+#[test]
+fn quotes_and_headings() {
+  assert_eq!(
+    render("> quoted\n> - item\nafter"),
+    "<blockquote>quoted<ul><li>item</li></ul></blockquote>after"
+  );
+  assert_eq!(render("## Title ##"), "<h2>Title</h2>");
+  assert_eq!(render("#hashtag"), "#hashtag");
+}
+
+#[test]
+fn tables_with_alignment() {
+  let html = render("| a | b |\n|:--|--:|\n| 1 | x\\|y |\n| 2 |");
+  assert_eq!(
+    html,
+    "<div class=\"md-table\"><table><thead><tr><th class=\"md-align-left\">a</th>\
+     <th class=\"md-align-right\">b</th></tr></thead><tbody><tr>\
+     <td class=\"md-align-left\">1</td><td class=\"md-align-right\">x|y</td></tr><tr>\
+     <td class=\"md-align-left\">2</td><td class=\"md-align-right\"></td></tr></tbody>\
+     </table></div>"
+  );
+  // Header and delimiter must agree on the column count.
+  assert!(!render("a | b\n|---|").contains("<table>"));
+}
+
+#[test]
+fn fenced_code_skips_block_and_inline_markup() {
+  assert_eq!(
+    render("```\n- **x**\n> y\n```"),
+    "<pre><code>- **x**\n&gt; y\n</code></pre>"
+  );
+}
+
+// ── Inline spans ──
+
+#[test]
+fn strikethrough_spoiler_and_escapes() {
+  assert_eq!(render("~~old~~ new"), "<del>old</del> new");
+  assert_eq!(
+    render("||secret||"),
+    "<span class=\"md-spoiler\" tabindex=\"0\">secret</span>"
+  );
+  assert_eq!(render("\\*\\*not bold\\*\\*"), "**not bold**");
+  assert_eq!(render("``a ` b``"), "<code>a ` b</code>");
+}
+
+#[test]
+fn masked_links_show_their_target() {
+  assert_eq!(
+    render("[**docs**](https://example.com/a)"),
+    "<a href=\"https://example.com/a\" title=\"https://example.com/a\" target=\"_blank\" \
+     rel=\"noopener noreferrer\"><strong>docs</strong></a>"
+  );
+  // Labels never contain a second anchor.
+  let html = render("[https://evil.example](https://example.com)");
+  assert_eq!(html.matches("<a ").count(), 1);
+}
+
+// ── XSS ──
+
+#[test]
+fn masked_links_reject_other_schemes() {
+  for source in [
+    "[x](javascript:alert(1))",
+    "[x](JAVASCRIPT:alert(1))",
+    "[x](data:text/html,<script>alert(1)</script>)",
+    "[x](vbscript:msgbox)",
+    "[x](//evil.example)",
+  ] {
+    let html = render(source);
+    assert!(!html.contains("<a"), "{source} -> {html}");
+    assert!(!html.contains("<script"), "{source} -> {html}");
+  }
+}
+
+#[test]
+fn attribute_breakout_is_escaped() {
+  let html = render("[x](https://a.example/\"onmouseover=\"alert(1))");
+  assert!(!html.contains("\"onmouseover"));
+  assert!(html.contains("&quot;onmouseover=&quot;"));
+}
+
+#[test]
+fn html_inside_every_construct_is_escaped() {
+  let payload = "<img src=x onerror=alert(1)>";
+  for source in [
+    format!("# {payload}"),
+    format!("> {payload}"),
+    format!("- {payload}"),
+    format!("1. {payload}"),
+    format!("| {payload} |\n|---|\n| {payload} |"),
+    format!("~~{payload}~~"),
+    format!("||{payload}||"),
+    format!("[{payload}](https://example.com)"),
+    format!("```\n{payload}\n```"),
+  ] {
+    let html = render(&source);
+    assert!(!html.contains("<img"), "{source} -> {html}");
+  }
+}
+
+#[test]
+fn deep_nesting_is_bounded() {
+  let quotes = format!("{}x", "> ".repeat(10_000));
+  let html = render(&quotes);
+  assert_eq!(html.matches("<blockquote>").count(), 8);
+
+  let spans = format!("{}x{}", "~~".repeat(5_000), "~~".repeat(5_000));
+  assert!(render(&spans).len() < spans.len() * 10);
+}
+
+#[test]
+fn plain_text_hides_spoilers_and_drops_structure() {
+  assert_eq!(
+    to_plain_text("the end: ||he lives||"),
+    format!("the end: {SPOILER_MASK}")
+  );
+  assert_eq!(to_plain_text("- a\n- b"), "a b");
+  assert_eq!(to_plain_text("| a | b |\n|---|---|\n| 1 | 2 |"), "a b 1 2");
+  assert_eq!(to_plain_text("[docs](https://x.com) ~~old~~"), "docs old");
+}
@@ -8,6 +8,9 @@
 //! * Inbound routing: decode `DataChannelMessage` -> update conversation state.
 //! * Outbound helpers: send text / sticker / voice / image / forward /
 //!   revoke / reaction / reply / read-receipts / typing-indicator.
+//! * Threads: per-root reply summaries and the main-timeline filter
+//!   (`threads`).
+//! * Polls: vote reconciliation and tallies for poll messages (`polls`).
 //! * Supporting UI components: message bubble, list, input bar, reply bar,
 //!   reaction picker, sticker panel, voice recorder, image picker,
 //!   forward modal, scroll-to-latest button, new-messages badge.
@@ -24,12 +27,15 @@ pub mod manager;
 pub mod markdown;
 pub mod mention;
 pub mod models;
+pub mod polls;
 pub mod read_batch;
 pub mod routing;
+pub mod threads;
 
 pub use manager::{ChatManager, provide_chat_manager, use_chat_manager};
 pub use models::{
-  ChatMessage, MessageContent, MessageStatus, ReactionEntry, ReplySnippet, StickerRef, VoiceClip,
+  ChatMessage, EditHistory, MessageContent, MessageRevision, MessageStatus, ReactionEntry,
+  ReplySnippet, StickerRef, VoiceClip,
 };
 
 #[cfg(test)]
@@ -6,6 +6,7 @@
 //! so the reactive rendering layer never has to pattern-match the wire
 //! enum.
 
+use super::polls::PollState;
 use message::{MessageId, UserId};
 use std::collections::BTreeMap;
 
@@ -26,6 +27,11 @@ pub const REVOKE_WINDOW_MS: i64 = 2 * 60 * 1_000;
 /// `cht101`).
 pub const MAX_TEXT_LENGTH: usize = 10_000;
 
+/// Maximum number of superseded revisions kept per edited message.
+/// Older revisions are dropped first so a message edited in a loop
+/// cannot grow its record without bound.
+pub const MAX_EDIT_HISTORY: usize = 50;
+
 /// Maximum voice-message duration (Req 4.9.x).
 pub const MAX_VOICE_DURATION_MS: u32 = 120_000;
 
@@ -101,6 +107,8 @@ pub enum MessageContent {
     /// forwarded as of Task 16 to keep scope bounded).
     content: String,
   },
+  /// Poll with its reconciled ballots.
+  Poll(PollState),
   /// Placeholder shown after a successful revoke (Req 4.4.x).
   Revoked,
 }
@@ -220,6 +228,28 @@ impl ReactionEntry {
   }
 }
 
+/// A superseded revision of an edited text message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRevision {
+  /// Revision number (`0` = the original send).
+  pub revision: u32,
+  /// Text content of this revision.
+  pub content: String,
+  /// Unix-ms timestamp at which this revision was written.
+  pub timestamp_ms: i64,
+}
+
+/// Edit state of a text message that has been edited at least once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHistory {
+  /// Revision number of the current content (`1` after the first edit).
+  pub revision: u32,
+  /// Unix-ms timestamp of the latest edit.
+  pub edited_at_ms: i64,
+  /// Earlier revisions, oldest first.
+  pub previous: Vec<MessageRevision>,
+}
+
 /// UI projection of a chat message.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChatMessage {
@@ -240,6 +270,9 @@ pub struct ChatMessage {
   pub status: MessageStatus,
   /// Reply-to snippet (if this message quotes an earlier one).
   pub reply_to: Option<ReplySnippet>,
+  /// Root message of the thread this message belongs to; `None` for
+  /// main-timeline messages (including thread roots themselves).
+  pub thread_root: Option<MessageId>,
   /// Set of user ids that have read this message (Req 4.3.x).
   pub read_by: Vec<UserId>,
   /// Reactions keyed by emoji. `BTreeMap` keeps render order stable.
@@ -251,6 +284,8 @@ pub struct ChatMessage {
   /// conversation's unread total. Prevents double counting when an
   /// outbound message is later acknowledged.
   pub counted_unread: bool,
+  /// Edit history; `None` until the message is first edited.
+  pub edit: Option<EditHistory>,
 }
 
 impl ChatMessage {
@@ -273,6 +308,77 @@ impl ChatMessage {
     self.content = MessageContent::Revoked;
     self.reply_to = None;
     self.reactions.clear();
+    self.edit = None;
+  }
+
+  /// Revision number of the current content (`0` if never edited).
+  #[must_use]
+  pub fn revision(&self) -> u32 {
+    self.edit.as_ref().map_or(0, |e| e.revision)
+  }
+
+  /// Whether the local user may edit this message. Only outbound text
+  /// messages are editable; there is no time window.
+  #[must_use]
+  pub const fn can_edit(&self) -> bool {
+    self.outgoing && matches!(self.content, MessageContent::Text(_))
+  }
+
+  /// Replace the text content with `revision`, moving the current text
+  /// into the history. Returns `true` on mutation.
+  ///
+  /// Ignored when the message is not text or `revision` is not newer
+  /// than the current one, which makes duplicated or reordered edit
+  /// frames idempotent.
+  pub fn apply_edit(&mut self, content: String, revision: u32, edited_at_ms: i64) -> bool {
+    let MessageContent::Text(current) = &mut self.content else {
+      return false;
+    };
+    if revision <= self.edit.as_ref().map_or(0, |e| e.revision) {
+      return false;
+    }
+    let superseded = MessageRevision {
+      revision: self.edit.as_ref().map_or(0, |e| e.revision),
+      content: std::mem::replace(current, content),
+      timestamp_ms: self
+        .edit
+        .as_ref()
+        .map_or(self.timestamp_ms, |e| e.edited_at_ms),
+    };
+    let edit = self.edit.get_or_insert_with(|| EditHistory {
+      revision: 0,
+      edited_at_ms,
+      previous: Vec::new(),
+    });
+    edit.previous.push(superseded);
+    if edit.previous.len() > MAX_EDIT_HISTORY {
+      let excess = edit.previous.len() - MAX_EDIT_HISTORY;
+      edit.previous.drain(..excess);
+    }
+    edit.revision = revision;
+    edit.edited_at_ms = edited_at_ms;
+    true
+  }
+
+  /// Record `voter`'s ballot on a poll message. Returns `true` on
+  /// mutation; see [`PollState::apply_vote`] for the rejection rules.
+  pub fn apply_poll_vote(&mut self, voter: UserId, choices: Vec<u32>, timestamp_ms: i64) -> bool {
+    let MessageContent::Poll(poll) = &mut self.content else {
+      return false;
+    };
+    poll.apply_vote(voter, choices, timestamp_ms)
+  }
+
+  /// Close a poll message on behalf of `by`. Only the poll's creator
+  /// may close it. Returns `true` on mutation.
+  pub fn close_poll(&mut self, by: &UserId, at_ms: i64) -> bool {
+    if &self.sender != by {
+      return false;
+    }
+    let MessageContent::Poll(poll) = &mut self.content else {
+      return false;
+    };
+    poll.close(at_ms)
   }
 
   /// Apply a reaction toggle. Returns `true` on mutation.
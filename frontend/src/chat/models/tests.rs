@@ -10,10 +10,12 @@ fn make_msg() -> ChatMessage {
     outgoing: true,
     status: MessageStatus::Sent,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -27,10 +29,12 @@ fn make_incoming_msg() -> ChatMessage {
     outgoing: false,
     status: MessageStatus::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -274,3 +278,115 @@ fn total_reaction_count_sums_across_emojis() {
 // MessageContent variants — only tested for runtime behaviour that
 // involves actual logic; derived PartialEq is a compile-time guarantee.
 // ---------------------------------------------------------------------------
+
+// ---------------------------------------------------------------------------
+// Editing
+// ---------------------------------------------------------------------------
+
+#[test]
+fn apply_edit_moves_previous_text_into_history() {
+  let mut msg = make_msg();
+  assert!(msg.apply_edit("hello".to_string(), 1, 500));
+  assert!(msg.apply_edit("hello!".to_string(), 2, 900));
+
+  assert_eq!(msg.content, MessageContent::Text("hello!".to_string()));
+  assert_eq!(msg.revision(), 2);
+  let edit = msg.edit.as_ref().unwrap();
+  assert_eq!(edit.edited_at_ms, 900);
+  assert_eq!(
+    edit.previous,
+    vec![
+      MessageRevision {
+        revision: 0,
+        content: "hi".to_string(),
+        timestamp_ms: 0,
+      },
+      MessageRevision {
+        revision: 1,
+        content: "hello".to_string(),
+        timestamp_ms: 500,
+      },
+    ]
+  );
+}
+
+#[test]
+fn apply_edit_ignores_stale_revisions() {
+  let mut msg = make_msg();
+  assert!(msg.apply_edit("second".to_string(), 2, 200));
+  assert!(!msg.apply_edit("first".to_string(), 1, 100));
+  assert!(!msg.apply_edit("second again".to_string(), 2, 300));
+  assert_eq!(msg.content, MessageContent::Text("second".to_string()));
+  assert_eq!(msg.edit.as_ref().unwrap().previous.len(), 1);
+}
+
+#[test]
+fn apply_edit_rejects_non_text() {
+  let mut msg = make_msg();
+  msg.mark_revoked();
+  assert!(!msg.apply_edit("x".to_string(), 1, 0));
+  assert!(msg.edit.is_none());
+}
+
+#[test]
+fn apply_edit_caps_history() {
+  let mut msg = make_msg();
+  for rev in 1..=(MAX_EDIT_HISTORY as u32 + 5) {
+    assert!(msg.apply_edit(format!("v{rev}"), rev, i64::from(rev)));
+  }
+  let edit = msg.edit.as_ref().unwrap();
+  assert_eq!(edit.previous.len(), MAX_EDIT_HISTORY);
+  assert_eq!(edit.previous[0].revision, 5);
+}
+
+#[test]
+fn can_edit_only_outgoing_text() {
+  assert!(make_msg().can_edit());
+  assert!(!make_incoming_msg().can_edit());
+  let mut revoked = make_msg();
+  revoked.mark_revoked();
+  assert!(!revoked.can_edit());
+}
+
+#[test]
+fn revoke_clears_edit_history() {
+  let mut msg = make_msg();
+  msg.apply_edit("changed".to_string(), 1, 10);
+  msg.mark_revoked();
+  assert!(msg.edit.is_none());
+}
+
+fn make_poll_msg() -> ChatMessage {
+  let mut msg = make_incoming_msg();
+  msg.content = MessageContent::Poll(PollState {
+    question: "Lunch?".to_string(),
+    options: vec!["Pizza".to_string(), "Sushi".to_string()],
+    multiple_choice: false,
+    anonymous: false,
+    deadline_ms: None,
+    ballots: BTreeMap::new(),
+    closed_at_ms: None,
+  });
+  msg
+}
+
+#[test]
+fn only_poll_creator_can_close() {
+  let mut msg = make_poll_msg();
+  assert!(!msg.close_poll(&UserId::from(3u64), 2_000));
+  assert!(msg.close_poll(&UserId::from(2u64), 2_000));
+  let MessageContent::Poll(poll) = &msg.content else {
+    panic!("expected poll");
+  };
+  assert_eq!(poll.closed_at_ms, Some(2_000));
+}
+
+#[test]
+fn poll_votes_ignore_non_poll_messages() {
+  let mut text = make_msg();
+  assert!(!text.apply_poll_vote(UserId::from(2u64), vec![0], 10));
+  assert!(!text.close_poll(&UserId::from(1u64), 10));
+
+  let mut poll = make_poll_msg();
+  assert!(poll.apply_poll_vote(UserId::from(1u64), vec![1], 10));
+}
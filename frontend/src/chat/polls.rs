@@ -0,0 +1,218 @@
+//! Poll state and vote reconciliation.
+//!
+//! A poll travels as a `ChatPoll` chat message; votes and the close
+//! arrive later as separate `PollVote` / `PollClose` frames from each
+//! participant. Every client folds those frames into the poll's
+//! [`PollState`] with the same rules, so tallies converge regardless of
+//! delivery order:
+//!
+//! * each voter's newest ballot (by sender timestamp) wins, and an
+//!   empty ballot is kept as a tombstone so an older vote arriving
+//!   late cannot resurrect a retraction;
+//! * ballots cast at or after the deadline or the close are ignored.
+//!   A received ballot counts as cast no earlier than it arrived (see
+//!   [`counted_at_ms`]), so a backdated vote cannot slip in late.
+
+use std::collections::BTreeMap;
+
+use message::UserId;
+use message::datachannel::ChatPoll;
+
+use crate::chat::routing::nanos_to_ms;
+
+/// One voter's current choice set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollBallot {
+  /// Chosen option indices. Empty after a retraction.
+  pub choices: Vec<u32>,
+  /// Sender timestamp (Unix ms) of the vote.
+  pub timestamp_ms: i64,
+}
+
+/// Poll carried by [`super::models::MessageContent::Poll`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollState {
+  /// Poll question.
+  pub question: String,
+  /// Answer options, in display order.
+  pub options: Vec<String>,
+  /// Whether a voter may pick more than one option.
+  pub multiple_choice: bool,
+  /// Whether voter names are hidden. Voter ids are still kept so
+  /// ballots can be de-duplicated, but are never rendered.
+  pub anonymous: bool,
+  /// Deadline (Unix ms) after which votes are ignored.
+  pub deadline_ms: Option<i64>,
+  /// Latest ballot per voter. `BTreeMap` keeps render order stable.
+  pub ballots: BTreeMap<UserId, PollBallot>,
+  /// Unix-ms timestamp at which the creator closed the poll.
+  pub closed_at_ms: Option<i64>,
+}
+
+impl PollState {
+  /// Fresh state for a poll received (or sent) over the wire.
+  #[must_use]
+  pub fn from_wire(poll: &ChatPoll) -> Self {
+    Self {
+      question: poll.question.clone(),
+      options: poll.options.clone(),
+      multiple_choice: poll.multiple_choice,
+      anonymous: poll.anonymous,
+      deadline_ms: poll.deadline_nanos.map(nanos_to_ms),
+      ballots: BTreeMap::new(),
+      closed_at_ms: None,
+    }
+  }
+
+  /// Whether a vote cast at `at_ms` still counts.
+  #[must_use]
+  pub fn is_open_at(&self, at_ms: i64) -> bool {
+    self.deadline_ms.is_none_or(|d| at_ms < d) && self.closed_at_ms.is_none_or(|c| at_ms < c)
+  }
+
+  /// Record `voter`'s ballot. Returns `true` on mutation.
+  ///
+  /// Rejected when the poll was no longer open at `timestamp_ms`, when
+  /// `choices` does not fit the poll (out-of-range or duplicate
+  /// indices, several picks on a single-choice poll), or when a newer
+  /// ballot from the same voter is already recorded.
+  pub fn apply_vote(&mut self, voter: UserId, choices: Vec<u32>, timestamp_ms: i64) -> bool {
+    if !self.is_open_at(timestamp_ms)
+      || !ChatPoll::choices_valid(self.options.len(), self.multiple_choice, &choices)
+    {
+      return false;
+    }
+    if let Some(current) = self.ballots.get(&voter)
+      && (current.timestamp_ms > timestamp_ms
+        || (current.timestamp_ms == timestamp_ms && current.choices == choices))
+    {
+      return false;
+    }
+    self.ballots.insert(
+      voter,
+      PollBallot {
+        choices,
+        timestamp_ms,
+      },
+    );
+    true
+  }
+
+  /// Close the poll at `at_ms`. Returns `true` on mutation.
+  ///
+  /// An earlier close wins over a later one. Ballots cast at or after
+  /// the close are dropped, since they may have been applied before
+  /// the close frame arrived.
+  pub fn close(&mut self, at_ms: i64) -> bool {
+    if self.closed_at_ms.is_some_and(|c| c <= at_ms) {
+      return false;
+    }
+    self.closed_at_ms = Some(at_ms);
+    self.ballots.retain(|_, b| b.timestamp_ms < at_ms);
+    true
+  }
+
+  /// Vote count per option, aligned with [`Self::options`].
+  #[must_use]
+  pub fn tally(&self) -> Vec<usize> {
+    let mut counts = vec![0; self.options.len()];
+    for ballot in self.ballots.values() {
+      for &choice in &ballot.choices {
+        if let Some(n) = counts.get_mut(choice as usize) {
+          *n += 1;
+        }
+      }
+    }
+    counts
+  }
+
+  /// Number of voters with a non-empty ballot.
+  #[must_use]
+  pub fn voter_count(&self) -> usize {
+    self
+      .ballots
+      .values()
+      .filter(|b| !b.choices.is_empty())
+      .count()
+  }
+
+  /// Options currently chosen by `user` (empty if they have not voted).
+  #[must_use]
+  pub fn choices_of(&self, user: &UserId) -> &[u32] {
+    self.ballots.get(user).map_or(&[], |b| b.choices.as_slice())
+  }
+
+  /// Voters who picked `option`. Always empty for anonymous polls.
+  #[must_use]
+  pub fn voters_for(&self, option: u32) -> Vec<UserId> {
+    if self.anonymous {
+      return Vec::new();
+    }
+    self
+      .ballots
+      .iter()
+      .filter(|(_, b)| b.choices.contains(&option))
+      .map(|(user, _)| user.clone())
+      .collect()
+  }
+}
+
+/// Time at which a ballot sent at `sent_ms` (sender clock) and
+/// received at `received_ms` (local clock) is counted: the later of
+/// the two.
+#[must_use]
+pub fn counted_at_ms(sent_ms: i64, received_ms: i64) -> i64 {
+  sent_ms.max(received_ms)
+}
+
+/// Poll as entered in the composer, before it is sent.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PollDraft {
+  /// Poll question.
+  pub question: String,
+  /// Answer options; blank entries are dropped on send.
+  pub options: Vec<String>,
+  /// Whether a voter may pick more than one option.
+  pub multiple_choice: bool,
+  /// Whether voter names are hidden.
+  pub anonymous: bool,
+  /// Optional deadline (Unix ms).
+  pub deadline_ms: Option<i64>,
+}
+
+impl PollDraft {
+  /// Trim the question and options and drop blank options. Returns
+  /// `None` when the result would not pass [`ChatPoll::is_valid`] or
+  /// the deadline is not after `now_ms`.
+  #[must_use]
+  pub fn normalized(&self, now_ms: i64) -> Option<Self> {
+    let question = self.question.trim();
+    let options: Vec<String> = self
+      .options
+      .iter()
+      .map(|o| o.trim())
+      .filter(|o| !o.is_empty())
+      .map(ToString::to_string)
+      .collect();
+    if question.is_empty()
+      || question.chars().count() > ChatPoll::MAX_QUESTION_LENGTH
+      || !(ChatPoll::MIN_OPTIONS..=ChatPoll::MAX_OPTIONS).contains(&options.len())
+      || options
+        .iter()
+        .any(|o| o.chars().count() > ChatPoll::MAX_OPTION_LENGTH)
+      || self.deadline_ms.is_some_and(|d| d <= now_ms)
+    {
+      return None;
+    }
+    Some(Self {
+      question: question.to_string(),
+      options,
+      multiple_choice: self.multiple_choice,
+      anonymous: self.anonymous,
+      deadline_ms: self.deadline_ms,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests;
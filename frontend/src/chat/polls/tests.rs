@@ -0,0 +1,166 @@
+use super::*;
+
+fn make_poll(multiple_choice: bool, anonymous: bool) -> PollState {
+  PollState {
+    question: "Lunch?".to_string(),
+    options: vec![
+      "Pizza".to_string(),
+      "Sushi".to_string(),
+      "Tacos".to_string(),
+    ],
+    multiple_choice,
+    anonymous,
+    deadline_ms: None,
+    ballots: BTreeMap::new(),
+    closed_at_ms: None,
+  }
+}
+
+fn user(n: u64) -> UserId {
+  UserId::from(n)
+}
+
+#[test]
+fn from_wire_converts_deadline_to_ms() {
+  let wire = ChatPoll {
+    message_id: message::MessageId::new(),
+    question: "Q".to_string(),
+    options: vec!["a".to_string(), "b".to_string()],
+    multiple_choice: true,
+    anonymous: true,
+    deadline_nanos: Some(5_000_000_000),
+    reply_to: None,
+    thread_root: None,
+    timestamp_nanos: 0,
+    room_id: None,
+  };
+  let poll = PollState::from_wire(&wire);
+  assert_eq!(poll.deadline_ms, Some(5_000));
+  assert!(poll.multiple_choice && poll.anonymous);
+  assert!(poll.ballots.is_empty());
+  assert_eq!(poll.closed_at_ms, None);
+}
+
+#[test]
+fn tally_counts_each_choice() {
+  let mut poll = make_poll(true, false);
+  assert!(poll.apply_vote(user(1), vec![0, 2], 10));
+  assert!(poll.apply_vote(user(2), vec![2], 11));
+  assert_eq!(poll.tally(), vec![1, 0, 2]);
+  assert_eq!(poll.voter_count(), 2);
+  assert_eq!(poll.choices_of(&user(1)), &[0, 2]);
+  assert!(poll.choices_of(&user(3)).is_empty());
+}
+
+#[test]
+fn single_choice_rejects_several_picks() {
+  let mut poll = make_poll(false, false);
+  assert!(!poll.apply_vote(user(1), vec![0, 1], 10));
+  assert!(!poll.apply_vote(user(1), vec![3], 10));
+  assert!(poll.apply_vote(user(1), vec![1], 10));
+  assert_eq!(poll.tally(), vec![0, 1, 0]);
+}
+
+#[test]
+fn newest_ballot_wins_regardless_of_arrival_order() {
+  let mut poll = make_poll(false, false);
+  assert!(poll.apply_vote(user(1), vec![1], 20));
+  assert!(!poll.apply_vote(user(1), vec![0], 10), "stale vote ignored");
+  assert!(!poll.apply_vote(user(1), vec![1], 20), "duplicate ignored");
+  assert_eq!(poll.tally(), vec![0, 1, 0]);
+}
+
+#[test]
+fn retraction_is_kept_as_tombstone() {
+  let mut poll = make_poll(false, false);
+  assert!(poll.apply_vote(user(1), vec![1], 10));
+  assert!(poll.apply_vote(user(1), vec![], 20));
+  assert_eq!(poll.voter_count(), 0);
+  assert!(
+    !poll.apply_vote(user(1), vec![2], 15),
+    "older vote stays retracted"
+  );
+  assert_eq!(poll.tally(), vec![0, 0, 0]);
+}
+
+#[test]
+fn votes_after_deadline_are_ignored() {
+  let mut poll = make_poll(false, false);
+  poll.deadline_ms = Some(100);
+  assert!(poll.apply_vote(user(1), vec![0], 99));
+  assert!(!poll.apply_vote(user(2), vec![0], 100));
+  assert!(!poll.is_open_at(100));
+}
+
+#[test]
+fn close_drops_later_ballots_and_keeps_earliest_close() {
+  let mut poll = make_poll(false, false);
+  assert!(poll.apply_vote(user(1), vec![0], 10));
+  assert!(poll.apply_vote(user(2), vec![1], 60));
+  assert!(poll.close(50));
+  assert_eq!(poll.tally(), vec![1, 0, 0]);
+  assert!(!poll.apply_vote(user(3), vec![2], 70));
+  assert!(!poll.close(80), "later close ignored");
+  assert!(poll.close(40), "earlier close wins");
+  assert_eq!(poll.closed_at_ms, Some(40));
+}
+
+#[test]
+fn backdated_votes_count_from_arrival() {
+  let mut poll = make_poll(false, false);
+  poll.deadline_ms = Some(100);
+  assert_eq!(counted_at_ms(50, 40), 50);
+  assert!(
+    !poll.apply_vote(user(1), vec![0], counted_at_ms(50, 120)),
+    "vote claiming to predate the deadline arrived after it"
+  );
+  assert!(poll.apply_vote(user(1), vec![0], counted_at_ms(50, 60)));
+}
+
+#[test]
+fn anonymous_polls_hide_voters() {
+  let mut named = make_poll(false, false);
+  named.apply_vote(user(1), vec![0], 10);
+  assert_eq!(named.voters_for(0), vec![user(1)]);
+
+  let mut anon = make_poll(false, true);
+  anon.apply_vote(user(1), vec![0], 10);
+  assert!(anon.voters_for(0).is_empty());
+  assert_eq!(anon.tally(), vec![1, 0, 0]);
+}
+
+#[test]
+fn draft_normalization_trims_and_validates() {
+  let draft = PollDraft {
+    question: "  Where?  ".to_string(),
+    options: vec![
+      " A ".to_string(),
+      String::new(),
+      "B".to_string(),
+      "  ".to_string(),
+    ],
+    multiple_choice: false,
+    anonymous: false,
+    deadline_ms: Some(1_000),
+  };
+  let norm = draft.normalized(0).expect("valid draft");
+  assert_eq!(norm.question, "Where?");
+  assert_eq!(norm.options, vec!["A".to_string(), "B".to_string()]);
+
+  assert!(
+    draft.normalized(1_000).is_none(),
+    "deadline must be in the future"
+  );
+
+  let one_option = PollDraft {
+    options: vec!["A".to_string(), " ".to_string()],
+    ..draft.clone()
+  };
+  assert!(one_option.normalized(0).is_none());
+
+  let blank_question = PollDraft {
+    question: " ".to_string(),
+    ..draft
+  };
+  assert!(blank_question.normalized(0).is_none());
+}
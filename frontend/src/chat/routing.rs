@@ -7,10 +7,11 @@
 //! [`ChatMessage`] projection.
 //!
 //! Every chat payload (`ChatText` / `ChatSticker` / `ChatVoice` /
-//! `ChatImage` / `ForwardMessage`) additionally triggers a
+//! `ChatImage` / `ForwardMessage` / `ChatPoll`) additionally triggers a
 //! `MessageAck{status=Received}` that the manager sends back to the
-//! peer. Control frames (`MessageAck` / `MessageRevoke` / `MessageRead`
-//! / `MessageReaction` / `TypingIndicator`) simply mutate local state.
+//! peer. Control frames (`MessageAck` / `MessageRevoke` / `MessageEdit`
+//! / `MessageRead` / `MessageReaction` / `PollVote` / `PollClose` /
+//! `TypingIndicator`) simply mutate local state.
 //!
 //! The mapping is deliberately exhaustive: variants outside the chat
 //! scope (file transfer, avatar, theater, encryption) are ignored here
@@ -20,6 +21,7 @@ use crate::chat::manager::ChatManager;
 use crate::chat::models::{
   ChatMessage, ImageRef, MessageContent, MessageStatus, ReplySnippet, StickerRef, VoiceClip,
 };
+use crate::chat::polls::PollState;
 use crate::state::ConversationId;
 use leptos::prelude::WithUntracked;
 use message::datachannel::{
@@ -74,7 +76,7 @@ fn base64_encode(input: &[u8]) -> String {
 
 /// Best-effort conversion from nanosecond wire timestamps to
 /// millisecond UI timestamps. Saturates on overflow.
-fn nanos_to_ms(nanos: u64) -> i64 {
+pub(crate) fn nanos_to_ms(nanos: u64) -> i64 {
   let ms = nanos / 1_000_000;
   i64::try_from(ms).unwrap_or(i64::MAX)
 }
@@ -99,10 +101,12 @@ fn build_inbound(
     outgoing: false,
     status: MessageStatus::Received,
     reply_to,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -176,6 +180,7 @@ pub fn dispatch_incoming(
       message_id,
       content,
       reply_to,
+      thread_root,
       timestamp_nanos,
       room_id: _,
       mentions,
@@ -197,7 +202,7 @@ pub fn dispatch_incoming(
         })
       };
       let reply = resolve_reply_snippet(mgr, &conv, reply_to);
-      let ui = build_inbound(
+      let mut ui = build_inbound(
         message_id,
         peer.clone(),
         peer_name,
@@ -206,6 +211,7 @@ pub fn dispatch_incoming(
         reply,
         mentions_me,
       );
+      ui.thread_root = thread_root;
       mgr.push_incoming(conv, ui);
       ack(mgr, peer, message_id, AckStatus::Received);
     }
@@ -215,6 +221,7 @@ pub fn dispatch_incoming(
       pack_id,
       sticker_id,
       reply_to,
+      thread_root,
       timestamp_nanos,
       room_id: _,
     }) => {
@@ -224,7 +231,7 @@ pub fn dispatch_incoming(
         return;
       }
       let reply = resolve_reply_snippet(mgr, &conv, reply_to);
-      let ui = build_inbound(
+      let mut ui = build_inbound(
         message_id,
         peer.clone(),
         peer_name,
@@ -236,6 +243,7 @@ pub fn dispatch_incoming(
         reply,
         false,
       );
+      ui.thread_root = thread_root;
       mgr.push_incoming(conv, ui);
       ack(mgr, peer, message_id, AckStatus::Received);
     }
@@ -246,6 +254,7 @@ pub fn dispatch_incoming(
       duration_ms,
       waveform,
       reply_to,
+      thread_root,
       timestamp_nanos,
       room_id: _,
     }) => {
@@ -256,7 +265,7 @@ pub fn dispatch_incoming(
       }
       let object_url = bytes_to_data_url("audio/webm", &audio_data);
       let reply = resolve_reply_snippet(mgr, &conv, reply_to);
-      let ui = build_inbound(
+      let mut ui = build_inbound(
         message_id,
         peer.clone(),
         peer_name,
@@ -269,6 +278,7 @@ pub fn dispatch_incoming(
         reply,
         false,
       );
+      ui.thread_root = thread_root;
       mgr.push_incoming(conv, ui);
       ack(mgr, peer, message_id, AckStatus::Received);
     }
@@ -280,6 +290,7 @@ pub fn dispatch_incoming(
       width,
       height,
       reply_to,
+      thread_root,
       timestamp_nanos,
       room_id: _,
     }) => {
@@ -295,7 +306,7 @@ pub fn dispatch_incoming(
         bytes_to_data_url("image/jpeg", &thumbnail)
       };
       let reply = resolve_reply_snippet(mgr, &conv, reply_to);
-      let ui = build_inbound(
+      let mut ui = build_inbound(
         message_id,
         peer.clone(),
         peer_name,
@@ -309,6 +320,7 @@ pub fn dispatch_incoming(
         reply,
         false,
       );
+      ui.thread_root = thread_root;
       mgr.push_incoming(conv, ui);
       ack(mgr, peer, message_id, AckStatus::Received);
     }
@@ -342,18 +354,53 @@ pub fn dispatch_incoming(
       ack(mgr, peer, message_id, AckStatus::Received);
     }
 
+    DataChannelMessage::ChatPoll(poll) => {
+      let message_id = poll.message_id;
+      // Deduplication check (Req 11.3.4).
+      if mgr.is_message_known(&message_id) {
+        ack(mgr, peer, message_id, AckStatus::Received);
+        return;
+      }
+      // A malformed poll is acknowledged so the sender stops retrying,
+      // but never shown.
+      if poll.is_valid() {
+        let reply = resolve_reply_snippet(mgr, &conv, poll.reply_to);
+        let mut ui = build_inbound(
+          message_id,
+          peer.clone(),
+          peer_name,
+          MessageContent::Poll(PollState::from_wire(&poll)),
+          poll.timestamp_nanos,
+          reply,
+          false,
+        );
+        ui.thread_root = poll.thread_root;
+        mgr.push_incoming(conv, ui);
+      }
+      ack(mgr, peer, message_id, AckStatus::Received);
+    }
+
     DataChannelMessage::MessageAck(ack_msg) => {
       mgr.apply_ack(peer, &ack_msg);
     }
     DataChannelMessage::MessageRevoke(revoke) => {
       mgr.apply_revoke(peer, &revoke);
     }
+    DataChannelMessage::MessageEdit(edit) => {
+      mgr.apply_edit(peer, &edit);
+    }
     DataChannelMessage::MessageRead(read) => {
       mgr.apply_read_receipts(peer, &read);
     }
     DataChannelMessage::MessageReaction(reaction) => {
       mgr.apply_reaction(peer, &reaction);
     }
+    DataChannelMessage::PollVote(vote) => {
+      mgr.apply_poll_vote(&conv, peer, &vote);
+    }
+    DataChannelMessage::PollClose(close) => {
+      mgr.apply_poll_close(&conv, peer, &close);
+    }
     DataChannelMessage::TypingIndicator(typing) => {
       mgr.apply_typing(conv, peer, peer_name, typing.is_typing);
     }
@@ -369,14 +416,28 @@ pub fn dispatch_incoming(
     | DataChannelMessage::PlaybackProgress(_)
     | DataChannelMessage::SubtitleData(_)
     | DataChannelMessage::SubtitleClear(_)
+    | DataChannelMessage::SubtitleOffset(_)
     | DataChannelMessage::DanmakuBatch(_)
     | DataChannelMessage::TheaterChatText(_)
+    | DataChannelMessage::TheaterQueueAdd(_)
+    | DataChannelMessage::TheaterQueueRemove(_)
+    | DataChannelMessage::TheaterQueueReorder(_)
+    | DataChannelMessage::TheaterNowPlaying(_)
+    | DataChannelMessage::TheaterClockPing(_)
+    | DataChannelMessage::TheaterClockPong(_)
+    // Group / SFU key material is consumed by `WebRtcManager` before
+    // the chat router runs.
+    | DataChannelMessage::SenderKey(_)
+    | DataChannelMessage::MediaKey(_)
+    // Channel announcements are transport plumbing.
+    | DataChannelMessage::ChannelSetup(_)
     // Call-status broadcasts (Req 3.5 / 7.1 / 10.5.24) are routed to
     // the call subsystem in `WebRtcManager::dispatch_data_channel_message`
     // and never reach this chat router. Listed explicitly here so the
     // exhaustiveness check enforces the contract.
     | DataChannelMessage::MediaStateUpdate(_)
     | DataChannelMessage::ReconnectingState(_)
+    | DataChannelMessage::CallRecordingState(_)
     | DataChannelMessage::FileResumeRequest(_) => {}
   }
 }
@@ -22,10 +22,12 @@ fn stub_message(content: MessageContent) -> ChatMessage {
     outgoing: true,
     status: MessageStatus::Sent,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -203,6 +205,7 @@ mod wasm {
       message_id: id,
       content: "hi".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 1_700_000_000_000_000_000,
       room_id: None,
       mentions: vec![],
@@ -251,6 +254,7 @@ mod wasm {
       message_id: id,
       content: "are you there".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 1_700_000_000_000_000_000,
       room_id: None,
       mentions: vec![],
@@ -289,6 +293,7 @@ mod wasm {
       message_id: id,
       content: "hi".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -364,6 +369,7 @@ mod wasm {
         message_id: id,
         content: "hi".to_string(),
         reply_to: None,
+        thread_root: None,
         timestamp_nanos: 0,
         room_id: None,
         mentions: vec![],
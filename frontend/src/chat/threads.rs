@@ -0,0 +1,121 @@
+//! Thread projections over a conversation's message list.
+//!
+//! A thread is the set of messages whose `thread_root` points at the
+//! same main-timeline message. Threads are not stored separately: the
+//! conversation's flat `Vec<ChatMessage>` stays the single source of
+//! truth and everything here is derived from it, so edits, revokes and
+//! reactions on replies need no extra bookkeeping.
+
+use std::collections::{HashMap, HashSet};
+
+use message::{MessageId, UserId};
+
+use super::models::{ChatMessage, MessageContent};
+
+/// Number of distinct participants kept in [`ThreadSummary::participants`].
+pub const MAX_SUMMARY_PARTICIPANTS: usize = 3;
+
+/// Aggregate shown under a thread root in the main timeline.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ThreadSummary {
+  /// Number of non-revoked replies.
+  pub reply_count: usize,
+  /// Timestamp (Unix ms) of the newest reply.
+  pub last_reply_ms: i64,
+  /// First few distinct repliers, in order of their first reply.
+  pub participants: Vec<UserId>,
+}
+
+/// Build a summary for every thread root that has at least one reply.
+///
+/// Revoked replies are left out so a thread whose only reply was
+/// revoked no longer advertises itself.
+#[must_use]
+pub fn summarize(list: &[ChatMessage]) -> HashMap<MessageId, ThreadSummary> {
+  let mut out: HashMap<MessageId, ThreadSummary> = HashMap::new();
+  for msg in list {
+    let Some(root) = msg.thread_root else {
+      continue;
+    };
+    if matches!(msg.content, MessageContent::Revoked) {
+      continue;
+    }
+    let entry = out.entry(root).or_default();
+    entry.reply_count += 1;
+    entry.last_reply_ms = entry.last_reply_ms.max(msg.timestamp_ms);
+    if entry.participants.len() < MAX_SUMMARY_PARTICIPANTS
+      && !entry.participants.contains(&msg.sender)
+    {
+      entry.participants.push(msg.sender.clone());
+    }
+  }
+  out
+}
+
+/// Replies in the thread rooted at `root`, oldest first.
+#[must_use]
+pub fn replies(list: &[ChatMessage], root: MessageId) -> Vec<ChatMessage> {
+  let mut out: Vec<ChatMessage> = list
+    .iter()
+    .filter(|m| m.thread_root == Some(root))
+    .cloned()
+    .collect();
+  out.sort_by_key(|m| m.timestamp_ms);
+  out
+}
+
+/// Whether `msg` belongs on the main timeline. Thread replies only
+/// appear there when the user opted to see them inline.
+#[must_use]
+pub const fn in_main_timeline(msg: &ChatMessage, show_replies: bool) -> bool {
+  show_replies || msg.thread_root.is_none()
+}
+
+/// Timestamp to page history back from: the oldest main-timeline
+/// message. Replies fetched by a thread load can predate the loaded
+/// window, so the oldest message overall is not a safe cursor.
+#[must_use]
+pub fn history_cursor(list: &[ChatMessage]) -> Option<i64> {
+  list
+    .iter()
+    .find(|m| m.thread_root.is_none())
+    .or_else(|| list.first())
+    .map(|m| m.timestamp_ms)
+}
+
+/// Main-timeline projection of `list`.
+#[must_use]
+pub fn main_timeline(list: &[ChatMessage], show_replies: bool) -> Vec<ChatMessage> {
+  list
+    .iter()
+    .filter(|m| in_main_timeline(m, show_replies))
+    .cloned()
+    .collect()
+}
+
+/// Merge messages loaded from storage into the in-memory list, skipping
+/// ids already present and keeping the list in timestamp order.
+///
+/// Used for thread loads, whose replies can be older than the loaded
+/// history window, and for history pages, which may then overlap with
+/// replies already in the list.
+///
+/// Returns the ids that were actually added so the caller can index
+/// them.
+pub fn merge_loaded(list: &mut Vec<ChatMessage>, loaded: Vec<ChatMessage>) -> Vec<MessageId> {
+  let mut seen: HashSet<MessageId> = list.iter().map(|m| m.id).collect();
+  let mut added = Vec::new();
+  for msg in loaded {
+    if seen.insert(msg.id) {
+      added.push(msg.id);
+      list.push(msg);
+    }
+  }
+  if !added.is_empty() {
+    list.sort_by_key(|m| m.timestamp_ms);
+  }
+  added
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,124 @@
+use super::*;
+use crate::chat::models::MessageStatus;
+use std::collections::BTreeMap;
+
+fn make_msg(sender: u64, ts: i64, thread_root: Option<MessageId>) -> ChatMessage {
+  ChatMessage {
+    id: MessageId::new(),
+    sender: UserId::from(sender),
+    sender_name: format!("user{sender}"),
+    content: MessageContent::Text("hi".to_string()),
+    timestamp_ms: ts,
+    outgoing: false,
+    status: MessageStatus::Received,
+    reply_to: None,
+    thread_root,
+    read_by: Vec::new(),
+    reactions: BTreeMap::new(),
+    mentions_me: false,
+    counted_unread: false,
+    edit: None,
+  }
+}
+
+#[test]
+fn summarize_counts_replies_per_root() {
+  let root_a = make_msg(1, 0, None);
+  let root_b = make_msg(2, 10, None);
+  let list = vec![
+    root_a.clone(),
+    root_b.clone(),
+    make_msg(2, 20, Some(root_a.id)),
+    make_msg(3, 30, Some(root_a.id)),
+    make_msg(1, 40, Some(root_b.id)),
+  ];
+  let summaries = summarize(&list);
+  assert_eq!(summaries.len(), 2);
+  let a = &summaries[&root_a.id];
+  assert_eq!(a.reply_count, 2);
+  assert_eq!(a.last_reply_ms, 30);
+  assert_eq!(a.participants, vec![UserId::from(2u64), UserId::from(3u64)]);
+  assert_eq!(summaries[&root_b.id].reply_count, 1);
+}
+
+#[test]
+fn summarize_skips_revoked_replies() {
+  let root = make_msg(1, 0, None);
+  let mut reply = make_msg(2, 10, Some(root.id));
+  reply.mark_revoked();
+  let summaries = summarize(&[root, reply]);
+  assert!(summaries.is_empty());
+}
+
+#[test]
+fn summarize_caps_and_dedups_participants() {
+  let root = make_msg(1, 0, None);
+  let mut list = vec![root.clone()];
+  for (i, sender) in [2u64, 2, 3, 4, 5].into_iter().enumerate() {
+    list.push(make_msg(sender, i as i64 + 1, Some(root.id)));
+  }
+  let summary = &summarize(&list)[&root.id];
+  assert_eq!(summary.reply_count, 5);
+  assert_eq!(summary.participants.len(), MAX_SUMMARY_PARTICIPANTS);
+  assert_eq!(
+    summary.participants,
+    vec![UserId::from(2u64), UserId::from(3u64), UserId::from(4u64)]
+  );
+}
+
+#[test]
+fn replies_are_sorted_oldest_first() {
+  let root = make_msg(1, 0, None);
+  let late = make_msg(2, 50, Some(root.id));
+  let early = make_msg(3, 20, Some(root.id));
+  let other = make_msg(3, 30, Some(MessageId::new()));
+  let list = vec![root.clone(), late.clone(), other, early.clone()];
+  let ids: Vec<MessageId> = replies(&list, root.id).iter().map(|m| m.id).collect();
+  assert_eq!(ids, vec![early.id, late.id]);
+}
+
+#[test]
+fn main_timeline_hides_replies_unless_requested() {
+  let root = make_msg(1, 0, None);
+  let reply = make_msg(2, 10, Some(root.id));
+  let plain = make_msg(3, 20, None);
+  let list = vec![root.clone(), reply.clone(), plain.clone()];
+
+  let hidden: Vec<MessageId> = main_timeline(&list, false).iter().map(|m| m.id).collect();
+  assert_eq!(hidden, vec![root.id, plain.id]);
+
+  let shown: Vec<MessageId> = main_timeline(&list, true).iter().map(|m| m.id).collect();
+  assert_eq!(shown, vec![root.id, reply.id, plain.id]);
+}
+
+#[test]
+fn history_cursor_skips_older_thread_replies() {
+  let root = make_msg(1, 100, None);
+  let old_reply = make_msg(2, 5, Some(root.id));
+  let list = vec![old_reply.clone(), root.clone(), make_msg(3, 110, None)];
+  assert_eq!(history_cursor(&list), Some(100));
+  assert_eq!(history_cursor(&[old_reply]), Some(5));
+  assert_eq!(history_cursor(&[]), None);
+}
+
+#[test]
+fn merge_loaded_dedups_and_sorts() {
+  let root = make_msg(1, 0, None);
+  let live = make_msg(2, 30, Some(root.id));
+  let mut list = vec![root.clone(), live.clone()];
+
+  let older = make_msg(3, 10, Some(root.id));
+  let added = merge_loaded(&mut list, vec![older.clone(), live.clone()]);
+
+  assert_eq!(added, vec![older.id]);
+  let ids: Vec<MessageId> = list.iter().map(|m| m.id).collect();
+  assert_eq!(ids, vec![root.id, older.id, live.id]);
+}
+
+#[test]
+fn merge_loaded_with_nothing_new_is_a_no_op() {
+  let root = make_msg(1, 0, None);
+  let mut list = vec![root.clone()];
+  assert!(merge_loaded(&mut list, vec![root]).is_empty());
+  assert_eq!(list.len(), 1);
+}
@@ -1,7 +1,7 @@
 //! Bottom control bar for an active call.
 //!
-//! Renders the mute, camera, screen-share, Picture-in-Picture, and
-//! end-call buttons. All handlers delegate to [`crate::call::CallManager`].
+//! Renders the mute, camera, screen-share, Picture-in-Picture, record,
+//! and end-call buttons. All handlers delegate to [`crate::call::CallManager`].
 
 use leptos::prelude::*;
 use leptos::task::spawn_local;
@@ -50,6 +50,7 @@ pub fn CallControls() -> impl IntoView {
   let cam_enabled = Memo::new(move |_| signals.local_media.get().camera_enabled);
   let screen_on = Memo::new(move |_| signals.local_media.get().screen_sharing);
   let pip_on = Memo::new(move |_| signals.pip_active.get());
+  let recording = Memo::new(move |_| signals.recording.get());
   let duration = Memo::new(move |_| format_duration(signals.duration_secs.get()));
 
   let on_mute = {
@@ -118,6 +119,18 @@ pub fn CallControls() -> impl IntoView {
     }
   };
 
+  let on_record = {
+    let manager = manager.clone();
+    move |_| {
+      let manager = manager.clone();
+      spawn_local(async move {
+        if let Err(e) = manager.toggle_recording().await {
+          web_sys::console::warn_1(&format!("[call] recording failed: {e}").into());
+        }
+      });
+    }
+  };
+
   let on_end = {
     let manager = manager.clone();
     move |_| {
@@ -184,6 +197,23 @@ pub fn CallControls() -> impl IntoView {
       >
         <Icon icon=i::LuPictureInPicture2 />
       </button>
+      <button
+        type="button"
+        class="call-controls__btn call-controls__btn--record"
+        class:is-on=move || recording.get()
+        on:click=on_record
+        aria-pressed=move || aria_pressed(recording.get())
+        aria-label=move || {
+          if recording.get() {
+            t_string!(i18n, call.record_stop)
+          } else {
+            t_string!(i18n, call.record_start)
+          }
+        }
+        data-testid="call-record-btn"
+      >
+        <Icon icon=i::LuCircleDot />
+      </button>
       <button
         type="button"
         class="call-controls__btn call-controls__btn--danger"
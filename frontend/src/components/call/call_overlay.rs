@@ -7,10 +7,14 @@
 //!   was found in localStorage.
 //! * `IncomingCallModal` — shown whenever `CallState::Ringing`.
 //! * `CallView` — shown for `CallState::Inviting` and `CallState::Active`.
+//! * `RecordingReadyPrompt` — shown when a local recording has finished
+//!   and is waiting to be saved, sent or discarded.
 
 use leptos::prelude::*;
 
-use crate::components::call::{CallRecoveryPrompt, CallView, IncomingCallModal};
+use crate::components::call::{
+  CallRecoveryPrompt, CallView, IncomingCallModal, RecordingReadyPrompt,
+};
 
 /// Root-level call overlay component.
 #[component]
@@ -19,5 +23,6 @@ pub fn CallOverlay() -> impl IntoView {
     <CallRecoveryPrompt />
     <IncomingCallModal />
     <CallView />
+    <RecordingReadyPrompt />
   }
 }
@@ -4,6 +4,9 @@
 //! caller sees their own preview while waiting for the callee to pick
 //! up). The `Idle` / `Ringing` / `Ended` states are handled by sibling
 //! components under [`crate::components::call`].
+//!
+//! While anyone in the call — including the local user — is recording,
+//! a banner in the header says so.
 
 use leptos::prelude::*;
 use leptos_i18n::t_string;
@@ -11,12 +14,35 @@ use leptos_i18n::t_string;
 use crate::call::{CallState, use_call_signals};
 use crate::components::call::{CallControls, VideoGrid};
 use crate::i18n;
+use crate::state::use_app_state;
+
+/// Text of the "being recorded" banner, or `None` when nobody records.
+///
+/// `self_text` is used when the local user records; otherwise the
+/// remote recorders' names are joined into `others_template`'s
+/// `{names}` placeholder.
+#[must_use]
+pub fn recording_banner_text(
+  self_recording: bool,
+  remote_recorders: &[String],
+  self_text: &str,
+  others_template: &str,
+) -> Option<String> {
+  if self_recording {
+    Some(self_text.to_string())
+  } else if remote_recorders.is_empty() {
+    None
+  } else {
+    Some(others_template.replace("{names}", &remote_recorders.join(", ")))
+  }
+}
 
 /// Active-call view component.
 #[component]
 pub fn CallView() -> impl IntoView {
   let signals = use_call_signals();
   let i18n = i18n::use_i18n();
+  let app_state = use_app_state();
 
   let should_render = Memo::new(move |_| {
     matches!(
@@ -31,6 +57,34 @@ pub fn CallView() -> impl IntoView {
     _ => "",
   });
 
+  let recording_banner = Memo::new(move |_| {
+    let mut recorders: Vec<_> = signals.participants.with(|map| {
+      map
+        .values()
+        .filter(|p| p.recording)
+        .map(|p| p.user_id.clone())
+        .collect()
+    });
+    recorders.sort_by_key(ToString::to_string);
+    let names: Vec<String> = app_state.online_users.with(|users| {
+      recorders
+        .iter()
+        .map(|id| {
+          users
+            .iter()
+            .find(|u| &u.user_id == id)
+            .map_or_else(|| id.to_string(), |u| u.nickname.clone())
+        })
+        .collect()
+    });
+    recording_banner_text(
+      signals.recording.get(),
+      &names,
+      &t_string!(i18n, call.recording_banner_self).to_string(),
+      &t_string!(i18n, call.recording_banner_others).to_string(),
+    )
+  });
+
   view! {
     <Show when=move || should_render.get()>
       <section
@@ -46,6 +100,17 @@ pub fn CallView() -> impl IntoView {
       >
         <header class="call-view__header">
           <span class="call-view__status">{move || status_label.get()}</span>
+          {move || {
+            recording_banner
+              .get()
+              .map(|text| {
+                view! {
+                  <span class="call-view__recording" role="status" data-testid="call-recording-banner">
+                    {text}
+                  </span>
+                }
+              })
+          }}
         </header>
         <VideoGrid />
         <CallControls />
@@ -53,3 +118,31 @@ pub fn CallView() -> impl IntoView {
     </Show>
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn banner_hidden_when_nobody_records() {
+    assert_eq!(recording_banner_text(false, &[], "me", "{names} rec"), None);
+  }
+
+  #[test]
+  fn banner_prefers_self_text() {
+    let names = vec!["alice".to_string()];
+    assert_eq!(
+      recording_banner_text(true, &names, "me", "{names} rec").as_deref(),
+      Some("me")
+    );
+  }
+
+  #[test]
+  fn banner_joins_remote_names() {
+    let names = vec!["alice".to_string(), "bob".to_string()];
+    assert_eq!(
+      recording_banner_text(false, &names, "me", "{names} rec").as_deref(),
+      Some("alice, bob rec")
+    );
+  }
+}
@@ -0,0 +1,153 @@
+//! "Recording ready" prompt.
+//!
+//! Shown once a local call recording has been finalised (either by the
+//! record button or because the call ended mid-recording). The user can
+//! save the file, send it into the call's room through the regular
+//! file-transfer pipeline, or discard it.
+
+use leptos::prelude::*;
+use leptos_i18n::t_string;
+
+use crate::call::{CallManager, CallRecording, use_call_manager, use_call_signals};
+use crate::components::chat_view::file_picker::begin_transfer;
+use crate::components::room::modal_wrapper::{ModalSize, ModalWrapper};
+use crate::file_transfer::format_bytes;
+use crate::i18n;
+use crate::state::ConversationId;
+use crate::utils::format_duration;
+
+/// One-line summary of a finished recording: `"<size> · <duration>"`.
+#[must_use]
+pub fn recording_summary(recording: &CallRecording) -> String {
+  format!(
+    "{} · {}",
+    format_bytes(recording.size),
+    format_duration(recording.duration_secs)
+  )
+}
+
+/// Hand the finished recording to the file-transfer pipeline of the
+/// room the call took place in, then dismiss it.
+fn send_to_room(manager: &CallManager) {
+  let Some(room_id) = manager
+    .signals()
+    .last_recording
+    .with_untracked(|r| r.as_ref().map(|r| r.room_id.clone()))
+  else {
+    return;
+  };
+  let Some(file) = manager.recording_file() else {
+    return;
+  };
+  if let Err(e) = begin_transfer(ConversationId::Room(room_id), file) {
+    web_sys::console::warn_1(&format!("[call] sending recording failed: {e:?}").into());
+    return;
+  }
+  manager.discard_recording();
+}
+
+/// Recording-ready prompt component.
+#[component]
+pub fn RecordingReadyPrompt() -> impl IntoView {
+  let signals = use_call_signals();
+  let manager = use_call_manager();
+  let i18n = i18n::use_i18n();
+
+  let is_open = Signal::derive(move || signals.last_recording.with(Option::is_some));
+  let filename = move || {
+    signals
+      .last_recording
+      .with(|r| r.as_ref().map(|r| r.filename.clone()).unwrap_or_default())
+  };
+  let summary = move || {
+    signals
+      .last_recording
+      .with(|r| r.as_ref().map(recording_summary).unwrap_or_default())
+  };
+
+  // The modal body is rebuilt every time it opens, so each click
+  // handler clones `manager` independently.
+  //
+  // No-op on_close: dismissal is driven by the explicit button clicks
+  // so a recording is never thrown away by a stray Escape.
+  let on_close = Callback::new(|()| {});
+
+  view! {
+    <ModalWrapper
+      on_close=on_close
+      open=is_open
+      size=ModalSize::Small
+      class="call-modal recording-prompt"
+      labelled_by="call-recording-title"
+      testid="call-recording-prompt"
+      dismiss_on_backdrop_click=false
+      dismiss_on_escape=false
+    >
+      <header class="call-modal__header">
+        <h2 id="call-recording-title" class="call-modal__title">
+          {move || t_string!(i18n, call.recording_ready)}
+        </h2>
+      </header>
+      <div class="call-modal__body">
+        <p class="recording-prompt__filename">{filename}</p>
+        <p class="recording-prompt__summary">{summary}</p>
+      </div>
+      <footer class="call-modal__actions">
+        <button
+          type="button"
+          class="btn btn--danger"
+          on:click={
+            let manager = manager.clone();
+            move |_| manager.discard_recording()
+          }
+        >
+          {move || t_string!(i18n, call.recording_discard)}
+        </button>
+        <button
+          type="button"
+          class="btn"
+          on:click={
+            let manager = manager.clone();
+            move |_| send_to_room(&manager)
+          }
+        >
+          {move || t_string!(i18n, call.recording_send)}
+        </button>
+        <button
+          type="button"
+          class="btn btn--primary"
+          on:click={
+            let manager = manager.clone();
+            move |_| manager.save_recording()
+          }
+        >
+          {move || t_string!(i18n, call.recording_save)}
+        </button>
+      </footer>
+    </ModalWrapper>
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use message::RoomId;
+
+  #[test]
+  fn summary_joins_size_and_duration() {
+    let recording = CallRecording {
+      room_id: RoomId::from_uuid(uuid::Uuid::new_v5(
+        &uuid::Uuid::NAMESPACE_DNS,
+        b"recording-test",
+      )),
+      filename: "call-recording.webm".to_string(),
+      mime_type: "video/webm".to_string(),
+      size: 2048,
+      duration_secs: 65,
+      object_url: "blob:test".to_string(),
+    };
+    let summary = recording_summary(&recording);
+    assert!(summary.contains(&format_bytes(2048)));
+    assert!(summary.ends_with(&format_duration(65)));
+  }
+}
@@ -86,6 +86,7 @@ pub fn VideoGrid() -> impl IntoView {
               mic_enabled=p.mic_enabled
               camera_enabled=p.camera_enabled
               reconnecting=p.reconnecting
+              recording=p.recording
             />
           }
         }
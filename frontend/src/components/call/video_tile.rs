@@ -10,16 +10,19 @@
 //! Remote-peer tiles additionally render three status icons driven by
 //! the peer's `MediaStateUpdate` / `ReconnectingState` broadcasts
 //! (Req 3.5 / 7.1 / 10.5.24): muted microphone, camera disabled,
-//! reconnecting.
+//! reconnecting. A "REC" badge marks peers that are recording the
+//! call (driven by their `CallRecordingState` broadcast).
 
 use icondata as i;
 use leptos::html;
 use leptos::prelude::*;
+use leptos_i18n::t_string;
 use leptos_icons::Icon;
 use message::UserId;
 use web_sys::{HtmlVideoElement, MediaStream};
 
 use crate::components::call::NetworkIndicator;
+use crate::i18n;
 use crate::identicon::generate_identicon_data_uri;
 
 /// Compute the BEM class list for a video tile given its flags.
@@ -107,7 +110,12 @@ pub fn VideoTile(
   /// false. Only meaningful for remote tiles.
   #[prop(default = false)]
   reconnecting: bool,
+  /// Whether the participant is recording the call. Defaults to false.
+  /// Only meaningful for remote tiles.
+  #[prop(default = false)]
+  recording: bool,
 ) -> impl IntoView {
+  let i18n = i18n::use_i18n();
   let video_ref: NodeRef<html::Video> = NodeRef::new();
   let stream_for_effect = stream.clone();
   let stream_present = stream.is_some();
@@ -194,6 +202,12 @@ pub fn VideoTile(
           <Icon icon=i::LuRefreshCw />
         </div>
       </Show>
+      <Show when=move || show_remote_media_icons && recording>
+        <span class="video-tile__rec-badge" role="status">
+          <Icon icon=i::LuCircleDot />
+          {move || t_string!(i18n, call.recording_badge)}
+        </span>
+      </Show>
       <footer class="video-tile__meta">
         <span class="video-tile__name">{display_name}</span>
         <Show when=move || show_remote_media_icons && !mic_enabled>
@@ -397,10 +397,12 @@ fn push_placeholder_message(placeholder: FilePlaceholder<'_>) {
     outgoing: true,
     status: MessageStatus::Sent,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   };
   chat.push_outgoing(conv, ui_msg);
 }
@@ -18,6 +18,18 @@ pub fn format_time_short(ts_ms: i64) -> String {
   }
 }
 
+/// Format a Unix-millisecond timestamp as `MM-DD HH:MM` in the user's
+/// local timezone, for instants that may not fall on today. Falls back
+/// to `--:--` like [`format_time_short`].
+#[must_use]
+pub fn format_date_time_short(ts_ms: i64) -> String {
+  use chrono::{Local, TimeZone};
+  match Local.timestamp_millis_opt(ts_ms).single() {
+    Some(dt) => dt.format("%m-%d %H:%M").to_string(),
+    None => "--:--".to_string(),
+  }
+}
+
 /// Render Markdown text to HTML and post-process any `@mentions` so
 /// that the local user's nickname is wrapped in a `span.mention-highlight`.
 ///
@@ -4,7 +4,9 @@
 //! button and performs a best-effort pipeline:
 //!
 //! 1. User selects a file via the native picker.
-//! 2. A `FileReader` reads the image into an ArrayBuffer.
+//! 2. A `FileReader` reads the image into an ArrayBuffer, and EXIF /
+//!    XMP / text metadata is stripped from the bytes unless the user
+//!    opted out in privacy settings.
 //! 3. `Url.createObjectURL` produces an object URL for the full image
 //!    and (for the MVP) the same URL is reused as the thumbnail URL.
 //! 4. The resulting `ImagePayload` is handed to `ChatManager::send_image`.
@@ -110,7 +112,10 @@ fn send_file(
     let Ok(buffer) = reader_clone.result() else {
       return;
     };
-    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+    let bytes = crate::file_transfer::send::outgoing_bytes(
+      js_sys::Uint8Array::new(&buffer).to_vec(),
+      crate::settings::load_snapshot().strip_metadata,
+    );
 
     // Use HtmlImageElement to obtain actual width/height before sending.
     let Ok(img) = web_sys::HtmlImageElement::new() else {
@@ -10,7 +10,7 @@
 //!   one outbound `TypingIndicator` per 3 s.
 //! * Reply preview bar above the textarea when the parent has set a
 //!   reply target.
-//! * Attach buttons for image / voice / sticker overlays. The actual
+//! * Attach buttons for image / voice / sticker / poll overlays. The actual
 //!   overlays live in sibling components; the input bar only toggles
 //!   their `RwSignal<bool>` visibility signals.
 
@@ -41,6 +41,8 @@ pub struct InputOverlays {
   pub image: RwSignal<bool>,
   /// File picker overlay visible (Task 19).
   pub file: RwSignal<bool>,
+  /// Poll composer visible.
+  pub poll: RwSignal<bool>,
 }
 
 /// Chat input bar component.
@@ -297,6 +299,17 @@ pub fn InputBar(
           <Icon icon=i::LuPaperclip />
         </button>
 
+        <button
+          type="button"
+          class="chat-input-btn"
+          data-testid="chat-input-poll"
+          aria-label=move || t_string!(i18n, chat.poll_create)
+          title=move || t_string!(i18n, chat.poll_create)
+          on:click=move |_| overlays.poll.update(|v| *v = !*v)
+        >
+          <Icon icon=i::LuClipboardList />
+        </button>
+
         <button
           type="button"
           class="chat-input-btn"
@@ -1,9 +1,10 @@
 //! A single chat message bubble.
 //!
 //! Renders the message content (text / sticker / voice / image /
-//! forwarded / revoked) along with the status indicator, reaction
-//! chips, reply-to quote, hover-action toolbar (reply, reaction,
-//! forward, revoke, copy, resend).
+//! file / poll / forwarded / revoked) along with the status indicator, reaction
+//! chips, reply-to quote, "edited" marker with revision history, thread
+//! footer ("N replies"), and hover-action toolbar (reply, reply in
+//! thread, reaction, forward, edit, revoke, copy, resend).
 //!
 //! The component is intentionally dumb: all mutations are delegated to
 //! the `ChatManager` provided via Leptos context. The parent passes in
@@ -12,12 +13,13 @@
 
 use crate::chat::use_chat_manager;
 use crate::chat::{
-  ChatMessage, MessageContent, MessageStatus, ReplySnippet, StickerRef, VoiceClip,
+  ChatMessage, EditHistory, MessageContent, MessageStatus, ReplySnippet, StickerRef, VoiceClip,
 };
 use crate::components::chat_view::helpers::{
   format_duration_ms, format_time_short, render_text_with_mentions,
 };
 use crate::components::chat_view::reaction_picker::ReactionPicker;
+use crate::components::chat_view::thread_panel::ThreadSummaries;
 use crate::components::chat_view::virtual_scroll::VirtualScrollState;
 use crate::i18n;
 use crate::state::use_app_state;
@@ -39,6 +41,8 @@ pub struct BubbleCallbacks {
   pub start_reply: Callback<ReplySnippet>,
   /// Scroll the message list to the target message id (reply jump).
   pub scroll_to: Callback<message::MessageId>,
+  /// Open the thread panel for the given thread root.
+  pub open_thread: Callback<message::MessageId>,
 }
 
 /// A single message bubble row.
@@ -47,6 +51,10 @@ pub fn MessageBubble(
   msg: ChatMessage,
   cbs: BubbleCallbacks,
   #[prop(optional)] vs: Option<VirtualScrollState>,
+  /// `true` when rendered inside the thread panel, which hides the
+  /// thread footer and the "reply in thread" action.
+  #[prop(optional)]
+  in_thread: bool,
 ) -> impl IntoView {
   let manager = use_chat_manager();
   let app_state = use_app_state();
@@ -94,6 +102,7 @@ pub fn MessageBubble(
     })
   });
   let reply_snippet = msg.reply_to.clone();
+  let thread_root = msg.thread_root;
   let reactions = msg.reactions.clone();
   let me_user_id = app_state.current_user_id();
 
@@ -118,6 +127,7 @@ pub fn MessageBubble(
     });
   }
   let is_revoked = matches!(msg.content, MessageContent::Revoked);
+  let can_start_thread = !in_thread && thread_root.is_none() && !is_revoked;
   let can_revoke = Memo::new(move |_| {
     if !msg_outgoing || is_revoked {
       return false;
@@ -128,12 +138,35 @@ pub fn MessageBubble(
   // Toggles the reaction picker for this bubble only.
   let picker_open = RwSignal::new(false);
 
+  // Inline editor state. `draft` is seeded from the current text each
+  // time the editor opens so a cancelled edit leaves no residue.
+  let can_edit = msg.can_edit();
+  let editing = RwSignal::new(false);
+  let draft = RwSignal::new(String::new());
+  let current_text = match &msg.content {
+    MessageContent::Text(text) => text.clone(),
+    _ => String::new(),
+  };
+  let history_open = RwSignal::new(false);
+  let edit_history = msg.edit.clone();
+  let is_edited = edit_history.is_some();
+
   // Clone pieces we need inside various closures.
   let msg_for_forward = msg.clone();
   let msg_for_reply = msg.clone();
   let msg_for_copy = msg.clone();
-
-  let content_view = content_view(&msg, self_nickname, cbs);
+  let msg_for_content = msg.clone();
+
+  let submit_edit = {
+    let manager = manager.clone();
+    let conv_signal = app_state.active_conversation;
+    move || {
+      if let Some(conv) = conv_signal.get_untracked() {
+        let _ = manager.edit_message(conv, msg_id, draft.get_untracked());
+      }
+      editing.set(false);
+    }
+  };
 
   // ResizeObserver-based height refinement for virtual scrolling.
   let bubble_ref = NodeRef::<leptos::html::Div>::new();
@@ -238,6 +271,12 @@ pub fn MessageBubble(
     })
   };
 
+  let thread_view = if in_thread {
+    None
+  } else {
+    thread_footer(msg_id, thread_root, cbs)
+  };
+
   view! {
     <div class=row_class data-message-id=msg_id.to_string() data-mentions-me=mentions_me_attr data-testid="message-row" node_ref=bubble_ref>
       <Show when=move || !outgoing fallback=|| ()>
@@ -270,15 +309,37 @@ pub fn MessageBubble(
           {reply_block(reply_snippet.clone(), cbs)}
         </Show>
 
-        {content_view}
+        <Show
+          when=move || editing.get()
+          fallback=move || content_view(&msg_for_content, self_nickname, cbs)
+        >
+          {edit_form(draft, editing, submit_edit.clone())}
+        </Show>
 
         <div class="message-time" aria-label=move || format!("Sent at {}", time_label.clone())>
+          <Show when=move || is_edited fallback=|| ()>
+            <button
+              type="button"
+              class="message-edited-label"
+              data-testid="message-edited-label"
+              aria-expanded=move || if history_open.get() { "true" } else { "false" }
+              title=move || t_string!(i18n, chat.edit_history)
+              on:click=move |_| history_open.update(|v| *v = !*v)
+            >
+              {move || t!(i18n, chat.edited)}
+            </button>
+          </Show>
           {time_label.clone()}
           {status_view}
         </div>
+
+        <Show when=move || history_open.get() fallback=|| ()>
+          {history_popover(edit_history.clone(), history_open)}
+        </Show>
       </div>
 
       {reaction_chips}
+      {thread_view}
 
       // Hover actions toolbar (reply / react / forward / revoke / copy).
       <div class="message-actions" role="toolbar">
@@ -303,6 +364,19 @@ pub fn MessageBubble(
           <Icon icon=i::LuReply />
         </button>
 
+        <Show when=move || can_start_thread fallback=|| ()>
+          <button
+            type="button"
+            class="message-action-btn"
+            data-testid="message-action-reply-thread"
+            aria-label=move || t_string!(i18n, chat.reply_in_thread)
+            title=move || t_string!(i18n, chat.reply_in_thread)
+            on:click=move |_| cbs.open_thread.run(msg_id)
+          >
+            <Icon icon=i::LuMessageCircle />
+          </button>
+        </Show>
+
         <button
           type="button"
           class="message-action-btn"
@@ -328,6 +402,25 @@ pub fn MessageBubble(
           <Icon icon=i::LuForward />
         </button>
 
+        <Show when=move || can_edit fallback=|| ()>
+          <button
+            type="button"
+            class="message-action-btn"
+            data-testid="message-action-edit"
+            aria-label=move || t_string!(i18n, chat.edit)
+            title=move || t_string!(i18n, chat.edit)
+            on:click={
+              let current_text = current_text.clone();
+              move |_| {
+                draft.set(current_text.clone());
+                editing.set(true);
+              }
+            }
+          >
+            <Icon icon=i::LuPencil />
+          </button>
+        </Show>
+
         <Show when=move || outgoing && can_revoke.get() fallback=|| ()>
           <button
             type="button"
@@ -374,6 +467,77 @@ pub fn MessageBubble(
   }
 }
 
+/// Thread entry point under a bubble.
+///
+/// A thread root with replies gets a "N replies" button with the
+/// thread's unread count; a reply shown inline in the main timeline
+/// gets a link back to its thread. Anything else renders nothing.
+fn thread_footer(
+  msg_id: message::MessageId,
+  thread_root: Option<message::MessageId>,
+  cbs: BubbleCallbacks,
+) -> Option<AnyView> {
+  let i18n = i18n::use_i18n();
+  if let Some(root) = thread_root {
+    return Some(
+      view! {
+        <button
+          type="button"
+          class="message-thread-link"
+          data-testid="message-thread-link"
+          on:click=move |_| cbs.open_thread.run(root)
+        >
+          <Icon icon=i::LuMessageCircle />
+          <span>{move || t_string!(i18n, chat.thread_title)}</span>
+        </button>
+      }
+      .into_any(),
+    );
+  }
+
+  let summaries = use_context::<ThreadSummaries>()?;
+  let manager = use_chat_manager();
+  let conv = use_app_state().active_conversation;
+  let reply_count = Memo::new(move |_| {
+    summaries
+      .0
+      .with(|map| map.get(&msg_id).map_or(0, |s| s.reply_count))
+  });
+  let unread = Memo::new(move |_| {
+    conv
+      .get()
+      .map_or(0, |conv| manager.thread_unread(&conv, &msg_id))
+  });
+  Some(
+    view! {
+      <Show when=move || { reply_count.get() > 0 } fallback=|| ()>
+        <button
+          type="button"
+          class="message-thread-summary"
+          data-testid="message-thread-summary"
+          data-reply-count=move || reply_count.get().to_string()
+          on:click=move |_| cbs.open_thread.run(msg_id)
+        >
+          <Icon icon=i::LuMessageCircle />
+          <span>
+            {move || format!("{} {}", reply_count.get(), t_string!(i18n, chat.thread_replies))}
+          </span>
+          <Show when=move || { unread.get() > 0 } fallback=|| ()>
+            <span
+              class="message-thread-unread"
+              data-testid="message-thread-unread"
+              aria-label=move || format!("{} {}", unread.get(), t_string!(i18n, chat.thread_unread))
+            >
+              {move || unread.get()}
+            </span>
+          </Show>
+        </button>
+      </Show>
+    }
+    .into_any(),
+  )
+}
+
 /// Render a status icon for the message status indicator.
 fn status_icon(status: MessageStatus) -> AnyView {
   match status {
@@ -457,6 +621,13 @@ fn content_view(msg: &ChatMessage, self_nickname: Memo<String>, cbs: BubbleCallb
       }
       .into_any()
     }
+    MessageContent::Poll(poll) => {
+      use crate::components::chat_view::poll_bubble::PollBubble;
+      let poll = poll.clone();
+      let message_id = msg.id;
+      let is_creator = msg.outgoing;
+      view! { <PollBubble message_id=message_id poll=poll is_creator=is_creator /> }.into_any()
+    }
     MessageContent::Revoked => {
       let i18n = i18n::use_i18n();
       view! {
@@ -469,6 +640,106 @@ fn content_view(msg: &ChatMessage, self_nickname: Memo<String>, cbs: BubbleCallb
   }
 }
 
+/// Inline editor replacing the text while an edit is in progress.
+/// Enter saves, Shift+Enter inserts a newline, Escape cancels.
+fn edit_form(
+  draft: RwSignal<String>,
+  editing: RwSignal<bool>,
+  submit: impl Fn() + Clone + 'static,
+) -> AnyView {
+  let i18n = i18n::use_i18n();
+  let submit_on_key = submit.clone();
+  view! {
+    <div class="message-edit-form" data-testid="message-edit-form">
+      <textarea
+        class="message-edit-input"
+        data-testid="message-edit-input"
+        aria-label=move || t_string!(i18n, chat.edit)
+        prop:value=move || draft.get()
+        on:input=move |ev| draft.set(event_target_value(&ev))
+        on:keydown=move |ev: web_sys::KeyboardEvent| match ev.key().as_str() {
+          "Enter" if !ev.shift_key() => {
+            ev.prevent_default();
+            submit_on_key();
+          }
+          "Escape" => editing.set(false),
+          _ => {}
+        }
+        autofocus=true
+      ></textarea>
+      <div class="message-edit-actions">
+        <button
+          type="button"
+          class="message-edit-cancel"
+          data-testid="message-edit-cancel"
+          on:click=move |_| editing.set(false)
+        >
+          {move || t!(i18n, chat.cancel_edit)}
+        </button>
+        <button
+          type="button"
+          class="message-edit-save"
+          data-testid="message-edit-save"
+          on:click=move |_| submit()
+        >
+          {move || t!(i18n, chat.save_edit)}
+        </button>
+      </div>
+    </div>
+  }
+  .into_any()
+}
+
+/// Popover listing the superseded revisions of an edited message,
+/// oldest first.
+fn history_popover(edit: Option<EditHistory>, open: RwSignal<bool>) -> AnyView {
+  let Some(edit) = edit else {
+    return ().into_any();
+  };
+  let i18n = i18n::use_i18n();
+  let entries = edit
+    .previous
+    .into_iter()
+    .map(|rev| {
+      let time = format_time_short(rev.timestamp_ms);
+      let text = crate::chat::markdown::to_plain_text(&rev.content);
+      view! {
+        <li class="message-history-entry" data-revision=rev.revision.to_string()>
+          <time class="message-history-time">{time}</time>
+          <span class="message-history-text">{text}</span>
+        </li>
+      }
+    })
+    .collect_view();
+  view! {
+    <div
+      class="message-history-popover"
+      role="dialog"
+      aria-label=move || t_string!(i18n, chat.edit_history)
+      data-testid="message-history-popover"
+      on:keydown=move |ev: web_sys::KeyboardEvent| {
+        if ev.key() == "Escape" {
+          open.set(false);
+        }
+      }
+    >
+      <div class="message-history-header">
+        <span>{move || t!(i18n, chat.edit_history)}</span>
+        <button
+          type="button"
+          class="message-history-close"
+          aria-label=move || t_string!(i18n, common.close)
+          on:click=move |_| open.set(false)
+        >
+          <Icon icon=i::LuX />
+        </button>
+      </div>
+      <ol class="message-history-list">{entries}</ol>
+    </div>
+  }
+  .into_any()
+}
+
 fn render_sticker(sticker: &StickerRef) -> AnyView {
   // Build the deterministic asset URL from the sticker pack/id. The
   // fallback uses the literal sticker id which in the built-in pack is
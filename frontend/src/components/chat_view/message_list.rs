@@ -13,13 +13,14 @@
 //! * Infinite scroll: loads older history from IndexedDB when the user
 //!   scrolls to the top (Task 17).
 
-use crate::chat::use_chat_manager;
+use crate::chat::{threads, use_chat_manager};
 use crate::components::chat_view::message_bubble::{BubbleCallbacks, MessageBubble};
 use crate::components::chat_view::scroll_perf::ScrollPerfController;
 use crate::components::chat_view::virtual_scroll::{
   LoadingSkeleton, VIRTUAL_THRESHOLD, VirtualMessageWindow, VirtualScrollState,
 };
 use crate::i18n;
+use crate::settings::use_settings_state;
 use crate::state::ConversationId;
 use icondata as i;
 use leptos::ev::Event;
@@ -81,10 +82,16 @@ pub fn MessageList(
 
   // Memoise the message list so we only re-render on real changes.
   let manager_for_messages = manager.clone();
-  let messages = Memo::new(move |_| match conv.get() {
+  let all_messages = Memo::new(move |_| match conv.get() {
     Some(id) => manager_for_messages.conversation_state(&id).messages.get(),
     None => Vec::new(),
   });
+  // Thread replies live in the thread panel unless the user asked to
+  // see them inline as well.
+  let settings = use_settings_state();
+  let show_replies = Memo::new(move |_| settings.get().thread_replies_in_timeline);
+  let messages =
+    Memo::new(move |_| all_messages.with(|list| threads::main_timeline(list, show_replies.get())));
 
   // Track the last-seen boundary so we can render the divider without
   // shifting when new messages arrive (Req 4.10.x).
@@ -184,38 +191,35 @@ pub fn MessageList(
       && !vs_for_scroll.loading_older.get_untracked()
       && vs_for_scroll.has_more.get_untracked()
       && let Some(conv_id) = conv.get_untracked()
+      && let Some(before_ts) = all_messages.with_untracked(|list| threads::history_cursor(list))
     {
-      let current_msgs = messages.get_untracked();
-      if let Some(oldest) = current_msgs.first() {
-        let before_ts = oldest.timestamp_ms;
-        vs_for_scroll.loading_older.set(true);
-        let vs_inner = vs_for_scroll.clone();
-        let scroll_ref_inner = scroll_ref;
+      vs_for_scroll.loading_older.set(true);
+      let vs_inner = vs_for_scroll.clone();
+      let scroll_ref_inner = scroll_ref;
 
-        // Capture current scroll height before prepend.
-        let old_height = el.scroll_height() as f64;
+      // Capture current scroll height before prepend.
+      let old_height = el.scroll_height() as f64;
 
-        manager_for_scroll.load_older(conv_id, before_ts, LOAD_BEFORE_PAGE, move |count| {
-          if count == 0 {
-            vs_inner.has_more.set(false);
-          } else {
-            // Preserve scroll position after prepend by adjusting
-            // scrollTop by the delta in scrollHeight. Deferred to
-            // the next animation frame so the DOM has updated.
-            let cb = Closure::once_into_js(move || {
-              if let Some(el2) = scroll_ref_inner.get() {
-                let new_height = el2.scroll_height() as f64;
-                let delta = new_height - old_height;
-                el2.set_scroll_top((el2.scroll_top() as f64 + delta) as i32);
-              }
-            });
-            if let Some(w) = web_sys::window() {
-              let _ = w.request_animation_frame(cb.unchecked_ref::<js_sys::Function>());
+      manager_for_scroll.load_older(conv_id, before_ts, LOAD_BEFORE_PAGE, move |count| {
+        if count == 0 {
+          vs_inner.has_more.set(false);
+        } else {
+          // Preserve scroll position after prepend by adjusting
+          // scrollTop by the delta in scrollHeight. Deferred to
+          // the next animation frame so the DOM has updated.
+          let cb = Closure::once_into_js(move || {
+            if let Some(el2) = scroll_ref_inner.get() {
+              let new_height = el2.scroll_height() as f64;
+              let delta = new_height - old_height;
+              el2.set_scroll_top((el2.scroll_top() as f64 + delta) as i32);
             }
+          });
+          if let Some(w) = web_sys::window() {
+            let _ = w.request_animation_frame(cb.unchecked_ref::<js_sys::Function>());
           }
-          vs_inner.loading_older.set(false);
-        });
-      }
+        }
+        vs_inner.loading_older.set(false);
+      });
     }
   };
 
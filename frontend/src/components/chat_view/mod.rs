@@ -20,6 +20,12 @@
 //!   reaction action.
 //! * [`image_preview::ImagePreviewOverlay`] — full-screen image viewer.
 //! * [`typing_indicator::TypingIndicator`] — inline typing strip.
+//! * [`safety_number::SafetyNumberPanel`] — safety-number verification
+//!   and identity-change warning for direct conversations.
+//! * [`poll_bubble::PollBubble`], [`poll_composer::PollComposer`] —
+//!   poll content inside a bubble and the modal that creates polls.
+//! * [`thread_panel::ThreadPanel`] — side panel for the open thread
+//!   (root, replies, thread composer).
 //! * [`helpers`] — pure formatting / mention rendering helpers.
 
 pub mod call_start_btn;
@@ -34,10 +40,14 @@ pub mod input_bar;
 pub mod mention_autocomplete;
 pub mod message_bubble;
 pub mod message_list;
+pub mod poll_bubble;
+pub mod poll_composer;
 pub mod reaction_picker;
+pub mod safety_number;
 pub mod scroll_perf;
 pub mod sticker_cache;
 pub mod sticker_panel;
+pub mod thread_panel;
 pub mod typing_indicator;
 pub mod view_root;
 pub mod virtual_scroll;
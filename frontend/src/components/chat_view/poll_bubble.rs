@@ -0,0 +1,184 @@
+//! Poll bubble content.
+//!
+//! Renders a poll's question, one row per option with its result bar,
+//! and a footer with the vote count, deadline and (for the creator) a
+//! close button. Clicking an option casts, changes or retracts the
+//! local user's vote through `ChatManager::vote_poll`; the bubble is
+//! re-rendered from the reconciled [`PollState`] whenever a vote lands.
+
+use crate::chat::polls::PollState;
+use crate::chat::use_chat_manager;
+use crate::components::chat_view::helpers::format_date_time_short;
+use crate::i18n;
+use crate::state::use_app_state;
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::{t, t_string};
+use leptos_icons::Icon;
+use message::{MessageId, UserId};
+
+/// Poll content inside a message bubble.
+#[component]
+pub fn PollBubble(
+  /// Id of the poll message.
+  message_id: MessageId,
+  /// Reconciled poll state at render time.
+  poll: PollState,
+  /// Whether the local user created the poll (may close it).
+  is_creator: bool,
+) -> impl IntoView {
+  let manager = use_chat_manager();
+  let app_state = use_app_state();
+  let i18n = i18n::use_i18n();
+
+  // Re-evaluate the deadline while the bubble is on screen so the
+  // options lock without waiting for another vote to re-render it.
+  let now_ms = RwSignal::new(chrono::Utc::now().timestamp_millis());
+  if poll.deadline_ms.is_some() && poll.closed_at_ms.is_none() {
+    let handle = crate::utils::set_interval(30_000, move || {
+      now_ms.set(chrono::Utc::now().timestamp_millis());
+    });
+    on_cleanup(move || {
+      if let Some(h) = handle {
+        h.cancel();
+      }
+    });
+  }
+  let deadline_ms = poll.deadline_ms;
+  let closed = poll.closed_at_ms.is_some();
+  let is_open = {
+    let poll = poll.clone();
+    Memo::new(move |_| poll.is_open_at(now_ms.get()))
+  };
+
+  let me = app_state.current_user_id();
+  let mine: Vec<u32> = me
+    .as_ref()
+    .map(|u| poll.choices_of(u).to_vec())
+    .unwrap_or_default();
+  let tally = poll.tally();
+  let voters = poll.voter_count();
+  let multiple_choice = poll.multiple_choice;
+
+  let name_of = move |user: &UserId| -> String {
+    app_state.online_users.with_untracked(|users| {
+      users
+        .iter()
+        .find(|u| &u.user_id == user)
+        .map(|u| u.nickname.clone())
+        .unwrap_or_else(|| user.to_string().chars().take(8).collect())
+    })
+  };
+
+  let rows = poll
+    .options
+    .iter()
+    .enumerate()
+    .map(|(idx, label)| {
+      let idx = idx as u32;
+      let count = tally.get(idx as usize).copied().unwrap_or(0);
+      let percent = if voters == 0 { 0 } else { count * 100 / voters };
+      let chosen = mine.contains(&idx);
+      let names = poll
+        .voters_for(idx)
+        .iter()
+        .map(&name_of)
+        .collect::<Vec<_>>()
+        .join(", ");
+      let next_choices = {
+        let mut next = mine.clone();
+        if chosen {
+          next.retain(|c| *c != idx);
+        } else if multiple_choice {
+          next.push(idx);
+          next.sort_unstable();
+        } else {
+          next = vec![idx];
+        }
+        next
+      };
+      let manager = manager.clone();
+      let on_click = move |_| {
+        manager.vote_poll(message_id, next_choices.clone());
+      };
+      let row_class = if chosen {
+        "message-poll-option chosen"
+      } else {
+        "message-poll-option"
+      };
+      view! {
+        <li>
+          <button
+            type="button"
+            class=row_class
+            data-testid="message-poll-option"
+            aria-pressed=if chosen { "true" } else { "false" }
+            prop:disabled=move || !is_open.get()
+            title=names
+            on:click=on_click
+          >
+            <span class="message-poll-option-check">
+              {chosen.then(|| view! { <Icon icon=i::LuCheck /> })}
+            </span>
+            <span class="message-poll-option-label">{label.clone()}</span>
+            <span class="message-poll-option-count">{count}</span>
+            <span class="message-poll-option-bar" style=format!("width: {percent}%")></span>
+          </button>
+        </li>
+      }
+    })
+    .collect_view();
+
+  let on_close = {
+    let manager = manager.clone();
+    move |_| {
+      manager.close_poll(message_id);
+    }
+  };
+
+  view! {
+    <div class="message-poll" data-testid="message-poll">
+      <div class="message-poll-question">
+        <Icon icon=i::LuClipboardList />
+        <span>{poll.question.clone()}</span>
+      </div>
+      <div class="message-poll-flags">
+        {multiple_choice.then(|| view! { <span>{t!(i18n, chat.poll_multiple_choice)}</span> })}
+        {poll.anonymous.then(|| view! { <span>{t!(i18n, chat.poll_anonymous)}</span> })}
+      </div>
+      <ol class="message-poll-options">{rows}</ol>
+      <div class="message-poll-footer">
+        <span data-testid="message-poll-votes">
+          {move || format!("{} {}", voters, t_string!(i18n, chat.poll_votes))}
+        </span>
+        {move || {
+          if closed {
+            Some(view! { <span class="message-poll-closed">{t!(i18n, chat.poll_closed)}</span> }.into_any())
+          } else if !is_open.get() {
+            Some(view! { <span class="message-poll-closed">{t!(i18n, chat.poll_expired)}</span> }.into_any())
+          } else {
+            deadline_ms.map(|d| {
+              let when = format_date_time_short(d);
+              view! {
+                <span>
+                  {move || format!("{} {}", t_string!(i18n, chat.poll_deadline_at), when)}
+                </span>
+              }
+              .into_any()
+            })
+          }
+        }}
+        <Show when=move || is_creator && is_open.get() fallback=|| ()>
+          <button
+            type="button"
+            class="btn btn-link message-poll-close"
+            data-testid="message-poll-close"
+            on:click=on_close.clone()
+          >
+            {t!(i18n, chat.poll_close)}
+          </button>
+        </Show>
+      </div>
+    </div>
+  }
+}
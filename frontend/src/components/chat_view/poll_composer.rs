@@ -0,0 +1,246 @@
+//! Poll composer modal.
+//!
+//! Opened from the input bar's poll button. Collects the question, a
+//! growing list of options, single / multiple choice, anonymous voting
+//! and an optional deadline, then hands a [`PollDraft`] to
+//! `ChatManager::send_poll`, which trims and validates it.
+
+use crate::chat::polls::PollDraft;
+use crate::chat::use_chat_manager;
+use crate::components::room::modal_wrapper::{ModalSize, ModalWrapper};
+use crate::components::room::utils::event_target_checked;
+use crate::i18n;
+use crate::state::ConversationId;
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::{t, t_string};
+use leptos_icons::Icon;
+use message::datachannel::ChatPoll;
+
+/// Deadline presets offered by the composer, as `(token, offset_ms)`.
+/// The empty token means "no deadline".
+const DEADLINE_PRESETS: [(&str, Option<i64>); 4] = [
+  ("", None),
+  ("1h", Some(60 * 60 * 1_000)),
+  ("24h", Some(24 * 60 * 60 * 1_000)),
+  ("7d", Some(7 * 24 * 60 * 60 * 1_000)),
+];
+
+/// Poll composer modal.
+#[component]
+pub fn PollComposer(
+  /// Active conversation.
+  conv: Signal<Option<ConversationId>>,
+  /// Visibility signal, toggled by the input bar.
+  visible: RwSignal<bool>,
+) -> impl IntoView {
+  let manager = use_chat_manager();
+  let i18n = i18n::use_i18n();
+
+  let question = RwSignal::new(String::new());
+  let options = RwSignal::new(vec![String::new(), String::new()]);
+  let multiple_choice = RwSignal::new(false);
+  let anonymous = RwSignal::new(false);
+  let deadline = RwSignal::new(String::new());
+  let error = RwSignal::new(false);
+  // The option rows only re-render when an option is added or removed,
+  // so typing into one does not rebuild the list and drop focus.
+  let option_count = Memo::new(move |_| options.with(Vec::len));
+
+  // Reset the form whenever the composer is reopened.
+  Effect::new(move |_| {
+    if visible.get() {
+      question.set(String::new());
+      options.set(vec![String::new(), String::new()]);
+      multiple_choice.set(false);
+      anonymous.set(false);
+      deadline.set(String::new());
+      error.set(false);
+    }
+  });
+
+  let close = move || visible.set(false);
+
+  let on_submit = move |ev: leptos::ev::SubmitEvent| {
+    ev.prevent_default();
+    let Some(conv_id) = conv.get_untracked() else {
+      return;
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let offset = deadline.with_untracked(|token| {
+      DEADLINE_PRESETS
+        .iter()
+        .find(|(t, _)| t == token)
+        .and_then(|(_, offset)| *offset)
+    });
+    let draft = PollDraft {
+      question: question.get_untracked(),
+      options: options.get_untracked(),
+      multiple_choice: multiple_choice.get_untracked(),
+      anonymous: anonymous.get_untracked(),
+      deadline_ms: offset.map(|o| now_ms.saturating_add(o)),
+    };
+    if manager.send_poll(conv_id, &draft).is_some() {
+      visible.set(false);
+    } else {
+      error.set(true);
+    }
+  };
+
+  let open_signal = Signal::derive(move || visible.get());
+
+  view! {
+    <ModalWrapper
+      on_close=Callback::new(move |()| close())
+      open=open_signal
+      size=ModalSize::Small
+      class="poll-composer"
+      labelled_by="poll-composer-title"
+      testid="poll-composer"
+    >
+      <form class="poll-composer-form" on:submit=on_submit>
+        <header class="modal-header">
+          <h2 id="poll-composer-title" class="modal-title">{t!(i18n, chat.poll_create)}</h2>
+          <button
+            type="button"
+            class="modal-close"
+            aria-label=move || t_string!(i18n, common.close)
+            on:click=move |_| close()
+          >
+            <Icon icon=i::LuX />
+          </button>
+        </header>
+
+        <div class="modal-body poll-composer-body">
+          <label class="poll-composer-label" for="poll-composer-question">
+            {t!(i18n, chat.poll_question)}
+          </label>
+          <input
+            id="poll-composer-question"
+            class="input"
+            type="text"
+            required
+            maxlength=ChatPoll::MAX_QUESTION_LENGTH as i64
+            prop:value=move || question.get()
+            on:input=move |ev| question.set(event_target_value(&ev))
+            data-testid="poll-composer-question"
+          />
+
+          <span class="poll-composer-label">{t!(i18n, chat.poll_options)}</span>
+          <ol class="poll-composer-options">
+            {move || {
+              let count = option_count.get();
+              (0..count)
+                .map(|idx| {
+                  view! {
+                    <li class="poll-composer-option">
+                      <input
+                        class="input"
+                        type="text"
+                        maxlength=ChatPoll::MAX_OPTION_LENGTH as i64
+                        aria-label=move || {
+                          format!("{} {}", t_string!(i18n, chat.poll_option), idx + 1)
+                        }
+                        prop:value=move || {
+                          options.with_untracked(|o| o.get(idx).cloned().unwrap_or_default())
+                        }
+                        on:input=move |ev| {
+                          let value = event_target_value(&ev);
+                          options.update(|o| {
+                            if let Some(slot) = o.get_mut(idx) {
+                              *slot = value;
+                            }
+                          });
+                        }
+                        data-testid="poll-composer-option"
+                      />
+                      <Show when=move || count > ChatPoll::MIN_OPTIONS fallback=|| ()>
+                        <button
+                          type="button"
+                          class="btn btn--icon"
+                          aria-label=move || t_string!(i18n, chat.poll_remove_option)
+                          title=move || t_string!(i18n, chat.poll_remove_option)
+                          on:click=move |_| {
+                            options.update(|o| {
+                              if idx < o.len() {
+                                o.remove(idx);
+                              }
+                            });
+                          }
+                        >
+                          <Icon icon=i::LuX />
+                        </button>
+                      </Show>
+                    </li>
+                  }
+                })
+                .collect_view()
+            }}
+          </ol>
+          <Show
+            when=move || option_count.get() < ChatPoll::MAX_OPTIONS
+            fallback=|| ()
+          >
+            <button
+              type="button"
+              class="btn btn--ghost poll-composer-add"
+              data-testid="poll-composer-add-option"
+              on:click=move |_| options.update(|o| o.push(String::new()))
+            >
+              <Icon icon=i::LuPlus />
+              <span>{t!(i18n, chat.poll_add_option)}</span>
+            </button>
+          </Show>
+
+          <label class="poll-composer-checkbox">
+            <input
+              type="checkbox"
+              prop:checked=move || multiple_choice.get()
+              on:change=move |ev| multiple_choice.set(event_target_checked(&ev))
+              data-testid="poll-composer-multiple"
+            />
+            <span>{t!(i18n, chat.poll_multiple_choice)}</span>
+          </label>
+          <label class="poll-composer-checkbox">
+            <input
+              type="checkbox"
+              prop:checked=move || anonymous.get()
+              on:change=move |ev| anonymous.set(event_target_checked(&ev))
+              data-testid="poll-composer-anonymous"
+            />
+            <span>{t!(i18n, chat.poll_anonymous)}</span>
+          </label>
+
+          <label class="poll-composer-label" for="poll-composer-deadline">
+            {t!(i18n, chat.poll_deadline)}
+          </label>
+          <select
+            id="poll-composer-deadline"
+            class="settings-select"
+            prop:value=move || deadline.get()
+            on:change=move |ev| deadline.set(event_target_value(&ev))
+            data-testid="poll-composer-deadline"
+          >
+            <option value="">{t!(i18n, chat.poll_deadline_none)}</option>
+            <option value="1h">{t!(i18n, chat.poll_deadline_hour)}</option>
+            <option value="24h">{t!(i18n, chat.poll_deadline_day)}</option>
+            <option value="7d">{t!(i18n, chat.poll_deadline_week)}</option>
+          </select>
+
+          <Show when=move || error.get() fallback=|| ()>
+            <p class="poll-composer-error" role="alert">{t!(i18n, chat.poll_invalid)}</p>
+          </Show>
+        </div>
+
+        <footer class="modal-footer">
+          <button type="button" class="btn btn--ghost" on:click=move |_| close()>
+            {t!(i18n, common.cancel)}
+          </button>
+          <button type="submit" class="btn btn--primary" data-testid="poll-composer-submit">
+            {t!(i18n, chat.poll_send)}
+          </button>
+        </footer>
+      </form>
+    </ModalWrapper>
+  }
+}
@@ -0,0 +1,112 @@
+//! Safety-number verification surface for direct conversations.
+//!
+//! Shows a shield button that expands into the peer's safety number
+//! (digits plus a fingerprint identicon) so both users can compare it
+//! out of band, and a persistent alert banner when the peer's identity
+//! key no longer matches the one pinned on first contact. The banner
+//! stays up until the user explicitly accepts the new key.
+
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::t_string;
+use leptos_icons::Icon;
+
+use crate::i18n;
+use crate::identicon::generate_fingerprint_identicon_data_uri;
+use crate::state::{ConversationId, use_app_state};
+use crate::webrtc::{format_safety_number, try_use_webrtc_manager};
+
+/// Safety-number button, panel and identity-change banner.
+///
+/// * `conv` — the current active conversation. Nothing is rendered for
+///   rooms or before the peer's signed key exchange has been verified.
+#[component]
+pub fn SafetyNumberPanel(conv: Signal<Option<ConversationId>>) -> impl IntoView {
+  let i18n = i18n::use_i18n();
+  let app_state = use_app_state();
+  let expanded = RwSignal::new(false);
+
+  // (peer id, safety number, identity changed) for the direct peer.
+  let identity = Memo::new(move |_| {
+    let Some(ConversationId::Direct(peer_id)) = conv.get() else {
+      return None;
+    };
+    app_state.webrtc_state.with(|s| {
+      s.get_peer(&peer_id).and_then(|p| {
+        p.safety_number
+          .clone()
+          .map(|number| (peer_id.clone(), number, p.encryption.identity_changed))
+      })
+    })
+  });
+  let identity_changed = move || identity.get().is_some_and(|(_, _, changed)| changed);
+
+  let on_accept = move |_| {
+    let Some((peer_id, _, _)) = identity.get_untracked() else {
+      return;
+    };
+    if let Some(manager) = try_use_webrtc_manager() {
+      manager.accept_peer_identity(&peer_id);
+    }
+  };
+
+  view! {
+    <Show when=move || identity.get().is_some() fallback=|| ()>
+      <Show when=identity_changed fallback=|| ()>
+        <div class="chat-identity-warning" role="alert" data-testid="identity-changed-banner">
+          <Icon icon=i::LuShieldAlert />
+          <span>{move || t_string!(i18n, chat.identity_changed_warning)}</span>
+          <button
+            type="button"
+            class="btn-secondary btn-sm"
+            on:click=move |_| expanded.set(true)
+            data-testid="identity-changed-verify"
+          >
+            {move || t_string!(i18n, chat.safety_number_verify)}
+          </button>
+          <button
+            type="button"
+            class="btn-danger btn-sm"
+            on:click=on_accept
+            data-testid="identity-changed-accept"
+          >
+            {move || t_string!(i18n, chat.identity_accept)}
+          </button>
+        </div>
+      </Show>
+      <button
+        type="button"
+        class="btn-icon chat-view__safety-toggle"
+        class:is-active=move || expanded.get()
+        class:is-warning=identity_changed
+        on:click=move |_| expanded.update(|v| *v = !*v)
+        aria-label=move || t_string!(i18n, chat.safety_number_title)
+        aria-pressed=move || expanded.get().to_string()
+        title=move || t_string!(i18n, chat.safety_number_title)
+        data-testid="safety-number-toggle"
+      >
+        <Icon icon=i::LuShieldCheck />
+      </button>
+      <Show when=move || expanded.get() fallback=|| ()>
+        {move || identity.get().map(|(_, number, _)| view! {
+          <div class="chat-safety-number" data-testid="safety-number-panel">
+            <h3>{move || t_string!(i18n, chat.safety_number_title)}</h3>
+            <img
+              class="chat-safety-number__identicon"
+              src=generate_fingerprint_identicon_data_uri(&number)
+              alt=""
+              width="96"
+              height="96"
+            />
+            <code class="chat-safety-number__digits" data-testid="safety-number-digits">
+              {format_safety_number(&number)}
+            </code>
+            <p class="chat-safety-number__hint">
+              {move || t_string!(i18n, chat.safety_number_hint)}
+            </p>
+          </div>
+        })}
+      </Show>
+    </Show>
+  }
+}
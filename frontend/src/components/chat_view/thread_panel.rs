@@ -0,0 +1,200 @@
+//! Thread side panel.
+//!
+//! Shows the root message of the open thread, its replies oldest
+//! first, and a compact composer that posts into the thread. Which
+//! thread is open lives in the conversation's
+//! `ChatConversationState::open_thread`, so the panel survives list
+//! re-renders and is closed per conversation.
+
+use std::collections::HashMap;
+
+use crate::chat::models::MAX_TEXT_LENGTH;
+use crate::chat::threads::{self, ThreadSummary};
+use crate::chat::use_chat_manager;
+use crate::components::chat_view::message_bubble::{BubbleCallbacks, MessageBubble};
+use crate::i18n;
+use crate::state::ConversationId;
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::t_string;
+use leptos_icons::Icon;
+use message::MessageId;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlTextAreaElement;
+
+/// Per-root thread summaries for the active conversation, provided as
+/// context by `ChatView` so every bubble can render its "N replies"
+/// footer without rescanning the message list.
+#[derive(Clone, Copy)]
+pub struct ThreadSummaries(pub Memo<HashMap<MessageId, ThreadSummary>>);
+
+/// Thread panel for the active conversation. Renders nothing while no
+/// thread is open.
+#[component]
+pub fn ThreadPanel(conv: Signal<Option<ConversationId>>, cbs: BubbleCallbacks) -> impl IntoView {
+  let manager = use_chat_manager();
+  let i18n = i18n::use_i18n();
+
+  let open_root = {
+    let manager = manager.clone();
+    Memo::new(move |_| {
+      let id = conv.get()?;
+      manager.conversation_state(&id).open_thread.get()
+    })
+  };
+  let root_msg = {
+    let manager = manager.clone();
+    Memo::new(move |_| {
+      let id = conv.get()?;
+      let root = open_root.get()?;
+      manager
+        .conversation_state(&id)
+        .messages
+        .with(|list| list.iter().find(|m| m.id == root).cloned())
+    })
+  };
+  let replies = {
+    let manager = manager.clone();
+    Memo::new(move |_| {
+      let (Some(id), Some(root)) = (conv.get(), open_root.get()) else {
+        return Vec::new();
+      };
+      manager
+        .conversation_state(&id)
+        .messages
+        .with(|list| threads::replies(list, root))
+    })
+  };
+
+  let draft = RwSignal::new(String::new());
+
+  let close = {
+    let manager = manager.clone();
+    move |_| {
+      if let Some(id) = conv.get_untracked() {
+        manager.close_thread(&id);
+      }
+    }
+  };
+
+  let do_send = {
+    let manager = manager.clone();
+    move || {
+      let (Some(id), Some(root)) = (conv.get_untracked(), open_root.get_untracked()) else {
+        return;
+      };
+      let text = draft.get_untracked();
+      if text.trim().is_empty() {
+        return;
+      }
+      if manager.reply_in_thread(id, root, text).is_some() {
+        draft.set(String::new());
+      }
+    }
+  };
+
+  let on_keydown = {
+    let do_send = do_send.clone();
+    move |ev: leptos::ev::KeyboardEvent| {
+      if crate::utils::safe_key(&ev) == "Enter" && !ev.shift_key() && !ev.is_composing() {
+        ev.prevent_default();
+        do_send();
+      }
+    }
+  };
+
+  let on_input = move |ev: leptos::ev::Event| {
+    if let Some(target) = ev.target()
+      && let Ok(textarea) = target.dyn_into::<HtmlTextAreaElement>()
+    {
+      draft.set(textarea.value());
+    }
+  };
+
+  view! {
+    <Show when=move || open_root.get().is_some() fallback=|| ()>
+      <aside
+        class="thread-panel"
+        data-testid="thread-panel"
+        aria-label=move || t_string!(i18n, chat.thread_title)
+      >
+        <header class="thread-panel-header">
+          <Icon icon=i::LuMessageCircle />
+          <h3>{move || t_string!(i18n, chat.thread_title)}</h3>
+          <button
+            type="button"
+            class="btn btn--icon thread-panel-close"
+            data-testid="thread-panel-close"
+            aria-label=move || t_string!(i18n, chat.thread_close)
+            title=move || t_string!(i18n, chat.thread_close)
+            on:click=close.clone()
+          >
+            <Icon icon=i::LuX />
+          </button>
+        </header>
+
+        <div class="thread-panel-body">
+          {move || match root_msg.get() {
+            Some(msg) => view! {
+              <div class="thread-panel-root" data-testid="thread-panel-root">
+                <MessageBubble msg=msg cbs=cbs in_thread=true />
+              </div>
+            }
+            .into_any(),
+            None => view! {
+              <p class="thread-panel-note">
+                {t_string!(i18n, chat.thread_root_unavailable)}
+              </p>
+            }
+            .into_any(),
+          }}
+
+          <div class="thread-panel-replies" data-testid="thread-panel-replies">
+            {move || {
+              let list = replies.get();
+              if list.is_empty() {
+                view! {
+                  <p class="thread-panel-note">{t_string!(i18n, chat.thread_empty)}</p>
+                }
+                .into_any()
+              } else {
+                list
+                  .into_iter()
+                  .map(|msg| view! { <MessageBubble msg=msg cbs=cbs in_thread=true /> })
+                  .collect_view()
+                  .into_any()
+              }
+            }}
+          </div>
+        </div>
+
+        <div class="thread-panel-composer">
+          <textarea
+            class="chat-input-textarea"
+            data-testid="thread-panel-input"
+            rows="1"
+            maxlength=MAX_TEXT_LENGTH as i64
+            placeholder=move || t_string!(i18n, chat.thread_reply_placeholder)
+            prop:value=move || draft.get()
+            on:input=on_input
+            on:keydown=on_keydown.clone()
+          ></textarea>
+          <button
+            type="button"
+            class="chat-input-btn primary"
+            data-testid="thread-panel-send"
+            aria-label=move || t_string!(i18n, chat.send)
+            title=move || t_string!(i18n, chat.send)
+            prop:disabled=move || draft.with(|d| d.trim().is_empty())
+            on:click={
+              let do_send = do_send.clone();
+              move |_| do_send()
+            }
+          >
+            <Icon icon=i::LuSend />
+          </button>
+        </div>
+      </aside>
+    </Show>
+  }
+}
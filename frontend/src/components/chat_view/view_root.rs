@@ -1,15 +1,16 @@
 //! Chat view root.
 //!
 //! Wires the message list, typing indicator, input bar, reply
-//! state, forward modal, sticker / voice / image overlays, and the
-//! image-preview overlay into a single container component. The
+//! state, thread panel, forward modal, sticker / voice / image
+//! overlays, and the image-preview overlay into a single container
+//! component. The
 //! parent (`HomePage`) simply mounts `<ChatView />` and the rest is
 //! self-contained.
 //!
 //! The view renders an empty-state placeholder when no conversation is
 //! active (Req 4.10.x).
 
-use crate::chat::{ChatMessage, ReplySnippet, use_chat_manager};
+use crate::chat::{ChatMessage, ReplySnippet, threads, use_chat_manager};
 use crate::components::chat_view::call_start_btn::CallStartButton;
 use crate::components::chat_view::dialog::Dialog;
 use crate::components::chat_view::file_picker::{
@@ -21,7 +22,10 @@ use crate::components::chat_view::image_preview::ImagePreviewOverlay;
 use crate::components::chat_view::input_bar::{InputBar, InputOverlays};
 use crate::components::chat_view::message_bubble::BubbleCallbacks;
 use crate::components::chat_view::message_list::{MessageList, ScrollController};
+use crate::components::chat_view::poll_composer::PollComposer;
+use crate::components::chat_view::safety_number::SafetyNumberPanel;
 use crate::components::chat_view::sticker_panel::StickerPanel;
+use crate::components::chat_view::thread_panel::{ThreadPanel, ThreadSummaries};
 use crate::components::chat_view::typing_indicator::TypingIndicator;
 use crate::components::chat_view::voice_recorder::VoiceRecorder;
 use crate::components::room::MemberListPanel;
@@ -91,6 +95,7 @@ pub fn ChatView() -> impl IntoView {
     voice: RwSignal::new(false),
     image: RwSignal::new(false),
     file: RwSignal::new(false),
+    poll: RwSignal::new(false),
   };
 
   // Drag-and-drop state (Req 6.1 — drop files onto the chat view).
@@ -129,6 +134,29 @@ pub fn ChatView() -> impl IntoView {
     });
   }
 
+  // Thread summaries for the bubbles' "N replies" footers, computed
+  // once per list change rather than once per bubble.
+  {
+    let manager = manager.clone();
+    let summaries = Memo::new(move |_| match conv.get() {
+      Some(id) => manager
+        .conversation_state(&id)
+        .messages
+        .with(|list| threads::summarize(list)),
+      None => Default::default(),
+    });
+    provide_context(ThreadSummaries(summaries));
+  }
+
+  let open_thread = {
+    let manager = manager.clone();
+    Callback::new(move |root: MessageId| {
+      if let Some(id) = conv.get_untracked() {
+        manager.open_thread(id, root);
+      }
+    })
+  };
+
   let cbs = BubbleCallbacks {
     open_image: Callback::new(move |url: String| preview_url.set(Some(url))),
     open_forward: Callback::new(move |msg: ChatMessage| forward_source.set(Some(msg))),
@@ -138,6 +166,7 @@ pub fn ChatView() -> impl IntoView {
         ctrl.scroll_to.run(id);
       }
     }),
+    open_thread,
   };
 
   // Drag-and-drop handlers (Req 6.1). Cleared on drop-leave so the
@@ -221,6 +250,7 @@ pub fn ChatView() -> impl IntoView {
           aria-hidden="true"
         ></span>
         <CallStartButton conv=conv />
+        <SafetyNumberPanel conv=conv />
         // G7: Toggle button for the member list panel (room only).
         <Show when=move || room_id_signal.get().is_some()>
           <button
@@ -248,6 +278,7 @@ pub fn ChatView() -> impl IntoView {
           <VoiceRecorder visible=overlays.voice conv=conv />
           <ImagePicker conv=conv visible=overlays.image />
           <FilePicker conv=conv visible=overlays.file />
+          <PollComposer conv=conv visible=overlays.poll />
           <InputBar conv=conv reply_target=reply_target overlays=overlays />
         </div>
 
@@ -257,6 +288,8 @@ pub fn ChatView() -> impl IntoView {
           </div>
         </Show>
 
+        <ThreadPanel conv=conv cbs=cbs />
+
         <ImagePreviewOverlay url=preview_url />
         <ForwardModal source=forward_source />
         <Dialog state=dialog_state.clone() />
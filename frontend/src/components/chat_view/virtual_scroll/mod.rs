@@ -77,6 +77,13 @@ const VOICE_BUBBLE_H: f64 = 60.0;
 /// Height (px) for a revoked-message placeholder.
 const REVOKED_BUBBLE_H: f64 = 48.0;
 
+/// Base height (px) for a poll bubble (question + footer), before
+/// the per-option rows.
+const POLL_BUBBLE_BASE_H: f64 = 96.0;
+
+/// Height (px) per poll option row (label + result bar).
+const POLL_OPTION_H: f64 = 40.0;
+
 /// Height (px) for a file-attachment bubble (filename + size line +
 /// progress bar + download button). Aligns with the `.message-file`
 /// card defined in `chat-messages.css`.
@@ -90,6 +97,7 @@ const FILE_BUBBLE_H: f64 = 96.0;
 /// - Voice: ~60 px
 /// - Sticker: ~120 px
 /// - File: ~80 px (added when Task 19 lands)
+/// - Poll: 96 px base + 40 px per option
 pub fn estimate_height(msg: &ChatMessage) -> f64 {
   match &msg.content {
     MessageContent::Text(t) => {
@@ -105,6 +113,7 @@ pub fn estimate_height(msg: &ChatMessage) -> f64 {
     MessageContent::Sticker(_) => STICKER_BUBBLE_H,
     MessageContent::Voice(_) => VOICE_BUBBLE_H,
     MessageContent::File(_) => FILE_BUBBLE_H,
+    MessageContent::Poll(poll) => POLL_BUBBLE_BASE_H + poll.options.len() as f64 * POLL_OPTION_H,
     MessageContent::Forwarded { content, .. } => {
       let char_count = content.chars().count();
       let lines = (char_count as f64 / CHARS_PER_LINE as f64).ceil().max(1.0);
@@ -18,10 +18,12 @@ fn make_msg(content: MessageContent) -> ChatMessage {
     outgoing: false,
     status: MessageStatus::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -122,6 +124,21 @@ fn estimate_height_revoked_is_48() {
   assert_eq!(estimate_height(&msg), 48.0);
 }
 
+#[test]
+fn estimate_height_poll_grows_with_options() {
+  let msg = make_msg(MessageContent::Poll(crate::chat::polls::PollState {
+    question: "Lunch?".into(),
+    options: vec!["a".into(), "b".into(), "c".into()],
+    multiple_choice: false,
+    anonymous: false,
+    deadline_ms: None,
+    ballots: BTreeMap::new(),
+    closed_at_ms: None,
+  }));
+  // 96 base + 3 options * 40.
+  assert_eq!(estimate_height(&msg), 216.0);
+}
+
 #[test]
 fn estimate_height_image_square() {
   // 300x300 → aspect=1.0, height=300*1+32=332.
@@ -1,4 +1,5 @@
-//! Appearance settings (theme / language / font size).
+//! Appearance settings (theme / language / font size / visual effects /
+//! thread replies in the timeline).
 //!
 //! Consumers of this component do not need any props: it binds
 //! directly to the `AppState` theme / locale signals and the
@@ -29,6 +30,7 @@ pub fn AppearanceSection() -> impl IntoView {
   // via an effect in `app.rs` so the CSS effects layer can react.
   let glass_enabled = Memo::new(move |_| settings.get().glass_enabled);
   let motion_enabled = Memo::new(move |_| settings.get().motion_enabled);
+  let thread_replies_inline = Memo::new(move |_| settings.get().thread_replies_in_timeline);
 
   let toggle_glass = move |_| {
     settings.update(|s| s.glass_enabled = !s.glass_enabled);
@@ -36,6 +38,9 @@ pub fn AppearanceSection() -> impl IntoView {
   let toggle_motion = move |_| {
     settings.update(|s| s.motion_enabled = !s.motion_enabled);
   };
+  let toggle_thread_replies = move |_| {
+    settings.update(|s| s.thread_replies_in_timeline = !s.thread_replies_in_timeline);
+  };
 
   view! {
     <section class="settings-section" aria-labelledby="appearance-heading">
@@ -188,6 +193,26 @@ pub fn AppearanceSection() -> impl IntoView {
           <span class="settings-toggle-thumb"></span>
         </button>
       </div>
+
+      <div class="settings-row settings-toggle-row">
+        <div class="settings-toggle-meta">
+          <label class="settings-label">
+            <Icon icon=i::LuMessageCircle attr:class="settings-label-icon" />
+            {t!(i18n, settings.thread_replies_in_timeline)}
+          </label>
+          <p class="settings-hint">{t!(i18n, settings.thread_replies_in_timeline_hint)}</p>
+        </div>
+        <button
+          class=move || toggle_root_class(thread_replies_inline.get())
+          role="switch"
+          aria-label=move || t_string!(i18n, settings.thread_replies_in_timeline)
+          aria-checked=move || thread_replies_inline.get().to_string()
+          on:click=toggle_thread_replies
+          data-testid="toggle-thread-replies-in-timeline"
+        >
+          <span class="settings-toggle-thumb"></span>
+        </button>
+      </div>
     </section>
   }
 }
@@ -3,11 +3,13 @@
 //! Enumerates `navigator.mediaDevices.enumerateDevices()` (lazily, via
 //! `load_devices`) and lets the user pick a preferred default for
 //! camera, microphone and speaker. Also exposes the speaker-volume
-//! slider and the video-quality radio group.
+//! slider, the outbound microphone processing toggles (noise
+//! suppression, noise gate, auto gain) and the video-quality radio
+//! group.
 
 use super::av_helpers::{DeviceCache, DeviceEntry, DeviceKind, enumerate_devices};
 use super::camera_preview::CameraPreview;
-use super::class_helpers::segmented_item_class;
+use super::class_helpers::{segmented_item_class, toggle_root_class};
 use super::device_select::DeviceSelect;
 use super::mic_level_feedback::MicrophoneLevelFeedback;
 use super::permission_badge::{PermissionBadge, PermissionState};
@@ -15,7 +17,7 @@ use crate::i18n;
 use crate::settings::{VideoQualityPref, use_settings_state};
 use icondata as i;
 use leptos::prelude::*;
-use leptos_i18n::t;
+use leptos_i18n::{t, t_string};
 use leptos_icons::Icon;
 
 /// Audio & video section.
@@ -75,6 +77,23 @@ pub fn AvSection() -> impl IntoView {
   let video_quality = Memo::new(move |_| settings.get().video_quality);
   let speaker_volume = Memo::new(move |_| settings.get().speaker_volume);
   let microphone_volume = Memo::new(move |_| settings.get().microphone_volume);
+  // Outbound call-audio processing stages; `lib.rs` forwards changes
+  // to the running call.
+  let noise_suppression = Memo::new(move |_| settings.get().audio_processing.noise_suppression);
+  let noise_gate = Memo::new(move |_| settings.get().audio_processing.noise_gate);
+  let auto_gain = Memo::new(move |_| settings.get().audio_processing.auto_gain);
+
+  let toggle_noise_suppression = move |_| {
+    settings.update(|s| {
+      s.audio_processing.noise_suppression = !s.audio_processing.noise_suppression;
+    });
+  };
+  let toggle_noise_gate = move |_| {
+    settings.update(|s| s.audio_processing.noise_gate = !s.audio_processing.noise_gate);
+  };
+  let toggle_auto_gain = move |_| {
+    settings.update(|s| s.audio_processing.auto_gain = !s.audio_processing.auto_gain);
+  };
 
   // Pre-computed per-kind device lists so the `<For>` in each
   // `DeviceSelect` does not re-filter the full catalogue on every
@@ -258,6 +277,67 @@ pub fn AvSection() -> impl IntoView {
       // Real-time microphone level meter (Req 13.1.3).
       <MicrophoneLevelFeedback />
 
+      // Outbound microphone processing applied during calls.
+      <div class="settings-row settings-toggle-row">
+        <div class="settings-toggle-meta">
+          <label class="settings-label">
+            <Icon icon=i::LuWind attr:class="settings-label-icon" />
+            {t!(i18n, settings.noise_suppression)}
+          </label>
+          <p class="settings-hint">{t!(i18n, settings.noise_suppression_hint)}</p>
+        </div>
+        <button
+          class=move || toggle_root_class(noise_suppression.get())
+          role="switch"
+          aria-label=move || t_string!(i18n, settings.noise_suppression)
+          aria-checked=move || noise_suppression.get().to_string()
+          on:click=toggle_noise_suppression
+          data-testid="toggle-noise-suppression"
+        >
+          <span class="settings-toggle-thumb"></span>
+        </button>
+      </div>
+
+      <div class="settings-row settings-toggle-row">
+        <div class="settings-toggle-meta">
+          <label class="settings-label">
+            <Icon icon=i::LuVolumeX attr:class="settings-label-icon" />
+            {t!(i18n, settings.noise_gate)}
+          </label>
+          <p class="settings-hint">{t!(i18n, settings.noise_gate_hint)}</p>
+        </div>
+        <button
+          class=move || toggle_root_class(noise_gate.get())
+          role="switch"
+          aria-label=move || t_string!(i18n, settings.noise_gate)
+          aria-checked=move || noise_gate.get().to_string()
+          on:click=toggle_noise_gate
+          data-testid="toggle-noise-gate"
+        >
+          <span class="settings-toggle-thumb"></span>
+        </button>
+      </div>
+
+      <div class="settings-row settings-toggle-row">
+        <div class="settings-toggle-meta">
+          <label class="settings-label">
+            <Icon icon=i::LuGauge attr:class="settings-label-icon" />
+            {t!(i18n, settings.auto_gain)}
+          </label>
+          <p class="settings-hint">{t!(i18n, settings.auto_gain_hint)}</p>
+        </div>
+        <button
+          class=move || toggle_root_class(auto_gain.get())
+          role="switch"
+          aria-label=move || t_string!(i18n, settings.auto_gain)
+          aria-checked=move || auto_gain.get().to_string()
+          on:click=toggle_auto_gain
+          data-testid="toggle-auto-gain"
+        >
+          <span class="settings-toggle-thumb"></span>
+        </button>
+      </div>
+
       // Video quality selector
       <div class="settings-row">
         <label class="settings-label">{t!(i18n, settings.video_quality)}</label>
@@ -9,12 +9,17 @@
 //!   clearing, storage-estimate polling.
 //! * **Export collectors** — snapshot builders for messages, contacts
 //!   and the blacklist.
+//! * **Backup** — encrypted backup download / restore upload and the
+//!   status line for each failure.
 
+use crate::backup::format::MIN_PASSPHRASE_CHARS;
+use crate::backup::{BackupError, RestoreSummary};
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_use::use_window;
 use wasm_bindgen::JsCast;
-use web_sys::{Blob, BlobPropertyBag, HtmlElement, Url};
+
+pub(super) use crate::utils::trigger_download;
 
 /// localStorage keys that must survive a cache clear (Req 13.5.5:
 /// "preserving user preference settings"). Anything not prefixed by
@@ -30,6 +35,7 @@ use web_sys::{Blob, BlobPropertyBag, HtmlElement, Url};
 /// | `blacklist` | `blacklist` | User intent — must survive cache wipes |
 /// | `pinned_` | (reserved for future) | Per-conversation pin metadata |
 /// | `settings_` | `settings_user`, `settings_theme`, `settings_locale`, `settings_theater_overlay` | Unified user-preferences namespace |
+/// | `device_id` | `device_id` | Device identity — clearing would register this browser as a new device |
 ///
 /// ## Intentionally NOT preserved
 ///
@@ -51,6 +57,7 @@ pub(super) const PRESERVED_STORAGE_PREFIXES: &[&str] = &[
   "blacklist",
   "pinned_",
   "settings_",
+  "device_id",
 ];
 
 /// Pretty-print a `(usage, quota)` tuple or fall back to `unknown`.
@@ -81,43 +88,6 @@ pub(super) fn format_bytes(bytes: u64) -> String {
   }
 }
 
-/// Trigger a "Save file" download for in-memory text content.
-///
-/// The object URL is revoked after a short delay so Firefox (which
-/// initiates the download asynchronously) has time to dereference
-/// the blob before the runtime drops it.
-pub(super) fn trigger_download(filename: &str, mime: &str, content: &str) {
-  let window = use_window();
-  let Some(window) = window.as_ref() else {
-    return;
-  };
-  let Some(document) = window.document() else {
-    return;
-  };
-  let options = BlobPropertyBag::new();
-  options.set_type(mime);
-  let array = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(content));
-  let Ok(blob) = Blob::new_with_str_sequence_and_options(&array, &options) else {
-    return;
-  };
-  let Ok(url) = Url::create_object_url_with_blob(&blob) else {
-    return;
-  };
-  let Ok(link) = document.create_element("a") else {
-    return;
-  };
-  let link: HtmlElement = link.unchecked_into();
-  let _ = link.set_attribute("href", &url);
-  let _ = link.set_attribute("download", filename);
-  link.click();
-  let url_for_revoke = url.clone();
-  // 5 s delay — Firefox initiates downloads asynchronously, so a
-  // 0 ms revoke can race and produce a "network error" download.
-  let _ = crate::utils::set_timeout_once(5_000, move || {
-    let _ = Url::revoke_object_url(&url_for_revoke);
-  });
-}
-
 /// Best-effort clear of browser-side caches belonging to this origin.
 ///
 /// Clears both the CacheStorage buckets (Service Worker cache,
@@ -386,6 +356,86 @@ pub(super) async fn clear_all_history(
   Ok(0)
 }
 
+/// Localised status copy for backup failures. Snapshotted before the
+/// async work starts, like the other status strings (B-4).
+#[derive(Debug, Clone)]
+pub(super) struct BackupErrorCopy {
+  /// Template with a `{count}` placeholder for the minimum length.
+  pub weak_passphrase: String,
+  pub wrong_passphrase: String,
+  pub not_a_backup: String,
+  pub newer_version: String,
+  /// Prefix for errors without a dedicated message.
+  pub failed: String,
+}
+
+/// Status line for a failed backup or restore.
+pub(super) fn backup_error_message(err: &BackupError, copy: &BackupErrorCopy) -> String {
+  match err {
+    BackupError::WeakPassphrase => copy
+      .weak_passphrase
+      .replace("{count}", &MIN_PASSPHRASE_CHARS.to_string()),
+    BackupError::Decrypt => copy.wrong_passphrase.clone(),
+    BackupError::NotABackup => copy.not_a_backup.clone(),
+    BackupError::UnsupportedVersion(_) | BackupError::NewerSchema(_) => copy.newer_version.clone(),
+    other => format!("{}: {other}", copy.failed),
+  }
+}
+
+/// Build an encrypted backup and download it.
+#[cfg(target_arch = "wasm32")]
+pub(super) async fn download_backup(
+  passphrase: &str,
+  settings: crate::settings::SettingsState,
+  app_state: crate::state::AppState,
+) -> Result<(), BackupError> {
+  let json = crate::backup::create_backup(passphrase, settings, app_state).await?;
+  let filename = timestamped_filename("chat-backup", "json");
+  trigger_download(&filename, "application/json", &json);
+  Ok(())
+}
+
+/// Native stub for tests / non-WASM builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) async fn download_backup(
+  _passphrase: &str,
+  _settings: crate::settings::SettingsState,
+  _app_state: crate::state::AppState,
+) -> Result<(), BackupError> {
+  Err(BackupError::Storage(
+    "backups require a browser".to_string(),
+  ))
+}
+
+/// Read a picked backup file and merge it into local storage.
+#[cfg(target_arch = "wasm32")]
+pub(super) async fn restore_backup_file(
+  file: web_sys::File,
+  passphrase: &str,
+  settings: crate::settings::SettingsState,
+  app_state: crate::state::AppState,
+) -> Result<RestoreSummary, BackupError> {
+  let text = wasm_bindgen_futures::JsFuture::from(file.text())
+    .await
+    .map_err(|e| BackupError::Codec(format!("{e:?}")))?
+    .as_string()
+    .ok_or(BackupError::NotABackup)?;
+  crate::backup::restore_backup(&text, passphrase, settings, app_state).await
+}
+
+/// Native stub for tests / non-WASM builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) async fn restore_backup_file(
+  _file: web_sys::File,
+  _passphrase: &str,
+  _settings: crate::settings::SettingsState,
+  _app_state: crate::state::AppState,
+) -> Result<RestoreSummary, BackupError> {
+  Err(BackupError::Storage(
+    "backups require a browser".to_string(),
+  ))
+}
+
 /// Refresh the storage-usage estimate via `navigator.storage.estimate()`.
 ///
 /// Uses `try_set` because the async task may resolve after the
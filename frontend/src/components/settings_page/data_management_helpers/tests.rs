@@ -29,6 +29,7 @@ fn preserved_storage_keys_match_prefix_list() {
   assert!(is_preserved_storage_key("blacklist"));
   assert!(is_preserved_storage_key("auth_token"));
   assert!(is_preserved_storage_key("pinned_rooms"));
+  assert!(is_preserved_storage_key("device_id"));
 
   // Non-matching keys are considered non-critical and get purged.
   assert!(!is_preserved_storage_key("cache_sticker_v1"));
@@ -48,3 +49,35 @@ fn timestamped_filename_format() {
   // YYYY-MM-DD is 10 characters, so the body length is fixed.
   assert_eq!(name.len(), "chat-export-YYYY-MM-DD.json".len());
 }
+
+#[test]
+fn backup_errors_map_to_status_copy() {
+  let copy = BackupErrorCopy {
+    weak_passphrase: "min {count}".to_string(),
+    wrong_passphrase: "wrong".to_string(),
+    not_a_backup: "not a backup".to_string(),
+    newer_version: "update".to_string(),
+    failed: "Backup failed".to_string(),
+  };
+  assert_eq!(
+    backup_error_message(&BackupError::WeakPassphrase, &copy),
+    format!("min {MIN_PASSPHRASE_CHARS}")
+  );
+  assert_eq!(backup_error_message(&BackupError::Decrypt, &copy), "wrong");
+  assert_eq!(
+    backup_error_message(&BackupError::NotABackup, &copy),
+    "not a backup"
+  );
+  assert_eq!(
+    backup_error_message(&BackupError::NewerSchema(99), &copy),
+    "update"
+  );
+  assert_eq!(
+    backup_error_message(&BackupError::UnsupportedVersion(2), &copy),
+    "update"
+  );
+  assert!(
+    backup_error_message(&BackupError::Storage("quota".to_string()), &copy)
+      .starts_with("Backup failed: ")
+  );
+}
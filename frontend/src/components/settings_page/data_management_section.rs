@@ -1,14 +1,16 @@
-//! Data management section (clear history, cache, export, diagnostics).
+//! Data management section (clear history, cache, export, encrypted
+//! backup, diagnostics).
 //!
 //! Pure view + handler wiring — everything testable lives in
 //! `data_management_helpers` so this file stays focused on
 //! presentation and state wiring.
 
 use super::data_management_helpers::{
-  clear_all_history, clear_cache_storage, collect_blacklist, collect_contacts,
-  collect_messages_for_export, format_storage_estimate, refresh_storage_estimate,
-  timestamped_filename, trigger_download,
+  BackupErrorCopy, backup_error_message, clear_all_history, clear_cache_storage, collect_blacklist,
+  collect_contacts, collect_messages_for_export, download_backup, format_storage_estimate,
+  refresh_storage_estimate, restore_backup_file, timestamped_filename, trigger_download,
 };
+use crate::backup::format::MIN_PASSPHRASE_CHARS;
 use crate::blacklist::use_blacklist_state;
 use crate::chat::use_chat_manager;
 use crate::components::debug::DebugPanelVisibility;
@@ -22,6 +24,7 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_i18n::{t, t_string};
 use leptos_icons::Icon;
+use wasm_bindgen::JsCast;
 
 /// Which destructive action the user is currently being asked to
 /// confirm. `None` means no dialog is open.
@@ -204,6 +207,70 @@ pub fn DataManagementSection() -> impl IntoView {
     }
   };
 
+  // Encrypted backup. Create and restore share the passphrase field
+  // and the busy flag; the async tasks use `try_set` because key
+  // derivation can outlive the settings drawer.
+  let backup_passphrase: RwSignal<String> = RwSignal::new(String::new());
+  let backup_busy: RwSignal<bool> = RwSignal::new(false);
+
+  let backup_error_copy = move || BackupErrorCopy {
+    weak_passphrase: t_string!(i18n, settings.backup_weak_passphrase).to_string(),
+    wrong_passphrase: t_string!(i18n, settings.backup_wrong_passphrase).to_string(),
+    not_a_backup: t_string!(i18n, settings.backup_not_a_backup).to_string(),
+    newer_version: t_string!(i18n, settings.backup_newer_version).to_string(),
+    failed: t_string!(i18n, settings.backup_failed).to_string(),
+  };
+
+  let on_create_backup = move |_| {
+    let copy = backup_error_copy();
+    let created_msg = t_string!(i18n, settings.backup_created).to_string();
+    let passphrase = backup_passphrase.get_untracked();
+    backup_busy.set(true);
+    spawn_local(async move {
+      match download_backup(&passphrase, settings, app_state).await {
+        Ok(()) => {
+          let _ = backup_passphrase.try_set(String::new());
+          let _ = status_message.try_set(Some(created_msg));
+        }
+        Err(err) => {
+          let _ = status_message.try_set(Some(backup_error_message(&err, &copy)));
+        }
+      }
+      let _ = backup_busy.try_set(false);
+    });
+  };
+
+  let on_restore_file = move |ev: leptos::ev::Event| {
+    let Some(target) = ev.target() else { return };
+    let Ok(input) = target.dyn_into::<web_sys::HtmlInputElement>() else {
+      return;
+    };
+    let Some(file) = input.files().and_then(|files| files.item(0)) else {
+      return;
+    };
+    // Reset so picking the same file twice fires change.
+    input.set_value("");
+
+    let copy = backup_error_copy();
+    let restored_template = t_string!(i18n, settings.backup_restored).to_string();
+    let passphrase = backup_passphrase.get_untracked();
+    backup_busy.set(true);
+    spawn_local(async move {
+      match restore_backup_file(file, &passphrase, settings, app_state).await {
+        Ok(summary) => {
+          let msg = restored_template.replace("{count}", &summary.messages.to_string());
+          let _ = backup_passphrase.try_set(String::new());
+          let _ = status_message.try_set(Some(msg));
+          refresh_storage_estimate(storage_usage);
+        }
+        Err(err) => {
+          let _ = status_message.try_set(Some(backup_error_message(&err, &copy)));
+        }
+      }
+      let _ = backup_busy.try_set(false);
+    });
+  };
+
   // "Open Debug Panel" handler. `DebugPanelVisibility` is provided at
   // the `App` root so in production the context lookup always
   // succeeds; we still defensively surface a status message if a
@@ -323,6 +390,65 @@ pub fn DataManagementSection() -> impl IntoView {
         {t!(i18n, settings.export_security_warning)}
       </p>
 
+      // Encrypted backup / restore
+      <div class="settings-row">
+        <label class="settings-label" for="settings-backup-passphrase">
+          {t!(i18n, settings.backup)}
+        </label>
+        <input
+          id="settings-backup-passphrase"
+          class="input settings-backup-passphrase"
+          type="password"
+          autocomplete="new-password"
+          aria-label=move || t_string!(i18n, settings.backup_passphrase)
+          placeholder=move || {
+            t_string!(i18n, settings.backup_passphrase_placeholder)
+              .replace("{count}", &MIN_PASSPHRASE_CHARS.to_string())
+          }
+          prop:value=move || backup_passphrase.get()
+          on:input=move |ev| backup_passphrase.set(event_target_value(&ev))
+          disabled=move || backup_busy.get()
+          data-testid="backup-passphrase"
+        />
+        <div class="settings-button-row">
+          <button
+            class="btn-secondary settings-action"
+            disabled=move || backup_busy.get()
+            on:click=on_create_backup
+            data-testid="backup-create"
+          >
+            <Icon icon=i::LuDownload />
+            <span>{t!(i18n, settings.backup_create)}</span>
+          </button>
+          <label
+            class="btn-secondary settings-action settings-backup-restore"
+            aria-disabled=move || backup_busy.get().to_string()
+          >
+            <Icon icon=i::LuUpload />
+            <span>{t!(i18n, settings.backup_restore)}</span>
+            <input
+              type="file"
+              accept="application/json,.json"
+              class="settings-backup-file-input"
+              disabled=move || backup_busy.get()
+              on:change=on_restore_file
+              data-testid="backup-restore-file"
+            />
+          </label>
+        </div>
+        <p class="settings-hint">{t!(i18n, settings.backup_hint)}</p>
+      </div>
+      <Show when=move || backup_busy.get()>
+        <p class="settings-hint" aria-live="polite">
+          <Icon icon=i::LuLoaderCircle attr:class="settings-spinner" />
+          {t!(i18n, settings.backup_in_progress)}
+        </p>
+      </Show>
+      <p class="settings-hint settings-warning-hint">
+        <Icon icon=i::LuShieldAlert attr:class="settings-warning-icon" />
+        {t!(i18n, settings.backup_passphrase_warning)}
+      </p>
+
       // Debug logs
       <div class="settings-row">
         <button
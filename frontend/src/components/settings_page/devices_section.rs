@@ -0,0 +1,259 @@
+//! Linked devices: the account's signed-in devices and the link flow
+//! that copies chat history to a new device (QR code + short code).
+
+use crate::devices::{self, DeviceLinkManager, LinkFailure, LinkPhase, LinkRole};
+use crate::i18n;
+use crate::state::use_app_state;
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::{t, t_string};
+use leptos_icons::Icon;
+
+/// Devices section.
+#[component]
+pub fn DevicesSection() -> impl IntoView {
+  let i18n = i18n::use_i18n();
+  let app_state = use_app_state();
+  let manager = StoredValue::new(devices::use_device_link_manager());
+  let phase = manager.with_value(|m| m.phase);
+  let local = devices::local_device_id();
+
+  // Before the server sends a device list this device is alone.
+  let device_list = Memo::new(move |_| {
+    let list = app_state.my_devices.get();
+    if list.is_empty() {
+      vec![devices::local_device()]
+    } else {
+      list
+    }
+  });
+  let busy = Memo::new(move |_| phase.get().is_active());
+
+  view! {
+    <section class="settings-section" aria-labelledby="devices-heading">
+      <h2 id="devices-heading" class="settings-section-title">
+        <Icon icon=i::LuMonitor attr:class="settings-section-icon" />
+        {t!(i18n, settings.devices)}
+      </h2>
+      <p class="settings-hint">{t!(i18n, settings.devices_hint)}</p>
+
+      <ul class="settings-device-list" data-testid="device-list">
+        <For each=move || device_list.get() key=|d| d.device_id let:device>
+          {
+            let id = device.device_id;
+            view! {
+              <li class="settings-device-row">
+                <Icon icon=i::LuMonitor attr:class="settings-device-icon" />
+                <span class="settings-device-name">{device.name}</span>
+                {if id == local {
+                  view! {
+                    <span class="settings-device-badge">
+                      {t!(i18n, settings.devices_this_device)}
+                    </span>
+                  }
+                    .into_any()
+                } else {
+                  view! {
+                    <button
+                      class="btn-ghost settings-action"
+                      disabled=move || busy.get()
+                      on:click=move |_| manager.with_value(|m| m.request_link(id))
+                      data-testid="device-sync-from"
+                    >
+                      <Icon icon=i::LuDownload />
+                      <span>{t!(i18n, settings.devices_sync_from)}</span>
+                    </button>
+                  }
+                    .into_any()
+                }}
+              </li>
+            }
+          }
+        </For>
+      </ul>
+
+      <div class="settings-row">
+        <button
+          class="btn-secondary settings-action"
+          disabled=move || busy.get()
+          on:click=move |_| manager.with_value(DeviceLinkManager::start_hosting)
+          data-testid="device-link-start"
+        >
+          <Icon icon=i::LuLink />
+          <span>{t!(i18n, settings.devices_link_new)}</span>
+        </button>
+        <p class="settings-hint">{t!(i18n, settings.devices_link_new_hint)}</p>
+      </div>
+
+      {move || match phase.get() {
+        LinkPhase::Idle => ().into_any(),
+        LinkPhase::Hosting => {
+          let url = web_sys::window()
+            .and_then(|w| w.location().origin().ok())
+            .map(|origin| devices::link_url(&origin, &local));
+          let svg = url.as_deref().and_then(devices::qr::qr_svg).unwrap_or_default();
+          view! {
+            <div class="settings-device-link" data-testid="device-link-hosting">
+              <div
+                class="settings-device-qr"
+                role="img"
+                aria-label=move || t_string!(i18n, settings.devices_qr_label)
+                inner_html=svg
+              ></div>
+              <p class="settings-hint">{t!(i18n, settings.devices_hosting_hint)}</p>
+              <code class="settings-device-url">{url}</code>
+              <CancelButton manager />
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Requesting { .. } => {
+          view! {
+            <div class="settings-device-link" data-testid="device-link-requesting">
+              <p class="settings-hint" aria-live="polite">
+                <Icon icon=i::LuLoaderCircle attr:class="settings-spinner" />
+                {t!(i18n, settings.devices_requesting)}
+              </p>
+              <CancelButton manager />
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Verifying { role, code, .. } => {
+          let actions = match role {
+            LinkRole::Holder => {
+              view! {
+                <p class="settings-hint">{t!(i18n, settings.devices_confirm_hint)}</p>
+                <div class="settings-button-row">
+                  <button
+                    class="btn-primary settings-action"
+                    on:click=move |_| manager.with_value(DeviceLinkManager::confirm)
+                    data-testid="device-link-confirm"
+                  >
+                    <Icon icon=i::LuCheck />
+                    <span>{t!(i18n, settings.devices_confirm)}</span>
+                  </button>
+                  <CancelButton manager />
+                </div>
+              }
+                .into_any()
+            }
+            LinkRole::Joiner => {
+              view! {
+                <p class="settings-hint">{t!(i18n, settings.devices_confirm_elsewhere)}</p>
+                <CancelButton manager />
+              }
+                .into_any()
+            }
+          };
+          view! {
+            <div class="settings-device-link" data-testid="device-link-verifying">
+              <span class="settings-label">{t!(i18n, settings.devices_code_label)}</span>
+              <p class="settings-device-code" data-testid="device-link-code">{code}</p>
+              {actions}
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Connecting { .. } => {
+          view! {
+            <div class="settings-device-link" data-testid="device-link-connecting">
+              <p class="settings-hint" aria-live="polite">
+                <Icon icon=i::LuLoaderCircle attr:class="settings-spinner" />
+                {t!(i18n, settings.devices_connecting)}
+              </p>
+              <CancelButton manager />
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Syncing { records, total, .. } => {
+          let progress = t_string!(i18n, settings.devices_syncing)
+            .replace("{count}", &records.to_string())
+            .replace("{total}", &total.to_string());
+          view! {
+            <div class="settings-device-link" data-testid="device-link-syncing">
+              <p class="settings-hint" aria-live="polite">
+                <Icon icon=i::LuLoaderCircle attr:class="settings-spinner" />
+                {progress}
+              </p>
+              <progress
+                class="settings-device-progress"
+                max=total.max(1).to_string()
+                value=records.to_string()
+              ></progress>
+              <CancelButton manager />
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Done { role, records } => {
+          let template = match role {
+            LinkRole::Holder => t_string!(i18n, settings.devices_done_sent),
+            LinkRole::Joiner => t_string!(i18n, settings.devices_done_imported),
+          };
+          let message = template.replace("{count}", &records.to_string());
+          view! {
+            <div class="settings-device-link" data-testid="device-link-done">
+              <p class="settings-status" aria-live="polite">
+                <Icon icon=i::LuCheck />
+                {message}
+              </p>
+              <CloseButton phase />
+            </div>
+          }
+            .into_any()
+        }
+        LinkPhase::Failed(reason) => {
+          let message = match reason {
+            LinkFailure::Cancelled => t_string!(i18n, settings.devices_failed_cancelled),
+            LinkFailure::Crypto => t_string!(i18n, settings.devices_failed_crypto),
+            LinkFailure::Connection => t_string!(i18n, settings.devices_failed_connection),
+            LinkFailure::Storage => t_string!(i18n, settings.devices_failed_storage),
+          };
+          view! {
+            <div class="settings-device-link" data-testid="device-link-failed">
+              <p class="settings-status settings-device-error" role="alert">
+                <Icon icon=i::LuX />
+                {message}
+              </p>
+              <CloseButton phase />
+            </div>
+          }
+            .into_any()
+        }
+      }}
+    </section>
+  }
+}
+
+/// Aborts the link in progress and tells the other device.
+#[component]
+fn CancelButton(manager: StoredValue<DeviceLinkManager>) -> impl IntoView {
+  let i18n = i18n::use_i18n();
+  view! {
+    <button
+      class="btn-ghost settings-action"
+      on:click=move |_| manager.with_value(DeviceLinkManager::cancel)
+      data-testid="device-link-cancel"
+    >
+      <Icon icon=i::LuX />
+      <span>{t!(i18n, common.cancel)}</span>
+    </button>
+  }
+}
+
+/// Dismisses a finished or failed link.
+#[component]
+fn CloseButton(phase: RwSignal<LinkPhase>) -> impl IntoView {
+  let i18n = i18n::use_i18n();
+  view! {
+    <button
+      class="btn-ghost settings-action"
+      on:click=move |_| phase.set(LinkPhase::Idle)
+      data-testid="device-link-close"
+    >
+      <span>{t!(i18n, common.close)}</span>
+    </button>
+  }
+}
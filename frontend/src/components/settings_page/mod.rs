@@ -16,6 +16,7 @@ mod class_helpers;
 mod data_management_helpers;
 mod data_management_section;
 mod device_select;
+mod devices_section;
 mod mic_level_feedback;
 mod notifications_helpers;
 mod notifications_section;
@@ -9,6 +9,7 @@ use super::av_helpers::DeviceCache;
 use super::av_section::AvSection;
 use super::background_section::BackgroundSection;
 use super::data_management_section::DataManagementSection;
+use super::devices_section::DevicesSection;
 use super::notifications_section::NotificationsSection;
 use super::privacy_section::PrivacySection;
 use crate::components::discovery::BlacklistManagementPanel;
@@ -273,6 +274,14 @@ pub fn SettingsPage() -> impl IntoView {
             >
               <Icon icon=i::LuDatabase />
             </a>
+            <a
+              class="settings-quick-nav-link"
+              href="#devices-heading"
+              aria-label=move || t_string!(i18n, settings.devices)
+              title=move || t_string!(i18n, settings.devices)
+            >
+              <Icon icon=i::LuMonitor />
+            </a>
             <a
               class="settings-quick-nav-link"
               href="#blacklist-heading"
@@ -297,6 +306,7 @@ pub fn SettingsPage() -> impl IntoView {
           <PrivacySection />
           <NotificationsSection />
           <DataManagementSection />
+          <DevicesSection />
 
           // Privacy -- blacklist management
           <section class="settings-section" aria-labelledby="blacklist-heading">
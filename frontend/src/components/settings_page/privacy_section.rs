@@ -1,4 +1,5 @@
-//! Privacy toggles (online-status visibility, read receipts).
+//! Privacy toggles (online-status visibility, read receipts, image
+//! metadata stripping).
 //!
 //! The blacklist management panel is rendered by the parent shell via
 //! the existing `BlacklistManagementPanel` component.
@@ -21,6 +22,7 @@ pub fn PrivacySection() -> impl IntoView {
 
   let online_visible = Memo::new(move |_| settings.get().online_status_visible);
   let read_receipts = Memo::new(move |_| settings.get().read_receipts);
+  let strip_metadata = Memo::new(move |_| settings.get().strip_metadata);
 
   let toggle_online = {
     let user_status = user_status.clone();
@@ -36,6 +38,9 @@ pub fn PrivacySection() -> impl IntoView {
   let toggle_receipts = move |_| {
     settings.update(|s| s.read_receipts = !s.read_receipts);
   };
+  let toggle_strip_metadata = move |_| {
+    settings.update(|s| s.strip_metadata = !s.strip_metadata);
+  };
 
   view! {
     <section class="settings-section" aria-labelledby="privacy-heading">
@@ -79,6 +84,24 @@ pub fn PrivacySection() -> impl IntoView {
           <span class="settings-toggle-thumb"></span>
         </button>
       </div>
+
+      // Image metadata stripping
+      <div class="settings-row settings-toggle-row">
+        <div class="settings-toggle-meta">
+          <label class="settings-label">{t!(i18n, settings.strip_metadata)}</label>
+          <p class="settings-hint">{t!(i18n, settings.strip_metadata_hint)}</p>
+        </div>
+        <button
+          class=move || toggle_root_class(strip_metadata.get())
+          role="switch"
+          aria-label=move || t_string!(i18n, settings.strip_metadata)
+          aria-checked=move || strip_metadata.get().to_string()
+          on:click=toggle_strip_metadata
+          data-testid="toggle-strip-metadata"
+        >
+          <span class="settings-toggle-thumb"></span>
+        </button>
+      </div>
     </section>
   }
 }
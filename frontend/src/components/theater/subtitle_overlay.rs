@@ -7,6 +7,11 @@
 //! * `overlay_settings.subtitle` — user-customised appearance
 //!   (position / font size / text color / background opacity).
 //! * `subtitle.visible` — whether the track is visible at all.
+//! * `active_subtitle_cues` — styled ASS / SSA cues. When non-empty
+//!   they replace the plain line and are laid out on a full-surface
+//!   layer using the script's own fonts, colours, alignment, position
+//!   and karaoke timing; the viewer's appearance settings only apply
+//!   to plain SRT / WebVTT cues.
 //!
 //! The component is deliberately "dumb" — it contains no scheduling
 //! logic. Advancement of the active cue is owned by
@@ -14,8 +19,13 @@
 //! re-render reactively without any local timers.
 
 use leptos::prelude::*;
+use message::types::{SubtitleCueStyle, SubtitleEntry, SubtitleSpan};
 
-use crate::theater::{SubtitlePosition, use_theater_state};
+use crate::theater::{SubtitlePosition, TheaterState, use_theater_state};
+
+/// Font size used when a styled cue does not specify one, in per-mille
+/// of the video height (matches a typical 1080p script's 54px).
+const DEFAULT_CUE_FONT_PERMILLE: u16 = 50;
 
 /// Subtitle overlay — renders the current cue above or below the
 /// video depending on the viewer's preference.
@@ -31,6 +41,14 @@ pub fn SubtitleOverlay() -> impl IntoView {
       .with(|t| t.as_ref().is_some_and(|t| t.visible));
     settings_visible && state.active_subtitle_text.with(Option::is_some)
   };
+  let styled = move || state.active_subtitle_cues.with(|cues| !cues.is_empty());
+  let show_plain = move || visible() && !styled();
+  let show_styled = move || {
+    styled()
+      && state
+        .subtitle
+        .with(|t| t.as_ref().is_some_and(|t| t.visible))
+  };
 
   let container_class = move || {
     let pos = state.overlay_settings.with(|s| s.subtitle.position);
@@ -56,7 +74,24 @@ pub fn SubtitleOverlay() -> impl IntoView {
   };
 
   view! {
-    <Show when=visible>
+    <Show when=show_styled>
+      <div
+        class="subtitle-overlay subtitle-overlay--styled"
+        role="region"
+        aria-live="polite"
+        data-testid="theater-subtitle-overlay"
+      >
+        {move || {
+          state
+            .active_subtitle_cues
+            .get()
+            .into_iter()
+            .map(|cue| styled_cue(state, cue))
+            .collect_view()
+        }}
+      </div>
+    </Show>
+    <Show when=show_plain>
       <div
         class=container_class
         role="region"
@@ -70,3 +105,137 @@ pub fn SubtitleOverlay() -> impl IntoView {
     </Show>
   }
 }
+
+/// Render one styled cue as an absolutely positioned paragraph.
+fn styled_cue(state: TheaterState, cue: SubtitleEntry) -> impl IntoView {
+  let Some(style) = cue.style else {
+    return ().into_any();
+  };
+  // Karaoke timing is in track time; the active cue list is rebuilt
+  // whenever the owner changes the offset, so reading it once is fine.
+  let offset_ms = state
+    .subtitle
+    .with_untracked(|t| t.as_ref().map_or(0, |t| t.offset_ms));
+  let cue_start_ms = i64::from(cue.start_ms) + i64::from(offset_ms);
+  let spans = style
+    .spans
+    .iter()
+    .map(|span| {
+      let karaoke_start_ms = span.karaoke.map(|k| cue_start_ms + i64::from(k.offset_ms));
+      // Syllables switch from the secondary look to the sung look when
+      // playback reaches their start, like `\k` in VSFilter.
+      let class = move || match karaoke_start_ms {
+        Some(start)
+          if i64::try_from(state.playback.with(|p| p.current_time_ms)).unwrap_or(i64::MAX)
+            < start =>
+        {
+          "subtitle-cue__span subtitle-cue__span--pending"
+        }
+        _ => "subtitle-cue__span",
+      };
+      view! {
+        <span class=class style=span_style(span)>
+          {span.text.clone()}
+        </span>
+      }
+    })
+    .collect_view();
+  view! {
+    <p class="subtitle-cue" style=cue_style(&style)>
+      {spans}
+    </p>
+  }
+  .into_any()
+}
+
+/// Inline CSS for a cue box: font, size and placement.
+///
+/// Sizes use `cqh` so they scale with the overlay (a size container
+/// spanning the video surface). Alignment follows the numpad layout:
+/// 1–3 bottom, 4–6 middle, 7–9 top; left / centre / right within each
+/// row. An explicit `\pos` anchors that point of the box at the given
+/// coordinates instead of using the margins.
+fn cue_style(style: &SubtitleCueStyle) -> String {
+  let alignment = if (1..=9).contains(&style.alignment) {
+    style.alignment
+  } else {
+    2
+  };
+  let column = (alignment - 1) % 3;
+  let row = (alignment - 1) / 3;
+  let font_permille = style
+    .font_size_permille
+    .unwrap_or(DEFAULT_CUE_FONT_PERMILLE);
+  let mut css = format!(
+    "font-size: {:.1}cqh; text-align: {};",
+    f64::from(font_permille) / 10.0,
+    ["left", "center", "right"][usize::from(column)],
+  );
+  if let Some(family) = style.font_name.as_deref().and_then(sanitize_font_name) {
+    css.push_str(&format!(" font-family: \"{family}\", sans-serif;"));
+  }
+
+  let translate_x = [0, -50, -100][usize::from(column)];
+  let translate_y = [-100, -50, 0][usize::from(row)];
+  if let Some(anchor) = style.position {
+    css.push_str(&format!(
+      " left: {:.1}%; top: {:.1}%; transform: translate({translate_x}%, {translate_y}%);",
+      f64::from(anchor.x_permille) / 10.0,
+      f64::from(anchor.y_permille) / 10.0,
+    ));
+    return css;
+  }
+
+  let margin = f64::from(style.margin_v_permille) / 10.0;
+  let horizontal = match column {
+    0 => "left: 2%;".to_string(),
+    1 => "left: 50%;".to_string(),
+    _ => "right: 2%;".to_string(),
+  };
+  let vertical = match row {
+    0 => format!("bottom: {margin:.1}%;"),
+    1 => "top: 50%;".to_string(),
+    _ => format!("top: {margin:.1}%;"),
+  };
+  let transform_x = if column == 1 { -50 } else { 0 };
+  let transform_y = if row == 1 { -50 } else { 0 };
+  css.push_str(&format!(
+    " {horizontal} {vertical} transform: translate({transform_x}%, {transform_y}%);"
+  ));
+  css
+}
+
+/// Inline CSS for one run of text inside a cue.
+fn span_style(span: &SubtitleSpan) -> String {
+  let mut css = format!(
+    "font-weight: {}; font-style: {};",
+    if span.bold { 700 } else { 400 },
+    if span.italic { "italic" } else { "normal" },
+  );
+  let decoration = match (span.underline, span.strike_out) {
+    (true, true) => Some("underline line-through"),
+    (true, false) => Some("underline"),
+    (false, true) => Some("line-through"),
+    (false, false) => None,
+  };
+  if let Some(decoration) = decoration {
+    css.push_str(&format!(" text-decoration: {decoration};"));
+  }
+  if let Some(color) = span.color {
+    css.push_str(&format!(" color: #{color:06x};"));
+  }
+  let outline = span.outline_color.unwrap_or(0);
+  css.push_str(&format!(" --subtitle-cue-outline: #{outline:06x};"));
+  css
+}
+
+/// Keep only characters that are safe inside a quoted CSS font-family
+/// value. Returns `None` when nothing usable is left.
+fn sanitize_font_name(name: &str) -> Option<String> {
+  let cleaned: String = name
+    .chars()
+    .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'))
+    .collect();
+  let cleaned = cleaned.trim();
+  (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
@@ -1,6 +1,6 @@
 //! Subtitle settings panel (Req 12.4a).
 //!
-//! Houses three groups of controls:
+//! Houses four groups of controls:
 //!
 //! 1. **Upload / clear** (owner-only) — picks a `.srt` or `.vtt` file,
 //!    parses it through [`parse_subtitle_file`], installs the track
@@ -17,6 +17,9 @@
 //!    background opacity. Changes are persisted to `localStorage` via
 //!    [`TheaterState::persist_overlay_settings`].
 //!
+//! 4. **Timing / cue editing** (owner-only) — delegated to
+//!    [`SubtitleTimingPanel`] once a track is loaded.
+//!
 //! The panel is a plain form and integrates with the parent drawer or
 //! dropdown; it does not manage its own open/close state.
 
@@ -30,11 +33,11 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Event, HtmlInputElement};
 
-use crate::components::theater::CopyrightNotice;
+use crate::components::theater::{CopyrightNotice, SubtitleTimingPanel};
 use crate::i18n;
 use crate::theater::{
-  SubtitlePosition, SubtitleTrack, TheaterRole, apply_subtitle_track, parse_subtitle_file,
-  use_theater_state,
+  SubtitlePosition, SubtitleTrack, TheaterRole, apply_subtitle_track, clear_active_subtitle,
+  parse_subtitle_file, use_theater_state,
 };
 use crate::webrtc::try_use_webrtc_manager;
 
@@ -100,6 +103,7 @@ pub fn SubtitleSettingsPanel() -> impl IntoView {
         filename: name.clone(),
         entries: entries.clone(),
         visible: true,
+        offset_ms: 0,
       };
       apply_subtitle_track(&state, track);
       upload_error.set(None);
@@ -121,7 +125,7 @@ pub fn SubtitleSettingsPanel() -> impl IntoView {
       return;
     }
     state.subtitle.set(None);
-    state.active_subtitle_text.set(None);
+    clear_active_subtitle(&state);
     upload_error.set(None);
     let Some(room_id) = state.room_id.get_untracked() else {
       return;
@@ -140,7 +144,7 @@ pub fn SubtitleSettingsPanel() -> impl IntoView {
       }
     });
     // Force a re-render of the active cue.
-    state.active_subtitle_text.set(None);
+    clear_active_subtitle(&state);
   };
 
   // --- Appearance controls -----------------------------------------------
@@ -242,7 +246,7 @@ pub fn SubtitleSettingsPanel() -> impl IntoView {
           <input
             node_ref=file_ref
             type="file"
-            accept=".srt,.vtt,text/plain,text/vtt"
+            accept=".srt,.vtt,.ass,.ssa,text/plain,text/vtt"
             class="subtitle-settings__file-input"
             on:change=handle_file_change
           />
@@ -333,6 +337,10 @@ pub fn SubtitleSettingsPanel() -> impl IntoView {
           data-testid="subtitle-bg-opacity"
         />
       </label>
+
+      <Show when=move || is_owner() && state.subtitle.with(Option::is_some)>
+        <SubtitleTimingPanel />
+      </Show>
     </section>
   }
 }
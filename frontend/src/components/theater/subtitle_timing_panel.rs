@@ -0,0 +1,449 @@
+//! Owner-only subtitle timing and cue editor.
+//!
+//! Rendered inside [`super::SubtitleSettingsPanel`] once a track is
+//! loaded. Four groups of controls:
+//!
+//! 1. **Offset** — step buttons and a millisecond field. Every change
+//!    is relayed as a small `SubtitleOffset` frame so viewers follow
+//!    live while the owner nudges the timing.
+//! 2. **Stretch** — two "cue time → should appear at" anchors that
+//!    rescale the whole track (frame-rate fixes). The "Now" buttons
+//!    fill in the current playback position.
+//! 3. **Cues** — a filterable list of cues with inline start / end /
+//!    text editing, deletion and a row for adding a new cue.
+//! 4. **Export** — download the adjusted track as SRT or WebVTT.
+//!
+//! Stretches and cue edits re-send the whole track as `SubtitleData`
+//! (see [`crate::theater::subtitle_edit`]).
+
+use icondata as i;
+use leptos::prelude::*;
+use leptos_i18n::{t, t_string};
+use leptos_icons::Icon;
+use message::datachannel::{DataChannelMessage, SubtitleEntry};
+
+use crate::i18n;
+use crate::theater::{
+  StretchAnchor, SubtitleEditError, TheaterState, edit_cue, export_filename, export_srt,
+  export_vtt, format_cue_time, insert_cue, owner_edit_entries, owner_set_offset, parse_cue_time,
+  remove_cue, stretch_entries, track_time_ms, use_theater_state,
+};
+use crate::utils::trigger_download;
+use crate::webrtc::try_use_webrtc_manager;
+
+/// Maximum number of cue rows rendered at once; the filter narrows
+/// longer tracks down.
+const CUE_EDITOR_LIMIT: usize = 100;
+
+/// Offset step buttons, in milliseconds.
+const OFFSET_STEPS_MS: [i64; 4] = [-1_000, -100, 100, 1_000];
+
+/// Default length of a cue added from the editor.
+const NEW_CUE_DURATION_MS: u32 = 2_000;
+
+fn broadcast(frame: &DataChannelMessage) {
+  if let Some(manager) = try_use_webrtc_manager() {
+    manager.broadcast_data_channel_message(frame);
+  }
+}
+
+/// Boxed cue edit handed to [`owner_edit_entries`].
+type CueEdit = Box<dyn FnOnce(&mut Vec<SubtitleEntry>) -> Result<(), SubtitleEditError>>;
+
+/// Format an offset step button label, e.g. `-0.1 s`.
+fn format_step(step_ms: i64) -> String {
+  let sign = if step_ms < 0 { '-' } else { '+' };
+  let abs = step_ms.unsigned_abs();
+  if abs % 1_000 == 0 {
+    format!("{sign}{} s", abs / 1_000)
+  } else {
+    format!("{sign}{}.{} s", abs / 1_000, abs % 1_000 / 100)
+  }
+}
+
+/// Format an offset for display, e.g. `+1.250 s`.
+fn format_offset(offset_ms: i32) -> String {
+  let sign = if offset_ms < 0 { '-' } else { '+' };
+  let abs = offset_ms.unsigned_abs();
+  format!("{sign}{}.{:03} s", abs / 1_000, abs % 1_000)
+}
+
+/// Current owner playback position, in video milliseconds.
+fn playback_ms(state: &TheaterState) -> u32 {
+  u32::try_from(state.playback.get_untracked().current_time_ms).unwrap_or(u32::MAX)
+}
+
+/// Owner subtitle timing / editing panel.
+#[component]
+pub fn SubtitleTimingPanel() -> impl IntoView {
+  let state = use_theater_state();
+  let i18n = i18n::use_i18n();
+
+  let error = RwSignal::new(Option::<String>::None);
+  let report = move |result: Result<(), SubtitleEditError>| match result {
+    Ok(()) => error.set(None),
+    Err(err) => {
+      let template = t_string!(i18n, theater.subtitle_edit_failed).to_string();
+      error.set(Some(template.replace("{reason}", &err.to_string())));
+    }
+  };
+  let invalid_time = move || {
+    error.set(Some(
+      t_string!(i18n, theater.subtitle_time_invalid).to_string(),
+    ));
+  };
+  let edit = move |f: CueEdit| {
+    report(owner_edit_entries(&state, f).map(|frame| broadcast(&frame)));
+  };
+
+  // --- Offset ---------------------------------------------------------------
+  let offset_ms = move || {
+    state
+      .subtitle
+      .with(|t| t.as_ref().map_or(0, |t| t.offset_ms))
+  };
+  let set_offset = move |value: i64| {
+    if let Some(frame) = owner_set_offset(&state, value) {
+      broadcast(&frame);
+    }
+  };
+  let handle_offset_input = move |ev| {
+    if let Ok(value) = event_target_value(&ev).trim().parse::<i64>() {
+      set_offset(value);
+    }
+  };
+
+  // --- Stretch --------------------------------------------------------------
+  let anchors = [
+    (RwSignal::new(String::new()), RwSignal::new(String::new())),
+    (RwSignal::new(String::new()), RwSignal::new(String::new())),
+  ];
+  let handle_stretch = move |_| {
+    let parse_anchor = |(track, target): &(RwSignal<String>, RwSignal<String>)| {
+      Some(StretchAnchor {
+        track_ms: parse_cue_time(&track.get_untracked())?,
+        target_ms: parse_cue_time(&target.get_untracked())?,
+      })
+    };
+    let (Some(first), Some(second)) = (parse_anchor(&anchors[0]), parse_anchor(&anchors[1])) else {
+      invalid_time();
+      return;
+    };
+    edit(Box::new(move |entries| {
+      *entries = stretch_entries(entries, first, second)?;
+      Ok(())
+    }));
+  };
+
+  // --- Cue list -------------------------------------------------------------
+  let filter = RwSignal::new(String::new());
+  let visible_cues = move || {
+    let needle = filter.get().to_lowercase();
+    state.subtitle.with(|t| {
+      t.as_ref().map_or_else(Vec::new, |t| {
+        t.entries
+          .iter()
+          .enumerate()
+          .filter(|(_, e)| needle.is_empty() || e.text.to_lowercase().contains(&needle))
+          .take(CUE_EDITOR_LIMIT)
+          .map(|(idx, e)| (idx, e.clone()))
+          .collect::<Vec<_>>()
+      })
+    })
+  };
+  let limit_label = move || {
+    t_string!(i18n, theater.subtitle_cue_limit)
+      .to_string()
+      .replace("{count}", &CUE_EDITOR_LIMIT.to_string())
+  };
+  let list_truncated = move || visible_cues().len() == CUE_EDITOR_LIMIT;
+
+  let new_start = RwSignal::new(String::new());
+  let new_end = RwSignal::new(String::new());
+  let new_text = RwSignal::new(String::new());
+  let fill_new_from_playback = move |_| {
+    let offset = state
+      .subtitle
+      .with_untracked(|t| t.as_ref().map_or(0, |t| t.offset_ms));
+    let start = track_time_ms(playback_ms(&state), offset).unwrap_or_default();
+    new_start.set(format_cue_time(start, ','));
+    new_end.set(format_cue_time(
+      start.saturating_add(NEW_CUE_DURATION_MS),
+      ',',
+    ));
+  };
+  let handle_add = move |_| {
+    let (Some(start), Some(end)) = (
+      parse_cue_time(&new_start.get_untracked()),
+      parse_cue_time(&new_end.get_untracked()),
+    ) else {
+      invalid_time();
+      return;
+    };
+    let text = new_text.get_untracked();
+    edit(Box::new(move |entries| {
+      insert_cue(entries, start, end, &text).map(|_| ())
+    }));
+    if error.with_untracked(Option::is_none) {
+      new_text.set(String::new());
+    }
+  };
+
+  // --- Export ---------------------------------------------------------------
+  let export = move |extension: &'static str| {
+    let Some((filename, content, mime)) = state.subtitle.with_untracked(|t| {
+      t.as_ref().map(|t| {
+        let name = export_filename(&t.filename, extension);
+        if extension == "vtt" {
+          (name, export_vtt(&t.entries, t.offset_ms), "text/vtt")
+        } else {
+          (
+            name,
+            export_srt(&t.entries, t.offset_ms),
+            "application/x-subrip",
+          )
+        }
+      })
+    }) else {
+      return;
+    };
+    trigger_download(&filename, mime, &content);
+  };
+
+  view! {
+    <section class="subtitle-timing" data-testid="subtitle-timing-panel">
+      <h4 class="subtitle-timing__title">{t!(i18n, theater.subtitle_timing)}</h4>
+
+      <fieldset class="subtitle-settings__appearance">
+        <legend>{t!(i18n, theater.subtitle_offset)}</legend>
+        <div class="subtitle-timing__offset">
+          {OFFSET_STEPS_MS
+            .into_iter()
+            .map(|step| {
+              view! {
+                <button
+                  type="button"
+                  class="btn btn--ghost btn--sm"
+                  on:click=move |_| set_offset(i64::from(offset_ms()) + step)
+                >
+                  {format_step(step)}
+                </button>
+              }
+            })
+            .collect_view()}
+          <output class="subtitle-timing__offset-value" aria-live="polite">
+            {move || format_offset(offset_ms())}
+          </output>
+          <button
+            type="button"
+            class="btn btn--ghost btn--sm"
+            on:click=move |_| set_offset(0)
+            disabled=move || offset_ms() == 0
+          >
+            <Icon icon=i::LuRotateCcw />
+            <span>{t!(i18n, theater.subtitle_offset_reset)}</span>
+          </button>
+        </div>
+        <label class="subtitle-settings__row">
+          <span>{t!(i18n, theater.subtitle_offset_ms)}</span>
+          <input
+            type="number"
+            class="input subtitle-timing__number"
+            step="50"
+            prop:value=move || offset_ms().to_string()
+            on:change=handle_offset_input
+            data-testid="subtitle-offset-input"
+          />
+        </label>
+      </fieldset>
+
+      <fieldset class="subtitle-settings__appearance">
+        <legend>{t!(i18n, theater.subtitle_stretch)}</legend>
+        <p class="subtitle-settings__hint">{t!(i18n, theater.subtitle_stretch_hint)}</p>
+        {anchors
+          .into_iter()
+          .map(|(track, target)| {
+            view! {
+              <div class="subtitle-timing__anchor">
+                <input
+                  type="text"
+                  class="input subtitle-timing__time"
+                  placeholder="00:00:00,000"
+                  aria-label=move || t_string!(i18n, theater.subtitle_stretch_cue_time)
+                  prop:value=move || track.get()
+                  on:input=move |ev| track.set(event_target_value(&ev))
+                />
+                <Icon icon=i::LuArrowRight />
+                <input
+                  type="text"
+                  class="input subtitle-timing__time"
+                  placeholder="00:00:00,000"
+                  aria-label=move || t_string!(i18n, theater.subtitle_stretch_target_time)
+                  prop:value=move || target.get()
+                  on:input=move |ev| target.set(event_target_value(&ev))
+                />
+                <button
+                  type="button"
+                  class="btn btn--ghost btn--sm"
+                  on:click=move |_| target.set(format_cue_time(playback_ms(&state), ','))
+                >
+                  {t!(i18n, theater.subtitle_stretch_use_now)}
+                </button>
+              </div>
+            }
+          })
+          .collect_view()}
+        <button
+          type="button"
+          class="btn btn--secondary btn--sm subtitle-timing__apply"
+          on:click=handle_stretch
+          data-testid="subtitle-stretch-apply"
+        >
+          {t!(i18n, theater.subtitle_stretch_apply)}
+        </button>
+      </fieldset>
+
+      <fieldset class="subtitle-settings__appearance">
+        <legend>{t!(i18n, theater.subtitle_cues)}</legend>
+        <input
+          type="search"
+          class="input"
+          placeholder=move || t_string!(i18n, theater.subtitle_cue_filter)
+          prop:value=move || filter.get()
+          on:input=move |ev| filter.set(event_target_value(&ev))
+        />
+        <ul class="subtitle-timing__cues" data-testid="subtitle-cue-list">
+          <For
+            each=visible_cues
+            key=|(idx, cue)| (*idx, cue.start_ms, cue.end_ms, cue.text.clone())
+            children=move |(idx, cue)| {
+              let start = RwSignal::new(format_cue_time(cue.start_ms, ','));
+              let end = RwSignal::new(format_cue_time(cue.end_ms, ','));
+              let text = RwSignal::new(cue.text.clone());
+              let save = move |_| {
+                let (Some(s), Some(e)) = (
+                  parse_cue_time(&start.get_untracked()),
+                  parse_cue_time(&end.get_untracked()),
+                ) else {
+                  invalid_time();
+                  return;
+                };
+                let t = text.get_untracked();
+                edit(Box::new(move |entries| edit_cue(entries, idx, s, e, &t).map(|_| ())));
+              };
+              let delete = move |_| {
+                edit(Box::new(move |entries| remove_cue(entries, idx).map(|_| ())));
+              };
+              view! {
+                <li class="subtitle-timing__cue">
+                  <div class="subtitle-timing__cue-times">
+                    <input
+                      type="text"
+                      class="input subtitle-timing__time"
+                      aria-label=move || t_string!(i18n, theater.subtitle_cue_start)
+                      prop:value=move || start.get()
+                      on:input=move |ev| start.set(event_target_value(&ev))
+                    />
+                    <input
+                      type="text"
+                      class="input subtitle-timing__time"
+                      aria-label=move || t_string!(i18n, theater.subtitle_cue_end)
+                      prop:value=move || end.get()
+                      on:input=move |ev| end.set(event_target_value(&ev))
+                    />
+                  </div>
+                  <textarea
+                    class="input subtitle-timing__text"
+                    rows="2"
+                    aria-label=move || t_string!(i18n, theater.subtitle_cue_text)
+                    prop:value=move || text.get()
+                    on:input=move |ev| text.set(event_target_value(&ev))
+                  ></textarea>
+                  <div class="subtitle-timing__cue-actions">
+                    <button type="button" class="btn btn--secondary btn--sm" on:click=save>
+                      <Icon icon=i::LuCheck />
+                      <span>{t!(i18n, theater.subtitle_cue_save)}</span>
+                    </button>
+                    <button
+                      type="button"
+                      class="btn btn--ghost btn--sm"
+                      on:click=delete
+                      aria-label=move || t_string!(i18n, theater.subtitle_cue_delete)
+                      title=move || t_string!(i18n, theater.subtitle_cue_delete)
+                    >
+                      <Icon icon=i::LuTrash2 />
+                    </button>
+                  </div>
+                </li>
+              }
+            }
+          />
+        </ul>
+        <Show when=list_truncated>
+          <p class="subtitle-settings__hint">{limit_label}</p>
+        </Show>
+
+        <div class="subtitle-timing__cue subtitle-timing__cue--new">
+          <div class="subtitle-timing__cue-times">
+            <input
+              type="text"
+              class="input subtitle-timing__time"
+              placeholder="00:00:00,000"
+              aria-label=move || t_string!(i18n, theater.subtitle_cue_start)
+              prop:value=move || new_start.get()
+              on:input=move |ev| new_start.set(event_target_value(&ev))
+            />
+            <input
+              type="text"
+              class="input subtitle-timing__time"
+              placeholder="00:00:02,000"
+              aria-label=move || t_string!(i18n, theater.subtitle_cue_end)
+              prop:value=move || new_end.get()
+              on:input=move |ev| new_end.set(event_target_value(&ev))
+            />
+            <button
+              type="button"
+              class="btn btn--ghost btn--sm"
+              on:click=fill_new_from_playback
+            >
+              {t!(i18n, theater.subtitle_stretch_use_now)}
+            </button>
+          </div>
+          <textarea
+            class="input subtitle-timing__text"
+            rows="2"
+            aria-label=move || t_string!(i18n, theater.subtitle_cue_text)
+            prop:value=move || new_text.get()
+            on:input=move |ev| new_text.set(event_target_value(&ev))
+          ></textarea>
+          <button
+            type="button"
+            class="btn btn--secondary btn--sm"
+            on:click=handle_add
+            data-testid="subtitle-cue-add"
+          >
+            <Icon icon=i::LuPlus />
+            <span>{t!(i18n, theater.subtitle_cue_add)}</span>
+          </button>
+        </div>
+      </fieldset>
+
+      <Show when=move || error.get().is_some()>
+        <p class="subtitle-settings__error" role="alert">
+          {move || error.get().unwrap_or_default()}
+        </p>
+      </Show>
+
+      <div class="subtitle-timing__export">
+        <button type="button" class="btn btn--ghost btn--sm" on:click=move |_| export("srt")>
+          <Icon icon=i::LuDownload />
+          <span>{t!(i18n, theater.subtitle_export_srt)}</span>
+        </button>
+        <button type="button" class="btn btn--ghost btn--sm" on:click=move |_| export("vtt")>
+          <Icon icon=i::LuDownload />
+          <span>{t!(i18n, theater.subtitle_export_vtt)}</span>
+        </button>
+      </div>
+    </section>
+  }
+}
@@ -37,20 +37,29 @@ use wasm_bindgen::closure::Closure;
 use crate::components::theater::{
   CopyrightNotice, DanmakuCanvas, DanmakuInput, DanmakuSettingsPanel, SubtitleOverlay,
   SubtitleSettingsPanel, TheaterChatPanel, TheaterGraceBanner, TheaterMemberPanel,
-  TheaterPlaybackControls, TheaterVideoPlayer,
+  TheaterPlaybackControls, TheaterQueuePanel, TheaterVideoPlayer,
 };
 use crate::i18n;
 use crate::state::use_app_state;
 use crate::theater::{
-  TheaterRole, apply_theater_inbound, classify_theater_inbound, use_theater_state,
+  CLOCK_PING_INTERVAL_MS, TheaterInbound, TheaterRole, answer_clock_ping, apply_clock_pong,
+  apply_theater_inbound, build_clock_ping, classify_theater_inbound, queue_snapshot_frames,
+  subtitle_snapshot_frames, use_theater_state,
 };
 use crate::webrtc::{TheaterPeerEvent, try_use_webrtc_manager};
 
+/// Wall-clock time in nanoseconds since the Unix epoch, the unit of
+/// every theater `timestamp_nanos` field.
+fn now_nanos() -> u64 {
+  (js_sys::Date::now() as u64).saturating_mul(1_000_000)
+}
+
 /// Right-side panel tab selection.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SidePanelTab {
   Chat,
   Members,
+  Queue,
 }
 
 /// Theater page root.
@@ -112,14 +121,45 @@ pub fn TheaterPage(
   // previous session (capturing an old `state` snapshot) cannot fire.
   if let Some(manager) = try_use_webrtc_manager() {
     let manager_for_cleanup = manager.clone();
-    manager.set_on_theater_message(move |_peer_id, msg| {
+    manager.set_on_theater_message(move |peer_id, msg| {
       // Classify + apply. Non-theater variants are impossible here
       // because the WebRTC layer only forwards the recognised set,
       // but we still unwrap defensively.
-      if let Ok(inbound) = classify_theater_inbound(msg) {
-        let _ = apply_theater_inbound(&state, inbound, |sender_id| {
-          app.resolve_user_display_name(sender_id)
-        });
+      let Ok(inbound) = classify_theater_inbound(msg) else {
+        return;
+      };
+      match inbound {
+        // Clock sync needs the peer id and the receive time, so it is
+        // handled here rather than in the router.
+        TheaterInbound::ClockPing(probe) => {
+          let recv_nanos = now_nanos();
+          let in_room = state
+            .room_id
+            .with_untracked(|r| r.as_ref() == Some(&probe.room_id));
+          if !in_room || state.my_role.get_untracked() != TheaterRole::Owner {
+            return;
+          }
+          let Some(mgr) = try_use_webrtc_manager() else {
+            return;
+          };
+          leptos::task::spawn_local(async move {
+            let reply = answer_clock_ping(&probe, recv_nanos, now_nanos());
+            let _ = mgr
+              .send_encrypted_data_channel_message(
+                peer_id,
+                &DataChannelMessage::TheaterClockPong(reply),
+              )
+              .await;
+          });
+        }
+        TheaterInbound::ClockPong(reply) => {
+          let _ = apply_clock_pong(&state, &reply, now_nanos());
+        }
+        inbound => {
+          let _ = apply_theater_inbound(&state, inbound, |sender_id| {
+            app.resolve_user_display_name(sender_id)
+          });
+        }
       }
     });
 
@@ -161,6 +201,32 @@ pub fn TheaterPage(
               mgr.publish_local_stream_to(&pid, &s);
             });
           }
+          // Late joiners need the watch queue, the current item and
+          // the (possibly edited) subtitle track; the relay only
+          // carries changes from here on.
+          if let Some(mgr) = try_use_webrtc_manager() {
+            let pid = peer_id.clone();
+            let _ = crate::utils::set_timeout_once(500, move || {
+              let mut frames = queue_snapshot_frames(&state, now_nanos());
+              frames.extend(subtitle_snapshot_frames(&state));
+              if frames.is_empty() {
+                return;
+              }
+              leptos::task::spawn_local(async move {
+                for frame in frames {
+                  if let Err(e) = mgr
+                    .send_encrypted_data_channel_message(pid.clone(), &frame)
+                    .await
+                  {
+                    web_sys::console::warn_1(
+                      &format!("[theater] Failed to send theater snapshot: {e}").into(),
+                    );
+                    break;
+                  }
+                }
+              });
+            });
+          }
         }
         (_, TheaterPeerEvent::Connected) if owner.as_ref() == Some(&peer_id) => {
           // Viewer: owner peer just came back — clear the banner.
@@ -343,6 +409,16 @@ pub fn TheaterPage(
           manager.broadcast_data_channel_message(&DataChannelMessage::TheaterChatText(payload));
         }
       }
+
+      // 3. Relay watch-queue changes (owner edits and accepted proposals).
+      let queue_frames = state.drain_queue_broadcast();
+      if !queue_frames.is_empty()
+        && let Some(manager) = try_use_webrtc_manager()
+      {
+        for frame in queue_frames {
+          manager.broadcast_data_channel_message(&frame);
+        }
+      }
     },
     std::time::Duration::from_millis(50),
   );
@@ -525,6 +601,48 @@ pub fn TheaterPage(
     }
   });
 
+  // ── Effect 3d: viewer clock-offset probes ──────────────────────
+  // Viewers ping the owner so `clock_sync` can estimate the clock
+  // offset used to extrapolate the owner's playback position. The
+  // owner answers in the theater-message handler above.
+  let clock_tick = set_interval_with_handle(
+    move || {
+      if state.my_role.get_untracked() == TheaterRole::Owner {
+        return;
+      }
+      let (Some(room_id), Some(owner_id)) = (
+        state.room_id.get_untracked(),
+        state.owner_id.get_untracked(),
+      ) else {
+        return;
+      };
+      let Some(manager) = try_use_webrtc_manager() else {
+        return;
+      };
+      if !manager.is_connected(&owner_id) {
+        return;
+      }
+      let Some(seq) = state.clock.try_update(|c| c.next_seq()) else {
+        return;
+      };
+      leptos::task::spawn_local(async move {
+        // Stamp as late as possible so local scheduling delay does
+        // not count towards the round trip.
+        let probe = build_clock_ping(room_id, seq, now_nanos());
+        let _ = manager
+          .send_encrypted_data_channel_message(
+            owner_id,
+            &DataChannelMessage::TheaterClockPing(probe),
+          )
+          .await;
+      });
+    },
+    std::time::Duration::from_millis(u64::from(CLOCK_PING_INTERVAL_MS)),
+  );
+  if let Ok(handle) = clock_tick {
+    on_cleanup(move || handle.clear());
+  }
+
   // ── Effect 4: auto-persist overlay settings on change ───────────────
   let persist_first_run = RwSignal::new(true);
   Effect::new(move |_| {
@@ -646,19 +764,36 @@ pub fn TheaterPage(
               <Icon icon=i::LuUsers />
               <span>{t!(i18n, theater.members_title)}</span>
             </button>
+            <button
+              type="button"
+              class="theater-page__tab"
+              class:is-active=move || active_tab.get() == SidePanelTab::Queue
+              on:click=move |_| active_tab.set(SidePanelTab::Queue)
+              role="tab"
+              aria-selected=move || (active_tab.get() == SidePanelTab::Queue).to_string()
+              data-testid="theater-tab-queue"
+            >
+              <Icon icon=i::LuClipboardList />
+              <span>{t!(i18n, theater.queue_title)}</span>
+              <Show when=move || state.queue.with(|q| !q.is_empty())>
+                <span class="theater-page__badge" aria-live="polite">
+                  {move || state.queue.with(Vec::len)}
+                </span>
+              </Show>
+            </button>
           </nav>
 
           <div class="theater-page__tab-panel">
-            <Show
-              when=move || active_tab.get() == SidePanelTab::Chat
-              fallback=move || view! {
+            {move || match active_tab.get() {
+              SidePanelTab::Chat => view! { <TheaterChatPanel /> }.into_any(),
+              SidePanelTab::Members => view! {
                 <TheaterMemberPanel
                   room_id=Signal::derive(move || room.with(|r| r.room_id.clone()))
                 />
               }
-            >
-              <TheaterChatPanel />
-            </Show>
+              .into_any(),
+              SidePanelTab::Queue => view! { <TheaterQueuePanel /> }.into_any(),
+            }}
           </div>
         </aside>
       </div>
@@ -0,0 +1,465 @@
+//! Theater watch-queue panel.
+//!
+//! Shown as a tab beside the member panel. Everyone can see what is
+//! queued and propose remote URLs (optionally with a subtitle file
+//! attached); the owner additionally queues local files, reorders,
+//! removes, plays or skips items and toggles auto-advance. Proposers
+//! may withdraw their own items.
+//!
+//! Owner actions go through the `crate::theater::queue` helpers, which
+//! mutate [`TheaterState::queue`] and schedule the relay broadcast.
+//! Viewer proposals are broadcast directly and only show up in the
+//! list once the owner relays them back.
+
+use icondata as i;
+use js_sys::Date;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_i18n::{t, t_string};
+use leptos_icons::Icon;
+use message::MessageId;
+use message::datachannel::{
+  DataChannelMessage, TheaterQueueAdd, TheaterQueueItem, TheaterQueueRemove, TheaterQueueSource,
+  TheaterQueueSubtitle,
+};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Event, HtmlInputElement};
+
+use crate::i18n;
+use crate::state::use_app_state;
+use crate::theater::{
+  QUEUE_CAPACITY, TheaterRole, can_remove_queue_item, owner_add_item, owner_advance,
+  owner_move_item, owner_remove_item, owner_set_now_playing, parse_subtitle_file, url_title,
+  use_theater_state, validate_queue_url,
+};
+use crate::webrtc::try_use_webrtc_manager;
+
+fn now_nanos() -> u64 {
+  (Date::now() as u64).saturating_mul(1_000_000)
+}
+
+/// Read the first file selected in a `<input type="file">` change event.
+fn picked_file(ev: &Event) -> Option<(HtmlInputElement, web_sys::File)> {
+  let input = ev
+    .target()
+    .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())?;
+  let file = input.files()?.get(0)?;
+  Some((input, file))
+}
+
+/// Render the theater watch-queue panel.
+#[component]
+pub fn TheaterQueuePanel() -> impl IntoView {
+  let i18n = i18n::use_i18n();
+  let state = use_theater_state();
+  let app = use_app_state();
+
+  let url_input = RwSignal::new(String::new());
+  let attachment = RwSignal::<Option<TheaterQueueSubtitle>>::new(None);
+  let error = RwSignal::<Option<String>>::new(None);
+  let notice = RwSignal::<Option<String>>::new(None);
+
+  let local_file_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+  let subtitle_file_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+
+  let is_owner = move || state.my_role.get() == TheaterRole::Owner;
+  let my_id = move || {
+    app
+      .auth
+      .with_untracked(|a| a.as_ref().map(|a| a.user_id.clone()))
+  };
+  let queue_count_label = move || {
+    t_string!(i18n, theater.queue_count)
+      .to_string()
+      .replace("{count}", &state.queue.with(Vec::len).to_string())
+  };
+
+  // Owner adds directly; viewers propose and wait for the relay.
+  let submit_item = move |source: TheaterQueueSource, title: String| -> bool {
+    let Some(me) = my_id() else {
+      return false;
+    };
+    let item = TheaterQueueItem {
+      item_id: MessageId::new(),
+      source,
+      title,
+      proposed_by: me,
+      subtitle: attachment.get_untracked(),
+    };
+    if state.queue.with_untracked(Vec::len) >= QUEUE_CAPACITY {
+      error.set(Some(t_string!(i18n, theater.queue_full).to_string()));
+      return false;
+    }
+    if state.my_role.get_untracked() == TheaterRole::Owner {
+      if !owner_add_item(&state, item) {
+        error.set(Some(t_string!(i18n, theater.queue_full).to_string()));
+        return false;
+      }
+      notice.set(None);
+    } else {
+      let Some(room_id) = state.room_id.get_untracked() else {
+        return false;
+      };
+      if let Some(manager) = try_use_webrtc_manager() {
+        manager.broadcast_data_channel_message(&DataChannelMessage::TheaterQueueAdd(
+          TheaterQueueAdd { room_id, item },
+        ));
+      }
+      notice.set(Some(
+        t_string!(i18n, theater.queue_proposal_sent).to_string(),
+      ));
+    }
+    attachment.set(None);
+    error.set(None);
+    true
+  };
+
+  let handle_url_submit = move |ev: leptos::ev::SubmitEvent| {
+    ev.prevent_default();
+    let Some(url) = validate_queue_url(&url_input.get_untracked()) else {
+      error.set(Some(
+        t_string!(i18n, theater.source_url_invalid).to_string(),
+      ));
+      return;
+    };
+    let title = url_title(&url);
+    if submit_item(TheaterQueueSource::Url(url), title) {
+      url_input.set(String::new());
+    }
+  };
+
+  // Owner-only: queue a local file. The object URL stays on this
+  // machine; the wire item only carries the filename.
+  let handle_local_file = move |ev: Event| {
+    let Some((input, file)) = picked_file(&ev) else {
+      return;
+    };
+    input.set_value("");
+    let name = file.name();
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&file) else {
+      return;
+    };
+    let item_id = MessageId::new();
+    let Some(me) = my_id() else {
+      return;
+    };
+    let item = TheaterQueueItem {
+      item_id,
+      source: TheaterQueueSource::LocalFile(name.clone()),
+      title: name,
+      proposed_by: me,
+      subtitle: attachment.get_untracked(),
+    };
+    if owner_add_item(&state, item) {
+      state.queue_local_urls.update(|urls| {
+        urls.insert(item_id, url);
+      });
+      attachment.set(None);
+      error.set(None);
+    } else {
+      let _ = web_sys::Url::revoke_object_url(&url);
+      error.set(Some(t_string!(i18n, theater.queue_full).to_string()));
+    }
+  };
+
+  // Parse a subtitle file and hold it until the next item is queued.
+  let handle_subtitle_file = move |ev: Event| {
+    let Some((input, file)) = picked_file(&ev) else {
+      return;
+    };
+    input.set_value("");
+    let name = file.name();
+    spawn_local(async move {
+      let text = JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|v| v.as_string());
+      let Some(content) = text else {
+        let template = t_string!(i18n, theater.subtitle_parse_failed).to_string();
+        error.set(Some(template.replace("{reason}", "non-string content")));
+        return;
+      };
+      match parse_subtitle_file(&name, &content) {
+        Ok(entries) => {
+          attachment.set(Some(TheaterQueueSubtitle {
+            filename: name,
+            entries,
+          }));
+          error.set(None);
+        }
+        Err(err) => {
+          let template = t_string!(i18n, theater.subtitle_parse_failed).to_string();
+          error.set(Some(template.replace("{reason}", &err.to_string())));
+        }
+      }
+    });
+  };
+
+  let handle_remove = move |item_id: MessageId| {
+    let Some(me) = my_id() else {
+      return;
+    };
+    if state.my_role.get_untracked() == TheaterRole::Owner {
+      let local_url = state
+        .queue_local_urls
+        .with_untracked(|urls| urls.get(&item_id).cloned());
+      if owner_remove_item(&state, item_id, me)
+        && let Some(url) = local_url
+      {
+        let _ = web_sys::Url::revoke_object_url(&url);
+      }
+      return;
+    }
+    let Some(room_id) = state.room_id.get_untracked() else {
+      return;
+    };
+    if let Some(manager) = try_use_webrtc_manager() {
+      manager.broadcast_data_channel_message(&DataChannelMessage::TheaterQueueRemove(
+        TheaterQueueRemove {
+          room_id,
+          item_id,
+          requested_by: me,
+        },
+      ));
+    }
+  };
+
+  view! {
+    <section
+      class="theater-queue-panel"
+      aria-label=move || t_string!(i18n, theater.queue_title)
+      data-testid="theater-queue-panel"
+    >
+      <header class="theater-queue-panel__header">
+        <h3 class="theater-queue-panel__title">{t!(i18n, theater.queue_title)}</h3>
+        <span class="theater-queue-panel__count" aria-live="polite">
+          {queue_count_label}
+        </span>
+      </header>
+
+      <Show when=is_owner>
+        <div class="theater-queue-panel__owner-row">
+          <label class="theater-queue-panel__auto-advance">
+            <input
+              type="checkbox"
+              prop:checked=move || state.queue_auto_advance.get()
+              on:change=move |ev| state.queue_auto_advance.set(event_target_checked(&ev))
+              data-testid="theater-queue-auto-advance"
+            />
+            <span>{t!(i18n, theater.queue_auto_advance)}</span>
+          </label>
+          <button
+            type="button"
+            class="btn btn--ghost"
+            disabled=move || state.queue.with(Vec::is_empty)
+            on:click=move |_| {
+              let _ = owner_advance(&state, now_nanos());
+            }
+            data-testid="theater-queue-skip"
+          >
+            <Icon icon=i::LuSkipForward />
+            <span>{t!(i18n, theater.queue_skip)}</span>
+          </button>
+        </div>
+      </Show>
+
+      <ol class="theater-queue-panel__list" role="list">
+        <Show
+          when=move || state.queue.with(|q| !q.is_empty())
+          fallback=move || view! {
+            <li class="theater-queue-panel__empty" role="note">
+              {t!(i18n, theater.queue_empty)}
+            </li>
+          }
+        >
+          <For
+            each=move || state.queue.get()
+            key=|item: &TheaterQueueItem| item.item_id
+            children=move |item: TheaterQueueItem| {
+              let item_id = item.item_id;
+              let is_playing = move || state.now_playing.get() == Some(item_id);
+              let proposer = app.resolve_user_display_name(&item.proposed_by);
+              let proposed_by_label = t_string!(i18n, theater.queue_proposed_by)
+                .to_string()
+                .replace("{name}", &proposer);
+              let subtitle_label = item.subtitle.as_ref().map(|s| {
+                t_string!(i18n, theater.queue_subtitle_attached)
+                  .to_string()
+                  .replace("{name}", &s.filename)
+              });
+              let item_for_remove = item.clone();
+              let removable = move || {
+                can_remove_queue_item(
+                  state.my_role.get(),
+                  my_id().as_ref(),
+                  &item_for_remove,
+                  state.now_playing.get().as_ref(),
+                )
+              };
+              view! {
+                <li
+                  class="theater-queue-panel__item"
+                  class:is-playing=is_playing
+                  data-testid="theater-queue-item"
+                >
+                  <div class="theater-queue-panel__item-info">
+                    <span class="theater-queue-panel__item-title">{item.title.clone()}</span>
+                    <span class="theater-queue-panel__item-meta">{proposed_by_label}</span>
+                    {subtitle_label.map(|label| view! {
+                      <span class="theater-queue-panel__item-meta">
+                        <Icon icon=i::LuFileText />
+                        {label}
+                      </span>
+                    })}
+                    <Show when=is_playing>
+                      <span class="theater-queue-panel__now-playing" aria-live="polite">
+                        {t!(i18n, theater.queue_now_playing)}
+                      </span>
+                    </Show>
+                  </div>
+                  <div class="theater-queue-panel__item-actions">
+                    <Show when=is_owner>
+                      <button
+                        type="button"
+                        class="btn btn--icon"
+                        on:click=move |_| {
+                          owner_set_now_playing(&state, Some(item_id), now_nanos());
+                        }
+                        aria-label=move || t_string!(i18n, theater.queue_play_now).to_string()
+                        title=move || t_string!(i18n, theater.queue_play_now).to_string()
+                        data-testid="theater-queue-play"
+                      >
+                        <Icon icon=i::LuPlay />
+                      </button>
+                      <button
+                        type="button"
+                        class="btn btn--icon"
+                        on:click=move |_| {
+                          owner_move_item(&state, &item_id, true);
+                        }
+                        aria-label=move || t_string!(i18n, theater.queue_move_up).to_string()
+                        title=move || t_string!(i18n, theater.queue_move_up).to_string()
+                      >
+                        <Icon icon=i::LuChevronUp />
+                      </button>
+                      <button
+                        type="button"
+                        class="btn btn--icon"
+                        on:click=move |_| {
+                          owner_move_item(&state, &item_id, false);
+                        }
+                        aria-label=move || t_string!(i18n, theater.queue_move_down).to_string()
+                        title=move || t_string!(i18n, theater.queue_move_down).to_string()
+                      >
+                        <Icon icon=i::LuChevronDown />
+                      </button>
+                    </Show>
+                    <Show when=removable>
+                      <button
+                        type="button"
+                        class="btn btn--icon"
+                        on:click=move |_| handle_remove(item_id)
+                        aria-label=move || t_string!(i18n, theater.queue_remove).to_string()
+                        title=move || t_string!(i18n, theater.queue_remove).to_string()
+                        data-testid="theater-queue-remove"
+                      >
+                        <Icon icon=i::LuTrash2 />
+                      </button>
+                    </Show>
+                  </div>
+                </li>
+              }
+            }
+          />
+        </Show>
+      </ol>
+
+      <form class="theater-queue-panel__form" on:submit=handle_url_submit>
+        <div class="theater-queue-panel__form-row">
+          <input
+            class="input theater-queue-panel__input"
+            type="url"
+            inputmode="url"
+            prop:value=move || url_input.get()
+            on:input=move |ev| url_input.set(event_target_value(&ev))
+            placeholder=move || t_string!(i18n, theater.source_url_placeholder)
+            aria-label=move || t_string!(i18n, theater.source_url)
+            data-testid="theater-queue-url-input"
+          />
+          <button type="submit" class="btn btn--primary" data-testid="theater-queue-add">
+            <Icon icon=i::LuPlus />
+            <span>{t!(i18n, theater.queue_add)}</span>
+          </button>
+        </div>
+        <div class="theater-queue-panel__form-row">
+          <button
+            type="button"
+            class="btn btn--ghost"
+            on:click=move |_| {
+              if let Some(el) = subtitle_file_ref.get() {
+                el.click();
+              }
+            }
+            data-testid="theater-queue-attach-subtitle"
+          >
+            <Icon icon=i::LuFileText />
+            <span>{t!(i18n, theater.queue_attach_subtitle)}</span>
+          </button>
+          <input
+            node_ref=subtitle_file_ref
+            type="file"
+            accept=".srt,.vtt,.ass,.ssa"
+            class="theater-queue-panel__file-input"
+            on:change=handle_subtitle_file
+          />
+          <Show when=is_owner>
+            <button
+              type="button"
+              class="btn btn--ghost"
+              on:click=move |_| {
+                if let Some(el) = local_file_ref.get() {
+                  el.click();
+                }
+              }
+              data-testid="theater-queue-add-local"
+            >
+              <Icon icon=i::LuFileVideo />
+              <span>{t!(i18n, theater.queue_add_local)}</span>
+            </button>
+          </Show>
+          <input
+            node_ref=local_file_ref
+            type="file"
+            accept="video/*"
+            class="theater-queue-panel__file-input"
+            on:change=handle_local_file
+          />
+        </div>
+        <Show when=move || attachment.with(Option::is_some)>
+          <p class="theater-queue-panel__attachment">
+            {move || {
+              attachment.with(|a| {
+                a.as_ref().map(|s| {
+                  t_string!(i18n, theater.queue_subtitle_attached)
+                    .to_string()
+                    .replace("{name}", &s.filename)
+                })
+              })
+            }}
+          </p>
+        </Show>
+        <Show when=move || notice.get().is_some()>
+          <p class="theater-queue-panel__notice" role="status">
+            {move || notice.get().unwrap_or_default()}
+          </p>
+        </Show>
+        <Show when=move || error.get().is_some()>
+          <p class="theater-queue-panel__error" role="alert">
+            {move || error.get().unwrap_or_default()}
+          </p>
+        </Show>
+      </form>
+    </section>
+  }
+}
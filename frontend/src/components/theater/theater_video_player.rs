@@ -12,12 +12,19 @@
 //! * **Viewer** — binds the incoming owner stream to `srcObject` and
 //!   never dispatches playback mutations. A reactive effect watches
 //!   [`TheaterState::playback`] so remote-driven seeks / pauses apply
-//!   to the local element via [`needs_seek`].
+//!   to the local element. The owner's position is extrapolated with
+//!   the clock-offset estimate ([`TheaterState::clock`]) and drift is
+//!   corrected by [`sync_action`] — `playbackRate` nudges for small
+//!   drift, seeks only for large jumps.
 //!
 //! Picking the video source is delegated to
 //! [`VideoSourcePicker`](super::VideoSourcePicker); until a source
 //! has been selected the owner sees the picker and viewers see a
-//! "waiting for stream" placeholder.
+//! "waiting for stream" placeholder. The owner can also drive the
+//! source from the watch queue: whenever [`TheaterState::now_playing`]
+//! changes the player loads that item (and its subtitle attachment),
+//! and the `ended` event advances to the next item when auto-advance
+//! is on.
 
 use icondata as i;
 use js_sys::{Date, Reflect};
@@ -26,7 +33,7 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_i18n::{t, t_string};
 use leptos_icons::Icon;
-use message::datachannel::DataChannelMessage;
+use message::datachannel::{DataChannelMessage, TheaterQueueSource};
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use web_sys::HtmlVideoElement;
@@ -35,8 +42,9 @@ use crate::components::theater::{VideoSource, VideoSourceKind, VideoSourcePicker
 use crate::error_handler::use_error_toast_manager;
 use crate::i18n;
 use crate::theater::{
-  PlaybackSnapshot, TheaterRole, TheaterState, build_progress_frame, needs_seek,
-  should_broadcast_progress, use_theater_state,
+  PlaybackSnapshot, SyncAction, TheaterRole, TheaterState, build_progress_frame,
+  extrapolate_owner_position, install_item_subtitle, owner_advance, owner_set_now_playing,
+  should_broadcast_progress, subtitle_frame_for, sync_action, use_theater_state,
 };
 use crate::webrtc::try_use_webrtc_manager;
 
@@ -281,6 +289,50 @@ pub fn TheaterVideoPlayer(
     }
   });
 
+  // --- Effect: load the queue item the owner marked as playing ----------
+  // `now_playing` is set by the queue panel ("play now" / "skip") and
+  // by the `ended` handler below. Resolving it into a `VideoSource`
+  // reuses the bind effect above, including the in-place track swap
+  // for viewers that already receive the previous item.
+  Effect::new(move |_| {
+    let Some(item_id) = state.now_playing.get() else {
+      return;
+    };
+    if state.my_role.get_untracked() != TheaterRole::Owner {
+      return;
+    }
+    let Some(item) = state
+      .queue
+      .with_untracked(|q| q.iter().find(|i| i.item_id == item_id).cloned())
+    else {
+      return;
+    };
+    let (kind, src_url) = match &item.source {
+      TheaterQueueSource::Url(url) => (VideoSourceKind::RemoteUrl, Some(url.clone())),
+      TheaterQueueSource::LocalFile(_) => (
+        VideoSourceKind::LocalFile,
+        state
+          .queue_local_urls
+          .with_untracked(|urls| urls.get(&item_id).cloned()),
+      ),
+    };
+    let Some(src_url) = src_url else {
+      return;
+    };
+    install_item_subtitle(&state, &item);
+    if let Some(room_id) = state.room_id.get_untracked()
+      && let Some(manager) = try_use_webrtc_manager()
+    {
+      manager.broadcast_data_channel_message(&subtitle_frame_for(room_id, &item));
+    }
+    source.set(Some(VideoSource {
+      kind,
+      label: item.title.clone(),
+      src_url: Some(src_url),
+      stream: None,
+    }));
+  });
+
   // --- Effect: react to live speaker-setting changes -----------------
   // Mirrors the video_tile equivalent so volume / output-device
   // adjustments from the settings drawer take effect during an active
@@ -357,10 +409,36 @@ pub fn TheaterVideoPlayer(
     let is_live_stream = !video.duration().is_finite() || video.duration().is_nan();
 
     if !is_live_stream {
-      // Seekable source (e.g. direct URL playback) — apply seek logic.
+      // Seekable source (e.g. direct URL playback). Project the
+      // owner's position to "now" on the owner's clock; until the first
+      // clock pong arrives fall back to the raw frame position.
       let local_ms = (video.current_time() * 1_000.0) as u64;
-      if let Some(target_ms) = needs_seek(local_ms, snapshot.current_time_ms) {
-        video.set_current_time((target_ms as f64) / 1_000.0);
+      let target_ms = match state.clock.with_untracked(|c| c.estimate()) {
+        Some(estimate) => extrapolate_owner_position(
+          snapshot,
+          state.playback_origin_nanos.get_untracked(),
+          (Date::now() as u64).saturating_mul(1_000_000),
+          estimate.offset_nanos,
+        ),
+        None => snapshot.current_time_ms,
+      };
+      let nudging = (video.playback_rate() - 1.0).abs() > f64::EPSILON;
+      match sync_action(local_ms, target_ms, nudging) {
+        SyncAction::Hold => {
+          if nudging {
+            video.set_playback_rate(1.0);
+          }
+        }
+        // A paused element cannot catch up by playing faster.
+        SyncAction::Nudge(_) if snapshot.is_paused => {
+          video.set_playback_rate(1.0);
+          video.set_current_time((target_ms as f64) / 1_000.0);
+        }
+        SyncAction::Nudge(rate) => video.set_playback_rate(rate),
+        SyncAction::Seek(seek_ms) => {
+          video.set_playback_rate(1.0);
+          video.set_current_time((seek_ms as f64) / 1_000.0);
+        }
       }
     }
 
@@ -428,6 +506,17 @@ pub fn TheaterVideoPlayer(
     owner_broadcast(&state, last_sent_ms, last_snapshot, snap);
   };
 
+  // Auto-advance the watch queue when the current item finishes.
+  let handle_ended = move |_| {
+    if state.my_role.get_untracked() != TheaterRole::Owner
+      || !state.queue_auto_advance.get_untracked()
+    {
+      return;
+    }
+    let now_nanos = (Date::now() as u64).saturating_mul(1_000_000);
+    let _ = owner_advance(&state, now_nanos);
+  };
+
   // --- CORS / media error handler (Req 12.3 §10) -------------------------
   let handle_video_error = move |_| {
     let Some(el) = video_ref.get() else { return };
@@ -471,6 +560,11 @@ pub fn TheaterVideoPlayer(
               }
             >
               <VideoSourcePicker on_selected=Callback::new(move |picked: VideoSource| {
+                // A manually picked source is outside the queue.
+                if state.now_playing.get_untracked().is_some() {
+                  let now_nanos = (Date::now() as u64).saturating_mul(1_000_000);
+                  owner_set_now_playing(&state, None, now_nanos);
+                }
                 source.set(Some(picked));
               }) />
             </Show>
@@ -486,6 +580,7 @@ pub fn TheaterVideoPlayer(
           on:timeupdate=handle_timeupdate
           on:play=handle_play
           on:pause=handle_pause
+          on:ended=handle_ended
           on:error=handle_video_error
           aria-label=move || t_string!(i18n, theater.video_player_label).to_string()
           data-testid="theater-video"
@@ -0,0 +1,331 @@
+//! Device-linking protocol.
+//!
+//! Linking copies the chat history of an existing device (the
+//! *holder*) to a new device (the *joiner*) of the same account:
+//!
+//! 1. The holder starts hosting ("Link a new device") and shows a QR
+//!    code of [`super::link_url`].
+//! 2. The joiner opens that URL (or picks the holder from its device
+//!    list) and sends `Hello` with an ephemeral ECDH P-256 key; the
+//!    holder answers `Accept` with its own.
+//! 3. Both derive [`short_code`] from the two public keys and display
+//!    it. The holder's user confirms that both screens match, which
+//!    rules out a relaying server that swapped the keys.
+//! 4. The holder offers a dedicated WebRTC `DataChannel`. Every frame
+//!    on it is a JSON [`HistoryFrame`] sealed with AES-256-GCM under
+//!    [`link_key`].
+//!
+//! Everything in this module is pure so it can be unit-tested
+//! natively; the browser side lives in `session`.
+
+use crate::persistence::MessageRecord;
+use message::DeviceId;
+use message::signaling::DeviceLinkPayload;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Domain separator hashed into the short code.
+const SHORT_CODE_CONTEXT: &[u8] = b"webrtc-e2ee-chat-device-link-code-v1";
+
+/// Domain separator hashed into the transfer key.
+const LINK_KEY_CONTEXT: &[u8] = b"webrtc-e2ee-chat-device-link-aes-v1";
+
+/// Upper bound for one plaintext frame. Chromium caps `DataChannel`
+/// messages at 256 KiB; the margin covers JSON framing and the GCM
+/// nonce and tag.
+pub const MAX_FRAME_BYTES: usize = 128 * 1024;
+
+/// Records read from `IndexedDB` per export page.
+pub const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Which side of the link this device is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkRole {
+  /// Existing device sending its history.
+  Holder,
+  /// New device receiving the history.
+  Joiner,
+}
+
+/// Why a link ended without completing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkFailure {
+  /// The other device cancelled, or went offline.
+  Cancelled,
+  /// Key agreement or decryption failed.
+  Crypto,
+  /// The `DataChannel` could not be opened or dropped mid-transfer.
+  Connection,
+  /// Reading or writing `IndexedDB` failed.
+  Storage,
+}
+
+/// Phase of the linking flow, as shown in the devices settings section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkPhase {
+  /// No link in progress.
+  Idle,
+  /// Holder waiting for a new device to say hello.
+  Hosting,
+  /// Joiner sent `Hello` to `holder` and waits for `Accept`.
+  Requesting {
+    /// Device asked for its history.
+    holder: DeviceId,
+  },
+  /// Keys exchanged; both devices show `code`. The holder confirms.
+  Verifying {
+    /// The other device.
+    peer: DeviceId,
+    /// This device's role.
+    role: LinkRole,
+    /// Short code to compare.
+    code: String,
+  },
+  /// Code confirmed; the `DataChannel` is being negotiated.
+  Connecting {
+    /// The other device.
+    peer: DeviceId,
+    /// This device's role.
+    role: LinkRole,
+  },
+  /// History is streaming.
+  Syncing {
+    /// The other device.
+    peer: DeviceId,
+    /// This device's role.
+    role: LinkRole,
+    /// Records sent or received so far.
+    records: usize,
+    /// Records the holder announced.
+    total: usize,
+  },
+  /// Transfer finished.
+  Done {
+    /// This device's role.
+    role: LinkRole,
+    /// Records sent or imported.
+    records: usize,
+  },
+  /// Link aborted.
+  Failed(LinkFailure),
+}
+
+impl LinkPhase {
+  /// The device on the other side of the link, once known.
+  #[must_use]
+  pub fn peer(&self) -> Option<DeviceId> {
+    match self {
+      Self::Requesting { holder } => Some(*holder),
+      Self::Verifying { peer, .. } | Self::Connecting { peer, .. } | Self::Syncing { peer, .. } => {
+        Some(*peer)
+      }
+      Self::Idle | Self::Hosting | Self::Done { .. } | Self::Failed(_) => None,
+    }
+  }
+
+  /// This device's role, once known.
+  #[must_use]
+  pub fn role(&self) -> Option<LinkRole> {
+    match self {
+      Self::Hosting => Some(LinkRole::Holder),
+      Self::Requesting { .. } => Some(LinkRole::Joiner),
+      Self::Verifying { role, .. }
+      | Self::Connecting { role, .. }
+      | Self::Syncing { role, .. }
+      | Self::Done { role, .. } => Some(*role),
+      Self::Idle | Self::Failed(_) => None,
+    }
+  }
+
+  /// Whether a link is in progress (as opposed to idle or finished).
+  #[must_use]
+  pub fn is_active(&self) -> bool {
+    !matches!(self, Self::Idle | Self::Done { .. } | Self::Failed(_))
+  }
+
+  /// Whether `payload` from device `from` is the next expected step.
+  /// Anything else is dropped, so a stray or replayed signal can never
+  /// move the flow forward.
+  #[must_use]
+  pub fn accepts(&self, from: &DeviceId, payload: &DeviceLinkPayload) -> bool {
+    let from_peer = self.peer().is_some_and(|peer| peer == *from);
+    match payload {
+      DeviceLinkPayload::Hello { .. } => *self == Self::Hosting,
+      DeviceLinkPayload::Accept { .. } => matches!(self, Self::Requesting { .. }) && from_peer,
+      DeviceLinkPayload::Offer { .. } => {
+        matches!(
+          self,
+          Self::Verifying {
+            role: LinkRole::Joiner,
+            ..
+          }
+        ) && from_peer
+      }
+      DeviceLinkPayload::Answer { .. } => {
+        matches!(
+          self,
+          Self::Connecting {
+            role: LinkRole::Holder,
+            ..
+          }
+        ) && from_peer
+      }
+      DeviceLinkPayload::IceCandidate { .. } => {
+        matches!(self, Self::Connecting { .. } | Self::Syncing { .. }) && from_peer
+      }
+      DeviceLinkPayload::Cancel => from_peer,
+    }
+  }
+}
+
+/// Short code both devices display:
+/// `SHA-256(context || hello_key || accept_key)`, six decimal digits
+/// grouped as `"123 456"`.
+#[must_use]
+pub fn short_code(hello_key: &[u8], accept_key: &[u8]) -> String {
+  let digest = Sha256::new()
+    .chain_update(SHORT_CODE_CONTEXT)
+    .chain_update(hello_key)
+    .chain_update(accept_key)
+    .finalize();
+  let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 1_000_000;
+  format!("{:03} {:03}", value / 1_000, value % 1_000)
+}
+
+/// AES-256 transfer key:
+/// `SHA-256(context || secret || hello_key || accept_key)`.
+#[must_use]
+pub fn link_key(secret: &[u8], hello_key: &[u8], accept_key: &[u8]) -> [u8; 32] {
+  Sha256::new()
+    .chain_update(LINK_KEY_CONTEXT)
+    .chain_update(secret)
+    .chain_update(hello_key)
+    .chain_update(accept_key)
+    .finalize()
+    .into()
+}
+
+/// A conversation the joiner should list, even if it had no local
+/// record of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedConversation {
+  /// Conversation key (`"d:<uuid>"` or `"r:<uuid>"`).
+  pub key: String,
+  /// Sidebar name on the holder.
+  pub display_name: String,
+}
+
+/// One decrypted `DataChannel` frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryFrame {
+  /// First frame: how many records follow, and the holder's
+  /// conversation list.
+  Start {
+    /// Stored records on the holder.
+    total: usize,
+    /// Conversations to list on the joiner.
+    conversations: Vec<LinkedConversation>,
+  },
+  /// A batch of stored messages.
+  Records {
+    /// Records in `timestamp_ms` order.
+    records: Vec<MessageRecord>,
+  },
+  /// Last frame; the holder sent every record.
+  End,
+}
+
+/// Split `records` into batches whose JSON encoding stays under
+/// [`MAX_FRAME_BYTES`]. Returns the batches and how many records were
+/// skipped because a single one exceeds the limit (inline media).
+#[must_use]
+pub fn split_batches(records: Vec<MessageRecord>) -> (Vec<Vec<MessageRecord>>, usize) {
+  // Envelope of a `Records` frame plus one separator per record.
+  const FRAME_OVERHEAD: usize = 64;
+  let mut batches = Vec::new();
+  let mut current = Vec::new();
+  let mut current_bytes = FRAME_OVERHEAD;
+  let mut skipped = 0;
+  for record in records {
+    let size = serde_json::to_vec(&record).map_or(usize::MAX, |json| json.len() + 1);
+    if size > MAX_FRAME_BYTES - FRAME_OVERHEAD {
+      skipped += 1;
+      continue;
+    }
+    if current_bytes + size > MAX_FRAME_BYTES {
+      batches.push(std::mem::take(&mut current));
+      current_bytes = FRAME_OVERHEAD;
+    }
+    current_bytes += size;
+    current.push(record);
+  }
+  if !current.is_empty() {
+    batches.push(current);
+  }
+  (batches, skipped)
+}
+
+/// Walks the message store in `timestamp_ms` order, one page at a time.
+///
+/// Pages are loaded with a `timestamp_ms >= from_ts` range, so records
+/// sharing the boundary timestamp come back on the next page and are
+/// filtered out here.
+#[derive(Debug, Default)]
+pub struct ExportCursor {
+  from_ts: i64,
+  seen_at_from: HashSet<String>,
+  done: bool,
+}
+
+impl ExportCursor {
+  /// Lower timestamp bound for the next page.
+  #[must_use]
+  pub fn from_ts(&self) -> i64 {
+    self.from_ts
+  }
+
+  /// Whether the last page has been consumed.
+  #[must_use]
+  pub fn is_done(&self) -> bool {
+    self.done
+  }
+
+  /// Consume one page of at most `limit` records loaded from
+  /// [`Self::from_ts`] and return the records not exported yet.
+  ///
+  /// If an entire page shares one already-exported timestamp (more
+  /// than `limit` messages in the same millisecond) the cursor moves
+  /// past it rather than loop forever.
+  pub fn advance(&mut self, page: Vec<MessageRecord>, limit: usize) -> Vec<MessageRecord> {
+    if page.len() < limit {
+      self.done = true;
+    }
+    let fresh: Vec<MessageRecord> = page
+      .into_iter()
+      .filter(|r| !(r.timestamp_ms == self.from_ts && self.seen_at_from.contains(&r.message_id)))
+      .collect();
+    match fresh.last() {
+      Some(last) => {
+        let ts = last.timestamp_ms;
+        if ts != self.from_ts {
+          self.from_ts = ts;
+          self.seen_at_from.clear();
+        }
+        self.seen_at_from.extend(
+          fresh
+            .iter()
+            .filter(|r| r.timestamp_ms == ts)
+            .map(|r| r.message_id.clone()),
+        );
+      }
+      None if !self.done => {
+        self.from_ts = self.from_ts.saturating_add(1);
+        self.seen_at_from.clear();
+      }
+      None => {}
+    }
+    fresh
+  }
+}
@@ -0,0 +1,255 @@
+//! Multi-device support.
+//!
+//! A user may be signed in on several browsers at once. Each browser
+//! profile is one *device*: it keeps a stable [`DeviceId`] in
+//! localStorage and announces it (with a readable name) in
+//! `TokenAuth`, so the server can route signaling per device and
+//! tell a reconnect from a second device.
+//!
+//! * [`link`] — pure pieces of the device-linking protocol: phases,
+//!   short-code and key derivation, history framing.
+//! * [`qr`] — SVG QR code of the link URL.
+//! * `session` — [`DeviceLinkManager`] runtime: ECDH over
+//!   `DeviceLinkSignal`, a dedicated WebRTC `DataChannel`, and the
+//!   `IndexedDB` history export / import (WASM only).
+//!
+//! ## Peer connections
+//!
+//! The mesh keeps one connection per remote *device*, so chat and
+//! calls reach every device a user is signed in on. Callers do not
+//! know the callee's devices, so the first offer to a user is not
+//! addressed to any and reaches all of them. Every device answers it:
+//! the caller binds the offering connection to the first device that
+//! answers and opens a connection addressed to each device that
+//! answers after it. See [`should_answer_offer`].
+
+pub mod link;
+pub mod qr;
+#[cfg(target_arch = "wasm32")]
+mod session;
+
+#[cfg(test)]
+mod tests;
+
+use crate::state::AppState;
+use crate::utils;
+use leptos::prelude::*;
+use message::DeviceId;
+use message::signaling::DeviceInfo;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub use link::{LinkFailure, LinkPhase, LinkRole};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use session::add_linked_conversations;
+
+/// localStorage key holding this browser's device id. Deliberately
+/// outside the `auth_` namespace: logging out must not turn the
+/// browser into a new device.
+const KEY_DEVICE_ID: &str = "device_id";
+
+/// URL query parameter carrying the holder's device id in the link
+/// QR code (`<origin>/?link=<device_id>`).
+pub const LINK_QUERY_PARAM: &str = "link";
+
+/// This browser's device id, generated and persisted on first use.
+#[must_use]
+pub fn local_device_id() -> DeviceId {
+  if let Some(id) = utils::load_from_local_storage(KEY_DEVICE_ID).and_then(|raw| raw.parse().ok()) {
+    return id;
+  }
+  let id = DeviceId::new();
+  utils::save_to_local_storage(KEY_DEVICE_ID, &id.to_string());
+  id
+}
+
+/// This browser's device as announced in `TokenAuth`.
+#[must_use]
+pub fn local_device() -> DeviceInfo {
+  let user_agent = web_sys::window()
+    .and_then(|w| w.navigator().user_agent().ok())
+    .unwrap_or_default();
+  DeviceInfo {
+    device_id: local_device_id(),
+    name: device_name(&user_agent),
+  }
+}
+
+/// Readable device name derived from a user-agent string, e.g.
+/// "Firefox on Linux".
+#[must_use]
+pub fn device_name(user_agent: &str) -> String {
+  // Order matters: Edge and Opera also advertise `Chrome/`, and every
+  // Chromium browser advertises `Safari/`.
+  let browser = [
+    ("Edg/", "Edge"),
+    ("OPR/", "Opera"),
+    ("Firefox/", "Firefox"),
+    ("Chrome/", "Chrome"),
+    ("Safari/", "Safari"),
+  ]
+  .iter()
+  .find(|(token, _)| user_agent.contains(token))
+  .map_or("Browser", |(_, name)| name);
+  let os = [
+    ("iPhone", "iPhone"),
+    ("iPad", "iPad"),
+    ("Android", "Android"),
+    ("CrOS", "ChromeOS"),
+    ("Windows", "Windows"),
+    ("Mac OS X", "macOS"),
+    ("Linux", "Linux"),
+  ]
+  .iter()
+  .find(|(token, _)| user_agent.contains(token))
+  .map(|(_, name)| *name);
+  match os {
+    Some(os) => format!("{browser} on {os}"),
+    None => browser.to_string(),
+  }
+}
+
+/// Whether this device should answer an SDP offer addressed to
+/// `to_device`: unaddressed offers and offers addressed to this
+/// device. The server only delivers an offer addressed to another
+/// device when that device is gone.
+#[must_use]
+pub fn should_answer_offer(to_device: Option<&DeviceId>, local: &DeviceId) -> bool {
+  to_device.is_none_or(|to| to == local)
+}
+
+/// Holder device id from a page query string (`?link=<device_id>`).
+#[must_use]
+pub fn link_param(search: &str) -> Option<DeviceId> {
+  search
+    .trim_start_matches('?')
+    .split('&')
+    .filter_map(|pair| pair.split_once('='))
+    .find(|(key, _)| *key == LINK_QUERY_PARAM)
+    .and_then(|(_, value)| value.parse().ok())
+}
+
+/// URL a new device opens to link with `holder`.
+#[must_use]
+pub fn link_url(origin: &str, holder: &DeviceId) -> String {
+  format!(
+    "{}/?{LINK_QUERY_PARAM}={holder}",
+    origin.trim_end_matches('/')
+  )
+}
+
+/// Runtime state of the device-linking flow.
+#[derive(Default)]
+struct Inner {
+  /// Holder from the `?link=` parameter this page was opened with. The
+  /// link starts once that device shows up in the device list.
+  pending_holder: Option<DeviceId>,
+  /// Handshake and transfer state (WASM only).
+  #[cfg(target_arch = "wasm32")]
+  session: Option<session::LinkSession>,
+}
+
+/// Drives linking this device with another device of the same account
+/// and syncing chat history between them.
+#[derive(Clone)]
+pub struct DeviceLinkManager {
+  /// Current phase, rendered by the devices settings section.
+  pub phase: RwSignal<LinkPhase>,
+  #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+  app_state: AppState,
+  inner: Rc<RefCell<Inner>>,
+}
+
+crate::wasm_send_sync!(DeviceLinkManager);
+
+impl DeviceLinkManager {
+  /// Create an idle manager, remembering the holder from the page URL.
+  #[must_use]
+  pub fn new(app_state: AppState) -> Self {
+    let pending_holder = web_sys::window()
+      .and_then(|w| w.location().search().ok())
+      .and_then(|search| link_param(&search));
+    Self {
+      phase: RwSignal::new(LinkPhase::Idle),
+      app_state,
+      inner: Rc::new(RefCell::new(Inner {
+        pending_holder,
+        ..Inner::default()
+      })),
+    }
+  }
+
+  /// React to a new device list: start the link requested through the
+  /// page URL once its holder is online.
+  pub fn on_device_list(&self, devices: &[DeviceInfo]) {
+    let holder = {
+      let mut inner = self.inner.borrow_mut();
+      let Some(holder) = inner.pending_holder else {
+        return;
+      };
+      if !devices.iter().any(|d| d.device_id == holder) {
+        return;
+      }
+      inner.pending_holder = None;
+      holder
+    };
+    if self.phase.get_untracked() == LinkPhase::Idle {
+      self.request_link(holder);
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DeviceLinkManager {
+  /// Start waiting for a new device (native builds cannot link).
+  pub fn start_hosting(&self) {}
+
+  /// Ask `holder` for its history (native builds cannot link).
+  pub fn request_link(&self, _holder: DeviceId) {}
+
+  /// Confirm the short code (native builds cannot link).
+  pub fn confirm(&self) {}
+
+  /// Abort the link (native builds cannot link).
+  pub fn cancel(&self) {
+    self.phase.set(LinkPhase::Idle);
+  }
+
+  /// Handle a relayed signal (native builds cannot link).
+  pub fn handle_signal(&self, _signal: message::signaling::DeviceLinkSignal) {}
+}
+
+thread_local! {
+  /// Fallback registry for callers outside the Leptos reactive owner — see
+  /// `crate::invite::INVITE_MANAGER_FALLBACK` for the rationale.
+  static DEVICE_LINK_FALLBACK: RefCell<Option<DeviceLinkManager>> = const { RefCell::new(None) };
+}
+
+/// Provide a `DeviceLinkManager` to the Leptos context.
+pub fn provide_device_link_manager(app_state: AppState) -> DeviceLinkManager {
+  let manager = DeviceLinkManager::new(app_state);
+  provide_context(manager.clone());
+  DEVICE_LINK_FALLBACK.with(|cell| {
+    cell.borrow_mut().replace(manager.clone());
+  });
+  manager
+}
+
+/// Retrieve the `DeviceLinkManager` from Leptos context.
+///
+/// # Panics
+/// Panics if `provide_device_link_manager` has not been called.
+#[must_use]
+pub fn use_device_link_manager() -> DeviceLinkManager {
+  expect_context::<DeviceLinkManager>()
+}
+
+/// Best-effort accessor that also resolves the thread-local fallback,
+/// so signaling callbacks can reach the manager.
+#[must_use]
+pub fn try_use_device_link_manager() -> Option<DeviceLinkManager> {
+  if let Some(manager) = use_context::<DeviceLinkManager>() {
+    return Some(manager);
+  }
+  DEVICE_LINK_FALLBACK.with(|cell| cell.borrow().clone())
+}
@@ -0,0 +1,35 @@
+//! QR code rendering for the device-link URL.
+//!
+//! The code is drawn as one SVG `<path>` (one unit square per dark
+//! module) so it scales crisply and needs no canvas.
+
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::fmt::Write;
+
+/// Quiet zone around the symbol, in modules (the spec minimum).
+const QUIET_ZONE: i32 = 4;
+
+/// Render `text` as an SVG QR code, or `None` if it does not fit.
+#[must_use]
+pub fn qr_svg(text: &str) -> Option<String> {
+  let qr = QrCode::encode_text(text, QrCodeEcc::Medium).ok()?;
+  Some(render_svg(qr.size(), |x, y| qr.get_module(x, y)))
+}
+
+/// SVG for a `size`×`size` module grid.
+fn render_svg(size: i32, is_dark: impl Fn(i32, i32) -> bool) -> String {
+  let extent = size + QUIET_ZONE * 2;
+  let mut path = String::new();
+  for y in 0..size {
+    for x in 0..size {
+      if is_dark(x, y) {
+        let _ = write!(path, "M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE);
+      }
+    }
+  }
+  format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {extent} {extent}\" \
+     shape-rendering=\"crispEdges\"><rect width=\"{extent}\" height=\"{extent}\" \
+     fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>"
+  )
+}
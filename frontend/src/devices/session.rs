@@ -0,0 +1,755 @@
+//! Browser side of device linking.
+//!
+//! Runs the handshake described in [`super::link`] over
+//! `DeviceLinkSignal`, opens a dedicated `RTCPeerConnection` between
+//! the two devices, and streams `IndexedDB` history across its
+//! `DataChannel`. All state is in memory and dropped when the link
+//! ends; nothing about a link attempt is persisted.
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use leptos::prelude::*;
+use message::signaling::{DeviceLinkPayload, DeviceLinkSignal};
+use message::{DeviceId, UserId};
+
+use super::DeviceLinkManager;
+use super::link::{
+  EXPORT_PAGE_SIZE, ExportCursor, HistoryFrame, LinkFailure, LinkPhase, LinkRole,
+  LinkedConversation, link_key, short_code, split_batches,
+};
+use crate::persistence::record::{conversation_key, parse_conversation_key};
+use crate::persistence::{PersistenceManager, try_use_persistence_manager};
+use crate::state::{AppState, Conversation, ConversationId, ConversationType};
+use crate::webrtc::{
+  IceCandidateData, LinkCipher, LinkKeyPair, PeerConnection, PeerConnectionState, PeerDataChannel,
+  try_use_webrtc_manager,
+};
+
+/// Pause sending while this many bytes are queued on the channel.
+const BUFFERED_HIGH_WATER: u32 = 1024 * 1024;
+
+/// Poll interval while waiting for the channel buffer to drain.
+const BUFFER_POLL_MS: u64 = 20;
+
+/// State of one link attempt.
+#[derive(Default)]
+pub(super) struct LinkSession {
+  /// The other device, fixed by the first `Hello` / `Accept`.
+  peer: Option<DeviceId>,
+  /// This device's ephemeral key pair.
+  keys: Option<Rc<LinkKeyPair>>,
+  /// Transfer key, available once both public keys are known.
+  cipher: Option<Rc<LinkCipher>>,
+  /// The link connection (holder: initiator, joiner: answerer).
+  pc: Option<PeerConnection>,
+  /// History channel, once open (joiner) or created (holder).
+  channel: Option<PeerDataChannel>,
+  /// ICE candidates that arrived before the remote description.
+  pending_ice: Vec<IceCandidateData>,
+  /// Whether the remote description has been applied.
+  remote_set: bool,
+  /// Joiner: sealed frames waiting to be imported, in arrival order.
+  incoming: VecDeque<Vec<u8>>,
+  /// Joiner: whether a task is draining `incoming`.
+  draining: bool,
+  /// Joiner: conversations announced in `Start`.
+  conversations: Vec<LinkedConversation>,
+  /// Joiner: total announced in `Start`.
+  total: usize,
+  /// Records sent (holder) or imported (joiner).
+  records: usize,
+}
+
+impl LinkSession {
+  /// Close the connection and drop every handler.
+  fn close(&mut self) {
+    if let Some(channel) = self.channel.take() {
+      channel.close();
+    }
+    if let Some(mut pc) = self.pc.take() {
+      pc.close();
+    }
+  }
+}
+
+impl DeviceLinkManager {
+  /// Start waiting for a new device to say hello.
+  pub fn start_hosting(&self) {
+    self.reset();
+    self.inner.borrow_mut().session = Some(LinkSession::default());
+    self.phase.set(LinkPhase::Hosting);
+  }
+
+  /// Ask `holder`, another device of this account, for its history.
+  pub fn request_link(&self, holder: DeviceId) {
+    self.reset();
+    self.inner.borrow_mut().session = Some(LinkSession {
+      peer: Some(holder),
+      ..LinkSession::default()
+    });
+    self.phase.set(LinkPhase::Requesting { holder });
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let keys = match LinkKeyPair::generate().await {
+        Ok(keys) => Rc::new(keys),
+        Err(e) => return this.fail(LinkFailure::Crypto, &e),
+      };
+      let public_key = keys.public_key().to_vec();
+      if let Some(session) = this.inner.borrow_mut().session.as_mut() {
+        session.keys = Some(keys);
+      }
+      this.send(holder, DeviceLinkPayload::Hello { public_key });
+    });
+  }
+
+  /// Holder: the user confirmed that both devices show the same code.
+  /// Opens the history channel.
+  pub fn confirm(&self) {
+    let LinkPhase::Verifying {
+      peer,
+      role: LinkRole::Holder,
+      ..
+    } = self.phase.get_untracked()
+    else {
+      return;
+    };
+    self.phase.set(LinkPhase::Connecting {
+      peer,
+      role: LinkRole::Holder,
+    });
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let mut pc = match this.create_connection(peer, true) {
+        Ok(pc) => pc,
+        Err(e) => return this.fail(LinkFailure::Connection, &e),
+      };
+      if let Err(e) = pc.create_data_channel() {
+        return this.fail(LinkFailure::Connection, &e);
+      }
+      let channel = pc.get_data_channel().cloned();
+      let Some(channel) = channel else {
+        return this.fail(LinkFailure::Connection, "link DataChannel missing");
+      };
+      let exporter = this.clone();
+      channel.set_on_open(move || {
+        let exporter = exporter.clone();
+        wasm_bindgen_futures::spawn_local(async move { exporter.export_history().await });
+      });
+      if let Some(session) = this.inner.borrow_mut().session.as_mut() {
+        session.channel = Some(channel);
+      }
+      match pc.create_offer().await {
+        Ok(sdp) => this.send(peer, DeviceLinkPayload::Offer { sdp }),
+        Err(e) => this.fail(LinkFailure::Connection, &e),
+      }
+    });
+  }
+
+  /// Abort the link, telling the other device.
+  pub fn cancel(&self) {
+    if let Some(peer) = self.current_peer() {
+      self.send(peer, DeviceLinkPayload::Cancel);
+    }
+    self.reset();
+    self.phase.set(LinkPhase::Idle);
+  }
+
+  /// Handle a step relayed from another device of this account.
+  pub fn handle_signal(&self, signal: DeviceLinkSignal) {
+    let DeviceLinkSignal {
+      from_device,
+      payload,
+      ..
+    } = signal;
+    let phase = self.phase.get_untracked();
+    if !phase.accepts(&from_device, &payload) {
+      log(&format!(
+        "ignoring unexpected link step from {from_device} in {phase:?}"
+      ));
+      return;
+    }
+    match payload {
+      DeviceLinkPayload::Hello { public_key } => self.on_hello(from_device, public_key),
+      DeviceLinkPayload::Accept { public_key } => self.on_accept(from_device, public_key),
+      DeviceLinkPayload::Offer { sdp } => self.on_offer(from_device, sdp),
+      DeviceLinkPayload::Answer { sdp } => self.on_answer(sdp),
+      DeviceLinkPayload::IceCandidate {
+        candidate,
+        sdp_mid,
+        sdp_m_line_index,
+      } => self.on_ice_candidate(IceCandidateData {
+        candidate,
+        sdp_mid,
+        sdp_m_line_index,
+      }),
+      DeviceLinkPayload::Cancel => {
+        self.reset();
+        self.phase.set(LinkPhase::Failed(LinkFailure::Cancelled));
+      }
+    }
+  }
+
+  /// Holder: a new device said hello. Answer with our key and show the
+  /// short code.
+  fn on_hello(&self, joiner: DeviceId, joiner_key: Vec<u8>) {
+    {
+      let mut inner = self.inner.borrow_mut();
+      let Some(session) = inner.session.as_mut() else {
+        return;
+      };
+      // Only the first device to say hello is served.
+      if session.peer.is_some() {
+        return;
+      }
+      session.peer = Some(joiner);
+    }
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let keys = match LinkKeyPair::generate().await {
+        Ok(keys) => keys,
+        Err(e) => return this.fail(LinkFailure::Crypto, &e),
+      };
+      let holder_key = keys.public_key().to_vec();
+      let cipher = match derive_cipher(&keys, &joiner_key, &joiner_key, &holder_key).await {
+        Ok(cipher) => cipher,
+        Err(e) => return this.fail(LinkFailure::Crypto, &e),
+      };
+      if let Some(session) = this.inner.borrow_mut().session.as_mut() {
+        session.keys = Some(Rc::new(keys));
+        session.cipher = Some(Rc::new(cipher));
+      }
+      this.send(
+        joiner,
+        DeviceLinkPayload::Accept {
+          public_key: holder_key.clone(),
+        },
+      );
+      this.phase.set(LinkPhase::Verifying {
+        peer: joiner,
+        role: LinkRole::Holder,
+        code: short_code(&joiner_key, &holder_key),
+      });
+    });
+  }
+
+  /// Joiner: the holder accepted. Show the short code and wait for the
+  /// holder's offer.
+  fn on_accept(&self, holder: DeviceId, holder_key: Vec<u8>) {
+    let keys = self
+      .inner
+      .borrow()
+      .session
+      .as_ref()
+      .and_then(|s| s.keys.clone());
+    let Some(keys) = keys else {
+      // Our own key pair is still being generated; `Accept` cannot
+      // legitimately arrive before our `Hello`.
+      return;
+    };
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let joiner_key = keys.public_key().to_vec();
+      let cipher = match derive_cipher(&keys, &holder_key, &joiner_key, &holder_key).await {
+        Ok(cipher) => cipher,
+        Err(e) => return this.fail(LinkFailure::Crypto, &e),
+      };
+      if let Some(session) = this.inner.borrow_mut().session.as_mut() {
+        session.cipher = Some(Rc::new(cipher));
+      }
+      this.phase.set(LinkPhase::Verifying {
+        peer: holder,
+        role: LinkRole::Joiner,
+        code: short_code(&joiner_key, &holder_key),
+      });
+    });
+  }
+
+  /// Joiner: the holder confirmed the code and offers the channel.
+  fn on_offer(&self, holder: DeviceId, sdp: String) {
+    self.phase.set(LinkPhase::Connecting {
+      peer: holder,
+      role: LinkRole::Joiner,
+    });
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let pc = match this.create_connection(holder, false) {
+        Ok(pc) => pc,
+        Err(e) => return this.fail(LinkFailure::Connection, &e),
+      };
+      let receiver = this.clone();
+      let local_user = this.local_user();
+      pc.set_on_data_channel(move |channel| {
+        channel.set_binary_type(web_sys::RtcDataChannelType::Arraybuffer);
+        let channel = PeerDataChannel::new(channel, local_user.clone(), false);
+        let frames = receiver.clone();
+        channel.set_on_raw_message(move |frame| frames.receive_frame(frame));
+        if let Some(session) = receiver.inner.borrow_mut().session.as_mut() {
+          session.channel = Some(channel);
+        }
+      });
+      match pc.handle_offer(&sdp).await {
+        Ok(sdp) => {
+          this.send(holder, DeviceLinkPayload::Answer { sdp });
+          this.flush_ice().await;
+        }
+        Err(e) => this.fail(LinkFailure::Connection, &e),
+      }
+    });
+  }
+
+  /// Holder: the joiner answered.
+  fn on_answer(&self, sdp: String) {
+    let Some(pc) = self.connection() else {
+      return;
+    };
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      match pc.handle_answer(&sdp).await {
+        Ok(()) => this.flush_ice().await,
+        Err(e) => this.fail(LinkFailure::Connection, &e),
+      }
+    });
+  }
+
+  /// Apply a remote candidate, or queue it until the remote
+  /// description is set.
+  fn on_ice_candidate(&self, candidate: IceCandidateData) {
+    let pc = {
+      let mut inner = self.inner.borrow_mut();
+      let Some(session) = inner.session.as_mut() else {
+        return;
+      };
+      if !session.remote_set {
+        session.pending_ice.push(candidate);
+        return;
+      }
+      session.pc.clone()
+    };
+    let Some(pc) = pc else {
+      return;
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+      if let Err(e) = pc.add_ice_candidate(&candidate).await {
+        log(&format!("failed to add link ICE candidate: {e}"));
+      }
+    });
+  }
+
+  /// Mark the remote description as applied and add queued candidates.
+  async fn flush_ice(&self) {
+    let (pc, pending) = {
+      let mut inner = self.inner.borrow_mut();
+      let Some(session) = inner.session.as_mut() else {
+        return;
+      };
+      session.remote_set = true;
+      (session.pc.clone(), std::mem::take(&mut session.pending_ice))
+    };
+    let Some(pc) = pc else {
+      return;
+    };
+    for candidate in pending {
+      if let Err(e) = pc.add_ice_candidate(&candidate).await {
+        log(&format!("failed to add link ICE candidate: {e}"));
+      }
+    }
+  }
+
+  /// Create the link connection and route its ICE candidates and
+  /// failures.
+  fn create_connection(&self, peer: DeviceId, initiator: bool) -> Result<PeerConnection, String> {
+    let ice_servers = try_use_webrtc_manager()
+      .map(|m| m.ice_servers())
+      .unwrap_or_default();
+    let pc = PeerConnection::new(self.local_user(), initiator, &ice_servers)?;
+    let signals = self.clone();
+    pc.set_on_ice_candidate(move |c| {
+      signals.send(
+        peer,
+        DeviceLinkPayload::IceCandidate {
+          candidate: c.candidate,
+          sdp_mid: c.sdp_mid,
+          sdp_m_line_index: c.sdp_m_line_index,
+        },
+      );
+    });
+    let watcher = self.clone();
+    pc.set_on_connection_state_change(move |state| {
+      if !matches!(
+        state,
+        PeerConnectionState::Failed | PeerConnectionState::Closed
+      ) {
+        return;
+      }
+      if watcher.phase.get_untracked().is_active() {
+        watcher.fail(LinkFailure::Connection, "link connection failed");
+      } else {
+        // The joiner closed the connection after importing `End`.
+        watcher.reset();
+      }
+    });
+    if let Some(session) = self.inner.borrow_mut().session.as_mut() {
+      session.pc = Some(pc.clone());
+    }
+    Ok(pc)
+  }
+
+  /// Holder: stream every stored message to the joiner.
+  async fn export_history(&self) {
+    let Some(pm) = try_use_persistence_manager() else {
+      return self.fail(LinkFailure::Storage, "persistence unavailable");
+    };
+    let (channel, cipher, peer) = {
+      let inner = self.inner.borrow();
+      let Some(session) = inner.session.as_ref() else {
+        return;
+      };
+      (
+        session.channel.clone(),
+        session.cipher.clone(),
+        session.peer,
+      )
+    };
+    let (Some(channel), Some(cipher), Some(peer)) = (channel, cipher, peer) else {
+      return self.fail(LinkFailure::Connection, "link channel not ready");
+    };
+
+    let total = match pm.count_messages().await {
+      Ok(total) => total,
+      Err(e) => return self.fail(LinkFailure::Storage, &e.to_string()),
+    };
+    let conversations = self.app_state.conversations.with_untracked(|list| {
+      list
+        .iter()
+        .map(|c| LinkedConversation {
+          key: conversation_key(&c.id),
+          display_name: c.display_name.clone(),
+        })
+        .collect()
+    });
+    let start = HistoryFrame::Start {
+      total,
+      conversations,
+    };
+    if let Err(e) = send_frame(&channel, &cipher, &start).await {
+      return self.fail(LinkFailure::Connection, &e);
+    }
+    self.phase.set(LinkPhase::Syncing {
+      peer,
+      role: LinkRole::Holder,
+      records: 0,
+      total,
+    });
+
+    let mut cursor = ExportCursor::default();
+    let mut sent = 0;
+    let mut skipped = 0;
+    while !cursor.is_done() {
+      let page = match pm.export_page(cursor.from_ts(), EXPORT_PAGE_SIZE).await {
+        Ok(page) => page,
+        Err(e) => return self.fail(LinkFailure::Storage, &e.to_string()),
+      };
+      let (batches, too_large) = split_batches(cursor.advance(page, EXPORT_PAGE_SIZE));
+      skipped += too_large;
+      for records in batches {
+        let count = records.len();
+        if let Err(e) = send_frame(&channel, &cipher, &HistoryFrame::Records { records }).await {
+          return self.fail(LinkFailure::Connection, &e);
+        }
+        sent += count;
+        if !self.phase.get_untracked().is_active() {
+          // Cancelled while streaming.
+          return;
+        }
+        self.phase.set(LinkPhase::Syncing {
+          peer,
+          role: LinkRole::Holder,
+          records: sent,
+          total,
+        });
+      }
+    }
+    if let Err(e) = send_frame(&channel, &cipher, &HistoryFrame::End).await {
+      return self.fail(LinkFailure::Connection, &e);
+    }
+    if skipped > 0 {
+      log(&format!("skipped {skipped} records too large for the link"));
+    }
+    // The joiner closes the connection once it has imported `End`.
+    self.phase.set(LinkPhase::Done {
+      role: LinkRole::Holder,
+      records: sent,
+    });
+  }
+
+  /// Joiner: queue a sealed frame and make sure a task imports it.
+  /// Frames are imported strictly in order so `End` cannot overtake
+  /// the last batch.
+  fn receive_frame(&self, frame: Vec<u8>) {
+    {
+      let mut inner = self.inner.borrow_mut();
+      let Some(session) = inner.session.as_mut() else {
+        return;
+      };
+      session.incoming.push_back(frame);
+      if session.draining {
+        return;
+      }
+      session.draining = true;
+    }
+    let this = self.clone();
+    wasm_bindgen_futures::spawn_local(async move { this.drain_frames().await });
+  }
+
+  async fn drain_frames(&self) {
+    let Some(pm) = try_use_persistence_manager() else {
+      return self.fail(LinkFailure::Storage, "persistence unavailable");
+    };
+    loop {
+      let next = {
+        let mut inner = self.inner.borrow_mut();
+        let Some(session) = inner.session.as_mut() else {
+          return;
+        };
+        match session.incoming.pop_front() {
+          Some(frame) => session.cipher.clone().map(|cipher| (frame, cipher)),
+          None => {
+            session.draining = false;
+            return;
+          }
+        }
+      };
+      let Some((frame, cipher)) = next else {
+        return self.fail(LinkFailure::Crypto, "link key missing");
+      };
+      let frame = match cipher.open(&frame).await {
+        Ok(plain) => serde_json::from_slice::<HistoryFrame>(&plain).map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+      };
+      match frame {
+        Ok(frame) => {
+          if !self.import_frame(&pm, frame).await {
+            return;
+          }
+        }
+        Err(e) => return self.fail(LinkFailure::Crypto, &e),
+      }
+    }
+  }
+
+  /// Apply one decrypted frame. Returns `false` once the link is over.
+  async fn import_frame(&self, pm: &PersistenceManager, frame: HistoryFrame) -> bool {
+    let Some(peer) = self.phase.get_untracked().peer() else {
+      return false;
+    };
+    match frame {
+      HistoryFrame::Start {
+        total,
+        conversations,
+      } => {
+        if let Some(session) = self.inner.borrow_mut().session.as_mut() {
+          session.total = total;
+          session.conversations = conversations;
+        }
+        self.phase.set(LinkPhase::Syncing {
+          peer,
+          role: LinkRole::Joiner,
+          records: 0,
+          total,
+        });
+        true
+      }
+      HistoryFrame::Records { records } => {
+        let count = records.len();
+        if let Err(e) = pm.import_records(records).await {
+          self.fail(LinkFailure::Storage, &e.to_string());
+          return false;
+        }
+        let (received, total) = {
+          let mut inner = self.inner.borrow_mut();
+          let Some(session) = inner.session.as_mut() else {
+            return false;
+          };
+          session.records += count;
+          (session.records, session.total)
+        };
+        self.phase.set(LinkPhase::Syncing {
+          peer,
+          role: LinkRole::Joiner,
+          records: received,
+          total,
+        });
+        true
+      }
+      HistoryFrame::End => {
+        let (records, conversations) = {
+          let mut inner = self.inner.borrow_mut();
+          let Some(session) = inner.session.as_mut() else {
+            return false;
+          };
+          (session.records, std::mem::take(&mut session.conversations))
+        };
+        add_linked_conversations(self.app_state, conversations);
+        let _ = pm.persist_inverted_index().await;
+        self.reset();
+        self.phase.set(LinkPhase::Done {
+          role: LinkRole::Joiner,
+          records,
+        });
+        false
+      }
+    }
+  }
+
+  /// Relay `payload` to `peer`.
+  fn send(&self, peer: DeviceId, payload: DeviceLinkPayload) {
+    let Some(client) = crate::signaling::try_use_signaling_client() else {
+      return;
+    };
+    if let Err(e) = client.send_device_link_signal(peer, payload) {
+      log(&format!("failed to send link step: {e}"));
+    }
+  }
+
+  /// The link connection, if one exists.
+  fn connection(&self) -> Option<PeerConnection> {
+    self
+      .inner
+      .borrow()
+      .session
+      .as_ref()
+      .and_then(|s| s.pc.clone())
+  }
+
+  /// The other device: from the phase, or from the session while the
+  /// holder is still answering a `Hello`.
+  fn current_peer(&self) -> Option<DeviceId> {
+    self
+      .phase
+      .get_untracked()
+      .peer()
+      .or_else(|| self.inner.borrow().session.as_ref().and_then(|s| s.peer))
+  }
+
+  /// The local user, which tags the link connection in logs.
+  fn local_user(&self) -> UserId {
+    self.app_state.current_user_id().unwrap_or_default()
+  }
+
+  /// Abort with `failure`, telling the other device.
+  fn fail(&self, failure: LinkFailure, detail: &str) {
+    log(&format!("link failed ({failure:?}): {detail}"));
+    if let Some(peer) = self.current_peer() {
+      self.send(peer, DeviceLinkPayload::Cancel);
+    }
+    self.reset();
+    self.phase.set(LinkPhase::Failed(failure));
+  }
+
+  /// Drop the current session, closing its connection.
+  fn reset(&self) {
+    let session = self.inner.borrow_mut().session.take();
+    if let Some(mut session) = session {
+      session.close();
+    }
+  }
+}
+
+/// ECDH with `peer_key`, then the transfer key over both public keys
+/// in protocol order (`Hello` first).
+async fn derive_cipher(
+  keys: &LinkKeyPair,
+  peer_key: &[u8],
+  hello_key: &[u8],
+  accept_key: &[u8],
+) -> Result<LinkCipher, String> {
+  let secret = keys.agree(peer_key).await?;
+  LinkCipher::new(&link_key(&secret, hello_key, accept_key)).await
+}
+
+/// Seal and send one frame, waiting for the channel buffer to drain
+/// first.
+async fn send_frame(
+  channel: &PeerDataChannel,
+  cipher: &LinkCipher,
+  frame: &HistoryFrame,
+) -> Result<(), String> {
+  let plain = serde_json::to_vec(frame).map_err(|e| e.to_string())?;
+  let sealed = cipher.seal(&plain).await?;
+  while channel
+    .buffered_amount()
+    .is_some_and(|queued| queued > BUFFERED_HIGH_WATER)
+  {
+    sleep_ms(BUFFER_POLL_MS).await;
+  }
+  channel.send_raw(&sealed)
+}
+
+/// Sleep for a number of milliseconds without blocking the JS event
+/// loop.
+async fn sleep_ms(ms: u64) {
+  use wasm_bindgen::closure::Closure;
+  use wasm_bindgen::{JsCast, JsValue};
+  use wasm_bindgen_futures::JsFuture;
+
+  let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+    let resolve = resolve.clone();
+    let cb = Closure::once_into_js(move || {
+      let _ = resolve.call0(&JsValue::NULL);
+    });
+    if let Some(window) = web_sys::window() {
+      let _ =
+        window.set_timeout_with_callback_and_timeout_and_arguments_0(cb.unchecked_ref(), ms as i32);
+    }
+  });
+  let _ = JsFuture::from(promise).await;
+}
+
+/// List the conversations of another device (or a backup) that this
+/// device does not have. Returns how many were added.
+pub(crate) fn add_linked_conversations(
+  app_state: AppState,
+  conversations: Vec<LinkedConversation>,
+) -> usize {
+  let now = chrono::Utc::now().timestamp_millis();
+  let mut added = Vec::new();
+  app_state.conversations.update(|list| {
+    for linked in conversations {
+      let Some(id) = parse_conversation_key(&linked.key) else {
+        continue;
+      };
+      if list.iter().any(|c| c.id == id) {
+        continue;
+      }
+      let conversation_type = match id {
+        ConversationId::Direct(_) => ConversationType::Direct,
+        ConversationId::Room(_) => ConversationType::Room,
+      };
+      added.push(id.clone());
+      list.push(Conversation {
+        id,
+        display_name: linked.display_name,
+        last_message: None,
+        last_message_ts: Some(now),
+        unread_count: 0,
+        pinned: false,
+        pinned_ts: None,
+        muted: false,
+        archived: false,
+        conversation_type,
+      });
+    }
+  });
+  if !added.is_empty() {
+    for id in &added {
+      app_state.mark_conv_dirty(id);
+    }
+    app_state.persist_conversations();
+  }
+  added.len()
+}
+
+fn log(msg: &str) {
+  web_sys::console::log_1(&format!("[devices] {msg}").into());
+}
@@ -0,0 +1,282 @@
+use super::link::{
+  EXPORT_PAGE_SIZE, ExportCursor, HistoryFrame, LinkedConversation, MAX_FRAME_BYTES, link_key,
+  short_code, split_batches,
+};
+use super::qr::qr_svg;
+use super::*;
+use crate::persistence::record::{ContentRecord, MessageRecord, StatusRecord};
+use message::signaling::DeviceLinkPayload;
+use std::collections::BTreeMap;
+
+fn device(n: u128) -> DeviceInfo {
+  DeviceInfo {
+    device_id: DeviceId(uuid::Uuid::from_u128(n)),
+    name: format!("Device {n}"),
+  }
+}
+
+fn record(id: &str, ts: i64, text: &str) -> MessageRecord {
+  MessageRecord {
+    message_id: id.to_string(),
+    conversation: "d:11111111-1111-1111-1111-111111111111".to_string(),
+    timestamp_ms: ts,
+    sender: "11111111-1111-1111-1111-111111111111".to_string(),
+    sender_name: "S".to_string(),
+    outgoing: false,
+    status: StatusRecord::Received,
+    reply_to: None,
+    thread_root: None,
+    read_by: Vec::new(),
+    reactions: BTreeMap::new(),
+    mentions_me: false,
+    content: ContentRecord::Text {
+      text: text.to_string(),
+    },
+    edit: None,
+  }
+}
+
+// ── Device identity ──
+
+#[test]
+fn device_name_detects_browser_and_os() {
+  let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0";
+  assert_eq!(device_name(firefox), "Firefox on Linux");
+  let edge = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+              Chrome/129.0 Safari/537.36 Edg/129.0";
+  assert_eq!(device_name(edge), "Edge on Windows");
+  let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 \
+                (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+  assert_eq!(device_name(iphone), "Safari on iPhone");
+  let android = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/129.0 Mobile Safari/537.36";
+  assert_eq!(device_name(android), "Chrome on Android");
+}
+
+#[test]
+fn device_name_falls_back_for_unknown_agents() {
+  assert_eq!(device_name(""), "Browser");
+}
+
+// ── Offer answering ──
+
+#[test]
+fn every_device_answers_unaddressed_offers() {
+  let (first, second) = (device(1).device_id, device(2).device_id);
+  assert!(should_answer_offer(None, &first));
+  assert!(should_answer_offer(None, &second));
+  assert!(should_answer_offer(Some(&second), &second));
+  assert!(!should_answer_offer(Some(&first), &second));
+}
+
+// ── Link URL ──
+
+#[test]
+fn link_url_roundtrips_through_query() {
+  let holder = device(7).device_id;
+  let url = link_url("https://chat.example/", &holder);
+  assert_eq!(url, format!("https://chat.example/?link={holder}"));
+  let search = &url[url.find('?').unwrap()..];
+  assert_eq!(link_param(search), Some(holder));
+  assert_eq!(link_param(&format!("?lang=en&link={holder}")), Some(holder));
+}
+
+#[test]
+fn link_param_ignores_missing_or_malformed_ids() {
+  assert_eq!(link_param(""), None);
+  assert_eq!(link_param("?lang=en"), None);
+  assert_eq!(link_param("?link=not-a-uuid"), None);
+}
+
+#[test]
+fn link_url_fits_in_a_qr_code() {
+  let url = link_url("https://chat.example", &device(7).device_id);
+  let svg = qr_svg(&url).expect("URL fits");
+  assert!(svg.starts_with("<svg"));
+  assert!(svg.contains("<path d=\"M"));
+}
+
+// ── Link phases ──
+
+#[test]
+fn phase_accepts_only_the_next_step() {
+  let (peer, other) = (device(1).device_id, device(2).device_id);
+  let hello = DeviceLinkPayload::Hello {
+    public_key: vec![1],
+  };
+  let accept = DeviceLinkPayload::Accept {
+    public_key: vec![2],
+  };
+  let offer = DeviceLinkPayload::Offer { sdp: String::new() };
+  let answer = DeviceLinkPayload::Answer { sdp: String::new() };
+
+  assert!(LinkPhase::Hosting.accepts(&peer, &hello));
+  assert!(!LinkPhase::Idle.accepts(&peer, &hello));
+
+  let requesting = LinkPhase::Requesting { holder: peer };
+  assert!(requesting.accepts(&peer, &accept));
+  assert!(!requesting.accepts(&other, &accept));
+  assert!(!requesting.accepts(&peer, &offer));
+
+  let joiner = LinkPhase::Verifying {
+    peer,
+    role: LinkRole::Joiner,
+    code: String::new(),
+  };
+  assert!(joiner.accepts(&peer, &offer));
+  let holder = LinkPhase::Verifying {
+    peer,
+    role: LinkRole::Holder,
+    code: String::new(),
+  };
+  assert!(!holder.accepts(&peer, &offer));
+  assert!(!holder.accepts(&peer, &answer));
+
+  let connecting = LinkPhase::Connecting {
+    peer,
+    role: LinkRole::Holder,
+  };
+  assert!(connecting.accepts(&peer, &answer));
+  assert!(!connecting.accepts(&other, &answer));
+}
+
+#[test]
+fn cancel_is_accepted_only_from_the_peer() {
+  let (peer, other) = (device(1).device_id, device(2).device_id);
+  let phase = LinkPhase::Syncing {
+    peer,
+    role: LinkRole::Joiner,
+    records: 0,
+    total: 10,
+  };
+  assert!(phase.accepts(&peer, &DeviceLinkPayload::Cancel));
+  assert!(!phase.accepts(&other, &DeviceLinkPayload::Cancel));
+  assert!(!LinkPhase::Hosting.accepts(&peer, &DeviceLinkPayload::Cancel));
+}
+
+#[test]
+fn phase_activity_and_role() {
+  assert!(!LinkPhase::Idle.is_active());
+  assert!(LinkPhase::Hosting.is_active());
+  assert_eq!(LinkPhase::Hosting.role(), Some(LinkRole::Holder));
+  let done = LinkPhase::Done {
+    role: LinkRole::Joiner,
+    records: 3,
+  };
+  assert!(!done.is_active());
+  assert_eq!(done.peer(), None);
+  assert!(!LinkPhase::Failed(LinkFailure::Crypto).is_active());
+}
+
+// ── Codes and keys ──
+
+#[test]
+fn short_code_is_six_grouped_digits() {
+  let code = short_code(&[1; 65], &[2; 65]);
+  assert_eq!(code.len(), 7);
+  assert_eq!(code.as_bytes()[3], b' ');
+  assert!(
+    code
+      .chars()
+      .filter(|c| *c != ' ')
+      .all(|c| c.is_ascii_digit())
+  );
+}
+
+#[test]
+fn short_code_depends_on_key_order() {
+  assert_eq!(
+    short_code(&[1; 65], &[2; 65]),
+    short_code(&[1; 65], &[2; 65])
+  );
+  assert_ne!(
+    short_code(&[1; 65], &[2; 65]),
+    short_code(&[2; 65], &[1; 65])
+  );
+}
+
+#[test]
+fn link_key_binds_secret_and_both_keys() {
+  let key = link_key(&[9; 32], &[1; 65], &[2; 65]);
+  assert_ne!(key, link_key(&[8; 32], &[1; 65], &[2; 65]));
+  assert_ne!(key, link_key(&[9; 32], &[2; 65], &[1; 65]));
+}
+
+// ── History framing ──
+
+#[test]
+fn history_frame_json_is_tagged() {
+  let start = HistoryFrame::Start {
+    total: 2,
+    conversations: vec![LinkedConversation {
+      key: "r:22222222-2222-2222-2222-222222222222".to_string(),
+      display_name: "Room".to_string(),
+    }],
+  };
+  let json = serde_json::to_string(&start).unwrap();
+  assert!(json.contains("\"kind\":\"start\""));
+  assert_eq!(serde_json::from_str::<HistoryFrame>(&json).unwrap(), start);
+  assert_eq!(
+    serde_json::to_string(&HistoryFrame::End).unwrap(),
+    "{\"kind\":\"end\"}"
+  );
+}
+
+#[test]
+fn split_batches_respects_frame_limit() {
+  let text = "x".repeat(10_000);
+  let records: Vec<_> = (0..40)
+    .map(|i| record(&format!("m{i}"), i, &text))
+    .collect();
+  let (batches, skipped) = split_batches(records);
+  assert_eq!(skipped, 0);
+  assert!(batches.len() > 1);
+  assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 40);
+  for batch in batches {
+    let frame = HistoryFrame::Records { records: batch };
+    assert!(serde_json::to_vec(&frame).unwrap().len() <= MAX_FRAME_BYTES);
+  }
+}
+
+#[test]
+fn split_batches_skips_oversized_records() {
+  let records = vec![
+    record("small", 1, "hi"),
+    record("huge", 2, &"x".repeat(MAX_FRAME_BYTES)),
+  ];
+  let (batches, skipped) = split_batches(records);
+  assert_eq!(skipped, 1);
+  assert_eq!(batches.len(), 1);
+  assert_eq!(batches[0][0].message_id, "small");
+}
+
+#[test]
+fn export_cursor_skips_records_at_page_boundary() {
+  let mut cursor = ExportCursor::default();
+  let first = cursor.advance(vec![record("a", 1, ""), record("b", 2, "")], 2);
+  assert_eq!(first.len(), 2);
+  assert_eq!(cursor.from_ts(), 2);
+  assert!(!cursor.is_done());
+
+  // Next page is loaded from ts 2 and repeats "b".
+  let second = cursor.advance(vec![record("b", 2, ""), record("c", 2, "")], 2);
+  assert_eq!(second.len(), 1);
+  assert_eq!(second[0].message_id, "c");
+
+  let last = cursor.advance(
+    vec![record("b", 2, ""), record("c", 2, "")],
+    EXPORT_PAGE_SIZE,
+  );
+  assert!(last.is_empty());
+  assert!(cursor.is_done());
+}
+
+#[test]
+fn export_cursor_moves_past_a_full_page_of_seen_records() {
+  let mut cursor = ExportCursor::default();
+  cursor.advance(vec![record("a", 5, ""), record("b", 5, "")], 2);
+  let repeat = cursor.advance(vec![record("a", 5, ""), record("b", 5, "")], 2);
+  assert!(repeat.is_empty());
+  assert_eq!(cursor.from_ts(), 6);
+  assert!(!cursor.is_done());
+}
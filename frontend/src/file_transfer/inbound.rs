@@ -21,8 +21,10 @@ impl FileTransferManager {
   ///
   /// Registers (or re-initialises) the reassembly buffer. Subsequent
   /// `FileChunk` frames for the same `(peer, transfer_id)` pair will
-  /// land in this buffer.
+  /// land in this buffer; chunks that overtook the metadata on the
+  /// bulk channel are replayed into it.
   pub fn on_file_metadata(&self, peer: UserId, meta: FileMetadata) {
+    let tid = meta.transfer_id;
     let info = FileInfo {
       message_id: meta.message_id,
       transfer_id: meta.transfer_id,
@@ -34,8 +36,11 @@ impl FileTransferManager {
       chunk_size: meta.chunk_size,
       room_id: meta.room_id,
     };
-    let rx = IncomingTransfer::new(info, peer);
+    let rx = IncomingTransfer::new(info, peer.clone());
     self.register_inbound(rx);
+    for chunk in self.take_early_chunks(&peer, &tid) {
+      self.on_file_chunk(peer.clone(), chunk);
+    }
   }
 
   /// Handle an inbound `FileChunk` frame from `peer`.
@@ -50,6 +55,12 @@ impl FileTransferManager {
   /// aborted by a single corrupted chunk).
   pub fn on_file_chunk(&self, peer: UserId, chunk: FileChunk) {
     let tid = chunk.transfer_id;
+    if !self.has_inbound(&peer, &tid) {
+      // Metadata still in flight on the control channel. Chunks past
+      // the buffer cap are dropped and re-requested by resume.
+      self.hold_early_chunk(&peer, chunk);
+      return;
+    }
     let index = chunk.chunk_index;
     let data = chunk.data;
     let expected_hash = chunk.chunk_hash;
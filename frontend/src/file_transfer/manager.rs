@@ -9,7 +9,7 @@
 //! following the same single-threaded WASM convention as the other
 //! managers in the codebase (`ChatManager`, `WebRtcManager`, ...).
 
-use super::receive::IncomingTransfer;
+use super::receive::{EarlyChunks, IncomingTransfer};
 use super::send::OutgoingTransfer;
 use super::types::{FileInfo, PeerProgress, TransferProgress, TransferStatus};
 use crate::state::{AppState, ConversationId, use_app_state};
@@ -31,6 +31,8 @@ struct Inner {
   /// the UI can address an inbound transfer by the chat message id
   /// that announced it.
   inbound_by_message: HashMap<MessageId, (UserId, TransferId)>,
+  /// Chunks that overtook their `FileMetadata` on the bulk channel.
+  early_chunks: EarlyChunks,
 }
 
 impl Inner {
@@ -39,6 +41,7 @@ impl Inner {
       outbound: HashMap::new(),
       inbound: HashMap::new(),
       inbound_by_message: HashMap::new(),
+      early_chunks: EarlyChunks::default(),
     }
   }
 }
@@ -167,6 +170,31 @@ impl FileTransferManager {
       .cloned()
   }
 
+  /// Whether an inbound transfer is registered for (peer, transfer_id).
+  #[must_use]
+  pub fn has_inbound(&self, peer: &UserId, transfer_id: &TransferId) -> bool {
+    self
+      .inner
+      .borrow()
+      .inbound
+      .contains_key(&(peer.clone(), *transfer_id))
+  }
+
+  /// Hold a chunk whose transfer is not registered yet. Returns
+  /// `false` if the chunk was dropped.
+  pub fn hold_early_chunk(&self, peer: &UserId, chunk: message::datachannel::FileChunk) -> bool {
+    self.inner.borrow_mut().early_chunks.push(peer, chunk)
+  }
+
+  /// Take the chunks held for a transfer that just registered.
+  pub fn take_early_chunks(
+    &self,
+    peer: &UserId,
+    transfer_id: &TransferId,
+  ) -> Vec<message::datachannel::FileChunk> {
+    self.inner.borrow_mut().early_chunks.take(peer, transfer_id)
+  }
+
   /// Mutate an inbound transfer in-place.
   ///
   /// Returns whatever the closure returns, or `None` when the
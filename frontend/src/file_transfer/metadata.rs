@@ -0,0 +1,326 @@
+//! Metadata scrubbing for outgoing images.
+//!
+//! Phone photos carry EXIF blocks with GPS coordinates, the device
+//! model and capture timestamps. Everything in a room sees the bytes
+//! we send, so images are rewritten before they leave the device:
+//!
+//! * **JPEG** — `APP1` (EXIF / XMP), `APP13` (IPTC / Photoshop), `COM`
+//!   and every other `APPn` segment except `APP0` (JFIF), `APP2`
+//!   `ICC_PROFILE` and `APP14` (Adobe colour transform) are dropped.
+//!   Anything after `EOI` — where multi-picture files keep secondary
+//!   images with their own EXIF — is dropped too. A non-default EXIF
+//!   `Orientation` survives as a one-tag EXIF block, since browsers
+//!   rely on it to show portrait photos the right way up.
+//! * **PNG** — `tEXt`, `zTXt`, `iTXt`, `eXIf` and `tIME` chunks are
+//!   dropped. Kept chunks are copied with their CRC untouched.
+//! * **WebP** — `EXIF` and `XMP ` chunks are dropped, the matching
+//!   `VP8X` flag bits are cleared and the RIFF size is rewritten.
+//!
+//! Pixel data is never decoded or re-encoded. Files in any other
+//! format are left as they are. A file that stops parsing part-way —
+//! usually a truncated download — still loses the metadata before the
+//! break. A JPEG cut short inside its image data keeps what arrived;
+//! anything after any other break in a JPEG is dropped, since metadata
+//! segments further on can no longer be found reliably. For PNG and
+//! WebP the unparseable tail is copied verbatim, unless it opens a
+//! metadata chunk, which is dropped.
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// PNG chunks that only carry metadata.
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 5] = [b"tEXt", b"zTXt", b"iTXt", b"eXIf", b"tIME"];
+
+const EXIF_HEADER: &[u8; 6] = b"Exif\0\0";
+const TAG_ORIENTATION: u16 = 0x0112;
+const TIFF_SHORT: u16 = 3;
+
+/// `VP8X` feature flags announcing EXIF (`0x08`) and XMP (`0x04`) chunks.
+const VP8X_METADATA_FLAGS: u8 = 0x08 | 0x04;
+
+/// Image container the scrubber understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+  Jpeg,
+  Png,
+  Webp,
+}
+
+/// Detect the container from its magic bytes.
+#[must_use]
+pub fn detect_format(bytes: &[u8]) -> Option<ImageFormat> {
+  if bytes.starts_with(&JPEG_SOI) {
+    Some(ImageFormat::Jpeg)
+  } else if bytes.starts_with(&PNG_SIGNATURE) {
+    Some(ImageFormat::Png)
+  } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+    Some(ImageFormat::Webp)
+  } else {
+    None
+  }
+}
+
+/// Remove metadata from a JPEG, PNG or WebP file.
+///
+/// Returns `None` when the format is not recognised; callers then fall
+/// back to the original bytes.
+#[must_use]
+pub fn strip_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+  Some(match detect_format(bytes)? {
+    ImageFormat::Jpeg => strip_jpeg(bytes),
+    ImageFormat::Png => strip_png(bytes),
+    ImageFormat::Webp => strip_webp(bytes),
+  })
+}
+
+/// [`strip_metadata`], keeping `bytes` unchanged when the format is
+/// not recognised.
+#[must_use]
+pub fn scrubbed(bytes: Vec<u8>) -> Vec<u8> {
+  strip_metadata(&bytes).unwrap_or(bytes)
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(&JPEG_SOI);
+  let mut pos = 2;
+  // Whatever follows a break is dropped: it may hide EXIF further on.
+  let _ = copy_jpeg_segments(bytes, &mut pos, &mut out);
+  out
+}
+
+/// Copy the segments from `pos` up to `EOI` into `out`, scrubbing as
+/// they go. Returns `None` where parsing breaks off, with `pos` left
+/// on the first byte not yet handled.
+fn copy_jpeg_segments(bytes: &[u8], pos: &mut usize, out: &mut Vec<u8>) -> Option<()> {
+  loop {
+    if *bytes.get(*pos)? != 0xFF {
+      return None;
+    }
+    // Markers may be preceded by any number of 0xFF fill bytes.
+    let mut at = *pos;
+    while *bytes.get(at + 1)? == 0xFF {
+      at += 1;
+    }
+    let marker = bytes[at + 1];
+    match marker {
+      // EOI: stop here and drop any trailer.
+      0xD9 => {
+        out.extend_from_slice(&[0xFF, 0xD9]);
+        return Some(());
+      }
+      // TEM and RSTn carry no length field.
+      0x01 | 0xD0..=0xD7 => {
+        out.extend_from_slice(&[0xFF, marker]);
+        *pos = at + 2;
+        continue;
+      }
+      _ => {}
+    }
+    let len = usize::from(u16::from_be_bytes([
+      *bytes.get(at + 2)?,
+      *bytes.get(at + 3)?,
+    ]));
+    if len < 2 {
+      return None;
+    }
+    let end = at + 2 + len;
+    let segment = bytes.get(at..end)?;
+    if keep_jpeg_segment(marker, &segment[4..]) {
+      out.extend_from_slice(segment);
+    } else if let Some(orientation) = exif_orientation(marker, &segment[4..]) {
+      out.extend(orientation_segment(orientation));
+    }
+    *pos = end;
+    if marker == 0xDA {
+      // Entropy-coded data runs until the next marker that is not a
+      // stuffed 0xFF00 or a restart marker. A scan cut off by the end
+      // of the file holds no other markers, so what arrived is kept.
+      let Some(scan_end) = entropy_end(bytes, end) else {
+        out.extend_from_slice(&bytes[end..]);
+        *pos = bytes.len();
+        return None;
+      };
+      out.extend_from_slice(&bytes[end..scan_end]);
+      *pos = scan_end;
+    }
+  }
+}
+
+fn keep_jpeg_segment(marker: u8, payload: &[u8]) -> bool {
+  // APP0 (JFIF) and APP14 (Adobe) fall through to the default arm.
+  match marker {
+    0xE2 => payload.starts_with(b"ICC_PROFILE\0"),
+    0xE1 | 0xE3..=0xED | 0xEF | 0xFE => false,
+    _ => true,
+  }
+}
+
+/// The `Orientation` tag of an EXIF `APP1` payload, when it is set to
+/// anything but the default "top-left".
+fn exif_orientation(marker: u8, payload: &[u8]) -> Option<u16> {
+  if marker != 0xE1 {
+    return None;
+  }
+  let tiff = payload.strip_prefix(EXIF_HEADER)?;
+  let big_endian = match tiff.get(..2)? {
+    b"MM" => true,
+    b"II" => false,
+    _ => return None,
+  };
+  let u16_at = |at: usize| {
+    let raw = [*tiff.get(at)?, *tiff.get(at + 1)?];
+    Some(if big_endian {
+      u16::from_be_bytes(raw)
+    } else {
+      u16::from_le_bytes(raw)
+    })
+  };
+  let raw_ifd = tiff.get(4..8)?.try_into().ok()?;
+  let ifd = usize::try_from(if big_endian {
+    u32::from_be_bytes(raw_ifd)
+  } else {
+    u32::from_le_bytes(raw_ifd)
+  })
+  .ok()?;
+  let entries = usize::from(u16_at(ifd)?);
+  (0..entries)
+    .map(|i| ifd + 2 + i * 12)
+    .find(|&entry| u16_at(entry) == Some(TAG_ORIENTATION))
+    .and_then(|entry| {
+      (u16_at(entry + 2)? == TIFF_SHORT)
+        .then(|| u16_at(entry + 8))
+        .flatten()
+    })
+    .filter(|orientation| (2..=8).contains(orientation))
+}
+
+/// A minimal big-endian EXIF `APP1` segment holding only `orientation`.
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+  let mut tiff = b"MM\0\x2A\0\0\0\x08".to_vec();
+  tiff.extend_from_slice(&1u16.to_be_bytes());
+  tiff.extend_from_slice(&TAG_ORIENTATION.to_be_bytes());
+  tiff.extend_from_slice(&TIFF_SHORT.to_be_bytes());
+  tiff.extend_from_slice(&1u32.to_be_bytes());
+  tiff.extend_from_slice(&orientation.to_be_bytes());
+  tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+  let len = u16::try_from(2 + EXIF_HEADER.len() + tiff.len()).unwrap_or(u16::MAX);
+  let mut out = vec![0xFF, 0xE1];
+  out.extend_from_slice(&len.to_be_bytes());
+  out.extend_from_slice(EXIF_HEADER);
+  out.extend(tiff);
+  out
+}
+
+fn entropy_end(bytes: &[u8], mut pos: usize) -> Option<usize> {
+  loop {
+    if *bytes.get(pos)? == 0xFF {
+      match *bytes.get(pos + 1)? {
+        0x00 | 0xD0..=0xD7 | 0xFF => {}
+        _ => return Some(pos),
+      }
+    }
+    pos += 1;
+  }
+}
+
+fn strip_png(bytes: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(&PNG_SIGNATURE);
+  let mut pos = PNG_SIGNATURE.len();
+  if copy_png_chunks(bytes, &mut pos, &mut out).is_none() {
+    // A metadata chunk cut short is dropped like a whole one.
+    let tail = bytes.get(pos..).unwrap_or_default();
+    if !tail.get(4..8).is_some_and(is_png_metadata) {
+      out.extend_from_slice(tail);
+    }
+  }
+  out
+}
+
+/// Copy the chunks from `pos` up to `IEND` into `out`, dropping
+/// metadata. Returns `None` where parsing breaks off, with `pos` left
+/// on the first chunk not yet handled.
+fn copy_png_chunks(bytes: &[u8], pos: &mut usize, out: &mut Vec<u8>) -> Option<()> {
+  loop {
+    let len = u32::from_be_bytes(bytes.get(*pos..*pos + 4)?.try_into().ok()?);
+    let kind = bytes.get(*pos + 4..*pos + 8)?;
+    // length + type + data + CRC
+    let end = pos
+      .checked_add(12)?
+      .checked_add(usize::try_from(len).ok()?)?;
+    let chunk = bytes.get(*pos..end)?;
+    if !is_png_metadata(kind) {
+      out.extend_from_slice(chunk);
+    }
+    if kind == b"IEND" {
+      return Some(());
+    }
+    *pos = end;
+  }
+}
+
+fn is_png_metadata(kind: &[u8]) -> bool {
+  PNG_METADATA_CHUNKS.iter().any(|m| m.as_slice() == kind)
+}
+
+fn strip_webp(bytes: &[u8]) -> Vec<u8> {
+  // A RIFF size past the end of the file means it was cut short; scrub
+  // what is there.
+  let riff_size = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+  let riff_end = usize::try_from(riff_size)
+    .ok()
+    .and_then(|size| size.checked_add(8))
+    .map_or(bytes.len(), |end| end.clamp(12, bytes.len()));
+  let body = &bytes[12..riff_end];
+
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(b"RIFF\0\0\0\0WEBP");
+  let mut pos = 0;
+  while pos < body.len() {
+    let Some((kind, end)) = webp_chunk_bounds(body, pos) else {
+      // A metadata chunk cut short is dropped like a whole one.
+      if !matches!(body.get(pos..pos + 4), Some(b"EXIF" | b"XMP ")) {
+        out.extend_from_slice(&body[pos..]);
+      }
+      break;
+    };
+    let chunk = &body[pos..end.min(body.len())];
+    match kind {
+      b"EXIF" | b"XMP " => {}
+      b"VP8X" => {
+        let flags_at = out.len() + 8;
+        out.extend_from_slice(chunk);
+        if let Some(flags) = out.get_mut(flags_at) {
+          *flags &= !VP8X_METADATA_FLAGS;
+        }
+      }
+      _ => out.extend_from_slice(chunk),
+    }
+    pos = end;
+  }
+  if out.len() % 2 == 1 {
+    out.push(0);
+  }
+  let size = u32::try_from(out.len() - 8).unwrap_or(u32::MAX);
+  out[4..8].copy_from_slice(&size.to_le_bytes());
+  out
+}
+
+/// Type and padded end of the chunk at `pos`, or `None` when its
+/// header is cut short or it overruns `body` by more than the final
+/// padding byte.
+fn webp_chunk_bounds(body: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+  let kind = body.get(pos..pos + 4)?;
+  let len = usize::try_from(u32::from_le_bytes(
+    body.get(pos + 4..pos + 8)?.try_into().ok()?,
+  ))
+  .ok()?;
+  // Chunks are padded to an even size.
+  let end = pos.checked_add(8)?.checked_add(len)?.checked_add(len % 2)?;
+  if end > body.len() && end - body.len() != 1 {
+    return None;
+  }
+  Some((kind, end))
+}
@@ -15,8 +15,9 @@
 //! * [`dispatch::broadcast_file`] — outbound dispatch loop that the
 //!   UI layer spawns after the user confirms a file pick.
 //! * [`start_outgoing_transfer`] — high-level public API used by the
-//!   picker component. Performs size + extension validation, seeds
-//!   the reactive state, and kicks off the dispatch loop.
+//!   picker component. Strips image metadata ([`metadata`]), performs
+//!   size + extension validation, seeds the reactive state, and kicks
+//!   off the dispatch loop.
 //!
 //! ## Reactivity
 //!
@@ -30,6 +31,7 @@ pub mod dispatch;
 pub mod hash;
 pub mod inbound;
 pub mod manager;
+pub mod metadata;
 pub mod receive;
 pub mod send;
 pub mod thumbnail;
@@ -88,7 +90,8 @@ pub enum StartTransferOutcome {
 /// * `conv` — destination conversation.
 /// * `filename` — display filename.
 /// * `mime_type` — MIME type (best effort).
-/// * `bytes` — raw file bytes (owned).
+/// * `bytes` — raw file bytes (owned). Image metadata is stripped
+///   here unless the user disabled that in privacy settings.
 /// * `object_url` — blob URL for local preview / download (created
 ///   by the picker component).
 ///
@@ -106,6 +109,9 @@ pub async fn start_outgoing_transfer(
   if bytes.is_empty() {
     return StartTransferOutcome::Empty;
   }
+  // Scrub before the size check and the hash so both describe the
+  // bytes the peers actually receive.
+  let bytes = send::outgoing_bytes(bytes, crate::settings::load_snapshot().strip_metadata);
   let peers = manager.peers_for_conversation(&conv);
   if peers.is_empty() {
     return StartTransferOutcome::NoPeers;
@@ -7,9 +7,10 @@
 
 use super::types::{FileInfo, TransferDirection, TransferProgress, TransferStatus};
 use leptos::prelude::*;
-use message::UserId;
+use message::datachannel::FileChunk;
 use message::frame::ChunkBitmap;
-use std::collections::HashMap;
+use message::{TransferId, UserId};
+use std::collections::{HashMap, VecDeque};
 
 /// Inbound transfer state record.
 #[derive(Debug, Clone)]
@@ -218,10 +219,71 @@ impl IncomingTransfer {
   }
 }
 
+/// Chunks that arrived before their `FileMetadata`.
+///
+/// Chunks travel on the unordered bulk channel while the metadata goes
+/// on the ordered control channel, so the first chunks of a transfer
+/// can overtake it. They wait here until the metadata registers the
+/// transfer. The buffer is bounded: the oldest transfer is evicted
+/// first, and a dropped chunk is simply re-requested by the resume
+/// round like any other gap.
+#[derive(Debug, Default)]
+pub struct EarlyChunks {
+  pending: VecDeque<((UserId, TransferId), Vec<FileChunk>)>,
+}
+
+impl EarlyChunks {
+  /// Transfers held at once.
+  pub const MAX_TRANSFERS: usize = 4;
+  /// Chunks held per transfer.
+  pub const MAX_CHUNKS: usize = 32;
+
+  /// Hold `chunk` from `peer`. Returns `false` when it was dropped
+  /// because its transfer already holds [`Self::MAX_CHUNKS`].
+  pub fn push(&mut self, peer: &UserId, chunk: FileChunk) -> bool {
+    let key = (peer.clone(), chunk.transfer_id);
+    if let Some((_, chunks)) = self.pending.iter_mut().find(|(k, _)| *k == key) {
+      if chunks.len() >= Self::MAX_CHUNKS {
+        return false;
+      }
+      chunks.push(chunk);
+      return true;
+    }
+    if self.pending.len() >= Self::MAX_TRANSFERS {
+      self.pending.pop_front();
+    }
+    self.pending.push_back((key, vec![chunk]));
+    true
+  }
+
+  /// Remove and return every chunk held for `(peer, transfer_id)`.
+  pub fn take(&mut self, peer: &UserId, transfer_id: &TransferId) -> Vec<FileChunk> {
+    self
+      .pending
+      .iter()
+      .position(|((p, t), _)| p == peer && t == transfer_id)
+      .and_then(|i| self.pending.remove(i))
+      .map(|(_, chunks)| chunks)
+      .unwrap_or_default()
+  }
+
+  /// Number of chunks held across all transfers.
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.pending.iter().map(|(_, chunks)| chunks.len()).sum()
+  }
+
+  /// Whether nothing is held.
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.pending.is_empty()
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
-  use message::{MessageId, TransferId, UserId};
+  use message::MessageId;
 
   fn make_info(total: u32, size: u64) -> FileInfo {
     FileInfo {
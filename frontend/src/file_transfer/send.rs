@@ -181,6 +181,18 @@ pub const fn initial_chunk_size() -> usize {
   INITIAL_CHUNK_SIZE
 }
 
+/// Bytes to put on the wire for an outbound file. JPEG, PNG and WebP
+/// images lose their EXIF / XMP / text metadata unless the user opted
+/// out in privacy settings (see [`super::metadata`]).
+#[must_use]
+pub fn outgoing_bytes(bytes: Vec<u8>, strip_metadata: bool) -> Vec<u8> {
+  if strip_metadata {
+    super::metadata::scrubbed(bytes)
+  } else {
+    bytes
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use crate::file_transfer::types::{
@@ -3,14 +3,16 @@
 //! # Test organization
 //! - `helpers_size_limits` — size limits, format helpers, extension checks
 //! - `progress_eta` — progress snapshots, percent, ETA, throughput
-//! - `reassembly` — inbound reassembly, bitmap accounting, chunk gaps
+//! - `reassembly` — inbound reassembly, bitmap accounting, chunk gaps, early chunks
 //! - `hashing` — SHA-256 native fallback, hex formatting
 //! - `flow_control` — chunk-size adaptation, stall-timeout, E2EE headroom
 //! - `resume` — disconnect-resume, per-chunk hash validation, resume requests
 //! - `room_routing` — room-id routing for file metadata
+//! - `scrubbing` — EXIF / XMP / text-chunk stripping over fixture images
 
 use super::hash;
-use super::receive::IncomingTransfer;
+use super::metadata;
+use super::receive::{EarlyChunks, IncomingTransfer};
 use super::send::OutgoingTransfer;
 use super::types::{
   DANGEROUS_EXTENSIONS, FileInfo, MULTI_PEER_SIZE_LIMIT, SINGLE_PEER_SIZE_LIMIT, TransferDirection,
@@ -40,3 +42,4 @@ mod progress_eta;
 mod reassembly;
 mod resume;
 mod room_routing;
+mod scrubbing;
@@ -43,3 +43,48 @@ fn reassemble_detects_size_mismatch() {
   let err = rx.reassemble().unwrap_err();
   assert!(err.contains("size mismatch"), "unexpected error: {err}");
 }
+
+fn early_chunk(transfer_id: TransferId, index: u32) -> message::datachannel::FileChunk {
+  message::datachannel::FileChunk {
+    transfer_id,
+    chunk_index: index,
+    total_chunks: 64,
+    data: vec![0; 4],
+    chunk_hash: [0; 32],
+  }
+}
+
+#[test]
+fn early_chunks_are_held_per_peer_and_transfer() {
+  let mut early = EarlyChunks::default();
+  let (a, b) = (UserId::from(1u64), UserId::from(2u64));
+  let tid = TransferId::new();
+  assert!(early.push(&a, early_chunk(tid, 1)));
+  assert!(early.push(&a, early_chunk(tid, 0)));
+  assert!(early.push(&b, early_chunk(tid, 0)));
+  assert_eq!(early.len(), 3);
+
+  let held: Vec<_> = early.take(&a, &tid).iter().map(|c| c.chunk_index).collect();
+  assert_eq!(held, vec![1, 0]);
+  assert!(early.take(&a, &tid).is_empty());
+  assert_eq!(early.take(&b, &tid).len(), 1);
+  assert!(early.is_empty());
+}
+
+#[test]
+fn early_chunks_are_bounded() {
+  let mut early = EarlyChunks::default();
+  let peer = UserId::from(1u64);
+  let first = TransferId::new();
+  for i in 0..EarlyChunks::MAX_CHUNKS {
+    assert!(early.push(&peer, early_chunk(first, u32::try_from(i).unwrap())));
+  }
+  assert!(!early.push(&peer, early_chunk(first, 99)));
+
+  // A new transfer beyond the cap evicts the oldest one.
+  for _ in 0..EarlyChunks::MAX_TRANSFERS {
+    early.push(&peer, early_chunk(TransferId::new(), 0));
+  }
+  assert!(early.take(&peer, &first).is_empty());
+  assert_eq!(early.len(), EarlyChunks::MAX_TRANSFERS);
+}
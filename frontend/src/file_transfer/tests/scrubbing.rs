@@ -0,0 +1,299 @@
+use super::metadata::{ImageFormat, detect_format, scrubbed, strip_metadata};
+
+const GPS: &[u8] = b"GPSLatitude=48.8584";
+
+// ── Fixtures ──
+
+fn jpeg_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+  let len = u16::try_from(payload.len() + 2).unwrap();
+  let mut out = vec![0xFF, marker];
+  out.extend_from_slice(&len.to_be_bytes());
+  out.extend_from_slice(payload);
+  out
+}
+
+/// SOI, JFIF, EXIF, XMP, IPTC, ICC, comment, a quantisation table,
+/// one scan with a stuffed byte and a restart marker, EOI, then a
+/// multi-picture trailer.
+fn jpeg_fixture() -> Vec<u8> {
+  let mut out = vec![0xFF, 0xD8];
+  out.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+  out.extend(jpeg_segment(0xE1, &[b"Exif\0\0".as_slice(), GPS].concat()));
+  out.extend(jpeg_segment(
+    0xE1,
+    b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>",
+  ));
+  out.extend(jpeg_segment(0xED, b"Photoshop 3.0\08BIM"));
+  out.extend(jpeg_segment(0xE2, b"ICC_PROFILE\0\x01\x01profile"));
+  out.extend(jpeg_segment(0xFE, b"Pixel 8 Pro"));
+  out.extend(jpeg_segment(0xDB, &[0; 65]));
+  out.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+  out.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56]);
+  out.extend_from_slice(&[0xFF, 0xD9]);
+  out.extend_from_slice(&[0xFF, 0xD8]);
+  out.extend(jpeg_segment(0xE1, &[b"Exif\0\0".as_slice(), GPS].concat()));
+  out
+}
+
+/// Little-endian EXIF payload: `Make`, then `Orientation`, in IFD0.
+fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+  let mut out = b"Exif\0\0II\x2A\0\x08\0\0\0\x02\0".to_vec();
+  out.extend_from_slice(&[0x0F, 0x01, 2, 0, 4, 0, 0, 0, b'A', b'c', b'm', 0]);
+  out.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0]);
+  out.extend_from_slice(&orientation.to_le_bytes());
+  out.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+  out.extend_from_slice(GPS);
+  out
+}
+
+fn png_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+  let mut out = u32::try_from(data.len()).unwrap().to_be_bytes().to_vec();
+  out.extend_from_slice(kind);
+  out.extend_from_slice(data);
+  out.extend_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+  out
+}
+
+fn png_fixture() -> Vec<u8> {
+  let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+  out.extend(png_chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+  out.extend(png_chunk(b"tEXt", b"Author\0Alice"));
+  out.extend(png_chunk(
+    b"iTXt",
+    b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>",
+  ));
+  out.extend(png_chunk(b"eXIf", GPS));
+  out.extend(png_chunk(b"tIME", &[7, 234, 1, 1, 0, 0, 0]));
+  out.extend(png_chunk(b"IDAT", &[1, 2, 3, 4]));
+  out.extend(png_chunk(b"IEND", &[]));
+  out
+}
+
+fn webp_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+  let mut out = kind.to_vec();
+  out.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+  out.extend_from_slice(data);
+  if data.len() % 2 == 1 {
+    out.push(0);
+  }
+  out
+}
+
+fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+  let body = chunks.concat();
+  let mut out = b"RIFF".to_vec();
+  out.extend_from_slice(&u32::try_from(body.len() + 4).unwrap().to_le_bytes());
+  out.extend_from_slice(b"WEBP");
+  out.extend(body);
+  out
+}
+
+fn webp_fixture() -> Vec<u8> {
+  webp(&[
+    webp_chunk(b"VP8X", &[0x2C, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+    webp_chunk(b"ICCP", b"profile"),
+    webp_chunk(b"VP8 ", &[9, 8, 7]),
+    webp_chunk(b"EXIF", GPS),
+    webp_chunk(b"XMP ", b"<x:xmpmeta/>"),
+  ])
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+  haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// ── Detection ──
+
+#[test]
+fn detects_formats_by_magic_bytes() {
+  assert_eq!(detect_format(&jpeg_fixture()), Some(ImageFormat::Jpeg));
+  assert_eq!(detect_format(&png_fixture()), Some(ImageFormat::Png));
+  assert_eq!(detect_format(&webp_fixture()), Some(ImageFormat::Webp));
+  assert_eq!(detect_format(b"RIFF\0\0\0\0WAVE"), None);
+  assert_eq!(detect_format(b"%PDF-1.7"), None);
+}
+
+// ── JPEG ──
+
+#[test]
+fn jpeg_drops_exif_xmp_iptc_comments_and_trailer() {
+  let out = strip_metadata(&jpeg_fixture()).expect("fixture parses");
+  let mut expected = vec![0xFF, 0xD8];
+  expected.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+  expected.extend(jpeg_segment(0xE2, b"ICC_PROFILE\0\x01\x01profile"));
+  expected.extend(jpeg_segment(0xDB, &[0; 65]));
+  expected.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+  expected.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56, 0xFF, 0xD9]);
+  assert_eq!(out, expected);
+  assert!(!contains(&out, GPS));
+  assert!(!contains(&out, b"Pixel 8 Pro"));
+}
+
+#[test]
+fn jpeg_keeps_only_a_non_default_orientation() {
+  let rotated = [
+    vec![0xFF, 0xD8],
+    jpeg_segment(0xE1, &exif_with_orientation(6)),
+    jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]),
+    vec![0x01, 0xFF, 0xD9],
+  ]
+  .concat();
+  let out = strip_metadata(&rotated).expect("fixture parses");
+  let mut minimal = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01".to_vec();
+  minimal.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+  let expected = [
+    vec![0xFF, 0xD8],
+    jpeg_segment(0xE1, &minimal),
+    jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]),
+    vec![0x01, 0xFF, 0xD9],
+  ]
+  .concat();
+  assert_eq!(out, expected);
+  assert!(!contains(&out, b"Acm"));
+
+  let upright = [
+    vec![0xFF, 0xD8],
+    jpeg_segment(0xE1, &exif_with_orientation(1)),
+    jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]),
+    vec![0x01, 0xFF, 0xD9],
+  ]
+  .concat();
+  let out = strip_metadata(&upright).expect("fixture parses");
+  assert!(!contains(&out, b"Exif"));
+}
+
+#[test]
+fn jpeg_without_metadata_is_unchanged() {
+  let mut clean = vec![0xFF, 0xD8];
+  clean.extend(jpeg_segment(0xE0, b"JFIF\0"));
+  clean.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+  clean.extend_from_slice(&[0x01, 0x02, 0xFF, 0xD9]);
+  assert_eq!(strip_metadata(&clean), Some(clean));
+}
+
+#[test]
+fn truncated_jpeg_drops_a_cut_exif_segment() {
+  let mut fixture = jpeg_fixture();
+  // Inside the EXIF segment that follows JFIF.
+  fixture.truncate(30);
+  let mut expected = vec![0xFF, 0xD8];
+  expected.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+  assert_eq!(strip_metadata(&fixture), Some(expected.clone()));
+  assert_eq!(scrubbed(fixture), expected);
+}
+
+#[test]
+fn truncated_jpeg_is_scrubbed_up_to_the_cut() {
+  let fixture = jpeg_fixture();
+  let scan = fixture.windows(2).position(|w| w == [0xFF, 0xDA]).unwrap();
+  // SOS segment plus the first four bytes of entropy-coded data.
+  let cut = fixture[..scan + 14].to_vec();
+  let out = scrubbed(cut);
+  let mut expected = vec![0xFF, 0xD8];
+  expected.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+  expected.extend(jpeg_segment(0xE2, b"ICC_PROFILE\0\x01\x01profile"));
+  expected.extend(jpeg_segment(0xDB, &[0; 65]));
+  expected.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+  expected.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34]);
+  assert_eq!(out, expected);
+  assert!(!contains(&out, GPS));
+  assert!(!contains(&out, b"Pixel 8 Pro"));
+}
+
+#[test]
+fn malformed_jpeg_drops_the_unparseable_tail() {
+  let mut fixture = vec![0xFF, 0xD8];
+  fixture.extend(jpeg_segment(0xFE, b"Pixel 8 Pro"));
+  fixture.extend_from_slice(b"garbage");
+  assert_eq!(strip_metadata(&fixture), Some(b"\xFF\xD8".to_vec()));
+}
+
+#[test]
+fn corrupt_jpeg_segment_hides_no_later_exif() {
+  let exif = jpeg_segment(0xE1, &[b"Exif\0\0".as_slice(), GPS].concat());
+  let mut expected = vec![0xFF, 0xD8];
+  expected.extend(jpeg_segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+
+  // A segment length below two, then a missing marker prefix.
+  for corrupt in [[0xFF, 0xDB, 0x00, 0x01].as_slice(), b"junk"] {
+    let mut fixture = expected.clone();
+    fixture.extend_from_slice(corrupt);
+    fixture.extend(exif.clone());
+    fixture.extend(jpeg_segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+    fixture.extend_from_slice(&[0x12, 0x34, 0xFF, 0xD9]);
+    let out = scrubbed(fixture);
+    assert_eq!(out, expected);
+    assert!(!contains(&out, GPS));
+  }
+}
+
+// ── PNG ──
+
+#[test]
+fn png_drops_text_exif_and_time_chunks() {
+  let out = strip_metadata(&png_fixture()).expect("fixture parses");
+  let mut expected = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+  expected.extend(png_chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+  expected.extend(png_chunk(b"IDAT", &[1, 2, 3, 4]));
+  expected.extend(png_chunk(b"IEND", &[]));
+  assert_eq!(out, expected);
+}
+
+#[test]
+fn truncated_png_keeps_a_cut_image_chunk() {
+  let mut fixture = png_fixture();
+  // Inside `IDAT`, just past its length and type.
+  fixture.truncate(fixture.len() - 20);
+  let mut expected = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+  expected.extend(png_chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+  expected.extend_from_slice(&png_chunk(b"IDAT", &[1, 2, 3, 4])[..8]);
+  assert_eq!(strip_metadata(&fixture), Some(expected));
+}
+
+#[test]
+fn truncated_png_drops_a_cut_text_chunk() {
+  let fixture = png_fixture();
+  let text = fixture.windows(4).position(|w| w == b"tEXt").unwrap();
+  let out = strip_metadata(&fixture[..text + 8]).unwrap();
+  assert!(!contains(&out, b"tEXt"));
+  assert!(!contains(&out, b"Author"));
+}
+
+// ── WebP ──
+
+#[test]
+fn webp_drops_exif_and_xmp_and_clears_flags() {
+  let out = strip_metadata(&webp_fixture()).expect("fixture parses");
+  let expected = webp(&[
+    webp_chunk(b"VP8X", &[0x20, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+    webp_chunk(b"ICCP", b"profile"),
+    webp_chunk(b"VP8 ", &[9, 8, 7]),
+  ]);
+  assert_eq!(out, expected);
+}
+
+#[test]
+fn truncated_webp_drops_a_cut_exif_chunk() {
+  let fixture = webp_fixture();
+  let exif = fixture.windows(4).position(|w| w == b"EXIF").unwrap();
+  let out = strip_metadata(&fixture[..exif + 12]).unwrap();
+  let expected = webp(&[
+    webp_chunk(b"VP8X", &[0x20, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+    webp_chunk(b"ICCP", b"profile"),
+    webp_chunk(b"VP8 ", &[9, 8, 7]),
+  ]);
+  assert_eq!(out, expected);
+}
+
+#[test]
+fn simple_webp_is_unchanged() {
+  let simple = webp(&[webp_chunk(b"VP8L", &[1, 2, 3, 4, 5])]);
+  assert_eq!(strip_metadata(&simple), Some(simple));
+}
+
+#[test]
+fn non_images_pass_through_untouched() {
+  let pdf = b"%PDF-1.7 GPSLatitude".to_vec();
+  assert_eq!(strip_metadata(&pdf), None);
+  assert_eq!(scrubbed(pdf.clone()), pdf);
+}
@@ -18,8 +18,10 @@
 //!    identicons at scale. FNV-1a provides sufficient distribution for
 //!    this use case.
 //!
-//! If cryptographic uniqueness becomes a requirement (e.g. for avatar
-//! fingerprint verification), replace `identicon_hash` with `sha2::Sha256`.
+//! Where cryptographic uniqueness matters — the visual form of an E2EE
+//! safety number — [`generate_fingerprint_identicon_svg`] derives the
+//! grid from a SHA-256 digest instead, so two different safety numbers
+//! cannot be made to look alike by searching for an FNV collision.
 
 /// Color palette for identicon generation (HSL-based, visually distinct).
 ///
@@ -42,7 +44,32 @@ const GRID_SIZE: usize = 5;
 /// or rendered inline.
 #[must_use]
 pub fn generate_identicon_svg(username: &str) -> String {
-  let hash = identicon_hash(username);
+  render_identicon_svg(&identicon_hash(username))
+}
+
+/// Generate a deterministic SVG identicon for a security fingerprint
+/// (e.g. a safety number).
+///
+/// Same 5×5 layout as [`generate_identicon_svg`], but the pattern and
+/// colour are taken from `SHA-256(fingerprint)` so the image inherits
+/// the collision resistance of the fingerprint it represents.
+#[must_use]
+pub fn generate_fingerprint_identicon_svg(fingerprint: &str) -> String {
+  use sha2::Digest;
+  render_identicon_svg(&sha2::Sha256::digest(fingerprint.as_bytes()))
+}
+
+/// Data URI variant of [`generate_fingerprint_identicon_svg`]. Not
+/// cached: fingerprints are rendered once per verification panel.
+#[must_use]
+pub fn generate_fingerprint_identicon_data_uri(fingerprint: &str) -> String {
+  let svg = generate_fingerprint_identicon_svg(fingerprint);
+  format!("data:image/svg+xml;charset=utf-8,{}", url_encode_svg(&svg))
+}
+
+/// Render the identicon SVG from hash bytes (at least 16 bytes are
+/// consumed; shorter inputs wrap around).
+fn render_identicon_svg(hash: &[u8]) -> String {
   let foreground_hue = HUE_PALETTE[(hash[0] as usize) % HUE_PALETTE.len()];
   let background_hue = (foreground_hue + 180) % 360;
 
@@ -72,3 +72,26 @@ fn test_data_uri_cache_distinguishes_keys() {
   let b = generate_identicon_data_uri("bob");
   assert_ne!(a, b);
 }
+
+#[test]
+fn test_fingerprint_identicon_is_deterministic() {
+  let a = generate_fingerprint_identicon_svg("12345 67890");
+  assert_eq!(a, generate_fingerprint_identicon_svg("12345 67890"));
+  assert!(a.starts_with("<svg"));
+}
+
+#[test]
+fn test_fingerprint_identicon_differs_from_username_identicon() {
+  // Same input, different hash: the fingerprint variant must not reuse
+  // the non-cryptographic FNV path.
+  assert_ne!(
+    generate_fingerprint_identicon_svg("alice"),
+    generate_identicon_svg("alice")
+  );
+}
+
+#[test]
+fn test_fingerprint_identicon_data_uri_format() {
+  let uri = generate_fingerprint_identicon_data_uri("123");
+  assert!(uri.starts_with("data:image/svg+xml"));
+}
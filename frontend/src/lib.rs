@@ -5,12 +5,14 @@
 
 pub mod app;
 pub mod auth;
+pub mod backup;
 pub mod blacklist;
 pub mod call;
 pub mod chat;
 pub mod components;
 pub mod config;
 pub mod cross_tab;
+pub mod devices;
 pub mod error_handler;
 pub mod file_transfer;
 pub mod i18n_helpers;
@@ -62,6 +64,9 @@ pub fn init() {
     // route inbound invitations through them immediately.
     let blacklist = blacklist::provide_blacklist_state();
     let invite_mgr = invite::provide_invite_manager();
+    // Device-link manager: handles `DeviceLinkSignal` frames and the
+    // `?link=` parameter of a page opened from another device's QR code.
+    devices::provide_device_link_manager(app_state);
 
     // Initialize config once and provide via context
     crate::config::provide_config();
@@ -181,6 +186,16 @@ pub fn init() {
     call_manager.set_signaling(signaling);
     call_manager.set_webrtc(webrtc_manager.clone());
 
+    // Push microphone-processing toggles into the running call so a
+    // change in the A/V settings is heard immediately.
+    {
+      let settings = settings_state;
+      Effect::new(move |_| {
+        let prefs = settings.signal().with(|s| s.audio_processing);
+        call_manager.set_audio_processing(call::dsp_config(prefs));
+      });
+    }
+
     // Initialize file-transfer manager (Task 19) and link it to the
     // WebRTC mesh so outbound chunks reach their peers and inbound
     // FileMetadata / FileChunk frames find a registered handler.
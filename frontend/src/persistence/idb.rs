@@ -55,7 +55,9 @@ pub async fn open_db() -> IdbResult<IdbDatabase> {
           // steps. Before this fix the value was hard-coded to 0 which
           // would re-run the v1 schema creation on every upgrade.
           let old_version = event.old_version() as u32;
-          let _ = apply_migration(&db, old_version);
+          if let Some(tx) = req.transaction() {
+            let _ = apply_migration(&db, &tx, old_version);
+          }
         }
       })
     };
@@ -6,7 +6,7 @@ use crate::persistence::idb::open_db;
 use crate::persistence::record::{MessageRecord, conversation_key, from_record, to_record};
 use crate::persistence::retention::{cleanup_on_quota_exceeded, sweep_retention};
 use crate::persistence::schema::{
-  HISTORY_PAGE_SIZE, INDEX_REBUILD_DELTA, INVERTED_INDEX_THRESHOLD,
+  HISTORY_PAGE_SIZE, INDEX_REBUILD_DELTA, INVERTED_INDEX_THRESHOLD, THREAD_LOAD_LIMIT,
 };
 use crate::persistence::search::{
   InvertedIndex, SearchQuery, SearchResult, extend_inverted_index, full_scan_search, score_records,
@@ -14,10 +14,12 @@ use crate::persistence::search::{
 use crate::persistence::store::{
   SearchIndexEntry, clear_search_index, count_all, delete_conversation,
   delete_search_index_for_conversation, get_message, load_after, load_all_from, load_before,
-  load_recent, load_search_index, message_exists, put_message, put_messages, put_search_entries,
+  load_recent, load_search_index, load_thread, message_exists, put_message, put_messages,
+  put_search_entries,
 };
 use crate::state::ConversationId;
 use chrono::Utc;
+use message::MessageId;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use web_sys::IdbDatabase;
@@ -170,6 +172,16 @@ impl PersistenceManager {
     Ok(records.iter().filter_map(from_record).collect())
   }
 
+  /// Load the newest [`THREAD_LOAD_LIMIT`] replies of the thread
+  /// rooted at `root`, oldest first.
+  pub async fn load_thread(&self, root: &MessageId) -> Result<Vec<ChatMessage>, PersistError> {
+    let db = self.db().await?;
+    let records = load_thread(&db, &root.to_string(), THREAD_LOAD_LIMIT)
+      .await
+      .map_err(|e| PersistError::Db(js_err(&e)))?;
+    Ok(records.iter().filter_map(from_record).collect())
+  }
+
   /// Page up by loading messages older than `before_ts` (Req 14.11.3).
   pub async fn load_before(
     &self,
@@ -262,6 +274,50 @@ impl PersistenceManager {
       .map_err(|e| PersistError::Db(js_err(&e)))
   }
 
+  /// Number of stored messages across every conversation.
+  pub async fn count_messages(&self) -> Result<usize, PersistError> {
+    let db = self.db().await?;
+    count_all(&db)
+      .await
+      .map_err(|e| PersistError::Db(js_err(&e)))
+  }
+
+  /// Up to `limit` stored messages with `timestamp_ms >= from_ts`
+  /// across every conversation, oldest first. Used to stream history
+  /// to a linked device.
+  pub async fn export_page(
+    &self,
+    from_ts: i64,
+    limit: usize,
+  ) -> Result<Vec<MessageRecord>, PersistError> {
+    let db = self.db().await?;
+    load_all_from(&db, from_ts, limit)
+      .await
+      .map_err(|e| PersistError::Db(js_err(&e)))
+  }
+
+  /// Store records received from a linked device. Messages this device
+  /// already has are kept as they are. Returns how many were added.
+  pub async fn import_records(&self, records: Vec<MessageRecord>) -> Result<usize, PersistError> {
+    let db = self.db().await?;
+    let mut fresh = Vec::with_capacity(records.len());
+    for record in records {
+      let exists = message_exists(&db, &record.message_id)
+        .await
+        .map_err(|e| PersistError::Db(js_err(&e)))?;
+      if !exists {
+        fresh.push(record);
+      }
+    }
+    put_messages(&db, &fresh)
+      .await
+      .map_err(|e| PersistError::Db(js_err(&e)))?;
+    if let Some(index) = self.inner.borrow_mut().index.as_mut() {
+      extend_inverted_index(index, &fresh);
+    }
+    Ok(fresh.len())
+  }
+
   /// Delete every stored message belonging to `conv` — used when
   /// the user clears the chat history.
   pub async fn clear_conversation(&self, conv: &ConversationId) -> Result<usize, PersistError> {
@@ -303,8 +359,9 @@ impl PersistenceManager {
   }
 
   /// Streaming index rebuild: reads messages in batches so the full
-  /// corpus is never materialised in memory at once.
-  async fn rebuild_index_streaming(&self) -> Result<(), PersistError> {
+  /// corpus is never materialised in memory at once. Also run after a
+  /// backup restore so `search_index` covers the imported records.
+  pub(crate) async fn rebuild_index_streaming(&self) -> Result<(), PersistError> {
     let db = self.db().await?;
     let mut from_ts = 0_i64;
     let batch_size = 5_000;
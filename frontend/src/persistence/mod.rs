@@ -45,6 +45,10 @@
 //! | `ack_queue` | Range deletes by `(message_id, peer_id)` |
 //! | `conversation_flags` | Authoritative pin/mute/archive (Req 7.7d) |
 //!
+//! Every store except `ack_queue` and the derived `search_index` is
+//! carried by the encrypted backup in [`crate::backup`]; restore
+//! rebuilds `search_index` from the merged messages.
+//!
 //! ### localStorage (see [`crate::utils`])
 //!
 //! Used for **small, synchronous-access bootstrap data**:
@@ -55,6 +59,7 @@
 //! | UI bootstrap | `conversations` (skeleton only), `active_conversation_id` |
 //! | User preferences | `settings_user`, `settings_theme`, `settings_locale`, `settings_theater_overlay` |
 //! | Privacy lists | `blacklist` |
+//! | Device identity | `device_id` (survives logout, see [`crate::devices`]) |
 //! | Developer tools | `debug_mode`, `debug_buffer_size`, `debug_filter` (NOT preserved on cache clear) |
 //!
 //! ### Decision rules for new state
@@ -85,7 +90,7 @@ pub mod store;
 #[cfg(target_arch = "wasm32")]
 pub use manager::try_use_persistence_manager;
 pub use manager::{PersistenceManager, provide_persistence_manager, use_persistence_manager};
-pub use record::{MessageRecord, RetentionPolicy};
+pub use record::{AvatarEntry, ConvFlagsEntry, MessageRecord, RetentionPolicy};
 pub use search::{SearchHit, SearchQuery, SearchResult};
 
 #[cfg(test)]
@@ -10,9 +10,10 @@
 //! browser's origin sandbox.
 
 use crate::chat::models::{
-  ChatMessage, FileRef, ImageRef, MessageContent, MessageStatus, ReactionEntry, ReplySnippet,
-  StickerRef, VoiceClip,
+  ChatMessage, EditHistory, FileRef, ImageRef, MessageContent, MessageRevision, MessageStatus,
+  ReactionEntry, ReplySnippet, StickerRef, VoiceClip,
 };
+use crate::chat::polls::{PollBallot, PollState};
 use crate::state::ConversationId;
 use message::{MessageId, RoomId, UserId};
 use serde::{Deserialize, Serialize};
@@ -90,6 +91,10 @@ pub struct MessageRecord {
   pub status: StatusRecord,
   /// Reply-to snippet (if any).
   pub reply_to: Option<ReplyRecord>,
+  /// Thread root id (UUID string) for thread replies. Omitted for
+  /// main-timeline messages so the `by_thread_ts` index stays sparse.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub thread_root: Option<String>,
   /// Users that acknowledged reading this message (Req 2.3).
   pub read_by: Vec<String>,
   /// Reactions keyed by emoji (BTreeMap -> stable order).
@@ -97,8 +102,34 @@ pub struct MessageRecord {
   /// `true` if this message mentions the local user.
   pub mentions_me: bool,
   /// Message content (Text / Sticker / Voice / Image / Forwarded /
-  /// Revoked). Stored as a nested JSON object.
+  /// File / Poll / Revoked). Stored as a nested JSON object.
   pub content: ContentRecord,
+  /// Edit state with prior revisions. Absent on unedited messages and
+  /// on records written before editing existed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub edit: Option<EditRecord>,
+}
+
+/// JSON projection of [`EditHistory`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditRecord {
+  /// Revision number of the current content.
+  pub revision: u32,
+  /// Unix-ms timestamp of the latest edit.
+  pub edited_at_ms: i64,
+  /// Earlier revisions, oldest first.
+  pub previous: Vec<RevisionRecord>,
+}
+
+/// JSON projection of [`MessageRevision`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevisionRecord {
+  /// Revision number (`0` = the original send).
+  pub revision: u32,
+  /// Text content of this revision.
+  pub text: String,
+  /// Unix-ms timestamp at which this revision was written.
+  pub timestamp_ms: i64,
 }
 
 /// JSON projection of [`MessageStatus`].
@@ -218,10 +249,74 @@ pub enum ContentRecord {
     #[serde(default)]
     file_hash: String,
   },
+  /// Poll with its reconciled ballots, so tallies survive a reload.
+  Poll {
+    /// Poll question.
+    question: String,
+    /// Answer options, in display order.
+    options: Vec<String>,
+    /// Whether a voter may pick more than one option.
+    multiple_choice: bool,
+    /// Whether voter names are hidden.
+    anonymous: bool,
+    /// Deadline (Unix ms).
+    deadline_ms: Option<i64>,
+    /// Latest ballot per voter, keyed by user id (UUID string).
+    ballots: BTreeMap<String, BallotRecord>,
+    /// Unix-ms timestamp at which the creator closed the poll.
+    closed_at_ms: Option<i64>,
+  },
   /// Placeholder shown after a successful revoke.
   Revoked,
 }
 
+/// JSON projection of [`PollBallot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BallotRecord {
+  /// Chosen option indices (empty after a retraction).
+  pub choices: Vec<u32>,
+  /// Sender timestamp (Unix ms) of the vote.
+  pub timestamp_ms: i64,
+}
+
+/// Avatar cache entry stored under the `user_id` primary key of the
+/// `avatars` store.
+///
+/// Defined here rather than in the wasm-only `store` tree so the
+/// backup format can carry it natively.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvatarEntry {
+  /// User id (UUID string) — primary key.
+  pub user_id: String,
+  /// Avatar data URI or object URL.
+  pub data_uri: String,
+  /// Unix-ms timestamp of last write. Used for LRU eviction once the
+  /// cache fills up.
+  pub cached_at_ms: i64,
+}
+
+/// One row in the `conversation_flags` store.
+///
+/// Serialised as JSON via `to_js` / `from_js`. The primary key is
+/// `conversation_id` to match the object-store `keyPath` declared in
+/// the v4 migration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConvFlagsEntry {
+  /// Conversation identifier serialised to a stable string. Matches
+  /// the persisted form used by the legacy localStorage cache so
+  /// existing entries can be migrated without re-keying.
+  pub conversation_id: String,
+  /// Whether the conversation is pinned to the top of the sidebar.
+  pub pinned: bool,
+  /// Unix-ms timestamp at which the user pinned the conversation.
+  /// `None` when the conversation is not pinned.
+  pub pinned_at_ms: Option<i64>,
+  /// Whether per-conversation Do-Not-Disturb is enabled.
+  pub muted: bool,
+  /// Whether the conversation has been archived.
+  pub archived: bool,
+}
+
 // ── Conversion helpers ────────────────────────────────────────────────
 
 /// Convert a [`ConversationId`] to its indexed string form.
@@ -265,6 +360,7 @@ pub fn to_record(msg: &ChatMessage, conv: &ConversationId) -> MessageRecord {
       sender_name: r.sender_name.clone(),
       preview: r.preview.clone(),
     }),
+    thread_root: msg.thread_root.map(|id| id.to_string()),
     read_by: msg.read_by.iter().map(ToString::to_string).collect(),
     reactions: msg
       .reactions
@@ -309,8 +405,42 @@ pub fn to_record(msg: &ChatMessage, conv: &ConversationId) -> MessageRecord {
         dangerous: file.dangerous,
         file_hash: hex::encode(file.file_hash),
       },
+      MessageContent::Poll(poll) => ContentRecord::Poll {
+        question: poll.question.clone(),
+        options: poll.options.clone(),
+        multiple_choice: poll.multiple_choice,
+        anonymous: poll.anonymous,
+        deadline_ms: poll.deadline_ms,
+        ballots: poll
+          .ballots
+          .iter()
+          .map(|(user, b)| {
+            (
+              user.to_string(),
+              BallotRecord {
+                choices: b.choices.clone(),
+                timestamp_ms: b.timestamp_ms,
+              },
+            )
+          })
+          .collect(),
+        closed_at_ms: poll.closed_at_ms,
+      },
       MessageContent::Revoked => ContentRecord::Revoked,
     },
+    edit: msg.edit.as_ref().map(|e| EditRecord {
+      revision: e.revision,
+      edited_at_ms: e.edited_at_ms,
+      previous: e
+        .previous
+        .iter()
+        .map(|r| RevisionRecord {
+          revision: r.revision,
+          text: r.content.clone(),
+          timestamp_ms: r.timestamp_ms,
+        })
+        .collect(),
+    }),
   }
 }
 
@@ -377,6 +507,34 @@ pub fn from_record(rec: &MessageRecord) -> Option<ChatMessage> {
         .and_then(|v| v.try_into().ok())
         .unwrap_or([0u8; 32]),
     }),
+    ContentRecord::Poll {
+      question,
+      options,
+      multiple_choice,
+      anonymous,
+      deadline_ms,
+      ballots,
+      closed_at_ms,
+    } => MessageContent::Poll(PollState {
+      question: question.clone(),
+      options: options.clone(),
+      multiple_choice: *multiple_choice,
+      anonymous: *anonymous,
+      deadline_ms: *deadline_ms,
+      ballots: ballots
+        .iter()
+        .filter_map(|(user, b)| {
+          Some((
+            user.parse::<UserId>().ok()?,
+            PollBallot {
+              choices: b.choices.clone(),
+              timestamp_ms: b.timestamp_ms,
+            },
+          ))
+        })
+        .collect(),
+      closed_at_ms: *closed_at_ms,
+    }),
     ContentRecord::Revoked => MessageContent::Revoked,
   };
   let reply_to = rec.reply_to.as_ref().and_then(|r| {
@@ -412,10 +570,24 @@ pub fn from_record(rec: &MessageRecord) -> Option<ChatMessage> {
     outgoing: rec.outgoing,
     status: rec.status.into(),
     reply_to,
+    thread_root: rec.thread_root.as_deref().and_then(|s| s.parse().ok()),
     read_by,
     reactions,
     mentions_me: rec.mentions_me,
     counted_unread: false,
+    edit: rec.edit.as_ref().map(|e| EditHistory {
+      revision: e.revision,
+      edited_at_ms: e.edited_at_ms,
+      previous: e
+        .previous
+        .iter()
+        .map(|r| MessageRevision {
+          revision: r.revision,
+          content: r.text.clone(),
+          timestamp_ms: r.timestamp_ms,
+        })
+        .collect(),
+    }),
   })
 }
 
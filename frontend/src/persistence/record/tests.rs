@@ -12,10 +12,12 @@ fn sample() -> ChatMessage {
     outgoing: false,
     status: MessageStatus::Received,
     reply_to: None,
+    thread_root: None,
     read_by: vec![UserId::from(2u64)],
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
@@ -28,6 +30,50 @@ fn text_record_roundtrip() {
   assert_eq!(back, msg);
 }
 
+#[test]
+fn edited_record_roundtrip_keeps_history() {
+  let mut msg = sample();
+  msg.apply_edit("hello again".to_string(), 1, 1_700_000_001_000);
+  msg.apply_edit("hello at last".to_string(), 2, 1_700_000_002_000);
+  let conv = ConversationId::Direct(UserId::from(7u64));
+  let rec = to_record(&msg, &conv);
+  assert_eq!(rec.edit.as_ref().unwrap().previous.len(), 2);
+  let back = from_record(&rec).unwrap();
+  assert_eq!(back, msg);
+}
+
+#[test]
+fn record_without_edit_field_deserializes() {
+  let conv = ConversationId::Direct(UserId::from(7u64));
+  let mut json = serde_json::to_value(to_record(&sample(), &conv)).unwrap();
+  assert!(json.get("edit").is_none());
+  json.as_object_mut().unwrap().remove("edit");
+  let rec: MessageRecord = serde_json::from_value(json).unwrap();
+  assert!(rec.edit.is_none());
+}
+
+#[test]
+fn thread_reply_record_roundtrip() {
+  let mut msg = sample();
+  msg.thread_root = Some(MessageId::new());
+  let conv = ConversationId::Direct(UserId::from(7u64));
+  let rec = to_record(&msg, &conv);
+  assert_eq!(rec.thread_root, msg.thread_root.map(|id| id.to_string()));
+  let back = from_record(&rec).unwrap();
+  assert_eq!(back, msg);
+}
+
+#[test]
+fn main_timeline_record_omits_thread_root() {
+  // Leaving the key out entirely keeps the record out of the sparse
+  // `by_thread_ts` index.
+  let conv = ConversationId::Direct(UserId::from(7u64));
+  let json = serde_json::to_value(to_record(&sample(), &conv)).unwrap();
+  assert!(json.get("thread_root").is_none());
+  let rec: MessageRecord = serde_json::from_value(json).unwrap();
+  assert!(rec.thread_root.is_none());
+}
+
 #[test]
 fn conversation_key_roundtrip_direct() {
   let id = ConversationId::Direct(UserId::from(42u64));
@@ -104,6 +150,27 @@ fn forwarded_record_roundtrip() {
   assert_eq!(back, msg);
 }
 
+#[test]
+fn poll_record_roundtrip_keeps_ballots() {
+  let mut msg = sample();
+  msg.content = MessageContent::Poll(crate::chat::polls::PollState {
+    question: "Lunch?".to_string(),
+    options: vec!["Pizza".to_string(), "Sushi".to_string()],
+    multiple_choice: true,
+    anonymous: true,
+    deadline_ms: Some(1_700_000_100_000),
+    ballots: BTreeMap::new(),
+    closed_at_ms: None,
+  });
+  assert!(msg.apply_poll_vote(UserId::from(2u64), vec![0, 1], 1_700_000_001_000));
+  assert!(msg.apply_poll_vote(UserId::from(3u64), vec![], 1_700_000_002_000));
+  assert!(msg.close_poll(&UserId::from(1u64), 1_700_000_003_000));
+  let conv = ConversationId::Direct(UserId::from(7u64));
+  let rec = to_record(&msg, &conv);
+  let back = from_record(&rec).unwrap();
+  assert_eq!(back, msg);
+}
+
 #[test]
 fn file_record_roundtrip() {
   let mut msg = sample();
@@ -5,13 +5,17 @@
 //! * `messages` — one record per message. Primary key: `message_id`
 //!   (UUID string). Indexes: `(conversation, timestamp_ms)` for paged
 //!   history loading, `(conversation)` for delete-all, `(timestamp_ms)`
-//!   for global retention sweeps.
+//!   for global retention sweeps, `(thread_root, timestamp_ms)` for
+//!   loading one thread. The thread index is sparse: main-timeline
+//!   records omit `thread_root`, so only replies are indexed.
 //! * `avatars` — cached avatar data URIs. Primary key: `user_id`. No
 //!   indexes; looked up by primary key only.
 //! * `search_index` — posting list entries for the inverted index.
 //!   Primary key: auto-increment. Indexes: `(token)` for lookups,
 //!   `(conversation)` for per-conversation cleanups.
 //! * `ack_queue` — unacknowledged message queue (Req 11.3).
+//! * `identity_keys` — the local identity key pair per account, with
+//!   the private key kept as a non-extractable `CryptoKey`.
 //!
 //! Versioning: bump [`DB_VERSION`] whenever [`apply_migration`] needs
 //! to add / alter a store or index. Downgrades are unsupported (the
@@ -22,7 +26,7 @@
 pub const DB_NAME: &str = "chat_frontend";
 
 /// Current schema version.
-pub const DB_VERSION: u32 = 5;
+pub const DB_VERSION: u32 = 7;
 
 /// Object store for chat messages.
 pub const STORE_MESSAGES: &str = "messages";
@@ -55,6 +59,12 @@ pub const STORE_CONV_FLAGS: &str = "conversation_flags";
 /// serialisation overhead.
 pub const STORE_BACKGROUND_IMAGE: &str = "background_image";
 
+/// Object store for the local identity key pairs. Out-of-line keyed
+/// by user id; each value is a `{ privateKey, publicKey }` object whose
+/// `privateKey` is a non-extractable ECDSA `CryptoKey`, structured-
+/// cloned as-is so the key material is never exposed to script.
+pub const STORE_IDENTITY_KEYS: &str = "identity_keys";
+
 /// Canonical IDB key for the user's light-theme background blob.
 pub const KEY_USER_BG_LIGHT: &str = "user_bg_light";
 
@@ -89,6 +99,10 @@ pub const IDX_MSG_CONV: &str = "by_conv";
 /// retention sweeps ("delete messages older than N ms").
 pub const IDX_MSG_TS: &str = "by_ts";
 
+/// Index on `messages` keyed by `(thread_root, timestamp_ms)` — used
+/// to load the replies of one thread in order.
+pub const IDX_MSG_THREAD_TS: &str = "by_thread_ts";
+
 /// Index on `search_index` keyed by `token` — used to look up all
 /// postings for a given search term.
 pub const IDX_SEARCH_TOKEN: &str = "by_token";
@@ -132,6 +146,9 @@ pub const HISTORY_PAGE_SIZE: usize = 50;
 /// is outside the currently loaded window (Req 14.11.4).
 pub const JUMP_WINDOW: usize = 25;
 
+/// Maximum replies fetched when a thread panel is opened.
+pub const THREAD_LOAD_LIMIT: usize = 500;
+
 /// Default retention window (72 hours in milliseconds). Configurable
 /// via settings: 24 h / 72 h / 7 d.
 pub const DEFAULT_RETENTION_MS: i64 = 72 * 60 * 60 * 1_000;
@@ -139,18 +156,27 @@ pub const DEFAULT_RETENTION_MS: i64 = 72 * 60 * 60 * 1_000;
 #[cfg(target_arch = "wasm32")]
 mod wasm {
   use super::{
-    IDX_ACK_MSG, IDX_ACK_MSG_PEER, IDX_MSG_CONV, IDX_MSG_CONV_TS, IDX_MSG_TS, IDX_SEARCH_CONV,
-    IDX_SEARCH_TOKEN, STORE_ACK_QUEUE, STORE_AVATARS, STORE_BACKGROUND_IMAGE, STORE_CONV_FLAGS,
-    STORE_MESSAGES, STORE_SEARCH,
+    IDX_ACK_MSG, IDX_ACK_MSG_PEER, IDX_MSG_CONV, IDX_MSG_CONV_TS, IDX_MSG_THREAD_TS, IDX_MSG_TS,
+    IDX_SEARCH_CONV, IDX_SEARCH_TOKEN, STORE_ACK_QUEUE, STORE_AVATARS, STORE_BACKGROUND_IMAGE,
+    STORE_CONV_FLAGS, STORE_IDENTITY_KEYS, STORE_MESSAGES, STORE_SEARCH,
   };
   use wasm_bindgen::JsValue;
-  use web_sys::{IdbDatabase, IdbIndexParameters, IdbObjectStore, IdbObjectStoreParameters};
+  use web_sys::{
+    IdbDatabase, IdbIndexParameters, IdbObjectStore, IdbObjectStoreParameters, IdbTransaction,
+  };
 
   /// Apply all outstanding migrations against `db` as the
   /// `onupgradeneeded` handler runs. The browser provides both the
   /// previous and current version via the event; we use the previous
   /// version to decide which migration steps to execute.
-  pub fn apply_migration(db: &IdbDatabase, from_version: u32) -> Result<(), JsValue> {
+  ///
+  /// `upgrade_tx` is the version-change transaction; steps that alter
+  /// an existing store reach it through this transaction.
+  pub fn apply_migration(
+    db: &IdbDatabase,
+    upgrade_tx: &IdbTransaction,
+    from_version: u32,
+  ) -> Result<(), JsValue> {
     if from_version < 1 {
       create_v1_schema(db)?;
     }
@@ -166,6 +192,12 @@ mod wasm {
     if from_version < 5 {
       create_v5_schema(db)?;
     }
+    if from_version < 6 {
+      create_v6_schema(upgrade_tx)?;
+    }
+    if from_version < 7 {
+      create_v7_schema(db)?;
+    }
     Ok(())
   }
 
@@ -253,6 +285,29 @@ mod wasm {
     Ok(())
   }
 
+  /// v6 migration: add the `(thread_root, timestamp_ms)` index on
+  /// `messages` so a thread panel can load its replies without
+  /// scanning the whole conversation. Existing records have no
+  /// `thread_root` and therefore stay out of the index.
+  fn create_v6_schema(upgrade_tx: &IdbTransaction) -> Result<(), JsValue> {
+    let messages = upgrade_tx.object_store(STORE_MESSAGES)?;
+    create_compound_index(
+      &messages,
+      IDX_MSG_THREAD_TS,
+      &["thread_root", "timestamp_ms"],
+      false,
+    )
+  }
+
+  /// v7 migration: introduce the out-of-line keyed `identity_keys`
+  /// store, replacing the JWK the identity key used to be exported to
+  /// in localStorage.
+  fn create_v7_schema(db: &IdbDatabase) -> Result<(), JsValue> {
+    let params = IdbObjectStoreParameters::new();
+    db.create_object_store_with_optional_parameters(STORE_IDENTITY_KEYS, &params)?;
+    Ok(())
+  }
+
   fn create_index(
     store: &IdbObjectStore,
     name: &str,
@@ -12,10 +12,12 @@ fn mk(id: &str, conv: &str, ts: i64, text: &str) -> MessageRecord {
     outgoing: false,
     status: StatusRecord::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     content: ContentRecord::Text { text: text.into() },
+    edit: None,
   }
 }
 
@@ -7,17 +7,7 @@ use crate::persistence::schema::STORE_AVATARS;
 use wasm_bindgen::JsValue;
 use web_sys::IdbDatabase;
 
-/// Avatar cache entry stored under the `user_id` primary key.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct AvatarEntry {
-  /// User id (UUID string) — primary key.
-  pub user_id: String,
-  /// Avatar data URI or object URL.
-  pub data_uri: String,
-  /// Unix-ms timestamp of last write. Used for LRU eviction once the
-  /// cache fills up.
-  pub cached_at_ms: i64,
-}
+pub use crate::persistence::record::AvatarEntry;
 
 /// Cache an avatar data URI.
 pub async fn put_avatar(db: &IdbDatabase, entry: &AvatarEntry) -> IdbResult<()> {
@@ -37,3 +27,21 @@ pub async fn get_avatar(db: &IdbDatabase, user_id: &str) -> IdbResult<Option<Ava
   }
   from_js(&val).map(Some)
 }
+
+/// Read every cached avatar. Used by the encrypted backup.
+pub async fn list_avatars(db: &IdbDatabase) -> IdbResult<Vec<AvatarEntry>> {
+  let (_tx, store) = ro_tx(db, STORE_AVATARS)?;
+  let req = store.get_all()?;
+  let val = await_request(req).await?;
+  if !js_sys::Array::is_array(&val) {
+    return Ok(Vec::new());
+  }
+  let array: js_sys::Array = val.into();
+  let mut out = Vec::with_capacity(array.length() as usize);
+  for entry in array.iter() {
+    if let Ok(parsed) = from_js::<AvatarEntry>(&entry) {
+      out.push(parsed);
+    }
+  }
+  Ok(out)
+}
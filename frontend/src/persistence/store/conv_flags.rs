@@ -20,27 +20,7 @@ use crate::persistence::schema::STORE_CONV_FLAGS;
 use wasm_bindgen::JsValue;
 use web_sys::IdbDatabase;
 
-/// One row in the conversation flags store.
-///
-/// Serialised as JSON via `to_js` / `from_js`. The primary key is
-/// `conversation_id` to match the object-store `keyPath` declared in
-/// the v4 migration.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ConvFlagsEntry {
-  /// Conversation identifier serialised to a stable string. Matches
-  /// the persisted form used by the legacy localStorage cache so
-  /// existing entries can be migrated without re-keying.
-  pub conversation_id: String,
-  /// Whether the conversation is pinned to the top of the sidebar.
-  pub pinned: bool,
-  /// Unix-ms timestamp at which the user pinned the conversation.
-  /// `None` when the conversation is not pinned.
-  pub pinned_at_ms: Option<i64>,
-  /// Whether per-conversation Do-Not-Disturb is enabled.
-  pub muted: bool,
-  /// Whether the conversation has been archived.
-  pub archived: bool,
-}
+pub use crate::persistence::record::ConvFlagsEntry;
 
 /// Insert / overwrite the flags row for a conversation.
 pub async fn put_conv_flags(db: &IdbDatabase, entry: &ConvFlagsEntry) -> IdbResult<()> {
@@ -0,0 +1,29 @@
+//! CRUD operations for the `identity_keys` object store.
+//!
+//! Values hold a non-extractable `CryptoKey`, which only survives
+//! IndexedDB's structured clone; like `background_image`, this store
+//! bypasses the `serde_json` round-trip the other stores use.
+
+use crate::persistence::idb::{IdbResult, await_request, await_transaction, ro_tx, rw_tx};
+use crate::persistence::schema::STORE_IDENTITY_KEYS;
+use wasm_bindgen::JsValue;
+use web_sys::IdbDatabase;
+
+/// Persist the identity record for `user_id`, replacing any previous one.
+pub async fn put_identity_key(db: &IdbDatabase, user_id: &str, record: &JsValue) -> IdbResult<()> {
+  let (tx, store) = rw_tx(db, STORE_IDENTITY_KEYS)?;
+  store.put_with_key(record, &JsValue::from_str(user_id))?;
+  await_transaction(tx).await
+}
+
+/// Fetch the identity record for `user_id`. Returns `Ok(None)` when
+/// none is stored.
+pub async fn get_identity_key(db: &IdbDatabase, user_id: &str) -> IdbResult<Option<JsValue>> {
+  let (_tx, store) = ro_tx(db, STORE_IDENTITY_KEYS)?;
+  let req = store.get(&JsValue::from_str(user_id))?;
+  let val = await_request(req).await?;
+  if val.is_null() || val.is_undefined() {
+    return Ok(None);
+  }
+  Ok(Some(val))
+}
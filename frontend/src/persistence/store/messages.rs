@@ -7,7 +7,9 @@ use crate::persistence::idb::{
   IdbResult, await_request, await_transaction, key_only, key_upper_bound, ro_tx, rw_tx, to_js,
 };
 use crate::persistence::record::MessageRecord;
-use crate::persistence::schema::{IDX_MSG_CONV, IDX_MSG_CONV_TS, IDX_MSG_TS, STORE_MESSAGES};
+use crate::persistence::schema::{
+  IDX_MSG_CONV, IDX_MSG_CONV_TS, IDX_MSG_THREAD_TS, IDX_MSG_TS, STORE_MESSAGES,
+};
 use js_sys::Array;
 use wasm_bindgen::JsValue;
 use web_sys::{IdbCursorDirection, IdbDatabase};
@@ -127,6 +129,30 @@ pub async fn load_after(
   collect_messages_from_cursor(req, limit).await
 }
 
+/// Load the most-recent `limit` replies in the thread rooted at
+/// `thread_root`, ordered oldest-first. Uses the sparse
+/// `(thread_root, timestamp_ms)` index, so only replies are visited.
+pub async fn load_thread(
+  db: &IdbDatabase,
+  thread_root: &str,
+  limit: usize,
+) -> IdbResult<Vec<MessageRecord>> {
+  let (_tx, store) = ro_tx(db, STORE_MESSAGES)?;
+  let index = store.index(IDX_MSG_THREAD_TS)?;
+  let lower = Array::new();
+  lower.push(&JsValue::from_str(thread_root));
+  lower.push(&JsValue::from_f64(f64::MIN));
+  let upper = Array::new();
+  upper.push(&JsValue::from_str(thread_root));
+  upper.push(&JsValue::from_f64(f64::MAX));
+  let range = web_sys::IdbKeyRange::bound(&lower, &upper)?;
+
+  let req = index.open_cursor_with_range_and_direction(&range, IdbCursorDirection::Prev)?;
+  let mut out = collect_messages_from_cursor(req, limit).await?;
+  out.reverse();
+  Ok(out)
+}
+
 /// Load all messages across every conversation, ordered by
 /// `timestamp_ms` ascending. Used by the inverted-index builder
 /// (Req 7.6).
@@ -21,6 +21,7 @@ mod avatars;
 mod background_image;
 mod conv_flags;
 mod cursor_helpers;
+mod identity_keys;
 mod messages;
 mod search_index;
 
@@ -30,6 +31,7 @@ pub use ack_queue::*;
 pub use avatars::*;
 pub use background_image::*;
 pub use conv_flags::*;
+pub use identity_keys::*;
 pub use messages::*;
 pub use search_index::*;
 
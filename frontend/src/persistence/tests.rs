@@ -27,12 +27,14 @@ fn make_text_record(
     outgoing: false,
     status: StatusRecord::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     content: ContentRecord::Text {
       text: text.to_string(),
     },
+    edit: None,
   }
 }
 
@@ -179,3 +181,19 @@ fn db_version_covers_background_image_migration() {
     )
   };
 }
+
+#[test]
+fn db_version_covers_thread_index_migration() {
+  use crate::persistence::schema::{DB_VERSION, IDX_MSG_THREAD_TS};
+  // v6 adds the `(thread_root, timestamp_ms)` index on `messages`.
+  const { assert!(DB_VERSION >= 6, "DB_VERSION must stay at 6 or higher") };
+  assert_eq!(IDX_MSG_THREAD_TS, "by_thread_ts");
+}
+
+#[test]
+fn db_version_covers_identity_key_migration() {
+  use crate::persistence::schema::{DB_VERSION, STORE_IDENTITY_KEYS};
+  // v7 moves the identity key out of localStorage into `identity_keys`.
+  const { assert!(DB_VERSION >= 7, "DB_VERSION must stay at 7 or higher") };
+  assert_eq!(STORE_IDENTITY_KEYS, "identity_keys");
+}
@@ -42,12 +42,14 @@ fn make_record(id: &str, conv: &str, ts: i64, text: &str) -> MessageRecord {
     outgoing: false,
     status: StatusRecord::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     content: ContentRecord::Text {
       text: text.to_string(),
     },
+    edit: None,
   }
 }
 
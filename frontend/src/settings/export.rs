@@ -3,6 +3,9 @@
 //! Contains [`ExportPayload`] and its JSON / HTML rendering. Separated
 //! from the settings types so the HTML template logic does not inflate
 //! the core data module.
+//!
+//! The export is meant for reading and cannot be imported back; the
+//! restorable, encrypted counterpart lives in [`crate::backup`].
 
 use serde::{Deserialize, Serialize};
 
@@ -51,9 +51,9 @@ pub use state::{
   provide_settings_state, use_settings_state,
 };
 pub use types::{
-  BACKGROUND_BLUR_MAX_PX, BACKGROUND_OVERLAY_ALPHA_MAX, BackgroundEffects, BackgroundMode,
-  BackgroundSettings, BackgroundVariantData, BackgroundVariantView, DndWindow, FontScale,
-  GradientKind, GradientSpec, GradientStop, UserSettings, VOLUME_MAX, VideoQualityPref,
+  AudioProcessingPrefs, BACKGROUND_BLUR_MAX_PX, BACKGROUND_OVERLAY_ALPHA_MAX, BackgroundEffects,
+  BackgroundMode, BackgroundSettings, BackgroundVariantData, BackgroundVariantView, DndWindow,
+  FontScale, GradientKind, GradientSpec, GradientStop, UserSettings, VOLUME_MAX, VideoQualityPref,
 };
 
 #[cfg(test)]
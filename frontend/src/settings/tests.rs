@@ -153,6 +153,13 @@ fn settings_serde_round_trip() {
     glass_enabled: false,
     motion_enabled: false,
     background: BackgroundSettings::default(),
+    audio_processing: AudioProcessingPrefs {
+      noise_suppression: false,
+      noise_gate: true,
+      auto_gain: false,
+    },
+    thread_replies_in_timeline: true,
+    strip_metadata: false,
   };
   let json = serde_json::to_string(&settings).expect("serialise");
   let decoded: UserSettings = serde_json::from_str(&json).expect("deserialise");
@@ -692,6 +699,9 @@ fn user_settings_deserialises_without_background_field() {
   assert_eq!(settings.background, BackgroundSettings::default());
   assert!(settings.glass_enabled);
   assert!(settings.motion_enabled);
+  assert_eq!(settings.audio_processing, AudioProcessingPrefs::default());
+  assert!(!settings.thread_replies_in_timeline);
+  assert!(settings.strip_metadata);
 }
 
 #[test]
@@ -708,3 +718,12 @@ fn user_settings_sanitised_cascades_into_background() {
   assert_eq!(sanitised.background.blur_px, BACKGROUND_BLUR_MAX_PX);
   assert!((sanitised.background.overlay_alpha - BACKGROUND_OVERLAY_ALPHA_MAX).abs() < f32::EPSILON);
 }
+
+#[test]
+fn audio_processing_prefs_fill_missing_stages() {
+  let prefs: AudioProcessingPrefs =
+    serde_json::from_str(r#"{ "noise_gate": false }"#).expect("partial prefs deserialise");
+  assert!(prefs.noise_suppression);
+  assert!(!prefs.noise_gate);
+  assert!(prefs.auto_gain);
+}
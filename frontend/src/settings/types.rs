@@ -1,9 +1,9 @@
 //! Settings data types and enumerations.
 //!
 //! Contains the core value types used by the settings system:
-//! [`FontScale`], [`VideoQualityPref`], [`DndWindow`], and
-//! [`UserSettings`]. These are pure data — no browser I/O or
-//! reactive state lives here.
+//! [`FontScale`], [`VideoQualityPref`], [`DndWindow`],
+//! [`AudioProcessingPrefs`], and [`UserSettings`]. These are pure
+//! data — no browser I/O or reactive state lives here.
 
 use serde::{Deserialize, Serialize};
 
@@ -117,6 +117,32 @@ pub struct DndWindow {
   pub enabled: bool,
 }
 
+/// Which outbound microphone processing stages are enabled during
+/// calls. Mirrors [`crate::call::DspConfig`]; kept as its own type so
+/// the persisted shape does not depend on the DSP module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioProcessingPrefs {
+  /// Spectral noise suppression (fans, hum, keyboard bed).
+  #[serde(default = "default_true")]
+  pub noise_suppression: bool,
+  /// Noise gate driven by voice-activity detection.
+  #[serde(default = "default_true")]
+  pub noise_gate: bool,
+  /// Automatic gain control.
+  #[serde(default = "default_true")]
+  pub auto_gain: bool,
+}
+
+impl Default for AudioProcessingPrefs {
+  fn default() -> Self {
+    Self {
+      noise_suppression: true,
+      noise_gate: true,
+      auto_gain: true,
+    }
+  }
+}
+
 impl DndWindow {
   /// Return `true` when `now_minutes` falls inside the configured
   /// window. Handles the wrap-around case (start after end) by
@@ -645,6 +671,18 @@ pub struct UserSettings {
   /// settings deserialising without this field present.
   #[serde(default)]
   pub background: BackgroundSettings,
+  /// Outbound call-audio processing stages. `#[serde(default)]` turns
+  /// every stage on for settings persisted before this field existed.
+  #[serde(default)]
+  pub audio_processing: AudioProcessingPrefs,
+  /// Whether thread replies also appear in the main timeline. Defaults
+  /// to `false`, so replies only show in the thread panel.
+  #[serde(default)]
+  pub thread_replies_in_timeline: bool,
+  /// Whether EXIF, XMP, IPTC and text metadata are removed from
+  /// outgoing images before they are sent. Defaults to `true`.
+  #[serde(default = "default_true")]
+  pub strip_metadata: bool,
 }
 
 impl Default for UserSettings {
@@ -666,6 +704,9 @@ impl Default for UserSettings {
       glass_enabled: true,
       motion_enabled: true,
       background: BackgroundSettings::default(),
+      audio_processing: AudioProcessingPrefs::default(),
+      thread_replies_in_timeline: false,
+      strip_metadata: true,
     }
   }
 }
@@ -236,6 +236,9 @@ impl SignalingClient {
         auth.user_id, auth_success.user_id
       ));
     }
+    // The server announces the device list right after `AuthSuccess`
+    // when other devices are online; until then this device is alone.
+    self.app_state.my_devices.set(Vec::new());
     // Use the nickname returned by the server so that a nickname
     // change made on another device is reflected here. Fall back to the
     // locally stored nickname if the server returns an empty string (for
@@ -293,6 +296,9 @@ impl SignalingClient {
       } else {
         console_log("[signaling] Server sent empty ICE list; keeping frontend defaults");
       }
+      // Publish our mailbox key and collect anything deposited while we
+      // were offline (no-op when the server has no mailbox).
+      manager.start_mailbox(auth_success.mailbox_enabled);
     }
 
     // If we are in a reconnection flow (banner visible), switch
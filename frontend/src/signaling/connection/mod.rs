@@ -14,19 +14,19 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use leptos::prelude::*;
-use message::UserId;
 use message::frame::{MessageFrame, encode_frame};
 use message::signaling::{
   AvatarChange as AvatarChangeMsg, BanMember as BanMemberMsg,
   ConnectionInvite as ConnectionInviteMsg, CreateRoom as CreateRoomMsg,
-  DemoteAdmin as DemoteAdminMsg, IceCandidate as IceCandidateMsg,
-  InviteAccepted as InviteAcceptedMsg, InviteDeclined as InviteDeclinedMsg,
-  JoinRoom as JoinRoomMsg, KickMember as KickMemberMsg, LeaveRoom as LeaveRoomMsg,
-  MultiInvite as MultiInviteMsg, MuteMember as MuteMemberMsg, NicknameChange as NicknameChangeMsg,
-  PeerClosed as PeerClosedMsg, PeerEstablished as PeerEstablishedMsg,
-  PromoteAdmin as PromoteAdminMsg, RoomAnnouncement as RoomAnnouncementMsg,
-  SdpAnswer as SdpAnswerMsg, SdpOffer as SdpOfferMsg, SignalingMessage,
-  TheaterMuteAll as TheaterMuteAllMsg, TheaterTransferOwner as TheaterTransferOwnerMsg, TokenAuth,
+  DemoteAdmin as DemoteAdminMsg, DeviceLinkPayload, DeviceLinkSignal,
+  IceCandidate as IceCandidateMsg, InviteAccepted as InviteAcceptedMsg,
+  InviteDeclined as InviteDeclinedMsg, JoinRoom as JoinRoomMsg, KickMember as KickMemberMsg,
+  LeaveRoom as LeaveRoomMsg, MultiInvite as MultiInviteMsg, MuteMember as MuteMemberMsg,
+  NicknameChange as NicknameChangeMsg, PeerClosed as PeerClosedMsg,
+  PeerEstablished as PeerEstablishedMsg, PromoteAdmin as PromoteAdminMsg,
+  RoomAnnouncement as RoomAnnouncementMsg, SdpAnswer as SdpAnswerMsg, SdpOffer as SdpOfferMsg,
+  SignalingMessage, TheaterMuteAll as TheaterMuteAllMsg,
+  TheaterTransferOwner as TheaterTransferOwnerMsg, TokenAuth,
   TransferOwnership as TransferOwnershipMsg, UnbanMember as UnbanMemberMsg,
   UnmuteMember as UnmuteMemberMsg, UpdateRoomInfo as UpdateRoomInfoMsg,
   UpdateRoomPassword as UpdateRoomPasswordMsg, UserLogout,
@@ -34,7 +34,12 @@ use message::signaling::{
 use message::signaling::{
   RoomInvite as RoomInviteMsg, RoomInviteResponse as RoomInviteResponseMsg,
 };
+use message::signaling::{
+  SfuAnswer as SfuAnswerMsg, SfuIceCandidate as SfuIceCandidateMsg, SfuLeave as SfuLeaveMsg,
+  SfuOffer as SfuOfferMsg,
+};
 use message::types::{RoomId, RoomType};
+use message::{DeviceId, UserId};
 use wasm_bindgen::prelude::*;
 use web_sys::WebSocket;
 
@@ -78,6 +83,9 @@ pub(super) const WS_CLOSE_AUTH_FORBIDDEN: u16 = 4003;
 pub struct SignalingClient {
   pub(super) app_state: AppState,
   ws_url: String,
+  /// HTTP base URL, used to renew an expired access token before
+  /// re-authenticating a reconnected socket.
+  http_url: String,
   pub(super) inner: Rc<RefCell<Inner>>,
   /// Cached UserStatusManager reference so WebSocket callbacks can access
   /// it without calling `expect_context` from outside the reactive owner.
@@ -122,6 +130,7 @@ impl SignalingClient {
   /// Create a new signaling client.
   pub fn new(
     ws_url: String,
+    http_url: String,
     app_state: AppState,
     user_status: crate::user_status::UserStatusManager,
     error_toast: crate::error_handler::ErrorToastManager,
@@ -129,6 +138,7 @@ impl SignalingClient {
     Self {
       app_state,
       ws_url,
+      http_url,
       inner: Rc::new(RefCell::new(Inner {
         ws: None,
         reconnect: ReconnectStrategy::new(),
@@ -322,7 +332,41 @@ impl SignalingClient {
       return;
     };
 
-    let msg = SignalingMessage::TokenAuth(TokenAuth { token: auth.token });
+    // Access tokens are short-lived, so a reconnect after a long
+    // outage (or a sleeping laptop) usually finds the stored token
+    // expired. Renew it first instead of letting `TokenAuth` fail and
+    // dropping the user to the login page.
+    if crate::auth::is_jwt_expired(&auth.token) && crate::auth::has_refresh_token() {
+      let client = self.clone();
+      let http_url = self.http_url.clone();
+      let expired_token = auth.token.clone();
+      wasm_bindgen_futures::spawn_local(async move {
+        match crate::auth::refresh_auth(&http_url, auth).await {
+          Ok(auth) => {
+            let token = auth.token.clone();
+            client.app_state.auth.set(Some(auth));
+            client.send_token(token);
+          }
+          Err(e) => {
+            console_warn(&format!("[signaling] Token refresh failed: {}", e));
+            // Let the server reject the stale token so the regular
+            // `AuthFailure` flow takes the user back to the login page.
+            client.send_token(expired_token);
+          }
+        }
+      });
+      return;
+    }
+
+    self.send_token(auth.token);
+  }
+
+  /// Send `TokenAuth` with `token`.
+  fn send_token(&self, token: String) {
+    let msg = SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: Some(crate::devices::local_device()),
+    });
     if let Err(e) = self.send(&msg) {
       console_error(&format!("[signaling] Failed to send TokenAuth: {}", e));
     } else {
@@ -387,7 +431,13 @@ impl SignalingClient {
   // ── WebRTC signaling methods ──
 
   /// Send an SDP offer via signaling server (for WebRTC connection).
-  pub fn send_sdp_offer(&self, peer_id: &UserId, sdp: &str) -> Result<(), String> {
+  /// Without `to_device` the offer reaches every device of `peer_id`.
+  pub fn send_sdp_offer(
+    &self,
+    peer_id: &UserId,
+    to_device: Option<DeviceId>,
+    sdp: &str,
+  ) -> Result<(), String> {
     let my_id = self
       .current_user_id()
       .ok_or("Cannot send SdpOffer: not authenticated")?;
@@ -395,12 +445,19 @@ impl SignalingClient {
       from: my_id,
       to: peer_id.clone(),
       sdp: sdp.to_string(),
+      from_device: None,
+      to_device,
     });
     self.send(&msg)
   }
 
   /// Send an SDP answer via signaling server (for WebRTC connection).
-  pub fn send_sdp_answer(&self, peer_id: &UserId, sdp: &str) -> Result<(), String> {
+  pub fn send_sdp_answer(
+    &self,
+    peer_id: &UserId,
+    to_device: Option<DeviceId>,
+    sdp: &str,
+  ) -> Result<(), String> {
     let my_id = self
       .current_user_id()
       .ok_or("Cannot send SdpAnswer: not authenticated")?;
@@ -408,6 +465,8 @@ impl SignalingClient {
       from: my_id,
       to: peer_id.clone(),
       sdp: sdp.to_string(),
+      from_device: None,
+      to_device,
     });
     self.send(&msg)
   }
@@ -416,6 +475,7 @@ impl SignalingClient {
   pub fn send_sdp_ice_candidate(
     &self,
     peer_id: UserId,
+    to_device: Option<DeviceId>,
     candidate: &str,
     sdp_mid: &str,
     sdp_m_line_index: Option<u16>,
@@ -429,10 +489,67 @@ impl SignalingClient {
       candidate: candidate.to_string(),
       sdp_mid: sdp_mid.to_string(),
       sdp_m_line_index,
+      from_device: None,
+      to_device,
+    });
+    self.send(&msg)
+  }
+
+  /// Relay a device-linking step to another device of this account.
+  pub fn send_device_link_signal(
+    &self,
+    to_device: DeviceId,
+    payload: DeviceLinkPayload,
+  ) -> Result<(), String> {
+    let msg = SignalingMessage::DeviceLinkSignal(DeviceLinkSignal {
+      from_device: crate::devices::local_device_id(),
+      to_device,
+      payload,
+    });
+    self.send(&msg)
+  }
+
+  /// Send an SDP offer for the room's SFU media session.
+  pub fn send_sfu_offer(&self, room_id: RoomId, sdp: &str) -> Result<(), String> {
+    let msg = SignalingMessage::SfuOffer(SfuOfferMsg {
+      room_id,
+      sdp: sdp.to_string(),
+    });
+    self.send(&msg)
+  }
+
+  /// Send an SDP answer to a renegotiation offer from the SFU.
+  pub fn send_sfu_answer(&self, room_id: RoomId, sdp: &str) -> Result<(), String> {
+    let msg = SignalingMessage::SfuAnswer(SfuAnswerMsg {
+      room_id,
+      sdp: sdp.to_string(),
+    });
+    self.send(&msg)
+  }
+
+  /// Send an ICE candidate for the room's SFU media session.
+  pub fn send_sfu_ice_candidate(
+    &self,
+    room_id: RoomId,
+    candidate: &str,
+    sdp_mid: &str,
+    sdp_m_line_index: Option<u16>,
+  ) -> Result<(), String> {
+    let msg = SignalingMessage::SfuIceCandidate(SfuIceCandidateMsg {
+      room_id,
+      candidate: candidate.to_string(),
+      sdp_mid: (!sdp_mid.is_empty()).then(|| sdp_mid.to_string()),
+      sdp_m_line_index,
     });
     self.send(&msg)
   }
 
+  /// Close the room's SFU media session.
+  pub fn send_sfu_leave(&self, room_id: RoomId) -> Result<(), String> {
+    let msg = SignalingMessage::SfuLeave(SfuLeaveMsg { room_id });
+    self.send(&msg)
+  }
+
   /// Notify the server that a peer connection has been established.
   pub fn send_peer_established(&self, peer_id: &UserId) -> Result<(), String> {
     let my_id = self
@@ -165,6 +165,7 @@ fn test_pong_message_encodes_to_valid_frame() {
 fn test_token_auth_encodes_to_valid_frame() {
   let msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "test-jwt-token-12345".to_string(),
+    device: None,
   });
   let bytes = encode_message(&msg);
   // TokenAuth with a token string should produce a larger payload
@@ -201,6 +202,7 @@ fn test_different_messages_produce_different_frames() {
   let ping = SignalingMessage::Ping(Ping);
   let token_auth = SignalingMessage::TokenAuth(TokenAuth {
     token: "token123".to_string(),
+    device: None,
   });
 
   let ping_frame = encode_message(&ping);
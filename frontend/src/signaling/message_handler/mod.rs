@@ -87,13 +87,29 @@ pub fn handle_signaling_message(
         update.members.iter().map(|m| m.user_id.clone()).collect();
 
       let removed: Vec<message::UserId> = prev_set.difference(&now_set).cloned().collect();
+      let membership_changed =
+        !removed.is_empty() || now_set.difference(&prev_set).next().is_some();
+      let still_member = app_state
+        .current_user_id()
+        .is_some_and(|me| now_set.contains(&me));
 
       app_state.room_members.update(|map| {
         map.insert(update.room_id.clone(), update.members);
       });
+      // Every join, leave, kick or ban starts a new sender-key epoch so
+      // departed members cannot read later room messages.
+      if membership_changed
+        && still_member
+        && let Some(manager) = crate::webrtc::try_use_webrtc_manager()
+      {
+        manager.rekey_room(&update.room_id, &removed);
+      }
       if !removed.is_empty()
         && let Some(manager) = crate::webrtc::try_use_webrtc_manager()
       {
+        // SFU rooms: forget the departed members' frame keys and roll
+        // our own so they cannot decrypt media relayed after they left.
+        manager.rotate_media_key(&removed);
         for peer in removed {
           manager.close_connection(&peer);
         }
@@ -107,6 +123,7 @@ pub fn handle_signaling_message(
       // The creator is automatically a member — materialise the room
       // conversation and switch to it so the chat view is shown.
       ensure_room_conversation(&created.room_id, Some(&created.room_info), app_state);
+      start_sfu_session(created.room_info.clone());
       // Seed `room_members` with the creator as the Owner. The server
       // does NOT broadcast a follow-up `RoomMemberUpdate` for the
       // creator on the create path (only `RoomListUpdate`), so without
@@ -144,6 +161,7 @@ pub fn handle_signaling_message(
       // Materialise the room conversation (if not yet present) and
       // switch to it so the chat view is shown.
       ensure_room_conversation(&joined.room_id, Some(&joined.room_info), app_state);
+      start_sfu_session(joined.room_info.clone());
     }
     SignalingMessage::RoomLeft(left) => {
       log_debug(&format!(
@@ -153,6 +171,10 @@ pub fn handle_signaling_message(
       // Clear the persisted room pointer so we do not try to rejoin a
       // room the user explicitly left (Req 10.4).
       crate::auth::save_active_room_id(None);
+      if let Some(manager) = crate::webrtc::try_use_webrtc_manager() {
+        manager.stop_sfu_session();
+        manager.forget_room_keys(&left.room_id);
+      }
       // Remove the room conversation entry and clear the active
       // conversation so the UI falls back to the room list panel.
       {
@@ -229,6 +251,13 @@ pub fn handle_signaling_message(
         } else {
           error_toast.show_error(&error);
         }
+      } else if let Some(message_id) = mailbox_rejected_message(&error) {
+        // The mailbox refused a deposit: the message cannot reach its
+        // offline recipient, so surface it as failed (with retry).
+        if let Some(chat) = crate::chat::manager::try_use_chat_manager() {
+          chat.mark_failed(message_id);
+        }
+        error_toast.show_error(&error);
       } else if code_str == "SIG004" {
         // Dedicated copy explaining that the server still
         // remembers a pending invite from a prior session even though
@@ -336,18 +365,25 @@ pub fn handle_signaling_message(
     // ── SDP / ICE Signaling → WebRtcManager ──
     SignalingMessage::SdpOffer(offer) => {
       log_debug(&format!("SdpOffer from {} to {}", offer.from, offer.to));
+      // An unaddressed offer reaches every device of this account and
+      // each of them answers it (see `crate::devices`).
+      let local = crate::devices::local_device_id();
+      if !crate::devices::should_answer_offer(offer.to_device.as_ref(), &local) {
+        log_debug("SdpOffer addressed to another device, ignoring");
+        return;
+      }
       delegate_to_webrtc(move |manager| {
         let sdp = offer.sdp.clone();
-        let peer_id = offer.from.clone();
-        async move { manager.handle_incoming_offer(peer_id, &sdp).await }
+        let peer = crate::webrtc::PeerKey::new(offer.from.clone(), offer.from_device);
+        async move { manager.handle_incoming_offer(peer, &sdp).await }
       });
     }
     SignalingMessage::SdpAnswer(answer) => {
       log_debug(&format!("SdpAnswer from {} to {}", answer.from, answer.to));
       delegate_to_webrtc(move |manager| {
         let sdp = answer.sdp.clone();
-        let peer_id = answer.from.clone();
-        async move { manager.handle_incoming_answer(peer_id, &sdp).await }
+        let peer = crate::webrtc::PeerKey::new(answer.from.clone(), answer.from_device);
+        async move { manager.handle_incoming_answer(peer, &sdp).await }
       });
     }
     SignalingMessage::IceCandidate(candidate) => {
@@ -359,15 +395,62 @@ pub fn handle_signaling_message(
         let cand = candidate.candidate.clone();
         let sdp_mid = candidate.sdp_mid.clone();
         let sdp_m_line_index = candidate.sdp_m_line_index;
-        let peer_id = candidate.from.clone();
+        let peer = crate::webrtc::PeerKey::new(candidate.from.clone(), candidate.from_device);
         async move {
           manager
-            .handle_incoming_ice_candidate(peer_id, &cand, &sdp_mid, sdp_m_line_index)
+            .handle_incoming_ice_candidate(peer, &cand, &sdp_mid, sdp_m_line_index)
             .await
         }
       });
     }
 
+    // ── SFU Media Session → WebRtcManager ──
+    SignalingMessage::SfuOffer(offer) => {
+      log_debug(&format!("SfuOffer for room {}", offer.room_id));
+      delegate_to_webrtc(move |manager| async move {
+        manager.handle_sfu_offer(offer.room_id, &offer.sdp).await
+      });
+    }
+    SignalingMessage::SfuAnswer(answer) => {
+      log_debug(&format!("SfuAnswer for room {}", answer.room_id));
+      delegate_to_webrtc(move |manager| async move {
+        manager.handle_sfu_answer(answer.room_id, &answer.sdp).await
+      });
+    }
+    SignalingMessage::SfuIceCandidate(candidate) => {
+      delegate_to_webrtc(move |manager| async move {
+        manager.handle_sfu_ice_candidate(candidate).await
+      });
+    }
+    SignalingMessage::SfuTrackMap(map) => {
+      log_debug(&format!(
+        "SfuTrackMap: room_id={}, {} tracks",
+        map.room_id,
+        map.tracks.len()
+      ));
+      if let Some(manager) = crate::webrtc::try_use_webrtc_manager() {
+        manager.handle_sfu_track_map(map);
+      }
+    }
+
+    // ── Offline Mailbox → WebRtcManager ──
+    SignalingMessage::MailboxKeyResponse(response) => {
+      if let Some(manager) = crate::webrtc::try_use_webrtc_manager() {
+        manager.handle_mailbox_key_response(response);
+      }
+    }
+    SignalingMessage::MailboxDrain(drain) => {
+      log_debug(&format!("MailboxDrain: {} items", drain.items.len()));
+      if let Some(manager) = crate::webrtc::try_use_webrtc_manager() {
+        manager.handle_mailbox_drain(drain);
+      }
+    }
+    SignalingMessage::MailboxDelivered(delivered) => {
+      if let Some(manager) = crate::webrtc::try_use_webrtc_manager() {
+        manager.handle_mailbox_delivered(&delivered);
+      }
+    }
+
     // ── Peer Tracking ──
     SignalingMessage::PeerEstablished(peer) => {
       log_debug(&format!("PeerEstablished: {} <-> {}", peer.from, peer.to));
@@ -603,6 +686,24 @@ pub fn handle_signaling_message(
       error_toast.show_info_message_with_key("ROM2403", key, &fallback);
     }
 
+    // ── Multi-device ──
+    SignalingMessage::DeviceListUpdate(update) => {
+      log_debug(&format!(
+        "DeviceListUpdate: {} devices",
+        update.devices.len()
+      ));
+      if let Some(manager) = crate::devices::try_use_device_link_manager() {
+        manager.on_device_list(&update.devices);
+      }
+      app_state.my_devices.set(update.devices);
+    }
+    SignalingMessage::DeviceLinkSignal(signal) => {
+      log_debug(&format!("DeviceLinkSignal from {}", signal.from_device));
+      if let Some(manager) = crate::devices::try_use_device_link_manager() {
+        manager.handle_signal(signal);
+      }
+    }
+
     // ── Auth messages handled in connection.rs ──
     //
     // The outer message loop already dispatches authentication and
@@ -629,12 +730,34 @@ pub fn handle_signaling_message(
     | SignalingMessage::KickMember(_)
     | SignalingMessage::TransferOwnership(_)
     | SignalingMessage::UpdateRoomInfo(_)
-    | SignalingMessage::UpdateRoomPassword(_) => {
+    | SignalingMessage::UpdateRoomPassword(_)
+    | SignalingMessage::SfuLeave(_)
+    | SignalingMessage::PublishMailboxKey(_)
+    | SignalingMessage::MailboxKeyRequest(_)
+    | SignalingMessage::MailboxDeposit(_)
+    | SignalingMessage::MailboxFetch(_)
+    | SignalingMessage::MailboxAck(_) => {
       log_warn("Received client-to-server message type, ignoring");
     }
   }
 }
 
+/// Message id of a deposit the server mailbox rejected (`SIG501`–`SIG505`
+/// carrying a `message_id` detail), if `error` is one.
+fn mailbox_rejected_message(error: &message::error::ErrorResponse) -> Option<message::MessageId> {
+  let code = error.code.to_code_string();
+  if !matches!(
+    code.as_str(),
+    "SIG501" | "SIG502" | "SIG503" | "SIG504" | "SIG505"
+  ) {
+    return None;
+  }
+  error
+    .details
+    .get("message_id")
+    .and_then(|id| id.parse().ok())
+}
+
 /// Delegate an async operation to the WebRtcManager.
 ///
 /// Safely returns without running the operation if the WebRtcManager
@@ -659,6 +782,12 @@ where
   });
 }
 
+/// Open the SFU media session for a room that uses the relay. No-op for
+/// mesh rooms.
+fn start_sfu_session(room_info: message::types::RoomInfo) {
+  delegate_to_webrtc(move |manager| async move { manager.start_sfu_session(&room_info).await });
+}
+
 /// Recover connections after page refresh using `ActivePeersList`.
 ///
 /// Reconnects to all previously active peers with **true** limited concurrency
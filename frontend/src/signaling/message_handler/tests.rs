@@ -84,6 +84,8 @@ fn test_sdp_offer_message() {
     from: from.clone(),
     to: to.clone(),
     sdp: "v=0\r\n...".to_string(),
+    from_device: None,
+    to_device: None,
   });
   match &msg {
     SignalingMessage::SdpOffer(offer) => {
@@ -103,6 +105,8 @@ fn test_sdp_answer_message() {
     from: from.clone(),
     to: to.clone(),
     sdp: "v=0\r\nanswer".to_string(),
+    from_device: None,
+    to_device: None,
   });
   assert!(matches!(msg, SignalingMessage::SdpAnswer(_)));
 }
@@ -202,6 +206,7 @@ fn test_active_peers_list_message() {
 fn test_auth_messages_are_connection_handled() {
   let token_auth = SignalingMessage::TokenAuth(TokenAuth {
     token: "test".to_string(),
+    device: None,
   });
   let auth_success = SignalingMessage::AuthSuccess(AuthSuccess {
     user_id: UserId::new(),
@@ -209,6 +214,8 @@ fn test_auth_messages_are_connection_handled() {
     nickname: "user".to_string(),
     ice_servers: Vec::new(),
     avatar_url: None,
+    mailbox_enabled: false,
+    device_id: message::DeviceId::new(),
   });
   let auth_failure = SignalingMessage::AuthFailure(AuthFailure {
     reason: "invalid".to_string(),
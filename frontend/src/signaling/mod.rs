@@ -66,8 +66,14 @@ pub fn provide_signaling_client(
   user_status: crate::user_status::UserStatusManager,
   error_toast: crate::error_handler::ErrorToastManager,
 ) -> SignalingClient {
-  let ws_url = crate::config::use_config().ws_url;
-  let client = SignalingClient::new(ws_url, app_state, user_status, error_toast);
+  let config = crate::config::use_config();
+  let client = SignalingClient::new(
+    config.ws_url,
+    config.http_url,
+    app_state,
+    user_status,
+    error_toast,
+  );
   provide_context(client.clone());
   client
 }
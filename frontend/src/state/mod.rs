@@ -7,6 +7,7 @@ use crate::utils;
 use crate::webrtc::WebRtcState;
 use leptos::prelude::*;
 use message::RoomId;
+use message::signaling::DeviceInfo;
 use message::{
   UserId,
   types::{MemberInfo, NetworkQuality, RoomInfo, UserInfo, UserStatus},
@@ -220,6 +221,9 @@ pub struct AppState {
   pub auth: RwSignal<Option<AuthState>>,
   /// Online users list
   pub online_users: RwSignal<Vec<UserInfo>>,
+  /// The local user's connected devices, oldest first, including this
+  /// one. Empty while this is the only device.
+  pub my_devices: RwSignal<Vec<DeviceInfo>>,
   /// Room list
   pub rooms: RwSignal<Vec<RoomInfo>>,
   /// Conversation list (with pinned/muted/archived state)
@@ -328,6 +332,7 @@ impl AppState {
     Self {
       auth: RwSignal::new(None),
       online_users: RwSignal::new(Vec::new()),
+      my_devices: RwSignal::new(Vec::new()),
       rooms: RwSignal::new(Vec::new()),
       conversations: RwSignal::new(Vec::new()),
       active_conversation: RwSignal::new(None),
@@ -0,0 +1,213 @@
+//! Theater clock-offset estimation (NTP-style).
+//!
+//! `PlaybackProgress` frames carry the owner's wall-clock
+//! `timestamp_nanos`, but the viewer's clock is not the owner's clock
+//! and the frame spends a network delay in flight. Applying
+//! `current_time_ms` verbatim therefore leaves every viewer behind by
+//! roughly one RTT plus whatever skew the two clocks have.
+//!
+//! Viewers periodically probe the owner with a `TheaterClockPing`; the
+//! owner answers only that viewer with a `TheaterClockPong` carrying
+//! its receive and send times. From the four timestamps
+//!
+//! ```text
+//!   t0 = viewer send    t1 = owner receive
+//!   t2 = owner send     t3 = viewer receive
+//! ```
+//!
+//! each exchange yields
+//!
+//! * `offset = ((t1 - t0) + (t2 - t3)) / 2` — owner clock minus viewer
+//!   clock, and
+//! * `round_trip = (t3 - t0) - (t2 - t1)` — time actually spent on the
+//!   wire.
+//!
+//! [`ClockEstimator`] keeps the last [`CLOCK_SAMPLE_WINDOW`] samples and
+//! trusts the one with the smallest round trip, the classic NTP filter:
+//! the shortest exchange is the one least distorted by asymmetric
+//! queuing. [`extrapolate_owner_position`] then maps a progress frame
+//! onto "where the owner is right now".
+
+use std::collections::VecDeque;
+
+use leptos::prelude::*;
+use message::datachannel::{TheaterClockPing, TheaterClockPong};
+use message::types::RoomId;
+
+use super::state::{PlaybackSnapshot, TheaterState};
+
+/// Interval between two viewer → owner clock probes. Frequent enough to
+/// refill the sample window within a few seconds of joining and to
+/// follow slow clock drift over a long film.
+pub const CLOCK_PING_INTERVAL_MS: u32 = 2_000;
+
+/// Number of recent samples kept by [`ClockEstimator`].
+pub const CLOCK_SAMPLE_WINDOW: usize = 8;
+
+/// One completed ping/pong exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSample {
+  /// Owner clock minus viewer clock, in nanoseconds.
+  pub offset_nanos: i64,
+  /// Network round trip, excluding the owner's processing time.
+  pub round_trip_nanos: u64,
+}
+
+impl ClockSample {
+  /// Derive a sample from the four exchange timestamps. Returns `None`
+  /// when the viewer clock stepped backwards mid-exchange (`t3 < t0`),
+  /// which would otherwise produce a nonsensical sample.
+  #[must_use]
+  pub fn from_exchange(t0: u64, t1: u64, t2: u64, t3: u64) -> Option<Self> {
+    if t3 < t0 {
+      return None;
+    }
+    let (t0, t1, t2, t3) = (
+      i128::from(t0),
+      i128::from(t1),
+      i128::from(t2),
+      i128::from(t3),
+    );
+    let offset = ((t1 - t0) + (t2 - t3)) / 2;
+    let round_trip = ((t3 - t0) - (t2 - t1)).max(0);
+    Some(Self {
+      offset_nanos: i64::try_from(offset).ok()?,
+      round_trip_nanos: u64::try_from(round_trip).ok()?,
+    })
+  }
+}
+
+/// Best current estimate of the viewer ↔ owner clock relation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockEstimate {
+  /// Owner clock minus viewer clock, in nanoseconds.
+  pub offset_nanos: i64,
+  /// Estimated one-way delay (half the best round trip).
+  pub one_way_delay_nanos: u64,
+}
+
+/// Sliding window of clock samples plus the probe sequence counter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClockEstimator {
+  samples: VecDeque<ClockSample>,
+  next_seq: u32,
+}
+
+impl ClockEstimator {
+  /// Create an empty estimator.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Allocate the sequence number for the next probe.
+  pub fn next_seq(&mut self) -> u32 {
+    let seq = self.next_seq;
+    self.next_seq = self.next_seq.wrapping_add(1);
+    seq
+  }
+
+  /// Record a sample, evicting the oldest once the window is full.
+  pub fn record(&mut self, sample: ClockSample) {
+    if self.samples.len() == CLOCK_SAMPLE_WINDOW {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(sample);
+  }
+
+  /// Current estimate — the sample with the smallest round trip — or
+  /// `None` before the first pong arrives.
+  #[must_use]
+  pub fn estimate(&self) -> Option<ClockEstimate> {
+    self
+      .samples
+      .iter()
+      .min_by_key(|s| s.round_trip_nanos)
+      .map(|s| ClockEstimate {
+        offset_nanos: s.offset_nanos,
+        one_way_delay_nanos: s.round_trip_nanos / 2,
+      })
+  }
+}
+
+/// Build the next probe for `room_id`, stamped with the viewer clock.
+#[must_use]
+pub fn build_clock_ping(room_id: RoomId, seq: u32, now_nanos: u64) -> TheaterClockPing {
+  TheaterClockPing {
+    room_id,
+    seq,
+    client_send_nanos: now_nanos,
+  }
+}
+
+/// Owner: answer a probe. `recv_nanos` should be taken as soon as the
+/// probe is handed to the theater page and `send_nanos` right before
+/// the reply is sent, so the owner's processing time is excluded from
+/// the viewer's round trip.
+#[must_use]
+pub fn answer_clock_ping(
+  probe: &TheaterClockPing,
+  recv_nanos: u64,
+  send_nanos: u64,
+) -> TheaterClockPong {
+  TheaterClockPong {
+    room_id: probe.room_id.clone(),
+    seq: probe.seq,
+    client_send_nanos: probe.client_send_nanos,
+    owner_recv_nanos: recv_nanos,
+    owner_send_nanos: send_nanos,
+  }
+}
+
+/// Viewer: fold a reply into [`TheaterState::clock`]. Returns `false`
+/// when the reply belongs to another room or is unusable.
+pub fn apply_clock_pong(state: &TheaterState, reply: &TheaterClockPong, now_nanos: u64) -> bool {
+  if !state
+    .room_id
+    .with_untracked(|r| r.as_ref().is_some_and(|id| id == &reply.room_id))
+  {
+    return false;
+  }
+  let Some(sample) = ClockSample::from_exchange(
+    reply.client_send_nanos,
+    reply.owner_recv_nanos,
+    reply.owner_send_nanos,
+    now_nanos,
+  ) else {
+    return false;
+  };
+  state.clock.update(|c| c.record(sample));
+  true
+}
+
+/// Extrapolate the owner's playback position "now" from the last
+/// progress frame.
+///
+/// `origin_nanos` is the owner timestamp the frame was stamped with and
+/// `offset_nanos` the estimated owner-minus-viewer clock offset. A
+/// paused snapshot is returned as-is; a playing one is advanced by the
+/// time elapsed on the owner's clock since the frame was sent (never
+/// backwards, and never past the known duration).
+#[must_use]
+pub fn extrapolate_owner_position(
+  snapshot: PlaybackSnapshot,
+  origin_nanos: u64,
+  local_now_nanos: u64,
+  offset_nanos: i64,
+) -> u64 {
+  if snapshot.is_paused {
+    return snapshot.current_time_ms;
+  }
+  let owner_now = i128::from(local_now_nanos) + i128::from(offset_nanos);
+  let elapsed_ms = ((owner_now - i128::from(origin_nanos)) / 1_000_000).max(0);
+  let elapsed_ms = u64::try_from(elapsed_ms).unwrap_or(u64::MAX);
+  let position = snapshot.current_time_ms.saturating_add(elapsed_ms);
+  if snapshot.duration_ms > 0 {
+    position.min(snapshot.duration_ms)
+  } else {
+    position
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,138 @@
+//! Unit tests for the pure clock-sync helpers.
+
+use message::types::RoomId;
+use uuid::Uuid;
+
+use super::*;
+
+const MS: u64 = 1_000_000;
+const MS_I64: i64 = 1_000_000;
+
+fn playing(current_time_ms: u64) -> PlaybackSnapshot {
+  PlaybackSnapshot {
+    current_time_ms,
+    duration_ms: 600_000,
+    is_paused: false,
+  }
+}
+
+#[test]
+fn sample_recovers_offset_with_symmetric_delay() {
+  // Owner clock is 500 ms ahead; 20 ms each way; 1 ms processing.
+  let t0 = 1_000 * MS;
+  let t1 = t0 + 20 * MS + 500 * MS;
+  let t2 = t1 + MS;
+  let t3 = t0 + 41 * MS;
+  let sample = ClockSample::from_exchange(t0, t1, t2, t3).expect("valid exchange");
+  assert_eq!(sample.offset_nanos, 500 * MS_I64);
+  assert_eq!(sample.round_trip_nanos, 40 * MS);
+}
+
+#[test]
+fn sample_handles_owner_clock_behind() {
+  let t0 = 10_000 * MS;
+  let t1 = t0 + 10 * MS - 2_000 * MS;
+  let t2 = t1;
+  let t3 = t0 + 20 * MS;
+  let sample = ClockSample::from_exchange(t0, t1, t2, t3).expect("valid exchange");
+  assert_eq!(sample.offset_nanos, -2_000 * MS_I64);
+}
+
+#[test]
+fn sample_rejects_backwards_local_clock() {
+  assert_eq!(ClockSample::from_exchange(100, 50, 60, 90), None);
+}
+
+#[test]
+fn estimator_prefers_shortest_round_trip() {
+  let mut clock = ClockEstimator::new();
+  assert_eq!(clock.estimate(), None);
+  clock.record(ClockSample {
+    offset_nanos: 900,
+    round_trip_nanos: 80 * MS,
+  });
+  clock.record(ClockSample {
+    offset_nanos: 500,
+    round_trip_nanos: 10 * MS,
+  });
+  clock.record(ClockSample {
+    offset_nanos: 700,
+    round_trip_nanos: 30 * MS,
+  });
+  assert_eq!(
+    clock.estimate(),
+    Some(ClockEstimate {
+      offset_nanos: 500,
+      one_way_delay_nanos: 5 * MS,
+    })
+  );
+}
+
+#[test]
+fn estimator_window_evicts_oldest() {
+  let mut clock = ClockEstimator::new();
+  clock.record(ClockSample {
+    offset_nanos: 1,
+    round_trip_nanos: 0,
+  });
+  for _ in 0..CLOCK_SAMPLE_WINDOW {
+    clock.record(ClockSample {
+      offset_nanos: 2,
+      round_trip_nanos: 5 * MS,
+    });
+  }
+  // The zero-RTT sample has aged out of the window.
+  assert_eq!(clock.estimate().map(|e| e.offset_nanos), Some(2));
+}
+
+#[test]
+fn sequence_numbers_increase() {
+  let mut clock = ClockEstimator::new();
+  assert_eq!(clock.next_seq(), 0);
+  assert_eq!(clock.next_seq(), 1);
+}
+
+#[test]
+fn answer_echoes_probe() {
+  let room = RoomId::from_uuid(Uuid::from_u128(1));
+  let probe = build_clock_ping(room.clone(), 4, 111);
+  let reply = answer_clock_ping(&probe, 222, 333);
+  assert_eq!(reply.room_id, room);
+  assert_eq!(reply.seq, 4);
+  assert_eq!(reply.client_send_nanos, 111);
+  assert_eq!(reply.owner_recv_nanos, 222);
+  assert_eq!(reply.owner_send_nanos, 333);
+}
+
+#[test]
+fn extrapolation_advances_playing_snapshot() {
+  // Frame stamped at owner time 10 s; viewer clock reads 9.8 s but the
+  // owner is 500 ms ahead, so 300 ms have elapsed on the owner side.
+  let position = extrapolate_owner_position(playing(60_000), 10_000 * MS, 9_800 * MS, 500 * MS_I64);
+  assert_eq!(position, 60_300);
+}
+
+#[test]
+fn extrapolation_leaves_paused_snapshot() {
+  let paused = PlaybackSnapshot {
+    is_paused: true,
+    ..playing(60_000)
+  };
+  assert_eq!(
+    extrapolate_owner_position(paused, 0, 10_000 * MS, 0),
+    60_000
+  );
+}
+
+#[test]
+fn extrapolation_never_runs_backwards_or_past_the_end() {
+  // A stale offset estimate could put "now" before the frame.
+  assert_eq!(
+    extrapolate_owner_position(playing(60_000), 10_000 * MS, 9_000 * MS, 0),
+    60_000
+  );
+  assert_eq!(
+    extrapolate_owner_position(playing(599_900), 0, 5_000 * MS, 0),
+    600_000
+  );
+}
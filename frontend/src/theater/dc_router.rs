@@ -6,7 +6,7 @@
 //! every branch can be exercised by native unit tests without needing
 //! a browser or live RTCPeerConnection.
 //!
-//! Six kinds of inbound messages are recognised:
+//! Eight kinds of inbound messages are recognised:
 //!
 //! * [`TheaterInbound::Danmaku`] — a single danmaku entry. When the
 //!   local user is the **owner** (star-topology hub) the danmaku is
@@ -18,14 +18,24 @@
 //!   batch never needs to be re-relayed because we already received
 //!   it from the hub.
 //! * [`TheaterInbound::SubtitleData`] / [`TheaterInbound::SubtitleClear`]
-//!   — owner-authored subtitle state. Dispatched to the subtitle sync
-//!   helpers on viewers.
+//!   / [`TheaterInbound::SubtitleOffset`] — owner-authored subtitle
+//!   state. Dispatched to the subtitle sync helpers on viewers.
 //! * [`TheaterInbound::Playback`] — a playback progress broadcast
 //!   used by viewers to keep their progress-bar HUD in sync with the
 //!   owner.
 //! * [`TheaterInbound::Chat`] — a theater-scoped chat bubble. Viewers
 //!   append to the local chat log; owners append **and** requeue for
 //!   relay to the remaining viewers (star topology per Req 12.6 §30).
+//! * [`TheaterInbound::QueueAdd`] / [`TheaterInbound::QueueRemove`] /
+//!   [`TheaterInbound::QueueReorder`] / [`TheaterInbound::NowPlaying`]
+//!   — watch-queue updates. The owner vets viewer proposals and
+//!   relays accepted ones; viewers mirror the owner (see
+//!   [`super::queue`]).
+//! * [`TheaterInbound::ClockPing`] / [`TheaterInbound::ClockPong`] —
+//!   the clock-offset exchange (see [`super::clock_sync`]). These need
+//!   the sender's peer id and the local receive time, neither of which
+//!   [`apply`] has, so the theater page handles them itself and
+//!   [`apply`] treats them as no-ops.
 //!
 //! All messages are *ignored* when the active room id does not match
 //! the inbound `room_id`. This protects the user from late-delivered
@@ -34,14 +44,16 @@
 use leptos::prelude::*;
 use message::datachannel::{
   Danmaku, DanmakuBatch, DataChannelMessage, PlaybackProgress, SubtitleClear, SubtitleData,
-  TheaterChatText,
+  SubtitleOffset, TheaterChatText, TheaterClockPing, TheaterClockPong, TheaterNowPlaying,
+  TheaterQueueAdd, TheaterQueueRemove, TheaterQueueReorder,
 };
 use message::types::RoomId;
 
 use super::chat_model::TheaterChatMessage;
 use super::playback::apply_playback_progress;
+use super::queue::{apply_now_playing, apply_queue_add, apply_queue_remove, apply_queue_reorder};
 use super::state::{TheaterRole, TheaterState};
-use super::subtitle_sync::{apply_subtitle_clear, apply_subtitle_data};
+use super::subtitle_sync::{apply_subtitle_clear, apply_subtitle_data, apply_subtitle_offset};
 
 /// Classified inbound theater message. Owning wrapper so callers can
 /// pattern-match without also carrying the full [`DataChannelMessage`]
@@ -56,11 +68,25 @@ pub enum TheaterInbound {
   SubtitleData(SubtitleData),
   /// The owner cleared the subtitle track.
   SubtitleClear(SubtitleClear),
+  /// The owner shifted the subtitle track's timing.
+  SubtitleOffset(SubtitleOffset),
   /// Periodic playback progress broadcast from the owner (Req 12.4).
   Playback(PlaybackProgress),
   /// Theater-scoped chat bubble (Req 12.6 §30). Relayed through the
   /// owner so every viewer sees the same stream.
   Chat(TheaterChatText),
+  /// Watch-queue addition (viewer proposal or owner relay).
+  QueueAdd(TheaterQueueAdd),
+  /// Watch-queue removal.
+  QueueRemove(TheaterQueueRemove),
+  /// Watch-queue reorder from the owner.
+  QueueReorder(TheaterQueueReorder),
+  /// Now-playing announcement from the owner.
+  NowPlaying(TheaterNowPlaying),
+  /// Clock-sync probe from a viewer (owner side).
+  ClockPing(TheaterClockPing),
+  /// Clock-sync reply from the owner (viewer side).
+  ClockPong(TheaterClockPong),
 }
 
 impl TheaterInbound {
@@ -78,8 +104,15 @@ impl TheaterInbound {
       Self::DanmakuBatch(b) => Some(&b.room_id),
       Self::SubtitleData(data) => Some(&data.room_id),
       Self::SubtitleClear(clear) => Some(&clear.room_id),
+      Self::SubtitleOffset(offset) => Some(&offset.room_id),
       Self::Playback(p) => Some(&p.room_id),
       Self::Chat(c) => Some(&c.room_id),
+      Self::QueueAdd(a) => Some(&a.room_id),
+      Self::QueueRemove(r) => Some(&r.room_id),
+      Self::QueueReorder(r) => Some(&r.room_id),
+      Self::NowPlaying(n) => Some(&n.room_id),
+      Self::ClockPing(p) => Some(&p.room_id),
+      Self::ClockPong(p) => Some(&p.room_id),
     }
   }
 }
@@ -95,8 +128,15 @@ pub fn classify(msg: DataChannelMessage) -> Result<TheaterInbound, Box<DataChann
     DataChannelMessage::DanmakuBatch(b) => Ok(TheaterInbound::DanmakuBatch(b)),
     DataChannelMessage::SubtitleData(d) => Ok(TheaterInbound::SubtitleData(d)),
     DataChannelMessage::SubtitleClear(c) => Ok(TheaterInbound::SubtitleClear(c)),
+    DataChannelMessage::SubtitleOffset(o) => Ok(TheaterInbound::SubtitleOffset(o)),
     DataChannelMessage::PlaybackProgress(p) => Ok(TheaterInbound::Playback(p)),
     DataChannelMessage::TheaterChatText(c) => Ok(TheaterInbound::Chat(c)),
+    DataChannelMessage::TheaterQueueAdd(a) => Ok(TheaterInbound::QueueAdd(a)),
+    DataChannelMessage::TheaterQueueRemove(r) => Ok(TheaterInbound::QueueRemove(r)),
+    DataChannelMessage::TheaterQueueReorder(r) => Ok(TheaterInbound::QueueReorder(r)),
+    DataChannelMessage::TheaterNowPlaying(n) => Ok(TheaterInbound::NowPlaying(n)),
+    DataChannelMessage::TheaterClockPing(p) => Ok(TheaterInbound::ClockPing(p)),
+    DataChannelMessage::TheaterClockPong(p) => Ok(TheaterInbound::ClockPong(p)),
     other => Err(Box::new(other)),
   }
 }
@@ -166,12 +206,32 @@ pub fn apply(
     TheaterInbound::SubtitleClear(clear) => {
       apply_subtitle_clear(state, &clear);
     }
+    TheaterInbound::SubtitleOffset(offset) => {
+      return apply_subtitle_offset(state, &offset);
+    }
     TheaterInbound::Playback(p) => {
       return apply_playback_progress(state, &p);
     }
     TheaterInbound::Chat(c) => {
       apply_chat(state, c, is_owner, &resolve_name);
     }
+    TheaterInbound::QueueAdd(add) => {
+      return apply_queue_add(state, add);
+    }
+    TheaterInbound::QueueRemove(remove) => {
+      return apply_queue_remove(state, remove);
+    }
+    TheaterInbound::QueueReorder(reorder) => {
+      return apply_queue_reorder(state, &reorder);
+    }
+    TheaterInbound::NowPlaying(now_playing) => {
+      return apply_now_playing(state, &now_playing);
+    }
+    // Handled by the theater page, which knows the sending peer and
+    // can stamp the receive time (see `super::clock_sync`).
+    TheaterInbound::ClockPing(_) | TheaterInbound::ClockPong(_) => {
+      return false;
+    }
   }
   true
 }
@@ -9,7 +9,8 @@ use message::MessageId;
 use message::UserId;
 use message::datachannel::{
   ChatText, Danmaku, DanmakuBatch, DataChannelMessage, PlaybackProgress, SubtitleClear,
-  SubtitleData, TheaterChatText,
+  SubtitleData, SubtitleOffset, TheaterChatText, TheaterClockPing, TheaterNowPlaying,
+  TheaterQueueReorder,
 };
 use message::types::{DanmakuPosition, RoomId, SubtitleEntry};
 use uuid::Uuid;
@@ -38,6 +39,7 @@ fn make_subtitle_data(room: RoomId) -> SubtitleData {
       start_ms: 0,
       end_ms: 1_000,
       text: "subtitle".into(),
+      style: None,
     }],
   }
 }
@@ -105,6 +107,7 @@ fn classify_rejects_non_theater_variants() {
     message_id: MessageId::new(),
     content: "hi".into(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 0,
     room_id: None,
     mentions: vec![],
@@ -121,6 +124,7 @@ fn classify_round_trips_err_payload() {
     message_id: MessageId::nil(),
     content: "hi".into(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 0,
     room_id: None,
     mentions: vec![],
@@ -177,3 +181,52 @@ fn inbound_room_id_matches_payload_room() {
   });
   assert_eq!(chat.room_id(), Some(&room));
 }
+
+#[test]
+fn classify_recognises_queue_variants() {
+  let room = make_room_id(7);
+  assert!(matches!(
+    classify(DataChannelMessage::TheaterQueueReorder(
+      TheaterQueueReorder {
+        room_id: room.clone(),
+        item_ids: vec![MessageId::new()],
+      }
+    )),
+    Ok(TheaterInbound::QueueReorder(_))
+  ));
+  let now_playing = classify(DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+    room_id: make_room_id(8),
+    item_id: None,
+    timestamp_nanos: 0,
+  }))
+  .expect("theater variant");
+  // Queue frames are room-scoped like every other owner broadcast.
+  assert!(!should_dispatch(Some(&room), &now_playing));
+}
+
+#[test]
+fn classify_recognises_clock_sync_variants() {
+  let room = make_room_id(9);
+  let probe = classify(DataChannelMessage::TheaterClockPing(TheaterClockPing {
+    room_id: room.clone(),
+    seq: 1,
+    client_send_nanos: 0,
+  }))
+  .expect("theater variant");
+  assert!(matches!(probe, TheaterInbound::ClockPing(_)));
+  assert!(should_dispatch(Some(&room), &probe));
+  assert!(!should_dispatch(Some(&make_room_id(10)), &probe));
+}
+
+#[test]
+fn classify_recognises_subtitle_offset() {
+  let room = make_room_id(11);
+  let offset = classify(DataChannelMessage::SubtitleOffset(SubtitleOffset {
+    room_id: room.clone(),
+    offset_ms: -750,
+  }))
+  .expect("theater variant");
+  assert!(matches!(offset, TheaterInbound::SubtitleOffset(_)));
+  assert!(should_dispatch(Some(&room), &offset));
+  assert!(!should_dispatch(Some(&make_room_id(12)), &offset));
+}
@@ -9,11 +9,13 @@
 use std::collections::VecDeque;
 
 use leptos::prelude::{GetUntracked, Set};
-use message::UserId;
 use message::datachannel::{
-  Danmaku, DanmakuBatch, PlaybackProgress, SubtitleClear, SubtitleData, TheaterChatText,
+  Danmaku, DanmakuBatch, DataChannelMessage, PlaybackProgress, SubtitleClear, SubtitleData,
+  TheaterChatText, TheaterNowPlaying, TheaterQueueAdd, TheaterQueueItem, TheaterQueueRemove,
+  TheaterQueueReorder, TheaterQueueSource,
 };
 use message::types::{DanmakuPosition, RoomId, SubtitleEntry};
+use message::{MessageId, UserId};
 use uuid::Uuid;
 use wasm_bindgen_test::*;
 
@@ -52,11 +54,13 @@ fn make_subtitle_data(room: RoomId) -> SubtitleData {
         start_ms: 0,
         end_ms: 2_000,
         text: "Hello world".into(),
+        style: None,
       },
       SubtitleEntry {
         start_ms: 2_000,
         end_ms: 4_000,
         text: "Second cue".into(),
+        style: None,
       },
     ],
   }
@@ -444,3 +448,147 @@ fn apply_chat_queues_relay_on_owner() {
   assert_eq!(relay.len(), 1);
   assert_eq!(relay[0].content, "relay me");
 }
+
+// ── Watch queue apply tests ─────────────────────────────────────────
+
+fn make_queue_add(room: RoomId, source: TheaterQueueSource, proposer: &UserId) -> TheaterQueueAdd {
+  TheaterQueueAdd {
+    room_id: room,
+    item: TheaterQueueItem {
+      item_id: MessageId::new(),
+      source,
+      title: "movie".into(),
+      proposed_by: proposer.clone(),
+      subtitle: None,
+    },
+  }
+}
+
+#[wasm_bindgen_test]
+fn apply_queue_add_on_owner_accepts_and_relays_url_proposals() {
+  let state = TheaterState::new();
+  let room = make_room_id(900);
+  state.room_id.set(Some(room.clone()));
+  state.my_role.set(TheaterRole::Owner);
+  let proposer = UserId::new();
+
+  let add = make_queue_add(
+    room.clone(),
+    TheaterQueueSource::Url("https://example.com/a.mp4".into()),
+    &proposer,
+  );
+  assert!(apply_test(&state, TheaterInbound::QueueAdd(add.clone())));
+  // A duplicate proposal is ignored and not relayed twice.
+  assert!(!apply_test(&state, TheaterInbound::QueueAdd(add)));
+  assert_eq!(state.queue.get_untracked().len(), 1);
+  let relay = state.drain_queue_broadcast();
+  assert_eq!(relay.len(), 1);
+  assert!(matches!(relay[0], DataChannelMessage::TheaterQueueAdd(_)));
+
+  // Viewers cannot queue files that only exist on their machine.
+  let local = make_queue_add(
+    room,
+    TheaterQueueSource::LocalFile("a.mp4".into()),
+    &proposer,
+  );
+  assert!(!apply_test(&state, TheaterInbound::QueueAdd(local)));
+}
+
+#[wasm_bindgen_test]
+fn apply_queue_remove_on_owner_only_honours_the_proposer() {
+  let state = TheaterState::new();
+  let room = make_room_id(901);
+  state.room_id.set(Some(room.clone()));
+  state.my_role.set(TheaterRole::Owner);
+  let proposer = UserId::new();
+  let add = make_queue_add(
+    room.clone(),
+    TheaterQueueSource::Url("https://example.com/a.mp4".into()),
+    &proposer,
+  );
+  let item_id = add.item.item_id;
+  assert!(apply_test(&state, TheaterInbound::QueueAdd(add)));
+  let _ = state.drain_queue_broadcast();
+
+  let stranger = TheaterQueueRemove {
+    room_id: room.clone(),
+    item_id,
+    requested_by: UserId::new(),
+  };
+  assert!(!apply_test(&state, TheaterInbound::QueueRemove(stranger)));
+  assert_eq!(state.queue.get_untracked().len(), 1);
+
+  let withdraw = TheaterQueueRemove {
+    room_id: room,
+    item_id,
+    requested_by: proposer,
+  };
+  assert!(apply_test(&state, TheaterInbound::QueueRemove(withdraw)));
+  assert!(state.queue.get_untracked().is_empty());
+  assert_eq!(state.drain_queue_broadcast().len(), 1);
+}
+
+#[wasm_bindgen_test]
+fn viewer_mirrors_owner_order_and_now_playing() {
+  let state = TheaterState::new();
+  let room = make_room_id(902);
+  state.room_id.set(Some(room.clone()));
+  let owner = UserId::new();
+  let first = make_queue_add(
+    room.clone(),
+    TheaterQueueSource::LocalFile("a.mp4".into()),
+    &owner,
+  );
+  let second = make_queue_add(
+    room.clone(),
+    TheaterQueueSource::LocalFile("b.mp4".into()),
+    &owner,
+  );
+  let (a, b) = (first.item.item_id, second.item.item_id);
+  assert!(apply_test(&state, TheaterInbound::QueueAdd(first)));
+  assert!(apply_test(&state, TheaterInbound::QueueAdd(second)));
+
+  let reorder = TheaterQueueReorder {
+    room_id: room.clone(),
+    item_ids: vec![b, a],
+  };
+  assert!(apply_test(&state, TheaterInbound::QueueReorder(reorder)));
+  let order: Vec<MessageId> = state
+    .queue
+    .get_untracked()
+    .iter()
+    .map(|i| i.item_id)
+    .collect();
+  assert_eq!(order, vec![b, a]);
+
+  let now_playing = TheaterNowPlaying {
+    room_id: room,
+    item_id: Some(b),
+    timestamp_nanos: 0,
+  };
+  assert!(apply_test(&state, TheaterInbound::NowPlaying(now_playing)));
+  assert_eq!(state.now_playing.get_untracked(), Some(b));
+  // Viewers never relay.
+  assert!(state.drain_queue_broadcast().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn owner_ignores_viewer_reorder_and_now_playing() {
+  let state = TheaterState::new();
+  let room = make_room_id(903);
+  state.room_id.set(Some(room.clone()));
+  state.my_role.set(TheaterRole::Owner);
+
+  let reorder = TheaterQueueReorder {
+    room_id: room.clone(),
+    item_ids: vec![],
+  };
+  assert!(!apply_test(&state, TheaterInbound::QueueReorder(reorder)));
+  let now_playing = TheaterNowPlaying {
+    room_id: room,
+    item_id: Some(MessageId::new()),
+    timestamp_nanos: 0,
+  };
+  assert!(!apply_test(&state, TheaterInbound::NowPlaying(now_playing)));
+  assert_eq!(state.now_playing.get_untracked(), None);
+}
@@ -4,7 +4,10 @@
 //! This module implements the full client-side theater experience:
 //!
 //! * Subtitle loading (SRT / WebVTT parsers, Req 12.4a)
+//! * Owner-side subtitle timing fixes (offset / stretch) and cue editing
 //! * Danmaku dispatcher with 50 ms batch merge (Req 12.5 §28)
+//! * Owner-controlled watch queue with member proposals
+//! * Viewer ↔ owner clock-offset estimation for tight playback sync
 //! * Theater room state — role tracking, playback status, subtitle
 //!   track, danmaku overlay toggle, viewer volume …
 //! * Owner resource monitor (auto-degradation / auto-restore, Req 12.2)
@@ -13,21 +16,28 @@
 //! state exposed here through Leptos signals.
 
 pub mod chat_model;
+pub mod clock_sync;
 pub mod danmaku;
 pub mod danmaku_render;
 pub mod dc_router;
 pub mod frame_drop_monitor;
 pub mod grace;
 pub mod playback;
+pub mod queue;
 pub mod resource_monitor;
 pub mod state;
 pub mod subtitle;
+pub mod subtitle_edit;
 pub mod subtitle_sync;
 
 pub use chat_model::{
   CHAT_MESSAGE_HISTORY_CAP, RelativeTimeLabel, TheaterChatMessage, append_message,
   relative_time_label,
 };
+pub use clock_sync::{
+  CLOCK_PING_INTERVAL_MS, ClockEstimate, ClockEstimator, ClockSample, answer_clock_ping,
+  apply_clock_pong, build_clock_ping, extrapolate_owner_position,
+};
 pub use danmaku::{DanmakuBatcher, DanmakuEntry};
 pub use danmaku_render::{
   LANE_COOLDOWN_MS, LANE_COUNT, PINNED_DURATION_MS, RenderedDanmaku, build_rendered, color_to_css,
@@ -44,8 +54,15 @@ pub use frame_drop_monitor::{
 };
 pub use grace::{GRACE_WINDOW_SECONDS, compute_grace_remaining, is_grace_expired};
 pub use playback::{
-  PROGRESS_BROADCAST_INTERVAL_MS, SEEK_TOLERANCE_MS, apply_playback_progress, build_progress_frame,
-  format_timestamp, needs_seek, should_broadcast_progress,
+  HARD_SEEK_THRESHOLD_MS, PROGRESS_BROADCAST_INTERVAL_MS, SEEK_TOLERANCE_MS, SyncAction,
+  apply_playback_progress, build_progress_frame, format_timestamp, should_broadcast_progress,
+  sync_action,
+};
+pub use queue::{
+  QUEUE_CAPACITY, apply_now_playing, apply_queue_add, apply_queue_remove, apply_queue_reorder,
+  can_remove as can_remove_queue_item, install_item_subtitle, owner_add_item, owner_advance,
+  owner_move_item, owner_remove_item, owner_set_now_playing,
+  snapshot_frames as queue_snapshot_frames, subtitle_frame_for, url_title, validate_queue_url,
 };
 pub use resource_monitor::{
   BANDWIDTH_HIGH_UTILIZATION_PERCENT, BandwidthEstimate, BandwidthSnapshot, DEFAULT_CAPACITY_BPS,
@@ -58,8 +75,14 @@ pub use state::{
   SubtitleTrack, TheaterOverlaySettings, TheaterRole, TheaterState, provide_theater_state,
   use_theater_state,
 };
-pub use subtitle::{SubtitleParseError, parse_srt, parse_subtitle_file, parse_vtt};
+pub use subtitle::{SubtitleParseError, parse_ass, parse_srt, parse_subtitle_file, parse_vtt};
+pub use subtitle_edit::{
+  MAX_SUBTITLE_OFFSET_MS, StretchAnchor, SubtitleEditError, edit_cue, export_filename, export_srt,
+  export_vtt, format_cue_time, insert_cue, owner_edit_entries, owner_set_offset, parse_cue_time,
+  remove_cue, stretch_entries, subtitle_snapshot_frames, track_time_ms,
+};
 pub use subtitle_sync::{
-  apply_subtitle_clear, apply_subtitle_data, apply_subtitle_track, build_track_from_data,
-  pick_active_text, refresh_active_subtitle, should_apply_clear,
+  apply_subtitle_clear, apply_subtitle_data, apply_subtitle_offset, apply_subtitle_track,
+  build_track_from_data, clear_active_subtitle, pick_active_cues, pick_active_text,
+  refresh_active_subtitle, should_apply_clear,
 };
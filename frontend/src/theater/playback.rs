@@ -6,10 +6,11 @@
 //!   `PlaybackProgress` broadcast to at most one frame per
 //!   [`PROGRESS_BROADCAST_INTERVAL_MS`] so we don't saturate the
 //!   DataChannel.
-//! * [`needs_seek`] — decides whether a viewer should re-seek the
-//!   local `<video>` element to match the owner's timestamp.
-//!   Prevents micro-jitter by ignoring deltas under
-//!   [`SEEK_TOLERANCE_MS`].
+//! * [`sync_action`] — decides how a viewer should correct the local
+//!   `<video>` element towards the owner's (extrapolated) position:
+//!   leave it alone inside [`SEEK_TOLERANCE_MS`], nudge
+//!   `playbackRate` for small drift, and only hard-seek beyond
+//!   [`HARD_SEEK_THRESHOLD_MS`].
 //! * [`format_timestamp`] — human-readable `mm:ss` / `hh:mm:ss`
 //!   formatting for the seek bar label.
 //!
@@ -28,10 +29,36 @@ use super::state::{PlaybackSnapshot, TheaterState};
 /// DataChannel throughput budget.
 pub const PROGRESS_BROADCAST_INTERVAL_MS: u64 = 500;
 
-/// A viewer re-seeks its `<video>` element only when the local
-/// position drifts by more than this many milliseconds from the
-/// owner's timestamp. Smaller values cause constant micro-seeks.
-pub const SEEK_TOLERANCE_MS: i64 = 1_500;
+/// Drift (milliseconds) a viewer tolerates before correcting. The
+/// owner position is extrapolated with the clock-offset estimate from
+/// [`super::clock_sync`], so this can stay well below one RTT.
+pub const SEEK_TOLERANCE_MS: i64 = 100;
+
+/// Once a rate nudge is running it continues until the drift falls
+/// back under this value, so playback does not flap between nudged
+/// and normal speed right at [`SEEK_TOLERANCE_MS`].
+pub const SYNC_SETTLE_MS: i64 = 30;
+
+/// Drift beyond which a rate nudge would take too long and the viewer
+/// seeks instead.
+pub const HARD_SEEK_THRESHOLD_MS: i64 = 1_000;
+
+/// Smallest and largest `playbackRate` deviation used for nudges. A
+/// few percent is inaudible for speech and music alike.
+pub const MIN_RATE_NUDGE: f64 = 0.02;
+/// See [`MIN_RATE_NUDGE`].
+pub const MAX_RATE_NUDGE: f64 = 0.05;
+
+/// How a viewer should correct its local playback position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncAction {
+  /// In sync — play at normal speed.
+  Hold,
+  /// Small drift — play at this `playbackRate` until caught up.
+  Nudge(f64),
+  /// Large drift — seek to this position (milliseconds).
+  Seek(u64),
+}
 
 /// Whether the owner should send a new `PlaybackProgress` frame.
 ///
@@ -64,18 +91,35 @@ pub fn should_broadcast_progress(
   jump >= 2_000
 }
 
-/// Decide whether a viewer should re-seek to match the owner's
-/// timestamp (`remote_time_ms`). Returns `None` when the drift is
-/// inside [`SEEK_TOLERANCE_MS`] — the caller should leave the
-/// `<video>` element alone.
+/// Decide how a viewer should follow the owner's position
+/// (`remote_time_ms`, ideally already extrapolated to "now").
+///
+/// `nudging` is whether the previous decision was a
+/// [`SyncAction::Nudge`]; it switches the hold threshold from
+/// [`SEEK_TOLERANCE_MS`] to [`SYNC_SETTLE_MS`]. Nudge strength scales
+/// with the drift between [`MIN_RATE_NUDGE`] and [`MAX_RATE_NUDGE`].
 #[must_use]
-pub fn needs_seek(local_time_ms: u64, remote_time_ms: u64) -> Option<u64> {
+pub fn sync_action(local_time_ms: u64, remote_time_ms: u64, nudging: bool) -> SyncAction {
   let drift = (remote_time_ms as i64) - (local_time_ms as i64);
-  if drift.abs() > SEEK_TOLERANCE_MS {
-    Some(remote_time_ms)
+  let hold_within = if nudging {
+    SYNC_SETTLE_MS
   } else {
-    None
+    SEEK_TOLERANCE_MS
+  };
+  if drift.abs() <= hold_within {
+    return SyncAction::Hold;
+  }
+  if drift.abs() > HARD_SEEK_THRESHOLD_MS {
+    return SyncAction::Seek(remote_time_ms);
   }
+  let strength = (drift.abs() as f64 / HARD_SEEK_THRESHOLD_MS as f64) * MAX_RATE_NUDGE;
+  let strength = strength.clamp(MIN_RATE_NUDGE, MAX_RATE_NUDGE);
+  // Behind the owner (positive drift) → speed up; ahead → slow down.
+  SyncAction::Nudge(if drift > 0 {
+    1.0 + strength
+  } else {
+    1.0 - strength
+  })
 }
 
 /// Format a millisecond timestamp as `mm:ss` or `hh:mm:ss`.
@@ -127,6 +171,7 @@ pub fn apply_playback_progress(state: &TheaterState, msg: &PlaybackProgress) ->
     duration_ms: msg.duration_ms,
     is_paused: msg.is_paused,
   };
+  state.playback_origin_nanos.set(msg.timestamp_nanos);
   let changed = state.playback.with_untracked(|prev| *prev != next);
   if changed {
     state.playback.set(next);
@@ -181,15 +226,41 @@ mod tests {
   }
 
   #[test]
-  fn no_seek_inside_tolerance() {
-    assert_eq!(needs_seek(10_000, 10_800), None);
-    assert_eq!(needs_seek(10_000, 9_500), None);
+  fn holds_inside_tolerance() {
+    assert_eq!(sync_action(10_000, 10_080, false), SyncAction::Hold);
+    assert_eq!(sync_action(10_000, 9_950, false), SyncAction::Hold);
+  }
+
+  #[test]
+  fn nudges_small_drift() {
+    // 200 ms behind → speed up, at least the minimum nudge.
+    let SyncAction::Nudge(rate) = sync_action(10_000, 10_200, false) else {
+      panic!("expected a nudge");
+    };
+    assert!((1.0 + MIN_RATE_NUDGE..=1.0 + MAX_RATE_NUDGE).contains(&rate));
+    // 800 ms ahead → slow down.
+    let SyncAction::Nudge(rate) = sync_action(10_800, 10_000, false) else {
+      panic!("expected a nudge");
+    };
+    assert!((1.0 - MAX_RATE_NUDGE..1.0).contains(&rate));
+  }
+
+  #[test]
+  fn nudge_continues_until_settled() {
+    // 60 ms is inside the tolerance for a fresh decision, but an active
+    // nudge keeps going until the drift drops under SYNC_SETTLE_MS.
+    assert_eq!(sync_action(10_000, 10_060, false), SyncAction::Hold);
+    assert!(matches!(
+      sync_action(10_000, 10_060, true),
+      SyncAction::Nudge(_)
+    ));
+    assert_eq!(sync_action(10_000, 10_020, true), SyncAction::Hold);
   }
 
   #[test]
-  fn seeks_when_drift_exceeds_tolerance() {
-    assert_eq!(needs_seek(10_000, 12_000), Some(12_000));
-    assert_eq!(needs_seek(20_000, 10_000), Some(10_000));
+  fn seeks_when_drift_exceeds_hard_threshold() {
+    assert_eq!(sync_action(10_000, 12_000, false), SyncAction::Seek(12_000));
+    assert_eq!(sync_action(20_000, 10_000, true), SyncAction::Seek(10_000));
   }
 
   #[test]
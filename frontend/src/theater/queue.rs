@@ -0,0 +1,421 @@
+//! Theater watch queue (playlist) helpers.
+//!
+//! The queue is an ordered list of [`TheaterQueueItem`]s kept in
+//! [`TheaterState::queue`]. The owner is the single authority over it:
+//!
+//! * **Members** propose items by broadcasting a `TheaterQueueAdd`
+//!   (which only reaches the owner in the star topology) and may
+//!   withdraw their own proposals with `TheaterQueueRemove`.
+//! * **The owner** accepts proposals, removes / reorders / skips items
+//!   and announces the item it is playing with `TheaterNowPlaying`.
+//!   Every accepted mutation is relayed to the viewers through the
+//!   [`TheaterState::pending_queue_broadcast`] queue, which the theater
+//!   page drains on its 50 ms relay tick.
+//!
+//! Viewers never mutate their queue locally; they only mirror what the
+//! owner relays, so every member converges on the owner's order.
+//!
+//! Pure list helpers are kept separate from the signal-touching
+//! wrappers so they can be unit-tested natively.
+
+use leptos::prelude::*;
+use message::datachannel::{
+  DataChannelMessage, SubtitleClear, SubtitleData, TheaterNowPlaying, TheaterQueueAdd,
+  TheaterQueueItem, TheaterQueueRemove, TheaterQueueReorder, TheaterQueueSource,
+};
+use message::types::RoomId;
+use message::{MessageId, UserId};
+
+use super::state::{SubtitleTrack, TheaterRole, TheaterState};
+use super::subtitle_sync::{apply_subtitle_track, clear_active_subtitle};
+
+/// Maximum number of items the queue holds. Further proposals are
+/// rejected until the owner removes something.
+pub const QUEUE_CAPACITY: usize = 50;
+
+/// Append `item` to the queue. Returns `false` (and leaves the queue
+/// untouched) when the item is already queued or the queue is full.
+pub fn insert_item(queue: &mut Vec<TheaterQueueItem>, item: TheaterQueueItem) -> bool {
+  if queue.len() >= QUEUE_CAPACITY || queue.iter().any(|i| i.item_id == item.item_id) {
+    return false;
+  }
+  queue.push(item);
+  true
+}
+
+/// Remove an item by id, returning it when it was queued.
+pub fn remove_item(
+  queue: &mut Vec<TheaterQueueItem>,
+  item_id: &MessageId,
+) -> Option<TheaterQueueItem> {
+  let index = queue.iter().position(|i| &i.item_id == item_id)?;
+  Some(queue.remove(index))
+}
+
+/// Reorder the queue to follow `order`.
+///
+/// Unknown ids in `order` are ignored and queued items missing from it
+/// keep their relative order at the end, so a reorder that races with
+/// an addition never drops the new item. Returns `true` when the order
+/// actually changed.
+pub fn apply_order(queue: &mut Vec<TheaterQueueItem>, order: &[MessageId]) -> bool {
+  let before: Vec<MessageId> = queue.iter().map(|i| i.item_id).collect();
+  let mut remaining = std::mem::take(queue);
+  for id in order {
+    if let Some(index) = remaining.iter().position(|i| &i.item_id == id) {
+      queue.push(remaining.remove(index));
+    }
+  }
+  queue.append(&mut remaining);
+  queue.iter().map(|i| i.item_id).ne(before)
+}
+
+/// Compute the order produced by moving `item_id` one slot up
+/// (`up = true`) or down. Returns `None` when the item is unknown or
+/// already at that edge of the queue.
+#[must_use]
+pub fn moved_order(
+  queue: &[TheaterQueueItem],
+  item_id: &MessageId,
+  up: bool,
+) -> Option<Vec<MessageId>> {
+  let index = queue.iter().position(|i| &i.item_id == item_id)?;
+  let target = if up {
+    index.checked_sub(1)?
+  } else {
+    let next = index + 1;
+    if next >= queue.len() {
+      return None;
+    }
+    next
+  };
+  let mut order: Vec<MessageId> = queue.iter().map(|i| i.item_id).collect();
+  order.swap(index, target);
+  Some(order)
+}
+
+/// Id of the item following `current`, or the first item when nothing
+/// is playing. Returns `None` at the end of the queue or when
+/// `current` is no longer queued.
+#[must_use]
+pub fn next_item_id(queue: &[TheaterQueueItem], current: Option<&MessageId>) -> Option<MessageId> {
+  let Some(current) = current else {
+    return queue.first().map(|i| i.item_id);
+  };
+  let index = queue.iter().position(|i| &i.item_id == current)?;
+  queue.get(index + 1).map(|i| i.item_id)
+}
+
+/// Whether `me` may remove `item`. The owner may remove anything that
+/// is not currently playing (skip it instead); everyone else may only
+/// withdraw their own proposals.
+#[must_use]
+pub fn can_remove(
+  role: TheaterRole,
+  me: Option<&UserId>,
+  item: &TheaterQueueItem,
+  now_playing: Option<&MessageId>,
+) -> bool {
+  if now_playing == Some(&item.item_id) {
+    return false;
+  }
+  role == TheaterRole::Owner || me == Some(&item.proposed_by)
+}
+
+/// Validate a URL typed into the queue form. Returns the trimmed URL
+/// when it uses `http(s)`.
+#[must_use]
+pub fn validate_queue_url(raw: &str) -> Option<String> {
+  let trimmed = raw.trim();
+  let rest = trimmed
+    .strip_prefix("https://")
+    .or_else(|| trimmed.strip_prefix("http://"))?;
+  if rest.is_empty() {
+    return None;
+  }
+  Some(trimmed.to_string())
+}
+
+/// Default display title for a queued URL — its host, mirroring the
+/// label the video source picker shows.
+#[must_use]
+pub fn url_title(url: &str) -> String {
+  url
+    .strip_prefix("https://")
+    .or_else(|| url.strip_prefix("http://"))
+    .unwrap_or(url)
+    .split('/')
+    .next()
+    .unwrap_or(url)
+    .to_string()
+}
+
+/// Build the subtitle frame to broadcast when `item` starts playing:
+/// its attached track, or a clear so the previous item's cues do not
+/// linger.
+#[must_use]
+pub fn subtitle_frame_for(room_id: RoomId, item: &TheaterQueueItem) -> DataChannelMessage {
+  match &item.subtitle {
+    Some(attachment) => DataChannelMessage::SubtitleData(SubtitleData {
+      room_id,
+      entries: attachment.entries.clone(),
+    }),
+    None => DataChannelMessage::SubtitleClear(SubtitleClear { room_id }),
+  }
+}
+
+/// Install the subtitle attachment of `item` on the local state, or
+/// clear the current track when the item has none.
+pub fn install_item_subtitle(state: &TheaterState, item: &TheaterQueueItem) {
+  match &item.subtitle {
+    Some(attachment) => apply_subtitle_track(
+      state,
+      SubtitleTrack {
+        filename: attachment.filename.clone(),
+        entries: attachment.entries.clone(),
+        visible: true,
+        offset_ms: 0,
+      },
+    ),
+    None => {
+      state.subtitle.set(None);
+      clear_active_subtitle(state);
+    }
+  }
+}
+
+fn is_active_room(state: &TheaterState, room_id: &RoomId) -> bool {
+  state
+    .room_id
+    .with_untracked(|r| r.as_ref().is_some_and(|id| id == room_id))
+}
+
+fn is_owner(state: &TheaterState) -> bool {
+  state.my_role.get_untracked() == TheaterRole::Owner
+}
+
+/// Apply an inbound `TheaterQueueAdd`.
+///
+/// On the owner this is a proposal: it is accepted unless it points at
+/// a local file (which only the owner can play) and then relayed to
+/// every viewer. On viewers it is the owner's relay and is mirrored.
+pub fn apply_queue_add(state: &TheaterState, msg: TheaterQueueAdd) -> bool {
+  if !is_active_room(state, &msg.room_id) {
+    return false;
+  }
+  let owner = is_owner(state);
+  if owner && matches!(msg.item.source, TheaterQueueSource::LocalFile(_)) {
+    return false;
+  }
+  let inserted = state
+    .queue
+    .try_update(|q| insert_item(q, msg.item.clone()))
+    .unwrap_or(false);
+  if inserted && owner {
+    state.enqueue_queue_broadcast(DataChannelMessage::TheaterQueueAdd(msg));
+  }
+  inserted
+}
+
+/// Apply an inbound `TheaterQueueRemove`. The owner only honours
+/// proposers withdrawing their own item; viewers mirror the relay.
+pub fn apply_queue_remove(state: &TheaterState, msg: TheaterQueueRemove) -> bool {
+  if !is_active_room(state, &msg.room_id) {
+    return false;
+  }
+  let owner = is_owner(state);
+  if owner {
+    let now_playing = state.now_playing.get_untracked();
+    let allowed = state.queue.with_untracked(|q| {
+      q.iter()
+        .find(|i| i.item_id == msg.item_id)
+        .is_some_and(|item| {
+          can_remove(
+            TheaterRole::Viewer,
+            Some(&msg.requested_by),
+            item,
+            now_playing.as_ref(),
+          )
+        })
+    });
+    if !allowed {
+      return false;
+    }
+  }
+  let removed = state
+    .queue
+    .try_update(|q| remove_item(q, &msg.item_id).is_some())
+    .unwrap_or(false);
+  if removed && owner {
+    state.enqueue_queue_broadcast(DataChannelMessage::TheaterQueueRemove(msg));
+  }
+  removed
+}
+
+/// Apply an inbound `TheaterQueueReorder`. Reordering is owner-only,
+/// so the owner ignores frames from viewers.
+pub fn apply_queue_reorder(state: &TheaterState, msg: &TheaterQueueReorder) -> bool {
+  if !is_active_room(state, &msg.room_id) || is_owner(state) {
+    return false;
+  }
+  state
+    .queue
+    .try_update(|q| apply_order(q, &msg.item_ids))
+    .unwrap_or(false)
+}
+
+/// Apply an inbound `TheaterNowPlaying`. Owner-only, so the owner
+/// ignores frames from viewers.
+pub fn apply_now_playing(state: &TheaterState, msg: &TheaterNowPlaying) -> bool {
+  if !is_active_room(state, &msg.room_id) || is_owner(state) {
+    return false;
+  }
+  state.now_playing.set(msg.item_id);
+  true
+}
+
+/// Owner: append a local item (typed URL, local file or an own
+/// proposal) and relay it to the viewers.
+pub fn owner_add_item(state: &TheaterState, item: TheaterQueueItem) -> bool {
+  let Some(room_id) = state.room_id.get_untracked() else {
+    return false;
+  };
+  if !is_owner(state) {
+    return false;
+  }
+  let inserted = state
+    .queue
+    .try_update(|q| insert_item(q, item.clone()))
+    .unwrap_or(false);
+  if inserted {
+    state.enqueue_queue_broadcast(DataChannelMessage::TheaterQueueAdd(TheaterQueueAdd {
+      room_id,
+      item,
+    }));
+  }
+  inserted
+}
+
+/// Owner: remove an item and relay the removal.
+pub fn owner_remove_item(state: &TheaterState, item_id: MessageId, me: UserId) -> bool {
+  let Some(room_id) = state.room_id.get_untracked() else {
+    return false;
+  };
+  if !is_owner(state) || state.now_playing.get_untracked() == Some(item_id) {
+    return false;
+  }
+  let removed = state
+    .queue
+    .try_update(|q| remove_item(q, &item_id).is_some())
+    .unwrap_or(false);
+  if removed {
+    state.queue_local_urls.update(|urls| {
+      urls.remove(&item_id);
+    });
+    state.enqueue_queue_broadcast(DataChannelMessage::TheaterQueueRemove(TheaterQueueRemove {
+      room_id,
+      item_id,
+      requested_by: me,
+    }));
+  }
+  removed
+}
+
+/// Owner: move an item one slot up or down and relay the new order.
+pub fn owner_move_item(state: &TheaterState, item_id: &MessageId, up: bool) -> bool {
+  let Some(room_id) = state.room_id.get_untracked() else {
+    return false;
+  };
+  if !is_owner(state) {
+    return false;
+  }
+  let Some(order) = state.queue.with_untracked(|q| moved_order(q, item_id, up)) else {
+    return false;
+  };
+  state.queue.update(|q| {
+    apply_order(q, &order);
+  });
+  state.enqueue_queue_broadcast(DataChannelMessage::TheaterQueueReorder(
+    TheaterQueueReorder {
+      room_id,
+      item_ids: order,
+    },
+  ));
+  true
+}
+
+/// Owner: mark `item_id` (or nothing) as playing and relay the
+/// announcement. The video player reacts to
+/// [`TheaterState::now_playing`] and loads the item's source.
+pub fn owner_set_now_playing(
+  state: &TheaterState,
+  item_id: Option<MessageId>,
+  timestamp_nanos: u64,
+) -> bool {
+  let Some(room_id) = state.room_id.get_untracked() else {
+    return false;
+  };
+  if !is_owner(state) {
+    return false;
+  }
+  if let Some(id) = item_id
+    && !state
+      .queue
+      .with_untracked(|q| q.iter().any(|i| i.item_id == id))
+  {
+    return false;
+  }
+  state.now_playing.set(item_id);
+  state.enqueue_queue_broadcast(DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+    room_id,
+    item_id,
+    timestamp_nanos,
+  }));
+  true
+}
+
+/// Owner: advance to the item after the one playing. Returns the new
+/// item id, or `None` when the queue has run out (in which case the
+/// now-playing marker is cleared).
+pub fn owner_advance(state: &TheaterState, timestamp_nanos: u64) -> Option<MessageId> {
+  let current = state.now_playing.get_untracked();
+  let next = state
+    .queue
+    .with_untracked(|q| next_item_id(q, current.as_ref()));
+  if next.is_some() || current.is_some() {
+    owner_set_now_playing(state, next, timestamp_nanos);
+  }
+  next
+}
+
+/// Frames that bring a late-joining viewer up to date: one
+/// `TheaterQueueAdd` per item (in order) followed by the current
+/// `TheaterNowPlaying`.
+#[must_use]
+pub fn snapshot_frames(state: &TheaterState, timestamp_nanos: u64) -> Vec<DataChannelMessage> {
+  let Some(room_id) = state.room_id.get_untracked() else {
+    return Vec::new();
+  };
+  let mut frames: Vec<DataChannelMessage> = state.queue.with_untracked(|q| {
+    q.iter()
+      .map(|item| {
+        DataChannelMessage::TheaterQueueAdd(TheaterQueueAdd {
+          room_id: room_id.clone(),
+          item: item.clone(),
+        })
+      })
+      .collect()
+  });
+  if frames.is_empty() {
+    return frames;
+  }
+  frames.push(DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+    room_id,
+    item_id: state.now_playing.get_untracked(),
+    timestamp_nanos,
+  }));
+  frames
+}
+
+#[cfg(test)]
+mod tests;
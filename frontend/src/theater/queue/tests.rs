@@ -0,0 +1,163 @@
+//! Unit tests for the pure watch-queue helpers. The signal-touching
+//! wrappers need a Leptos runtime and are covered by the
+//! `dc_router` wasm-bindgen suite.
+
+use message::datachannel::{TheaterQueueItem, TheaterQueueSource, TheaterQueueSubtitle};
+use message::types::{RoomId, SubtitleEntry};
+use message::{MessageId, UserId};
+use uuid::Uuid;
+
+use super::*;
+
+fn item(seed: u128, proposer: &UserId) -> TheaterQueueItem {
+  TheaterQueueItem {
+    item_id: MessageId::from_uuid(Uuid::from_u128(seed)),
+    source: TheaterQueueSource::Url(format!("https://example.com/{seed}.mp4")),
+    title: format!("item {seed}"),
+    proposed_by: proposer.clone(),
+    subtitle: None,
+  }
+}
+
+fn ids(queue: &[TheaterQueueItem]) -> Vec<MessageId> {
+  queue.iter().map(|i| i.item_id).collect()
+}
+
+#[test]
+fn insert_rejects_duplicates_and_respects_capacity() {
+  let me = UserId::new();
+  let mut queue = Vec::new();
+  assert!(insert_item(&mut queue, item(1, &me)));
+  assert!(!insert_item(&mut queue, item(1, &me)));
+  assert_eq!(queue.len(), 1);
+
+  for seed in 2..=QUEUE_CAPACITY as u128 {
+    assert!(insert_item(&mut queue, item(seed, &me)));
+  }
+  assert!(!insert_item(&mut queue, item(999, &me)));
+  assert_eq!(queue.len(), QUEUE_CAPACITY);
+}
+
+#[test]
+fn remove_returns_the_removed_item() {
+  let me = UserId::new();
+  let mut queue = vec![item(1, &me), item(2, &me)];
+  let first = queue[0].item_id;
+  let removed = remove_item(&mut queue, &first).expect("queued");
+  assert_eq!(removed.title, "item 1");
+  assert_eq!(queue.len(), 1);
+  assert!(remove_item(&mut queue, &MessageId::nil()).is_none());
+}
+
+#[test]
+fn apply_order_keeps_unlisted_items_at_the_end() {
+  let me = UserId::new();
+  let mut queue = vec![item(1, &me), item(2, &me), item(3, &me)];
+  let (a, b, c) = (queue[0].item_id, queue[1].item_id, queue[2].item_id);
+
+  // `b` was added after the owner computed this order.
+  assert!(apply_order(&mut queue, &[c, MessageId::nil(), a]));
+  assert_eq!(ids(&queue), vec![c, a, b]);
+
+  // Re-applying the same order is a no-op.
+  assert!(!apply_order(&mut queue, &[c, a, b]));
+}
+
+#[test]
+fn moved_order_swaps_neighbours_and_stops_at_edges() {
+  let me = UserId::new();
+  let queue = vec![item(1, &me), item(2, &me), item(3, &me)];
+  let (a, b, c) = (queue[0].item_id, queue[1].item_id, queue[2].item_id);
+
+  assert_eq!(moved_order(&queue, &b, true), Some(vec![b, a, c]));
+  assert_eq!(moved_order(&queue, &b, false), Some(vec![a, c, b]));
+  assert_eq!(moved_order(&queue, &a, true), None);
+  assert_eq!(moved_order(&queue, &c, false), None);
+  assert_eq!(moved_order(&queue, &MessageId::nil(), true), None);
+}
+
+#[test]
+fn next_item_walks_the_queue() {
+  let me = UserId::new();
+  let queue = vec![item(1, &me), item(2, &me)];
+  let (a, b) = (queue[0].item_id, queue[1].item_id);
+
+  assert_eq!(next_item_id(&queue, None), Some(a));
+  assert_eq!(next_item_id(&queue, Some(&a)), Some(b));
+  assert_eq!(next_item_id(&queue, Some(&b)), None);
+  assert_eq!(next_item_id(&[], None), None);
+}
+
+#[test]
+fn removal_permissions() {
+  let owner = UserId::new();
+  let proposer = UserId::new();
+  let other = UserId::new();
+  let proposal = item(1, &proposer);
+
+  assert!(can_remove(
+    TheaterRole::Owner,
+    Some(&owner),
+    &proposal,
+    None
+  ));
+  assert!(can_remove(
+    TheaterRole::Viewer,
+    Some(&proposer),
+    &proposal,
+    None
+  ));
+  assert!(!can_remove(
+    TheaterRole::Viewer,
+    Some(&other),
+    &proposal,
+    None
+  ));
+  assert!(!can_remove(
+    TheaterRole::Admin,
+    Some(&other),
+    &proposal,
+    None
+  ));
+  // The playing item is skipped, never removed.
+  assert!(!can_remove(
+    TheaterRole::Owner,
+    Some(&owner),
+    &proposal,
+    Some(&proposal.item_id)
+  ));
+}
+
+#[test]
+fn queue_url_validation() {
+  assert_eq!(
+    validate_queue_url("  https://example.com/a.mp4 ").as_deref(),
+    Some("https://example.com/a.mp4")
+  );
+  assert!(validate_queue_url("ftp://example.com/a.mp4").is_none());
+  assert!(validate_queue_url("https://").is_none());
+  assert_eq!(
+    url_title("https://cdn.example.com/path/a.mp4"),
+    "cdn.example.com"
+  );
+}
+
+#[test]
+fn subtitle_frame_follows_the_attachment() {
+  let me = UserId::new();
+  let room = RoomId::from_uuid(Uuid::from_u128(7));
+  let mut with_subs = item(1, &me);
+  with_subs.subtitle = Some(TheaterQueueSubtitle {
+    filename: "a.srt".into(),
+    entries: vec![SubtitleEntry::new(0, 1_000, "Hi".into())],
+  });
+
+  assert!(matches!(
+    subtitle_frame_for(room.clone(), &with_subs),
+    DataChannelMessage::SubtitleData(data) if data.entries.len() == 1
+  ));
+  assert!(matches!(
+    subtitle_frame_for(room, &item(2, &me)),
+    DataChannelMessage::SubtitleClear(_)
+  ));
+}
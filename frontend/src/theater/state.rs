@@ -9,12 +9,13 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
 use leptos::prelude::*;
-use message::UserId;
-use message::datachannel::{Danmaku, TheaterChatText};
+use message::datachannel::{Danmaku, DataChannelMessage, TheaterChatText, TheaterQueueItem};
 use message::types::{RoomId, SubtitleEntry};
+use message::{MessageId, UserId};
 use web_sys::MediaStream;
 
 use super::chat_model::{TheaterChatMessage, append_message};
+use super::clock_sync::ClockEstimator;
 use super::danmaku::DanmakuBatcher;
 
 /// Thread-safe shared batcher handle.
@@ -97,6 +98,9 @@ pub struct SubtitleTrack {
   pub entries: Vec<SubtitleEntry>,
   /// Whether the subtitle overlay is currently visible.
   pub visible: bool,
+  /// Global timing offset set by the owner: cues are shown at
+  /// `start_ms + offset_ms`.
+  pub offset_ms: i32,
 }
 
 /// Owner-side video quality tier tracked by the resource monitor.
@@ -195,6 +199,13 @@ pub struct TheaterState {
   pub remote_stream: RwSignal<Option<MediaStream>>,
   /// Current playback snapshot (updated by the video element).
   pub playback: RwSignal<PlaybackSnapshot>,
+  /// Viewer-only: owner-clock `timestamp_nanos` of the progress frame
+  /// that produced [`Self::playback`]. Used with [`Self::clock`] to
+  /// extrapolate the owner's position between frames.
+  pub playback_origin_nanos: RwSignal<u64>,
+  /// Viewer-only: clock-offset estimate to the owner, fed by the
+  /// ping/pong exchange in [`super::clock_sync`].
+  pub clock: RwSignal<ClockEstimator>,
   /// Owner quality tier — only meaningful when I am the owner.
   pub quality_tier: RwSignal<QualityTier>,
   /// Whether the owner is in "high load" mode (shown as a banner).
@@ -209,6 +220,9 @@ pub struct TheaterState {
   /// Subtitle cue currently on screen (cached so the render layer does
   /// not perform binary search on every frame).
   pub active_subtitle_text: RwSignal<Option<String>>,
+  /// Styled (ASS / SSA) cues currently on screen, in start order.
+  /// Empty for plain SRT / WebVTT tracks.
+  pub active_subtitle_cues: RwSignal<Vec<SubtitleEntry>>,
   /// Danmaku / subtitle overlay settings (persisted).
   pub overlay_settings: RwSignal<TheaterOverlaySettings>,
   /// Danmaku relay batcher (owner-only — viewers push directly).
@@ -245,6 +259,21 @@ pub struct TheaterState {
   /// remaining viewers. The theater page drains this queue inside
   /// an effect so this module stays free of `web_sys` side-effects.
   pub pending_chat_relay: RwSignal<VecDeque<TheaterChatText>>,
+  /// Watch queue, in play order. Viewers mirror the owner's copy
+  /// (see [`super::queue`]).
+  pub queue: RwSignal<Vec<TheaterQueueItem>>,
+  /// Queue item currently playing (`None` when the owner is playing a
+  /// source picked outside the queue, or nothing at all).
+  pub now_playing: RwSignal<Option<MessageId>>,
+  /// Owner-only: start the next queued item when playback ends.
+  pub queue_auto_advance: RwSignal<bool>,
+  /// Owner-only object URLs backing queued local files. Never leaves
+  /// the owner — the wire item only carries the filename.
+  pub queue_local_urls: RwSignal<HashMap<MessageId, String>>,
+  /// Queue frames the owner still needs to relay to the viewers.
+  /// Drained by the theater page's relay tick, like
+  /// [`Self::pending_chat_relay`].
+  pub pending_queue_broadcast: RwSignal<VecDeque<DataChannelMessage>>,
 }
 
 impl TheaterState {
@@ -262,12 +291,15 @@ impl TheaterState {
       local_stream: RwSignal::new(None),
       remote_stream: RwSignal::new(None),
       playback: RwSignal::new(PlaybackSnapshot::default()),
+      playback_origin_nanos: RwSignal::new(0),
+      clock: RwSignal::new(ClockEstimator::new()),
       quality_tier: RwSignal::new(QualityTier::HighDefinition),
       owner_high_load: RwSignal::new(false),
       owner_reconnecting: RwSignal::new(false),
       owner_grace_seconds: RwSignal::new(0),
       subtitle: RwSignal::new(None),
       active_subtitle_text: RwSignal::new(None),
+      active_subtitle_cues: RwSignal::new(Vec::new()),
       overlay_settings,
       danmaku_batcher: RwSignal::new(Arc::new(Mutex::new(DanmakuBatcher::new()))),
       self_muted: RwSignal::new(false),
@@ -280,6 +312,11 @@ impl TheaterState {
       chat_unread: RwSignal::new(0),
       next_chat_msg_id: RwSignal::new(1),
       pending_chat_relay: RwSignal::new(VecDeque::new()),
+      queue: RwSignal::new(Vec::new()),
+      now_playing: RwSignal::new(None),
+      queue_auto_advance: RwSignal::new(true),
+      queue_local_urls: RwSignal::new(HashMap::new()),
+      pending_queue_broadcast: RwSignal::new(VecDeque::new()),
     }
   }
 
@@ -313,12 +350,15 @@ impl TheaterState {
     self.local_stream.set(None);
     self.remote_stream.set(None);
     self.playback.set(PlaybackSnapshot::default());
+    self.playback_origin_nanos.set(0);
+    self.clock.set(ClockEstimator::new());
     self.quality_tier.set(QualityTier::HighDefinition);
     self.owner_high_load.set(false);
     self.owner_reconnecting.set(false);
     self.owner_grace_seconds.set(0);
     self.subtitle.set(None);
     self.active_subtitle_text.set(None);
+    self.active_subtitle_cues.set(Vec::new());
     self.self_muted.set(false);
     self.all_muted.set(false);
     self.muted_viewers.set(HashMap::new());
@@ -329,6 +369,11 @@ impl TheaterState {
     self.chat_unread.set(0);
     self.next_chat_msg_id.set(1);
     self.pending_chat_relay.set(VecDeque::new());
+    self.queue.set(Vec::new());
+    self.now_playing.set(None);
+    self.queue_auto_advance.set(true);
+    self.queue_local_urls.set(HashMap::new());
+    self.pending_queue_broadcast.set(VecDeque::new());
     let batcher = self.danmaku_batcher.get_untracked();
     if let Ok(mut guard) = batcher.lock() {
       guard.clear();
@@ -406,6 +451,27 @@ impl TheaterState {
       .unwrap_or_default()
   }
 
+  /// Enqueue a queue frame for owner-side relay. Bounded at 512
+  /// entries like the chat relay queue.
+  pub fn enqueue_queue_broadcast(&self, frame: DataChannelMessage) {
+    self.pending_queue_broadcast.update(|queue| {
+      const MAX_PENDING: usize = 512;
+      if queue.len() >= MAX_PENDING {
+        queue.pop_front();
+      }
+      queue.push_back(frame);
+    });
+  }
+
+  /// Drain the queue relay, returning the frames the caller should
+  /// broadcast to the viewers in order.
+  pub fn drain_queue_broadcast(&self) -> Vec<DataChannelMessage> {
+    self
+      .pending_queue_broadcast
+      .try_update(|queue| queue.drain(..).collect::<Vec<_>>())
+      .unwrap_or_default()
+  }
+
   /// Borrow the shared danmaku batcher for the duration of `f`.
   ///
   /// Silently returns the default value of `R` when the mutex is
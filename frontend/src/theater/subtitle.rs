@@ -1,12 +1,14 @@
 //! Subtitle file parsers for Theater mode (Req 12.4a).
 //!
-//! Supports two formats:
+//! Supports three formats:
 //!
 //! * **SRT** (SubRip) — `HH:MM:SS,mmm --> HH:MM:SS,mmm` timestamps,
 //!   blank-line separated cues, optional numeric index line.
 //! * **WebVTT** (`.vtt`) — `HH:MM:SS.mmm --> HH:MM:SS.mmm` timestamps,
 //!   mandatory `WEBVTT` header, optional `NOTE` / `STYLE` / region
 //!   blocks which are ignored by this implementation.
+//! * **ASS / SSA** (`.ass`, `.ssa`) — styled scripts; see the `ass` submodule for
+//!   the subset of styles and override tags that is rendered.
 //!
 //! Parser design goals:
 //!
@@ -21,6 +23,10 @@ use core::fmt;
 
 use message::types::SubtitleEntry;
 
+mod ass;
+
+pub use ass::parse_ass;
+
 /// Error returned by [`parse_srt`] / [`parse_vtt`] when the whole file
 /// cannot be parsed at all (wrong header, empty input, …).
 ///
@@ -32,7 +38,9 @@ pub enum SubtitleParseError {
   Empty,
   /// WebVTT header (`WEBVTT`) was missing or misspelled.
   MissingWebVttHeader,
-  /// Neither SRT nor WebVTT markers were detected.
+  /// ASS / SSA script has no `[Events]` section.
+  MissingAssEvents,
+  /// Neither SRT, WebVTT nor ASS markers were detected.
   UnknownFormat,
 }
 
@@ -41,7 +49,11 @@ impl fmt::Display for SubtitleParseError {
     match self {
       Self::Empty => write!(f, "Subtitle file is empty"),
       Self::MissingWebVttHeader => write!(f, "WebVTT file is missing the 'WEBVTT' header"),
-      Self::UnknownFormat => write!(f, "Subtitle format not recognised (expected SRT or WebVTT)"),
+      Self::MissingAssEvents => write!(f, "ASS/SSA file has no [Events] section"),
+      Self::UnknownFormat => write!(
+        f,
+        "Subtitle format not recognised (expected SRT, WebVTT or ASS/SSA)"
+      ),
     }
   }
 }
@@ -50,7 +62,8 @@ impl std::error::Error for SubtitleParseError {}
 
 /// Detect subtitle format by filename extension and dispatch to the
 /// matching parser. When the extension is absent or unknown the input
-/// is sniffed for a `WEBVTT` header first, falling back to SRT.
+/// is sniffed for a `WEBVTT` header or an ASS `[Script Info]` section
+/// first, falling back to SRT.
 pub fn parse_subtitle_file(
   filename: &str,
   content: &str,
@@ -62,14 +75,17 @@ pub fn parse_subtitle_file(
   if lower.ends_with(".srt") {
     return parse_srt(content);
   }
-  // Fall back to sniffing: WEBVTT header wins, otherwise try SRT.
+  if lower.ends_with(".ass") || lower.ends_with(".ssa") {
+    return parse_ass(content);
+  }
+  // Fall back to sniffing: WEBVTT / [Script Info] headers win,
+  // otherwise try SRT.
   let trimmed = content.trim_start_matches('\u{feff}').trim_start();
-  if trimmed
-    .lines()
-    .next()
-    .is_some_and(|line| line.trim_start().starts_with("WEBVTT"))
-  {
+  let first_line = trimmed.lines().next().map_or("", str::trim);
+  if first_line.starts_with("WEBVTT") {
     parse_vtt(content)
+  } else if first_line.eq_ignore_ascii_case("[Script Info]") {
+    parse_ass(content)
   } else if trimmed.is_empty() {
     Err(SubtitleParseError::Empty)
   } else {
@@ -127,6 +143,7 @@ pub fn parse_srt(content: &str) -> Result<Vec<SubtitleEntry>, SubtitleParseError
       start_ms: start,
       end_ms: end,
       text,
+      style: None,
     });
   }
 
@@ -198,6 +215,7 @@ pub fn parse_vtt(content: &str) -> Result<Vec<SubtitleEntry>, SubtitleParseError
       start_ms: start,
       end_ms: end,
       text,
+      style: None,
     });
   }
 
@@ -0,0 +1,642 @@
+//! ASS / SSA (Advanced SubStation Alpha) subtitle parser.
+//!
+//! Reads the three sections that matter for playback:
+//!
+//! * `[Script Info]` — `PlayResX` / `PlayResY`, the coordinate space
+//!   every size and position in the script is expressed in.
+//! * `[V4+ Styles]` / `[V4 Styles]` — named base styles (font, size,
+//!   colours, bold / italic / underline / strike-out, alignment,
+//!   vertical margin). Column order follows the section's `Format:`
+//!   line.
+//! * `[Events]` — `Dialogue:` lines. `Comment:` and the other event
+//!   kinds are skipped.
+//!
+//! Inline override blocks (`{\b1\c&H00FFFF&}`) are folded into styled
+//! [`SubtitleSpan`]s. Supported tags: `\b` `\i` `\u` `\s`, `\c` / `\1c`
+//! and `\3c` colours, `\fn` / `\fs` (cue-wide), `\pos` (and the start
+//! point of `\move`), `\an` / legacy `\a` alignment, `\r` reset,
+//! `\k` / `\K` / `\kf` / `\ko` karaoke and `\p` drawings (dropped).
+//! Everything else — transforms, clipping, blur, rotation, fades … —
+//! is ignored so the text still shows, just without the effect.
+//!
+//! Sizes and positions are converted to per-mille of the script
+//! resolution so viewers can scale them to their own video element.
+
+use std::collections::HashMap;
+
+use message::types::{
+  SubtitleAnchor, SubtitleCueStyle, SubtitleEntry, SubtitleKaraoke, SubtitleSpan,
+};
+
+use super::SubtitleParseError;
+
+/// Script resolution assumed when `[Script Info]` declares none (the
+/// `VSFilter` / libass default).
+const DEFAULT_PLAY_RES: (f64, f64) = (384.0, 288.0);
+
+/// `Format:` used by `[V4+ Styles]` when the line is missing.
+const DEFAULT_STYLE_FORMAT: &str = "Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, \
+  OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+  BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding";
+
+/// `Format:` used by `[Events]` when the line is missing.
+const DEFAULT_EVENT_FORMAT: &str =
+  "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+/// Parsed `Style:` line.
+#[derive(Debug, Clone, PartialEq)]
+struct AssStyle {
+  font_name: Option<String>,
+  font_size: Option<f64>,
+  primary: Option<u32>,
+  outline: Option<u32>,
+  bold: bool,
+  italic: bool,
+  underline: bool,
+  strike_out: bool,
+  alignment: u8,
+  margin_v: f64,
+}
+
+impl Default for AssStyle {
+  fn default() -> Self {
+    Self {
+      font_name: None,
+      font_size: None,
+      primary: None,
+      outline: None,
+      bold: false,
+      italic: false,
+      underline: false,
+      strike_out: false,
+      alignment: 2,
+      margin_v: 20.0,
+    }
+  }
+}
+
+/// Inline formatting that applies span by span.
+#[derive(Debug, Clone, PartialEq)]
+struct SpanFormat {
+  bold: bool,
+  italic: bool,
+  underline: bool,
+  strike_out: bool,
+  color: Option<u32>,
+  outline_color: Option<u32>,
+  karaoke: Option<SubtitleKaraoke>,
+}
+
+impl SpanFormat {
+  fn from_style(style: &AssStyle) -> Self {
+    Self {
+      bold: style.bold,
+      italic: style.italic,
+      underline: style.underline,
+      strike_out: style.strike_out,
+      color: style.primary,
+      outline_color: style.outline,
+      karaoke: None,
+    }
+  }
+
+  fn span(&self, text: String) -> SubtitleSpan {
+    SubtitleSpan {
+      text,
+      bold: self.bold,
+      italic: self.italic,
+      underline: self.underline,
+      strike_out: self.strike_out,
+      color: self.color,
+      outline_color: self.outline_color,
+      karaoke: self.karaoke,
+    }
+  }
+}
+
+/// Overrides that apply to the whole cue, whichever span they occur in.
+#[derive(Debug, Default)]
+struct CueOverrides {
+  font_name: Option<String>,
+  font_size: Option<f64>,
+  alignment: Option<u8>,
+  position: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+  ScriptInfo,
+  Styles { legacy: bool },
+  Events,
+  Other,
+}
+
+/// Parse ASS / SSA subtitle content into a sorted entry list.
+///
+/// Malformed style or dialogue lines are skipped; lines that only
+/// contain drawings or whitespace produce no entry.
+///
+/// # Errors
+/// Returns [`SubtitleParseError::Empty`] for blank input and
+/// [`SubtitleParseError::MissingAssEvents`] when the file has no
+/// `[Events]` section.
+pub fn parse_ass(content: &str) -> Result<Vec<SubtitleEntry>, SubtitleParseError> {
+  let trimmed = content.trim_start_matches('\u{feff}');
+  if trimmed.trim().is_empty() {
+    return Err(SubtitleParseError::Empty);
+  }
+
+  let mut section = Section::Other;
+  let mut saw_events = false;
+  let mut play_res_x = None;
+  let mut play_res_y = None;
+  let mut styles: HashMap<String, AssStyle> = HashMap::new();
+  let mut style_format = split_format(DEFAULT_STYLE_FORMAT);
+  let mut event_format = split_format(DEFAULT_EVENT_FORMAT);
+  let mut dialogues = Vec::new();
+
+  for line in trimmed.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with(';') || line.starts_with("!:") {
+      continue;
+    }
+    if line.starts_with('[') && line.ends_with(']') {
+      section = match line.to_ascii_lowercase().as_str() {
+        "[script info]" => Section::ScriptInfo,
+        "[v4+ styles]" => Section::Styles { legacy: false },
+        "[v4 styles]" => Section::Styles { legacy: true },
+        "[events]" => {
+          saw_events = true;
+          Section::Events
+        }
+        _ => Section::Other,
+      };
+      continue;
+    }
+    let Some((key, value)) = line.split_once(':') else {
+      continue;
+    };
+    let (key, value) = (key.trim(), value.trim());
+    match section {
+      Section::ScriptInfo => {
+        if key.eq_ignore_ascii_case("PlayResX") {
+          play_res_x = value.parse::<f64>().ok().filter(|v| *v > 0.0);
+        } else if key.eq_ignore_ascii_case("PlayResY") {
+          play_res_y = value.parse::<f64>().ok().filter(|v| *v > 0.0);
+        }
+      }
+      Section::Styles { legacy } => {
+        if key.eq_ignore_ascii_case("Format") {
+          style_format = split_format(value);
+        } else if key.eq_ignore_ascii_case("Style")
+          && let Some((name, style)) = parse_style_line(&style_format, value, legacy)
+        {
+          styles.insert(name, style);
+        }
+      }
+      Section::Events => {
+        if key.eq_ignore_ascii_case("Format") {
+          event_format = split_format(value);
+        } else if key.eq_ignore_ascii_case("Dialogue") {
+          dialogues.push(value);
+        }
+      }
+      Section::Other => {}
+    }
+  }
+
+  if !saw_events {
+    return Err(SubtitleParseError::MissingAssEvents);
+  }
+
+  let play_res = resolve_play_res(play_res_x, play_res_y);
+  let mut entries: Vec<SubtitleEntry> = dialogues
+    .into_iter()
+    .filter_map(|line| parse_dialogue(&event_format, line, &styles, play_res))
+    .collect();
+  entries.sort_by_key(|e| e.start_ms);
+  Ok(entries)
+}
+
+/// Split a `Format:` value into lower-cased column names.
+fn split_format(value: &str) -> Vec<String> {
+  value
+    .split(',')
+    .map(|f| f.trim().to_ascii_lowercase())
+    .collect()
+}
+
+/// Fill in a missing `PlayResX` / `PlayResY` the way renderers do:
+/// assume 4:3 from whichever side is present.
+fn resolve_play_res(x: Option<f64>, y: Option<f64>) -> (f64, f64) {
+  match (x, y) {
+    (Some(x), Some(y)) => (x, y),
+    (Some(x), None) => (x, x * 3.0 / 4.0),
+    (None, Some(y)) => (y * 4.0 / 3.0, y),
+    (None, None) => DEFAULT_PLAY_RES,
+  }
+}
+
+/// Split a comma-separated line into exactly `columns` fields; the
+/// last one keeps any further commas (dialogue text does).
+fn split_fields(value: &str, columns: usize) -> Vec<&str> {
+  value.splitn(columns.max(1), ',').map(str::trim).collect()
+}
+
+fn field<'a>(format: &[String], fields: &[&'a str], name: &str) -> Option<&'a str> {
+  let idx = format.iter().position(|f| f == name)?;
+  fields.get(idx).copied()
+}
+
+fn parse_style_line(format: &[String], value: &str, legacy: bool) -> Option<(String, AssStyle)> {
+  let fields = split_fields(value, format.len());
+  let name = field(format, &fields, "name")?.to_string();
+  let flag = |column: &str| {
+    field(format, &fields, column)
+      .and_then(|v| v.parse::<i32>().ok())
+      .is_some_and(|v| v != 0)
+  };
+  let alignment = field(format, &fields, "alignment")
+    .and_then(|v| v.parse::<u8>().ok())
+    .map_or(2, |a| if legacy { legacy_alignment(a) } else { a });
+  let style = AssStyle {
+    font_name: field(format, &fields, "fontname")
+      .filter(|v| !v.is_empty())
+      .map(str::to_string),
+    font_size: field(format, &fields, "fontsize")
+      .and_then(|v| v.parse::<f64>().ok())
+      .filter(|v| *v > 0.0),
+    primary: field(format, &fields, "primarycolour").and_then(parse_color),
+    // SSA calls the outline colour "TertiaryColour".
+    outline: field(format, &fields, "outlinecolour")
+      .or_else(|| field(format, &fields, "tertiarycolour"))
+      .and_then(parse_color),
+    bold: flag("bold"),
+    italic: flag("italic"),
+    underline: flag("underline"),
+    strike_out: flag("strikeout"),
+    alignment: if (1..=9).contains(&alignment) {
+      alignment
+    } else {
+      2
+    },
+    margin_v: field(format, &fields, "marginv")
+      .and_then(|v| v.parse::<f64>().ok())
+      .unwrap_or(20.0),
+  };
+  Some((name, style))
+}
+
+fn parse_dialogue(
+  format: &[String],
+  value: &str,
+  styles: &HashMap<String, AssStyle>,
+  (res_x, res_y): (f64, f64),
+) -> Option<SubtitleEntry> {
+  let fields = split_fields(value, format.len());
+  let start_ms = parse_ass_time(field(format, &fields, "start")?)?;
+  let end_ms = parse_ass_time(field(format, &fields, "end")?)?;
+  if end_ms <= start_ms {
+    return None;
+  }
+  let text = field(format, &fields, "text")?;
+  let style_name = field(format, &fields, "style").unwrap_or("Default");
+  let base = styles
+    .get(style_name.trim_start_matches('*'))
+    .or_else(|| styles.get("Default"))
+    .cloned()
+    .unwrap_or_default();
+
+  let (spans, cue) = parse_text(text, &base, styles);
+  let plain: String = spans.iter().map(|s| s.text.as_str()).collect();
+  if plain.trim().is_empty() {
+    return None;
+  }
+
+  let margin_v = field(format, &fields, "marginv")
+    .and_then(|v| v.parse::<f64>().ok())
+    .filter(|v| *v > 0.0)
+    .unwrap_or(base.margin_v);
+  let style = SubtitleCueStyle {
+    font_name: cue.font_name.or(base.font_name),
+    font_size_permille: cue
+      .font_size
+      .or(base.font_size)
+      .map(|fs| permille(fs, res_y)),
+    alignment: cue.alignment.unwrap_or(base.alignment),
+    margin_v_permille: permille(margin_v, res_y),
+    position: cue.position.map(|(x, y)| SubtitleAnchor {
+      x_permille: permille(x, res_x),
+      y_permille: permille(y, res_y),
+    }),
+    spans,
+  };
+  Some(SubtitleEntry {
+    start_ms,
+    end_ms,
+    text: plain,
+    style: Some(style),
+  })
+}
+
+/// Scale `value` in script pixels to per-mille of `extent`, clamped
+/// to the visible area.
+fn permille(value: f64, extent: f64) -> u16 {
+  let scaled = (value / extent * 1_000.0).round().clamp(0.0, 1_000.0);
+  // Clamped to 0..=1000 above, so the cast cannot truncate.
+  scaled as u16
+}
+
+/// Parse `H:MM:SS.cc` into milliseconds. The fraction is usually
+/// centiseconds but any number of digits is accepted.
+fn parse_ass_time(raw: &str) -> Option<u32> {
+  let (hms, frac) = raw.trim().split_once('.').unwrap_or((raw.trim(), "0"));
+  let mut parts = hms.split(':');
+  let hours: u32 = parts.next()?.parse().ok()?;
+  let minutes: u32 = parts.next()?.parse().ok()?;
+  let seconds: u32 = parts.next()?.parse().ok()?;
+  if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+    return None;
+  }
+  let frac_ms = match frac.len() {
+    0 => 0,
+    1 => frac.parse::<u32>().ok()? * 100,
+    2 => frac.parse::<u32>().ok()? * 10,
+    _ => frac.get(..3)?.parse::<u32>().ok()?,
+  };
+  hours
+    .checked_mul(3_600_000)?
+    .checked_add(minutes * 60_000 + seconds * 1_000 + frac_ms)
+}
+
+/// Parse an ASS colour (`&HAABBGGRR&`, `&HBBGGRR`, or SSA decimal)
+/// into `0xRRGGBB`. Alpha is dropped.
+fn parse_color(raw: &str) -> Option<u32> {
+  let raw = raw.trim().trim_end_matches('&');
+  let bgr = if let Some(hex) = raw.strip_prefix("&H").or_else(|| raw.strip_prefix("&h")) {
+    u32::from_str_radix(hex, 16).ok()?
+  } else {
+    // SSA stores colours as signed decimals; keep the low 32 bits.
+    raw.parse::<i64>().ok()? as u32
+  };
+  Some(((bgr & 0xFF) << 16) | (bgr & 0xFF00) | ((bgr >> 16) & 0xFF))
+}
+
+/// Map SSA's legacy alignment (1–3 bottom, 5–7 top, 9–11 middle) onto
+/// the numpad layout used by `\an` and `[V4+ Styles]`.
+fn legacy_alignment(value: u8) -> u8 {
+  match value {
+    9..=11 => value - 5,
+    5..=7 => value + 2,
+    _ => value,
+  }
+}
+
+/// Leading numeric prefix of a tag argument (`"700"`, `"20.5"`).
+fn number_arg(arg: &str) -> Option<f64> {
+  let end = arg
+    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+    .unwrap_or(arg.len());
+  arg[..end].parse().ok()
+}
+
+/// Parse a `\b`-style toggle. An empty argument restores the base
+/// style; any non-zero value (including weights like `700`) enables.
+fn toggle_arg(arg: &str, base: bool) -> Option<bool> {
+  if arg.is_empty() {
+    return Some(base);
+  }
+  number_arg(arg).map(|n| n != 0.0)
+}
+
+/// Parse the `x,y` pair out of `pos(x,y)` / `move(x1,y1,…)`.
+fn point_arg(args: &str) -> Option<(f64, f64)> {
+  let inner = args.trim_end_matches(')');
+  let mut parts = inner.split(',').map(str::trim);
+  let x = parts.next()?.parse().ok()?;
+  let y = parts.next()?.parse().ok()?;
+  Some((x, y))
+}
+
+/// Split an override block into individual tags, keeping the
+/// backslashes inside parentheses (e.g. `\t(0,500,\fs30)`) with the
+/// tag that owns them.
+fn split_tags(block: &str) -> Vec<&str> {
+  let mut tags = Vec::new();
+  let mut depth = 0_u32;
+  let mut start = None;
+  for (idx, c) in block.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      '\\' if depth == 0 => {
+        if let Some(s) = start {
+          tags.push(block[s..idx].trim());
+        }
+        start = Some(idx + 1);
+      }
+      _ => {}
+    }
+  }
+  if let Some(s) = start {
+    tags.push(block[s..].trim());
+  }
+  tags
+}
+
+/// Mutable state while walking one dialogue line.
+struct TextWalker<'a> {
+  base: &'a AssStyle,
+  styles: &'a HashMap<String, AssStyle>,
+  format: SpanFormat,
+  cue: CueOverrides,
+  spans: Vec<SubtitleSpan>,
+  buffer: String,
+  karaoke_cursor_ms: u32,
+  drawing: bool,
+}
+
+impl TextWalker<'_> {
+  fn flush(&mut self) {
+    if self.buffer.is_empty() {
+      return;
+    }
+    let text = std::mem::take(&mut self.buffer);
+    let span = self.format.span(text);
+    // Merge with the previous run when nothing changed in between
+    // (e.g. an ignored tag split the text).
+    if let Some(last) = self.spans.last_mut()
+      && same_format(last, &span)
+    {
+      last.text.push_str(&span.text);
+      return;
+    }
+    self.spans.push(span);
+  }
+
+  fn apply_tag(&mut self, tag: &str) {
+    if let Some(args) = tag.strip_prefix("pos(") {
+      if self.cue.position.is_none() {
+        self.cue.position = point_arg(args);
+      }
+    } else if let Some(args) = tag.strip_prefix("move(") {
+      // Animation is not supported — pin the cue to its start point.
+      if self.cue.position.is_none() {
+        self.cue.position = point_arg(args);
+      }
+    } else if let Some(arg) = tag.strip_prefix("an") {
+      if self.cue.alignment.is_none() {
+        self.cue.alignment = arg.parse::<u8>().ok().filter(|a| (1..=9).contains(a));
+      }
+    } else if tag.starts_with("alpha") {
+      // Transparency is not rendered.
+    } else if let Some(arg) = tag.strip_prefix('a') {
+      if self.cue.alignment.is_none() {
+        self.cue.alignment = arg
+          .parse::<u8>()
+          .ok()
+          .map(legacy_alignment)
+          .filter(|a| (1..=9).contains(a));
+      }
+    } else if let Some(arg) = tag.strip_prefix("fn") {
+      if !arg.is_empty() {
+        self.cue.font_name = Some(arg.to_string());
+      }
+    } else if tag.starts_with("fsc") || tag.starts_with("fsp") {
+      // Scaling / spacing are not rendered.
+    } else if let Some(arg) = tag.strip_prefix("fs") {
+      self.cue.font_size = number_arg(arg).filter(|v| *v > 0.0);
+    } else if tag.starts_with("blur") || tag.starts_with("bord") || tag.starts_with("be") {
+      // Edge effects are not rendered.
+    } else if let Some(arg) = tag.strip_prefix('b') {
+      if let Some(on) = toggle_arg(arg, self.base.bold) {
+        self.format.bold = on;
+      }
+    } else if tag.starts_with("iclip") {
+      // Clipping is not rendered.
+    } else if let Some(arg) = tag.strip_prefix('i') {
+      if let Some(on) = toggle_arg(arg, self.base.italic) {
+        self.format.italic = on;
+      }
+    } else if let Some(arg) = tag.strip_prefix('u') {
+      if let Some(on) = toggle_arg(arg, self.base.underline) {
+        self.format.underline = on;
+      }
+    } else if tag.starts_with("shad") {
+      // Shadows are not rendered.
+    } else if let Some(arg) = tag.strip_prefix('s') {
+      if let Some(on) = toggle_arg(arg, self.base.strike_out) {
+        self.format.strike_out = on;
+      }
+    } else if tag.starts_with("clip") {
+      // Clipping is not rendered.
+    } else if let Some(arg) = tag.strip_prefix("1c").or_else(|| tag.strip_prefix('c')) {
+      self.format.color = if arg.is_empty() {
+        self.base.primary
+      } else {
+        parse_color(arg).or(self.format.color)
+      };
+    } else if let Some(arg) = tag.strip_prefix("3c") {
+      self.format.outline_color = if arg.is_empty() {
+        self.base.outline
+      } else {
+        parse_color(arg).or(self.format.outline_color)
+      };
+    } else if tag.starts_with("kt") {
+      // Karaoke time resets are not supported.
+    } else if let Some(arg) = tag
+      .strip_prefix("kf")
+      .or_else(|| tag.strip_prefix("ko"))
+      .or_else(|| tag.strip_prefix('K'))
+      .or_else(|| tag.strip_prefix('k'))
+    {
+      // Durations are in centiseconds.
+      let duration_ms = number_arg(arg).map_or(0, |cs| (cs.max(0.0) * 10.0).round() as u32);
+      self.format.karaoke = Some(SubtitleKaraoke {
+        offset_ms: self.karaoke_cursor_ms,
+        duration_ms,
+      });
+      self.karaoke_cursor_ms = self.karaoke_cursor_ms.saturating_add(duration_ms);
+    } else if let Some(name) = tag.strip_prefix('r') {
+      let style = self.styles.get(name).unwrap_or(self.base);
+      let karaoke = self.format.karaoke;
+      self.format = SpanFormat::from_style(style);
+      self.format.karaoke = karaoke;
+    } else if let Some(arg) = tag.strip_prefix('p')
+      && let Some(level) = number_arg(arg)
+    {
+      self.drawing = level > 0.0;
+    }
+    // Anything else (`\t`, `\fad`, `\frz`, `\org`, `\q`, …) is an
+    // effect we do not render; the text is still shown.
+  }
+}
+
+/// Whether two spans differ only in their text.
+fn same_format(a: &SubtitleSpan, b: &SubtitleSpan) -> bool {
+  a.bold == b.bold
+    && a.italic == b.italic
+    && a.underline == b.underline
+    && a.strike_out == b.strike_out
+    && a.color == b.color
+    && a.outline_color == b.outline_color
+    && a.karaoke == b.karaoke
+}
+
+/// Walk a dialogue's text, folding override blocks into styled spans
+/// and collecting the cue-wide overrides.
+fn parse_text(
+  text: &str,
+  base: &AssStyle,
+  styles: &HashMap<String, AssStyle>,
+) -> (Vec<SubtitleSpan>, CueOverrides) {
+  let mut walker = TextWalker {
+    base,
+    styles,
+    format: SpanFormat::from_style(base),
+    cue: CueOverrides::default(),
+    spans: Vec::new(),
+    buffer: String::new(),
+    karaoke_cursor_ms: 0,
+    drawing: false,
+  };
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '{' => {
+        let block: String = chars.by_ref().take_while(|&c| c != '}').collect();
+        // Blocks without a backslash are comments (common in fansubs).
+        if block.contains('\\') {
+          walker.flush();
+          for tag in split_tags(&block) {
+            walker.apply_tag(tag);
+          }
+        }
+      }
+      '\\' => match chars.peek() {
+        Some('N') => {
+          chars.next();
+          walker.buffer.push('\n');
+        }
+        Some('n') => {
+          // Soft break — only honoured in a wrapping mode we do not
+          // implement, so treat it as a space.
+          chars.next();
+          walker.buffer.push(' ');
+        }
+        Some('h') => {
+          chars.next();
+          walker.buffer.push('\u{a0}');
+        }
+        _ => walker.buffer.push('\\'),
+      },
+      _ if walker.drawing => {}
+      _ => walker.buffer.push(c),
+    }
+  }
+  walker.flush();
+  (walker.spans, walker.cue)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,229 @@
+//! Unit tests for the ASS / SSA parser.
+
+use super::*;
+
+const HEADER: &str = "[Script Info]\nScriptType: v4.00+\nPlayResX: 1920\nPlayResY: 1080\n\n\
+  [V4+ Styles]\n\
+  Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+  Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, \
+  Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+  Style: Default,Arial,54,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,\
+  0,2,10,10,54,1\n\
+  Style: Sign,Georgia,72,&H0000FFFF,&H000000FF,&H00402000,&H00000000,-1,-1,0,0,100,100,0,0,1,\
+  2,0,8,10,10,108,1\n\n\
+  [Events]\n\
+  Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n";
+
+fn script(events: &str) -> String {
+  format!("{HEADER}{events}")
+}
+
+fn style(entry: &SubtitleEntry) -> &SubtitleCueStyle {
+  entry.style.as_ref().expect("ASS cues carry a style")
+}
+
+#[test]
+fn parses_dialogue_with_base_style() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,Hello, world\n",
+  ))
+  .expect("valid ASS");
+  assert_eq!(entries.len(), 1);
+  let entry = &entries[0];
+  assert_eq!((entry.start_ms, entry.end_ms), (1_500, 3_000));
+  // Commas inside the text column survive.
+  assert_eq!(entry.text, "Hello, world");
+  let style = style(entry);
+  assert_eq!(style.font_name.as_deref(), Some("Arial"));
+  assert_eq!(style.font_size_permille, Some(50));
+  assert_eq!(style.alignment, 2);
+  assert_eq!(style.margin_v_permille, 50);
+  assert_eq!(style.position, None);
+  assert_eq!(style.spans.len(), 1);
+  assert_eq!(style.spans[0].color, Some(0xFF_FF_FF));
+  assert!(!style.spans[0].bold);
+}
+
+#[test]
+fn named_style_applies_colours_and_flags() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:00.00,0:00:01.00,Sign,,0,0,0,,Exit\n",
+  ))
+  .expect("valid ASS");
+  let style = style(&entries[0]);
+  assert_eq!(style.alignment, 8);
+  let span = &style.spans[0];
+  // &H0000FFFF is BGR yellow.
+  assert_eq!(span.color, Some(0xFF_FF_00));
+  assert_eq!(span.outline_color, Some(0x00_20_40));
+  assert!(span.bold && span.italic);
+}
+
+#[test]
+fn override_tags_split_spans() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,Plain {\\b1\\c&H0000FF&}bold red{\\r} back\n",
+  ))
+  .expect("valid ASS");
+  let spans = &style(&entries[0]).spans;
+  assert_eq!(spans.len(), 3);
+  assert_eq!(spans[0].text, "Plain ");
+  assert_eq!(spans[1].text, "bold red");
+  assert!(spans[1].bold);
+  assert_eq!(spans[1].color, Some(0xFF_00_00));
+  assert_eq!(spans[2].text, " back");
+  assert!(!spans[2].bold);
+  assert_eq!(spans[2].color, Some(0xFF_FF_FF));
+  assert_eq!(entries[0].text, "Plain bold red back");
+}
+
+#[test]
+fn cue_overrides_position_alignment_and_font() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,{\\an7\\pos(960,270)\\fnImpact\\fs108}Sign\n",
+  ))
+  .expect("valid ASS");
+  let style = style(&entries[0]);
+  assert_eq!(style.alignment, 7);
+  assert_eq!(
+    style.position,
+    Some(SubtitleAnchor {
+      x_permille: 500,
+      y_permille: 250,
+    })
+  );
+  assert_eq!(style.font_name.as_deref(), Some("Impact"));
+  assert_eq!(style.font_size_permille, Some(100));
+}
+
+#[test]
+fn move_pins_to_start_point() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,{\\move(192,108,1728,972)}Sliding\n",
+  ))
+  .expect("valid ASS");
+  assert_eq!(
+    style(&entries[0]).position,
+    Some(SubtitleAnchor {
+      x_permille: 100,
+      y_permille: 100,
+    })
+  );
+}
+
+#[test]
+fn karaoke_offsets_accumulate() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:10.00,0:00:12.00,Default,,0,0,0,,{\\k50}Ka{\\kf30}ra{\\K20}o\n",
+  ))
+  .expect("valid ASS");
+  let karaoke: Vec<_> = style(&entries[0])
+    .spans
+    .iter()
+    .map(|s| (s.text.as_str(), s.karaoke))
+    .collect();
+  assert_eq!(
+    karaoke,
+    vec![
+      (
+        "Ka",
+        Some(SubtitleKaraoke {
+          offset_ms: 0,
+          duration_ms: 500,
+        })
+      ),
+      (
+        "ra",
+        Some(SubtitleKaraoke {
+          offset_ms: 500,
+          duration_ms: 300,
+        })
+      ),
+      (
+        "o",
+        Some(SubtitleKaraoke {
+          offset_ms: 800,
+          duration_ms: 200,
+        })
+      ),
+    ]
+  );
+}
+
+#[test]
+fn escapes_comments_and_unknown_tags() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,{TL note}One\\Ntwo\\hthree{\\blur3\\t(0,500,\\fs80)\\frz10}!\n",
+  ))
+  .expect("valid ASS");
+  assert_eq!(entries[0].text, "One\ntwo\u{a0}three!");
+  // Ignored tags do not fragment the text.
+  assert_eq!(style(&entries[0]).spans.len(), 1);
+  assert_eq!(style(&entries[0]).font_size_permille, Some(50));
+}
+
+#[test]
+fn drawings_and_comments_are_skipped() {
+  let entries = parse_ass(&script(
+    "Comment: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,hidden\n\
+     Dialogue: 0,0:00:00.00,0:00:02.00,Default,,0,0,0,,{\\p1}m 0 0 l 100 0 100 100{\\p0}\n\
+     Dialogue: 0,0:00:05.00,0:00:04.00,Default,,0,0,0,,backwards\n\
+     Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,kept\n",
+  ))
+  .expect("valid ASS");
+  assert_eq!(entries.len(), 1);
+  assert_eq!(entries[0].text, "kept");
+}
+
+#[test]
+fn entries_are_sorted_by_start() {
+  let entries = parse_ass(&script(
+    "Dialogue: 0,0:01:00.00,0:01:01.00,Default,,0,0,0,,late\n\
+     Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,early\n",
+  ))
+  .expect("valid ASS");
+  assert_eq!(entries[0].text, "early");
+  assert_eq!(entries[1].start_ms, 60_000);
+}
+
+#[test]
+fn legacy_ssa_styles_and_defaults() {
+  let ssa = "[Script Info]\nScriptType: v4.00\n\n\
+    [V4 Styles]\n\
+    Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, TertiaryColour, BackColour, \
+    Bold, Italic, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, AlphaLevel, \
+    Encoding\n\
+    Style: Default,Tahoma,24,16777215,65535,255,0,-1,0,1,2,0,6,30,30,12,0,0\n\n\
+    [Events]\n\
+    Format: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+    Dialogue: Marked=0,0:00:01.00,0:00:02.00,Default,,0000,0000,0000,,Top\n";
+  let entries = parse_ass(ssa).expect("valid SSA");
+  let style = style(&entries[0]);
+  // Legacy alignment 6 (top centre) maps to numpad 8.
+  assert_eq!(style.alignment, 8);
+  // No PlayRes: 384x288 is assumed, so 24px is 1/12 of the height.
+  assert_eq!(style.font_size_permille, Some(83));
+  assert_eq!(style.spans[0].color, Some(0xFF_FF_FF));
+  // Decimal 255 is pure red in BGR order.
+  assert_eq!(style.spans[0].outline_color, Some(0xFF_00_00));
+  assert!(style.spans[0].bold);
+}
+
+#[test]
+fn rejects_empty_and_missing_events() {
+  assert_eq!(parse_ass("  \n"), Err(SubtitleParseError::Empty));
+  assert_eq!(
+    parse_ass("[Script Info]\nTitle: nothing\n"),
+    Err(SubtitleParseError::MissingAssEvents)
+  );
+}
+
+#[test]
+fn time_and_colour_helpers() {
+  assert_eq!(parse_ass_time("1:02:03.45"), Some(3_723_450));
+  assert_eq!(parse_ass_time("0:00:00.5"), Some(500));
+  assert_eq!(parse_ass_time("0:61:00.00"), None);
+  assert_eq!(parse_color("&H80112233&"), Some(0x33_22_11));
+  assert_eq!(parse_color("&H00FF00"), Some(0x00_FF_00));
+  assert_eq!(parse_color("garbage"), None);
+}
@@ -151,3 +151,15 @@ fn parse_srt_rejects_end_before_start() {
   let entries = parse_srt(srt).expect("parses");
   assert!(entries.is_empty());
 }
+
+#[test]
+fn parse_subtitle_file_handles_ass() {
+  let ass = "[Script Info]\nPlayResY: 720\n\n[Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+             Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Styled\n";
+  let by_ext = parse_subtitle_file("movie.ASS", ass).expect("ass ok");
+  assert_eq!(by_ext[0].text, "Styled");
+  assert!(by_ext[0].style.is_some());
+  let sniffed = parse_subtitle_file("movie.txt", ass).expect("sniffed");
+  assert_eq!(sniffed, by_ext);
+}
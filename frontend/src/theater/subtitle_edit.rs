@@ -0,0 +1,436 @@
+//! Owner-side subtitle timing adjustment and editing.
+//!
+//! Three kinds of fixes are supported on top of the loaded
+//! [`SubtitleTrack`]:
+//!
+//! * **Global offset** — [`SubtitleTrack::offset_ms`] shifts every cue
+//!   without touching the cue list. The owner relays it as a small
+//!   `SubtitleOffset` frame, so nudging the timing while watching does
+//!   not re-send the whole track.
+//! * **Linear stretch** — [`stretch_entries`] remaps cue times through
+//!   two anchors ("this cue at 0:01:02 should be at 0:01:05"). This is
+//!   the classic fix for a track timed against a different frame rate
+//!   (23.976 vs 25 fps) where the drift grows over the film.
+//! * **Per-cue edits** — [`edit_cue`] / [`insert_cue`] /
+//!   [`remove_cue`] keep the list sorted.
+//!
+//! Stretches and edits change the cues themselves and are relayed as a
+//! full `SubtitleData`. Viewers reset the offset whenever a new track
+//! arrives, and the manager's broadcast does not guarantee that two
+//! frames reach a peer in order, so an edit bakes the current offset
+//! into the cues instead of following the data with a second frame.
+//! [`export_srt`] / [`export_vtt`] write the adjusted track, offset
+//! baked in, back to a file.
+
+use core::fmt;
+use std::fmt::Write as _;
+
+use leptos::prelude::*;
+use message::datachannel::{DataChannelMessage, SubtitleData, SubtitleOffset, SubtitleSpan};
+use message::types::{RoomId, SubtitleEntry};
+
+use super::state::{SubtitleTrack, TheaterRole, TheaterState};
+use super::subtitle_sync::clear_active_subtitle;
+
+/// Largest accepted global offset (±10 minutes). Anything beyond this
+/// is almost certainly a track for a different cut.
+pub const MAX_SUBTITLE_OFFSET_MS: i32 = 10 * 60 * 1_000;
+
+/// Shortest cue a stretch or edit may produce.
+const MIN_CUE_MS: u32 = 1;
+
+/// Error returned by the editing helpers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubtitleEditError {
+  /// The two stretch anchors share a source time, so no scale can be
+  /// derived from them.
+  AnchorsTooClose,
+  /// No cue exists at the given index.
+  CueOutOfRange,
+  /// The cue would end before (or when) it starts.
+  InvalidTiming,
+  /// The cue text is empty.
+  EmptyText,
+  /// No subtitle track is loaded (or the local user is not the owner).
+  NoTrack,
+}
+
+impl fmt::Display for SubtitleEditError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::AnchorsTooClose => write!(f, "Stretch anchors must use two different cue times"),
+      Self::CueOutOfRange => write!(f, "Subtitle cue does not exist"),
+      Self::InvalidTiming => write!(f, "Cue end must be after its start"),
+      Self::EmptyText => write!(f, "Cue text is empty"),
+      Self::NoTrack => write!(f, "No subtitle track is loaded"),
+    }
+  }
+}
+
+impl std::error::Error for SubtitleEditError {}
+
+/// One stretch anchor: the cue time as written in the track and the
+/// video time it should appear at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StretchAnchor {
+  /// Time in the current track (before the global offset).
+  pub track_ms: u32,
+  /// Time at which that moment should be shown.
+  pub target_ms: u32,
+}
+
+/// Clamp an offset to [`MAX_SUBTITLE_OFFSET_MS`].
+#[must_use]
+pub fn clamp_offset(offset_ms: i64) -> i32 {
+  let max = i64::from(MAX_SUBTITLE_OFFSET_MS);
+  // Clamped to the i32 range above, so the conversion cannot fail.
+  i32::try_from(offset_ms.clamp(-max, max)).unwrap_or_default()
+}
+
+/// Map a video timestamp onto track time for cue lookup. Returns
+/// `None` before the (delayed) track starts.
+#[must_use]
+pub fn track_time_ms(video_ms: u32, offset_ms: i32) -> Option<u32> {
+  u32::try_from(i64::from(video_ms) - i64::from(offset_ms)).ok()
+}
+
+fn shift(ms: u32, offset_ms: i32) -> u32 {
+  let shifted = (i64::from(ms) + i64::from(offset_ms)).max(0);
+  u32::try_from(shifted).unwrap_or(u32::MAX)
+}
+
+/// Remap every cue through the line defined by two anchors.
+///
+/// # Errors
+/// Returns [`SubtitleEditError::AnchorsTooClose`] when both anchors use
+/// the same track time.
+pub fn stretch_entries(
+  entries: &[SubtitleEntry],
+  first: StretchAnchor,
+  second: StretchAnchor,
+) -> Result<Vec<SubtitleEntry>, SubtitleEditError> {
+  if first.track_ms == second.track_ms {
+    return Err(SubtitleEditError::AnchorsTooClose);
+  }
+  let scale = (f64::from(second.target_ms) - f64::from(first.target_ms))
+    / (f64::from(second.track_ms) - f64::from(first.track_ms));
+  let map = |ms: u32| {
+    let mapped = f64::from(first.target_ms) + (f64::from(ms) - f64::from(first.track_ms)) * scale;
+    // Clamped to the u32 range, so the cast cannot truncate.
+    mapped.round().clamp(0.0, f64::from(u32::MAX)) as u32
+  };
+  let mut stretched: Vec<SubtitleEntry> = entries
+    .iter()
+    .map(|entry| {
+      let start_ms = map(entry.start_ms);
+      let end_ms = map(entry.end_ms).max(start_ms.saturating_add(MIN_CUE_MS));
+      SubtitleEntry {
+        start_ms,
+        end_ms,
+        ..entry.clone()
+      }
+    })
+    .collect();
+  // A negative scale reverses the order; keep the list sorted anyway.
+  stretched.sort_by_key(|e| e.start_ms);
+  Ok(stretched)
+}
+
+/// Trim every line and drop blank ones — a blank line ends a cue in
+/// both SRT and WebVTT.
+#[must_use]
+pub fn normalize_cue_text(text: &str) -> String {
+  text
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn validate_cue(start_ms: u32, end_ms: u32, text: &str) -> Result<String, SubtitleEditError> {
+  if end_ms <= start_ms {
+    return Err(SubtitleEditError::InvalidTiming);
+  }
+  let text = normalize_cue_text(text);
+  if text.is_empty() {
+    return Err(SubtitleEditError::EmptyText);
+  }
+  Ok(text)
+}
+
+/// Replace the text of a styled cue. The spans collapse to a single
+/// run carrying the first run's formatting (karaoke timing dropped),
+/// since the new text no longer lines up with the old runs.
+fn retext_style(entry: &mut SubtitleEntry, text: &str) {
+  let Some(style) = entry.style.as_mut() else {
+    return;
+  };
+  let mut span = style.spans.first().cloned().unwrap_or(SubtitleSpan {
+    text: String::new(),
+    bold: false,
+    italic: false,
+    underline: false,
+    strike_out: false,
+    color: None,
+    outline_color: None,
+    karaoke: None,
+  });
+  span.text = text.to_string();
+  span.karaoke = None;
+  style.spans = vec![span];
+}
+
+/// Update the cue at `index`, re-sorting the list when its start moved.
+/// Returns the cue's new index.
+///
+/// # Errors
+/// Fails when `index` is out of range, the timing is inverted or the
+/// text is blank.
+pub fn edit_cue(
+  entries: &mut Vec<SubtitleEntry>,
+  index: usize,
+  start_ms: u32,
+  end_ms: u32,
+  text: &str,
+) -> Result<usize, SubtitleEditError> {
+  let text = validate_cue(start_ms, end_ms, text)?;
+  if index >= entries.len() {
+    return Err(SubtitleEditError::CueOutOfRange);
+  }
+  let mut entry = entries.remove(index);
+  if entry.text != text {
+    retext_style(&mut entry, &text);
+    entry.text = text;
+  }
+  entry.start_ms = start_ms;
+  entry.end_ms = end_ms;
+  let at = entries.partition_point(|e| e.start_ms <= start_ms);
+  entries.insert(at, entry);
+  Ok(at)
+}
+
+/// Insert a new plain cue in start order. Returns its index.
+///
+/// # Errors
+/// Fails when the timing is inverted or the text is blank.
+pub fn insert_cue(
+  entries: &mut Vec<SubtitleEntry>,
+  start_ms: u32,
+  end_ms: u32,
+  text: &str,
+) -> Result<usize, SubtitleEditError> {
+  let text = validate_cue(start_ms, end_ms, text)?;
+  let at = entries.partition_point(|e| e.start_ms <= start_ms);
+  entries.insert(at, SubtitleEntry::new(start_ms, end_ms, text));
+  Ok(at)
+}
+
+/// Remove the cue at `index`.
+///
+/// # Errors
+/// Returns [`SubtitleEditError::CueOutOfRange`] for a bad index.
+pub fn remove_cue(
+  entries: &mut Vec<SubtitleEntry>,
+  index: usize,
+) -> Result<SubtitleEntry, SubtitleEditError> {
+  if index >= entries.len() {
+    return Err(SubtitleEditError::CueOutOfRange);
+  }
+  Ok(entries.remove(index))
+}
+
+/// Apply `offset_ms` to the cues themselves. Cues pushed entirely
+/// before zero are dropped; ones straddling zero are trimmed.
+#[must_use]
+pub fn bake_offset(entries: &[SubtitleEntry], offset_ms: i32) -> Vec<SubtitleEntry> {
+  entries
+    .iter()
+    .filter_map(|entry| {
+      let end_ms = shift(entry.end_ms, offset_ms);
+      if end_ms == 0 {
+        return None;
+      }
+      Some(SubtitleEntry {
+        start_ms: shift(entry.start_ms, offset_ms),
+        end_ms,
+        ..entry.clone()
+      })
+    })
+    .collect()
+}
+
+/// Format milliseconds as `HH:MM:SS{sep}mmm`.
+#[must_use]
+pub fn format_cue_time(ms: u32, fractional_separator: char) -> String {
+  let (hours, rest) = (ms / 3_600_000, ms % 3_600_000);
+  let (minutes, rest) = (rest / 60_000, rest % 60_000);
+  let (seconds, millis) = (rest / 1_000, rest % 1_000);
+  format!("{hours:02}:{minutes:02}:{seconds:02}{fractional_separator}{millis:03}")
+}
+
+/// Parse a cue time typed by the owner: `[[H:]MM:]SS[.mmm]`, with
+/// either `.` or `,` before the milliseconds.
+#[must_use]
+pub fn parse_cue_time(input: &str) -> Option<u32> {
+  let input = input.trim();
+  let (clock, frac) = input.split_once(['.', ',']).unwrap_or((input, ""));
+  let millis = match frac.len() {
+    0 => 0,
+    1..=3 => frac.parse::<u32>().ok()? * 10_u32.pow(3 - u32::try_from(frac.len()).ok()?),
+    _ => return None,
+  };
+  let mut total: u32 = 0;
+  let parts: Vec<&str> = clock.split(':').collect();
+  if parts.is_empty() || parts.len() > 3 {
+    return None;
+  }
+  for (i, part) in parts.iter().enumerate() {
+    let value: u32 = part.parse().ok()?;
+    // Every field but the leading one is base-60.
+    if i > 0 && value >= 60 {
+      return None;
+    }
+    total = total.checked_mul(60)?.checked_add(value)?;
+  }
+  total.checked_mul(1_000)?.checked_add(millis)
+}
+
+/// Export the cues as SubRip with `offset_ms` baked in.
+#[must_use]
+pub fn export_srt(entries: &[SubtitleEntry], offset_ms: i32) -> String {
+  let mut out = String::new();
+  for (i, entry) in bake_offset(entries, offset_ms).iter().enumerate() {
+    let _ = write!(
+      out,
+      "{}\n{} --> {}\n{}\n\n",
+      i + 1,
+      format_cue_time(entry.start_ms, ','),
+      format_cue_time(entry.end_ms, ','),
+      normalize_cue_text(&entry.text),
+    );
+  }
+  out
+}
+
+/// Export the cues as WebVTT with `offset_ms` baked in.
+#[must_use]
+pub fn export_vtt(entries: &[SubtitleEntry], offset_ms: i32) -> String {
+  let mut out = String::from("WEBVTT\n\n");
+  for entry in bake_offset(entries, offset_ms) {
+    let _ = write!(
+      out,
+      "{} --> {}\n{}\n\n",
+      format_cue_time(entry.start_ms, '.'),
+      format_cue_time(entry.end_ms, '.'),
+      normalize_cue_text(&entry.text),
+    );
+  }
+  out
+}
+
+/// Filename for an exported track: `movie.ass` → `movie.adjusted.srt`.
+#[must_use]
+pub fn export_filename(original: &str, extension: &str) -> String {
+  let stem = original
+    .rsplit_once('.')
+    .map_or(original, |(stem, _)| stem)
+    .trim();
+  let stem = if stem.is_empty() { "subtitles" } else { stem };
+  format!("{stem}.adjusted.{extension}")
+}
+
+fn owner_room(state: &TheaterState) -> Option<RoomId> {
+  if state.my_role.get_untracked() != TheaterRole::Owner {
+    return None;
+  }
+  state.room_id.get_untracked()
+}
+
+/// Owner: set the global offset and return the frame to broadcast.
+/// Returns `None` (and changes nothing) without a track or when the
+/// local user is not the owner.
+pub fn owner_set_offset(state: &TheaterState, offset_ms: i64) -> Option<DataChannelMessage> {
+  let room_id = owner_room(state)?;
+  let offset_ms = clamp_offset(offset_ms);
+  let changed = state
+    .subtitle
+    .try_update(|track| {
+      track.as_mut().map(|t| {
+        let changed = t.offset_ms != offset_ms;
+        t.offset_ms = offset_ms;
+        changed
+      })
+    })
+    .flatten()?;
+  if !changed {
+    return None;
+  }
+  clear_active_subtitle(state);
+  Some(DataChannelMessage::SubtitleOffset(SubtitleOffset {
+    room_id,
+    offset_ms,
+  }))
+}
+
+/// Owner: run `edit` against the track's cues, bake the current
+/// offset into the result and return the `SubtitleData` frame that
+/// re-syncs the viewers.
+///
+/// # Errors
+/// Propagates the error from `edit`; the track is left untouched.
+pub fn owner_edit_entries(
+  state: &TheaterState,
+  edit: impl FnOnce(&mut Vec<SubtitleEntry>) -> Result<(), SubtitleEditError>,
+) -> Result<DataChannelMessage, SubtitleEditError> {
+  let room_id = owner_room(state).ok_or(SubtitleEditError::NoTrack)?;
+  let (mut entries, offset_ms) = state
+    .subtitle
+    .with_untracked(|t| t.as_ref().map(|t| (t.entries.clone(), t.offset_ms)))
+    .ok_or(SubtitleEditError::NoTrack)?;
+  edit(&mut entries)?;
+  let entries = bake_offset(&entries, offset_ms);
+  state.subtitle.update(|track| {
+    if let Some(t) = track.as_mut() {
+      t.entries = entries.clone();
+      t.offset_ms = 0;
+    }
+  });
+  clear_active_subtitle(state);
+  Ok(DataChannelMessage::SubtitleData(SubtitleData {
+    room_id,
+    entries,
+  }))
+}
+
+/// Owner: frames that install the current track on a late-joining
+/// viewer. The caller must send them in order (the offset only
+/// applies once the data has arrived). Empty when no track is loaded.
+#[must_use]
+pub fn subtitle_snapshot_frames(state: &TheaterState) -> Vec<DataChannelMessage> {
+  let Some(room_id) = owner_room(state) else {
+    return Vec::new();
+  };
+  state.subtitle.with_untracked(|track| {
+    let Some(SubtitleTrack {
+      entries, offset_ms, ..
+    }) = track.as_ref()
+    else {
+      return Vec::new();
+    };
+    let mut frames = vec![DataChannelMessage::SubtitleData(SubtitleData {
+      room_id: room_id.clone(),
+      entries: entries.clone(),
+    })];
+    if *offset_ms != 0 {
+      frames.push(DataChannelMessage::SubtitleOffset(SubtitleOffset {
+        room_id,
+        offset_ms: *offset_ms,
+      }));
+    }
+    frames
+  })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,183 @@
+//! Unit tests for the pure subtitle editing helpers.
+
+use super::*;
+
+fn entry(start_ms: u32, end_ms: u32, text: &str) -> SubtitleEntry {
+  SubtitleEntry::new(start_ms, end_ms, text.into())
+}
+
+fn times(entries: &[SubtitleEntry]) -> Vec<(u32, u32)> {
+  entries.iter().map(|e| (e.start_ms, e.end_ms)).collect()
+}
+
+#[test]
+fn offset_is_clamped() {
+  assert_eq!(clamp_offset(1_500), 1_500);
+  assert_eq!(clamp_offset(-1_500), -1_500);
+  assert_eq!(clamp_offset(i64::MAX), MAX_SUBTITLE_OFFSET_MS);
+  assert_eq!(clamp_offset(i64::MIN), -MAX_SUBTITLE_OFFSET_MS);
+}
+
+#[test]
+fn track_time_maps_through_offset() {
+  assert_eq!(track_time_ms(5_000, 1_000), Some(4_000));
+  assert_eq!(track_time_ms(5_000, -1_000), Some(6_000));
+  assert_eq!(track_time_ms(500, 1_000), None);
+}
+
+#[test]
+fn stretch_fixes_framerate_drift() {
+  // Track timed for 25 fps played back at 23.976 fps: every timestamp
+  // is 25 / 23.976 too early.
+  let entries = vec![entry(0, 1_000, "a"), entry(600_000, 602_000, "b")];
+  let first = StretchAnchor {
+    track_ms: 0,
+    target_ms: 0,
+  };
+  let second = StretchAnchor {
+    track_ms: 600_000,
+    target_ms: 625_626,
+  };
+  let stretched = stretch_entries(&entries, first, second).expect("stretch");
+  assert_eq!(times(&stretched), vec![(0, 1_043), (625_626, 627_711)]);
+}
+
+#[test]
+fn stretch_with_shifted_anchors_also_offsets() {
+  let entries = vec![entry(10_000, 11_000, "a"), entry(20_000, 21_000, "b")];
+  let stretched = stretch_entries(
+    &entries,
+    StretchAnchor {
+      track_ms: 10_000,
+      target_ms: 12_000,
+    },
+    StretchAnchor {
+      track_ms: 20_000,
+      target_ms: 22_000,
+    },
+  )
+  .expect("stretch");
+  assert_eq!(times(&stretched), vec![(12_000, 13_000), (22_000, 23_000)]);
+}
+
+#[test]
+fn stretch_rejects_degenerate_anchors_and_clamps_at_zero() {
+  let anchor = StretchAnchor {
+    track_ms: 5_000,
+    target_ms: 1_000,
+  };
+  assert_eq!(
+    stretch_entries(&[], anchor, anchor),
+    Err(SubtitleEditError::AnchorsTooClose)
+  );
+  let stretched = stretch_entries(
+    &[entry(0, 100, "early")],
+    anchor,
+    StretchAnchor {
+      track_ms: 10_000,
+      target_ms: 6_000,
+    },
+  )
+  .expect("stretch");
+  // Shifted 4 s earlier: clamped to zero with the minimum duration.
+  assert_eq!(times(&stretched), vec![(0, 1)]);
+}
+
+#[test]
+fn edit_cue_resorts_and_validates() {
+  let mut entries = vec![entry(0, 1_000, "a"), entry(2_000, 3_000, "b")];
+  let at = edit_cue(&mut entries, 0, 4_000, 5_000, "  moved \n\n line ").expect("edit");
+  assert_eq!(at, 1);
+  assert_eq!(entries[1].text, "moved\nline");
+  assert_eq!(entries[0].text, "b");
+  assert_eq!(
+    edit_cue(&mut entries, 0, 5_000, 5_000, "x"),
+    Err(SubtitleEditError::InvalidTiming)
+  );
+  assert_eq!(
+    edit_cue(&mut entries, 0, 0, 5_000, " \n "),
+    Err(SubtitleEditError::EmptyText)
+  );
+  assert_eq!(
+    edit_cue(&mut entries, 9, 0, 5_000, "x"),
+    Err(SubtitleEditError::CueOutOfRange)
+  );
+}
+
+#[test]
+fn editing_styled_text_collapses_spans() {
+  let mut styled = crate::theater::parse_ass(
+    "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+     Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\b1}Bold{\\b0} plain\n",
+  )
+  .expect("ass");
+  edit_cue(&mut styled, 0, 1_000, 2_000, "Fixed").expect("edit");
+  let spans = &styled[0].style.as_ref().expect("style kept").spans;
+  assert_eq!(spans.len(), 1);
+  assert_eq!(spans[0].text, "Fixed");
+  assert!(spans[0].bold);
+}
+
+#[test]
+fn insert_and_remove_cues() {
+  let mut entries = vec![entry(0, 1_000, "a"), entry(2_000, 3_000, "c")];
+  assert_eq!(insert_cue(&mut entries, 1_500, 1_800, "b"), Ok(1));
+  assert_eq!(entries[1].text, "b");
+  assert_eq!(remove_cue(&mut entries, 0).map(|e| e.text), Ok("a".into()));
+  assert_eq!(entries.len(), 2);
+  assert_eq!(
+    remove_cue(&mut entries, 5),
+    Err(SubtitleEditError::CueOutOfRange)
+  );
+}
+
+#[test]
+fn bake_offset_drops_and_trims() {
+  let entries = vec![entry(0, 500, "gone"), entry(800, 2_000, "trimmed")];
+  let baked = bake_offset(&entries, -1_000);
+  assert_eq!(times(&baked), vec![(0, 1_000)]);
+  assert_eq!(baked[0].text, "trimmed");
+}
+
+#[test]
+fn cue_time_roundtrip() {
+  assert_eq!(format_cue_time(3_723_045, ','), "01:02:03,045");
+  assert_eq!(parse_cue_time("01:02:03,045"), Some(3_723_045));
+  assert_eq!(parse_cue_time("2:03.5"), Some(123_500));
+  assert_eq!(parse_cue_time("42"), Some(42_000));
+  assert_eq!(parse_cue_time("1:60"), None);
+  assert_eq!(parse_cue_time("abc"), None);
+  assert_eq!(parse_cue_time("1.2345"), None);
+}
+
+#[test]
+fn export_srt_bakes_offset() {
+  let entries = vec![
+    entry(1_000, 2_500, "Hello\nworld"),
+    entry(3_000, 4_000, "Bye"),
+  ];
+  let srt = export_srt(&entries, 500);
+  assert_eq!(
+    srt,
+    "1\n00:00:01,500 --> 00:00:03,000\nHello\nworld\n\n2\n00:00:03,500 --> 00:00:04,500\nBye\n\n"
+  );
+  // The export parses back to the shifted cues.
+  let parsed = crate::theater::parse_srt(&srt).expect("srt");
+  assert_eq!(times(&parsed), vec![(1_500, 3_000), (3_500, 4_500)]);
+}
+
+#[test]
+fn export_vtt_roundtrips() {
+  let entries = vec![entry(61_000, 62_000, "One")];
+  let vtt = export_vtt(&entries, 0);
+  assert!(vtt.starts_with("WEBVTT\n\n00:01:01.000 --> 00:01:02.000\nOne\n"));
+  let parsed = crate::theater::parse_vtt(&vtt).expect("vtt");
+  assert_eq!(parsed, entries);
+}
+
+#[test]
+fn export_filename_swaps_extension() {
+  assert_eq!(export_filename("movie.ass", "srt"), "movie.adjusted.srt");
+  assert_eq!(export_filename("a.b.vtt", "vtt"), "a.b.adjusted.vtt");
+  assert_eq!(export_filename("", "srt"), "subtitles.adjusted.srt");
+}
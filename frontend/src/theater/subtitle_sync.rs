@@ -9,10 +9,14 @@
 //!    writes the same payload into viewer state.
 //! 3. Owner broadcast a `SubtitleClear` frame → [`apply_subtitle_clear`]
 //!    wipes the viewer track.
+//! 4. Owner broadcast a `SubtitleOffset` frame → [`apply_subtitle_offset`]
+//!    shifts the viewer track (see [`super::subtitle_edit`]).
 //!
 //! The playback loop also calls [`refresh_active_subtitle`] on every
 //! `timeupdate` tick so the overlay component can render the current
-//! cue without scanning the list itself.
+//! cue without scanning the list itself. Styled (ASS / SSA) tracks can
+//! show several cues at once — a sign and a dialogue line, say — so
+//! those are cached as a list next to the plain text.
 //!
 //! Signal-touching helpers are thin wrappers around pure helpers that
 //! live alongside unit tests; the wrappers themselves require a
@@ -20,11 +24,12 @@
 //! runtime.
 
 use leptos::prelude::*;
-use message::datachannel::{SubtitleClear, SubtitleData};
+use message::datachannel::{SubtitleClear, SubtitleData, SubtitleEntry, SubtitleOffset};
 use message::types::RoomId;
 
 use super::state::{SubtitleTrack, TheaterState};
 use super::subtitle::active_entry;
+use super::subtitle_edit::{clamp_offset, track_time_ms};
 
 /// Pure helper: turn a `SubtitleData` payload into a [`SubtitleTrack`]
 /// when the frame targets the active room. Returns `None` when the
@@ -41,6 +46,7 @@ pub fn build_track_from_data(
     filename: String::new(),
     entries: payload.entries.clone(),
     visible: true,
+    offset_ms: 0,
   })
 }
 
@@ -52,23 +58,50 @@ pub fn should_apply_clear(active_room: Option<&RoomId>, payload: &SubtitleClear)
 }
 
 /// Pure helper: pick the active subtitle text for a given playback
-/// timestamp, respecting the `visible` flag.
+/// timestamp, respecting the `visible` flag and the track offset.
 #[must_use]
 pub fn pick_active_text(track: Option<&SubtitleTrack>, time_ms: u32) -> Option<String> {
   let track = track?;
   if !track.visible {
     return None;
   }
+  let time_ms = track_time_ms(time_ms, track.offset_ms)?;
   active_entry(&track.entries, time_ms).map(|entry| entry.text.clone())
 }
 
+/// Pure helper: collect every styled cue active at `time_ms`, in start
+/// order, respecting the `visible` flag. Plain SRT / WebVTT cues are
+/// left to [`pick_active_text`].
+#[must_use]
+pub fn pick_active_cues(track: Option<&SubtitleTrack>, time_ms: u32) -> Vec<SubtitleEntry> {
+  let Some(track) = track.filter(|t| t.visible) else {
+    return Vec::new();
+  };
+  let Some(time_ms) = track_time_ms(time_ms, track.offset_ms) else {
+    return Vec::new();
+  };
+  let idx = track.entries.partition_point(|e| e.start_ms <= time_ms);
+  track.entries[..idx]
+    .iter()
+    .filter(|e| e.style.is_some() && e.is_active_at(time_ms))
+    .cloned()
+    .collect()
+}
+
 /// Install a freshly parsed subtitle track on the local state.
 ///
 /// Also clears the currently rendered cue so the overlay refreshes on
 /// the next `timeupdate` tick.
 pub fn apply_subtitle_track(state: &TheaterState, track: SubtitleTrack) {
   state.subtitle.set(Some(track));
+  clear_active_subtitle(state);
+}
+
+/// Drop the cached on-screen cue(s) so the overlay re-renders on the
+/// next `timeupdate` tick.
+pub fn clear_active_subtitle(state: &TheaterState) {
   state.active_subtitle_text.set(None);
+  state.active_subtitle_cues.set(Vec::new());
 }
 
 /// Apply an inbound `SubtitleData` frame to the viewer state. No-op
@@ -89,32 +122,63 @@ pub fn apply_subtitle_clear(state: &TheaterState, msg: &SubtitleClear) -> bool {
     return false;
   }
   state.subtitle.set(None);
-  state.active_subtitle_text.set(None);
+  clear_active_subtitle(state);
   true
 }
 
-/// Update [`TheaterState::active_subtitle_text`] for the current
-/// playback timestamp. Invoked from the video player's timeupdate
-/// handler so the overlay renders synchronously with the video.
+/// Apply an inbound `SubtitleOffset` frame to the viewer state. No-op
+/// (returns `false`) for other rooms or when no track is loaded.
+pub fn apply_subtitle_offset(state: &TheaterState, msg: &SubtitleOffset) -> bool {
+  let active = state.room_id.get_untracked();
+  if active.as_ref() != Some(&msg.room_id) {
+    return false;
+  }
+  let offset_ms = clamp_offset(i64::from(msg.offset_ms));
+  let applied = state
+    .subtitle
+    .try_update(|track| track.as_mut().map(|t| t.offset_ms = offset_ms).is_some())
+    .unwrap_or(false);
+  if applied {
+    clear_active_subtitle(state);
+  }
+  applied
+}
+
+/// Update [`TheaterState::active_subtitle_text`] and
+/// [`TheaterState::active_subtitle_cues`] for the current playback
+/// timestamp. Invoked from the video player's timeupdate handler so
+/// the overlay renders synchronously with the video.
 ///
-/// Returns `true` when the active text actually changed.
+/// Returns `true` when the active text or cue set actually changed.
 pub fn refresh_active_subtitle(state: &TheaterState, time_ms: u32) -> bool {
-  let next = state
-    .subtitle
-    .with_untracked(|track| pick_active_text(track.as_ref(), time_ms));
-  let prev = state.active_subtitle_text.get_untracked();
-  if prev != next {
-    state.active_subtitle_text.set(next);
-    true
-  } else {
-    false
+  let (next_text, next_cues) = state.subtitle.with_untracked(|track| {
+    (
+      pick_active_text(track.as_ref(), time_ms),
+      pick_active_cues(track.as_ref(), time_ms),
+    )
+  });
+  let mut changed = false;
+  if state
+    .active_subtitle_text
+    .with_untracked(|prev| *prev != next_text)
+  {
+    state.active_subtitle_text.set(next_text);
+    changed = true;
   }
+  if state
+    .active_subtitle_cues
+    .with_untracked(|prev| *prev != next_cues)
+  {
+    state.active_subtitle_cues.set(next_cues);
+    changed = true;
+  }
+  changed
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use message::datachannel::SubtitleEntry;
+  use message::datachannel::SubtitleCueStyle;
   use uuid::Uuid;
 
   fn mk_room() -> RoomId {
@@ -126,6 +190,7 @@ mod tests {
       start_ms,
       end_ms,
       text: text.into(),
+      style: None,
     }
   }
 
@@ -176,6 +241,7 @@ mod tests {
       filename: "m.srt".into(),
       entries: vec![entry(0, 1_000, "Hello")],
       visible: false,
+      offset_ms: 0,
     };
     assert_eq!(pick_active_text(Some(&track), 500), None);
   }
@@ -186,6 +252,7 @@ mod tests {
       filename: "m.srt".into(),
       entries: vec![entry(0, 1_000, "Hello"), entry(1_000, 2_000, "World")],
       visible: true,
+      offset_ms: 0,
     };
     assert_eq!(
       pick_active_text(Some(&track), 500).as_deref(),
@@ -202,4 +269,92 @@ mod tests {
   fn pick_active_text_none_without_track() {
     assert_eq!(pick_active_text(None, 500), None);
   }
+
+  fn styled(start_ms: u32, end_ms: u32, text: &str) -> SubtitleEntry {
+    SubtitleEntry {
+      style: Some(SubtitleCueStyle {
+        font_name: None,
+        font_size_permille: None,
+        alignment: 2,
+        margin_v_permille: 20,
+        position: None,
+        spans: Vec::new(),
+      }),
+      ..entry(start_ms, end_ms, text)
+    }
+  }
+
+  #[test]
+  fn pick_active_cues_returns_overlapping_styled_cues() {
+    let track = SubtitleTrack {
+      filename: "m.ass".into(),
+      entries: vec![
+        styled(0, 5_000, "Sign"),
+        styled(1_000, 2_000, "Line"),
+        styled(3_000, 4_000, "Later"),
+      ],
+      visible: true,
+      offset_ms: 0,
+    };
+    let texts: Vec<_> = pick_active_cues(Some(&track), 1_500)
+      .into_iter()
+      .map(|e| e.text)
+      .collect();
+    assert_eq!(texts, vec!["Sign", "Line"]);
+    assert!(pick_active_cues(Some(&track), 6_000).is_empty());
+  }
+
+  #[test]
+  fn pick_active_cues_ignores_plain_and_hidden_tracks() {
+    let plain = SubtitleTrack {
+      filename: "m.srt".into(),
+      entries: vec![entry(0, 1_000, "Hello")],
+      visible: true,
+      offset_ms: 0,
+    };
+    assert!(pick_active_cues(Some(&plain), 500).is_empty());
+    let hidden = SubtitleTrack {
+      filename: "m.ass".into(),
+      entries: vec![styled(0, 1_000, "Hello")],
+      visible: false,
+      offset_ms: 0,
+    };
+    assert!(pick_active_cues(Some(&hidden), 500).is_empty());
+    assert!(pick_active_cues(None, 500).is_empty());
+  }
+
+  #[test]
+  fn offset_delays_and_advances_cues() {
+    let mut track = SubtitleTrack {
+      filename: "m.srt".into(),
+      entries: vec![entry(1_000, 2_000, "Hello")],
+      visible: true,
+      offset_ms: 500,
+    };
+    assert_eq!(pick_active_text(Some(&track), 1_200), None);
+    assert_eq!(
+      pick_active_text(Some(&track), 2_200).as_deref(),
+      Some("Hello")
+    );
+    track.offset_ms = -1_000;
+    assert_eq!(
+      pick_active_text(Some(&track), 500).as_deref(),
+      Some("Hello")
+    );
+    assert_eq!(pick_active_text(Some(&track), 1_500), None);
+  }
+
+  #[test]
+  fn offset_applies_to_styled_cues() {
+    let track = SubtitleTrack {
+      filename: "m.ass".into(),
+      entries: vec![styled(1_000, 2_000, "Sign")],
+      visible: true,
+      offset_ms: 1_000,
+    };
+    assert!(pick_active_cues(Some(&track), 1_500).is_empty());
+    assert_eq!(pick_active_cues(Some(&track), 2_500).len(), 1);
+    // Before a delayed track starts, nothing is active.
+    assert!(pick_active_cues(Some(&track), 0).is_empty());
+  }
 }
@@ -73,6 +73,46 @@ pub fn remove_from_local_storage(key: &str) {
   }
 }
 
+/// Trigger a "Save file" download for in-memory text content.
+///
+/// The object URL is revoked after a short delay so Firefox (which
+/// initiates the download asynchronously) has time to dereference
+/// the blob before the runtime drops it.
+pub fn trigger_download(filename: &str, mime: &str, content: &str) {
+  let options = web_sys::BlobPropertyBag::new();
+  options.set_type(mime);
+  let array = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(content));
+  let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&array, &options) else {
+    return;
+  };
+  let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else {
+    return;
+  };
+  trigger_url_download(filename, &url);
+  // 5 s delay — Firefox initiates downloads asynchronously, so a
+  // 0 ms revoke can race and produce a "network error" download.
+  let _ = set_timeout_once(5_000, move || {
+    let _ = web_sys::Url::revoke_object_url(&url);
+  });
+}
+
+/// Trigger a "Save file" download for an existing (blob or data) URL.
+///
+/// The caller keeps ownership of `url` and is responsible for revoking
+/// it when it is a blob URL.
+pub fn trigger_url_download(filename: &str, url: &str) {
+  let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+    return;
+  };
+  let Ok(link) = document.create_element("a") else {
+    return;
+  };
+  let link: web_sys::HtmlElement = wasm_bindgen::JsCast::unchecked_into(link);
+  let _ = link.set_attribute("href", url);
+  let _ = link.set_attribute("download", filename);
+  link.click();
+}
+
 /// Shared cell that holds the `setTimeout` closure so the timer code
 /// can drop itself after firing. Factored out to keep
 /// `TimeoutHandle` and `set_timeout_once` under the `clippy::type_complexity`
@@ -0,0 +1,145 @@
+//! Passphrase-derived key for encrypted backups.
+//!
+//! The AES-256-GCM key is derived with PBKDF2-SHA256 from the user's
+//! passphrase and a random salt, both through Web Crypto; neither the
+//! passphrase nor the key ever leaves the browser. The file format and
+//! its versioning are pure and live in [`crate::backup::format`].
+
+use js_sys::{Array, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+use super::encryption::GCM_NONCE_SIZE;
+use super::frame_crypto::{gcm_params, web_crypto};
+
+/// AES-256-GCM key derived from a backup passphrase.
+pub struct BackupCipher {
+  key: web_sys::CryptoKey,
+}
+
+impl BackupCipher {
+  /// Fresh random salt of `len` bytes.
+  ///
+  /// # Errors
+  /// Returns an error if Web Crypto is unavailable.
+  pub fn random_salt(len: usize) -> Result<Vec<u8>, String> {
+    let salt = Uint8Array::new_with_length(len as u32);
+    web_crypto()?
+      .get_random_values_with_array_buffer_view(&salt)
+      .map_err(|e| format!("Failed to generate salt: {:?}", e))?;
+    Ok(salt.to_vec())
+  }
+
+  /// Derive the key from `passphrase` with PBKDF2-SHA256.
+  ///
+  /// # Errors
+  /// Returns an error if Web Crypto is unavailable or rejects the
+  /// parameters.
+  pub async fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Result<Self, String> {
+    let subtle = web_crypto()?.subtle();
+
+    let pbkdf2 = js_sys::Object::new();
+    Reflect::set(&pbkdf2, &"name".into(), &"PBKDF2".into())
+      .map_err(|_| "Failed to set PBKDF2 algorithm name")?;
+    let base_key = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .import_key_with_object(
+          "raw",
+          &Uint8Array::from(passphrase.as_bytes()).buffer(),
+          &pbkdf2,
+          false,
+          &Array::of1(&"deriveKey".into()),
+        )
+        .map_err(|e| format!("Failed to call importKey: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Passphrase import failed: {:?}", e))?;
+    let base_key: web_sys::CryptoKey = base_key
+      .dyn_into()
+      .map_err(|_| "Passphrase key is not a CryptoKey")?;
+
+    Reflect::set(&pbkdf2, &"salt".into(), &Uint8Array::from(salt).buffer())
+      .map_err(|_| "Failed to set PBKDF2 salt")?;
+    Reflect::set(&pbkdf2, &"iterations".into(), &JsValue::from(iterations))
+      .map_err(|_| "Failed to set PBKDF2 iterations")?;
+    Reflect::set(&pbkdf2, &"hash".into(), &"SHA-256".into())
+      .map_err(|_| "Failed to set PBKDF2 hash")?;
+
+    let aes = js_sys::Object::new();
+    Reflect::set(&aes, &"name".into(), &"AES-GCM".into())
+      .map_err(|_| "Failed to set AES algorithm name")?;
+    Reflect::set(&aes, &"length".into(), &JsValue::from(256))
+      .map_err(|_| "Failed to set AES algorithm length")?;
+
+    let key = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .derive_key_with_object_and_object(
+          &pbkdf2,
+          &base_key,
+          &aes,
+          false,
+          &Array::of2(&"encrypt".into(), &"decrypt".into()),
+        )
+        .map_err(|e| format!("Failed to call deriveKey: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("PBKDF2 key derivation failed: {:?}", e))?;
+    Ok(Self {
+      key: key
+        .dyn_into()
+        .map_err(|_| "Derived key is not a CryptoKey".to_string())?,
+    })
+  }
+
+  /// Encrypt `plaintext` with `header` bound as additional data.
+  /// Returns the random IV and the ciphertext (tag included).
+  ///
+  /// # Errors
+  /// Returns an error if a Web Crypto operation fails.
+  pub async fn seal(&self, plaintext: &[u8], header: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let crypto = web_crypto()?;
+    let iv = Uint8Array::new_with_length(GCM_NONCE_SIZE as u32);
+    crypto
+      .get_random_values_with_array_buffer_view(&iv)
+      .map_err(|e| format!("Failed to generate IV: {:?}", e))?;
+    let iv = iv.to_vec();
+    let algo = gcm_params(&iv, header).map_err(|_| "Failed to build AES-GCM parameters")?;
+    let encrypted = wasm_bindgen_futures::JsFuture::from(
+      crypto
+        .subtle()
+        .encrypt_with_object_and_buffer_source(
+          &algo,
+          &self.key,
+          &Uint8Array::from(plaintext).buffer(),
+        )
+        .map_err(|e| format!("Failed to call encrypt: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Backup encryption failed: {:?}", e))?;
+    Ok((iv, Uint8Array::new(&encrypted).to_vec()))
+  }
+
+  /// Decrypt a backup body sealed by [`Self::seal`].
+  ///
+  /// # Errors
+  /// Returns an error if the IV is malformed or authentication fails
+  /// (wrong passphrase or a tampered file).
+  pub async fn open(&self, iv: &[u8], ciphertext: &[u8], header: &[u8]) -> Result<Vec<u8>, String> {
+    if iv.len() != GCM_NONCE_SIZE {
+      return Err(format!("Backup IV has {} bytes", iv.len()));
+    }
+    let algo = gcm_params(iv, header).map_err(|_| "Failed to build AES-GCM parameters")?;
+    let decrypted = wasm_bindgen_futures::JsFuture::from(
+      web_crypto()?
+        .subtle()
+        .decrypt_with_object_and_buffer_source(
+          &algo,
+          &self.key,
+          &Uint8Array::from(ciphertext).buffer(),
+        )
+        .map_err(|e| format!("Failed to call decrypt: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Backup decryption failed: {:?}", e))?;
+    Ok(Uint8Array::new(&decrypted).to_vec())
+  }
+}
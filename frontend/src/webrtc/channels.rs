@@ -0,0 +1,100 @@
+//! Per-class DataChannel routing for [`WebRtcManager`].
+//!
+//! The primary channel carries the ECDH handshake and is the only one
+//! an older peer knows about. Once the shared key exists both sides
+//! open the pre-negotiated extra channels (see
+//! [`ChannelClass::EXTRA`]) and announce them with an encrypted
+//! `ChannelSetup`. Outbound frames are then routed by their
+//! discriminator; a class only leaves the primary channel after the
+//! peer has announced it, so a peer that never does keeps the single
+//! channel it started with.
+
+use super::{PeerDataChannel, PeerKey, WebRtcError, WebRtcManager};
+use message::datachannel::{ChannelClass, ChannelSetup, DataChannelMessage};
+use message::error::{ErrorCategory, ErrorCode, ErrorModule};
+
+impl WebRtcManager {
+  /// Open the extra channels to `peer` and announce them.
+  ///
+  /// Called after every completed ECDH exchange. Channels that already
+  /// exist are kept, so a repeated handshake only re-sends the
+  /// announcement.
+  pub(super) fn open_extra_channels(&self, peer: &PeerKey) {
+    let (created, classes) = {
+      let mut inner = self.inner.borrow_mut();
+      let Some(pc) = inner.connections.get_mut(peer) else {
+        return;
+      };
+      let created = match pc.open_extra_channels() {
+        Ok(created) => created,
+        Err(e) => {
+          web_sys::console::warn_1(
+            &format!("[webrtc] Extra DataChannels unavailable for {peer}: {e}").into(),
+          );
+          Vec::new()
+        }
+      };
+      (created, pc.local_classes())
+    };
+
+    for (_, dc) in created {
+      let manager = self.clone();
+      let dc_peer = peer.clone();
+      dc.set_on_raw_message(move |bytes| {
+        manager.handle_data_channel_raw_frame(dc_peer.clone(), bytes);
+      });
+    }
+    if classes.is_empty() {
+      return;
+    }
+
+    let manager = self.clone();
+    let peer = peer.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let msg = DataChannelMessage::ChannelSetup(ChannelSetup { classes });
+      if let Err(e) = manager
+        .send_encrypted_data_channel_message_to(&peer, &msg)
+        .await
+      {
+        web_sys::console::warn_1(&format!("[webrtc] ChannelSetup to {peer} failed: {e}").into());
+      }
+    });
+  }
+
+  /// Record the extra channels announced by `peer`.
+  pub(super) fn handle_channel_setup(&self, peer: &PeerKey, setup: ChannelSetup) {
+    let classes: Vec<ChannelClass> = setup
+      .classes
+      .into_iter()
+      .filter(|c| ChannelClass::EXTRA.contains(c))
+      .collect();
+    web_sys::console::log_1(
+      &format!("[webrtc] Peer {peer} opened extra DataChannels {classes:?}").into(),
+    );
+    if let Some(pc) = self.inner.borrow_mut().connections.get_mut(peer) {
+      pc.set_remote_classes(classes);
+    }
+  }
+
+  /// Channel that carries `class` traffic to `peer`: the class's
+  /// own channel when both sides opened it, the primary otherwise.
+  pub(super) fn routed_data_channel(
+    &self,
+    peer: &PeerKey,
+    class: ChannelClass,
+  ) -> Result<PeerDataChannel, WebRtcError> {
+    let peer_id = &peer.user_id;
+    let inner = self.inner.borrow();
+    let pc = inner
+      .connections
+      .get(peer)
+      .ok_or_else(|| WebRtcError::peer_not_found(peer_id.clone()))?;
+    pc.channel_for(class).cloned().ok_or_else(|| {
+      WebRtcError::new(
+        ErrorCode::new(ErrorModule::E2e, ErrorCategory::Client, 2),
+        "No DataChannel for peer",
+        Some(peer_id.clone()),
+      )
+    })
+  }
+}
@@ -4,7 +4,8 @@
 //! payloads through the AES-GCM envelope path:
 //!
 //! * [`WebRtcManager::send_encrypted_message`] — low-level, takes a
-//!   pre-framed `[discriminator][bitcode]` byte slice.
+//!   pre-framed `[discriminator][bitcode]` byte slice and sends it to
+//!   every connected device of the peer.
 //! * [`WebRtcManager::send_encrypted_data_channel_message`] — high
 //!   level, accepts a typed `DataChannelMessage` and frames it for
 //!   you (preferred).
@@ -20,30 +21,65 @@
 //! Extracted from `webrtc/mod.rs` so the main module stays below the
 //! 2 000-line hotspot threshold (Task 15 T15-1 refactor).
 
-use super::{BroadcastResult, WebRtcError, WebRtcManager};
+use super::{BroadcastResult, PeerKey, WebRtcError, WebRtcManager};
 use message::UserId;
+use message::datachannel::ChannelClass;
 use message::error::{ErrorCategory, ErrorCode, ErrorModule};
 
 impl WebRtcManager {
   /// Send an encrypted message to a specific peer.
   ///
-  /// Encrypts the plaintext with the peer's shared key and sends it
-  /// wrapped in an `ENCRYPTED_MARKER` envelope frame.
+  /// Each connected device of the peer gets its own copy, encrypted
+  /// with that connection's shared key (see
+  /// [`send_encrypted_message_to`](Self::send_encrypted_message_to)).
   ///
   /// # Errors
-  /// Returns an error if the peer has no shared key or the send
-  /// operation fails.
+  /// Returns an error if no device of the peer has a shared key or
+  /// every send fails.
   pub async fn send_encrypted_message(
     &self,
     peer_id: UserId,
     plaintext: &[u8],
   ) -> Result<(), WebRtcError> {
+    let peers: Vec<PeerKey> = self
+      .inner
+      .borrow()
+      .crypto
+      .keys()
+      .filter(|key| key.user_id == peer_id)
+      .cloned()
+      .collect();
+    let futures = peers
+      .iter()
+      .map(|peer| self.send_encrypted_message_to(peer, plaintext));
+    let results = futures::future::join_all(futures).await;
+    results
+      .into_iter()
+      .reduce(|acc, result| acc.or(result))
+      .unwrap_or_else(|| Err(WebRtcError::no_crypto(peer_id)))
+  }
+
+  /// Send an encrypted message to one device of a peer.
+  ///
+  /// Encrypts the plaintext with the connection's shared key and sends
+  /// it wrapped in an `ENCRYPTED_MARKER` envelope frame, on the channel
+  /// its discriminator routes to.
+  ///
+  /// # Errors
+  /// Returns an error if the connection has no shared key or the send
+  /// operation fails.
+  pub(super) async fn send_encrypted_message_to(
+    &self,
+    peer: &PeerKey,
+    plaintext: &[u8],
+  ) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
     // Scope borrow to extract crypto.
     let crypto = {
       let inner = self.inner.borrow();
       inner
         .crypto
-        .get(&peer_id)
+        .get(peer)
         .ok_or_else(|| WebRtcError::no_crypto(peer_id.clone()))?
         .clone()
     };
@@ -60,22 +96,12 @@ impl WebRtcManager {
       )
     })?;
 
-    // Extract DataChannel (clone to avoid holding RefCell borrow across send).
-    let dc = {
-      let inner = self.inner.borrow();
-      let pc = inner
-        .connections
-        .get(&peer_id)
-        .ok_or_else(|| WebRtcError::peer_not_found(peer_id.clone()))?;
-
-      pc.get_data_channel().cloned().ok_or_else(|| {
-        WebRtcError::new(
-          ErrorCode::new(ErrorModule::E2e, ErrorCategory::Client, 2),
-          "No DataChannel for peer",
-          Some(peer_id.clone()),
-        )
-      })?
-    };
+    // Pick the channel by the frame's discriminator (clone to avoid
+    // holding the RefCell borrow across send).
+    let class = plaintext.first().map_or(ChannelClass::Control, |&d| {
+      ChannelClass::for_discriminator(d)
+    });
+    let dc = self.routed_data_channel(peer, class)?;
 
     dc.send_raw_envelope(&encrypted).map_err(|e| {
       WebRtcError::new(
@@ -113,12 +139,21 @@ impl WebRtcManager {
     peer_id: UserId,
     msg: &message::datachannel::DataChannelMessage,
   ) -> Result<(), WebRtcError> {
-    let discriminator = msg.discriminator();
-    let payload = bitcode::encode(msg);
-    let mut plaintext = Vec::with_capacity(1 + payload.len());
-    plaintext.push(discriminator);
-    plaintext.extend_from_slice(&payload);
-    self.send_encrypted_message(peer_id, &plaintext).await
+    self
+      .send_encrypted_message(peer_id, &plaintext_frame(msg))
+      .await
+  }
+
+  /// [`send_encrypted_data_channel_message`](Self::send_encrypted_data_channel_message)
+  /// for one device of a peer.
+  pub(super) async fn send_encrypted_data_channel_message_to(
+    &self,
+    peer: &PeerKey,
+    msg: &message::datachannel::DataChannelMessage,
+  ) -> Result<(), WebRtcError> {
+    self
+      .send_encrypted_message_to(peer, &plaintext_frame(msg))
+      .await
   }
 
   /// Broadcast an encrypted message to all peers with established keys.
@@ -186,23 +221,24 @@ impl WebRtcManager {
     Ok(BroadcastResult { sent, failed_peers })
   }
 
-  /// Receive and decrypt a message from a peer.
+  /// Receive and decrypt a message from one device of a peer.
   ///
-  /// Decrypts ciphertext using the peer's established shared key.
+  /// Decrypts ciphertext using the connection's established shared key.
   ///
   /// # Errors
   /// Returns an error if no shared key exists or decryption fails.
   pub async fn receive_encrypted_message(
     &self,
-    peer_id: UserId,
+    peer: &PeerKey,
     ciphertext: &[u8],
   ) -> Result<Vec<u8>, WebRtcError> {
+    let peer_id = peer.user_id.clone();
     // Scope borrow to extract crypto.
     let crypto = {
       let inner = self.inner.borrow();
       inner
         .crypto
-        .get(&peer_id)
+        .get(peer)
         .ok_or_else(|| WebRtcError::no_crypto(peer_id.clone()))?
         .clone()
     };
@@ -220,27 +256,50 @@ impl WebRtcManager {
     })
   }
 
-  /// Check if a peer has an established shared encryption key.
+  /// Check if any device of a peer has an established shared
+  /// encryption key.
   #[must_use]
   pub fn has_encryption_key(&self, peer_id: &UserId) -> bool {
     self
       .inner
       .borrow()
       .crypto
-      .get(peer_id)
-      .is_some_and(|c| c.has_shared_key())
+      .iter()
+      .any(|(key, c)| key.user_id == *peer_id && c.has_shared_key())
   }
 
-  /// Get the list of peers with established encryption keys.
-  #[must_use]
-  pub fn encrypted_peers(&self) -> Vec<UserId> {
+  /// Check if the connection to one device of a peer has an
+  /// established shared encryption key.
+  pub(super) fn has_connection_key(&self, peer: &PeerKey) -> bool {
     self
       .inner
       .borrow()
       .crypto
-      .iter()
-      .filter(|(_, c)| c.has_shared_key())
-      .map(|(id, _)| id.clone())
-      .collect()
+      .get(peer)
+      .is_some_and(|c| c.has_shared_key())
+  }
+
+  /// Get the list of peers with established encryption keys, once per
+  /// peer however many of its devices have one.
+  #[must_use]
+  pub fn encrypted_peers(&self) -> Vec<UserId> {
+    let mut peers: Vec<UserId> = Vec::new();
+    for (key, crypto) in &self.inner.borrow().crypto {
+      if crypto.has_shared_key() && !peers.contains(&key.user_id) {
+        peers.push(key.user_id.clone());
+      }
+    }
+    peers
   }
 }
+
+/// Frame a message as `[discriminator][bitcode payload]`, the plaintext
+/// layout every envelope decrypts to.
+pub(super) fn plaintext_frame(msg: &message::datachannel::DataChannelMessage) -> Vec<u8> {
+  let discriminator = msg.discriminator();
+  let payload = bitcode::encode(msg);
+  let mut plaintext = Vec::with_capacity(1 + payload.len());
+  plaintext.push(discriminator);
+  plaintext.extend_from_slice(&payload);
+  plaintext
+}
@@ -5,7 +5,7 @@
 //!
 //! # Frame formats (Task 19.1 — Req 5.1.3)
 //!
-//! Two on-the-wire frame formats coexist on the DataChannel:
+//! Three on-the-wire frame formats coexist on the DataChannel:
 //!
 //! 1. **Plaintext frame** — `[discriminator (1 B)][bitcode payload]`.
 //!    Used exclusively for ECDH bootstrap messages which cannot be
@@ -18,12 +18,30 @@
 //!    control). `ciphertext` decrypts to a plaintext frame
 //!    (format 1), which is then dispatched to the caller.
 //!
-//! The envelope marker `0xFE` lives outside the discriminator value
-//! range reserved for real message kinds, so a single byte suffices
-//! to route inbound frames to the correct path without ambiguity.
+//! 3. **Group envelope** — `[GROUP_ENCRYPTED_MARKER=0xFD][room_id (16 B)][epoch (4 B)][iv (12 B)][ciphertext+tag]`.
+//!    Used for room conversations: encrypted once under the sender's
+//!    room key (see `group_crypto`) and sent unchanged to every
+//!    member. The marker, room id and epoch are authenticated as
+//!    additional data.
+//!
+//! Both envelope markers live outside the discriminator value range
+//! reserved for real message kinds, so a single byte suffices to
+//! route inbound frames to the correct path without ambiguity.
+//!
+//! # Channels
+//!
+//! The primary channel (label `"chat"`) is announced in-band and is
+//! reliable and ordered. Once the ECDH handshake completes each side
+//! also opens one pre-negotiated channel per
+//! [`ChannelClass::EXTRA`] class — reliable unordered `"bulk"` for
+//! file chunks, unreliable `"ephemeral"` for typing and playback
+//! ticks — and announces them with an encrypted `ChannelSetup`. The
+//! same frame formats are used on every channel; only the sender's
+//! choice of channel changes (see
+//! [`ChannelClass::for_discriminator`]).
 
 use js_sys::{ArrayBuffer, Uint8Array};
-use message::datachannel::DataChannelMessage;
+use message::datachannel::{ChannelClass, DataChannelMessage};
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::JsCast;
@@ -37,6 +55,11 @@ use web_sys::{RtcDataChannel, RtcDataChannelState};
 /// byte without ambiguity.
 pub const ENCRYPTED_MARKER: u8 = 0xFE;
 
+/// Marker byte that identifies a group envelope frame (room message
+/// encrypted under the sender's room key). Outside the discriminator
+/// range for the same reason as [`ENCRYPTED_MARKER`].
+pub const GROUP_ENCRYPTED_MARKER: u8 = 0xFD;
+
 type MessageClosure = Closure<dyn FnMut(web_sys::MessageEvent)>;
 type EventClosure = Closure<dyn FnMut(web_sys::Event)>;
 
@@ -108,6 +131,52 @@ impl PeerDataChannel {
     })
   }
 
+  /// Create a pre-negotiated DataChannel for an extra
+  /// [`ChannelClass`].
+  ///
+  /// Negotiated channels do not fire `ondatachannel` on the remote
+  /// side: both peers create the channel with the same stream id and
+  /// the browser pairs them. A peer that never creates its half simply
+  /// drops anything sent on the stream, which is why nothing is routed
+  /// here until the remote `ChannelSetup` arrives.
+  ///
+  /// # Errors
+  /// Returns an error if `class` is the primary class, which is
+  /// announced in-band instead.
+  pub fn create_negotiated(
+    connection: &web_sys::RtcPeerConnection,
+    peer_id: message::UserId,
+    class: ChannelClass,
+  ) -> Result<Self, String> {
+    let id = class
+      .stream_id()
+      .ok_or_else(|| format!("{class:?} has no negotiated channel"))?;
+    let init = web_sys::RtcDataChannelInit::new();
+    init.set_negotiated(true);
+    init.set_id(id);
+    init.set_ordered(class.ordered());
+    if let Some(max) = class.max_retransmits() {
+      init.set_max_retransmits(max);
+    }
+    // `priority` is not in every browser's dictionary; unknown members
+    // are ignored, so set it loosely.
+    let _ = js_sys::Reflect::set(&init, &"priority".into(), &class.priority().into());
+
+    let channel = connection.create_data_channel_with_data_channel_dict(class.label(), &init);
+    channel.set_binary_type(web_sys::RtcDataChannelType::Arraybuffer);
+
+    web_sys::console::log_1(
+      &format!(
+        "[datachannel] Created {} DataChannel (id={id}) for peer {}",
+        class.label(),
+        peer_id
+      )
+      .into(),
+    );
+
+    Ok(Self::new(channel, peer_id, true))
+  }
+
   /// Send a DataChannel message.
   ///
   /// Serializes the message using bitcode and sends it as a binary message.
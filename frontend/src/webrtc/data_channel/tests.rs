@@ -1,9 +1,11 @@
 use message::datachannel::AckStatus;
 use message::datachannel::{
-  AvatarData, AvatarRequest, ChatImage, ChatSticker, ChatText, ChatVoice, Danmaku, DanmakuBatch,
-  DataChannelMessage, EcdhKeyExchange, FileChunk, FileMetadata, FileResumeRequest, ForwardMessage,
-  MediaStateUpdate, MessageAck, MessageReaction, MessageRead, MessageRevoke, PlaybackProgress,
-  ReactionAction, ReconnectingState, SubtitleClear, SubtitleData, SubtitleEntry, TheaterChatText,
+  AvatarData, AvatarRequest, CallRecordingState, ChannelClass, ChannelSetup, ChatImage, ChatPoll,
+  ChatSticker, ChatText, ChatVoice, Danmaku, DanmakuBatch, DataChannelMessage, EcdhKeyExchange,
+  FileChunk, FileMetadata, FileResumeRequest, ForwardMessage, MediaStateUpdate, MessageAck,
+  MessageEdit, MessageReaction, MessageRead, MessageRevoke, PlaybackProgress, PollClose, PollVote,
+  ReactionAction, ReconnectingState, SubtitleClear, SubtitleData, SubtitleEntry, SubtitleOffset,
+  TheaterChatText, TheaterClockPing, TheaterClockPong, TheaterNowPlaying, TheaterQueueReorder,
   TypingIndicator,
 };
 
@@ -34,6 +36,7 @@ fn all_discriminators_are_unique() {
       message_id: mid(),
       content: "t".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -43,6 +46,7 @@ fn all_discriminators_are_unique() {
       pack_id: "p".to_string(),
       sticker_id: "s".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -52,6 +56,7 @@ fn all_discriminators_are_unique() {
       duration_ms: 1000,
       waveform: vec![0u8; 16],
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -62,6 +67,7 @@ fn all_discriminators_are_unique() {
       width: 100,
       height: 100,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -99,6 +105,33 @@ fn all_discriminators_are_unique() {
       message_id: mid(),
       timestamp_nanos: 0,
     }),
+    DataChannelMessage::MessageEdit(MessageEdit {
+      message_id: mid(),
+      content: "edited".to_string(),
+      revision: 1,
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::ChatPoll(ChatPoll {
+      message_id: mid(),
+      question: "q".to_string(),
+      options: vec!["a".to_string(), "b".to_string()],
+      multiple_choice: false,
+      anonymous: false,
+      deadline_nanos: None,
+      reply_to: None,
+      thread_root: None,
+      timestamp_nanos: 0,
+      room_id: None,
+    }),
+    DataChannelMessage::PollVote(PollVote {
+      poll_id: mid(),
+      choices: vec![1],
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::PollClose(PollClose {
+      poll_id: mid(),
+      timestamp_nanos: 0,
+    }),
     DataChannelMessage::TypingIndicator(TypingIndicator { is_typing: true }),
     DataChannelMessage::MessageRead(MessageRead {
       message_ids: vec![mid()],
@@ -121,6 +154,8 @@ fn all_discriminators_are_unique() {
     DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
       public_key: vec![0u8; 65],
       timestamp_nanos: 0,
+      identity_key: vec![0u8; 65],
+      signature: vec![0u8; 64],
     }),
     DataChannelMessage::AvatarRequest(AvatarRequest { user_id: uid() }),
     DataChannelMessage::AvatarData(AvatarData {
@@ -151,9 +186,14 @@ fn all_discriminators_are_unique() {
         start_ms: 0,
         end_ms: 2000,
         text: "hello".to_string(),
+        style: None,
       }],
     }),
     DataChannelMessage::SubtitleClear(SubtitleClear { room_id: rid() }),
+    DataChannelMessage::SubtitleOffset(SubtitleOffset {
+      room_id: rid(),
+      offset_ms: -500,
+    }),
     DataChannelMessage::DanmakuBatch(DanmakuBatch {
       room_id: rid(),
       entries: vec![],
@@ -164,12 +204,37 @@ fn all_discriminators_are_unique() {
       content: "tc".to_string(),
       timestamp_nanos: 0,
     }),
+    DataChannelMessage::TheaterQueueReorder(TheaterQueueReorder {
+      room_id: rid(),
+      item_ids: vec![mid()],
+    }),
+    DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+      room_id: rid(),
+      item_id: Some(mid()),
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::TheaterClockPing(TheaterClockPing {
+      room_id: rid(),
+      seq: 1,
+      client_send_nanos: 0,
+    }),
+    DataChannelMessage::TheaterClockPong(TheaterClockPong {
+      room_id: rid(),
+      seq: 1,
+      client_send_nanos: 0,
+      owner_recv_nanos: 0,
+      owner_send_nanos: 0,
+    }),
     DataChannelMessage::MediaStateUpdate(MediaStateUpdate {
       mic_enabled: true,
       camera_enabled: false,
       screen_sharing: false,
     }),
     DataChannelMessage::ReconnectingState(ReconnectingState { reconnecting: true }),
+    DataChannelMessage::CallRecordingState(CallRecordingState { recording: true }),
+    DataChannelMessage::ChannelSetup(ChannelSetup {
+      classes: vec![ChannelClass::Bulk, ChannelClass::Ephemeral],
+    }),
   ];
 
   let discriminators: Vec<u8> = msgs.iter().map(|m| m.discriminator()).collect();
@@ -206,6 +271,7 @@ fn message_discriminator_roundtrip() {
     message_id: mid(),
     content: "Hello, World!".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1234567890,
     room_id: None,
     mentions: vec![],
@@ -319,6 +385,7 @@ fn chat_sticker_roundtrip() {
     pack_id: "animals".to_string(),
     sticker_id: "cat".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 0,
     room_id: None,
   });
@@ -337,6 +404,8 @@ fn ecdh_key_exchange_roundtrip() {
   let msg = DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
     public_key: vec![0x04; 65],
     timestamp_nanos: 42,
+    identity_key: vec![0u8; 65],
+    signature: vec![0u8; 64],
   });
   let payload = bitcode::encode(&msg);
   let decoded: DataChannelMessage = bitcode::decode(&payload).unwrap();
@@ -478,11 +547,13 @@ fn subtitle_data_roundtrip() {
         start_ms: 0,
         end_ms: 2000,
         text: "Hello".to_string(),
+        style: None,
       },
       SubtitleEntry {
         start_ms: 2000,
         end_ms: 4000,
         text: "World".to_string(),
+        style: None,
       },
     ],
   });
@@ -658,6 +729,7 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       message_id: mid(),
       content: "t".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -667,6 +739,7 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       pack_id: "p".to_string(),
       sticker_id: "s".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -676,6 +749,7 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       duration_ms: 1000,
       waveform: vec![0u8; 16],
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -686,6 +760,7 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       width: 100,
       height: 100,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     }),
@@ -723,6 +798,33 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       message_id: mid(),
       timestamp_nanos: 0,
     }),
+    DataChannelMessage::MessageEdit(MessageEdit {
+      message_id: mid(),
+      content: "edited".to_string(),
+      revision: 1,
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::ChatPoll(ChatPoll {
+      message_id: mid(),
+      question: "q".to_string(),
+      options: vec!["a".to_string(), "b".to_string()],
+      multiple_choice: false,
+      anonymous: false,
+      deadline_nanos: None,
+      reply_to: None,
+      thread_root: None,
+      timestamp_nanos: 0,
+      room_id: None,
+    }),
+    DataChannelMessage::PollVote(PollVote {
+      poll_id: mid(),
+      choices: vec![1],
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::PollClose(PollClose {
+      poll_id: mid(),
+      timestamp_nanos: 0,
+    }),
     DataChannelMessage::TypingIndicator(TypingIndicator { is_typing: true }),
     DataChannelMessage::MessageRead(MessageRead {
       message_ids: vec![mid()],
@@ -745,6 +847,8 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
     DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
       public_key: vec![0u8; 65],
       timestamp_nanos: 0,
+      identity_key: vec![0u8; 65],
+      signature: vec![0u8; 64],
     }),
     DataChannelMessage::AvatarRequest(AvatarRequest { user_id: uid() }),
     DataChannelMessage::AvatarData(AvatarData {
@@ -775,9 +879,14 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
         start_ms: 0,
         end_ms: 2000,
         text: "hello".to_string(),
+        style: None,
       }],
     }),
     DataChannelMessage::SubtitleClear(SubtitleClear { room_id: rid() }),
+    DataChannelMessage::SubtitleOffset(SubtitleOffset {
+      room_id: rid(),
+      offset_ms: -500,
+    }),
     DataChannelMessage::DanmakuBatch(DanmakuBatch {
       room_id: rid(),
       entries: vec![],
@@ -788,11 +897,61 @@ fn all_message_variants() -> Vec<DataChannelMessage> {
       content: "tc".to_string(),
       timestamp_nanos: 0,
     }),
+    DataChannelMessage::TheaterQueueReorder(TheaterQueueReorder {
+      room_id: rid(),
+      item_ids: vec![mid()],
+    }),
+    DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+      room_id: rid(),
+      item_id: Some(mid()),
+      timestamp_nanos: 0,
+    }),
+    DataChannelMessage::TheaterClockPing(TheaterClockPing {
+      room_id: rid(),
+      seq: 1,
+      client_send_nanos: 0,
+    }),
+    DataChannelMessage::TheaterClockPong(TheaterClockPong {
+      room_id: rid(),
+      seq: 1,
+      client_send_nanos: 0,
+      owner_recv_nanos: 0,
+      owner_send_nanos: 0,
+    }),
     DataChannelMessage::MediaStateUpdate(MediaStateUpdate {
       mic_enabled: true,
       camera_enabled: false,
       screen_sharing: false,
     }),
     DataChannelMessage::ReconnectingState(ReconnectingState { reconnecting: true }),
+    DataChannelMessage::CallRecordingState(CallRecordingState { recording: true }),
+    DataChannelMessage::ChannelSetup(ChannelSetup {
+      classes: vec![ChannelClass::Bulk, ChannelClass::Ephemeral],
+    }),
   ]
 }
+
+// ---------------------------------------------------------------------------
+// Channel routing
+// ---------------------------------------------------------------------------
+
+#[test]
+fn every_variant_routes_to_a_channel_class() {
+  for msg in all_message_variants() {
+    let class = msg.channel_class();
+    match msg {
+      DataChannelMessage::FileChunk(_) | DataChannelMessage::AvatarData(_) => {
+        assert_eq!(class, ChannelClass::Bulk);
+      }
+      DataChannelMessage::TypingIndicator(_)
+      | DataChannelMessage::PlaybackProgress(_)
+      | DataChannelMessage::Danmaku(_)
+      | DataChannelMessage::DanmakuBatch(_)
+      | DataChannelMessage::TheaterClockPing(_)
+      | DataChannelMessage::TheaterClockPong(_) => assert_eq!(class, ChannelClass::Ephemeral),
+      // The handshake must stay on the primary channel: the extra
+      // channels only exist once it has completed.
+      _ => assert_eq!(class, ChannelClass::Control, "{msg:?}"),
+    }
+  }
+}
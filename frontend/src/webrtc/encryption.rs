@@ -42,7 +42,7 @@ impl CryptoKeyValue {
   ///
   /// # Errors
   /// Returns an error string if `value` is not a `CryptoKey` instance.
-  fn from_js(value: JsValue) -> Result<Self, String> {
+  pub(super) fn from_js(value: JsValue) -> Result<Self, String> {
     if value.dyn_ref::<web_sys::CryptoKey>().is_none() {
       return Err("Expected a CryptoKey value".to_string());
     }
@@ -53,7 +53,7 @@ impl CryptoKeyValue {
   ///
   /// Safe because the only constructor (`from_js`) verifies the dynamic
   /// type, so `unchecked_ref` never observes a non-`CryptoKey` value.
-  fn as_crypto_key(&self) -> &web_sys::CryptoKey {
+  pub(super) fn as_crypto_key(&self) -> &web_sys::CryptoKey {
     self.0.unchecked_ref()
   }
 }
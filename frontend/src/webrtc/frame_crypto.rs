@@ -0,0 +1,405 @@
+//! End-to-end encryption of media frames relayed by the SFU.
+//!
+//! In SFU rooms the server forwards RTP between members, so the SRTP
+//! layer no longer protects media from the relay itself. Every sender
+//! therefore encrypts its *encoded* frames (before packetisation) with
+//! its own AES-256-GCM key, using the browser's encoded-transform API
+//! (`RTCRtpSender.createEncodedStreams`). The key is handed to each
+//! room member as a [`MediaKey`] over the already-E2EE data channel.
+//!
+//! Encrypted frame layout:
+//!
+//! ```text
+//! [clear header][ciphertext + 16-byte tag][12-byte IV][key_id]
+//! ```
+//!
+//! The clear header keeps the codec payload descriptor readable so the
+//! RTP packetiser keeps working; it is authenticated as GCM additional
+//! data.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Reflect, Uint8Array};
+use message::UserId;
+use message::datachannel::MediaKey;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+
+use super::encryption::GCM_NONCE_SIZE;
+
+/// Raw AES-256 key length.
+pub(super) const MEDIA_KEY_LEN: usize = 32;
+
+/// Bytes appended after the ciphertext: the IV and the key id.
+pub(super) const FRAME_TRAILER_LEN: usize = GCM_NONCE_SIZE + 1;
+
+/// Number of key ids remembered per sender, so frames already in
+/// flight under the previous key still decrypt after a rotation.
+const REMOTE_KEYS_PER_SENDER: usize = 2;
+
+/// Bytes left in clear at the start of a frame.
+///
+/// Matches the VP8 payload header (10 bytes on keyframes, 3 on delta
+/// frames) and the Opus TOC byte; other codecs only need these to be
+/// a prefix of the frame.
+#[must_use]
+pub(super) fn clear_header_len(kind: &str, is_keyframe: bool, frame_len: usize) -> usize {
+  let len = match (kind, is_keyframe) {
+    ("audio", _) => 1,
+    (_, true) => 10,
+    (_, false) => 3,
+  };
+  len.min(frame_len)
+}
+
+/// Assemble an encrypted frame from its parts.
+#[must_use]
+pub(super) fn pack_frame(header: &[u8], ciphertext: &[u8], iv: &[u8], key_id: u8) -> Vec<u8> {
+  let mut frame = Vec::with_capacity(header.len() + ciphertext.len() + FRAME_TRAILER_LEN);
+  frame.extend_from_slice(header);
+  frame.extend_from_slice(ciphertext);
+  frame.extend_from_slice(iv);
+  frame.push(key_id);
+  frame
+}
+
+/// Borrowed view of an encrypted frame.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct EncryptedFrame<'a> {
+  pub(super) header: &'a [u8],
+  pub(super) ciphertext: &'a [u8],
+  pub(super) iv: &'a [u8],
+  pub(super) key_id: u8,
+}
+
+/// Split an encrypted frame into its parts. Returns `None` when the
+/// frame is too short to hold a header and trailer.
+#[must_use]
+pub(super) fn unpack_frame(frame: &[u8], header_len: usize) -> Option<EncryptedFrame<'_>> {
+  if frame.len() < header_len + FRAME_TRAILER_LEN {
+    return None;
+  }
+  let (rest, key_id) = frame.split_at(frame.len() - 1);
+  let (rest, iv) = rest.split_at(rest.len() - GCM_NONCE_SIZE);
+  let (header, ciphertext) = rest.split_at(header_len);
+  Some(EncryptedFrame {
+    header,
+    ciphertext,
+    iv,
+    key_id: key_id[0],
+  })
+}
+
+/// The local sender's current frame key.
+struct LocalMediaKey {
+  key_id: u8,
+  raw: Vec<u8>,
+  key: web_sys::CryptoKey,
+}
+
+/// Frame keys for the local sender and every remote publisher.
+#[derive(Default)]
+pub(super) struct MediaKeyRing {
+  local: Option<LocalMediaKey>,
+  remote: HashMap<UserId, Vec<(u8, web_sys::CryptoKey)>>,
+}
+
+impl MediaKeyRing {
+  /// The key currently announced to other members, if any.
+  #[must_use]
+  pub(super) fn local_media_key(&self) -> Option<MediaKey> {
+    self.local.as_ref().map(|local| MediaKey {
+      key_id: local.key_id,
+      key: local.raw.clone(),
+    })
+  }
+
+  /// Forget every remote key (session closed).
+  pub(super) fn clear_remote(&mut self) {
+    self.remote.clear();
+  }
+
+  /// Forget the keys of a member who left.
+  pub(super) fn remove_sender(&mut self, user_id: &UserId) {
+    self.remote.remove(user_id);
+  }
+
+  fn remote_key(&self, user_id: &UserId, key_id: u8) -> Option<web_sys::CryptoKey> {
+    self
+      .remote
+      .get(user_id)?
+      .iter()
+      .find(|(id, _)| *id == key_id)
+      .map(|(_, key)| key.clone())
+  }
+}
+
+/// Generate a fresh local frame key, replacing the current one, and
+/// return it for distribution.
+///
+/// # Errors
+/// Returns an error if WebCrypto is unavailable or rejects the key.
+pub(super) async fn rotate_local_key(ring: &Rc<RefCell<MediaKeyRing>>) -> Result<MediaKey, String> {
+  let crypto = web_crypto()?;
+  let raw = Uint8Array::new_with_length(MEDIA_KEY_LEN as u32);
+  crypto
+    .get_random_values_with_array_buffer_view(&raw)
+    .map_err(|e| format!("Failed to generate media key: {:?}", e))?;
+  let raw = raw.to_vec();
+  let key = import_key(&raw).await?;
+
+  let mut ring = ring.borrow_mut();
+  let key_id = ring
+    .local
+    .as_ref()
+    .map_or(0, |local| local.key_id.wrapping_add(1));
+  ring.local = Some(LocalMediaKey {
+    key_id,
+    raw: raw.clone(),
+    key,
+  });
+  Ok(MediaKey { key_id, key: raw })
+}
+
+/// Install a frame key announced by `sender`.
+///
+/// # Errors
+/// Returns an error if the key has the wrong length or cannot be
+/// imported.
+pub(super) async fn install_remote_key(
+  ring: &Rc<RefCell<MediaKeyRing>>,
+  sender: UserId,
+  media_key: MediaKey,
+) -> Result<(), String> {
+  if media_key.key.len() != MEDIA_KEY_LEN {
+    return Err(format!(
+      "Media key from {sender} has invalid length {}",
+      media_key.key.len()
+    ));
+  }
+  let key = import_key(&media_key.key).await?;
+  let mut ring = ring.borrow_mut();
+  let keys = ring.remote.entry(sender).or_default();
+  keys.retain(|(id, _)| *id != media_key.key_id);
+  keys.push((media_key.key_id, key));
+  if keys.len() > REMOTE_KEYS_PER_SENDER {
+    keys.remove(0);
+  }
+  Ok(())
+}
+
+/// Whether the browser exposes `createEncodedStreams`, without which
+/// relayed media cannot be end-to-end encrypted.
+#[must_use]
+pub(super) fn encoded_transforms_supported() -> bool {
+  let global = js_sys::global();
+  Reflect::get(&global, &"RTCRtpSender".into())
+    .and_then(|sender| Reflect::get(&sender, &"prototype".into()))
+    .and_then(|proto| Reflect::get(&proto, &"createEncodedStreams".into()))
+    .is_ok_and(|f| f.is_function())
+}
+
+/// Direction a frame transform works in.
+#[derive(Clone)]
+pub(super) enum FrameOp {
+  /// Encrypt outgoing frames with the local key.
+  Encrypt,
+  /// Decrypt frames published by the given member.
+  Decrypt(UserId),
+}
+
+/// Closure backing a frame transform; kept alive for as long as the
+/// SFU session so the stream pipeline is not torn down by GC.
+pub(super) type FrameTransform = Closure<dyn FnMut(JsValue, JsValue) -> js_sys::Promise>;
+
+/// Insert an encrypting or decrypting transform between the encoder
+/// (or depacketiser) and the network on an `RTCRtpSender` /
+/// `RTCRtpReceiver`.
+///
+/// Frames that cannot be processed (no key yet, authentication
+/// failure) are dropped rather than passed through, so plaintext is
+/// never sent to the relay and garbage is never fed to the decoder.
+///
+/// # Errors
+/// Returns an error if the endpoint does not support encoded streams.
+pub(super) fn attach_frame_transform(
+  endpoint: &JsValue,
+  kind: String,
+  op: FrameOp,
+  ring: Rc<RefCell<MediaKeyRing>>,
+) -> Result<FrameTransform, String> {
+  let create = Reflect::get(endpoint, &"createEncodedStreams".into())
+    .ok()
+    .and_then(|f| f.dyn_into::<Function>().ok())
+    .ok_or("createEncodedStreams is not supported")?;
+  let streams = create
+    .call0(endpoint)
+    .map_err(|e| format!("createEncodedStreams failed: {:?}", e))?;
+  let readable = Reflect::get(&streams, &"readable".into()).map_err(|_| "No readable stream")?;
+  let writable = Reflect::get(&streams, &"writable".into()).map_err(|_| "No writable stream")?;
+
+  let closure: FrameTransform =
+    Closure::wrap(Box::new(move |chunk: JsValue, controller: JsValue| {
+      let ring = ring.clone();
+      let kind = kind.clone();
+      let op = op.clone();
+      wasm_bindgen_futures::future_to_promise(async move {
+        let data = Reflect::get(&chunk, &"data".into())?;
+        let frame = Uint8Array::new(&data).to_vec();
+        let is_keyframe = Reflect::get(&chunk, &"type".into())
+          .ok()
+          .and_then(|t| t.as_string())
+          .is_some_and(|t| t == "key");
+        let header_len = clear_header_len(&kind, is_keyframe, frame.len());
+        let processed = match &op {
+          FrameOp::Encrypt => encrypt_frame(&ring, &frame, header_len).await,
+          FrameOp::Decrypt(sender) => decrypt_frame(&ring, sender, &frame, header_len).await,
+        };
+        if let Some(processed) = processed {
+          Reflect::set(
+            &chunk,
+            &"data".into(),
+            &Uint8Array::from(&processed[..]).buffer(),
+          )?;
+          let enqueue: Function = Reflect::get(&controller, &"enqueue".into())?.dyn_into()?;
+          enqueue.call1(&controller, &chunk)?;
+        }
+        Ok(JsValue::UNDEFINED)
+      })
+    })
+      as Box<dyn FnMut(JsValue, JsValue) -> js_sys::Promise>);
+
+  let transformer = js_sys::Object::new();
+  Reflect::set(&transformer, &"transform".into(), closure.as_ref())
+    .map_err(|_| "Failed to build transformer")?;
+  let ctor: Function = Reflect::get(&js_sys::global(), &"TransformStream".into())
+    .ok()
+    .and_then(|f| f.dyn_into().ok())
+    .ok_or("TransformStream is not supported")?;
+  let transform = Reflect::construct(&ctor, &Array::of1(&transformer))
+    .map_err(|e| format!("Failed to create TransformStream: {:?}", e))?;
+
+  let pipe_through: Function = Reflect::get(&readable, &"pipeThrough".into())
+    .ok()
+    .and_then(|f| f.dyn_into().ok())
+    .ok_or("pipeThrough is not supported")?;
+  let piped = pipe_through
+    .call1(&readable, &transform)
+    .map_err(|e| format!("pipeThrough failed: {:?}", e))?;
+  let pipe_to: Function = Reflect::get(&piped, &"pipeTo".into())
+    .ok()
+    .and_then(|f| f.dyn_into().ok())
+    .ok_or("pipeTo is not supported")?;
+  pipe_to
+    .call1(&piped, &writable)
+    .map_err(|e| format!("pipeTo failed: {:?}", e))?;
+
+  Ok(closure)
+}
+
+async fn encrypt_frame(
+  ring: &Rc<RefCell<MediaKeyRing>>,
+  frame: &[u8],
+  header_len: usize,
+) -> Option<Vec<u8>> {
+  let (key_id, key) = {
+    let ring = ring.borrow();
+    let local = ring.local.as_ref()?;
+    (local.key_id, local.key.clone())
+  };
+  let crypto = web_crypto().ok()?;
+  let iv = Uint8Array::new_with_length(GCM_NONCE_SIZE as u32);
+  crypto.get_random_values_with_array_buffer_view(&iv).ok()?;
+  let iv = iv.to_vec();
+
+  let (header, payload) = frame.split_at(header_len);
+  let algo = gcm_params(&iv, header).ok()?;
+  let encrypted = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .encrypt_with_object_and_buffer_source(&algo, &key, &Uint8Array::from(payload).buffer())
+      .ok()?,
+  )
+  .await
+  .ok()?;
+  let ciphertext = Uint8Array::new(&encrypted).to_vec();
+  Some(pack_frame(header, &ciphertext, &iv, key_id))
+}
+
+async fn decrypt_frame(
+  ring: &Rc<RefCell<MediaKeyRing>>,
+  sender: &UserId,
+  frame: &[u8],
+  header_len: usize,
+) -> Option<Vec<u8>> {
+  let parts = unpack_frame(frame, header_len)?;
+  let key = ring.borrow().remote_key(sender, parts.key_id)?;
+  let crypto = web_crypto().ok()?;
+  let algo = gcm_params(parts.iv, parts.header).ok()?;
+  let decrypted = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .decrypt_with_object_and_buffer_source(
+        &algo,
+        &key,
+        &Uint8Array::from(parts.ciphertext).buffer(),
+      )
+      .ok()?,
+  )
+  .await
+  .ok()?;
+
+  let mut plain = Vec::with_capacity(frame.len());
+  plain.extend_from_slice(parts.header);
+  plain.extend_from_slice(&Uint8Array::new(&decrypted).to_vec());
+  Some(plain)
+}
+
+/// AES-GCM parameters with `header` as additional authenticated data.
+pub(super) fn gcm_params(iv: &[u8], header: &[u8]) -> Result<js_sys::Object, JsValue> {
+  let algo = js_sys::Object::new();
+  Reflect::set(&algo, &"name".into(), &"AES-GCM".into())?;
+  Reflect::set(&algo, &"iv".into(), &Uint8Array::from(iv).buffer())?;
+  Reflect::set(
+    &algo,
+    &"additionalData".into(),
+    &Uint8Array::from(header).buffer(),
+  )?;
+  Ok(algo)
+}
+
+/// Import a raw AES-256-GCM key as a non-extractable `CryptoKey`.
+pub(super) async fn import_key(raw: &[u8]) -> Result<web_sys::CryptoKey, String> {
+  let crypto = web_crypto()?;
+  let algo = js_sys::Object::new();
+  Reflect::set(&algo, &"name".into(), &"AES-GCM".into())
+    .map_err(|_| "Failed to set algorithm name")?;
+  let usages = Array::of2(&"encrypt".into(), &"decrypt".into());
+  let key = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .import_key_with_object(
+        "raw",
+        &Uint8Array::from(raw).buffer(),
+        &algo,
+        false,
+        &usages,
+      )
+      .map_err(|e| format!("Failed to call importKey: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("AES key import failed: {:?}", e))?;
+  key
+    .dyn_into()
+    .map_err(|_| "Imported key is not a CryptoKey".to_string())
+}
+
+/// The window's `Crypto` object.
+pub(super) fn web_crypto() -> Result<web_sys::Crypto, String> {
+  web_sys::window()
+    .ok_or("No window object available")?
+    .crypto()
+    .map_err(|_| "Crypto not available".to_string())
+}
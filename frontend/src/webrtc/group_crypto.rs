@@ -0,0 +1,352 @@
+//! Sender keys for room conversations.
+//!
+//! Pairwise encryption costs one AES-GCM pass and one ciphertext per
+//! member for every room message. Instead, each member holds a
+//! *sender key* per room: a random AES-256-GCM key it encrypts its own
+//! room messages with, handed to every other member as a
+//! [`SenderKey`] over the pairwise E2EE channel. One ciphertext then
+//! goes out unchanged on every member's data channel.
+//!
+//! A member signed in on several devices has a sender key on each, so
+//! remote keys are held per sender *device*.
+//!
+//! Keys are scoped to a membership *epoch*. Every membership change
+//! (join, leave, kick, ban) retires each member's key; its next room
+//! message goes out under a fresh key for the next epoch, sent only to
+//! the current members first, so a removed member cannot decrypt
+//! anything sent afterwards and a new member cannot decrypt anything
+//! sent before it joined.
+//!
+//! Group frame layout (see `data_channel` for the marker):
+//!
+//! ```text
+//! [0xFD][room_id (16 B)][epoch (4 B, BE)][iv (12 B)][ciphertext + tag]
+//! ```
+//!
+//! The marker, room id and epoch are authenticated as GCM additional
+//! data. The sender is not carried in the frame: it is the peer device
+//! whose (DTLS-authenticated) data channel the frame arrived on, so one
+//! member cannot pass frames off as another's.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use js_sys::Uint8Array;
+use message::datachannel::SenderKey;
+use message::{RoomId, UserId};
+
+use super::PeerKey;
+use super::data_channel::GROUP_ENCRYPTED_MARKER;
+use super::encryption::GCM_NONCE_SIZE;
+use super::frame_crypto::{gcm_params, import_key, web_crypto};
+
+/// Raw AES-256 sender key length.
+pub(super) const SENDER_KEY_LEN: usize = 32;
+
+/// Authenticated frame header: marker, room id and epoch.
+pub(super) const GROUP_HEADER_LEN: usize = 1 + 16 + 4;
+
+/// Smallest possible group frame: header, IV and an empty GCM payload.
+pub(super) const MIN_GROUP_FRAME_LEN: usize = GROUP_HEADER_LEN + GCM_NONCE_SIZE + 16;
+
+/// Epochs remembered per sender and room, so messages already in
+/// flight under the previous key still decrypt after a rotation.
+const EPOCHS_PER_SENDER: usize = 2;
+
+/// Frames held per sender device while waiting for the key they were sent
+/// under. A sender's new key and its first message under that key
+/// travel on the same channel but are encrypted concurrently, so the
+/// message can overtake the key.
+pub(super) const PENDING_GROUP_FRAMES_LIMIT: usize = 32;
+
+/// Assemble a group frame header.
+#[must_use]
+pub(super) fn group_frame_header(room_id: &RoomId, epoch: u32) -> [u8; GROUP_HEADER_LEN] {
+  let mut header = [0u8; GROUP_HEADER_LEN];
+  header[0] = GROUP_ENCRYPTED_MARKER;
+  header[1..17].copy_from_slice(room_id.0.as_bytes());
+  header[17..].copy_from_slice(&epoch.to_be_bytes());
+  header
+}
+
+/// Borrowed view of a group frame.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct GroupFrame<'a> {
+  pub(super) room_id: RoomId,
+  pub(super) epoch: u32,
+  /// Additional data (the whole header).
+  pub(super) header: &'a [u8],
+  pub(super) iv: &'a [u8],
+  pub(super) ciphertext: &'a [u8],
+}
+
+/// Split a group frame into its parts. Returns `None` for frames that
+/// are too short or carry the wrong marker.
+#[must_use]
+pub(super) fn unpack_group_frame(frame: &[u8]) -> Option<GroupFrame<'_>> {
+  if frame.len() < MIN_GROUP_FRAME_LEN || frame[0] != GROUP_ENCRYPTED_MARKER {
+    return None;
+  }
+  let (header, rest) = frame.split_at(GROUP_HEADER_LEN);
+  let (iv, ciphertext) = rest.split_at(GCM_NONCE_SIZE);
+  let room_id = RoomId(uuid::Uuid::from_bytes(header[1..17].try_into().ok()?));
+  let epoch = u32::from_be_bytes(header[17..].try_into().ok()?);
+  Some(GroupFrame {
+    room_id,
+    epoch,
+    header,
+    iv,
+    ciphertext,
+  })
+}
+
+/// The local member's current key for one room.
+struct LocalSenderKey {
+  epoch: u32,
+  raw: Vec<u8>,
+  key: web_sys::CryptoKey,
+  /// Set on a membership change. A retired key is never used again;
+  /// it is only kept so the next key continues its epoch count.
+  retired: bool,
+}
+
+/// Sender keys for the local member and every other room member's
+/// devices. Remote senders are keyed by user and device.
+#[derive(Default)]
+pub(super) struct GroupKeyRing {
+  local: HashMap<RoomId, LocalSenderKey>,
+  remote: HashMap<(RoomId, PeerKey), Vec<(u32, web_sys::CryptoKey)>>,
+  pending: HashMap<PeerKey, VecDeque<Vec<u8>>>,
+}
+
+impl GroupKeyRing {
+  /// The key currently announced in `room_id`, if any.
+  #[must_use]
+  pub(super) fn local_sender_key(&self, room_id: &RoomId) -> Option<SenderKey> {
+    self.current_local(room_id).map(|local| SenderKey {
+      room_id: room_id.clone(),
+      device_id: crate::devices::local_device_id(),
+      epoch: local.epoch,
+      key: local.raw.clone(),
+    })
+  }
+
+  /// Rooms the local member has a sender key for.
+  #[must_use]
+  pub(super) fn local_rooms(&self) -> Vec<RoomId> {
+    self
+      .local
+      .iter()
+      .filter(|(_, local)| !local.retired)
+      .map(|(room_id, _)| room_id.clone())
+      .collect()
+  }
+
+  /// Stop using the local key for `room_id` after a membership change.
+  pub(super) fn retire_local(&mut self, room_id: &RoomId) {
+    if let Some(local) = self.local.get_mut(room_id) {
+      local.retired = true;
+    }
+  }
+
+  /// Whether a key from the `sender` device for `epoch` in `room_id`
+  /// is installed.
+  #[must_use]
+  pub(super) fn has_remote_key(&self, room_id: &RoomId, sender: &PeerKey, epoch: u32) -> bool {
+    self.remote_key(room_id, sender, epoch).is_some()
+  }
+
+  /// Forget the keys of every device of a member who left `room_id`.
+  pub(super) fn remove_sender(&mut self, room_id: &RoomId, sender: &UserId) {
+    self
+      .remote
+      .retain(|(room, key), _| room != room_id || key.user_id != *sender);
+  }
+
+  /// Forget every key for a room the local member left.
+  pub(super) fn forget_room(&mut self, room_id: &RoomId) {
+    self.local.remove(room_id);
+    self.remote.retain(|(room, _), _| room != room_id);
+  }
+
+  /// Hold a frame until the `sender` device's key for it arrives. The
+  /// oldest frame is dropped once [`PENDING_GROUP_FRAMES_LIMIT`] is
+  /// reached.
+  pub(super) fn enqueue_pending(&mut self, sender: PeerKey, frame: Vec<u8>) {
+    let queue = self.pending.entry(sender).or_default();
+    if queue.len() >= PENDING_GROUP_FRAMES_LIMIT {
+      queue.pop_front();
+    }
+    queue.push_back(frame);
+  }
+
+  /// Take every frame held for the `sender` device.
+  pub(super) fn take_pending(&mut self, sender: &PeerKey) -> Vec<Vec<u8>> {
+    self
+      .pending
+      .remove(sender)
+      .map(Vec::from)
+      .unwrap_or_default()
+  }
+
+  fn current_local(&self, room_id: &RoomId) -> Option<&LocalSenderKey> {
+    self.local.get(room_id).filter(|local| !local.retired)
+  }
+
+  fn remote_key(
+    &self,
+    room_id: &RoomId,
+    sender: &PeerKey,
+    epoch: u32,
+  ) -> Option<web_sys::CryptoKey> {
+    self
+      .remote
+      .get(&(room_id.clone(), sender.clone()))?
+      .iter()
+      .find(|(e, _)| *e == epoch)
+      .map(|(_, key)| key.clone())
+  }
+}
+
+/// Generate a fresh sender key for `room_id` under the next epoch,
+/// replacing the current one, and return it for distribution.
+///
+/// # Errors
+/// Returns an error if WebCrypto is unavailable or rejects the key.
+pub(super) async fn rotate_sender_key(
+  ring: &Rc<RefCell<GroupKeyRing>>,
+  room_id: &RoomId,
+) -> Result<SenderKey, String> {
+  let crypto = web_crypto()?;
+  let raw = Uint8Array::new_with_length(SENDER_KEY_LEN as u32);
+  crypto
+    .get_random_values_with_array_buffer_view(&raw)
+    .map_err(|e| format!("Failed to generate sender key: {:?}", e))?;
+  let raw = raw.to_vec();
+  let key = import_key(&raw).await?;
+
+  let mut ring = ring.borrow_mut();
+  let epoch = ring
+    .local
+    .get(room_id)
+    .map_or(0, |local| local.epoch.wrapping_add(1));
+  ring.local.insert(
+    room_id.clone(),
+    LocalSenderKey {
+      epoch,
+      raw: raw.clone(),
+      key,
+      retired: false,
+    },
+  );
+  Ok(SenderKey {
+    room_id: room_id.clone(),
+    device_id: crate::devices::local_device_id(),
+    epoch,
+    key: raw,
+  })
+}
+
+/// Install a sender key announced by the `sender` device.
+///
+/// # Errors
+/// Returns an error if the key has the wrong length or cannot be
+/// imported.
+pub(super) async fn install_sender_key(
+  ring: &Rc<RefCell<GroupKeyRing>>,
+  sender: PeerKey,
+  sender_key: SenderKey,
+) -> Result<(), String> {
+  if sender_key.key.len() != SENDER_KEY_LEN {
+    return Err(format!(
+      "Sender key from {sender} has invalid length {}",
+      sender_key.key.len()
+    ));
+  }
+  let key = import_key(&sender_key.key).await?;
+  let mut ring = ring.borrow_mut();
+  let keys = ring.remote.entry((sender_key.room_id, sender)).or_default();
+  keys.retain(|(epoch, _)| *epoch != sender_key.epoch);
+  keys.push((sender_key.epoch, key));
+  if keys.len() > EPOCHS_PER_SENDER {
+    keys.remove(0);
+  }
+  Ok(())
+}
+
+/// Encrypt a `[discriminator][bitcode]` plaintext under the local
+/// sender key for `room_id` and return the complete group frame.
+///
+/// # Errors
+/// Returns an error if there is no local key for the room or
+/// encryption fails.
+pub(super) async fn encrypt_group_frame(
+  ring: &Rc<RefCell<GroupKeyRing>>,
+  room_id: &RoomId,
+  plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+  let (epoch, key) = {
+    let ring = ring.borrow();
+    let local = ring
+      .current_local(room_id)
+      .ok_or("No sender key for this room")?;
+    (local.epoch, local.key.clone())
+  };
+  let crypto = web_crypto()?;
+  let iv = Uint8Array::new_with_length(GCM_NONCE_SIZE as u32);
+  crypto
+    .get_random_values_with_array_buffer_view(&iv)
+    .map_err(|e| format!("Failed to generate IV: {:?}", e))?;
+  let iv = iv.to_vec();
+
+  let header = group_frame_header(room_id, epoch);
+  let algo = gcm_params(&iv, &header).map_err(|_| "Failed to build AES-GCM parameters")?;
+  let encrypted = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .encrypt_with_object_and_buffer_source(&algo, &key, &Uint8Array::from(plaintext).buffer())
+      .map_err(|e| format!("Failed to call encrypt: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Group encryption failed: {:?}", e))?;
+  let ciphertext = Uint8Array::new(&encrypted).to_vec();
+
+  let mut frame = Vec::with_capacity(GROUP_HEADER_LEN + GCM_NONCE_SIZE + ciphertext.len());
+  frame.extend_from_slice(&header);
+  frame.extend_from_slice(&iv);
+  frame.extend_from_slice(&ciphertext);
+  Ok(frame)
+}
+
+/// Decrypt a group frame sent by the `sender` device with the matching
+/// key.
+///
+/// # Errors
+/// Returns an error if the key is unknown or authentication fails.
+pub(super) async fn decrypt_group_frame(
+  ring: &Rc<RefCell<GroupKeyRing>>,
+  sender: &PeerKey,
+  frame: &GroupFrame<'_>,
+) -> Result<Vec<u8>, String> {
+  let key = ring
+    .borrow()
+    .remote_key(&frame.room_id, sender, frame.epoch)
+    .ok_or("No sender key for this epoch")?;
+  let crypto = web_crypto()?;
+  let algo =
+    gcm_params(frame.iv, frame.header).map_err(|_| "Failed to build AES-GCM parameters")?;
+  let decrypted = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .decrypt_with_object_and_buffer_source(
+        &algo,
+        &key,
+        &Uint8Array::from(frame.ciphertext).buffer(),
+      )
+      .map_err(|e| format!("Failed to call decrypt: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Group decryption failed: {:?}", e))?;
+  Ok(Uint8Array::new(&decrypted).to_vec())
+}
@@ -0,0 +1,311 @@
+//! Room sender-key distribution and group frame I/O for
+//! [`WebRtcManager`].
+//!
+//! The key material and frame layout live in [`group_crypto`]; this
+//! module ties them to room membership:
+//!
+//! * [`WebRtcManager::send_room_message`] encrypts a room message once
+//!   and sends the same frame to every member (used by the chat
+//!   manager's room fan-out).
+//! * [`WebRtcManager::rekey_room`] starts a new membership epoch on
+//!   every `RoomMemberUpdate` that adds or removes members. The new
+//!   key is generated by the next room send, which hands it out before
+//!   encrypting, so nothing goes out under the old key afterwards.
+//! * [`WebRtcManager::handle_sender_key`] /
+//!   [`WebRtcManager::handle_group_frame`] are the receive side. Both
+//!   only accept input from peers that are currently members of the
+//!   room in question. Remote keys are held per sender device, taken
+//!   from the connection the key arrived on.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use leptos::prelude::WithUntracked;
+use message::datachannel::{ChannelClass, DataChannelMessage, SenderKey};
+use message::error::{ErrorCategory, ErrorCode, ErrorModule};
+use message::{RoomId, UserId};
+
+use super::crypto_ops::plaintext_frame;
+use super::group_crypto::{self, GroupKeyRing};
+use super::{BroadcastResult, PeerKey, WebRtcError, WebRtcManager};
+
+impl WebRtcManager {
+  /// Send a message to a room conversation.
+  ///
+  /// The message is encrypted once under the local sender key for
+  /// `room_id` and the resulting frame is sent unchanged to each of
+  /// `peers`. The first message in a room, and the first after a
+  /// [`Self::rekey_room`], generates the key and hands it to the
+  /// current members before the frame goes out.
+  ///
+  /// # Errors
+  /// Returns an error if the sender key cannot be generated or the
+  /// message cannot be encrypted. Per-peer send failures are reported
+  /// in [`BroadcastResult::failed_peers`].
+  pub async fn send_room_message(
+    &self,
+    room_id: &RoomId,
+    peers: &[UserId],
+    msg: &DataChannelMessage,
+  ) -> Result<BroadcastResult, WebRtcError> {
+    let ring = self.group_key_ring();
+    // A membership change can retire the new key while it is still
+    // being handed out; rotate again until one is current.
+    while ring.borrow().local_sender_key(room_id).is_none() {
+      self.rotate_room_key(room_id).await?;
+    }
+    let frame = group_crypto::encrypt_group_frame(&ring, room_id, &plaintext_frame(msg))
+      .await
+      .map_err(|e| {
+        WebRtcError::new(
+          ErrorCode::new(ErrorModule::Cht, ErrorCategory::Security, 1),
+          format!("Encryption failed: {}", e),
+          None,
+        )
+      })?;
+
+    let mut sent = 0;
+    let mut failed_peers = Vec::new();
+    for peer_id in peers {
+      match self.send_group_frame(peer_id, &frame, msg.channel_class()) {
+        Ok(()) => sent += 1,
+        Err(e) => {
+          web_sys::console::warn_1(&format!("[webrtc] Room send to peer failed: {e}").into());
+          failed_peers.push((peer_id.clone(), e));
+        }
+      }
+    }
+    Ok(BroadcastResult { sent, failed_peers })
+  }
+
+  /// Start a new membership epoch in `room_id` after members joined or
+  /// left. Keys of `removed` members are dropped and our own key is
+  /// retired, both before this returns: a room send racing the update
+  /// waits for a new key, which goes to the current members only.
+  pub fn rekey_room(&self, room_id: &RoomId, removed: &[UserId]) {
+    let ring = self.group_key_ring();
+    let mut ring = ring.borrow_mut();
+    for user_id in removed {
+      ring.remove_sender(room_id, user_id);
+    }
+    ring.retire_local(room_id);
+  }
+
+  /// Forget every key for a room the local user left.
+  pub fn forget_room_keys(&self, room_id: &RoomId) {
+    self.group_key_ring().borrow_mut().forget_room(room_id);
+  }
+
+  /// Send our sender key for every room shared with a peer device whose
+  /// data channel just became encrypted.
+  pub(super) fn send_sender_keys_to(&self, peer: &PeerKey) {
+    let keys: Vec<SenderKey> = {
+      let ring = self.group_key_ring();
+      let ring = ring.borrow();
+      ring
+        .local_rooms()
+        .into_iter()
+        .filter(|room_id| self.is_room_member(room_id, &peer.user_id))
+        .filter_map(|room_id| ring.local_sender_key(&room_id))
+        .collect()
+    };
+    if keys.is_empty() {
+      return;
+    }
+    let manager = self.clone();
+    let peer = peer.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      for key in keys {
+        let msg = DataChannelMessage::SenderKey(key);
+        if let Err(e) = manager
+          .send_encrypted_data_channel_message_to(&peer, &msg)
+          .await
+        {
+          web_sys::console::warn_1(&format!("[webrtc] Failed to send sender key: {e}").into());
+        }
+      }
+    });
+  }
+
+  /// Install a sender key announced by a room member's device, then
+  /// replay any of its frames that arrived ahead of it.
+  ///
+  /// A key naming a device other than the one at the far end of the
+  /// connection is dropped, so one device cannot replace the key of
+  /// another.
+  pub(super) fn handle_sender_key(&self, peer: PeerKey, sender_key: SenderKey) {
+    if !self.is_room_member(&sender_key.room_id, &peer.user_id) {
+      web_sys::console::warn_1(
+        &format!(
+          "[webrtc] Ignoring sender key from non-member {} for room {}",
+          peer, sender_key.room_id
+        )
+        .into(),
+      );
+      return;
+    }
+    let sender = self.inner.borrow().device_key(&peer);
+    if sender
+      .device_id
+      .is_some_and(|device| device != sender_key.device_id)
+    {
+      web_sys::console::warn_1(
+        &format!(
+          "[webrtc] Ignoring sender key for device {} from peer {}",
+          sender_key.device_id, sender
+        )
+        .into(),
+      );
+      return;
+    }
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let ring = manager.group_key_ring();
+      if let Err(e) = group_crypto::install_sender_key(&ring, sender.clone(), sender_key).await {
+        web_sys::console::warn_1(&format!("[webrtc] {e}").into());
+        return;
+      }
+      let pending = ring.borrow_mut().take_pending(&sender);
+      for frame in pending {
+        manager.handle_group_frame(peer.clone(), frame);
+      }
+    });
+  }
+
+  /// Handle a group envelope frame arriving on the connection keyed by
+  /// `peer`.
+  ///
+  /// Frames from peers that are not members of the frame's room are
+  /// dropped. Frames whose key has not arrived yet are held (bounded)
+  /// and replayed by [`Self::handle_sender_key`].
+  pub(super) fn handle_group_frame(&self, peer: PeerKey, bytes: Vec<u8>) {
+    let ring = self.group_key_ring();
+    let sender = self.inner.borrow().device_key(&peer);
+    let has_key = {
+      let Some(frame) = group_crypto::unpack_group_frame(&bytes) else {
+        web_sys::console::warn_1(
+          &format!(
+            "[webrtc] Dropping malformed group frame from peer {} ({} B)",
+            peer,
+            bytes.len()
+          )
+          .into(),
+        );
+        return;
+      };
+      if !self.is_room_member(&frame.room_id, &peer.user_id) {
+        web_sys::console::warn_1(
+          &format!(
+            "[webrtc] Dropping group frame from non-member {} for room {}",
+            peer, frame.room_id
+          )
+          .into(),
+        );
+        return;
+      }
+      ring
+        .borrow()
+        .has_remote_key(&frame.room_id, &sender, frame.epoch)
+    };
+    if !has_key {
+      ring.borrow_mut().enqueue_pending(sender, bytes);
+      return;
+    }
+
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let Some(frame) = group_crypto::unpack_group_frame(&bytes) else {
+        return;
+      };
+      match group_crypto::decrypt_group_frame(&ring, &sender, &frame).await {
+        Ok(plaintext) => manager.dispatch_decrypted_frame(peer, &plaintext),
+        Err(e) => web_sys::console::warn_1(
+          &format!(
+            "[webrtc] Failed to decrypt group frame from peer {}: {}",
+            sender, e
+          )
+          .into(),
+        ),
+      }
+    });
+  }
+
+  /// Generate a new local sender key for `room_id` and hand it to every
+  /// other member with an encrypted channel. Sends are awaited so the
+  /// key is queued on each channel before any frame encrypted under it.
+  async fn rotate_room_key(&self, room_id: &RoomId) -> Result<(), WebRtcError> {
+    let key = group_crypto::rotate_sender_key(&self.group_key_ring(), room_id)
+      .await
+      .map_err(|e| {
+        WebRtcError::new(
+          ErrorCode::new(ErrorModule::E2e, ErrorCategory::Client, 7),
+          format!("Sender key generation failed: {}", e),
+          None,
+        )
+      })?;
+    let me = self.app_state.current_user_id();
+    let members: Vec<UserId> = self.app_state.room_members.with_untracked(|map| {
+      map
+        .get(room_id)
+        .map(|list| {
+          list
+            .iter()
+            .map(|m| m.user_id.clone())
+            .filter(|uid| me.as_ref() != Some(uid))
+            .collect()
+        })
+        .unwrap_or_default()
+    });
+    let msg = DataChannelMessage::SenderKey(key);
+    for peer_id in members {
+      if !self.has_encryption_key(&peer_id) {
+        // Delivered by `send_sender_keys_to` once the handshake ends.
+        continue;
+      }
+      if let Err(e) = self
+        .send_encrypted_data_channel_message(peer_id, &msg)
+        .await
+      {
+        web_sys::console::warn_1(&format!("[webrtc] Failed to send sender key: {e}").into());
+      }
+    }
+    Ok(())
+  }
+
+  /// Send a ready-made group frame on the channel for `class` to every
+  /// connected device of the peer. Succeeds if any device was reached.
+  fn send_group_frame(
+    &self,
+    peer_id: &UserId,
+    frame: &[u8],
+    class: ChannelClass,
+  ) -> Result<(), WebRtcError> {
+    let peers = self.inner.borrow().peer_keys(peer_id);
+    peers
+      .iter()
+      .map(|peer| {
+        let dc = self.routed_data_channel(peer, class)?;
+        dc.send_raw(frame).map_err(|e| {
+          WebRtcError::new(
+            ErrorCode::new(ErrorModule::Cht, ErrorCategory::Network, 1),
+            format!("DataChannel send failed: {}", e),
+            Some(peer_id.clone()),
+          )
+        })
+      })
+      .reduce(|acc, result| acc.or(result))
+      .unwrap_or_else(|| Err(WebRtcError::peer_not_found(peer_id.clone())))
+  }
+
+  fn is_room_member(&self, room_id: &RoomId, user_id: &UserId) -> bool {
+    self.app_state.room_members.with_untracked(|map| {
+      map
+        .get(room_id)
+        .is_some_and(|list| list.iter().any(|m| m.user_id == *user_id))
+    })
+  }
+
+  fn group_key_ring(&self) -> Rc<RefCell<GroupKeyRing>> {
+    Rc::clone(&self.inner.borrow().group_keys)
+  }
+}
@@ -7,35 +7,173 @@
 //!
 //! # Responsibilities
 //!
-//! * [`WebRtcManager::handle_ecdh_key`] — process an inbound
-//!   `EcdhKeyExchange`, import the peer's public key, and mirror the
-//!   completion into reactive UI state.
+//! * [`WebRtcManager::handle_signed_ecdh_key`] — authenticate an
+//!   inbound `EcdhKeyExchange` against the peer's identity key, check
+//!   the identity against the pinned one, then hand the ephemeral key
+//!   to [`WebRtcManager::handle_ecdh_key`].
+//! * [`WebRtcManager::handle_ecdh_key`] — import the peer's public key
+//!   and mirror the completion into reactive UI state.
+//! * [`WebRtcManager::sign_ecdh_key`] — wrap a locally-generated public
+//!   key in an `EcdhKeyExchange` signed by our identity key.
 //! * [`WebRtcManager::send_datachannel_ecdh_key_direct`] — push a
-//!   locally-generated public key over an already-open DataChannel.
-//! * [`WebRtcManager::buffer_pending_ecdh_key`] — stash a key while
-//!   the DataChannel is still opening.
+//!   signed exchange over an already-open DataChannel.
+//! * [`WebRtcManager::buffer_pending_ecdh_key`] — stash a signed
+//!   exchange while the DataChannel is still opening.
 //! * [`WebRtcManager::handle_data_channel_open`] — flush any buffered
 //!   key once the channel reaches the `Open` state.
 //! * [`WebRtcManager::prune_expired_ecdh`] — evict stale pending
 //!   handshakes so the UI can surface a `handshake_timed_out` flag.
 
+use super::identity::{self, IdentityCheck, IdentityKeyPair};
 use super::{
-  DataChannelState, ECDH_EXCHANGE_TIMEOUT_MS, PeerCrypto, PendingEcdh, WebRtcError, WebRtcManager,
+  DataChannelState, ECDH_EXCHANGE_TIMEOUT_MS, ECDH_MAX_CLOCK_SKEW_NANOS, PeerCrypto, PeerKey,
+  PendingEcdh, WebRtcError, WebRtcManager,
 };
-use leptos::prelude::Update;
+use leptos::prelude::{GetUntracked, Update};
 use message::UserId;
+use message::datachannel::EcdhKeyExchange;
 use message::error::{ErrorCategory, ErrorCode, ErrorModule};
 
 impl WebRtcManager {
-  /// Handle an incoming ECDH public key (received over DataChannel or signaling).
+  /// Handle an incoming signed `EcdhKeyExchange` from a peer.
+  ///
+  /// The signature must verify against the identity key carried in the
+  /// message, and the signed timestamp must be within
+  /// [`ECDH_MAX_CLOCK_SKEW_NANOS`] of the local clock and newer than the
+  /// last exchange accepted from this peer device; otherwise the
+  /// exchange is rejected before the ephemeral key is imported. The identity key is
+  /// then compared with the one pinned for the peer device the
+  /// connection is bound to. A changed key does not abort the handshake
+  /// — the user may have reinstalled — but raises `identity_changed` in
+  /// the UI state so the chat view can warn loudly until the user
+  /// accepts it via [`Self::accept_peer_identity`].
+  pub async fn handle_signed_ecdh_key(
+    &self,
+    peer: PeerKey,
+    exchange: &EcdhKeyExchange,
+  ) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
+    let local_id = self.local_user_id(&peer_id)?;
+    let payload = exchange.signing_payload(&peer_id, &local_id);
+    let valid = identity::verify_signature(&exchange.identity_key, &payload, &exchange.signature)
+      .await
+      .unwrap_or_else(|e| {
+        web_sys::console::warn_1(
+          &format!(
+            "[identity] Malformed identity key from peer {}: {}",
+            peer_id, e
+          )
+          .into(),
+        );
+        false
+      });
+    if !valid {
+      return Err(WebRtcError::new(
+        ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 3),
+        "Key exchange signature verification failed",
+        Some(peer_id),
+      ));
+    }
+
+    // Freshness and pinning are tracked per device.
+    let device_key = self.inner.borrow().device_key(&peer);
+    let device = device_key.device_id;
+
+    let now_nanos =
+      u64::try_from(chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)).unwrap_or(0);
+    let last_accepted = self
+      .inner
+      .borrow()
+      .ecdh_last_timestamps
+      .get(&device_key)
+      .copied();
+    if !is_fresh_exchange(exchange.timestamp_nanos, now_nanos, last_accepted) {
+      return Err(WebRtcError::new(
+        ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 4),
+        "Key exchange is stale or replayed",
+        Some(peer_id),
+      ));
+    }
+    self
+      .inner
+      .borrow_mut()
+      .ecdh_last_timestamps
+      .insert(device_key.clone(), exchange.timestamp_nanos);
+
+    let check = {
+      let mut inner = self.inner.borrow_mut();
+      inner
+        .peer_identity_keys
+        .insert(device_key, exchange.identity_key.clone());
+      inner
+        .known_identities
+        .check(&peer_id, device, &exchange.identity_key)
+    };
+    if check == IdentityCheck::Changed {
+      web_sys::console::error_1(
+        &format!(
+          "[identity] Identity key of peer {} has CHANGED since it was first seen",
+          peer_id
+        )
+        .into(),
+      );
+    }
+
+    let local_identity = self.local_identity(&peer_id).await?;
+    let safety_number = identity::safety_number(
+      &local_id,
+      local_identity.public_key(),
+      &peer_id,
+      &exchange.identity_key,
+    );
+    self
+      .app_state
+      .webrtc_state
+      .update(|s| s.set_peer_identity(&peer_id, safety_number, check == IdentityCheck::Changed));
+
+    self.handle_ecdh_key(peer, &exchange.public_key).await
+  }
+
+  /// Pin the identity keys most recently presented by each device of
+  /// `peer_id`, clearing the key-change warning. Called when the user
+  /// confirms the new safety number.
+  pub fn accept_peer_identity(&self, peer_id: &UserId) {
+    {
+      let mut inner = self.inner.borrow_mut();
+      let presented: Vec<(PeerKey, Vec<u8>)> = inner
+        .peer_identity_keys
+        .iter()
+        .filter(|(peer, _)| peer.user_id == *peer_id)
+        .map(|(peer, key)| (peer.clone(), key.clone()))
+        .collect();
+      if presented.is_empty() {
+        return;
+      }
+      for (peer, key) in presented {
+        inner.known_identities.accept(peer_id, peer.device_id, &key);
+      }
+    }
+    self
+      .app_state
+      .webrtc_state
+      .update(|s| s.clear_identity_changed(peer_id));
+  }
+
+  /// Handle an incoming ECDH public key once the exchange carrying it
+  /// has been authenticated by [`Self::handle_signed_ecdh_key`].
   ///
   /// The `key_data` parameter contains the raw public key bytes (P-256 raw
   /// format, 65 bytes) received directly from the `EcdhKeyExchange` message.
-  pub async fn handle_ecdh_key(&self, peer_id: UserId, key_data: &[u8]) -> Result<(), WebRtcError> {
+  pub(super) async fn handle_ecdh_key(
+    &self,
+    peer: PeerKey,
+    key_data: &[u8],
+  ) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
     let public_key = key_data;
 
     // Check if we already have crypto for this peer (scoped borrow)
-    let has_existing = self.inner.borrow().crypto.contains_key(&peer_id);
+    let has_existing = self.inner.borrow().crypto.contains_key(&peer);
 
     if has_existing {
       // Re-keying: remove, update, and re-insert to avoid holding borrow across await.
@@ -57,7 +195,7 @@ impl WebRtcManager {
         .inner
         .borrow_mut()
         .crypto
-        .remove(&peer_id)
+        .remove(&peer)
         .ok_or_else(|| {
           WebRtcError::new(
             ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
@@ -91,20 +229,14 @@ impl WebRtcManager {
           )
         })?;
 
-        self
-          .inner
-          .borrow_mut()
-          .crypto
-          .insert(peer_id.clone(), crypto);
+        self.inner.borrow_mut().crypto.insert(peer.clone(), crypto);
 
         // Send our public key back so the peer can derive the shared secret
-        self.send_datachannel_ecdh_key_direct(peer_id.clone(), &our_public_key);
-      } else {
         self
-          .inner
-          .borrow_mut()
-          .crypto
-          .insert(peer_id.clone(), crypto);
+          .send_signed_ecdh_key(peer.clone(), our_public_key)
+          .await;
+      } else {
+        self.inner.borrow_mut().crypto.insert(peer.clone(), crypto);
       }
     } else {
       // First time: create crypto and import peer's public key (all async, no borrow held)
@@ -136,14 +268,12 @@ impl WebRtcManager {
         )
       })?;
 
-      self
-        .inner
-        .borrow_mut()
-        .crypto
-        .insert(peer_id.clone(), crypto);
+      self.inner.borrow_mut().crypto.insert(peer.clone(), crypto);
 
       // Send our ECDH key back via DataChannel (if channel is open)
-      self.send_datachannel_ecdh_key_direct(peer_id.clone(), &our_public_key);
+      self
+        .send_signed_ecdh_key(peer.clone(), our_public_key)
+        .await;
     }
 
     // Mirror the key-exchange completion into the reactive UI
@@ -159,7 +289,10 @@ impl WebRtcManager {
     // queued while the handshake was in flight. Must happen after the
     // shared key is installed so `send_encrypted_data_channel_message`
     // succeeds on the drained frames.
-    self.flush_pending_broadcast(&peer_id);
+    self.flush_pending_broadcast(&peer);
+    self.send_media_key_to(&peer);
+    self.send_sender_keys_to(&peer);
+    self.open_extra_channels(&peer);
 
     // After the encryption channel is established, retry any inbound
     // file transfers from this peer that are still in `Paused` status.
@@ -171,16 +304,96 @@ impl WebRtcManager {
       file_mgr.try_resume_inbound_from_peer(&peer_id);
     }
 
-    web_sys::console::log_1(
-      &format!("[webrtc] Completed ECDH exchange with peer {}", peer_id).into(),
-    );
+    web_sys::console::log_1(&format!("[webrtc] Completed ECDH exchange with peer {}", peer).into());
 
     Ok(())
   }
 
-  /// Send a raw ECDH public key over DataChannel to a peer.
+  /// Return the local user's identity key pair, loading (or creating)
+  /// it on first use and caching it for the session.
   ///
-  /// The `public_key` is in raw P-256 format (65 bytes, uncompressed point).
+  /// The cache is keyed by user id so a re-login as a different account
+  /// never signs with the previous account's key.
+  pub(super) async fn local_identity(
+    &self,
+    peer_id: &UserId,
+  ) -> Result<IdentityKeyPair, WebRtcError> {
+    let local_id = self.local_user_id(peer_id)?;
+    if let Some((cached_id, identity)) = self.inner.borrow().identity.as_ref()
+      && *cached_id == local_id
+    {
+      return Ok(identity.clone());
+    }
+
+    let identity = IdentityKeyPair::load_or_create(&local_id)
+      .await
+      .map_err(|e| {
+        WebRtcError::new(
+          ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
+          format!("Failed to load identity key: {}", e),
+          Some(peer_id.clone()),
+        )
+      })?;
+    self.inner.borrow_mut().identity = Some((local_id, identity.clone()));
+    Ok(identity)
+  }
+
+  /// The authenticated local user id, required to bind signatures to
+  /// both endpoints.
+  fn local_user_id(&self, peer_id: &UserId) -> Result<UserId, WebRtcError> {
+    self
+      .app_state
+      .auth
+      .get_untracked()
+      .map(|auth| auth.user_id)
+      .ok_or_else(|| {
+        WebRtcError::new(
+          ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
+          "Cannot sign key exchange while logged out",
+          Some(peer_id.clone()),
+        )
+      })
+  }
+
+  /// Wrap our ephemeral `public_key` in an `EcdhKeyExchange` signed by
+  /// the local identity key for delivery to `peer_id`.
+  pub(super) async fn sign_ecdh_key(
+    &self,
+    peer_id: &UserId,
+    public_key: Vec<u8>,
+  ) -> Result<EcdhKeyExchange, WebRtcError> {
+    let local_id = self.local_user_id(peer_id)?;
+    let identity = self.local_identity(peer_id).await?;
+
+    let mut exchange = EcdhKeyExchange {
+      public_key,
+      timestamp_nanos: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
+      identity_key: identity.public_key().to_vec(),
+      signature: Vec::new(),
+    };
+    let payload = exchange.signing_payload(&local_id, peer_id);
+    exchange.signature = identity.sign(&payload).await.map_err(|e| {
+      WebRtcError::new(
+        ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
+        format!("Failed to sign ECDH key: {}", e),
+        Some(peer_id.clone()),
+      )
+    })?;
+    Ok(exchange)
+  }
+
+  /// Sign `public_key` and send it to the peer. Failures are logged —
+  /// the handshake then times out through the usual pending-key path.
+  async fn send_signed_ecdh_key(&self, peer: PeerKey, public_key: Vec<u8>) {
+    match self.sign_ecdh_key(&peer.user_id, public_key).await {
+      Ok(exchange) => self.send_datachannel_ecdh_key_direct(peer, exchange),
+      Err(e) => web_sys::console::error_1(
+        &format!("[webrtc] Failed to sign ECDH key for peer {}: {}", peer, e).into(),
+      ),
+    }
+  }
+
+  /// Send a signed ECDH key exchange over DataChannel to a peer.
   ///
   /// P2-11 (Review Round 4): both call sites (`handle_data_channel_open`
   /// and `handle_ecdh_key`) only reach this helper after the DataChannel
@@ -193,12 +406,12 @@ impl WebRtcManager {
   ///    so the eventual `DataChannel.onopen` callback flushes it. This
   ///    keeps the handshake eventually-consistent even if a future
   ///    refactor changes the call order.
-  pub(super) fn send_datachannel_ecdh_key_direct(&self, peer_id: UserId, public_key: &[u8]) {
-    use message::datachannel::{DataChannelMessage, EcdhKeyExchange};
+  pub(super) fn send_datachannel_ecdh_key_direct(&self, peer: PeerKey, exchange: EcdhKeyExchange) {
+    use message::datachannel::DataChannelMessage;
     use web_sys::RtcDataChannelState;
 
     let inner = self.inner.borrow();
-    let Some(pc) = inner.connections.get(&peer_id) else {
+    let Some(pc) = inner.connections.get(&peer) else {
       return;
     };
 
@@ -206,39 +419,36 @@ impl WebRtcManager {
       // DataChannel not yet created. Drop the borrow before mutating
       // `pending_ecdh_keys` to avoid a nested borrow.
       drop(inner);
-      self.buffer_pending_ecdh_key(peer_id, public_key.to_vec());
+      self.buffer_pending_ecdh_key(peer, exchange);
       return;
     };
 
     if dc.ready_state() != RtcDataChannelState::Open {
       // DataChannel exists but not yet Open. Same safety net as above.
       drop(inner);
-      self.buffer_pending_ecdh_key(peer_id, public_key.to_vec());
+      self.buffer_pending_ecdh_key(peer, exchange);
       return;
     }
 
-    let msg = DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
-      public_key: public_key.to_vec(),
-      timestamp_nanos: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
-    });
+    let msg = DataChannelMessage::EcdhKeyExchange(exchange);
     if let Err(e) = dc.send_message(&msg) {
       web_sys::console::warn_1(&format!("[webrtc] Failed to send ECDH key: {}", e).into());
     } else {
-      web_sys::console::log_1(&format!("[webrtc] Sent ECDH key to peer {}", peer_id).into());
+      web_sys::console::log_1(&format!("[webrtc] Sent ECDH key to peer {}", peer).into());
     }
   }
 
-  /// Buffer an ECDH public key into `pending_ecdh_keys` so
+  /// Buffer a signed ECDH exchange into `pending_ecdh_keys` so
   /// [`WebRtcManager::handle_data_channel_open`] flushes it once the
   /// DataChannel transitions to the `Open` state. Used as the release-
   /// build fallback in [`WebRtcManager::send_datachannel_ecdh_key_direct`]
   /// when the DataChannel is unexpectedly not open.
-  pub(super) fn buffer_pending_ecdh_key(&self, peer_id: UserId, public_key: Vec<u8>) {
+  pub(super) fn buffer_pending_ecdh_key(&self, peer: PeerKey, exchange: EcdhKeyExchange) {
     let mut inner = self.inner.borrow_mut();
     inner.pending_ecdh_keys.insert(
-      peer_id,
+      peer,
       PendingEcdh {
-        public_key,
+        exchange,
         started_at_ms: js_sys::Date::now(),
       },
     );
@@ -250,20 +460,18 @@ impl WebRtcManager {
   /// key exists and no crypto has been established yet (answerer side
   /// race condition), proactively initiates the ECDH exchange so the
   /// handshake is not stuck waiting for the remote peer's key.
-  pub(super) fn handle_data_channel_open(&self, peer_id: UserId) {
-    web_sys::console::log_1(&format!("[webrtc] DataChannel opened for peer {}", peer_id).into());
+  pub(super) fn handle_data_channel_open(&self, peer: PeerKey) {
+    let peer_id = peer.user_id.clone();
+    web_sys::console::log_1(&format!("[webrtc] DataChannel opened for peer {}", peer).into());
 
     // Send pending ECDH key if available
-    let key_data = {
+    let pending = {
       let mut inner = self.inner.borrow_mut();
-      inner
-        .pending_ecdh_keys
-        .remove(&peer_id)
-        .map(|p| p.public_key)
+      inner.pending_ecdh_keys.remove(&peer).map(|p| p.exchange)
     };
 
-    if let Some(key_data) = key_data {
-      self.send_datachannel_ecdh_key_direct(peer_id.clone(), &key_data);
+    if let Some(exchange) = pending {
+      self.send_datachannel_ecdh_key_direct(peer.clone(), exchange);
     } else {
       // No pending key found. This happens on the answerer side (which
       // never calls `initiate_ecdh_exchange`) or when the initiator's
@@ -272,13 +480,13 @@ impl WebRtcManager {
       // proactively start the ECDH exchange so the handshake completes
       // even if the remote side's key is delayed or lost.
       let inner = self.inner.borrow();
-      let has_crypto = inner.crypto.contains_key(&peer_id);
-      let ecdh_running = inner.ecdh_in_progress.contains(&peer_id);
+      let has_crypto = inner.crypto.contains_key(&peer);
+      let ecdh_running = inner.ecdh_in_progress.contains(&peer);
       drop(inner);
 
       if !has_crypto && !ecdh_running {
         let manager = self.clone();
-        let pid = peer_id.clone();
+        let pid = peer.clone();
         wasm_bindgen_futures::spawn_local(async move {
           web_sys::console::log_1(
             &format!(
@@ -329,39 +537,39 @@ impl WebRtcManager {
   ///    *outside* the RefCell borrow, letting UI layers surface a
   ///    "key exchange failed" indicator.
   ///
-  /// Returns the list of peer IDs that were pruned, so callers can log
-  /// or trigger any follow-up actions (e.g. retry via signaling).
-  pub fn prune_expired_ecdh(&self) -> Vec<UserId> {
+  /// Returns the list of peer connections that were pruned, so callers
+  /// can log or trigger any follow-up actions (e.g. retry via signaling).
+  pub fn prune_expired_ecdh(&self) -> Vec<PeerKey> {
     let now = js_sys::Date::now();
-    let expired: Vec<UserId> = {
+    let expired: Vec<PeerKey> = {
       let mut inner = self.inner.borrow_mut();
-      let to_remove: Vec<UserId> = inner
+      let to_remove: Vec<PeerKey> = inner
         .pending_ecdh_keys
         .iter()
         .filter(|(_, pending)| now - pending.started_at_ms >= ECDH_EXCHANGE_TIMEOUT_MS)
         .map(|(id, _)| id.clone())
         .collect();
-      for peer_id in &to_remove {
-        inner.pending_ecdh_keys.remove(peer_id);
+      for peer in &to_remove {
+        inner.pending_ecdh_keys.remove(peer);
         // Task 19.1 C-1 — handshake timed out; drop any queued
         // control frames so we do not leak them for an ECDH that
         // will never complete.
-        inner.pending_broadcast.remove(peer_id);
+        inner.pending_broadcast.remove(peer);
       }
       to_remove
     };
 
     // Update reactive UI state outside the RefCell borrow so signal
     // subscribers cannot observe a partially-mutated InnerManager.
-    for peer_id in &expired {
+    for peer in &expired {
       self
         .app_state
         .webrtc_state
-        .update(|s| s.mark_encryption_timed_out(peer_id));
+        .update(|s| s.mark_encryption_timed_out(&peer.user_id));
       web_sys::console::warn_1(
         &format!(
           "[webrtc] ECDH handshake with peer {} timed out after {}ms",
-          peer_id, ECDH_EXCHANGE_TIMEOUT_MS as u64
+          peer, ECDH_EXCHANGE_TIMEOUT_MS as u64
         )
         .into(),
       );
@@ -370,3 +578,15 @@ impl WebRtcManager {
     expired
   }
 }
+
+/// Whether a signed key exchange stamped `timestamp_nanos` may be
+/// accepted at `now_nanos`: within [`ECDH_MAX_CLOCK_SKEW_NANOS`] of the
+/// local clock and strictly newer than the last accepted exchange.
+pub(super) fn is_fresh_exchange(
+  timestamp_nanos: u64,
+  now_nanos: u64,
+  last_accepted: Option<u64>,
+) -> bool {
+  timestamp_nanos.abs_diff(now_nanos) <= ECDH_MAX_CLOCK_SKEW_NANOS
+    && last_accepted.is_none_or(|last| timestamp_nanos > last)
+}
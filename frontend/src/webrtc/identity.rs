@@ -0,0 +1,552 @@
+//! Long-term identity keys and safety numbers for authenticated E2EE.
+//!
+//! A bare ECDH exchange only proves that *someone* holds the matching
+//! private key; a malicious signaling server sitting in the middle can
+//! hand each side its own key and relay traffic. To close that gap
+//! every user owns a long-term ECDSA P-256 identity key pair:
+//!
+//! * [`IdentityKeyPair`] is generated once per account on each device
+//!   and persisted in IndexedDB as a non-extractable `CryptoKey`, so
+//!   the identity survives page reloads without its private key ever
+//!   being readable by script. Each outgoing `EcdhKeyExchange` carries
+//!   the identity public key plus a signature over the ephemeral key
+//!   (see `EcdhKeyExchange::signing_payload`).
+//! * [`verify_signature`] checks an inbound exchange before its
+//!   ephemeral key is imported.
+//! * [`KnownIdentities`] pins each peer device's identity key on first
+//!   contact (trust on first use) and reports when it later changes,
+//!   which the UI surfaces as a loud warning.
+//! * [`safety_number`] derives a 60-digit code from both identities that
+//!   the two users can compare out of band. It is symmetric, so both
+//!   sides display the same digits.
+
+use std::collections::HashMap;
+
+use js_sys::{Array, Uint8Array};
+use message::{DeviceId, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use super::encryption::CryptoKeyValue;
+
+/// localStorage key prefix under which older builds kept the identity
+/// private key as an exported JWK. Read once to migrate it into
+/// IndexedDB, then removed.
+pub const LEGACY_IDENTITY_STORAGE_PREFIX: &str = "e2ee_identity_key:";
+
+/// localStorage key for the pinned peer identity keys.
+pub const KNOWN_IDENTITIES_STORAGE_KEY: &str = "e2ee_known_identities";
+
+/// Number of SHA-256 iterations per safety-number half. Slows down a
+/// brute-force search for an identity key that collides with a target
+/// safety number while staying well under a millisecond in WASM.
+pub const SAFETY_NUMBER_ITERATIONS: u32 = 1024;
+
+/// Digits contributed by each party to the safety number.
+pub const SAFETY_NUMBER_HALF_DIGITS: usize = 30;
+
+/// Version prefix hashed into every safety-number half so a future
+/// change of derivation never produces colliding codes.
+const SAFETY_NUMBER_VERSION: &[u8] = b"webrtc-e2ee-chat-safety-number-v1";
+
+/// Identity exported to localStorage by older builds.
+#[derive(Debug, Deserialize)]
+struct LegacyIdentity {
+  /// Private key as a JSON Web Key string (`JSON.stringify` output).
+  private_jwk: String,
+  /// Raw uncompressed public key, hex-encoded.
+  public_key: String,
+}
+
+/// The local user's long-term ECDSA P-256 identity key pair.
+#[derive(Debug, Clone)]
+pub struct IdentityKeyPair {
+  /// Non-extractable signing key.
+  private_key: CryptoKeyValue,
+  /// Raw public key (65 bytes, uncompressed point).
+  public_key: Vec<u8>,
+}
+
+impl IdentityKeyPair {
+  /// Load the identity for `user_id` from IndexedDB, generating and
+  /// persisting a fresh one on first use.
+  ///
+  /// The private key is generated non-extractable and stored as a
+  /// `CryptoKey`, so it never exists in a form script can read. An
+  /// identity left in localStorage by an older build is imported once
+  /// and the exported copy deleted.
+  ///
+  /// A stored identity that cannot be read back is treated as missing
+  /// and replaced; peers will then see a key-change warning, which is
+  /// the correct outcome for a lost identity.
+  ///
+  /// # Errors
+  /// Returns an error if the Web Crypto API or IndexedDB is unavailable
+  /// or key generation fails.
+  pub async fn load_or_create(user_id: &UserId) -> Result<Self, String> {
+    let subtle = subtle_crypto()?;
+    if let Some(record) = load_record(user_id).await? {
+      match Self::from_record(&record) {
+        Ok(identity) => return Ok(identity),
+        Err(e) => web_sys::console::warn_1(
+          &format!("[identity] Discarding unreadable identity key: {}", e).into(),
+        ),
+      }
+    }
+
+    let legacy_key = format!("{LEGACY_IDENTITY_STORAGE_PREFIX}{user_id}");
+    let legacy = match crate::utils::load_from_local_storage(&legacy_key) {
+      Some(raw) => Self::import_legacy(&subtle, &raw)
+        .await
+        .inspect_err(|e| {
+          web_sys::console::warn_1(
+            &format!("[identity] Discarding unreadable identity key: {}", e).into(),
+          );
+        })
+        .ok(),
+      None => None,
+    };
+    let identity = match legacy {
+      Some(identity) => identity,
+      None => {
+        let identity = Self::generate(&subtle).await?;
+        web_sys::console::log_1(&"[identity] Generated new identity key".into());
+        identity
+      }
+    };
+    save_record(user_id, &identity.to_record()?).await?;
+    crate::utils::remove_from_local_storage(&legacy_key);
+    Ok(identity)
+  }
+
+  /// Raw identity public key (65 bytes, uncompressed point).
+  #[must_use]
+  pub fn public_key(&self) -> &[u8] {
+    &self.public_key
+  }
+
+  /// Sign `payload` with ECDSA P-256 / SHA-256.
+  ///
+  /// Returns the IEEE P1363 `r || s` encoding (64 bytes).
+  ///
+  /// # Errors
+  /// Returns an error if the signing operation fails.
+  pub async fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, String> {
+    let subtle = subtle_crypto()?;
+    let algo = ecdsa_sign_algorithm()?;
+    let data = Uint8Array::from(payload).buffer();
+
+    let signature = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .sign_with_object_and_buffer_source(&algo, self.private_key.as_crypto_key(), &data)
+        .map_err(|e| format!("Failed to call sign: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Signing failed: {:?}", e))?;
+
+    Ok(Uint8Array::new(&signature).to_vec())
+  }
+
+  /// Generate a key pair whose private half is non-extractable. The
+  /// public half of a generated pair is always exportable.
+  async fn generate(subtle: &web_sys::SubtleCrypto) -> Result<Self, String> {
+    let algo = JsValue::from(web_sys::EcKeyGenParams::new("ECDSA", "P-256"));
+    let algo_obj: &js_sys::Object = algo.dyn_ref().ok_or("ECDSA algorithm is not an Object")?;
+    let usages = Array::new();
+    usages.push(&"sign".into());
+    usages.push(&"verify".into());
+
+    let key_pair = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .generate_key_with_object(algo_obj, false, &usages)
+        .map_err(|e| format!("Failed to call generate_key: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Failed to generate identity key pair: {:?}", e))?;
+
+    let private_key = CryptoKeyValue::from_js(
+      js_sys::Reflect::get(&key_pair, &"privateKey".into())
+        .map_err(|_| "Failed to get private key")?,
+    )?;
+    let public_key = CryptoKeyValue::from_js(
+      js_sys::Reflect::get(&key_pair, &"publicKey".into())
+        .map_err(|_| "Failed to get public key")?,
+    )?;
+
+    let raw_public = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .export_key("raw", public_key.as_crypto_key())
+        .map_err(|e| format!("Failed to call export_key: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Failed to export identity public key: {:?}", e))?;
+
+    Ok(Self {
+      private_key,
+      public_key: Uint8Array::new(&raw_public).to_vec(),
+    })
+  }
+
+  /// `{ privateKey, publicKey }` as stored in IndexedDB: the
+  /// `CryptoKey` itself plus the hex-encoded raw public key.
+  fn to_record(&self) -> Result<JsValue, String> {
+    let record = js_sys::Object::new();
+    js_sys::Reflect::set(
+      &record,
+      &"privateKey".into(),
+      self.private_key.as_crypto_key(),
+    )
+    .map_err(|_| "Failed to set privateKey")?;
+    js_sys::Reflect::set(
+      &record,
+      &"publicKey".into(),
+      &hex::encode(&self.public_key).into(),
+    )
+    .map_err(|_| "Failed to set publicKey")?;
+    Ok(record.into())
+  }
+
+  /// Inverse of [`Self::to_record`].
+  fn from_record(record: &JsValue) -> Result<Self, String> {
+    let private_key = CryptoKeyValue::from_js(
+      js_sys::Reflect::get(record, &"privateKey".into()).map_err(|_| "Missing privateKey")?,
+    )?;
+    let public_key = js_sys::Reflect::get(record, &"publicKey".into())
+      .ok()
+      .and_then(|v| v.as_string())
+      .ok_or("Missing publicKey")?;
+    Ok(Self {
+      private_key,
+      public_key: hex::decode(public_key).map_err(|e| e.to_string())?,
+    })
+  }
+
+  /// Import an identity exported to localStorage by an older build,
+  /// keeping the private key non-extractable from now on.
+  async fn import_legacy(subtle: &web_sys::SubtleCrypto, raw: &str) -> Result<Self, String> {
+    let stored: LegacyIdentity = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    let public_key = hex::decode(&stored.public_key).map_err(|e| e.to_string())?;
+    let jwk = js_sys::JSON::parse(&stored.private_jwk)
+      .map_err(|e| format!("Failed to parse JWK: {:?}", e))?;
+    let jwk_obj: &js_sys::Object = jwk.dyn_ref().ok_or("JWK is not an Object")?;
+
+    let algo = ecdsa_key_algorithm()?;
+    let usages = Array::new();
+    usages.push(&"sign".into());
+
+    let private_key = wasm_bindgen_futures::JsFuture::from(
+      subtle
+        .import_key_with_object("jwk", jwk_obj, &algo, false, &usages)
+        .map_err(|e| format!("Failed to call import_key: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Failed to import identity private key: {:?}", e))?;
+
+    Ok(Self {
+      private_key: CryptoKeyValue::from_js(private_key)?,
+      public_key,
+    })
+  }
+}
+
+/// The stored identity record for `user_id`, if any.
+#[cfg(target_arch = "wasm32")]
+async fn load_record(user_id: &UserId) -> Result<Option<JsValue>, String> {
+  let db = crate::persistence::idb::open_db()
+    .await
+    .map_err(|e| format!("Failed to open IndexedDB: {:?}", e))?;
+  crate::persistence::store::get_identity_key(&db, &user_id.to_string())
+    .await
+    .map_err(|e| format!("Failed to read identity key: {:?}", e))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_record(_user_id: &UserId) -> Result<Option<JsValue>, String> {
+  Err("IndexedDB is only available in the browser".to_string())
+}
+
+/// Persist the identity record for `user_id`.
+#[cfg(target_arch = "wasm32")]
+async fn save_record(user_id: &UserId, record: &JsValue) -> Result<(), String> {
+  let db = crate::persistence::idb::open_db()
+    .await
+    .map_err(|e| format!("Failed to open IndexedDB: {:?}", e))?;
+  crate::persistence::store::put_identity_key(&db, &user_id.to_string(), record)
+    .await
+    .map_err(|e| format!("Failed to store identity key: {:?}", e))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn save_record(_user_id: &UserId, _record: &JsValue) -> Result<(), String> {
+  Err("IndexedDB is only available in the browser".to_string())
+}
+
+/// Verify an ECDSA P-256 / SHA-256 `signature` over `payload` made by
+/// the holder of the raw `identity_key`.
+///
+/// Returns `Ok(false)` for a well-formed but wrong signature and `Err`
+/// when the key cannot be imported at all; callers treat both as a
+/// failed handshake.
+///
+/// # Errors
+/// Returns an error if the identity key is malformed or Web Crypto is
+/// unavailable.
+pub async fn verify_signature(
+  identity_key: &[u8],
+  payload: &[u8],
+  signature: &[u8],
+) -> Result<bool, String> {
+  let subtle = subtle_crypto()?;
+  let algo = ecdsa_key_algorithm()?;
+  let key_buffer = Uint8Array::from(identity_key).buffer();
+  let usages = Array::new();
+  usages.push(&"verify".into());
+
+  let public_key = wasm_bindgen_futures::JsFuture::from(
+    subtle
+      .import_key_with_object("raw", &key_buffer, &algo, false, &usages)
+      .map_err(|e| format!("Failed to call import_key: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Failed to import identity key: {:?}", e))?;
+  let public_key = CryptoKeyValue::from_js(public_key)?;
+
+  let sign_algo = ecdsa_sign_algorithm()?;
+  let valid = wasm_bindgen_futures::JsFuture::from(
+    subtle
+      .verify_with_object_and_buffer_source_and_buffer_source(
+        &sign_algo,
+        public_key.as_crypto_key(),
+        &Uint8Array::from(signature).buffer(),
+        &Uint8Array::from(payload).buffer(),
+      )
+      .map_err(|e| format!("Failed to call verify: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Signature verification failed: {:?}", e))?;
+
+  Ok(valid.as_bool().unwrap_or(false))
+}
+
+fn subtle_crypto() -> Result<web_sys::SubtleCrypto, String> {
+  let window = web_sys::window().ok_or("No window object available")?;
+  let crypto = window
+    .crypto()
+    .map_err(|_| "Crypto not available".to_string())?;
+  Ok(crypto.subtle())
+}
+
+/// `{ name: "ECDSA", namedCurve: "P-256" }` for key import.
+fn ecdsa_key_algorithm() -> Result<js_sys::Object, String> {
+  let algo = js_sys::Object::new();
+  js_sys::Reflect::set(&algo, &"name".into(), &"ECDSA".into())
+    .map_err(|_| "Failed to set algorithm name")?;
+  js_sys::Reflect::set(&algo, &"namedCurve".into(), &"P-256".into())
+    .map_err(|_| "Failed to set namedCurve")?;
+  Ok(algo)
+}
+
+/// `{ name: "ECDSA", hash: "SHA-256" }` for sign / verify.
+fn ecdsa_sign_algorithm() -> Result<js_sys::Object, String> {
+  let algo = js_sys::Object::new();
+  js_sys::Reflect::set(&algo, &"name".into(), &"ECDSA".into())
+    .map_err(|_| "Failed to set algorithm name")?;
+  js_sys::Reflect::set(&algo, &"hash".into(), &"SHA-256".into())
+    .map_err(|_| "Failed to set hash")?;
+  Ok(algo)
+}
+
+// ── Safety numbers ──
+
+/// Derive one party's 30-digit half of the safety number.
+///
+/// `SHA-256(version || key || user_id)` is re-hashed together with the
+/// key [`SAFETY_NUMBER_ITERATIONS`] times, then six 5-byte chunks of
+/// the digest are each reduced to five decimal digits.
+#[must_use]
+pub fn identity_fingerprint(user_id: &UserId, identity_key: &[u8]) -> String {
+  let mut digest = Sha256::new()
+    .chain_update(SAFETY_NUMBER_VERSION)
+    .chain_update(identity_key)
+    .chain_update(user_id.as_uuid().as_bytes())
+    .finalize();
+  for _ in 0..SAFETY_NUMBER_ITERATIONS {
+    digest = Sha256::new()
+      .chain_update(&digest[..])
+      .chain_update(identity_key)
+      .finalize();
+  }
+
+  let mut out = String::with_capacity(SAFETY_NUMBER_HALF_DIGITS);
+  for chunk in digest[..SAFETY_NUMBER_HALF_DIGITS].chunks_exact(5) {
+    let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+    out.push_str(&format!("{:05}", value % 100_000));
+  }
+  out
+}
+
+/// Safety number shared by two users (60 digits).
+///
+/// The two halves are ordered lexicographically so both peers compute
+/// the same string regardless of who is "local".
+#[must_use]
+pub fn safety_number(
+  local_id: &UserId,
+  local_key: &[u8],
+  peer_id: &UserId,
+  peer_key: &[u8],
+) -> String {
+  let local = identity_fingerprint(local_id, local_key);
+  let peer = identity_fingerprint(peer_id, peer_key);
+  if local <= peer {
+    local + &peer
+  } else {
+    peer + &local
+  }
+}
+
+/// Split a safety number into space-separated groups of five digits
+/// for display.
+#[must_use]
+pub fn format_safety_number(number: &str) -> String {
+  number
+    .as_bytes()
+    .chunks(5)
+    .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+// ── Trust on first use ──
+
+/// Outcome of comparing a peer's identity key with the pinned one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityCheck {
+  /// No key was pinned for this peer; the presented key is now pinned.
+  FirstSeen,
+  /// The presented key matches the pinned key.
+  Unchanged,
+  /// The presented key differs from the pinned key. The pin is left
+  /// untouched until the user explicitly accepts the new key.
+  Changed,
+}
+
+/// One pinned identity, as persisted in localStorage.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))] // Only (de)serialised in the browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PinnedIdentity {
+  user_id: UserId,
+  /// Device the key belongs to. Absent for pins written before keys
+  /// were tracked per device, and for peers whose device is unknown.
+  #[serde(default)]
+  device_id: Option<DeviceId>,
+  /// Hex-encoded raw identity public key.
+  identity_key: String,
+}
+
+/// Peer identity keys pinned on first contact.
+///
+/// Every device generates its own identity key, so pins are kept per
+/// `(UserId, DeviceId)`: a peer's second device is a first sighting,
+/// not a key change. A key already pinned for another device of the
+/// same peer (or for the device-less legacy slot) is recognised as
+/// unchanged when it first shows up under a device.
+///
+/// Hydrated from localStorage on WASM; native test builds start empty
+/// and skip persistence, mirroring `BlacklistState`.
+#[derive(Debug, Clone, Default)]
+pub struct KnownIdentities {
+  keys: HashMap<(UserId, Option<DeviceId>), String>,
+}
+
+impl KnownIdentities {
+  /// Load the pinned identities from localStorage.
+  #[must_use]
+  pub fn load() -> Self {
+    #[cfg(target_arch = "wasm32")]
+    {
+      let keys = crate::utils::load_from_local_storage(KNOWN_IDENTITIES_STORAGE_KEY)
+        .and_then(|raw| serde_json::from_str::<Vec<PinnedIdentity>>(&raw).ok())
+        .map(|pins| {
+          pins
+            .into_iter()
+            .map(|p| ((p.user_id, p.device_id), p.identity_key))
+            .collect()
+        })
+        .unwrap_or_default();
+      Self { keys }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      Self::default()
+    }
+  }
+
+  /// Compare `identity_key` against the key pinned for `device` of
+  /// `peer_id`, pinning it if none exists yet.
+  pub fn check(
+    &mut self,
+    peer_id: &UserId,
+    device: Option<DeviceId>,
+    identity_key: &[u8],
+  ) -> IdentityCheck {
+    let presented = hex::encode(identity_key);
+    match self.keys.get(&(peer_id.clone(), device)) {
+      Some(pinned) if *pinned == presented => IdentityCheck::Unchanged,
+      Some(_) => IdentityCheck::Changed,
+      None => {
+        let seen_before = self
+          .keys
+          .iter()
+          .any(|((user_id, _), pinned)| user_id == peer_id && *pinned == presented);
+        self.keys.insert((peer_id.clone(), device), presented);
+        self.persist();
+        if seen_before {
+          IdentityCheck::Unchanged
+        } else {
+          IdentityCheck::FirstSeen
+        }
+      }
+    }
+  }
+
+  /// Replace the pinned key for `device` of `peer_id` after the user
+  /// acknowledged a key change.
+  pub fn accept(&mut self, peer_id: &UserId, device: Option<DeviceId>, identity_key: &[u8]) {
+    self
+      .keys
+      .insert((peer_id.clone(), device), hex::encode(identity_key));
+    self.persist();
+  }
+
+  /// Returns `true` when a key is pinned for any device of `peer_id`.
+  #[must_use]
+  pub fn is_known(&self, peer_id: &UserId) -> bool {
+    self.keys.keys().any(|(user_id, _)| user_id == peer_id)
+  }
+
+  fn persist(&self) {
+    #[cfg(target_arch = "wasm32")]
+    {
+      let pins: Vec<PinnedIdentity> = self
+        .keys
+        .iter()
+        .map(|((user_id, device_id), identity_key)| PinnedIdentity {
+          user_id: user_id.clone(),
+          device_id: *device_id,
+          identity_key: identity_key.clone(),
+        })
+        .collect();
+      if let Ok(json) = serde_json::to_string(&pins) {
+        crate::utils::save_to_local_storage(KNOWN_IDENTITIES_STORAGE_KEY, &json);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests;
@@ -0,0 +1,145 @@
+use super::*;
+
+fn key(byte: u8) -> Vec<u8> {
+  let mut key = vec![byte; 65];
+  key[0] = 0x04;
+  key
+}
+
+// ── Safety numbers ──
+
+#[test]
+fn test_identity_fingerprint_is_thirty_digits() {
+  let fp = identity_fingerprint(&UserId::new(), &key(1));
+  assert_eq!(fp.len(), SAFETY_NUMBER_HALF_DIGITS);
+  assert!(fp.chars().all(|c| c.is_ascii_digit()));
+}
+
+#[test]
+fn test_identity_fingerprint_is_deterministic() {
+  let user = UserId::new();
+  assert_eq!(
+    identity_fingerprint(&user, &key(1)),
+    identity_fingerprint(&user, &key(1))
+  );
+}
+
+#[test]
+fn test_identity_fingerprint_binds_user_id() {
+  assert_ne!(
+    identity_fingerprint(&UserId::new(), &key(1)),
+    identity_fingerprint(&UserId::new(), &key(1))
+  );
+}
+
+#[test]
+fn test_safety_number_is_symmetric() {
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let from_alice = safety_number(&alice, &key(1), &bob, &key(2));
+  let from_bob = safety_number(&bob, &key(2), &alice, &key(1));
+  assert_eq!(from_alice, from_bob);
+  assert_eq!(from_alice.len(), SAFETY_NUMBER_HALF_DIGITS * 2);
+}
+
+#[test]
+fn test_safety_number_changes_with_either_key() {
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let original = safety_number(&alice, &key(1), &bob, &key(2));
+  assert_ne!(original, safety_number(&alice, &key(3), &bob, &key(2)));
+  assert_ne!(original, safety_number(&alice, &key(1), &bob, &key(3)));
+}
+
+#[test]
+fn test_format_safety_number_groups_of_five() {
+  let number = safety_number(&UserId::new(), &key(1), &UserId::new(), &key(2));
+  let formatted = format_safety_number(&number);
+  let groups: Vec<&str> = formatted.split(' ').collect();
+  assert_eq!(groups.len(), 12);
+  assert!(groups.iter().all(|g| g.len() == 5));
+  assert_eq!(groups.concat(), number);
+}
+
+// ── Trust on first use ──
+
+#[test]
+fn test_known_identities_pins_on_first_sight() {
+  let mut known = KnownIdentities::default();
+  let peer = UserId::new();
+  let device = Some(DeviceId::new());
+  assert!(!known.is_known(&peer));
+  assert_eq!(
+    known.check(&peer, device, &key(1)),
+    IdentityCheck::FirstSeen
+  );
+  assert!(known.is_known(&peer));
+  assert_eq!(
+    known.check(&peer, device, &key(1)),
+    IdentityCheck::Unchanged
+  );
+}
+
+#[test]
+fn test_known_identities_reports_change_until_accepted() {
+  let mut known = KnownIdentities::default();
+  let peer = UserId::new();
+  let device = Some(DeviceId::new());
+  known.check(&peer, device, &key(1));
+
+  assert_eq!(known.check(&peer, device, &key(2)), IdentityCheck::Changed);
+  // The pin is not silently replaced.
+  assert_eq!(known.check(&peer, device, &key(2)), IdentityCheck::Changed);
+  assert_eq!(
+    known.check(&peer, device, &key(1)),
+    IdentityCheck::Unchanged
+  );
+
+  known.accept(&peer, device, &key(2));
+  assert_eq!(
+    known.check(&peer, device, &key(2)),
+    IdentityCheck::Unchanged
+  );
+  assert_eq!(known.check(&peer, device, &key(1)), IdentityCheck::Changed);
+}
+
+#[test]
+fn test_known_identities_are_per_peer() {
+  let mut known = KnownIdentities::default();
+  let alice = UserId::new();
+  let bob = UserId::new();
+  known.check(&alice, None, &key(1));
+  assert_eq!(known.check(&bob, None, &key(1)), IdentityCheck::FirstSeen);
+}
+
+#[test]
+fn test_known_identities_are_per_device() {
+  let mut known = KnownIdentities::default();
+  let peer = UserId::new();
+  let laptop = Some(DeviceId::new());
+  let phone = Some(DeviceId::new());
+  known.check(&peer, laptop, &key(1));
+
+  // A second device with its own key is not a key change.
+  assert_eq!(known.check(&peer, phone, &key(2)), IdentityCheck::FirstSeen);
+  assert_eq!(
+    known.check(&peer, laptop, &key(1)),
+    IdentityCheck::Unchanged
+  );
+  assert_eq!(known.check(&peer, phone, &key(2)), IdentityCheck::Unchanged);
+  assert_eq!(known.check(&peer, phone, &key(1)), IdentityCheck::Changed);
+}
+
+#[test]
+fn test_known_identities_carry_legacy_pin_to_device() {
+  let mut known = KnownIdentities::default();
+  let peer = UserId::new();
+  let device = Some(DeviceId::new());
+  known.check(&peer, None, &key(1));
+
+  assert_eq!(
+    known.check(&peer, device, &key(1)),
+    IdentityCheck::Unchanged
+  );
+  assert_eq!(known.check(&peer, device, &key(2)), IdentityCheck::Changed);
+}
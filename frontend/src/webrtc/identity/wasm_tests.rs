@@ -0,0 +1,103 @@
+use super::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// The identity is generated once and reloaded from IndexedDB on the
+/// next call, so the public key stays stable across page loads.
+#[wasm_bindgen_test]
+async fn test_identity_persists_across_loads() {
+  let user = UserId::new();
+  let first = IdentityKeyPair::load_or_create(&user).await.unwrap();
+  let second = IdentityKeyPair::load_or_create(&user).await.unwrap();
+  assert_eq!(first.public_key().len(), 65);
+  assert_eq!(first.public_key(), second.public_key());
+
+  let other = IdentityKeyPair::load_or_create(&UserId::new())
+    .await
+    .unwrap();
+  assert_ne!(first.public_key(), other.public_key());
+}
+
+/// An identity exported to localStorage by an older build is moved into
+/// IndexedDB with its key unchanged, and the exported copy is removed.
+#[wasm_bindgen_test]
+async fn test_legacy_identity_is_migrated() {
+  let subtle = subtle_crypto().unwrap();
+  let algo = JsValue::from(web_sys::EcKeyGenParams::new("ECDSA", "P-256"));
+  let usages = Array::new();
+  usages.push(&"sign".into());
+  usages.push(&"verify".into());
+  let pair = wasm_bindgen_futures::JsFuture::from(
+    subtle
+      .generate_key_with_object(algo.unchecked_ref(), true, &usages)
+      .unwrap(),
+  )
+  .await
+  .unwrap();
+  let export = |format: &'static str, half: &'static str| {
+    let key: web_sys::CryptoKey = js_sys::Reflect::get(&pair, &half.into())
+      .unwrap()
+      .unchecked_into();
+    wasm_bindgen_futures::JsFuture::from(subtle.export_key(format, &key).unwrap())
+  };
+  let jwk = export("jwk", "privateKey").await.unwrap();
+  let raw = Uint8Array::new(&export("raw", "publicKey").await.unwrap()).to_vec();
+
+  let user = UserId::new();
+  let legacy_key = format!("{LEGACY_IDENTITY_STORAGE_PREFIX}{user}");
+  let legacy = serde_json::json!({
+    "private_jwk": String::from(js_sys::JSON::stringify(&jwk).unwrap()),
+    "public_key": hex::encode(&raw),
+  });
+  crate::utils::save_to_local_storage(&legacy_key, &legacy.to_string());
+
+  let migrated = IdentityKeyPair::load_or_create(&user).await.unwrap();
+  assert_eq!(migrated.public_key(), raw.as_slice());
+  assert!(crate::utils::load_from_local_storage(&legacy_key).is_none());
+  let reloaded = IdentityKeyPair::load_or_create(&user).await.unwrap();
+  assert_eq!(reloaded.public_key(), raw.as_slice());
+}
+
+/// A signature made by the identity verifies against its public key
+/// and fails for a modified payload or a different key.
+#[wasm_bindgen_test]
+async fn test_sign_and_verify() {
+  let identity = IdentityKeyPair::load_or_create(&UserId::new())
+    .await
+    .unwrap();
+  let payload = b"ephemeral key bytes";
+  let signature = identity.sign(payload).await.unwrap();
+  assert_eq!(signature.len(), 64);
+
+  assert!(
+    verify_signature(identity.public_key(), payload, &signature)
+      .await
+      .unwrap()
+  );
+  assert!(
+    !verify_signature(identity.public_key(), b"tampered key bytes", &signature)
+      .await
+      .unwrap()
+  );
+
+  let impostor = IdentityKeyPair::load_or_create(&UserId::new())
+    .await
+    .unwrap();
+  assert!(
+    !verify_signature(impostor.public_key(), payload, &signature)
+      .await
+      .unwrap()
+  );
+}
+
+/// A malformed identity key is reported as an error rather than
+/// silently treated as valid.
+#[wasm_bindgen_test]
+async fn test_verify_rejects_malformed_key() {
+  assert!(
+    verify_signature(&[0u8; 3], b"payload", &[0u8; 64])
+      .await
+      .is_err()
+  );
+}
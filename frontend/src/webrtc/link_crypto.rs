@@ -0,0 +1,128 @@
+//! Key agreement and sealing for the device-link `DataChannel`.
+//!
+//! Linking two devices of one account uses a fresh ECDH P-256 key pair
+//! per attempt (nothing is persisted) and seals every frame with
+//! AES-256-GCM as `[iv (12 B)][ciphertext + tag]`. Key derivation and
+//! the short code are pure and live in [`crate::devices::link`].
+
+use js_sys::Uint8Array;
+
+use super::encryption::{CryptoKeyValue, GCM_NONCE_SIZE};
+use super::frame_crypto::{gcm_params, import_key, web_crypto};
+use super::mailbox_crypto::{
+  derive_secret, export_raw, generate_ecdh_key_pair, import_ecdh_public,
+};
+
+/// Additional data bound into every link frame.
+const LINK_FRAME_BINDING: &[u8] = b"device-link-frame";
+
+/// Ephemeral ECDH key pair for one link attempt.
+pub struct LinkKeyPair {
+  private_key: CryptoKeyValue,
+  public_key: Vec<u8>,
+}
+
+impl LinkKeyPair {
+  /// Generate a fresh key pair.
+  ///
+  /// # Errors
+  /// Returns an error if Web Crypto is unavailable.
+  pub async fn generate() -> Result<Self, String> {
+    let (private_key, public_key) = generate_ecdh_key_pair(false).await?;
+    let public_key = export_raw(&public_key).await?;
+    Ok(Self {
+      private_key,
+      public_key,
+    })
+  }
+
+  /// Raw public key (65 bytes, uncompressed point).
+  #[must_use]
+  pub fn public_key(&self) -> &[u8] {
+    &self.public_key
+  }
+
+  /// Raw ECDH secret with the other device's public key.
+  ///
+  /// # Errors
+  /// Returns an error if `peer_key` is not a valid P-256 point.
+  pub async fn agree(&self, peer_key: &[u8]) -> Result<Vec<u8>, String> {
+    let peer = import_ecdh_public(peer_key).await?;
+    derive_secret(&self.private_key, &peer).await
+  }
+}
+
+/// AES-256-GCM key sealing the frames of one link.
+pub struct LinkCipher {
+  key: web_sys::CryptoKey,
+}
+
+impl LinkCipher {
+  /// Import the derived transfer key.
+  ///
+  /// # Errors
+  /// Returns an error if the key cannot be imported.
+  pub async fn new(raw: &[u8; 32]) -> Result<Self, String> {
+    Ok(Self {
+      key: import_key(raw).await?,
+    })
+  }
+
+  /// Seal one frame.
+  ///
+  /// # Errors
+  /// Returns an error if a Web Crypto operation fails.
+  pub async fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let crypto = web_crypto()?;
+    let iv = Uint8Array::new_with_length(GCM_NONCE_SIZE as u32);
+    crypto
+      .get_random_values_with_array_buffer_view(&iv)
+      .map_err(|e| format!("Failed to generate IV: {:?}", e))?;
+    let iv = iv.to_vec();
+    let algo =
+      gcm_params(&iv, LINK_FRAME_BINDING).map_err(|_| "Failed to build AES-GCM parameters")?;
+    let encrypted = wasm_bindgen_futures::JsFuture::from(
+      crypto
+        .subtle()
+        .encrypt_with_object_and_buffer_source(
+          &algo,
+          &self.key,
+          &Uint8Array::from(plaintext).buffer(),
+        )
+        .map_err(|e| format!("Failed to call encrypt: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Link frame encryption failed: {:?}", e))?;
+
+    let mut frame = iv;
+    frame.extend_from_slice(&Uint8Array::new(&encrypted).to_vec());
+    Ok(frame)
+  }
+
+  /// Open one frame.
+  ///
+  /// # Errors
+  /// Returns an error if the frame is truncated or fails
+  /// authentication.
+  pub async fn open(&self, frame: &[u8]) -> Result<Vec<u8>, String> {
+    if frame.len() <= GCM_NONCE_SIZE {
+      return Err(format!("Link frame too short ({} bytes)", frame.len()));
+    }
+    let (iv, ciphertext) = frame.split_at(GCM_NONCE_SIZE);
+    let algo =
+      gcm_params(iv, LINK_FRAME_BINDING).map_err(|_| "Failed to build AES-GCM parameters")?;
+    let decrypted = wasm_bindgen_futures::JsFuture::from(
+      web_crypto()?
+        .subtle()
+        .decrypt_with_object_and_buffer_source(
+          &algo,
+          &self.key,
+          &Uint8Array::from(ciphertext).buffer(),
+        )
+        .map_err(|e| format!("Failed to call decrypt: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Link frame decryption failed: {:?}", e))?;
+    Ok(Uint8Array::new(&decrypted).to_vec())
+  }
+}
@@ -0,0 +1,432 @@
+//! Offline mailbox for [`WebRtcManager`].
+//!
+//! When the server offers a mailbox (`AuthSuccess::mailbox_enabled`),
+//! direct chat messages that cannot reach their recipient over a data
+//! channel are sealed with [`mailbox_crypto`] and left on the server:
+//!
+//! * [`WebRtcManager::start_mailbox`] publishes our signed mailbox key
+//!   and drains whatever arrived while we were away.
+//! * [`WebRtcManager::deposit_to_mailbox`] looks up the keys of the
+//!   recipient's devices, seals one copy of the message per device and
+//!   uploads them. Failures mark the message `Failed`, exactly like an
+//!   exhausted retry budget used to.
+//! * [`WebRtcManager::handle_mailbox_drain`] opens stored messages and
+//!   feeds them to the chat router as if they had arrived live.
+//!
+//! Every device keeps its own mailbox key and the server keeps a
+//! mailbox per device, so a copy is only ever drained and acknowledged
+//! by the device it was sealed to.
+//!
+//! Both the recipient's key and every envelope are signed by an
+//! identity key, which must match the key pinned for that user
+//! ([`KnownIdentities`](super::identity::KnownIdentities)). Mailbox
+//! traffic never overrides a pin: a changed identity has to be
+//! accepted during a live handshake first.
+
+use std::collections::HashMap;
+
+use message::datachannel::DataChannelMessage;
+use message::signaling::{
+  DeviceMailboxKey, MailboxAck, MailboxDelivered, MailboxDeposit, MailboxDrain, MailboxEnvelope,
+  MailboxFetch, MailboxItem, MailboxKeyBundle, MailboxKeyRequest, MailboxKeyResponse,
+  PublishMailboxKey, SignalingMessage,
+};
+use message::{DeviceId, MessageId, UserId};
+
+use super::crypto_ops::plaintext_frame;
+use super::identity::{self, IdentityCheck};
+use super::mailbox_crypto::{self, MailboxKeyPair};
+use super::{PeerKey, WebRtcManager};
+
+/// Messages held per recipient while their mailbox key is being
+/// fetched. Mirrors the chat manager's offline buffer cap.
+const MAX_AWAITING_PER_PEER: usize = 50;
+
+/// Mailbox state kept on the manager.
+#[derive(Debug, Default)]
+pub(super) struct MailboxSession {
+  /// Whether the server offered a mailbox on the last `AuthSuccess`.
+  enabled: bool,
+  /// Local mailbox key pair, tagged with its owner.
+  key: Option<(UserId, MailboxKeyPair)>,
+  /// Messages waiting for the recipient's `MailboxKeyResponse`.
+  awaiting_key: HashMap<UserId, Vec<(MessageId, DataChannelMessage)>>,
+}
+
+/// Why a drained item could not be opened.
+enum OpenError {
+  /// The item was sealed to a mailbox key this device does not hold.
+  /// It is left on the server rather than acknowledged.
+  KeyMismatch(String),
+  /// The item can never be opened (bad signature, changed identity,
+  /// malformed content) and is dropped.
+  Rejected(String),
+}
+
+impl From<String> for OpenError {
+  fn from(reason: String) -> Self {
+    Self::Rejected(reason)
+  }
+}
+
+impl From<&str> for OpenError {
+  fn from(reason: &str) -> Self {
+    Self::Rejected(reason.to_string())
+  }
+}
+
+impl WebRtcManager {
+  /// Whether the server offers an offline mailbox.
+  #[must_use]
+  pub fn mailbox_enabled(&self) -> bool {
+    self.inner.borrow().mailbox.enabled
+  }
+
+  /// Apply the server's mailbox flag after authentication. When the
+  /// mailbox is on, publish our key and fetch stored messages.
+  pub fn start_mailbox(&self, enabled: bool) {
+    let stale = {
+      let mut inner = self.inner.borrow_mut();
+      inner.mailbox.enabled = enabled;
+      if enabled {
+        Vec::new()
+      } else {
+        std::mem::take(&mut inner.mailbox.awaiting_key)
+          .into_values()
+          .flatten()
+          .map(|(id, _)| id)
+          .collect()
+      }
+    };
+    self.fail_mailbox_messages(stale);
+    if !enabled {
+      return;
+    }
+
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      if let Err(e) = manager.publish_mailbox_key().await {
+        web_sys::console::warn_1(&format!("[mailbox] Failed to publish key: {e}").into());
+      }
+      manager.send_mailbox_signal(&SignalingMessage::MailboxFetch(MailboxFetch));
+    });
+  }
+
+  /// Leave `msg` in `peer`'s mailbox. The recipient's key is fetched
+  /// first; the message is sealed once it arrives.
+  pub fn deposit_to_mailbox(&self, peer: UserId, message_id: MessageId, msg: DataChannelMessage) {
+    if !self.mailbox_enabled() || mailbox_crypto::mailbox_message_id(&msg).is_none() {
+      self.fail_mailbox_messages(vec![message_id]);
+      return;
+    }
+    let first = {
+      let mut inner = self.inner.borrow_mut();
+      let queue = inner.mailbox.awaiting_key.entry(peer.clone()).or_default();
+      if queue.len() >= MAX_AWAITING_PER_PEER {
+        None
+      } else {
+        queue.push((message_id, msg));
+        Some(queue.len() == 1)
+      }
+    };
+    match first {
+      None => self.fail_mailbox_messages(vec![message_id]),
+      Some(true) => {
+        if !self.send_mailbox_signal(&SignalingMessage::MailboxKeyRequest(MailboxKeyRequest {
+          user_id: peer.clone(),
+        })) {
+          let pending = self.inner.borrow_mut().mailbox.awaiting_key.remove(&peer);
+          self.fail_mailbox_messages(pending.into_iter().flatten().map(|(id, _)| id).collect());
+        }
+      }
+      Some(false) => {}
+    }
+  }
+
+  /// Seal and upload everything waiting for `response.user_id`, one
+  /// copy per device key in the response.
+  pub fn handle_mailbox_key_response(&self, response: MailboxKeyResponse) {
+    let Some(pending) = self
+      .inner
+      .borrow_mut()
+      .mailbox
+      .awaiting_key
+      .remove(&response.user_id)
+    else {
+      return;
+    };
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let peer = response.user_id;
+      let mut recipient_keys = Vec::with_capacity(response.keys.len());
+      for DeviceMailboxKey { device_id, bundle } in response.keys {
+        match manager.verify_mailbox_key(&peer, device_id, bundle).await {
+          Ok(key) => recipient_keys.push((device_id, key)),
+          Err(e) => web_sys::console::warn_1(
+            &format!("[mailbox] Skipping a device key of peer {peer}: {e}").into(),
+          ),
+        }
+      }
+      if recipient_keys.is_empty() {
+        web_sys::console::warn_1(
+          &format!("[mailbox] Cannot deposit for peer {peer}: no valid mailbox key").into(),
+        );
+        manager.fail_mailbox_messages(pending.into_iter().map(|(id, _)| id).collect());
+        return;
+      }
+      for (message_id, msg) in pending {
+        let mut deposited = false;
+        for (to_device, recipient_key) in &recipient_keys {
+          match manager
+            .seal_for_mailbox(&peer, &message_id, &msg, recipient_key)
+            .await
+          {
+            Ok(blob) => {
+              deposited |=
+                manager.send_mailbox_signal(&SignalingMessage::MailboxDeposit(MailboxDeposit {
+                  to: peer.clone(),
+                  to_device: *to_device,
+                  message_id,
+                  blob,
+                }));
+            }
+            Err(e) => web_sys::console::warn_1(
+              &format!("[mailbox] Failed to seal message {message_id}: {e}").into(),
+            ),
+          }
+        }
+        if !deposited {
+          manager.fail_mailbox_messages(vec![message_id]);
+        }
+      }
+    });
+  }
+
+  /// Open drained messages, hand them to the chat router and confirm
+  /// them to the server.
+  ///
+  /// Items that fail verification are confirmed too: they can never
+  /// be opened, and leaving them would only fill the quota. Items
+  /// addressed to another device, or sealed to a key this device does
+  /// not hold, are left for their device.
+  pub fn handle_mailbox_drain(&self, drain: MailboxDrain) {
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      let Some(me) = manager.app_state.current_user_id() else {
+        return;
+      };
+      let key = match manager.local_mailbox_key(&me).await {
+        Ok(key) => key,
+        Err(e) => {
+          // Leave the items stored; they are drained again next login.
+          web_sys::console::warn_1(&format!("[mailbox] Cannot open mailbox: {e}").into());
+          return;
+        }
+      };
+      let local = crate::devices::local_device_id();
+      let mut processed = Vec::with_capacity(drain.items.len());
+      for item in drain.items {
+        if item.to_device.is_some_and(|device| device != local) {
+          continue;
+        }
+        match manager.open_mailbox_item(&me, &key, &item).await {
+          Ok(msg) => manager
+            .handle_data_channel_message(PeerKey::new(item.from.clone(), item.from_device), msg),
+          Err(OpenError::KeyMismatch(e)) => {
+            web_sys::console::warn_1(
+              &format!(
+                "[mailbox] Leaving message {} from {}: {}",
+                item.message_id, item.from, e
+              )
+              .into(),
+            );
+            continue;
+          }
+          Err(OpenError::Rejected(e)) => web_sys::console::warn_1(
+            &format!(
+              "[mailbox] Dropping message {} from {}: {}",
+              item.message_id, item.from, e
+            )
+            .into(),
+          ),
+        }
+        processed.push(item.message_id);
+      }
+      if processed.is_empty() {
+        return;
+      }
+      manager.send_mailbox_signal(&SignalingMessage::MailboxAck(MailboxAck {
+        message_ids: processed,
+      }));
+    });
+  }
+
+  /// The recipient drained our deposits: report them as delivered.
+  pub fn handle_mailbox_delivered(&self, delivered: &MailboxDelivered) {
+    if let Some(chat) = self.chat_manager.borrow().clone() {
+      chat.apply_mailbox_delivered(delivered);
+    }
+  }
+
+  /// Load (or create) the local mailbox key and publish it, signed by
+  /// the identity key.
+  async fn publish_mailbox_key(&self) -> Result<(), String> {
+    let me = self
+      .app_state
+      .current_user_id()
+      .ok_or("Cannot publish mailbox key while logged out")?;
+    let key = self.local_mailbox_key(&me).await?;
+    let identity = self.local_identity(&me).await.map_err(|e| e.to_string())?;
+    let mut bundle = MailboxKeyBundle {
+      public_key: key.public_key().to_vec(),
+      identity_key: identity.public_key().to_vec(),
+      signature: Vec::new(),
+    };
+    bundle.signature = identity.sign(&bundle.signing_payload(&me)).await?;
+    if self.send_mailbox_signal(&SignalingMessage::PublishMailboxKey(PublishMailboxKey {
+      bundle,
+    })) {
+      Ok(())
+    } else {
+      Err("signaling is not connected".to_string())
+    }
+  }
+
+  /// Return the local mailbox key, loading it on first use.
+  async fn local_mailbox_key(&self, me: &UserId) -> Result<MailboxKeyPair, String> {
+    if let Some((owner, key)) = self.inner.borrow().mailbox.key.as_ref()
+      && owner == me
+    {
+      return Ok(key.clone());
+    }
+    let key = MailboxKeyPair::load_or_create(me).await?;
+    self.inner.borrow_mut().mailbox.key = Some((me.clone(), key.clone()));
+    Ok(key)
+  }
+
+  /// Check a key bundle published from `device` and return its mailbox
+  /// public key.
+  async fn verify_mailbox_key(
+    &self,
+    owner: &UserId,
+    device: Option<DeviceId>,
+    bundle: MailboxKeyBundle,
+  ) -> Result<Vec<u8>, String> {
+    let valid = identity::verify_signature(
+      &bundle.identity_key,
+      &bundle.signing_payload(owner),
+      &bundle.signature,
+    )
+    .await
+    .unwrap_or(false);
+    if !valid {
+      return Err("mailbox key signature is invalid".to_string());
+    }
+    self.check_mailbox_identity(owner, device, &bundle.identity_key)?;
+    Ok(bundle.public_key)
+  }
+
+  /// Seal `msg` to `recipient_key` and sign the envelope.
+  async fn seal_for_mailbox(
+    &self,
+    recipient: &UserId,
+    message_id: &MessageId,
+    msg: &DataChannelMessage,
+    recipient_key: &[u8],
+  ) -> Result<Vec<u8>, String> {
+    let me = self.app_state.current_user_id().ok_or("Logged out")?;
+    let identity = self
+      .local_identity(recipient)
+      .await
+      .map_err(|e| e.to_string())?;
+    let binding = MailboxEnvelope::binding(&me, recipient, message_id);
+    let mut envelope = mailbox_crypto::seal(recipient_key, &binding, &plaintext_frame(msg)).await?;
+    envelope.identity_key = identity.public_key().to_vec();
+    envelope.signature = identity
+      .sign(&envelope.signing_payload(&me, recipient, message_id))
+      .await?;
+    Ok(bitcode::encode(&envelope))
+  }
+
+  /// Verify and decrypt one drained item.
+  async fn open_mailbox_item(
+    &self,
+    me: &UserId,
+    key: &MailboxKeyPair,
+    item: &MailboxItem,
+  ) -> Result<DataChannelMessage, OpenError> {
+    let envelope: MailboxEnvelope =
+      bitcode::decode(&item.blob).map_err(|e| format!("malformed envelope: {e}"))?;
+    let valid = identity::verify_signature(
+      &envelope.identity_key,
+      &envelope.signing_payload(&item.from, me, &item.message_id),
+      &envelope.signature,
+    )
+    .await
+    .unwrap_or(false);
+    if !valid {
+      return Err("envelope signature is invalid".into());
+    }
+    self.check_mailbox_identity(&item.from, item.from_device, &envelope.identity_key)?;
+
+    let binding = MailboxEnvelope::binding(&item.from, me, &item.message_id);
+    let plaintext = mailbox_crypto::open(key, &envelope, &binding)
+      .await
+      .map_err(OpenError::KeyMismatch)?;
+    let (&discriminator, payload) = plaintext.split_first().ok_or("empty plaintext")?;
+    let msg: DataChannelMessage =
+      bitcode::decode(payload).map_err(|e| format!("malformed message: {e}"))?;
+    if msg.discriminator() != discriminator
+      || mailbox_crypto::mailbox_message_id(&msg) != Some(item.message_id)
+    {
+      return Err("envelope content does not match its id".into());
+    }
+    Ok(msg)
+  }
+
+  /// Require `identity_key` to match the key pinned for `user_id` on
+  /// `device`, pinning it on first contact.
+  fn check_mailbox_identity(
+    &self,
+    user_id: &UserId,
+    device: Option<DeviceId>,
+    identity_key: &[u8],
+  ) -> Result<(), String> {
+    let check = self
+      .inner
+      .borrow_mut()
+      .known_identities
+      .check(user_id, device, identity_key);
+    if check == IdentityCheck::Changed {
+      return Err("identity key differs from the pinned key".to_string());
+    }
+    Ok(())
+  }
+
+  /// Mark messages that could not be deposited as failed.
+  fn fail_mailbox_messages(&self, ids: Vec<MessageId>) {
+    if ids.is_empty() {
+      return;
+    }
+    if let Some(chat) = self.chat_manager.borrow().clone() {
+      for id in ids {
+        chat.mark_failed(id);
+      }
+    }
+  }
+
+  /// Send a mailbox message to the server. Returns `false` when the
+  /// signaling connection is unavailable.
+  fn send_mailbox_signal(&self, msg: &SignalingMessage) -> bool {
+    let Some(signaling) = self.get_signaling() else {
+      return false;
+    };
+    match signaling.send(msg) {
+      Ok(()) => true,
+      Err(e) => {
+        web_sys::console::warn_1(&format!("[mailbox] Signaling send failed: {e}").into());
+        false
+      }
+    }
+  }
+}
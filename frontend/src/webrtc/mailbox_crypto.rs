@@ -0,0 +1,335 @@
+//! Sealing and opening offline mailbox envelopes.
+//!
+//! Every account owns a long-term ECDH P-256 *mailbox key*, persisted
+//! in `localStorage` next to its identity key and published (signed by
+//! the identity key) whenever the server offers a mailbox. A sender
+//! seals a message for an offline recipient like this:
+//!
+//! 1. Generate a one-time ECDH key pair and agree a secret with the
+//!    recipient's mailbox key.
+//! 2. Derive the AES-256-GCM key with [`envelope_key`]. The context,
+//!    both public keys and the secret all go into the hash, so a key
+//!    is only ever used for one envelope.
+//! 3. Encrypt the `[discriminator][bitcode]` frame with
+//!    [`MailboxEnvelope::binding`] as additional data.
+//!
+//! The envelope is signed by the sender's identity key on top of that
+//! (see `WebRtcManager::seal_for_mailbox`), because the mailbox key
+//! alone says nothing about who wrote the message.
+
+use js_sys::{Array, Uint8Array};
+use message::datachannel::DataChannelMessage;
+use message::signaling::MailboxEnvelope;
+use message::{MessageId, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use super::encryption::{AES_KEY_SIZE, CryptoKeyValue, GCM_NONCE_SIZE};
+use super::frame_crypto::{gcm_params, import_key, web_crypto};
+
+/// localStorage key prefix for the local mailbox key pair. The user id
+/// is appended, as for the identity key.
+pub const MAILBOX_KEY_STORAGE_PREFIX: &str = "e2ee_mailbox_key:";
+
+/// Domain separator hashed into every envelope key.
+const ENVELOPE_KEY_CONTEXT: &[u8] = b"webrtc-e2ee-chat-mailbox-aes-v1";
+
+/// Serialised form of the mailbox key persisted in localStorage.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredMailboxKey {
+  /// Private key as a JSON Web Key string (`JSON.stringify` output).
+  private_jwk: String,
+  /// Raw uncompressed public key, hex-encoded.
+  public_key: String,
+}
+
+/// The local user's long-term ECDH P-256 mailbox key pair.
+#[derive(Debug, Clone)]
+pub(super) struct MailboxKeyPair {
+  /// Non-extractable key used to open envelopes.
+  private_key: CryptoKeyValue,
+  /// Raw public key (65 bytes, uncompressed point).
+  public_key: Vec<u8>,
+}
+
+impl MailboxKeyPair {
+  /// Load the mailbox key for `user_id` from localStorage, generating
+  /// and persisting a fresh one on first use.
+  ///
+  /// An unreadable stored key is replaced. Envelopes sealed to the old
+  /// key can then no longer be opened and are dropped on drain.
+  ///
+  /// # Errors
+  /// Returns an error if the Web Crypto API is unavailable or key
+  /// generation fails.
+  pub(super) async fn load_or_create(user_id: &UserId) -> Result<Self, String> {
+    let storage_key = format!("{MAILBOX_KEY_STORAGE_PREFIX}{user_id}");
+
+    if let Some(raw) = crate::utils::load_from_local_storage(&storage_key) {
+      match Self::import_stored(&raw).await {
+        Ok(key) => return Ok(key),
+        Err(e) => web_sys::console::warn_1(
+          &format!("[mailbox] Discarding unreadable mailbox key: {}", e).into(),
+        ),
+      }
+    }
+
+    let (private_key, public_key) = generate_ecdh_key_pair(true).await?;
+    let jwk = wasm_bindgen_futures::JsFuture::from(
+      web_crypto()?
+        .subtle()
+        .export_key("jwk", private_key.as_crypto_key())
+        .map_err(|e| format!("Failed to call export_key: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Failed to export mailbox private key: {:?}", e))?;
+    let stored = StoredMailboxKey {
+      private_jwk: js_sys::JSON::stringify(&jwk)
+        .map_err(|e| format!("Failed to serialise JWK: {:?}", e))?
+        .into(),
+      public_key: hex::encode(export_raw(&public_key).await?),
+    };
+    let raw = serde_json::to_string(&stored).map_err(|e| e.to_string())?;
+    crate::utils::save_to_local_storage(&storage_key, &raw);
+    web_sys::console::log_1(&"[mailbox] Generated new mailbox key".into());
+    Self::import_stored(&raw).await
+  }
+
+  /// Raw mailbox public key (65 bytes, uncompressed point).
+  #[must_use]
+  pub(super) fn public_key(&self) -> &[u8] {
+    &self.public_key
+  }
+
+  /// Re-import a stored key, keeping the private key non-extractable
+  /// for the rest of the session.
+  async fn import_stored(raw: &str) -> Result<Self, String> {
+    let stored: StoredMailboxKey = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    let public_key = hex::decode(&stored.public_key).map_err(|e| e.to_string())?;
+    let jwk = js_sys::JSON::parse(&stored.private_jwk)
+      .map_err(|e| format!("Failed to parse JWK: {:?}", e))?;
+    let jwk_obj: &js_sys::Object = jwk.dyn_ref().ok_or("JWK is not an Object")?;
+
+    let usages = Array::new();
+    usages.push(&"deriveBits".into());
+    let private_key = wasm_bindgen_futures::JsFuture::from(
+      web_crypto()?
+        .subtle()
+        .import_key_with_object("jwk", jwk_obj, &ecdh_algorithm()?, false, &usages)
+        .map_err(|e| format!("Failed to call import_key: {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Failed to import mailbox private key: {:?}", e))?;
+
+    Ok(Self {
+      private_key: CryptoKeyValue::from_js(private_key)?,
+      public_key,
+    })
+  }
+}
+
+/// Id of `msg` if it may travel through the mailbox. Only direct chat
+/// content is sealed; control frames (acks, edits, typing) make no
+/// sense after the fact and room messages use sender keys.
+#[must_use]
+pub(super) fn mailbox_message_id(msg: &DataChannelMessage) -> Option<MessageId> {
+  match msg {
+    DataChannelMessage::ChatText(m) if m.room_id.is_none() => Some(m.message_id),
+    DataChannelMessage::ChatSticker(m) if m.room_id.is_none() => Some(m.message_id),
+    DataChannelMessage::ChatVoice(m) if m.room_id.is_none() => Some(m.message_id),
+    DataChannelMessage::ChatImage(m) if m.room_id.is_none() => Some(m.message_id),
+    DataChannelMessage::ForwardMessage(m) if m.room_id.is_none() => Some(m.message_id),
+    _ => None,
+  }
+}
+
+/// AES-256 key for one envelope:
+/// `SHA-256(context || secret || ephemeral_key || recipient_key)`.
+#[must_use]
+pub(super) fn envelope_key(secret: &[u8], ephemeral_key: &[u8], recipient_key: &[u8]) -> [u8; 32] {
+  Sha256::new()
+    .chain_update(ENVELOPE_KEY_CONTEXT)
+    .chain_update(secret)
+    .chain_update(ephemeral_key)
+    .chain_update(recipient_key)
+    .finalize()
+    .into()
+}
+
+/// Encrypt `plaintext` to `recipient_key`. Returns an unsigned envelope
+/// (`identity_key` and `signature` empty).
+///
+/// # Errors
+/// Returns an error if the recipient key is malformed or a Web Crypto
+/// operation fails.
+pub(super) async fn seal(
+  recipient_key: &[u8],
+  binding: &[u8],
+  plaintext: &[u8],
+) -> Result<MailboxEnvelope, String> {
+  let (ephemeral_private, ephemeral_public) = generate_ecdh_key_pair(false).await?;
+  let ephemeral_key = export_raw(&ephemeral_public).await?;
+  let recipient = import_ecdh_public(recipient_key).await?;
+  let secret = derive_secret(&ephemeral_private, &recipient).await?;
+  let key = import_key(&envelope_key(&secret, &ephemeral_key, recipient_key)).await?;
+
+  let crypto = web_crypto()?;
+  let iv = Uint8Array::new_with_length(GCM_NONCE_SIZE as u32);
+  crypto
+    .get_random_values_with_array_buffer_view(&iv)
+    .map_err(|e| format!("Failed to generate IV: {:?}", e))?;
+  let iv = iv.to_vec();
+  let algo = gcm_params(&iv, binding).map_err(|_| "Failed to build AES-GCM parameters")?;
+  let encrypted = wasm_bindgen_futures::JsFuture::from(
+    crypto
+      .subtle()
+      .encrypt_with_object_and_buffer_source(&algo, &key, &Uint8Array::from(plaintext).buffer())
+      .map_err(|e| format!("Failed to call encrypt: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Mailbox encryption failed: {:?}", e))?;
+
+  Ok(MailboxEnvelope {
+    ephemeral_key,
+    identity_key: Vec::new(),
+    iv,
+    ciphertext: Uint8Array::new(&encrypted).to_vec(),
+    signature: Vec::new(),
+  })
+}
+
+/// Decrypt an envelope sealed to `key`. The signature is not checked
+/// here.
+///
+/// # Errors
+/// Returns an error if the ephemeral key is malformed or
+/// authentication fails.
+pub(super) async fn open(
+  key: &MailboxKeyPair,
+  envelope: &MailboxEnvelope,
+  binding: &[u8],
+) -> Result<Vec<u8>, String> {
+  if envelope.iv.len() != GCM_NONCE_SIZE {
+    return Err(format!("Invalid envelope IV length {}", envelope.iv.len()));
+  }
+  let ephemeral = import_ecdh_public(&envelope.ephemeral_key).await?;
+  let secret = derive_secret(&key.private_key, &ephemeral).await?;
+  let aes_key = import_key(&envelope_key(
+    &secret,
+    &envelope.ephemeral_key,
+    key.public_key(),
+  ))
+  .await?;
+
+  let algo = gcm_params(&envelope.iv, binding).map_err(|_| "Failed to build AES-GCM parameters")?;
+  let decrypted = wasm_bindgen_futures::JsFuture::from(
+    web_crypto()?
+      .subtle()
+      .decrypt_with_object_and_buffer_source(
+        &algo,
+        &aes_key,
+        &Uint8Array::from(envelope.ciphertext.as_slice()).buffer(),
+      )
+      .map_err(|e| format!("Failed to call decrypt: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Mailbox decryption failed: {:?}", e))?;
+  Ok(Uint8Array::new(&decrypted).to_vec())
+}
+
+/// Generate an ECDH P-256 key pair usable for `deriveBits`.
+pub(super) async fn generate_ecdh_key_pair(
+  extractable: bool,
+) -> Result<(CryptoKeyValue, CryptoKeyValue), String> {
+  let algo = JsValue::from(web_sys::EcKeyGenParams::new("ECDH", "P-256"));
+  let algo_obj: &js_sys::Object = algo.dyn_ref().ok_or("ECDH algorithm is not an Object")?;
+  let usages = Array::new();
+  usages.push(&"deriveBits".into());
+
+  let key_pair = wasm_bindgen_futures::JsFuture::from(
+    web_crypto()?
+      .subtle()
+      .generate_key_with_object(algo_obj, extractable, &usages)
+      .map_err(|e| format!("Failed to call generate_key: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Failed to generate ECDH key pair: {:?}", e))?;
+
+  let private_key = CryptoKeyValue::from_js(
+    js_sys::Reflect::get(&key_pair, &"privateKey".into())
+      .map_err(|_| "Failed to get private key")?,
+  )?;
+  let public_key = CryptoKeyValue::from_js(
+    js_sys::Reflect::get(&key_pair, &"publicKey".into()).map_err(|_| "Failed to get public key")?,
+  )?;
+  Ok((private_key, public_key))
+}
+
+/// Export a public key in raw (uncompressed point) form.
+pub(super) async fn export_raw(public_key: &CryptoKeyValue) -> Result<Vec<u8>, String> {
+  let raw = wasm_bindgen_futures::JsFuture::from(
+    web_crypto()?
+      .subtle()
+      .export_key("raw", public_key.as_crypto_key())
+      .map_err(|e| format!("Failed to call export_key: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Failed to export public key: {:?}", e))?;
+  Ok(Uint8Array::new(&raw).to_vec())
+}
+
+/// Import a raw ECDH P-256 public key. Public keys carry no usages.
+pub(super) async fn import_ecdh_public(raw: &[u8]) -> Result<CryptoKeyValue, String> {
+  let key = wasm_bindgen_futures::JsFuture::from(
+    web_crypto()?
+      .subtle()
+      .import_key_with_object(
+        "raw",
+        &Uint8Array::from(raw).buffer(),
+        &ecdh_algorithm()?,
+        false,
+        &Array::new(),
+      )
+      .map_err(|e| format!("Failed to call import_key: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Failed to import ECDH public key: {:?}", e))?;
+  CryptoKeyValue::from_js(key)
+}
+
+/// Raw 32-byte ECDH secret between `private_key` and `public_key`.
+pub(super) async fn derive_secret(
+  private_key: &CryptoKeyValue,
+  public_key: &CryptoKeyValue,
+) -> Result<Vec<u8>, String> {
+  let algo = JsValue::from(web_sys::EcdhKeyDeriveParams::new(
+    "ECDH",
+    public_key.as_crypto_key(),
+  ));
+  let algo_obj: &js_sys::Object = algo
+    .dyn_ref()
+    .ok_or("ECDH derive algorithm is not an Object")?;
+  let secret = wasm_bindgen_futures::JsFuture::from(
+    web_crypto()?
+      .subtle()
+      .derive_bits_with_object(algo_obj, private_key.as_crypto_key(), AES_KEY_SIZE)
+      .map_err(|e| format!("Failed to call derive_bits: {:?}", e))?,
+  )
+  .await
+  .map_err(|e| format!("Failed to derive mailbox secret: {:?}", e))?;
+  Ok(Uint8Array::new(&secret).to_vec())
+}
+
+/// `{ name: "ECDH", namedCurve: "P-256" }` for key import.
+fn ecdh_algorithm() -> Result<js_sys::Object, String> {
+  let algo = js_sys::Object::new();
+  js_sys::Reflect::set(&algo, &"name".into(), &"ECDH".into())
+    .map_err(|_| "Failed to set algorithm name")?;
+  js_sys::Reflect::set(&algo, &"namedCurve".into(), &"P-256".into())
+    .map_err(|_| "Failed to set namedCurve")?;
+  Ok(algo)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::*;
+use message::datachannel::{AckStatus, ChatText, MessageAck};
+use message::{MessageId, RoomId};
+
+fn text(room_id: Option<RoomId>) -> DataChannelMessage {
+  DataChannelMessage::ChatText(ChatText {
+    message_id: MessageId::new(),
+    content: "hello".to_string(),
+    reply_to: None,
+    thread_root: None,
+    timestamp_nanos: 0,
+    room_id,
+    mentions: vec![],
+  })
+}
+
+#[test]
+fn test_envelope_key_is_deterministic() {
+  assert_eq!(
+    envelope_key(&[1; 32], &[2; 65], &[3; 65]),
+    envelope_key(&[1; 32], &[2; 65], &[3; 65])
+  );
+}
+
+#[test]
+fn test_envelope_key_binds_every_input() {
+  let key = envelope_key(&[1; 32], &[2; 65], &[3; 65]);
+  assert_ne!(key, envelope_key(&[9; 32], &[2; 65], &[3; 65]));
+  assert_ne!(key, envelope_key(&[1; 32], &[9; 65], &[3; 65]));
+  assert_ne!(key, envelope_key(&[1; 32], &[2; 65], &[9; 65]));
+}
+
+#[test]
+fn test_only_direct_chat_content_goes_to_mailbox() {
+  let direct = text(None);
+  let DataChannelMessage::ChatText(ref inner) = direct else {
+    unreachable!();
+  };
+  assert_eq!(mailbox_message_id(&direct), Some(inner.message_id));
+  assert_eq!(mailbox_message_id(&text(Some(RoomId::new()))), None);
+  assert_eq!(
+    mailbox_message_id(&DataChannelMessage::MessageAck(MessageAck {
+      message_id: MessageId::new(),
+      status: AckStatus::Received,
+      timestamp_nanos: 0,
+    })),
+    None
+  );
+}
@@ -4,35 +4,63 @@
 //! and end-to-end encryption for the WebRTC chat application.
 //!
 //! # Architecture
-//! - `WebRtcManager` orchestrates all peer connections (mesh topology)
+//! - `WebRtcManager` orchestrates all peer connections (mesh topology),
+//!   one per remote device, keyed by [`PeerKey`]
 //! - `PeerConnection` wraps RTCPeerConnection with SDP/ICE handling
-//! - `PeerDataChannel` wraps RTCDataChannel with message encoding
+//! - `PeerDataChannel` wraps RTCDataChannel with message encoding;
+//!   `channels` routes each frame onto the primary, bulk or ephemeral
+//!   channel by its discriminator
 //! - `PeerCrypto` handles ECDH key exchange and AES-256-GCM encryption
-
+//! - `identity` signs the ECDH exchange with a long-term identity key
+//!   and derives the safety number users compare out of band
+//! - `sfu` keeps the single media connection to the server in rooms
+//!   above the mesh limit; `frame_crypto` encrypts the relayed frames
+//! - `group_crypto` holds per-room sender keys so a room message is
+//!   encrypted once for all members (`group_keys` distributes them)
+//! - `mailbox` seals direct messages for offline recipients and leaves
+//!   them on the server (`mailbox_crypto` holds the envelope crypto)
+//! - `link_crypto` seals the history a linked device receives over its
+//!   own `DataChannel` (see [`crate::devices`])
+//! - `backup_crypto` derives the passphrase key of encrypted backups
+//!   (see [`crate::backup`])
+
+mod backup_crypto;
 mod broadcast;
+mod channels;
 mod crypto_ops;
 pub(crate) mod data_channel;
 mod encryption;
+mod frame_crypto;
+mod group_crypto;
+mod group_keys;
 mod handshake;
+mod identity;
+mod link_crypto;
+mod mailbox;
+mod mailbox_crypto;
 mod peer_connection;
 mod raw_frame;
+mod sfu;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
+pub use backup_crypto::BackupCipher;
 pub use data_channel::{PeerDataChannel, handle_incoming_channel};
 pub use encryption::PeerCrypto;
+pub use identity::format_safety_number;
+pub use link_crypto::{LinkCipher, LinkKeyPair};
 pub use peer_connection::{IceCandidateData, IceServerConfig, PeerConnection};
 pub use types::{
-  DataChannelState, PeerConnectionState, PeerEncryptionStatus, PeerState, WebRtcState,
+  DataChannelState, PeerConnectionState, PeerEncryptionStatus, PeerKey, PeerState, WebRtcState,
 };
 
 use crate::signaling::SignalingClient;
 use crate::state::AppState;
 use leptos::prelude::*;
-use message::UserId;
 use message::error::{ErrorCategory, ErrorCode, ErrorModule};
+use message::{DeviceId, UserId};
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -145,6 +173,9 @@ pub struct BroadcastResult {
 }
 
 /// Maximum number of peers in a mesh (requirements: ≤8).
+///
+/// SFU rooms raise the limit to the room size for the data-only
+/// connections; see [`InnerManager::peer_limit`].
 const MAX_MESH_PEERS: usize = 8;
 
 /// ECDH handshake timeout (P2-2): if the peer has not responded with its
@@ -157,6 +188,11 @@ const MAX_MESH_PEERS: usize = 8;
 /// round-trip completes well within 15 s even under moderate load.
 pub(super) const ECDH_EXCHANGE_TIMEOUT_MS: f64 = 15_000.0;
 
+/// How far an inbound `EcdhKeyExchange` timestamp may lie from the local
+/// clock before the exchange is rejected as a replay. Generous enough
+/// for ordinary clock skew between devices.
+pub(super) const ECDH_MAX_CLOCK_SKEW_NANOS: u64 = 5 * 60 * 1_000_000_000;
+
 /// Maximum number of control-frame broadcast messages queued per peer
 /// while the ECDH handshake is in flight (Task 19.1).
 ///
@@ -169,16 +205,16 @@ pub(super) const ECDH_EXCHANGE_TIMEOUT_MS: f64 = 15_000.0;
 /// bounding memory use.
 const PENDING_BROADCAST_LIMIT: usize = 16;
 
-/// A pending ECDH public key that has been generated locally but not yet
-/// flushed over the DataChannel (which only opens after ICE completes).
+/// A pending signed ECDH exchange that has been generated locally but not
+/// yet flushed over the DataChannel (which only opens after ICE completes).
 ///
 /// `started_at_ms` is captured from `js_sys::Date::now()` at insertion
 /// time and used by [`WebRtcManager::prune_expired_ecdh`] to evict
 /// entries whose peer never responded within `ECDH_EXCHANGE_TIMEOUT_MS`.
 #[derive(Debug, Clone)]
 pub(super) struct PendingEcdh {
-  /// Raw P-256 public key bytes (65 bytes, uncompressed point).
-  pub(super) public_key: Vec<u8>,
+  /// Our ephemeral public key, already signed by the identity key.
+  pub(super) exchange: message::datachannel::EcdhKeyExchange,
   /// Wall-clock timestamp in milliseconds since Unix epoch, captured
   /// when the pending entry was inserted. Used purely for timeout
   /// detection — monotonic time is not needed because we only compare
@@ -215,6 +251,12 @@ type MediaStateUpdateHandler = Rc<dyn Fn(UserId, message::datachannel::MediaStat
 /// from a transient network blip (Req 10.5.24).
 type ReconnectingStateHandler = Rc<dyn Fn(UserId, message::datachannel::ReconnectingState)>;
 
+/// Callback invoked when a remote peer broadcasts that it started or
+/// stopped recording the call via
+/// [`message::datachannel::CallRecordingState`]. Routed to the call
+/// subsystem so every participant sees who is recording.
+type CallRecordingStateHandler = Rc<dyn Fn(UserId, message::datachannel::CallRecordingState)>;
+
 /// Callback invoked when a remote peer forwards a theater-class
 /// DataChannel message (danmaku, subtitle track replacement, subtitle
 /// clear, playback progress). Routed to the theater subsystem so the
@@ -287,6 +329,9 @@ pub struct WebRtcManager {
   /// Callback invoked when a remote peer broadcasts a
   /// [`message::datachannel::ReconnectingState`] (Req 10.5.24).
   on_reconnecting_state: Rc<RefCell<Option<ReconnectingStateHandler>>>,
+  /// Callback invoked when a remote peer broadcasts a
+  /// [`message::datachannel::CallRecordingState`].
+  on_call_recording_state: Rc<RefCell<Option<CallRecordingStateHandler>>>,
   /// Callback invoked when a remote peer forwards a theater-class
   /// DataChannel message (Req 12.3 – 12.6). Wired by the theater page
   /// on mount so the overlay / subtitle / playback HUDs stay in sync.
@@ -310,16 +355,16 @@ pub struct WebRtcManager {
 }
 
 pub(super) struct InnerManager {
-  /// All peer connections, keyed by user ID.
-  pub(super) connections: HashMap<UserId, PeerConnection>,
-  /// All crypto instances, keyed by user ID.
-  pub(super) crypto: HashMap<UserId, PeerCrypto>,
+  /// All peer connections, one per remote device.
+  pub(super) connections: HashMap<PeerKey, PeerConnection>,
+  /// All crypto instances, keyed like [`Self::connections`].
+  pub(super) crypto: HashMap<PeerKey, PeerCrypto>,
   /// ICE server configuration.
   pub(super) ice_servers: Vec<IceServerConfig>,
   /// Pending ECDH public keys awaiting DataChannel open (P2-2: tracks
   /// the start timestamp so [`WebRtcManager::prune_expired_ecdh`] can
   /// evict entries whose peer never completed the handshake).
-  pub(super) pending_ecdh_keys: HashMap<UserId, PendingEcdh>,
+  pub(super) pending_ecdh_keys: HashMap<PeerKey, PendingEcdh>,
   /// Control-frame broadcast messages queued per peer while the ECDH
   /// handshake is still in flight (Task 19.1).
   ///
@@ -333,13 +378,13 @@ pub(super) struct InnerManager {
   /// Bounded at [`PENDING_BROADCAST_LIMIT`] per peer to stop a
   /// pathological handshake hang from blowing the heap.
   pub(super) pending_broadcast:
-    HashMap<UserId, std::collections::VecDeque<message::datachannel::DataChannelMessage>>,
+    HashMap<PeerKey, std::collections::VecDeque<message::datachannel::DataChannelMessage>>,
   /// Number of in-flight connection attempts.
   ///
   /// Counts concurrent `connect_to_peer` / `handle_incoming_offer` calls
   /// that have passed the mesh limit check but not yet stored their
   /// connection. Used atomically with `borrow_mut` to prevent races
-  /// that could exceed `MAX_MESH_PEERS`.
+  /// that could exceed [`Self::peer_limit`].
   pub(super) in_flight: Rc<Cell<usize>>,
   /// Periodic `setInterval` handle that drives
   /// [`WebRtcManager::prune_expired_ecdh`]. Retained so the
@@ -349,7 +394,7 @@ pub(super) struct InnerManager {
   /// ICE restart timeout timers per peer. When a peer enters
   /// `Disconnected`, the initiator starts a timer; if ICE hasn't
   /// recovered by the timeout, the peer is treated as `Failed`.
-  pub(super) ice_restart_timers: HashMap<UserId, crate::utils::TimeoutHandle>,
+  pub(super) ice_restart_timers: HashMap<PeerKey, crate::utils::TimeoutHandle>,
   /// Early ICE candidates received before the local
   /// [`PeerConnection`] has been inserted into [`Self::connections`].
   ///
@@ -368,12 +413,85 @@ pub(super) struct InnerManager {
   ///
   /// Bounded at [`PENDING_ICE_LIMIT`] per peer to bound memory.
   pub(super) pending_ice_candidates:
-    HashMap<UserId, std::collections::VecDeque<PendingIceCandidate>>,
+    HashMap<PeerKey, std::collections::VecDeque<PendingIceCandidate>>,
   /// Peers for which `initiate_ecdh_exchange` is currently in-flight
   /// (between the first `await` and the final key storage). Used by
   /// `handle_data_channel_open` to avoid spawning a redundant
   /// proactive ECDH when the initiator's exchange is still running.
-  pub(super) ecdh_in_progress: std::collections::HashSet<UserId>,
+  pub(super) ecdh_in_progress: std::collections::HashSet<PeerKey>,
+  /// Timestamp of the last key exchange accepted from each peer device. A
+  /// later exchange must carry a newer one, so a captured exchange
+  /// cannot be replayed within the clock-skew window.
+  pub(super) ecdh_last_timestamps: HashMap<PeerKey, u64>,
+  /// Local identity key pair, loaded lazily on the first handshake and
+  /// tagged with the user id it belongs to.
+  pub(super) identity: Option<(UserId, identity::IdentityKeyPair)>,
+  /// Peer identity keys pinned on first contact (trust on first use).
+  pub(super) known_identities: identity::KnownIdentities,
+  /// Identity key most recently presented by each peer device in a
+  /// verified exchange. Consulted when the user accepts a changed key.
+  pub(super) peer_identity_keys: HashMap<PeerKey, Vec<u8>>,
+  /// Maximum number of peer connections. [`MAX_MESH_PEERS`] unless an
+  /// SFU session is open, in which case peer connections only carry
+  /// data channels and may span the whole room.
+  pub(super) peer_limit: usize,
+  /// Media connection to the SFU while in an SFU room.
+  pub(super) sfu: Option<sfu::SfuSession>,
+  /// Frame keys for SFU media, shared with the frame transforms.
+  pub(super) media_keys: Rc<RefCell<frame_crypto::MediaKeyRing>>,
+  /// Room sender keys (ours and every member's), plus group frames
+  /// waiting for the key they were sent under.
+  pub(super) group_keys: Rc<RefCell<group_crypto::GroupKeyRing>>,
+  /// Offline mailbox state: whether the server offers one, our mailbox
+  /// key and messages waiting for a recipient's key.
+  pub(super) mailbox: mailbox::MailboxSession,
+}
+
+impl InnerManager {
+  /// Keys of every connection to `user_id`, one per device.
+  pub(super) fn peer_keys(&self, user_id: &UserId) -> Vec<PeerKey> {
+    self
+      .connections
+      .keys()
+      .filter(|key| key.user_id == *user_id)
+      .cloned()
+      .collect()
+  }
+
+  /// User and device at the other end of the connection keyed by
+  /// `peer`. A connection not addressed to a device learns it from
+  /// the first answer.
+  pub(super) fn device_key(&self, peer: &PeerKey) -> PeerKey {
+    let device = peer.device_id.or_else(|| {
+      self
+        .connections
+        .get(peer)
+        .and_then(PeerConnection::remote_device)
+    });
+    PeerKey::new(peer.user_id.clone(), device)
+  }
+
+  /// Key of the connection a signal from `peer` belongs to.
+  ///
+  /// An offer that was not addressed to a device is kept under the
+  /// user's deviceless key until its first answer binds it to the
+  /// answering device, so signals from that device resolve to it.
+  /// With `unbound`, a connection not yet bound to any device matches
+  /// too (an answer about to bind it, or a glare offer replacing it).
+  pub(super) fn resolve_peer(&self, peer: &PeerKey, unbound: bool) -> PeerKey {
+    if peer.device_id.is_none() || self.connections.contains_key(peer) {
+      return peer.clone();
+    }
+    let probe = PeerKey::user(peer.user_id.clone());
+    match self
+      .connections
+      .get(&probe)
+      .map(PeerConnection::remote_device)
+    {
+      Some(bound) if bound == peer.device_id || (unbound && bound.is_none()) => probe,
+      _ => peer.clone(),
+    }
+  }
 }
 
 /// A trickle-ICE candidate that arrived before the local
@@ -416,6 +534,7 @@ impl WebRtcManager {
       on_peer_connected: Rc::new(RefCell::new(None)),
       on_media_state_update: Rc::new(RefCell::new(None)),
       on_reconnecting_state: Rc::new(RefCell::new(None)),
+      on_call_recording_state: Rc::new(RefCell::new(None)),
       on_theater_message: Rc::new(RefCell::new(None)),
       on_theater_peer_event: Rc::new(RefCell::new(None)),
       on_theater_remote_track: Rc::new(RefCell::new(None)),
@@ -431,6 +550,15 @@ impl WebRtcManager {
         ice_restart_timers: HashMap::new(),
         pending_ice_candidates: HashMap::new(),
         ecdh_in_progress: std::collections::HashSet::new(),
+        ecdh_last_timestamps: HashMap::new(),
+        identity: None,
+        known_identities: identity::KnownIdentities::load(),
+        peer_identity_keys: HashMap::new(),
+        peer_limit: MAX_MESH_PEERS,
+        sfu: None,
+        media_keys: Rc::new(RefCell::new(frame_crypto::MediaKeyRing::default())),
+        group_keys: Rc::new(RefCell::new(group_crypto::GroupKeyRing::default())),
+        mailbox: mailbox::MailboxSession::default(),
       })),
     }
   }
@@ -513,6 +641,16 @@ impl WebRtcManager {
     *self.on_reconnecting_state.borrow_mut() = Some(Rc::new(callback));
   }
 
+  /// Register a callback for remote `CallRecordingState` broadcasts.
+  /// The call subsystem uses this to mark recording participants so
+  /// everyone in the call knows they are being recorded.
+  pub fn set_on_call_recording_state<F>(&self, callback: F)
+  where
+    F: Fn(UserId, message::datachannel::CallRecordingState) + 'static,
+  {
+    *self.on_call_recording_state.borrow_mut() = Some(Rc::new(callback));
+  }
+
   /// Register a callback for inbound theater DataChannel messages
   /// (Req 12.3 – 12.5). The theater page installs a handler on mount
   /// so danmaku, subtitle replacements, subtitle clears, and playback
@@ -647,7 +785,7 @@ impl WebRtcManager {
         match pc_for_async.create_offer().await {
           Ok(sdp) => {
             if let Some(sig) = manager.get_signaling()
-              && let Err(e) = sig.send_sdp_offer(&peer_id, &sdp)
+              && let Err(e) = sig.send_sdp_offer(&peer_id, pc_for_async.remote_device(), &sdp)
             {
               web_sys::console::warn_1(
                 &format!("[webrtc] Renegotiation send_sdp_offer failed: {e}").into(),
@@ -666,12 +804,30 @@ impl WebRtcManager {
 
   /// Publish a single peer's tracks, used on mid-call peer-joins so
   /// late arrivals receive the already-active call's media (Task 18).
+  /// Covers every device of the peer.
   ///
   /// Safely no-ops when no tracks are being published yet.
   pub fn publish_local_stream_to(&self, peer_id: &UserId, stream: &web_sys::MediaStream) {
-    let Some(pc) = self.inner.borrow().connections.get(peer_id).cloned() else {
+    if self.has_sfu_session() {
+      // The relay already forwards our tracks to every member.
       return;
+    }
+    let connections: Vec<PeerConnection> = {
+      let inner = self.inner.borrow();
+      inner
+        .peer_keys(peer_id)
+        .iter()
+        .filter_map(|key| inner.connections.get(key).cloned())
+        .collect()
     };
+    for pc in connections {
+      self.publish_local_stream_on(pc, stream);
+    }
+  }
+
+  /// Publish `stream` on one connection and renegotiate it.
+  fn publish_local_stream_on(&self, pc: PeerConnection, stream: &web_sys::MediaStream) {
+    let peer_id = pc.peer_id();
     if let Err(e) = pc.publish_local_stream(stream) {
       web_sys::console::warn_1(
         &format!("[webrtc] Failed to publish local stream to {peer_id}: {e}").into(),
@@ -710,18 +866,16 @@ impl WebRtcManager {
     }
 
     let manager = self.clone();
-    let pid = peer_id.clone();
-    let pc_clone = pc.clone();
     wasm_bindgen_futures::spawn_local(async move {
-      match pc_clone.create_offer().await {
+      match pc.create_offer().await {
         Ok(sdp) => {
           if let Some(sig) = manager.get_signaling()
-            && let Err(e) = sig.send_sdp_offer(&pid, &sdp)
+            && let Err(e) = sig.send_sdp_offer(&peer_id, pc.remote_device(), &sdp)
           {
             web_sys::console::warn_1(
               &format!(
                 "[webrtc] publish_local_stream_to renegotiation send_sdp_offer failed for {}: {e}",
-                pid
+                peer_id
               )
               .into(),
             );
@@ -731,7 +885,7 @@ impl WebRtcManager {
           web_sys::console::warn_1(
             &format!(
               "[webrtc] publish_local_stream_to renegotiation create_offer failed for {}: {e}",
-              pid
+              peer_id
             )
             .into(),
           );
@@ -778,15 +932,32 @@ impl WebRtcManager {
     inner.ice_servers = ice_servers;
   }
 
+  /// ICE servers currently in use, for connections created outside the
+  /// mesh (device linking).
+  #[must_use]
+  pub fn ice_servers(&self) -> Vec<IceServerConfig> {
+    self.inner.borrow().ice_servers.clone()
+  }
+
   /// Initiate a connection to a peer (initiator side).
   ///
+  /// The offer is not addressed to a device, so it reaches every device
+  /// of the peer. The connection is bound to the first device that
+  /// answers and a separate connection is opened to each device that
+  /// answers after it (see [`Self::handle_incoming_answer`]).
+  ///
   /// 1. Creates RTCPeerConnection
   /// 2. Creates DataChannel
   /// 3. Creates SDP offer
   /// 4. Sends SdpOffer via signaling
   /// 5. Initiates ECDH key exchange (key sent when DataChannel opens)
   pub async fn connect_to_peer(&self, peer_id: UserId) -> Result<(), WebRtcError> {
-    self.connect_to_peer_inner(peer_id, None).await
+    if self.is_connected(&peer_id) {
+      return Err(WebRtcError::already_connected(peer_id));
+    }
+    self
+      .connect_to_peer_inner(PeerKey::user(peer_id), None)
+      .await
   }
 
   /// Same as [`Self::connect_to_peer`] but pre-attaches the given
@@ -803,27 +974,38 @@ impl WebRtcManager {
     peer_id: UserId,
     stream: &web_sys::MediaStream,
   ) -> Result<(), WebRtcError> {
-    self.connect_to_peer_inner(peer_id, Some(stream)).await
+    if self.is_connected(&peer_id) {
+      return Err(WebRtcError::already_connected(peer_id));
+    }
+    self
+      .connect_to_peer_inner(PeerKey::user(peer_id), Some(stream))
+      .await
   }
 
   /// Core implementation shared by [`Self::connect_to_peer`] and
-  /// [`Self::connect_to_peer_with_stream`].
+  /// [`Self::connect_to_peer_with_stream`]. The offer is addressed to
+  /// `peer.device_id` when it is set.
   async fn connect_to_peer_inner(
     &self,
-    peer_id: UserId,
+    peer: PeerKey,
     initial_stream: Option<&web_sys::MediaStream>,
   ) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
     // Atomically check total occupied slots (existing + in-flight)
     // and reserve a slot before any async yield point.
     let in_flight_rc = {
-      let inner = self.inner.borrow_mut();
-      if inner.connections.contains_key(&peer_id) {
+      let mut inner = self.inner.borrow_mut();
+      if inner.connections.contains_key(&peer) {
         return Err(WebRtcError::already_connected(peer_id));
       }
       let total = inner.connections.len() + inner.in_flight.get();
-      if total >= MAX_MESH_PEERS {
+      if total >= inner.peer_limit {
         return Err(WebRtcError::mesh_limit());
       }
+      // Candidates buffered under this key belong to an earlier
+      // connection of the device (e.g. its answer to an offer that
+      // another device won).
+      inner.pending_ice_candidates.remove(&peer);
       inner.in_flight.set(inner.in_flight.get() + 1);
       Rc::clone(&inner.in_flight)
     };
@@ -841,28 +1023,18 @@ impl WebRtcManager {
           Some(peer_id.clone()),
         )
       })?;
+      pc.bind_remote_device(peer.device_id);
 
       // Set up ICE candidate handler
-      let signaling = self.get_signaling();
-      let ice_peer_id = peer_id.clone();
-      pc.set_on_ice_candidate(move |candidate| {
-        if let Some(ref sig) = signaling {
-          let _ = sig.send_sdp_ice_candidate(
-            ice_peer_id.clone(),
-            &candidate.candidate,
-            &candidate.sdp_mid,
-            candidate.sdp_m_line_index,
-          );
-        }
-      });
+      self.wire_ice_candidate_handler(&pc);
 
       // Set up connection state handler (P1-16: capture instance_id so stale
       // callbacks from a replaced PC can be detected and ignored).
       let manager = self.clone();
-      let state_peer_id = peer_id.clone();
+      let state_peer = peer.clone();
       let instance_id = pc.instance_id();
       pc.set_on_connection_state_change(move |state| {
-        manager.handle_connection_state_change(state_peer_id.clone(), state, instance_id);
+        manager.handle_connection_state_change(state_peer.clone(), state, instance_id);
       });
 
       // Wire the call-subsystem remote-track handler (Task 18). This
@@ -891,53 +1063,46 @@ impl WebRtcManager {
     // PeerDataChannel::set_on_open), the connection is already in the
     // map and can be found by handle_data_channel_open /
     // initiate_ecdh_exchange.
-    self
-      .inner
-      .borrow_mut()
-      .connections
-      .insert(peer_id.clone(), pc);
+    self.inner.borrow_mut().connections.insert(peer.clone(), pc);
 
     // Set up DataChannel open handler to send ECDH key when ready.
     // PeerDataChannel::set_on_open includes a race-condition guard
     // that invokes the callback immediately if the DataChannel is
     // already open.
     let manager_dc_open = self.clone();
-    let dc_open_peer_id = peer_id.clone();
+    let dc_open_peer = peer.clone();
     if let Some(dc) = self
       .inner
       .borrow()
       .connections
-      .get(&peer_id)
+      .get(&peer)
       .and_then(|pc| pc.get_data_channel())
     {
       dc.set_on_open(move || {
-        manager_dc_open.handle_data_channel_open(dc_open_peer_id.clone());
+        manager_dc_open.handle_data_channel_open(dc_open_peer.clone());
       });
 
       // Task 19.1 — route raw frames through the envelope-aware
       // dispatcher so encrypted application frames can be decrypted
       // before being handed to `handle_data_channel_message`.
       let manager_dc_msg = self.clone();
-      let dc_msg_peer_id = peer_id.clone();
+      let dc_msg_peer = peer.clone();
       dc.set_on_raw_message(move |bytes| {
-        manager_dc_msg.handle_data_channel_raw_frame(dc_msg_peer_id.clone(), bytes);
+        manager_dc_msg.handle_data_channel_raw_frame(dc_msg_peer.clone(), bytes);
       });
     }
 
     // Register the peer in the reactive UI state so that
     // subsequent `update_connection_state` / `update_data_channel_state`
     // calls can find it. Without this, the UI signal stays empty.
-    self
-      .app_state
-      .webrtc_state
-      .update(|s| s.add_peer(peer_id.clone(), true));
+    self.add_ui_peer(&peer_id, true);
 
     // Create SDP offer — clone PeerConnection out of RefCell before .await
     let peer_connection = self
       .inner
       .borrow()
       .connections
-      .get(&peer_id)
+      .get(&peer)
       .cloned()
       .ok_or_else(|| WebRtcError::peer_not_found(peer_id.clone()))?;
 
@@ -957,7 +1122,7 @@ impl WebRtcManager {
       }
       if let Err(e) = peer_connection.publish_local_stream(stream) {
         web_sys::console::warn_1(
-          &format!("[webrtc] Failed to attach initial stream to {peer_id}: {e}").into(),
+          &format!("[webrtc] Failed to attach initial stream to {peer}: {e}").into(),
         );
       }
     }
@@ -968,7 +1133,7 @@ impl WebRtcManager {
       Ok(sdp) => sdp,
       Err(e) => {
         // Clean up the stored connection on offer failure
-        self.close_connection(&peer_id);
+        self.close_peer(&peer);
         return Err(WebRtcError::new(
           ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
           format!("Failed to create offer: {}", e),
@@ -979,13 +1144,15 @@ impl WebRtcManager {
 
     // Send SdpOffer via signaling
     if let Some(sig) = self.get_signaling() {
-      sig.send_sdp_offer(&peer_id, &offer_sdp).map_err(|e| {
-        WebRtcError::new(
-          ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
-          format!("Failed to send SDP offer: {}", e),
-          Some(peer_id.clone()),
-        )
-      })?;
+      sig
+        .send_sdp_offer(&peer_id, peer.device_id, &offer_sdp)
+        .map_err(|e| {
+          WebRtcError::new(
+            ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
+            format!("Failed to send SDP offer: {}", e),
+            Some(peer_id.clone()),
+          )
+        })?;
     }
 
     // Initiate ECDH key exchange (keys stored pending DataChannel open).
@@ -994,7 +1161,7 @@ impl WebRtcManager {
     // "Already connected"; there is no reuse path, so the ECDH exchange
     // always corresponds to a freshly-created PeerConnection.
     self
-      .initiate_ecdh_exchange(peer_id.clone())
+      .initiate_ecdh_exchange(peer.clone())
       .await
       .map_err(|e| {
         WebRtcError::new(
@@ -1004,11 +1171,40 @@ impl WebRtcManager {
         )
       })?;
 
-    web_sys::console::log_1(&format!("[webrtc] Initiated connection to peer {}", peer_id).into());
+    web_sys::console::log_1(&format!("[webrtc] Initiated connection to peer {}", peer).into());
 
     Ok(())
   }
 
+  /// Send local ICE candidates of `pc` to the device it is bound to,
+  /// or to every device of the peer while it is unbound.
+  fn wire_ice_candidate_handler(&self, pc: &PeerConnection) {
+    let signaling = self.get_signaling();
+    let peer_id = pc.peer_id();
+    let device = pc.remote_device_handle();
+    pc.set_on_ice_candidate(move |candidate| {
+      if let Some(ref sig) = signaling {
+        let _ = sig.send_sdp_ice_candidate(
+          peer_id.clone(),
+          device.get(),
+          &candidate.candidate,
+          &candidate.sdp_mid,
+          candidate.sdp_m_line_index,
+        );
+      }
+    });
+  }
+
+  /// Register `peer_id` in the reactive UI state unless another device
+  /// of the peer already did.
+  fn add_ui_peer(&self, peer_id: &UserId, is_initiator: bool) {
+    self.app_state.webrtc_state.update(|s| {
+      if s.get_peer(peer_id).is_none() {
+        s.add_peer(peer_id.clone(), is_initiator);
+      }
+    });
+  }
+
   /// Handle an incoming SDP offer (receiver side).
   ///
   /// Per Req 10.3.14: if an existing connection for this peer already exists
@@ -1023,13 +1219,20 @@ impl WebRtcManager {
   /// PeerConnection. Tearing it down would drop the DataChannel, the
   /// established E2EE keys, and any in-flight media tracks.
   ///
+  /// `peer` is the sending user and device. Every device answers an
+  /// offer that is not addressed to a particular one, so the new
+  /// connection is keyed by, and bound to, the device that offered.
+  ///
   /// 1. Detects renegotiation vs. fresh connect
-  /// 2. (Fresh) Closes any existing connection for the peer
+  /// 2. (Fresh) Closes any existing connection for the device
   /// 3. (Fresh) Creates RTCPeerConnection
   /// 4. (Fresh) Sets up DataChannel handler
   /// 5. Handles offer and creates answer
   /// 6. Sends SdpAnswer via signaling
-  pub async fn handle_incoming_offer(&self, peer_id: UserId, sdp: &str) -> Result<(), WebRtcError> {
+  pub async fn handle_incoming_offer(&self, peer: PeerKey, sdp: &str) -> Result<(), WebRtcError> {
+    let from_device = peer.device_id;
+    let peer = self.inner.borrow().resolve_peer(&peer, true);
+    let peer_id = peer.user_id.clone();
     // Fast path: in-place renegotiation when an existing connection is
     // healthy enough to accept a new SDP without rebuild. We check for
     // both the connection and a stable signaling state under the same
@@ -1082,13 +1285,15 @@ impl WebRtcManager {
         )
       })?;
       if let Some(sig) = self.get_signaling() {
-        sig.send_sdp_answer(&peer_id, &answer_sdp).map_err(|e| {
-          WebRtcError::new(
-            ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
-            format!("Failed to send renegotiation SDP answer: {}", e),
-            Some(peer_id.clone()),
-          )
-        })?;
+        sig
+          .send_sdp_answer(&peer_id, pc.remote_device(), &answer_sdp)
+          .map_err(|e| {
+            WebRtcError::new(
+              ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
+              format!("Failed to send renegotiation SDP answer: {}", e),
+              Some(peer_id.clone()),
+            )
+          })?;
       }
       return Ok(());
     }
@@ -1101,31 +1306,34 @@ impl WebRtcManager {
     // borrow scope would have deadlocked. Closing under one lock also
     // removes a TOCTOU window where another `handle_incoming_offer` for
     // the same peer could interleave between the two borrows.
-    let replaced_peer = {
+    let (replaced_peer, last_connection) = {
       let mut inner = self.inner.borrow_mut();
-      Self::close_connection_locked(&mut inner, &peer_id)
+      let replaced = Self::close_connection_locked(&mut inner, &peer);
+      (replaced, inner.peer_keys(&peer_id).is_empty())
     };
     if replaced_peer {
       web_sys::console::log_1(
         &format!(
           "[webrtc] Replaced existing connection for peer {} before accepting new offer",
-          peer_id
+          peer
         )
         .into(),
       );
-      // Sync the UI-facing peer set, mirroring what `close_connection` does
+      // Sync the UI-facing peer set, mirroring what `close_peer` does
       // for its own callers. Must happen outside the RefCell borrow.
-      self
-        .app_state
-        .webrtc_state
-        .update(|s| s.remove_peer(&peer_id));
+      if last_connection {
+        self
+          .app_state
+          .webrtc_state
+          .update(|s| s.remove_peer(&peer_id));
+      }
     }
 
     // Atomically check total occupied slots and reserve a slot.
     let in_flight_rc = {
       let inner = self.inner.borrow_mut();
       let total = inner.connections.len() + inner.in_flight.get();
-      if total >= MAX_MESH_PEERS {
+      if total >= inner.peer_limit {
         return Err(WebRtcError::mesh_limit());
       }
       inner.in_flight.set(inner.in_flight.get() + 1);
@@ -1145,28 +1353,18 @@ impl WebRtcManager {
           Some(peer_id.clone()),
         )
       })?;
+      pc.bind_remote_device(from_device);
 
       // Set up ICE candidate handler
-      let signaling = self.get_signaling();
-      let ice_peer_id = peer_id.clone();
-      pc.set_on_ice_candidate(move |candidate| {
-        if let Some(ref sig) = signaling {
-          let _ = sig.send_sdp_ice_candidate(
-            ice_peer_id.clone(),
-            &candidate.candidate,
-            &candidate.sdp_mid,
-            candidate.sdp_m_line_index,
-          );
-        }
-      });
+      self.wire_ice_candidate_handler(&pc);
 
       // Set up connection state handler (P1-16: capture instance_id so stale
       // callbacks from a replaced PC can be detected and ignored).
       let manager = self.clone();
-      let state_peer_id = peer_id.clone();
+      let state_peer = peer.clone();
       let instance_id = pc.instance_id();
       pc.set_on_connection_state_change(move |state| {
-        manager.handle_connection_state_change(state_peer_id.clone(), state, instance_id);
+        manager.handle_connection_state_change(state_peer.clone(), state, instance_id);
       });
 
       // Wire the call-subsystem remote-track handler (Task 18). See
@@ -1180,36 +1378,34 @@ impl WebRtcManager {
 
       // Set up incoming DataChannel handler
       let manager_dc = self.clone();
-      let dc_peer_id = peer_id.clone();
+      let dc_peer = peer.clone();
       pc.set_on_data_channel(move |channel| {
-        web_sys::console::log_1(
-          &format!("[webrtc] Incoming DataChannel from {}", dc_peer_id).into(),
-        );
-        if let Ok(dc) = handle_incoming_channel(channel, dc_peer_id.clone()) {
+        web_sys::console::log_1(&format!("[webrtc] Incoming DataChannel from {}", dc_peer).into());
+        if let Ok(dc) = handle_incoming_channel(channel, dc_peer.user_id.clone()) {
           // IMPORTANT: Store the DataChannel on the peer connection
           // BEFORE setting up event handlers. If the DataChannel is
           // already in the Open state when we call set_on_open, the
           // callback fires synchronously and needs the DataChannel to
           // be available in the connection map (e.g. for ECDH key
           // exchange via initiate_ecdh_exchange).
-          manager_dc.setup_data_channel(dc_peer_id.clone(), dc.clone());
+          manager_dc.setup_data_channel(&dc_peer, dc.clone());
 
           // Set up open handler for ECDH key exchange.
           // PeerDataChannel::set_on_open includes a race-condition
           // guard that invokes the callback immediately if the
           // DataChannel is already open.
           let manager_open = manager_dc.clone();
-          let open_peer_id = dc_peer_id.clone();
+          let open_peer = dc_peer.clone();
           dc.set_on_open(move || {
-            manager_open.handle_data_channel_open(open_peer_id.clone());
+            manager_open.handle_data_channel_open(open_peer.clone());
           });
 
           // Task 19.1 — raw-frame dispatcher (see callee-side setup
           // in `connect_to_peer`).
           let manager_msg = manager_dc.clone();
-          let msg_peer_id = dc_peer_id.clone();
+          let msg_peer = dc_peer.clone();
           dc.set_on_raw_message(move |bytes| {
-            manager_msg.handle_data_channel_raw_frame(msg_peer_id.clone(), bytes);
+            manager_msg.handle_data_channel_raw_frame(msg_peer.clone(), bytes);
           });
         }
       });
@@ -1227,7 +1423,7 @@ impl WebRtcManager {
       .inner
       .borrow_mut()
       .connections
-      .insert(peer_id.clone(), pc.clone());
+      .insert(peer.clone(), pc.clone());
 
     // Handle offer and create answer (await without holding RefCell borrow)
     let answer_sdp = pc.handle_offer(sdp).await.map_err(|e| {
@@ -1241,24 +1437,24 @@ impl WebRtcManager {
     // Apply any trickle-ICE candidates that arrived during the
     // multi-await PC setup above. See
     // [`InnerManager::pending_ice_candidates`] for the rationale.
-    self.flush_pending_ice_candidates(&peer_id).await;
+    self.adopt_early_candidates(&peer, from_device);
+    self.flush_pending_ice_candidates(&peer).await;
 
     // Register the peer in the reactive UI state (receiver side,
     // so `is_initiator` is false). See `connect_to_peer` for rationale.
-    self
-      .app_state
-      .webrtc_state
-      .update(|s| s.add_peer(peer_id.clone(), false));
+    self.add_ui_peer(&peer_id, false);
 
     // Send SdpAnswer via signaling
     if let Some(sig) = self.get_signaling() {
-      sig.send_sdp_answer(&peer_id, &answer_sdp).map_err(|e| {
-        WebRtcError::new(
-          ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
-          format!("Failed to send SDP answer: {}", e),
-          Some(peer_id.clone()),
-        )
-      })?;
+      sig
+        .send_sdp_answer(&peer_id, pc.remote_device(), &answer_sdp)
+        .map_err(|e| {
+          WebRtcError::new(
+            ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
+            format!("Failed to send SDP answer: {}", e),
+            Some(peer_id.clone()),
+          )
+        })?;
     }
 
     // Eagerly start ECDH key generation on the answerer side so the
@@ -1268,7 +1464,7 @@ impl WebRtcManager {
     // from spawning a duplicate.
     {
       let manager = self.clone();
-      let pid = peer_id.clone();
+      let pid = peer.clone();
       wasm_bindgen_futures::spawn_local(async move {
         if let Err(e) = manager.initiate_ecdh_exchange(pid.clone()).await {
           web_sys::console::error_1(
@@ -1282,36 +1478,51 @@ impl WebRtcManager {
       });
     }
 
-    web_sys::console::log_1(
-      &format!("[webrtc] Handling incoming offer from peer {}", peer_id).into(),
-    );
+    web_sys::console::log_1(&format!("[webrtc] Handling incoming offer from peer {}", peer).into());
 
     Ok(())
   }
 
-  /// Handle an incoming SDP answer.
-  pub async fn handle_incoming_answer(
-    &self,
-    peer_id: UserId,
-    sdp: &str,
-  ) -> Result<(), WebRtcError> {
+  /// Handle an incoming SDP answer from `peer` (user and device).
+  ///
+  /// The first answer to an offer that was not addressed to a device
+  /// binds the connection to the answering device. A later answer from
+  /// another device of the same user opens a connection addressed to
+  /// that device instead.
+  pub async fn handle_incoming_answer(&self, peer: PeerKey, sdp: &str) -> Result<(), WebRtcError> {
+    let from_device = peer.device_id;
     // Extract the RtcPeerConnection (cloned JsValue) within a scoped borrow,
     // then drop the borrow before awaiting.
-    let pc = {
+    let (peer, existing) = {
       let inner = self.inner.borrow();
-      inner
+      let peer = inner.resolve_peer(&peer, true);
+      let existing = inner.connections.get(&peer).cloned();
+      (peer, existing)
+    };
+    let peer_id = peer.user_id.clone();
+    let Some(existing) = existing else {
+      let offered = self
+        .inner
+        .borrow()
         .connections
-        .get(&peer_id)
-        .ok_or_else(|| WebRtcError::peer_not_found(peer_id.clone()))?
-        .get_rtc_pc()
-        .map_err(|e| {
-          WebRtcError::new(
-            ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
-            format!("Invalid RTCPeerConnection: {}", e),
-            Some(peer_id.clone()),
-          )
-        })?
+        .contains_key(&PeerKey::user(peer_id.clone()));
+      if from_device.is_some() && offered {
+        web_sys::console::log_1(
+          &format!("[webrtc] Another device answered, connecting to {}", peer).into(),
+        );
+        return self.connect_to_peer_inner(peer, None).await;
+      }
+      return Err(WebRtcError::peer_not_found(peer_id));
     };
+    existing.bind_remote_device(from_device);
+    let pc = existing.get_rtc_pc().map_err(|e| {
+      WebRtcError::new(
+        ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
+        format!("Invalid RTCPeerConnection: {}", e),
+        Some(peer_id.clone()),
+      )
+    })?;
+    self.adopt_early_candidates(&peer, from_device);
 
     let answer_desc = web_sys::RtcSessionDescriptionInit::new(web_sys::RtcSdpType::Answer);
     answer_desc.set_sdp(sdp);
@@ -1333,15 +1544,20 @@ impl WebRtcManager {
       .webrtc_state
       .update(|s| s.update_connection_state(&peer_id, PeerConnectionState::Connecting));
 
-    web_sys::console::log_1(&format!("[webrtc] Handled answer from peer {}", peer_id).into());
+    self.flush_pending_ice_candidates(&peer).await;
+
+    web_sys::console::log_1(&format!("[webrtc] Handled answer from peer {}", peer).into());
 
     Ok(())
   }
 
-  /// Handle an incoming ICE candidate.
+  /// Handle an incoming ICE candidate from `peer` (user and device).
+  ///
+  /// Candidates from a device whose answer has not bound a connection
+  /// yet are held under the device's own key.
   pub async fn handle_incoming_ice_candidate(
     &self,
-    peer_id: UserId,
+    peer: PeerKey,
     candidate: &str,
     sdp_mid: &str,
     sdp_m_line_index: Option<u16>,
@@ -1355,9 +1571,11 @@ impl WebRtcManager {
     // and registers the connection. When that happens we stash the
     // candidate on `pending_ice_candidates` and let
     // `flush_pending_ice_candidates` apply it once the PC exists.
+    let peer_id = peer.user_id.clone();
     let pc = {
       let mut inner = self.inner.borrow_mut();
-      match inner.connections.get(&peer_id) {
+      let peer = inner.resolve_peer(&peer, false);
+      match inner.connections.get(&peer) {
         Some(pc) => pc.get_rtc_pc().map_err(|e| {
           WebRtcError::new(
             ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 2),
@@ -1368,13 +1586,13 @@ impl WebRtcManager {
         None => {
           let queue = inner
             .pending_ice_candidates
-            .entry(peer_id.clone())
+            .entry(peer.clone())
             .or_default();
           if queue.len() >= PENDING_ICE_LIMIT {
             web_sys::console::warn_1(
               &format!(
                 "[webrtc] Pending ICE queue for peer {} is full ({}); dropping oldest candidate",
-                peer_id, PENDING_ICE_LIMIT
+                peer, PENDING_ICE_LIMIT
               )
               .into(),
             );
@@ -1388,7 +1606,7 @@ impl WebRtcManager {
           web_sys::console::log_1(
             &format!(
               "[webrtc] Buffered early ICE candidate for peer {} (queue depth: {})",
-              peer_id,
+              peer,
               queue.len()
             )
             .into(),
@@ -1418,8 +1636,25 @@ impl WebRtcManager {
     Ok(())
   }
 
+  /// Move candidates that `from_device` sent before its connection was
+  /// bound, held under the device's own key, to `peer`.
+  fn adopt_early_candidates(&self, peer: &PeerKey, from_device: Option<DeviceId>) {
+    let device_key = PeerKey::new(peer.user_id.clone(), from_device);
+    if device_key == *peer {
+      return;
+    }
+    let mut inner = self.inner.borrow_mut();
+    if let Some(early) = inner.pending_ice_candidates.remove(&device_key) {
+      inner
+        .pending_ice_candidates
+        .entry(peer.clone())
+        .or_default()
+        .extend(early);
+    }
+  }
+
   /// Apply any ICE candidates that arrived before the local
-  /// [`PeerConnection`] for `peer_id` was stored. Called immediately
+  /// [`PeerConnection`] for `peer` was stored. Called immediately
   /// after a freshly-created PC lands in [`InnerManager::connections`]
   /// so trickle candidates that lost the race against the
   /// `handle_incoming_offer` await chain are still applied.
@@ -1428,12 +1663,8 @@ impl WebRtcManager {
   /// propagate — a single malformed candidate must not poison the
   /// rest of the queue, and the connection can still complete via
   /// the candidates that come after it.
-  async fn flush_pending_ice_candidates(&self, peer_id: &UserId) {
-    let drained = self
-      .inner
-      .borrow_mut()
-      .pending_ice_candidates
-      .remove(peer_id);
+  async fn flush_pending_ice_candidates(&self, peer: &PeerKey) {
+    let drained = self.inner.borrow_mut().pending_ice_candidates.remove(peer);
     let Some(queue) = drained else { return };
     if queue.is_empty() {
       return;
@@ -1442,7 +1673,7 @@ impl WebRtcManager {
     web_sys::console::log_1(
       &format!(
         "[webrtc] Flushing {} buffered ICE candidate(s) for peer {}",
-        count, peer_id
+        count, peer
       )
       .into(),
     );
@@ -1450,17 +1681,13 @@ impl WebRtcManager {
       let inner = self.inner.borrow();
       match inner
         .connections
-        .get(peer_id)
+        .get(peer)
         .and_then(|pc| pc.get_rtc_pc().ok())
       {
         Some(pc) => pc,
         None => {
           web_sys::console::warn_1(
-            &format!(
-              "[webrtc] Cannot flush ICE for peer {}: PC vanished",
-              peer_id
-            )
-            .into(),
+            &format!("[webrtc] Cannot flush ICE for peer {}: PC vanished", peer).into(),
           );
           return;
         }
@@ -1478,7 +1705,7 @@ impl WebRtcManager {
         web_sys::console::warn_1(
           &format!(
             "[webrtc] Failed to flush buffered ICE candidate for peer {}: {:?}",
-            peer_id, e
+            peer, e
           )
           .into(),
         );
@@ -1488,7 +1715,8 @@ impl WebRtcManager {
 
   /// Initiate ECDH key exchange with a peer.
   ///
-  /// Generates an ECDH key pair and stores the raw public key as pending.
+  /// Generates an ECDH key pair, signs the public key with our identity
+  /// key and stores the resulting exchange as pending.
   /// The key is sent over the DataChannel once it opens. If the
   /// DataChannel is already open (race: the `onopen` callback fired
   /// before this async function completed), the key is sent immediately.
@@ -1496,18 +1724,19 @@ impl WebRtcManager {
   /// Sets `ecdh_in_progress` before the first await so that
   /// `handle_data_channel_open` can detect an in-flight exchange and
   /// skip redundant proactive initiation.
-  async fn initiate_ecdh_exchange(&self, peer_id: UserId) -> Result<(), WebRtcError> {
+  async fn initiate_ecdh_exchange(&self, peer: PeerKey) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
     // Mark this peer as having an ECDH exchange in progress so
     // `handle_data_channel_open` does not spawn a duplicate.
     self
       .inner
       .borrow_mut()
       .ecdh_in_progress
-      .insert(peer_id.clone());
+      .insert(peer.clone());
 
     // Perform async operations first, without holding RefCell borrow
     let crypto = PeerCrypto::new(peer_id.clone()).await.map_err(|e| {
-      self.inner.borrow_mut().ecdh_in_progress.remove(&peer_id);
+      self.inner.borrow_mut().ecdh_in_progress.remove(&peer);
       WebRtcError::new(
         ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
         format!("Failed to create PeerCrypto: {}", e),
@@ -1516,7 +1745,7 @@ impl WebRtcManager {
     })?;
 
     let public_key = crypto.export_public_key().await.map_err(|e| {
-      self.inner.borrow_mut().ecdh_in_progress.remove(&peer_id);
+      self.inner.borrow_mut().ecdh_in_progress.remove(&peer);
       WebRtcError::new(
         ErrorCode::new(ErrorModule::E2e, ErrorCategory::Security, 1),
         format!("Failed to export public key: {}", e),
@@ -1524,6 +1753,13 @@ impl WebRtcManager {
       )
     })?;
 
+    let exchange = self
+      .sign_ecdh_key(&peer_id, public_key)
+      .await
+      .inspect_err(|_| {
+        self.inner.borrow_mut().ecdh_in_progress.remove(&peer);
+      })?;
+
     // Check if the DataChannel is already open (race condition fix):
     // The `handle_data_channel_open` callback may have already fired
     // while we were awaiting the crypto operations above. In that case
@@ -1533,7 +1769,7 @@ impl WebRtcManager {
       let inner = self.inner.borrow();
       inner
         .connections
-        .get(&peer_id)
+        .get(&peer)
         .and_then(|pc| pc.get_data_channel())
         .map(|dc| dc.ready_state() == web_sys::RtcDataChannelState::Open)
         .unwrap_or(false)
@@ -1550,24 +1786,24 @@ impl WebRtcManager {
     // key pair. Instead, just clear the in-progress flag and bail out.
     let crypto_already_established = {
       let mut inner = self.inner.borrow_mut();
-      if inner.crypto.contains_key(&peer_id) {
+      if inner.crypto.contains_key(&peer) {
         // Another path (handle_ecdh_key) already completed the
         // handshake. Don't overwrite — just clean up.
-        inner.ecdh_in_progress.remove(&peer_id);
+        inner.ecdh_in_progress.remove(&peer);
         true
       } else {
-        inner.crypto.insert(peer_id.clone(), crypto);
+        inner.crypto.insert(peer.clone(), crypto);
         if !dc_already_open {
           // DataChannel not yet open — buffer the key for the onopen callback.
           inner.pending_ecdh_keys.insert(
-            peer_id.clone(),
+            peer.clone(),
             PendingEcdh {
-              public_key: public_key.clone(),
+              exchange: exchange.clone(),
               started_at_ms: js_sys::Date::now(),
             },
           );
         }
-        inner.ecdh_in_progress.remove(&peer_id);
+        inner.ecdh_in_progress.remove(&peer);
         false
       }
     };
@@ -1576,7 +1812,7 @@ impl WebRtcManager {
       web_sys::console::log_1(
         &format!(
           "[webrtc] ECDH for peer {} already completed by handle_ecdh_key, skipping insert",
-          peer_id
+          peer
         )
         .into(),
       );
@@ -1588,24 +1824,22 @@ impl WebRtcManager {
       web_sys::console::log_1(
         &format!(
           "[webrtc] DataChannel already open for peer {}, sending ECDH key immediately",
-          peer_id
+          peer
         )
         .into(),
       );
-      self.send_datachannel_ecdh_key_direct(peer_id.clone(), &public_key);
+      self.send_datachannel_ecdh_key_direct(peer.clone(), exchange);
     }
 
-    web_sys::console::log_1(
-      &format!("[webrtc] Initiated ECDH exchange with peer {}", peer_id).into(),
-    );
+    web_sys::console::log_1(&format!("[webrtc] Initiated ECDH exchange with peer {}", peer).into());
 
     Ok(())
   }
 
   /// Store a DataChannel on an existing peer connection.
-  fn setup_data_channel(&self, peer_id: UserId, dc: PeerDataChannel) {
+  fn setup_data_channel(&self, peer: &PeerKey, dc: PeerDataChannel) {
     let mut inner = self.inner.borrow_mut();
-    if let Some(pc) = inner.connections.get_mut(&peer_id) {
+    if let Some(pc) = inner.connections.get_mut(peer) {
       pc.set_data_channel(dc);
     }
   }
@@ -1622,13 +1856,14 @@ impl WebRtcManager {
   /// drops any non-ECDH plaintext frame as a downgrade-attack guard.
   pub fn send_message(
     &self,
-    peer_id: UserId,
+    peer: &PeerKey,
     msg: &message::datachannel::DataChannelMessage,
   ) -> Result<(), WebRtcError> {
+    let peer_id = peer.user_id.clone();
     let inner = self.inner.borrow();
     let pc = inner
       .connections
-      .get(&peer_id)
+      .get(peer)
       .ok_or_else(|| WebRtcError::peer_not_found(peer_id.clone()))?;
 
     let dc = pc.get_data_channel().ok_or_else(|| {
@@ -1648,11 +1883,25 @@ impl WebRtcManager {
     })
   }
 
-  /// Close a peer connection.
+  /// Close the connections to every device of a peer.
   pub fn close_connection(&self, peer_id: &UserId) {
     let existed = {
       let mut inner = self.inner.borrow_mut();
-      Self::close_connection_locked(&mut inner, peer_id)
+      // Crypto can outlive its connection (a handshake racing a close),
+      // so sweep it by user too.
+      let mut keys = inner.peer_keys(peer_id);
+      keys.extend(
+        inner
+          .crypto
+          .keys()
+          .filter(|key| key.user_id == *peer_id)
+          .cloned(),
+      );
+      let mut existed = false;
+      for key in &keys {
+        existed |= Self::close_connection_locked(&mut inner, key);
+      }
+      existed
     };
 
     // Update app state outside the RefCell borrow; `remove_peer` is a no-op
@@ -1667,6 +1916,24 @@ impl WebRtcManager {
     web_sys::console::log_1(&format!("[webrtc] Closed connection to peer {}", peer_id).into());
   }
 
+  /// Close the connection to one device of a peer. The peer leaves the
+  /// UI state with its last connection.
+  fn close_peer(&self, peer: &PeerKey) {
+    let (existed, last_connection) = {
+      let mut inner = self.inner.borrow_mut();
+      let existed = Self::close_connection_locked(&mut inner, peer);
+      (existed, inner.peer_keys(&peer.user_id).is_empty())
+    };
+    if existed && last_connection {
+      self
+        .app_state
+        .webrtc_state
+        .update(|s| s.remove_peer(&peer.user_id));
+    }
+
+    web_sys::console::log_1(&format!("[webrtc] Closed connection to peer {}", peer).into());
+  }
+
   /// Shared cleanup helper: remove a peer's connection, crypto and pending
   /// ECDH state from an already-held `&mut InnerManager` borrow. Returns
   /// `true` if a `PeerConnection` was actually removed (i.e. the peer was
@@ -1679,37 +1946,38 @@ impl WebRtcManager {
   /// previous implementation used two sequential `borrow()` + `borrow_mut()`
   /// calls, which were safe today only because the first borrow was
   /// released before the second, but was fragile against future refactors.
-  fn close_connection_locked(inner: &mut InnerManager, peer_id: &UserId) -> bool {
-    let had_connection = if let Some(mut pc) = inner.connections.remove(peer_id) {
+  fn close_connection_locked(inner: &mut InnerManager, peer: &PeerKey) -> bool {
+    let had_connection = if let Some(mut pc) = inner.connections.remove(peer) {
       pc.close();
       true
     } else {
       false
     };
 
-    inner.crypto.remove(peer_id);
-    inner.pending_ecdh_keys.remove(peer_id);
+    inner.crypto.remove(peer);
+    inner.pending_ecdh_keys.remove(peer);
     // Clear any in-progress ECDH flag so the new connection's
     // `handle_data_channel_open` can proactively initiate a fresh
     // exchange. Without this, a stale flag from the old connection
     // would prevent the handshake from starting.
-    inner.ecdh_in_progress.remove(peer_id);
+    inner.ecdh_in_progress.remove(peer);
     // Task 19.1 C-1 — discard any queued control frames so a peer
     // that drops out mid-handshake cannot leak memory via
     // `pending_broadcast`.
-    inner.pending_broadcast.remove(peer_id);
+    inner.pending_broadcast.remove(peer);
     // Cancel any pending ICE restart timer so a closed peer does not
     // trigger a stale timeout callback.
-    inner.ice_restart_timers.remove(peer_id);
+    inner.ice_restart_timers.remove(peer);
     // Drop any trickle-ICE candidates we were holding for this peer
     // — without a PC they have nowhere to go.
-    inner.pending_ice_candidates.remove(peer_id);
+    inner.pending_ice_candidates.remove(peer);
 
     had_connection
   }
 
   /// Close all connections.
   pub fn close_all(&self) {
+    self.stop_sfu_session();
     let mut inner = self.inner.borrow_mut();
 
     // synchronously pause inbound transfers BEFORE closing
@@ -1718,14 +1986,14 @@ impl WebRtcManager {
     // `onconnectionstatechange` event (which may fire too late for
     // the E2E test that force-closes the WS).
     if let Some(file_mgr) = self.file_manager.borrow().clone() {
-      for peer_id in inner.connections.keys() {
-        file_mgr.pause_inbound_transfers(peer_id);
+      for peer in inner.connections.keys() {
+        file_mgr.pause_inbound_transfers(&peer.user_id);
       }
     }
 
-    for (peer_id, pc) in &mut inner.connections {
+    for (peer, pc) in &mut inner.connections {
       pc.close();
-      web_sys::console::log_1(&format!("[webrtc] Closed connection to peer {}", peer_id).into());
+      web_sys::console::log_1(&format!("[webrtc] Closed connection to peer {}", peer).into());
     }
 
     inner.connections.clear();
@@ -1741,7 +2009,7 @@ impl WebRtcManager {
     });
   }
 
-  /// Get the number of active connections.
+  /// Get the number of active connections (one per remote device).
   #[must_use]
   pub fn connection_count(&self) -> usize {
     self.inner.borrow().connections.len()
@@ -1755,7 +2023,7 @@ impl WebRtcManager {
   /// async boundaries.
   #[must_use]
   pub fn peer_ids(&self) -> Vec<UserId> {
-    self.inner.borrow().connections.keys().cloned().collect()
+    self.connected_peers()
   }
 
   /// Publish a local capture `MediaStream` on every currently-connected
@@ -1774,6 +2042,10 @@ impl WebRtcManager {
   /// notify the remote via `onnegotiationneeded`, and the existing
   /// signaling flow picks it up from there.
   pub fn publish_local_stream(&self, stream: &web_sys::MediaStream) {
+    if self.has_sfu_session() {
+      self.publish_to_sfu(stream);
+      return;
+    }
     let connections: Vec<PeerConnection> =
       self.inner.borrow().connections.values().cloned().collect();
     for pc in &connections {
@@ -1819,7 +2091,7 @@ impl WebRtcManager {
           match pc_clone.create_offer().await {
             Ok(sdp) => {
               if let Some(sig) = manager.get_signaling()
-                && let Err(e) = sig.send_sdp_offer(&peer_id, &sdp)
+                && let Err(e) = sig.send_sdp_offer(&peer_id, pc_clone.remote_device(), &sdp)
               {
                 web_sys::console::warn_1(
                   &format!(
@@ -1849,8 +2121,7 @@ impl WebRtcManager {
   /// connection. Used when the call ends or the user revokes media
   /// permission mid-call.
   pub fn unpublish_local_media(&self) {
-    let connections: Vec<PeerConnection> =
-      self.inner.borrow().connections.values().cloned().collect();
+    let connections: Vec<PeerConnection> = self.media_connections();
     for pc in &connections {
       pc.unpublish_local_media();
     }
@@ -1867,8 +2138,7 @@ impl WebRtcManager {
     new_track: &web_sys::MediaStreamTrack,
     stream: &web_sys::MediaStream,
   ) -> Result<(), String> {
-    let connections: Vec<PeerConnection> =
-      self.inner.borrow().connections.values().cloned().collect();
+    let connections: Vec<PeerConnection> = self.media_connections();
     for pc in &connections {
       pc.replace_local_track(new_track, stream)
         .await
@@ -1884,8 +2154,7 @@ impl WebRtcManager {
   ///
   /// Per-peer failures are logged but do not abort the sweep.
   pub async fn clear_local_track_of_kind(&self, kind: &str) {
-    let connections: Vec<PeerConnection> =
-      self.inner.borrow().connections.values().cloned().collect();
+    let connections: Vec<PeerConnection> = self.media_connections();
     for pc in &connections {
       if let Err(e) = pc.clear_local_track_of_kind(kind).await {
         web_sys::console::warn_1(
@@ -1927,7 +2196,7 @@ impl WebRtcManager {
       .borrow()
       .connections
       .iter()
-      .map(|(k, v)| (k.clone(), v.clone()))
+      .map(|(k, v)| (k.user_id.clone(), v.clone()))
       .collect();
 
     let mut out = Vec::with_capacity(connections.len());
@@ -1953,44 +2222,63 @@ impl WebRtcManager {
     out
   }
 
-  /// Check if connected to a specific peer.
+  /// Check if connected to any device of a specific peer.
   #[must_use]
   pub fn is_connected(&self, peer_id: &UserId) -> bool {
-    self.inner.borrow().connections.contains_key(peer_id)
+    self
+      .inner
+      .borrow()
+      .connections
+      .keys()
+      .any(|key| key.user_id == *peer_id)
   }
 
-  /// Check whether the local side initiated the connection to `peer_id`.
+  /// Check whether the local side initiated a connection to `peer_id`.
   /// Returns `false` if no connection exists or if the remote side
-  /// initiated it (i.e. we received an incoming offer).
+  /// initiated every one of them (i.e. we received incoming offers).
   pub fn is_initiator(&self, peer_id: &UserId) -> bool {
     self
       .inner
       .borrow()
       .connections
-      .get(peer_id)
-      .is_some_and(|pc| pc.is_initiator())
+      .iter()
+      .any(|(key, pc)| key.user_id == *peer_id && pc.is_initiator())
   }
 
-  /// Get the current `bufferedAmount` of a peer's DataChannel.
+  /// Get the current `bufferedAmount` of the DataChannel that carries
+  /// file chunks to a peer (the bulk channel, or the primary one for
+  /// single-channel peers).
   ///
   /// Returns `None` if the peer is not connected or has no
   /// DataChannel. Used by the file-transfer subsystem for flow
-  /// control (Req 6.4).
+  /// control (Req 6.4); with several devices the fullest channel
+  /// counts, since every device is sent the same chunks.
   #[must_use]
   pub fn buffered_amount(&self, peer_id: &UserId) -> Option<u32> {
     let inner = self.inner.borrow();
-    let pc = inner.connections.get(peer_id)?;
-    let dc = pc.get_data_channel()?;
-    dc.buffered_amount()
+    inner
+      .connections
+      .iter()
+      .filter(|(key, _)| key.user_id == *peer_id)
+      .filter_map(|(_, pc)| pc.channel_for(message::datachannel::ChannelClass::Bulk))
+      .filter_map(PeerDataChannel::buffered_amount)
+      .max()
   }
 
   /// Return a snapshot of all peer ids that currently have an active
-  /// connection. Used by the call subsystem's refresh-recovery path to
+  /// connection, once per peer however many devices it is connected
+  /// on. Used by the call subsystem's refresh-recovery path to
   /// iterate peers without leaking internal `HashMap` / `RefCell`
   /// references.
   #[must_use]
   pub fn connected_peers(&self) -> Vec<UserId> {
-    self.inner.borrow().connections.keys().cloned().collect()
+    let mut peers: Vec<UserId> = Vec::new();
+    for key in self.inner.borrow().connections.keys() {
+      if !peers.contains(&key.user_id) {
+        peers.push(key.user_id.clone());
+      }
+    }
+    peers
   }
 
   /// Handle connection state changes (stale callback guard).
@@ -2008,12 +2296,13 @@ impl WebRtcManager {
   /// must be ignored.
   fn handle_connection_state_change(
     &self,
-    peer_id: UserId,
+    peer: PeerKey,
     state: PeerConnectionState,
     instance_id: uuid::Uuid,
   ) {
+    let peer_id = peer.user_id.clone();
     web_sys::console::log_1(
-      &format!("[webrtc] Peer {} connection state: {:?}", peer_id, state).into(),
+      &format!("[webrtc] Peer {} connection state: {:?}", peer, state).into(),
     );
 
     // If the PeerConnection for this peer_id has been replaced
@@ -2023,31 +2312,49 @@ impl WebRtcManager {
       .inner
       .borrow()
       .connections
-      .get(&peer_id)
+      .get(&peer)
       .is_some_and(|pc| pc.instance_id() != instance_id);
 
     if is_stale {
       web_sys::console::log_1(
         &format!(
           "[webrtc] Ignoring stale connection state change for peer {} (old instance)",
-          peer_id
+          peer
         )
         .into(),
       );
       return;
     }
 
+    // The UI tracks peers, not devices: while another device of the
+    // peer stays connected, losing this one is not shown.
+    let other_devices = self
+      .inner
+      .borrow()
+      .peer_keys(&peer_id)
+      .iter()
+      .any(|key| *key != peer);
+    let peer_gone = !other_devices
+      || !matches!(
+        state,
+        PeerConnectionState::Disconnected
+          | PeerConnectionState::Failed
+          | PeerConnectionState::Closed
+      );
+
     // Update app state
-    self
-      .app_state
-      .webrtc_state
-      .update(|s| s.update_connection_state(&peer_id, state));
+    if peer_gone {
+      self
+        .app_state
+        .webrtc_state
+        .update(|s| s.update_connection_state(&peer_id, state));
+    }
 
     match state {
       PeerConnectionState::Connected => {
         // Cancel any pending ICE restart timer since the connection
         // has recovered (Req 10.5.24 — ICE restart success path).
-        self.inner.borrow_mut().ice_restart_timers.remove(&peer_id);
+        self.inner.borrow_mut().ice_restart_timers.remove(&peer);
 
         // Notify server about established peer
         if let Some(sig) = self.get_signaling() {
@@ -2099,13 +2406,13 @@ impl WebRtcManager {
         // Req 6.6 / P1-1 — pause any in-flight inbound file transfers
         // from this peer so they can be automatically resumed when the
         // connection recovers (or when the peer reconnects later).
-        if let Some(file_mgr) = self.file_manager.borrow().clone() {
+        if peer_gone && let Some(file_mgr) = self.file_manager.borrow().clone() {
           file_mgr.pause_inbound_transfers(&peer_id);
         }
 
         // Theater subsystem — Req 12.2 §6a (surface the 30-second
         // owner-reconnecting grace banner on viewers).
-        if let Some(handler) = self.on_theater_peer_event.borrow().clone() {
+        if peer_gone && let Some(handler) = self.on_theater_peer_event.borrow().clone() {
           handler(peer_id.clone(), TheaterPeerEvent::Disconnected);
         }
 
@@ -2116,7 +2423,7 @@ impl WebRtcManager {
           let inner = self.inner.borrow();
           inner
             .connections
-            .get(&peer_id)
+            .get(&peer)
             .map(|pc| pc.is_initiator())
             .unwrap_or(false)
         };
@@ -2126,34 +2433,37 @@ impl WebRtcManager {
           // treat the peer as Failed so recover_active_peers can
           // perform a full rebuild.
           let timeout_mgr = self.clone();
-          let timeout_peer_id = peer_id.clone();
+          let timeout_peer = peer.clone();
           if let Some(handle) = crate::utils::set_timeout_once(5000, move || {
-            timeout_mgr.handle_ice_restart_timeout(timeout_peer_id);
+            timeout_mgr.handle_ice_restart_timeout(timeout_peer);
           }) {
             self
               .inner
               .borrow_mut()
               .ice_restart_timers
-              .insert(peer_id.clone(), handle);
+              .insert(peer.clone(), handle);
           }
 
           // Initiate ICE restart offer asynchronously.
           let restart_mgr = self.clone();
-          let restart_peer_id = peer_id.clone();
+          let restart_peer = peer.clone();
           wasm_bindgen_futures::spawn_local(async move {
-            restart_mgr.initiate_ice_restart(restart_peer_id).await;
+            restart_mgr.initiate_ice_restart(restart_peer).await;
           });
         }
       }
       PeerConnectionState::Failed | PeerConnectionState::Closed => {
         // Cancel any pending ICE restart timer before cleanup.
-        self.inner.borrow_mut().ice_restart_timers.remove(&peer_id);
+        self.inner.borrow_mut().ice_restart_timers.remove(&peer);
 
         // Notify server and clean up
+        self.close_peer(&peer);
+        if !peer_gone {
+          return;
+        }
         if let Some(sig) = self.get_signaling() {
           let _ = sig.send_peer_closed(peer_id.clone());
         }
-        self.close_connection(&peer_id);
         // Req 6.6 / P1-1 — pause inbound file transfers from this peer
         // so they can be resumed if/when the peer reconnects.
         if let Some(file_mgr) = self.file_manager.borrow().clone() {
@@ -2162,7 +2472,7 @@ impl WebRtcManager {
         // Notify the call subsystem so it can drop the participant
         // from the grid and detect "all peers left" (Task 18).
         // We invoke the handler outside any inner borrow
-        // (close_connection above already released them).
+        // (close_peer above already released them).
         if let Some(handler) = self.on_peer_closed.borrow().clone() {
           handler(peer_id.clone());
         }
@@ -2218,14 +2528,15 @@ impl WebRtcManager {
 
   /// Initiate ICE restart for a peer by creating a new offer with
   /// iceRestart: true and sending it via signaling.
-  async fn initiate_ice_restart(&self, peer_id: UserId) {
+  async fn initiate_ice_restart(&self, peer: PeerKey) {
+    let peer_id = peer.user_id.clone();
     let pc = {
       let inner = self.inner.borrow();
-      match inner.connections.get(&peer_id).cloned() {
+      match inner.connections.get(&peer).cloned() {
         Some(pc) => pc,
         None => {
           web_sys::console::warn_1(
-            &format!("[webrtc] ICE restart: peer {} not found", peer_id).into(),
+            &format!("[webrtc] ICE restart: peer {} not found", peer).into(),
           );
           return;
         }
@@ -2262,7 +2573,7 @@ impl WebRtcManager {
     match pc.create_offer_with_ice_restart().await {
       Ok(sdp) => {
         if let Some(sig) = self.get_signaling()
-          && let Err(e) = sig.send_sdp_offer(&peer_id, &sdp)
+          && let Err(e) = sig.send_sdp_offer(&peer_id, pc.remote_device(), &sdp)
         {
           web_sys::console::warn_1(
             &format!("[webrtc] ICE restart: failed to send offer: {}", e).into(),
@@ -2279,24 +2590,24 @@ impl WebRtcManager {
 
   /// Called when ICE restart timer expires without the connection
   /// recovering. Treats the peer as Failed and triggers full teardown.
-  fn handle_ice_restart_timeout(&self, peer_id: UserId) {
+  fn handle_ice_restart_timeout(&self, peer: PeerKey) {
     web_sys::console::warn_1(
       &format!(
         "[webrtc] ICE restart timed out for {}, treating as Failed",
-        peer_id
+        peer
       )
       .into(),
     );
 
     // Remove the timer handle so it is not double-cancelled.
-    self.inner.borrow_mut().ice_restart_timers.remove(&peer_id);
+    self.inner.borrow_mut().ice_restart_timers.remove(&peer);
 
     // If the connection already recovered, do nothing.
     let current_state = {
       let inner = self.inner.borrow();
       inner
         .connections
-        .get(&peer_id)
+        .get(&peer)
         .and_then(|pc| pc.get_rtc_pc().ok())
         .map(|rtc| rtc.connection_state())
     };
@@ -2305,11 +2616,7 @@ impl WebRtcManager {
       && state == web_sys::RtcPeerConnectionState::Connected
     {
       web_sys::console::log_1(
-        &format!(
-          "[webrtc] Peer {} recovered before timeout, ignoring",
-          peer_id
-        )
-        .into(),
+        &format!("[webrtc] Peer {} recovered before timeout, ignoring", peer).into(),
       );
       return;
     }
@@ -2320,11 +2627,11 @@ impl WebRtcManager {
       let inner = self.inner.borrow();
       inner
         .connections
-        .get(&peer_id)
+        .get(&peer)
         .map(|pc| pc.instance_id())
         .unwrap_or_else(uuid::Uuid::new_v4)
     };
-    self.handle_connection_state_change(peer_id, PeerConnectionState::Failed, instance_id);
+    self.handle_connection_state_change(peer, PeerConnectionState::Failed, instance_id);
   }
 }
 
@@ -2380,22 +2687,22 @@ pub fn provide_webrtc_manager(app_state: AppState) -> WebRtcManager {
     let expired = prune_mgr.prune_expired_ecdh();
     // Auto-retry ECDH for expired peers that still have a live
     // DataChannel but no established encryption.
-    for peer_id in expired {
+    for peer in expired {
       let should_retry = {
         let inner = prune_mgr.inner.borrow();
         let has_open_dc = inner
           .connections
-          .get(&peer_id)
+          .get(&peer)
           .and_then(|pc| pc.get_data_channel())
           .map(|dc| dc.ready_state() == web_sys::RtcDataChannelState::Open)
           .unwrap_or(false);
-        let has_crypto = inner.crypto.contains_key(&peer_id);
-        let already_retrying = inner.ecdh_in_progress.contains(&peer_id);
+        let has_crypto = inner.crypto.contains_key(&peer);
+        let already_retrying = inner.ecdh_in_progress.contains(&peer);
         has_open_dc && !has_crypto && !already_retrying
       };
       if should_retry {
         let retry_mgr = prune_mgr.clone();
-        let retry_pid = peer_id.clone();
+        let retry_pid = peer.clone();
         wasm_bindgen_futures::spawn_local(async move {
           web_sys::console::log_1(
             &format!(
@@ -2487,10 +2794,12 @@ pub(super) fn build_file_placeholder(
     outgoing: false,
     status: MessageStatus::Received,
     reply_to: None,
+    thread_root: None,
     read_by: Vec::new(),
     reactions: BTreeMap::new(),
     mentions_me: false,
     counted_unread: false,
+    edit: None,
   }
 }
 
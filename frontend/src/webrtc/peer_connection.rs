@@ -5,14 +5,16 @@
 //! ICE candidate handling.
 
 use js_sys::{Array, Reflect};
-use message::UserId;
+use message::datachannel::ChannelClass;
+use message::{DeviceId, UserId};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 use web_sys::{
-  MediaStream, MediaStreamTrack, RtcConfiguration, RtcDataChannel, RtcIceServer, RtcPeerConnection,
-  RtcRtpSender, RtcTrackEvent,
+  MediaStream, MediaStreamTrack, RtcConfiguration, RtcDataChannel, RtcDataChannelState,
+  RtcIceServer, RtcPeerConnection, RtcRtpSender, RtcTrackEvent,
 };
 
 use super::data_channel::PeerDataChannel;
@@ -31,6 +33,10 @@ pub struct PeerConnection {
   pc: JsValue,
   /// The peer's user ID.
   peer_id: UserId,
+  /// Device of the peer this connection is bound to; `None` until the
+  /// first answer to an unaddressed offer arrives. Shared with the ICE
+  /// handler so later candidates are addressed to that device only.
+  remote_device: Rc<std::cell::Cell<Option<DeviceId>>>,
   /// Unique identifier for this PeerConnection instance.
   ///
   /// Used to detect stale `onconnectionstatechange` callbacks from a
@@ -42,6 +48,13 @@ pub struct PeerConnection {
   id: Rc<uuid::Uuid>,
   /// The DataChannel (if established).
   data_channel: Option<PeerDataChannel>,
+  /// Pre-negotiated channels opened next to the primary one, keyed by
+  /// class (see [`ChannelClass::EXTRA`]).
+  extra_channels: HashMap<ChannelClass, PeerDataChannel>,
+  /// Classes the peer announced in its `ChannelSetup`. Traffic is only
+  /// routed onto an extra channel once both sides opened it; peers
+  /// that never announce keep everything on the primary channel.
+  remote_classes: Vec<ChannelClass>,
   /// Whether we are the initiator (offer sender).
   is_initiator: bool,
   /// Stored ICE candidate closure to prevent memory leak.
@@ -92,7 +105,31 @@ impl PeerConnection {
     ice_servers: &[IceServerConfig],
   ) -> Result<Self, String> {
     let config = Self::build_configuration(ice_servers)?;
-    let pc = RtcPeerConnection::new_with_configuration(&config)
+    Self::with_configuration(peer_id, is_initiator, &config)
+  }
+
+  /// Create the media connection to the SFU.
+  ///
+  /// The relay has no user id of its own, so the connection is tagged
+  /// with the local user's id. Encoded insertable streams are enabled
+  /// so frames can be end-to-end encrypted before they reach the relay
+  /// (see `frame_crypto`).
+  ///
+  /// # Errors
+  /// Returns an error if the connection cannot be created.
+  pub fn new_sfu(local_id: UserId, ice_servers: &[IceServerConfig]) -> Result<Self, String> {
+    let config = Self::build_configuration(ice_servers)?;
+    Reflect::set(&config, &"encodedInsertableStreams".into(), &JsValue::TRUE)
+      .map_err(|_| "Failed to enable encoded insertable streams")?;
+    Self::with_configuration(local_id, true, &config)
+  }
+
+  fn with_configuration(
+    peer_id: UserId,
+    is_initiator: bool,
+    config: &RtcConfiguration,
+  ) -> Result<Self, String> {
+    let pc = RtcPeerConnection::new_with_configuration(config)
       .map_err(|e| format!("Failed to create RTCPeerConnection: {:?}", e))?;
 
     web_sys::console::log_1(
@@ -106,8 +143,11 @@ impl PeerConnection {
     Ok(Self {
       pc: JsValue::from(pc),
       peer_id,
+      remote_device: Rc::new(std::cell::Cell::new(None)),
       id: Rc::new(uuid::Uuid::new_v4()),
       data_channel: None,
+      extra_channels: HashMap::new(),
+      remote_classes: Vec::new(),
       is_initiator,
       on_ice_candidate: Rc::new(RefCell::new(None)),
       on_connection_state_change: Rc::new(RefCell::new(None)),
@@ -357,12 +397,82 @@ impl PeerConnection {
     self.data_channel.as_ref()
   }
 
+  /// Open the pre-negotiated channel for every extra class that does
+  /// not have one yet. Returns the newly created channels so the
+  /// caller can attach handlers; calling it again is a no-op.
+  ///
+  /// # Errors
+  /// Returns an error if the connection or a channel cannot be used.
+  pub fn open_extra_channels(&mut self) -> Result<Vec<(ChannelClass, PeerDataChannel)>, String> {
+    let pc = self.get_pc()?;
+    let mut created = Vec::new();
+    for class in ChannelClass::EXTRA {
+      if self.extra_channels.contains_key(&class) {
+        continue;
+      }
+      let channel = PeerDataChannel::create_negotiated(&pc, self.peer_id.clone(), class)?;
+      self.extra_channels.insert(class, channel.clone());
+      created.push((class, channel));
+    }
+    Ok(created)
+  }
+
+  /// Classes with a local extra channel, in [`ChannelClass::EXTRA`]
+  /// order.
+  #[must_use]
+  pub fn local_classes(&self) -> Vec<ChannelClass> {
+    ChannelClass::EXTRA
+      .into_iter()
+      .filter(|c| self.extra_channels.contains_key(c))
+      .collect()
+  }
+
+  /// Record the classes announced by the peer's `ChannelSetup`.
+  pub fn set_remote_classes(&mut self, classes: Vec<ChannelClass>) {
+    self.remote_classes = classes;
+  }
+
+  /// Channel that carries traffic of `class`: its own channel when
+  /// both sides opened one and it is open, the primary channel
+  /// otherwise.
+  #[must_use]
+  pub fn channel_for(&self, class: ChannelClass) -> Option<&PeerDataChannel> {
+    self
+      .extra_channels
+      .get(&class)
+      .filter(|dc| {
+        self.remote_classes.contains(&class) && dc.ready_state() == RtcDataChannelState::Open
+      })
+      .or(self.data_channel.as_ref())
+  }
+
   /// Get the peer ID.
   #[must_use]
   pub fn peer_id(&self) -> UserId {
     self.peer_id.clone()
   }
 
+  /// Device of the peer this connection is bound to.
+  #[must_use]
+  pub fn remote_device(&self) -> Option<DeviceId> {
+    self.remote_device.get()
+  }
+
+  /// Bind the connection to the peer's `device`. A connection stays
+  /// bound to its first device; `None` (a server that does not stamp
+  /// devices) leaves it unbound.
+  pub fn bind_remote_device(&self, device: Option<DeviceId>) {
+    if self.remote_device.get().is_none() {
+      self.remote_device.set(device);
+    }
+  }
+
+  /// Shared handle on the bound device, for callbacks that outlive
+  /// a borrow of the connection.
+  pub(crate) fn remote_device_handle(&self) -> Rc<std::cell::Cell<Option<DeviceId>>> {
+    Rc::clone(&self.remote_device)
+  }
+
   /// Check if we are the initiator.
   #[must_use]
   pub fn is_initiator(&self) -> bool {
@@ -417,6 +527,12 @@ impl PeerConnection {
     self.needs_manual_renegotiation.replace(false)
   }
 
+  /// Senders created by the last [`Self::publish_local_stream`] call.
+  #[must_use]
+  pub fn local_senders(&self) -> Vec<RtcRtpSender> {
+    self.local_senders.borrow().clone()
+  }
+
   ///
   /// # Errors
   /// Returns `Err` if the browser rejects `addTrack` (e.g. the track
@@ -565,6 +681,29 @@ impl PeerConnection {
     *self.on_track.borrow_mut() = Some(closure);
   }
 
+  /// Register an `ontrack` callback that receives the raw event, for
+  /// callers that need the track's `RTCRtpReceiver` (SFU frame
+  /// decryption). Replaces any handler set by [`Self::set_on_track`].
+  pub fn set_on_track_event<F>(&self, callback: F)
+  where
+    F: Fn(RtcTrackEvent) + 'static,
+  {
+    let pc = match self.get_pc() {
+      Ok(p) => p,
+      Err(e) => {
+        web_sys::console::error_1(&format!("[webrtc] Failed to set ontrack: {}", e).into());
+        return;
+      }
+    };
+
+    let closure = Closure::wrap(Box::new(move |event: RtcTrackEvent| {
+      callback(event);
+    }) as Box<dyn FnMut(RtcTrackEvent)>);
+
+    pc.set_ontrack(Some(closure.as_ref().unchecked_ref()));
+    *self.on_track.borrow_mut() = Some(closure);
+  }
+
   /// Register an `onnegotiationneeded` callback. The callback is fired
   /// by the browser whenever a track is added/removed/replaced and a
   /// new SDP offer/answer round-trip is required to apply the change.
@@ -647,6 +786,11 @@ impl PeerConnection {
       dc.close();
     }
     self.data_channel = None;
+    for dc in self.extra_channels.values() {
+      dc.close();
+    }
+    self.extra_channels.clear();
+    self.remote_classes.clear();
   }
 
   /// Get the underlying `RtcPeerConnection` (cloned `JsValue`).
@@ -11,7 +11,7 @@
 //!   `DataChannelMessage` fan-out that feeds chat, file, and call
 //!   subsystems.
 
-use super::{WebRtcManager, build_file_placeholder};
+use super::{PeerKey, WebRtcManager, build_file_placeholder};
 use leptos::prelude::GetUntracked;
 use message::UserId;
 use message::datachannel::DataChannelMessage;
@@ -30,9 +30,11 @@ fn resolve_conv_from_msg(
     DataChannelMessage::ChatVoice(m) => m.room_id.as_ref(),
     DataChannelMessage::ChatImage(m) => m.room_id.as_ref(),
     DataChannelMessage::ForwardMessage(m) => m.room_id.as_ref(),
-    // Control frames (ACK, revoke, read, reaction, typing) don't carry
-    // room_id — they are routed by the message index lookup inside the
-    // chat manager, so Direct(peer) is fine as a placeholder.
+    DataChannelMessage::ChatPoll(m) => m.room_id.as_ref(),
+    // Control frames (ACK, revoke, edit, read, reaction, typing, poll
+    // vote / close) don't carry room_id — they are routed by the
+    // message index lookup inside the chat manager, so Direct(peer) is
+    // fine as a placeholder.
     _ => None,
   };
   match room_id {
@@ -44,21 +46,25 @@ fn resolve_conv_from_msg(
 impl WebRtcManager {
   /// Handle a raw DataChannel frame (Task 19.1 — Req 5.1.3).
   ///
-  /// Routes the frame into one of two paths based on the first byte:
+  /// Routes the frame into one of three paths based on the first byte:
   ///
   /// 1. `ENCRYPTED_MARKER` (`0xFE`) → envelope frame. The IV+ciphertext
-  ///    is decrypted with the peer's shared key; the plaintext is then
+  ///    is decrypted with the connection's shared key; the plaintext is then
   ///    `[discriminator][bitcode]` and is decoded + dispatched to
   ///    `handle_data_channel_message` exactly like a plaintext frame.
   ///
-  /// 2. Any other first byte → plaintext frame. Used for ECDH
+  /// 2. `GROUP_ENCRYPTED_MARKER` (`0xFD`) → group envelope for a room
+  ///    conversation, decrypted with the sender's room key (see
+  ///    [`WebRtcManager::handle_group_frame`]).
+  ///
+  /// 3. Any other first byte → plaintext frame. Used for ECDH
   ///    bootstrap messages which cannot be encrypted because the
   ///    shared key has not been derived yet. The frame is decoded
   ///    with the historical `[discriminator][bitcode]` layout.
   ///
   /// Envelope decryption runs on an async task so a missing shared
   /// key or decrypt error does not block the JS microtask queue.
-  pub(super) fn handle_data_channel_raw_frame(&self, peer_id: UserId, bytes: Vec<u8>) {
+  pub(super) fn handle_data_channel_raw_frame(&self, peer: PeerKey, bytes: Vec<u8>) {
     use message::datachannel::DataChannelMessage;
 
     if bytes.is_empty() {
@@ -80,7 +86,7 @@ impl WebRtcManager {
         web_sys::console::warn_1(
           &format!(
             "[webrtc] Dropping undersized envelope from peer {} ({} B < {} B minimum)",
-            peer_id,
+            peer,
             bytes.len(),
             MIN_ENVELOPE_LEN
           )
@@ -91,16 +97,13 @@ impl WebRtcManager {
       let manager = self.clone();
       wasm_bindgen_futures::spawn_local(async move {
         let ciphertext = &bytes[1..];
-        let plaintext = match manager
-          .receive_encrypted_message(peer_id.clone(), ciphertext)
-          .await
-        {
+        let plaintext = match manager.receive_encrypted_message(&peer, ciphertext).await {
           Ok(pt) => pt,
           Err(e) => {
             web_sys::console::warn_1(
               &format!(
                 "[webrtc] Failed to decrypt envelope from peer {}: {}",
-                peer_id, e
+                peer, e
               )
               .into(),
             );
@@ -108,48 +111,17 @@ impl WebRtcManager {
           }
         };
 
-        if plaintext.is_empty() {
-          web_sys::console::warn_1(
-            &format!(
-              "[webrtc] Empty plaintext after decrypt from peer {}",
-              peer_id
-            )
-            .into(),
-          );
-          return;
-        }
-
-        let discriminator = plaintext[0];
-        let payload = &plaintext[1..];
-        match bitcode::decode::<DataChannelMessage>(payload) {
-          Ok(msg) => {
-            if msg.discriminator() != discriminator {
-              web_sys::console::warn_1(
-                &format!(
-                  "[webrtc] Discriminator mismatch in decrypted frame (expected 0x{:02X}, got 0x{:02X})",
-                  discriminator,
-                  msg.discriminator()
-                )
-                .into(),
-              );
-              return;
-            }
-            manager.handle_data_channel_message(peer_id, msg);
-          }
-          Err(e) => {
-            web_sys::console::error_1(
-              &format!(
-                "[webrtc] Failed to decode decrypted frame (type=0x{:02X}): {:?}",
-                discriminator, e
-              )
-              .into(),
-            );
-          }
-        }
+        manager.dispatch_decrypted_frame(peer, &plaintext);
       });
       return;
     }
 
+    // --- Group envelope path (room messages) --------------------------
+    if first == crate::webrtc::data_channel::GROUP_ENCRYPTED_MARKER {
+      self.handle_group_frame(peer, bytes);
+      return;
+    }
+
     // --- Plaintext path (ECDH bootstrap only) ------------------------
     let discriminator = first;
     let payload = &bytes[1..];
@@ -173,13 +145,13 @@ impl WebRtcManager {
           web_sys::console::warn_1(
             &format!(
               "[webrtc] Dropping non-ECDH plaintext frame from peer {} (type=0x{:02X}) — E2EE required",
-              peer_id, discriminator
+              peer, discriminator
             )
             .into(),
           );
           return;
         }
-        self.handle_data_channel_message(peer_id, msg);
+        self.handle_data_channel_message(peer, msg);
       }
       Err(e) => {
         web_sys::console::error_1(
@@ -193,27 +165,62 @@ impl WebRtcManager {
     }
   }
 
-  /// Handle incoming DataChannel message from a peer.
+  /// Decode a decrypted `[discriminator][bitcode]` plaintext and
+  /// dispatch it. Shared by the pairwise and group envelope paths.
+  pub(super) fn dispatch_decrypted_frame(&self, peer: PeerKey, plaintext: &[u8]) {
+    let Some((&discriminator, payload)) = plaintext.split_first() else {
+      web_sys::console::warn_1(
+        &format!("[webrtc] Empty plaintext after decrypt from peer {}", peer).into(),
+      );
+      return;
+    };
+    match bitcode::decode::<DataChannelMessage>(payload) {
+      Ok(msg) => {
+        if msg.discriminator() != discriminator {
+          web_sys::console::warn_1(
+            &format!(
+              "[webrtc] Discriminator mismatch in decrypted frame (expected 0x{:02X}, got 0x{:02X})",
+              discriminator,
+              msg.discriminator()
+            )
+            .into(),
+          );
+          return;
+        }
+        self.handle_data_channel_message(peer, msg);
+      }
+      Err(e) => {
+        web_sys::console::error_1(
+          &format!(
+            "[webrtc] Failed to decode decrypted frame (type=0x{:02X}): {:?}",
+            discriminator, e
+          )
+          .into(),
+        );
+      }
+    }
+  }
+
+  /// Handle incoming DataChannel message from a peer device.
   ///
   /// Invoked by [`handle_data_channel_raw_frame`] once the frame has
   /// been decoded to a typed [`DataChannelMessage`]. Fans out to the
   /// chat, file-transfer and call subsystems based on the variant.
   pub(super) fn handle_data_channel_message(
     &self,
-    peer_id: UserId,
+    peer: PeerKey,
     msg: message::datachannel::DataChannelMessage,
   ) {
     use message::datachannel::DataChannelMessage;
 
+    let peer_id = peer.user_id.clone();
     match msg {
       DataChannelMessage::EcdhKeyExchange(exchange) => {
-        web_sys::console::log_1(
-          &format!("[webrtc] Received ECDH key from peer {}", peer_id).into(),
-        );
+        web_sys::console::log_1(&format!("[webrtc] Received ECDH key from peer {}", peer).into());
         // Handle the ECDH key exchange asynchronously
         let manager = self.clone();
         wasm_bindgen_futures::spawn_local(async move {
-          if let Err(e) = manager.handle_ecdh_key(peer_id, &exchange.public_key).await {
+          if let Err(e) = manager.handle_signed_ecdh_key(peer, &exchange).await {
             web_sys::console::error_1(&format!("[webrtc] ECDH key handling failed: {}", e).into());
           }
         });
@@ -227,6 +234,10 @@ impl WebRtcManager {
       | DataChannelMessage::MessageRevoke(_)
       | DataChannelMessage::MessageRead(_)
       | DataChannelMessage::MessageReaction(_)
+      | DataChannelMessage::MessageEdit(_)
+      | DataChannelMessage::ChatPoll(_)
+      | DataChannelMessage::PollVote(_)
+      | DataChannelMessage::PollClose(_)
       | DataChannelMessage::TypingIndicator(_) => {
         // Task 16: forward to ChatManager via the inbound router.
         let chat = self.chat_manager.borrow().clone();
@@ -269,6 +280,13 @@ impl WebRtcManager {
           handler(peer_id, state);
         }
       }
+      DataChannelMessage::CallRecordingState(state) => {
+        // Forward to the call subsystem so every tile can show who is
+        // recording the call.
+        if let Some(handler) = self.on_call_recording_state.borrow().clone() {
+          handler(peer_id, state);
+        }
+      }
       DataChannelMessage::FileMetadata(meta) => {
         // Task 19 — announce a new inbound transfer. A placeholder
         // chat bubble is also injected so the receiver sees the
@@ -315,12 +333,34 @@ impl WebRtcManager {
         };
         file_mgr.on_file_resume_request(peer_id, request);
       }
+      DataChannelMessage::SenderKey(sender_key) => {
+        // Room conversations — the sender's group key for the current
+        // membership epoch (see `group_crypto`).
+        self.handle_sender_key(peer, sender_key);
+      }
+      DataChannelMessage::ChannelSetup(setup) => {
+        // The peer opened its extra channels; route traffic onto them
+        // from now on (see `channels`).
+        self.handle_channel_setup(&peer, setup);
+      }
+      DataChannelMessage::MediaKey(media_key) => {
+        // SFU rooms — the sender's frame key for media relayed by the
+        // server (see `sfu`).
+        self.handle_media_key(peer_id, media_key);
+      }
       DataChannelMessage::Danmaku(_)
       | DataChannelMessage::SubtitleData(_)
       | DataChannelMessage::SubtitleClear(_)
+      | DataChannelMessage::SubtitleOffset(_)
       | DataChannelMessage::PlaybackProgress(_)
       | DataChannelMessage::DanmakuBatch(_)
-      | DataChannelMessage::TheaterChatText(_) => {
+      | DataChannelMessage::TheaterChatText(_)
+      | DataChannelMessage::TheaterQueueAdd(_)
+      | DataChannelMessage::TheaterQueueRemove(_)
+      | DataChannelMessage::TheaterQueueReorder(_)
+      | DataChannelMessage::TheaterNowPlaying(_)
+      | DataChannelMessage::TheaterClockPing(_)
+      | DataChannelMessage::TheaterClockPong(_) => {
         // Req 12.3 – 12.6 — theater-class DataChannel messages. The
         // theater page installs the handler on mount; when no page is
         // currently mounted the message is silently ignored because
@@ -0,0 +1,428 @@
+//! SFU media session for [`WebRtcManager`].
+//!
+//! Rooms whose topology is [`RoomTopology::Sfu`] keep the usual
+//! per-peer connections for the E2EE data channels, but route audio
+//! and video through a single `RTCPeerConnection` to the server. The
+//! relay forwards every member's tracks to everyone else and announces
+//! who published what in an `SfuTrackMap` before each offer.
+//!
+//! Negotiation follows the "perfect negotiation" pattern with the
+//! client as the polite side: a server offer that collides with our
+//! own rolls our offer back (see [`PeerConnection::handle_offer`]) and
+//! `onnegotiationneeded` fires again once the exchange settles.
+//!
+//! Outgoing frames are encrypted per sender and incoming frames
+//! decrypted per publisher by [`frame_crypto`]; the keys travel as
+//! [`MediaKey`] messages over the data channels.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Reflect;
+use message::UserId;
+use message::datachannel::{DataChannelMessage, MediaKey};
+use message::error::{ErrorCategory, ErrorCode, ErrorModule};
+use message::signaling::{SfuIceCandidate, SfuTrack, SfuTrackMap};
+use message::types::{RoomId, RoomInfo, RoomTopology};
+use wasm_bindgen::{JsCast, JsValue};
+
+use super::frame_crypto::{self, FrameOp, FrameTransform};
+use super::{
+  IceCandidateData, MAX_MESH_PEERS, PeerConnection, PeerConnectionState, PeerKey, WebRtcError,
+  WebRtcManager,
+};
+
+/// The local member's media connection to the SFU.
+#[derive(Clone)]
+pub(super) struct SfuSession {
+  /// Room the session belongs to.
+  room_id: RoomId,
+  /// Connection to the relay.
+  pc: PeerConnection,
+  /// Tracks the relay forwards to us, from the latest track map.
+  tracks: Rc<RefCell<Vec<SfuTrack>>>,
+  /// Frame transforms attached to our senders and receivers. Dropped
+  /// with the session.
+  transforms: Rc<RefCell<Vec<FrameTransform>>>,
+}
+
+impl WebRtcError {
+  /// Convenience: relayed media cannot be end-to-end encrypted.
+  fn sfu_unsupported() -> Self {
+    Self::new(
+      ErrorCode::new(ErrorModule::E2e, ErrorCategory::Client, 6),
+      "Browser cannot encrypt media relayed by the server",
+      None,
+    )
+  }
+
+  /// Convenience: SFU negotiation failed.
+  fn sfu_negotiation(message: String) -> Self {
+    Self::new(
+      ErrorCode::new(ErrorModule::Sig, ErrorCategory::Network, 4),
+      message,
+      None,
+    )
+  }
+}
+
+impl WebRtcManager {
+  /// Open the media session for a room if it uses the SFU, replacing
+  /// any previous session. Also raises the peer limit so every member
+  /// gets a data-channel connection.
+  ///
+  /// # Errors
+  /// Returns an error if the browser cannot encrypt relayed frames or
+  /// the connection cannot be set up.
+  pub async fn start_sfu_session(&self, room_info: &RoomInfo) -> Result<(), WebRtcError> {
+    if room_info.topology != RoomTopology::Sfu {
+      return Ok(());
+    }
+    self.stop_sfu_session();
+    if !frame_crypto::encoded_transforms_supported() {
+      return Err(WebRtcError::sfu_unsupported());
+    }
+    let local_id = self
+      .app_state
+      .current_user_id()
+      .ok_or_else(|| WebRtcError::sfu_negotiation("Not authenticated".to_string()))?;
+    let room_id = room_info.room_id.clone();
+
+    let session = {
+      let inner = self.inner.borrow();
+      let pc = PeerConnection::new_sfu(local_id, &inner.ice_servers)
+        .map_err(WebRtcError::sfu_negotiation)?;
+      SfuSession {
+        room_id: room_id.clone(),
+        pc,
+        tracks: Rc::new(RefCell::new(Vec::new())),
+        transforms: Rc::new(RefCell::new(Vec::new())),
+      }
+    };
+    self.install_sfu_handlers(&session);
+
+    // Receive-only transceivers give the first offer media sections,
+    // so the relay can start forwarding before we publish anything.
+    let rtc = session
+      .pc
+      .get_rtc_pc()
+      .map_err(WebRtcError::sfu_negotiation)?;
+    for kind in ["audio", "video"] {
+      let init = js_sys::Object::new();
+      let _ = Reflect::set(&init, &"direction".into(), &"recvonly".into());
+      let add = Reflect::get(&rtc, &"addTransceiver".into())
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+      if let Some(add) = add {
+        let _ = add.call2(&rtc, &kind.into(), &init);
+      }
+    }
+
+    {
+      let mut inner = self.inner.borrow_mut();
+      inner.peer_limit = usize::from(room_info.max_members)
+        .saturating_sub(1)
+        .max(MAX_MESH_PEERS);
+      inner.sfu = Some(session.clone());
+    }
+    self.announce_media_key().await;
+
+    let sdp = session
+      .pc
+      .create_offer()
+      .await
+      .map_err(WebRtcError::sfu_negotiation)?;
+    if let Some(sig) = self.get_signaling() {
+      sig
+        .send_sfu_offer(room_id, &sdp)
+        .map_err(WebRtcError::sfu_negotiation)?;
+    }
+    Ok(())
+  }
+
+  /// Close the media session (room left, kicked or logged out) and
+  /// restore the mesh peer limit.
+  pub fn stop_sfu_session(&self) {
+    let session = {
+      let mut inner = self.inner.borrow_mut();
+      inner.peer_limit = MAX_MESH_PEERS;
+      inner.media_keys.borrow_mut().clear_remote();
+      inner.sfu.take()
+    };
+    let Some(mut session) = session else {
+      return;
+    };
+    session.pc.close();
+    session.transforms.borrow_mut().clear();
+    if let Some(sig) = self.get_signaling() {
+      let _ = sig.send_sfu_leave(session.room_id.clone());
+    }
+    web_sys::console::log_1(
+      &format!("[sfu] Closed media session for room {}", session.room_id).into(),
+    );
+  }
+
+  /// Whether media currently goes through the SFU.
+  #[must_use]
+  pub fn has_sfu_session(&self) -> bool {
+    self.inner.borrow().sfu.is_some()
+  }
+
+  /// Apply a renegotiation offer from the relay and answer it.
+  ///
+  /// # Errors
+  /// Returns an error if the offer cannot be applied.
+  pub async fn handle_sfu_offer(&self, room_id: RoomId, sdp: &str) -> Result<(), WebRtcError> {
+    let Some(session) = self.sfu_session(&room_id) else {
+      return Ok(());
+    };
+    let answer = session
+      .pc
+      .handle_offer(sdp)
+      .await
+      .map_err(WebRtcError::sfu_negotiation)?;
+    if let Some(sig) = self.get_signaling() {
+      sig
+        .send_sfu_answer(room_id, &answer)
+        .map_err(WebRtcError::sfu_negotiation)?;
+    }
+    Ok(())
+  }
+
+  /// Apply the relay's answer to our offer.
+  ///
+  /// # Errors
+  /// Returns an error if the answer cannot be applied.
+  pub async fn handle_sfu_answer(&self, room_id: RoomId, sdp: &str) -> Result<(), WebRtcError> {
+    let Some(session) = self.sfu_session(&room_id) else {
+      return Ok(());
+    };
+    session
+      .pc
+      .handle_answer(sdp)
+      .await
+      .map_err(WebRtcError::sfu_negotiation)
+  }
+
+  /// Add a trickled candidate from the relay.
+  ///
+  /// # Errors
+  /// Returns an error if the candidate is rejected.
+  pub async fn handle_sfu_ice_candidate(
+    &self,
+    candidate: SfuIceCandidate,
+  ) -> Result<(), WebRtcError> {
+    let Some(session) = self.sfu_session(&candidate.room_id) else {
+      return Ok(());
+    };
+    session
+      .pc
+      .add_ice_candidate(&IceCandidateData {
+        candidate: candidate.candidate,
+        sdp_mid: candidate.sdp_mid.unwrap_or_default(),
+        sdp_m_line_index: candidate.sdp_m_line_index,
+      })
+      .await
+      .map_err(WebRtcError::sfu_negotiation)
+  }
+
+  /// Record which member published each forwarded track.
+  pub fn handle_sfu_track_map(&self, map: SfuTrackMap) {
+    if let Some(session) = self.sfu_session(&map.room_id) {
+      *session.tracks.borrow_mut() = map.tracks;
+    }
+  }
+
+  /// Install a frame key announced by a room member.
+  pub(super) fn handle_media_key(&self, peer_id: UserId, media_key: MediaKey) {
+    let ring = Rc::clone(&self.inner.borrow().media_keys);
+    wasm_bindgen_futures::spawn_local(async move {
+      if let Err(e) = frame_crypto::install_remote_key(&ring, peer_id, media_key).await {
+        web_sys::console::warn_1(&format!("[sfu] {e}").into());
+      }
+    });
+  }
+
+  /// Send our current frame key to a peer whose data channel just
+  /// became encrypted, so it can decrypt what the relay forwards.
+  pub(super) fn send_media_key_to(&self, peer: &PeerKey) {
+    let key = {
+      let inner = self.inner.borrow();
+      if inner.sfu.is_none() {
+        return;
+      }
+      inner.media_keys.borrow().local_media_key()
+    };
+    if let Some(key) = key {
+      let manager = self.clone();
+      let peer = peer.clone();
+      wasm_bindgen_futures::spawn_local(async move {
+        let msg = DataChannelMessage::MediaKey(key);
+        if let Err(e) = manager
+          .send_encrypted_data_channel_message_to(&peer, &msg)
+          .await
+        {
+          web_sys::console::warn_1(&format!("[sfu] Failed to send media key: {e}").into());
+        }
+      });
+    }
+  }
+
+  /// Replace our frame key after a member left, so they cannot decrypt
+  /// media they may still receive through a lingering relay session.
+  pub fn rotate_media_key(&self, departed: &[UserId]) {
+    {
+      let inner = self.inner.borrow();
+      if inner.sfu.is_none() {
+        return;
+      }
+      let mut ring = inner.media_keys.borrow_mut();
+      for user_id in departed {
+        ring.remove_sender(user_id);
+      }
+    }
+    let manager = self.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+      manager.announce_media_key().await;
+    });
+  }
+
+  /// Generate a new local frame key and broadcast it to every peer.
+  async fn announce_media_key(&self) {
+    let ring = Rc::clone(&self.inner.borrow().media_keys);
+    match frame_crypto::rotate_local_key(&ring).await {
+      Ok(key) => self.broadcast_data_channel_message(&DataChannelMessage::MediaKey(key)),
+      Err(e) => web_sys::console::error_1(&format!("[sfu] Media key rotation failed: {e}").into()),
+    }
+  }
+
+  /// Publish the local capture stream on the relay connection, with
+  /// frame encryption attached to every new sender.
+  pub(super) fn publish_to_sfu(&self, stream: &web_sys::MediaStream) {
+    let Some(session) = self.inner.borrow().sfu.clone() else {
+      return;
+    };
+    if let Err(e) = session.pc.publish_local_stream(stream) {
+      web_sys::console::warn_1(&format!("[sfu] Failed to publish local stream: {e}").into());
+      return;
+    }
+    let ring = Rc::clone(&self.inner.borrow().media_keys);
+    for sender in session.pc.local_senders() {
+      let kind = sender.track().map(|t| t.kind()).unwrap_or_default();
+      match frame_crypto::attach_frame_transform(
+        &JsValue::from(sender),
+        kind,
+        FrameOp::Encrypt,
+        Rc::clone(&ring),
+      ) {
+        Ok(transform) => session.transforms.borrow_mut().push(transform),
+        Err(e) => web_sys::console::error_1(&format!("[sfu] Frame encryption failed: {e}").into()),
+      }
+    }
+  }
+
+  /// Connections that carry local media: the relay connection in an
+  /// SFU room, every peer connection otherwise.
+  pub(super) fn media_connections(&self) -> Vec<PeerConnection> {
+    let inner = self.inner.borrow();
+    match &inner.sfu {
+      Some(session) => vec![session.pc.clone()],
+      None => inner.connections.values().cloned().collect(),
+    }
+  }
+
+  fn sfu_session(&self, room_id: &RoomId) -> Option<SfuSession> {
+    self
+      .inner
+      .borrow()
+      .sfu
+      .as_ref()
+      .filter(|session| session.room_id == *room_id)
+      .cloned()
+  }
+
+  fn install_sfu_handlers(&self, session: &SfuSession) {
+    let signaling = self.get_signaling();
+    let room_id = session.room_id.clone();
+    session.pc.set_on_ice_candidate(move |candidate| {
+      if let Some(ref sig) = signaling {
+        let _ = sig.send_sfu_ice_candidate(
+          room_id.clone(),
+          &candidate.candidate,
+          &candidate.sdp_mid,
+          candidate.sdp_m_line_index,
+        );
+      }
+    });
+
+    // Polite side: only offer from a stable state; a colliding relay
+    // offer rolls ours back and the browser fires this event again.
+    let manager = self.clone();
+    let pc = session.pc.clone();
+    let room_id = session.room_id.clone();
+    session.pc.set_on_negotiation_needed(move || {
+      let manager = manager.clone();
+      let pc = pc.clone();
+      let room_id = room_id.clone();
+      wasm_bindgen_futures::spawn_local(async move {
+        let stable = pc
+          .get_rtc_pc()
+          .is_ok_and(|rtc| rtc.signaling_state() == web_sys::RtcSignalingState::Stable);
+        if !stable {
+          return;
+        }
+        match pc.create_offer().await {
+          Ok(sdp) => {
+            if let Some(sig) = manager.get_signaling() {
+              let _ = sig.send_sfu_offer(room_id, &sdp);
+            }
+          }
+          Err(e) => web_sys::console::warn_1(&format!("[sfu] Renegotiation failed: {e}").into()),
+        }
+      });
+    });
+
+    let manager = self.clone();
+    let transforms = Rc::clone(&session.transforms);
+    session.pc.set_on_track_event(move |event| {
+      let streams = event.streams();
+      let Some(stream) = streams.get(0).dyn_ref::<web_sys::MediaStream>().cloned() else {
+        return;
+      };
+      // The relay sets each forwarded stream's id to the publisher.
+      let Ok(publisher) = stream.id().parse::<UserId>() else {
+        return;
+      };
+      let receiver = Reflect::get(&event, &"receiver".into()).unwrap_or(JsValue::NULL);
+      let ring = Rc::clone(&manager.inner.borrow().media_keys);
+      match frame_crypto::attach_frame_transform(
+        &receiver,
+        event.track().kind(),
+        FrameOp::Decrypt(publisher.clone()),
+        ring,
+      ) {
+        Ok(transform) => transforms.borrow_mut().push(transform),
+        Err(e) => {
+          web_sys::console::error_1(&format!("[sfu] Frame decryption failed: {e}").into());
+          return;
+        }
+      }
+      if let Some(handler) = manager.on_remote_track.borrow().clone() {
+        handler(publisher, stream);
+      }
+    });
+
+    let manager = self.clone();
+    let room_id = session.room_id.clone();
+    session.pc.set_on_connection_state_change(move |state| {
+      if state == PeerConnectionState::Failed {
+        web_sys::console::warn_1(&format!("[sfu] Media session for room {room_id} failed").into());
+        // Closing drops this very callback, so leave the handler first.
+        let manager = manager.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+          manager.stop_sfu_session();
+        });
+      }
+    });
+  }
+}
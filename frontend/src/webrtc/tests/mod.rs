@@ -23,6 +23,7 @@ fn test_datachannel_message_discriminators() {
     message_id: message::MessageId(uuid::Uuid::new_v4()),
     content: "test".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 0,
     room_id: None,
     mentions: vec![],
@@ -32,6 +33,8 @@ fn test_datachannel_message_discriminators() {
   let ecdh = DataChannelMessage::EcdhKeyExchange(message::datachannel::EcdhKeyExchange {
     public_key: vec![0u8; 65], // P-256 raw format: 65 bytes
     timestamp_nanos: 0,
+    identity_key: vec![0u8; 65],
+    signature: vec![0u8; 64],
   });
   assert_eq!(ecdh.discriminator(), 0xA0);
 }
@@ -73,6 +76,7 @@ fn encrypted_marker_disjoint_from_every_discriminator() {
       message_id: message::MessageId(uuid::Uuid::new_v4()),
       content: "m".into(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -81,6 +85,8 @@ fn encrypted_marker_disjoint_from_every_discriminator() {
     DataChannelMessage::EcdhKeyExchange(message::datachannel::EcdhKeyExchange {
       public_key: vec![0u8; 65],
       timestamp_nanos: 0,
+      identity_key: vec![0u8; 65],
+      signature: vec![0u8; 64],
     })
     .discriminator(),
   ];
@@ -92,6 +98,151 @@ fn encrypted_marker_disjoint_from_every_discriminator() {
   }
   // 0xFE sits above the 0xC3 ceiling used by current message kinds.
   const { assert!(crate::webrtc::data_channel::ENCRYPTED_MARKER > 0xC3) };
+  const { assert!(crate::webrtc::data_channel::GROUP_ENCRYPTED_MARKER > 0xC3) };
+  const {
+    assert!(
+      crate::webrtc::data_channel::GROUP_ENCRYPTED_MARKER
+        != crate::webrtc::data_channel::ENCRYPTED_MARKER
+    )
+  };
+}
+
+// ── SFU frame encryption layout tests ──
+
+#[test]
+fn test_clear_header_len() {
+  use super::frame_crypto::clear_header_len;
+
+  assert_eq!(clear_header_len("audio", false, 100), 1);
+  assert_eq!(clear_header_len("video", true, 100), 10);
+  assert_eq!(clear_header_len("video", false, 100), 3);
+  // Never longer than the frame itself.
+  assert_eq!(clear_header_len("video", true, 4), 4);
+  assert_eq!(clear_header_len("audio", false, 0), 0);
+}
+
+#[test]
+fn test_frame_pack_unpack_roundtrip() {
+  use super::frame_crypto::{EncryptedFrame, FRAME_TRAILER_LEN, pack_frame, unpack_frame};
+
+  let header = [0x90, 0x01, 0x02];
+  let ciphertext = [7u8; 40];
+  let iv = [9u8; encryption::GCM_NONCE_SIZE];
+  let frame = pack_frame(&header, &ciphertext, &iv, 3);
+  assert_eq!(
+    frame.len(),
+    header.len() + ciphertext.len() + FRAME_TRAILER_LEN
+  );
+  assert_eq!(
+    unpack_frame(&frame, header.len()),
+    Some(EncryptedFrame {
+      header: &header,
+      ciphertext: &ciphertext,
+      iv: &iv,
+      key_id: 3,
+    })
+  );
+}
+
+#[test]
+fn test_unpack_rejects_truncated_frame() {
+  use super::frame_crypto::{FRAME_TRAILER_LEN, unpack_frame};
+
+  assert_eq!(unpack_frame(&[0u8; FRAME_TRAILER_LEN - 1], 0), None);
+  assert_eq!(unpack_frame(&[0u8; FRAME_TRAILER_LEN + 2], 3), None);
+  assert!(unpack_frame(&[0u8; FRAME_TRAILER_LEN + 3], 3).is_some());
+}
+
+// ── Room sender-key frame layout tests ──
+
+#[test]
+fn test_group_frame_header_layout() {
+  use super::group_crypto::{GROUP_HEADER_LEN, group_frame_header};
+
+  let room_id = message::RoomId::new();
+  let header = group_frame_header(&room_id, 0x0102_0304);
+  assert_eq!(header.len(), GROUP_HEADER_LEN);
+  assert_eq!(header[0], data_channel::GROUP_ENCRYPTED_MARKER);
+  assert_eq!(&header[1..17], room_id.0.as_bytes());
+  assert_eq!(&header[17..], &[1, 2, 3, 4]);
+}
+
+#[test]
+fn test_group_frame_unpack_roundtrip() {
+  use super::group_crypto::{GroupFrame, group_frame_header, unpack_group_frame};
+
+  let room_id = message::RoomId::new();
+  let header = group_frame_header(&room_id, 7);
+  let iv = [5u8; encryption::GCM_NONCE_SIZE];
+  let ciphertext = [9u8; 24];
+  let mut frame = header.to_vec();
+  frame.extend_from_slice(&iv);
+  frame.extend_from_slice(&ciphertext);
+
+  assert_eq!(
+    unpack_group_frame(&frame),
+    Some(GroupFrame {
+      room_id,
+      epoch: 7,
+      header: &header,
+      iv: &iv,
+      ciphertext: &ciphertext,
+    })
+  );
+}
+
+#[test]
+fn test_group_frame_unpack_rejects_invalid() {
+  use super::group_crypto::{MIN_GROUP_FRAME_LEN, group_frame_header, unpack_group_frame};
+
+  let mut frame = group_frame_header(&message::RoomId::new(), 0).to_vec();
+  frame.resize(MIN_GROUP_FRAME_LEN - 1, 0);
+  assert_eq!(unpack_group_frame(&frame), None);
+  frame.push(0);
+  assert!(unpack_group_frame(&frame).is_some());
+  // A pairwise envelope is never mistaken for a group frame.
+  frame[0] = data_channel::ENCRYPTED_MARKER;
+  assert_eq!(unpack_group_frame(&frame), None);
+}
+
+// ── Key exchange freshness tests ──
+
+#[test]
+fn test_key_exchange_freshness_window() {
+  use super::handshake::is_fresh_exchange;
+
+  let now = 1_700_000_000_000_000_000;
+  assert!(is_fresh_exchange(now, now, None));
+  assert!(is_fresh_exchange(
+    now - ECDH_MAX_CLOCK_SKEW_NANOS,
+    now,
+    None
+  ));
+  assert!(is_fresh_exchange(
+    now + ECDH_MAX_CLOCK_SKEW_NANOS,
+    now,
+    None
+  ));
+  assert!(!is_fresh_exchange(
+    now - ECDH_MAX_CLOCK_SKEW_NANOS - 1,
+    now,
+    None
+  ));
+  assert!(!is_fresh_exchange(
+    now + ECDH_MAX_CLOCK_SKEW_NANOS + 1,
+    now,
+    None
+  ));
+}
+
+#[test]
+fn test_key_exchange_replay_is_rejected() {
+  use super::handshake::is_fresh_exchange;
+
+  let now = 1_700_000_000_000_000_000;
+  assert!(!is_fresh_exchange(now - 10, now, Some(now - 10)));
+  assert!(!is_fresh_exchange(now - 20, now, Some(now - 10)));
+  assert!(is_fresh_exchange(now - 5, now, Some(now - 10)));
 }
 
 #[cfg(target_arch = "wasm32")]
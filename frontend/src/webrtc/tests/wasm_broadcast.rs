@@ -31,7 +31,7 @@ async fn test_broadcast_encrypted_message_multi_peer_independent_encryption() {
     let foreign_crypto = PeerCrypto::new(peer_id.clone()).await.unwrap();
     let foreign_pk = foreign_crypto.export_public_key().await.unwrap();
     manager
-      .handle_ecdh_key(peer_id.clone(), &foreign_pk)
+      .handle_ecdh_key(PeerKey::user(peer_id.clone()), &foreign_pk)
       .await
       .unwrap();
   }
@@ -49,7 +49,11 @@ async fn test_broadcast_encrypted_message_multi_peer_independent_encryption() {
   for peer_id in &peer_ids {
     let crypto = {
       let inner = manager.inner.borrow();
-      inner.crypto.get(peer_id).expect("crypto present").clone()
+      inner
+        .crypto
+        .get(&PeerKey::user(peer_id.clone()))
+        .expect("crypto present")
+        .clone()
     };
     let ct = crypto.encrypt(plaintext).await.unwrap();
     ciphertexts.push(ct);
@@ -99,7 +103,10 @@ async fn test_broadcast_encrypted_message_without_data_channels_returns_zero() {
     let peer_id = UserId::new();
     let foreign_crypto = PeerCrypto::new(peer_id.clone()).await.unwrap();
     let foreign_pk = foreign_crypto.export_public_key().await.unwrap();
-    manager.handle_ecdh_key(peer_id, &foreign_pk).await.unwrap();
+    manager
+      .handle_ecdh_key(PeerKey::user(peer_id), &foreign_pk)
+      .await
+      .unwrap();
   }
   assert_eq!(manager.encrypted_peers().len(), 2);
 
@@ -133,3 +140,32 @@ async fn test_broadcast_encrypted_message_no_peers_errors() {
     "error must mention missing peers, got: {err}"
   );
 }
+
+/// A membership change retires the local sender key before
+/// `rekey_room` returns, so the next room send cannot go out under the
+/// old epoch and instead generates the next one.
+#[wasm_bindgen_test]
+async fn test_rekey_room_retires_sender_key_until_next_send() {
+  let app_state = test_app_state();
+  let manager = WebRtcManager::new(app_state);
+  let room_id = message::RoomId::new();
+  let msg =
+    DataChannelMessage::TypingIndicator(message::datachannel::TypingIndicator { is_typing: true });
+  let ring = Rc::clone(&manager.inner.borrow().group_keys);
+
+  manager
+    .send_room_message(&room_id, &[], &msg)
+    .await
+    .unwrap();
+  assert_eq!(ring.borrow().local_sender_key(&room_id).unwrap().epoch, 0);
+
+  manager.rekey_room(&room_id, &[]);
+  assert!(ring.borrow().local_sender_key(&room_id).is_none());
+  assert!(ring.borrow().local_rooms().is_empty());
+
+  manager
+    .send_room_message(&room_id, &[], &msg)
+    .await
+    .unwrap();
+  assert_eq!(ring.borrow().local_sender_key(&room_id).unwrap().epoch, 1);
+}
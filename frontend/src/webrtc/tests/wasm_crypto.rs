@@ -21,7 +21,7 @@ async fn test_close_connection_clears_crypto_state() {
 
   // Establish encryption state for the peer.
   manager
-    .handle_ecdh_key(peer_id.clone(), &foreign_pk)
+    .handle_ecdh_key(PeerKey::user(peer_id.clone()), &foreign_pk)
     .await
     .unwrap();
   assert!(
@@ -54,7 +54,7 @@ async fn test_close_connection_clears_pending_ecdh_keys() {
   let foreign_crypto = PeerCrypto::new(UserId::from(99u64)).await.unwrap();
   let foreign_pk = foreign_crypto.export_public_key().await.unwrap();
   manager
-    .handle_ecdh_key(peer_id.clone(), &foreign_pk)
+    .handle_ecdh_key(PeerKey::user(peer_id.clone()), &foreign_pk)
     .await
     .unwrap();
 
@@ -78,7 +78,7 @@ async fn test_handle_ecdh_key_first_time_establishes_shared_key() {
   assert!(!manager.has_encryption_key(&peer_id));
 
   manager
-    .handle_ecdh_key(peer_id.clone(), &foreign_pk)
+    .handle_ecdh_key(PeerKey::user(peer_id.clone()), &foreign_pk)
     .await
     .unwrap();
 
@@ -118,16 +118,21 @@ fn test_prune_expired_ecdh_flags_timed_out_peer() {
   {
     let mut inner = manager.inner.borrow_mut();
     inner.pending_ecdh_keys.insert(
-      peer_id.clone(),
+      PeerKey::user(peer_id.clone()),
       PendingEcdh {
-        public_key: vec![0u8; 65],
+        exchange: message::datachannel::EcdhKeyExchange {
+          public_key: vec![0u8; 65],
+          timestamp_nanos: 0,
+          identity_key: vec![0u8; 65],
+          signature: vec![0u8; 64],
+        },
         started_at_ms: 0.0,
       },
     );
   }
 
   let expired = manager.prune_expired_ecdh();
-  assert_eq!(expired, vec![peer_id.clone()]);
+  assert_eq!(expired, vec![PeerKey::user(peer_id.clone())]);
 
   // Pending entry should have been drained.
   assert!(
@@ -135,7 +140,7 @@ fn test_prune_expired_ecdh_flags_timed_out_peer() {
       .inner
       .borrow()
       .pending_ecdh_keys
-      .contains_key(&peer_id)
+      .contains_key(&PeerKey::user(peer_id.clone()))
   );
 
   // UI state must mirror the timeout.
@@ -158,9 +163,14 @@ fn test_prune_expired_ecdh_preserves_fresh_entry() {
   {
     let mut inner = manager.inner.borrow_mut();
     inner.pending_ecdh_keys.insert(
-      peer_id.clone(),
+      PeerKey::user(peer_id.clone()),
       PendingEcdh {
-        public_key: vec![0u8; 65],
+        exchange: message::datachannel::EcdhKeyExchange {
+          public_key: vec![0u8; 65],
+          timestamp_nanos: 0,
+          identity_key: vec![0u8; 65],
+          signature: vec![0u8; 64],
+        },
         started_at_ms: js_sys::Date::now(),
       },
     );
@@ -173,6 +183,39 @@ fn test_prune_expired_ecdh_preserves_fresh_entry() {
       .inner
       .borrow()
       .pending_ecdh_keys
-      .contains_key(&peer_id)
+      .contains_key(&PeerKey::user(peer_id.clone()))
   );
 }
+
+/// Two devices of one member each hold their own sender key for the
+/// same room and epoch; installing one must not replace the other, and
+/// the member leaving drops both.
+#[wasm_bindgen_test]
+async fn test_group_key_ring_keeps_sender_key_per_device() {
+  use crate::webrtc::group_crypto::{self, GroupKeyRing};
+
+  let room_id = message::RoomId::new();
+  let user_id = UserId::from(90u64);
+  let laptop = PeerKey::new(user_id.clone(), Some(DeviceId::new()));
+  let phone = PeerKey::new(user_id.clone(), Some(DeviceId::new()));
+
+  let ring = Rc::new(RefCell::new(GroupKeyRing::default()));
+  for sender in [&laptop, &phone] {
+    let remote = Rc::new(RefCell::new(GroupKeyRing::default()));
+    let key = group_crypto::rotate_sender_key(&remote, &room_id)
+      .await
+      .unwrap();
+    group_crypto::install_sender_key(&ring, sender.clone(), key)
+      .await
+      .unwrap();
+  }
+
+  assert!(ring.borrow().has_remote_key(&room_id, &laptop, 0));
+  assert!(ring.borrow().has_remote_key(&room_id, &phone, 0));
+  let other = PeerKey::new(user_id.clone(), Some(DeviceId::new()));
+  assert!(!ring.borrow().has_remote_key(&room_id, &other, 0));
+
+  ring.borrow_mut().remove_sender(&room_id, &user_id);
+  assert!(!ring.borrow().has_remote_key(&room_id, &laptop, 0));
+  assert!(!ring.borrow().has_remote_key(&room_id, &phone, 0));
+}
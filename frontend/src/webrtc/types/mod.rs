@@ -3,8 +3,44 @@
 //! Defines types for managing RTCPeerConnection state, DataChannel state,
 //! and encryption status.
 
-use message::UserId;
+use message::{DeviceId, UserId};
 use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies one peer connection in the mesh: a remote user and the
+/// device the connection is bound to.
+///
+/// `device_id` is `None` while an outgoing connection waits for its
+/// first answer (the offer went to every device of the user) and for
+/// servers that do not stamp devices on signaling messages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PeerKey {
+  /// Remote user ID.
+  pub user_id: UserId,
+  /// Remote device, once known.
+  pub device_id: Option<DeviceId>,
+}
+
+impl PeerKey {
+  /// Key for `user_id` on `device_id`.
+  pub fn new(user_id: UserId, device_id: Option<DeviceId>) -> Self {
+    Self { user_id, device_id }
+  }
+
+  /// Key for `user_id` with no known device.
+  pub fn user(user_id: UserId) -> Self {
+    Self::new(user_id, None)
+  }
+}
+
+impl fmt::Display for PeerKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.device_id {
+      Some(device) => write!(f, "{}/{device}", self.user_id),
+      None => write!(f, "{}", self.user_id),
+    }
+  }
+}
 
 /// Connection state for a peer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -81,6 +117,11 @@ pub struct PeerEncryptionStatus {
   /// automatically by `mark_encryption_established` (successful handshake
   /// supersedes a prior timeout) and by `clear_encryption`.
   pub handshake_timed_out: bool,
+  /// True when the peer presented an identity key that differs from
+  /// the one pinned on first contact. UI layers render a prominent
+  /// warning until the user accepts the new key. Survives
+  /// `clear_encryption` on purpose — a reconnect must not hide it.
+  pub identity_changed: bool,
 }
 
 impl PeerEncryptionStatus {
@@ -101,6 +142,9 @@ pub struct PeerState {
   pub encryption: PeerEncryptionStatus,
   /// Whether we are the initiator (offer sender).
   pub is_initiator: bool,
+  /// Safety number derived from both identity keys, set once the
+  /// peer's signed key exchange has been verified.
+  pub safety_number: Option<String>,
 }
 
 impl PeerState {
@@ -112,6 +156,7 @@ impl PeerState {
       data_channel_state: None,
       encryption: PeerEncryptionStatus::default(),
       is_initiator,
+      safety_number: None,
     }
   }
 
@@ -215,6 +260,26 @@ impl WebRtcState {
     }
   }
 
+  /// Record the verified identity of a peer: its safety number and
+  /// whether the identity key differs from the pinned one. Each device
+  /// of the peer reports separately, so a changed key stays flagged
+  /// until [`Self::clear_identity_changed`], whatever the other devices
+  /// present.
+  pub fn set_peer_identity(&mut self, user_id: &UserId, safety_number: String, changed: bool) {
+    if let Some(peer) = self.peers.get_mut(user_id) {
+      peer.safety_number = Some(safety_number);
+      peer.encryption.identity_changed |= changed;
+    }
+  }
+
+  /// Clear the identity-change warning after the user accepted the
+  /// peer's new identity key.
+  pub fn clear_identity_changed(&mut self, user_id: &UserId) {
+    if let Some(peer) = self.peers.get_mut(user_id) {
+      peer.encryption.identity_changed = false;
+    }
+  }
+
   /// Get the number of connected peers.
   pub fn connected_count(&self) -> usize {
     self.peers.values().filter(|p| p.is_ready()).count()
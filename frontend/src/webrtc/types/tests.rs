@@ -1,5 +1,21 @@
 use super::*;
 
+// ── PeerKey tests ──
+
+#[test]
+fn test_peer_key_distinguishes_devices() {
+  let user_id = UserId::new();
+  let phone = PeerKey::new(user_id.clone(), Some(DeviceId::new()));
+  let laptop = PeerKey::new(user_id.clone(), Some(DeviceId::new()));
+  assert_ne!(phone, laptop);
+  assert_ne!(phone, PeerKey::user(user_id.clone()));
+  assert_eq!(
+    PeerKey::user(user_id.clone()).to_string(),
+    user_id.to_string()
+  );
+  assert!(phone.to_string().starts_with(&format!("{user_id}/")));
+}
+
 // ── PeerConnectionState tests ──
 
 #[test]
@@ -82,6 +98,7 @@ fn test_peer_encryption_status_is_copy() {
     key_id: 3,
     established: true,
     handshake_timed_out: false,
+    identity_changed: false,
   };
   let copy = status;
   assert_eq!(status, copy);
@@ -441,3 +458,45 @@ fn test_webrtc_state_add_peer_replaces_existing() {
   assert!(!peer.is_initiator);
   assert_eq!(peer.connection_state, PeerConnectionState::Connecting);
 }
+
+#[test]
+fn test_webrtc_state_set_peer_identity() {
+  let mut state = WebRtcState::new();
+  let user_id = UserId::new();
+  state.add_peer(user_id.clone(), true);
+  assert!(state.get_peer(&user_id).unwrap().safety_number.is_none());
+
+  state.set_peer_identity(&user_id, "12345".to_string(), true);
+  let peer = state.get_peer(&user_id).unwrap();
+  assert_eq!(peer.safety_number.as_deref(), Some("12345"));
+  assert!(peer.encryption.identity_changed);
+
+  // Another device presenting its pinned key does not hide the warning.
+  state.set_peer_identity(&user_id, "12345".to_string(), false);
+  assert!(
+    state
+      .get_peer(&user_id)
+      .unwrap()
+      .encryption
+      .identity_changed
+  );
+
+  // A reconnect resets the handshake but keeps the warning visible.
+  state.clear_encryption(&user_id);
+  assert!(
+    state
+      .get_peer(&user_id)
+      .unwrap()
+      .encryption
+      .identity_changed
+  );
+
+  state.clear_identity_changed(&user_id);
+  assert!(
+    !state
+      .get_peer(&user_id)
+      .unwrap()
+      .encryption
+      .identity_changed
+  );
+}
@@ -6,7 +6,7 @@
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use crate::types::{DanmakuPosition, MessageId, RoomId, TransferId, UserId};
+use crate::types::{DanmakuPosition, DeviceId, MessageId, RoomId, TransferId, UserId};
 
 // =============================================================================
 // Message Type Discriminator Constants
@@ -48,11 +48,25 @@ pub mod discriminator {
   /// Message read receipt type.
   pub const MESSAGE_READ: u8 = 0x93;
 
-  // Message Enhancement (0x94-0x95)
+  // Message Enhancement (0x94-0x96)
   /// Forward message type.
   pub const FORWARD_MESSAGE: u8 = 0x94;
   /// Message reaction type.
   pub const MESSAGE_REACTION: u8 = 0x95;
+  /// Message edit type — replaces the content of a previously sent
+  /// text message in place, keeping its `MessageId` stable so reply
+  /// chains survive the correction.
+  pub const MESSAGE_EDIT: u8 = 0x96;
+
+  // Polls (0x97-0x99)
+  /// Poll creation type — a question with a fixed list of options,
+  /// shown as a chat bubble.
+  pub const CHAT_POLL: u8 = 0x97;
+  /// Poll vote type — the sender's complete current choice set for
+  /// one poll, replacing any earlier vote.
+  pub const POLL_VOTE: u8 = 0x98;
+  /// Poll close type — the poll creator stops accepting votes.
+  pub const POLL_CLOSE: u8 = 0x99;
 
   // Encryption (0xA0)
   /// ECDH key exchange type.
@@ -64,7 +78,18 @@ pub mod discriminator {
   /// Avatar data type.
   pub const AVATAR_DATA: u8 = 0xA2;
 
-  // Theater (0xB0-0xB5)
+  // Group encryption (0xA3)
+  /// Room sender key type — the sender's group key for one room
+  /// membership epoch, delivered over the pairwise E2EE channel.
+  pub const SENDER_KEY: u8 = 0xA3;
+
+  // Transport (0xA4)
+  /// Channel setup type — lists the extra `DataChannel`s the sender
+  /// opened next to the primary one, so the receiver may route
+  /// traffic onto them (see [`super::ChannelClass`]).
+  pub const CHANNEL_SETUP: u8 = 0xA4;
+
+  // Theater (0xB0-0xBC)
   /// Danmaku message type.
   pub const DANMAKU: u8 = 0xB0;
   /// Playback progress type.
@@ -82,8 +107,24 @@ pub mod discriminator {
   /// the generic [`CHAT_TEXT`] so the routing layer can branch on
   /// theater membership without guessing.
   pub const THEATER_CHAT_TEXT: u8 = 0xB5;
-
-  // Call-side status broadcasts (0xC0-0xC1)
+  /// Theater watch-queue addition — a member proposes an item, or
+  /// the owner relays an accepted item to every viewer.
+  pub const THEATER_QUEUE_ADD: u8 = 0xB6;
+  /// Theater watch-queue removal.
+  pub const THEATER_QUEUE_REMOVE: u8 = 0xB7;
+  /// Theater watch-queue reorder (owner-only, carries the full order).
+  pub const THEATER_QUEUE_REORDER: u8 = 0xB8;
+  /// Theater now-playing announcement (owner-only).
+  pub const THEATER_NOW_PLAYING: u8 = 0xB9;
+  /// Theater clock-sync probe (viewer → owner, NTP-style).
+  pub const THEATER_CLOCK_PING: u8 = 0xBA;
+  /// Theater clock-sync reply (owner → probing viewer).
+  pub const THEATER_CLOCK_PONG: u8 = 0xBB;
+  /// Subtitle timing offset — shifts the current track on every
+  /// viewer without re-sending its cues.
+  pub const SUBTITLE_OFFSET: u8 = 0xBC;
+
+  // Call-side status broadcasts (0xC0-0xC3)
   /// Local media state broadcast (mic / camera / screen-share flags).
   /// Delivered to every call participant whenever a local toggle fires,
   /// so remote `VideoTile`s can render muted / camera-off icons without
@@ -95,6 +136,14 @@ pub mod discriminator {
   /// hint without relying on server-observed WebSocket state
   /// (Req 10.5.24).
   pub const RECONNECTING_STATE: u8 = 0xC1;
+  /// Frame-encryption key for the sender's media in an SFU room.
+  /// Delivered over the E2EE data channel so the relaying server never
+  /// sees it.
+  pub const MEDIA_KEY: u8 = 0xC2;
+  /// Local call-recording status broadcast. Sent when a participant
+  /// starts or stops recording the call so every other participant is
+  /// told (and can leave) before any of their media is captured.
+  pub const CALL_RECORDING_STATE: u8 = 0xC3;
 }
 
 // =============================================================================
@@ -110,6 +159,9 @@ pub struct ChatText {
   pub content: String,
   /// Reply-to message ID (optional).
   pub reply_to: Option<MessageId>,
+  /// Root message of the thread this message replies in. `None` for
+  /// messages on the main timeline.
+  pub thread_root: Option<MessageId>,
   /// Sender timestamp in nanoseconds since Unix epoch.
   pub timestamp_nanos: u64,
   /// Room ID when sent inside a room conversation. `None` for 1:1 chats.
@@ -133,6 +185,9 @@ pub struct ChatSticker {
   pub sticker_id: String,
   /// Reply-to message ID (optional).
   pub reply_to: Option<MessageId>,
+  /// Root message of the thread this message replies in. `None` for
+  /// messages on the main timeline.
+  pub thread_root: Option<MessageId>,
   /// Sender timestamp in nanoseconds since Unix epoch.
   pub timestamp_nanos: u64,
   /// Room ID when sent inside a room conversation. `None` for 1:1 chats.
@@ -152,6 +207,9 @@ pub struct ChatVoice {
   pub waveform: Vec<u8>,
   /// Reply-to message ID (optional).
   pub reply_to: Option<MessageId>,
+  /// Root message of the thread this message replies in. `None` for
+  /// messages on the main timeline.
+  pub thread_root: Option<MessageId>,
   /// Sender timestamp in nanoseconds since Unix epoch.
   pub timestamp_nanos: u64,
   /// Room ID when sent inside a room conversation. `None` for 1:1 chats.
@@ -173,6 +231,9 @@ pub struct ChatImage {
   pub height: u32,
   /// Reply-to message ID (optional).
   pub reply_to: Option<MessageId>,
+  /// Root message of the thread this message replies in. `None` for
+  /// messages on the main timeline.
+  pub thread_root: Option<MessageId>,
   /// Sender timestamp in nanoseconds since Unix epoch.
   pub timestamp_nanos: u64,
   /// Room ID when sent inside a room conversation. `None` for 1:1 chats.
@@ -282,6 +343,25 @@ pub struct MessageRevoke {
   pub timestamp_nanos: u64,
 }
 
+/// Edit of a previously sent text message.
+///
+/// Only the original sender may edit, and only `ChatText` content.
+/// `revision` starts at `1` for the first edit and increases by one
+/// per edit; receivers ignore revisions that are not newer than the
+/// one they already display, so duplicated or reordered frames are
+/// harmless.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MessageEdit {
+  /// Message ID being edited.
+  pub message_id: MessageId,
+  /// Replacement content (Markdown supported).
+  pub content: String,
+  /// Revision number of the new content.
+  pub revision: u32,
+  /// Edit timestamp in nanoseconds since Unix epoch.
+  pub timestamp_nanos: u64,
+}
+
 /// Typing indicator.
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
 pub struct TypingIndicator {
@@ -342,11 +422,78 @@ pub struct MessageReaction {
   pub timestamp_nanos: u64,
 }
 
+// =============================================================================
+// Polls
+// =============================================================================
+
+/// Poll posted into a conversation.
+///
+/// Options are addressed by their index in `options`, which never
+/// changes after creation. Tallies are not carried on the wire: every
+/// receiver reconciles them from the [`PollVote`] frames it sees.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct ChatPoll {
+  /// Unique message ID, also used as the poll ID.
+  pub message_id: MessageId,
+  /// Poll question.
+  pub question: String,
+  /// Answer options, in display order.
+  pub options: Vec<String>,
+  /// Whether a voter may pick more than one option.
+  pub multiple_choice: bool,
+  /// Whether voter identities are hidden from other participants.
+  pub anonymous: bool,
+  /// Deadline in nanoseconds since Unix epoch after which votes are
+  /// ignored. `None` keeps the poll open until its creator closes it.
+  pub deadline_nanos: Option<u64>,
+  /// Reply-to message ID (optional).
+  pub reply_to: Option<MessageId>,
+  /// Root message of the thread this poll is posted in. `None` for
+  /// polls on the main timeline.
+  pub thread_root: Option<MessageId>,
+  /// Sender timestamp in nanoseconds since Unix epoch.
+  pub timestamp_nanos: u64,
+  /// Room ID when sent inside a room conversation. `None` for 1:1 chats.
+  pub room_id: Option<RoomId>,
+}
+
+/// Vote on a [`ChatPoll`].
+///
+/// Carries the voter's whole choice set rather than a delta, so the
+/// newest vote per voter (by `timestamp_nanos`) simply replaces the
+/// previous one and duplicated or reordered frames are harmless. An
+/// empty `choices` list retracts the vote.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PollVote {
+  /// Poll (message) ID being voted on.
+  pub poll_id: MessageId,
+  /// Indices into [`ChatPoll::options`].
+  pub choices: Vec<u32>,
+  /// Vote timestamp in nanoseconds since Unix epoch.
+  pub timestamp_nanos: u64,
+}
+
+/// Closes a [`ChatPoll`]. Receivers only honour it from the poll's
+/// creator.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PollClose {
+  /// Poll (message) ID being closed.
+  pub poll_id: MessageId,
+  /// Close timestamp in nanoseconds since Unix epoch.
+  pub timestamp_nanos: u64,
+}
+
 // =============================================================================
 // Encryption
 // =============================================================================
 
-/// ECDH public key for key exchange.
+/// ECDH public key for key exchange, signed by the sender's long-term
+/// identity key.
+///
+/// The ephemeral `public_key` alone is unauthenticated: anyone able to
+/// tamper with the signaling path could substitute their own. The
+/// `signature` binds it to `identity_key`, which peers pin on first
+/// contact and compare out of band via the safety number.
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
 pub struct EcdhKeyExchange {
   /// Sender's ECDH public key (P-256 raw uncompressed point, 65 bytes).
@@ -356,6 +503,13 @@ pub struct EcdhKeyExchange {
   pub public_key: Vec<u8>,
   /// Key exchange timestamp in nanoseconds.
   pub timestamp_nanos: u64,
+  /// Sender's long-term identity public key (ECDSA P-256 raw
+  /// uncompressed point, 65 bytes).
+  pub identity_key: Vec<u8>,
+  /// ECDSA P-256 / SHA-256 signature over
+  /// [`EcdhKeyExchange::signing_payload`], in the IEEE P1363 `r || s`
+  /// form produced by Web Crypto (64 bytes).
+  pub signature: Vec<u8>,
 }
 
 // =============================================================================
@@ -472,8 +626,77 @@ pub struct SubtitleEntry {
   pub start_ms: u32,
   /// End time in milliseconds.
   pub end_ms: u32,
-  /// Subtitle text.
+  /// Subtitle text. For styled cues this is the plain text of all
+  /// [`SubtitleCueStyle::spans`] joined together.
   pub text: String,
+  /// Script-authored styling (ASS/SSA). `None` for SRT / `WebVTT` cues,
+  /// which render with the viewer's own overlay settings.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub style: Option<SubtitleCueStyle>,
+}
+
+/// Styling and placement of a single ASS/SSA cue.
+///
+/// Sizes and coordinates are stored as per-mille of the script's
+/// playback resolution so every viewer can scale them to the size of
+/// their own video element.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SubtitleCueStyle {
+  /// Font family requested by the script (used when installed).
+  pub font_name: Option<String>,
+  /// Font size as per-mille of the video height.
+  pub font_size_permille: Option<u16>,
+  /// Numpad-style alignment (1–9); `2` is bottom centre.
+  pub alignment: u8,
+  /// Vertical margin as per-mille of the video height.
+  pub margin_v_permille: u16,
+  /// Explicit anchor point (`\pos`), overriding alignment margins.
+  pub position: Option<SubtitleAnchor>,
+  /// Styled runs making up the cue, in display order. Line breaks are
+  /// embedded as `\n`.
+  pub spans: Vec<SubtitleSpan>,
+}
+
+/// Anchor point of a positioned cue, as per-mille of the video size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SubtitleAnchor {
+  /// Horizontal position (0 = left edge, 1000 = right edge).
+  pub x_permille: u16,
+  /// Vertical position (0 = top edge, 1000 = bottom edge).
+  pub y_permille: u16,
+}
+
+/// A run of cue text sharing one set of inline styles.
+// The flags mirror the ASS `\b` / `\i` / `\u` / `\s` override tags
+// one-to-one; packing them into a bitfield would only obscure that.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SubtitleSpan {
+  /// Run text.
+  pub text: String,
+  /// Bold weight.
+  pub bold: bool,
+  /// Italic.
+  pub italic: bool,
+  /// Underline.
+  pub underline: bool,
+  /// Strike-through.
+  pub strike_out: bool,
+  /// Fill colour (RGB hex, e.g. `0xFFFFFF`).
+  pub color: Option<u32>,
+  /// Outline colour (RGB hex).
+  pub outline_color: Option<u32>,
+  /// Karaoke syllable timing, for `\k`-style lines.
+  pub karaoke: Option<SubtitleKaraoke>,
+}
+
+/// Karaoke timing of one syllable, relative to the cue start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SubtitleKaraoke {
+  /// Offset from the cue start at which the syllable is sung.
+  pub offset_ms: u32,
+  /// How long the syllable is sung for.
+  pub duration_ms: u32,
 }
 
 /// Clear subtitle display.
@@ -483,6 +706,141 @@ pub struct SubtitleClear {
   pub room_id: RoomId,
 }
 
+/// Global timing offset for the current subtitle track.
+///
+/// Applied on top of the cues from the last [`SubtitleData`]: a cue is
+/// shown at `start_ms + offset_ms`. Sent on its own so the owner can
+/// nudge timing live without re-sending the whole track.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SubtitleOffset {
+  /// Room ID.
+  pub room_id: RoomId,
+  /// Offset in milliseconds; positive delays the subtitles.
+  pub offset_ms: i32,
+}
+
+/// Where a queued theater item is played from.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TheaterQueueSource {
+  /// Remote `http(s)` URL fetched directly by the owner's browser.
+  Url(String),
+  /// File on the owner's machine, identified by its filename. The
+  /// object URL backing it never leaves the owner, so only the owner
+  /// can queue local files.
+  LocalFile(String),
+}
+
+/// Subtitle track attached to a queued item. Installed (and broadcast
+/// as [`SubtitleData`]) when the item starts playing.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterQueueSubtitle {
+  /// Origin filename, shown in the queue panel.
+  pub filename: String,
+  /// Parsed subtitle cues.
+  pub entries: Vec<SubtitleEntry>,
+}
+
+/// Single entry of the theater watch queue.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterQueueItem {
+  /// Unique item ID, generated by the proposer.
+  pub item_id: MessageId,
+  /// Playback source.
+  pub source: TheaterQueueSource,
+  /// Display title (filename or URL host by default).
+  pub title: String,
+  /// User who proposed the item. Proposers may withdraw their own
+  /// items; everything else is owner-controlled.
+  pub proposed_by: UserId,
+  /// Optional subtitle track attached to the item.
+  pub subtitle: Option<TheaterQueueSubtitle>,
+}
+
+/// Add an item to the theater watch queue.
+///
+/// Viewers send this to the owner as a proposal; the owner appends
+/// accepted items and relays the same frame to every viewer. Duplicate
+/// `item_id`s are ignored, so replaying the queue to a late joiner is
+/// harmless.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterQueueAdd {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Item to append.
+  pub item: TheaterQueueItem,
+}
+
+/// Remove an item from the theater watch queue.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterQueueRemove {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Item to remove.
+  pub item_id: MessageId,
+  /// User asking for the removal — the owner, or the item's proposer
+  /// withdrawing their own proposal.
+  pub requested_by: UserId,
+}
+
+/// Reorder the theater watch queue (owner-only).
+///
+/// Carries the complete new order rather than a single move so that
+/// applying the same frame twice yields the same queue.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterQueueReorder {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Item IDs in their new order.
+  pub item_ids: Vec<MessageId>,
+}
+
+/// Announce which queue item the owner is playing (owner-only).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterNowPlaying {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Item now playing, or `None` once the queue has run out or the
+  /// owner switched to a source outside the queue.
+  pub item_id: Option<MessageId>,
+  /// Sender timestamp in nanoseconds since Unix epoch.
+  pub timestamp_nanos: u64,
+}
+
+/// Clock-sync probe sent by a theater viewer to the owner.
+///
+/// Together with [`TheaterClockPong`] this forms an NTP-style
+/// four-timestamp exchange from which the viewer estimates its clock
+/// offset to the owner and the one-way network delay, so
+/// [`PlaybackProgress::timestamp_nanos`] can be used to extrapolate
+/// the owner's current playback position.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterClockPing {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Probe sequence number, echoed back in the pong.
+  pub seq: u32,
+  /// Viewer clock when the probe was sent (nanoseconds since Unix
+  /// epoch).
+  pub client_send_nanos: u64,
+}
+
+/// Owner's reply to a [`TheaterClockPing`], sent only to the viewer
+/// that probed.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TheaterClockPong {
+  /// Room ID of the theater.
+  pub room_id: RoomId,
+  /// Sequence number copied from the probe.
+  pub seq: u32,
+  /// `client_send_nanos` copied from the probe.
+  pub client_send_nanos: u64,
+  /// Owner clock when the probe arrived.
+  pub owner_recv_nanos: u64,
+  /// Owner clock when this reply was sent.
+  pub owner_send_nanos: u64,
+}
+
 // =============================================================================
 // Call Status Broadcasts (Req 3.5 / 7.1 / 10.5.24)
 // =============================================================================
@@ -512,6 +870,167 @@ pub struct ReconnectingState {
   pub reconnecting: bool,
 }
 
+/// Call-recording status broadcast.
+///
+/// Recording happens entirely on the recorder's device; this frame
+/// only tells the other participants that it is going on. Sent with
+/// `recording = true` when the recorder starts (and again to peers
+/// that join mid-recording) and with `recording = false` when it
+/// stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct CallRecordingState {
+  /// Whether the sender is currently recording the call.
+  pub recording: bool,
+}
+
+/// Frame-encryption key for the sender's media in an SFU room.
+///
+/// The SFU relays RTP it cannot decrypt: every sender encrypts its
+/// encoded audio / video frames with this key before packetisation,
+/// and hands the key to each room member over the (already E2EE)
+/// data channel. Sent again with a new `key_id` whenever the sender
+/// rotates, e.g. after a member leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MediaKey {
+  /// Key generation. Carried in the trailer of every encrypted frame
+  /// so receivers can pick the right key across a rotation.
+  pub key_id: u8,
+  /// Raw AES-256-GCM key bytes.
+  pub key: Vec<u8>,
+}
+
+/// The sender's group key for a room conversation.
+///
+/// Room messages are encrypted once under the sender's key and the
+/// same ciphertext is sent to every member. Each member generates a
+/// fresh key whenever the membership changes (join, leave, kick, ban)
+/// and hands it only to the current members, so a removed member
+/// cannot read anything sent afterwards.
+///
+/// Every device of a member holds a key of its own, so receivers keep
+/// one key per sender device.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SenderKey {
+  /// Room the key is used in.
+  pub room_id: RoomId,
+  /// Device that encrypts under the key.
+  pub device_id: DeviceId,
+  /// Membership epoch. Carried in every group frame so receivers can
+  /// pick the right key across a rotation.
+  pub epoch: u32,
+  /// Raw AES-256-GCM key bytes.
+  pub key: Vec<u8>,
+}
+
+// =============================================================================
+// Channel Routing
+// =============================================================================
+
+/// Transport class of a peer `DataChannel`.
+///
+/// Every peer connection has a primary, reliable and ordered channel
+/// (labelled `"chat"`) that carries the ECDH handshake and anything
+/// not routed elsewhere. Peers that support it add one negotiated
+/// channel per extra class so a large file transfer cannot
+/// head-of-line-block chat, and lost typing or playback ticks are not
+/// retransmitted. [`ChannelClass::for_discriminator`] is the routing
+/// table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelClass {
+  /// Reliable, ordered: chat, receipts, file metadata and control
+  /// frames. Served by the primary channel.
+  Control,
+  /// Reliable, unordered: bulk binary data (file chunks, avatars).
+  Bulk,
+  /// Unreliable, unordered: ephemeral state where a newer frame
+  /// supersedes a lost one (typing, playback ticks, danmaku).
+  Ephemeral,
+}
+
+impl ChannelClass {
+  /// Classes carried on their own negotiated channel, in the order
+  /// they are opened.
+  pub const EXTRA: [Self; 2] = [Self::Bulk, Self::Ephemeral];
+
+  /// Channel class for a message type discriminator. Unknown values
+  /// stay on [`Self::Control`], the safe default.
+  #[must_use]
+  pub const fn for_discriminator(discriminator: u8) -> Self {
+    match discriminator {
+      discriminator::FILE_CHUNK | discriminator::AVATAR_DATA => Self::Bulk,
+      discriminator::TYPING_INDICATOR
+      | discriminator::PLAYBACK_PROGRESS
+      | discriminator::DANMAKU
+      | discriminator::DANMAKU_BATCH
+      | discriminator::THEATER_CLOCK_PING
+      | discriminator::THEATER_CLOCK_PONG => Self::Ephemeral,
+      _ => Self::Control,
+    }
+  }
+
+  /// `DataChannel` label.
+  #[must_use]
+  pub const fn label(self) -> &'static str {
+    match self {
+      Self::Control => "chat",
+      Self::Bulk => "bulk",
+      Self::Ephemeral => "ephemeral",
+    }
+  }
+
+  /// Whether the channel delivers messages in order.
+  #[must_use]
+  pub const fn ordered(self) -> bool {
+    matches!(self, Self::Control)
+  }
+
+  /// Retransmission limit; `None` means fully reliable.
+  #[must_use]
+  pub const fn max_retransmits(self) -> Option<u16> {
+    match self {
+      Self::Ephemeral => Some(0),
+      Self::Control | Self::Bulk => None,
+    }
+  }
+
+  /// SCTP stream id of the pre-negotiated channel. `None` for the
+  /// primary channel, which is announced in-band and gets id 0 or 1
+  /// from the browser. The ids stay low enough for every browser's
+  /// default stream limit.
+  #[must_use]
+  pub const fn stream_id(self) -> Option<u16> {
+    match self {
+      Self::Control => None,
+      Self::Bulk => Some(16),
+      Self::Ephemeral => Some(17),
+    }
+  }
+
+  /// Send priority hint (`RTCPriorityType`): control traffic goes
+  /// ahead of bulk data when the SCTP association is congested.
+  #[must_use]
+  pub const fn priority(self) -> &'static str {
+    match self {
+      Self::Control => "high",
+      Self::Bulk => "very-low",
+      Self::Ephemeral => "medium",
+    }
+  }
+}
+
+/// Announces the extra channels the sender has opened.
+///
+/// Sent (encrypted) on the primary channel once the ECDH handshake
+/// completes. A peer routes a class onto its own channel only after
+/// the other side listed it here; peers that never send this frame
+/// keep everything on the primary channel.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct ChannelSetup {
+  /// Classes with an open negotiated channel on the sender's side.
+  pub classes: Vec<ChannelClass>,
+}
+
 // =============================================================================
 // Unified DataChannel Message Enum
 // =============================================================================
@@ -556,6 +1075,16 @@ pub enum DataChannelMessage {
   ForwardMessage(ForwardMessage),
   /// Message reaction.
   MessageReaction(MessageReaction),
+  /// Message edit.
+  MessageEdit(MessageEdit),
+
+  // Polls
+  /// Poll creation.
+  ChatPoll(ChatPoll),
+  /// Poll vote.
+  PollVote(PollVote),
+  /// Poll close.
+  PollClose(PollClose),
 
   // Encryption
   /// ECDH key exchange.
@@ -567,6 +1096,14 @@ pub enum DataChannelMessage {
   /// Avatar data.
   AvatarData(AvatarData),
 
+  // Group Encryption
+  /// Room sender key.
+  SenderKey(SenderKey),
+
+  // Transport
+  /// Extra channel announcement.
+  ChannelSetup(ChannelSetup),
+
   // Theater
   /// Danmaku message.
   Danmaku(Danmaku),
@@ -576,16 +1113,34 @@ pub enum DataChannelMessage {
   SubtitleData(SubtitleData),
   /// Subtitle clear.
   SubtitleClear(SubtitleClear),
+  /// Subtitle timing offset.
+  SubtitleOffset(SubtitleOffset),
   /// Batched danmaku fan-out frame (owner-relay, Req 12.5 §28).
   DanmakuBatch(DanmakuBatch),
   /// Theater-scoped chat bubble (Req 12.6 §30).
   TheaterChatText(TheaterChatText),
+  /// Theater watch-queue addition.
+  TheaterQueueAdd(TheaterQueueAdd),
+  /// Theater watch-queue removal.
+  TheaterQueueRemove(TheaterQueueRemove),
+  /// Theater watch-queue reorder.
+  TheaterQueueReorder(TheaterQueueReorder),
+  /// Theater now-playing announcement.
+  TheaterNowPlaying(TheaterNowPlaying),
+  /// Theater clock-sync probe.
+  TheaterClockPing(TheaterClockPing),
+  /// Theater clock-sync reply.
+  TheaterClockPong(TheaterClockPong),
 
   // Call Status Broadcasts
   /// Local media state (mic / camera / screen-share) broadcast.
   MediaStateUpdate(MediaStateUpdate),
   /// Peer-side reconnecting status broadcast.
   ReconnectingState(ReconnectingState),
+  /// SFU media frame-encryption key.
+  MediaKey(MediaKey),
+  /// Local call-recording status broadcast.
+  CallRecordingState(CallRecordingState),
 }
 
 impl DataChannelMessage {
@@ -609,21 +1164,39 @@ impl DataChannelMessage {
 
       Self::ForwardMessage(_) => discriminator::FORWARD_MESSAGE,
       Self::MessageReaction(_) => discriminator::MESSAGE_REACTION,
+      Self::MessageEdit(_) => discriminator::MESSAGE_EDIT,
+
+      Self::ChatPoll(_) => discriminator::CHAT_POLL,
+      Self::PollVote(_) => discriminator::POLL_VOTE,
+      Self::PollClose(_) => discriminator::POLL_CLOSE,
 
       Self::EcdhKeyExchange(_) => discriminator::ECDH_KEY_EXCHANGE,
 
       Self::AvatarRequest(_) => discriminator::AVATAR_REQUEST,
       Self::AvatarData(_) => discriminator::AVATAR_DATA,
 
+      Self::SenderKey(_) => discriminator::SENDER_KEY,
+
+      Self::ChannelSetup(_) => discriminator::CHANNEL_SETUP,
+
       Self::Danmaku(_) => discriminator::DANMAKU,
       Self::PlaybackProgress(_) => discriminator::PLAYBACK_PROGRESS,
       Self::SubtitleData(_) => discriminator::SUBTITLE_DATA,
       Self::SubtitleClear(_) => discriminator::SUBTITLE_CLEAR,
+      Self::SubtitleOffset(_) => discriminator::SUBTITLE_OFFSET,
       Self::DanmakuBatch(_) => discriminator::DANMAKU_BATCH,
       Self::TheaterChatText(_) => discriminator::THEATER_CHAT_TEXT,
+      Self::TheaterQueueAdd(_) => discriminator::THEATER_QUEUE_ADD,
+      Self::TheaterQueueRemove(_) => discriminator::THEATER_QUEUE_REMOVE,
+      Self::TheaterQueueReorder(_) => discriminator::THEATER_QUEUE_REORDER,
+      Self::TheaterNowPlaying(_) => discriminator::THEATER_NOW_PLAYING,
+      Self::TheaterClockPing(_) => discriminator::THEATER_CLOCK_PING,
+      Self::TheaterClockPong(_) => discriminator::THEATER_CLOCK_PONG,
 
       Self::MediaStateUpdate(_) => discriminator::MEDIA_STATE_UPDATE,
       Self::ReconnectingState(_) => discriminator::RECONNECTING_STATE,
+      Self::MediaKey(_) => discriminator::MEDIA_KEY,
+      Self::CallRecordingState(_) => discriminator::CALL_RECORDING_STATE,
     }
   }
 
@@ -644,17 +1217,35 @@ impl DataChannelMessage {
         | Self::TypingIndicator(_)
         | Self::MessageRead(_)
         | Self::MessageReaction(_)
+        | Self::MessageEdit(_)
+        | Self::ChatPoll(_)
+        | Self::PollVote(_)
+        | Self::PollClose(_)
         | Self::EcdhKeyExchange(_)
         | Self::AvatarRequest(_)
+        | Self::ChannelSetup(_)
         | Self::Danmaku(_)
         | Self::PlaybackProgress(_)
         | Self::SubtitleClear(_)
+        | Self::SubtitleOffset(_)
         | Self::MediaStateUpdate(_)
         | Self::ReconnectingState(_)
+        | Self::CallRecordingState(_)
         | Self::FileResumeRequest(_)
         | Self::TheaterChatText(_)
+        | Self::TheaterQueueRemove(_)
+        | Self::TheaterQueueReorder(_)
+        | Self::TheaterNowPlaying(_)
+        | Self::TheaterClockPing(_)
+        | Self::TheaterClockPong(_)
     )
   }
+
+  /// Channel class this message is routed to.
+  #[must_use]
+  pub const fn channel_class(&self) -> ChannelClass {
+    ChannelClass::for_discriminator(self.discriminator())
+  }
 }
 
 // =============================================================================
@@ -669,6 +1260,7 @@ impl SubtitleEntry {
       start_ms,
       end_ms,
       text,
+      style: None,
     }
   }
 
@@ -685,6 +1277,87 @@ impl SubtitleEntry {
   }
 }
 
+impl EcdhKeyExchange {
+  /// Domain-separation prefix for the signed payload.
+  pub const SIGNATURE_CONTEXT: &'static [u8] = b"webrtc-e2ee-chat-ecdh-sig-v1";
+
+  /// Bytes covered by [`Self::signature`].
+  ///
+  /// Binds the ephemeral key and timestamp to both endpoints so a
+  /// captured exchange cannot be replayed towards a different peer or
+  /// re-attributed to a different sender.
+  #[must_use]
+  pub fn signing_payload(&self, sender: &UserId, recipient: &UserId) -> Vec<u8> {
+    let mut payload =
+      Vec::with_capacity(Self::SIGNATURE_CONTEXT.len() + 32 + self.public_key.len() + 8);
+    payload.extend_from_slice(Self::SIGNATURE_CONTEXT);
+    payload.extend_from_slice(sender.as_uuid().as_bytes());
+    payload.extend_from_slice(recipient.as_uuid().as_bytes());
+    payload.extend_from_slice(&self.public_key);
+    payload.extend_from_slice(&self.timestamp_nanos.to_be_bytes());
+    payload
+  }
+}
+
+impl ChatPoll {
+  /// Maximum question length in characters.
+  pub const MAX_QUESTION_LENGTH: usize = 300;
+
+  /// Minimum number of options.
+  pub const MIN_OPTIONS: usize = 2;
+
+  /// Maximum number of options.
+  pub const MAX_OPTIONS: usize = 10;
+
+  /// Maximum option length in characters.
+  pub const MAX_OPTION_LENGTH: usize = 100;
+
+  /// Returns true if this poll is well formed: a non-empty question
+  /// and between [`Self::MIN_OPTIONS`] and [`Self::MAX_OPTIONS`]
+  /// non-empty options, all within their length limits.
+  #[must_use]
+  pub fn is_valid(&self) -> bool {
+    let question = self.question.trim();
+    !question.is_empty()
+      && question.chars().count() <= Self::MAX_QUESTION_LENGTH
+      && (Self::MIN_OPTIONS..=Self::MAX_OPTIONS).contains(&self.options.len())
+      && self.options.iter().all(|o| {
+        let o = o.trim();
+        !o.is_empty() && o.chars().count() <= Self::MAX_OPTION_LENGTH
+      })
+  }
+
+  /// Returns true if `choices` is an acceptable vote on this poll:
+  /// every index in range, no duplicates, and at most one choice for
+  /// single-choice polls. An empty set (a retraction) is always
+  /// acceptable.
+  #[must_use]
+  pub fn accepts_choices(&self, choices: &[u32]) -> bool {
+    Self::choices_valid(self.options.len(), self.multiple_choice, choices)
+  }
+
+  /// Shape check behind [`Self::accepts_choices`], for callers that
+  /// keep the poll in their own representation.
+  #[must_use]
+  pub fn choices_valid(option_count: usize, multiple_choice: bool, choices: &[u32]) -> bool {
+    if !multiple_choice && choices.len() > 1 {
+      return false;
+    }
+    let mut seen = Vec::with_capacity(choices.len());
+    choices.iter().all(|&c| {
+      let fresh = !seen.contains(&c);
+      seen.push(c);
+      fresh && (c as usize) < option_count
+    })
+  }
+
+  /// Returns true if the deadline has passed at `now_nanos`.
+  #[must_use]
+  pub fn is_expired_at(&self, now_nanos: u64) -> bool {
+    self.deadline_nanos.is_some_and(|d| now_nanos >= d)
+  }
+}
+
 impl Danmaku {
   /// Maximum allowed content length (100 characters).
   pub const MAX_CONTENT_LENGTH: usize = 100;
@@ -8,6 +8,7 @@ fn test_datachannel_message_discriminator() {
       message_id: MessageId::new(),
       content: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -22,6 +23,7 @@ fn test_datachannel_message_discriminator() {
       pack_id: String::new(),
       sticker_id: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -81,6 +83,7 @@ fn test_discriminator_chat_messages() {
       message_id: MessageId::new(),
       content: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -95,6 +98,7 @@ fn test_discriminator_chat_messages() {
       pack_id: String::new(),
       sticker_id: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -109,6 +113,7 @@ fn test_discriminator_chat_messages() {
       duration_ms: 0,
       waveform: vec![],
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -124,6 +129,7 @@ fn test_discriminator_chat_messages() {
       width: 0,
       height: 0,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -226,6 +232,56 @@ fn test_discriminator_message_enhancement() {
     .discriminator(),
     discriminator::MESSAGE_REACTION
   );
+
+  assert_eq!(
+    DataChannelMessage::MessageEdit(MessageEdit {
+      message_id: MessageId::new(),
+      content: String::new(),
+      revision: 1,
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+    discriminator::MESSAGE_EDIT
+  );
+}
+
+#[test]
+fn test_discriminator_polls() {
+  assert_eq!(
+    DataChannelMessage::ChatPoll(ChatPoll {
+      message_id: MessageId::new(),
+      question: String::new(),
+      options: vec![],
+      multiple_choice: false,
+      anonymous: false,
+      deadline_nanos: None,
+      reply_to: None,
+      thread_root: None,
+      timestamp_nanos: 0,
+      room_id: None,
+    })
+    .discriminator(),
+    discriminator::CHAT_POLL
+  );
+
+  assert_eq!(
+    DataChannelMessage::PollVote(PollVote {
+      poll_id: MessageId::new(),
+      choices: vec![0],
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+    discriminator::POLL_VOTE
+  );
+
+  assert_eq!(
+    DataChannelMessage::PollClose(PollClose {
+      poll_id: MessageId::new(),
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+    discriminator::POLL_CLOSE
+  );
 }
 
 #[test]
@@ -234,6 +290,8 @@ fn test_discriminator_encryption_and_avatar() {
     DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
       public_key: vec![0u8; 65],
       timestamp_nanos: 0,
+      identity_key: vec![0u8; 65],
+      signature: vec![0u8; 64],
     })
     .discriminator(),
     discriminator::ECDH_KEY_EXCHANGE
@@ -314,6 +372,7 @@ fn create_chat_message_discriminators() -> Vec<u8> {
       message_id: MessageId::new(),
       content: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -324,6 +383,7 @@ fn create_chat_message_discriminators() -> Vec<u8> {
       pack_id: String::new(),
       sticker_id: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -334,6 +394,7 @@ fn create_chat_message_discriminators() -> Vec<u8> {
       duration_ms: 0,
       waveform: vec![],
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -345,6 +406,7 @@ fn create_chat_message_discriminators() -> Vec<u8> {
       width: 0,
       height: 0,
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
     })
@@ -416,6 +478,43 @@ fn create_message_status_discriminators() -> Vec<u8> {
       timestamp_nanos: 0,
     })
     .discriminator(),
+    DataChannelMessage::MessageEdit(MessageEdit {
+      message_id: MessageId::new(),
+      content: String::new(),
+      revision: 1,
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+  ]
+}
+
+/// Create discriminators for poll `DataChannelMessage` variants.
+fn create_poll_discriminators() -> Vec<u8> {
+  vec![
+    DataChannelMessage::ChatPoll(ChatPoll {
+      message_id: MessageId::new(),
+      question: String::new(),
+      options: vec![],
+      multiple_choice: false,
+      anonymous: false,
+      deadline_nanos: None,
+      reply_to: None,
+      thread_root: None,
+      timestamp_nanos: 0,
+      room_id: None,
+    })
+    .discriminator(),
+    DataChannelMessage::PollVote(PollVote {
+      poll_id: MessageId::new(),
+      choices: vec![],
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+    DataChannelMessage::PollClose(PollClose {
+      poll_id: MessageId::new(),
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
   ]
 }
 
@@ -425,6 +524,8 @@ fn create_user_avatar_discriminators() -> Vec<u8> {
     DataChannelMessage::EcdhKeyExchange(EcdhKeyExchange {
       public_key: vec![0u8; 65],
       timestamp_nanos: 0,
+      identity_key: vec![0u8; 65],
+      signature: vec![0u8; 64],
     })
     .discriminator(),
     DataChannelMessage::AvatarRequest(AvatarRequest {
@@ -483,6 +584,53 @@ fn create_theater_discriminators() -> Vec<u8> {
       timestamp_nanos: 0,
     })
     .discriminator(),
+    DataChannelMessage::TheaterQueueAdd(TheaterQueueAdd {
+      room_id: RoomId::new(),
+      item: TheaterQueueItem {
+        item_id: MessageId::new(),
+        source: TheaterQueueSource::Url(String::new()),
+        title: String::new(),
+        proposed_by: UserId::default(),
+        subtitle: None,
+      },
+    })
+    .discriminator(),
+    DataChannelMessage::TheaterQueueRemove(TheaterQueueRemove {
+      room_id: RoomId::new(),
+      item_id: MessageId::new(),
+      requested_by: UserId::default(),
+    })
+    .discriminator(),
+    DataChannelMessage::TheaterQueueReorder(TheaterQueueReorder {
+      room_id: RoomId::new(),
+      item_ids: vec![],
+    })
+    .discriminator(),
+    DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+      room_id: RoomId::new(),
+      item_id: None,
+      timestamp_nanos: 0,
+    })
+    .discriminator(),
+    DataChannelMessage::TheaterClockPing(TheaterClockPing {
+      room_id: RoomId::new(),
+      seq: 0,
+      client_send_nanos: 0,
+    })
+    .discriminator(),
+    DataChannelMessage::TheaterClockPong(TheaterClockPong {
+      room_id: RoomId::new(),
+      seq: 0,
+      client_send_nanos: 0,
+      owner_recv_nanos: 0,
+      owner_send_nanos: 0,
+    })
+    .discriminator(),
+    DataChannelMessage::SubtitleOffset(SubtitleOffset {
+      room_id: RoomId::new(),
+      offset_ms: 0,
+    })
+    .discriminator(),
   ]
 }
 
@@ -492,6 +640,7 @@ fn test_discriminator_all_values_unique() {
   discriminators.extend(create_chat_message_discriminators());
   discriminators.extend(create_file_transfer_discriminators());
   discriminators.extend(create_message_status_discriminators());
+  discriminators.extend(create_poll_discriminators());
   discriminators.extend(create_user_avatar_discriminators());
   discriminators.extend(create_theater_discriminators());
 
@@ -501,8 +650,8 @@ fn test_discriminator_all_values_unique() {
   }
   assert_eq!(
     discriminators.len(),
-    21,
-    "Should have 21 DataChannel variants"
+    32,
+    "Should have 32 DataChannel variants"
   );
 }
 
@@ -524,9 +673,16 @@ fn test_reconnecting_state_discriminator() {
   assert_eq!(msg.discriminator(), 0xC1);
 }
 
+#[test]
+fn test_call_recording_state_discriminator() {
+  let msg = DataChannelMessage::CallRecordingState(CallRecordingState { recording: true });
+  assert_eq!(msg.discriminator(), discriminator::CALL_RECORDING_STATE);
+  assert_eq!(msg.discriminator(), 0xC3);
+}
+
 #[test]
 fn test_call_status_messages_are_lightweight() {
-  // Both call-status broadcasts should be lightweight so they fit
+  // Every call-status broadcast should be lightweight so they fit
   // through the ACK queue's JSON-persistence fast path (Req 11.3).
   let media = DataChannelMessage::MediaStateUpdate(MediaStateUpdate {
     mic_enabled: true,
@@ -536,6 +692,103 @@ fn test_call_status_messages_are_lightweight() {
   let recon = DataChannelMessage::ReconnectingState(ReconnectingState {
     reconnecting: false,
   });
+  let recording = DataChannelMessage::CallRecordingState(CallRecordingState { recording: true });
   assert!(media.is_lightweight());
   assert!(recon.is_lightweight());
+  assert!(recording.is_lightweight());
+}
+
+#[test]
+fn test_media_key_discriminator_and_not_persisted() {
+  let msg = DataChannelMessage::MediaKey(MediaKey {
+    key_id: 1,
+    key: vec![0u8; 32],
+  });
+  assert_eq!(msg.discriminator(), discriminator::MEDIA_KEY);
+  assert_eq!(msg.discriminator(), 0xC2);
+  // Key material must never reach the JSON-persisted ACK queue.
+  assert!(!msg.is_lightweight());
+}
+
+#[test]
+fn test_sender_key_discriminator_and_not_persisted() {
+  let msg = DataChannelMessage::SenderKey(SenderKey {
+    room_id: RoomId::new(),
+    device_id: DeviceId::new(),
+    epoch: 3,
+    key: vec![0u8; 32],
+  });
+  assert_eq!(msg.discriminator(), discriminator::SENDER_KEY);
+  assert_eq!(msg.discriminator(), 0xA3);
+  assert!(!msg.is_lightweight());
+}
+
+#[test]
+fn test_channel_setup_discriminator() {
+  let msg = DataChannelMessage::ChannelSetup(ChannelSetup {
+    classes: vec![ChannelClass::Bulk, ChannelClass::Ephemeral],
+  });
+  assert_eq!(msg.discriminator(), discriminator::CHANNEL_SETUP);
+  assert_eq!(msg.discriminator(), 0xA4);
+  assert!(msg.is_lightweight());
+}
+
+#[test]
+fn test_theater_queue_discriminators() {
+  let add = DataChannelMessage::TheaterQueueAdd(TheaterQueueAdd {
+    room_id: RoomId::new(),
+    item: TheaterQueueItem {
+      item_id: MessageId::new(),
+      source: TheaterQueueSource::LocalFile("movie.mp4".to_string()),
+      title: "movie.mp4".to_string(),
+      proposed_by: UserId::default(),
+      subtitle: None,
+    },
+  });
+  assert_eq!(add.discriminator(), discriminator::THEATER_QUEUE_ADD);
+  assert_eq!(add.discriminator(), 0xB6);
+  // Additions may carry a whole subtitle track, like `SubtitleData`.
+  assert!(!add.is_lightweight());
+
+  let now_playing = DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+    room_id: RoomId::new(),
+    item_id: Some(MessageId::new()),
+    timestamp_nanos: 0,
+  });
+  assert_eq!(now_playing.discriminator(), 0xB9);
+  assert!(now_playing.is_lightweight());
+}
+
+#[test]
+fn test_theater_clock_sync_discriminators() {
+  let probe = DataChannelMessage::TheaterClockPing(TheaterClockPing {
+    room_id: RoomId::new(),
+    seq: 1,
+    client_send_nanos: 10,
+  });
+  assert_eq!(probe.discriminator(), discriminator::THEATER_CLOCK_PING);
+  assert_eq!(probe.discriminator(), 0xBA);
+  assert!(probe.is_lightweight());
+
+  let reply = DataChannelMessage::TheaterClockPong(TheaterClockPong {
+    room_id: RoomId::new(),
+    seq: 1,
+    client_send_nanos: 10,
+    owner_recv_nanos: 20,
+    owner_send_nanos: 30,
+  });
+  assert_eq!(reply.discriminator(), discriminator::THEATER_CLOCK_PONG);
+  assert_eq!(reply.discriminator(), 0xBB);
+  assert!(reply.is_lightweight());
+}
+
+#[test]
+fn test_subtitle_offset_discriminator() {
+  let msg = DataChannelMessage::SubtitleOffset(SubtitleOffset {
+    room_id: RoomId::new(),
+    offset_ms: -1_500,
+  });
+  assert_eq!(msg.discriminator(), discriminator::SUBTITLE_OFFSET);
+  assert_eq!(msg.discriminator(), 0xBC);
+  assert!(msg.is_lightweight());
 }
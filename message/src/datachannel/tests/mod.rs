@@ -3,24 +3,30 @@
 //! Tests are organized by functionality:
 //! - `roundtrip`: Bitcode roundtrip tests for each message type
 //! - `discriminator`: Discriminator value and uniqueness tests
-//! - `validation`: Danmaku, `SubtitleEntry`, and boundary validation tests
+//! - `validation`: Danmaku, `SubtitleEntry`, `EcdhKeyExchange` signing payload, and boundary validation tests
 //! - `serialization`: JSON serialization roundtrip tests
+//! - `routing`: `ChannelClass` routing table and channel parameters
 
 mod discriminator_test;
 mod roundtrip;
+mod routing;
 mod serialization;
 mod validation;
 
 // Re-export all necessary types for test submodules
 pub(super) use super::{
-  AckStatus, AvatarData, AvatarRequest, ChatImage, ChatSticker, ChatText, ChatVoice, Danmaku,
-  DanmakuBatch, DanmakuPosition, DataChannelMessage, EcdhKeyExchange, FileChunk, FileMetadata,
-  FileResumeRequest, ForwardMessage, MediaStateUpdate, MessageAck, MessageReaction, MessageRead,
-  MessageRevoke, PlaybackProgress, ReactionAction, ReconnectingState, SubtitleClear, SubtitleData,
-  SubtitleEntry, TheaterChatText, TypingIndicator, discriminator,
+  AckStatus, AvatarData, AvatarRequest, CallRecordingState, ChannelClass, ChannelSetup, ChatImage,
+  ChatPoll, ChatSticker, ChatText, ChatVoice, Danmaku, DanmakuBatch, DanmakuPosition,
+  DataChannelMessage, EcdhKeyExchange, FileChunk, FileMetadata, FileResumeRequest, ForwardMessage,
+  MediaKey, MediaStateUpdate, MessageAck, MessageEdit, MessageReaction, MessageRead, MessageRevoke,
+  PlaybackProgress, PollClose, PollVote, ReactionAction, ReconnectingState, SenderKey,
+  SubtitleAnchor, SubtitleClear, SubtitleCueStyle, SubtitleData, SubtitleEntry, SubtitleKaraoke,
+  SubtitleOffset, SubtitleSpan, TheaterChatText, TheaterClockPing, TheaterClockPong,
+  TheaterNowPlaying, TheaterQueueAdd, TheaterQueueItem, TheaterQueueRemove, TheaterQueueReorder,
+  TheaterQueueSource, TheaterQueueSubtitle, TypingIndicator, discriminator,
 };
 
-pub(super) use crate::types::{MessageId, RoomId, TransferId, UserId};
+pub(super) use crate::types::{DeviceId, MessageId, RoomId, TransferId, UserId};
 
 /// Helper: test bitcode encode→decode roundtrip for a value.
 pub(super) fn test_bitcode_roundtrip<
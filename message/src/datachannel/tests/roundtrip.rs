@@ -6,6 +6,7 @@ fn test_chat_text_roundtrip() {
     message_id: MessageId::new(),
     content: "Hello, **world**!".to_string(),
     reply_to: Some(MessageId::new()),
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
     mentions: vec![],
@@ -17,11 +18,24 @@ fn test_chat_text_roundtrip() {
     message_id: MessageId::new(),
     content: "@alice check this".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 2_000_000_000,
     room_id: Some(RoomId::new()),
     mentions: vec![UserId::new(), UserId::new()],
   };
   test_bitcode_roundtrip(&msg_room);
+
+  // Thread reply.
+  let msg_thread = ChatText {
+    message_id: MessageId::new(),
+    content: "in thread".to_string(),
+    reply_to: None,
+    thread_root: Some(MessageId::new()),
+    timestamp_nanos: 3_000_000_000,
+    room_id: Some(RoomId::new()),
+    mentions: vec![],
+  };
+  test_bitcode_roundtrip(&msg_thread);
 }
 
 #[test]
@@ -31,6 +45,7 @@ fn test_chat_sticker_roundtrip() {
     pack_id: "pack_001".to_string(),
     sticker_id: "sticker_123".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -41,6 +56,7 @@ fn test_chat_sticker_roundtrip() {
     pack_id: "pack_002".to_string(),
     sticker_id: "sticker_456".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 2_000_000_000,
     room_id: Some(RoomId::new()),
   };
@@ -55,6 +71,7 @@ fn test_chat_voice_roundtrip() {
     duration_ms: 5000,
     waveform: vec![10, 20, 30, 40, 50],
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -70,6 +87,7 @@ fn test_chat_image_roundtrip() {
     width: 1920,
     height: 1080,
     reply_to: Some(MessageId::new()),
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -190,11 +208,64 @@ fn test_message_reaction_roundtrip() {
   test_bitcode_roundtrip(&msg);
 }
 
+#[test]
+fn test_message_edit_roundtrip() {
+  let msg = MessageEdit {
+    message_id: MessageId::new(),
+    content: "fixed typo".to_string(),
+    revision: 2,
+    timestamp_nanos: 1_000_000_000,
+  };
+  test_bitcode_roundtrip(&msg);
+}
+
+#[test]
+fn test_chat_poll_roundtrip() {
+  let msg = ChatPoll {
+    message_id: MessageId::new(),
+    question: "Lunch?".to_string(),
+    options: vec!["Pizza".to_string(), "Sushi".to_string()],
+    multiple_choice: true,
+    anonymous: true,
+    deadline_nanos: Some(5_000_000_000),
+    reply_to: None,
+    thread_root: Some(MessageId::new()),
+    timestamp_nanos: 1_000_000_000,
+    room_id: Some(RoomId::new()),
+  };
+  test_bitcode_roundtrip(&msg);
+}
+
+#[test]
+fn test_poll_vote_and_close_roundtrip() {
+  let vote = PollVote {
+    poll_id: MessageId::new(),
+    choices: vec![0, 2],
+    timestamp_nanos: 1_000_000_000,
+  };
+  test_bitcode_roundtrip(&vote);
+
+  let retract = PollVote {
+    poll_id: MessageId::new(),
+    choices: vec![],
+    timestamp_nanos: 2_000_000_000,
+  };
+  test_bitcode_roundtrip(&retract);
+
+  let close = PollClose {
+    poll_id: MessageId::new(),
+    timestamp_nanos: 3_000_000_000,
+  };
+  test_bitcode_roundtrip(&close);
+}
+
 #[test]
 fn test_ecdh_key_exchange_roundtrip() {
   let msg = EcdhKeyExchange {
     public_key: vec![0xAB; 65], // P-256 raw format: 65 bytes
     timestamp_nanos: 1_000_000_000,
+    identity_key: vec![0xCD; 65],
+    signature: vec![0xEF; 64],
   };
   test_bitcode_roundtrip(&msg);
 }
@@ -253,17 +324,74 @@ fn test_subtitle_data_roundtrip() {
         start_ms: 0,
         end_ms: 3000,
         text: "Hello, world!".to_string(),
+        style: None,
       },
       SubtitleEntry {
         start_ms: 3000,
         end_ms: 6000,
         text: "Goodbye!".to_string(),
+        style: None,
       },
     ],
   };
   test_bitcode_roundtrip(&msg);
 }
 
+#[test]
+fn test_styled_subtitle_entry_roundtrip() {
+  let entry = SubtitleEntry {
+    start_ms: 1_000,
+    end_ms: 4_000,
+    text: "ka-ra".to_string(),
+    style: Some(SubtitleCueStyle {
+      font_name: Some("Arial".to_string()),
+      font_size_permille: Some(70),
+      alignment: 8,
+      margin_v_permille: 35,
+      position: Some(SubtitleAnchor {
+        x_permille: 500,
+        y_permille: 100,
+      }),
+      spans: vec![
+        SubtitleSpan {
+          text: "ka".to_string(),
+          bold: true,
+          italic: false,
+          underline: false,
+          strike_out: false,
+          color: Some(0x00FF_0000),
+          outline_color: None,
+          karaoke: Some(SubtitleKaraoke {
+            offset_ms: 0,
+            duration_ms: 500,
+          }),
+        },
+        SubtitleSpan {
+          text: "-ra".to_string(),
+          bold: false,
+          italic: true,
+          underline: false,
+          strike_out: false,
+          color: None,
+          outline_color: Some(0),
+          karaoke: Some(SubtitleKaraoke {
+            offset_ms: 500,
+            duration_ms: 250,
+          }),
+        },
+      ],
+    }),
+  };
+  test_bitcode_roundtrip(&entry);
+
+  // Plain cues keep their pre-styling JSON shape.
+  let plain = SubtitleEntry::new(0, 1_000, "plain".to_string());
+  let json = serde_json::to_string(&plain).expect("serialize");
+  assert!(!json.contains("style"));
+  let decoded: SubtitleEntry = serde_json::from_str(&json).expect("deserialize");
+  assert_eq!(decoded, plain);
+}
+
 #[test]
 fn test_subtitle_clear_roundtrip() {
   let msg = SubtitleClear {
@@ -272,12 +400,23 @@ fn test_subtitle_clear_roundtrip() {
   test_bitcode_roundtrip(&msg);
 }
 
+#[test]
+fn test_subtitle_offset_roundtrip() {
+  for offset_ms in [0, 1_250, -3_000, i32::MIN, i32::MAX] {
+    test_bitcode_roundtrip(&SubtitleOffset {
+      room_id: RoomId::new(),
+      offset_ms,
+    });
+  }
+}
+
 #[test]
 fn test_datachannel_message_roundtrip() {
   let msg = DataChannelMessage::ChatText(ChatText {
     message_id: MessageId::new(),
     content: "Test message".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
     mentions: vec![],
@@ -306,3 +445,98 @@ fn test_reconnecting_state_roundtrip() {
   test_bitcode_roundtrip(&on);
   test_bitcode_roundtrip(&off);
 }
+
+#[test]
+fn test_call_recording_state_roundtrip() {
+  test_bitcode_roundtrip(&CallRecordingState { recording: true });
+  test_bitcode_roundtrip(&CallRecordingState { recording: false });
+}
+
+#[test]
+fn test_media_key_roundtrip() {
+  let msg = MediaKey {
+    key_id: 7,
+    key: (0u8..32).collect(),
+  };
+  test_bitcode_roundtrip(&msg);
+}
+
+#[test]
+fn test_sender_key_roundtrip() {
+  let msg = SenderKey {
+    room_id: RoomId::new(),
+    device_id: DeviceId::new(),
+    epoch: u32::MAX,
+    key: (0u8..32).collect(),
+  };
+  test_bitcode_roundtrip(&msg);
+  test_bitcode_roundtrip(&DataChannelMessage::SenderKey(msg));
+}
+
+#[test]
+fn test_channel_setup_roundtrip() {
+  let msg = ChannelSetup {
+    classes: vec![ChannelClass::Bulk, ChannelClass::Ephemeral],
+  };
+  test_bitcode_roundtrip(&msg);
+  test_bitcode_roundtrip(&DataChannelMessage::ChannelSetup(msg));
+  test_bitcode_roundtrip(&ChannelSetup { classes: vec![] });
+}
+
+#[test]
+fn test_theater_queue_roundtrip() {
+  let add = TheaterQueueAdd {
+    room_id: RoomId::new(),
+    item: TheaterQueueItem {
+      item_id: MessageId::new(),
+      source: TheaterQueueSource::Url("https://example.com/movie.mp4".to_string()),
+      title: "example.com".to_string(),
+      proposed_by: UserId::new(),
+      subtitle: Some(TheaterQueueSubtitle {
+        filename: "movie.srt".to_string(),
+        entries: vec![SubtitleEntry::new(0, 1_000, "Hello".to_string())],
+      }),
+    },
+  };
+  test_bitcode_roundtrip(&add);
+  test_bitcode_roundtrip(&DataChannelMessage::TheaterQueueAdd(add));
+
+  let reorder = TheaterQueueReorder {
+    room_id: RoomId::new(),
+    item_ids: vec![MessageId::new(), MessageId::new()],
+  };
+  test_bitcode_roundtrip(&reorder);
+
+  let remove = TheaterQueueRemove {
+    room_id: RoomId::new(),
+    item_id: MessageId::new(),
+    requested_by: UserId::new(),
+  };
+  test_bitcode_roundtrip(&remove);
+
+  let now_playing = TheaterNowPlaying {
+    room_id: RoomId::new(),
+    item_id: None,
+    timestamp_nanos: 1_000_000_000,
+  };
+  test_bitcode_roundtrip(&now_playing);
+}
+
+#[test]
+fn test_theater_clock_sync_roundtrip() {
+  let probe = TheaterClockPing {
+    room_id: RoomId::new(),
+    seq: 7,
+    client_send_nanos: 1_700_000_000_000_000_000,
+  };
+  test_bitcode_roundtrip(&probe);
+
+  let reply = TheaterClockPong {
+    room_id: RoomId::new(),
+    seq: 7,
+    client_send_nanos: 1_700_000_000_000_000_000,
+    owner_recv_nanos: 1_700_000_000_012_000_000,
+    owner_send_nanos: 1_700_000_000_012_500_000,
+  };
+  test_bitcode_roundtrip(&reply);
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+fn chunk() -> DataChannelMessage {
+  DataChannelMessage::FileChunk(FileChunk {
+    transfer_id: TransferId::new(),
+    chunk_index: 0,
+    total_chunks: 1,
+    data: vec![0; 16],
+    chunk_hash: [0; 32],
+  })
+}
+
+#[test]
+fn test_file_data_routes_to_bulk() {
+  assert_eq!(chunk().channel_class(), ChannelClass::Bulk);
+  assert_eq!(
+    ChannelClass::for_discriminator(discriminator::AVATAR_DATA),
+    ChannelClass::Bulk
+  );
+}
+
+#[test]
+fn test_ephemeral_state_routes_to_unreliable() {
+  for d in [
+    discriminator::TYPING_INDICATOR,
+    discriminator::PLAYBACK_PROGRESS,
+    discriminator::DANMAKU,
+    discriminator::DANMAKU_BATCH,
+    discriminator::THEATER_CLOCK_PING,
+    discriminator::THEATER_CLOCK_PONG,
+  ] {
+    assert_eq!(
+      ChannelClass::for_discriminator(d),
+      ChannelClass::Ephemeral,
+      "{d:#04x}"
+    );
+  }
+}
+
+#[test]
+fn test_chat_and_control_stay_on_control() {
+  for d in [
+    discriminator::CHAT_TEXT,
+    discriminator::CHAT_IMAGE,
+    discriminator::FILE_METADATA,
+    discriminator::FILE_RESUME_REQUEST,
+    discriminator::MESSAGE_ACK,
+    discriminator::ECDH_KEY_EXCHANGE,
+    discriminator::SENDER_KEY,
+    discriminator::CHANNEL_SETUP,
+    discriminator::SUBTITLE_DATA,
+    discriminator::THEATER_NOW_PLAYING,
+    discriminator::MEDIA_STATE_UPDATE,
+    discriminator::CALL_RECORDING_STATE,
+  ] {
+    assert_eq!(
+      ChannelClass::for_discriminator(d),
+      ChannelClass::Control,
+      "{d:#04x}"
+    );
+  }
+  // Unknown discriminators fall back to the reliable, ordered channel.
+  assert_eq!(ChannelClass::for_discriminator(0x00), ChannelClass::Control);
+}
+
+#[test]
+fn test_channel_parameters() {
+  assert_eq!(ChannelClass::Control.label(), "chat");
+  assert!(ChannelClass::Control.ordered());
+  assert_eq!(ChannelClass::Control.max_retransmits(), None);
+  assert_eq!(ChannelClass::Control.stream_id(), None);
+
+  assert!(!ChannelClass::Bulk.ordered());
+  assert_eq!(ChannelClass::Bulk.max_retransmits(), None);
+
+  assert!(!ChannelClass::Ephemeral.ordered());
+  assert_eq!(ChannelClass::Ephemeral.max_retransmits(), Some(0));
+}
+
+#[test]
+fn test_extra_channels_have_distinct_stream_ids_and_labels() {
+  let ids: Vec<_> = ChannelClass::EXTRA
+    .iter()
+    .map(|c| c.stream_id().expect("negotiated"))
+    .collect();
+  assert_eq!(ids.len(), 2);
+  assert_ne!(ids[0], ids[1]);
+  assert!(!ChannelClass::EXTRA.contains(&ChannelClass::Control));
+  assert_ne!(ChannelClass::Bulk.label(), ChannelClass::Ephemeral.label());
+}
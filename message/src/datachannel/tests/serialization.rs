@@ -10,6 +10,7 @@ fn test_datachannel_message_json_roundtrip() {
     message_id: MessageId::new(),
     content: "Hello, **world**!".to_string(),
     reply_to: Some(MessageId::new()),
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
     mentions: vec![],
@@ -28,6 +29,7 @@ fn test_datachannel_message_json_roundtrip_all_variants() {
       pack_id: "pack_001".to_string(),
       sticker_id: "sticker_123".to_string(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 1_000_000_000,
       room_id: None,
     }),
@@ -72,6 +74,36 @@ fn test_datachannel_message_json_roundtrip_all_variants() {
       content: "Hello theater".to_string(),
       timestamp_nanos: 1_000_000_000,
     }),
+    DataChannelMessage::TheaterQueueAdd(TheaterQueueAdd {
+      room_id: RoomId::new(),
+      item: TheaterQueueItem {
+        item_id: MessageId::new(),
+        source: TheaterQueueSource::LocalFile("movie.mp4".to_string()),
+        title: "movie.mp4".to_string(),
+        proposed_by: UserId::default(),
+        subtitle: None,
+      },
+    }),
+    DataChannelMessage::TheaterNowPlaying(TheaterNowPlaying {
+      room_id: RoomId::new(),
+      item_id: Some(MessageId::new()),
+      timestamp_nanos: 1_000_000_000,
+    }),
+    DataChannelMessage::TheaterClockPong(TheaterClockPong {
+      room_id: RoomId::new(),
+      seq: 3,
+      client_send_nanos: 1_000_000_000,
+      owner_recv_nanos: 1_040_000_000,
+      owner_send_nanos: 1_041_000_000,
+    }),
+    DataChannelMessage::SubtitleOffset(SubtitleOffset {
+      room_id: RoomId::new(),
+      offset_ms: -2_250,
+    }),
+    DataChannelMessage::CallRecordingState(CallRecordingState { recording: true }),
+    DataChannelMessage::ChannelSetup(ChannelSetup {
+      classes: vec![ChannelClass::Bulk],
+    }),
   ];
 
   for msg in messages {
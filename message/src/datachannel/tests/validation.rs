@@ -157,6 +157,79 @@ fn test_danmaku_is_valid_all_positions() {
   }
 }
 
+// =============================================================================
+// ChatPoll Validation Tests
+// =============================================================================
+
+fn make_poll(options: &[&str], multiple_choice: bool) -> ChatPoll {
+  ChatPoll {
+    message_id: MessageId::new(),
+    question: "Where to?".to_string(),
+    options: options.iter().map(|o| (*o).to_string()).collect(),
+    multiple_choice,
+    anonymous: false,
+    deadline_nanos: None,
+    reply_to: None,
+    thread_root: None,
+    timestamp_nanos: 0,
+    room_id: None,
+  }
+}
+
+#[test]
+fn test_chat_poll_is_valid() {
+  assert!(make_poll(&["a", "b"], false).is_valid());
+
+  let mut blank_question = make_poll(&["a", "b"], false);
+  blank_question.question = "   ".to_string();
+  assert!(
+    !blank_question.is_valid(),
+    "Blank question should be invalid"
+  );
+
+  assert!(
+    !make_poll(&["only"], false).is_valid(),
+    "A single option should be invalid"
+  );
+  assert!(
+    !make_poll(&["a", " "], false).is_valid(),
+    "Blank option should be invalid"
+  );
+
+  let many: Vec<String> = (0..=ChatPoll::MAX_OPTIONS).map(|i| i.to_string()).collect();
+  let many: Vec<&str> = many.iter().map(String::as_str).collect();
+  assert!(
+    !make_poll(&many, false).is_valid(),
+    "Too many options should be invalid"
+  );
+  assert!(make_poll(&many[..ChatPoll::MAX_OPTIONS], false).is_valid());
+}
+
+#[test]
+fn test_chat_poll_accepts_choices() {
+  let single = make_poll(&["a", "b", "c"], false);
+  assert!(single.accepts_choices(&[]));
+  assert!(single.accepts_choices(&[2]));
+  assert!(
+    !single.accepts_choices(&[0, 1]),
+    "Single choice allows one pick"
+  );
+  assert!(!single.accepts_choices(&[3]), "Index out of range");
+
+  let multi = make_poll(&["a", "b", "c"], true);
+  assert!(multi.accepts_choices(&[0, 2]));
+  assert!(!multi.accepts_choices(&[1, 1]), "Duplicates are rejected");
+}
+
+#[test]
+fn test_chat_poll_deadline() {
+  let mut poll = make_poll(&["a", "b"], false);
+  assert!(!poll.is_expired_at(u64::MAX));
+  poll.deadline_nanos = Some(1_000);
+  assert!(!poll.is_expired_at(999));
+  assert!(poll.is_expired_at(1_000));
+}
+
 // =============================================================================
 // SubtitleEntry Boundary Tests (P2-2)
 // =============================================================================
@@ -356,3 +429,60 @@ fn test_reaction_action_both_variants_roundtrip() {
     test_bitcode_roundtrip(&msg);
   }
 }
+
+// =============================================================================
+// EcdhKeyExchange Signing Payload
+// =============================================================================
+
+fn sample_ecdh_exchange() -> EcdhKeyExchange {
+  EcdhKeyExchange {
+    public_key: vec![0x04; 65],
+    timestamp_nanos: 42,
+    identity_key: vec![0x04; 65],
+    signature: vec![0xAA; 64],
+  }
+}
+
+#[test]
+fn test_ecdh_signing_payload_layout() {
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let payload = sample_ecdh_exchange().signing_payload(&sender, &recipient);
+
+  let ctx = EcdhKeyExchange::SIGNATURE_CONTEXT;
+  assert!(payload.starts_with(ctx));
+  assert_eq!(
+    &payload[ctx.len()..ctx.len() + 16],
+    sender.as_uuid().as_bytes()
+  );
+  assert_eq!(
+    &payload[ctx.len() + 16..ctx.len() + 32],
+    recipient.as_uuid().as_bytes()
+  );
+  assert!(payload.ends_with(&42u64.to_be_bytes()));
+  assert_eq!(payload.len(), ctx.len() + 32 + 65 + 8);
+}
+
+#[test]
+fn test_ecdh_signing_payload_is_direction_bound() {
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let exchange = sample_ecdh_exchange();
+  assert_ne!(
+    exchange.signing_payload(&alice, &bob),
+    exchange.signing_payload(&bob, &alice)
+  );
+}
+
+#[test]
+fn test_ecdh_signing_payload_excludes_signature() {
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let mut exchange = sample_ecdh_exchange();
+  let before = exchange.signing_payload(&alice, &bob);
+  exchange.signature = vec![0xBB; 64];
+  assert_eq!(before, exchange.signing_payload(&alice, &bob));
+
+  exchange.timestamp_nanos += 1;
+  assert_ne!(before, exchange.signing_payload(&alice, &bob));
+}
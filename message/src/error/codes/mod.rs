@@ -192,7 +192,7 @@ pub const AUTH001: ErrorCode = ErrorCode::new(ErrorModule::Auth, ErrorCategory::
 pub const AUTH501: ErrorCode = ErrorCode::new(ErrorModule::Auth, ErrorCategory::Security, 1);
 /// JWT token invalid
 pub const AUTH502: ErrorCode = ErrorCode::new(ErrorModule::Auth, ErrorCategory::Security, 2);
-/// Session invalidated (another device logged in)
+/// Session invalidated (revoked, or replaced by the same device)
 pub const AUTH503: ErrorCode = ErrorCode::new(ErrorModule::Auth, ErrorCategory::Security, 3);
 /// Invalid credentials
 pub const AUTH101: ErrorCode = ErrorCode::new(ErrorModule::Auth, ErrorCategory::Client, 1);
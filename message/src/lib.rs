@@ -35,10 +35,10 @@ pub mod wasm;
 
 // Re-export commonly used types at crate root
 pub use datachannel::{
-  AckStatus, AvatarData, AvatarRequest, ChatImage, ChatSticker, ChatText, ChatVoice, Danmaku,
-  DataChannelMessage, EcdhKeyExchange, FileChunk, FileMetadata, ForwardMessage, MessageAck,
-  MessageReaction, MessageRead, MessageRevoke, PlaybackProgress, ReactionAction, SubtitleClear,
-  SubtitleData, SubtitleEntry, TypingIndicator,
+  AckStatus, AvatarData, AvatarRequest, ChatImage, ChatPoll, ChatSticker, ChatText, ChatVoice,
+  Danmaku, DataChannelMessage, EcdhKeyExchange, FileChunk, FileMetadata, ForwardMessage,
+  MessageAck, MessageEdit, MessageReaction, MessageRead, MessageRevoke, PlaybackProgress,
+  PollClose, PollVote, ReactionAction, SubtitleClear, SubtitleData, SubtitleEntry, TypingIndicator,
 };
 pub use error::{ErrorCode, ErrorResponse};
 pub use frame::{
@@ -54,4 +54,4 @@ pub use signaling::{
   TheaterMuteAll, TheaterTransferOwner, TokenAuth, TransferOwnership, UnbanMember, UnmuteMember,
   UserListUpdate, UserLogout, UserStatusChange,
 };
-pub use types::{MessageId, RoomId, TransferId, UserId};
+pub use types::{DeviceId, MessageId, RoomId, TransferId, UserId};
@@ -4,7 +4,9 @@ use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
 // Re-export UserId from types
-use crate::types::UserId;
+use crate::types::{DeviceId, UserId};
+
+use super::device::DeviceInfo;
 
 // ---------------------------------------------------------
 // Token authentication message types for signaling protocol
@@ -18,6 +20,10 @@ use crate::types::UserId;
 pub struct TokenAuth {
   /// JWT token string.
   pub token: String,
+  /// The connecting device. Clients that omit it are given a fresh
+  /// device id for the lifetime of the connection.
+  #[serde(default)]
+  pub device: Option<DeviceInfo>,
 }
 
 /// Authentication success response.
@@ -33,6 +39,13 @@ pub struct AuthSuccess {
   pub ice_servers: Vec<IceServerSpec>,
   /// Avatar URL (data URL or CDN URL).
   pub avatar_url: Option<String>,
+  /// Whether the server stores sealed messages for offline users
+  /// (see [`super::mailbox`]).
+  #[serde(default)]
+  pub mailbox_enabled: bool,
+  /// Device this connection was registered as (the one sent in
+  /// [`TokenAuth::device`], or one assigned by the server).
+  pub device_id: DeviceId,
 }
 
 /// Authentication failure response.
@@ -46,7 +59,8 @@ pub struct AuthFailure {
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, Default)]
 pub struct UserLogout;
 
-/// Session invalidated by another device login.
+/// Session ended by the server: revoked (logout elsewhere, password
+/// change) or replaced by a newer connection of the same device.
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, Default)]
 pub struct SessionInvalidated;
 
@@ -0,0 +1,106 @@
+//! Multi-device signaling messages.
+//!
+//! A user may be signed in on several devices at once. Each device
+//! identifies itself in [`TokenAuth::device`](super::TokenAuth) and is
+//! a separate signaling endpoint on the server:
+//!
+//! * Frames addressed to a user fan out to every connected device.
+//! * Frames that carry a target device (SDP / ICE with `to_device`,
+//!   [`DeviceLinkSignal`]) reach that device only.
+//! * The user's own devices learn about each other through
+//!   [`DeviceListUpdate`], sent whenever one of them connects or
+//!   disconnects while another one is online.
+//!
+//! Linking a new device runs over [`DeviceLinkSignal`]: the two devices
+//! exchange ephemeral ECDH keys and negotiate a dedicated WebRTC
+//! `DataChannel`, over which the existing device streams its history.
+//! The server only relays these frames between devices of the same
+//! account; both devices show a short code derived from the two keys
+//! and the user confirms it on the existing device, so a relaying
+//! server cannot read the history.
+
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::types::DeviceId;
+
+/// One of a user's devices.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct DeviceInfo {
+  /// Stable device identifier.
+  pub device_id: DeviceId,
+  /// Human-readable label, e.g. "Firefox on Linux".
+  pub name: String,
+}
+
+impl DeviceInfo {
+  /// Maximum length of [`Self::name`] in characters. Longer names are
+  /// truncated by the server.
+  pub const MAX_NAME_LENGTH: usize = 64;
+}
+
+/// The user's currently connected devices (server → every device of
+/// the user).
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct DeviceListUpdate {
+  /// Connected devices, in connection order. Includes the receiving
+  /// device itself.
+  pub devices: Vec<DeviceInfo>,
+}
+
+/// Device-linking signal relayed between two devices of one user.
+///
+/// The server overwrites [`Self::from_device`] with the sending
+/// connection's device, and drops signals whose target is not one of
+/// the sender's own devices.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct DeviceLinkSignal {
+  /// Sending device.
+  pub from_device: DeviceId,
+  /// Receiving device.
+  pub to_device: DeviceId,
+  /// Linking step.
+  pub payload: DeviceLinkPayload,
+}
+
+/// Steps of the device-linking handshake.
+///
+/// The new device asks for the history with [`Self::Hello`]; the
+/// existing device (the one holding the history) answers with
+/// [`Self::Accept`]. Once the short code is confirmed, the existing
+/// device offers the `DataChannel` and both sides trickle ICE
+/// candidates until it opens.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub enum DeviceLinkPayload {
+  /// New device's ephemeral ECDH P-256 public key (raw, 65 bytes).
+  Hello {
+    /// Raw public key.
+    public_key: Vec<u8>,
+  },
+  /// Existing device's ephemeral ECDH P-256 public key (raw, 65 bytes).
+  Accept {
+    /// Raw public key.
+    public_key: Vec<u8>,
+  },
+  /// SDP offer for the link `DataChannel`.
+  Offer {
+    /// SDP offer string.
+    sdp: String,
+  },
+  /// SDP answer for the link `DataChannel`.
+  Answer {
+    /// SDP answer string.
+    sdp: String,
+  },
+  /// ICE candidate for the link connection.
+  IceCandidate {
+    /// ICE candidate string.
+    candidate: String,
+    /// SDP media stream identification tag.
+    sdp_mid: String,
+    /// SDP media line index.
+    sdp_m_line_index: Option<u16>,
+  },
+  /// Either side aborted the link.
+  Cancel,
+}
@@ -18,7 +18,7 @@ pub const PING: u8 = 0x04;
 pub const PONG: u8 = 0x05;
 /// Error response message type.
 pub const ERROR_RESPONSE: u8 = 0x06;
-/// Session invalidated by another device login.
+/// Session ended by the server.
 pub const SESSION_INVALIDATED: u8 = 0x07;
 
 // User Discovery & Status (0x10-0x11)
@@ -27,6 +27,30 @@ pub const USER_LIST_UPDATE: u8 = 0x10;
 /// User status change message type.
 pub const USER_STATUS_CHANGE: u8 = 0x11;
 
+// Offline Mailbox (0x12-0x19)
+/// Publish mailbox key message type.
+pub const PUBLISH_MAILBOX_KEY: u8 = 0x12;
+/// Mailbox key request message type.
+pub const MAILBOX_KEY_REQUEST: u8 = 0x13;
+/// Mailbox key response message type.
+pub const MAILBOX_KEY_RESPONSE: u8 = 0x14;
+/// Mailbox deposit message type.
+pub const MAILBOX_DEPOSIT: u8 = 0x15;
+/// Mailbox fetch message type.
+pub const MAILBOX_FETCH: u8 = 0x16;
+/// Mailbox drain message type.
+pub const MAILBOX_DRAIN: u8 = 0x17;
+/// Mailbox acknowledgement message type.
+pub const MAILBOX_ACK: u8 = 0x18;
+/// Mailbox delivered notification message type.
+pub const MAILBOX_DELIVERED: u8 = 0x19;
+
+// Devices (0x1A-0x1B)
+/// Device list update message type.
+pub const DEVICE_LIST_UPDATE: u8 = 0x1A;
+/// Device link signal message type.
+pub const DEVICE_LINK_SIGNAL: u8 = 0x1B;
+
 // Connection Invitation (0x20-0x24)
 /// Connection invitation message type.
 pub const CONNECTION_INVITE: u8 = 0x20;
@@ -47,6 +71,18 @@ pub const SDP_ANSWER: u8 = 0x31;
 /// ICE candidate message type.
 pub const ICE_CANDIDATE: u8 = 0x32;
 
+// SFU Signaling (0x33-0x37)
+/// SFU SDP offer message type.
+pub const SFU_OFFER: u8 = 0x33;
+/// SFU SDP answer message type.
+pub const SFU_ANSWER: u8 = 0x34;
+/// SFU ICE candidate message type.
+pub const SFU_ICE_CANDIDATE: u8 = 0x35;
+/// SFU track map message type.
+pub const SFU_TRACK_MAP: u8 = 0x36;
+/// SFU leave message type.
+pub const SFU_LEAVE: u8 = 0x37;
+
 // Peer Tracking (0x40-0x42)
 /// Peer connection established message type.
 pub const PEER_ESTABLISHED: u8 = 0x40;
@@ -0,0 +1,211 @@
+//! Offline mailbox signaling messages.
+//!
+//! Chat normally flows peer to peer over data channels. When the server
+//! runs with the mailbox enabled (see [`AuthSuccess::mailbox_enabled`](super::AuthSuccess)),
+//! a sender whose recipient stays unreachable can leave the message on
+//! the server instead:
+//!
+//! 1. Every device publishes its own long-term ECDH P-256 *mailbox
+//!    key*, signed by its identity key ([`PublishMailboxKey`]).
+//! 2. The sender fetches the keys of all the recipient's devices
+//!    ([`MailboxKeyRequest`] / [`MailboxKeyResponse`]), seals one
+//!    [`MailboxEnvelope`] per device and uploads each copy
+//!    ([`MailboxDeposit`]).
+//! 3. Each device drains its own mailbox after authenticating
+//!    ([`MailboxFetch`] / [`MailboxDrain`]) and confirms every item it
+//!    processed ([`MailboxAck`]). The server then tells the sender
+//!    ([`MailboxDelivered`]).
+//!
+//! The server only stores and forwards the encoded envelope; it never
+//! holds a key that opens it.
+
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{DeviceId, MessageId, UserId};
+
+/// A user's published mailbox key.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxKeyBundle {
+  /// Raw ECDH P-256 public key (65 bytes, uncompressed point).
+  pub public_key: Vec<u8>,
+  /// Raw ECDSA P-256 identity public key of the owner.
+  pub identity_key: Vec<u8>,
+  /// Identity signature over [`Self::signing_payload`] (IEEE P1363
+  /// `r || s`).
+  pub signature: Vec<u8>,
+}
+
+impl MailboxKeyBundle {
+  /// Domain separator for mailbox key signatures.
+  pub const SIGNATURE_CONTEXT: &'static [u8] = b"webrtc-e2ee-chat-mailbox-key-v1";
+
+  /// Bytes signed by the owner's identity key: the context, the owner
+  /// and the mailbox public key. Binding the owner stops the server
+  /// from handing one user's (validly signed) key out as another's.
+  #[must_use]
+  pub fn signing_payload(&self, owner: &UserId) -> Vec<u8> {
+    let mut payload =
+      Vec::with_capacity(Self::SIGNATURE_CONTEXT.len() + 16 + self.public_key.len());
+    payload.extend_from_slice(Self::SIGNATURE_CONTEXT);
+    payload.extend_from_slice(owner.as_uuid().as_bytes());
+    payload.extend_from_slice(&self.public_key);
+    payload
+  }
+}
+
+/// Sealed message stored in a mailbox, bitcode-encoded into
+/// [`MailboxDeposit::blob`].
+///
+/// The ciphertext is AES-256-GCM under a key agreed between
+/// [`Self::ephemeral_key`] and the recipient's mailbox key. The sender
+/// signs the whole envelope with its identity key so the recipient can
+/// tell who sent it.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxEnvelope {
+  /// One-time ECDH P-256 public key of the sender.
+  pub ephemeral_key: Vec<u8>,
+  /// Raw ECDSA P-256 identity public key of the sender.
+  pub identity_key: Vec<u8>,
+  /// AES-GCM nonce (12 bytes).
+  pub iv: Vec<u8>,
+  /// Encrypted `[discriminator][bitcode]` data channel frame plus tag.
+  pub ciphertext: Vec<u8>,
+  /// Identity signature over [`Self::signing_payload`].
+  pub signature: Vec<u8>,
+}
+
+impl MailboxEnvelope {
+  /// Domain separator for envelope signatures.
+  pub const SIGNATURE_CONTEXT: &'static [u8] = b"webrtc-e2ee-chat-mailbox-envelope-v1";
+
+  /// Sender, recipient and message id, in that order. Used both as
+  /// GCM additional data and as part of the signed payload so an
+  /// envelope cannot be replayed to another user or under another id.
+  #[must_use]
+  pub fn binding(from: &UserId, to: &UserId, message_id: &MessageId) -> Vec<u8> {
+    let mut binding = Vec::with_capacity(48);
+    binding.extend_from_slice(from.as_uuid().as_bytes());
+    binding.extend_from_slice(to.as_uuid().as_bytes());
+    binding.extend_from_slice(message_id.0.as_bytes());
+    binding
+  }
+
+  /// Bytes signed by the sender's identity key.
+  #[must_use]
+  pub fn signing_payload(&self, from: &UserId, to: &UserId, message_id: &MessageId) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(
+      Self::SIGNATURE_CONTEXT.len()
+        + 48
+        + self.ephemeral_key.len()
+        + self.iv.len()
+        + self.ciphertext.len(),
+    );
+    payload.extend_from_slice(Self::SIGNATURE_CONTEXT);
+    payload.extend_from_slice(&Self::binding(from, to, message_id));
+    payload.extend_from_slice(&self.ephemeral_key);
+    payload.extend_from_slice(&self.iv);
+    payload.extend_from_slice(&self.ciphertext);
+    payload
+  }
+}
+
+/// Publish (or replace) the sender's mailbox key.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PublishMailboxKey {
+  /// The signed key.
+  pub bundle: MailboxKeyBundle,
+}
+
+/// Ask for another user's mailbox key.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxKeyRequest {
+  /// User whose key is requested.
+  pub user_id: UserId,
+}
+
+/// A mailbox key and the device that published it.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct DeviceMailboxKey {
+  /// Publishing device, set by the server from the publishing
+  /// connection. Each device has its own identity key.
+  pub device_id: Option<DeviceId>,
+  /// The signed key.
+  pub bundle: MailboxKeyBundle,
+}
+
+/// Answer to a [`MailboxKeyRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxKeyResponse {
+  /// User whose keys were requested.
+  pub user_id: UserId,
+  /// One key per device, oldest first; empty if the user never
+  /// published one.
+  pub keys: Vec<DeviceMailboxKey>,
+}
+
+/// Leave a sealed message in another user's mailbox.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxDeposit {
+  /// Recipient.
+  pub to: UserId,
+  /// Recipient device whose key the blob is sealed to; only that
+  /// device drains it.
+  #[serde(default)]
+  pub to_device: Option<DeviceId>,
+  /// Id of the chat message inside the envelope.
+  pub message_id: MessageId,
+  /// Encoded [`MailboxEnvelope`]; opaque to the server.
+  pub blob: Vec<u8>,
+}
+
+/// Ask the server for everything waiting in the sender's mailbox.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, Default)]
+pub struct MailboxFetch;
+
+/// One stored message.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxItem {
+  /// Sender, as authenticated by the server at deposit time.
+  pub from: UserId,
+  /// Sending device, recorded by the server from the depositing
+  /// connection.
+  #[serde(default)]
+  pub from_device: Option<DeviceId>,
+  /// Recipient device the item is sealed to.
+  #[serde(default)]
+  pub to_device: Option<DeviceId>,
+  /// Id of the chat message inside the envelope.
+  pub message_id: MessageId,
+  /// Encoded [`MailboxEnvelope`].
+  pub blob: Vec<u8>,
+  /// When the server accepted the deposit (Unix nanoseconds).
+  pub deposited_at_nanos: i64,
+}
+
+/// Mailbox contents, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxDrain {
+  /// Stored messages.
+  pub items: Vec<MailboxItem>,
+}
+
+/// Remove processed messages from the sender's mailbox.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxAck {
+  /// Messages that were opened (or rejected) and can be discarded.
+  /// They are removed from the acknowledging device's mailbox only.
+  pub message_ids: Vec<MessageId>,
+}
+
+/// The recipient collected messages the receiver deposited.
+///
+/// Sent right away if the depositor is online, otherwise queued and
+/// sent after its next [`MailboxFetch`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct MailboxDelivered {
+  /// User who drained the messages.
+  pub recipient: UserId,
+  /// Delivered message ids.
+  pub message_ids: Vec<MessageId>,
+}
@@ -5,10 +5,13 @@
 
 pub mod auth;
 pub mod call;
+pub mod device;
 pub mod discriminator;
 pub mod invite;
+pub mod mailbox;
 pub mod moderation;
 pub mod room;
+pub mod sfu;
 pub mod user;
 pub mod webrtc;
 
@@ -17,7 +20,13 @@ pub use auth::{
   AuthFailure, AuthSuccess, IceServerSpec, Ping, Pong, SessionInvalidated, TokenAuth, UserLogout,
 };
 pub use call::{CallAccept, CallDecline, CallEnd, CallInvite};
+pub use device::{DeviceInfo, DeviceLinkPayload, DeviceLinkSignal, DeviceListUpdate};
 pub use invite::{ConnectionInvite, InviteAccepted, InviteDeclined, InviteTimeout, MultiInvite};
+pub use mailbox::{
+  DeviceMailboxKey, MailboxAck, MailboxDelivered, MailboxDeposit, MailboxDrain, MailboxEnvelope,
+  MailboxFetch, MailboxItem, MailboxKeyBundle, MailboxKeyRequest, MailboxKeyResponse,
+  PublishMailboxKey,
+};
 pub use moderation::{
   AvatarChange, BanMember, DemoteAdmin, ModerationAction, ModerationNotification, MuteMember,
   NicknameChange, PromoteAdmin, RoomAnnouncement, TheaterMuteAll, TheaterTransferOwner,
@@ -28,6 +37,7 @@ pub use room::{
   RoomInvite, RoomInviteResponse, RoomJoined, RoomLeft, RoomListUpdate, RoomMemberUpdate,
   TransferOwnership, UpdateRoomInfo, UpdateRoomPassword,
 };
+pub use sfu::{SfuAnswer, SfuIceCandidate, SfuLeave, SfuOffer, SfuTrack, SfuTrackMap};
 pub use user::{UserListUpdate, UserStatusChange};
 pub use webrtc::{ActivePeersList, IceCandidate, PeerClosed, PeerEstablished, SdpAnswer, SdpOffer};
 
@@ -60,7 +70,8 @@ pub enum SignalingMessage {
   Pong(Pong),
   /// Error response.
   ErrorResponse(crate::ErrorResponse),
-  /// Session invalidated by another device login.
+  /// Session ended by the server (revoked, or replaced by a reconnect
+  /// of the same device).
   SessionInvalidated(SessionInvalidated),
 
   // User Discovery & Status
@@ -69,6 +80,30 @@ pub enum SignalingMessage {
   /// User status change.
   UserStatusChange(UserStatusChange),
 
+  // Offline Mailbox
+  /// Publish the sender's mailbox key.
+  PublishMailboxKey(PublishMailboxKey),
+  /// Request another user's mailbox key.
+  MailboxKeyRequest(MailboxKeyRequest),
+  /// Mailbox key lookup result.
+  MailboxKeyResponse(MailboxKeyResponse),
+  /// Store a sealed message for an offline user.
+  MailboxDeposit(MailboxDeposit),
+  /// Fetch the sender's stored messages.
+  MailboxFetch(MailboxFetch),
+  /// Stored messages.
+  MailboxDrain(MailboxDrain),
+  /// Discard drained messages.
+  MailboxAck(MailboxAck),
+  /// Deposited messages were collected by their recipient.
+  MailboxDelivered(MailboxDelivered),
+
+  // Devices
+  /// The user's connected devices.
+  DeviceListUpdate(DeviceListUpdate),
+  /// Device-linking signal between two devices of one user.
+  DeviceLinkSignal(DeviceLinkSignal),
+
   // Connection Invitation
   /// Connection invitation.
   ConnectionInvite(ConnectionInvite),
@@ -89,6 +124,18 @@ pub enum SignalingMessage {
   /// ICE candidate.
   IceCandidate(IceCandidate),
 
+  // SFU Signaling
+  /// SDP offer for the SFU media session.
+  SfuOffer(SfuOffer),
+  /// SDP answer for the SFU media session.
+  SfuAnswer(SfuAnswer),
+  /// ICE candidate for the SFU media session.
+  SfuIceCandidate(SfuIceCandidate),
+  /// Tracks forwarded by the SFU and who published them.
+  SfuTrackMap(SfuTrackMap),
+  /// Leave the SFU media session.
+  SfuLeave(SfuLeave),
+
   // Peer Tracking
   /// Peer connection established.
   PeerEstablished(PeerEstablished),
@@ -187,6 +234,18 @@ impl SignalingMessage {
       Self::UserListUpdate(_) => discriminator::USER_LIST_UPDATE,
       Self::UserStatusChange(_) => discriminator::USER_STATUS_CHANGE,
 
+      Self::PublishMailboxKey(_) => discriminator::PUBLISH_MAILBOX_KEY,
+      Self::MailboxKeyRequest(_) => discriminator::MAILBOX_KEY_REQUEST,
+      Self::MailboxKeyResponse(_) => discriminator::MAILBOX_KEY_RESPONSE,
+      Self::MailboxDeposit(_) => discriminator::MAILBOX_DEPOSIT,
+      Self::MailboxFetch(_) => discriminator::MAILBOX_FETCH,
+      Self::MailboxDrain(_) => discriminator::MAILBOX_DRAIN,
+      Self::MailboxAck(_) => discriminator::MAILBOX_ACK,
+      Self::MailboxDelivered(_) => discriminator::MAILBOX_DELIVERED,
+
+      Self::DeviceListUpdate(_) => discriminator::DEVICE_LIST_UPDATE,
+      Self::DeviceLinkSignal(_) => discriminator::DEVICE_LINK_SIGNAL,
+
       Self::ConnectionInvite(_) => discriminator::CONNECTION_INVITE,
       Self::InviteAccepted(_) => discriminator::INVITE_ACCEPTED,
       Self::InviteDeclined(_) => discriminator::INVITE_DECLINED,
@@ -197,6 +256,12 @@ impl SignalingMessage {
       Self::SdpAnswer(_) => discriminator::SDP_ANSWER,
       Self::IceCandidate(_) => discriminator::ICE_CANDIDATE,
 
+      Self::SfuOffer(_) => discriminator::SFU_OFFER,
+      Self::SfuAnswer(_) => discriminator::SFU_ANSWER,
+      Self::SfuIceCandidate(_) => discriminator::SFU_ICE_CANDIDATE,
+      Self::SfuTrackMap(_) => discriminator::SFU_TRACK_MAP,
+      Self::SfuLeave(_) => discriminator::SFU_LEAVE,
+
       Self::PeerEstablished(_) => discriminator::PEER_ESTABLISHED,
       Self::PeerClosed(_) => discriminator::PEER_CLOSED,
       Self::ActivePeersList(_) => discriminator::ACTIVE_PEERS_LIST,
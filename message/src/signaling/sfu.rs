@@ -0,0 +1,82 @@
+//! Selective forwarding unit (SFU) signaling messages.
+//!
+//! Rooms whose [`RoomInfo::topology`](crate::types::RoomInfo) is
+//! [`RoomTopology::Sfu`](crate::types::RoomTopology) route media through
+//! the server instead of a full mesh. Each member keeps a single media
+//! `RTCPeerConnection` to the server: it publishes its own tracks once
+//! and receives every other member's tracks over the same connection.
+//!
+//! Either side may send an offer. The client offers when it joins or
+//! changes its published tracks; the server offers whenever another
+//! member's tracks are added or removed.
+
+use bitcode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{RoomId, UserId};
+
+/// SDP offer exchanged with the SFU.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuOffer {
+  /// Room the media session belongs to.
+  pub room_id: RoomId,
+  /// SDP offer string.
+  pub sdp: String,
+}
+
+/// SDP answer exchanged with the SFU.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuAnswer {
+  /// Room the media session belongs to.
+  pub room_id: RoomId,
+  /// SDP answer string.
+  pub sdp: String,
+}
+
+/// Trickled ICE candidate for the SFU media session.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuIceCandidate {
+  /// Room the media session belongs to.
+  pub room_id: RoomId,
+  /// ICE candidate string.
+  pub candidate: String,
+  /// SDP media stream identification tag.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sdp_mid: Option<String>,
+  /// SDP media line index.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sdp_m_line_index: Option<u16>,
+}
+
+/// One track the SFU forwards to a subscriber.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuTrack {
+  /// Member who published the track.
+  pub user_id: UserId,
+  /// `MediaStream` id the track arrives in on the subscriber side.
+  pub stream_id: String,
+  /// `MediaStreamTrack` id on the subscriber side.
+  pub track_id: String,
+  /// Track kind (`"audio"` or `"video"`).
+  pub kind: String,
+}
+
+/// Full list of tracks forwarded to the receiving member.
+///
+/// Sent by the server before every renegotiation offer so the client
+/// can attribute incoming tracks (and pick the right frame key) before
+/// `ontrack` fires.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuTrackMap {
+  /// Room the media session belongs to.
+  pub room_id: RoomId,
+  /// Every track currently forwarded to the receiver.
+  pub tracks: Vec<SfuTrack>,
+}
+
+/// Tear down the sender's SFU media session.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct SfuLeave {
+  /// Room the media session belongs to.
+  pub room_id: RoomId,
+}
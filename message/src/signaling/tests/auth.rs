@@ -6,6 +6,7 @@ use super::*;
 fn test_token_auth_roundtrip() {
   let msg = TokenAuth {
     token: "test_token_123".to_string(),
+    device: None,
   };
   let encoded = bitcode::encode(&msg);
   let decoded: TokenAuth = bitcode::decode(&encoded).expect("Failed to decode");
@@ -20,6 +21,8 @@ fn test_auth_success_roundtrip() {
     nickname: "alice".to_string(),
     ice_servers: Vec::new(),
     avatar_url: None,
+    mailbox_enabled: false,
+    device_id: crate::types::DeviceId::new(),
   };
   let encoded = bitcode::encode(&msg);
   let decoded: AuthSuccess = bitcode::decode(&encoded).expect("Failed to decode");
@@ -110,7 +113,11 @@ fn test_signaling_message_pong_roundtrip() {
 #[test]
 fn test_discriminator_auth_messages() {
   assert_eq!(
-    SignalingMessage::TokenAuth(TokenAuth { token: "t".into() }).discriminator(),
+    SignalingMessage::TokenAuth(TokenAuth {
+      token: "t".into(),
+      device: None
+    })
+    .discriminator(),
     TOKEN_AUTH
   );
   assert_eq!(
@@ -120,6 +127,8 @@ fn test_discriminator_auth_messages() {
       nickname: "u".into(),
       ice_servers: Vec::new(),
       avatar_url: None,
+      mailbox_enabled: false,
+      device_id: crate::types::DeviceId::new(),
     })
     .discriminator(),
     AUTH_SUCCESS
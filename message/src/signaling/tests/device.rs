@@ -0,0 +1,73 @@
+//! Multi-device and device-linking message tests.
+
+use super::*;
+
+fn sample_device(name: &str) -> DeviceInfo {
+  DeviceInfo {
+    device_id: DeviceId::new(),
+    name: name.to_string(),
+  }
+}
+
+#[test]
+fn test_device_list_update_roundtrip() {
+  let msg = SignalingMessage::DeviceListUpdate(DeviceListUpdate {
+    devices: vec![sample_device("Laptop"), sample_device("Phone")],
+  });
+  let encoded = bitcode::encode(&msg);
+  let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
+  assert_eq!(msg, decoded);
+  assert_eq!(msg.discriminator(), DEVICE_LIST_UPDATE);
+}
+
+#[test]
+fn test_device_link_signal_roundtrip_all_payloads() {
+  let payloads = vec![
+    DeviceLinkPayload::Hello {
+      public_key: vec![0x04; 65],
+    },
+    DeviceLinkPayload::Accept {
+      public_key: vec![0x04; 65],
+    },
+    DeviceLinkPayload::Offer {
+      sdp: "v=0".to_string(),
+    },
+    DeviceLinkPayload::Answer {
+      sdp: "v=0".to_string(),
+    },
+    DeviceLinkPayload::IceCandidate {
+      candidate: "candidate:1 1 udp 1 127.0.0.1 9 typ host".to_string(),
+      sdp_mid: "0".to_string(),
+      sdp_m_line_index: Some(0),
+    },
+    DeviceLinkPayload::Cancel,
+  ];
+  for payload in payloads {
+    let msg = SignalingMessage::DeviceLinkSignal(DeviceLinkSignal {
+      from_device: DeviceId::new(),
+      to_device: DeviceId::new(),
+      payload,
+    });
+    let encoded = bitcode::encode(&msg);
+    let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
+    assert_eq!(msg, decoded);
+    assert_eq!(msg.discriminator(), DEVICE_LINK_SIGNAL);
+  }
+}
+
+#[test]
+fn test_token_auth_without_device_deserializes_from_legacy_json() {
+  let decoded: TokenAuth = serde_json::from_str(r#"{"token":"t"}"#).unwrap();
+  assert_eq!(decoded.token, "t");
+  assert!(decoded.device.is_none());
+}
+
+#[test]
+fn test_sdp_offer_without_devices_deserializes_from_legacy_json() {
+  let from = UserId::new();
+  let to = UserId::new();
+  let json = format!(r#"{{"from":"{from}","to":"{to}","sdp":"v=0"}}"#);
+  let decoded: SdpOffer = serde_json::from_str(&json).unwrap();
+  assert!(decoded.from_device.is_none());
+  assert!(decoded.to_device.is_none());
+}
@@ -6,6 +6,7 @@ use super::*;
 fn test_signaling_message_discriminator() {
   let msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "test".to_string(),
+    device: None,
   });
   assert_eq!(msg.discriminator(), 0x00);
 
@@ -15,6 +16,8 @@ fn test_signaling_message_discriminator() {
     nickname: "alice".to_string(),
     ice_servers: Vec::new(),
     avatar_url: None,
+    mailbox_enabled: false,
+    device_id: crate::types::DeviceId::new(),
   });
   assert_eq!(msg.discriminator(), 0x01);
 
@@ -22,9 +25,17 @@ fn test_signaling_message_discriminator() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "test".to_string(),
+    from_device: None,
+    to_device: None,
   });
   assert_eq!(msg.discriminator(), 0x30);
 
+  let msg = SignalingMessage::SfuOffer(SfuOffer {
+    room_id: RoomId::new(),
+    sdp: "test".to_string(),
+  });
+  assert_eq!(msg.discriminator(), 0x33);
+
   let msg = SignalingMessage::MuteMember(MuteMember {
     room_id: RoomId::new(),
     target: UserId::new(),
@@ -52,6 +63,7 @@ fn create_auth_session_discriminators() -> Vec<u8> {
   vec![
     SignalingMessage::TokenAuth(TokenAuth {
       token: String::new(),
+      device: None,
     })
     .discriminator(),
     SignalingMessage::AuthSuccess(AuthSuccess {
@@ -60,6 +72,8 @@ fn create_auth_session_discriminators() -> Vec<u8> {
       nickname: String::new(),
       ice_servers: Vec::new(),
       avatar_url: None,
+      mailbox_enabled: false,
+      device_id: crate::types::DeviceId::new(),
     })
     .discriminator(),
     SignalingMessage::AuthFailure(AuthFailure {
@@ -114,12 +128,16 @@ fn create_peer_connection_discriminators() -> Vec<u8> {
       from: UserId::new(),
       to: UserId::new(),
       sdp: String::new(),
+      from_device: None,
+      to_device: None,
     })
     .discriminator(),
     SignalingMessage::SdpAnswer(SdpAnswer {
       from: UserId::new(),
       to: UserId::new(),
       sdp: String::new(),
+      from_device: None,
+      to_device: None,
     })
     .discriminator(),
     SignalingMessage::IceCandidate(IceCandidate::new(
@@ -142,6 +160,30 @@ fn create_peer_connection_discriminators() -> Vec<u8> {
   ]
 }
 
+/// Create discriminators for mailbox `SignalingMessage` variants.
+fn create_mailbox_discriminators() -> Vec<u8> {
+  create_mailbox_messages()
+    .iter()
+    .map(SignalingMessage::discriminator)
+    .collect()
+}
+
+/// Create discriminators for device `SignalingMessage` variants.
+fn create_device_discriminators() -> Vec<u8> {
+  create_device_messages()
+    .iter()
+    .map(SignalingMessage::discriminator)
+    .collect()
+}
+
+/// Create discriminators for SFU `SignalingMessage` variants.
+fn create_sfu_discriminators() -> Vec<u8> {
+  create_sfu_messages()
+    .iter()
+    .map(SignalingMessage::discriminator)
+    .collect()
+}
+
 /// Create discriminators for room management `SignalingMessage` variants.
 fn create_room_management_discriminators() -> Vec<u8> {
   vec![
@@ -282,6 +324,9 @@ fn create_all_signaling_discriminators() -> Vec<u8> {
   let mut discriminators = Vec::new();
   discriminators.extend(create_auth_session_discriminators());
   discriminators.extend(create_peer_connection_discriminators());
+  discriminators.extend(create_sfu_discriminators());
+  discriminators.extend(create_mailbox_discriminators());
+  discriminators.extend(create_device_discriminators());
   discriminators.extend(create_room_management_discriminators());
   discriminators.extend(create_call_theater_discriminators());
   discriminators.extend(create_moderation_discriminators());
@@ -302,6 +347,7 @@ fn create_auth_session_messages() -> Vec<SignalingMessage> {
   vec![
     SignalingMessage::TokenAuth(TokenAuth {
       token: String::new(),
+      device: None,
     }),
     SignalingMessage::AuthSuccess(AuthSuccess {
       user_id: UserId::new(),
@@ -309,6 +355,8 @@ fn create_auth_session_messages() -> Vec<SignalingMessage> {
       nickname: String::new(),
       ice_servers: Vec::new(),
       avatar_url: None,
+      mailbox_enabled: false,
+      device_id: crate::types::DeviceId::new(),
     }),
     SignalingMessage::AuthFailure(AuthFailure {
       reason: String::new(),
@@ -354,11 +402,15 @@ fn create_peer_connection_messages() -> Vec<SignalingMessage> {
       from: UserId::new(),
       to: UserId::new(),
       sdp: String::new(),
+      from_device: None,
+      to_device: None,
     }),
     SignalingMessage::SdpAnswer(SdpAnswer {
       from: UserId::new(),
       to: UserId::new(),
       sdp: String::new(),
+      from_device: None,
+      to_device: None,
     }),
     SignalingMessage::IceCandidate(IceCandidate::new(
       UserId::new(),
@@ -377,6 +429,84 @@ fn create_peer_connection_messages() -> Vec<SignalingMessage> {
   ]
 }
 
+/// Create SFU `SignalingMessage` variants.
+fn create_sfu_messages() -> Vec<SignalingMessage> {
+  vec![
+    SignalingMessage::SfuOffer(SfuOffer {
+      room_id: RoomId::new(),
+      sdp: String::new(),
+    }),
+    SignalingMessage::SfuAnswer(SfuAnswer {
+      room_id: RoomId::new(),
+      sdp: String::new(),
+    }),
+    SignalingMessage::SfuIceCandidate(SfuIceCandidate {
+      room_id: RoomId::new(),
+      candidate: String::new(),
+      sdp_mid: None,
+      sdp_m_line_index: None,
+    }),
+    SignalingMessage::SfuTrackMap(SfuTrackMap {
+      room_id: RoomId::new(),
+      tracks: vec![],
+    }),
+    SignalingMessage::SfuLeave(SfuLeave {
+      room_id: RoomId::new(),
+    }),
+  ]
+}
+
+/// Create mailbox `SignalingMessage` variants.
+fn create_mailbox_messages() -> Vec<SignalingMessage> {
+  let bundle = MailboxKeyBundle {
+    public_key: vec![],
+    identity_key: vec![],
+    signature: vec![],
+  };
+  vec![
+    SignalingMessage::PublishMailboxKey(PublishMailboxKey {
+      bundle: bundle.clone(),
+    }),
+    SignalingMessage::MailboxKeyRequest(MailboxKeyRequest {
+      user_id: UserId::new(),
+    }),
+    SignalingMessage::MailboxKeyResponse(MailboxKeyResponse {
+      user_id: UserId::new(),
+      keys: vec![DeviceMailboxKey {
+        device_id: None,
+        bundle,
+      }],
+    }),
+    SignalingMessage::MailboxDeposit(MailboxDeposit {
+      to: UserId::new(),
+      to_device: None,
+      message_id: MessageId::new(),
+      blob: vec![],
+    }),
+    SignalingMessage::MailboxFetch(MailboxFetch),
+    SignalingMessage::MailboxDrain(MailboxDrain { items: vec![] }),
+    SignalingMessage::MailboxAck(MailboxAck {
+      message_ids: vec![],
+    }),
+    SignalingMessage::MailboxDelivered(MailboxDelivered {
+      recipient: UserId::new(),
+      message_ids: vec![],
+    }),
+  ]
+}
+
+/// Create device `SignalingMessage` variants.
+fn create_device_messages() -> Vec<SignalingMessage> {
+  vec![
+    SignalingMessage::DeviceListUpdate(DeviceListUpdate { devices: vec![] }),
+    SignalingMessage::DeviceLinkSignal(DeviceLinkSignal {
+      from_device: DeviceId::new(),
+      to_device: DeviceId::new(),
+      payload: DeviceLinkPayload::Cancel,
+    }),
+  ]
+}
+
 /// Create a default `RoomInfo` for testing.
 fn create_test_room_info() -> crate::types::RoomInfo {
   crate::types::RoomInfo {
@@ -387,6 +517,7 @@ fn create_test_room_info() -> crate::types::RoomInfo {
     owner_id: UserId::new(),
     password_hash: None,
     max_members: 8,
+    topology: RoomTopology::Mesh,
     member_count: 1,
     created_at_nanos: 0,
     announcement: String::new(),
@@ -531,6 +662,9 @@ fn create_all_signaling_messages() -> Vec<SignalingMessage> {
   let mut messages = Vec::new();
   messages.extend(create_auth_session_messages());
   messages.extend(create_peer_connection_messages());
+  messages.extend(create_sfu_messages());
+  messages.extend(create_mailbox_messages());
+  messages.extend(create_device_messages());
   messages.extend(create_room_management_messages());
   messages.extend(create_call_theater_messages());
   messages.extend(create_moderation_messages());
@@ -559,6 +693,7 @@ fn test_signaling_bitcode_roundtrip_preserves_discriminator() {
   // Encode a TokenAuth and verify the discriminator is preserved
   let msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "roundtrip-token".to_string(),
+    device: None,
   });
   let disc = msg.discriminator();
   assert_eq!(disc, TOKEN_AUTH);
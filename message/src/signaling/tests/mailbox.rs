@@ -0,0 +1,178 @@
+//! Offline mailbox message tests.
+
+use super::*;
+
+fn sample_bundle() -> MailboxKeyBundle {
+  MailboxKeyBundle {
+    public_key: vec![0x04; 65],
+    identity_key: vec![0x04; 65],
+    signature: vec![0xAB; 64],
+  }
+}
+
+fn sample_envelope() -> MailboxEnvelope {
+  MailboxEnvelope {
+    ephemeral_key: vec![0x04; 65],
+    identity_key: vec![0x04; 65],
+    iv: vec![7; 12],
+    ciphertext: vec![1, 2, 3, 4],
+    signature: vec![0xCD; 64],
+  }
+}
+
+#[test]
+fn test_mailbox_drain_roundtrip() {
+  let msg = SignalingMessage::MailboxDrain(MailboxDrain {
+    items: vec![MailboxItem {
+      from: UserId::new(),
+      from_device: Some(DeviceId::new()),
+      to_device: Some(DeviceId::new()),
+      message_id: MessageId::new(),
+      blob: bitcode::encode(&sample_envelope()),
+      deposited_at_nanos: 1_700_000_000_000_000_000,
+    }],
+  });
+  let encoded = bitcode::encode(&msg);
+  let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_mailbox_key_response_without_key_roundtrip() {
+  let msg = MailboxKeyResponse {
+    user_id: UserId::new(),
+    keys: vec![],
+  };
+  let json = serde_json::to_string(&msg).unwrap();
+  let decoded: MailboxKeyResponse = serde_json::from_str(&json).unwrap();
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_mailbox_key_response_per_device_roundtrip() {
+  let msg = SignalingMessage::MailboxKeyResponse(MailboxKeyResponse {
+    user_id: UserId::new(),
+    keys: vec![
+      DeviceMailboxKey {
+        device_id: Some(DeviceId::new()),
+        bundle: sample_bundle(),
+      },
+      DeviceMailboxKey {
+        device_id: None,
+        bundle: sample_bundle(),
+      },
+    ],
+  });
+  let encoded = bitcode::encode(&msg);
+  let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_mailbox_envelope_blob_roundtrip() {
+  let envelope = sample_envelope();
+  let blob = bitcode::encode(&envelope);
+  let decoded: MailboxEnvelope = bitcode::decode(&blob).expect("Failed to decode");
+  assert_eq!(envelope, decoded);
+}
+
+#[test]
+fn test_mailbox_key_signing_payload_binds_owner() {
+  let bundle = sample_bundle();
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let payload = bundle.signing_payload(&alice);
+  assert!(payload.starts_with(MailboxKeyBundle::SIGNATURE_CONTEXT));
+  assert!(payload.ends_with(&bundle.public_key));
+  assert_ne!(payload, bundle.signing_payload(&bob));
+}
+
+#[test]
+fn test_mailbox_envelope_signing_payload_binds_parties_and_id() {
+  let envelope = sample_envelope();
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let id = MessageId::new();
+  let payload = envelope.signing_payload(&alice, &bob, &id);
+  assert!(payload.starts_with(MailboxEnvelope::SIGNATURE_CONTEXT));
+  assert_ne!(payload, envelope.signing_payload(&bob, &alice, &id));
+  assert_ne!(
+    payload,
+    envelope.signing_payload(&alice, &bob, &MessageId::new())
+  );
+
+  let mut tampered = envelope.clone();
+  tampered.ciphertext.push(0);
+  assert_ne!(payload, tampered.signing_payload(&alice, &bob, &id));
+}
+
+#[test]
+fn test_mailbox_binding_layout() {
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let id = MessageId::new();
+  let binding = MailboxEnvelope::binding(&alice, &bob, &id);
+  assert_eq!(binding.len(), 48);
+  assert_eq!(&binding[..16], alice.as_uuid().as_bytes());
+  assert_eq!(&binding[16..32], bob.as_uuid().as_bytes());
+  assert_eq!(&binding[32..], id.0.as_bytes());
+}
+
+#[test]
+fn test_discriminator_mailbox_messages() {
+  assert_eq!(
+    SignalingMessage::PublishMailboxKey(PublishMailboxKey {
+      bundle: sample_bundle()
+    })
+    .discriminator(),
+    PUBLISH_MAILBOX_KEY
+  );
+  assert_eq!(
+    SignalingMessage::MailboxKeyRequest(MailboxKeyRequest {
+      user_id: UserId::new()
+    })
+    .discriminator(),
+    MAILBOX_KEY_REQUEST
+  );
+  assert_eq!(
+    SignalingMessage::MailboxKeyResponse(MailboxKeyResponse {
+      user_id: UserId::new(),
+      keys: vec![],
+    })
+    .discriminator(),
+    MAILBOX_KEY_RESPONSE
+  );
+  assert_eq!(
+    SignalingMessage::MailboxDeposit(MailboxDeposit {
+      to: UserId::new(),
+      to_device: None,
+      message_id: MessageId::new(),
+      blob: vec![]
+    })
+    .discriminator(),
+    MAILBOX_DEPOSIT
+  );
+  assert_eq!(
+    SignalingMessage::MailboxFetch(MailboxFetch).discriminator(),
+    MAILBOX_FETCH
+  );
+  assert_eq!(
+    SignalingMessage::MailboxDrain(MailboxDrain { items: vec![] }).discriminator(),
+    MAILBOX_DRAIN
+  );
+  assert_eq!(
+    SignalingMessage::MailboxAck(MailboxAck {
+      message_ids: vec![]
+    })
+    .discriminator(),
+    MAILBOX_ACK
+  );
+  assert_eq!(
+    SignalingMessage::MailboxDelivered(MailboxDelivered {
+      recipient: UserId::new(),
+      message_ids: vec![]
+    })
+    .discriminator(),
+    MAILBOX_DELIVERED
+  );
+}
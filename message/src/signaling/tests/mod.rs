@@ -6,16 +6,22 @@
 //! - `invite`: Connection invite messages
 //! - `webrtc`: WebRTC signaling messages (SDP, ICE, Peer)
 //! - `room`: Room management messages
+//! - `sfu`: SFU media session messages
+//! - `mailbox`: Offline mailbox messages
 //! - `call`: Call control messages
 //! - `moderation`: Moderation action messages
+//! - `device`: Multi-device and device-linking messages
 //! - `discriminator`: Discriminator value and uniqueness tests
 
 mod auth;
 mod call;
+mod device;
 mod discriminator;
 mod invite;
+mod mailbox;
 mod moderation;
 mod room;
+mod sfu;
 mod user;
 mod webrtc;
 
@@ -40,6 +46,13 @@ pub(super) use super::{
   // Room messages
   CreateRoom,
   DemoteAdmin,
+  // Device messages
+  DeviceInfo,
+  DeviceLinkPayload,
+  DeviceLinkSignal,
+  DeviceListUpdate,
+  // Mailbox messages
+  DeviceMailboxKey,
   IceCandidate,
   InviteAccepted,
   InviteDeclined,
@@ -47,6 +60,16 @@ pub(super) use super::{
   JoinRoom,
   KickMember,
   LeaveRoom,
+  MailboxAck,
+  MailboxDelivered,
+  MailboxDeposit,
+  MailboxDrain,
+  MailboxEnvelope,
+  MailboxFetch,
+  MailboxItem,
+  MailboxKeyBundle,
+  MailboxKeyRequest,
+  MailboxKeyResponse,
   ModerationNotification,
   MultiInvite,
   MuteMember,
@@ -58,6 +81,7 @@ pub(super) use super::{
   Ping,
   Pong,
   PromoteAdmin,
+  PublishMailboxKey,
   RoomAnnouncement,
   RoomCreated,
   RoomJoined,
@@ -67,6 +91,13 @@ pub(super) use super::{
   SdpAnswer,
   SdpOffer,
   SessionInvalidated,
+  // SFU messages
+  SfuAnswer,
+  SfuIceCandidate,
+  SfuLeave,
+  SfuOffer,
+  SfuTrack,
+  SfuTrackMap,
   // Main enum
   SignalingMessage,
   TheaterMuteAll,
@@ -85,7 +116,9 @@ pub(super) use super::{
 pub(super) use crate::frame::{MessageFrame, decode_frame, encode_frame};
 
 // Re-export common types
-pub(super) use crate::types::{MediaType, MemberInfo, RoomId, RoomInfo, RoomRole, RoomType};
+pub(super) use crate::types::{
+  MediaType, MemberInfo, RoomId, RoomInfo, RoomRole, RoomTopology, RoomType,
+};
 
 // Re-export error codes for tests
 pub(super) use crate::error::codes::SIG001;
@@ -94,17 +127,19 @@ pub(super) use crate::error::codes::SIG001;
 pub(super) use crate::signaling::discriminator::{
   ACTIVE_PEERS_LIST, AUTH_FAILURE, AUTH_SUCCESS, AVATAR_CHANGE, BAN_MEMBER, CALL_ACCEPT,
   CALL_DECLINE, CALL_END, CALL_INVITE, CONNECTION_INVITE, CREATE_ROOM, DEMOTE_ADMIN,
-  ERROR_RESPONSE, ICE_CANDIDATE, INVITE_ACCEPTED, INVITE_DECLINED, INVITE_TIMEOUT, JOIN_ROOM,
-  KICK_MEMBER, LEAVE_ROOM, MODERATION_NOTIFICATION, MULTI_INVITE, MUTE_MEMBER, MUTE_STATUS_CHANGE,
+  DEVICE_LINK_SIGNAL, DEVICE_LIST_UPDATE, ERROR_RESPONSE, ICE_CANDIDATE, INVITE_ACCEPTED,
+  INVITE_DECLINED, INVITE_TIMEOUT, JOIN_ROOM, KICK_MEMBER, LEAVE_ROOM, MAILBOX_ACK,
+  MAILBOX_DELIVERED, MAILBOX_DEPOSIT, MAILBOX_DRAIN, MAILBOX_FETCH, MAILBOX_KEY_REQUEST,
+  MAILBOX_KEY_RESPONSE, MODERATION_NOTIFICATION, MULTI_INVITE, MUTE_MEMBER, MUTE_STATUS_CHANGE,
   NICKNAME_CHANGE, OWNER_CHANGED, PEER_CLOSED, PEER_ESTABLISHED, PING, PONG, PROMOTE_ADMIN,
-  ROOM_CREATED, ROOM_JOINED, ROOM_LEFT, ROOM_LIST_UPDATE, ROOM_MEMBER_UPDATE, SDP_ANSWER,
-  SDP_OFFER, SESSION_INVALIDATED, THEATER_MUTE_ALL, THEATER_TRANSFER_OWNER, TOKEN_AUTH,
-  TRANSFER_OWNERSHIP, UNBAN_MEMBER, UNMUTE_MEMBER, USER_LIST_UPDATE, USER_LOGOUT,
-  USER_STATUS_CHANGE,
+  PUBLISH_MAILBOX_KEY, ROOM_CREATED, ROOM_JOINED, ROOM_LEFT, ROOM_LIST_UPDATE, ROOM_MEMBER_UPDATE,
+  SDP_ANSWER, SDP_OFFER, SESSION_INVALIDATED, SFU_ANSWER, SFU_ICE_CANDIDATE, SFU_LEAVE, SFU_OFFER,
+  SFU_TRACK_MAP, THEATER_MUTE_ALL, THEATER_TRANSFER_OWNER, TOKEN_AUTH, TRANSFER_OWNERSHIP,
+  UNBAN_MEMBER, UNMUTE_MEMBER, USER_LIST_UPDATE, USER_LOGOUT, USER_STATUS_CHANGE,
 };
 
 // Re-export specific types used by individual test files
-pub(super) use crate::types::{UserId, UserStatus};
+pub(super) use crate::types::{DeviceId, MessageId, UserId, UserStatus};
 
 // Re-export ModerationAction for moderation tests
 pub(super) use crate::ModerationAction;
@@ -0,0 +1,96 @@
+//! SFU media session message tests.
+
+use super::*;
+
+#[test]
+fn test_sfu_offer_roundtrip() {
+  let msg = SfuOffer {
+    room_id: RoomId::new(),
+    sdp: "v=0\r\no=- 123456 2 IN IP4 127.0.0.1\r\n".to_string(),
+  };
+  let encoded = bitcode::encode(&msg);
+  let decoded: SfuOffer = bitcode::decode(&encoded).expect("Failed to decode");
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_sfu_ice_candidate_json_omits_missing_fields() {
+  let msg = SfuIceCandidate {
+    room_id: RoomId::new(),
+    candidate: "candidate:1 1 UDP 2122260223 10.0.0.1 40000 typ host".to_string(),
+    sdp_mid: None,
+    sdp_m_line_index: None,
+  };
+  let json = serde_json::to_string(&msg).unwrap();
+  assert!(!json.contains("sdp_mid"));
+  let decoded: SfuIceCandidate = serde_json::from_str(&json).unwrap();
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_sfu_track_map_roundtrip() {
+  let publisher = UserId::new();
+  let msg = SignalingMessage::SfuTrackMap(SfuTrackMap {
+    room_id: RoomId::new(),
+    tracks: vec![
+      SfuTrack {
+        user_id: publisher.clone(),
+        stream_id: publisher.to_string(),
+        track_id: "audio-1".to_string(),
+        kind: "audio".to_string(),
+      },
+      SfuTrack {
+        user_id: publisher.clone(),
+        stream_id: publisher.to_string(),
+        track_id: "video-1".to_string(),
+        kind: "video".to_string(),
+      },
+    ],
+  });
+  let encoded = bitcode::encode(&msg);
+  let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
+  assert_eq!(msg, decoded);
+}
+
+#[test]
+fn test_discriminator_sfu_messages() {
+  let room_id = RoomId::new();
+  assert_eq!(
+    SignalingMessage::SfuOffer(SfuOffer {
+      room_id: room_id.clone(),
+      sdp: "offer".into()
+    })
+    .discriminator(),
+    SFU_OFFER
+  );
+  assert_eq!(
+    SignalingMessage::SfuAnswer(SfuAnswer {
+      room_id: room_id.clone(),
+      sdp: "answer".into()
+    })
+    .discriminator(),
+    SFU_ANSWER
+  );
+  assert_eq!(
+    SignalingMessage::SfuIceCandidate(SfuIceCandidate {
+      room_id: room_id.clone(),
+      candidate: "c".into(),
+      sdp_mid: Some("0".into()),
+      sdp_m_line_index: Some(0),
+    })
+    .discriminator(),
+    SFU_ICE_CANDIDATE
+  );
+  assert_eq!(
+    SignalingMessage::SfuTrackMap(SfuTrackMap {
+      room_id: room_id.clone(),
+      tracks: vec![]
+    })
+    .discriminator(),
+    SFU_TRACK_MAP
+  );
+  assert_eq!(
+    SignalingMessage::SfuLeave(SfuLeave { room_id }).discriminator(),
+    SFU_LEAVE
+  );
+}
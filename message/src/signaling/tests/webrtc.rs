@@ -8,6 +8,8 @@ fn test_sdp_offer_roundtrip() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "v=0\r\no=- 123456 123456 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   let encoded = bitcode::encode(&msg);
   let decoded: SdpOffer = bitcode::decode(&encoded).expect("Failed to decode");
@@ -53,6 +55,8 @@ fn test_sdp_answer_roundtrip() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "v=0\r\no=- 123 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   let encoded = bitcode::encode(&msg);
   let decoded: SdpAnswer = bitcode::decode(&encoded).expect("Failed to decode");
@@ -76,6 +80,8 @@ fn test_signaling_message_sdp_answer_roundtrip() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "answer-sdp".to_string(),
+    from_device: None,
+    to_device: None,
   });
   let encoded = bitcode::encode(&msg);
   let decoded: SignalingMessage = bitcode::decode(&encoded).expect("Failed to decode");
@@ -101,7 +107,9 @@ fn test_discriminator_webrtc_messages() {
     SignalingMessage::SdpOffer(SdpOffer {
       from: uid1.clone(),
       to: uid2.clone(),
-      sdp: "offer".into()
+      sdp: "offer".into(),
+      from_device: None,
+      to_device: None,
     })
     .discriminator(),
     SDP_OFFER
@@ -110,7 +118,9 @@ fn test_discriminator_webrtc_messages() {
     SignalingMessage::SdpAnswer(SdpAnswer {
       from: uid1.clone(),
       to: uid2.clone(),
-      sdp: "answer".into()
+      sdp: "answer".into(),
+      from_device: None,
+      to_device: None,
     })
     .discriminator(),
     SDP_ANSWER
@@ -184,6 +194,7 @@ fn test_signaling_unmapped_discriminator_frame_succeeds_bitcode_fails() {
     // Create a frame with a valid payload (TokenAuth bitcode bytes)
     let valid_payload = bitcode::encode(&TokenAuth {
       token: "test".to_string(),
+      device: None,
     });
     let frame = MessageFrame::new(disc, valid_payload.clone());
     let encoded = encode_frame(&frame).expect("encode should succeed");
@@ -211,6 +222,7 @@ fn test_signaling_datachannel_discriminator_not_confused() {
   let datachannel_disc: u8 = 0x80; // CHAT_TEXT
   let payload = bitcode::encode(&TokenAuth {
     token: "cross-namespace".to_string(),
+    device: None,
   });
 
   let frame = MessageFrame::new(datachannel_disc, payload);
@@ -3,7 +3,7 @@
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use crate::types::UserId;
+use crate::types::{DeviceId, UserId};
 
 /// SDP Offer forwarding.
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
@@ -14,6 +14,14 @@ pub struct SdpOffer {
   pub to: UserId,
   /// SDP offer string.
   pub sdp: String,
+  /// Sending device. Set by the server from the sender's connection.
+  #[serde(default)]
+  pub from_device: Option<DeviceId>,
+  /// Target device. `None` delivers to every device of [`Self::to`] and
+  /// every one of them answers; the caller keeps a peer connection per
+  /// answering device.
+  #[serde(default)]
+  pub to_device: Option<DeviceId>,
 }
 
 /// SDP Answer forwarding.
@@ -25,6 +33,13 @@ pub struct SdpAnswer {
   pub to: UserId,
   /// SDP answer string.
   pub sdp: String,
+  /// Sending device. Set by the server from the sender's connection.
+  #[serde(default)]
+  pub from_device: Option<DeviceId>,
+  /// Target device. `None` delivers to every device of [`Self::to`];
+  /// only the device holding the matching peer connection uses it.
+  #[serde(default)]
+  pub to_device: Option<DeviceId>,
 }
 
 /// ICE Candidate forwarding.
@@ -48,6 +63,13 @@ pub struct IceCandidate {
   /// Defaults to `Some(0)` for DataChannel-only connections.
   #[serde(default = "default_sdp_m_line_index")]
   pub sdp_m_line_index: Option<u16>,
+  /// Sending device. Set by the server from the sender's connection.
+  #[serde(default)]
+  pub from_device: Option<DeviceId>,
+  /// Target device. `None` delivers to every device of [`Self::to`];
+  /// only the device holding the matching peer connection uses it.
+  #[serde(default)]
+  pub to_device: Option<DeviceId>,
 }
 
 fn default_sdp_mid() -> String {
@@ -71,6 +93,8 @@ impl IceCandidate {
       candidate,
       sdp_mid: default_sdp_mid(),
       sdp_m_line_index: default_sdp_m_line_index(),
+      from_device: None,
+      to_device: None,
     }
   }
 }
@@ -57,6 +57,29 @@ impl fmt::Display for RoomType {
   }
 }
 
+/// How a room routes call and theater media between its members.
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomTopology {
+  /// Every member holds a direct peer connection to every other member
+  #[default]
+  Mesh,
+  /// Members publish media once to the server's selective forwarding
+  /// unit, which relays it to every subscriber
+  Sfu,
+}
+
+impl fmt::Display for RoomTopology {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Mesh => write!(f, "Mesh"),
+      Self::Sfu => write!(f, "SFU"),
+    }
+  }
+}
+
 /// Media type for calls and screen sharing.
 #[derive(
   Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode,
@@ -116,6 +139,8 @@ pub enum MessageContentType {
   File,
   /// System message (join, leave, etc.)
   System,
+  /// Poll message
+  Poll,
 }
 
 impl fmt::Display for MessageContentType {
@@ -127,6 +152,7 @@ impl fmt::Display for MessageContentType {
       Self::Image => write!(f, "Image"),
       Self::File => write!(f, "File"),
       Self::System => write!(f, "System"),
+      Self::Poll => write!(f, "Poll"),
     }
   }
 }
@@ -201,3 +201,50 @@ impl FromStr for TransferId {
     Uuid::parse_str(s).map(Self)
   }
 }
+
+/// Unique identifier for one of a user's devices (browser profile or
+/// native client). Generated by the client once and kept across
+/// reconnects, so the server can tell a reconnect of the same device
+/// from a second device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
+pub struct DeviceId(pub Uuid);
+
+impl DeviceId {
+  /// Create a new random `DeviceId`.
+  #[must_use]
+  pub fn new() -> Self {
+    Self(Uuid::new_v4())
+  }
+
+  /// Create a `DeviceId` from a `Uuid`.
+  #[must_use]
+  pub const fn from_uuid(uuid: Uuid) -> Self {
+    Self(uuid)
+  }
+
+  /// Get the inner `Uuid`.
+  #[must_use]
+  pub const fn as_uuid(&self) -> &Uuid {
+    &self.0
+  }
+}
+
+impl Default for DeviceId {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Display for DeviceId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl FromStr for DeviceId {
+  type Err = uuid::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Uuid::parse_str(s).map(Self)
+  }
+}
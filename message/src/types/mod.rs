@@ -11,10 +11,10 @@ pub mod structs;
 
 // Re-export all public types for backward compatibility
 pub use enums::{
-  DanmakuPosition, MediaType, MessageContentType, NetworkQuality, ReactionAction, RoomType,
-  UserStatus,
+  DanmakuPosition, MediaType, MessageContentType, NetworkQuality, ReactionAction, RoomTopology,
+  RoomType, UserStatus,
 };
-pub use identifiers::{MessageId, RoomId, TransferId, UserId};
+pub use identifiers::{DeviceId, MessageId, RoomId, TransferId, UserId};
 pub use mute::MuteInfo;
 pub use role::RoomRole;
 pub use structs::{ImageMeta, MemberInfo, RoomInfo, UserInfo};
@@ -22,7 +22,9 @@ pub use structs::{ImageMeta, MemberInfo, RoomInfo, UserInfo};
 // SubtitleEntry is defined in datachannel module and re-exported here
 // for backward compatibility. The datachannel version uses u32 timestamps
 // which is sufficient for ~49 days of millisecond precision.
-pub use crate::datachannel::SubtitleEntry;
+pub use crate::datachannel::{
+  SubtitleAnchor, SubtitleCueStyle, SubtitleEntry, SubtitleKaraoke, SubtitleSpan,
+};
 
 #[cfg(test)]
 mod tests;
@@ -7,7 +7,7 @@ use bitcode::{Decode, Encode};
 use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::enums::{RoomType, UserStatus};
+use super::enums::{RoomTopology, RoomType, UserStatus};
 use super::identifiers::{RoomId, UserId};
 use super::mute::MuteInfo;
 use super::role::RoomRole;
@@ -93,6 +93,9 @@ pub struct RoomInfo {
   pub password_hash: Option<String>,
   /// Maximum number of members (default 8)
   pub max_members: u8,
+  /// Media topology. `Sfu` rooms may exceed the 8-peer mesh limit.
+  #[serde(default)]
+  pub topology: RoomTopology,
   /// Current member count
   pub member_count: u8,
   /// Room creation timestamp (Unix timestamp in nanoseconds)
@@ -117,6 +120,7 @@ impl RoomInfo {
       owner_id,
       password_hash: None,
       max_members: 8,
+      topology: RoomTopology::Mesh,
       member_count: 1,
       created_at_nanos: Utc::now().timestamp_nanos_opt().unwrap_or(0),
       announcement: String::new(),
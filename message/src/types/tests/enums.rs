@@ -22,6 +22,18 @@ fn test_room_type_default() {
   assert_eq!(default, RoomType::Chat);
 }
 
+#[test]
+fn test_room_topology_default_is_mesh() {
+  assert_eq!(RoomTopology::default(), RoomTopology::Mesh);
+  assert_eq!(format!("{}", RoomTopology::Sfu), "SFU");
+}
+
+#[test]
+fn test_room_topology_serde_snake_case() {
+  let json = serde_json::to_string(&RoomTopology::Sfu).unwrap();
+  assert_eq!(json, "\"sfu\"");
+}
+
 // ===========================================================================
 // UserStatus tests
 // ===========================================================================
@@ -166,6 +178,11 @@ fn test_message_content_type_file_display() {
   assert_eq!(format!("{}", MessageContentType::File), "File");
 }
 
+#[test]
+fn test_message_content_type_poll_display() {
+  assert_eq!(format!("{}", MessageContentType::Poll), "Poll");
+}
+
 #[test]
 fn test_message_content_type_default() {
   let default = MessageContentType::default();
@@ -1,4 +1,5 @@
-//! Identifier type tests: `UserId`, `RoomId`, `MessageId`, `TransferId`.
+//! Identifier type tests: `UserId`, `RoomId`, `MessageId`, `TransferId`,
+//! `DeviceId`.
 
 use super::*;
 
@@ -152,6 +153,23 @@ fn test_transfer_id_clone_and_equality() {
   assert_eq!(id, cloned);
 }
 
+// ===========================================================================
+// DeviceId tests
+// ===========================================================================
+
+#[test]
+fn test_device_id_new_creates_unique_ids() {
+  assert_ne!(DeviceId::new(), DeviceId::new());
+}
+
+#[test]
+fn test_device_id_display_parse_roundtrip() {
+  let id = DeviceId::new();
+  let parsed: DeviceId = id.to_string().parse().expect("parse DeviceId");
+  assert_eq!(parsed, id);
+  assert!("not-a-uuid".parse::<DeviceId>().is_err());
+}
+
 // ===========================================================================
 // Cross-identifier type safety
 // ===========================================================================
@@ -85,6 +85,7 @@ fn test_message_content_type_bitcode_roundtrip() {
     MessageContentType::Text,
     MessageContentType::Image,
     MessageContentType::System,
+    MessageContentType::Poll,
   ] {
     let encoded = bitcode::encode(&variant);
     let decoded: MessageContentType = bitcode::decode(&encoded).unwrap();
@@ -223,6 +224,7 @@ fn test_room_info_bitcode_roundtrip() {
     room_type: RoomType::Chat,
     password_hash: Some("hashed".to_string()),
     max_members: 8,
+    topology: RoomTopology::Mesh,
     member_count: 3,
     created_at_nanos: now,
     announcement: "Welcome!".to_string(),
@@ -344,6 +346,7 @@ fn test_room_info_json_roundtrip() {
     room_type: RoomType::Chat,
     password_hash: None,
     max_members: 8,
+    topology: RoomTopology::Mesh,
     member_count: 5,
     created_at_nanos: now,
     announcement: String::new(),
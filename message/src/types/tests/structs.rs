@@ -87,6 +87,7 @@ fn test_room_info_creation() {
     room_type: RoomType::Chat,
     password_hash: None,
     max_members: 8,
+    topology: RoomTopology::Mesh,
     member_count: 1,
     created_at_nanos: now,
     announcement: String::new(),
@@ -116,6 +117,7 @@ fn test_room_info_theater_type() {
     room_type: RoomType::Theater,
     password_hash: Some("hashed_password".to_string()),
     max_members: 50,
+    topology: RoomTopology::Sfu,
     member_count: 5,
     created_at_nanos: now,
     announcement: "Welcome!".to_string(),
@@ -123,6 +125,7 @@ fn test_room_info_theater_type() {
   };
 
   assert_eq!(room_info.room_type, RoomType::Theater);
+  assert_eq!(room_info.topology, RoomTopology::Sfu);
   assert!(room_info.is_password_protected());
   assert_eq!(
     room_info.video_url.unwrap(),
@@ -144,6 +147,7 @@ fn test_room_info_password_protection() {
     room_type: RoomType::Chat,
     password_hash: None,
     max_members: 8,
+    topology: RoomTopology::Mesh,
     member_count: 0,
     created_at_nanos: now,
     announcement: String::new(),
@@ -261,6 +261,7 @@ fn test_signaling_discriminator_range_wasm() {
   let messages: Vec<SignalingMessage> = vec![
     SignalingMessage::TokenAuth(crate::signaling::TokenAuth {
       token: String::new(),
+      device: None,
     }),
     SignalingMessage::Ping(crate::signaling::Ping {}),
     SignalingMessage::Pong(crate::signaling::Pong {}),
@@ -289,6 +290,7 @@ fn test_datachannel_discriminator_range_wasm() {
       message_id: MessageId::new(),
       content: String::new(),
       reply_to: None,
+      thread_root: None,
       timestamp_nanos: 0,
       room_id: None,
       mentions: vec![],
@@ -9,6 +9,7 @@ fn test_wasm_datachannel_chat_sticker_roundtrip() {
     pack_id: "pack_001".to_string(),
     sticker_id: "sticker_042".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -25,6 +26,7 @@ fn test_wasm_datachannel_chat_voice_roundtrip() {
     duration_ms: 3500,
     waveform: vec![10, 20, 30, 40],
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -42,6 +44,7 @@ fn test_wasm_datachannel_chat_image_roundtrip() {
     width: 1920,
     height: 1080,
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
   };
@@ -157,6 +160,8 @@ fn test_wasm_datachannel_ecdh_key_exchange_roundtrip() {
   let msg = EcdhKeyExchange {
     public_key: vec![42u8; 65], // P-256 raw format: 65 bytes
     timestamp_nanos: 1_000_000_000,
+    identity_key: vec![0u8; 65],
+    signature: vec![0u8; 64],
   };
   roundtrip_datachannel(0xA0, &msg);
 }
@@ -224,6 +229,7 @@ fn test_wasm_datachannel_subtitle_data_roundtrip() {
       start_ms: 1000,
       end_ms: 3000,
       text: "WASM subtitle".to_string(),
+      style: None,
     }],
   };
   roundtrip_datachannel(0xB2, &msg);
@@ -139,6 +139,7 @@ fn test_decode_truncated_bitcode_payload() {
   use crate::signaling::TokenAuth;
   let msg = TokenAuth {
     token: "a-long-enough-token-string".to_string(),
+    device: None,
   };
   let payload = bitcode::encode(&msg);
   assert!(payload.len() > 2, "Payload should be > 2 bytes");
@@ -266,6 +267,7 @@ fn test_decode_wrong_message_type_bitcode_mismatch() {
     message_id: MessageId::new(),
     content: "Hello WASM".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
     mentions: vec![],
@@ -5,6 +5,7 @@ fn test_wasm_token_auth_roundtrip() {
   use crate::signaling::TokenAuth;
   let msg = TokenAuth {
     token: "wasm-test-token".to_string(),
+    device: None,
   };
   roundtrip_signaling(0x00, &msg);
 }
@@ -19,6 +20,8 @@ fn test_wasm_auth_success_roundtrip() {
     nickname: "wasm_user".to_string(),
     ice_servers: Vec::new(),
     avatar_url: None,
+    mailbox_enabled: false,
+    device_id: crate::types::DeviceId::new(),
   };
   roundtrip_signaling(0x01, &msg);
 }
@@ -144,6 +147,8 @@ fn test_wasm_sdp_offer_roundtrip() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "v=0\r\no=- 456 1 IN IP4 0.0.0.0\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   roundtrip_signaling(crate::signaling::discriminator::SDP_OFFER, &msg);
 }
@@ -156,6 +161,8 @@ fn test_wasm_sdp_answer_roundtrip() {
     from: UserId::new(),
     to: UserId::new(),
     sdp: "v=0\r\no=- 789 1 IN IP4 0.0.0.0\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   roundtrip_signaling(crate::signaling::discriminator::SDP_ANSWER, &msg);
 }
@@ -172,6 +179,30 @@ fn test_wasm_ice_candidate_roundtrip() {
   roundtrip_signaling(crate::signaling::discriminator::ICE_CANDIDATE, &msg);
 }
 
+#[wasm_bindgen_test]
+fn test_wasm_sfu_offer_roundtrip() {
+  use crate::signaling::SfuOffer;
+  use crate::types::RoomId;
+  let msg = SfuOffer {
+    room_id: RoomId::new(),
+    sdp: "v=0\r\no=- 321 1 IN IP4 0.0.0.0\r\n".to_string(),
+  };
+  roundtrip_signaling(crate::signaling::discriminator::SFU_OFFER, &msg);
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_mailbox_deposit_roundtrip() {
+  use crate::signaling::MailboxDeposit;
+  use crate::types::{MessageId, UserId};
+  let msg = MailboxDeposit {
+    to: UserId::new(),
+    to_device: None,
+    message_id: MessageId::new(),
+    blob: vec![0x5A; 128],
+  };
+  roundtrip_signaling(crate::signaling::discriminator::MAILBOX_DEPOSIT, &msg);
+}
+
 #[wasm_bindgen_test]
 fn test_wasm_peer_established_roundtrip() {
   use crate::signaling::PeerEstablished;
@@ -416,6 +447,7 @@ fn test_wasm_datachannel_chat_text_roundtrip() {
     message_id: MessageId::new(),
     content: "Hello from WASM!".to_string(),
     reply_to: None,
+    thread_root: None,
     timestamp_nanos: 1_000_000_000,
     room_id: None,
     mentions: vec![],
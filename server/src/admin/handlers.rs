@@ -0,0 +1,352 @@
+//! HTTP handlers for the admin API.
+//!
+//! All handlers run behind [`super::require_admin`] and receive the
+//! authenticated [`AdminPrincipal`] through the request extensions so
+//! every mutating action is attributed in the logs.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use message::signaling::{
+  ModerationAction, ModerationNotification, RoomListUpdate, SignalingMessage,
+};
+use message::types::{DeviceId, RoomId, RoomRole, RoomTopology, RoomType, UserId, UserStatus};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{AdminError, AdminPrincipal};
+use crate::ws::{WebSocketState, encode_signaling_message};
+
+/// Connected user as reported by `GET /api/admin/users`.
+#[derive(Debug, Serialize)]
+pub struct AdminUser {
+  /// User ID.
+  pub user_id: UserId,
+  /// Login username.
+  pub username: String,
+  /// Display nickname.
+  pub nickname: String,
+  /// Presence status.
+  pub status: UserStatus,
+  /// Room the user is currently in, if any.
+  pub room_id: Option<RoomId>,
+  /// The user's connections, oldest first.
+  pub devices: Vec<AdminDevice>,
+}
+
+/// One connection of an [`AdminUser`].
+#[derive(Debug, Serialize)]
+pub struct AdminDevice {
+  /// Device ID announced by the client, if any.
+  pub device_id: Option<DeviceId>,
+  /// Readable device name.
+  pub name: String,
+  /// Remote socket address of the connection.
+  pub remote_addr: String,
+  /// Seconds since the WebSocket was opened.
+  pub connected_secs: u64,
+}
+
+/// Room member as reported inside [`AdminRoom`].
+#[derive(Debug, Serialize)]
+pub struct AdminRoomMember {
+  /// User ID.
+  pub user_id: UserId,
+  /// Nickname in the room.
+  pub nickname: String,
+  /// Role in the room.
+  pub role: RoomRole,
+}
+
+/// Room as reported by `GET /api/admin/rooms`.
+#[derive(Debug, Serialize)]
+pub struct AdminRoom {
+  /// Room ID.
+  pub room_id: RoomId,
+  /// Room name.
+  pub name: String,
+  /// Room description.
+  pub description: String,
+  /// Chat or theater.
+  pub room_type: RoomType,
+  /// Owner's user ID.
+  pub owner_id: UserId,
+  /// Whether joining requires a password.
+  pub has_password: bool,
+  /// Member capacity.
+  pub max_members: u8,
+  /// Mesh or SFU media routing.
+  pub topology: RoomTopology,
+  /// Creation timestamp (Unix nanoseconds).
+  pub created_at_nanos: i64,
+  /// Current members (empty for dormant rooms restored from storage).
+  pub members: Vec<AdminRoomMember>,
+}
+
+/// Pending invitation as reported by `GET /api/admin/invitations`.
+#[derive(Debug, Serialize)]
+pub struct AdminInvitation {
+  /// Invitation ID.
+  pub invitation_id: String,
+  /// Inviting user.
+  pub from: UserId,
+  /// Invited user.
+  pub to: UserId,
+  /// Optional note from the inviter.
+  pub note: Option<String>,
+  /// Room of a multi-user invitation, if any.
+  pub room_id: Option<RoomId>,
+  /// Seconds since the invitation was sent.
+  pub age_secs: u64,
+}
+
+/// Rename request payload for `PATCH /api/admin/rooms/{room_id}`.
+#[derive(Debug, Deserialize)]
+pub struct RenameRoomRequest {
+  /// New room name.
+  pub name: String,
+}
+
+/// Handle `GET /api/admin/users`.
+pub async fn list_users(State(ws_state): State<Arc<WebSocketState>>) -> Json<Vec<AdminUser>> {
+  let user_store = ws_state.user_store();
+  let room_state = ws_state.room_state();
+  let mut users: HashMap<UserId, AdminUser> = HashMap::new();
+  for conn in ws_state.connection_metadata() {
+    let Some(user_id) = conn.user_id else {
+      continue;
+    };
+    let user = match users.entry(user_id) {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        let Some(info) = user_store.get_user(entry.key()) else {
+          continue;
+        };
+        entry.insert(AdminUser {
+          room_id: room_state.get_user_room(&info.user_id),
+          user_id: info.user_id,
+          username: info.username,
+          nickname: info.nickname,
+          status: info.status,
+          devices: Vec::new(),
+        })
+      }
+    };
+    let name = conn
+      .device_id
+      .and_then(|device_id| {
+        ws_state
+          .devices(&user.user_id)
+          .into_iter()
+          .find(|device| device.device_id == device_id)
+      })
+      .map(|device| device.name)
+      .unwrap_or_default();
+    user.devices.push(AdminDevice {
+      device_id: conn.device_id,
+      name,
+      remote_addr: conn.remote_addr,
+      connected_secs: conn.connected_at.elapsed().as_secs(),
+    });
+  }
+  let mut users: Vec<AdminUser> = users.into_values().collect();
+  for user in &mut users {
+    user
+      .devices
+      .sort_by_key(|device| std::cmp::Reverse(device.connected_secs));
+  }
+  users.sort_by(|a, b| a.username.cmp(&b.username));
+  Json(users)
+}
+
+/// Handle `POST /api/admin/users/{user_id}/kick`.
+///
+/// Closes the user's WebSocket. The session stays valid, so the client
+/// may reconnect; use the logout endpoint to revoke it.
+///
+/// # Errors
+/// Returns 404 if the user has no live connection.
+pub async fn kick_user(
+  State(ws_state): State<Arc<WebSocketState>>,
+  Extension(actor): Extension<AdminPrincipal>,
+  Path(user_id): Path<String>,
+) -> Result<StatusCode, AdminError> {
+  let user_id = parse_user_id(&user_id)?;
+  if !ws_state.disconnect_user(&user_id) {
+    return Err(AdminError::NotFound("Connection".to_string()));
+  }
+  info!(actor = %actor, target = %user_id, "Admin disconnected user");
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle `POST /api/admin/users/{user_id}/logout`.
+///
+/// Invalidates the user's session through [`crate::auth::UserStore::logout`]
+/// and, if the user is online, tells the client and closes its socket.
+///
+/// # Errors
+/// Returns 404 if the user does not exist.
+pub async fn logout_user(
+  State(ws_state): State<Arc<WebSocketState>>,
+  Extension(actor): Extension<AdminPrincipal>,
+  Path(user_id): Path<String>,
+) -> Result<StatusCode, AdminError> {
+  let user_id = parse_user_id(&user_id)?;
+  if ws_state.user_store().get_user(&user_id).is_none() {
+    return Err(AdminError::NotFound("User".to_string()));
+  }
+  ws_state.user_store().logout(&user_id);
+  ws_state.end_session(&user_id).await;
+
+  info!(actor = %actor, target = %user_id, "Admin forced logout");
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle `GET /api/admin/rooms`.
+pub async fn list_rooms(State(ws_state): State<Arc<WebSocketState>>) -> Json<Vec<AdminRoom>> {
+  let room_state = ws_state.room_state();
+  let mut rooms: Vec<AdminRoom> = room_state
+    .get_all_rooms()
+    .into_iter()
+    .map(|info| {
+      let members = room_state
+        .get_room_members(&info.room_id)
+        .unwrap_or_default();
+      to_admin_room(info, members)
+    })
+    .collect();
+  rooms.sort_by_key(|room| room.created_at_nanos);
+  Json(rooms)
+}
+
+/// Handle `PATCH /api/admin/rooms/{room_id}`.
+///
+/// # Errors
+/// Returns 404 for an unknown room and 400 for an invalid name.
+pub async fn rename_room(
+  State(ws_state): State<Arc<WebSocketState>>,
+  Extension(actor): Extension<AdminPrincipal>,
+  Path(room_id): Path<String>,
+  Json(req): Json<RenameRoomRequest>,
+) -> Result<Json<AdminRoom>, AdminError> {
+  let room_id = parse_room_id(&room_id)?;
+  let room_state = ws_state.room_state();
+  let info = room_state.rename_room(&room_id, &req.name)?;
+  let members = room_state.get_room_members(&room_id).unwrap_or_default();
+
+  broadcast_room_list(&ws_state).await;
+
+  info!(actor = %actor, room_id = %room_id, "Admin renamed room");
+  Ok(Json(to_admin_room(info, members)))
+}
+
+/// Handle `DELETE /api/admin/rooms/{room_id}`.
+///
+/// Removes every member, notifies them as if they had been kicked and
+/// broadcasts the updated room list.
+///
+/// # Errors
+/// Returns 404 for an unknown room.
+pub async fn close_room(
+  State(ws_state): State<Arc<WebSocketState>>,
+  Extension(actor): Extension<AdminPrincipal>,
+  Path(room_id): Path<String>,
+) -> Result<StatusCode, AdminError> {
+  let room_id = parse_room_id(&room_id)?;
+  let members = ws_state.room_state().close_room(&room_id)?;
+
+  for member in &members {
+    if let Some(sfu) = ws_state.sfu() {
+      sfu.leave(&room_id, &member.user_id).await;
+    }
+    let notification = SignalingMessage::ModerationNotification(ModerationNotification {
+      room_id: room_id.clone(),
+      action: ModerationAction::Kicked,
+      target: member.user_id.clone(),
+      reason: Some("Room closed by administrator".to_string()),
+      duration_secs: None,
+    });
+    if let Ok(encoded) = encode_signaling_message(&notification) {
+      ws_state.send_to(&member.user_id, encoded).await;
+    }
+  }
+  broadcast_room_list(&ws_state).await;
+
+  info!(
+    actor = %actor,
+    room_id = %room_id,
+    member_count = members.len(),
+    "Admin closed room"
+  );
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle `GET /api/admin/invitations`.
+pub async fn list_invitations(
+  State(ws_state): State<Arc<WebSocketState>>,
+) -> Json<Vec<AdminInvitation>> {
+  let invitations = ws_state
+    .discovery_state()
+    .all_pending_invitations()
+    .into_iter()
+    .map(|inv| AdminInvitation {
+      invitation_id: inv.id.to_string(),
+      from: inv.from,
+      to: inv.to,
+      note: inv.note,
+      room_id: inv.room_id,
+      age_secs: inv.created_at.elapsed().as_secs(),
+    })
+    .collect();
+  Json(invitations)
+}
+
+fn to_admin_room(
+  info: message::types::RoomInfo,
+  members: Vec<message::types::MemberInfo>,
+) -> AdminRoom {
+  AdminRoom {
+    room_id: info.room_id,
+    name: info.name,
+    description: info.description,
+    room_type: info.room_type,
+    owner_id: info.owner_id,
+    has_password: info.password_hash.is_some(),
+    max_members: info.max_members,
+    topology: info.topology,
+    created_at_nanos: info.created_at_nanos,
+    members: members
+      .into_iter()
+      .map(|m| AdminRoomMember {
+        user_id: m.user_id,
+        nickname: m.nickname,
+        role: m.role,
+      })
+      .collect(),
+  }
+}
+
+async fn broadcast_room_list(ws_state: &WebSocketState) {
+  let rooms = ws_state.room_state().get_all_rooms();
+  let list_update = SignalingMessage::RoomListUpdate(RoomListUpdate { rooms });
+  if let Ok(encoded) = encode_signaling_message(&list_update) {
+    ws_state.broadcast(encoded).await;
+  }
+}
+
+fn parse_user_id(raw: &str) -> Result<UserId, AdminError> {
+  raw
+    .parse()
+    .map_err(|_| AdminError::BadRequest(format!("Invalid user ID '{raw}'")))
+}
+
+fn parse_room_id(raw: &str) -> Result<RoomId, AdminError> {
+  raw
+    .parse()
+    .map_err(|_| AdminError::BadRequest(format!("Invalid room ID '{raw}'")))
+}
@@ -0,0 +1,196 @@
+//! Operator REST API under `/api/admin/*`.
+//!
+//! Gives operators a way to inspect and act on live server state
+//! without a WebSocket client:
+//! - List and disconnect connected users
+//! - Force-logout a user's session
+//! - List, rename and close rooms
+//! - List pending connection invitations
+//!
+//! Every route is guarded by [`require_admin`], which accepts either
+//! the static [`Config::admin_token`](crate::config::Config::admin_token)
+//! or a login token belonging to one of
+//! [`Config::admin_usernames`](crate::config::Config::admin_usernames),
+//! presented as `Authorization: Bearer <token>`.
+
+use std::fmt;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use message::UserId;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::auth::handlers::bearer_token;
+use crate::room::RoomError;
+use crate::ws::WebSocketState;
+
+pub mod handlers;
+
+/// Build the admin router, to be nested under `/api/admin`.
+pub fn router(ws_state: Arc<WebSocketState>) -> Router<Arc<WebSocketState>> {
+  Router::new()
+    .route("/users", get(handlers::list_users))
+    .route("/users/{user_id}/kick", post(handlers::kick_user))
+    .route("/users/{user_id}/logout", post(handlers::logout_user))
+    .route("/rooms", get(handlers::list_rooms))
+    .route(
+      "/rooms/{room_id}",
+      axum::routing::patch(handlers::rename_room).delete(handlers::close_room),
+    )
+    .route("/invitations", get(handlers::list_invitations))
+    .route_layer(middleware::from_fn_with_state(ws_state, require_admin))
+}
+
+/// Identity that passed the admin guard, attached to the request
+/// extensions for audit logging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminPrincipal {
+  /// Authenticated with the static admin token.
+  StaticToken,
+  /// Authenticated with the login token of an admin user.
+  User {
+    /// Admin's user ID.
+    user_id: UserId,
+    /// Admin's username.
+    username: String,
+  },
+}
+
+impl fmt::Display for AdminPrincipal {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::StaticToken => write!(f, "admin-token"),
+      Self::User { username, .. } => write!(f, "user:{username}"),
+    }
+  }
+}
+
+/// Errors returned by the admin API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminError {
+  /// Missing, malformed or invalid credentials.
+  Unauthorized,
+  /// Valid credentials without the admin role.
+  Forbidden,
+  /// The addressed user, room or connection does not exist.
+  NotFound(String),
+  /// The request was rejected by validation.
+  BadRequest(String),
+}
+
+impl fmt::Display for AdminError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Unauthorized => write!(f, "Missing or invalid admin credentials"),
+      Self::Forbidden => write!(f, "Admin role required"),
+      Self::NotFound(what) => write!(f, "{what} not found"),
+      Self::BadRequest(msg) => write!(f, "{msg}"),
+    }
+  }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<RoomError> for AdminError {
+  fn from(e: RoomError) -> Self {
+    match e {
+      RoomError::RoomNotFound => Self::NotFound("Room".to_string()),
+      other => Self::BadRequest(other.to_string()),
+    }
+  }
+}
+
+/// Error response payload.
+#[derive(Debug, Serialize)]
+pub struct AdminErrorResponse {
+  /// Error message.
+  pub error: String,
+}
+
+impl IntoResponse for AdminError {
+  fn into_response(self) -> Response {
+    let status = match self {
+      Self::Unauthorized => StatusCode::UNAUTHORIZED,
+      Self::Forbidden => StatusCode::FORBIDDEN,
+      Self::NotFound(_) => StatusCode::NOT_FOUND,
+      Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+    };
+    let body = Json(AdminErrorResponse {
+      error: self.to_string(),
+    });
+    if status == StatusCode::UNAUTHORIZED {
+      (status, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response()
+    } else {
+      (status, body).into_response()
+    }
+  }
+}
+
+/// Middleware rejecting requests that do not carry admin credentials.
+///
+/// # Errors
+/// Returns 401 for missing or invalid credentials and 403 for a valid
+/// login token whose user is not an admin.
+pub async fn require_admin(
+  State(ws_state): State<Arc<WebSocketState>>,
+  mut req: Request,
+  next: Next,
+) -> Result<Response, AdminError> {
+  let principal = bearer_token(req.headers())
+    .ok_or(AdminError::Unauthorized)
+    .and_then(|token| authorize(&ws_state, token))
+    .inspect_err(|e| {
+      warn!(
+        path = %req.uri().path(),
+        error = %e,
+        "Rejected admin API request"
+      );
+    })?;
+  req.extensions_mut().insert(principal);
+  Ok(next.run(req).await)
+}
+
+/// Resolve `token` to an admin identity.
+///
+/// # Errors
+/// Returns [`AdminError::Unauthorized`] if the token is neither the
+/// static admin token nor a live login token, and
+/// [`AdminError::Forbidden`] if it belongs to a non-admin user.
+pub fn authorize(ws_state: &WebSocketState, token: &str) -> Result<AdminPrincipal, AdminError> {
+  let config = ws_state.config();
+  if let Some(expected) = &config.admin_token
+    && digest_eq(expected, token)
+  {
+    return Ok(AdminPrincipal::StaticToken);
+  }
+
+  // A token from a logged-out or superseded session must not keep
+  // admin access alive.
+  let (user_id, claims) = ws_state
+    .user_store()
+    .verify_session_token(token)
+    .map_err(|_| AdminError::Unauthorized)?;
+  if !config.admin_usernames.contains(&claims.username) {
+    return Err(AdminError::Forbidden);
+  }
+  Ok(AdminPrincipal::User {
+    user_id,
+    username: claims.username,
+  })
+}
+
+/// Compare two secrets via their SHA-256 digests so the comparison
+/// time does not depend on the length of the common prefix.
+fn digest_eq(a: &str, b: &str) -> bool {
+  Sha256::digest(a.as_bytes()) == Sha256::digest(b.as_bytes())
+}
+
+#[cfg(test)]
+mod tests;
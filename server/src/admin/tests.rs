@@ -0,0 +1,331 @@
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request};
+use http_body_util::BodyExt;
+use message::signaling::{ConnectionInvite, CreateRoom};
+use message::types::RoomType;
+use tower::ServiceExt;
+
+use super::*;
+use crate::config::Config;
+use crate::server::Server;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+fn admin_router() -> (Router, Arc<WebSocketState>) {
+  let config = Config {
+    admin_token: Some(ADMIN_TOKEN.to_string()),
+    admin_usernames: vec!["operator".to_string()],
+    ..Default::default()
+  };
+  Server::new(config).build_router()
+}
+
+async fn send(
+  router: &Router,
+  method: Method,
+  uri: &str,
+  token: Option<&str>,
+  body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+  let mut builder = Request::builder().method(method).uri(uri);
+  if let Some(token) = token {
+    builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+  }
+  let request = match body {
+    Some(json) => builder
+      .header(header::CONTENT_TYPE, "application/json")
+      .body(Body::from(json.to_string()))
+      .unwrap(),
+    None => builder.body(Body::empty()).unwrap(),
+  };
+  let response = router.clone().oneshot(request).await.unwrap();
+  let status = response.status();
+  let bytes = response.into_body().collect().await.unwrap().to_bytes();
+  let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+  (status, json)
+}
+
+fn create_room(ws_state: &WebSocketState, name: &str) -> message::types::RoomId {
+  let request = CreateRoom {
+    name: name.to_string(),
+    description: "desc".to_string(),
+    room_type: RoomType::Chat,
+    password: None,
+    max_participants: 8,
+  };
+  ws_state
+    .room_state()
+    .create_room(&request, UserId::new())
+    .unwrap()
+    .0
+}
+
+#[test]
+fn test_bearer_token_parsing() {
+  let mut headers = HeaderMap::new();
+  assert_eq!(bearer_token(&headers), None);
+  headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+  assert_eq!(bearer_token(&headers), Some("abc"));
+  headers.insert(header::AUTHORIZATION, "bearer  abc ".parse().unwrap());
+  assert_eq!(bearer_token(&headers), Some("abc"));
+  headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+  assert_eq!(bearer_token(&headers), None);
+  headers.insert(header::AUTHORIZATION, "Bearer ".parse().unwrap());
+  assert_eq!(bearer_token(&headers), None);
+}
+
+#[tokio::test]
+async fn test_admin_requires_credentials() {
+  let (router, _ws_state) = admin_router();
+
+  let (status, body) = send(&router, Method::GET, "/api/admin/users", None, None).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+  assert!(body["error"].is_string());
+
+  let (status, _) = send(
+    &router,
+    Method::GET,
+    "/api/admin/users",
+    Some("wrong-token"),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_disabled_without_configuration() {
+  let config = Config {
+    admin_token: None,
+    admin_usernames: vec![],
+    ..Default::default()
+  };
+  let (router, _ws_state) = Server::new(config).build_router();
+  let (status, _) = send(&router, Method::GET, "/api/admin/rooms", Some(""), None).await;
+  assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_static_token_lists_users() {
+  let (router, _ws_state) = admin_router();
+  let (status, body) = send(
+    &router,
+    Method::GET,
+    "/api/admin/users",
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_admin_lists_each_user_once_with_devices() {
+  let (router, ws_state) = admin_router();
+  let (user_id, _) = ws_state
+    .user_store()
+    .register("multi", "password123")
+    .unwrap();
+  for (addr, name) in [("10.0.0.1:1", "Laptop"), ("10.0.0.2:2", "Phone")] {
+    let device_id = message::DeviceId::new();
+    let mut conn = crate::ws::ConnectionState::new(addr.to_string());
+    conn.user_id = Some(user_id.clone());
+    conn.device_id = Some(device_id);
+    conn.sender = Some(tokio::sync::mpsc::channel(8).0);
+    ws_state.add_device(
+      &conn,
+      message::signaling::DeviceInfo {
+        device_id,
+        name: name.to_string(),
+      },
+    );
+  }
+
+  let (status, body) = send(
+    &router,
+    Method::GET,
+    "/api/admin/users",
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  let users = body.as_array().unwrap();
+  assert_eq!(users.len(), 1);
+  assert_eq!(users[0]["username"], "multi");
+  let mut names: Vec<_> = users[0]["devices"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|device| device["name"].as_str().unwrap())
+    .collect();
+  names.sort_unstable();
+  assert_eq!(names, ["Laptop", "Phone"]);
+}
+
+#[tokio::test]
+async fn test_admin_role_from_login_token() {
+  let (router, ws_state) = admin_router();
+  let (_, user_token) = ws_state
+    .user_store()
+    .register("regular", "password123")
+    .unwrap();
+  let (_, admin_token) = ws_state
+    .user_store()
+    .register("operator", "password123")
+    .unwrap();
+
+  let (status, _) = send(
+    &router,
+    Method::GET,
+    "/api/admin/rooms",
+    Some(&user_token),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::FORBIDDEN);
+
+  let (status, _) = send(
+    &router,
+    Method::GET,
+    "/api/admin/rooms",
+    Some(&admin_token),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_rename_and_close_room() {
+  let (router, ws_state) = admin_router();
+  let room_id = create_room(&ws_state, "Old Name");
+  let uri = format!("/api/admin/rooms/{room_id}");
+
+  let (status, body) = send(
+    &router,
+    Method::GET,
+    "/api/admin/rooms",
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body[0]["name"], "Old Name");
+  assert_eq!(body[0]["members"].as_array().unwrap().len(), 1);
+  assert_eq!(body[0]["has_password"], false);
+
+  let (status, body) = send(
+    &router,
+    Method::PATCH,
+    &uri,
+    Some(ADMIN_TOKEN),
+    Some(serde_json::json!({ "name": "New Name" })),
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body["name"], "New Name");
+  assert_eq!(body["description"], "desc");
+
+  let (status, _) = send(
+    &router,
+    Method::PATCH,
+    &uri,
+    Some(ADMIN_TOKEN),
+    Some(serde_json::json!({ "name": " bad name" })),
+  )
+  .await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+
+  let (status, _) = send(&router, Method::DELETE, &uri, Some(ADMIN_TOKEN), None).await;
+  assert_eq!(status, StatusCode::NO_CONTENT);
+  assert_eq!(ws_state.room_state().room_count(), 0);
+  assert_eq!(ws_state.room_state().total_member_count(), 0);
+
+  let (status, _) = send(&router, Method::DELETE, &uri, Some(ADMIN_TOKEN), None).await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_force_logout_revokes_session() {
+  let (router, ws_state) = admin_router();
+  let (user_id, token) = ws_state
+    .user_store()
+    .register("operator", "password123")
+    .unwrap();
+  assert!(authorize(&ws_state, &token).is_ok());
+
+  let (status, _) = send(
+    &router,
+    Method::POST,
+    &format!("/api/admin/users/{user_id}/logout"),
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::NO_CONTENT);
+  assert_eq!(authorize(&ws_state, &token), Err(AdminError::Unauthorized));
+
+  let (status, _) = send(
+    &router,
+    Method::POST,
+    &format!("/api/admin/users/{}/logout", UserId::new()),
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_kick_requires_live_connection() {
+  let (router, _ws_state) = admin_router();
+  let (status, _) = send(
+    &router,
+    Method::POST,
+    &format!("/api/admin/users/{}/kick", UserId::new()),
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+
+  let (status, _) = send(
+    &router,
+    Method::POST,
+    "/api/admin/users/not-a-uuid/kick",
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_admin_lists_pending_invitations() {
+  let (router, ws_state) = admin_router();
+  let (from, to) = (UserId::new(), UserId::new());
+  ws_state
+    .discovery_state()
+    .send_invitation(&ConnectionInvite {
+      from: from.clone(),
+      to: to.clone(),
+      note: Some("hi".to_string()),
+    })
+    .unwrap();
+
+  let (status, body) = send(
+    &router,
+    Method::GET,
+    "/api/admin/invitations",
+    Some(ADMIN_TOKEN),
+    None,
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body.as_array().unwrap().len(), 1);
+  assert_eq!(body[0]["from"], from.to_string());
+  assert_eq!(body[0]["to"], to.to_string());
+  assert_eq!(body[0]["note"], "hi");
+}
@@ -1,13 +1,24 @@
 //! HTTP authentication handlers.
 //!
-//! Provides REST API endpoints for user registration and login.
-//! These endpoints return JWT tokens that clients use for WebSocket
-//! authentication via the `TokenAuth` signaling message.
+//! Provides REST API endpoints for user registration, login, token
+//! refresh, password changes and account deletion. Register, login and
+//! refresh return a short-lived JWT access token, which clients use for
+//! WebSocket authentication via the `TokenAuth` signaling message, and a
+//! single-use refresh token for renewing it.
+//!
+//! The account endpoints (`/api/password`, `/api/account`) authenticate
+//! with `Authorization: Bearer <access token>`.
+
+use std::sync::Arc;
 
 use axum::Json;
 use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use message::UserId;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
+use super::{RefreshError, TokenPair};
 use crate::ws::WebSocketState;
 
 /// Registration request payload.
@@ -28,13 +39,52 @@ pub struct LoginRequest {
   pub password: String,
 }
 
-/// Auth response payload (used for both register and login).
+/// Refresh request payload.
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+  /// Refresh token from the previous register, login or refresh.
+  pub refresh_token: String,
+}
+
+/// Password change request payload.
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+  /// Current password.
+  pub current_password: String,
+  /// New password (minimum 8 characters).
+  pub new_password: String,
+}
+
+/// Account deletion request payload.
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+  /// Account password, as confirmation.
+  pub password: String,
+}
+
+/// Auth response payload (used for register, login, refresh and
+/// password changes).
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
   /// Assigned or existing user ID.
   pub user_id: String,
-  /// JWT token for WebSocket authentication.
+  /// JWT access token for WebSocket authentication.
   pub token: String,
+  /// Single-use token for `POST /api/refresh`.
+  pub refresh_token: String,
+  /// Lifetime of `token` in seconds.
+  pub expires_in: u64,
+}
+
+impl AuthResponse {
+  fn new(user_id: &UserId, tokens: TokenPair) -> Self {
+    Self {
+      user_id: user_id.to_string(),
+      token: tokens.access_token,
+      refresh_token: tokens.refresh_token,
+      expires_in: tokens.expires_in,
+    }
+  }
 }
 
 /// Error response payload.
@@ -44,26 +94,21 @@ pub struct AuthErrorResponse {
   pub error: String,
 }
 
+/// Error returned by the auth handlers.
+type AuthError = (StatusCode, Json<AuthErrorResponse>);
+
 /// Handle user registration.
 ///
 /// # Errors
 /// Returns 400 if the username is taken or input is invalid.
 pub async fn register(
-  State(ws_state): State<std::sync::Arc<WebSocketState>>,
+  State(ws_state): State<Arc<WebSocketState>>,
   Json(req): Json<RegisterRequest>,
-) -> Result<Json<AuthResponse>, (axum::http::StatusCode, Json<AuthErrorResponse>)> {
+) -> Result<Json<AuthResponse>, AuthError> {
   let user_store = ws_state.user_store();
-  match user_store.register(&req.username, &req.password) {
-    Ok((user_id, token)) => Ok(Json(AuthResponse {
-      user_id: user_id.to_string(),
-      token,
-    })),
-    Err(e) => Err((
-      axum::http::StatusCode::BAD_REQUEST,
-      Json(AuthErrorResponse {
-        error: e.to_string(),
-      }),
-    )),
+  match user_store.register_session(&req.username, &req.password) {
+    Ok((user_id, tokens)) => Ok(Json(AuthResponse::new(&user_id, tokens))),
+    Err(e) => Err(auth_error(StatusCode::BAD_REQUEST, e)),
   }
 }
 
@@ -72,20 +117,121 @@ pub async fn register(
 /// # Errors
 /// Returns 401 if credentials are invalid.
 pub async fn login(
-  State(ws_state): State<std::sync::Arc<WebSocketState>>,
+  State(ws_state): State<Arc<WebSocketState>>,
   Json(req): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (axum::http::StatusCode, Json<AuthErrorResponse>)> {
+) -> Result<Json<AuthResponse>, AuthError> {
   let user_store = ws_state.user_store();
-  match user_store.login(&req.username, &req.password) {
-    Ok((user_id, token)) => Ok(Json(AuthResponse {
-      user_id: user_id.to_string(),
-      token,
-    })),
-    Err(e) => Err((
-      axum::http::StatusCode::UNAUTHORIZED,
-      Json(AuthErrorResponse {
-        error: e.to_string(),
-      }),
-    )),
+  match user_store.login_session(&req.username, &req.password) {
+    Ok((user_id, tokens)) => Ok(Json(AuthResponse::new(&user_id, tokens))),
+    Err(e) => Err(auth_error(StatusCode::UNAUTHORIZED, e)),
+  }
+}
+
+/// Handle `POST /api/refresh`.
+///
+/// Rotates the refresh token and issues a new access token. On reuse
+/// of a rotated-out token the session is revoked and the devices
+/// connected with it are closed.
+///
+/// # Errors
+/// Returns 401 if the refresh token is invalid, expired or reused.
+pub async fn refresh(
+  State(ws_state): State<Arc<WebSocketState>>,
+  Json(req): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, AuthError> {
+  match ws_state.user_store().refresh(&req.refresh_token) {
+    Ok((user_id, tokens)) => Ok(Json(AuthResponse::new(&user_id, tokens))),
+    Err(e) => {
+      let status = match &e {
+        RefreshError::Reused {
+          user_id,
+          session_id,
+        } => {
+          ws_state.end_auth_session(user_id, session_id).await;
+          StatusCode::UNAUTHORIZED
+        }
+        RefreshError::Invalid | RefreshError::Expired => StatusCode::UNAUTHORIZED,
+        RefreshError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+      };
+      Err(auth_error(status, e))
+    }
   }
 }
+
+/// Handle `POST /api/password`.
+///
+/// Every existing session of the user is revoked, including the live
+/// WebSocket, and the response carries tokens for a new session.
+///
+/// # Errors
+/// Returns 401 without a valid access token and 400 if the current
+/// password is wrong or the new one is rejected.
+pub async fn change_password(
+  State(ws_state): State<Arc<WebSocketState>>,
+  headers: HeaderMap,
+  Json(req): Json<ChangePasswordRequest>,
+) -> Result<Json<AuthResponse>, AuthError> {
+  let user_id = authenticate(&ws_state, &headers)?;
+  let tokens = ws_state
+    .user_store()
+    .change_password(&user_id, &req.current_password, &req.new_password)
+    .map_err(|e| auth_error(StatusCode::BAD_REQUEST, e))?;
+  ws_state.end_session(&user_id).await;
+  Ok(Json(AuthResponse::new(&user_id, tokens)))
+}
+
+/// Handle `DELETE /api/account`.
+///
+/// Deletes the account and closes the user's live WebSocket, whose
+/// regular disconnect cleanup removes them from any room.
+///
+/// # Errors
+/// Returns 401 without a valid access token and 400 if the password
+/// is wrong.
+pub async fn delete_account(
+  State(ws_state): State<Arc<WebSocketState>>,
+  headers: HeaderMap,
+  Json(req): Json<DeleteAccountRequest>,
+) -> Result<StatusCode, AuthError> {
+  let user_id = authenticate(&ws_state, &headers)?;
+  ws_state
+    .user_store()
+    .delete_account(&user_id, &req.password)
+    .map_err(|e| auth_error(StatusCode::BAD_REQUEST, e))?;
+  if let Some(mailbox) = ws_state.mailbox() {
+    mailbox.forget_user(&user_id);
+  }
+  ws_state.end_session(&user_id).await;
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Extract the token from an `Authorization: Bearer` header.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+  let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+  let (scheme, token) = value.split_once(' ')?;
+  let token = token.trim();
+  (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Resolve the bearer access token of an account request to its user.
+fn authenticate(ws_state: &WebSocketState, headers: &HeaderMap) -> Result<UserId, AuthError> {
+  let token = bearer_token(headers)
+    .ok_or_else(|| auth_error(StatusCode::UNAUTHORIZED, "Missing bearer token"))?;
+  ws_state
+    .user_store()
+    .verify_session_token(token)
+    .map(|(user_id, _)| user_id)
+    .map_err(|e| {
+      warn!(error = %e, "Rejected account request");
+      auth_error(StatusCode::UNAUTHORIZED, e)
+    })
+}
+
+fn auth_error(status: StatusCode, error: impl std::fmt::Display) -> AuthError {
+  (
+    status,
+    Json(AuthErrorResponse {
+      error: error.to_string(),
+    }),
+  )
+}
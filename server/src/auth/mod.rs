@@ -3,8 +3,12 @@
 //! This module provides:
 //! - User registration and login with Argon2 password hashing
 //! - JWT token generation and verification
-//! - Session management with single-device login policy
+//! - Session management with one session per logged-in device
+//! - Short-lived access tokens renewed through rotating refresh tokens
+//! - Password changes and account deletion
 //! - User status tracking (online/offline/busy/away)
+//! - Write-through persistence of accounts via [`crate::storage`]
+//! - Replication of accounts between cluster replicas (`replication`)
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,18 +21,31 @@ use argon2::{
 };
 use base64::Engine;
 use chrono::{DateTime, Utc};
-use dashmap::DashMap;
+use dashmap::{DashMap, mapref::entry::Entry};
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
 
-use message::UserId;
 use message::error::validation::validate_username;
 use message::signaling::{AuthFailure, AuthSuccess, UserStatusChange};
 use message::types::{UserInfo, UserStatus};
+use message::{DeviceId, UserId};
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::storage::{MemoryStorage, Storage, StoredSession, StoredUser};
+
+mod replication;
+
+pub use replication::AccountChange;
+
+/// Minimum accepted password length.
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Maximum number of concurrent sessions (logged-in devices) per user.
+/// A login beyond it ends the least recently used session.
+pub const MAX_SESSIONS_PER_USER: usize = 10;
 
 /// JWT claims structure.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,10 +58,95 @@ pub struct Claims {
   pub iat: i64,
   /// Expiration time
   pub exp: i64,
-  /// Session ID (for single-device login)
+  /// Session ID, one per logged-in device
   pub sid: String,
 }
 
+/// Credentials handed out by register, login and refresh.
+#[derive(Debug, Clone)]
+pub struct TokenPair {
+  /// Short-lived JWT access token.
+  pub access_token: String,
+  /// Opaque refresh token, valid for a single use.
+  pub refresh_token: String,
+  /// Lifetime of `access_token` in seconds.
+  pub expires_in: u64,
+}
+
+/// Reasons a refresh token is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshError {
+  /// Malformed, unknown, or issued for a session that has ended.
+  Invalid,
+  /// The refresh token is past its expiry.
+  Expired,
+  /// A refresh token that was already rotated out was presented
+  /// again. The whole token family (the session) has been revoked.
+  Reused {
+    /// Owner of the revoked session.
+    user_id: UserId,
+    /// The revoked session.
+    session_id: String,
+  },
+  /// Signing the new access token failed.
+  Internal(String),
+}
+
+impl std::fmt::Display for RefreshError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Invalid => write!(f, "Invalid refresh token"),
+      Self::Expired => write!(f, "Refresh token expired"),
+      Self::Reused { .. } => write!(f, "Refresh token reuse detected; session revoked"),
+      Self::Internal(msg) => write!(f, "Failed to refresh session: {msg}"),
+    }
+  }
+}
+
+impl std::error::Error for RefreshError {}
+
+/// One logged-in device of a user: a refresh-token family.
+#[derive(Debug, Clone)]
+pub struct AuthSession {
+  /// Session ID, carried as `sid` in access tokens.
+  pub session_id: String,
+  /// SHA-256 of the session's current refresh token. Earlier tokens of
+  /// the same session no longer match, which is how reuse is detected.
+  pub refresh_token_hash: String,
+  /// Expiry of the current refresh token.
+  pub refresh_expires_at: Option<DateTime<Utc>>,
+  /// Last login, refresh or WebSocket authentication.
+  pub last_used: DateTime<Utc>,
+}
+
+impl AuthSession {
+  /// Convert to the durable storage record.
+  #[must_use]
+  pub fn to_stored(&self) -> StoredSession {
+    StoredSession {
+      session_id: self.session_id.clone(),
+      refresh_token_hash: self.refresh_token_hash.clone(),
+      refresh_expires_at_nanos: self
+        .refresh_expires_at
+        .and_then(|at| at.timestamp_nanos_opt()),
+      last_used_nanos: self.last_used.timestamp_nanos_opt().unwrap_or(0),
+    }
+  }
+
+  /// Rebuild a session from its storage record.
+  #[must_use]
+  pub fn from_stored(stored: StoredSession) -> Self {
+    Self {
+      session_id: stored.session_id,
+      refresh_token_hash: stored.refresh_token_hash,
+      refresh_expires_at: stored
+        .refresh_expires_at_nanos
+        .map(DateTime::from_timestamp_nanos),
+      last_used: DateTime::from_timestamp_nanos(stored.last_used_nanos),
+    }
+  }
+}
+
 /// User session data.
 #[derive(Debug, Clone)]
 pub struct UserSession {
@@ -56,8 +158,8 @@ pub struct UserSession {
   pub nickname: String,
   /// Hashed password
   pub password_hash: String,
-  /// Current session ID (for single-device login)
-  pub session_id: Option<String>,
+  /// Open sessions, one per logged-in device, oldest first.
+  pub sessions: Vec<AuthSession>,
   /// User status
   pub status: UserStatus,
   /// User bio
@@ -81,7 +183,7 @@ impl UserSession {
       username: username.clone(),
       nickname: username,
       password_hash,
-      session_id: None,
+      sessions: Vec::new(),
       status: UserStatus::Online,
       bio: String::new(),
       avatar_url: None,
@@ -104,9 +206,74 @@ impl UserSession {
       last_seen_nanos: self.last_seen.timestamp_nanos_opt().unwrap_or(0),
     }
   }
+
+  /// Convert to the durable storage record.
+  #[must_use]
+  pub fn to_stored(&self) -> StoredUser {
+    StoredUser {
+      user_id: self.user_id.clone(),
+      username: self.username.clone(),
+      nickname: self.nickname.clone(),
+      password_hash: self.password_hash.clone(),
+      sessions: self.sessions.iter().map(AuthSession::to_stored).collect(),
+      bio: self.bio.clone(),
+      avatar_url: self.avatar_url.clone(),
+      created_at_nanos: self.created_at.timestamp_nanos_opt().unwrap_or(0),
+      last_seen_nanos: self.last_seen.timestamp_nanos_opt().unwrap_or(0),
+    }
+  }
+
+  /// Rebuild a session from its storage record. Restored users start
+  /// `Offline` until their client re-authenticates.
+  #[must_use]
+  pub fn from_stored(stored: StoredUser) -> Self {
+    let sessions = stored
+      .sessions
+      .into_iter()
+      .map(AuthSession::from_stored)
+      .collect();
+    Self {
+      user_id: stored.user_id,
+      username: stored.username,
+      nickname: stored.nickname,
+      password_hash: stored.password_hash,
+      sessions,
+      status: UserStatus::Offline,
+      bio: stored.bio,
+      avatar_url: stored.avatar_url,
+      created_at: DateTime::from_timestamp_nanos(stored.created_at_nanos),
+      last_seen: DateTime::from_timestamp_nanos(stored.last_seen_nanos),
+    }
+  }
+
+  /// The open session with ID `session_id`.
+  fn session_mut(&mut self, session_id: &str) -> Option<&mut AuthSession> {
+    self
+      .sessions
+      .iter_mut()
+      .find(|session| session.session_id == session_id)
+  }
+
+  /// Storage record of the open session with ID `session_id`.
+  fn stored_session(&self, session_id: &str) -> Option<StoredSession> {
+    self
+      .sessions
+      .iter()
+      .find(|session| session.session_id == session_id)
+      .map(AuthSession::to_stored)
+  }
+
+  /// Whether `session_id` is one of the open sessions.
+  #[must_use]
+  pub fn has_session(&self, session_id: &str) -> bool {
+    self
+      .sessions
+      .iter()
+      .any(|session| session.session_id == session_id)
+  }
 }
 
-/// In-memory user store.
+/// User store with in-memory indexes and write-through persistence.
 #[derive(Clone)]
 pub struct UserStore {
   /// Users indexed by user ID
@@ -117,8 +284,14 @@ pub struct UserStore {
   encoding_key: EncodingKey,
   /// JWT decoding key (derived from secret)
   decoding_key: DecodingKey,
-  /// Token expiration duration
-  token_expiration: Duration,
+  /// Access token lifetime
+  access_token_ttl: Duration,
+  /// Refresh token lifetime
+  refresh_token_ttl: Duration,
+  /// Durable backing store for accounts.
+  storage: Arc<dyn Storage>,
+  /// Change tracking for cluster replication; `None` on a single node.
+  replication: Option<Arc<replication::Replication>>,
 }
 
 impl std::fmt::Debug for UserStore {
@@ -126,74 +299,88 @@ impl std::fmt::Debug for UserStore {
     f.debug_struct("UserStore")
       .field("users_count", &self.users.len())
       .field("username_index_count", &self.username_index.len())
-      .field("token_expiration", &self.token_expiration)
+      .field("access_token_ttl", &self.access_token_ttl)
+      .field("refresh_token_ttl", &self.refresh_token_ttl)
       .finish_non_exhaustive()
   }
 }
 
 impl UserStore {
-  /// Create a new user store.
+  /// Create a new user store backed by volatile memory storage.
   pub fn new(config: &Config) -> Self {
+    Self::with_storage(config, Arc::new(MemoryStorage::new()))
+  }
+
+  /// Create a user store on top of `storage`, restoring every
+  /// account it already holds.
+  pub fn with_storage(config: &Config, storage: Arc<dyn Storage>) -> Self {
     let jwt_secret = config.jwt_secret.clone();
     let encoding_key = EncodingKey::from_secret(jwt_secret.as_bytes());
     let decoding_key = DecodingKey::from_secret(jwt_secret.as_bytes());
 
+    let users = DashMap::new();
+    let username_index = DashMap::new();
+    for stored in storage.users() {
+      username_index.insert(stored.username.clone(), stored.user_id.clone());
+      users.insert(stored.user_id.clone(), UserSession::from_stored(stored));
+    }
+    if !users.is_empty() {
+      info!(count = users.len(), "Restored users from storage");
+    }
+
     Self {
-      users: Arc::new(DashMap::new()),
-      username_index: Arc::new(DashMap::new()),
+      users: Arc::new(users),
+      username_index: Arc::new(username_index),
       encoding_key,
       decoding_key,
-      token_expiration: Duration::from_secs(7 * 24 * 60 * 60), // 7 days
+      access_token_ttl: config.access_token_ttl,
+      refresh_token_ttl: config.refresh_token_ttl,
+      storage,
+      replication: None,
     }
   }
 
   /// Register a new user.
   ///
+  /// Returns the access token only; see [`Self::register_session`]
+  /// for the matching refresh token.
+  ///
   /// # Errors
   /// Returns an error if the username already exists or password hashing fails.
   pub fn register(&self, username: &str, password: &str) -> Result<(UserId, String)> {
+    self
+      .register_session(username, password)
+      .map(|(user_id, tokens)| (user_id, tokens.access_token))
+  }
+
+  /// Register a new user and open a session with a refresh token.
+  ///
+  /// # Errors
+  /// Returns an error if the username already exists or password hashing fails.
+  pub fn register_session(&self, username: &str, password: &str) -> Result<(UserId, TokenPair)> {
     // Validate username using shared validation from message crate
     validate_username(username).map_err(|e| anyhow!("{}", e.message))?;
 
-    // Check if username exists
-    if self.username_index.contains_key(username) {
-      return Err(anyhow!("Username already exists"));
-    }
-
-    // Validate password
-    if password.len() < 8 {
-      return Err(anyhow!("Password must be at least 8 characters"));
-    }
-
-    // Hash password with Argon2 (parameters per requirements: memory=64MB, time=3, parallelism=4, output=32)
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::new(
-      argon2::Algorithm::Argon2id,
-      argon2::Version::V0x13,
-      argon2::Params::new(65536, 3, 4, Some(32)).expect("valid Argon2 params"),
-    );
-    let password_hash = argon2
-      .hash_password(password.as_bytes(), &salt)
-      .map_err(|e| anyhow!("Failed to hash password: {}", e))?
-      .to_string();
+    validate_password(password)?;
 
-    // Create user
+    // Reserve the name before anything is persisted so two concurrent
+    // registrations cannot both write an account with it.
     let user_id = UserId::new();
-    let mut session =
-      UserSession::new(user_id.clone(), username.to_string(), password_hash.clone());
-
-    // Generate session ID and store it for single-device login enforcement
-    let session_id = generate_session_id();
-    session.session_id = Some(session_id.clone());
+    match self.username_index.entry(username.to_string()) {
+      Entry::Occupied(_) => return Err(anyhow!("Username already exists")),
+      Entry::Vacant(entry) => {
+        entry.insert(user_id.clone());
+      }
+    }
 
-    // Store user
-    self.users.insert(user_id.clone(), session);
-    self
-      .username_index
-      .insert(username.to_string(), user_id.clone());
+    let created = self.create_account(&user_id, username, password);
+    if created.is_err() {
+      self.username_index.remove(username);
+    }
+    let (session_id, refresh_token) = created?;
 
     // Generate JWT token with session ID
-    let token = self.generate_token_with_session(&user_id, username, &session_id)?;
+    let tokens = self.token_pair(&user_id, username, &session_id, refresh_token)?;
 
     info!(
       user_id = %user_id,
@@ -201,14 +388,60 @@ impl UserStore {
       "User registered successfully"
     );
 
-    Ok((user_id, token))
+    Ok((user_id, tokens))
+  }
+
+  /// Create and persist the account for a reserved username, returning
+  /// its first session id and refresh token.
+  fn create_account(
+    &self,
+    user_id: &UserId,
+    username: &str,
+    password: &str,
+  ) -> Result<(String, String)> {
+    let password_hash = hash_password(password)?;
+    let mut session = UserSession::new(user_id.clone(), username.to_string(), password_hash);
+
+    // Open the first session
+    let session_id = generate_session_id();
+    let refresh_token = self.open_session(&mut session, &session_id);
+
+    // Persist before publishing so a failed write never leaves an
+    // account that would vanish on the next restart.
+    let stored = session.to_stored();
+    self
+      .storage
+      .put_user(&stored)
+      .map_err(|e| anyhow!("Failed to persist user: {}", e))?;
+
+    // Record the account while holding its entry, so it is published
+    // ahead of a login that races the registration.
+    let _user = self.users.entry(user_id.clone()).insert(session);
+    self.changed(AccountChange::Created(stored));
+    Ok((session_id, refresh_token))
   }
 
   /// Login a user.
   ///
+  /// Returns the access token only; see [`Self::login_session`] for
+  /// the matching refresh token.
+  ///
   /// # Errors
   /// Returns an error if credentials are invalid.
   pub fn login(&self, username: &str, password: &str) -> Result<(UserId, String)> {
+    self
+      .login_session(username, password)
+      .map(|(user_id, tokens)| (user_id, tokens.access_token))
+  }
+
+  /// Login a user and open a new session with a refresh token.
+  ///
+  /// Sessions of the user's other devices stay valid. Beyond
+  /// [`MAX_SESSIONS_PER_USER`] the least recently used one is ended.
+  ///
+  /// # Errors
+  /// Returns an error if credentials are invalid.
+  pub fn login_session(&self, username: &str, password: &str) -> Result<(UserId, TokenPair)> {
     // Find user by username
     let user_id = self
       .username_index
@@ -216,43 +449,187 @@ impl UserStore {
       .map(|u| u.clone())
       .ok_or_else(|| anyhow!("Invalid credentials"))?;
 
-    // Verify password
-    let session = self
-      .users
-      .get(&user_id)
-      .ok_or_else(|| anyhow!("User not found"))?;
+    self.check_password(&user_id, password)?;
 
-    let parsed_hash = PasswordHash::new(&session.password_hash)
-      .map_err(|e| anyhow!("Invalid password hash: {}", e))?;
+    // Open a session for the new device
+    let session_id = generate_session_id();
+    let refresh_token = {
+      let mut session = self
+        .users
+        .get_mut(&user_id)
+        .ok_or_else(|| anyhow!("User not found"))?;
+      let refresh_token = self.open_session(&mut session, &session_id);
+      session.status = UserStatus::Online;
+      session.last_seen = Utc::now();
+      self.persist(&session);
+      self.session_changed(&session, &session_id);
+      refresh_token
+    };
+    let tokens = self.token_pair(&user_id, username, &session_id, refresh_token)?;
 
-    let argon2 = Argon2::new(
-      argon2::Algorithm::Argon2id,
-      argon2::Version::V0x13,
-      argon2::Params::new(65536, 3, 4, Some(32)).expect("valid Argon2 params"),
+    info!(
+      user_id = %user_id,
+      username = %username,
+      "User logged in successfully"
     );
-    argon2
-      .verify_password(password.as_bytes(), &parsed_hash)
-      .map_err(|_| anyhow!("Invalid credentials"))?;
 
-    // Generate new session ID and token
-    let session_id = generate_session_id();
-    let token = self.generate_token_with_session(&user_id, username, &session_id)?;
+    Ok((user_id, tokens))
+  }
 
-    // Update session (single-device login: invalidate old session)
+  /// Exchange a refresh token for a new access token.
+  ///
+  /// The presented token is rotated: the response carries a new
+  /// refresh token and the old one stops working. Presenting a token
+  /// that was already rotated out is treated as theft and revokes the
+  /// session, so both the attacker and the legitimate client have to
+  /// log in again. The user's other devices are not affected.
+  ///
+  /// # Errors
+  /// Returns a [`RefreshError`] describing why the token was rejected.
+  pub fn refresh(&self, refresh_token: &str) -> Result<(UserId, TokenPair), RefreshError> {
+    let (user_id, session_id) = parse_refresh_token(refresh_token).ok_or(RefreshError::Invalid)?;
+    let mut session = self.users.get_mut(&user_id).ok_or(RefreshError::Invalid)?;
+    let Some(auth_session) = session.session_mut(&session_id) else {
+      return Err(RefreshError::Invalid);
+    };
+
+    if auth_session.refresh_token_hash != hash_refresh_token(refresh_token) {
+      session.sessions.retain(|s| s.session_id != session_id);
+      if session.sessions.is_empty() {
+        session.status = UserStatus::Offline;
+      }
+      self.persist(&session);
+      self.changed(AccountChange::SessionEnded {
+        user_id: user_id.clone(),
+        session_id: session_id.clone(),
+      });
+      warn!(
+        user_id = %user_id,
+        username = %session.username,
+        "Refresh token reuse detected, session revoked"
+      );
+      return Err(RefreshError::Reused {
+        user_id,
+        session_id,
+      });
+    }
+    if auth_session
+      .refresh_expires_at
+      .is_none_or(|expires_at| expires_at <= Utc::now())
+    {
+      return Err(RefreshError::Expired);
+    }
+
+    let new_refresh_token = self.rotate_refresh_token(auth_session, &user_id, &session_id);
+    session.last_seen = Utc::now();
+    self.persist(&session);
+    self.session_changed(&session, &session_id);
+    let username = session.username.clone();
     drop(session);
-    if let Some(mut session) = self.users.get_mut(&user_id) {
-      session.session_id = Some(session_id);
-      session.status = UserStatus::Online;
-      session.last_seen = Utc::now();
+
+    let tokens = self
+      .token_pair(&user_id, &username, &session_id, new_refresh_token)
+      .map_err(|e| RefreshError::Internal(e.to_string()))?;
+    debug!(user_id = %user_id, "Session refreshed");
+    Ok((user_id, tokens))
+  }
+
+  /// Change a user's password.
+  ///
+  /// Requires the current password. On success every existing session
+  /// and refresh token is invalidated and a fresh session is returned.
+  ///
+  /// # Errors
+  /// Returns an error if the current password is wrong or the new one
+  /// is rejected by the password policy.
+  pub fn change_password(
+    &self,
+    user_id: &UserId,
+    current_password: &str,
+    new_password: &str,
+  ) -> Result<TokenPair> {
+    self.check_password(user_id, current_password)?;
+    validate_password(new_password)?;
+    if current_password == new_password {
+      return Err(anyhow!(
+        "New password must differ from the current password"
+      ));
     }
+    let password_hash = hash_password(new_password)?;
+
+    let session_id = generate_session_id();
+    let (username, refresh_token) = {
+      let mut session = self
+        .users
+        .get_mut(user_id)
+        .ok_or_else(|| anyhow!("User not found"))?;
+      session.password_hash = password_hash;
+      session.sessions.clear();
+      let refresh_token = self.open_session(&mut session, &session_id);
+      // Unlike profile tweaks, a password change that is not durable
+      // would silently revert on restart, so the write must succeed.
+      self
+        .storage
+        .put_user(&session.to_stored())
+        .map_err(|e| anyhow!("Failed to persist user: {}", e))?;
+      if let Some(stored) = session.stored_session(&session_id) {
+        self.changed(AccountChange::Password {
+          user_id: user_id.clone(),
+          password_hash: session.password_hash.clone(),
+          session: stored,
+        });
+      }
+      (session.username.clone(), refresh_token)
+    };
+
+    info!(user_id = %user_id, username = %username, "Password changed");
+    self.token_pair(user_id, &username, &session_id, refresh_token)
+  }
+
+  /// Permanently delete a user's account.
+  ///
+  /// Requires the account password as confirmation.
+  ///
+  /// # Errors
+  /// Returns an error if the password is wrong or the deletion could
+  /// not be persisted.
+  pub fn delete_account(&self, user_id: &UserId, password: &str) -> Result<()> {
+    self.check_password(user_id, password)?;
+
+    // Hold the entry across the storage delete so a concurrent profile
+    // or session write cannot land after it and resurrect the account.
+    let Entry::Occupied(entry) = self.users.entry(user_id.clone()) else {
+      return Err(anyhow!("User not found"));
+    };
+    self
+      .storage
+      .delete_user(user_id)
+      .map_err(|e| anyhow!("Failed to delete user: {}", e))?;
+    self.changed(AccountChange::Deleted(user_id.clone()));
+    let (_, session) = entry.remove_entry();
 
+    self.username_index.remove(&session.username);
     info!(
       user_id = %user_id,
-      username = %username,
-      "User logged in successfully"
+      username = %session.username,
+      "Account deleted"
     );
+    Ok(())
+  }
 
-    Ok((user_id, token))
+  /// Verify an access token and check that its session is still open.
+  ///
+  /// # Errors
+  /// Returns an error if the token is invalid, expired, or belongs to
+  /// a session that has since been logged out or revoked.
+  pub fn verify_session_token(&self, token: &str) -> Result<(UserId, Claims)> {
+    let claims = self.verify_token(token)?;
+    let uuid = Uuid::parse_str(&claims.sub).map_err(|_| anyhow!("Invalid token subject"))?;
+    let user_id = UserId::from_uuid(uuid);
+    if !self.is_session_valid(&user_id, &claims.sid) {
+      return Err(anyhow!("Session is no longer valid"));
+    }
+    Ok((user_id, claims))
   }
 
   /// Verify JWT token and return user info.
@@ -273,8 +650,22 @@ impl UserStore {
   /// Authenticate with token (for WebSocket connection).
   ///
   /// Returns `AuthSuccess` if valid, or `AuthFailure` if invalid.
-  /// Also handles single-device login policy.
+  /// See [`Self::authenticate_session`] for the session ID.
   pub fn authenticate_with_token(&self, token: &str) -> Result<AuthSuccess, AuthFailure> {
+    self
+      .authenticate_session(token)
+      .map(|(auth_success, _)| auth_success)
+  }
+
+  /// Authenticate with token and return the session it belongs to.
+  ///
+  /// The session ID lets the WebSocket handler end exactly this
+  /// device's session on logout or refresh-token reuse.
+  ///
+  /// # Errors
+  /// Returns `AuthFailure` if the token is invalid or its session has
+  /// ended.
+  pub fn authenticate_session(&self, token: &str) -> Result<(AuthSuccess, String), AuthFailure> {
     // Verify token
     let claims = self.verify_token(token).map_err(|e| AuthFailure {
       reason: e.to_string(),
@@ -297,29 +688,27 @@ impl UserStore {
       ),
     })?;
 
-    // Check if session matches (single-device login)
-    if let Some(ref sid) = session.session_id
-      && sid != &claims.sid
-    {
-      // Another device logged in
+    // Logged out, evicted, or revoked by a password change or
+    // refresh-token reuse. The JWT itself may not have expired yet.
+    if !session.has_session(&claims.sid) {
       debug!(
         user_id = %user_id,
         token_sid = %claims.sid,
-        current_sid = %sid,
-        "Session invalidated by another device"
+        "Token presented for an ended session"
       );
       return Err(AuthFailure {
-        reason: format!(
-          "Session invalidated: another device has logged in with user '{}'. Please re-authenticate.",
-          user_id
-        ),
+        reason: format!("Session ended for user '{}'. Please log in again.", user_id),
       });
     }
 
     // Update last seen
     drop(session);
     if let Some(mut session) = self.users.get_mut(&user_id) {
-      session.last_seen = Utc::now();
+      let now = Utc::now();
+      session.last_seen = now;
+      if let Some(auth_session) = session.session_mut(&claims.sid) {
+        auth_session.last_used = now;
+      }
     }
 
     // Include the user's current nickname in AuthSuccess so that
@@ -342,7 +731,7 @@ impl UserStore {
       "Token authentication successful"
     );
 
-    Ok(AuthSuccess {
+    let auth_success = AuthSuccess {
       user_id,
       username: claims.username,
       nickname,
@@ -352,14 +741,21 @@ impl UserStore {
       // response is augmented just before being sent on the wire.
       ice_servers: Vec::new(),
       avatar_url,
-    })
+      mailbox_enabled: false,
+      // Like the ICE servers, the device is known to the WS handler
+      // only and is overwritten there.
+      device_id: DeviceId::default(),
+    };
+    Ok((auth_success, claims.sid))
   }
 
-  /// Logout a user.
+  /// Logout a user from every device.
   pub fn logout(&self, user_id: &UserId) {
     if let Some(mut session) = self.users.get_mut(user_id) {
-      session.session_id = None;
+      session.sessions.clear();
       session.status = UserStatus::Offline;
+      self.persist(&session);
+      self.changed(AccountChange::LoggedOut(user_id.clone()));
       info!(
         user_id = %user_id,
         username = %session.username,
@@ -368,14 +764,40 @@ impl UserStore {
     }
   }
 
+  /// Logout a single device by ending its session.
+  ///
+  /// Returns `true` if it was the user's last session, in which case
+  /// the user is also marked `Offline`.
+  pub fn logout_session(&self, user_id: &UserId, session_id: &str) -> bool {
+    let Some(mut session) = self.users.get_mut(user_id) else {
+      return false;
+    };
+    session.sessions.retain(|s| s.session_id != session_id);
+    let last = session.sessions.is_empty();
+    if last {
+      session.status = UserStatus::Offline;
+    }
+    self.persist(&session);
+    self.changed(AccountChange::SessionEnded {
+      user_id: user_id.clone(),
+      session_id: session_id.to_string(),
+    });
+    info!(
+      user_id = %user_id,
+      username = %session.username,
+      remaining_sessions = session.sessions.len(),
+      "Device logged out"
+    );
+    last
+  }
+
   /// Check if session is valid.
   #[must_use]
   pub fn is_session_valid(&self, user_id: &UserId, session_id: &str) -> bool {
     self
       .users
       .get(user_id)
-      .map(|s| s.session_id.as_ref().is_some_and(|sid| sid == session_id))
-      .unwrap_or(false)
+      .is_some_and(|s| s.has_session(session_id))
   }
 
   /// Get user info by ID.
@@ -421,6 +843,8 @@ impl UserStore {
     {
       session.nickname = new_nickname.to_string();
       session.last_seen = Utc::now();
+      self.persist(&session);
+      self.profile_changed(&session);
       return true;
     }
     false
@@ -444,6 +868,8 @@ impl UserStore {
       if session.avatar_url != new_value {
         session.avatar_url = new_value;
         session.last_seen = Utc::now();
+        self.persist(&session);
+        self.profile_changed(&session);
         return true;
       }
     }
@@ -471,6 +897,8 @@ impl UserStore {
     if let Some(mut session) = self.users.get_mut(user_id) {
       session.bio = bio.clone();
       session.last_seen = Utc::now();
+      self.persist(&session);
+      self.profile_changed(&session);
 
       Some(UserStatusChange {
         user_id: user_id.clone(),
@@ -482,6 +910,106 @@ impl UserStore {
     }
   }
 
+  /// Mirror `session` into storage.
+  ///
+  /// Failures are logged rather than propagated: the in-memory change
+  /// has already been applied and the caller (a profile update or a
+  /// session bookkeeping step) has no meaningful way to roll it back.
+  ///
+  /// Call this while still holding the `users` guard: concurrent writes
+  /// to the same user must reach storage in the order they were applied.
+  fn persist(&self, session: &UserSession) {
+    if let Err(e) = self.storage.put_user(&session.to_stored()) {
+      warn!(
+        user_id = %session.user_id,
+        error = %e,
+        "Failed to persist user"
+      );
+    }
+  }
+
+  /// Verify `password` against the stored hash of `user_id`.
+  ///
+  /// The hash is cloned out first so the (deliberately slow) Argon2
+  /// verification never runs while holding a map shard lock.
+  fn check_password(&self, user_id: &UserId, password: &str) -> Result<()> {
+    let password_hash = self
+      .users
+      .get(user_id)
+      .map(|s| s.password_hash.clone())
+      .ok_or_else(|| anyhow!("Invalid credentials"))?;
+    let parsed_hash =
+      PasswordHash::new(&password_hash).map_err(|e| anyhow!("Invalid password hash: {}", e))?;
+    argon2()
+      .verify_password(password.as_bytes(), &parsed_hash)
+      .map_err(|_| anyhow!("Invalid credentials"))
+  }
+
+  /// Open session `session_id` on `session` and return its first
+  /// refresh token, ending the least recently used session if the
+  /// user is at [`MAX_SESSIONS_PER_USER`].
+  fn open_session(&self, session: &mut UserSession, session_id: &str) -> String {
+    while session.sessions.len() >= MAX_SESSIONS_PER_USER {
+      let oldest = session
+        .sessions
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, s)| s.last_used)
+        .map_or(0, |(i, _)| i);
+      let evicted = session.sessions.remove(oldest);
+      debug!(
+        user_id = %session.user_id,
+        session_id = %evicted.session_id,
+        "Ended least recently used session"
+      );
+      self.changed(AccountChange::SessionEnded {
+        user_id: session.user_id.clone(),
+        session_id: evicted.session_id,
+      });
+    }
+    let mut auth_session = AuthSession {
+      session_id: session_id.to_string(),
+      refresh_token_hash: String::new(),
+      refresh_expires_at: None,
+      last_used: Utc::now(),
+    };
+    let token = self.rotate_refresh_token(&mut auth_session, &session.user_id, session_id);
+    session.sessions.push(auth_session);
+    token
+  }
+
+  /// Start a new refresh token for `session_id` on `session`,
+  /// replacing (and thereby revoking) the previous one.
+  fn rotate_refresh_token(
+    &self,
+    session: &mut AuthSession,
+    user_id: &UserId,
+    session_id: &str,
+  ) -> String {
+    let token = generate_refresh_token(user_id, session_id);
+    session.refresh_token_hash = hash_refresh_token(&token);
+    session.refresh_expires_at = chrono::Duration::from_std(self.refresh_token_ttl)
+      .ok()
+      .and_then(|ttl| Utc::now().checked_add_signed(ttl));
+    session.last_used = Utc::now();
+    token
+  }
+
+  /// Sign an access token and bundle it with `refresh_token`.
+  fn token_pair(
+    &self,
+    user_id: &UserId,
+    username: &str,
+    session_id: &str,
+    refresh_token: String,
+  ) -> Result<TokenPair> {
+    Ok(TokenPair {
+      access_token: self.generate_token_with_session(user_id, username, session_id)?,
+      refresh_token,
+      expires_in: self.access_token_ttl.as_secs(),
+    })
+  }
+
   /// Generate JWT token with specific session ID.
   fn generate_token_with_session(
     &self,
@@ -490,7 +1018,7 @@ impl UserStore {
     session_id: &str,
   ) -> Result<String> {
     let now = Utc::now();
-    let exp = now + chrono::Duration::from_std(self.token_expiration)?;
+    let exp = now + chrono::Duration::from_std(self.access_token_ttl)?;
 
     let claims = Claims {
       sub: user_id.to_string(),
@@ -512,6 +1040,58 @@ fn generate_session_id() -> String {
   base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
 }
 
+/// Generate a refresh token of the form `<user_id>.<session_id>.<secret>`.
+///
+/// The IDs let [`UserStore::refresh`] find the token family without a
+/// separate index; only the random secret makes the token unguessable.
+fn generate_refresh_token(user_id: &UserId, session_id: &str) -> String {
+  format!("{user_id}.{session_id}.{}", generate_session_id())
+}
+
+/// Split a refresh token into its user and session IDs.
+fn parse_refresh_token(token: &str) -> Option<(UserId, String)> {
+  let mut parts = token.splitn(3, '.');
+  let user_id = parts.next()?.parse().ok()?;
+  let session_id = parts.next()?;
+  let secret = parts.next()?;
+  (!session_id.is_empty() && !secret.is_empty()).then(|| (user_id, session_id.to_string()))
+}
+
+/// Hash a refresh token for storage. Only the hash is kept, so a
+/// leaked storage file does not yield usable tokens.
+fn hash_refresh_token(token: &str) -> String {
+  base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Argon2id with the parameters required for password storage
+/// (memory=64MB, time=3, parallelism=4, output=32).
+fn argon2() -> Argon2<'static> {
+  Argon2::new(
+    argon2::Algorithm::Argon2id,
+    argon2::Version::V0x13,
+    argon2::Params::new(65536, 3, 4, Some(32)).expect("valid Argon2 params"),
+  )
+}
+
+/// Hash a password into a PHC string.
+fn hash_password(password: &str) -> Result<String> {
+  let salt = SaltString::generate(&mut OsRng);
+  argon2()
+    .hash_password(password.as_bytes(), &salt)
+    .map(|hash| hash.to_string())
+    .map_err(|e| anyhow!("Failed to hash password: {}", e))
+}
+
+/// Enforce the password policy.
+fn validate_password(password: &str) -> Result<()> {
+  if password.len() < MIN_PASSWORD_LENGTH {
+    return Err(anyhow!(
+      "Password must be at least {MIN_PASSWORD_LENGTH} characters"
+    ));
+  }
+  Ok(())
+}
+
 pub mod handlers;
 
 #[cfg(test)]
@@ -0,0 +1,266 @@
+//! Account replication between cluster replicas.
+//!
+//! With replication enabled, every local account mutation is recorded
+//! as an [`AccountChange`] for the cluster bus to publish. The other
+//! replicas apply it to their own copy and storage. Changes carry a
+//! single session or the profile rather than the whole account, so two
+//! replicas changing different sessions of one user at the same moment
+//! keep both changes.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bitcode::{Decode, Encode};
+use dashmap::mapref::entry::Entry;
+use message::UserId;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use super::{AuthSession, UserSession, UserStore};
+use crate::storage::{StoredSession, StoredUser};
+
+/// One account mutation, as shipped between cluster replicas.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum AccountChange {
+  /// An account was registered. Also sent for every account to a
+  /// replica that joins the cluster, which keeps the accounts it
+  /// already holds.
+  Created(StoredUser),
+  /// A session was opened, or its refresh token rotated.
+  Session {
+    /// Owner of the session.
+    user_id: UserId,
+    /// The session as it is now.
+    session: StoredSession,
+  },
+  /// A session was logged out, evicted, or revoked after
+  /// refresh-token reuse.
+  SessionEnded {
+    /// Owner of the session.
+    user_id: UserId,
+    /// The ended session.
+    session_id: String,
+  },
+  /// Every session of the user was logged out.
+  LoggedOut(UserId),
+  /// The password changed, ending every session but `session`.
+  Password {
+    /// Owner of the account.
+    user_id: UserId,
+    /// New Argon2id PHC string.
+    password_hash: String,
+    /// The session opened by the change.
+    session: StoredSession,
+  },
+  /// The nickname, bio or avatar changed.
+  Profile {
+    /// Owner of the account.
+    user_id: UserId,
+    /// Display nickname.
+    nickname: String,
+    /// User bio.
+    bio: String,
+    /// Avatar URL.
+    avatar_url: Option<String>,
+  },
+  /// The account was deleted.
+  Deleted(UserId),
+}
+
+/// Local changes waiting to be published.
+#[derive(Debug, Default)]
+pub(super) struct Replication {
+  /// Changes made since the last [`UserStore::take_changes`], oldest
+  /// first.
+  pending: Mutex<Vec<AccountChange>>,
+  /// Woken whenever a change is added to `pending`.
+  notify: Arc<Notify>,
+}
+
+impl UserStore {
+  /// Record local changes so they can be replicated to the other
+  /// cluster replicas.
+  #[must_use]
+  pub fn with_replication(mut self) -> Self {
+    self.replication = Some(Arc::new(Replication::default()));
+    self
+  }
+
+  /// Notified whenever an account changes locally (`None` without
+  /// replication).
+  #[must_use]
+  pub fn change_notify(&self) -> Option<Arc<Notify>> {
+    self.replication.as_ref().map(|r| r.notify.clone())
+  }
+
+  /// Drain the changes made locally since the last call, oldest first.
+  pub fn take_changes(&self) -> Vec<AccountChange> {
+    self
+      .replication
+      .as_ref()
+      .map(|replication| {
+        std::mem::take(
+          &mut *replication
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+        )
+      })
+      .unwrap_or_default()
+  }
+
+  /// Every account, for a replica that has just joined the cluster.
+  #[must_use]
+  pub fn replicas(&self) -> Vec<AccountChange> {
+    if self.replication.is_none() {
+      return Vec::new();
+    }
+    self
+      .users
+      .iter()
+      .map(|user| AccountChange::Created(user.to_stored()))
+      .collect()
+  }
+
+  /// Apply a change replicated from another replica, persisting it
+  /// but not recording it again. Returns whether anything changed.
+  pub fn apply_change(&self, change: AccountChange) -> bool {
+    if self.replication.is_none() {
+      return false;
+    }
+    match change {
+      AccountChange::Created(stored) => self.apply_created(stored),
+      AccountChange::Session { user_id, session } => self.apply_to(&user_id, |user| {
+        let session = AuthSession::from_stored(session);
+        match user.session_mut(&session.session_id) {
+          Some(existing) => *existing = session,
+          None => user.sessions.push(session),
+        }
+      }),
+      AccountChange::SessionEnded {
+        user_id,
+        session_id,
+      } => self.apply_to(&user_id, |user| {
+        user.sessions.retain(|s| s.session_id != session_id);
+      }),
+      AccountChange::LoggedOut(user_id) => self.apply_to(&user_id, |user| user.sessions.clear()),
+      AccountChange::Password {
+        user_id,
+        password_hash,
+        session,
+      } => self.apply_to(&user_id, |user| {
+        user.password_hash = password_hash;
+        user.sessions = vec![AuthSession::from_stored(session)];
+      }),
+      AccountChange::Profile {
+        user_id,
+        nickname,
+        bio,
+        avatar_url,
+      } => self.apply_to(&user_id, |user| {
+        user.nickname = nickname;
+        user.bio = bio;
+        user.avatar_url = avatar_url;
+      }),
+      AccountChange::Deleted(user_id) => self.apply_deleted(&user_id),
+    }
+  }
+
+  /// Record a local change. No-op without replication.
+  ///
+  /// Call this while still holding the `users` guard so that changes
+  /// to one user are published in the order they were applied.
+  pub(super) fn changed(&self, change: AccountChange) {
+    if let Some(replication) = &self.replication {
+      replication
+        .pending
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(change);
+      replication.notify.notify_one();
+    }
+  }
+
+  /// Record that session `session_id` of `user` was opened or rotated.
+  pub(super) fn session_changed(&self, user: &UserSession, session_id: &str) {
+    if let Some(session) = user.stored_session(session_id) {
+      self.changed(AccountChange::Session {
+        user_id: user.user_id.clone(),
+        session,
+      });
+    }
+  }
+
+  /// Record that the profile of `user` changed.
+  pub(super) fn profile_changed(&self, user: &UserSession) {
+    self.changed(AccountChange::Profile {
+      user_id: user.user_id.clone(),
+      nickname: user.nickname.clone(),
+      bio: user.bio.clone(),
+      avatar_url: user.avatar_url.clone(),
+    });
+  }
+
+  /// Insert an account registered on another replica.
+  ///
+  /// If two replicas registered the same username at the same moment,
+  /// every replica keeps the account with the lower user ID.
+  fn apply_created(&self, stored: StoredUser) -> bool {
+    if self.users.contains_key(&stored.user_id) {
+      return false;
+    }
+    let holder = self
+      .username_index
+      .get(&stored.username)
+      .map(|user_id| user_id.clone());
+    if let Some(holder) = holder {
+      if holder < stored.user_id {
+        return false;
+      }
+      warn!(
+        username = %stored.username,
+        kept = %stored.user_id,
+        dropped = %holder,
+        "Username registered on two replicas"
+      );
+      self.apply_deleted(&holder);
+    }
+
+    let Entry::Vacant(entry) = self.users.entry(stored.user_id.clone()) else {
+      return false;
+    };
+    if let Err(e) = self.storage.put_user(&stored) {
+      warn!(user_id = %stored.user_id, error = %e, "Failed to persist user");
+    }
+    self
+      .username_index
+      .insert(stored.username.clone(), stored.user_id.clone());
+    entry.insert(UserSession::from_stored(stored));
+    true
+  }
+
+  /// Remove an account deleted on another replica.
+  fn apply_deleted(&self, user_id: &UserId) -> bool {
+    let Entry::Occupied(entry) = self.users.entry(user_id.clone()) else {
+      return false;
+    };
+    if let Err(e) = self.storage.delete_user(user_id) {
+      warn!(user_id = %user_id, error = %e, "Failed to delete persisted user");
+    }
+    let (_, user) = entry.remove_entry();
+    self
+      .username_index
+      .remove_if(&user.username, |_, holder| holder == user_id);
+    true
+  }
+
+  /// Update and persist a known account. Returns `false` if the
+  /// account is unknown here.
+  fn apply_to(&self, user_id: &UserId, apply: impl FnOnce(&mut UserSession)) -> bool {
+    let Some(mut user) = self.users.get_mut(user_id) else {
+      return false;
+    };
+    apply(&mut user);
+    self.persist(&user);
+    true
+  }
+}
@@ -15,6 +15,8 @@ pub(super) fn create_store_with_secret(secret: &str) -> UserStore {
   UserStore::new(&config)
 }
 
+mod refresh_tokens;
+mod replication;
 mod token_lifecycle;
 mod token_security;
 mod user_management;
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::config::Config;
+use crate::storage::MemoryStorage;
+
+#[test]
+fn test_login_session_returns_refresh_token() {
+  let store = create_test_store();
+  store.register("refresher", "password123").unwrap();
+
+  let (user_id, tokens) = store.login_session("refresher", "password123").unwrap();
+  assert_eq!(
+    tokens.expires_in,
+    Config::default().access_token_ttl.as_secs()
+  );
+  assert!(tokens.refresh_token.starts_with(&user_id.to_string()));
+
+  let claims = store.verify_token(&tokens.access_token).unwrap();
+  assert!(store.is_session_valid(&user_id, &claims.sid));
+  assert!(claims.exp - claims.iat <= 15 * 60);
+}
+
+#[test]
+fn test_refresh_rotates_token_and_keeps_session() {
+  let store = create_test_store();
+  let (user_id, first) = store.register_session("rotator", "password123").unwrap();
+  let sid = store.verify_token(&first.access_token).unwrap().sid;
+
+  let (refreshed_id, second) = store.refresh(&first.refresh_token).unwrap();
+  assert_eq!(refreshed_id, user_id);
+  assert_ne!(second.refresh_token, first.refresh_token);
+
+  // The session (token family) is unchanged, so both access tokens
+  // remain usable until they expire.
+  let claims = store.verify_token(&second.access_token).unwrap();
+  assert_eq!(claims.sid, sid);
+  assert!(store.authenticate_with_token(&first.access_token).is_ok());
+  assert!(store.authenticate_with_token(&second.access_token).is_ok());
+
+  let (_, third) = store.refresh(&second.refresh_token).unwrap();
+  assert_ne!(third.refresh_token, second.refresh_token);
+}
+
+#[test]
+fn test_refresh_token_reuse_revokes_family() {
+  let store = create_test_store();
+  let (user_id, first) = store.register_session("reused", "password123").unwrap();
+  let (_, other_device) = store.login_session("reused", "password123").unwrap();
+  let session_id = store.verify_token(&first.access_token).unwrap().sid;
+  let (_, second) = store.refresh(&first.refresh_token).unwrap();
+
+  // Replaying the rotated-out token revokes the whole session.
+  assert_eq!(
+    store.refresh(&first.refresh_token).unwrap_err(),
+    RefreshError::Reused {
+      user_id: user_id.clone(),
+      session_id,
+    }
+  );
+  assert_eq!(
+    store.refresh(&second.refresh_token).unwrap_err(),
+    RefreshError::Invalid
+  );
+  assert!(store.authenticate_with_token(&second.access_token).is_err());
+  assert!(store.verify_session_token(&second.access_token).is_err());
+
+  // The user's other device keeps its session.
+  assert!(
+    store
+      .verify_session_token(&other_device.access_token)
+      .is_ok()
+  );
+
+  // A fresh login starts a new family.
+  let (_, relogin) = store.login_session("reused", "password123").unwrap();
+  assert!(store.refresh(&relogin.refresh_token).is_ok());
+}
+
+#[test]
+fn test_refresh_rejects_logged_out_sessions() {
+  let store = create_test_store();
+  let (user_id, first) = store.register_session("loggedout", "password123").unwrap();
+  let (_, second) = store.login_session("loggedout", "password123").unwrap();
+
+  // Logging out one device leaves the other one refreshable.
+  let sid = store.verify_token(&first.access_token).unwrap().sid;
+  store.logout_session(&user_id, &sid);
+  assert_eq!(
+    store.refresh(&first.refresh_token).unwrap_err(),
+    RefreshError::Invalid
+  );
+  let (_, second) = store.refresh(&second.refresh_token).unwrap();
+
+  store.logout(&user_id);
+  assert_eq!(
+    store.refresh(&second.refresh_token).unwrap_err(),
+    RefreshError::Invalid
+  );
+}
+
+#[test]
+fn test_refresh_rejects_malformed_tokens() {
+  let store = create_test_store();
+  let (user_id, _) = store.register_session("malformed", "password123").unwrap();
+
+  for token in [
+    "",
+    "not-a-token",
+    "a.b.c",
+    &format!("{user_id}"),
+    &format!("{user_id}.session"),
+    &format!("{user_id}..secret"),
+    &format!("{}.session.secret", UserId::new()),
+  ] {
+    assert_eq!(
+      store.refresh(token).unwrap_err(),
+      RefreshError::Invalid,
+      "token {token:?} should be invalid"
+    );
+  }
+  // Malformed tokens never revoke the live session.
+  assert!(store.get_user(&user_id).is_some());
+}
+
+#[test]
+fn test_expired_refresh_token_rejected() {
+  let config = Config {
+    refresh_token_ttl: std::time::Duration::ZERO,
+    ..Config::default()
+  };
+  let store = UserStore::new(&config);
+  let (_, tokens) = store.register_session("expiring", "password123").unwrap();
+
+  assert_eq!(
+    store.refresh(&tokens.refresh_token).unwrap_err(),
+    RefreshError::Expired
+  );
+  // Expiry is not reuse: the access token stays valid.
+  assert!(store.verify_session_token(&tokens.access_token).is_ok());
+}
+
+#[test]
+fn test_refresh_token_survives_restart() {
+  let config = Config::default();
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+  let store = UserStore::with_storage(&config, storage.clone());
+  let (_, tokens) = store.register_session("durable", "password123").unwrap();
+
+  let restarted = UserStore::with_storage(&config, storage);
+  let (_, rotated) = restarted.refresh(&tokens.refresh_token).unwrap();
+  assert!(
+    restarted
+      .verify_session_token(&rotated.access_token)
+      .is_ok()
+  );
+}
+
+#[test]
+fn test_change_password() {
+  let store = create_test_store();
+  let (user_id, old) = store.register_session("changer", "password123").unwrap();
+  let (_, other_device) = store.login_session("changer", "password123").unwrap();
+
+  assert!(
+    store
+      .change_password(&user_id, "wrong-password", "newpassword456")
+      .is_err()
+  );
+  assert!(
+    store
+      .change_password(&user_id, "password123", "short")
+      .is_err()
+  );
+  assert!(
+    store
+      .change_password(&user_id, "password123", "password123")
+      .is_err()
+  );
+
+  let tokens = store
+    .change_password(&user_id, "password123", "newpassword456")
+    .unwrap();
+  assert!(store.verify_session_token(&tokens.access_token).is_ok());
+
+  // Every credential of the old sessions is revoked.
+  assert!(store.verify_session_token(&old.access_token).is_err());
+  assert!(
+    store
+      .verify_session_token(&other_device.access_token)
+      .is_err()
+  );
+  assert_eq!(
+    store.refresh(&old.refresh_token).unwrap_err(),
+    RefreshError::Invalid
+  );
+
+  assert!(store.login("changer", "password123").is_err());
+  assert!(store.login("changer", "newpassword456").is_ok());
+}
+
+#[test]
+fn test_delete_account() {
+  let config = Config::default();
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+  let store = UserStore::with_storage(&config, storage.clone());
+  let (user_id, tokens) = store.register_session("leaver", "password123").unwrap();
+
+  assert!(store.delete_account(&user_id, "wrong-password").is_err());
+  assert!(store.get_user(&user_id).is_some());
+
+  store.delete_account(&user_id, "password123").unwrap();
+  assert!(store.get_user(&user_id).is_none());
+  assert!(store.authenticate_with_token(&tokens.access_token).is_err());
+  assert_eq!(
+    store.refresh(&tokens.refresh_token).unwrap_err(),
+    RefreshError::Invalid
+  );
+  assert!(storage.users().is_empty());
+
+  // The username is free again.
+  assert!(store.register("leaver", "password123").is_ok());
+}
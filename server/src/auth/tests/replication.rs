@@ -0,0 +1,139 @@
+//! Cluster replication tests: change tracking and applying changes
+//! made on another replica.
+
+use super::*;
+
+/// Two replicated stores with separate storage.
+fn replicated_pair() -> (UserStore, UserStore) {
+  (
+    create_test_store().with_replication(),
+    create_test_store().with_replication(),
+  )
+}
+
+/// Apply every change recorded on `from` to `to`.
+fn sync(from: &UserStore, to: &UserStore) {
+  for change in from.take_changes() {
+    to.apply_change(change);
+  }
+}
+
+#[test]
+fn test_changes_not_tracked_without_replication() {
+  let store = create_test_store();
+  store.register("alice", "password123").unwrap();
+
+  assert!(store.change_notify().is_none());
+  assert!(store.take_changes().is_empty());
+  assert!(store.replicas().is_empty());
+  assert!(!store.apply_change(AccountChange::LoggedOut(UserId::new())));
+}
+
+#[test]
+fn test_registration_and_login_authenticate_on_other_replica() {
+  let (a, b) = replicated_pair();
+  let (user_id, first) = a.register_session("alice", "password123").unwrap();
+  sync(&a, &b);
+  assert_eq!(b.get_user(&user_id).unwrap().username, "alice");
+  assert!(b.authenticate_session(&first.access_token).is_ok());
+
+  let (_, second) = a.login_session("alice", "password123").unwrap();
+  sync(&a, &b);
+  assert!(b.authenticate_session(&second.access_token).is_ok());
+  // The replicated password hash lets the user log in on B too.
+  assert!(b.login_session("alice", "password123").is_ok());
+}
+
+#[test]
+fn test_concurrent_logins_on_two_replicas_keep_both_sessions() {
+  let (a, b) = replicated_pair();
+  a.register("alice", "password123").unwrap();
+  sync(&a, &b);
+
+  let (_, on_a) = a.login_session("alice", "password123").unwrap();
+  let (_, on_b) = b.login_session("alice", "password123").unwrap();
+  sync(&a, &b);
+  sync(&b, &a);
+
+  for store in [&a, &b] {
+    assert!(store.authenticate_session(&on_a.access_token).is_ok());
+    assert!(store.authenticate_session(&on_b.access_token).is_ok());
+  }
+}
+
+#[test]
+fn test_logout_and_refresh_replicate() {
+  let (a, b) = replicated_pair();
+  let (user_id, tokens) = a.register_session("alice", "password123").unwrap();
+  sync(&a, &b);
+
+  // A refresh on B rotates the token on A as well.
+  let (_, rotated) = b.refresh(&tokens.refresh_token).unwrap();
+  sync(&b, &a);
+  assert!(a.refresh(&rotated.refresh_token).is_ok());
+  sync(&a, &b);
+
+  a.logout(&user_id);
+  sync(&a, &b);
+  assert!(b.authenticate_session(&tokens.access_token).is_err());
+}
+
+#[test]
+fn test_profile_and_deletion_replicate() {
+  let (a, b) = replicated_pair();
+  let (user_id, _) = a.register("alice", "password123").unwrap();
+  sync(&a, &b);
+
+  assert!(a.set_nickname(&user_id, "Alice"));
+  a.update_bio(&user_id, "hello".to_string());
+  sync(&a, &b);
+  let info = b.get_user(&user_id).unwrap();
+  assert_eq!(info.nickname, "Alice");
+  assert_eq!(info.bio, "hello");
+
+  a.delete_account(&user_id, "password123").unwrap();
+  sync(&a, &b);
+  assert!(b.get_user(&user_id).is_none());
+  assert!(b.login("alice", "password123").is_err());
+}
+
+#[test]
+fn test_username_registered_on_both_replicas_keeps_lower_id() {
+  let (a, b) = replicated_pair();
+  let (on_a, _) = a.register("alice", "password123").unwrap();
+  let (on_b, _) = b.register("alice", "password456").unwrap();
+  sync(&a, &b);
+  sync(&b, &a);
+
+  let kept = on_a.clone().min(on_b.clone());
+  for store in [&a, &b] {
+    assert!(store.get_user(&kept).is_some());
+    assert_eq!(
+      store.get_user(&on_a).is_some(),
+      kept == on_a,
+      "replicas disagree on the account kept"
+    );
+    assert_eq!(store.get_user(&on_b).is_some(), kept == on_b);
+  }
+}
+
+#[test]
+fn test_replicas_fill_in_missing_accounts_only() {
+  let (a, b) = replicated_pair();
+  let (user_id, tokens) = a.register_session("alice", "password123").unwrap();
+  sync(&a, &b);
+  b.logout(&user_id);
+  b.take_changes();
+
+  // A's snapshot adds nothing B knows already, so B's logout stands.
+  for change in a.replicas() {
+    assert!(!b.apply_change(change));
+  }
+  assert!(b.authenticate_session(&tokens.access_token).is_err());
+
+  let c = create_test_store().with_replication();
+  for change in a.replicas() {
+    assert!(c.apply_change(change));
+  }
+  assert!(c.authenticate_session(&tokens.access_token).is_ok());
+}
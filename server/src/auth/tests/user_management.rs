@@ -1,4 +1,8 @@
+use std::sync::Arc;
+
 use super::*;
+use crate::config::Config;
+use crate::storage::MemoryStorage;
 
 #[test]
 fn test_register_user() {
@@ -22,6 +26,25 @@ fn test_register_duplicate_username() {
   assert!(result.is_err());
 }
 
+#[test]
+fn test_concurrent_registrations_persist_one_account() {
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+  let store = UserStore::with_storage(&Config::default(), storage.clone());
+
+  let registered = std::thread::scope(|scope| {
+    let handles: Vec<_> = (0..4)
+      .map(|_| scope.spawn(|| store.register("racer", "password123").is_ok()))
+      .collect();
+    handles
+      .into_iter()
+      .map(|handle| handle.join().unwrap())
+      .filter(|ok| *ok)
+      .count()
+  });
+  assert_eq!(registered, 1);
+  assert_eq!(storage.users().len(), 1);
+}
+
 #[test]
 fn test_login_user() {
   let store = create_test_store();
@@ -53,27 +76,40 @@ fn test_authenticate_with_token() {
 }
 
 #[test]
-fn test_single_device_login() {
+fn test_multi_device_login() {
   let store = create_test_store();
   let _ = store.register("testuser", "password123").unwrap();
 
-  // First login
+  // First device
   let (_, token1) = store.login("testuser", "password123").unwrap();
+  assert!(store.authenticate_with_token(&token1).is_ok());
 
-  // First token should work
-  let auth1 = store.authenticate_with_token(&token1);
-  assert!(auth1.is_ok());
+  // Second device
+  let (_, token2) = store.login("testuser", "password123").unwrap();
 
-  // Login again (simulating another device)
+  // Both devices stay signed in
+  assert!(store.authenticate_with_token(&token1).is_ok());
+  assert!(store.authenticate_with_token(&token2).is_ok());
+}
+
+#[test]
+fn test_logout_session_ends_one_device_only() {
+  let store = create_test_store();
+  let (user_id, token1) = store.register("testuser", "password123").unwrap();
   let (_, token2) = store.login("testuser", "password123").unwrap();
 
-  // Old token should now be invalid
-  let auth1_again = store.authenticate_with_token(&token1);
-  assert!(auth1_again.is_err());
+  let (_, sid1) = store.authenticate_session(&token1).unwrap();
+  assert!(!store.logout_session(&user_id, &sid1));
+  assert!(store.authenticate_with_token(&token1).is_err());
+  assert!(store.authenticate_with_token(&token2).is_ok());
+  assert_eq!(store.get_user(&user_id).unwrap().status, UserStatus::Online);
 
-  // New token should work
-  let auth2 = store.authenticate_with_token(&token2);
-  assert!(auth2.is_ok());
+  let (_, sid2) = store.authenticate_session(&token2).unwrap();
+  assert!(store.logout_session(&user_id, &sid2));
+  assert_eq!(
+    store.get_user(&user_id).unwrap().status,
+    UserStatus::Offline
+  );
 }
 
 #[test]
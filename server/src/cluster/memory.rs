@@ -0,0 +1,113 @@
+//! In-process cluster bus.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use tokio::sync::mpsc;
+
+use super::{BusNotice, ClusterBus, ClusterEvent, NOTICE_QUEUE_SIZE, NodeId};
+
+/// Shared switchboard connecting [`InProcessBus`]es.
+///
+/// Every bus that joins is fully meshed with the ones already there;
+/// dropping a bus announces [`BusNotice::PeerDown`] to the rest.
+#[derive(Debug, Default)]
+pub struct InProcessNetwork {
+  nodes: Mutex<HashMap<NodeId, mpsc::Sender<BusNotice>>>,
+}
+
+impl InProcessNetwork {
+  /// Create an empty network.
+  #[must_use]
+  pub fn new() -> Arc<Self> {
+    Arc::new(Self::default())
+  }
+
+  /// Add a replica named `node` to the network.
+  ///
+  /// # Panics
+  ///
+  /// Panics if a replica with the same name has already joined.
+  #[must_use]
+  pub fn join(self: &Arc<Self>, node: impl Into<String>) -> InProcessBus {
+    let node = NodeId(node.into());
+    let (tx, rx) = mpsc::channel(NOTICE_QUEUE_SIZE);
+    let mut nodes = self.nodes();
+    assert!(
+      !nodes.contains_key(&node),
+      "node {node} already joined the network"
+    );
+    for (other, other_tx) in nodes.iter() {
+      let _ = other_tx.try_send(BusNotice::PeerUp(node.clone()));
+      let _ = tx.try_send(BusNotice::PeerUp(other.clone()));
+    }
+    nodes.insert(node.clone(), tx);
+    drop(nodes);
+
+    InProcessBus {
+      node,
+      network: self.clone(),
+      inbound: Mutex::new(Some(rx)),
+    }
+  }
+
+  fn nodes(&self) -> MutexGuard<'_, HashMap<NodeId, mpsc::Sender<BusNotice>>> {
+    self.nodes.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+/// Cluster bus between replicas running in the same process.
+#[derive(Debug)]
+pub struct InProcessBus {
+  node: NodeId,
+  network: Arc<InProcessNetwork>,
+  inbound: Mutex<Option<mpsc::Receiver<BusNotice>>>,
+}
+
+impl ClusterBus for InProcessBus {
+  fn node_id(&self) -> &NodeId {
+    &self.node
+  }
+
+  fn publish(&self, event: ClusterEvent) {
+    for (other, tx) in self.network.nodes().iter() {
+      if other != &self.node {
+        let _ = tx.try_send(BusNotice::Event {
+          from: self.node.clone(),
+          event: event.clone(),
+        });
+      }
+    }
+  }
+
+  fn send(&self, node: &NodeId, event: ClusterEvent) -> bool {
+    if node == &self.node {
+      return false;
+    }
+    self.network.nodes().get(node).is_some_and(|tx| {
+      tx.try_send(BusNotice::Event {
+        from: self.node.clone(),
+        event,
+      })
+      .is_ok()
+    })
+  }
+
+  fn subscribe(&self) -> Option<mpsc::Receiver<BusNotice>> {
+    self
+      .inbound
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .take()
+  }
+}
+
+impl Drop for InProcessBus {
+  fn drop(&mut self) {
+    let mut nodes = self.network.nodes();
+    nodes.remove(&self.node);
+    for tx in nodes.values() {
+      let _ = tx.try_send(BusNotice::PeerDown(self.node.clone()));
+    }
+  }
+}
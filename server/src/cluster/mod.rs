@@ -0,0 +1,281 @@
+//! Cluster bus between replicas of the signaling server.
+//!
+//! A single replica keeps its connections, presence and rooms in
+//! process memory. To run several replicas behind a load balancer they
+//! exchange [`ClusterEvent`]s over a [`ClusterBus`]:
+//! - Signaling frames for a user connected to another replica are
+//!   routed to that replica by `UserId` ([`ClusterEvent::Deliver`]).
+//! - Broadcasts reach every replica ([`ClusterEvent::Broadcast`]).
+//! - Presence changes are announced so every replica lists the same
+//!   online users ([`ClusterEvent::UserOnline`] / [`ClusterEvent::UserOffline`]).
+//! - Room changes are replicated as whole-room snapshots, applied
+//!   last-writer-wins by [`RoomVersion`](crate::room::RoomVersion).
+//! - Connection invitations and peer relationships are recorded on the
+//!   recipient's replica as the frames carrying them are delivered.
+//! - Accounts and sessions are replicated change by change
+//!   ([`ClusterEvent::Account`]), so a user registered or logged in on
+//!   one replica authenticates on every other. Each replica persists
+//!   the changes to its own storage.
+//! - Mailboxes are replicated the same way ([`ClusterEvent::Mailbox`]):
+//!   a deposit made on one replica is delivered and fetched on the
+//!   replica the recipient connects to.
+//!
+//! Two buses ship with the server:
+//! - [`InProcessBus`] — replicas inside one process, joined through an
+//!   [`InProcessNetwork`]. Used by tests.
+//! - [`TcpBus`] — replicas in separate processes, fully meshed over
+//!   TCP. Selected with `CLUSTER_ENABLED=true`.
+//!
+//! A replica that joins is sent every account it does not know yet.
+//! Session and mailbox changes made while it was unreachable are not
+//! replayed.
+
+mod memory;
+mod tcp;
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+use std::sync::Arc;
+
+use bitcode::{Decode, Encode};
+use dashmap::DashMap;
+use message::UserId;
+use message::types::UserInfo;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::auth::AccountChange;
+use crate::mailbox::MailboxChange;
+use crate::room::RoomReplica;
+
+pub use memory::{InProcessBus, InProcessNetwork};
+pub use tcp::TcpBus;
+
+// =============================================================================
+// Bus Messages
+// =============================================================================
+
+/// Name of a replica, unique within the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub struct NodeId(pub String);
+
+impl fmt::Display for NodeId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+/// Event published by one replica to the others.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum ClusterEvent {
+  /// Encoded signaling frame for a user connected to the receiving
+  /// replica.
+  Deliver {
+    /// Recipient.
+    to: UserId,
+    /// Encoded signaling frame.
+    frame: Vec<u8>,
+  },
+  /// Encoded signaling frame for every user connected to the
+  /// receiving replica.
+  Broadcast {
+    /// Encoded signaling frame.
+    frame: Vec<u8>,
+  },
+  /// A user authenticated on the sending replica, or their profile
+  /// changed there.
+  UserOnline(UserInfo),
+  /// A user's connection to the sending replica closed.
+  UserOffline(UserId),
+  /// Latest state of a room.
+  Room(Box<RoomReplica>),
+  /// A change to an account or its sessions.
+  Account(Box<AccountChange>),
+  /// A change to a mailbox.
+  Mailbox(Box<MailboxChange>),
+}
+
+/// Notification delivered by a bus to its replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusNotice {
+  /// A replica joined (or re-joined) the cluster.
+  PeerUp(NodeId),
+  /// A replica left the cluster or became unreachable.
+  PeerDown(NodeId),
+  /// An event published by another replica.
+  Event {
+    /// Publishing replica.
+    from: NodeId,
+    /// The event.
+    event: ClusterEvent,
+  },
+}
+
+// =============================================================================
+// Bus Trait
+// =============================================================================
+
+/// Transport between the replicas of a cluster.
+///
+/// Sends are fire-and-forget: events for an unreachable replica are
+/// dropped, and the replicas resynchronise on the next
+/// [`BusNotice::PeerUp`].
+pub trait ClusterBus: Send + Sync + fmt::Debug {
+  /// Name of the local replica.
+  fn node_id(&self) -> &NodeId;
+
+  /// Send `event` to every other replica.
+  fn publish(&self, event: ClusterEvent);
+
+  /// Send `event` to one replica. Returns `false` if it is not
+  /// connected.
+  fn send(&self, node: &NodeId, event: ClusterEvent) -> bool;
+
+  /// Take the stream of notices for the local replica. Only the first
+  /// call returns `Some`.
+  fn subscribe(&self) -> Option<mpsc::Receiver<BusNotice>>;
+}
+
+/// Notices buffered for the local replica. A TCP bus stops reading
+/// from its peers while the buffer is full; the in-process bus drops.
+pub const NOTICE_QUEUE_SIZE: usize = 4096;
+
+// =============================================================================
+// Remote Presence
+// =============================================================================
+
+/// A user connected to another replica.
+#[derive(Debug)]
+struct RemoteUser {
+  /// Replica holding the user's connection.
+  node: NodeId,
+  /// Profile announced by that replica.
+  info: UserInfo,
+  /// Queue whose frames are forwarded to `node` by a background task.
+  sender: mpsc::Sender<Vec<u8>>,
+}
+
+/// Cluster membership of one replica: its bus and the users connected
+/// to the other replicas.
+///
+/// Every remote user gets a local queue with the same type as a
+/// WebSocket connection's, so code that looks up a user's sender
+/// reaches them wherever they are connected.
+#[derive(Debug)]
+pub struct ClusterState {
+  bus: Arc<dyn ClusterBus>,
+  remote_users: DashMap<UserId, RemoteUser>,
+  queue_size: usize,
+}
+
+impl ClusterState {
+  /// Wrap `bus`. Forwarding queues hold up to `queue_size` frames.
+  #[must_use]
+  pub fn new(bus: Arc<dyn ClusterBus>, queue_size: usize) -> Self {
+    Self {
+      bus,
+      remote_users: DashMap::new(),
+      queue_size,
+    }
+  }
+
+  /// The underlying bus.
+  #[must_use]
+  pub fn bus(&self) -> &Arc<dyn ClusterBus> {
+    &self.bus
+  }
+
+  /// Name of the local replica.
+  #[must_use]
+  pub fn node_id(&self) -> &NodeId {
+    self.bus.node_id()
+  }
+
+  /// Record that `info.user_id` is connected to `node`.
+  ///
+  /// Returns `true` if the user was not known to be online on `node`
+  /// before (as opposed to a profile refresh).
+  pub fn add_remote_user(&self, node: NodeId, info: UserInfo) -> bool {
+    if let Some(mut existing) = self.remote_users.get_mut(&info.user_id)
+      && existing.node == node
+    {
+      existing.info = info;
+      return false;
+    }
+
+    let (sender, mut rx) = mpsc::channel::<Vec<u8>>(self.queue_size);
+    let bus = self.bus.clone();
+    let to = info.user_id.clone();
+    let target = node.clone();
+    tokio::spawn(async move {
+      while let Some(frame) = rx.recv().await {
+        if !bus.send(
+          &target,
+          ClusterEvent::Deliver {
+            to: to.clone(),
+            frame,
+          },
+        ) {
+          debug!(node = %target, user_id = %to, "Dropped frame for unreachable replica");
+        }
+      }
+    });
+    self
+      .remote_users
+      .insert(info.user_id.clone(), RemoteUser { node, info, sender });
+    true
+  }
+
+  /// Forget that `user_id` is connected to `node`. Returns `false` if
+  /// the user is not known there (e.g. they already moved on).
+  pub fn remove_remote_user(&self, node: &NodeId, user_id: &UserId) -> bool {
+    self
+      .remote_users
+      .remove_if(user_id, |_, remote| &remote.node == node)
+      .is_some()
+  }
+
+  /// Forget every user connected to `node`, returning their IDs.
+  pub fn remove_node(&self, node: &NodeId) -> Vec<UserId> {
+    let users: Vec<UserId> = self
+      .remote_users
+      .iter()
+      .filter(|entry| &entry.node == node)
+      .map(|entry| entry.key().clone())
+      .collect();
+    users
+      .into_iter()
+      .filter(|user_id| self.remove_remote_user(node, user_id))
+      .collect()
+  }
+
+  /// Forwarding queue for a user connected to another replica.
+  #[must_use]
+  pub fn remote_sender(&self, user_id: &UserId) -> Option<mpsc::Sender<Vec<u8>>> {
+    self
+      .remote_users
+      .get(user_id)
+      .map(|remote| remote.sender.clone())
+  }
+
+  /// Replica a remote user is connected to.
+  #[must_use]
+  pub fn remote_node(&self, user_id: &UserId) -> Option<NodeId> {
+    self
+      .remote_users
+      .get(user_id)
+      .map(|remote| remote.node.clone())
+  }
+
+  /// Profiles of every user connected to another replica.
+  #[must_use]
+  pub fn remote_users(&self) -> Vec<UserInfo> {
+    self
+      .remote_users
+      .iter()
+      .map(|remote| remote.info.clone())
+      .collect()
+  }
+}
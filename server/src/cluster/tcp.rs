@@ -0,0 +1,563 @@
+//! TCP cluster bus.
+//!
+//! Every replica listens on [`ClusterConfig::listen`] and dials each
+//! address in [`ClusterConfig::peers`], retrying until it answers.
+//! Frames are a big-endian `u32` length followed by a bitcode-encoded
+//! [`Frame`].
+//!
+//! A connection starts with a handshake: both sides send a `Hello`
+//! with their node ID and a random nonce, then prove knowledge of the
+//! shared secret with an HMAC over the whole transcript (both node
+//! IDs, both nonces, and which side dialed), so a proof cannot be
+//! relayed to another connection. Every later frame carries an HMAC
+//! under a per-connection, per-direction key and a sequence number.
+//! When two replicas dial each other, the connection dialed by the
+//! smaller node ID is kept on both ends.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use bitcode::{Decode, Encode};
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::{BusNotice, ClusterBus, ClusterEvent, NOTICE_QUEUE_SIZE, NodeId};
+use crate::config::ClusterConfig;
+
+/// Domain separation for the handshake key derived from the JWT secret.
+const SECRET_CONTEXT: &[u8] = b"chat-cluster-v1";
+/// Largest accepted frame.
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+/// Largest frame accepted before the peer is authenticated.
+pub(super) const MAX_HANDSHAKE_FRAME_BYTES: usize = 4 * 1024;
+/// Length of the HMAC-SHA256 tag after each authenticated frame.
+const TAG_BYTES: usize = 32;
+/// Frames queued for one peer before further events to it are dropped.
+const LINK_QUEUE_SIZE: usize = 4096;
+/// Time allowed for the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Delay before re-dialing a peer, doubled up to [`MAX_REDIAL_DELAY`].
+const INITIAL_REDIAL_DELAY: Duration = Duration::from_millis(250);
+/// Longest delay between two dial attempts.
+const MAX_REDIAL_DELAY: Duration = Duration::from_secs(10);
+
+/// Unit of the wire protocol.
+#[derive(Debug, Encode, Decode)]
+pub(super) enum Frame {
+  /// First frame on a connection.
+  Hello { node: NodeId, nonce: [u8; 32] },
+  /// Second frame: HMAC over the handshake transcript and the
+  /// sender's role.
+  Proof { mac: Vec<u8> },
+  /// Any later frame.
+  Event(ClusterEvent),
+}
+
+/// An established connection to another replica.
+#[derive(Debug)]
+struct PeerLink {
+  /// Distinguishes this connection from a later one to the same peer.
+  id: u64,
+  /// Replica that dialed the connection.
+  dialer: NodeId,
+  /// Encoded frames waiting to be authenticated and written.
+  tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl PeerLink {
+  /// Queue `frame`, dropping it if the peer is not keeping up.
+  fn queue(&self, peer: &NodeId, frame: Vec<u8>) -> bool {
+    match self.tx.try_send(frame) {
+      Ok(()) => true,
+      Err(mpsc::error::TrySendError::Full(_)) => {
+        warn!(peer = %peer, "Cluster link queue full, dropping event");
+        false
+      }
+      Err(mpsc::error::TrySendError::Closed(_)) => false,
+    }
+  }
+}
+
+/// Keys authenticating the frames of one connection after the
+/// handshake, one per direction.
+struct SessionKeys {
+  send: [u8; 32],
+  recv: [u8; 32],
+}
+
+/// State shared by the listener, dialers and connections.
+#[derive(Debug)]
+struct Shared {
+  node: NodeId,
+  key: [u8; 32],
+  links: DashMap<NodeId, PeerLink>,
+  notices: mpsc::Sender<BusNotice>,
+  next_link_id: AtomicU64,
+  cancel: CancellationToken,
+}
+
+/// Cluster bus between replicas in separate processes.
+#[derive(Debug)]
+pub struct TcpBus {
+  shared: Arc<Shared>,
+  local_addr: SocketAddr,
+  inbound: Mutex<Option<mpsc::Receiver<BusNotice>>>,
+}
+
+impl TcpBus {
+  /// Bind the cluster listener and start dialing `config.peers`.
+  ///
+  /// Peers prove they hold the same `jwt_secret`.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the listener cannot be bound.
+  pub async fn start(config: &ClusterConfig, jwt_secret: &str) -> io::Result<Self> {
+    let listener = TcpListener::bind(config.listen).await?;
+    let local_addr = listener.local_addr()?;
+    let (notices, inbound) = mpsc::channel(NOTICE_QUEUE_SIZE);
+    let shared = Arc::new(Shared {
+      node: NodeId(config.node_id.clone()),
+      key: derive_key(jwt_secret),
+      links: DashMap::new(),
+      notices,
+      next_link_id: AtomicU64::new(0),
+      cancel: CancellationToken::new(),
+    });
+
+    tokio::spawn(accept_loop(shared.clone(), listener));
+    for peer in &config.peers {
+      tokio::spawn(dial_loop(shared.clone(), peer.clone()));
+    }
+    info!(
+      node = %shared.node,
+      address = %local_addr,
+      peers = config.peers.len(),
+      "Cluster bus listening"
+    );
+
+    Ok(Self {
+      shared,
+      local_addr,
+      inbound: Mutex::new(Some(inbound)),
+    })
+  }
+
+  /// Address the cluster listener is bound to.
+  #[must_use]
+  pub const fn local_addr(&self) -> SocketAddr {
+    self.local_addr
+  }
+
+  /// Replicas currently connected.
+  #[must_use]
+  pub fn peers(&self) -> Vec<NodeId> {
+    self
+      .shared
+      .links
+      .iter()
+      .map(|link| link.key().clone())
+      .collect()
+  }
+}
+
+impl ClusterBus for TcpBus {
+  fn node_id(&self) -> &NodeId {
+    &self.shared.node
+  }
+
+  fn publish(&self, event: ClusterEvent) {
+    let frame = encode_frame(&Frame::Event(event));
+    for link in self.shared.links.iter() {
+      link.queue(link.key(), frame.clone());
+    }
+  }
+
+  fn send(&self, node: &NodeId, event: ClusterEvent) -> bool {
+    self
+      .shared
+      .links
+      .get(node)
+      .is_some_and(|link| link.queue(node, encode_frame(&Frame::Event(event))))
+  }
+
+  fn subscribe(&self) -> Option<mpsc::Receiver<BusNotice>> {
+    self
+      .inbound
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .take()
+  }
+}
+
+impl Drop for TcpBus {
+  fn drop(&mut self) {
+    self.shared.cancel.cancel();
+  }
+}
+
+// =============================================================================
+// Connection Management
+// =============================================================================
+
+/// Accept connections from replicas that dial us.
+async fn accept_loop(shared: Arc<Shared>, listener: TcpListener) {
+  loop {
+    tokio::select! {
+      () = shared.cancel.cancelled() => break,
+      accepted = listener.accept() => match accepted {
+        Ok((stream, addr)) => {
+          debug!(address = %addr, "Accepted cluster connection");
+          tokio::spawn(run_connection(shared.clone(), stream, false));
+        }
+        Err(e) => warn!(error = %e, "Failed to accept cluster connection"),
+      },
+    }
+  }
+}
+
+/// Keep a connection to the replica at `address` open.
+async fn dial_loop(shared: Arc<Shared>, address: String) {
+  let mut delay = INITIAL_REDIAL_DELAY;
+  let mut known: Option<NodeId> = None;
+  while !shared.cancel.is_cancelled() {
+    // The peer dialed us and won the tie-break; wait for that link.
+    if known
+      .as_ref()
+      .is_some_and(|node| shared.links.contains_key(node))
+    {
+      tokio::select! {
+        () = shared.cancel.cancelled() => break,
+        () = sleep(MAX_REDIAL_DELAY) => continue,
+      }
+    }
+    match TcpStream::connect(&address).await {
+      Ok(stream) => {
+        delay = INITIAL_REDIAL_DELAY;
+        if let Some(node) = run_connection(shared.clone(), stream, true).await {
+          known = Some(node);
+        }
+      }
+      Err(e) => debug!(address = %address, error = %e, "Cluster peer unreachable"),
+    }
+    tokio::select! {
+      () = shared.cancel.cancelled() => break,
+      () = sleep(delay) => {}
+    }
+    delay = (delay * 2).min(MAX_REDIAL_DELAY);
+  }
+}
+
+/// Handshake, register and serve one connection until it closes.
+///
+/// Returns the peer's node ID if the handshake succeeded.
+async fn run_connection(shared: Arc<Shared>, stream: TcpStream, dialed: bool) -> Option<NodeId> {
+  let _ = stream.set_nodelay(true);
+  let (mut reader, mut writer) = stream.into_split();
+  let (peer, keys) = match timeout(
+    HANDSHAKE_TIMEOUT,
+    handshake(&shared, &mut reader, &mut writer, dialed),
+  )
+  .await
+  {
+    Ok(Ok(authenticated)) => authenticated,
+    Ok(Err(e)) => {
+      warn!(error = %e, "Cluster handshake failed");
+      return None;
+    }
+    Err(_) => {
+      warn!("Cluster handshake timed out");
+      return None;
+    }
+  };
+
+  let dialer = if dialed {
+    shared.node.clone()
+  } else {
+    peer.clone()
+  };
+  let link_id = shared.next_link_id.fetch_add(1, Ordering::Relaxed);
+  let (tx, mut rx) = mpsc::channel::<Vec<u8>>(LINK_QUEUE_SIZE);
+  let link = PeerLink {
+    id: link_id,
+    dialer,
+    tx,
+  };
+  let is_new = match shared.links.entry(peer.clone()) {
+    Entry::Occupied(mut existing) => {
+      // Both replicas dialed: keep the link dialed by the smaller ID.
+      // A reconnect from the same dialer replaces the stale link.
+      if link.dialer <= existing.get().dialer {
+        existing.insert(link);
+        false
+      } else {
+        debug!(peer = %peer, "Dropping duplicate cluster connection");
+        return Some(peer);
+      }
+    }
+    Entry::Vacant(slot) => {
+      slot.insert(link);
+      true
+    }
+  };
+  if is_new {
+    info!(peer = %peer, "Cluster peer connected");
+    let _ = shared.notices.send(BusNotice::PeerUp(peer.clone())).await;
+  }
+
+  let SessionKeys { send, recv } = keys;
+  let writer_task = tokio::spawn(async move {
+    let mut seq = 0u64;
+    while let Some(frame) = rx.recv().await {
+      let tag = frame_tag(&send, seq, &frame);
+      seq += 1;
+      if writer.write_all(&frame).await.is_err() || writer.write_all(&tag).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  let mut seq = 0u64;
+  loop {
+    let frame = tokio::select! {
+      () = shared.cancel.cancelled() => break,
+      frame = read_authenticated_frame(&mut reader, &recv, seq) => frame,
+    };
+    seq += 1;
+    match frame {
+      Ok(Frame::Event(event)) => {
+        // Awaiting here stops reading from the peer while the local
+        // replica is behind, pushing back through TCP.
+        let _ = shared
+          .notices
+          .send(BusNotice::Event {
+            from: peer.clone(),
+            event,
+          })
+          .await;
+      }
+      Ok(_) => {
+        warn!(peer = %peer, "Unexpected handshake frame from cluster peer");
+        break;
+      }
+      Err(e) => {
+        debug!(peer = %peer, error = %e, "Cluster connection closed");
+        break;
+      }
+    }
+  }
+
+  writer_task.abort();
+  if shared
+    .links
+    .remove_if(&peer, |_, link| link.id == link_id)
+    .is_some()
+  {
+    info!(peer = %peer, "Cluster peer disconnected");
+    let _ = shared.notices.send(BusNotice::PeerDown(peer.clone())).await;
+  }
+  Some(peer)
+}
+
+/// Exchange `Hello` and `Proof` frames; returns the authenticated
+/// peer's node ID and the keys for the rest of the connection.
+async fn handshake(
+  shared: &Shared,
+  reader: &mut OwnedReadHalf,
+  writer: &mut OwnedWriteHalf,
+  dialed: bool,
+) -> io::Result<(NodeId, SessionKeys)> {
+  let mut nonce = [0u8; 32];
+  OsRng.fill_bytes(&mut nonce);
+  writer
+    .write_all(&encode_frame(&Frame::Hello {
+      node: shared.node.clone(),
+      nonce,
+    }))
+    .await?;
+  let Frame::Hello {
+    node: peer,
+    nonce: peer_nonce,
+  } = read_frame(reader, MAX_HANDSHAKE_FRAME_BYTES).await?
+  else {
+    return Err(invalid_data("expected Hello"));
+  };
+  if peer == shared.node {
+    return Err(invalid_data("connected to self"));
+  }
+
+  let local = (&shared.node, &nonce);
+  let remote = (&peer, &peer_nonce);
+  let transcript = if dialed {
+    transcript(local, remote)
+  } else {
+    transcript(remote, local)
+  };
+  let (own_role, peer_role) = if dialed {
+    (Role::Dialer, Role::Listener)
+  } else {
+    (Role::Listener, Role::Dialer)
+  };
+
+  writer
+    .write_all(&encode_frame(&Frame::Proof {
+      mac: keyed(&shared.key, own_role.proof_label(), &transcript)
+        .finalize()
+        .into_bytes()
+        .to_vec(),
+    }))
+    .await?;
+  let Frame::Proof { mac } = read_frame(reader, MAX_HANDSHAKE_FRAME_BYTES).await? else {
+    return Err(invalid_data("expected Proof"));
+  };
+  keyed(&shared.key, peer_role.proof_label(), &transcript)
+    .verify_slice(&mac)
+    .map_err(|_| invalid_data("peer does not share the cluster secret"))?;
+
+  let session_key = |role: Role| -> [u8; 32] {
+    keyed(&shared.key, role.frame_label(), &transcript)
+      .finalize()
+      .into_bytes()
+      .into()
+  };
+  let keys = SessionKeys {
+    send: session_key(own_role),
+    recv: session_key(peer_role),
+  };
+  Ok((peer, keys))
+}
+
+// =============================================================================
+// Framing
+// =============================================================================
+
+pub(super) fn encode_frame(frame: &Frame) -> Vec<u8> {
+  let body = bitcode::encode(frame);
+  let mut out = Vec::with_capacity(4 + body.len());
+  out.extend_from_slice(&u32::try_from(body.len()).unwrap_or(u32::MAX).to_be_bytes());
+  out.extend_from_slice(&body);
+  out
+}
+
+/// Read one frame of at most `max_len` bytes, before authentication.
+pub(super) async fn read_frame(reader: &mut OwnedReadHalf, max_len: usize) -> io::Result<Frame> {
+  let body = read_body(reader, max_len).await?;
+  bitcode::decode(&body).map_err(|_| invalid_data("malformed frame"))
+}
+
+/// Read the `seq`-th frame after the handshake and check its tag.
+async fn read_authenticated_frame(
+  reader: &mut OwnedReadHalf,
+  key: &[u8; 32],
+  seq: u64,
+) -> io::Result<Frame> {
+  let body = read_body(reader, MAX_FRAME_BYTES).await?;
+  let mut tag = [0u8; TAG_BYTES];
+  reader.read_exact(&mut tag).await?;
+  let len = u32::try_from(body.len()).unwrap_or(u32::MAX).to_be_bytes();
+  let mut mac = frame_mac(key, seq);
+  mac.update(&len);
+  mac.update(&body);
+  mac
+    .verify_slice(&tag)
+    .map_err(|_| invalid_data("frame failed authentication"))?;
+  bitcode::decode(&body).map_err(|_| invalid_data("malformed frame"))
+}
+
+async fn read_body(reader: &mut OwnedReadHalf, max_len: usize) -> io::Result<Vec<u8>> {
+  let mut len = [0u8; 4];
+  reader.read_exact(&mut len).await?;
+  let len = u32::from_be_bytes(len) as usize;
+  if len > max_len {
+    return Err(invalid_data("frame too large"));
+  }
+  let mut body = vec![0u8; len];
+  reader.read_exact(&mut body).await?;
+  Ok(body)
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+// =============================================================================
+// Authentication
+// =============================================================================
+
+/// Derive the handshake key from the JWT secret.
+fn derive_key(jwt_secret: &str) -> [u8; 32] {
+  let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(jwt_secret.as_bytes())
+    .expect("HMAC accepts keys of any length");
+  mac.update(SECRET_CONTEXT);
+  mac.finalize().into_bytes().into()
+}
+
+/// Side of a connection; each proves and sends under its own label.
+#[derive(Debug, Clone, Copy)]
+enum Role {
+  Dialer,
+  Listener,
+}
+
+impl Role {
+  const fn proof_label(self) -> &'static [u8] {
+    match self {
+      Self::Dialer => b"proof dialer",
+      Self::Listener => b"proof listener",
+    }
+  }
+
+  const fn frame_label(self) -> &'static [u8] {
+    match self {
+      Self::Dialer => b"frames from dialer",
+      Self::Listener => b"frames from listener",
+    }
+  }
+}
+
+/// Handshake transcript: both node IDs (length-prefixed) and nonces,
+/// dialer first.
+fn transcript(dialer: (&NodeId, &[u8; 32]), listener: (&NodeId, &[u8; 32])) -> Vec<u8> {
+  let mut out = Vec::new();
+  for (node, nonce) in [dialer, listener] {
+    let id = node.0.as_bytes();
+    out.extend_from_slice(&u32::try_from(id.len()).unwrap_or(u32::MAX).to_be_bytes());
+    out.extend_from_slice(id);
+    out.extend_from_slice(nonce);
+  }
+  out
+}
+
+/// HMAC under `key` over `label` and `data`.
+fn keyed(key: &[u8; 32], label: &[u8], data: &[u8]) -> Hmac<Sha256> {
+  let mut mac =
+    <Hmac<Sha256> as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+  mac.update(label);
+  mac.update(data);
+  mac
+}
+
+/// MAC of the `seq`-th frame in one direction, before the frame bytes.
+fn frame_mac(key: &[u8; 32], seq: u64) -> Hmac<Sha256> {
+  keyed(key, b"frame", &seq.to_be_bytes())
+}
+
+/// Tag appended to an encoded frame (length prefix included).
+fn frame_tag(key: &[u8; 32], seq: u64, frame: &[u8]) -> [u8; TAG_BYTES] {
+  let mut mac = frame_mac(key, seq);
+  mac.update(frame);
+  mac.finalize().into_bytes().into()
+}
@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use message::types::UserStatus;
+use tokio::time::timeout;
+
+use super::tcp::{Frame, MAX_HANDSHAKE_FRAME_BYTES, encode_frame, read_frame};
+use super::*;
+use crate::config::ClusterConfig;
+
+fn user(n: u64) -> UserInfo {
+  UserInfo {
+    user_id: UserId::from(n),
+    username: format!("user{n}"),
+    nickname: format!("User {n}"),
+    status: UserStatus::Online,
+    avatar_url: None,
+    bio: String::new(),
+    created_at_nanos: 0,
+    last_seen_nanos: 0,
+  }
+}
+
+fn node(name: &str) -> NodeId {
+  NodeId(name.to_string())
+}
+
+async fn next(rx: &mut mpsc::Receiver<BusNotice>) -> BusNotice {
+  timeout(Duration::from_secs(5), rx.recv())
+    .await
+    .expect("Timed out waiting for bus notice")
+    .expect("Bus closed")
+}
+
+async fn next_event(rx: &mut mpsc::Receiver<BusNotice>) -> (NodeId, ClusterEvent) {
+  loop {
+    if let BusNotice::Event { from, event } = next(rx).await {
+      return (from, event);
+    }
+  }
+}
+
+fn tcp_config(name: &str, peers: Vec<String>) -> ClusterConfig {
+  ClusterConfig {
+    node_id: name.to_string(),
+    listen: "127.0.0.1:0".parse().unwrap(),
+    peers,
+  }
+}
+
+// =============================================================================
+// In-process bus
+// =============================================================================
+
+#[tokio::test]
+async fn test_in_process_peer_up_and_down() {
+  let network = InProcessNetwork::new();
+  let a = network.join("a");
+  let mut a_rx = a.subscribe().unwrap();
+  assert!(a.subscribe().is_none());
+
+  let b = network.join("b");
+  let mut b_rx = b.subscribe().unwrap();
+  assert_eq!(next(&mut a_rx).await, BusNotice::PeerUp(node("b")));
+  assert_eq!(next(&mut b_rx).await, BusNotice::PeerUp(node("a")));
+
+  drop(b);
+  assert_eq!(next(&mut a_rx).await, BusNotice::PeerDown(node("b")));
+}
+
+#[tokio::test]
+async fn test_in_process_publish_and_send() {
+  let network = InProcessNetwork::new();
+  let a = network.join("a");
+  let b = network.join("b");
+  let c = network.join("c");
+  let mut b_rx = b.subscribe().unwrap();
+  let mut c_rx = c.subscribe().unwrap();
+
+  a.publish(ClusterEvent::UserOffline(UserId::from(1)));
+  for rx in [&mut b_rx, &mut c_rx] {
+    assert_eq!(
+      next_event(rx).await,
+      (node("a"), ClusterEvent::UserOffline(UserId::from(1)))
+    );
+  }
+
+  assert!(a.send(&node("c"), ClusterEvent::UserOffline(UserId::from(2))));
+  assert_eq!(
+    next_event(&mut c_rx).await,
+    (node("a"), ClusterEvent::UserOffline(UserId::from(2)))
+  );
+  assert!(b_rx.try_recv().is_err());
+
+  assert!(!a.send(&node("a"), ClusterEvent::UserOffline(UserId::from(3))));
+  assert!(!a.send(&node("z"), ClusterEvent::UserOffline(UserId::from(3))));
+}
+
+// =============================================================================
+// Remote presence
+// =============================================================================
+
+#[tokio::test]
+async fn test_remote_user_lifecycle() {
+  let network = InProcessNetwork::new();
+  let a = Arc::new(network.join("a"));
+  let b = network.join("b");
+  let mut b_rx = b.subscribe().unwrap();
+  let state = ClusterState::new(a, 8);
+
+  assert!(state.add_remote_user(node("b"), user(1)));
+  let mut renamed = user(1);
+  renamed.nickname = "Renamed".to_string();
+  assert!(!state.add_remote_user(node("b"), renamed));
+  assert_eq!(state.remote_users()[0].nickname, "Renamed");
+  assert_eq!(state.remote_node(&UserId::from(1)), Some(node("b")));
+
+  // Frames queued for a remote user are delivered to their replica
+  let sender = state.remote_sender(&UserId::from(1)).unwrap();
+  sender.send(vec![1, 2, 3]).await.unwrap();
+  assert_eq!(
+    next_event(&mut b_rx).await,
+    (
+      node("a"),
+      ClusterEvent::Deliver {
+        to: UserId::from(1),
+        frame: vec![1, 2, 3],
+      }
+    )
+  );
+
+  // Only the replica holding the user can take them offline
+  assert!(!state.remove_remote_user(&node("c"), &UserId::from(1)));
+  assert!(state.remove_remote_user(&node("b"), &UserId::from(1)));
+  assert!(state.remote_sender(&UserId::from(1)).is_none());
+}
+
+#[tokio::test]
+async fn test_remove_node_forgets_its_users() {
+  let network = InProcessNetwork::new();
+  let state = ClusterState::new(Arc::new(network.join("a")), 8);
+  state.add_remote_user(node("b"), user(1));
+  state.add_remote_user(node("b"), user(2));
+  state.add_remote_user(node("c"), user(3));
+
+  let mut removed = state.remove_node(&node("b"));
+  removed.sort_by_key(ToString::to_string);
+  let mut expected = vec![UserId::from(1), UserId::from(2)];
+  expected.sort_by_key(ToString::to_string);
+  assert_eq!(removed, expected);
+  assert_eq!(state.remote_users(), vec![user(3)]);
+}
+
+// =============================================================================
+// TCP bus
+// =============================================================================
+
+#[tokio::test]
+async fn test_tcp_bus_connects_and_exchanges_events() {
+  let a = TcpBus::start(&tcp_config("a", Vec::new()), "secret")
+    .await
+    .unwrap();
+  let mut a_rx = a.subscribe().unwrap();
+  let b = TcpBus::start(&tcp_config("b", vec![a.local_addr().to_string()]), "secret")
+    .await
+    .unwrap();
+  let mut b_rx = b.subscribe().unwrap();
+
+  assert_eq!(next(&mut a_rx).await, BusNotice::PeerUp(node("b")));
+  assert_eq!(next(&mut b_rx).await, BusNotice::PeerUp(node("a")));
+
+  b.publish(ClusterEvent::UserOnline(user(7)));
+  assert_eq!(
+    next_event(&mut a_rx).await,
+    (node("b"), ClusterEvent::UserOnline(user(7)))
+  );
+  assert!(a.send(&node("b"), ClusterEvent::UserOffline(UserId::from(7))));
+  assert_eq!(
+    next_event(&mut b_rx).await,
+    (node("a"), ClusterEvent::UserOffline(UserId::from(7)))
+  );
+
+  drop(b);
+  assert_eq!(next(&mut a_rx).await, BusNotice::PeerDown(node("b")));
+}
+
+#[tokio::test]
+async fn test_tcp_bus_rejects_wrong_secret() {
+  let a = TcpBus::start(&tcp_config("a", Vec::new()), "secret")
+    .await
+    .unwrap();
+  let mut a_rx = a.subscribe().unwrap();
+  let b = TcpBus::start(
+    &tcp_config("b", vec![a.local_addr().to_string()]),
+    "other-secret",
+  )
+  .await
+  .unwrap();
+
+  tokio::time::sleep(Duration::from_millis(500)).await;
+  assert!(a.peers().is_empty());
+  assert!(b.peers().is_empty());
+  assert!(a_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_tcp_bus_rejects_relayed_proof() {
+  use tokio::io::AsyncWriteExt;
+  use tokio::net::TcpStream;
+
+  let a = TcpBus::start(&tcp_config("a", Vec::new()), "secret")
+    .await
+    .unwrap();
+  let b = TcpBus::start(&tcp_config("b", Vec::new()), "secret")
+    .await
+    .unwrap();
+
+  // Claim to be `b` towards `a` and learn a's nonce.
+  let (mut a_read, mut a_write) = TcpStream::connect(a.local_addr())
+    .await
+    .unwrap()
+    .into_split();
+  let Frame::Hello { nonce: a_nonce, .. } = read_frame(&mut a_read, MAX_HANDSHAKE_FRAME_BYTES)
+    .await
+    .unwrap()
+  else {
+    panic!("expected Hello from a");
+  };
+  a_write
+    .write_all(&encode_frame(&Frame::Hello {
+      node: node("b"),
+      nonce: [7; 32],
+    }))
+    .await
+    .unwrap();
+
+  // Get `b` to prove itself over a's nonce, then relay that proof.
+  let (mut b_read, mut b_write) = TcpStream::connect(b.local_addr())
+    .await
+    .unwrap()
+    .into_split();
+  b_write
+    .write_all(&encode_frame(&Frame::Hello {
+      node: node("z"),
+      nonce: a_nonce,
+    }))
+    .await
+    .unwrap();
+  let _ = read_frame(&mut b_read, MAX_HANDSHAKE_FRAME_BYTES).await;
+  let Frame::Proof { mac } = read_frame(&mut b_read, MAX_HANDSHAKE_FRAME_BYTES)
+    .await
+    .unwrap()
+  else {
+    panic!("expected Proof from b");
+  };
+  a_write
+    .write_all(&encode_frame(&Frame::Proof { mac }))
+    .await
+    .unwrap();
+
+  tokio::time::sleep(Duration::from_millis(300)).await;
+  assert!(a.peers().is_empty());
+}
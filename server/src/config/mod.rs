@@ -4,10 +4,13 @@
 //! supporting environment variable based configuration with sensible defaults.
 
 use std::env;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::storage::StorageBackend;
+
 /// Log rotation strategy.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogRotation {
@@ -40,6 +43,163 @@ pub struct TlsConfig {
   pub key_path: PathBuf,
 }
 
+/// Embedded TURN relay configuration.
+///
+/// The relay shares the embedded STUN socket (see
+/// [`Config::stun_port`]) and is only started when both are enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnConfig {
+  /// Realm used for long-term credential authentication.
+  pub realm: String,
+  /// Address advertised to clients as the relayed transport address.
+  pub public_ip: IpAddr,
+  /// UDP ports relay sockets are allocated from.
+  pub relay_ports: RangeInclusive<u16>,
+  /// Per-user relay bandwidth cap in kilobits per second (`0` = unlimited).
+  pub user_bandwidth_kbps: u32,
+  /// Lifetime of the credentials minted for each authenticated client.
+  pub credential_ttl: Duration,
+  /// Peer networks the relay refuses to reach, so an authenticated
+  /// client cannot use it to probe the server's own network. Defaults
+  /// to [`DEFAULT_DENIED_PEER_NETWORKS`].
+  pub denied_peer_ips: Vec<IpNetwork>,
+  /// Exceptions to [`Self::denied_peer_ips`].
+  pub allowed_peer_ips: Vec<IpNetwork>,
+}
+
+/// Networks a TURN relay must not reach by default: "this network",
+/// private, shared, loopback, link-local, benchmarking, multicast and
+/// reserved ranges of both families (like coturn's `denied-peer-ip`).
+pub const DEFAULT_DENIED_PEER_NETWORKS: &[&str] = &[
+  "0.0.0.0/8",
+  "10.0.0.0/8",
+  "100.64.0.0/10",
+  "127.0.0.0/8",
+  "169.254.0.0/16",
+  "172.16.0.0/12",
+  "192.0.0.0/24",
+  "192.168.0.0/16",
+  "198.18.0.0/15",
+  "224.0.0.0/4",
+  "240.0.0.0/4",
+  "::/128",
+  "::1/128",
+  "64:ff9b::/96",
+  "fc00::/7",
+  "fe80::/10",
+  "ff00::/8",
+];
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8`. A bare
+/// address is a single-host network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+  addr: IpAddr,
+  prefix: u8,
+}
+
+impl IpNetwork {
+  /// Whether `ip` lies in this network. IPv4-mapped IPv6 addresses are
+  /// matched as the IPv4 address they carry.
+  #[must_use]
+  pub fn contains(&self, ip: IpAddr) -> bool {
+    match (self.addr, ip.to_canonical()) {
+      (IpAddr::V4(net), IpAddr::V4(ip)) => {
+        let mask = u32::MAX
+          .checked_shl(32 - u32::from(self.prefix))
+          .unwrap_or(0);
+        u32::from(net) & mask == u32::from(ip) & mask
+      }
+      (IpAddr::V6(net), IpAddr::V6(ip)) => {
+        let mask = u128::MAX
+          .checked_shl(128 - u32::from(self.prefix))
+          .unwrap_or(0);
+        u128::from(net) & mask == u128::from(ip) & mask
+      }
+      _ => false,
+    }
+  }
+
+  /// Parse a comma-separated list, skipping malformed entries.
+  pub(crate) fn parse_list(s: &str) -> Vec<Self> {
+    s.split(',')
+      .map(str::trim)
+      .filter_map(|part| part.parse().ok())
+      .collect()
+  }
+}
+
+impl std::str::FromStr for IpNetwork {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (addr, prefix) = s.split_once('/').unwrap_or((s, ""));
+    let addr: IpAddr = addr
+      .parse()
+      .map_err(|_| format!("invalid network address: {s}"))?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = if prefix.is_empty() {
+      max
+    } else {
+      prefix
+        .parse()
+        .ok()
+        .filter(|prefix| *prefix <= max)
+        .ok_or_else(|| format!("invalid network prefix: {s}"))?
+    };
+    Ok(Self { addr, prefix })
+  }
+}
+
+/// Selective forwarding unit (SFU) configuration.
+///
+/// When present, rooms created with more than 8 participants skip the
+/// browser mesh and relay media through [`crate::sfu`] instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfuConfig {
+  /// Largest `max_participants` an SFU room may be created with.
+  pub max_room_members: u8,
+  /// Address advertised in the relay's ICE candidates when the server
+  /// sits behind a 1:1 NAT. `None` advertises the interface addresses.
+  pub public_ip: Option<IpAddr>,
+  /// UDP ports media sockets are bound to. `None` lets the OS choose.
+  pub udp_ports: Option<RangeInclusive<u16>>,
+}
+
+/// Offline mailbox configuration.
+///
+/// When present, clients may leave end-to-end encrypted messages on
+/// the server for users they cannot reach over a DataChannel (see
+/// [`crate::mailbox`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailboxConfig {
+  /// How long an undelivered message is kept.
+  pub ttl: Duration,
+  /// Messages stored per recipient device.
+  pub max_messages_per_user: usize,
+  /// Total blob bytes stored per recipient device.
+  pub max_bytes_per_user: usize,
+  /// Messages one sender may have stored for the same recipient
+  /// device, so a single sender cannot fill someone's mailbox.
+  pub max_messages_per_sender: usize,
+  /// Largest accepted blob.
+  pub max_blob_bytes: usize,
+}
+
+/// Cluster bus configuration.
+///
+/// When present, this replica joins the other signaling servers listed
+/// in `peers` over TCP (see [`crate::cluster`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterConfig {
+  /// Name of this replica. Must be unique within the cluster.
+  pub node_id: String,
+  /// Address the cluster listener binds to.
+  pub listen: SocketAddr,
+  /// `host:port` cluster addresses of the other replicas.
+  pub peers: Vec<String>,
+}
+
 /// Server configuration loaded from environment variables.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -50,6 +210,34 @@ pub struct Config {
   // Security configuration
   /// JWT secret key for token signing and verification.
   pub jwt_secret: String,
+  /// Lifetime of the JWT access tokens returned by the auth endpoints.
+  ///
+  /// Kept short so a leaked token is only useful briefly; clients
+  /// renew it through `POST /api/refresh`. Configured via
+  /// `ACCESS_TOKEN_TTL_SECS` (default 15 minutes).
+  pub access_token_ttl: Duration,
+  /// Lifetime of a refresh token. Every refresh rotates the token and
+  /// restarts this window. Configured via `REFRESH_TOKEN_TTL_SECS`
+  /// (default 30 days).
+  pub refresh_token_ttl: Duration,
+
+  /// Static bearer token accepted by the `/api/admin/*` endpoints.
+  ///
+  /// `None` (the default) leaves the admin API reachable only by
+  /// users listed in [`Self::admin_usernames`]. Configured via
+  /// `ADMIN_TOKEN`.
+  pub admin_token: Option<String>,
+  /// Usernames whose login tokens grant access to `/api/admin/*`.
+  ///
+  /// Configured via the comma-separated `ADMIN_USERNAMES` variable.
+  pub admin_usernames: Vec<String>,
+
+  /// Serve Prometheus metrics on `GET /metrics`.
+  ///
+  /// Off by default because the endpoint is unauthenticated; enable
+  /// it with `METRICS_ENABLED=true` when the port is only reachable
+  /// by the scraper.
+  pub metrics_enabled: bool,
 
   // ICE configuration
   /// STUN/TURN servers configuration for WebRTC.
@@ -75,6 +263,45 @@ pub struct Config {
   /// (`STUN_PORT=0` ⇒ disabled).
   pub stun_port: Option<u16>,
 
+  /// Embedded TURN relay, served on the STUN port.
+  ///
+  /// `None` (the default) leaves relaying to an external TURN server
+  /// listed in [`Self::ice_servers`]. When set, every `AuthSuccess`
+  /// carries a `turn:` entry with short-lived credentials minted from
+  /// [`Self::jwt_secret`].
+  ///
+  /// Enabled with `TURN_ENABLED=true`; tuned with `TURN_REALM`,
+  /// `TURN_PUBLIC_IP`, `TURN_RELAY_PORT_MIN`/`TURN_RELAY_PORT_MAX`,
+  /// `TURN_USER_BANDWIDTH_KBPS`, `TURN_CREDENTIAL_TTL_SECS` and the
+  /// comma-separated CIDR lists `TURN_DENIED_PEER_IPS` and
+  /// `TURN_ALLOWED_PEER_IPS`.
+  pub turn: Option<TurnConfig>,
+
+  /// Server-side media relay for rooms larger than the 8-peer mesh.
+  ///
+  /// `None` (the default) keeps every room on the mesh topology.
+  /// Enabled with `SFU_ENABLED=true`; tuned with
+  /// `SFU_MAX_ROOM_MEMBERS`, `SFU_PUBLIC_IP` and
+  /// `SFU_UDP_PORT_MIN`/`SFU_UDP_PORT_MAX`.
+  pub sfu: Option<SfuConfig>,
+
+  /// Store-and-forward of sealed messages for offline users.
+  ///
+  /// `None` (the default) leaves chat strictly peer to peer. Enabled
+  /// with `MAILBOX_ENABLED=true`; tuned with `MAILBOX_TTL_SECS`,
+  /// `MAILBOX_MAX_MESSAGES`, `MAILBOX_MAX_BYTES`,
+  /// `MAILBOX_MAX_MESSAGES_PER_SENDER` and `MAILBOX_MAX_BLOB_BYTES`.
+  pub mailbox: Option<MailboxConfig>,
+
+  /// Bus to the other replicas of a horizontally scaled deployment.
+  ///
+  /// `None` (the default) runs a standalone server. Enabled with
+  /// `CLUSTER_ENABLED=true`; configured with `CLUSTER_NODE_ID`,
+  /// `CLUSTER_LISTEN` and the comma-separated `CLUSTER_PEERS`. Peers
+  /// authenticate each other with [`Self::jwt_secret`], which every
+  /// replica already has to share.
+  pub cluster: Option<ClusterConfig>,
+
   // TLS configuration
   /// Optional TLS configuration for secure connections.
   pub tls: Option<TlsConfig>,
@@ -110,6 +337,15 @@ pub struct Config {
   pub max_message_size: usize,
   /// Maximum pending messages in the send queue per connection.
   pub send_queue_size: usize,
+
+  // Persistence configuration
+  /// Backend for durable user and room state.
+  ///
+  /// Configured via `STORAGE_BACKEND` (`memory` — the default — or
+  /// `file`) and `STORAGE_PATH` (log location for the file backend,
+  /// default `./data/store.jsonl`). The memory backend loses every
+  /// account on restart and is meant for development and tests.
+  pub storage: StorageBackend,
 }
 
 impl Config {
@@ -130,6 +366,27 @@ impl Config {
     // Security configuration
     let jwt_secret =
       env::var("JWT_SECRET").unwrap_or_else(|_| "dev-secret-change-in-production".to_string());
+    let access_token_ttl = Duration::from_secs(env_parse("ACCESS_TOKEN_TTL_SECS", 15 * 60));
+    let refresh_token_ttl =
+      Duration::from_secs(env_parse("REFRESH_TOKEN_TTL_SECS", 30 * 24 * 60 * 60));
+
+    // Admin API access. An empty token is treated as unset so a
+    // blank `ADMIN_TOKEN=` line never opens the admin surface.
+    let admin_token = env::var("ADMIN_TOKEN")
+      .ok()
+      .map(|s| s.trim().to_string())
+      .filter(|s| !s.is_empty());
+    let admin_usernames = env::var("ADMIN_USERNAMES")
+      .map(|s| {
+        s.split(',')
+          .map(str::trim)
+          .filter(|part| !part.is_empty())
+          .map(String::from)
+          .collect()
+      })
+      .unwrap_or_default();
+
+    let metrics_enabled = env_flag("METRICS_ENABLED");
 
     // Embedded STUN port. `STUN_PORT=0` disables the embedded
     // service so an external coturn deployment can take over.
@@ -142,6 +399,71 @@ impl Config {
       Err(_) => Some(3478),
     };
 
+    // Embedded TURN relay (opt-in).
+    let turn = env_flag("TURN_ENABLED").then(|| {
+      let port_min = env_parse("TURN_RELAY_PORT_MIN", 49152u16);
+      let port_max = env_parse("TURN_RELAY_PORT_MAX", 65535u16).max(port_min);
+      TurnConfig {
+        realm: env::var("TURN_REALM").unwrap_or_else(|_| "webrtc-chat".to_string()),
+        public_ip: env::var("TURN_PUBLIC_IP")
+          .ok()
+          .and_then(|s| s.trim().parse().ok())
+          .unwrap_or_else(|| IpAddr::V4(crate::stun::detect_lan_ipv4())),
+        relay_ports: port_min..=port_max,
+        user_bandwidth_kbps: env_parse("TURN_USER_BANDWIDTH_KBPS", 2048),
+        credential_ttl: Duration::from_secs(env_parse("TURN_CREDENTIAL_TTL_SECS", 86400)),
+        // Setting `TURN_DENIED_PEER_IPS` replaces the default list; an
+        // empty value denies nothing.
+        denied_peer_ips: env::var("TURN_DENIED_PEER_IPS").map_or_else(
+          |_| IpNetwork::parse_list(&DEFAULT_DENIED_PEER_NETWORKS.join(",")),
+          |s| IpNetwork::parse_list(&s),
+        ),
+        allowed_peer_ips: env::var("TURN_ALLOWED_PEER_IPS")
+          .map(|s| IpNetwork::parse_list(&s))
+          .unwrap_or_default(),
+      }
+    });
+
+    // Selective forwarding unit (opt-in).
+    let sfu = env_flag("SFU_ENABLED").then(|| SfuConfig {
+      max_room_members: env_parse("SFU_MAX_ROOM_MEMBERS", 32u8).max(9),
+      public_ip: env::var("SFU_PUBLIC_IP")
+        .ok()
+        .and_then(|s| s.trim().parse().ok()),
+      udp_ports: env::var("SFU_UDP_PORT_MIN")
+        .ok()
+        .and_then(|s| s.trim().parse::<u16>().ok())
+        .map(|min| min..=env_parse("SFU_UDP_PORT_MAX", u16::MAX).max(min)),
+    });
+
+    // Offline mailbox (opt-in).
+    let mailbox = env_flag("MAILBOX_ENABLED").then(|| MailboxConfig {
+      ttl: Duration::from_secs(env_parse("MAILBOX_TTL_SECS", 7 * 24 * 3600)),
+      max_messages_per_user: env_parse("MAILBOX_MAX_MESSAGES", 200),
+      max_bytes_per_user: env_parse("MAILBOX_MAX_BYTES", 4 * 1024 * 1024),
+      max_messages_per_sender: env_parse("MAILBOX_MAX_MESSAGES_PER_SENDER", 50),
+      max_blob_bytes: env_parse("MAILBOX_MAX_BLOB_BYTES", 64 * 1024),
+    });
+
+    // Cluster bus (opt-in).
+    let cluster = env_flag("CLUSTER_ENABLED").then(|| ClusterConfig {
+      node_id: env::var("CLUSTER_NODE_ID")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+      listen: env_parse("CLUSTER_LISTEN", SocketAddr::from(([0, 0, 0, 0], 7946))),
+      peers: env::var("CLUSTER_PEERS")
+        .map(|s| {
+          s.split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(String::from)
+            .collect()
+        })
+        .unwrap_or_default(),
+    });
+
     // ICE servers configuration
     //
     // `STUN_TURN_SERVERS` is a comma-separated list of ICE URLs.
@@ -238,11 +560,34 @@ impl Config {
       .map(|s| s.parse().unwrap_or(256))
       .unwrap_or(256);
 
+    // Persistence configuration
+    let storage = match env::var("STORAGE_BACKEND")
+      .unwrap_or_default()
+      .trim()
+      .to_lowercase()
+      .as_str()
+    {
+      "file" => StorageBackend::File {
+        path: env::var("STORAGE_PATH")
+          .map_or_else(|_| PathBuf::from("./data/store.jsonl"), PathBuf::from),
+      },
+      _ => StorageBackend::Memory,
+    };
+
     Ok(Self {
       addr,
       jwt_secret,
+      access_token_ttl,
+      refresh_token_ttl,
+      admin_token,
+      admin_usernames,
+      metrics_enabled,
       ice_servers,
       stun_port,
+      turn,
+      sfu,
+      mailbox,
+      cluster,
       tls,
       static_dir,
       stickers_dir,
@@ -257,10 +602,25 @@ impl Config {
       heartbeat_timeout: Duration::from_secs(heartbeat_timeout_secs),
       max_message_size,
       send_queue_size,
+      storage,
     })
   }
 }
 
+/// Whether a boolean environment variable is set to a truthy value.
+fn env_flag(key: &str) -> bool {
+  env::var(key).is_ok_and(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Parse an environment variable, falling back to `default` when it
+/// is unset or malformed.
+fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+  env::var(key)
+    .ok()
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or(default)
+}
+
 impl Default for Config {
   fn default() -> Self {
     Self::from_env().expect("failed to create default config")
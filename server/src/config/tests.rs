@@ -32,3 +32,82 @@ fn test_default_config() {
   assert_eq!(config.heartbeat_timeout, Duration::from_secs(60));
   assert_eq!(config.send_queue_size, 256);
 }
+
+#[test]
+fn test_default_storage_is_memory() {
+  let config = Config::default();
+  assert_eq!(config.storage, StorageBackend::Memory);
+}
+
+#[test]
+fn test_turn_disabled_by_default() {
+  let config = Config::default();
+  assert!(config.turn.is_none());
+}
+
+#[test]
+fn test_ip_network_contains() {
+  let private: IpNetwork = "10.0.0.0/8".parse().unwrap();
+  assert!(private.contains("10.1.2.3".parse().unwrap()));
+  assert!(!private.contains("11.0.0.1".parse().unwrap()));
+  // IPv4-mapped IPv6 addresses match their IPv4 network.
+  assert!(private.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+  let host: IpNetwork = "::1".parse().unwrap();
+  assert!(host.contains("::1".parse().unwrap()));
+  assert!(!host.contains("::2".parse().unwrap()));
+
+  let everything: IpNetwork = "0.0.0.0/0".parse().unwrap();
+  assert!(everything.contains("203.0.113.9".parse().unwrap()));
+
+  assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+  assert!("not-an-ip/8".parse::<IpNetwork>().is_err());
+}
+
+#[test]
+fn test_default_denied_peer_networks_parse() {
+  let parsed = IpNetwork::parse_list(&DEFAULT_DENIED_PEER_NETWORKS.join(","));
+  assert_eq!(parsed.len(), DEFAULT_DENIED_PEER_NETWORKS.len());
+}
+
+#[test]
+fn test_admin_api_closed_by_default() {
+  let config = Config::default();
+  assert!(config.admin_token.is_none());
+  assert!(config.admin_usernames.is_empty());
+}
+
+#[test]
+fn test_metrics_disabled_by_default() {
+  let config = Config::default();
+  assert!(!config.metrics_enabled);
+}
+
+#[test]
+fn test_sfu_disabled_by_default() {
+  let config = Config::default();
+  assert!(config.sfu.is_none());
+}
+
+#[test]
+fn test_mailbox_disabled_by_default() {
+  let config = Config::default();
+  assert!(config.mailbox.is_none());
+}
+
+#[test]
+fn test_cluster_disabled_by_default() {
+  let config = Config::default();
+  assert!(config.cluster.is_none());
+}
+
+#[test]
+fn test_default_token_lifetimes() {
+  let config = Config::default();
+  assert_eq!(config.access_token_ttl, Duration::from_secs(15 * 60));
+  assert_eq!(
+    config.refresh_token_ttl,
+    Duration::from_secs(30 * 24 * 60 * 60)
+  );
+  assert!(config.access_token_ttl < config.refresh_token_ttl);
+}
@@ -15,9 +15,9 @@ mod types;
 pub use rate_limit::UserRateLimit;
 pub use state::DiscoveryState;
 pub use types::{
-  INVITATION_TIMEOUT, INVITE_RATE_LIMIT_PER_HOUR, INVITE_RATE_LIMIT_PER_MINUTE, InvitationError,
-  InvitationId, MAX_UNANSWERED_INVITATIONS_PER_TARGET, MultiInviteAcceptResult, MultiInviteState,
-  MultiInviteStats, PendingInvitation, SdpNegotiationState,
+  DiscoveryStats, INVITATION_TIMEOUT, INVITE_RATE_LIMIT_PER_HOUR, INVITE_RATE_LIMIT_PER_MINUTE,
+  InvitationError, InvitationId, MAX_UNANSWERED_INVITATIONS_PER_TARGET, MultiInviteAcceptResult,
+  MultiInviteState, MultiInviteStats, PendingInvitation, SdpNegotiationState,
 };
 
 // =============================================================================
@@ -1,6 +1,7 @@
 //! Discovery state manager for handling invitations, peers, and SDP negotiations.
 
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use dashmap::DashMap;
 use message::RoomId;
@@ -10,8 +11,9 @@ use tracing::{debug, info};
 
 use super::rate_limit::UserRateLimit;
 use super::types::{
-  InvitationError, InvitationId, MAX_UNANSWERED_INVITATIONS_PER_TARGET, MultiInviteAcceptResult,
-  MultiInviteState, MultiInviteStats, PendingInvitation, SdpNegotiationState,
+  DiscoveryStats, InvitationError, InvitationId, MAX_UNANSWERED_INVITATIONS_PER_TARGET,
+  MultiInviteAcceptResult, MultiInviteState, MultiInviteStats, PendingInvitation,
+  SdpNegotiationState,
 };
 
 // =============================================================================
@@ -33,6 +35,10 @@ pub struct DiscoveryState {
   active_peers: DashMap<UserId, HashSet<UserId>>,
   /// SDP negotiation state: (from_user_id, to_user_id) -> negotiation state.
   sdp_negotiations: DashMap<(UserId, UserId), SdpNegotiationState>,
+  /// Invitations rejected by the rate limiter.
+  rate_limit_hits: AtomicU64,
+  /// SDP negotiations removed by timeout cleanup.
+  sdp_timeouts: AtomicU64,
 }
 
 impl DiscoveryState {
@@ -46,6 +52,20 @@ impl DiscoveryState {
       multi_invites: DashMap::new(),
       active_peers: DashMap::new(),
       sdp_negotiations: DashMap::new(),
+      rate_limit_hits: AtomicU64::new(0),
+      sdp_timeouts: AtomicU64::new(0),
+    }
+  }
+
+  /// Snapshot of the discovery counters.
+  #[must_use]
+  pub fn stats(&self) -> DiscoveryStats {
+    DiscoveryStats {
+      pending_invitations: self.pending_invitations.len(),
+      multi_invites: self.multi_invites.len(),
+      sdp_negotiations: self.sdp_negotiations.len(),
+      rate_limit_hits: self.rate_limit_hits.load(Ordering::Relaxed),
+      sdp_timeouts: self.sdp_timeouts.load(Ordering::Relaxed),
     }
   }
 
@@ -56,7 +76,11 @@ impl DiscoveryState {
   /// Check if a user can send an invitation (rate limiting).
   pub fn can_send_invitation(&self, from: &UserId) -> bool {
     let mut rate_limit = self.rate_limits.entry(from.clone()).or_default();
-    rate_limit.can_send()
+    let allowed = rate_limit.can_send();
+    if !allowed {
+      self.rate_limit_hits.fetch_add(1, Ordering::Relaxed);
+    }
+    allowed
   }
 
   /// Get remaining invitation quota for a user.
@@ -265,6 +289,18 @@ impl DiscoveryState {
       .count()
   }
 
+  /// Get every pending invitation, oldest first.
+  #[must_use]
+  pub fn all_pending_invitations(&self) -> Vec<PendingInvitation> {
+    let mut invitations: Vec<_> = self
+      .pending_invitations
+      .iter()
+      .map(|entry| entry.value().clone())
+      .collect();
+    invitations.sort_by_key(|inv| inv.created_at);
+    invitations
+  }
+
   /// Get all pending invitations sent by a user.
   #[must_use]
   pub fn get_pending_sent(&self, from: &UserId) -> Vec<PendingInvitation> {
@@ -716,7 +752,9 @@ impl DiscoveryState {
       .collect();
 
     for key in keys_to_remove {
-      self.sdp_negotiations.remove(&key);
+      if self.sdp_negotiations.remove(&key).is_some() {
+        self.sdp_timeouts.fetch_add(1, Ordering::Relaxed);
+      }
       debug!(
         from = %key.0,
         to = %key.1,
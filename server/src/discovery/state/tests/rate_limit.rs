@@ -17,3 +17,23 @@ fn test_get_remaining_quota_initially_full() {
   assert_eq!(minute, INVITE_RATE_LIMIT_PER_MINUTE);
   assert_eq!(hour, INVITE_RATE_LIMIT_PER_HOUR);
 }
+
+#[test]
+fn test_rate_limit_hits_are_counted() {
+  let state = DiscoveryState::new();
+  let from = UserId::new();
+  for _ in 0..INVITE_RATE_LIMIT_PER_MINUTE {
+    state
+      .send_invitation(&create_invite(from.clone(), UserId::new()))
+      .unwrap();
+  }
+  assert_eq!(state.stats().rate_limit_hits, 0);
+
+  assert_eq!(
+    state.send_invitation(&create_invite(from, UserId::new())),
+    Err(InvitationError::RateLimitExceeded)
+  );
+  let stats = state.stats();
+  assert_eq!(stats.rate_limit_hits, 1);
+  assert_eq!(stats.pending_invitations, INVITE_RATE_LIMIT_PER_MINUTE);
+}
@@ -228,6 +228,21 @@ pub struct MultiInviteStats {
   pub room_id: Option<RoomId>,
 }
 
+/// Point-in-time discovery counters, exported as metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiscoveryStats {
+  /// Unanswered one-to-one invitations.
+  pub pending_invitations: usize,
+  /// Open multi-user invitations.
+  pub multi_invites: usize,
+  /// SDP negotiations currently tracked.
+  pub sdp_negotiations: usize,
+  /// Invitations rejected by the rate limiter since startup.
+  pub rate_limit_hits: u64,
+  /// SDP negotiations expired by cleanup since startup.
+  pub sdp_timeouts: u64,
+}
+
 // =============================================================================
 // SDP Negotiation Types
 // =============================================================================
@@ -1,11 +1,17 @@
 //! Server library module.
 
+pub mod admin;
 pub mod auth;
+pub mod cluster;
 pub mod config;
 pub mod discovery;
 pub mod logging;
+pub mod mailbox;
+pub mod metrics;
 pub mod room;
 pub mod server;
+pub mod sfu;
+pub mod storage;
 pub mod stun;
 pub mod ws;
 
@@ -33,8 +33,17 @@ fn test_create_file_writer_with_nested_directory() {
   let config = crate::config::Config {
     addr: "0.0.0.0:3000".parse().unwrap(),
     jwt_secret: "test-secret".to_string(),
+    access_token_ttl: std::time::Duration::from_secs(900),
+    refresh_token_ttl: std::time::Duration::from_secs(86400),
+    admin_token: None,
+    admin_usernames: vec![],
+    metrics_enabled: false,
     ice_servers: vec![],
     stun_port: None,
+    turn: None,
+    sfu: None,
+    mailbox: None,
+    cluster: None,
     tls: None,
     static_dir: std::path::PathBuf::from("./static"),
     stickers_dir: std::path::PathBuf::from("./stickers"),
@@ -49,6 +58,7 @@ fn test_create_file_writer_with_nested_directory() {
     heartbeat_timeout: std::time::Duration::from_secs(60),
     max_message_size: 1024 * 1024,
     send_queue_size: 256,
+    storage: crate::storage::StorageBackend::Memory,
   };
 
   // Create the nested directory first
@@ -71,8 +81,17 @@ fn test_init_creates_log_directory() {
   let config = crate::config::Config {
     addr: "0.0.0.0:3000".parse().unwrap(),
     jwt_secret: "test-secret".to_string(),
+    access_token_ttl: std::time::Duration::from_secs(900),
+    refresh_token_ttl: std::time::Duration::from_secs(86400),
+    admin_token: None,
+    admin_usernames: vec![],
+    metrics_enabled: false,
     ice_servers: vec![],
     stun_port: None,
+    turn: None,
+    sfu: None,
+    mailbox: None,
+    cluster: None,
     tls: None,
     static_dir: std::path::PathBuf::from("./static"),
     stickers_dir: std::path::PathBuf::from("./stickers"),
@@ -87,6 +106,7 @@ fn test_init_creates_log_directory() {
     heartbeat_timeout: std::time::Duration::from_secs(60),
     max_message_size: 1024 * 1024,
     send_queue_size: 256,
+    storage: crate::storage::StorageBackend::Memory,
   };
 
   // The key assertion is that the log directory gets created
@@ -6,8 +6,17 @@ pub(super) fn create_test_config(temp_dir: &TempDir, log_output: &str) -> crate:
   crate::config::Config {
     addr: "0.0.0.0:3000".parse().unwrap(),
     jwt_secret: "test-secret".to_string(),
+    access_token_ttl: std::time::Duration::from_secs(900),
+    refresh_token_ttl: std::time::Duration::from_secs(86400),
+    admin_token: None,
+    admin_usernames: vec![],
+    metrics_enabled: false,
     ice_servers: vec![],
     stun_port: None,
+    turn: None,
+    sfu: None,
+    mailbox: None,
+    cluster: None,
     tls: None,
     static_dir: std::path::PathBuf::from("./static"),
     stickers_dir: std::path::PathBuf::from("./stickers"),
@@ -22,6 +31,7 @@ pub(super) fn create_test_config(temp_dir: &TempDir, log_output: &str) -> crate:
     heartbeat_timeout: std::time::Duration::from_secs(60),
     max_message_size: 1024 * 1024,
     send_queue_size: 256,
+    storage: crate::storage::StorageBackend::Memory,
   }
 }
 
@@ -0,0 +1,568 @@
+//! Store-and-forward mailbox for users who are offline.
+//!
+//! A sender that cannot reach a recipient over a DataChannel seals the
+//! chat message to each mailbox key the recipient's devices published
+//! and deposits one blob per device here. The server never opens them:
+//! it only keeps each blob in the mailbox of the device it is sealed
+//! to, bounded by [`MailboxConfig`], until that device fetches and
+//! acknowledges it or the TTL runs out.
+//!
+//! Acknowledged deposits produce a delivery receipt for their sender.
+//! Receipts for senders that are offline at that moment wait here too,
+//! merged per recipient, and are handed out on the sender's next fetch.
+//!
+//! Everything is kept in memory; a restart drops stored messages and
+//! published keys, and clients republish their key on every login.
+//!
+//! In a cluster every replica holds every mailbox: each change is
+//! recorded as a [`MailboxChange`], published over the cluster bus and
+//! applied by the other replicas, so a deposit made on one replica is
+//! fetched on another. A replica that joins late starts without the
+//! mailboxes filled before it joined.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use bitcode::{Decode, Encode};
+use dashmap::DashMap;
+use message::signaling::{
+  DeviceMailboxKey, MailboxDelivered, MailboxDeposit, MailboxItem, MailboxKeyBundle,
+};
+use message::{DeviceId, MessageId, UserId};
+
+use tokio::sync::Notify;
+
+use crate::auth::MAX_SESSIONS_PER_USER;
+use crate::config::MailboxConfig;
+
+/// Largest accepted field of a [`MailboxKeyBundle`]. Raw P-256 keys
+/// are 65 bytes and P1363 signatures 64.
+pub const MAX_KEY_FIELD_BYTES: usize = 256;
+
+// =============================================================================
+// Error Types
+// =============================================================================
+
+/// Error types for mailbox operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailboxError {
+  /// The published key bundle is empty or oversized.
+  InvalidKey,
+  /// The blob exceeds [`MailboxConfig::max_blob_bytes`].
+  BlobTooLarge,
+  /// The recipient device's mailbox is at its message or byte quota.
+  MailboxFull,
+  /// The sender already has [`MailboxConfig::max_messages_per_sender`]
+  /// messages waiting for this recipient.
+  SenderQuotaReached,
+}
+
+impl fmt::Display for MailboxError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidKey => write!(f, "Invalid mailbox key"),
+      Self::BlobTooLarge => write!(f, "Message is too large for the mailbox"),
+      Self::MailboxFull => write!(f, "Recipient mailbox is full"),
+      Self::SenderQuotaReached => write!(
+        f,
+        "Too many undelivered messages are waiting for this recipient"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for MailboxError {}
+
+// =============================================================================
+// Replication
+// =============================================================================
+
+/// One mailbox mutation, as shipped between cluster replicas.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum MailboxChange {
+  /// A device published its mailbox key.
+  Key {
+    /// Owner of the device.
+    user_id: UserId,
+    /// The published key.
+    key: DeviceMailboxKey,
+  },
+  /// A message was deposited.
+  Deposit {
+    /// Recipient.
+    to: UserId,
+    /// The stored message.
+    item: MailboxItem,
+  },
+  /// A device acknowledged messages.
+  Ack {
+    /// Owner of the device.
+    user_id: UserId,
+    /// The acknowledging device.
+    device_id: Option<DeviceId>,
+    /// Acknowledged messages.
+    message_ids: Vec<MessageId>,
+  },
+  /// A receipt is waiting for its sender.
+  Receipt {
+    /// Sender of the acknowledged messages.
+    sender: UserId,
+    /// The receipt.
+    receipt: MailboxDelivered,
+  },
+  /// A sender took its waiting receipts.
+  ReceiptsTaken(UserId),
+  /// A deleted account's keys, mailboxes and receipts were dropped.
+  Forgotten(UserId),
+}
+
+/// Local changes waiting to be published.
+#[derive(Debug, Default)]
+struct Replication {
+  /// Changes made since the last [`MailboxState::take_changes`],
+  /// oldest first.
+  pending: Mutex<Vec<MailboxChange>>,
+  /// Woken whenever a change is added to `pending`.
+  notify: Arc<Notify>,
+}
+
+// =============================================================================
+// Mailbox State
+// =============================================================================
+
+/// A deposited message and the instant it expires.
+#[derive(Debug)]
+struct StoredItem {
+  item: MailboxItem,
+  expires_at: Instant,
+}
+
+/// A recipient device. Devices without an id (clients that predate
+/// device linking) share the `None` mailbox of their user.
+type Recipient = (UserId, Option<DeviceId>);
+
+/// One recipient device's stored messages, oldest first.
+#[derive(Debug, Default)]
+struct Mailbox {
+  items: VecDeque<StoredItem>,
+  bytes: usize,
+}
+
+impl Mailbox {
+  fn remove_where(&mut self, mut pred: impl FnMut(&StoredItem) -> bool) -> Vec<MailboxItem> {
+    let mut removed = Vec::new();
+    let mut kept = VecDeque::with_capacity(self.items.len());
+    for stored in self.items.drain(..) {
+      if pred(&stored) {
+        self.bytes -= stored.item.blob.len();
+        removed.push(stored.item);
+      } else {
+        kept.push_back(stored);
+      }
+    }
+    self.items = kept;
+    removed
+  }
+}
+
+/// A delivery receipt waiting for its sender to come online.
+#[derive(Debug)]
+struct PendingReceipt {
+  receipt: MailboxDelivered,
+  expires_at: Instant,
+}
+
+/// Mailbox keys, stored messages and pending receipts.
+#[derive(Debug)]
+pub struct MailboxState {
+  config: MailboxConfig,
+  /// Keys per user, one per device, oldest first.
+  keys: DashMap<UserId, Vec<DeviceMailboxKey>>,
+  boxes: DashMap<Recipient, Mailbox>,
+  receipts: DashMap<UserId, Vec<PendingReceipt>>,
+  /// Change tracking for cluster replication; `None` on a single node.
+  replication: Option<Replication>,
+}
+
+impl MailboxState {
+  /// Create an empty mailbox store.
+  #[must_use]
+  pub fn new(config: MailboxConfig) -> Self {
+    Self {
+      config,
+      keys: DashMap::new(),
+      boxes: DashMap::new(),
+      receipts: DashMap::new(),
+      replication: None,
+    }
+  }
+
+  /// Record local changes so they can be replicated to the other
+  /// cluster replicas.
+  #[must_use]
+  pub fn with_replication(mut self) -> Self {
+    self.replication = Some(Replication::default());
+    self
+  }
+
+  /// Notified whenever a mailbox changes locally (`None` without
+  /// replication).
+  #[must_use]
+  pub fn change_notify(&self) -> Option<Arc<Notify>> {
+    self.replication.as_ref().map(|r| r.notify.clone())
+  }
+
+  /// Drain the changes made locally since the last call, oldest first.
+  pub fn take_changes(&self) -> Vec<MailboxChange> {
+    self
+      .replication
+      .as_ref()
+      .map(|replication| {
+        std::mem::take(
+          &mut *replication
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+        )
+      })
+      .unwrap_or_default()
+  }
+
+  /// Apply a change replicated from another replica without recording
+  /// it again. Quotas were enforced where the change was made.
+  pub fn apply_change(&self, change: MailboxChange) {
+    if self.replication.is_none() {
+      return;
+    }
+    match change {
+      MailboxChange::Key { user_id, key } => self.insert_key(user_id, key),
+      MailboxChange::Deposit { to, item } => {
+        let mut mailbox = self.boxes.entry((to, item.to_device)).or_default();
+        let now = Instant::now();
+        mailbox.remove_where(|stored| {
+          stored.expires_at <= now
+            || (stored.item.from == item.from && stored.item.message_id == item.message_id)
+        });
+        self.store(&mut mailbox, item, now);
+      }
+      MailboxChange::Ack {
+        user_id,
+        device_id,
+        message_ids,
+      } => {
+        self.remove_acknowledged(&(user_id, device_id), &message_ids);
+      }
+      MailboxChange::Receipt { sender, receipt } => self.merge_receipt(sender, receipt),
+      MailboxChange::ReceiptsTaken(sender) => {
+        self.receipts.remove(&sender);
+      }
+      MailboxChange::Forgotten(user_id) => self.remove_user(&user_id),
+    }
+  }
+
+  /// Record a local change. No-op without replication.
+  fn changed(&self, change: MailboxChange) {
+    if let Some(replication) = &self.replication {
+      replication
+        .pending
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(change);
+      replication.notify.notify_one();
+    }
+  }
+
+  /// Publish or replace the mailbox key of `user_id`'s `device_id`.
+  ///
+  /// The signature is checked by the senders, who know the owner's
+  /// identity key; the server only rejects malformed bundles. The
+  /// device is handed out with the key so senders can check it against
+  /// that device's identity. Beyond [`MAX_SESSIONS_PER_USER`] devices
+  /// the key published longest ago is dropped.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`MailboxError::InvalidKey`] if a field is empty or
+  /// longer than [`MAX_KEY_FIELD_BYTES`].
+  pub fn publish_key(
+    &self,
+    user_id: UserId,
+    device_id: Option<DeviceId>,
+    bundle: MailboxKeyBundle,
+  ) -> Result<(), MailboxError> {
+    let fields = [&bundle.public_key, &bundle.identity_key, &bundle.signature];
+    if fields
+      .iter()
+      .any(|field| field.is_empty() || field.len() > MAX_KEY_FIELD_BYTES)
+    {
+      return Err(MailboxError::InvalidKey);
+    }
+    let key = DeviceMailboxKey { device_id, bundle };
+    self.changed(MailboxChange::Key {
+      user_id: user_id.clone(),
+      key: key.clone(),
+    });
+    self.insert_key(user_id, key);
+    Ok(())
+  }
+
+  /// Replace the key of `key.device_id`, dropping the key published
+  /// longest ago beyond [`MAX_SESSIONS_PER_USER`] devices.
+  fn insert_key(&self, user_id: UserId, key: DeviceMailboxKey) {
+    let mut keys = self.keys.entry(user_id).or_default();
+    keys.retain(|existing| existing.device_id != key.device_id);
+    keys.push(key);
+    let excess = keys.len().saturating_sub(MAX_SESSIONS_PER_USER);
+    keys.drain(..excess);
+  }
+
+  /// The keys published by `user_id`'s devices, oldest first.
+  #[must_use]
+  pub fn keys(&self, user_id: &UserId) -> Vec<DeviceMailboxKey> {
+    self
+      .keys
+      .get(user_id)
+      .map(|keys| keys.clone())
+      .unwrap_or_default()
+  }
+
+  /// Store a deposit from `from` (sent by `from_device`) in the mailbox
+  /// of the device it is sealed to, and return the stored item.
+  ///
+  /// Re-depositing the same message id from the same sender to the
+  /// same device replaces the stored blob, so a sender retrying after a
+  /// lost reply does not produce duplicates.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`MailboxError::BlobTooLarge`],
+  /// [`MailboxError::SenderQuotaReached`] or
+  /// [`MailboxError::MailboxFull`] when the deposit exceeds the
+  /// configured limits.
+  pub fn deposit(
+    &self,
+    from: UserId,
+    from_device: Option<DeviceId>,
+    deposit: MailboxDeposit,
+  ) -> Result<MailboxItem, MailboxError> {
+    let size = deposit.blob.len();
+    if size > self.config.max_blob_bytes {
+      return Err(MailboxError::BlobTooLarge);
+    }
+    let now = Instant::now();
+    let mut mailbox = self
+      .boxes
+      .entry((deposit.to.clone(), deposit.to_device))
+      .or_default();
+    mailbox.remove_where(|stored| {
+      stored.expires_at <= now
+        || (stored.item.from == from && stored.item.message_id == deposit.message_id)
+    });
+    let from_sender = mailbox
+      .items
+      .iter()
+      .filter(|stored| stored.item.from == from)
+      .count();
+    if from_sender >= self.config.max_messages_per_sender {
+      return Err(MailboxError::SenderQuotaReached);
+    }
+    if mailbox.items.len() >= self.config.max_messages_per_user
+      || mailbox.bytes + size > self.config.max_bytes_per_user
+    {
+      return Err(MailboxError::MailboxFull);
+    }
+    let item = MailboxItem {
+      from,
+      from_device,
+      to_device: deposit.to_device,
+      message_id: deposit.message_id,
+      blob: deposit.blob,
+      deposited_at_nanos: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0),
+    };
+    self.changed(MailboxChange::Deposit {
+      to: deposit.to,
+      item: item.clone(),
+    });
+    self.store(&mut mailbox, item.clone(), now);
+    Ok(item)
+  }
+
+  /// Append `item` to `mailbox`, expiring one TTL after `now`.
+  fn store(&self, mailbox: &mut Mailbox, item: MailboxItem, now: Instant) {
+    mailbox.bytes += item.blob.len();
+    mailbox.items.push_back(StoredItem {
+      item,
+      expires_at: now + self.config.ttl,
+    });
+  }
+
+  /// Everything stored for `user_id`'s `device_id` that has not
+  /// expired, oldest first. Items stay stored until
+  /// [`Self::acknowledge`]d, so a client that disconnects mid-drain
+  /// receives them again.
+  #[must_use]
+  pub fn drain(&self, user_id: &UserId, device_id: Option<DeviceId>) -> Vec<MailboxItem> {
+    let now = Instant::now();
+    self
+      .boxes
+      .get(&(user_id.clone(), device_id))
+      .map(|mailbox| {
+        mailbox
+          .items
+          .iter()
+          .filter(|stored| stored.expires_at > now)
+          .map(|stored| stored.item.clone())
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
+  /// Remove acknowledged items from the mailbox of `user_id`'s
+  /// `device_id` and return one delivery receipt per sender, paired
+  /// with that sender. Unknown ids are ignored; copies held for the
+  /// user's other devices stay.
+  pub fn acknowledge(
+    &self,
+    user_id: &UserId,
+    device_id: Option<DeviceId>,
+    message_ids: &[MessageId],
+  ) -> Vec<(UserId, MailboxDelivered)> {
+    let removed = self.remove_acknowledged(&(user_id.clone(), device_id), message_ids);
+    if removed.is_empty() {
+      return Vec::new();
+    }
+    self.changed(MailboxChange::Ack {
+      user_id: user_id.clone(),
+      device_id,
+      message_ids: message_ids.to_vec(),
+    });
+
+    let mut by_sender: HashMap<UserId, Vec<MessageId>> = HashMap::new();
+    for item in removed {
+      by_sender
+        .entry(item.from)
+        .or_default()
+        .push(item.message_id);
+    }
+    by_sender
+      .into_iter()
+      .map(|(sender, message_ids)| {
+        let receipt = MailboxDelivered {
+          recipient: user_id.clone(),
+          message_ids,
+        };
+        (sender, receipt)
+      })
+      .collect()
+  }
+
+  /// Remove the messages in `message_ids` from `recipient`'s mailbox
+  /// and return them.
+  fn remove_acknowledged(
+    &self,
+    recipient: &Recipient,
+    message_ids: &[MessageId],
+  ) -> Vec<MailboxItem> {
+    let removed = match self.boxes.get_mut(recipient) {
+      Some(mut mailbox) => {
+        mailbox.remove_where(|stored| message_ids.contains(&stored.item.message_id))
+      }
+      None => return Vec::new(),
+    };
+    self
+      .boxes
+      .remove_if(recipient, |_, mailbox| mailbox.items.is_empty());
+    removed
+  }
+
+  /// Hold a receipt until `sender` next fetches its mailbox.
+  ///
+  /// Receipts for the same recipient are merged, and each sender keeps
+  /// at most [`MailboxConfig::max_messages_per_user`] message ids per
+  /// recipient and as many recipients, oldest dropped first.
+  pub fn queue_receipt(&self, sender: UserId, receipt: MailboxDelivered) {
+    self.changed(MailboxChange::Receipt {
+      sender: sender.clone(),
+      receipt: receipt.clone(),
+    });
+    self.merge_receipt(sender, receipt);
+  }
+
+  /// Merge `receipt` into those waiting for `sender`.
+  fn merge_receipt(&self, sender: UserId, receipt: MailboxDelivered) {
+    let limit = self.config.max_messages_per_user;
+    let expires_at = Instant::now() + self.config.ttl;
+    let mut pending = self.receipts.entry(sender).or_default();
+    let index = match pending
+      .iter()
+      .position(|p| p.receipt.recipient == receipt.recipient)
+    {
+      Some(index) => {
+        let merged = &mut pending[index];
+        merged.receipt.message_ids.extend(receipt.message_ids);
+        merged.expires_at = expires_at;
+        index
+      }
+      None => {
+        pending.push(PendingReceipt {
+          receipt,
+          expires_at,
+        });
+        pending.len() - 1
+      }
+    };
+    let ids = &mut pending[index].receipt.message_ids;
+    ids.drain(..ids.len().saturating_sub(limit));
+    let excess = pending.len().saturating_sub(limit);
+    pending.drain(..excess);
+  }
+
+  /// Take every receipt waiting for `sender`.
+  pub fn take_receipts(&self, sender: &UserId) -> Vec<MailboxDelivered> {
+    let now = Instant::now();
+    let Some((_, pending)) = self.receipts.remove(sender) else {
+      return Vec::new();
+    };
+    self.changed(MailboxChange::ReceiptsTaken(sender.clone()));
+    pending
+      .into_iter()
+      .filter(|p| p.expires_at > now)
+      .map(|p| p.receipt)
+      .collect()
+  }
+
+  /// Drop messages and receipts that expired before `now`. Returns the
+  /// number of messages dropped.
+  pub fn purge_expired(&self, now: Instant) -> usize {
+    let mut purged = 0;
+    for mut mailbox in self.boxes.iter_mut() {
+      purged += mailbox
+        .remove_where(|stored| stored.expires_at <= now)
+        .len();
+    }
+    self.boxes.retain(|_, mailbox| !mailbox.items.is_empty());
+    for mut pending in self.receipts.iter_mut() {
+      pending.retain(|p| p.expires_at > now);
+    }
+    self.receipts.retain(|_, pending| !pending.is_empty());
+    purged
+  }
+
+  /// Forget everything belonging to a deleted account.
+  pub fn forget_user(&self, user_id: &UserId) {
+    self.changed(MailboxChange::Forgotten(user_id.clone()));
+    self.remove_user(user_id);
+  }
+
+  /// Drop the keys, mailboxes and receipts of `user_id`.
+  fn remove_user(&self, user_id: &UserId) {
+    self.keys.remove(user_id);
+    self.boxes.retain(|(owner, _), _| owner != user_id);
+    self.receipts.remove(user_id);
+  }
+}
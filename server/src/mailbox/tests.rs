@@ -0,0 +1,436 @@
+//! Mailbox state tests.
+
+use std::time::Duration;
+
+use super::*;
+
+fn test_config() -> MailboxConfig {
+  MailboxConfig {
+    ttl: Duration::from_secs(60),
+    max_messages_per_user: 3,
+    max_bytes_per_user: 100,
+    max_messages_per_sender: 10,
+    max_blob_bytes: 50,
+  }
+}
+
+fn deposit(to: &UserId, blob_len: usize) -> MailboxDeposit {
+  MailboxDeposit {
+    to: to.clone(),
+    to_device: None,
+    message_id: MessageId::new(),
+    blob: vec![0xAA; blob_len],
+  }
+}
+
+fn bundle() -> MailboxKeyBundle {
+  MailboxKeyBundle {
+    public_key: vec![4; 65],
+    identity_key: vec![4; 65],
+    signature: vec![1; 64],
+  }
+}
+
+#[test]
+fn test_publish_and_lookup_key() {
+  let state = MailboxState::new(test_config());
+  let user = UserId::new();
+  assert!(state.keys(&user).is_empty());
+  let device = Some(DeviceId::new());
+  state.publish_key(user.clone(), device, bundle()).unwrap();
+  assert_eq!(
+    state.keys(&user),
+    vec![DeviceMailboxKey {
+      device_id: device,
+      bundle: bundle(),
+    }]
+  );
+}
+
+#[test]
+fn test_keys_are_kept_per_device_and_bounded() {
+  let state = MailboxState::new(test_config());
+  let user = UserId::new();
+  let devices: Vec<_> = (0..=MAX_SESSIONS_PER_USER)
+    .map(|_| Some(DeviceId::new()))
+    .collect();
+  for device in &devices {
+    state.publish_key(user.clone(), *device, bundle()).unwrap();
+  }
+  // Republishing replaces the device's key and makes it the newest.
+  let mut renewed = bundle();
+  renewed.public_key = vec![5; 65];
+  state
+    .publish_key(user.clone(), devices[1], renewed.clone())
+    .unwrap();
+
+  let keys = state.keys(&user);
+  assert_eq!(keys.len(), MAX_SESSIONS_PER_USER);
+  assert!(keys.iter().all(|key| key.device_id != devices[0]));
+  assert_eq!(keys.last().unwrap().device_id, devices[1]);
+  assert_eq!(keys.last().unwrap().bundle, renewed);
+}
+
+#[test]
+fn test_publish_rejects_malformed_key() {
+  let state = MailboxState::new(test_config());
+  let mut empty = bundle();
+  empty.signature.clear();
+  assert_eq!(
+    state.publish_key(UserId::new(), None, empty),
+    Err(MailboxError::InvalidKey)
+  );
+
+  let mut oversized = bundle();
+  oversized.public_key = vec![0; MAX_KEY_FIELD_BYTES + 1];
+  assert_eq!(
+    state.publish_key(UserId::new(), None, oversized),
+    Err(MailboxError::InvalidKey)
+  );
+}
+
+#[test]
+fn test_deposit_and_drain_in_order() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let first = deposit(&recipient, 10);
+  let second = deposit(&recipient, 10);
+  state.deposit(sender.clone(), None, first.clone()).unwrap();
+  state.deposit(sender.clone(), None, second.clone()).unwrap();
+
+  let items = state.drain(&recipient, None);
+  assert_eq!(items.len(), 2);
+  assert_eq!(items[0].message_id, first.message_id);
+  assert_eq!(items[1].message_id, second.message_id);
+  assert!(items.iter().all(|item| item.from == sender));
+  // Draining does not remove anything.
+  assert_eq!(state.drain(&recipient, None).len(), 2);
+  assert!(state.drain(&sender, None).is_empty());
+}
+
+#[test]
+fn test_redeposit_replaces_blob() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let mut msg = deposit(&recipient, 10);
+  state.deposit(sender.clone(), None, msg.clone()).unwrap();
+  msg.blob = vec![0xBB; 20];
+  state.deposit(sender, None, msg.clone()).unwrap();
+
+  let items = state.drain(&recipient, None);
+  assert_eq!(items.len(), 1);
+  assert_eq!(items[0].blob, msg.blob);
+}
+
+#[test]
+fn test_deposit_records_sending_device() {
+  let state = MailboxState::new(test_config());
+  let recipient = UserId::new();
+  let device = Some(DeviceId::new());
+  let item = state
+    .deposit(UserId::new(), device, deposit(&recipient, 10))
+    .unwrap();
+  assert_eq!(item.from_device, device);
+  assert_eq!(state.drain(&recipient, None)[0].from_device, device);
+}
+
+#[test]
+fn test_devices_have_separate_mailboxes() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let (laptop, phone) = (Some(DeviceId::new()), Some(DeviceId::new()));
+  let mut copy = deposit(&recipient, 10);
+  for device in [laptop, phone] {
+    copy.to_device = device;
+    state.deposit(sender.clone(), None, copy.clone()).unwrap();
+  }
+  assert_eq!(state.drain(&recipient, laptop).len(), 1);
+  assert_eq!(state.drain(&recipient, phone)[0].to_device, phone);
+  assert!(state.drain(&recipient, None).is_empty());
+
+  // Acknowledging on one device leaves the other device's copy.
+  let receipts = state.acknowledge(&recipient, laptop, &[copy.message_id]);
+  assert_eq!(receipts.len(), 1);
+  assert!(state.drain(&recipient, laptop).is_empty());
+  assert_eq!(state.drain(&recipient, phone).len(), 1);
+}
+
+#[test]
+fn test_deposit_limits() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  assert_eq!(
+    state.deposit(sender.clone(), None, deposit(&recipient, 51)),
+    Err(MailboxError::BlobTooLarge)
+  );
+
+  state
+    .deposit(sender.clone(), None, deposit(&recipient, 50))
+    .unwrap();
+  state
+    .deposit(sender.clone(), None, deposit(&recipient, 40))
+    .unwrap();
+  assert_eq!(
+    state.deposit(sender.clone(), None, deposit(&recipient, 20)),
+    Err(MailboxError::MailboxFull),
+    "byte quota"
+  );
+  state
+    .deposit(sender.clone(), None, deposit(&recipient, 10))
+    .unwrap();
+  assert_eq!(
+    state.deposit(sender, None, deposit(&recipient, 0)),
+    Err(MailboxError::MailboxFull),
+    "message quota"
+  );
+
+  // Quotas are per recipient.
+  state
+    .deposit(UserId::new(), None, deposit(&UserId::new(), 50))
+    .unwrap();
+}
+
+#[test]
+fn test_acknowledge_removes_items_and_groups_receipts() {
+  let state = MailboxState::new(test_config());
+  let alice = UserId::new();
+  let bob = UserId::new();
+  let recipient = UserId::new();
+  let from_alice = deposit(&recipient, 10);
+  let from_bob = deposit(&recipient, 10);
+  let unacked = deposit(&recipient, 10);
+  state
+    .deposit(alice.clone(), None, from_alice.clone())
+    .unwrap();
+  state.deposit(bob.clone(), None, from_bob.clone()).unwrap();
+  state.deposit(alice.clone(), None, unacked.clone()).unwrap();
+
+  let mut receipts = state.acknowledge(
+    &recipient,
+    None,
+    &[from_alice.message_id, from_bob.message_id, MessageId::new()],
+  );
+  receipts.sort_by_key(|(sender, _)| *sender == bob);
+  assert_eq!(receipts.len(), 2);
+  assert_eq!(receipts[0].0, alice);
+  assert_eq!(receipts[0].1.recipient, recipient);
+  assert_eq!(receipts[0].1.message_ids, vec![from_alice.message_id]);
+  assert_eq!(receipts[1].0, bob);
+  assert_eq!(receipts[1].1.message_ids, vec![from_bob.message_id]);
+
+  let left = state.drain(&recipient, None);
+  assert_eq!(left.len(), 1);
+  assert_eq!(left[0].message_id, unacked.message_id);
+}
+
+#[test]
+fn test_acknowledge_frees_quota() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let big = deposit(&recipient, 50);
+  state.deposit(sender.clone(), None, big.clone()).unwrap();
+  state
+    .deposit(sender.clone(), None, deposit(&recipient, 50))
+    .unwrap();
+  assert!(
+    state
+      .deposit(sender.clone(), None, deposit(&recipient, 10))
+      .is_err()
+  );
+
+  state.acknowledge(&recipient, None, &[big.message_id]);
+  state
+    .deposit(sender, None, deposit(&recipient, 10))
+    .unwrap();
+}
+
+#[test]
+fn test_queued_receipts_are_taken_once() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let receipt = MailboxDelivered {
+    recipient: UserId::new(),
+    message_ids: vec![MessageId::new()],
+  };
+  state.queue_receipt(sender.clone(), receipt.clone());
+  assert_eq!(state.take_receipts(&sender), vec![receipt]);
+  assert!(state.take_receipts(&sender).is_empty());
+}
+
+#[test]
+fn test_sender_quota_leaves_room_for_others() {
+  let state = MailboxState::new(MailboxConfig {
+    max_messages_per_sender: 2,
+    ..test_config()
+  });
+  let spammer = UserId::new();
+  let recipient = UserId::new();
+  for _ in 0..2 {
+    state
+      .deposit(spammer.clone(), None, deposit(&recipient, 10))
+      .unwrap();
+  }
+  assert_eq!(
+    state.deposit(spammer, None, deposit(&recipient, 10)),
+    Err(MailboxError::SenderQuotaReached)
+  );
+  state
+    .deposit(UserId::new(), None, deposit(&recipient, 10))
+    .unwrap();
+}
+
+#[test]
+fn test_queued_receipts_merge_per_recipient_and_stay_bounded() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let ids: Vec<_> = (0..5).map(|_| MessageId::new()).collect();
+  for id in &ids {
+    state.queue_receipt(
+      sender.clone(),
+      MailboxDelivered {
+        recipient: recipient.clone(),
+        message_ids: vec![*id],
+      },
+    );
+  }
+  // Other recipients beyond the limit push out the oldest receipt.
+  for _ in 0..3 {
+    state.queue_receipt(
+      sender.clone(),
+      MailboxDelivered {
+        recipient: UserId::new(),
+        message_ids: vec![MessageId::new()],
+      },
+    );
+  }
+  let receipts = state.take_receipts(&sender);
+  assert_eq!(receipts.len(), 3);
+  assert!(receipts.iter().all(|r| r.recipient != recipient));
+
+  state.queue_receipt(
+    sender.clone(),
+    MailboxDelivered {
+      recipient: recipient.clone(),
+      message_ids: ids.clone(),
+    },
+  );
+  let receipts = state.take_receipts(&sender);
+  assert_eq!(receipts[0].message_ids, ids[2..]);
+}
+
+#[test]
+fn test_purge_expired() {
+  let state = MailboxState::new(test_config());
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  state
+    .deposit(sender.clone(), None, deposit(&recipient, 10))
+    .unwrap();
+  state.queue_receipt(
+    sender.clone(),
+    MailboxDelivered {
+      recipient: recipient.clone(),
+      message_ids: vec![],
+    },
+  );
+
+  assert_eq!(state.purge_expired(Instant::now()), 0);
+  assert_eq!(state.drain(&recipient, None).len(), 1);
+
+  let later = Instant::now() + Duration::from_secs(61);
+  assert_eq!(state.purge_expired(later), 1);
+  assert!(state.drain(&recipient, None).is_empty());
+  assert!(state.take_receipts(&sender).is_empty());
+}
+
+#[test]
+fn test_forget_user() {
+  let state = MailboxState::new(test_config());
+  let user = UserId::new();
+  state.publish_key(user.clone(), None, bundle()).unwrap();
+  state
+    .deposit(UserId::new(), None, deposit(&user, 10))
+    .unwrap();
+  state.forget_user(&user);
+  assert!(state.keys(&user).is_empty());
+  assert!(state.drain(&user, None).is_empty());
+}
+
+/// Apply every change recorded on `from` to `to`.
+fn sync(from: &MailboxState, to: &MailboxState) {
+  for change in from.take_changes() {
+    to.apply_change(change);
+  }
+}
+
+#[test]
+fn test_changes_not_tracked_without_replication() {
+  let state = MailboxState::new(test_config());
+  let user = UserId::new();
+  state.publish_key(user.clone(), None, bundle()).unwrap();
+  state
+    .deposit(UserId::new(), None, deposit(&user, 10))
+    .unwrap();
+
+  assert!(state.change_notify().is_none());
+  assert!(state.take_changes().is_empty());
+}
+
+#[test]
+fn test_deposit_and_key_replicate_to_other_replica() {
+  let a = MailboxState::new(test_config()).with_replication();
+  let b = MailboxState::new(test_config()).with_replication();
+  let sender = UserId::new();
+  let recipient = UserId::new();
+  let device = Some(DeviceId::new());
+
+  b.publish_key(recipient.clone(), device, bundle()).unwrap();
+  sync(&b, &a);
+  assert_eq!(a.keys(&recipient), b.keys(&recipient));
+
+  let mut sealed = deposit(&recipient, 10);
+  sealed.to_device = device;
+  let item = a.deposit(sender.clone(), None, sealed.clone()).unwrap();
+  // A retried deposit replaces the copy on both replicas.
+  a.deposit(sender.clone(), None, sealed).unwrap();
+  sync(&a, &b);
+  assert_eq!(b.drain(&recipient, device).len(), 1);
+  assert_eq!(b.drain(&recipient, device)[0].message_id, item.message_id);
+
+  // Acknowledged on B: gone from A too, and the receipt is B's to hand
+  // out or queue.
+  let receipts = b.acknowledge(&recipient, device, &[item.message_id]);
+  assert_eq!(receipts.len(), 1);
+  sync(&b, &a);
+  assert!(a.drain(&recipient, device).is_empty());
+  assert!(a.take_changes().is_empty());
+}
+
+#[test]
+fn test_receipts_and_forget_replicate() {
+  let a = MailboxState::new(test_config()).with_replication();
+  let b = MailboxState::new(test_config()).with_replication();
+  let sender = UserId::new();
+  let receipt = MailboxDelivered {
+    recipient: UserId::new(),
+    message_ids: vec![MessageId::new()],
+  };
+
+  a.queue_receipt(sender.clone(), receipt.clone());
+  sync(&a, &b);
+  assert_eq!(b.take_receipts(&sender), vec![receipt]);
+  sync(&b, &a);
+  assert!(a.take_receipts(&sender).is_empty());
+
+  b.publish_key(sender.clone(), None, bundle()).unwrap();
+  sync(&b, &a);
+  a.forget_user(&sender);
+  sync(&a, &b);
+  assert!(b.keys(&sender).is_empty());
+}
@@ -11,7 +11,7 @@
 #![allow(clippy::must_use_candidate)]
 #![allow(clippy::missing_errors_doc)]
 
-use server::{Server, config::Config, logging};
+use server::{Server, config::Config, logging, storage};
 
 /// Application entry point.
 #[tokio::main]
@@ -29,7 +29,11 @@ async fn main() -> anyhow::Result<()> {
     "Starting WebRTC Chat Signaling Server"
   );
 
+  // Open the configured storage backend before accepting traffic so
+  // that a broken data directory fails the deploy loudly.
+  let storage = storage::open(&config.storage)?;
+
   // Create and start server
-  let server = Server::new(config);
+  let server = Server::with_storage(config, storage);
   server.start().await
 }
@@ -0,0 +1,356 @@
+//! Prometheus metrics for the signaling server.
+//!
+//! A deliberately small, dependency-free registry: counters and
+//! histograms are plain atomics updated on the hot paths
+//! ([`crate::ws`] handler, [`crate::stun`] receive loop), while gauges
+//! such as connection and room counts are read from the live state at
+//! scrape time. [`render`] serializes everything in the Prometheus text
+//! exposition format (version 0.0.4) for the `/metrics` endpoint, which
+//! is only mounted when [`Config::metrics_enabled`](crate::config::Config::metrics_enabled)
+//! is set.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use crate::ws::WebSocketState;
+
+/// Content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Bucket bounds (seconds) for signaling message handling latency.
+const HANDLE_SECONDS_BUCKETS: &[f64] = &[
+  0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+];
+/// Bucket bounds (bytes) for inbound signaling frame sizes.
+const MESSAGE_BYTES_BUCKETS: &[f64] = &[
+  64.0,
+  256.0,
+  1024.0,
+  4096.0,
+  16_384.0,
+  65_536.0,
+  262_144.0,
+  1_048_576.0,
+];
+/// Bucket bounds (seconds) for WebSocket connection lifetimes.
+const CONNECTION_SECONDS_BUCKETS: &[f64] =
+  &[1.0, 10.0, 60.0, 300.0, 900.0, 3600.0, 14_400.0, 86_400.0];
+
+/// Monotonically increasing counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+  /// Increment by one.
+  pub fn inc(&self) {
+    self.add(1);
+  }
+
+  /// Increment by `n`.
+  pub fn add(&self, n: u64) {
+    self.0.fetch_add(n, Ordering::Relaxed);
+  }
+
+  /// Current value.
+  #[must_use]
+  pub fn get(&self) -> u64 {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+/// Cumulative histogram with fixed bucket bounds.
+#[derive(Debug)]
+pub struct Histogram {
+  bounds: &'static [f64],
+  /// Non-cumulative count per bucket; the extra last slot is `+Inf`.
+  buckets: Box<[AtomicU64]>,
+  count: AtomicU64,
+  /// `f64` sum stored as raw bits.
+  sum: AtomicU64,
+}
+
+impl Histogram {
+  /// Create a histogram with the given ascending upper bounds.
+  #[must_use]
+  pub fn new(bounds: &'static [f64]) -> Self {
+    Self {
+      bounds,
+      buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+      count: AtomicU64::new(0),
+      sum: AtomicU64::new(0f64.to_bits()),
+    }
+  }
+
+  /// Record one observation.
+  pub fn observe(&self, value: f64) {
+    let idx = self
+      .bounds
+      .iter()
+      .position(|&bound| value <= bound)
+      .unwrap_or(self.bounds.len());
+    self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+    self.count.fetch_add(1, Ordering::Relaxed);
+    let _ = self
+      .sum
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some((f64::from_bits(bits) + value).to_bits())
+      });
+  }
+
+  /// Record a duration in seconds.
+  pub fn observe_duration(&self, duration: Duration) {
+    self.observe(duration.as_secs_f64());
+  }
+
+  /// Number of observations.
+  #[must_use]
+  pub fn count(&self) -> u64 {
+    self.count.load(Ordering::Relaxed)
+  }
+
+  /// Sum of all observations.
+  #[must_use]
+  pub fn sum(&self) -> f64 {
+    f64::from_bits(self.sum.load(Ordering::Relaxed))
+  }
+
+  fn write(&self, out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    let mut cumulative = 0;
+    for (bound, bucket) in self.bounds.iter().zip(self.buckets.iter()) {
+      cumulative += bucket.load(Ordering::Relaxed);
+      let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+    }
+    cumulative += self.buckets[self.bounds.len()].load(Ordering::Relaxed);
+    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}");
+    let _ = writeln!(out, "{name}_sum {}", self.sum());
+    let _ = writeln!(out, "{name}_count {cumulative}");
+  }
+}
+
+/// Process-level counters and histograms.
+#[derive(Debug)]
+pub struct Metrics {
+  /// WebSocket connections accepted.
+  pub ws_connections_opened: Counter,
+  /// `TokenAuth` attempts rejected.
+  pub ws_auth_failures: Counter,
+  /// Lifetime of closed WebSocket connections.
+  pub ws_connection_seconds: Histogram,
+  /// Inbound frames that failed frame or signaling decoding.
+  pub signaling_decode_errors: Counter,
+  /// Time spent handling one decoded signaling message.
+  pub signaling_handle_seconds: Histogram,
+  /// Size of inbound binary frames.
+  pub signaling_message_bytes: Histogram,
+  /// Inbound signaling messages per `SignalingMessage` discriminator.
+  signaling_messages: Box<[AtomicU64]>,
+  /// STUN Binding requests answered.
+  pub stun_binding_requests: Counter,
+  /// Packets handed to the embedded TURN relay.
+  pub turn_packets: Counter,
+  /// Datagrams dropped as malformed or unsupported.
+  pub stun_dropped_packets: Counter,
+}
+
+impl Default for Metrics {
+  fn default() -> Self {
+    Self {
+      ws_connections_opened: Counter::default(),
+      ws_auth_failures: Counter::default(),
+      ws_connection_seconds: Histogram::new(CONNECTION_SECONDS_BUCKETS),
+      signaling_decode_errors: Counter::default(),
+      signaling_handle_seconds: Histogram::new(HANDLE_SECONDS_BUCKETS),
+      signaling_message_bytes: Histogram::new(MESSAGE_BYTES_BUCKETS),
+      signaling_messages: (0..=u8::MAX).map(|_| AtomicU64::new(0)).collect(),
+      stun_binding_requests: Counter::default(),
+      turn_packets: Counter::default(),
+      stun_dropped_packets: Counter::default(),
+    }
+  }
+}
+
+impl Metrics {
+  /// Count one inbound signaling message of the given discriminator.
+  pub fn record_signaling_message(&self, discriminator: u8) {
+    self.signaling_messages[usize::from(discriminator)].fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Inbound signaling messages counted for `discriminator`.
+  #[must_use]
+  pub fn signaling_message_count(&self, discriminator: u8) -> u64 {
+    self.signaling_messages[usize::from(discriminator)].load(Ordering::Relaxed)
+  }
+}
+
+/// Render every metric in the Prometheus text format.
+#[must_use]
+pub fn render(ws_state: &WebSocketState) -> String {
+  let metrics = ws_state.metrics();
+  let discovery = ws_state.discovery_state().stats();
+  let room_state = ws_state.room_state();
+  let mut out = String::with_capacity(4096);
+
+  gauge(
+    &mut out,
+    "chat_ws_connections",
+    "Authenticated WebSocket connections.",
+    ws_state.connection_count() as u64,
+  );
+  counter(
+    &mut out,
+    "chat_ws_connections_opened_total",
+    "WebSocket connections accepted.",
+    metrics.ws_connections_opened.get(),
+  );
+  counter(
+    &mut out,
+    "chat_ws_auth_failures_total",
+    "Rejected TokenAuth attempts.",
+    metrics.ws_auth_failures.get(),
+  );
+  metrics.ws_connection_seconds.write(
+    &mut out,
+    "chat_ws_connection_duration_seconds",
+    "Lifetime of closed WebSocket connections.",
+  );
+
+  let _ = writeln!(
+    out,
+    "# HELP chat_signaling_messages_total Inbound signaling messages by discriminator."
+  );
+  let _ = writeln!(out, "# TYPE chat_signaling_messages_total counter");
+  for discriminator in 0..=u8::MAX {
+    let count = metrics.signaling_message_count(discriminator);
+    if count > 0 {
+      let _ = writeln!(
+        out,
+        "chat_signaling_messages_total{{type=\"0x{discriminator:02x}\"}} {count}"
+      );
+    }
+  }
+  counter(
+    &mut out,
+    "chat_signaling_decode_errors_total",
+    "Inbound frames that failed to decode.",
+    metrics.signaling_decode_errors.get(),
+  );
+  metrics.signaling_handle_seconds.write(
+    &mut out,
+    "chat_signaling_handle_duration_seconds",
+    "Time spent handling one signaling message.",
+  );
+  metrics.signaling_message_bytes.write(
+    &mut out,
+    "chat_signaling_message_bytes",
+    "Size of inbound binary frames.",
+  );
+
+  gauge(
+    &mut out,
+    "chat_rooms",
+    "Rooms currently held in memory.",
+    room_state.room_count() as u64,
+  );
+  gauge(
+    &mut out,
+    "chat_room_members",
+    "Users currently in a room.",
+    room_state.total_member_count() as u64,
+  );
+  if let Some(sfu) = ws_state.sfu() {
+    gauge(
+      &mut out,
+      "chat_sfu_sessions",
+      "Open SFU media sessions.",
+      sfu.session_count() as u64,
+    );
+    gauge(
+      &mut out,
+      "chat_sfu_tracks",
+      "Tracks forwarded by the SFU.",
+      sfu.forwarded_track_count() as u64,
+    );
+  }
+
+  gauge(
+    &mut out,
+    "chat_pending_invitations",
+    "Unanswered connection invitations.",
+    discovery.pending_invitations as u64,
+  );
+  gauge(
+    &mut out,
+    "chat_multi_invites",
+    "Open multi-user invitations.",
+    discovery.multi_invites as u64,
+  );
+  gauge(
+    &mut out,
+    "chat_sdp_negotiations",
+    "SDP negotiations in progress.",
+    discovery.sdp_negotiations as u64,
+  );
+  counter(
+    &mut out,
+    "chat_invite_rate_limited_total",
+    "Invitations rejected by the per-user rate limit.",
+    discovery.rate_limit_hits,
+  );
+  counter(
+    &mut out,
+    "chat_sdp_negotiation_timeouts_total",
+    "SDP negotiations expired before completing.",
+    discovery.sdp_timeouts,
+  );
+
+  counter(
+    &mut out,
+    "chat_stun_binding_requests_total",
+    "STUN Binding requests answered.",
+    metrics.stun_binding_requests.get(),
+  );
+  counter(
+    &mut out,
+    "chat_turn_packets_total",
+    "Packets handled by the embedded TURN relay.",
+    metrics.turn_packets.get(),
+  );
+  counter(
+    &mut out,
+    "chat_stun_dropped_packets_total",
+    "STUN datagrams dropped as malformed or unsupported.",
+    metrics.stun_dropped_packets.get(),
+  );
+
+  out
+}
+
+/// Handle `GET /metrics`.
+pub async fn metrics_handler(State(ws_state): State<Arc<WebSocketState>>) -> impl IntoResponse {
+  ([(header::CONTENT_TYPE, CONTENT_TYPE)], render(&ws_state))
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+  sample(out, name, help, "counter", value);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+  sample(out, name, help, "gauge", value);
+}
+
+fn sample(out: &mut String, name: &str, help: &str, kind: &str, value: u64) {
+  let _ = writeln!(out, "# HELP {name} {help}");
+  let _ = writeln!(out, "# TYPE {name} {kind}");
+  let _ = writeln!(out, "{name} {value}");
+}
+
+#[cfg(test)]
+mod tests;
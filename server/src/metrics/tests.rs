@@ -0,0 +1,93 @@
+use super::*;
+use crate::auth::UserStore;
+use crate::config::Config;
+
+fn test_ws_state() -> WebSocketState {
+  let config = Config::default();
+  let user_store = UserStore::new(&config);
+  WebSocketState::new(config, user_store)
+}
+
+#[test]
+fn test_counter_increments() {
+  let counter = Counter::default();
+  counter.inc();
+  counter.add(4);
+  assert_eq!(counter.get(), 5);
+}
+
+#[test]
+fn test_histogram_buckets_are_cumulative() {
+  const BOUNDS: &[f64] = &[1.0, 10.0];
+  let histogram = Histogram::new(BOUNDS);
+  histogram.observe(0.5);
+  histogram.observe(1.0);
+  histogram.observe(5.0);
+  histogram.observe(50.0);
+  assert_eq!(histogram.count(), 4);
+  assert!((histogram.sum() - 56.5).abs() < f64::EPSILON);
+
+  let mut out = String::new();
+  histogram.write(&mut out, "h", "help");
+  assert!(out.contains("# TYPE h histogram\n"));
+  assert!(out.contains("h_bucket{le=\"1\"} 2\n"));
+  assert!(out.contains("h_bucket{le=\"10\"} 3\n"));
+  assert!(out.contains("h_bucket{le=\"+Inf\"} 4\n"));
+  assert!(out.contains("h_sum 56.5\n"));
+  assert!(out.contains("h_count 4\n"));
+}
+
+#[test]
+fn test_signaling_messages_labelled_by_discriminator() {
+  let state = test_ws_state();
+  state.metrics().record_signaling_message(0x01);
+  state.metrics().record_signaling_message(0x01);
+  state.metrics().record_signaling_message(0x30);
+
+  let out = render(&state);
+  assert!(out.contains("chat_signaling_messages_total{type=\"0x01\"} 2\n"));
+  assert!(out.contains("chat_signaling_messages_total{type=\"0x30\"} 1\n"));
+  assert!(!out.contains("type=\"0x02\""));
+}
+
+#[test]
+fn test_render_reports_live_gauges() {
+  let state = test_ws_state();
+  state
+    .room_state()
+    .create_room(
+      &message::signaling::CreateRoom {
+        name: "Metrics".to_string(),
+        description: String::new(),
+        room_type: message::types::RoomType::Chat,
+        password: None,
+        max_participants: 8,
+      },
+      message::UserId::new(),
+    )
+    .unwrap();
+
+  let out = render(&state);
+  assert!(out.contains("# TYPE chat_rooms gauge\nchat_rooms 1\n"));
+  assert!(out.contains("chat_room_members 1\n"));
+  assert!(out.contains("chat_ws_connections 0\n"));
+  assert!(out.contains("chat_invite_rate_limited_total 0\n"));
+  assert!(out.contains("chat_stun_binding_requests_total 0\n"));
+}
+
+#[test]
+fn test_render_lines_are_well_formed() {
+  let state = test_ws_state();
+  for line in render(&state).lines() {
+    if line.starts_with('#') {
+      assert!(
+        line.starts_with("# HELP ") || line.starts_with("# TYPE "),
+        "{line}"
+      );
+    } else {
+      let (name, value) = line.rsplit_once(' ').expect("sample has a value");
+      assert!(name.starts_with("chat_"), "{line}");
+      assert!(value.parse::<f64>().is_ok(), "{line}");
+    }
+  }
+}
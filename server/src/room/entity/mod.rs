@@ -8,7 +8,7 @@ use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, Salt
 use message::types::{MemberInfo, MuteInfo, RoomId, RoomInfo, RoomRole, RoomType, UserId};
 use tracing::info;
 
-use super::RoomError;
+use super::{RoomError, RoomSnapshot};
 
 // =============================================================================
 // Constants
@@ -60,6 +60,22 @@ impl Room {
     }
   }
 
+  /// Rebuild a dormant room from its persisted metadata.
+  ///
+  /// Membership is not persisted, so the room comes back empty; the
+  /// owner regains the `Owner` role when they re-join (see
+  /// [`Self::add_member`]).
+  #[must_use]
+  pub fn restore(mut info: RoomInfo, banned_users: Vec<UserId>) -> Self {
+    info.member_count = 0;
+    Self {
+      info,
+      members: HashMap::new(),
+      banned_users,
+      join_order: Vec::new(),
+    }
+  }
+
   /// Check if the room is password protected.
   #[must_use]
   pub const fn is_password_protected(&self) -> bool {
@@ -179,8 +195,14 @@ impl Room {
       return Err(RoomError::AlreadyMember);
     }
 
-    // Add member
-    let member = MemberInfo::new(user_id.clone(), nickname, RoomRole::Member);
+    // Add member. Only reachable for the owner when re-joining a room
+    // restored from storage, in which case they take their role back.
+    let role = if user_id == self.info.owner_id {
+      RoomRole::Owner
+    } else {
+      RoomRole::Member
+    };
+    let member = MemberInfo::new(user_id.clone(), nickname, role);
     self.members.insert(user_id.clone(), member);
     self.join_order.push(user_id);
 
@@ -434,6 +456,32 @@ impl Room {
   pub fn get_members(&self) -> Vec<MemberInfo> {
     self.members.values().cloned().collect()
   }
+
+  /// Capture the full room state for replication.
+  #[must_use]
+  pub fn to_snapshot(&self) -> RoomSnapshot {
+    RoomSnapshot {
+      info: self.info.clone(),
+      members: self.get_members(),
+      banned_users: self.banned_users.clone(),
+      join_order: self.join_order.clone(),
+    }
+  }
+
+  /// Rebuild a room from a replicated snapshot.
+  #[must_use]
+  pub fn from_snapshot(snapshot: RoomSnapshot) -> Self {
+    Self {
+      info: snapshot.info,
+      members: snapshot
+        .members
+        .into_iter()
+        .map(|member| (member.user_id.clone(), member))
+        .collect(),
+      banned_users: snapshot.banned_users,
+      join_order: snapshot.join_order,
+    }
+  }
 }
 
 #[cfg(test)]
@@ -4,6 +4,7 @@
 //! - Room entity with member management
 //! - Global room state management
 //! - Room-related types and errors
+//! - Replication of room state between cluster nodes
 
 mod entity;
 mod state;
@@ -12,7 +13,9 @@ mod types;
 // Re-export public types
 pub use entity::Room;
 pub use state::RoomState;
-pub use types::{LeaveRoomResult, PermissionCheckResult, RoomError};
+pub use types::{
+  LeaveRoomResult, PermissionCheckResult, RoomError, RoomReplica, RoomSnapshot, RoomVersion,
+};
 
 // =============================================================================
 // Tests
@@ -1,6 +1,7 @@
 //! Room state manager for handling multiple rooms.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use dashmap::DashMap;
 use message::signaling::{
@@ -8,16 +9,18 @@ use message::signaling::{
   MuteMember, NicknameChange, PromoteAdmin, RoomAnnouncement, TransferOwnership, UnbanMember,
   UnmuteMember,
 };
-use message::types::{MemberInfo, MuteInfo, RoomId, RoomInfo, RoomRole, UserId};
-use tracing::{debug, info};
+use message::types::{MemberInfo, MuteInfo, RoomId, RoomInfo, RoomRole, RoomTopology, UserId};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
 
-use super::{LeaveRoomResult, PermissionCheckResult, Room, RoomError};
+use super::{LeaveRoomResult, PermissionCheckResult, Room, RoomError, RoomReplica, RoomVersion};
+use crate::storage::{MemoryStorage, Storage, StoredRoom};
 
 // =============================================================================
 // Constants
 // =============================================================================
 
-/// Maximum number of members per room.
+/// Maximum number of members per mesh room.
 const MAX_MEMBERS_PER_ROOM: u8 = 8;
 /// Maximum room name length.
 const MAX_ROOM_NAME_LENGTH: usize = 100;
@@ -92,15 +95,220 @@ pub struct RoomState {
   rooms: DashMap<RoomId, Room>,
   /// User to room mapping (for quick lookup of user's current room).
   user_rooms: DashMap<UserId, RoomId>,
+  /// Durable backing store for room metadata and bans.
+  storage: Arc<dyn Storage>,
+  /// Capacity limit for SFU rooms; `None` when the SFU is disabled
+  /// and every room stays on the mesh.
+  sfu_max_members: Option<u8>,
+  /// Change tracking for cluster replication; `None` on a single node.
+  replication: Option<Replication>,
+}
+
+/// Bookkeeping for replicating rooms to other cluster nodes.
+#[derive(Debug)]
+struct Replication {
+  /// This node's ID, stamped on local changes.
+  node: String,
+  /// Version of every room seen so far. Entries outlive destroyed
+  /// rooms so a stale snapshot cannot bring one back.
+  versions: DashMap<RoomId, RoomVersion>,
+  /// Rooms changed locally since the last [`RoomState::take_changes`].
+  pending: Mutex<HashSet<RoomId>>,
+  /// Woken whenever a room is added to `pending`.
+  notify: Arc<Notify>,
 }
 
 impl RoomState {
-  /// Create a new room state.
+  /// Create a new room state backed by volatile memory storage.
   #[must_use]
   pub fn new() -> Self {
+    Self::with_storage(Arc::new(MemoryStorage::new()))
+  }
+
+  /// Create a room state on top of `storage`, restoring every room it
+  /// already holds as a dormant (memberless) room.
+  #[must_use]
+  pub fn with_storage(storage: Arc<dyn Storage>) -> Self {
+    let rooms = DashMap::new();
+    for stored in storage.rooms() {
+      let room = Room::restore(stored.info, stored.banned_users);
+      rooms.insert(room.room_id().clone(), room);
+    }
+    if !rooms.is_empty() {
+      info!(count = rooms.len(), "Restored rooms from storage");
+    }
+
     Self {
-      rooms: DashMap::new(),
+      rooms,
       user_rooms: DashMap::new(),
+      storage,
+      sfu_max_members: None,
+      replication: None,
+    }
+  }
+
+  /// Allow rooms of up to `max_members` by switching any room created
+  /// with more than 8 participants to the SFU topology.
+  #[must_use]
+  pub const fn with_sfu_capacity(mut self, max_members: u8) -> Self {
+    self.sfu_max_members = Some(max_members);
+    self
+  }
+
+  /// Track local changes so they can be replicated to other cluster
+  /// nodes. `node` identifies this node in [`RoomVersion`]s.
+  #[must_use]
+  pub fn with_replication(mut self, node: impl Into<String>) -> Self {
+    self.replication = Some(Replication {
+      node: node.into(),
+      versions: DashMap::new(),
+      pending: Mutex::new(HashSet::new()),
+      notify: Arc::new(Notify::new()),
+    });
+    self
+  }
+
+  /// Notified whenever a room changes locally (`None` without
+  /// replication).
+  #[must_use]
+  pub fn change_notify(&self) -> Option<Arc<Notify>> {
+    self.replication.as_ref().map(|r| r.notify.clone())
+  }
+
+  /// Record that `room_id` changed locally. No-op without replication.
+  fn changed(&self, room_id: &RoomId) {
+    if let Some(replication) = &self.replication {
+      replication
+        .pending
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .insert(room_id.clone());
+      replication.notify.notify_one();
+    }
+  }
+
+  /// Current version of a room. A room that has not changed since
+  /// startup sorts before every replicated version.
+  fn version_of(replication: &Replication, room_id: &RoomId) -> RoomVersion {
+    replication
+      .versions
+      .get(room_id)
+      .map(|v| v.clone())
+      .unwrap_or_default()
+  }
+
+  /// Drain the rooms changed locally since the last call, stamping
+  /// each with a new version.
+  pub fn take_changes(&self) -> Vec<RoomReplica> {
+    let Some(replication) = &self.replication else {
+      return Vec::new();
+    };
+    let pending: Vec<RoomId> = replication
+      .pending
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+      .drain()
+      .collect();
+    pending
+      .into_iter()
+      .map(|room_id| {
+        let version = RoomVersion {
+          revision: Self::version_of(replication, &room_id).revision + 1,
+          node: replication.node.clone(),
+        };
+        replication
+          .versions
+          .insert(room_id.clone(), version.clone());
+        let room = self.rooms.get(&room_id).map(|r| r.to_snapshot());
+        RoomReplica {
+          room_id,
+          version,
+          room,
+        }
+      })
+      .collect()
+  }
+
+  /// Every live room at its current version, for a node that has
+  /// just joined the cluster.
+  #[must_use]
+  pub fn replicas(&self) -> Vec<RoomReplica> {
+    let Some(replication) = &self.replication else {
+      return Vec::new();
+    };
+    self
+      .rooms
+      .iter()
+      .map(|room| RoomReplica {
+        room_id: room.room_id().clone(),
+        version: Self::version_of(replication, room.room_id()),
+        room: Some(room.to_snapshot()),
+      })
+      .collect()
+  }
+
+  /// Apply a room replicated from another node.
+  ///
+  /// The replica replaces the local room (or destroys it) only if its
+  /// version is newer than the one already applied; returns whether
+  /// it did.
+  pub fn apply_replica(&self, replica: RoomReplica) -> bool {
+    let Some(replication) = &self.replication else {
+      return false;
+    };
+    if replica.version <= Self::version_of(replication, &replica.room_id) {
+      return false;
+    }
+    replication
+      .versions
+      .insert(replica.room_id.clone(), replica.version);
+
+    let room_id = replica.room_id;
+    let previous = self.rooms.remove(&room_id).map(|(_, room)| room);
+    if let Some(previous) = &previous {
+      for user_id in previous.members.keys() {
+        self
+          .user_rooms
+          .remove_if(user_id, |_, current| *current == room_id);
+      }
+    }
+    match replica.room {
+      Some(snapshot) => {
+        let room = Room::from_snapshot(snapshot);
+        for user_id in room.members.keys() {
+          self.user_rooms.insert(user_id.clone(), room_id.clone());
+        }
+        let room = self.rooms.entry(room_id).insert(room);
+        self.persist(&room);
+      }
+      None => {
+        if previous.is_some() {
+          self.forget(&room_id);
+        }
+      }
+    }
+    true
+  }
+
+  /// Mirror a room's durable fields into storage. Failures are logged:
+  /// the in-memory change has already been applied and broadcast.
+  ///
+  /// Call this while still holding the `rooms` guard so that racing
+  /// writes to the same room reach storage in the order they happened.
+  fn persist(&self, room: &Room) {
+    let stored = StoredRoom {
+      info: room.to_room_info(),
+      banned_users: room.banned_users.clone(),
+    };
+    if let Err(e) = self.storage.put_room(&stored) {
+      warn!(room_id = %room.room_id(), error = %e, "Failed to persist room");
+    }
+  }
+
+  /// Drop a destroyed room from storage.
+  fn forget(&self, room_id: &RoomId) {
+    if let Err(e) = self.storage.delete_room(room_id) {
+      warn!(room_id = %room_id, error = %e, "Failed to delete persisted room");
     }
   }
 
@@ -130,8 +338,17 @@ impl RoomState {
       owner_id.clone(),
     );
 
-    // Set max participants
-    room.info.max_members = request.max_participants.clamp(2, MAX_MEMBERS_PER_ROOM);
+    // Set max participants. Rooms beyond the mesh limit relay media
+    // through the SFU when it is enabled, and are clamped otherwise.
+    match self.sfu_max_members {
+      Some(sfu_max) if request.max_participants > MAX_MEMBERS_PER_ROOM => {
+        room.info.max_members = request.max_participants.min(sfu_max);
+        room.info.topology = RoomTopology::Sfu;
+      }
+      _ => {
+        room.info.max_members = request.max_participants.clamp(2, MAX_MEMBERS_PER_ROOM);
+      }
+    }
 
     // Set optional description (Req 4.1).
     room.info.description = request.description.clone();
@@ -148,7 +365,9 @@ impl RoomState {
 
     // Store room
     let room_info = room.to_room_info();
+    self.persist(&room);
     self.rooms.insert(room_id.clone(), room);
+    self.changed(&room_id);
 
     info!(
       room_id = %room_id,
@@ -194,6 +413,7 @@ impl RoomState {
 
     // Track user -> room mapping
     self.user_rooms.insert(user_id, request.room_id.clone());
+    self.changed(&request.room_id);
 
     Ok((room_info, members))
   }
@@ -250,6 +470,8 @@ impl RoomState {
     if room.is_empty() {
       drop(room);
       self.rooms.remove(&room_id);
+      self.forget(&room_id);
+      self.changed(&room_id);
 
       info!(
         room_id = %room_id,
@@ -266,6 +488,11 @@ impl RoomState {
       });
     }
 
+    if transfer_result.is_some() {
+      self.persist(&room);
+    }
+    self.changed(&room_id);
+
     let room_info = room.to_room_info();
     let members = room.get_members();
 
@@ -310,6 +537,7 @@ impl RoomState {
       && let Some(member) = room.get_member_mut(user_id)
     {
       member.nickname = nickname;
+      self.changed(room_id);
     }
   }
 
@@ -377,6 +605,7 @@ impl RoomState {
 
     // Remove from user -> room mapping
     self.user_rooms.remove(&request.target);
+    self.changed(&request.room_id);
 
     let room_info = room.to_room_info();
 
@@ -415,6 +644,7 @@ impl RoomState {
       .ok_or(RoomError::RoomNotFound)?;
 
     let mute_info = room.mute_member(&request.target, request.duration_secs)?;
+    self.changed(&request.room_id);
 
     let member = room.get_member(&request.target).cloned().unwrap();
 
@@ -454,6 +684,7 @@ impl RoomState {
       .ok_or(RoomError::RoomNotFound)?;
 
     room.unmute_member(&request.target)?;
+    self.changed(&request.room_id);
 
     let member = room.get_member(&request.target).cloned().unwrap();
 
@@ -496,9 +727,11 @@ impl RoomState {
 
     // Ban user
     room.ban_user(request.target.clone())?;
+    self.persist(&room);
 
     // Remove from user -> room mapping
     self.user_rooms.remove(&request.target);
+    self.changed(&request.room_id);
 
     let room_info = room.to_room_info();
 
@@ -535,6 +768,8 @@ impl RoomState {
     if !room.unban_user(&request.target) {
       return Err(RoomError::NotBanned);
     }
+    self.persist(&room);
+    self.changed(&request.room_id);
 
     info!(
       room_id = %request.room_id,
@@ -571,6 +806,7 @@ impl RoomState {
       .ok_or(RoomError::RoomNotFound)?;
 
     room.promote_admin(&request.target)?;
+    self.changed(&request.room_id);
 
     let member = room.get_member(&request.target).cloned().unwrap();
 
@@ -609,6 +845,7 @@ impl RoomState {
       .ok_or(RoomError::RoomNotFound)?;
 
     room.demote_admin(&request.target)?;
+    self.changed(&request.room_id);
 
     let member = room.get_member(&request.target).cloned().unwrap();
 
@@ -641,6 +878,8 @@ impl RoomState {
 
     // Transfer ownership
     room.transfer_ownership(&request.target)?;
+    self.persist(&room);
+    self.changed(&request.room_id);
 
     // Get owner info AFTER transfer - old owner is now Admin, new owner is Owner
     let old_owner = room.get_member(actor_id).cloned().unwrap();
@@ -676,6 +915,8 @@ impl RoomState {
     }
 
     room.set_room_info(request.name.clone(), request.description.clone())?;
+    self.persist(&room);
+    self.changed(&request.room_id);
     let updated = room.to_room_info();
 
     info!(
@@ -717,6 +958,8 @@ impl RoomState {
       }
     };
 
+    self.persist(&room);
+    self.changed(&request.room_id);
     let updated = room.to_room_info();
 
     info!(
@@ -747,6 +990,8 @@ impl RoomState {
     }
 
     room.set_announcement(request.content.clone())?;
+    self.persist(&room);
+    self.changed(&request.room_id);
 
     info!(
       room_id = %request.room_id,
@@ -774,6 +1019,7 @@ impl RoomState {
       .ok_or(RoomError::RoomNotFound)?;
 
     room.set_nickname(&request.user_id, request.new_nickname.clone())?;
+    self.changed(&room_id_value);
 
     debug!(
       room_id = %room_id_value,
@@ -785,6 +1031,49 @@ impl RoomState {
     Ok(())
   }
 
+  /// Rename a room on behalf of a server administrator.
+  ///
+  /// Applies the same validation as [`Self::update_room_info`] but
+  /// skips the owner check and keeps the current description.
+  pub fn rename_room(&self, room_id: &RoomId, name: &str) -> Result<RoomInfo, RoomError> {
+    validate_room_name(name)?;
+
+    let mut room = self.rooms.get_mut(room_id).ok_or(RoomError::RoomNotFound)?;
+    let description = room.info.description.clone();
+    room.set_room_info(name.to_string(), description)?;
+    self.persist(&room);
+    self.changed(room_id);
+
+    info!(room_id = %room_id, "Room renamed by administrator");
+
+    Ok(room.to_room_info())
+  }
+
+  /// Destroy a room regardless of its members.
+  ///
+  /// Every member is detached from the room and the room is dropped
+  /// from storage. Returns the members that were removed so the
+  /// caller can notify them.
+  pub fn close_room(&self, room_id: &RoomId) -> Result<Vec<MemberInfo>, RoomError> {
+    let (_, room) = self.rooms.remove(room_id).ok_or(RoomError::RoomNotFound)?;
+    let members = room.get_members();
+    for member in &members {
+      self
+        .user_rooms
+        .remove_if(&member.user_id, |_, current| current == room_id);
+    }
+    self.forget(room_id);
+    self.changed(room_id);
+
+    info!(
+      room_id = %room_id,
+      member_count = members.len(),
+      "Room closed by administrator"
+    );
+
+    Ok(members)
+  }
+
   /// Check and update expired mutes across all rooms.
   /// Returns map of room_id -> list of user IDs whose mutes were expired.
   pub fn check_expired_mutes(&self) -> HashMap<RoomId, Vec<UserId>> {
@@ -797,6 +1086,9 @@ impl RoomState {
         result.insert(room.room_id().clone(), expired);
       }
     }
+    for room_id in result.keys() {
+      self.changed(room_id);
+    }
 
     result
   }
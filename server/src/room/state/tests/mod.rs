@@ -3,6 +3,7 @@
 mod creation;
 mod edge_cases;
 mod moderation;
+mod replication;
 mod role_management;
 mod room_lifecycle;
 mod validation;
@@ -0,0 +1,127 @@
+//! Cluster replication tests: change tracking and last-writer-wins.
+
+use message::signaling::{JoinRoom, LeaveRoom};
+
+use super::super::RoomState;
+use super::*;
+use crate::room::RoomVersion;
+
+#[test]
+fn test_changes_not_tracked_without_replication() {
+  let state = RoomState::new();
+  state
+    .create_room(&create_room_request(), test_user_id(1))
+    .unwrap();
+
+  assert!(state.change_notify().is_none());
+  assert!(state.take_changes().is_empty());
+  assert!(state.replicas().is_empty());
+}
+
+#[test]
+fn test_take_changes_bumps_revision() {
+  let state = RoomState::new().with_replication("a");
+  let (room_id, _) = state
+    .create_room(&create_room_request(), test_user_id(1))
+    .unwrap();
+
+  let changes = state.take_changes();
+  assert_eq!(changes.len(), 1);
+  assert_eq!(changes[0].room_id, room_id);
+  assert_eq!(
+    changes[0].version,
+    RoomVersion {
+      revision: 1,
+      node: "a".to_string(),
+    }
+  );
+  assert!(state.take_changes().is_empty());
+
+  let join = JoinRoom {
+    room_id: room_id.clone(),
+    password: None,
+  };
+  state
+    .join_room(&join, test_user_id(2), "member".to_string())
+    .unwrap();
+  let changes = state.take_changes();
+  assert_eq!(changes[0].version.revision, 2);
+  assert_eq!(changes[0].room.as_ref().unwrap().members.len(), 2);
+}
+
+#[test]
+fn test_apply_replica_creates_and_destroys_room() {
+  let a = RoomState::new().with_replication("a");
+  let b = RoomState::new().with_replication("b");
+  let owner = test_user_id(1);
+  let (room_id, _) = a
+    .create_room(&create_room_request(), owner.clone())
+    .unwrap();
+
+  for replica in a.take_changes() {
+    assert!(b.apply_replica(replica));
+  }
+  assert_eq!(b.get_user_room(&owner), Some(room_id.clone()));
+  assert_eq!(b.get_room(&room_id).unwrap().info.name, "test-room");
+  // Applying a replica is not itself a local change
+  assert!(b.take_changes().is_empty());
+
+  a.leave_room(
+    &LeaveRoom {
+      room_id: room_id.clone(),
+    },
+    &owner,
+  )
+  .unwrap();
+  for replica in a.take_changes() {
+    assert!(replica.room.is_none());
+    assert!(b.apply_replica(replica));
+  }
+  assert_eq!(b.room_count(), 0);
+  assert!(b.get_user_room(&owner).is_none());
+}
+
+#[test]
+fn test_apply_replica_last_writer_wins() {
+  let a = RoomState::new().with_replication("a");
+  let b = RoomState::new().with_replication("b");
+  let (room_id, _) = a
+    .create_room(&create_room_request(), test_user_id(1))
+    .unwrap();
+  let created = a.take_changes().remove(0);
+  assert!(b.apply_replica(created.clone()));
+
+  // Replaying the same or an older version is ignored
+  assert!(!b.apply_replica(created.clone()));
+
+  // Concurrent changes at the same revision: the larger node ID wins
+  let mut from_a = created.clone();
+  from_a.version = RoomVersion {
+    revision: 2,
+    node: "a".to_string(),
+  };
+  let mut from_b = created;
+  from_b.version = RoomVersion {
+    revision: 2,
+    node: "b".to_string(),
+  };
+  assert!(b.apply_replica(from_a.clone()));
+  assert!(b.apply_replica(from_b.clone()));
+  assert!(a.apply_replica(from_b));
+  assert!(!a.apply_replica(from_a));
+
+  // A later local change continues from the applied version
+  let join = JoinRoom {
+    room_id,
+    password: None,
+  };
+  a.join_room(&join, test_user_id(2), "member".to_string())
+    .unwrap();
+  assert_eq!(
+    a.take_changes()[0].version,
+    RoomVersion {
+      revision: 3,
+      node: "a".to_string(),
+    }
+  );
+}
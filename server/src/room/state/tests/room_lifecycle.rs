@@ -1,7 +1,7 @@
 //! Room lifecycle tests: create, join, leave.
 
 use message::signaling::{JoinRoom, LeaveRoom};
-use message::types::RoomType;
+use message::types::{RoomTopology, RoomType};
 
 use super::super::RoomState;
 use super::*;
@@ -67,6 +67,36 @@ fn test_create_room_max_participants_clamped() {
 
   let (_, room_info) = result.unwrap();
   assert_eq!(room_info.max_members, 8);
+  assert_eq!(room_info.topology, RoomTopology::Mesh);
+}
+
+#[test]
+fn test_create_large_room_uses_sfu_when_enabled() {
+  let state = RoomState::new().with_sfu_capacity(32);
+  let mut request = create_room_request();
+  request.max_participants = 20;
+
+  let (_, room_info) = state.create_room(&request, test_user_id(1)).unwrap();
+  assert_eq!(room_info.max_members, 20);
+  assert_eq!(room_info.topology, RoomTopology::Sfu);
+
+  // Capped at the configured SFU capacity.
+  let mut request = create_room_request();
+  request.room_type = RoomType::Theater;
+  request.max_participants = 200;
+  let (_, room_info) = state.create_room(&request, test_user_id(2)).unwrap();
+  assert_eq!(room_info.max_members, 32);
+  assert_eq!(room_info.topology, RoomTopology::Sfu);
+}
+
+#[test]
+fn test_create_small_room_stays_mesh_with_sfu_enabled() {
+  let state = RoomState::new().with_sfu_capacity(32);
+  let (_, room_info) = state
+    .create_room(&create_room_request(), test_user_id(1))
+    .unwrap();
+  assert_eq!(room_info.max_members, 8);
+  assert_eq!(room_info.topology, RoomTopology::Mesh);
 }
 
 #[test]
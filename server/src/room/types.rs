@@ -1,5 +1,6 @@
 //! Room-related types and error definitions.
 
+use bitcode::{Decode, Encode};
 use message::types::{MemberInfo, RoomId, RoomInfo, RoomRole, UserId};
 
 // =============================================================================
@@ -99,3 +100,44 @@ pub struct PermissionCheckResult {
   /// Target's role.
   pub target_role: RoomRole,
 }
+
+// =============================================================================
+// Replication Types
+// =============================================================================
+
+/// Version of a replicated room.
+///
+/// Ordered by `revision` first; concurrent changes made on two nodes
+/// with the same revision are settled by comparing the node IDs, so
+/// every node picks the same winner.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Encode, Decode)]
+pub struct RoomVersion {
+  /// Number of changes applied to the room.
+  pub revision: u64,
+  /// Node that made the change.
+  pub node: String,
+}
+
+/// Full state of a room, as shipped between cluster nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RoomSnapshot {
+  /// Room information, including password hash and announcement.
+  pub info: RoomInfo,
+  /// Current members.
+  pub members: Vec<MemberInfo>,
+  /// Banned user IDs.
+  pub banned_users: Vec<UserId>,
+  /// Member join order (oldest first).
+  pub join_order: Vec<UserId>,
+}
+
+/// Latest state of one room at a given version.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RoomReplica {
+  /// Room ID.
+  pub room_id: RoomId,
+  /// Version of this state.
+  pub version: RoomVersion,
+  /// Room state, or `None` once the room has been destroyed.
+  pub room: Option<RoomSnapshot>,
+}
@@ -6,7 +6,7 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::Router;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
@@ -15,7 +15,10 @@ use tracing::{info, warn};
 
 use crate::auth::UserStore;
 use crate::auth::handlers;
+use crate::cluster::{ClusterBus, TcpBus};
 use crate::config::Config;
+use crate::room::RoomState;
+use crate::storage::{MemoryStorage, Storage};
 use crate::ws::{WebSocketState, ws_handler};
 
 pub use health::{HealthResponse, health_check, spa_fallback};
@@ -23,13 +26,38 @@ pub use health::{HealthResponse, health_check, spa_fallback};
 /// WebRTC Chat signaling server.
 pub struct Server {
   config: Config,
+  storage: Arc<dyn Storage>,
+  cluster: Option<Arc<dyn ClusterBus>>,
 }
 
 impl Server {
-  /// Create a new server instance.
+  /// Create a new server instance backed by volatile memory storage.
+  ///
+  /// `config.storage` is ignored here; use [`Self::with_storage`] with
+  /// a backend from [`crate::storage::open`] for durable state.
   #[must_use]
   pub fn new(config: Config) -> Self {
-    Self { config }
+    Self::with_storage(config, Arc::new(MemoryStorage::new()))
+  }
+
+  /// Create a new server instance on top of an opened storage backend.
+  #[must_use]
+  pub fn with_storage(config: Config, storage: Arc<dyn Storage>) -> Self {
+    Self {
+      config,
+      storage,
+      cluster: None,
+    }
+  }
+
+  /// Run this server as a replica joined to other replicas over `bus`.
+  ///
+  /// Without it, [`Self::start`] opens a [`TcpBus`] when
+  /// `config.cluster` is set.
+  #[must_use]
+  pub fn with_cluster(mut self, bus: Arc<dyn ClusterBus>) -> Self {
+    self.cluster = Some(bus);
+    self
   }
 
   /// Get a reference to the server configuration.
@@ -40,11 +68,15 @@ impl Server {
 
   /// Build the application router with all routes and middleware.
   ///
-  /// This creates the shared state (UserStore, WebSocketState) and
-  /// constructs the Axum router with:
+  /// This creates the shared state (UserStore, RoomState,
+  /// WebSocketState), restoring users and rooms from the storage
+  /// backend, and constructs the Axum router with:
   /// - `/ws` WebSocket upgrade route
   /// - `/api/health` liveness probe (used by Docker / Kubernetes)
-  /// - `/api/register` and `/api/login` HTTP auth endpoints
+  /// - `/api/register`, `/api/login` and `/api/refresh` HTTP auth endpoints
+  /// - `/api/password` and `/api/account` account management endpoints
+  /// - `/api/admin/*` operator endpoints (see [`crate::admin`])
+  /// - `/metrics` Prometheus endpoint when `Config::metrics_enabled`
   /// - Static file serving via `ServeDir`, with an SPA fallback that
   ///   serves `index.html` for navigation requests that do not match
   ///   any static file. This ensures PWA deep links survive a full
@@ -53,10 +85,16 @@ impl Server {
   /// - Request tracing layer
   pub fn build_router(&self) -> (Router, Arc<WebSocketState>) {
     // Create shared user store for authentication
-    let user_store = UserStore::new(&self.config);
+    let user_store = UserStore::with_storage(&self.config, self.storage.clone());
+    let room_state = RoomState::with_storage(self.storage.clone());
 
     // Create shared WebSocket state
-    let ws_state = Arc::new(WebSocketState::new(self.config.clone(), user_store.clone()));
+    let mut ws_state =
+      WebSocketState::with_room_state(self.config.clone(), user_store.clone(), room_state);
+    if let Some(bus) = &self.cluster {
+      ws_state = ws_state.with_cluster(bus.clone());
+    }
+    let ws_state = Arc::new(ws_state);
 
     // CORS layer for local development (Trunk dev server → Axum API)
     let cors = CorsLayer::new()
@@ -78,7 +116,7 @@ impl Server {
       .fallback(spa_router);
 
     // Build the application router
-    let app = Router::new()
+    let mut app = Router::new()
       // WebSocket route
       .route("/ws", get(ws_handler))
       // Liveness probe — used by Docker / Kubernetes / load balancers
@@ -86,6 +124,16 @@ impl Server {
       // HTTP auth endpoints
       .route("/api/register", post(handlers::register))
       .route("/api/login", post(handlers::login))
+      .route("/api/refresh", post(handlers::refresh))
+      .route("/api/password", post(handlers::change_password))
+      .route("/api/account", delete(handlers::delete_account))
+      // Operator endpoints, guarded by the admin token / role
+      .nest("/api/admin", crate::admin::router(ws_state.clone()));
+    if self.config.metrics_enabled {
+      // Prometheus scrape target (opt-in, unauthenticated)
+      app = app.route("/metrics", get(crate::metrics::metrics_handler));
+    }
+    let app = app
       // Shared state
       .with_state(ws_state.clone())
       // Static file serving (with SPA fallback) for frontend
@@ -105,10 +153,19 @@ impl Server {
   ///
   /// # Errors
   ///
-  /// Returns an error if the server fails to start.
-  pub async fn start(self) -> anyhow::Result<()> {
+  /// Returns an error if the server or its cluster bus fails to
+  /// start.
+  pub async fn start(mut self) -> anyhow::Result<()> {
     let addr = self.config.addr;
 
+    // Join the other replicas when clustering is configured
+    if self.cluster.is_none()
+      && let Some(cluster) = &self.config.cluster
+    {
+      let bus = TcpBus::start(cluster, &self.config.jwt_secret).await?;
+      self.cluster = Some(Arc::new(bus));
+    }
+
     let (app, ws_state) = self.build_router();
 
     // Create cancellation token for background tasks
@@ -128,10 +185,19 @@ impl Server {
     // unless explicitly disabled. The bind port is taken from
     // `Config::stun_port`; setting it to `0` turns the embedded
     // server off so a separately-deployed coturn can take over.
+    //
+    // The optional TURN relay rides on the same socket, so it is only
+    // available while the STUN service is enabled.
     if let Some(stun_port) = self.config.stun_port {
       let stun_addr = SocketAddr::from((std::net::Ipv4Addr::UNSPECIFIED, stun_port));
-      match crate::stun::spawn(stun_addr).await {
-        Ok(()) => {}
+      let turn = self.config.turn.clone().map(|turn| {
+        (
+          turn,
+          crate::stun::turn::TurnSecret::from_jwt_secret(&self.config.jwt_secret),
+        )
+      });
+      match crate::stun::spawn(stun_addr, turn, ws_state.metrics().clone()).await {
+        Ok(_) => {}
         Err(e) => {
           warn!(
             error = %e,
@@ -141,6 +207,8 @@ impl Server {
           );
         }
       }
+    } else if self.config.turn.is_some() {
+      warn!("TURN_ENABLED is set but STUN_PORT=0 — the embedded TURN relay is disabled");
     }
 
     // Create TCP listener
@@ -192,6 +192,176 @@ async fn test_router_health_endpoint_returns_200() {
   );
 }
 
+#[tokio::test]
+async fn test_router_metrics_endpoint_when_enabled() {
+  let config = Config {
+    metrics_enabled: true,
+    ..Default::default()
+  };
+  let server = Server::new(config);
+  let (router, _ws_state) = server.build_router();
+
+  let request = axum::http::Request::builder()
+    .uri("/metrics")
+    .method("GET")
+    .body(Body::empty())
+    .unwrap();
+
+  let response = router.oneshot(request).await.unwrap();
+  assert_eq!(response.status().as_u16(), 200);
+  assert_eq!(
+    response.headers()[axum::http::header::CONTENT_TYPE],
+    crate::metrics::CONTENT_TYPE
+  );
+  let body = response.into_body().collect().await.unwrap().to_bytes();
+  let text = std::str::from_utf8(&body).unwrap();
+  assert!(
+    text.contains("# TYPE chat_ws_connections gauge"),
+    "body was: {text}"
+  );
+}
+
+#[tokio::test]
+async fn test_router_metrics_endpoint_disabled_by_default() {
+  let config = Config {
+    metrics_enabled: false,
+    ..Default::default()
+  };
+  let server = Server::new(config);
+  let (router, _ws_state) = server.build_router();
+
+  let request = axum::http::Request::builder()
+    .uri("/metrics")
+    .method("GET")
+    .body(Body::empty())
+    .unwrap();
+
+  let response = router.oneshot(request).await.unwrap();
+  let body = response.into_body().collect().await.unwrap().to_bytes();
+  let text = std::str::from_utf8(&body).unwrap();
+  assert!(!text.contains("chat_ws_connections"), "body was: {text}");
+}
+
+/// Send a JSON request and return the status and parsed body.
+async fn send_json(
+  router: &Router,
+  method: &str,
+  uri: &str,
+  bearer: Option<&str>,
+  body: serde_json::Value,
+) -> (u16, serde_json::Value) {
+  let mut builder = axum::http::Request::builder()
+    .uri(uri)
+    .method(method)
+    .header("content-type", "application/json");
+  if let Some(token) = bearer {
+    builder = builder.header("authorization", format!("Bearer {token}"));
+  }
+  let request = builder.body(Body::from(body.to_string())).unwrap();
+  let response = router.clone().oneshot(request).await.unwrap();
+  let status = response.status().as_u16();
+  let bytes = response.into_body().collect().await.unwrap().to_bytes();
+  let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+  (status, json)
+}
+
+#[tokio::test]
+async fn test_router_refresh_rotates_and_detects_reuse() {
+  let server = Server::new(Config::default());
+  let (router, _ws_state) = server.build_router();
+  let credentials = serde_json::json!({ "username": "httprefresh", "password": "password123" });
+
+  let (status, registered) = send_json(&router, "POST", "/api/register", None, credentials).await;
+  assert_eq!(status, 200);
+  assert!(registered["expires_in"].as_u64().unwrap() > 0);
+  let first = registered["refresh_token"].as_str().unwrap().to_string();
+
+  let (status, refreshed) = send_json(
+    &router,
+    "POST",
+    "/api/refresh",
+    None,
+    serde_json::json!({ "refresh_token": first }),
+  )
+  .await;
+  assert_eq!(status, 200);
+  assert_eq!(refreshed["user_id"], registered["user_id"]);
+  assert_ne!(refreshed["refresh_token"].as_str().unwrap(), first);
+
+  let (status, body) = send_json(
+    &router,
+    "POST",
+    "/api/refresh",
+    None,
+    serde_json::json!({ "refresh_token": first }),
+  )
+  .await;
+  assert_eq!(status, 401);
+  assert!(body["error"].as_str().unwrap().contains("reuse"));
+
+  let (status, _) = send_json(
+    &router,
+    "POST",
+    "/api/refresh",
+    None,
+    serde_json::json!({ "refresh_token": refreshed["refresh_token"] }),
+  )
+  .await;
+  assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn test_router_change_password_and_delete_account() {
+  let server = Server::new(Config::default());
+  let (router, ws_state) = server.build_router();
+  let (_, token) = ws_state
+    .user_store()
+    .register("httpaccount", "password123")
+    .unwrap();
+  let change = serde_json::json!({
+    "current_password": "password123",
+    "new_password": "newpassword456",
+  });
+
+  let (status, _) = send_json(&router, "POST", "/api/password", None, change.clone()).await;
+  assert_eq!(status, 401);
+
+  let (status, changed) = send_json(&router, "POST", "/api/password", Some(&token), change).await;
+  assert_eq!(status, 200);
+  let new_token = changed["token"].as_str().unwrap().to_string();
+
+  // The old access token belongs to the revoked session.
+  let delete = serde_json::json!({ "password": "newpassword456" });
+  let (status, _) = send_json(
+    &router,
+    "DELETE",
+    "/api/account",
+    Some(&token),
+    delete.clone(),
+  )
+  .await;
+  assert_eq!(status, 401);
+
+  let (status, _) = send_json(
+    &router,
+    "DELETE",
+    "/api/account",
+    Some(&new_token),
+    serde_json::json!({ "password": "password123" }),
+  )
+  .await;
+  assert_eq!(status, 400);
+
+  let (status, _) = send_json(&router, "DELETE", "/api/account", Some(&new_token), delete).await;
+  assert_eq!(status, 204);
+  assert!(
+    ws_state
+      .user_store()
+      .login("httpaccount", "newpassword456")
+      .is_err()
+  );
+}
+
 #[tokio::test]
 async fn test_server_start_fails_on_invalid_address() {
   // Bind to a port that is already in use to trigger a start failure.
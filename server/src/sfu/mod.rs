@@ -0,0 +1,343 @@
+//! Selective forwarding unit (SFU) for rooms beyond the 8-peer mesh.
+//!
+//! Every member of a [`RoomTopology::Sfu`](message::types::RoomTopology)
+//! room keeps a single `RTCPeerConnection` to the server from each
+//! device in the call. Each track a
+//! member publishes is read once, and its RTP packets are copied
+//! unmodified into a local track attached to every other member's
+//! connection. Upload cost per member is therefore constant instead of
+//! growing with the room size.
+//!
+//! Browsers encrypt encoded frames end to end before packetisation,
+//! with per-sender keys delivered as `MediaKey` over the E2EE
+//! DataChannels, so the relay only ever handles RTP headers and
+//! ciphertext.
+//!
+//! Negotiation follows the "perfect negotiation" pattern with the
+//! server as the impolite side: a client offer that collides with an
+//! outstanding server offer is ignored and the client rolls back.
+
+mod room;
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use message::signaling::SfuIceCandidate;
+use message::{DeviceId, RoomId, UserId};
+use tokio::sync::mpsc;
+use tracing::info;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::setting_engine::SettingEngine;
+use webrtc::api::{API, APIBuilder};
+use webrtc::ice::udp_network::{EphemeralUDP, UDPNetwork};
+use webrtc::ice_transport::ice_candidate_type::RTCIceCandidateType;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+
+use crate::config::SfuConfig;
+use room::{SessionKey, SfuRoom};
+
+// =============================================================================
+// Error Types
+// =============================================================================
+
+/// Error types for SFU operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SfuError {
+  /// The user has no media session in the room.
+  SessionNotFound,
+  /// The WebRTC stack rejected the operation.
+  WebRtc(String),
+}
+
+impl fmt::Display for SfuError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::SessionNotFound => write!(f, "No SFU session for this room"),
+      Self::WebRtc(msg) => write!(f, "WebRTC error: {msg}"),
+    }
+  }
+}
+
+impl std::error::Error for SfuError {}
+
+impl From<webrtc::Error> for SfuError {
+  fn from(err: webrtc::Error) -> Self {
+    Self::WebRtc(err.to_string())
+  }
+}
+
+// =============================================================================
+// SFU State
+// =============================================================================
+
+/// Media relay state shared by every SFU room.
+pub struct SfuState {
+  /// WebRTC stack with default codecs and interceptors.
+  api: API,
+  /// ICE servers used by the relay's own peer connections.
+  ice_servers: Vec<RTCIceServer>,
+  /// Active media sessions, grouped by room.
+  rooms: DashMap<RoomId, Arc<SfuRoom>>,
+}
+
+impl fmt::Debug for SfuState {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SfuState")
+      .field("ice_servers", &self.ice_servers)
+      .field("rooms", &self.rooms.len())
+      .finish_non_exhaustive()
+  }
+}
+
+impl SfuState {
+  /// Build the relay's WebRTC stack.
+  ///
+  /// Only the `stun:` entries of `ice_servers` are used: they let the
+  /// relay discover its server-reflexive address. TURN entries are
+  /// meant for clients.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the default codecs or interceptors cannot be
+  /// registered, or the UDP port range is invalid.
+  pub fn new(config: &SfuConfig, ice_servers: &[String]) -> Result<Self, SfuError> {
+    let mut media_engine = MediaEngine::default();
+    media_engine.register_default_codecs()?;
+    let registry = register_default_interceptors(Registry::new(), &mut media_engine)?;
+
+    let mut settings = SettingEngine::default();
+    if let Some(ip) = config.public_ip {
+      settings.set_nat_1to1_ips(vec![ip.to_string()], RTCIceCandidateType::Host);
+    }
+    if let Some(ports) = &config.udp_ports {
+      let udp = EphemeralUDP::new(*ports.start(), *ports.end())
+        .map_err(|e| SfuError::WebRtc(e.to_string()))?;
+      settings.set_udp_network(UDPNetwork::Ephemeral(udp));
+    }
+
+    let api = APIBuilder::new()
+      .with_media_engine(media_engine)
+      .with_interceptor_registry(registry)
+      .with_setting_engine(settings)
+      .build();
+
+    let stun_urls: Vec<String> = ice_servers
+      .iter()
+      .filter(|url| url.starts_with("stun:"))
+      .cloned()
+      .collect();
+    let ice_servers = if stun_urls.is_empty() {
+      Vec::new()
+    } else {
+      vec![RTCIceServer {
+        urls: stun_urls,
+        ..Default::default()
+      }]
+    };
+
+    Ok(Self {
+      api,
+      ice_servers,
+      rooms: DashMap::new(),
+    })
+  }
+
+  /// Apply a client offer from `device_id`, creating that device's
+  /// media session on first use. The answer, track map and trickled ICE
+  /// candidates are sent through `signal_tx`.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the peer connection cannot be created or the
+  /// offer is rejected.
+  pub async fn handle_offer(
+    &self,
+    room_id: &RoomId,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+    sdp: String,
+    signal_tx: mpsc::Sender<Vec<u8>>,
+  ) -> Result<(), SfuError> {
+    let room = self
+      .rooms
+      .entry(room_id.clone())
+      .or_insert_with(|| Arc::new(SfuRoom::new(room_id.clone())))
+      .clone();
+    let key: SessionKey = (user_id.clone(), device_id.copied());
+    if let Some(participant) = room.participant(&key) {
+      if participant.signals_to(&signal_tx) {
+        return room.answer(&participant, sdp).await;
+      }
+      // The device reconnected: its old session has nobody to signal.
+      room.leave(&key).await;
+    }
+
+    let participant = room
+      .join(&self.api, &self.ice_servers, key.clone(), signal_tx)
+      .await?;
+    if let Err(e) = room.answer(&participant, sdp).await {
+      // Do not keep a session the client never managed to open.
+      room.leave(&key).await;
+      self.rooms.retain(|_, room| !room.is_empty());
+      return Err(e);
+    }
+    info!(room_id = %room_id, user_id = %user_id, "SFU session opened");
+    Ok(())
+  }
+
+  /// Apply the client's answer to a server offer.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the user has no session or the answer is
+  /// rejected.
+  pub async fn handle_answer(
+    &self,
+    room_id: &RoomId,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+    sdp: String,
+  ) -> Result<(), SfuError> {
+    let (room, participant) = self.session(room_id, user_id, device_id)?;
+    room.apply_answer(&participant, sdp).await
+  }
+
+  /// Add a trickled client ICE candidate.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the user has no session or the candidate is
+  /// rejected.
+  pub async fn add_ice_candidate(
+    &self,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+    candidate: SfuIceCandidate,
+  ) -> Result<(), SfuError> {
+    let (_, participant) = self.session(&candidate.room_id, user_id, device_id)?;
+    participant.add_ice_candidate(candidate).await
+  }
+
+  /// Close the user's media sessions in a room, on every device.
+  /// Returns `false` when there were none.
+  pub async fn leave(&self, room_id: &RoomId, user_id: &UserId) -> bool {
+    let Some(room) = self.rooms.get(room_id).map(|r| r.value().clone()) else {
+      return false;
+    };
+    let mut left = false;
+    for participant in room.sessions_of(user_id) {
+      left |= room.leave(&participant.key()).await;
+    }
+    self.rooms.retain(|_, room| !room.is_empty());
+    if left {
+      info!(room_id = %room_id, user_id = %user_id, "SFU session closed");
+    }
+    left
+  }
+
+  /// Close the media session `device_id` holds in a room. Returns
+  /// `false` when there was none.
+  pub async fn leave_session(
+    &self,
+    room_id: &RoomId,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+  ) -> bool {
+    let Ok((room, participant)) = self.session(room_id, user_id, device_id) else {
+      return false;
+    };
+    let left = room.leave(&participant.key()).await;
+    self.rooms.retain(|_, room| !room.is_empty());
+    if left {
+      info!(room_id = %room_id, user_id = %user_id, "SFU session closed");
+    }
+    left
+  }
+
+  /// Close every media session the user holds (used on disconnect).
+  pub async fn leave_all(&self, user_id: &UserId) {
+    let room_ids: Vec<RoomId> = self
+      .rooms
+      .iter()
+      .filter(|entry| !entry.value().sessions_of(user_id).is_empty())
+      .map(|entry| entry.key().clone())
+      .collect();
+    for room_id in room_ids {
+      self.leave(&room_id, user_id).await;
+    }
+  }
+
+  /// Close the user's media sessions held by the connection behind
+  /// `sender` (used when one of several devices disconnects).
+  pub async fn leave_device(&self, user_id: &UserId, sender: &mpsc::Sender<Vec<u8>>) {
+    let sessions: Vec<(Arc<SfuRoom>, SessionKey)> = self
+      .rooms
+      .iter()
+      .flat_map(|entry| {
+        let room = entry.value().clone();
+        room
+          .sessions_of(user_id)
+          .into_iter()
+          .filter(|participant| participant.signals_to(sender))
+          .map(move |participant| (room.clone(), participant.key()))
+          .collect::<Vec<_>>()
+      })
+      .collect();
+    for (room, key) in sessions {
+      room.leave(&key).await;
+    }
+    self.rooms.retain(|_, room| !room.is_empty());
+  }
+
+  /// Whether the user has a media session in the room on any device.
+  #[must_use]
+  pub fn has_session(&self, room_id: &RoomId, user_id: &UserId) -> bool {
+    self
+      .rooms
+      .get(room_id)
+      .is_some_and(|room| !room.sessions_of(user_id).is_empty())
+  }
+
+  /// Total number of open media sessions.
+  #[must_use]
+  pub fn session_count(&self) -> usize {
+    self
+      .rooms
+      .iter()
+      .map(|entry| entry.value().participant_count())
+      .sum()
+  }
+
+  /// Total number of tracks currently being forwarded.
+  #[must_use]
+  pub fn forwarded_track_count(&self) -> usize {
+    self
+      .rooms
+      .iter()
+      .map(|entry| entry.value().track_count())
+      .sum()
+  }
+
+  fn session(
+    &self,
+    room_id: &RoomId,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+  ) -> Result<(Arc<SfuRoom>, Arc<room::Participant>), SfuError> {
+    let room = self
+      .rooms
+      .get(room_id)
+      .map(|r| r.value().clone())
+      .ok_or(SfuError::SessionNotFound)?;
+    let participant = room
+      .participant(&(user_id.clone(), device_id.copied()))
+      .ok_or(SfuError::SessionNotFound)?;
+    Ok((room, participant))
+  }
+}
@@ -0,0 +1,552 @@
+//! Per-room SFU state: participants, forwarded tracks and renegotiation.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use message::signaling::{
+  SfuAnswer, SfuIceCandidate, SfuOffer, SfuTrack, SfuTrackMap, SignalingMessage,
+};
+use message::{DeviceId, RoomId, UserId};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use webrtc::api::API;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use webrtc::rtp_transceiver::rtp_codec::RTPCodecType;
+use webrtc::rtp_transceiver::rtp_sender::RTCRtpSender;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::{TrackLocal, TrackLocalWriter};
+use webrtc::track::track_remote::TrackRemote;
+
+use super::SfuError;
+use crate::ws::encode_signaling_message;
+
+/// A member's connection: one user may hold a media session from each
+/// of their devices.
+pub(super) type SessionKey = (UserId, Option<DeviceId>);
+
+/// A published track being relayed to the rest of the room.
+struct ForwardedTrack {
+  /// Member who published the track.
+  publisher: UserId,
+  /// Device the track is published from.
+  publisher_device: Option<DeviceId>,
+  /// SSRC of the published stream, used for keyframe requests.
+  media_ssrc: u32,
+  /// Local track every subscriber's connection sends from.
+  track: Arc<TrackLocalStaticRTP>,
+}
+
+/// Offer/answer bookkeeping for one connection.
+#[derive(Debug, Default)]
+struct Negotiation {
+  /// A server offer has been sent and its answer is outstanding.
+  offer_in_flight: bool,
+  /// Tracks changed since the last offer; renegotiate when possible.
+  pending: bool,
+}
+
+/// One member's media session.
+pub(super) struct Participant {
+  /// Member owning the session.
+  user_id: UserId,
+  /// Device holding the session.
+  device_id: Option<DeviceId>,
+  /// Room the session belongs to.
+  room_id: RoomId,
+  /// Connection between the member's browser and the relay.
+  pc: Arc<RTCPeerConnection>,
+  /// Outgoing WebSocket queue of the member.
+  signal_tx: mpsc::Sender<Vec<u8>>,
+  /// Senders of the tracks forwarded to this member, by track id.
+  senders: Mutex<HashMap<String, Arc<RTCRtpSender>>>,
+  /// Renegotiation state.
+  negotiation: Mutex<Negotiation>,
+}
+
+impl Participant {
+  /// Key of the session within its room.
+  pub(super) fn key(&self) -> SessionKey {
+    (self.user_id.clone(), self.device_id)
+  }
+
+  /// Whether the session signals through `sender`, i.e. belongs to
+  /// that connection of the member.
+  pub(super) fn signals_to(&self, sender: &mpsc::Sender<Vec<u8>>) -> bool {
+    self.signal_tx.same_channel(sender)
+  }
+
+  /// Queue a signaling message to the member.
+  async fn send(&self, msg: SignalingMessage) {
+    if let Ok(encoded) = encode_signaling_message(&msg)
+      && self.signal_tx.send(encoded).await.is_err()
+    {
+      debug!(user_id = %self.user_id, "SFU signal dropped: connection closed");
+    }
+  }
+
+  /// Add a trickled ICE candidate from the member.
+  pub(super) async fn add_ice_candidate(&self, candidate: SfuIceCandidate) -> Result<(), SfuError> {
+    self
+      .pc
+      .add_ice_candidate(RTCIceCandidateInit {
+        candidate: candidate.candidate,
+        sdp_mid: candidate.sdp_mid,
+        sdp_mline_index: candidate.sdp_m_line_index,
+        username_fragment: None,
+      })
+      .await?;
+    Ok(())
+  }
+}
+
+/// Media sessions of one SFU room.
+pub(super) struct SfuRoom {
+  /// Room ID.
+  room_id: RoomId,
+  /// Members' devices with an open media session.
+  participants: Mutex<HashMap<SessionKey, Arc<Participant>>>,
+  /// Tracks currently relayed.
+  tracks: Mutex<Vec<ForwardedTrack>>,
+}
+
+impl SfuRoom {
+  pub(super) fn new(room_id: RoomId) -> Self {
+    Self {
+      room_id,
+      participants: Mutex::new(HashMap::new()),
+      tracks: Mutex::new(Vec::new()),
+    }
+  }
+
+  pub(super) fn participant(&self, key: &SessionKey) -> Option<Arc<Participant>> {
+    lock(&self.participants).get(key).cloned()
+  }
+
+  /// Sessions `user_id` holds in the room, one per device.
+  pub(super) fn sessions_of(&self, user_id: &UserId) -> Vec<Arc<Participant>> {
+    lock(&self.participants)
+      .values()
+      .filter(|p| p.user_id == *user_id)
+      .cloned()
+      .collect()
+  }
+
+  pub(super) fn participant_count(&self) -> usize {
+    lock(&self.participants).len()
+  }
+
+  pub(super) fn track_count(&self) -> usize {
+    lock(&self.tracks).len()
+  }
+
+  pub(super) fn is_empty(&self) -> bool {
+    lock(&self.participants).is_empty()
+  }
+
+  /// Open a media session for `user_id` on `device_id`, already
+  /// subscribed to every track other members publish.
+  pub(super) async fn join(
+    self: &Arc<Self>,
+    api: &API,
+    ice_servers: &[RTCIceServer],
+    (user_id, device_id): SessionKey,
+    signal_tx: mpsc::Sender<Vec<u8>>,
+  ) -> Result<Arc<Participant>, SfuError> {
+    let pc = Arc::new(
+      api
+        .new_peer_connection(RTCConfiguration {
+          ice_servers: ice_servers.to_vec(),
+          ..Default::default()
+        })
+        .await?,
+    );
+    let participant = Arc::new(Participant {
+      user_id: user_id.clone(),
+      device_id,
+      room_id: self.room_id.clone(),
+      pc: pc.clone(),
+      signal_tx,
+      senders: Mutex::new(HashMap::new()),
+      negotiation: Mutex::new(Negotiation::default()),
+    });
+
+    let weak_participant = Arc::downgrade(&participant);
+    pc.on_ice_candidate(Box::new(move |candidate| {
+      let participant = weak_participant.clone();
+      Box::pin(async move {
+        let (Some(candidate), Some(participant)) = (candidate, participant.upgrade()) else {
+          return;
+        };
+        match candidate.to_json() {
+          Ok(init) => {
+            let msg = SignalingMessage::SfuIceCandidate(SfuIceCandidate {
+              room_id: participant.room_id.clone(),
+              candidate: init.candidate,
+              sdp_mid: init.sdp_mid,
+              sdp_m_line_index: init.sdp_mline_index,
+            });
+            participant.send(msg).await;
+          }
+          Err(e) => warn!(error = %e, "Failed to serialise SFU ICE candidate"),
+        }
+      })
+    }));
+
+    let key: SessionKey = (user_id.clone(), device_id);
+    let weak_room = Arc::downgrade(self);
+    let publisher = key.clone();
+    pc.on_track(Box::new(move |track, _receiver, _transceiver| {
+      let room = weak_room.clone();
+      let publisher = publisher.clone();
+      Box::pin(async move {
+        if let Some(room) = room.upgrade() {
+          room.forward(publisher, track).await;
+        }
+      })
+    }));
+
+    let weak_room = Arc::downgrade(self);
+    let member = key.clone();
+    pc.on_peer_connection_state_change(Box::new(move |state| {
+      if state == RTCPeerConnectionState::Failed
+        && let Some(room) = weak_room.upgrade()
+      {
+        let member = member.clone();
+        // Detached: closing the connection from inside its own
+        // callback would wait on itself.
+        tokio::spawn(async move {
+          if room.leave(&member).await {
+            info!(room_id = %room.room_id, user_id = %member.0, "SFU session failed");
+          }
+        });
+      }
+      Box::pin(async {})
+    }));
+
+    // Register before subscribing so a track published meanwhile is
+    // attached by `forward`; `attach` skips tracks already present.
+    lock(&self.participants).insert(key.clone(), participant.clone());
+    let existing: Vec<(SessionKey, u32, Arc<TrackLocalStaticRTP>)> = lock(&self.tracks)
+      .iter()
+      .filter(|t| t.publisher != user_id)
+      .map(|t| {
+        (
+          (t.publisher.clone(), t.publisher_device),
+          t.media_ssrc,
+          t.track.clone(),
+        )
+      })
+      .collect();
+    for (publisher, media_ssrc, track) in existing {
+      if let Err(e) = self
+        .attach(&participant, publisher, media_ssrc, track)
+        .await
+      {
+        self.leave(&key).await;
+        return Err(e);
+      }
+    }
+    Ok(participant)
+  }
+
+  /// Answer a client offer.
+  pub(super) async fn answer(
+    &self,
+    participant: &Arc<Participant>,
+    sdp: String,
+  ) -> Result<(), SfuError> {
+    if lock(&participant.negotiation).offer_in_flight {
+      // Offer collision. The relay is the impolite side: the client
+      // rolls back, answers our offer and offers again afterwards.
+      debug!(
+        room_id = %self.room_id,
+        user_id = %participant.user_id,
+        "Ignoring colliding SFU offer"
+      );
+      return Ok(());
+    }
+
+    let pc = &participant.pc;
+    pc.set_remote_description(RTCSessionDescription::offer(sdp)?)
+      .await?;
+    let answer = pc.create_answer(None).await?;
+    pc.set_local_description(answer).await?;
+    let sdp = pc
+      .local_description()
+      .await
+      .map(|desc| desc.sdp)
+      .unwrap_or_default();
+
+    participant.send(self.track_map(&participant.user_id)).await;
+    participant
+      .send(SignalingMessage::SfuAnswer(SfuAnswer {
+        room_id: self.room_id.clone(),
+        sdp,
+      }))
+      .await;
+
+    self.negotiate_pending(participant).await;
+    Ok(())
+  }
+
+  /// Apply the client's answer to our last offer.
+  pub(super) async fn apply_answer(
+    &self,
+    participant: &Arc<Participant>,
+    sdp: String,
+  ) -> Result<(), SfuError> {
+    let result = participant
+      .pc
+      .set_remote_description(RTCSessionDescription::answer(sdp)?)
+      .await;
+    lock(&participant.negotiation).offer_in_flight = false;
+    result?;
+    self.negotiate_pending(participant).await;
+    Ok(())
+  }
+
+  /// Close the session behind `key` and stop relaying its tracks.
+  pub(super) async fn leave(&self, key: &SessionKey) -> bool {
+    let Some(participant) = lock(&self.participants).remove(key) else {
+      return false;
+    };
+    if let Err(e) = participant.pc.close().await {
+      debug!(user_id = %key.0, error = %e, "Error closing SFU peer connection");
+    }
+
+    let track_ids: Vec<String> = lock(&self.tracks)
+      .iter()
+      .filter(|t| t.publisher == key.0 && t.publisher_device == key.1)
+      .map(|t| t.track.id().to_string())
+      .collect();
+    for track_id in track_ids {
+      self.unforward(&track_id).await;
+    }
+    true
+  }
+
+  /// Start relaying a newly received track to every other member.
+  async fn forward(self: Arc<Self>, (publisher, device): SessionKey, remote: Arc<TrackRemote>) {
+    let kind = remote.kind();
+    let media_ssrc = remote.ssrc();
+    let local = Arc::new(TrackLocalStaticRTP::new(
+      remote.codec().capability,
+      remote.id(),
+      publisher.to_string(),
+    ));
+    let track_id = local.id().to_string();
+
+    lock(&self.tracks).push(ForwardedTrack {
+      publisher: publisher.clone(),
+      publisher_device: device,
+      media_ssrc,
+      track: local.clone(),
+    });
+    info!(
+      room_id = %self.room_id,
+      publisher = %publisher,
+      track_id = %track_id,
+      kind = %kind,
+      "Forwarding SFU track"
+    );
+
+    for subscriber in self.others(&publisher) {
+      match self
+        .attach(
+          &subscriber,
+          (publisher.clone(), device),
+          media_ssrc,
+          local.clone(),
+        )
+        .await
+      {
+        Ok(()) => self.negotiate(&subscriber).await,
+        Err(e) => warn!(
+          user_id = %subscriber.user_id,
+          error = %e,
+          "Failed to subscribe to SFU track"
+        ),
+      }
+    }
+
+    // Copy RTP until the publisher stops sending (track removed or
+    // connection closed), then drop the track everywhere.
+    tokio::spawn(async move {
+      while let Ok((packet, _)) = remote.read_rtp().await {
+        if let Err(e) = local.write_rtp(&packet).await
+          && !matches!(e, webrtc::Error::ErrClosedPipe)
+        {
+          debug!(error = %e, "SFU track write failed");
+          break;
+        }
+      }
+      self.unforward(&track_id).await;
+    });
+  }
+
+  /// Stop relaying a track and remove it from every subscriber.
+  async fn unforward(&self, track_id: &str) {
+    let removed = {
+      let mut tracks = lock(&self.tracks);
+      tracks
+        .iter()
+        .position(|t| t.track.id() == track_id)
+        .map(|index| tracks.remove(index))
+    };
+    let Some(removed) = removed else {
+      return;
+    };
+
+    for subscriber in self.others(&removed.publisher) {
+      let sender = lock(&subscriber.senders).remove(track_id);
+      if let Some(sender) = sender {
+        if let Err(e) = subscriber.pc.remove_track(&sender).await {
+          debug!(user_id = %subscriber.user_id, error = %e, "Failed to remove SFU track");
+        }
+        self.negotiate(&subscriber).await;
+      }
+    }
+    debug!(room_id = %self.room_id, track_id = %track_id, "Stopped forwarding SFU track");
+  }
+
+  /// Add a forwarded track to a subscriber's connection, and relay the
+  /// subscriber's keyframe requests back to the publisher.
+  async fn attach(
+    self: &Arc<Self>,
+    subscriber: &Participant,
+    publisher: SessionKey,
+    media_ssrc: u32,
+    track: Arc<TrackLocalStaticRTP>,
+  ) -> Result<(), SfuError> {
+    let is_video = track.kind() == RTPCodecType::Video;
+    let track_id = track.id().to_string();
+    if lock(&subscriber.senders).contains_key(&track_id) {
+      return Ok(());
+    }
+    let sender = subscriber
+      .pc
+      .add_track(track as Arc<dyn TrackLocal + Send + Sync>)
+      .await?;
+    lock(&subscriber.senders).insert(track_id, sender.clone());
+    lock(&subscriber.negotiation).pending = true;
+
+    // RTCP must be drained for the interceptors (NACK, reports) to run.
+    let room = Arc::downgrade(self);
+    tokio::spawn(async move {
+      while let Ok((packets, _)) = sender.read_rtcp().await {
+        let wants_keyframe = is_video
+          && packets.iter().any(|p| {
+            p.as_any().is::<PictureLossIndication>() || p.as_any().is::<FullIntraRequest>()
+          });
+        if wants_keyframe && let Some(room) = Weak::upgrade(&room) {
+          room.request_keyframe(&publisher, media_ssrc).await;
+        }
+      }
+    });
+    Ok(())
+  }
+
+  /// Ask a publisher for a new keyframe.
+  async fn request_keyframe(&self, publisher: &SessionKey, media_ssrc: u32) {
+    let Some(participant) = self.participant(publisher) else {
+      return;
+    };
+    let pli = PictureLossIndication {
+      sender_ssrc: 0,
+      media_ssrc,
+    };
+    if let Err(e) = participant.pc.write_rtcp(&[Box::new(pli)]).await {
+      debug!(publisher = %publisher.0, error = %e, "Failed to send PLI");
+    }
+  }
+
+  /// Mark the subscriber's connection for renegotiation and send an
+  /// offer unless one is already outstanding.
+  async fn negotiate(&self, participant: &Arc<Participant>) {
+    lock(&participant.negotiation).pending = true;
+    self.negotiate_pending(participant).await;
+  }
+
+  /// Send an offer if tracks changed and no exchange is in progress.
+  async fn negotiate_pending(&self, participant: &Arc<Participant>) {
+    {
+      let mut negotiation = lock(&participant.negotiation);
+      if !negotiation.pending || negotiation.offer_in_flight {
+        return;
+      }
+      negotiation.pending = false;
+      negotiation.offer_in_flight = true;
+    }
+
+    if let Err(e) = self.send_offer(participant).await {
+      warn!(
+        room_id = %self.room_id,
+        user_id = %participant.user_id,
+        error = %e,
+        "SFU renegotiation failed"
+      );
+      lock(&participant.negotiation).offer_in_flight = false;
+    }
+  }
+
+  async fn send_offer(&self, participant: &Participant) -> Result<(), SfuError> {
+    let pc = &participant.pc;
+    let offer = pc.create_offer(None).await?;
+    pc.set_local_description(offer).await?;
+    let sdp = pc
+      .local_description()
+      .await
+      .map(|desc| desc.sdp)
+      .unwrap_or_default();
+
+    participant.send(self.track_map(&participant.user_id)).await;
+    participant
+      .send(SignalingMessage::SfuOffer(SfuOffer {
+        room_id: self.room_id.clone(),
+        sdp,
+      }))
+      .await;
+    Ok(())
+  }
+
+  /// Every track forwarded to `user_id`.
+  fn track_map(&self, user_id: &UserId) -> SignalingMessage {
+    let tracks = lock(&self.tracks)
+      .iter()
+      .filter(|t| t.publisher != *user_id)
+      .map(|t| SfuTrack {
+        user_id: t.publisher.clone(),
+        stream_id: t.track.stream_id().to_string(),
+        track_id: t.track.id().to_string(),
+        kind: t.track.kind().to_string(),
+      })
+      .collect();
+    SignalingMessage::SfuTrackMap(SfuTrackMap {
+      room_id: self.room_id.clone(),
+      tracks,
+    })
+  }
+
+  /// Every participant except the sessions of `user_id`.
+  fn others(&self, user_id: &UserId) -> Vec<Arc<Participant>> {
+    lock(&self.participants)
+      .values()
+      .filter(|p| p.user_id != *user_id)
+      .cloned()
+      .collect()
+  }
+}
+
+/// Lock a mutex, recovering the data if a holder panicked.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+  mutex
+    .lock()
+    .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
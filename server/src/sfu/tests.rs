@@ -0,0 +1,249 @@
+//! SFU state tests.
+//!
+//! A webrtc-rs peer connection stands in for the browser. ICE never
+//! completes (no candidates are exchanged), which is enough to cover
+//! offer/answer handling and session bookkeeping.
+
+use std::time::Duration;
+
+use message::signaling::{SfuAnswer, SignalingMessage};
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::rtp_transceiver::RTCRtpTransceiverInit;
+use webrtc::rtp_transceiver::rtp_codec::RTPCodecType;
+use webrtc::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
+
+use super::*;
+use crate::ws::decode_signaling_message;
+
+fn test_config() -> SfuConfig {
+  SfuConfig {
+    max_room_members: 32,
+    public_ip: None,
+    udp_ports: None,
+  }
+}
+
+/// A client connection that wants to receive one audio track.
+async fn client_offer() -> (Arc<RTCPeerConnection>, String) {
+  let mut media_engine = MediaEngine::default();
+  media_engine.register_default_codecs().unwrap();
+  let api = APIBuilder::new().with_media_engine(media_engine).build();
+  let pc = Arc::new(api.new_peer_connection(Default::default()).await.unwrap());
+  pc.add_transceiver_from_kind(
+    RTPCodecType::Audio,
+    Some(RTCRtpTransceiverInit {
+      direction: RTCRtpTransceiverDirection::Recvonly,
+      send_encodings: vec![],
+    }),
+  )
+  .await
+  .unwrap();
+  let offer = pc.create_offer(None).await.unwrap();
+  pc.set_local_description(offer.clone()).await.unwrap();
+  (pc, offer.sdp)
+}
+
+/// Next signaling message that is not a trickled candidate.
+async fn recv_signal(rx: &mut mpsc::Receiver<Vec<u8>>) -> SignalingMessage {
+  loop {
+    let bytes = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+      .await
+      .expect("timed out waiting for SFU signal")
+      .expect("signal channel closed");
+    let frame = message::decode_frame(&bytes).unwrap();
+    let msg = decode_signaling_message(&frame).unwrap();
+    if !matches!(msg, SignalingMessage::SfuIceCandidate(_)) {
+      return msg;
+    }
+  }
+}
+
+#[tokio::test]
+async fn test_offer_opens_session_and_answers() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let room_id = RoomId::new();
+  let user_id = UserId::new();
+  let (tx, mut rx) = mpsc::channel(64);
+  let (client, sdp) = client_offer().await;
+
+  sfu
+    .handle_offer(&room_id, &user_id, None, sdp, tx)
+    .await
+    .unwrap();
+  assert!(sfu.has_session(&room_id, &user_id));
+  assert_eq!(sfu.session_count(), 1);
+
+  // The track map always precedes the answer.
+  match recv_signal(&mut rx).await {
+    SignalingMessage::SfuTrackMap(map) => {
+      assert_eq!(map.room_id, room_id);
+      assert!(map.tracks.is_empty());
+    }
+    other => panic!("Expected SfuTrackMap, got {other:?}"),
+  }
+  let answer = match recv_signal(&mut rx).await {
+    SignalingMessage::SfuAnswer(SfuAnswer { room_id: id, sdp }) => {
+      assert_eq!(id, room_id);
+      sdp
+    }
+    other => panic!("Expected SfuAnswer, got {other:?}"),
+  };
+  client
+    .set_remote_description(RTCSessionDescription::answer(answer).unwrap())
+    .await
+    .unwrap();
+
+  client.close().await.unwrap();
+  sfu.leave_all(&user_id).await;
+}
+
+#[tokio::test]
+async fn test_leave_closes_session() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let room_id = RoomId::new();
+  let user_id = UserId::new();
+  let (tx, _rx) = mpsc::channel(64);
+  let (client, sdp) = client_offer().await;
+
+  sfu
+    .handle_offer(&room_id, &user_id, None, sdp, tx)
+    .await
+    .unwrap();
+  assert!(sfu.leave(&room_id, &user_id).await);
+  assert!(!sfu.has_session(&room_id, &user_id));
+  assert_eq!(sfu.session_count(), 0);
+  assert!(!sfu.leave(&room_id, &user_id).await);
+
+  client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_leave_device_closes_only_that_devices_session() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let room_id = RoomId::new();
+  let user_id = UserId::new();
+  let (tx, _rx) = mpsc::channel(64);
+  let (other_device, _other_rx) = mpsc::channel(64);
+  let (client, sdp) = client_offer().await;
+
+  sfu
+    .handle_offer(&room_id, &user_id, None, sdp, tx.clone())
+    .await
+    .unwrap();
+  sfu.leave_device(&user_id, &other_device).await;
+  assert!(sfu.has_session(&room_id, &user_id));
+  sfu.leave_device(&user_id, &tx).await;
+  assert!(!sfu.has_session(&room_id, &user_id));
+
+  client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_each_device_holds_its_own_session() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let room_id = RoomId::new();
+  let user_id = UserId::new();
+  let (laptop, phone) = (DeviceId::new(), DeviceId::new());
+  let (laptop_tx, mut laptop_rx) = mpsc::channel(64);
+  let (phone_tx, mut phone_rx) = mpsc::channel(64);
+  let (laptop_client, laptop_sdp) = client_offer().await;
+  let (phone_client, phone_sdp) = client_offer().await;
+
+  sfu
+    .handle_offer(&room_id, &user_id, Some(&laptop), laptop_sdp, laptop_tx)
+    .await
+    .unwrap();
+  sfu
+    .handle_offer(&room_id, &user_id, Some(&phone), phone_sdp, phone_tx)
+    .await
+    .unwrap();
+  assert_eq!(sfu.session_count(), 2);
+
+  // Each device is answered on its own connection.
+  for rx in [&mut laptop_rx, &mut phone_rx] {
+    assert!(matches!(
+      recv_signal(rx).await,
+      SignalingMessage::SfuTrackMap(_)
+    ));
+    assert!(matches!(
+      recv_signal(rx).await,
+      SignalingMessage::SfuAnswer(_)
+    ));
+  }
+
+  assert!(sfu.leave_session(&room_id, &user_id, Some(&laptop)).await);
+  assert!(sfu.has_session(&room_id, &user_id));
+  assert_eq!(
+    sfu
+      .handle_answer(&room_id, &user_id, Some(&laptop), "v=0".to_string())
+      .await,
+    Err(SfuError::SessionNotFound)
+  );
+  assert!(sfu.leave(&room_id, &user_id).await);
+  assert_eq!(sfu.session_count(), 0);
+
+  laptop_client.close().await.unwrap();
+  phone_client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_answer_without_session_fails() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let result = sfu
+    .handle_answer(&RoomId::new(), &UserId::new(), None, "v=0".to_string())
+    .await;
+  assert_eq!(result, Err(SfuError::SessionNotFound));
+}
+
+#[tokio::test]
+async fn test_candidate_without_session_fails() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let candidate = SfuIceCandidate {
+    room_id: RoomId::new(),
+    candidate: "candidate:1 1 UDP 2122260223 10.0.0.1 40000 typ host".to_string(),
+    sdp_mid: Some("0".to_string()),
+    sdp_m_line_index: Some(0),
+  };
+  let result = sfu.add_ice_candidate(&UserId::new(), None, candidate).await;
+  assert_eq!(result, Err(SfuError::SessionNotFound));
+}
+
+#[tokio::test]
+async fn test_invalid_offer_is_rejected() {
+  let sfu = SfuState::new(&test_config(), &[]).unwrap();
+  let room_id = RoomId::new();
+  let user_id = UserId::new();
+  let (tx, _rx) = mpsc::channel(64);
+  let result = sfu
+    .handle_offer(&room_id, &user_id, None, "not sdp".to_string(), tx)
+    .await;
+  assert!(matches!(result, Err(SfuError::WebRtc(_))));
+  assert!(!sfu.has_session(&room_id, &user_id));
+}
+
+#[test]
+fn test_stun_servers_only() {
+  let sfu = SfuState::new(
+    &test_config(),
+    &[
+      "stun:stun.example.com:3478".to_string(),
+      "turn:turn.example.com:3478".to_string(),
+    ],
+  )
+  .unwrap();
+  assert_eq!(sfu.ice_servers.len(), 1);
+  assert_eq!(sfu.ice_servers[0].urls, vec!["stun:stun.example.com:3478"]);
+}
+
+#[test]
+fn test_error_display() {
+  assert_eq!(
+    SfuError::SessionNotFound.to_string(),
+    "No SFU session for this room"
+  );
+  assert_eq!(
+    SfuError::WebRtc("boom".into()).to_string(),
+    "WebRTC error: boom"
+  );
+}
@@ -0,0 +1,239 @@
+//! File-backed storage: an append-only JSON-lines log.
+//!
+//! Every mutation appends one [`LogEntry`] line and is flushed with
+//! `fsync` before the call returns. On open the log is replayed into a
+//! [`Snapshot`] and rewritten in compacted form (one `put_*` line per
+//! live record) via a temp file + atomic rename, so a crash during
+//! compaction leaves the previous log intact.
+//!
+//! A failed append is truncated away before the error is returned, so
+//! a torn final line — the only damage a crash mid-append can cause —
+//! is skipped with a warning. Corruption anywhere else is reported as
+//! an error instead of silently dropping accounts.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result, anyhow};
+use message::types::{RoomId, UserId};
+use tracing::{info, warn};
+
+use super::{LogEntry, Snapshot, Storage, StoredRoom, StoredUser};
+
+/// Compact once the log holds this many more lines than live records.
+const COMPACTION_SLACK: usize = 1024;
+
+/// Append-only JSON-lines [`Storage`] backend.
+#[derive(Debug)]
+pub struct FileStorage {
+  path: PathBuf,
+  inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+  snapshot: Snapshot,
+  log: File,
+  /// Lines currently in the log file (live + superseded).
+  log_lines: usize,
+}
+
+impl FileStorage {
+  /// Open (or create) the log at `path`, replay it and compact it.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the file cannot be read or written, or if a
+  /// line other than the last one fails to parse.
+  pub fn open(path: &Path) -> Result<Self> {
+    if let Some(parent) = path.parent()
+      && !parent.as_os_str().is_empty()
+    {
+      fs::create_dir_all(parent)
+        .with_context(|| format!("failed to create storage directory {}", parent.display()))?;
+    }
+
+    let snapshot = replay(path)?;
+    let log = compact(path, &snapshot)?;
+    let log_lines = snapshot.len();
+
+    info!(
+      path = %path.display(),
+      users = snapshot.users.len(),
+      rooms = snapshot.rooms.len(),
+      "File storage opened"
+    );
+
+    Ok(Self {
+      path: path.to_path_buf(),
+      inner: Mutex::new(Inner {
+        snapshot,
+        log,
+        log_lines,
+      }),
+    })
+  }
+
+  /// Path of the backing log file.
+  #[must_use]
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  fn append(&self, entry: LogEntry) -> Result<()> {
+    let mut inner = self
+      .inner
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    let mut line = serde_json::to_vec(&entry)?;
+    line.push(b'\n');
+    let len = inner
+      .log
+      .metadata()
+      .with_context(|| format!("failed to stat {}", self.path.display()))?
+      .len();
+    if let Err(e) = inner
+      .log
+      .write_all(&line)
+      .and_then(|()| inner.log.sync_data())
+    {
+      // A partial line followed by later successful appends would be
+      // corruption in the middle of the log, which refuses to replay.
+      if let Err(truncate) = inner.log.set_len(len) {
+        warn!(
+          path = %self.path.display(),
+          error = %truncate,
+          "Failed to truncate partially appended storage log entry"
+        );
+      }
+      return Err(e).with_context(|| format!("failed to append to {}", self.path.display()));
+    }
+
+    inner.snapshot.apply(entry);
+    inner.log_lines += 1;
+
+    // The entry is durable at this point; a failed compaction is
+    // retried on the next append instead of failing this write.
+    if inner.log_lines > inner.snapshot.len() + COMPACTION_SLACK {
+      match compact(&self.path, &inner.snapshot) {
+        Ok(log) => {
+          inner.log = log;
+          inner.log_lines = inner.snapshot.len();
+        }
+        Err(e) => warn!(
+          path = %self.path.display(),
+          error = %e,
+          "Failed to compact storage log"
+        ),
+      }
+    }
+    Ok(())
+  }
+
+  fn read<T>(&self, f: impl FnOnce(&Snapshot) -> T) -> T {
+    f(&self
+      .inner
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+      .snapshot)
+  }
+}
+
+impl Storage for FileStorage {
+  fn users(&self) -> Vec<StoredUser> {
+    self.read(|s| s.users.values().cloned().collect())
+  }
+
+  fn put_user(&self, user: &StoredUser) -> Result<()> {
+    self.append(LogEntry::PutUser(user.clone()))
+  }
+
+  fn delete_user(&self, user_id: &UserId) -> Result<()> {
+    self.append(LogEntry::DeleteUser {
+      user_id: user_id.clone(),
+    })
+  }
+
+  fn rooms(&self) -> Vec<StoredRoom> {
+    self.read(|s| s.rooms.values().cloned().collect())
+  }
+
+  fn put_room(&self, room: &StoredRoom) -> Result<()> {
+    self.append(LogEntry::PutRoom(room.clone()))
+  }
+
+  fn delete_room(&self, room_id: &RoomId) -> Result<()> {
+    self.append(LogEntry::DeleteRoom {
+      room_id: room_id.clone(),
+    })
+  }
+}
+
+/// Replay the log at `path` into a fresh snapshot. A missing file
+/// yields an empty snapshot.
+fn replay(path: &Path) -> Result<Snapshot> {
+  let mut snapshot = Snapshot::default();
+  let file = match File::open(path) {
+    Ok(file) => file,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(snapshot),
+    Err(e) => return Err(e).with_context(|| format!("failed to open {}", path.display())),
+  };
+
+  let lines = BufReader::new(file)
+    .lines()
+    .collect::<std::io::Result<Vec<_>>>()
+    .with_context(|| format!("failed to read {}", path.display()))?;
+  let last = lines.len().saturating_sub(1);
+
+  for (index, line) in lines.iter().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match serde_json::from_str::<LogEntry>(line) {
+      Ok(entry) => snapshot.apply(entry),
+      Err(e) if index == last => {
+        warn!(
+          path = %path.display(),
+          line = index + 1,
+          error = %e,
+          "Skipping torn trailing storage log entry"
+        );
+      }
+      Err(e) => {
+        return Err(anyhow!(
+          "corrupt storage log {} at line {}: {}",
+          path.display(),
+          index + 1,
+          e
+        ));
+      }
+    }
+  }
+  Ok(snapshot)
+}
+
+/// Rewrite the log as the minimal set of entries for `snapshot` and
+/// return an append handle to the new file.
+fn compact(path: &Path, snapshot: &Snapshot) -> Result<File> {
+  let tmp_path = path.with_extension("compact.tmp");
+  {
+    let tmp = File::create(&tmp_path)
+      .with_context(|| format!("failed to create {}", tmp_path.display()))?;
+    let mut writer = BufWriter::new(tmp);
+    for entry in snapshot.to_entries() {
+      serde_json::to_writer(&mut writer, &entry)?;
+      writer.write_all(b"\n")?;
+    }
+    let tmp = writer.into_inner().map_err(|e| e.into_error())?;
+    tmp.sync_all()?;
+  }
+  fs::rename(&tmp_path, path).with_context(|| format!("failed to replace {}", path.display()))?;
+
+  OpenOptions::new()
+    .append(true)
+    .open(path)
+    .with_context(|| format!("failed to open {} for append", path.display()))
+}
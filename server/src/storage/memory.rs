@@ -0,0 +1,75 @@
+//! Volatile in-process storage backend.
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use message::types::{RoomId, UserId};
+
+use super::{LogEntry, Snapshot, Storage, StoredRoom, StoredUser};
+
+/// In-memory [`Storage`] used by default and throughout the tests.
+///
+/// Everything is lost when the process exits. Two stores built on the
+/// same `Arc<MemoryStorage>` observe each other's writes, which lets
+/// tests exercise the restore path without touching the filesystem.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+  snapshot: Mutex<Snapshot>,
+}
+
+impl MemoryStorage {
+  /// Create an empty memory store.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn apply(&self, entry: LogEntry) {
+    self
+      .snapshot
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+      .apply(entry);
+  }
+
+  fn read<T>(&self, f: impl FnOnce(&Snapshot) -> T) -> T {
+    f(&self
+      .snapshot
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner))
+  }
+}
+
+impl Storage for MemoryStorage {
+  fn users(&self) -> Vec<StoredUser> {
+    self.read(|s| s.users.values().cloned().collect())
+  }
+
+  fn put_user(&self, user: &StoredUser) -> Result<()> {
+    self.apply(LogEntry::PutUser(user.clone()));
+    Ok(())
+  }
+
+  fn delete_user(&self, user_id: &UserId) -> Result<()> {
+    self.apply(LogEntry::DeleteUser {
+      user_id: user_id.clone(),
+    });
+    Ok(())
+  }
+
+  fn rooms(&self) -> Vec<StoredRoom> {
+    self.read(|s| s.rooms.values().cloned().collect())
+  }
+
+  fn put_room(&self, room: &StoredRoom) -> Result<()> {
+    self.apply(LogEntry::PutRoom(room.clone()));
+    Ok(())
+  }
+
+  fn delete_room(&self, room_id: &RoomId) -> Result<()> {
+    self.apply(LogEntry::DeleteRoom {
+      room_id: room_id.clone(),
+    });
+    Ok(())
+  }
+}
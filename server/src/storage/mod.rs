@@ -0,0 +1,209 @@
+//! Pluggable persistence for server-side state.
+//!
+//! `UserStore` and `RoomState` keep their hot state in `DashMap`s and
+//! mirror every durable mutation into a [`Storage`] backend so that
+//! registered accounts and room metadata survive a restart.
+//!
+//! Two backends ship with the server:
+//! - [`MemoryStorage`] — the default. Nothing touches the disk, which
+//!   keeps unit and integration tests hermetic. Sharing one instance
+//!   between two stores simulates a restart inside a single test.
+//! - [`FileStorage`] — an append-only JSON-lines log that is replayed
+//!   and compacted on open. Selected with `STORAGE_BACKEND=file`.
+//!
+//! Backends materialise their full contents when opened, so reads are
+//! infallible; only writes can fail. Callers decide per operation
+//! whether a failed write aborts the request (registration) or is
+//! merely logged (profile tweaks, room metadata).
+
+mod file;
+mod memory;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use bitcode::{Decode, Encode};
+use message::types::{RoomId, RoomInfo, UserId};
+use serde::{Deserialize, Serialize};
+
+pub use file::FileStorage;
+pub use memory::MemoryStorage;
+
+// =============================================================================
+// Records
+// =============================================================================
+
+/// Durable subset of a user account. Also the form in which accounts
+/// are shipped between cluster replicas.
+///
+/// Presence (`status`) is deliberately absent: every user is offline
+/// after a restart until their client re-authenticates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct StoredUser {
+  /// User ID.
+  pub user_id: UserId,
+  /// Unique login name.
+  pub username: String,
+  /// Display nickname.
+  pub nickname: String,
+  /// Argon2id PHC string.
+  pub password_hash: String,
+  /// Open sessions, one per logged-in device. Persisted so that
+  /// logins and logouts keep their effect across restarts.
+  #[serde(default)]
+  pub sessions: Vec<StoredSession>,
+  /// User bio.
+  #[serde(default)]
+  pub bio: String,
+  /// Avatar URL (data URL or CDN URL).
+  #[serde(default)]
+  pub avatar_url: Option<String>,
+  /// Account creation timestamp (Unix nanoseconds).
+  pub created_at_nanos: i64,
+  /// Last activity timestamp (Unix nanoseconds).
+  pub last_seen_nanos: i64,
+}
+
+/// Durable part of one logged-in device's session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct StoredSession {
+  /// Session ID.
+  pub session_id: String,
+  /// Hash of the session's current refresh token.
+  pub refresh_token_hash: String,
+  /// Expiry of the current refresh token (Unix nanoseconds).
+  #[serde(default)]
+  pub refresh_expires_at_nanos: Option<i64>,
+  /// Last use of the session (Unix nanoseconds).
+  #[serde(default)]
+  pub last_used_nanos: i64,
+}
+
+/// Durable subset of a room.
+///
+/// Membership is not persisted: connections do not survive a restart,
+/// so rooms come back empty ("dormant") and members re-join. The owner
+/// regains the `Owner` role when they re-join.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredRoom {
+  /// Room metadata, including password hash and announcement.
+  pub info: RoomInfo,
+  /// Users banned from the room.
+  #[serde(default)]
+  pub banned_users: Vec<UserId>,
+}
+
+// =============================================================================
+// Storage Trait
+// =============================================================================
+
+/// Persistence backend for users and rooms.
+pub trait Storage: Send + Sync + std::fmt::Debug {
+  /// All stored users, in no particular order.
+  fn users(&self) -> Vec<StoredUser>;
+
+  /// Insert or replace a user record.
+  fn put_user(&self, user: &StoredUser) -> Result<()>;
+
+  /// Delete a user record. Deleting a missing user is a no-op.
+  fn delete_user(&self, user_id: &UserId) -> Result<()>;
+
+  /// All stored rooms, in no particular order.
+  fn rooms(&self) -> Vec<StoredRoom>;
+
+  /// Insert or replace a room record.
+  fn put_room(&self, room: &StoredRoom) -> Result<()>;
+
+  /// Delete a room record. Deleting a missing room is a no-op.
+  fn delete_room(&self, room_id: &RoomId) -> Result<()>;
+}
+
+// =============================================================================
+// Backend Selection
+// =============================================================================
+
+/// Storage backend selected through [`crate::config::Config`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StorageBackend {
+  /// Volatile in-process storage (default).
+  #[default]
+  Memory,
+  /// Append-only JSON-lines log at the given path.
+  File {
+    /// Log file path. Parent directories are created on open.
+    path: PathBuf,
+  },
+}
+
+/// Open the configured storage backend.
+///
+/// # Errors
+///
+/// Returns an error if the file backend cannot create, read or compact
+/// its log.
+pub fn open(backend: &StorageBackend) -> Result<Arc<dyn Storage>> {
+  match backend {
+    StorageBackend::Memory => Ok(Arc::new(MemoryStorage::new())),
+    StorageBackend::File { path } => Ok(Arc::new(FileStorage::open(path)?)),
+  }
+}
+
+// =============================================================================
+// Shared Snapshot
+// =============================================================================
+
+/// Single mutation as recorded in the file log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogEntry {
+  PutUser(StoredUser),
+  DeleteUser { user_id: UserId },
+  PutRoom(StoredRoom),
+  DeleteRoom { room_id: RoomId },
+}
+
+/// In-memory image of everything a backend holds.
+#[derive(Debug, Default)]
+struct Snapshot {
+  users: HashMap<UserId, StoredUser>,
+  rooms: HashMap<RoomId, StoredRoom>,
+}
+
+impl Snapshot {
+  fn apply(&mut self, entry: LogEntry) {
+    match entry {
+      LogEntry::PutUser(user) => {
+        self.users.insert(user.user_id.clone(), user);
+      }
+      LogEntry::DeleteUser { user_id } => {
+        self.users.remove(&user_id);
+      }
+      LogEntry::PutRoom(room) => {
+        self.rooms.insert(room.info.room_id.clone(), room);
+      }
+      LogEntry::DeleteRoom { room_id } => {
+        self.rooms.remove(&room_id);
+      }
+    }
+  }
+
+  /// Number of live records (the size of a fully compacted log).
+  fn len(&self) -> usize {
+    self.users.len() + self.rooms.len()
+  }
+
+  /// Entries that recreate this snapshot from an empty log.
+  fn to_entries(&self) -> impl Iterator<Item = LogEntry> + '_ {
+    self
+      .users
+      .values()
+      .cloned()
+      .map(LogEntry::PutUser)
+      .chain(self.rooms.values().cloned().map(LogEntry::PutRoom))
+  }
+}
+
+#[cfg(test)]
+mod tests;
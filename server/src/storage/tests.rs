@@ -0,0 +1,307 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use message::signaling::{BanMember, CreateRoom, JoinRoom, RoomAnnouncement};
+use message::types::{RoomType, UserStatus};
+
+use super::*;
+use crate::auth::UserStore;
+use crate::config::Config;
+use crate::room::RoomState;
+
+fn stored_user(username: &str) -> StoredUser {
+  StoredUser {
+    user_id: UserId::new(),
+    username: username.to_string(),
+    nickname: username.to_string(),
+    password_hash: "$argon2id$placeholder".to_string(),
+    sessions: Vec::new(),
+    bio: String::new(),
+    avatar_url: None,
+    created_at_nanos: 1,
+    last_seen_nanos: 2,
+  }
+}
+
+fn stored_room(name: &str) -> StoredRoom {
+  StoredRoom {
+    info: RoomInfo::new(
+      RoomId::new(),
+      name.to_string(),
+      RoomType::Chat,
+      UserId::new(),
+    ),
+    banned_users: vec![UserId::new()],
+  }
+}
+
+fn create_room_request(name: &str) -> CreateRoom {
+  CreateRoom {
+    name: name.to_string(),
+    description: String::new(),
+    room_type: RoomType::Chat,
+    password: None,
+    max_participants: 8,
+  }
+}
+
+// =============================================================================
+// Backends
+// =============================================================================
+
+#[test]
+fn test_memory_storage_put_and_delete() {
+  let storage = MemoryStorage::new();
+  let user = stored_user("alice");
+  let room = stored_room("lobby");
+
+  storage.put_user(&user).unwrap();
+  storage.put_room(&room).unwrap();
+  assert_eq!(storage.users(), vec![user.clone()]);
+  assert_eq!(storage.rooms(), vec![room.clone()]);
+
+  storage.delete_user(&user.user_id).unwrap();
+  storage.delete_room(&room.info.room_id).unwrap();
+  assert!(storage.users().is_empty());
+  assert!(storage.rooms().is_empty());
+}
+
+#[test]
+fn test_file_storage_survives_reopen() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("nested").join("store.jsonl");
+
+  let mut alice = stored_user("alice");
+  let bob = stored_user("bob");
+  let room = stored_room("lobby");
+  {
+    let storage = FileStorage::open(&path).unwrap();
+    storage.put_user(&alice).unwrap();
+    storage.put_user(&bob).unwrap();
+    storage.put_room(&room).unwrap();
+    alice.nickname = "Alice".to_string();
+    storage.put_user(&alice).unwrap();
+    storage.delete_user(&bob.user_id).unwrap();
+  }
+
+  let storage = FileStorage::open(&path).unwrap();
+  assert_eq!(storage.users(), vec![alice]);
+  assert_eq!(storage.rooms(), vec![room]);
+}
+
+#[test]
+fn test_file_storage_compacts_on_open() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("store.jsonl");
+
+  {
+    let storage = FileStorage::open(&path).unwrap();
+    let mut user = stored_user("alice");
+    for i in 0..10 {
+      user.bio = format!("bio {i}");
+      storage.put_user(&user).unwrap();
+    }
+  }
+  assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 10);
+
+  let storage = FileStorage::open(&path).unwrap();
+  assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+  assert_eq!(storage.users()[0].bio, "bio 9");
+}
+
+#[test]
+fn test_file_storage_append_survives_failed_compaction() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("store.jsonl");
+  let storage = FileStorage::open(&path).unwrap();
+  // A directory in the way of the temporary file makes compaction fail.
+  let tmp_path = path.with_extension("compact.tmp");
+  std::fs::create_dir(&tmp_path).unwrap();
+
+  let mut user = stored_user("alice");
+  for i in 0..1100 {
+    user.bio = format!("bio {i}");
+    storage.put_user(&user).unwrap();
+  }
+  assert_eq!(
+    std::fs::read_to_string(&path).unwrap().lines().count(),
+    1100
+  );
+
+  // The next append retries compaction.
+  std::fs::remove_dir(&tmp_path).unwrap();
+  user.bio = "final".to_string();
+  storage.put_user(&user).unwrap();
+  assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+  assert_eq!(FileStorage::open(&path).unwrap().users()[0].bio, "final");
+}
+
+#[test]
+fn test_file_storage_skips_torn_trailing_line() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("store.jsonl");
+  let user = stored_user("alice");
+  {
+    let storage = FileStorage::open(&path).unwrap();
+    storage.put_user(&user).unwrap();
+  }
+  let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+  file.write_all(b"{\"op\":\"put_us").unwrap();
+  drop(file);
+
+  let storage = FileStorage::open(&path).unwrap();
+  assert_eq!(storage.users(), vec![user]);
+}
+
+#[test]
+fn test_file_storage_rejects_corruption_before_last_line() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("store.jsonl");
+  std::fs::write(
+    &path,
+    "not json\n{\"op\":\"delete_room\",\"room_id\":\"x\"}\n",
+  )
+  .unwrap();
+
+  assert!(FileStorage::open(&path).is_err());
+}
+
+#[test]
+fn test_open_selects_backend() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("store.jsonl");
+
+  let memory = open(&StorageBackend::Memory).unwrap();
+  memory.put_user(&stored_user("alice")).unwrap();
+  assert!(!path.exists());
+
+  let file = open(&StorageBackend::File { path: path.clone() }).unwrap();
+  file.put_user(&stored_user("alice")).unwrap();
+  assert!(path.exists());
+}
+
+// =============================================================================
+// Restore Through The Stores
+// =============================================================================
+
+#[test]
+fn test_user_store_restores_accounts() {
+  let config = Config::default();
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+
+  let store = UserStore::with_storage(&config, storage.clone());
+  let (user_id, _) = store.register("alice", "password123").unwrap();
+  store.set_nickname(&user_id, "Alice");
+  store.update_bio(&user_id, "hello".to_string());
+
+  let restored = UserStore::with_storage(&config, storage);
+  let user = restored.get_user(&user_id).unwrap();
+  assert_eq!(user.nickname, "Alice");
+  assert_eq!(user.bio, "hello");
+  assert_eq!(user.status, UserStatus::Offline);
+  assert!(restored.login("alice", "password123").is_ok());
+  assert!(restored.register("alice", "password456").is_err());
+}
+
+#[test]
+fn test_user_store_restores_session_for_existing_tokens() {
+  let config = Config::default();
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+
+  let store = UserStore::with_storage(&config, storage.clone());
+  let (user_id, laptop_token) = store.register("alice", "password123").unwrap();
+  let (_, phone_token) = store.login("alice", "password123").unwrap();
+  let (_, tablet_token) = store.login("alice", "password123").unwrap();
+  let tablet_sid = store.verify_token(&tablet_token).unwrap().sid;
+  store.logout_session(&user_id, &tablet_sid);
+
+  // Every device keeps its own session across a restart; a device
+  // that logged out stays logged out.
+  let restored = UserStore::with_storage(&config, storage);
+  assert!(restored.authenticate_with_token(&laptop_token).is_ok());
+  assert!(restored.authenticate_with_token(&phone_token).is_ok());
+  assert!(restored.authenticate_with_token(&tablet_token).is_err());
+}
+
+#[test]
+fn test_room_state_restores_dormant_rooms() {
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+  let owner = UserId::new();
+  let banned = UserId::new();
+
+  let state = RoomState::with_storage(storage.clone());
+  let (room_id, _) = state
+    .create_room(&create_room_request("lobby"), owner.clone())
+    .unwrap();
+  state
+    .join_room(
+      &JoinRoom {
+        room_id: room_id.clone(),
+        password: None,
+      },
+      banned.clone(),
+      "troll".to_string(),
+    )
+    .unwrap();
+  state
+    .ban_member(
+      &BanMember {
+        room_id: room_id.clone(),
+        target: banned.clone(),
+      },
+      &owner,
+    )
+    .unwrap();
+  state
+    .set_announcement(
+      &RoomAnnouncement {
+        room_id: room_id.clone(),
+        content: "welcome".to_string(),
+      },
+      &owner,
+    )
+    .unwrap();
+
+  let restored = RoomState::with_storage(storage);
+  let room = restored.get_room(&room_id).unwrap();
+  assert_eq!(room.info.announcement, "welcome");
+  assert_eq!(room.info.member_count, 0);
+  assert!(room.is_banned(&banned));
+  assert_eq!(restored.get_user_room(&owner), None);
+
+  // The owner takes their role back on re-join; the banned user stays out.
+  let join = JoinRoom {
+    room_id: room_id.clone(),
+    password: None,
+  };
+  restored
+    .join_room(&join, owner.clone(), "owner".to_string())
+    .unwrap();
+  let room = restored.get_room(&room_id).unwrap();
+  assert_eq!(
+    room.get_member(&owner).unwrap().role,
+    message::types::RoomRole::Owner
+  );
+  assert!(
+    restored
+      .join_room(&join, banned, "troll".to_string())
+      .is_err()
+  );
+}
+
+#[test]
+fn test_room_state_forgets_destroyed_rooms() {
+  let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+  let owner = UserId::new();
+
+  let state = RoomState::with_storage(storage.clone());
+  let (room_id, _) = state
+    .create_room(&create_room_request("lobby"), owner.clone())
+    .unwrap();
+  assert_eq!(storage.rooms().len(), 1);
+
+  state
+    .leave_room(&message::signaling::LeaveRoom { room_id }, &owner)
+    .unwrap();
+  assert!(storage.rooms().is_empty());
+}
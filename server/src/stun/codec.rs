@@ -0,0 +1,409 @@
+//! STUN message parsing and construction (RFC 5389 §6, §15).
+//!
+//! Shared by the Binding responder and the TURN relay. Parsing is
+//! zero-copy over the received datagram; building appends TLVs to a
+//! buffer and patches the header length as it goes so that
+//! `MESSAGE-INTEGRITY` can be computed over the exact bytes the peer
+//! will see.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha1::Sha1;
+
+/// RFC 5389 magic cookie (in network byte order it ends up `0x2112A442`).
+pub(crate) const MAGIC_COOKIE: u32 = 0x2112_A442;
+
+/// Fixed STUN header size.
+pub(crate) const HEADER_LEN: usize = 20;
+
+/// `MESSAGE-INTEGRITY` attribute size including its 4-byte TLV header.
+const INTEGRITY_ATTR_LEN: usize = 24;
+
+// Attribute types (RFC 5389 §18.2, RFC 5766 §14).
+pub(crate) const ATTR_USERNAME: u16 = 0x0006;
+pub(crate) const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+pub(crate) const ATTR_ERROR_CODE: u16 = 0x0009;
+pub(crate) const ATTR_CHANNEL_NUMBER: u16 = 0x000C;
+pub(crate) const ATTR_LIFETIME: u16 = 0x000D;
+pub(crate) const ATTR_XOR_PEER_ADDRESS: u16 = 0x0012;
+pub(crate) const ATTR_DATA: u16 = 0x0013;
+pub(crate) const ATTR_REALM: u16 = 0x0014;
+pub(crate) const ATTR_NONCE: u16 = 0x0015;
+pub(crate) const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+pub(crate) const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+pub(crate) const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// Address-family discriminator used inside `XOR-*-ADDRESS` attributes.
+pub(crate) const FAMILY_IPV4: u8 = 0x01;
+pub(crate) const FAMILY_IPV6: u8 = 0x02;
+
+/// Class bits of a message type (RFC 5389 §6).
+const CLASS_MASK: u16 = 0x0110;
+const CLASS_INDICATION: u16 = 0x0010;
+const CLASS_SUCCESS: u16 = 0x0100;
+const CLASS_ERROR: u16 = 0x0110;
+
+/// Success-response type for a request type.
+pub(crate) const fn success_type(request: u16) -> u16 {
+  (request & !CLASS_MASK) | CLASS_SUCCESS
+}
+
+/// Error-response type for a request type.
+pub(crate) const fn error_type(request: u16) -> u16 {
+  (request & !CLASS_MASK) | CLASS_ERROR
+}
+
+/// Whether a message type is an indication (no response expected).
+pub(crate) const fn is_indication(msg_type: u16) -> bool {
+  msg_type & CLASS_MASK == CLASS_INDICATION
+}
+
+/// One attribute inside a parsed message.
+#[derive(Debug, Clone, Copy)]
+struct Attribute<'a> {
+  attr_type: u16,
+  value: &'a [u8],
+  /// Offset of the attribute's TLV header within the message.
+  offset: usize,
+}
+
+/// A parsed STUN message borrowing the received datagram.
+#[derive(Debug)]
+pub(crate) struct StunMessage<'a> {
+  /// Message type (method + class).
+  pub(crate) msg_type: u16,
+  /// Transaction ID.
+  pub(crate) tx_id: [u8; 12],
+  raw: &'a [u8],
+  attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> StunMessage<'a> {
+  /// Parse a datagram. Returns `None` for anything that is not a
+  /// well-formed RFC 5389 message so the caller drops it silently.
+  pub(crate) fn parse(raw: &'a [u8]) -> Option<Self> {
+    if raw.len() < HEADER_LEN || raw[0] & 0xC0 != 0 {
+      return None;
+    }
+    let msg_type = u16::from_be_bytes([raw[0], raw[1]]);
+    let length = usize::from(u16::from_be_bytes([raw[2], raw[3]]));
+    let cookie = u32::from_be_bytes([raw[4], raw[5], raw[6], raw[7]]);
+    if cookie != MAGIC_COOKIE || length % 4 != 0 || HEADER_LEN + length != raw.len() {
+      return None;
+    }
+    let tx_id: [u8; 12] = raw[8..20].try_into().ok()?;
+
+    let mut attrs = Vec::new();
+    let mut offset = HEADER_LEN;
+    while offset < raw.len() {
+      if offset + 4 > raw.len() {
+        return None;
+      }
+      let attr_type = u16::from_be_bytes([raw[offset], raw[offset + 1]]);
+      let value_len = usize::from(u16::from_be_bytes([raw[offset + 2], raw[offset + 3]]));
+      let value_start = offset + 4;
+      let value_end = value_start + value_len;
+      if value_end > raw.len() {
+        return None;
+      }
+      attrs.push(Attribute {
+        attr_type,
+        value: &raw[value_start..value_end],
+        offset,
+      });
+      offset = value_end + padding(value_len);
+    }
+
+    Some(Self {
+      msg_type,
+      tx_id,
+      raw,
+      attrs,
+    })
+  }
+
+  /// First value of an attribute type.
+  pub(crate) fn attr(&self, attr_type: u16) -> Option<&'a [u8]> {
+    self
+      .attrs
+      .iter()
+      .find(|a| a.attr_type == attr_type)
+      .map(|a| a.value)
+  }
+
+  /// Every value of an attribute type, in message order.
+  pub(crate) fn attrs(&self, attr_type: u16) -> impl Iterator<Item = &'a [u8]> + '_ {
+    self
+      .attrs
+      .iter()
+      .filter(move |a| a.attr_type == attr_type)
+      .map(|a| a.value)
+  }
+
+  /// Attribute value as UTF-8 text.
+  pub(crate) fn text_attr(&self, attr_type: u16) -> Option<&'a str> {
+    std::str::from_utf8(self.attr(attr_type)?).ok()
+  }
+
+  /// Attribute value as a big-endian `u32`.
+  pub(crate) fn u32_attr(&self, attr_type: u16) -> Option<u32> {
+    let value: [u8; 4] = self.attr(attr_type)?.get(..4)?.try_into().ok()?;
+    Some(u32::from_be_bytes(value))
+  }
+
+  /// Decode an `XOR-*-ADDRESS` attribute value.
+  pub(crate) fn xor_address(&self, value: &[u8]) -> Option<SocketAddr> {
+    decode_xor_address(value, &self.tx_id)
+  }
+
+  /// Whether the message carries a `MESSAGE-INTEGRITY` attribute.
+  pub(crate) fn has_integrity(&self) -> bool {
+    self.attr(ATTR_MESSAGE_INTEGRITY).is_some()
+  }
+
+  /// Check `MESSAGE-INTEGRITY` against `key` (RFC 5389 §15.4).
+  ///
+  /// The HMAC covers everything before the attribute, with the
+  /// header length rewritten to end right after it, so trailing
+  /// attributes such as `FINGERPRINT` are excluded.
+  pub(crate) fn verify_integrity(&self, key: &[u8]) -> bool {
+    let Some(attr) = self
+      .attrs
+      .iter()
+      .find(|a| a.attr_type == ATTR_MESSAGE_INTEGRITY)
+    else {
+      return false;
+    };
+    if attr.value.len() != 20 {
+      return false;
+    }
+    let covered = &self.raw[..attr.offset];
+    let Ok(length) = u16::try_from(attr.offset + INTEGRITY_ATTR_LEN - HEADER_LEN) else {
+      return false;
+    };
+    let mut mac = hmac_sha1(key);
+    mac.update(&covered[..2]);
+    mac.update(&length.to_be_bytes());
+    mac.update(&covered[4..]);
+    mac.verify_slice(attr.value).is_ok()
+  }
+}
+
+/// Incrementally builds an outgoing STUN message.
+#[derive(Debug)]
+pub(crate) struct MessageBuilder {
+  buf: Vec<u8>,
+  tx_id: [u8; 12],
+}
+
+impl MessageBuilder {
+  /// Start a message with the given type and transaction ID.
+  pub(crate) fn new(msg_type: u16, tx_id: [u8; 12]) -> Self {
+    let mut buf = Vec::with_capacity(128);
+    buf.extend_from_slice(&msg_type.to_be_bytes());
+    buf.extend_from_slice(&0u16.to_be_bytes());
+    buf.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    buf.extend_from_slice(&tx_id);
+    Self { buf, tx_id }
+  }
+
+  /// Append a raw attribute, padded to a 4-byte boundary.
+  pub(crate) fn attr(mut self, attr_type: u16, value: &[u8]) -> Self {
+    let value_len = u16::try_from(value.len()).expect("STUN attribute value fits in u16");
+    self.buf.extend_from_slice(&attr_type.to_be_bytes());
+    self.buf.extend_from_slice(&value_len.to_be_bytes());
+    self.buf.extend_from_slice(value);
+    self
+      .buf
+      .extend(std::iter::repeat_n(0u8, padding(value.len())));
+    self.patch_length();
+    self
+  }
+
+  /// Append a big-endian `u32` attribute.
+  pub(crate) fn u32_attr(self, attr_type: u16, value: u32) -> Self {
+    self.attr(attr_type, &value.to_be_bytes())
+  }
+
+  /// Append an `XOR-*-ADDRESS` attribute.
+  pub(crate) fn xor_address(self, attr_type: u16, addr: SocketAddr) -> Self {
+    let value = encode_xor_address(addr, &self.tx_id);
+    self.attr(attr_type, &value)
+  }
+
+  /// Append an `ERROR-CODE` attribute (RFC 5389 §15.6).
+  pub(crate) fn error_code(self, code: u16, reason: &str) -> Self {
+    let mut value = vec![0, 0];
+    value.push(u8::try_from(code / 100).expect("error class fits in u8"));
+    value.push(u8::try_from(code % 100).expect("error number fits in u8"));
+    value.extend_from_slice(reason.as_bytes());
+    self.attr(ATTR_ERROR_CODE, &value)
+  }
+
+  /// Append `MESSAGE-INTEGRITY` keyed with `key`. Must be the last
+  /// attribute added.
+  pub(crate) fn integrity(mut self, key: &[u8]) -> Self {
+    let length = u16::try_from(self.buf.len() + INTEGRITY_ATTR_LEN - HEADER_LEN)
+      .expect("STUN message fits in u16");
+    self.buf[2..4].copy_from_slice(&length.to_be_bytes());
+    let mut mac = hmac_sha1(key);
+    mac.update(&self.buf);
+    let digest = mac.finalize().into_bytes();
+    self.attr(ATTR_MESSAGE_INTEGRITY, &digest)
+  }
+
+  /// Finish and return the encoded message.
+  pub(crate) fn build(self) -> Vec<u8> {
+    self.buf
+  }
+
+  fn patch_length(&mut self) {
+    let length = u16::try_from(self.buf.len() - HEADER_LEN).expect("STUN message fits in u16");
+    self.buf[2..4].copy_from_slice(&length.to_be_bytes());
+  }
+}
+
+/// Number of zero bytes padding a value of `len` bytes to 4-byte alignment.
+const fn padding(len: usize) -> usize {
+  (4 - len % 4) % 4
+}
+
+fn hmac_sha1(key: &[u8]) -> Hmac<Sha1> {
+  <Hmac<Sha1> as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+/// Encode the value of an `XOR-*-ADDRESS` attribute (RFC 5389 §15.2).
+///
+/// The address is XOR'd with the magic cookie (and, for IPv6, the
+/// transaction id) so middleboxes that rewrite payloads cannot
+/// tamper with it without realising they did.
+fn encode_xor_address(addr: SocketAddr, tx_id: &[u8; 12]) -> Vec<u8> {
+  let port_xor = addr.port() ^ ((MAGIC_COOKIE >> 16) as u16);
+  let mask = xor_mask(tx_id);
+
+  let mut value = Vec::with_capacity(4 + 16);
+  value.push(0); // RESERVED
+  match addr.ip() {
+    IpAddr::V4(ipv4) => {
+      value.push(FAMILY_IPV4);
+      value.extend_from_slice(&port_xor.to_be_bytes());
+      value.extend(ipv4.octets().iter().zip(mask.iter()).map(|(o, m)| o ^ m));
+    }
+    IpAddr::V6(ipv6) => {
+      value.push(FAMILY_IPV6);
+      value.extend_from_slice(&port_xor.to_be_bytes());
+      value.extend(ipv6.octets().iter().zip(mask.iter()).map(|(o, m)| o ^ m));
+    }
+  }
+  value
+}
+
+/// Decode the value of an `XOR-*-ADDRESS` attribute.
+fn decode_xor_address(value: &[u8], tx_id: &[u8; 12]) -> Option<SocketAddr> {
+  if value.len() < 4 {
+    return None;
+  }
+  let port = u16::from_be_bytes([value[2], value[3]]) ^ ((MAGIC_COOKIE >> 16) as u16);
+  let mask = xor_mask(tx_id);
+  let ip = match (value[1], &value[4..]) {
+    (FAMILY_IPV4, octets) if octets.len() == 4 => {
+      let mut ip = [0u8; 4];
+      for (i, byte) in ip.iter_mut().enumerate() {
+        *byte = octets[i] ^ mask[i];
+      }
+      IpAddr::V4(Ipv4Addr::from(ip))
+    }
+    (FAMILY_IPV6, octets) if octets.len() == 16 => {
+      let mut ip = [0u8; 16];
+      for (i, byte) in ip.iter_mut().enumerate() {
+        *byte = octets[i] ^ mask[i];
+      }
+      IpAddr::V6(Ipv6Addr::from(ip))
+    }
+    _ => return None,
+  };
+  Some(SocketAddr::new(ip, port))
+}
+
+/// Cookie followed by the transaction id: the XOR mask for addresses.
+fn xor_mask(tx_id: &[u8; 12]) -> [u8; 16] {
+  let mut mask = [0u8; 16];
+  mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+  mask[4..].copy_from_slice(tx_id);
+  mask
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TX_ID: [u8; 12] = [7; 12];
+
+  #[test]
+  fn test_builder_output_parses_back() {
+    let peer: SocketAddr = "10.0.0.9:4000".parse().unwrap();
+    let raw = MessageBuilder::new(0x0003, TX_ID)
+      .u32_attr(ATTR_LIFETIME, 600)
+      .xor_address(ATTR_XOR_PEER_ADDRESS, peer)
+      .attr(ATTR_USERNAME, b"alice")
+      .build();
+
+    let msg = StunMessage::parse(&raw).unwrap();
+    assert_eq!(msg.msg_type, 0x0003);
+    assert_eq!(msg.tx_id, TX_ID);
+    assert_eq!(msg.u32_attr(ATTR_LIFETIME), Some(600));
+    assert_eq!(msg.text_attr(ATTR_USERNAME), Some("alice"));
+    let value = msg.attr(ATTR_XOR_PEER_ADDRESS).unwrap();
+    assert_eq!(msg.xor_address(value), Some(peer));
+  }
+
+  #[test]
+  fn test_xor_address_round_trip_ipv6() {
+    let addr: SocketAddr = "[2001:db8::1]:5000".parse().unwrap();
+    let value = encode_xor_address(addr, &TX_ID);
+    assert_eq!(decode_xor_address(&value, &TX_ID), Some(addr));
+  }
+
+  #[test]
+  fn test_integrity_verifies_with_same_key_only() {
+    let raw = MessageBuilder::new(0x0003, TX_ID)
+      .attr(ATTR_USERNAME, b"alice")
+      .integrity(b"key")
+      .build();
+    let msg = StunMessage::parse(&raw).unwrap();
+    assert!(msg.has_integrity());
+    assert!(msg.verify_integrity(b"key"));
+    assert!(!msg.verify_integrity(b"other"));
+  }
+
+  #[test]
+  fn test_integrity_ignores_trailing_attributes() {
+    let mut raw = MessageBuilder::new(0x0003, TX_ID)
+      .integrity(b"key")
+      .attr(0x8028, &[1, 2, 3, 4])
+      .build();
+    assert!(StunMessage::parse(&raw).unwrap().verify_integrity(b"key"));
+
+    // Tampering with a covered byte breaks the check.
+    raw[8] ^= 0xFF;
+    assert!(!StunMessage::parse(&raw).unwrap().verify_integrity(b"key"));
+  }
+
+  #[test]
+  fn test_parse_rejects_truncated_attribute() {
+    let mut raw = MessageBuilder::new(0x0001, TX_ID)
+      .attr(ATTR_USERNAME, b"abcd")
+      .build();
+    raw[22..24].copy_from_slice(&40u16.to_be_bytes());
+    assert!(StunMessage::parse(&raw).is_none());
+  }
+
+  #[test]
+  fn test_class_helpers() {
+    assert_eq!(success_type(0x0003), 0x0103);
+    assert_eq!(error_type(0x0003), 0x0113);
+    assert!(is_indication(0x0016));
+    assert!(!is_indication(0x0003));
+  }
+}
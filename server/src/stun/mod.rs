@@ -1,16 +1,23 @@
-//! Embedded STUN server (RFC 5389 Binding subset).
+//! Embedded STUN server (RFC 5389 Binding subset) with an optional
+//! TURN relay (RFC 5766).
 //!
 //! Exists so that **a fresh deployment can immediately serve WebRTC
 //! peers on a closed LAN** — without requiring the operator to run a
 //! separate `coturn` container, configure firewalls, or set
 //! `STUN_TURN_SERVERS`.
 //!
-//! Scope: only the bare minimum needed for WebRTC ICE host-candidate
-//! discovery. We answer Binding Requests with an XOR-MAPPED-ADDRESS
-//! pointing back at the source IP/port. We do **not** implement
-//! authentication, ALLOCATE / TURN relays, fingerprint validation,
-//! or message-integrity — those are unnecessary for STUN-only use
-//! and reduce attack surface by being absent.
+//! Scope: by default only the bare minimum needed for WebRTC ICE
+//! host-candidate discovery. We answer Binding Requests with an
+//! XOR-MAPPED-ADDRESS pointing back at the source IP/port; Binding
+//! needs no authentication, fingerprint validation or
+//! message-integrity.
+//!
+//! When [`TurnConfig`](crate::config::TurnConfig) is set, the same UDP
+//! socket also serves TURN (see [`turn`]) so peers behind symmetric
+//! NAT or strict firewalls can still connect through a relay. TURN
+//! requests are authenticated with short-lived credentials minted
+//! from the JWT secret; leaving TURN disabled keeps the original,
+//! unauthenticated-but-harmless Binding-only surface.
 //!
 //! Wire format (RFC 5389 §6):
 //!
@@ -26,53 +33,70 @@
 //!   Value  (Length B)
 //! ```
 
+mod codec;
+pub mod turn;
+
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
 
 use tokio::net::UdpSocket;
 use tracing::{debug, info, warn};
 
-/// RFC 5389 magic cookie (in network byte order it ends up `0x2112A442`).
-const MAGIC_COOKIE: u32 = 0x2112_A442;
+use crate::metrics::Metrics;
+
+use codec::{ATTR_XOR_MAPPED_ADDRESS, MessageBuilder, StunMessage};
+use turn::TurnServer;
 
 /// `BINDING REQUEST` message type (RFC 5389 §3, table on p.16).
 const BINDING_REQUEST: u16 = 0x0001;
 /// `BINDING SUCCESS RESPONSE` message type.
 const BINDING_SUCCESS: u16 = 0x0101;
 
-/// Attribute type for `XOR-MAPPED-ADDRESS` (RFC 5389 §15.2).
-const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
-
-/// Address-family discriminator used inside `XOR-MAPPED-ADDRESS`.
-const FAMILY_IPV4: u8 = 0x01;
-const FAMILY_IPV6: u8 = 0x02;
-
 /// Spawn the STUN server task on `bind_addr`. Returns once the
 /// socket is bound; the server then runs forever on a detached task.
 ///
+/// With `turn` set, the same socket also serves TURN allocations
+/// using the given configuration and credential secret. Request
+/// volume is recorded in `metrics`.
+///
 /// Errors propagate from the `UdpSocket::bind` call so the caller
 /// can decide whether to abort startup or just log a warning and
 /// continue (the chat server itself is still usable without an
 /// embedded STUN — clients fall back to the public STUN list).
-pub async fn spawn(bind_addr: SocketAddr) -> std::io::Result<()> {
-  let socket = UdpSocket::bind(bind_addr).await?;
+pub async fn spawn(
+  bind_addr: SocketAddr,
+  turn: Option<(crate::config::TurnConfig, turn::TurnSecret)>,
+  metrics: Arc<Metrics>,
+) -> std::io::Result<SocketAddr> {
+  let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
   let local_addr = socket.local_addr()?;
   info!(stun_addr = %local_addr, "Embedded STUN server listening");
 
+  let turn = turn.map(|(config, secret)| {
+    info!(
+      relay_ports = ?config.relay_ports,
+      public_ip = %config.public_ip,
+      "Embedded TURN relay enabled"
+    );
+    let server = Arc::new(TurnServer::new(config, secret, socket.clone()));
+    server.spawn_sweeper();
+    server
+  });
+
   tokio::spawn(async move {
-    serve(socket).await;
+    serve(socket, turn, metrics).await;
   });
 
-  Ok(())
+  Ok(local_addr)
 }
 
 /// Receive loop. Each packet is parsed and either responded to or
 /// silently dropped (RFC 5389 §6 mandates that we MUST silently
 /// discard malformed messages).
-async fn serve(socket: UdpSocket) {
-  // 576 bytes covers any well-formed STUN message (Binding Request
-  // is at most ~100 B in practice). MTU is irrelevant because STUN
-  // packets are small.
-  let mut buf = [0u8; 1500];
+async fn serve(socket: Arc<UdpSocket>, turn: Option<Arc<TurnServer>>, metrics: Arc<Metrics>) {
+  // Sized for the largest UDP payload: TURN Send indications and
+  // `ChannelData` may carry datagrams beyond a single Ethernet MTU.
+  let mut buf = vec![0u8; turn::MAX_DATAGRAM];
   loop {
     let (n, src) = match socket.recv_from(&mut buf).await {
       Ok(pair) => pair,
@@ -82,9 +106,24 @@ async fn serve(socket: UdpSocket) {
       }
     };
 
-    let request = &buf[..n];
-    let Some(response) = handle_request(request, src) else {
-      // Not a valid Binding Request — drop silently.
+    let packet = &buf[..n];
+    let response = match &turn {
+      Some(turn) if !is_binding_request(packet) => {
+        metrics.turn_packets.inc();
+        turn.handle_packet(packet, src).await
+      }
+      _ => {
+        let response = handle_request(packet, src);
+        if response.is_some() {
+          metrics.stun_binding_requests.inc();
+        } else {
+          metrics.stun_dropped_packets.inc();
+        }
+        response
+      }
+    };
+    let Some(response) = response else {
+      // Not a valid Binding Request (or a TURN indication) — nothing to send.
       continue;
     };
 
@@ -94,86 +133,30 @@ async fn serve(socket: UdpSocket) {
   }
 }
 
+/// Cheap pre-dispatch check on the message type field.
+fn is_binding_request(packet: &[u8]) -> bool {
+  packet.len() >= 2 && u16::from_be_bytes([packet[0], packet[1]]) == BINDING_REQUEST
+}
+
 /// Parse a Binding Request and return the bytes of the corresponding
 /// Binding Success Response. Returns `None` for malformed or
 /// unsupported messages so the caller drops them silently.
 fn handle_request(request: &[u8], src: SocketAddr) -> Option<Vec<u8>> {
-  if request.len() < 20 {
+  // Pre-RFC 5389 ("classic STUN") clients omit the cookie and are
+  // rejected by the parser; modern browsers always send it.
+  let msg = StunMessage::parse(request)?;
+  if msg.msg_type != BINDING_REQUEST {
     return None;
   }
-  let msg_type = u16::from_be_bytes([request[0], request[1]]);
-  if msg_type != BINDING_REQUEST {
-    return None;
-  }
-  let cookie = u32::from_be_bytes([request[4], request[5], request[6], request[7]]);
-  if cookie != MAGIC_COOKIE {
-    // Pre-RFC 5389 ("classic STUN") clients omit the cookie. We
-    // ignore them; modern browsers always send the cookie.
-    return None;
-  }
-  let tx_id: [u8; 12] = request[8..20].try_into().ok()?;
-
-  Some(build_binding_response(tx_id, src))
+  Some(build_binding_response(msg.tx_id, src))
 }
 
 /// Build a Binding Success Response containing a single
 /// `XOR-MAPPED-ADDRESS` attribute that echoes `src` back to the peer.
 fn build_binding_response(tx_id: [u8; 12], src: SocketAddr) -> Vec<u8> {
-  let attr = encode_xor_mapped_address(src, &tx_id);
-  // STUN message length covers attributes only (not the 20-byte header).
-  let attr_len = u16::try_from(attr.len()).expect("attribute fits in u16");
-
-  let mut out = Vec::with_capacity(20 + attr.len());
-  out.extend_from_slice(&BINDING_SUCCESS.to_be_bytes());
-  out.extend_from_slice(&attr_len.to_be_bytes());
-  out.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
-  out.extend_from_slice(&tx_id);
-  out.extend_from_slice(&attr);
-  out
-}
-
-/// Encode a `XOR-MAPPED-ADDRESS` attribute (RFC 5389 §15.2).
-///
-/// The address is XOR'd with the magic cookie (and, for IPv6, the
-/// transaction id) so middleboxes that rewrite payloads cannot
-/// tamper with it without realising they did.
-fn encode_xor_mapped_address(addr: SocketAddr, tx_id: &[u8; 12]) -> Vec<u8> {
-  let port_xor = addr.port() ^ ((MAGIC_COOKIE >> 16) as u16);
-
-  let mut value = Vec::with_capacity(4 + 16);
-  value.push(0); // RESERVED
-  match addr.ip() {
-    IpAddr::V4(ipv4) => {
-      value.push(FAMILY_IPV4);
-      value.extend_from_slice(&port_xor.to_be_bytes());
-      let octets = ipv4.octets();
-      let cookie_bytes = MAGIC_COOKIE.to_be_bytes();
-      value.extend(octets.iter().zip(cookie_bytes.iter()).map(|(o, c)| o ^ c));
-    }
-    IpAddr::V6(ipv6) => {
-      value.push(FAMILY_IPV6);
-      value.extend_from_slice(&port_xor.to_be_bytes());
-      let octets = ipv6.octets();
-      let cookie_bytes = MAGIC_COOKIE.to_be_bytes();
-      value.extend(
-        octets[..4]
-          .iter()
-          .zip(cookie_bytes.iter())
-          .map(|(o, c)| o ^ c),
-      );
-      value.extend(octets[4..].iter().zip(tx_id.iter()).map(|(o, t)| o ^ t));
-    }
-  }
-
-  // Wrap into a TLV (type, length, value, padded to 4-byte boundary).
-  let value_len = u16::try_from(value.len()).expect("XOR-MAPPED-ADDRESS value fits in u16");
-  let pad = (4 - value.len() % 4) % 4;
-  let mut attr = Vec::with_capacity(4 + value.len() + pad);
-  attr.extend_from_slice(&ATTR_XOR_MAPPED_ADDRESS.to_be_bytes());
-  attr.extend_from_slice(&value_len.to_be_bytes());
-  attr.extend_from_slice(&value);
-  attr.extend(std::iter::repeat_n(0u8, pad));
-  attr
+  MessageBuilder::new(BINDING_SUCCESS, tx_id)
+    .xor_address(ATTR_XOR_MAPPED_ADDRESS, src)
+    .build()
 }
 
 /// Best-effort detection of the host's primary LAN IPv4. Used by the
@@ -224,6 +207,7 @@ const _: Option<Ipv6Addr> = None;
 
 #[cfg(test)]
 mod tests {
+  use super::codec::{FAMILY_IPV4, MAGIC_COOKIE};
   use super::*;
 
   #[test]
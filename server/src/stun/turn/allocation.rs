@@ -0,0 +1,258 @@
+//! TURN allocations (RFC 5766 §5).
+//!
+//! An allocation ties a client transport address to a relayed UDP
+//! socket, together with the permissions and channel bindings that
+//! decide which peers may exchange data through it. Each allocation
+//! owns a task that forwards peer datagrams back to the client; the
+//! task is aborted when the allocation is dropped.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::bandwidth::BandwidthLimiter;
+use super::{DATA_INDICATION, data_indication_tx_id};
+use crate::stun::codec::{ATTR_DATA, ATTR_XOR_PEER_ADDRESS, MessageBuilder};
+
+/// How long a permission lasts once installed or refreshed (RFC 5766 §8).
+pub(super) const PERMISSION_LIFETIME: Duration = Duration::from_secs(300);
+
+/// How long a channel binding lasts once installed or refreshed (RFC 5766 §11).
+pub(super) const CHANNEL_LIFETIME: Duration = Duration::from_secs(600);
+
+/// Largest UDP payload, so relayed datagrams are never truncated.
+pub(crate) const MAX_DATAGRAM: usize = 65_535;
+
+/// First pause after a failed `recv_from`, doubled up to
+/// [`MAX_RECV_BACKOFF`] while the socket keeps failing.
+const INITIAL_RECV_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Longest pause between two failing `recv_from` calls.
+const MAX_RECV_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Authenticated TURN user that owns an allocation.
+#[derive(Debug, Clone)]
+pub(super) struct Credential {
+  /// Chat user the credential was minted for.
+  pub(super) user: String,
+  /// Long-term key used for `MESSAGE-INTEGRITY`.
+  pub(super) key: [u8; 16],
+}
+
+/// Why a channel binding was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChannelConflict {
+  /// The channel number is bound to a different peer.
+  ChannelInUse,
+  /// The peer is bound to a different channel number.
+  PeerInUse,
+}
+
+/// One live relay allocation.
+#[derive(Debug)]
+pub(super) struct Allocation {
+  /// Client transport address (the 5-tuple key, UDP only).
+  pub(super) client: SocketAddr,
+  /// Owner of the allocation.
+  pub(super) credential: Credential,
+  /// Relayed transport address advertised to the client.
+  pub(super) relay_addr: SocketAddr,
+  relay: Arc<UdpSocket>,
+  expires_at: Mutex<Instant>,
+  permissions: DashMap<IpAddr, Instant>,
+  channels: DashMap<u16, (SocketAddr, Instant)>,
+  relay_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Allocation {
+  /// Create an allocation around an already-bound relay socket.
+  pub(super) fn new(
+    client: SocketAddr,
+    credential: Credential,
+    relay: UdpSocket,
+    relay_addr: SocketAddr,
+    lifetime: Duration,
+  ) -> Arc<Self> {
+    Arc::new(Self {
+      client,
+      credential,
+      relay_addr,
+      relay: Arc::new(relay),
+      expires_at: Mutex::new(Instant::now() + lifetime),
+      permissions: DashMap::new(),
+      channels: DashMap::new(),
+      relay_task: Mutex::new(None),
+    })
+  }
+
+  /// Start forwarding peer datagrams to the client through `listener`.
+  pub(super) fn spawn_relay(
+    self: &Arc<Self>,
+    listener: Arc<UdpSocket>,
+    bandwidth: Arc<BandwidthLimiter>,
+  ) {
+    let task = tokio::spawn(relay_loop(
+      Arc::downgrade(self),
+      self.relay.clone(),
+      listener,
+      bandwidth,
+    ));
+    *self.relay_task.lock().expect("relay task lock poisoned") = Some(task);
+  }
+
+  /// Extend (or shorten) the allocation's lifetime from now.
+  pub(super) fn refresh(&self, lifetime: Duration) {
+    *self.expires_at.lock().expect("expiry lock poisoned") = Instant::now() + lifetime;
+  }
+
+  /// Whether the allocation's lifetime has run out.
+  pub(super) fn is_expired(&self) -> bool {
+    Instant::now() >= *self.expires_at.lock().expect("expiry lock poisoned")
+  }
+
+  /// Install or refresh a permission for `ip`.
+  pub(super) fn add_permission(&self, ip: IpAddr) {
+    self
+      .permissions
+      .insert(ip, Instant::now() + PERMISSION_LIFETIME);
+  }
+
+  /// Whether `ip` currently holds a permission.
+  pub(super) fn has_permission(&self, ip: IpAddr) -> bool {
+    self
+      .permissions
+      .get(&ip)
+      .is_some_and(|expires| Instant::now() < *expires)
+  }
+
+  /// Bind `number` to `peer`, or refresh an identical binding.
+  pub(super) fn bind_channel(&self, number: u16, peer: SocketAddr) -> Result<(), ChannelConflict> {
+    let now = Instant::now();
+    if let Some(entry) = self.channels.get(&number)
+      && entry.0 != peer
+      && now < entry.1
+    {
+      return Err(ChannelConflict::ChannelInUse);
+    }
+    if self
+      .channel_for_peer(peer)
+      .is_some_and(|bound| bound != number)
+    {
+      return Err(ChannelConflict::PeerInUse);
+    }
+    self.channels.insert(number, (peer, now + CHANNEL_LIFETIME));
+    self.add_permission(peer.ip());
+    Ok(())
+  }
+
+  /// Peer bound to a channel number.
+  pub(super) fn channel_peer(&self, number: u16) -> Option<SocketAddr> {
+    self
+      .channels
+      .get(&number)
+      .filter(|entry| Instant::now() < entry.1)
+      .map(|entry| entry.0)
+  }
+
+  /// Channel number bound to a peer.
+  pub(super) fn channel_for_peer(&self, peer: SocketAddr) -> Option<u16> {
+    let now = Instant::now();
+    self
+      .channels
+      .iter()
+      .find(|entry| entry.value().0 == peer && now < entry.value().1)
+      .map(|entry| *entry.key())
+  }
+
+  /// Send client data out of the relay socket to `peer`.
+  pub(super) async fn send_to_peer(&self, data: &[u8], peer: SocketAddr) {
+    if let Err(e) = self.relay.send_to(data, peer).await {
+      debug!(peer = %peer, error = %e, "TURN: relay send_to failed");
+    }
+  }
+}
+
+impl Drop for Allocation {
+  fn drop(&mut self) {
+    if let Ok(mut task) = self.relay_task.lock()
+      && let Some(task) = task.take()
+    {
+      task.abort();
+    }
+  }
+}
+
+/// Forward datagrams arriving on the relay socket to the client, as
+/// `ChannelData` when the peer has a channel and as a Data indication
+/// otherwise. Datagrams from peers without a permission are dropped
+/// (RFC 5766 §10.3).
+async fn relay_loop(
+  allocation: Weak<Allocation>,
+  relay: Arc<UdpSocket>,
+  listener: Arc<UdpSocket>,
+  bandwidth: Arc<BandwidthLimiter>,
+) {
+  let mut buf = vec![0u8; MAX_DATAGRAM];
+  let mut backoff = INITIAL_RECV_BACKOFF;
+  loop {
+    let (n, peer) = match relay.recv_from(&mut buf).await {
+      Ok(received) => {
+        backoff = INITIAL_RECV_BACKOFF;
+        received
+      }
+      Err(e) => {
+        // Errors such as ICMP port unreachable are reported here; pause
+        // so a socket that keeps failing does not spin the task.
+        debug!(error = %e, "TURN: relay recv_from failed");
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RECV_BACKOFF);
+        continue;
+      }
+    };
+    let Some(allocation) = allocation.upgrade() else {
+      return;
+    };
+    if !allocation.has_permission(peer.ip()) || !bandwidth.allow(&allocation.credential.user, n) {
+      continue;
+    }
+
+    let data = &buf[..n];
+    let packet = match allocation.channel_for_peer(peer) {
+      Some(channel) => encode_channel_data(channel, data),
+      None => MessageBuilder::new(DATA_INDICATION, data_indication_tx_id())
+        .xor_address(ATTR_XOR_PEER_ADDRESS, peer)
+        .attr(ATTR_DATA, data)
+        .build(),
+    };
+    if let Err(e) = listener.send_to(&packet, allocation.client).await {
+      debug!(client = %allocation.client, error = %e, "TURN: send to client failed");
+    }
+  }
+}
+
+/// Encode a `ChannelData` message (RFC 5766 §11.4). No padding is
+/// added: it is optional over UDP.
+pub(super) fn encode_channel_data(channel: u16, data: &[u8]) -> Vec<u8> {
+  let len = u16::try_from(data.len()).expect("datagram fits in u16");
+  let mut out = Vec::with_capacity(4 + data.len());
+  out.extend_from_slice(&channel.to_be_bytes());
+  out.extend_from_slice(&len.to_be_bytes());
+  out.extend_from_slice(data);
+  out
+}
+
+/// Split a `ChannelData` message into channel number and payload.
+pub(super) fn decode_channel_data(packet: &[u8]) -> Option<(u16, &[u8])> {
+  if packet.len() < 4 {
+    return None;
+  }
+  let channel = u16::from_be_bytes([packet[0], packet[1]]);
+  let len = usize::from(u16::from_be_bytes([packet[2], packet[3]]));
+  let data = packet.get(4..4 + len)?;
+  Some((channel, data))
+}
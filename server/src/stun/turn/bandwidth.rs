@@ -0,0 +1,109 @@
+//! Per-user relay bandwidth caps.
+//!
+//! A token bucket per TURN user, refilled at the configured rate with
+//! a one-second burst. Packets that do not fit are dropped, which is
+//! what a congested path would do anyway; WebRTC congestion control
+//! then backs the sender off.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+/// Buckets untouched for this long are forgotten by [`BandwidthLimiter::prune`].
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+  tokens: u64,
+  last_refill: Instant,
+}
+
+/// Token-bucket limiter keyed by TURN user.
+#[derive(Debug)]
+pub(super) struct BandwidthLimiter {
+  /// Refill rate; `0` disables limiting.
+  bytes_per_sec: u64,
+  buckets: DashMap<String, Bucket>,
+}
+
+impl BandwidthLimiter {
+  /// Create a limiter capping each user at `kbps` kilobits per second
+  /// (`0` = unlimited).
+  pub(super) fn new(kbps: u32) -> Self {
+    Self {
+      bytes_per_sec: u64::from(kbps) * 1000 / 8,
+      buckets: DashMap::new(),
+    }
+  }
+
+  /// Charge `bytes` to `user`. Returns `false` if the packet exceeds
+  /// the user's remaining budget and must be dropped.
+  pub(super) fn allow(&self, user: &str, bytes: usize) -> bool {
+    self.allow_at(user, bytes, Instant::now())
+  }
+
+  fn allow_at(&self, user: &str, bytes: usize, now: Instant) -> bool {
+    if self.bytes_per_sec == 0 {
+      return true;
+    }
+    let mut bucket = self
+      .buckets
+      .entry(user.to_string())
+      .or_insert_with(|| Bucket {
+        tokens: self.bytes_per_sec,
+        last_refill: now,
+      });
+
+    let elapsed = now.saturating_duration_since(bucket.last_refill);
+    let refill = elapsed.as_micros() * u128::from(self.bytes_per_sec) / 1_000_000;
+    let refill = u64::try_from(refill).unwrap_or(u64::MAX);
+    bucket.tokens = bucket.tokens.saturating_add(refill).min(self.bytes_per_sec);
+    bucket.last_refill = now;
+
+    let cost = u64::try_from(bytes).unwrap_or(u64::MAX);
+    if bucket.tokens >= cost {
+      bucket.tokens -= cost;
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Drop buckets that have been idle long enough to be full again.
+  pub(super) fn prune(&self) {
+    let now = Instant::now();
+    self
+      .buckets
+      .retain(|_, bucket| now.saturating_duration_since(bucket.last_refill) < IDLE_BUCKET_TTL);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_unlimited_when_zero() {
+    let limiter = BandwidthLimiter::new(0);
+    assert!(limiter.allow("alice", usize::MAX));
+  }
+
+  #[test]
+  fn test_burst_then_throttle_then_refill() {
+    // 8 kbps = 1000 bytes per second.
+    let limiter = BandwidthLimiter::new(8);
+    let start = Instant::now();
+    assert!(limiter.allow_at("alice", 600, start));
+    assert!(!limiter.allow_at("alice", 600, start));
+    assert!(limiter.allow_at("alice", 600, start + Duration::from_millis(300)));
+  }
+
+  #[test]
+  fn test_users_have_independent_budgets() {
+    let limiter = BandwidthLimiter::new(8);
+    let now = Instant::now();
+    assert!(limiter.allow_at("alice", 1000, now));
+    assert!(!limiter.allow_at("alice", 1, now));
+    assert!(limiter.allow_at("bob", 1000, now));
+  }
+}
@@ -0,0 +1,628 @@
+//! Embedded TURN relay (RFC 5766 subset, UDP only).
+//!
+//! Covers what browsers need to relay media when no direct path
+//! exists: Allocate, Refresh, CreatePermission and ChannelBind
+//! requests, Send/Data indications and `ChannelData` framing. TCP
+//! relays, `DONT-FRAGMENT`, `EVEN-PORT` and reservations are not
+//! supported.
+//!
+//! Authentication follows the long-term credential mechanism
+//! (RFC 5389 §10.2) with time-limited REST-style credentials: the
+//! username is `<unix expiry>:<user id>` and the password is
+//! `base64(HMAC-SHA1(secret, username))`, where the secret is derived
+//! from the JWT secret. The signaling server mints a fresh pair for
+//! every authenticated client and pushes it in `AuthSuccess`, so the
+//! relay needs no user database of its own.
+
+mod allocation;
+mod bandwidth;
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use dashmap::DashMap;
+use hmac::{Hmac, KeyInit, Mac};
+use md5::{Digest, Md5};
+use message::UserId;
+use sha1::Sha1;
+use sha2::Sha256;
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+pub(crate) use self::allocation::MAX_DATAGRAM;
+use self::allocation::{Allocation, ChannelConflict, Credential, decode_channel_data};
+use self::bandwidth::BandwidthLimiter;
+use super::codec::{
+  ATTR_CHANNEL_NUMBER, ATTR_DATA, ATTR_LIFETIME, ATTR_NONCE, ATTR_REALM, ATTR_REQUESTED_TRANSPORT,
+  ATTR_USERNAME, ATTR_XOR_MAPPED_ADDRESS, ATTR_XOR_PEER_ADDRESS, ATTR_XOR_RELAYED_ADDRESS,
+  MessageBuilder, StunMessage, error_type, is_indication, success_type,
+};
+use crate::config::{IpNetwork, TurnConfig};
+
+// Method types (RFC 5766 §13).
+const ALLOCATE: u16 = 0x0003;
+const REFRESH: u16 = 0x0004;
+const SEND_INDICATION: u16 = 0x0016;
+const DATA_INDICATION: u16 = 0x0017;
+const CREATE_PERMISSION: u16 = 0x0008;
+const CHANNEL_BIND: u16 = 0x0009;
+
+/// `REQUESTED-TRANSPORT` protocol number for UDP.
+const TRANSPORT_UDP: u8 = 17;
+
+/// Valid channel numbers (RFC 5766 §11).
+const CHANNEL_NUMBERS: std::ops::RangeInclusive<u16> = 0x4000..=0x7FFE;
+
+/// Allocation lifetime when the client does not ask for one.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(600);
+
+/// Upper bound on any requested allocation lifetime.
+const MAX_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Concurrent allocations one user may hold, so a single account
+/// cannot exhaust the relay port range.
+const MAX_ALLOCATIONS_PER_USER: usize = 8;
+
+/// How long a nonce handed out in a 401 or 438 response stays valid.
+const NONCE_LIFETIME: Duration = Duration::from_secs(600);
+
+/// How often expired allocations are reclaimed.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Domain separator for deriving the TURN secret from the JWT secret.
+const SECRET_CONTEXT: &[u8] = b"webrtc-chat-turn-credentials-v1";
+
+/// Secret used to mint and verify TURN credentials.
+///
+/// Derived from the JWT secret so operators have one secret to
+/// rotate, while a leaked TURN password never helps forge a JWT.
+#[derive(Clone)]
+pub struct TurnSecret([u8; 32]);
+
+impl TurnSecret {
+  /// Derive the TURN secret from the server's JWT secret.
+  #[must_use]
+  pub fn from_jwt_secret(jwt_secret: &str) -> Self {
+    let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(jwt_secret.as_bytes())
+      .expect("HMAC accepts keys of any length");
+    mac.update(SECRET_CONTEXT);
+    Self(mac.finalize().into_bytes().into())
+  }
+}
+
+impl std::fmt::Debug for TurnSecret {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("TurnSecret(..)")
+  }
+}
+
+/// Time-limited TURN username and password for one chat user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnCredentials {
+  /// `<unix expiry>:<user id>`.
+  pub username: String,
+  /// `base64(HMAC-SHA1(secret, username))`.
+  pub password: String,
+}
+
+/// Mint credentials for `user_id` valid for `ttl` from `now`.
+#[must_use]
+pub fn mint_credentials(
+  secret: &TurnSecret,
+  user_id: &UserId,
+  ttl: Duration,
+  now: SystemTime,
+) -> TurnCredentials {
+  let expires = (now + ttl)
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+  let username = format!("{expires}:{user_id}");
+  let password = password_for(secret, &username);
+  TurnCredentials { username, password }
+}
+
+fn password_for(secret: &TurnSecret, username: &str) -> String {
+  let mut mac =
+    <Hmac<Sha1> as KeyInit>::new_from_slice(&secret.0).expect("HMAC accepts keys of any length");
+  mac.update(username.as_bytes());
+  base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// Long-term credential key: `MD5(username ":" realm ":" password)`.
+fn long_term_key(username: &str, realm: &str, password: &str) -> [u8; 16] {
+  Md5::digest(format!("{username}:{realm}:{password}").as_bytes()).into()
+}
+
+/// Seconds since the Unix epoch.
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs()
+}
+
+/// Split a username into its expiry and chat user parts.
+fn parse_username(username: &str) -> Option<(u64, &str)> {
+  let (expires, user) = username.split_once(':')?;
+  Some((expires.parse().ok()?, user))
+}
+
+/// Random transaction ID for server-originated Data indications.
+fn data_indication_tx_id() -> [u8; 12] {
+  let bytes = uuid::Uuid::new_v4().into_bytes();
+  bytes[..12].try_into().expect("uuid has 16 bytes")
+}
+
+/// A rejected request, turned into an error response by [`TurnServer::respond`].
+#[derive(Debug)]
+struct TurnError {
+  code: u16,
+  reason: &'static str,
+  /// Key to sign the error response with, once the client is authenticated.
+  key: Option<[u8; 16]>,
+}
+
+impl TurnError {
+  const fn new(code: u16, reason: &'static str) -> Self {
+    Self {
+      code,
+      reason,
+      key: None,
+    }
+  }
+
+  const fn signed(code: u16, reason: &'static str, credential: &Credential) -> Self {
+    Self {
+      code,
+      reason,
+      key: Some(credential.key),
+    }
+  }
+
+  const fn bad_request() -> Self {
+    Self::new(400, "Bad Request")
+  }
+
+  const fn unauthorized() -> Self {
+    Self::new(401, "Unauthorized")
+  }
+}
+
+/// TURN request handler sharing the embedded STUN socket.
+#[derive(Debug)]
+pub(crate) struct TurnServer {
+  config: TurnConfig,
+  secret: TurnSecret,
+  /// Key nonces are signed with, so they can expire without the server
+  /// remembering each one it handed out.
+  nonce_key: [u8; 32],
+  socket: Arc<UdpSocket>,
+  allocations: DashMap<SocketAddr, Arc<Allocation>>,
+  /// Serializes Allocate requests so the per-user quota and the
+  /// one-allocation-per-client rule cannot be raced.
+  allocate_lock: Mutex<()>,
+  bandwidth: Arc<BandwidthLimiter>,
+  next_port: AtomicUsize,
+}
+
+impl TurnServer {
+  /// Create a relay answering on `socket`.
+  pub(crate) fn new(config: TurnConfig, secret: TurnSecret, socket: Arc<UdpSocket>) -> Self {
+    let bandwidth = Arc::new(BandwidthLimiter::new(config.user_bandwidth_kbps));
+    let mut nonce_key = [0u8; 32];
+    OsRng.fill_bytes(&mut nonce_key);
+    Self {
+      config,
+      secret,
+      nonce_key,
+      socket,
+      allocations: DashMap::new(),
+      allocate_lock: Mutex::new(()),
+      bandwidth,
+      next_port: AtomicUsize::new(0),
+    }
+  }
+
+  /// Periodically reclaim expired allocations and idle bandwidth buckets.
+  pub(crate) fn spawn_sweeper(self: &Arc<Self>) {
+    let server = Arc::downgrade(self);
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+      loop {
+        interval.tick().await;
+        let Some(server) = server.upgrade() else {
+          return;
+        };
+        server.allocations.retain(|_, a| !a.is_expired());
+        server.bandwidth.prune();
+      }
+    });
+  }
+
+  /// Handle a non-Binding packet from `src`. Returns the response to
+  /// send back, if any (indications and `ChannelData` get none).
+  pub(crate) async fn handle_packet(&self, packet: &[u8], src: SocketAddr) -> Option<Vec<u8>> {
+    // The top two bits distinguish ChannelData (0b01) from STUN (0b00).
+    if packet.first().is_some_and(|b| b & 0xC0 == 0x40) {
+      self.handle_channel_data(packet, src).await;
+      return None;
+    }
+
+    let msg = StunMessage::parse(packet)?;
+    if is_indication(msg.msg_type) {
+      if msg.msg_type == SEND_INDICATION {
+        self.handle_send(&msg, src).await;
+      }
+      return None;
+    }
+
+    let result = match msg.msg_type {
+      ALLOCATE => self.handle_allocate(&msg, src).await,
+      REFRESH => self.handle_refresh(&msg, src),
+      CREATE_PERMISSION => self.handle_create_permission(&msg, src),
+      CHANNEL_BIND => self.handle_channel_bind(&msg, src),
+      _ => return None,
+    };
+    Some(self.respond(&msg, result))
+  }
+
+  fn respond(&self, msg: &StunMessage<'_>, result: Result<Vec<u8>, TurnError>) -> Vec<u8> {
+    let err = match result {
+      Ok(response) => return response,
+      Err(err) => err,
+    };
+    let mut builder =
+      MessageBuilder::new(error_type(msg.msg_type), msg.tx_id).error_code(err.code, err.reason);
+    if matches!(err.code, 401 | 438) {
+      builder = builder
+        .attr(ATTR_REALM, self.config.realm.as_bytes())
+        .attr(ATTR_NONCE, self.issue_nonce().as_bytes());
+    }
+    if let Some(key) = err.key {
+      builder = builder.integrity(&key);
+    }
+    builder.build()
+  }
+
+  /// Check the long-term credential on a request (RFC 5389 §10.2.2).
+  fn authenticate(&self, msg: &StunMessage<'_>) -> Result<Credential, TurnError> {
+    if !msg.has_integrity() {
+      return Err(TurnError::unauthorized());
+    }
+    let (Some(username), Some(realm), Some(nonce)) = (
+      msg.text_attr(ATTR_USERNAME),
+      msg.text_attr(ATTR_REALM),
+      msg.text_attr(ATTR_NONCE),
+    ) else {
+      return Err(TurnError::bad_request());
+    };
+    if !self.nonce_valid(nonce) {
+      return Err(TurnError::new(438, "Stale Nonce"));
+    }
+    let Some((expires, user)) = parse_username(username) else {
+      return Err(TurnError::unauthorized());
+    };
+    if realm != self.config.realm || expires < unix_now() {
+      return Err(TurnError::unauthorized());
+    }
+
+    let key = long_term_key(username, realm, &password_for(&self.secret, username));
+    if !msg.verify_integrity(&key) {
+      return Err(TurnError::unauthorized());
+    }
+    Ok(Credential {
+      user: user.to_string(),
+      key,
+    })
+  }
+
+  /// A fresh nonce: `<unix expiry>-<MAC of the expiry>`.
+  fn issue_nonce(&self) -> String {
+    let expires = unix_now() + NONCE_LIFETIME.as_secs();
+    let mac = self.nonce_mac(expires).finalize().into_bytes();
+    format!(
+      "{expires}-{}",
+      base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(mac)
+    )
+  }
+
+  /// Whether `nonce` was issued by this server and has not expired.
+  fn nonce_valid(&self, nonce: &str) -> bool {
+    let Some((expires, mac)) = nonce.split_once('-') else {
+      return false;
+    };
+    let (Ok(expires), Ok(mac)) = (
+      expires.parse::<u64>(),
+      base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(mac),
+    ) else {
+      return false;
+    };
+    expires >= unix_now() && self.nonce_mac(expires).verify_slice(&mac).is_ok()
+  }
+
+  fn nonce_mac(&self, expires: u64) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(&self.nonce_key)
+      .expect("HMAC accepts keys of any length");
+    mac.update(&expires.to_be_bytes());
+    mac
+  }
+
+  /// Whether the relay may exchange data with `ip`: anything outside
+  /// the denied networks, plus explicit exceptions. The relay's own
+  /// address is allowed so two clients of this server can reach each
+  /// other's relayed candidates; [`Self::peer_addr_allowed`] then
+  /// limits it to relay ports.
+  fn peer_ip_allowed(&self, ip: IpAddr) -> bool {
+    let listed = |networks: &[IpNetwork]| networks.iter().any(|net| net.contains(ip));
+    ip.to_canonical() == self.config.public_ip
+      || listed(&self.config.allowed_peer_ips)
+      || !listed(&self.config.denied_peer_ips)
+  }
+
+  /// Whether the relay may send to `peer`; see [`Self::peer_ip_allowed`].
+  fn peer_addr_allowed(&self, peer: SocketAddr) -> bool {
+    let ip = peer.ip();
+    if ip.to_canonical() == self.config.public_ip
+      && !self
+        .config
+        .allowed_peer_ips
+        .iter()
+        .any(|net| net.contains(ip))
+    {
+      return self.config.relay_ports.contains(&peer.port());
+    }
+    self.peer_ip_allowed(ip)
+  }
+
+  /// Live allocation for `src` owned by `credential`.
+  fn allocation_for(
+    &self,
+    src: SocketAddr,
+    credential: &Credential,
+  ) -> Result<Arc<Allocation>, TurnError> {
+    let allocation = self
+      .live_allocation(src)
+      .ok_or_else(|| TurnError::signed(437, "Allocation Mismatch", credential))?;
+    if allocation.credential.user != credential.user {
+      return Err(TurnError::signed(441, "Wrong Credentials", credential));
+    }
+    Ok(allocation)
+  }
+
+  fn live_allocation(&self, src: SocketAddr) -> Option<Arc<Allocation>> {
+    let allocation = self.allocations.get(&src)?.clone();
+    if allocation.is_expired() {
+      self.allocations.remove(&src);
+      return None;
+    }
+    Some(allocation)
+  }
+
+  async fn handle_allocate(
+    &self,
+    msg: &StunMessage<'_>,
+    src: SocketAddr,
+  ) -> Result<Vec<u8>, TurnError> {
+    let credential = self.authenticate(msg)?;
+    let _serialized = self.allocate_lock.lock().await;
+    if self.live_allocation(src).is_some() {
+      return Err(TurnError::signed(437, "Allocation Mismatch", &credential));
+    }
+    match msg.attr(ATTR_REQUESTED_TRANSPORT) {
+      Some([TRANSPORT_UDP, ..]) => {}
+      Some(_) => {
+        return Err(TurnError::signed(
+          442,
+          "Unsupported Transport Protocol",
+          &credential,
+        ));
+      }
+      None => return Err(TurnError::signed(400, "Bad Request", &credential)),
+    }
+    let held = self
+      .allocations
+      .iter()
+      .filter(|a| a.credential.user == credential.user && !a.is_expired())
+      .count();
+    if held >= MAX_ALLOCATIONS_PER_USER {
+      return Err(TurnError::signed(
+        486,
+        "Allocation Quota Reached",
+        &credential,
+      ));
+    }
+
+    let Some(relay) = self.bind_relay().await else {
+      return Err(TurnError::signed(508, "Insufficient Capacity", &credential));
+    };
+    let relay_port = relay
+      .local_addr()
+      .map_err(|_| TurnError::signed(508, "Insufficient Capacity", &credential))?
+      .port();
+    let relay_addr = SocketAddr::new(self.config.public_ip, relay_port);
+    let lifetime = requested_lifetime(msg).unwrap_or(DEFAULT_LIFETIME);
+
+    let key = credential.key;
+    let allocation = Allocation::new(src, credential, relay, relay_addr, lifetime);
+    allocation.spawn_relay(self.socket.clone(), self.bandwidth.clone());
+    info!(
+      client = %src,
+      relay = %allocation.relay_addr,
+      user = %allocation.credential.user,
+      "TURN allocation created"
+    );
+    self.allocations.insert(src, allocation);
+
+    Ok(
+      MessageBuilder::new(success_type(ALLOCATE), msg.tx_id)
+        .xor_address(ATTR_XOR_RELAYED_ADDRESS, relay_addr)
+        .u32_attr(ATTR_LIFETIME, lifetime_secs(lifetime))
+        .xor_address(ATTR_XOR_MAPPED_ADDRESS, src)
+        .integrity(&key)
+        .build(),
+    )
+  }
+
+  fn handle_refresh(&self, msg: &StunMessage<'_>, src: SocketAddr) -> Result<Vec<u8>, TurnError> {
+    let credential = self.authenticate(msg)?;
+    let allocation = self.allocation_for(src, &credential)?;
+    let lifetime = requested_lifetime(msg).unwrap_or(DEFAULT_LIFETIME);
+    if lifetime.is_zero() {
+      self.allocations.remove(&src);
+      debug!(client = %src, "TURN allocation released");
+    } else {
+      allocation.refresh(lifetime);
+    }
+    Ok(
+      MessageBuilder::new(success_type(REFRESH), msg.tx_id)
+        .u32_attr(ATTR_LIFETIME, lifetime_secs(lifetime))
+        .integrity(&credential.key)
+        .build(),
+    )
+  }
+
+  fn handle_create_permission(
+    &self,
+    msg: &StunMessage<'_>,
+    src: SocketAddr,
+  ) -> Result<Vec<u8>, TurnError> {
+    let credential = self.authenticate(msg)?;
+    let allocation = self.allocation_for(src, &credential)?;
+    let peers: Option<Vec<IpAddr>> = msg
+      .attrs(ATTR_XOR_PEER_ADDRESS)
+      .map(|value| msg.xor_address(value).map(|addr| addr.ip()))
+      .collect();
+    let peers = match peers {
+      Some(peers) if !peers.is_empty() => peers,
+      _ => return Err(TurnError::signed(400, "Bad Request", &credential)),
+    };
+    // All or nothing (RFC 5766 §9.2): one refused peer fails the request.
+    if !peers.iter().all(|ip| self.peer_ip_allowed(*ip)) {
+      return Err(TurnError::signed(403, "Forbidden", &credential));
+    }
+    for ip in peers {
+      allocation.add_permission(ip);
+    }
+    Ok(
+      MessageBuilder::new(success_type(CREATE_PERMISSION), msg.tx_id)
+        .integrity(&credential.key)
+        .build(),
+    )
+  }
+
+  fn handle_channel_bind(
+    &self,
+    msg: &StunMessage<'_>,
+    src: SocketAddr,
+  ) -> Result<Vec<u8>, TurnError> {
+    let credential = self.authenticate(msg)?;
+    let allocation = self.allocation_for(src, &credential)?;
+    let number = msg
+      .attr(ATTR_CHANNEL_NUMBER)
+      .and_then(|v| Some(u16::from_be_bytes([*v.first()?, *v.get(1)?])))
+      .filter(|n| CHANNEL_NUMBERS.contains(n));
+    let peer = msg
+      .attr(ATTR_XOR_PEER_ADDRESS)
+      .and_then(|v| msg.xor_address(v));
+    let (Some(number), Some(peer)) = (number, peer) else {
+      return Err(TurnError::signed(400, "Bad Request", &credential));
+    };
+    if !self.peer_addr_allowed(peer) {
+      return Err(TurnError::signed(403, "Forbidden", &credential));
+    }
+    match allocation.bind_channel(number, peer) {
+      Ok(()) => {}
+      Err(ChannelConflict::ChannelInUse | ChannelConflict::PeerInUse) => {
+        return Err(TurnError::signed(400, "Bad Request", &credential));
+      }
+    }
+    Ok(
+      MessageBuilder::new(success_type(CHANNEL_BIND), msg.tx_id)
+        .integrity(&credential.key)
+        .build(),
+    )
+  }
+
+  /// Relay a Send indication's payload to its peer (RFC 5766 §10.2).
+  async fn handle_send(&self, msg: &StunMessage<'_>, src: SocketAddr) {
+    let Some(allocation) = self.live_allocation(src) else {
+      return;
+    };
+    let (Some(peer), Some(data)) = (
+      msg
+        .attr(ATTR_XOR_PEER_ADDRESS)
+        .and_then(|v| msg.xor_address(v)),
+      msg.attr(ATTR_DATA),
+    ) else {
+      return;
+    };
+    self.relay_to_peer(&allocation, data, peer).await;
+  }
+
+  /// Relay a `ChannelData` payload to the channel's peer (RFC 5766 §11.6).
+  async fn handle_channel_data(&self, packet: &[u8], src: SocketAddr) {
+    let Some(allocation) = self.live_allocation(src) else {
+      return;
+    };
+    let Some((channel, data)) = decode_channel_data(packet) else {
+      return;
+    };
+    let Some(peer) = allocation.channel_peer(channel) else {
+      return;
+    };
+    self.relay_to_peer(&allocation, data, peer).await;
+  }
+
+  async fn relay_to_peer(&self, allocation: &Allocation, data: &[u8], peer: SocketAddr) {
+    if !allocation.has_permission(peer.ip())
+      || !self.peer_addr_allowed(peer)
+      || !self
+        .bandwidth
+        .allow(&allocation.credential.user, data.len())
+    {
+      return;
+    }
+    allocation.send_to_peer(data, peer).await;
+  }
+
+  /// Bind a relay socket on the next free port in the configured range.
+  async fn bind_relay(&self) -> Option<UdpSocket> {
+    let start = *self.config.relay_ports.start();
+    let span = usize::from(*self.config.relay_ports.end()).checked_sub(usize::from(start))? + 1;
+    for _ in 0..span {
+      let offset = self.next_port.fetch_add(1, Ordering::Relaxed) % span;
+      let port = start + u16::try_from(offset).ok()?;
+      if let Ok(socket) = UdpSocket::bind(SocketAddr::new(self.bind_ip(), port)).await {
+        return Some(socket);
+      }
+    }
+    None
+  }
+
+  /// Relay sockets listen on the same family as the advertised address.
+  fn bind_ip(&self) -> IpAddr {
+    match self.config.public_ip {
+      IpAddr::V4(_) => IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
+      IpAddr::V6(_) => IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED),
+    }
+  }
+}
+
+/// Requested `LIFETIME`, clamped to [`MAX_LIFETIME`].
+fn requested_lifetime(msg: &StunMessage<'_>) -> Option<Duration> {
+  let secs = msg.u32_attr(ATTR_LIFETIME)?;
+  Some(Duration::from_secs(u64::from(secs)).min(MAX_LIFETIME))
+}
+
+fn lifetime_secs(lifetime: Duration) -> u32 {
+  u32::try_from(lifetime.as_secs()).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests;
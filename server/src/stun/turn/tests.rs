@@ -0,0 +1,395 @@
+use std::net::Ipv4Addr;
+
+use super::allocation::encode_channel_data;
+use super::*;
+use crate::config::DEFAULT_DENIED_PEER_NETWORKS;
+use crate::stun::codec::ATTR_ERROR_CODE;
+
+const REALM: &str = "test-realm";
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn test_config() -> TurnConfig {
+  TurnConfig {
+    realm: REALM.to_string(),
+    public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+    // Port 0 lets the OS pick a free relay port for every allocation.
+    relay_ports: 0..=0,
+    user_bandwidth_kbps: 0,
+    credential_ttl: Duration::from_secs(3600),
+    denied_peer_ips: IpNetwork::parse_list(&DEFAULT_DENIED_PEER_NETWORKS.join(",")),
+    // Test peers listen on loopback, which is denied by default.
+    allowed_peer_ips: vec!["127.0.0.0/8".parse().unwrap()],
+  }
+}
+
+async fn start_server() -> (SocketAddr, TurnSecret) {
+  start_server_with(test_config()).await
+}
+
+async fn start_server_with(config: TurnConfig) -> (SocketAddr, TurnSecret) {
+  let secret = TurnSecret::from_jwt_secret("test-jwt-secret");
+  let addr = crate::stun::spawn(
+    SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+    Some((config, secret.clone())),
+    Arc::default(),
+  )
+  .await
+  .expect("bind STUN/TURN socket");
+  (addr, secret)
+}
+
+async fn recv(socket: &UdpSocket) -> (Vec<u8>, SocketAddr) {
+  let mut buf = [0u8; 1500];
+  let (n, from) = tokio::time::timeout(RECV_TIMEOUT, socket.recv_from(&mut buf))
+    .await
+    .expect("timed out waiting for datagram")
+    .expect("recv_from");
+  (buf[..n].to_vec(), from)
+}
+
+fn error_code(msg: &StunMessage<'_>) -> Option<u16> {
+  let value = msg.attr(ATTR_ERROR_CODE)?;
+  Some(u16::from(value[2]) * 100 + u16::from(value[3]))
+}
+
+/// Minimal TURN client speaking to the embedded server.
+struct TestClient {
+  socket: UdpSocket,
+  server: SocketAddr,
+  username: String,
+  key: [u8; 16],
+  nonce: String,
+}
+
+impl TestClient {
+  async fn new(server: SocketAddr, secret: &TurnSecret, user: u64) -> Self {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let creds = mint_credentials(
+      secret,
+      &UserId::from(user),
+      Duration::from_secs(600),
+      SystemTime::now(),
+    );
+    Self {
+      socket,
+      server,
+      key: long_term_key(&creds.username, REALM, &creds.password),
+      username: creds.username,
+      nonce: String::new(),
+    }
+  }
+
+  /// Send a request and return the raw response.
+  async fn request(&self, msg: Vec<u8>) -> Vec<u8> {
+    self.socket.send_to(&msg, self.server).await.unwrap();
+    recv(&self.socket).await.0
+  }
+
+  /// Authenticated request of `method`; `extra` adds attributes
+  /// before `MESSAGE-INTEGRITY`.
+  async fn signed(
+    &self,
+    method: u16,
+    extra: impl FnOnce(MessageBuilder) -> MessageBuilder,
+  ) -> Vec<u8> {
+    let builder = MessageBuilder::new(method, data_indication_tx_id())
+      .attr(ATTR_USERNAME, self.username.as_bytes())
+      .attr(ATTR_REALM, REALM.as_bytes())
+      .attr(ATTR_NONCE, self.nonce.as_bytes());
+    self
+      .request(extra(builder).integrity(&self.key).build())
+      .await
+  }
+
+  /// Unauthenticated Allocate to learn the nonce, then the real one.
+  /// Returns the relayed address.
+  async fn allocate(&mut self) -> SocketAddr {
+    let challenge = self
+      .request(
+        MessageBuilder::new(ALLOCATE, data_indication_tx_id())
+          .attr(ATTR_REQUESTED_TRANSPORT, &[TRANSPORT_UDP, 0, 0, 0])
+          .build(),
+      )
+      .await;
+    let challenge = StunMessage::parse(&challenge).unwrap();
+    assert_eq!(challenge.msg_type, error_type(ALLOCATE));
+    assert_eq!(error_code(&challenge), Some(401));
+    assert_eq!(challenge.text_attr(ATTR_REALM), Some(REALM));
+    self.nonce = challenge.text_attr(ATTR_NONCE).unwrap().to_string();
+
+    let response = self
+      .signed(ALLOCATE, |b| {
+        b.attr(ATTR_REQUESTED_TRANSPORT, &[TRANSPORT_UDP, 0, 0, 0])
+      })
+      .await;
+    let response = StunMessage::parse(&response).unwrap();
+    assert_eq!(response.msg_type, success_type(ALLOCATE));
+    assert!(response.verify_integrity(&self.key));
+    assert_eq!(
+      response
+        .attr(ATTR_XOR_MAPPED_ADDRESS)
+        .and_then(|v| response.xor_address(v)),
+      Some(self.socket.local_addr().unwrap())
+    );
+    response
+      .attr(ATTR_XOR_RELAYED_ADDRESS)
+      .and_then(|v| response.xor_address(v))
+      .expect("relayed address")
+  }
+}
+
+#[test]
+fn test_credentials_round_trip() {
+  let secret = TurnSecret::from_jwt_secret("secret");
+  let now = UNIX_EPOCH + Duration::from_secs(1_000);
+  let user = UserId::from(7);
+  let creds = mint_credentials(&secret, &user, Duration::from_secs(60), now);
+  assert_eq!(creds.username, format!("1060:{user}"));
+  assert_eq!(creds.password, password_for(&secret, &creds.username));
+  assert_eq!(
+    parse_username(&creds.username),
+    Some((1060, user.to_string().as_str()))
+  );
+}
+
+#[test]
+fn test_secret_depends_on_jwt_secret() {
+  let a = TurnSecret::from_jwt_secret("one");
+  let b = TurnSecret::from_jwt_secret("two");
+  assert_ne!(password_for(&a, "1:u"), password_for(&b, "1:u"));
+  assert_eq!(format!("{a:?}"), "TurnSecret(..)");
+}
+
+#[tokio::test]
+async fn test_allocate_rejects_wrong_password() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  client.allocate().await;
+
+  // Same username, key derived from a different password.
+  let mut intruder = TestClient::new(server, &secret, 2).await;
+  intruder.username.clone_from(&client.username);
+  intruder.nonce.clone_from(&client.nonce);
+  let response = intruder
+    .signed(ALLOCATE, |b| {
+      b.attr(ATTR_REQUESTED_TRANSPORT, &[TRANSPORT_UDP, 0, 0, 0])
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(response.msg_type, error_type(ALLOCATE));
+  assert_eq!(error_code(&response), Some(401));
+}
+
+#[tokio::test]
+async fn test_allocate_rejects_tcp_transport() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  client.allocate().await;
+
+  let mut other = TestClient::new(server, &secret, 1).await;
+  other.nonce.clone_from(&client.nonce);
+  let response = other
+    .signed(ALLOCATE, |b| {
+      b.attr(ATTR_REQUESTED_TRANSPORT, &[6, 0, 0, 0])
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(error_code(&response), Some(442));
+  assert!(response.verify_integrity(&other.key));
+}
+
+#[tokio::test]
+async fn test_send_and_data_indications() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  let relay = client.allocate().await;
+  let peer = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+  let peer_addr = peer.local_addr().unwrap();
+
+  // Without a permission the relay drops traffic from the peer.
+  peer.send_to(b"early", relay).await.unwrap();
+
+  let response = client
+    .signed(CREATE_PERMISSION, |b| {
+      b.xor_address(ATTR_XOR_PEER_ADDRESS, peer_addr)
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(response.msg_type, success_type(CREATE_PERMISSION));
+  assert!(response.verify_integrity(&client.key));
+
+  let send = MessageBuilder::new(SEND_INDICATION, data_indication_tx_id())
+    .xor_address(ATTR_XOR_PEER_ADDRESS, peer_addr)
+    .attr(ATTR_DATA, b"hello peer")
+    .build();
+  client.socket.send_to(&send, server).await.unwrap();
+  let (data, from) = recv(&peer).await;
+  assert_eq!(data, b"hello peer");
+  assert_eq!(from.port(), relay.port());
+
+  peer.send_to(b"hello client", relay).await.unwrap();
+  let (packet, _) = recv(&client.socket).await;
+  let indication = StunMessage::parse(&packet).unwrap();
+  assert_eq!(indication.msg_type, DATA_INDICATION);
+  assert_eq!(indication.attr(ATTR_DATA), Some(&b"hello client"[..]));
+  assert_eq!(
+    indication
+      .attr(ATTR_XOR_PEER_ADDRESS)
+      .and_then(|v| indication.xor_address(v)),
+    Some(peer_addr)
+  );
+}
+
+#[tokio::test]
+async fn test_channel_bind_and_channel_data() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  let relay = client.allocate().await;
+  let peer = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+  let peer_addr = peer.local_addr().unwrap();
+
+  let response = client
+    .signed(CHANNEL_BIND, |b| {
+      b.attr(ATTR_CHANNEL_NUMBER, &[0x40, 0x01, 0, 0])
+        .xor_address(ATTR_XOR_PEER_ADDRESS, peer_addr)
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(response.msg_type, success_type(CHANNEL_BIND));
+
+  client
+    .socket
+    .send_to(&encode_channel_data(0x4001, b"via channel"), server)
+    .await
+    .unwrap();
+  let (data, _) = recv(&peer).await;
+  assert_eq!(data, b"via channel");
+
+  peer.send_to(b"back", relay).await.unwrap();
+  let (packet, _) = recv(&client.socket).await;
+  assert_eq!(decode_channel_data(&packet), Some((0x4001, &b"back"[..])));
+
+  // Channel numbers outside 0x4000..=0x7FFE are rejected.
+  let response = client
+    .signed(CHANNEL_BIND, |b| {
+      b.attr(ATTR_CHANNEL_NUMBER, &[0x80, 0x00, 0, 0])
+        .xor_address(ATTR_XOR_PEER_ADDRESS, peer_addr)
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(error_code(&response), Some(400));
+}
+
+#[tokio::test]
+async fn test_refresh_zero_releases_allocation() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  client.allocate().await;
+
+  let response = client
+    .signed(REFRESH, |b| b.u32_attr(ATTR_LIFETIME, 0))
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(response.msg_type, success_type(REFRESH));
+  assert_eq!(response.u32_attr(ATTR_LIFETIME), Some(0));
+
+  let response = client
+    .signed(REFRESH, |b| b.u32_attr(ATTR_LIFETIME, 600))
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(error_code(&response), Some(437));
+}
+
+#[tokio::test]
+async fn test_stale_nonce() {
+  let (server, secret) = start_server().await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  client.allocate().await;
+  client.nonce = "not-the-nonce".to_string();
+
+  let response = client
+    .signed(REFRESH, |b| b.u32_attr(ATTR_LIFETIME, 600))
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(error_code(&response), Some(438));
+  assert!(response.text_attr(ATTR_NONCE).is_some());
+}
+
+#[tokio::test]
+async fn test_nonce_expires_and_resists_forgery() {
+  let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+  let server = TurnServer::new(
+    test_config(),
+    TurnSecret::from_jwt_secret("test-jwt-secret"),
+    Arc::new(socket),
+  );
+  let nonce = server.issue_nonce();
+  assert!(server.nonce_valid(&nonce));
+
+  // Moving the expiry invalidates the MAC.
+  let (expires, mac) = nonce.split_once('-').unwrap();
+  let later = expires.parse::<u64>().unwrap() + 3600;
+  assert!(!server.nonce_valid(&format!("{later}-{mac}")));
+
+  // A correctly signed nonce is refused once it has expired.
+  let past = unix_now() - 1;
+  let mac = server.nonce_mac(past).finalize().into_bytes();
+  let expired = format!(
+    "{past}-{}",
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(mac)
+  );
+  assert!(!server.nonce_valid(&expired));
+}
+
+#[tokio::test]
+async fn test_denied_peers_are_forbidden() {
+  let mut config = test_config();
+  config.allowed_peer_ips.clear();
+  let (server, secret) = start_server_with(config).await;
+  let mut client = TestClient::new(server, &secret, 1).await;
+  client.allocate().await;
+
+  for peer in [
+    "127.0.0.2:9",
+    "10.0.0.1:80",
+    "169.254.169.254:80",
+    "[::1]:9",
+  ] {
+    let peer: SocketAddr = peer.parse().unwrap();
+    let response = client
+      .signed(CREATE_PERMISSION, |b| {
+        b.xor_address(ATTR_XOR_PEER_ADDRESS, peer)
+      })
+      .await;
+    let response = StunMessage::parse(&response).unwrap();
+    assert_eq!(error_code(&response), Some(403), "peer {peer}");
+
+    let response = client
+      .signed(CHANNEL_BIND, |b| {
+        b.attr(ATTR_CHANNEL_NUMBER, &[0x40, 0x02, 0, 0])
+          .xor_address(ATTR_XOR_PEER_ADDRESS, peer)
+      })
+      .await;
+    let response = StunMessage::parse(&response).unwrap();
+    assert_eq!(error_code(&response), Some(403), "peer {peer}");
+  }
+
+  // The relay's own address is reachable on relay ports only.
+  let response = client
+    .signed(CHANNEL_BIND, |b| {
+      b.attr(ATTR_CHANNEL_NUMBER, &[0x40, 0x02, 0, 0])
+        .xor_address(ATTR_XOR_PEER_ADDRESS, "127.0.0.1:9".parse().unwrap())
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(error_code(&response), Some(403));
+
+  // Public peers are still reachable.
+  let response = client
+    .signed(CREATE_PERMISSION, |b| {
+      b.xor_address(ATTR_XOR_PEER_ADDRESS, "203.0.113.7:3478".parse().unwrap())
+    })
+    .await;
+  let response = StunMessage::parse(&response).unwrap();
+  assert_eq!(response.msg_type, success_type(CREATE_PERMISSION));
+}
@@ -0,0 +1,285 @@
+//! Cluster bus servicing: applies events from other replicas and
+//! replicates local room, account and mailbox changes.
+
+use std::sync::Arc;
+
+use message::UserId;
+use message::frame::decode_frame;
+use message::signaling::{
+  OwnerChanged, RoomListUpdate, RoomMemberUpdate, SignalingMessage, UserListUpdate,
+  UserStatusChange,
+};
+use message::types::UserStatus;
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::mailbox::push_deposit;
+use super::{WebSocketState, decode_signaling_message, encode_signaling_message};
+use crate::auth::AccountChange;
+use crate::cluster::{BusNotice, ClusterEvent, NodeId};
+use crate::mailbox::{MailboxChange, MailboxState};
+
+/// Start servicing the cluster bus of `ws_state` until `cancel_token`
+/// fires.
+pub(super) fn spawn(ws_state: &Arc<WebSocketState>, cancel_token: CancellationToken) {
+  let Some(mut notices) = ws_state.cluster().and_then(|c| c.bus().subscribe()) else {
+    warn!("Cluster bus already subscribed, not servicing it again");
+    return;
+  };
+  let room_changes = ws_state.room_state.change_notify().unwrap_or_default();
+  let account_changes = ws_state.user_store.change_notify().unwrap_or_default();
+  let mailbox_changes = ws_state
+    .mailbox()
+    .and_then(MailboxState::change_notify)
+    .unwrap_or_default();
+  let ws_state = Arc::clone(ws_state);
+  tokio::spawn(async move {
+    loop {
+      select! {
+        () = cancel_token.cancelled() => break,
+        notice = notices.recv() => match notice {
+          Some(notice) => handle_notice(&ws_state, notice).await,
+          None => break,
+        },
+        () = room_changes.notified() => ws_state.flush_room_changes(),
+        () = account_changes.notified() => ws_state.flush_account_changes(),
+        () = mailbox_changes.notified() => ws_state.flush_mailbox_changes(),
+      }
+    }
+    info!("Cluster bus task stopped");
+  });
+}
+
+/// Apply one notice from the bus.
+async fn handle_notice(ws_state: &Arc<WebSocketState>, notice: BusNotice) {
+  match notice {
+    BusNotice::PeerUp(node) => handle_peer_up(ws_state, &node),
+    BusNotice::PeerDown(node) => handle_peer_down(ws_state, &node).await,
+    BusNotice::Event { from, event } => handle_event(ws_state, from, event).await,
+  }
+}
+
+/// Bring a replica that just joined up to date with our accounts,
+/// users and rooms.
+fn handle_peer_up(ws_state: &WebSocketState, node: &NodeId) {
+  let Some(cluster) = ws_state.cluster() else {
+    return;
+  };
+  for change in ws_state.user_store.replicas() {
+    cluster
+      .bus()
+      .send(node, ClusterEvent::Account(Box::new(change)));
+  }
+  let users: Vec<UserId> = ws_state
+    .connections
+    .iter()
+    .map(|entry| entry.key().clone())
+    .collect();
+  for user_id in users {
+    if let Some(info) = ws_state.user_store.get_user(&user_id) {
+      cluster.bus().send(node, ClusterEvent::UserOnline(info));
+    }
+  }
+  for replica in ws_state.room_state.replicas() {
+    cluster
+      .bus()
+      .send(node, ClusterEvent::Room(Box::new(replica)));
+  }
+  info!(peer = %node, "Cluster peer synchronised");
+}
+
+/// Treat every user of a lost replica as disconnected.
+async fn handle_peer_down(ws_state: &Arc<WebSocketState>, node: &NodeId) {
+  let Some(cluster) = ws_state.cluster() else {
+    return;
+  };
+  let users = cluster.remove_node(node);
+  for user_id in &users {
+    leave_rooms(ws_state, user_id).await;
+    if !ws_state.connections.contains_key(user_id) {
+      notify_presence(ws_state, user_id, UserStatus::Offline).await;
+    }
+  }
+  ws_state.flush_room_changes();
+  warn!(peer = %node, users = users.len(), "Cluster peer lost");
+}
+
+/// Apply one event published by another replica.
+async fn handle_event(ws_state: &Arc<WebSocketState>, from: NodeId, event: ClusterEvent) {
+  let Some(cluster) = ws_state.cluster() else {
+    return;
+  };
+  match event {
+    ClusterEvent::Deliver { to, frame } => {
+      if let Some(sender) = ws_state.local_sender(&to) {
+        mirror_discovery(ws_state, &frame);
+        let _ = sender.send(frame).await;
+      } else {
+        debug!(user_id = %to, from = %from, "Dropped cluster frame for absent user");
+      }
+    }
+    ClusterEvent::Broadcast { frame } => ws_state.broadcast_local(frame).await,
+    ClusterEvent::UserOnline(info) => {
+      let user_id = info.user_id.clone();
+      // A user's devices are served by a single replica, since frames
+      // for the user are routed to one node: the user connected to
+      // another replica, so the devices here have to move there.
+      if ws_state.connections.contains_key(&user_id) {
+        info!(user_id = %user_id, node = %from, "User logged in on another replica");
+        ws_state.end_session(&user_id).await;
+      }
+      if cluster.add_remote_user(from, info) {
+        notify_presence(ws_state, &user_id, UserStatus::Online).await;
+      }
+    }
+    ClusterEvent::UserOffline(user_id) => {
+      if cluster.remove_remote_user(&from, &user_id) && !ws_state.connections.contains_key(&user_id)
+      {
+        notify_presence(ws_state, &user_id, UserStatus::Offline).await;
+      }
+    }
+    ClusterEvent::Room(replica) => {
+      let room_id = replica.room_id.clone();
+      if !ws_state.room_state.apply_replica(*replica) {
+        debug!(room_id = %room_id, from = %from, "Ignored stale room replica");
+      }
+    }
+    ClusterEvent::Account(change) => apply_account_change(ws_state, *change).await,
+    ClusterEvent::Mailbox(change) => {
+      let Some(mailbox) = ws_state.mailbox() else {
+        return;
+      };
+      let deposit = match &*change {
+        MailboxChange::Deposit { to, item } => Some((to.clone(), item.clone())),
+        _ => None,
+      };
+      mailbox.apply_change(*change);
+      if let Some((to, item)) = deposit {
+        push_deposit(ws_state, &to, item).await;
+      }
+    }
+  }
+}
+
+/// Apply an account change made on another replica, then close the
+/// sockets here whose sessions it ended.
+///
+/// A single revoked session was only notified on the replica that
+/// revoked it, so its devices here get the notice too. For the other
+/// changes the origin already sent every device of the user the notice
+/// through the bus and only the sockets are left to close.
+async fn apply_account_change(ws_state: &WebSocketState, change: AccountChange) {
+  let ended = match &change {
+    AccountChange::SessionEnded {
+      user_id,
+      session_id,
+    } => Some((user_id.clone(), Some(session_id.clone()))),
+    AccountChange::LoggedOut(user_id)
+    | AccountChange::Password { user_id, .. }
+    | AccountChange::Deleted(user_id) => Some((user_id.clone(), None)),
+    _ => None,
+  };
+  if !ws_state.user_store.apply_change(change) {
+    return;
+  }
+  match ended {
+    Some((user_id, Some(session_id))) => {
+      ws_state.end_auth_session(&user_id, &session_id).await;
+    }
+    Some((user_id, None)) => {
+      ws_state.disconnect_user(&user_id);
+    }
+    None => {}
+  }
+}
+
+/// Record the invitations and peer relationships carried by a frame
+/// relayed from another replica, so the recipient can answer them
+/// here.
+fn mirror_discovery(ws_state: &WebSocketState, frame: &[u8]) {
+  let Ok(msg) = decode_frame(frame).and_then(|frame| decode_signaling_message(&frame)) else {
+    return;
+  };
+  let discovery = &ws_state.discovery_state;
+  match msg {
+    SignalingMessage::ConnectionInvite(invite) => {
+      if let Err(e) = discovery.send_invitation(&invite) {
+        debug!(from = %invite.from, to = %invite.to, error = ?e, "Relayed invitation not recorded");
+      }
+    }
+    SignalingMessage::InviteAccepted(accepted) => {
+      discovery.accept_invitation(&accepted.to, &accepted.from);
+      discovery.add_active_peer(&accepted.from, &accepted.to);
+    }
+    SignalingMessage::InviteDeclined(declined) => {
+      discovery.decline_invitation(&declined.to, &declined.from);
+    }
+    SignalingMessage::PeerEstablished(established) => {
+      discovery.add_active_peer(&established.from, &established.to);
+    }
+    SignalingMessage::PeerClosed(closed) => {
+      discovery.remove_active_peer(&closed.from, &closed.to);
+    }
+    _ => {}
+  }
+}
+
+/// Send a user's presence change and the refreshed user list to the
+/// users connected to this replica.
+async fn notify_presence(ws_state: &WebSocketState, user_id: &UserId, status: UserStatus) {
+  let status_change = SignalingMessage::UserStatusChange(UserStatusChange {
+    user_id: user_id.clone(),
+    status,
+    signature: None,
+  });
+  let user_list = SignalingMessage::UserListUpdate(UserListUpdate {
+    users: ws_state.online_users(),
+  });
+  for msg in [status_change, user_list] {
+    if let Ok(encoded) = encode_signaling_message(&msg) {
+      for entry in ws_state.connections.iter() {
+        if entry.key() != user_id {
+          let _ = entry.value().send(encoded.clone()).await;
+        }
+      }
+    }
+  }
+}
+
+/// Remove a user of a lost replica from their room and tell the room
+/// members connected to this replica.
+async fn leave_rooms(ws_state: &WebSocketState, user_id: &UserId) {
+  for result in ws_state.room_state.remove_user_from_all_rooms(user_id) {
+    if result.room_destroyed {
+      let rooms = ws_state.room_state.get_all_rooms();
+      if let Ok(encoded) =
+        encode_signaling_message(&SignalingMessage::RoomListUpdate(RoomListUpdate { rooms }))
+      {
+        ws_state.broadcast_local(encoded).await;
+      }
+      continue;
+    }
+
+    let mut messages = vec![SignalingMessage::RoomMemberUpdate(RoomMemberUpdate {
+      room_id: result.room_id.clone(),
+      members: result.members.clone(),
+    })];
+    if let Some(new_owner) = result.ownership_transfer {
+      messages.push(SignalingMessage::OwnerChanged(OwnerChanged {
+        room_id: result.room_id.clone(),
+        old_owner: user_id.clone(),
+        new_owner,
+      }));
+    }
+    for msg in &messages {
+      if let Ok(encoded) = encode_signaling_message(msg) {
+        for member in &result.members {
+          if let Some(sender) = ws_state.local_sender(&member.user_id) {
+            let _ = sender.send(encoded.clone()).await;
+          }
+        }
+      }
+    }
+  }
+}
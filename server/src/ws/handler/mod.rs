@@ -8,13 +8,13 @@ use axum::body::Bytes;
 use axum::extract::ws::Message;
 use futures::Sink;
 use futures::SinkExt;
-use message::UserId;
 use message::frame::decode_frame;
 use message::signaling::{
-  AuthFailure, AuthSuccess, Pong, RoomListUpdate, RoomMemberUpdate, SessionInvalidated,
+  AuthFailure, AuthSuccess, DeviceInfo, DeviceLinkSignal, Pong, RoomListUpdate, RoomMemberUpdate,
   SignalingMessage, UserListUpdate, UserStatusChange,
 };
 use message::types::UserStatus;
+use message::{DeviceId, UserId};
 use tracing::{debug, info, warn};
 
 use super::{ConnectionState, WebSocketState, encode_signaling_message};
@@ -93,10 +93,16 @@ where
   S: Sink<Message> + Unpin,
   S::Error: Display,
 {
+  ws_state
+    .metrics
+    .signaling_message_bytes
+    .observe(data.len() as f64);
+
   // Decode frame
   let frame = match decode_frame(&data) {
     Ok(frame) => frame,
     Err(e) => {
+      ws_state.metrics.signaling_decode_errors.inc();
       warn!(
         user_id = ?conn_state.user_id,
         error = %e,
@@ -119,6 +125,7 @@ where
   let signaling_msg = match super::decode_signaling_message(&frame) {
     Ok(msg) => msg,
     Err(e) => {
+      ws_state.metrics.signaling_decode_errors.inc();
       warn!(
         user_id = ?conn_state.user_id,
         error = %e,
@@ -141,8 +148,18 @@ where
     }
   };
 
+  ws_state
+    .metrics
+    .record_signaling_message(signaling_msg.discriminator());
+
   // Handle message based on type
-  handle_signaling_message(socket_tx, ws_state, conn_state, signaling_msg).await
+  let started = Instant::now();
+  let keep_open = handle_signaling_message(socket_tx, ws_state, conn_state, signaling_msg).await;
+  ws_state
+    .metrics
+    .signaling_handle_seconds
+    .observe_duration(started.elapsed());
+  keep_open
 }
 
 /// Handle decoded signaling message.
@@ -210,43 +227,36 @@ where
       }
 
       // Authenticate with token
-      match ws_state.user_store.authenticate_with_token(&auth.token) {
-        Ok(auth_success) => {
+      match ws_state.user_store.authenticate_session(&auth.token) {
+        Ok((auth_success, session_id)) => {
           let user_id = auth_success.user_id.clone();
+          let device = device_info(auth.device);
 
-          // Check if this user is already connected (another session)
-          if let Some(existing_sender) = ws_state.get_sender(&user_id) {
-            // Send SessionInvalidated to the old connection
-            let invalidated_msg = SignalingMessage::SessionInvalidated(SessionInvalidated);
-            if let Ok(encoded) = encode_signaling_message(&invalidated_msg) {
-              let _ = existing_sender.send(encoded).await;
-            }
-            // Remove old connection
-            ws_state.remove_connection(&user_id);
+          // Other devices of the user stay connected; only a stale
+          // connection of this very device is replaced.
+          if ws_state.replace_device(&user_id, &device.device_id).await {
             info!(
               user_id = %user_id,
-              "Kicked existing session for single-device login"
+              device_id = %device.device_id,
+              "Replaced previous connection of the same device"
             );
           }
 
           // Update connection state
           conn_state.user_id = Some(user_id.clone());
+          conn_state.device_id = Some(device.device_id);
+          conn_state.session_id = Some(session_id);
           conn_state.last_heartbeat = Instant::now();
 
-          // Store sender in connections map for message routing
-          if let Some(sender) = &conn_state.sender {
-            ws_state.add_connection(user_id.clone(), sender.clone());
-          }
+          // Register the device for message routing (stores metadata)
+          let device_id = device.device_id;
+          let first_device = ws_state.add_device(conn_state, device);
 
           // Update user status to online
           ws_state
             .user_store
             .update_status(&user_id, UserStatus::Online);
-
-          // Store metadata
-          ws_state
-            .metadata
-            .insert(user_id.clone(), conn_state.clone());
+          ws_state.announce_online(&user_id);
 
           // Send AuthSuccess response
           let success_msg = SignalingMessage::AuthSuccess(AuthSuccess {
@@ -257,13 +267,19 @@ where
             // (`STUN_TURN_SERVERS`) to the client so intranet
             // STUN/TURN endpoints can be set per environment without
             // a frontend rebuild. Empty → client keeps its default.
-            ice_servers: ws_state.ice_server_specs(),
+            // The embedded TURN relay, when enabled, is prepended
+            // with credentials minted for this user.
+            ice_servers: ws_state.ice_server_specs_for(&user_id),
             // G26 — forward the persisted avatar so the client
             // restores the user's choice on reload instead of
             // defaulting to the identicon. `auth_success.avatar_url`
             // is `None` for fresh sessions where the user has not
             // uploaded one yet.
             avatar_url: auth_success.avatar_url,
+            // Clients only seal messages for offline peers when the
+            // server will hold them.
+            mailbox_enabled: ws_state.mailbox().is_some(),
+            device_id,
           });
           if let Ok(encoded) = encode_signaling_message(&success_msg)
             && socket_tx
@@ -275,14 +291,22 @@ where
             return false;
           }
 
-          // Broadcast UserStatusChange to all other users
+          // Every device of the user learns about the new one. A user
+          // on a single device gets no list.
+          if !first_device {
+            ws_state.send_device_list(&user_id).await;
+          }
+
+          // Broadcast UserStatusChange to all other users, unless the
+          // user was online on another device already
           let status_change = UserStatusChange {
             user_id: user_id.clone(),
             status: UserStatus::Online,
             signature: None,
           };
-          if let Ok(encoded) =
-            encode_signaling_message(&SignalingMessage::UserStatusChange(status_change))
+          if first_device
+            && let Ok(encoded) =
+              encode_signaling_message(&SignalingMessage::UserStatusChange(status_change))
           {
             // Broadcast to all connected users except self
             for entry in ws_state.connections.iter() {
@@ -295,7 +319,7 @@ where
           }
 
           // Send current online user list to the newly authenticated user
-          let online_users = ws_state.user_store.get_online_users();
+          let online_users = ws_state.online_users();
           let user_list_msg = SignalingMessage::UserListUpdate(UserListUpdate {
             users: online_users.clone(),
           });
@@ -349,11 +373,13 @@ where
 
           info!(
             user_id = %user_id,
+            device_id = %device_id,
             remote_addr = %mask_ip(&conn_state.remote_addr),
             "User authenticated successfully"
           );
         }
         Err(auth_failure) => {
+          ws_state.metrics.ws_auth_failures.inc();
           warn!(
             remote_addr = %mask_ip(&conn_state.remote_addr),
             reason = %auth_failure.reason,
@@ -371,32 +397,19 @@ where
       if let Some(ref user_id) = conn_state.user_id {
         info!(
           user_id = %user_id,
+          device_id = ?conn_state.device_id,
           "User logout requested"
         );
 
-        // Logout from user store
-        ws_state.user_store.logout(user_id);
-
-        // Broadcast UserStatusChange (Offline) to all other users
-        let status_change = UserStatusChange {
-          user_id: user_id.clone(),
-          status: UserStatus::Offline,
-          signature: None,
-        };
-        if let Ok(encoded) =
-          encode_signaling_message(&SignalingMessage::UserStatusChange(status_change))
-        {
-          for entry in ws_state.connections.iter() {
-            let other_user_id = entry.key();
-            if other_user_id != user_id {
-              let sender = entry.value();
-              let _ = sender.send(encoded.clone()).await;
-            }
+        // End this device's session only; the socket closes below and
+        // the disconnect cleanup updates presence once the user's last
+        // device is gone.
+        match &conn_state.session_id {
+          Some(session_id) => {
+            ws_state.user_store.logout_session(user_id, session_id);
           }
+          None => ws_state.user_store.logout(user_id),
         }
-
-        // Remove connection
-        ws_state.remove_connection(user_id);
       }
       return false; // Close connection after logout
     }
@@ -509,14 +522,17 @@ where
         SignalingMessage::RoomInviteResponse(response) => {
           super::room::handle_room_invite_response(socket_tx, ws_state, &user_id, response).await;
         }
-        // SDP/ICE signaling messages
-        SignalingMessage::SdpOffer(sdp_offer) => {
+        // SDP/ICE signaling messages, stamped with the sending device
+        SignalingMessage::SdpOffer(mut sdp_offer) => {
+          sdp_offer.from_device = conn_state.device_id;
           super::webrtc::handle_sdp_offer(socket_tx, ws_state, &user_id, sdp_offer).await;
         }
-        SignalingMessage::SdpAnswer(sdp_answer) => {
+        SignalingMessage::SdpAnswer(mut sdp_answer) => {
+          sdp_answer.from_device = conn_state.device_id;
           super::webrtc::handle_sdp_answer(socket_tx, ws_state, &user_id, sdp_answer).await;
         }
-        SignalingMessage::IceCandidate(ice_candidate) => {
+        SignalingMessage::IceCandidate(mut ice_candidate) => {
+          ice_candidate.from_device = conn_state.device_id;
           super::webrtc::handle_ice_candidate(socket_tx, ws_state, &user_id, ice_candidate).await;
         }
         SignalingMessage::PeerEstablished(peer_established) => {
@@ -526,6 +542,87 @@ where
         SignalingMessage::PeerClosed(peer_closed) => {
           super::webrtc::handle_peer_closed(socket_tx, ws_state, &user_id, peer_closed).await;
         }
+        // SFU media session messages
+        SignalingMessage::SfuOffer(sfu_offer) => {
+          super::sfu::handle_sfu_offer(
+            socket_tx,
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            sfu_offer,
+          )
+          .await;
+        }
+        SignalingMessage::SfuAnswer(sfu_answer) => {
+          super::sfu::handle_sfu_answer(
+            socket_tx,
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            sfu_answer,
+          )
+          .await;
+        }
+        SignalingMessage::SfuIceCandidate(sfu_candidate) => {
+          super::sfu::handle_sfu_ice_candidate(
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            sfu_candidate,
+          )
+          .await;
+        }
+        SignalingMessage::SfuLeave(sfu_leave) => {
+          super::sfu::handle_sfu_leave(
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            sfu_leave,
+          )
+          .await;
+        }
+        // Offline mailbox messages
+        SignalingMessage::PublishMailboxKey(publish) => {
+          super::mailbox::handle_publish_mailbox_key(
+            socket_tx,
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            publish,
+          )
+          .await;
+        }
+        SignalingMessage::MailboxKeyRequest(request) => {
+          super::mailbox::handle_mailbox_key_request(socket_tx, ws_state, request).await;
+        }
+        SignalingMessage::MailboxDeposit(deposit) => {
+          super::mailbox::handle_mailbox_deposit(
+            socket_tx,
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            deposit,
+          )
+          .await;
+        }
+        SignalingMessage::MailboxFetch(_) => {
+          super::mailbox::handle_mailbox_fetch(
+            socket_tx,
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+          )
+          .await;
+        }
+        SignalingMessage::MailboxAck(ack) => {
+          super::mailbox::handle_mailbox_ack(
+            ws_state,
+            &user_id,
+            conn_state.device_id.as_ref(),
+            ack,
+          )
+          .await;
+        }
         // Call signaling messages
         SignalingMessage::CallInvite(call_invite) => {
           super::call::handle_call_invite(socket_tx, ws_state, &user_id, call_invite).await;
@@ -539,6 +636,10 @@ where
         SignalingMessage::CallEnd(call_end) => {
           super::call::handle_call_end(socket_tx, ws_state, &user_id, call_end).await;
         }
+        // Device linking
+        SignalingMessage::DeviceLinkSignal(signal) => {
+          handle_device_link_signal(ws_state, &user_id, conn_state, signal).await;
+        }
         // Theater signaling messages
         SignalingMessage::TheaterMuteAll(mute_all) => {
           super::theater::handle_theater_mute_all(socket_tx, ws_state, &user_id, mute_all).await;
@@ -560,6 +661,94 @@ where
   true
 }
 
+/// Fill in the device a connection authenticates as.
+///
+/// Clients that do not announce a device (older builds) get a fresh
+/// ID per connection. Over-long names are truncated.
+fn device_info(device: Option<DeviceInfo>) -> DeviceInfo {
+  let mut device = device.unwrap_or_else(|| DeviceInfo {
+    device_id: DeviceId::new(),
+    name: String::new(),
+  });
+  if let Some((index, _)) = device.name.char_indices().nth(DeviceInfo::MAX_NAME_LENGTH) {
+    device.name.truncate(index);
+  }
+  device
+}
+
+/// Relay a device-linking signal to another device of the same user.
+///
+/// The sender's device is stamped by the server. Signals for a device
+/// that is not connected, or for the sending device itself, are
+/// dropped: linking never crosses accounts.
+async fn handle_device_link_signal(
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  conn_state: &ConnectionState,
+  mut signal: DeviceLinkSignal,
+) {
+  let Some(from_device) = conn_state.device_id else {
+    return;
+  };
+  signal.from_device = from_device;
+  let target = signal.to_device;
+  if target == from_device
+    || !ws_state
+      .devices(user_id)
+      .iter()
+      .any(|d| d.device_id == target)
+  {
+    debug!(
+      user_id = %user_id,
+      to_device = %target,
+      "Dropped device link signal for unknown device"
+    );
+    return;
+  }
+  if let Ok(encoded) = encode_signaling_message(&SignalingMessage::DeviceLinkSignal(signal))
+    && let Some(sender) = ws_state.get_device_sender(user_id, Some(&target))
+  {
+    let _ = sender.send(encoded).await;
+  }
+}
+
+/// Cleanup when one device connection closes.
+///
+/// Unregisters the device. When it was the user's last device the full
+/// [`handle_user_disconnect`] cleanup runs; otherwise the remaining
+/// devices get a fresh device list. A connection that was already
+/// replaced by a newer one of the same device is left alone.
+pub async fn handle_device_disconnect(
+  ws_state: &Arc<WebSocketState>,
+  conn_state: &ConnectionState,
+) {
+  let Some(user_id) = &conn_state.user_id else {
+    return;
+  };
+  let Some(device_id) = &conn_state.device_id else {
+    handle_user_disconnect(ws_state, user_id).await;
+    return;
+  };
+  match ws_state.remove_device(user_id, device_id, conn_state.sender.as_ref()) {
+    None => {
+      debug!(
+        user_id = %user_id,
+        device_id = %device_id,
+        "Replaced connection closed"
+      );
+    }
+    Some(0) => handle_user_disconnect(ws_state, user_id).await,
+    Some(_) => {
+      // The user stays online, but a media session signalling to this
+      // device has nobody left to talk to.
+      if let (Some(sfu), Some(sender)) = (ws_state.sfu(), &conn_state.sender) {
+        sfu.leave_device(user_id, sender).await;
+      }
+      ws_state.send_device_list(user_id).await;
+    }
+  }
+}
+
 /// Comprehensive cleanup when a user disconnects.
 ///
 /// Handles:
@@ -594,7 +783,11 @@ pub async fn handle_user_disconnect(ws_state: &Arc<WebSocketState>, user_id: &Us
     );
   }
 
-  // 2. Remove user from rooms (handles ownership transfer and empty room destruction)
+  // 2. Close any SFU media sessions, then remove user from rooms
+  //    (handles ownership transfer and empty room destruction)
+  if let Some(sfu) = ws_state.sfu() {
+    sfu.leave_all(user_id).await;
+  }
   let leave_results = ws_state.room_state.remove_user_from_all_rooms(user_id);
   for result in &leave_results {
     if result.room_destroyed {
@@ -665,18 +858,22 @@ pub async fn handle_user_disconnect(ws_state: &Arc<WebSocketState>, user_id: &Us
     .user_store
     .update_status(user_id, UserStatus::Offline);
 
-  // Note: The user being disconnected is still in `connections` at this point
-  // (removed in step 6 below). The iteration skips `user_id` explicitly so
-  // the Offline status is not echoed back to the disconnecting socket.
+  // Note: The user being disconnected may still be in `connections` at this
+  // point (removed in step 6 below; a last device has already left it). The
+  // iteration skips `user_id` explicitly so the Offline status is not echoed
+  // back to the disconnecting socket.
   // DashMap iteration provides a consistent snapshot, so concurrent
   // connection additions/removals are handled safely.
 
+  // A user who moved to another replica is still online there.
   let status_change = UserStatusChange {
     user_id: user_id.clone(),
     status: UserStatus::Offline,
     signature: None,
   };
-  if let Ok(encoded) = encode_signaling_message(&SignalingMessage::UserStatusChange(status_change))
+  if !ws_state.is_connected_elsewhere(user_id)
+    && let Ok(encoded) =
+      encode_signaling_message(&SignalingMessage::UserStatusChange(status_change))
   {
     for entry in ws_state.connections.iter() {
       let other_user_id = entry.key();
@@ -687,8 +884,10 @@ pub async fn handle_user_disconnect(ws_state: &Arc<WebSocketState>, user_id: &Us
     }
   }
 
+  ws_state.announce_offline(user_id);
+
   // Broadcast updated user list
-  let users = ws_state.user_store.get_online_users();
+  let users = ws_state.online_users();
   let user_list = SignalingMessage::UserListUpdate(UserListUpdate { users });
   if let Ok(encoded) = encode_signaling_message(&user_list) {
     for entry in ws_state.connections.iter() {
@@ -0,0 +1,342 @@
+//! Tests for multi-device sessions.
+//!
+//! Tests:
+//! - Several devices of one user stay connected and share fan-out
+//! - Presence only changes with the first and last device
+//! - A reconnecting device replaces its stale connection
+//! - Device-link signals are relayed between the user's own devices
+//! - Unaddressed SDP offers reach every device, addressed ones one device
+
+use std::time::Duration;
+
+use message::frame::decode_frame;
+use message::signaling::{AuthSuccess, DeviceLinkPayload, DeviceLinkSignal, UserListUpdate};
+
+use super::*;
+use crate::ws::decode_signaling_message;
+use crate::ws::handler::handle_device_disconnect;
+
+/// One authenticated device connection.
+struct Device {
+  conn_state: ConnectionState,
+  sink: MockSink,
+  sink_rx: mpsc::UnboundedReceiver<Message>,
+  outgoing_rx: mpsc::Receiver<Vec<u8>>,
+  device_id: DeviceId,
+}
+
+impl Device {
+  /// Connect and authenticate a device with `token`.
+  async fn connect(ws_state: &Arc<WebSocketState>, token: &str, name: &str) -> Self {
+    Self::connect_as(ws_state, token, DeviceId::new(), name).await
+  }
+
+  /// Connect and authenticate as a specific device.
+  async fn connect_as(
+    ws_state: &Arc<WebSocketState>,
+    token: &str,
+    device_id: DeviceId,
+    name: &str,
+  ) -> Self {
+    let (sink, sink_rx) = MockSink::new();
+    let (tx, outgoing_rx) = mpsc::channel(100);
+    let mut conn_state = ConnectionState::new("127.0.0.1:12345".to_string());
+    conn_state.sender = Some(tx);
+    let mut device = Self {
+      conn_state,
+      sink,
+      sink_rx,
+      outgoing_rx,
+      device_id,
+    };
+    let auth = SignalingMessage::TokenAuth(TokenAuth {
+      token: token.to_string(),
+      device: Some(DeviceInfo {
+        device_id,
+        name: name.to_string(),
+      }),
+    });
+    device.send(ws_state, &auth).await;
+    device
+  }
+
+  /// Send a message from this device.
+  async fn send(&mut self, ws_state: &Arc<WebSocketState>, msg: &SignalingMessage) {
+    let encoded = TestContext::create_encoded_message(msg);
+    handle_binary_message(&mut self.sink, ws_state, &mut self.conn_state, encoded).await;
+  }
+
+  /// Messages written directly to this device's socket so far.
+  fn direct_messages(&mut self) -> Vec<SignalingMessage> {
+    let mut messages = Vec::new();
+    while let Ok(Message::Binary(data)) = self.sink_rx.try_recv() {
+      messages.push(decode(&data));
+    }
+    messages
+  }
+
+  /// Next message routed to this device, waiting for fan-out.
+  async fn routed_message(&mut self) -> Option<SignalingMessage> {
+    tokio::time::timeout(Duration::from_millis(200), self.outgoing_rx.recv())
+      .await
+      .ok()
+      .flatten()
+      .map(|data| decode(&data))
+  }
+}
+
+fn decode(data: &[u8]) -> SignalingMessage {
+  let frame = decode_frame(data).expect("Failed to decode frame");
+  decode_signaling_message(&frame).expect("Failed to decode message")
+}
+
+fn auth_success(messages: &[SignalingMessage]) -> AuthSuccess {
+  messages
+    .iter()
+    .find_map(|msg| match msg {
+      SignalingMessage::AuthSuccess(success) => Some(success.clone()),
+      _ => None,
+    })
+    .expect("Expected AuthSuccess")
+}
+
+#[tokio::test]
+async fn test_second_device_keeps_first_connected() {
+  let ctx = TestContext::new();
+  let token = create_valid_token(&ctx.ws_state, "multi");
+  let mut laptop = Device::connect(&ctx.ws_state, &token, "Laptop").await;
+  let mut phone = Device::connect(&ctx.ws_state, &token, "Phone").await;
+
+  assert_eq!(
+    auth_success(&laptop.direct_messages()).device_id,
+    laptop.device_id
+  );
+  assert_eq!(
+    auth_success(&phone.direct_messages()).device_id,
+    phone.device_id
+  );
+
+  let user_id = phone.conn_state.user_id.clone().unwrap();
+  let devices = ctx.ws_state.devices(&user_id);
+  assert_eq!(devices.len(), 2);
+  assert_eq!(devices[0].name, "Laptop");
+  assert_eq!(devices[1].name, "Phone");
+  assert_eq!(ctx.ws_state.connection_metadata().len(), 2);
+
+  // The first device was not kicked and learns about the second one.
+  let mut saw_update = false;
+  while let Some(msg) = laptop.routed_message().await {
+    assert!(!matches!(msg, SignalingMessage::SessionInvalidated(_)));
+    if let SignalingMessage::DeviceListUpdate(update) = msg {
+      saw_update = update.devices.len() == 2;
+    }
+  }
+  assert!(saw_update, "Expected a DeviceListUpdate with both devices");
+}
+
+#[tokio::test]
+async fn test_messages_fan_out_to_every_device() {
+  let ctx = TestContext::new();
+  let token = create_valid_token(&ctx.ws_state, "fanout");
+  let mut laptop = Device::connect(&ctx.ws_state, &token, "Laptop").await;
+  let mut phone = Device::connect(&ctx.ws_state, &token, "Phone").await;
+  let user_id = laptop.conn_state.user_id.clone().unwrap();
+  while laptop.routed_message().await.is_some() {}
+  while phone.routed_message().await.is_some() {}
+
+  let update = SignalingMessage::UserListUpdate(UserListUpdate { users: vec![] });
+  assert!(
+    ctx
+      .ws_state
+      .send_to(&user_id, TestContext::create_encoded_message(&update))
+      .await
+  );
+  assert_eq!(laptop.routed_message().await, Some(update.clone()));
+  assert_eq!(phone.routed_message().await, Some(update));
+}
+
+#[tokio::test]
+async fn test_presence_follows_first_and_last_device() {
+  let ctx = TestContext::new();
+  let token = create_valid_token(&ctx.ws_state, "presence");
+  let laptop = Device::connect(&ctx.ws_state, &token, "Laptop").await;
+  let mut phone = Device::connect(&ctx.ws_state, &token, "Phone").await;
+  let user_id = laptop.conn_state.user_id.clone().unwrap();
+  while phone.routed_message().await.is_some() {}
+
+  // Closing one device keeps the user online.
+  handle_device_disconnect(&ctx.ws_state, &laptop.conn_state).await;
+  assert!(ctx.ws_state.is_connected(&user_id));
+  assert_eq!(
+    ctx.ws_state.user_store.get_user(&user_id).unwrap().status,
+    UserStatus::Online
+  );
+  match phone.routed_message().await {
+    Some(SignalingMessage::DeviceListUpdate(update)) => {
+      assert_eq!(update.devices.len(), 1);
+      assert_eq!(update.devices[0].device_id, phone.device_id);
+    }
+    other => panic!("Expected DeviceListUpdate, got {other:?}"),
+  }
+
+  // Closing the last one takes the user offline.
+  handle_device_disconnect(&ctx.ws_state, &phone.conn_state).await;
+  assert!(!ctx.ws_state.is_connected(&user_id));
+  assert_eq!(
+    ctx.ws_state.user_store.get_user(&user_id).unwrap().status,
+    UserStatus::Offline
+  );
+  assert!(ctx.ws_state.connection_metadata().is_empty());
+}
+
+#[tokio::test]
+async fn test_reconnecting_device_replaces_stale_connection() {
+  let ctx = TestContext::new();
+  let token = create_valid_token(&ctx.ws_state, "reload");
+  let device_id = DeviceId::new();
+  let mut stale = Device::connect_as(&ctx.ws_state, &token, device_id, "Laptop").await;
+  let fresh = Device::connect_as(&ctx.ws_state, &token, device_id, "Laptop").await;
+  let user_id = fresh.conn_state.user_id.clone().unwrap();
+
+  let mut invalidated = false;
+  while let Some(msg) = stale.routed_message().await {
+    invalidated |= matches!(msg, SignalingMessage::SessionInvalidated(_));
+  }
+  assert!(invalidated, "Stale connection should be invalidated");
+  assert!(stale.conn_state.close_token.is_cancelled());
+  assert!(!fresh.conn_state.close_token.is_cancelled());
+
+  // The stale connection's late cleanup leaves the fresh one alone.
+  handle_device_disconnect(&ctx.ws_state, &stale.conn_state).await;
+  assert_eq!(ctx.ws_state.devices(&user_id).len(), 1);
+  assert!(ctx.ws_state.is_connected(&user_id));
+}
+
+#[tokio::test]
+async fn test_device_link_signal_relayed_to_target_device_only() {
+  let ctx = TestContext::new();
+  let token = create_valid_token(&ctx.ws_state, "linker");
+  let mut laptop = Device::connect(&ctx.ws_state, &token, "Laptop").await;
+  let mut phone = Device::connect(&ctx.ws_state, &token, "Phone").await;
+  while laptop.routed_message().await.is_some() {}
+  while phone.routed_message().await.is_some() {}
+
+  // A forged sender device is overwritten by the server.
+  let hello = SignalingMessage::DeviceLinkSignal(DeviceLinkSignal {
+    from_device: DeviceId::new(),
+    to_device: phone.device_id,
+    payload: DeviceLinkPayload::Hello {
+      public_key: vec![0x04; 65],
+    },
+  });
+  laptop.send(&ctx.ws_state, &hello).await;
+  match phone.routed_message().await {
+    Some(SignalingMessage::DeviceLinkSignal(signal)) => {
+      assert_eq!(signal.from_device, laptop.device_id);
+      assert_eq!(signal.to_device, phone.device_id);
+    }
+    other => panic!("Expected DeviceLinkSignal, got {other:?}"),
+  }
+  assert!(laptop.routed_message().await.is_none());
+}
+
+#[tokio::test]
+async fn test_device_link_signal_never_leaves_the_account() {
+  let ctx = TestContext::new();
+  let alice_token = create_valid_token(&ctx.ws_state, "alice");
+  let bob_token = create_valid_token(&ctx.ws_state, "bob");
+  let mut alice = Device::connect(&ctx.ws_state, &alice_token, "Laptop").await;
+  let mut bob = Device::connect(&ctx.ws_state, &bob_token, "Laptop").await;
+  while bob.routed_message().await.is_some() {}
+
+  let signal = SignalingMessage::DeviceLinkSignal(DeviceLinkSignal {
+    from_device: alice.device_id,
+    to_device: bob.device_id,
+    payload: DeviceLinkPayload::Cancel,
+  });
+  alice.send(&ctx.ws_state, &signal).await;
+  assert!(bob.routed_message().await.is_none());
+}
+
+#[tokio::test]
+async fn test_logout_ends_only_this_device_session() {
+  let ctx = TestContext::new();
+  let (_, laptop_token) = ctx
+    .ws_state
+    .user_store
+    .register("logout", "test_password")
+    .unwrap();
+  let (_, phone_token) = ctx
+    .ws_state
+    .user_store
+    .login("logout", "test_password")
+    .unwrap();
+  let mut laptop = Device::connect(&ctx.ws_state, &laptop_token, "Laptop").await;
+  let _phone = Device::connect(&ctx.ws_state, &phone_token, "Phone").await;
+
+  laptop
+    .send(
+      &ctx.ws_state,
+      &SignalingMessage::UserLogout(message::signaling::UserLogout),
+    )
+    .await;
+  assert!(
+    ctx
+      .ws_state
+      .user_store
+      .authenticate_with_token(&laptop_token)
+      .is_err()
+  );
+  assert!(
+    ctx
+      .ws_state
+      .user_store
+      .authenticate_with_token(&phone_token)
+      .is_ok()
+  );
+}
+
+#[tokio::test]
+async fn test_unaddressed_offer_reaches_every_device() {
+  let ctx = TestContext::new();
+  let alice_token = create_valid_token(&ctx.ws_state, "caller");
+  let bob_token = create_valid_token(&ctx.ws_state, "callee");
+  let mut alice = Device::connect(&ctx.ws_state, &alice_token, "Laptop").await;
+  let mut laptop = Device::connect(&ctx.ws_state, &bob_token, "Laptop").await;
+  let mut phone = Device::connect(&ctx.ws_state, &bob_token, "Phone").await;
+  while laptop.routed_message().await.is_some() {}
+  while phone.routed_message().await.is_some() {}
+
+  let caller = alice.conn_state.user_id.clone().unwrap();
+  let callee = laptop.conn_state.user_id.clone().unwrap();
+  let offer = |to_device| {
+    SignalingMessage::SdpOffer(message::signaling::SdpOffer {
+      from: caller.clone(),
+      to: callee.clone(),
+      sdp: "v=0".to_string(),
+      from_device: None,
+      to_device,
+    })
+  };
+
+  // Every device sees an unaddressed offer; the primary one answers it.
+  let unaddressed = offer(None);
+  alice.send(&ctx.ws_state, &unaddressed).await;
+  assert!(matches!(
+    laptop.routed_message().await,
+    Some(SignalingMessage::SdpOffer(_))
+  ));
+  assert!(matches!(
+    phone.routed_message().await,
+    Some(SignalingMessage::SdpOffer(_))
+  ));
+
+  // An addressed offer reaches that device only.
+  let addressed = offer(Some(phone.device_id));
+  alice.send(&ctx.ws_state, &addressed).await;
+  assert!(matches!(
+    phone.routed_message().await,
+    Some(SignalingMessage::SdpOffer(_))
+  ));
+  assert!(laptop.routed_message().await.is_none());
+}
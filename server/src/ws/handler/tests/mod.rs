@@ -3,10 +3,12 @@
 //! This module tests the handler.rs functions:
 //! - handle_incoming_message (public)
 //! - handle_user_disconnect (public)
+//! - handle_device_disconnect (public)
 //! - handle_binary_message (internal)
 //! - handle_signaling_message (internal)
 
 mod binary_message;
+mod devices;
 mod disconnect;
 mod incoming_message;
 mod token_auth;
@@ -114,10 +116,15 @@ impl TestContext {
       .register(user_id_str, "test_password")
       .expect("Failed to register user");
 
-    // Authenticate the user
+    // Authenticate the user on a single device (also stores metadata)
+    let device = DeviceInfo {
+      device_id: DeviceId::new(),
+      name: "Test device".to_string(),
+    };
     ctx.conn_state.user_id = Some(user_id.clone());
     ctx.conn_state.sender = Some(ctx.tx.clone());
-    ctx.ws_state.add_connection(user_id.clone(), ctx.tx.clone());
+    ctx.conn_state.device_id = Some(device.device_id);
+    ctx.ws_state.add_device(&ctx.conn_state, device);
 
     // Update user status to Online
     ctx
@@ -125,12 +132,6 @@ impl TestContext {
       .user_store
       .update_status(&user_id, UserStatus::Online);
 
-    // Store metadata
-    ctx
-      .ws_state
-      .metadata
-      .insert(user_id, ctx.conn_state.clone());
-
     ctx
   }
 
@@ -181,7 +182,10 @@ pub(crate) use crate::ws::handler::handle_binary_message;
 pub(crate) use crate::ws::{ConnectionState, WebSocketState, encode_signaling_message};
 pub(crate) use axum::extract::ws::Message;
 pub(crate) use futures::Sink;
-pub(crate) use message::signaling::{ConnectionInvite, Pong, SignalingMessage, TokenAuth};
+pub(crate) use message::DeviceId;
+pub(crate) use message::signaling::{
+  ConnectionInvite, DeviceInfo, Pong, SignalingMessage, TokenAuth,
+};
 pub(crate) use message::types::UserStatus;
 pub(crate) use std::sync::Arc;
 pub(crate) use std::task::{Context, Poll};
@@ -19,7 +19,10 @@ async fn test_token_auth_success() {
   let token = create_valid_token(&ctx.ws_state, "testuser");
 
   // Create TokenAuth message
-  let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+  let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+    token,
+    device: None,
+  });
   let encoded = TestContext::create_encoded_message(&auth_msg);
 
   // Handle the binary message
@@ -56,7 +59,10 @@ async fn test_token_auth_failure() {
   let token = create_invalid_token();
 
   // Create TokenAuth message with invalid token
-  let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+  let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+    token,
+    device: None,
+  });
   let encoded = TestContext::create_encoded_message(&auth_msg);
 
   // Handle the binary message
@@ -92,7 +98,10 @@ async fn test_reauth_rejected() {
 
   // Try to authenticate again
   let token = create_valid_token(&ctx.ws_state, "testuser2");
-  let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+  let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+    token,
+    device: None,
+  });
   let encoded = TestContext::create_encoded_message(&auth_msg);
 
   // Handle the binary message
@@ -0,0 +1,229 @@
+//! Offline mailbox handling functions.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::ws::Message;
+use futures::{Sink, SinkExt};
+use message::signaling::{
+  MailboxAck, MailboxDeposit, MailboxDrain, MailboxItem, MailboxKeyRequest, MailboxKeyResponse,
+  PublishMailboxKey, SignalingMessage,
+};
+use message::{DeviceId, UserId};
+use tracing::{debug, warn};
+
+use super::{WebSocketState, encode_signaling_message};
+use crate::mailbox::{MailboxError, MailboxState};
+use crate::ws::utils::{send_error_response, send_error_response_with_details};
+
+/// Return the mailbox, or tell the client it is disabled.
+async fn mailbox_or_error<'a, S>(
+  socket_tx: &mut S,
+  ws_state: &'a WebSocketState,
+  details: HashMap<String, String>,
+) -> Option<&'a MailboxState>
+where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let mailbox = ws_state.mailbox();
+  if mailbox.is_none() {
+    send_error_response_with_details(
+      socket_tx,
+      "SIG501",
+      "Offline mailbox is disabled on this server",
+      Some("mailbox_disabled"),
+      details,
+    )
+    .await;
+  }
+  mailbox
+}
+
+/// Send a message straight back on the requesting socket.
+async fn reply<S>(socket_tx: &mut S, msg: &SignalingMessage)
+where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  if let Ok(encoded) = encode_signaling_message(msg) {
+    let _ = socket_tx.send(Message::Binary(Bytes::from(encoded))).await;
+  }
+}
+
+/// Handle PublishMailboxKey message.
+pub async fn handle_publish_mailbox_key<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  publish: PublishMailboxKey,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(mailbox) = mailbox_or_error(socket_tx, ws_state, HashMap::new()).await else {
+    return;
+  };
+  if let Err(e) = mailbox.publish_key(user_id.clone(), device_id.copied(), publish.bundle) {
+    send_error_response(
+      socket_tx,
+      "SIG502",
+      &e.to_string(),
+      Some("mailbox_invalid_key"),
+    )
+    .await;
+  }
+}
+
+/// Handle MailboxKeyRequest message.
+/// Answers with the key of each of the requested user's devices, or
+/// none if they have not published one.
+pub async fn handle_mailbox_key_request<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  request: MailboxKeyRequest,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(mailbox) = mailbox_or_error(socket_tx, ws_state, HashMap::new()).await else {
+    return;
+  };
+  let response = SignalingMessage::MailboxKeyResponse(MailboxKeyResponse {
+    keys: mailbox.keys(&request.user_id),
+    user_id: request.user_id,
+  });
+  reply(socket_tx, &response).await;
+}
+
+/// Handle MailboxDeposit message.
+///
+/// Rejections carry the `message_id` detail so the sender can mark
+/// the right message as failed. A recipient device that happens to be
+/// online (but unreachable peer to peer) is sent the new message right
+/// away.
+pub async fn handle_mailbox_deposit<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  deposit: MailboxDeposit,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let details = HashMap::from([("message_id".to_string(), deposit.message_id.to_string())]);
+  let Some(mailbox) = mailbox_or_error(socket_tx, ws_state, details.clone()).await else {
+    return;
+  };
+
+  if ws_state.user_store.get_user(&deposit.to).is_none() {
+    send_error_response_with_details(
+      socket_tx,
+      "SIG503",
+      "Mailbox recipient does not exist",
+      Some("mailbox_unknown_recipient"),
+      details,
+    )
+    .await;
+    return;
+  }
+
+  let recipient = deposit.to.clone();
+  let item = match mailbox.deposit(user_id.clone(), device_id.copied(), deposit) {
+    Ok(item) => item,
+    Err(e) => {
+      let (code, i18n_key) = match e {
+        MailboxError::BlobTooLarge => ("SIG504", "mailbox_blob_too_large"),
+        MailboxError::MailboxFull | MailboxError::SenderQuotaReached | MailboxError::InvalidKey => {
+          ("SIG505", "mailbox_full")
+        }
+      };
+      warn!(from = %user_id, to = %recipient, error = %e, "Mailbox deposit rejected");
+      send_error_response_with_details(socket_tx, code, &e.to_string(), Some(i18n_key), details)
+        .await;
+      return;
+    }
+  };
+  debug!(from = %user_id, to = %recipient, "Mailbox deposit stored");
+
+  push_deposit(ws_state, &recipient, item).await;
+}
+
+/// Send a newly deposited item to its recipient device if that device
+/// is connected to this replica.
+///
+/// Only the new message: the rest was pushed or fetched before, and is
+/// fetched again on reconnect until acknowledged. A device on another
+/// replica is sent the item there, after the deposit is replicated, so
+/// its acknowledgement never arrives ahead of the item.
+pub(super) async fn push_deposit(ws_state: &WebSocketState, recipient: &UserId, item: MailboxItem) {
+  let Some(sender) = ws_state.get_local_device_sender(recipient, item.to_device.as_ref()) else {
+    return;
+  };
+  let drain = SignalingMessage::MailboxDrain(MailboxDrain { items: vec![item] });
+  if let Ok(encoded) = encode_signaling_message(&drain) {
+    let _ = sender.send(encoded).await;
+  }
+}
+
+/// Handle MailboxFetch message.
+/// Sends the messages stored for the fetching device (if any), then
+/// every delivery receipt that arrived while the user was offline.
+pub async fn handle_mailbox_fetch<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(mailbox) = mailbox_or_error(socket_tx, ws_state, HashMap::new()).await else {
+    return;
+  };
+
+  let items = mailbox.drain(user_id, device_id.copied());
+  if !items.is_empty() {
+    debug!(user_id = %user_id, count = items.len(), "Draining mailbox");
+    reply(
+      socket_tx,
+      &SignalingMessage::MailboxDrain(MailboxDrain { items }),
+    )
+    .await;
+  }
+  for receipt in mailbox.take_receipts(user_id) {
+    reply(socket_tx, &SignalingMessage::MailboxDelivered(receipt)).await;
+  }
+}
+
+/// Handle MailboxAck message.
+/// Removes the acknowledged messages from the device's mailbox and
+/// notifies their senders, or keeps the receipt for a sender that is
+/// offline.
+pub async fn handle_mailbox_ack(
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  ack: MailboxAck,
+) {
+  let Some(mailbox) = ws_state.mailbox() else {
+    return;
+  };
+  for (sender, receipt) in mailbox.acknowledge(user_id, device_id.copied(), &ack.message_ids) {
+    let delivered =
+      match encode_signaling_message(&SignalingMessage::MailboxDelivered(receipt.clone())) {
+        Ok(encoded) => ws_state.send_to(&sender, encoded).await,
+        Err(_) => false,
+      };
+    if !delivered {
+      mailbox.queue_receipt(sender, receipt);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,331 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use message::MessageId;
+use message::frame::decode_frame;
+use message::signaling::{DeviceMailboxKey, MailboxKeyBundle};
+use tokio::sync::mpsc;
+
+use super::*;
+use crate::config::{Config, MailboxConfig};
+use crate::ws::tests::create_test_ws_state;
+use crate::ws::utils::decode_signaling_message;
+
+fn mailbox_ws_state() -> Arc<WebSocketState> {
+  let config = Config {
+    mailbox: Some(MailboxConfig {
+      ttl: Duration::from_secs(60),
+      max_messages_per_user: 10,
+      max_bytes_per_user: 1024,
+      max_messages_per_sender: 10,
+      max_blob_bytes: 64,
+    }),
+    ..Config::default()
+  };
+  let user_store = crate::auth::UserStore::new(&config);
+  Arc::new(WebSocketState::new(config, user_store))
+}
+
+fn register(ws_state: &WebSocketState, name: &str) -> UserId {
+  ws_state
+    .user_store
+    .register(name, "password123")
+    .expect("Failed to register user")
+    .0
+}
+
+fn decode(message: Message) -> SignalingMessage {
+  let Message::Binary(bytes) = message else {
+    panic!("Expected binary message");
+  };
+  decode_signaling_message(&decode_frame(&bytes).unwrap()).unwrap()
+}
+
+fn decode_bytes(bytes: Vec<u8>) -> SignalingMessage {
+  decode_signaling_message(&decode_frame(&bytes).unwrap()).unwrap()
+}
+
+fn socket() -> (UnboundedSender<Message>, UnboundedReceiver<Message>) {
+  unbounded()
+}
+
+fn deposit(to: &UserId, blob_len: usize) -> MailboxDeposit {
+  MailboxDeposit {
+    to: to.clone(),
+    to_device: None,
+    message_id: MessageId::new(),
+    blob: vec![0xAA; blob_len],
+  }
+}
+
+#[tokio::test]
+async fn test_mailbox_disabled_reports_error() {
+  let ws_state = Arc::new(create_test_ws_state());
+  let user = UserId::new();
+  let (mut tx, mut rx) = socket();
+  let msg = deposit(&UserId::new(), 4);
+  let message_id = msg.message_id;
+
+  handle_mailbox_deposit(&mut tx, &ws_state, &user, None, msg).await;
+
+  let SignalingMessage::ErrorResponse(error) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected ErrorResponse");
+  };
+  assert_eq!(error.code.to_code_string(), "SIG501");
+  assert_eq!(
+    error.details.get("message_id"),
+    Some(&message_id.to_string())
+  );
+}
+
+#[tokio::test]
+async fn test_deposit_to_unknown_recipient_is_rejected() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let (mut tx, mut rx) = socket();
+
+  handle_mailbox_deposit(
+    &mut tx,
+    &ws_state,
+    &sender,
+    None,
+    deposit(&UserId::new(), 4),
+  )
+  .await;
+
+  let SignalingMessage::ErrorResponse(error) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected ErrorResponse");
+  };
+  assert_eq!(error.code.to_code_string(), "SIG503");
+}
+
+#[tokio::test]
+async fn test_oversized_deposit_is_rejected() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let recipient = register(&ws_state, "bob");
+  let (mut tx, mut rx) = socket();
+
+  handle_mailbox_deposit(&mut tx, &ws_state, &sender, None, deposit(&recipient, 65)).await;
+
+  let SignalingMessage::ErrorResponse(error) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected ErrorResponse");
+  };
+  assert_eq!(error.code.to_code_string(), "SIG504");
+  assert!(
+    ws_state
+      .mailbox()
+      .unwrap()
+      .drain(&recipient, None)
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn test_fetch_ack_and_offline_receipt() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let recipient = register(&ws_state, "bob");
+  let (mut tx, mut rx) = socket();
+  let msg = deposit(&recipient, 8);
+  let message_id = msg.message_id;
+
+  // Both offline: the deposit is stored silently.
+  handle_mailbox_deposit(&mut tx, &ws_state, &sender, None, msg).await;
+  assert!(rx.try_recv().is_err(), "no reply expected on success");
+
+  handle_mailbox_fetch(&mut tx, &ws_state, &recipient, None).await;
+  let SignalingMessage::MailboxDrain(drain) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected MailboxDrain");
+  };
+  assert_eq!(drain.items.len(), 1);
+  assert_eq!(drain.items[0].from, sender);
+  assert_eq!(drain.items[0].message_id, message_id);
+
+  // The sender is offline, so the receipt waits for its next fetch.
+  handle_mailbox_ack(
+    &ws_state,
+    &recipient,
+    None,
+    MailboxAck {
+      message_ids: vec![message_id],
+    },
+  )
+  .await;
+  assert!(
+    ws_state
+      .mailbox()
+      .unwrap()
+      .drain(&recipient, None)
+      .is_empty()
+  );
+
+  handle_mailbox_fetch(&mut tx, &ws_state, &sender, None).await;
+  let SignalingMessage::MailboxDelivered(receipt) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected MailboxDelivered");
+  };
+  assert_eq!(receipt.recipient, recipient);
+  assert_eq!(receipt.message_ids, vec![message_id]);
+
+  // Nothing left for either side.
+  handle_mailbox_fetch(&mut tx, &ws_state, &sender, None).await;
+  handle_mailbox_fetch(&mut tx, &ws_state, &recipient, None).await;
+  assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_deposit_pushes_to_connected_recipient_and_receipt_to_sender() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let recipient = register(&ws_state, "bob");
+  let (sender_tx, mut sender_rx) = mpsc::channel(8);
+  let (recipient_tx, mut recipient_rx) = mpsc::channel(8);
+  ws_state.add_connection(sender.clone(), sender_tx);
+  ws_state.add_connection(recipient.clone(), recipient_tx);
+  let (mut tx, _rx) = socket();
+  let msg = deposit(&recipient, 8);
+  let message_id = msg.message_id;
+
+  handle_mailbox_deposit(&mut tx, &ws_state, &sender, None, msg).await;
+  let SignalingMessage::MailboxDrain(drain) = decode_bytes(recipient_rx.recv().await.unwrap())
+  else {
+    panic!("Expected MailboxDrain");
+  };
+  assert_eq!(drain.items[0].message_id, message_id);
+
+  handle_mailbox_ack(
+    &ws_state,
+    &recipient,
+    None,
+    MailboxAck {
+      message_ids: vec![message_id],
+    },
+  )
+  .await;
+  let SignalingMessage::MailboxDelivered(receipt) = decode_bytes(sender_rx.recv().await.unwrap())
+  else {
+    panic!("Expected MailboxDelivered");
+  };
+  assert_eq!(receipt.message_ids, vec![message_id]);
+  assert!(
+    ws_state
+      .mailbox()
+      .unwrap()
+      .take_receipts(&sender)
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn test_deposit_pushes_only_the_new_message() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let recipient = register(&ws_state, "bob");
+  let (recipient_tx, mut recipient_rx) = mpsc::channel(8);
+  ws_state.add_connection(recipient.clone(), recipient_tx);
+  let (mut tx, _rx) = socket();
+
+  for _ in 0..3 {
+    let msg = deposit(&recipient, 8);
+    let message_id = msg.message_id;
+    handle_mailbox_deposit(&mut tx, &ws_state, &sender, None, msg).await;
+    let SignalingMessage::MailboxDrain(drain) = decode_bytes(recipient_rx.recv().await.unwrap())
+    else {
+      panic!("Expected MailboxDrain");
+    };
+    assert_eq!(drain.items.len(), 1);
+    assert_eq!(drain.items[0].message_id, message_id);
+  }
+}
+
+#[tokio::test]
+async fn test_key_request_reports_missing_key() {
+  let ws_state = mailbox_ws_state();
+  let owner = register(&ws_state, "alice");
+  let (mut tx, mut rx) = socket();
+
+  handle_mailbox_key_request(
+    &mut tx,
+    &ws_state,
+    MailboxKeyRequest {
+      user_id: owner.clone(),
+    },
+  )
+  .await;
+  let SignalingMessage::MailboxKeyResponse(response) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected MailboxKeyResponse");
+  };
+  assert_eq!(response.user_id, owner);
+  assert!(response.keys.is_empty());
+}
+
+#[tokio::test]
+async fn test_published_key_is_served() {
+  let ws_state = mailbox_ws_state();
+  let owner = register(&ws_state, "alice");
+  let bundle = MailboxKeyBundle {
+    public_key: vec![4; 65],
+    identity_key: vec![4; 65],
+    signature: vec![1; 64],
+  };
+  let device = DeviceId::new();
+  let (mut tx, mut rx) = socket();
+
+  handle_publish_mailbox_key(
+    &mut tx,
+    &ws_state,
+    &owner,
+    Some(&device),
+    PublishMailboxKey {
+      bundle: bundle.clone(),
+    },
+  )
+  .await;
+  handle_mailbox_key_request(&mut tx, &ws_state, MailboxKeyRequest { user_id: owner }).await;
+  let SignalingMessage::MailboxKeyResponse(response) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected MailboxKeyResponse");
+  };
+  assert_eq!(
+    response.keys,
+    vec![DeviceMailboxKey {
+      device_id: Some(device),
+      bundle,
+    }]
+  );
+}
+
+#[tokio::test]
+async fn test_deposit_reaches_only_the_device_it_is_sealed_to() {
+  let ws_state = mailbox_ws_state();
+  let sender = register(&ws_state, "alice");
+  let recipient = register(&ws_state, "bob");
+  let (laptop, phone) = (DeviceId::new(), DeviceId::new());
+  let (mut tx, mut rx) = socket();
+  let mut msg = deposit(&recipient, 8);
+  msg.to_device = Some(laptop);
+  let message_id = msg.message_id;
+
+  handle_mailbox_deposit(&mut tx, &ws_state, &sender, None, msg).await;
+
+  // The phone neither receives nor can acknowledge the laptop's copy.
+  handle_mailbox_fetch(&mut tx, &ws_state, &recipient, Some(&phone)).await;
+  assert!(rx.try_recv().is_err());
+  handle_mailbox_ack(
+    &ws_state,
+    &recipient,
+    Some(&phone),
+    MailboxAck {
+      message_ids: vec![message_id],
+    },
+  )
+  .await;
+
+  handle_mailbox_fetch(&mut tx, &ws_state, &recipient, Some(&laptop)).await;
+  let SignalingMessage::MailboxDrain(drain) = decode(rx.next().await.unwrap()) else {
+    panic!("Expected MailboxDrain");
+  };
+  assert_eq!(drain.items.len(), 1);
+  assert_eq!(drain.items[0].message_id, message_id);
+  assert_eq!(drain.items[0].to_device, Some(laptop));
+}
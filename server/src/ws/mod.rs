@@ -3,13 +3,17 @@
 //! This module provides WebSocket connection management with:
 //! - Binary message encoding/decoding using bitcode
 //! - Heartbeat detection (Ping/Pong)
-//! - Connection state management
+//! - Connection state management, with one endpoint per device of a user
+//! - Routing to users connected to other cluster replicas
 //! - Graceful shutdown handling
 
 mod call;
+mod cluster;
 mod handler;
 mod invite;
+mod mailbox;
 mod room;
+mod sfu;
 mod theater;
 mod utils;
 mod webrtc;
@@ -23,8 +27,10 @@ use axum::extract::{ConnectInfo, State};
 use axum::response::Response;
 use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
-use message::UserId;
-use message::signaling::{ModerationNotification, Ping, SignalingMessage};
+use message::signaling::{
+  DeviceInfo, DeviceListUpdate, ModerationNotification, Ping, SessionInvalidated, SignalingMessage,
+};
+use message::{DeviceId, UserId};
 use tokio::select;
 use tokio::sync::mpsc;
 use tokio::time::interval;
@@ -32,9 +38,12 @@ use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
 use crate::auth::UserStore;
+use crate::cluster::{ClusterBus, ClusterEvent, ClusterState};
 use crate::config::Config;
 use crate::discovery::DiscoveryState;
 use crate::logging::mask_ip;
+use crate::mailbox::MailboxState;
+use crate::metrics::Metrics;
 
 /// Background cleanup task interval in seconds.
 ///
@@ -43,8 +52,10 @@ use crate::logging::mask_ip;
 /// - Expired invitation cleanup
 /// - Expired multi-invite cleanup
 /// - Expired SDP negotiation cleanup
+/// - Expired mailbox message cleanup
 const BACKGROUND_CLEANUP_INTERVAL_SECS: u64 = 30;
 use crate::room::RoomState;
+use crate::sfu::SfuState;
 
 pub use utils::{decode_signaling_message, encode_signaling_message};
 
@@ -61,6 +72,13 @@ pub struct ConnectionState {
   pub remote_addr: String,
   /// Sender for outgoing messages (set after authentication).
   pub sender: Option<mpsc::Sender<Vec<u8>>>,
+  /// Device this connection authenticated as.
+  pub device_id: Option<DeviceId>,
+  /// Auth session of the token the connection authenticated with.
+  pub session_id: Option<String>,
+  /// Cancelled to close the socket from outside the connection task
+  /// (e.g. an administrator kick).
+  pub close_token: CancellationToken,
 }
 
 impl ConnectionState {
@@ -73,17 +91,34 @@ impl ConnectionState {
       last_heartbeat: Instant::now(),
       remote_addr,
       sender: None,
+      device_id: None,
+      session_id: None,
+      close_token: CancellationToken::new(),
     }
   }
 }
 
+/// One connected device of a user.
+#[derive(Debug, Clone)]
+struct DeviceConnection {
+  /// Device identity from `TokenAuth`.
+  info: DeviceInfo,
+  /// Sender for this device's socket.
+  sender: mpsc::Sender<Vec<u8>>,
+  /// Auth session the device authenticated with.
+  session_id: Option<String>,
+}
+
 /// Global state for WebSocket connections.
 #[derive(Debug)]
 pub struct WebSocketState {
-  /// Active connections: UserId -> Sender.
+  /// Active connections: UserId -> Sender reaching every device of
+  /// the user.
   connections: DashMap<UserId, mpsc::Sender<Vec<u8>>>,
-  /// Connection metadata for logging.
-  metadata: DashMap<UserId, ConnectionState>,
+  /// Connected devices of each user, in connection order.
+  devices: DashMap<UserId, Vec<DeviceConnection>>,
+  /// Connection metadata for logging, per device.
+  metadata: DashMap<DeviceId, ConnectionState>,
   /// User authentication store.
   user_store: UserStore,
   /// User discovery and invitation state.
@@ -92,35 +127,263 @@ pub struct WebSocketState {
   room_state: RoomState,
   /// Configuration reference.
   config: Config,
+  /// Prometheus counters and histograms.
+  metrics: Arc<Metrics>,
+  /// Media relay for SFU rooms (`None` when disabled).
+  sfu: Option<SfuState>,
+  /// Store-and-forward mailbox (`None` when disabled).
+  mailbox: Option<MailboxState>,
+  /// Bus to the other replicas (`None` for a standalone server).
+  cluster: Option<ClusterState>,
 }
 
 impl WebSocketState {
-  /// Create a new WebSocket state.
+  /// Create a new WebSocket state with a volatile room state.
   #[must_use]
   pub fn new(config: Config, user_store: UserStore) -> Self {
+    Self::with_room_state(config, user_store, RoomState::new())
+  }
+
+  /// Create a new WebSocket state around an existing room state
+  /// (e.g. one restored from storage).
+  ///
+  /// When [`Config::sfu`] is set the media relay is started here and
+  /// the room state is allowed to create rooms above the mesh limit.
+  /// A relay that fails to start is logged and left disabled.
+  #[must_use]
+  pub fn with_room_state(config: Config, user_store: UserStore, room_state: RoomState) -> Self {
+    let sfu = config.sfu.as_ref().and_then(|sfu_config| {
+      SfuState::new(sfu_config, &config.ice_servers)
+        .inspect_err(|e| error!(error = %e, "Failed to start SFU, large rooms disabled"))
+        .ok()
+    });
+    let mailbox = config.mailbox.clone().map(MailboxState::new);
+    let room_state = match (&config.sfu, &sfu) {
+      (Some(sfu_config), Some(_)) => room_state.with_sfu_capacity(sfu_config.max_room_members),
+      _ => room_state,
+    };
     Self {
       connections: DashMap::new(),
+      devices: DashMap::new(),
       metadata: DashMap::new(),
       user_store,
       discovery_state: DiscoveryState::new(),
-      room_state: RoomState::new(),
+      room_state,
       config,
+      metrics: Arc::new(Metrics::default()),
+      sfu,
+      mailbox,
+      cluster: None,
     }
   }
 
-  /// Add a connection for a user.
+  /// Join a cluster of replicas over `bus`.
+  ///
+  /// Users connected to other replicas become reachable through
+  /// [`Self::get_sender`], presence is announced to the other
+  /// replicas and room, account and mailbox changes are replicated.
+  /// The bus is serviced by [`Self::spawn_background_tasks`].
+  #[must_use]
+  pub fn with_cluster(mut self, bus: Arc<dyn ClusterBus>) -> Self {
+    self.room_state = self.room_state.with_replication(bus.node_id().0.clone());
+    self.user_store = self.user_store.with_replication();
+    self.mailbox = self.mailbox.map(MailboxState::with_replication);
+    self.cluster = Some(ClusterState::new(bus, self.config.send_queue_size));
+    self
+  }
+
+  /// Add a connection for a user as their only device.
+  ///
+  /// Devices registered for the user before are forgotten; see
+  /// [`Self::add_device`] for users signed in on several devices.
   pub fn add_connection(&self, user_id: UserId, sender: mpsc::Sender<Vec<u8>>) {
+    let device = DeviceConnection {
+      info: DeviceInfo {
+        device_id: DeviceId::new(),
+        name: String::new(),
+      },
+      sender: sender.clone(),
+      session_id: None,
+    };
+    self.devices.insert(user_id.clone(), vec![device]);
     self.connections.insert(user_id, sender);
   }
 
-  /// Remove a connection for a user.
+  /// Register an authenticated connection as one device of its user.
+  ///
+  /// The connection must carry a user, a device and a sender. Its
+  /// metadata is stored as well. Returns `true` if it is the user's
+  /// first device on this replica, i.e. the user just came online.
+  pub fn add_device(&self, conn_state: &ConnectionState, info: DeviceInfo) -> bool {
+    let (Some(user_id), Some(sender)) = (&conn_state.user_id, &conn_state.sender) else {
+      return false;
+    };
+    let device = DeviceConnection {
+      info,
+      sender: sender.clone(),
+      session_id: conn_state.session_id.clone(),
+    };
+    self
+      .metadata
+      .insert(device.info.device_id, conn_state.clone());
+    // Route while holding the entry so concurrent adds and removes for
+    // the same user install their routes in the order they happened.
+    let mut entry = self.devices.entry(user_id.clone()).or_default();
+    entry.push(device);
+    self.route_user(user_id, &entry);
+    entry.len() == 1
+  }
+
+  /// Unregister one device of a user.
+  ///
+  /// With `sender` given, the device is only removed while it is still
+  /// that connection, so the late cleanup of a replaced connection
+  /// leaves its successor alone. Returns the number of devices the
+  /// user has left, or `None` if the device was not registered.
+  pub fn remove_device(
+    &self,
+    user_id: &UserId,
+    device_id: &DeviceId,
+    sender: Option<&mpsc::Sender<Vec<u8>>>,
+  ) -> Option<usize> {
+    let remaining = {
+      let mut entry = self.devices.get_mut(user_id)?;
+      let index = entry.iter().position(|device| {
+        device.info.device_id == *device_id
+          && sender.is_none_or(|sender| sender.same_channel(&device.sender))
+      })?;
+      entry.remove(index);
+      self.route_user(user_id, &entry);
+      entry.len()
+    };
+    self.metadata.remove(device_id);
+    if remaining == 0 {
+      self
+        .devices
+        .remove_if(user_id, |_, devices| devices.is_empty());
+    }
+    Some(remaining)
+  }
+
+  /// Point the user's entry in `connections` at their devices: the
+  /// device's own sender for a single device, a fan-out queue for
+  /// several.
+  fn route_user(&self, user_id: &UserId, devices: &[DeviceConnection]) {
+    match devices {
+      [] => {
+        self.connections.remove(user_id);
+      }
+      [device] => {
+        self
+          .connections
+          .insert(user_id.clone(), device.sender.clone());
+      }
+      _ => {
+        let targets: Vec<_> = devices.iter().map(|d| d.sender.clone()).collect();
+        let (sender, mut rx) = mpsc::channel::<Vec<u8>>(self.config.send_queue_size);
+        // Ends once the queue is replaced here and every clone handed
+        // out by `get_sender` is dropped. A device whose queue is full
+        // misses the frame rather than stalling its siblings.
+        tokio::spawn(async move {
+          while let Some(frame) = rx.recv().await {
+            for target in &targets {
+              if let Err(mpsc::error::TrySendError::Full(_)) = target.try_send(frame.clone()) {
+                warn!("Device send queue full, dropping frame");
+              }
+            }
+          }
+        });
+        self.connections.insert(user_id.clone(), sender);
+      }
+    }
+  }
+
+  /// Remove a connection for a user, with all of their devices.
   pub fn remove_connection(&self, user_id: &UserId) {
     self.connections.remove(user_id);
-    self.metadata.remove(user_id);
+    self.devices.remove(user_id);
+    self
+      .metadata
+      .retain(|_, conn| conn.user_id.as_ref() != Some(user_id));
+  }
+
+  /// Connected devices of a user on this replica, in connection
+  /// order.
+  #[must_use]
+  pub fn devices(&self, user_id: &UserId) -> Vec<DeviceInfo> {
+    self
+      .devices
+      .get(user_id)
+      .map(|devices| devices.iter().map(|d| d.info.clone()).collect())
+      .unwrap_or_default()
+  }
+
+  /// Send every device of a user the current [`DeviceListUpdate`].
+  pub async fn send_device_list(&self, user_id: &UserId) {
+    let devices = self.devices(user_id);
+    if devices.is_empty() {
+      return;
+    }
+    let update = SignalingMessage::DeviceListUpdate(DeviceListUpdate { devices });
+    if let Ok(encoded) = encode_signaling_message(&update) {
+      self.send_to(user_id, encoded).await;
+    }
   }
 
   /// Get the sender for a user.
+  ///
+  /// For a user connected to another replica this is a queue whose
+  /// frames are forwarded over the cluster bus.
   pub fn get_sender(&self, user_id: &UserId) -> Option<mpsc::Sender<Vec<u8>>> {
+    self.local_sender(user_id).or_else(|| {
+      self
+        .cluster
+        .as_ref()
+        .and_then(|cluster| cluster.remote_sender(user_id))
+    })
+  }
+
+  /// Get the sender for one device of a user.
+  ///
+  /// Without a device, or for a device that is not connected to this
+  /// replica, this is [`Self::get_sender`]: clients drop frames
+  /// addressed to another of their devices.
+  pub fn get_device_sender(
+    &self,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+  ) -> Option<mpsc::Sender<Vec<u8>>> {
+    self
+      .get_local_device_sender(user_id, device_id)
+      .or_else(|| {
+        self
+          .cluster
+          .as_ref()
+          .and_then(|cluster| cluster.remote_sender(user_id))
+      })
+  }
+
+  /// Like [`Self::get_device_sender`], for users connected to this
+  /// replica only.
+  pub fn get_local_device_sender(
+    &self,
+    user_id: &UserId,
+    device_id: Option<&DeviceId>,
+  ) -> Option<mpsc::Sender<Vec<u8>>> {
+    device_id
+      .and_then(|device_id| {
+        self.devices.get(user_id).and_then(|devices| {
+          devices
+            .iter()
+            .find(|device| device.info.device_id == *device_id)
+            .map(|device| device.sender.clone())
+        })
+      })
+      .or_else(|| self.local_sender(user_id))
+  }
+
+  /// Get the sender for a user connected to this replica.
+  fn local_sender(&self, user_id: &UserId) -> Option<mpsc::Sender<Vec<u8>>> {
     self
       .connections
       .get(user_id)
@@ -145,9 +408,70 @@ impl WebSocketState {
       .collect()
   }
 
-  /// Check if a user is currently connected.
+  /// ICE server list for one authenticated user.
+  ///
+  /// Same as [`Self::ice_server_specs`], with an entry for the
+  /// embedded TURN relay prepended when it is enabled. The entry
+  /// carries credentials minted for `user_id` that expire after
+  /// [`TurnConfig::credential_ttl`](crate::config::TurnConfig::credential_ttl).
+  #[must_use]
+  pub fn ice_server_specs_for(&self, user_id: &UserId) -> Vec<message::signaling::IceServerSpec> {
+    let mut specs = self.ice_server_specs();
+    if let (Some(turn), Some(port)) = (&self.config.turn, self.config.stun_port) {
+      let secret = crate::stun::turn::TurnSecret::from_jwt_secret(&self.config.jwt_secret);
+      let credentials = crate::stun::turn::mint_credentials(
+        &secret,
+        user_id,
+        turn.credential_ttl,
+        std::time::SystemTime::now(),
+      );
+      let host = match turn.public_ip {
+        std::net::IpAddr::V4(ip) => ip.to_string(),
+        std::net::IpAddr::V6(ip) => format!("[{ip}]"),
+      };
+      specs.insert(
+        0,
+        message::signaling::IceServerSpec {
+          url: format!("turn:{host}:{port}?transport=udp"),
+          username: Some(credentials.username),
+          credential: Some(credentials.password),
+        },
+      );
+    }
+    specs
+  }
+
+  /// Check if a user is currently connected, to this or another
+  /// replica.
   pub fn is_connected(&self, user_id: &UserId) -> bool {
     self.connections.contains_key(user_id)
+      || self
+        .cluster
+        .as_ref()
+        .is_some_and(|cluster| cluster.remote_node(user_id).is_some())
+  }
+
+  /// Check if a user is connected to another replica only.
+  pub fn is_connected_elsewhere(&self, user_id: &UserId) -> bool {
+    !self.connections.contains_key(user_id)
+      && self
+        .cluster
+        .as_ref()
+        .is_some_and(|cluster| cluster.remote_node(user_id).is_some())
+  }
+
+  /// Profiles of every online user across the cluster.
+  #[must_use]
+  pub fn online_users(&self) -> Vec<message::types::UserInfo> {
+    let mut users = self.user_store.get_online_users();
+    if let Some(cluster) = &self.cluster {
+      for remote in cluster.remote_users() {
+        if !users.iter().any(|u| u.user_id == remote.user_id) {
+          users.push(remote);
+        }
+      }
+    }
+    users
   }
 
   /// Check if two users are members of the same room.
@@ -185,6 +509,198 @@ impl WebSocketState {
     &self.user_store
   }
 
+  /// Get a reference to the room state.
+  #[must_use]
+  pub fn room_state(&self) -> &RoomState {
+    &self.room_state
+  }
+
+  /// Get a reference to the discovery state.
+  #[must_use]
+  pub fn discovery_state(&self) -> &DiscoveryState {
+    &self.discovery_state
+  }
+
+  /// Get a reference to the server configuration.
+  #[must_use]
+  pub fn config(&self) -> &Config {
+    &self.config
+  }
+
+  /// Get the shared metrics registry.
+  #[must_use]
+  pub fn metrics(&self) -> &Arc<Metrics> {
+    &self.metrics
+  }
+
+  /// Get the media relay, if enabled.
+  #[must_use]
+  pub fn sfu(&self) -> Option<&SfuState> {
+    self.sfu.as_ref()
+  }
+
+  /// Get the offline mailbox, if enabled.
+  #[must_use]
+  pub fn mailbox(&self) -> Option<&MailboxState> {
+    self.mailbox.as_ref()
+  }
+
+  /// Get the cluster membership, if this replica is clustered.
+  #[must_use]
+  pub fn cluster(&self) -> Option<&ClusterState> {
+    self.cluster.as_ref()
+  }
+
+  /// Tell the other replicas that `user_id` is online here (or that
+  /// their profile changed). No-op for a standalone server.
+  pub fn announce_online(&self, user_id: &UserId) {
+    if let Some(cluster) = &self.cluster
+      && let Some(info) = self.user_store.get_user(user_id)
+    {
+      cluster.bus().publish(ClusterEvent::UserOnline(info));
+    }
+  }
+
+  /// Tell the other replicas that `user_id` disconnected from here.
+  /// No-op for a standalone server.
+  pub fn announce_offline(&self, user_id: &UserId) {
+    if let Some(cluster) = &self.cluster {
+      cluster
+        .bus()
+        .publish(ClusterEvent::UserOffline(user_id.clone()));
+    }
+  }
+
+  /// Publish the rooms changed since the last call to the other
+  /// replicas. No-op for a standalone server.
+  pub fn flush_room_changes(&self) {
+    if let Some(cluster) = &self.cluster {
+      for replica in self.room_state.take_changes() {
+        cluster.bus().publish(ClusterEvent::Room(Box::new(replica)));
+      }
+    }
+  }
+
+  /// Publish the account changes made since the last call to the
+  /// other replicas. No-op for a standalone server.
+  pub fn flush_account_changes(&self) {
+    if let Some(cluster) = &self.cluster {
+      for change in self.user_store.take_changes() {
+        cluster
+          .bus()
+          .publish(ClusterEvent::Account(Box::new(change)));
+      }
+    }
+  }
+
+  /// Publish the mailbox changes made since the last call to the
+  /// other replicas. No-op for a standalone server.
+  pub fn flush_mailbox_changes(&self) {
+    if let (Some(cluster), Some(mailbox)) = (&self.cluster, &self.mailbox) {
+      for change in mailbox.take_changes() {
+        cluster
+          .bus()
+          .publish(ClusterEvent::Mailbox(Box::new(change)));
+      }
+    }
+  }
+
+  /// Snapshot of the metadata of every authenticated connection.
+  #[must_use]
+  pub fn connection_metadata(&self) -> Vec<ConnectionState> {
+    self
+      .metadata
+      .iter()
+      .map(|entry| entry.value().clone())
+      .collect()
+  }
+
+  /// Close the sockets of every device of a connected user.
+  ///
+  /// The connection tasks perform the regular disconnect cleanup
+  /// (room departure, peer notification, offline broadcast) once they
+  /// observe the cancellation. Returns `false` if the user has no
+  /// authenticated connection.
+  pub fn disconnect_user(&self, user_id: &UserId) -> bool {
+    let mut found = false;
+    for entry in self.metadata.iter() {
+      if entry.user_id.as_ref() == Some(user_id) {
+        entry.close_token.cancel();
+        found = true;
+      }
+    }
+    found
+  }
+
+  /// Tell every device of a connected user that their session has
+  /// ended and close their sockets.
+  ///
+  /// Used whenever all sessions of a user are revoked outside the
+  /// WebSocket (forced logout, password change, account deletion).
+  /// The `SessionInvalidated` notice is flushed before the close
+  /// frame. Returns `false` if the user was not connected.
+  pub async fn end_session(&self, user_id: &UserId) -> bool {
+    if let Ok(encoded) =
+      encode_signaling_message(&SignalingMessage::SessionInvalidated(SessionInvalidated))
+    {
+      self.send_to(user_id, encoded).await;
+    }
+    self.disconnect_user(user_id)
+  }
+
+  /// Like [`Self::end_session`], for the devices connected with auth
+  /// session `session_id` only (e.g. after refresh-token reuse).
+  pub async fn end_auth_session(&self, user_id: &UserId, session_id: &str) -> bool {
+    let targets: Vec<(DeviceId, mpsc::Sender<Vec<u8>>)> = self
+      .devices
+      .get(user_id)
+      .map(|devices| {
+        devices
+          .iter()
+          .filter(|device| device.session_id.as_deref() == Some(session_id))
+          .map(|device| (device.info.device_id, device.sender.clone()))
+          .collect()
+      })
+      .unwrap_or_default();
+    for (device_id, sender) in &targets {
+      self.end_device_session(device_id, sender).await;
+    }
+    !targets.is_empty()
+  }
+
+  /// Tell one device its session has ended and close its socket.
+  async fn end_device_session(&self, device_id: &DeviceId, sender: &mpsc::Sender<Vec<u8>>) {
+    if let Ok(encoded) =
+      encode_signaling_message(&SignalingMessage::SessionInvalidated(SessionInvalidated))
+    {
+      let _ = sender.send(encoded).await;
+    }
+    if let Some(conn) = self.metadata.get(device_id) {
+      conn.close_token.cancel();
+    }
+  }
+
+  /// End the session of a device that connects again while its
+  /// previous connection is still registered (e.g. a reload that beat
+  /// the old socket's heartbeat timeout).
+  ///
+  /// The stale connection is unregistered right away, so its late
+  /// cleanup does not touch the new one. Returns `false` if the device
+  /// had no connection.
+  pub async fn replace_device(&self, user_id: &UserId, device_id: &DeviceId) -> bool {
+    let Some(sender) = self.devices.get(user_id).and_then(|devices| {
+      devices
+        .iter()
+        .find(|device| device.info.device_id == *device_id)
+        .map(|device| device.sender.clone())
+    }) else {
+      return false;
+    };
+    self.end_device_session(device_id, &sender).await;
+    self.remove_device(user_id, device_id, Some(&sender));
+    true
+  }
+
   /// Send a message to a specific user.
   pub async fn send_to(&self, user_id: &UserId, data: Vec<u8>) -> bool {
     if let Some(sender) = self.get_sender(user_id) {
@@ -194,8 +710,22 @@ impl WebSocketState {
     }
   }
 
-  /// Broadcast a message to all connected users.
+  /// Broadcast a message to all connected users, on every replica.
+  ///
+  /// Pending room changes are replicated first, so a replica never
+  /// relays a room list that mentions a room it does not know yet.
   pub async fn broadcast(&self, data: Vec<u8>) {
+    if let Some(cluster) = &self.cluster {
+      self.flush_room_changes();
+      cluster.bus().publish(ClusterEvent::Broadcast {
+        frame: data.clone(),
+      });
+    }
+    self.broadcast_local(data).await;
+  }
+
+  /// Broadcast a message to the users connected to this replica.
+  pub async fn broadcast_local(&self, data: Vec<u8>) {
     for entry in self.connections.iter() {
       let sender = entry.value();
       if sender.send(data.clone()).await.is_err() {
@@ -211,10 +741,16 @@ impl WebSocketState {
   /// - Expired invitation cleanup
   /// - Expired multi-invite cleanup
   /// - Expired SDP negotiation cleanup
+  /// - Expired mailbox messages
+  ///
+  /// A clustered replica also starts servicing its cluster bus.
   ///
   /// The tasks will be gracefully cancelled when the provided
   /// `CancellationToken` is triggered during server shutdown.
   pub fn spawn_background_tasks(self: &Arc<Self>, cancel_token: CancellationToken) {
+    if self.cluster.is_some() {
+      cluster::spawn(self, cancel_token.clone());
+    }
     let ws_state = Arc::clone(self);
     tokio::spawn(async move {
       let mut cleanup_interval = interval(std::time::Duration::from_secs(
@@ -282,7 +818,15 @@ impl WebSocketState {
             // 4. Clean up expired SDP negotiations
             ws_state.discovery_state.cleanup_expired_sdp_negotiations();
 
-            // 5. Clean up old log files based on config limits
+            // 5. Drop mailbox messages nobody collected in time
+            if let Some(mailbox) = &ws_state.mailbox {
+              let purged = mailbox.purge_expired(std::time::Instant::now());
+              if purged > 0 {
+                debug!(count = purged, "Purged expired mailbox messages");
+              }
+            }
+
+            // 6. Clean up old log files based on config limits
             if ws_state.config.log_output != "stdout"
               && let Err(e) = crate::logging::cleanup_old_logs(
                 &ws_state.config.log_dir,
@@ -321,6 +865,7 @@ pub async fn ws_handler(
 /// Handle WebSocket connection.
 async fn handle_socket(socket: WebSocket, ws_state: Arc<WebSocketState>, remote_addr: String) {
   let mut conn_state = ConnectionState::new(remote_addr.clone());
+  ws_state.metrics.ws_connections_opened.inc();
 
   info!(
     remote_addr = %mask_ip(&remote_addr),
@@ -334,6 +879,8 @@ async fn handle_socket(socket: WebSocket, ws_state: Arc<WebSocketState>, remote_
 
   let (mut socket_tx, mut socket_rx) = socket.split();
 
+  let close_token = conn_state.close_token.clone();
+
   // Heartbeat interval
   let mut heartbeat_interval = interval(ws_state.config.heartbeat_interval);
   let heartbeat_timeout = ws_state.config.heartbeat_timeout;
@@ -378,6 +925,25 @@ async fn handle_socket(socket: WebSocket, ws_state: Arc<WebSocketState>, remote_
         }
       }
 
+      // Closed from outside the connection task
+      () = close_token.cancelled() => {
+        // Flush anything queued before the close (e.g. the
+        // `SessionInvalidated` sent ahead of a forced logout).
+        while let Ok(data) = rx.try_recv() {
+          let _ = socket_tx.send(Message::Binary(Bytes::from(data))).await;
+        }
+        info!(
+          remote_addr = %mask_ip(&remote_addr),
+          user_id = ?conn_state.user_id,
+          "Connection closed by server"
+        );
+        let _ = socket_tx.send(Message::Close(Some(CloseFrame {
+          code: 4000,
+          reason: "Disconnected by server".into(),
+        }))).await;
+        break;
+      }
+
       // Heartbeat check
       _ = heartbeat_interval.tick() => {
         // Check heartbeat timeout
@@ -411,9 +977,14 @@ async fn handle_socket(socket: WebSocket, ws_state: Arc<WebSocketState>, remote_
     }
   }
 
+  ws_state
+    .metrics
+    .ws_connection_seconds
+    .observe_duration(conn_state.connected_at.elapsed());
+
   // Cleanup on disconnect
-  if let Some(user_id) = conn_state.user_id {
-    handler::handle_user_disconnect(&ws_state, &user_id).await;
+  if let Some(user_id) = conn_state.user_id.clone() {
+    handler::handle_device_disconnect(&ws_state, &conn_state).await;
     info!(
       user_id = %user_id,
       remote_addr = %mask_ip(&remote_addr),
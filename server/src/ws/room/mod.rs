@@ -171,6 +171,10 @@ pub async fn handle_leave_room<S>(
 {
   match ws_state.room_state.leave_room(&leave_room, user_id) {
     Ok(result) => {
+      if let Some(sfu) = ws_state.sfu() {
+        sfu.leave(&result.room_id, user_id).await;
+      }
+
       // Send RoomLeft response to the leaving user
       let left_msg = SignalingMessage::RoomLeft(message::signaling::RoomLeft {
         room_id: result.room_id.clone(),
@@ -260,6 +264,10 @@ pub async fn handle_kick_member<S>(
 {
   match ws_state.room_state.kick_member(&kick_member, user_id) {
     Ok((_removed_member, _room_info)) => {
+      if let Some(sfu) = ws_state.sfu() {
+        sfu.leave(&kick_member.room_id, &kick_member.target).await;
+      }
+
       // Broadcast ModerationNotification to all remaining members AND the
       // kicked user so everyone sees the toast (Req 15.3.23).
       let notification = SignalingMessage::ModerationNotification(ModerationNotification {
@@ -498,6 +506,10 @@ pub async fn handle_ban_member<S>(
 {
   match ws_state.room_state.ban_member(&ban_member, user_id) {
     Ok((_removed_member, _room_info)) => {
+      if let Some(sfu) = ws_state.sfu() {
+        sfu.leave(&ban_member.room_id, &ban_member.target).await;
+      }
+
       // Broadcast ModerationNotification to all remaining members AND the
       // banned user so everyone sees the toast (Req 15.3.23).
       let notification = SignalingMessage::ModerationNotification(ModerationNotification {
@@ -976,6 +988,7 @@ pub async fn handle_nickname_change<S>(
   ws_state
     .user_store
     .set_nickname(user_id, &nickname_change.new_nickname);
+  ws_state.announce_online(user_id);
 
   // Try to mirror into the room-scoped MemberInfo + broadcast to
   // room members. This is best-effort: when the user is not in a
@@ -1110,12 +1123,12 @@ pub async fn handle_avatar_change<S>(
   // UserInfo refreshes. Mirrors how `UserStatusChange` propagates —
   // we re-emit a full UserListUpdate so receivers can overwrite
   // their map in one go.
-  let users = ws_state.user_store.get_online_users();
+  ws_state.announce_online(user_id);
+  let users = ws_state.online_users();
   let list_update = SignalingMessage::UserListUpdate(message::signaling::UserListUpdate { users });
   if let Ok(encoded) = encode_signaling_message(&list_update) {
     let online_ids: Vec<_> = ws_state
-      .user_store
-      .get_online_users()
+      .online_users()
       .into_iter()
       .map(|u| u.user_id)
       .collect();
@@ -0,0 +1,167 @@
+//! SFU media session handling functions.
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use axum::extract::ws::Message;
+use futures::Sink;
+use message::types::RoomTopology;
+use message::{DeviceId, RoomId, UserId};
+use tracing::{debug, warn};
+
+use super::WebSocketState;
+use crate::sfu::{SfuError, SfuState};
+use crate::ws::utils::send_error_response;
+
+/// Validate that `room_id` is an SFU room the user belongs to and that
+/// the relay is running. Sends the matching error response otherwise.
+async fn sfu_for_member<'a, S>(
+  socket_tx: &mut S,
+  ws_state: &'a WebSocketState,
+  user_id: &UserId,
+  room_id: &RoomId,
+) -> Option<&'a SfuState>
+where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(room) = ws_state.room_state.get_room(room_id) else {
+    send_error_response(
+      socket_tx,
+      "SIG401",
+      "Room not found",
+      Some("room_not_found"),
+    )
+    .await;
+    return None;
+  };
+
+  if !room.is_member(user_id) {
+    send_error_response(
+      socket_tx,
+      "SIG402",
+      "You are not a member of this room",
+      Some("not_member"),
+    )
+    .await;
+    return None;
+  }
+
+  match ws_state.sfu() {
+    Some(sfu) if room.info.topology == RoomTopology::Sfu => Some(sfu),
+    _ => {
+      send_error_response(
+        socket_tx,
+        "SIG403",
+        "Room does not use the SFU",
+        Some("sfu_unavailable"),
+      )
+      .await;
+      None
+    }
+  }
+}
+
+/// Report a failed SFU negotiation to the client.
+async fn send_negotiation_error<S>(socket_tx: &mut S, user_id: &UserId, error: &SfuError)
+where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  warn!(user_id = %user_id, error = %error, "SFU negotiation failed");
+  send_error_response(
+    socket_tx,
+    "SIG404",
+    "Failed to negotiate media session",
+    Some("sfu_negotiation_failed"),
+  )
+  .await;
+}
+
+/// Handle SfuOffer message.
+/// Opens (or renegotiates) the user's media session with the relay.
+///
+/// Relay signaling goes to the offering device only, since it is the
+/// one holding the media session.
+pub async fn handle_sfu_offer<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  offer: message::signaling::SfuOffer,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(sfu) = sfu_for_member(socket_tx, ws_state, user_id, &offer.room_id).await else {
+    return;
+  };
+  let Some(signal_tx) = ws_state.get_device_sender(user_id, device_id) else {
+    return;
+  };
+
+  if let Err(e) = sfu
+    .handle_offer(&offer.room_id, user_id, device_id, offer.sdp, signal_tx)
+    .await
+  {
+    send_negotiation_error(socket_tx, user_id, &e).await;
+  }
+}
+
+/// Handle SfuAnswer message.
+/// Completes a renegotiation started by the relay.
+pub async fn handle_sfu_answer<S>(
+  socket_tx: &mut S,
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  answer: message::signaling::SfuAnswer,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
+{
+  let Some(sfu) = sfu_for_member(socket_tx, ws_state, user_id, &answer.room_id).await else {
+    return;
+  };
+
+  if let Err(e) = sfu
+    .handle_answer(&answer.room_id, user_id, device_id, answer.sdp)
+    .await
+  {
+    send_negotiation_error(socket_tx, user_id, &e).await;
+  }
+}
+
+/// Handle SfuIceCandidate message.
+/// Candidates for sessions that no longer exist are dropped silently:
+/// they routinely race with a leave.
+pub async fn handle_sfu_ice_candidate(
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  candidate: message::signaling::SfuIceCandidate,
+) {
+  let Some(sfu) = ws_state.sfu() else {
+    return;
+  };
+  if let Err(e) = sfu.add_ice_candidate(user_id, device_id, candidate).await {
+    debug!(user_id = %user_id, error = %e, "Dropped SFU ICE candidate");
+  }
+}
+
+/// Handle SfuLeave message.
+/// Closes the sending device's media session in the room; the user's
+/// other devices stay in the call.
+pub async fn handle_sfu_leave(
+  ws_state: &Arc<WebSocketState>,
+  user_id: &UserId,
+  device_id: Option<&DeviceId>,
+  leave: message::signaling::SfuLeave,
+) {
+  if let Some(sfu) = ws_state.sfu() {
+    sfu.leave_session(&leave.room_id, user_id, device_id).await;
+  }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,50 @@
+use super::*;
+use crate::config::{Config, SfuConfig};
+use crate::ws::tests::create_test_ws_state;
+use message::signaling::CreateRoom;
+use message::types::RoomType;
+
+fn create_room(max_participants: u8) -> CreateRoom {
+  CreateRoom {
+    name: "Large Room".to_string(),
+    description: String::new(),
+    room_type: RoomType::Chat,
+    password: None,
+    max_participants,
+  }
+}
+
+#[test]
+fn test_sfu_disabled_keeps_mesh_limit() {
+  let ws_state = create_test_ws_state();
+  assert!(ws_state.sfu().is_none());
+
+  let (_, room_info) = ws_state
+    .room_state
+    .create_room(&create_room(20), UserId::new())
+    .unwrap();
+  assert_eq!(room_info.max_members, 8);
+  assert_eq!(room_info.topology, RoomTopology::Mesh);
+}
+
+#[test]
+fn test_sfu_enabled_allows_large_rooms() {
+  let config = Config {
+    sfu: Some(SfuConfig {
+      max_room_members: 16,
+      public_ip: None,
+      udp_ports: None,
+    }),
+    ..Config::default()
+  };
+  let user_store = crate::auth::UserStore::new(&config);
+  let ws_state = WebSocketState::new(config, user_store);
+  assert!(ws_state.sfu().is_some());
+
+  let (_, room_info) = ws_state
+    .room_state
+    .create_room(&create_room(20), UserId::new())
+    .unwrap();
+  assert_eq!(room_info.max_members, 16);
+  assert_eq!(room_info.topology, RoomTopology::Sfu);
+}
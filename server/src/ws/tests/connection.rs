@@ -135,3 +135,58 @@ fn test_get_all_connected_users() {
     assert!(state.is_connected(user));
   }
 }
+
+#[test]
+fn test_ice_server_specs_for_prepends_embedded_turn() {
+  let mut config = create_test_config();
+  config.stun_port = Some(3478);
+  config.turn = Some(crate::config::TurnConfig {
+    realm: "test".to_string(),
+    public_ip: std::net::IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1)),
+    relay_ports: 49152..=49200,
+    user_bandwidth_kbps: 0,
+    credential_ttl: std::time::Duration::from_secs(60),
+    denied_peer_ips: Vec::new(),
+    allowed_peer_ips: Vec::new(),
+  });
+  let user_store = UserStore::new(&config);
+  let state = WebSocketState::new(config, user_store);
+  let user_id = UserId::new();
+
+  let specs = state.ice_server_specs_for(&user_id);
+  assert_eq!(specs.len(), state.ice_server_specs().len() + 1);
+  let turn = &specs[0];
+  assert_eq!(turn.url, "turn:192.0.2.1:3478?transport=udp");
+  assert!(
+    turn
+      .username
+      .as_deref()
+      .is_some_and(|u| u.ends_with(&format!(":{user_id}")))
+  );
+  assert!(turn.credential.is_some());
+}
+
+#[test]
+fn test_ice_server_specs_for_without_turn() {
+  let state = create_test_ws_state();
+  assert_eq!(
+    state.ice_server_specs_for(&UserId::new()),
+    state.ice_server_specs()
+  );
+}
+
+#[test]
+fn test_disconnect_user_cancels_connection() {
+  let state = create_test_ws_state();
+  let user_id = UserId::new();
+  assert!(!state.disconnect_user(&user_id));
+
+  let mut conn = ConnectionState::new("127.0.0.1:5000".to_string());
+  conn.user_id = Some(user_id.clone());
+  let close_token = conn.close_token.clone();
+  state.metadata.insert(DeviceId::new(), conn);
+  assert_eq!(state.connection_metadata().len(), 1);
+
+  assert!(state.disconnect_user(&user_id));
+  assert!(close_token.is_cancelled());
+}
@@ -6,6 +6,7 @@ use super::*;
 fn test_encode_signaling_message() {
   let msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "test_token".to_string(),
+    device: None,
   });
   let result = encode_signaling_message(&msg);
   assert!(result.is_ok());
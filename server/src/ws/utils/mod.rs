@@ -1,5 +1,6 @@
 //! Utility functions for WebSocket message encoding/decoding.
 
+use std::collections::HashMap;
 use std::fmt::Display;
 
 use axum::body::Bytes;
@@ -87,6 +88,23 @@ pub async fn send_error_response<S>(
 ) where
   S: Sink<Message> + Unpin,
   S::Error: Display,
+{
+  send_error_response_with_details(socket_tx, code, message, i18n_key, HashMap::new()).await;
+}
+
+/// Send an error response carrying contextual `details`.
+///
+/// Same as [`send_error_response`]; the details let the client match
+/// the error to the request that caused it (e.g. a `message_id`).
+pub async fn send_error_response_with_details<S>(
+  socket_tx: &mut S,
+  code: &str,
+  message: &str,
+  i18n_key: Option<&str>,
+  details: HashMap<String, String>,
+) where
+  S: Sink<Message> + Unpin,
+  S::Error: Display,
 {
   // Parse error code string to ErrorCode
   let error_code = parse_error_code(code);
@@ -95,7 +113,7 @@ pub async fn send_error_response<S>(
     code: error_code,
     message: message.to_string(),
     i18n_key: i18n_key.unwrap_or("error.unknown").to_string(),
-    details: std::collections::HashMap::new(),
+    details,
     timestamp_nanos: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0),
     trace_id: uuid::Uuid::new_v4().to_string(),
   });
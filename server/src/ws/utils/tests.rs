@@ -6,6 +6,7 @@ use message::signaling::{Ping, TokenAuth};
 fn test_encode_signaling_message() {
   let msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "test_token".to_string(),
+    device: None,
   });
   let result = encode_signaling_message(&msg);
   assert!(result.is_ok());
@@ -143,6 +144,8 @@ fn test_encode_decode_roundtrip_auth_success() {
     nickname: "testuser".to_string(),
     ice_servers: Vec::new(),
     avatar_url: None,
+    mailbox_enabled: false,
+    device_id: message::DeviceId::new(),
   });
   let encoded = encode_signaling_message(&msg).unwrap();
   let frame = decode_frame(&encoded).unwrap();
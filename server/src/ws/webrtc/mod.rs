@@ -102,7 +102,7 @@ pub async fn handle_sdp_offer<S>(
   // Forward SDP offer to target
   let offer_msg = SignalingMessage::SdpOffer(sdp_offer.clone());
   if let Ok(encoded) = encode_signaling_message(&offer_msg)
-    && let Some(sender) = ws_state.get_sender(&sdp_offer.to)
+    && let Some(sender) = ws_state.get_device_sender(&sdp_offer.to, sdp_offer.to_device.as_ref())
     && sender.send(encoded).await.is_err()
   {
     warn!(
@@ -180,7 +180,7 @@ pub async fn handle_sdp_answer<S>(
   // Forward SDP answer to target
   let answer_msg = SignalingMessage::SdpAnswer(sdp_answer.clone());
   if let Ok(encoded) = encode_signaling_message(&answer_msg)
-    && let Some(sender) = ws_state.get_sender(&sdp_answer.to)
+    && let Some(sender) = ws_state.get_device_sender(&sdp_answer.to, sdp_answer.to_device.as_ref())
     && sender.send(encoded).await.is_err()
   {
     warn!(
@@ -247,7 +247,8 @@ pub async fn handle_ice_candidate<S>(
   // Forward ICE candidate to target
   let candidate_msg = SignalingMessage::IceCandidate(ice_candidate.clone());
   if let Ok(encoded) = encode_signaling_message(&candidate_msg)
-    && let Some(sender) = ws_state.get_sender(&ice_candidate.to)
+    && let Some(sender) =
+      ws_state.get_device_sender(&ice_candidate.to, ice_candidate.to_device.as_ref())
     && sender.send(encoded).await.is_err()
   {
     debug!(
@@ -11,6 +11,8 @@ fn test_sdp_offer_sender_validation() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0...".to_string(),
+    from_device: None,
+    to_device: None,
   };
 
   // Verify sender matches
@@ -14,18 +14,16 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use axum::Router;
-use futures::{SinkExt, StreamExt};
-use message::frame::{decode_frame, encode_frame};
-use message::signaling::{SignalingMessage, TokenAuth};
+use client::Connection;
+use message::signaling::SignalingMessage;
 use server::auth::UserStore;
 use server::config::Config;
 use server::ws::WebSocketState;
 use tokio::net::TcpListener;
-use tokio::time::{sleep, timeout};
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use tokio::time::sleep;
 
-/// Convenient type alias for the WebSocket stream used in integration tests.
-pub type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+/// Integration tests talk to the server through the native client crate.
+pub type WsStream = Connection;
 
 /// Create a test server bound to a random port and return its address,
 /// shared WebSocket state, and user store.
@@ -56,19 +54,20 @@ pub async fn create_test_server() -> (SocketAddr, Arc<WebSocketState>, UserStore
 }
 
 /// Connect to the WebSocket server at the given address.
+///
+/// Auto-pong is disabled so heartbeat tests see and answer `Ping`
+/// themselves; every other test filters heartbeats out as noise.
 pub async fn connect_ws(addr: SocketAddr) -> WsStream {
-  let url = format!("ws://{}/ws", addr);
-  let (ws_stream, _) = connect_async(&url).await.unwrap();
-  ws_stream
+  let mut ws = Connection::connect(&format!("ws://{addr}/ws"))
+    .await
+    .unwrap();
+  ws.set_auto_pong(false);
+  ws
 }
 
 /// Send a signaling message over the WebSocket connection.
 pub async fn send_signaling(ws: &mut WsStream, msg: &SignalingMessage) {
-  let discriminator = msg.discriminator();
-  let payload = bitcode::encode(msg);
-  let frame = message::frame::MessageFrame::new(discriminator, payload);
-  let encoded = encode_frame(&frame).unwrap();
-  ws.send(Message::Binary(encoded.into())).await.unwrap();
+  ws.send(msg).await.unwrap();
 }
 
 /// Receive a signaling message, skipping any message for which `should_skip` returns true.
@@ -84,24 +83,9 @@ pub async fn recv_signaling_filtered<F>(
 where
   F: Fn(&SignalingMessage) -> bool,
 {
-  timeout(Duration::from_secs(5), async {
-    loop {
-      match ws.next().await? {
-        Ok(Message::Binary(data)) => {
-          let frame = decode_frame(&data).ok()?;
-          let msg: SignalingMessage = bitcode::decode(&frame.payload).ok()?;
-          if should_skip(&msg) {
-            continue;
-          }
-          return Some(msg);
-        }
-        Ok(Message::Ping(_) | Message::Pong(_)) => continue,
-        _ => return None,
-      }
-    }
-  })
-  .await
-  .ok()?
+  ws.recv_matching(Duration::from_secs(5), |msg| !should_skip(msg))
+    .await
+    .ok()
 }
 
 /// Register a user, connect via WebSocket, authenticate, and skip the initial
@@ -117,15 +101,9 @@ pub async fn auth_user(
   let (user_id, token) = user_store.register(username, password).unwrap();
   let mut ws = connect_ws(addr).await;
 
-  send_signaling(&mut ws, &SignalingMessage::TokenAuth(TokenAuth { token })).await;
-
-  // Wait for AuthSuccess (skip only heartbeat noise)
-  let response = recv_signaling_filtered(&mut ws, |msg| {
-    matches!(msg, SignalingMessage::Ping(_) | SignalingMessage::Pong(_))
-  })
-  .await;
+  let response = ws.authenticate(&token).await;
   assert!(
-    matches!(response, Some(SignalingMessage::AuthSuccess(_))),
+    response.is_ok(),
     "Expected AuthSuccess after TokenAuth, got: {:?}",
     response
   );
@@ -141,16 +119,5 @@ pub async fn auth_user(
 /// Optionally waits `wait` duration before starting to drain, then reads and discards
 /// every message that arrives within 50ms windows until no more messages come.
 pub async fn drain_messages(ws: &mut WsStream, wait: Duration) {
-  sleep(wait).await;
-  loop {
-    match timeout(Duration::from_millis(50), ws.next()).await {
-      Ok(Some(Ok(Message::Binary(data)))) => {
-        if let Ok(frame) = decode_frame(&data) {
-          let _: Option<SignalingMessage> = bitcode::decode(&frame.payload).ok();
-        }
-      }
-      Ok(Some(Ok(Message::Ping(_) | Message::Pong(_)))) => continue,
-      _ => break,
-    }
-  }
+  ws.drain(wait).await;
 }
@@ -3,7 +3,7 @@
 //! Tests the complete WebSocket authentication lifecycle including:
 //! - User registration and login
 //! - TokenAuth via WebSocket
-//! - Single-device login policy
+//! - Multi-device sessions
 //! - User status management
 //! - Logout flow
 
@@ -12,8 +12,9 @@ mod common;
 use std::time::Duration;
 
 use common::{WsStream, connect_ws, create_test_server, recv_signaling_filtered, send_signaling};
+use message::DeviceId;
 use message::signaling::{
-  AuthFailure, AuthSuccess, Ping, SignalingMessage, TokenAuth, UserListUpdate,
+  AuthFailure, AuthSuccess, DeviceInfo, Ping, SignalingMessage, TokenAuth, UserListUpdate,
 };
 use message::types::UserStatus;
 
@@ -67,7 +68,10 @@ async fn test_websocket_auth_valid_token() {
   let mut ws = connect_ws(addr).await;
 
   // Send TokenAuth
-  let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+  let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+    token,
+    device: None,
+  });
   send_signaling(&mut ws, &auth_msg).await;
 
   // Receive AuthSuccess
@@ -110,6 +114,7 @@ async fn test_websocket_auth_invalid_token() {
   // Send TokenAuth with invalid token
   let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
     token: "invalid_token".to_string(),
+    device: None,
   });
   send_signaling(&mut ws, &auth_msg).await;
 
@@ -125,21 +130,25 @@ async fn test_websocket_auth_invalid_token() {
   }
 }
 
-/// Test: Single-device login policy.
+/// Test: A user can be signed in on several devices at once.
 #[tokio::test]
-async fn test_single_device_login_policy() {
+async fn test_multi_device_login() {
   let (addr, ws_state, user_store) = create_test_server().await;
 
   // Register user
-  let (_user_id, _token) = user_store.register("testuser3", "password123").unwrap();
+  let (user_id, _token) = user_store.register("testuser3", "password123").unwrap();
 
-  // First login - get token
+  // First device - get token
   let (_, token1) = user_store.login("testuser3", "password123").unwrap();
 
   // Connect first WebSocket
   let mut ws1 = connect_ws(addr).await;
   let auth_msg1 = SignalingMessage::TokenAuth(TokenAuth {
     token: token1.clone(),
+    device: Some(DeviceInfo {
+      device_id: DeviceId::new(),
+      name: "Laptop".to_string(),
+    }),
   });
   send_signaling(&mut ws1, &auth_msg1).await;
 
@@ -147,29 +156,52 @@ async fn test_single_device_login_policy() {
   let response1 = recv_signaling(&mut ws1).await;
   assert!(matches!(response1, Some(SignalingMessage::AuthSuccess(_))));
 
-  // Also receive UserListUpdate
-  let _ = recv_signaling(&mut ws1).await;
-
-  // Second login - get new token (invalidates old session)
+  // Second device - its login leaves the first session valid
   let (_, token2) = user_store.login("testuser3", "password123").unwrap();
 
   // Connect second WebSocket with new token
   let mut ws2 = connect_ws(addr).await;
-  let auth_msg2 = SignalingMessage::TokenAuth(TokenAuth { token: token2 });
+  let auth_msg2 = SignalingMessage::TokenAuth(TokenAuth {
+    token: token2,
+    device: Some(DeviceInfo {
+      device_id: DeviceId::new(),
+      name: "Phone".to_string(),
+    }),
+  });
   send_signaling(&mut ws2, &auth_msg2).await;
 
   // Second WebSocket should succeed
   let response2 = recv_signaling(&mut ws2).await;
   assert!(matches!(response2, Some(SignalingMessage::AuthSuccess(_))));
 
-  // First WebSocket should receive SessionInvalidated or be disconnected
-  // Note: The server sends SessionInvalidated to the old connection
-  tokio::time::sleep(Duration::from_millis(200)).await;
+  // The first device is told about the second one instead of being
+  // invalidated
+  let update = recv_signaling_filtered(&mut ws1, |msg| match msg {
+    SignalingMessage::DeviceListUpdate(update) => update.devices.len() < 2,
+    SignalingMessage::SessionInvalidated(_) => false,
+    _ => true,
+  })
+  .await;
+  match update {
+    Some(SignalingMessage::DeviceListUpdate(update)) => {
+      let names: Vec<_> = update.devices.iter().map(|d| d.name.as_str()).collect();
+      assert_eq!(names, ["Laptop", "Phone"]);
+    }
+    other => panic!("Expected DeviceListUpdate, got: {:?}", other),
+  }
+
+  // One user, two devices
+  assert_eq!(ws_state.connection_count(), 1);
+  assert_eq!(ws_state.devices(&user_id).len(), 2);
 
-  // Verify first user is no longer in connections
-  // (This would require access to ws_state internals)
-  let connected_count = ws_state.connection_count();
-  assert_eq!(connected_count, 1); // Only the second connection
+  // Closing one device keeps the user online
+  drop(ws1);
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert_eq!(ws_state.devices(&user_id).len(), 1);
+  assert_eq!(
+    user_store.get_user(&user_id).unwrap().status,
+    UserStatus::Online
+  );
 }
 
 /// Test: Complete authentication lifecycle.
@@ -184,7 +216,10 @@ async fn test_complete_auth_lifecycle() {
   let mut ws = connect_ws(addr).await;
 
   // Step 3: Authenticate with token
-  let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+  let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+    token,
+    device: None,
+  });
   send_signaling(&mut ws, &auth_msg).await;
 
   // Step 4: Receive AuthSuccess
@@ -245,6 +280,7 @@ async fn test_tokenauth_recovery() {
     let mut ws = connect_ws(addr).await;
     let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
       token: token.clone(),
+      device: None,
     });
     send_signaling(&mut ws, &auth_msg).await;
 
@@ -260,7 +296,10 @@ async fn test_tokenauth_recovery() {
   // Second connection with same token (simulating page refresh recovery)
   {
     let mut ws = connect_ws(addr).await;
-    let auth_msg = SignalingMessage::TokenAuth(TokenAuth { token });
+    let auth_msg = SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    });
     send_signaling(&mut ws, &auth_msg).await;
 
     let response = recv_signaling(&mut ws).await;
@@ -275,14 +314,6 @@ async fn test_tokenauth_recovery() {
         assert_eq!(username, "testuser5");
         assert_eq!(nickname, "testuser5");
       }
-      Some(SignalingMessage::AuthFailure(AuthFailure { reason })) => {
-        // Token might be invalidated if login was called in between
-        // This is expected behavior for single-device login
-        println!(
-          "Token invalidated (expected for single-device policy): {}",
-          reason
-        );
-      }
       other => panic!("Unexpected response: {:?}", other),
     }
   }
@@ -306,17 +337,26 @@ async fn test_multiple_users_simultaneous() {
   // Authenticate all users
   send_signaling(
     &mut ws1,
-    &SignalingMessage::TokenAuth(TokenAuth { token: token1 }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token1,
+      device: None,
+    }),
   )
   .await;
   send_signaling(
     &mut ws2,
-    &SignalingMessage::TokenAuth(TokenAuth { token: token2 }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token2,
+      device: None,
+    }),
   )
   .await;
   send_signaling(
     &mut ws3,
-    &SignalingMessage::TokenAuth(TokenAuth { token: token3 }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token3,
+      device: None,
+    }),
   )
   .await;
 
@@ -349,7 +389,10 @@ async fn test_user_status_broadcast() {
   let mut ws1 = connect_ws(addr).await;
   send_signaling(
     &mut ws1,
-    &SignalingMessage::TokenAuth(TokenAuth { token: token1 }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token1,
+      device: None,
+    }),
   )
   .await;
   let _ = recv_signaling(&mut ws1).await; // AuthSuccess
@@ -359,7 +402,10 @@ async fn test_user_status_broadcast() {
   let mut ws2 = connect_ws(addr).await;
   send_signaling(
     &mut ws2,
-    &SignalingMessage::TokenAuth(TokenAuth { token: token2 }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token2,
+      device: None,
+    }),
   )
   .await;
   let _ = recv_signaling(&mut ws2).await; // AuthSuccess
@@ -0,0 +1,434 @@
+//! Integration tests for clustered replicas.
+//!
+//! Two `Server` instances joined over an in-process cluster bus, each
+//! with its own storage, with users connected to different replicas:
+//! - Accounts registered and logged in on one replica, used on another
+//! - Presence across replicas
+//! - Signaling routed to a user on another replica
+//! - Rooms replicated between replicas
+//! - Mailbox deposits delivered and fetched on another replica
+
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{WsStream, connect_ws, drain_messages, recv_signaling_filtered, send_signaling};
+use message::signaling::{
+  ConnectionInvite, CreateRoom, DeviceInfo, InviteAccepted, JoinRoom, MailboxAck, MailboxDeposit,
+  MailboxFetch, RoomListUpdate, SdpAnswer, SdpOffer, SignalingMessage, TokenAuth, UserListUpdate,
+};
+use message::types::{RoomType, UserStatus};
+use message::{DeviceId, MessageId, UserId};
+use server::Server;
+use server::cluster::{ClusterBus, InProcessNetwork};
+use server::config::{Config, MailboxConfig};
+use server::storage::MemoryStorage;
+use server::ws::WebSocketState;
+use tokio::net::TcpListener;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// A replica serving its router on a random port.
+struct Replica {
+  addr: SocketAddr,
+  ws_state: Arc<WebSocketState>,
+}
+
+/// Start a replica on fresh storage, joined to the cluster over `bus`.
+async fn start_replica(
+  config: &Config,
+  bus: Arc<dyn ClusterBus>,
+  cancel: &CancellationToken,
+) -> Replica {
+  let (app, ws_state) = Server::with_storage(config.clone(), Arc::new(MemoryStorage::new()))
+    .with_cluster(bus)
+    .build_router();
+  ws_state.spawn_background_tasks(cancel.clone());
+
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    axum::serve(
+      listener,
+      app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+  });
+
+  Replica { addr, ws_state }
+}
+
+/// Two replicas with the mailbox enabled, plus access tokens for the
+/// given usernames, registered on replica A once both are running.
+async fn create_cluster(
+  usernames: &[&str],
+) -> (Replica, Replica, Vec<(UserId, String)>, CancellationToken) {
+  let config = Config {
+    mailbox: Some(MailboxConfig {
+      ttl: Duration::from_secs(60),
+      max_messages_per_user: 10,
+      max_bytes_per_user: 1024,
+      max_messages_per_sender: 10,
+      max_blob_bytes: 64,
+    }),
+    ..Config::default()
+  };
+  let network = InProcessNetwork::new();
+  let cancel = CancellationToken::new();
+  let a = start_replica(&config, Arc::new(network.join("a")), &cancel).await;
+  let b = start_replica(&config, Arc::new(network.join("b")), &cancel).await;
+  sleep(Duration::from_millis(100)).await;
+
+  let users = usernames
+    .iter()
+    .map(|name| a.ws_state.user_store().register(name, "password").unwrap())
+    .collect();
+  sleep(Duration::from_millis(100)).await;
+
+  (a, b, users, cancel)
+}
+
+/// Connect to `addr` and authenticate with `token`.
+async fn login(addr: SocketAddr, token: &str) -> WsStream {
+  let mut ws = connect_ws(addr).await;
+  ws.authenticate(token).await.unwrap();
+  ws
+}
+
+/// Connect to `addr` and authenticate with `token` as `device_id`.
+async fn login_device(addr: SocketAddr, token: &str, device_id: DeviceId) -> WsStream {
+  let mut ws = connect_ws(addr).await;
+  send_signaling(
+    &mut ws,
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: token.to_string(),
+      device: Some(DeviceInfo {
+        device_id,
+        name: "phone".to_string(),
+      }),
+    }),
+  )
+  .await;
+  match recv_signaling(&mut ws).await {
+    Some(SignalingMessage::AuthSuccess(success)) => assert_eq!(success.device_id, device_id),
+    other => panic!("Expected AuthSuccess, got: {other:?}"),
+  }
+  ws
+}
+
+/// Receive the next message other than heartbeats and presence.
+async fn recv_signaling(ws: &mut WsStream) -> Option<SignalingMessage> {
+  recv_signaling_filtered(ws, |msg| {
+    matches!(
+      msg,
+      SignalingMessage::Ping(_)
+        | SignalingMessage::Pong(_)
+        | SignalingMessage::UserListUpdate(_)
+        | SignalingMessage::UserStatusChange(_)
+        | SignalingMessage::ActivePeersList(_)
+        | SignalingMessage::RoomListUpdate(_)
+    )
+  })
+  .await
+}
+
+#[tokio::test]
+async fn test_presence_spans_replicas() {
+  let (a, b, users, cancel) = create_cluster(&["alice", "bob"]).await;
+  let (alice_id, alice_token) = &users[0];
+  let (bob_id, bob_token) = &users[1];
+
+  let mut alice = login(a.addr, alice_token).await;
+  drain_messages(&mut alice, Duration::from_millis(100)).await;
+  let mut bob = login(b.addr, bob_token).await;
+
+  // Alice, on the other replica, is told Bob came online
+  let status = recv_signaling_filtered(&mut alice, |msg| {
+    !matches!(msg, SignalingMessage::UserStatusChange(_))
+  })
+  .await;
+  match status {
+    Some(SignalingMessage::UserStatusChange(change)) => {
+      assert_eq!(&change.user_id, bob_id);
+      assert_eq!(change.status, UserStatus::Online);
+    }
+    other => panic!("Expected UserStatusChange, got: {other:?}"),
+  }
+  assert!(a.ws_state.is_connected(bob_id));
+  assert!(b.ws_state.is_connected(alice_id));
+
+  // Bob's initial user list already includes Alice
+  let list = recv_signaling_filtered(&mut bob, |msg| {
+    !matches!(msg, SignalingMessage::UserListUpdate(_))
+  })
+  .await;
+  match list {
+    Some(SignalingMessage::UserListUpdate(UserListUpdate { users })) => {
+      assert!(users.iter().any(|u| &u.user_id == alice_id));
+      assert!(users.iter().any(|u| &u.user_id == bob_id));
+    }
+    other => panic!("Expected UserListUpdate, got: {other:?}"),
+  }
+
+  // Bob disconnecting takes him offline on Alice's replica too
+  drop(bob);
+  let status = recv_signaling_filtered(&mut alice, |msg| {
+    !matches!(msg, SignalingMessage::UserStatusChange(_))
+  })
+  .await;
+  match status {
+    Some(SignalingMessage::UserStatusChange(change)) => {
+      assert_eq!(&change.user_id, bob_id);
+      assert_eq!(change.status, UserStatus::Offline);
+    }
+    other => panic!("Expected UserStatusChange, got: {other:?}"),
+  }
+  assert!(!a.ws_state.is_connected(bob_id));
+
+  cancel.cancel();
+}
+
+#[tokio::test]
+async fn test_invitation_and_sdp_cross_replicas() {
+  let (a, b, users, cancel) = create_cluster(&["alice", "bob"]).await;
+  let (alice_id, alice_token) = &users[0];
+  let (bob_id, bob_token) = &users[1];
+  let mut alice = login(a.addr, alice_token).await;
+  let mut bob = login(b.addr, bob_token).await;
+  sleep(Duration::from_millis(100)).await;
+  drain_messages(&mut alice, Duration::ZERO).await;
+  drain_messages(&mut bob, Duration::ZERO).await;
+
+  // Alice invites Bob from replica A; Bob accepts on replica B
+  send_signaling(
+    &mut alice,
+    &SignalingMessage::ConnectionInvite(ConnectionInvite {
+      from: alice_id.clone(),
+      to: bob_id.clone(),
+      note: None,
+    }),
+  )
+  .await;
+  assert!(matches!(
+    recv_signaling(&mut bob).await,
+    Some(SignalingMessage::ConnectionInvite(_))
+  ));
+  send_signaling(
+    &mut bob,
+    &SignalingMessage::InviteAccepted(InviteAccepted {
+      from: bob_id.clone(),
+      to: alice_id.clone(),
+    }),
+  )
+  .await;
+  assert!(matches!(
+    recv_signaling(&mut alice).await,
+    Some(SignalingMessage::InviteAccepted(_))
+  ));
+  drain_messages(&mut alice, Duration::from_millis(100)).await;
+  drain_messages(&mut bob, Duration::ZERO).await;
+
+  // Both replicas know the peers, so offer and answer are relayed
+  send_signaling(
+    &mut alice,
+    &SignalingMessage::SdpOffer(SdpOffer {
+      from: alice_id.clone(),
+      to: bob_id.clone(),
+      sdp: "v=0\r\noffer".to_string(),
+      from_device: None,
+      to_device: None,
+    }),
+  )
+  .await;
+  match recv_signaling(&mut bob).await {
+    Some(SignalingMessage::SdpOffer(offer)) => {
+      assert_eq!(&offer.from, alice_id);
+      assert!(offer.sdp.contains("offer"));
+    }
+    other => panic!("Expected SdpOffer, got: {other:?}"),
+  }
+  send_signaling(
+    &mut bob,
+    &SignalingMessage::SdpAnswer(SdpAnswer {
+      from: bob_id.clone(),
+      to: alice_id.clone(),
+      sdp: "v=0\r\nanswer".to_string(),
+      from_device: None,
+      to_device: None,
+    }),
+  )
+  .await;
+  match recv_signaling(&mut alice).await {
+    Some(SignalingMessage::SdpAnswer(answer)) => assert_eq!(&answer.from, bob_id),
+    other => panic!("Expected SdpAnswer, got: {other:?}"),
+  }
+
+  cancel.cancel();
+}
+
+#[tokio::test]
+async fn test_room_created_on_one_replica_joined_on_another() {
+  let (a, b, users, cancel) = create_cluster(&["alice", "bob"]).await;
+  let (alice_id, alice_token) = &users[0];
+  let (bob_id, bob_token) = &users[1];
+  let mut alice = login(a.addr, alice_token).await;
+  let mut bob = login(b.addr, bob_token).await;
+  sleep(Duration::from_millis(100)).await;
+  drain_messages(&mut alice, Duration::ZERO).await;
+  drain_messages(&mut bob, Duration::ZERO).await;
+
+  send_signaling(
+    &mut alice,
+    &SignalingMessage::CreateRoom(CreateRoom {
+      name: "cluster-room".to_string(),
+      description: String::new(),
+      room_type: RoomType::Chat,
+      password: None,
+      max_participants: 8,
+    }),
+  )
+  .await;
+
+  // The room list broadcast reaches Bob on the other replica
+  let list = recv_signaling_filtered(&mut bob, |msg| {
+    !matches!(msg, SignalingMessage::RoomListUpdate(_))
+  })
+  .await;
+  let room_id = match list {
+    Some(SignalingMessage::RoomListUpdate(RoomListUpdate { rooms })) => {
+      assert_eq!(rooms.len(), 1);
+      rooms[0].room_id.clone()
+    }
+    other => panic!("Expected RoomListUpdate, got: {other:?}"),
+  };
+  assert!(b.ws_state.room_state().get_room(&room_id).is_some());
+
+  // Bob joins from replica B; Alice sees the new member list
+  send_signaling(
+    &mut bob,
+    &SignalingMessage::JoinRoom(JoinRoom {
+      room_id: room_id.clone(),
+      password: None,
+    }),
+  )
+  .await;
+  assert!(matches!(
+    recv_signaling_filtered(&mut bob, |msg| !matches!(
+      msg,
+      SignalingMessage::RoomJoined(_)
+    ))
+    .await,
+    Some(SignalingMessage::RoomJoined(_))
+  ));
+  match recv_signaling_filtered(&mut alice, |msg| {
+    !matches!(msg, SignalingMessage::RoomMemberUpdate(_))
+  })
+  .await
+  {
+    Some(SignalingMessage::RoomMemberUpdate(update)) => {
+      assert_eq!(update.members.len(), 2);
+      assert!(update.members.iter().any(|m| &m.user_id == bob_id));
+    }
+    other => panic!("Expected RoomMemberUpdate, got: {other:?}"),
+  }
+
+  // The membership change made on B is replicated back to A
+  sleep(Duration::from_millis(100)).await;
+  assert_eq!(
+    a.ws_state.room_state().get_user_room(bob_id),
+    Some(room_id.clone())
+  );
+  assert_eq!(
+    a.ws_state.room_state().get_user_room(alice_id),
+    Some(room_id)
+  );
+
+  cancel.cancel();
+}
+
+#[tokio::test]
+async fn test_login_and_mailbox_span_replicas() {
+  let (a, b, users, cancel) = create_cluster(&["alice", "bob"]).await;
+  let (alice_id, alice_token) = &users[0];
+  let (bob_id, _) = &users[1];
+  let mut alice = login(a.addr, alice_token).await;
+  drain_messages(&mut alice, Duration::from_millis(100)).await;
+
+  // Alice deposits on A while Bob's phone is offline
+  let phone = DeviceId::new();
+  let first = MessageId::new();
+  send_signaling(
+    &mut alice,
+    &SignalingMessage::MailboxDeposit(MailboxDeposit {
+      to: bob_id.clone(),
+      to_device: Some(phone),
+      message_id: first,
+      blob: vec![0xAA; 16],
+    }),
+  )
+  .await;
+
+  // Bob logs in on A, then authenticates and fetches on B
+  let (_, bob_token) = a.ws_state.user_store().login("bob", "password").unwrap();
+  sleep(Duration::from_millis(100)).await;
+  let mut bob = login_device(b.addr, &bob_token, phone).await;
+  drain_messages(&mut bob, Duration::from_millis(100)).await;
+  send_signaling(&mut bob, &SignalingMessage::MailboxFetch(MailboxFetch)).await;
+  match recv_signaling(&mut bob).await {
+    Some(SignalingMessage::MailboxDrain(drain)) => {
+      assert_eq!(drain.items.len(), 1);
+      assert_eq!(drain.items[0].message_id, first);
+      assert_eq!(&drain.items[0].from, alice_id);
+    }
+    other => panic!("Expected MailboxDrain, got: {other:?}"),
+  }
+
+  // The acknowledgement on B reaches Alice and empties A's copy
+  send_signaling(
+    &mut bob,
+    &SignalingMessage::MailboxAck(MailboxAck {
+      message_ids: vec![first],
+    }),
+  )
+  .await;
+  match recv_signaling(&mut alice).await {
+    Some(SignalingMessage::MailboxDelivered(delivered)) => {
+      assert_eq!(&delivered.recipient, bob_id);
+      assert_eq!(delivered.message_ids, vec![first]);
+    }
+    other => panic!("Expected MailboxDelivered, got: {other:?}"),
+  }
+  sleep(Duration::from_millis(100)).await;
+  assert!(
+    a.ws_state
+      .mailbox()
+      .unwrap()
+      .drain(bob_id, Some(phone))
+      .is_empty()
+  );
+
+  // A deposit on A for Bob, online on B, is pushed to him there
+  let second = MessageId::new();
+  send_signaling(
+    &mut alice,
+    &SignalingMessage::MailboxDeposit(MailboxDeposit {
+      to: bob_id.clone(),
+      to_device: Some(phone),
+      message_id: second,
+      blob: vec![0xBB; 16],
+    }),
+  )
+  .await;
+  match recv_signaling(&mut bob).await {
+    Some(SignalingMessage::MailboxDrain(drain)) => {
+      assert_eq!(drain.items.len(), 1);
+      assert_eq!(drain.items[0].message_id, second);
+    }
+    other => panic!("Expected MailboxDrain, got: {other:?}"),
+  }
+
+  cancel.cancel();
+}
@@ -13,6 +13,7 @@
 //! - Transfer ownership errors (ROM1101)
 //! - Announcement permission errors (ROM1201)
 //! - Nickname errors (ROM1301, ROM1302)
+//! - SFU session errors (SIG401, SIG403)
 
 mod common;
 
@@ -23,7 +24,8 @@ use common::{
 };
 use message::signaling::{
   BanMember, CreateRoom, DemoteAdmin, JoinRoom, KickMember, MuteMember, NicknameChange,
-  PromoteAdmin, RoomAnnouncement, SignalingMessage, TransferOwnership, UnbanMember, UnmuteMember,
+  PromoteAdmin, RoomAnnouncement, SfuOffer, SignalingMessage, TransferOwnership, UnbanMember,
+  UnmuteMember,
 };
 use message::types::{RoomId, RoomType, UserId};
 
@@ -910,3 +912,45 @@ async fn test_error_rom1301_nickname_change_other_user() {
   )
   .await;
 }
+
+// =============================================================================
+// SIG401 / SIG403: SFU media sessions
+// =============================================================================
+
+/// Test: SfuOffer for an unknown room returns SIG401.
+#[tokio::test]
+async fn test_error_sig401_sfu_offer_unknown_room() {
+  let (addr, _ws_state, user_store) = create_test_server().await;
+  let (mut ws, _user_id) = auth_user(addr, &user_store, "sig401_user", "password").await;
+
+  send_signaling(
+    &mut ws,
+    &SignalingMessage::SfuOffer(SfuOffer {
+      room_id: RoomId::new(),
+      sdp: "v=0".to_string(),
+    }),
+  )
+  .await;
+
+  assert_error_code(&mut ws, "SIG401", "SfuOffer for unknown room").await;
+}
+
+/// Test: SfuOffer for a mesh room returns SIG403.
+#[tokio::test]
+async fn test_error_sig403_sfu_offer_mesh_room() {
+  let (addr, _ws_state, user_store) = create_test_server().await;
+  let (mut ws, _user_id) = auth_user(addr, &user_store, "sig403_user", "password").await;
+
+  let room_id = create_room_and_get_id(&mut ws, "Mesh Room", RoomType::Chat).await;
+
+  send_signaling(
+    &mut ws,
+    &SignalingMessage::SfuOffer(SfuOffer {
+      room_id,
+      sdp: "v=0".to_string(),
+    }),
+  )
+  .await;
+
+  assert_error_code(&mut ws, "SIG403", "SfuOffer for mesh room").await;
+}
@@ -12,13 +12,12 @@ use std::time::Duration;
 
 use axum::Router;
 use common::{WsStream, connect_ws, send_signaling};
-use message::signaling::{Pong, SignalingMessage, TokenAuth};
+use message::signaling::{Pong, SignalingMessage};
 use server::auth::UserStore;
 use server::config::Config;
 use server::ws::WebSocketState;
 use tokio::net::TcpListener;
 use tokio::time::{sleep, timeout};
-use tokio_tungstenite::tungstenite::Message;
 
 /// Create a test server with custom heartbeat configuration.
 async fn create_test_server_with_heartbeat(
@@ -57,25 +56,11 @@ async fn create_test_server_with_heartbeat(
 
 /// Receive a signaling message, skipping non-heartbeat messages.
 async fn recv_heartbeat(ws: &mut WsStream) -> Option<SignalingMessage> {
-  timeout(Duration::from_secs(5), async {
-    use futures::StreamExt;
-    loop {
-      match ws.next().await? {
-        Ok(Message::Binary(data)) => {
-          let frame = message::frame::decode_frame(&data).ok()?;
-          let msg: SignalingMessage = bitcode::decode(&frame.payload).ok()?;
-          if matches!(msg, SignalingMessage::Ping(_) | SignalingMessage::Pong(_)) {
-            return Some(msg);
-          }
-          // Skip non-heartbeat messages
-        }
-        Ok(Message::Ping(_) | Message::Pong(_)) => continue,
-        _ => return None,
-      }
-    }
+  ws.recv_matching(Duration::from_secs(5), |msg| {
+    matches!(msg, SignalingMessage::Ping(_) | SignalingMessage::Pong(_))
   })
   .await
-  .ok()?
+  .ok()
 }
 
 /// Helper to register, connect, and authenticate a user.
@@ -87,27 +72,8 @@ async fn auth_user_quick(
   let (user_id, token) = user_store.register(username, "password").unwrap();
   let mut ws = connect_ws(addr).await;
 
-  send_signaling(&mut ws, &SignalingMessage::TokenAuth(TokenAuth { token })).await;
-
   // Wait for AuthSuccess
-  use futures::StreamExt;
-  timeout(Duration::from_secs(2), async {
-    loop {
-      match ws.next().await? {
-        Ok(Message::Binary(data)) => {
-          let frame = message::frame::decode_frame(&data).ok()?;
-          let msg: SignalingMessage = bitcode::decode(&frame.payload).ok()?;
-          if matches!(msg, SignalingMessage::AuthSuccess(_)) {
-            return Some(());
-          }
-        }
-        _ => continue,
-      }
-    }
-  })
-  .await
-  .ok()
-  .flatten();
+  let _ = timeout(Duration::from_secs(2), ws.authenticate(&token)).await;
 
   // Skip initial messages
   sleep(Duration::from_millis(100)).await;
@@ -277,3 +243,26 @@ async fn test_connection_remains_open_with_pong_response() {
     "User should remain connected when responding to heartbeats"
   );
 }
+
+#[tokio::test]
+async fn test_client_auto_pong_keeps_connection_alive() {
+  let (addr, ws_state, user_store) = create_test_server_with_heartbeat(1, 2).await;
+  let (mut ws, user_id) = auth_user_quick(addr, &user_store, "test_user").await;
+  ws.set_auto_pong(true);
+
+  // Reading drives the automatic Pong replies; no Ping reaches the caller.
+  let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+  while tokio::time::Instant::now() < deadline {
+    if let Ok(Some(msg)) = ws.recv_timeout(Duration::from_millis(200)).await {
+      assert!(
+        !matches!(msg, SignalingMessage::Ping(_)),
+        "Auto-pong should swallow heartbeat Pings"
+      );
+    }
+  }
+
+  assert!(
+    ws_state.is_connected(&user_id),
+    "Client with auto-pong should survive past the heartbeat timeout"
+  );
+}
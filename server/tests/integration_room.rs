@@ -14,8 +14,6 @@ use std::time::Duration;
 use common::{
   WsStream, auth_user, create_test_server, drain_messages, recv_signaling_filtered, send_signaling,
 };
-use futures::StreamExt;
-use message::frame::decode_frame;
 use message::signaling::{
   BanMember, CreateRoom, DemoteAdmin, JoinRoom, KickMember, LeaveRoom, ModerationAction,
   MuteMember, NicknameChange, OwnerChanged, PromoteAdmin, RoomAnnouncement, RoomListUpdate,
@@ -24,7 +22,6 @@ use message::signaling::{
 };
 use message::types::{RoomRole, RoomType};
 use tokio::time::{sleep, timeout};
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
 
 /// Check whether a signaling message is a "noise" message that should be skipped
 /// during test message retrieval (heartbeats, presence broadcasts, join responses, etc.).
@@ -587,29 +584,24 @@ async fn test_automatic_ownership_transfer() {
   // Helper: collect OwnerChanged and RoomMemberUpdate from a member's stream.
   // The server sends both messages; order is not guaranteed, so we collect both.
   async fn collect_transfer_messages(
-    ws: &mut WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    ws: &mut WsStream,
   ) -> (Option<OwnerChanged>, Option<RoomMemberUpdate>) {
     let mut owner_changed: Option<OwnerChanged> = None;
     let mut member_update: Option<RoomMemberUpdate> = None;
 
     // We expect exactly 2 relevant messages; use a generous timeout.
     for _ in 0..10 {
-      let msg = timeout(Duration::from_secs(5), ws.next()).await;
-      match msg {
-        Ok(Some(Ok(Message::Binary(data)))) => {
-          if let Ok(frame) = decode_frame(&data)
-            && let Ok(sm) = bitcode::decode::<SignalingMessage>(&frame.payload)
-          {
-            match sm {
-              SignalingMessage::OwnerChanged(oc) => {
-                owner_changed = Some(oc);
-              }
-              SignalingMessage::RoomMemberUpdate(mu) => {
-                member_update = Some(mu);
-              }
-              // Skip noise (ping, pong, list updates, etc.)
-              _ => continue,
+      match ws.recv_timeout(Duration::from_secs(5)).await {
+        Ok(Some(sm)) => {
+          match sm {
+            SignalingMessage::OwnerChanged(oc) => {
+              owner_changed = Some(oc);
             }
+            SignalingMessage::RoomMemberUpdate(mu) => {
+              member_update = Some(mu);
+            }
+            // Skip noise (ping, pong, list updates, etc.)
+            _ => continue,
           }
           if owner_changed.is_some() && member_update.is_some() {
             break;
@@ -1289,30 +1281,21 @@ async fn test_unmute_member() {
 
   let result = timeout(Duration::from_secs(10), async {
     loop {
-      match ws_target.next().await {
-        Some(Ok(Message::Binary(data))) => {
-          if let Ok(frame) = decode_frame(&data)
-            && let Ok(msg) = bitcode::decode::<SignalingMessage>(&frame.payload)
-          {
-            match &msg {
-              SignalingMessage::ModerationNotification(notif) => {
-                assert_eq!(notif.room_id, room_id);
-                assert_eq!(notif.target, target_id);
-                match notif.action {
-                  ModerationAction::Muted => found_muted = true,
-                  ModerationAction::Unmuted => found_unmuted = true,
-                  _ => {}
-                }
-                if found_muted && found_unmuted {
-                  return (found_muted, found_unmuted);
-                }
-              }
-              _ => continue, // Skip all other messages
-            }
+      match ws_target.recv().await {
+        Ok(SignalingMessage::ModerationNotification(notif)) => {
+          assert_eq!(notif.room_id, room_id);
+          assert_eq!(notif.target, target_id);
+          match notif.action {
+            ModerationAction::Muted => found_muted = true,
+            ModerationAction::Unmuted => found_unmuted = true,
+            _ => {}
+          }
+          if found_muted && found_unmuted {
+            return (found_muted, found_unmuted);
           }
         }
-        Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
-        _ => break,
+        Ok(_) => continue, // Skip all other messages
+        Err(_) => break,
       }
     }
     (found_muted, found_unmuted)
@@ -119,7 +119,14 @@ async fn auth_user(
   let (user_id, token) = user_store.register(username, password).unwrap();
   let mut ws = connect_ws(addr).await;
 
-  send_signaling(&mut ws, &SignalingMessage::TokenAuth(TokenAuth { token })).await;
+  send_signaling(
+    &mut ws,
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    }),
+  )
+  .await;
 
   // Wait for AuthSuccess
   let response = recv_signaling(&mut ws).await;
@@ -178,6 +185,8 @@ async fn test_sdp_offer_forwarding() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 123456 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer.clone())).await;
 
@@ -208,6 +217,8 @@ async fn test_sdp_answer_forwarding() {
     from: user2.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- 789012 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(answer.clone())).await;
 
@@ -380,7 +391,10 @@ async fn test_active_peers_list_recovery() {
   // Send TokenAuth
   send_signaling(
     &mut ws_new,
-    &SignalingMessage::TokenAuth(TokenAuth { token: new_token }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token: new_token,
+      device: None,
+    }),
   )
   .await;
 
@@ -565,6 +579,8 @@ async fn test_complete_sdp_negotiation() {
     from: offerer_id.clone(),
     to: answerer_id.clone(),
     sdp: "v=0\r\no=- 111111 2 IN IP4 127.0.0.1\r\ns=Test Session\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws_offerer, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -580,6 +596,8 @@ async fn test_complete_sdp_negotiation() {
     from: answerer_id.clone(),
     to: offerer_id.clone(),
     sdp: "v=0\r\no=- 222222 2 IN IP4 127.0.0.1\r\ns=Test Session\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws_answerer, &SignalingMessage::SdpAnswer(answer)).await;
 
@@ -643,6 +661,8 @@ async fn test_concurrent_sdp_negotiations() {
     from: init_id.clone(),
     to: t1_id,
     sdp: "v=0\r\no=- 111 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws_init, &SignalingMessage::SdpOffer(offer1)).await;
 
@@ -650,6 +670,8 @@ async fn test_concurrent_sdp_negotiations() {
     from: init_id,
     to: t2_id,
     sdp: "v=0\r\no=- 222 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws_init, &SignalingMessage::SdpOffer(offer2)).await;
 
@@ -694,6 +716,8 @@ async fn test_peer_tracking_on_disconnect() {
     from: user1,
     to: user2,
     sdp: "test".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(test_msg)).await;
 
@@ -762,6 +786,8 @@ async fn test_ice_before_answer() {
     from: user1,
     to: user2,
     sdp: "v=0\r\no=- 333 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -790,6 +816,8 @@ async fn test_sdp_renegotiation() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 100 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer1)).await;
   let _ = recv_signaling(&mut ws2).await;
@@ -799,6 +827,8 @@ async fn test_sdp_renegotiation() {
     from: user2.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- 200 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(answer1)).await;
   let _ = recv_signaling(&mut ws1).await;
@@ -808,6 +838,8 @@ async fn test_sdp_renegotiation() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 101 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer2)).await;
 
@@ -966,7 +998,10 @@ async fn test_active_peers_list_updated_after_disconnect() {
   let mut ws1_new = connect_ws(addr).await;
   send_signaling(
     &mut ws1_new,
-    &SignalingMessage::TokenAuth(TokenAuth { token }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    }),
   )
   .await;
 
@@ -1001,7 +1036,14 @@ async fn test_connection_lifecycle() {
   // Phase 2: Authentication
   let (user_id, token) = user_store.register("lifecycle_user", "password").unwrap();
   let mut ws = ws; // Reuse connection
-  send_signaling(&mut ws, &SignalingMessage::TokenAuth(TokenAuth { token })).await;
+  send_signaling(
+    &mut ws,
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    }),
+  )
+  .await;
 
   let auth_response = recv_signaling(&mut ws).await;
   assert!(matches!(
@@ -1023,6 +1065,8 @@ async fn test_connection_lifecycle() {
     from: user_id.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- lifecycle 1 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws, &SignalingMessage::SdpOffer(offer)).await;
   let received = recv_signaling(&mut ws2).await;
@@ -1062,6 +1106,8 @@ async fn test_sdp_renegotiation_media_change() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 1000 2 IN IP4 127.0.0.1\r\nm=video 54321 RTP/AVP 96\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer1)).await;
   let _ = recv_signaling(&mut ws2).await;
@@ -1070,6 +1116,8 @@ async fn test_sdp_renegotiation_media_change() {
     from: user2.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- 2000 2 IN IP4 127.0.0.1\r\nm=video 54321 RTP/AVP 96\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(answer1)).await;
   let _ = recv_signaling(&mut ws1).await;
@@ -1090,6 +1138,8 @@ async fn test_sdp_renegotiation_media_change() {
     sdp:
       "v=0\r\no=- 1001 2 IN IP4 127.0.0.1\r\nm=audio 5000 RTP/AVP 0\r\nm=video 54321 RTP/AVP 96\r\n"
         .to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer2)).await;
 
@@ -1112,6 +1162,8 @@ async fn test_sdp_renegotiation_media_change() {
     sdp:
       "v=0\r\no=- 2001 2 IN IP4 127.0.0.1\r\nm=audio 5000 RTP/AVP 0\r\nm=video 54321 RTP/AVP 96\r\n"
         .to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(answer2)).await;
 
@@ -1138,6 +1190,8 @@ async fn test_sdp_renegotiation_ice_restart() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 3000 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer1)).await;
   let _ = recv_signaling(&mut ws2).await;
@@ -1146,6 +1200,8 @@ async fn test_sdp_renegotiation_ice_restart() {
     from: user2.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- 4000 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(answer1)).await;
   let _ = recv_signaling(&mut ws1).await;
@@ -1155,6 +1211,8 @@ async fn test_sdp_renegotiation_ice_restart() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 3001 2 IN IP4 127.0.0.1\r\na=ice-ufrag:newufrag\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer2)).await;
 
@@ -1206,6 +1264,8 @@ async fn test_ice_candidate_trickle_timing() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 5000 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
   let _ = recv_signaling(&mut ws2).await;
@@ -1315,6 +1375,8 @@ async fn test_end_of_candidates_indication() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 6000 2 IN IP4 127.0.0.1\r\na=end-of-candidates\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -1354,6 +1416,8 @@ async fn test_peer_reconnection_after_network_issue() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 7000 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer1)).await;
   let _ = recv_signaling(&mut ws2).await;
@@ -1370,7 +1434,10 @@ async fn test_peer_reconnection_after_network_issue() {
   let mut ws1_new = connect_ws(addr).await;
   send_signaling(
     &mut ws1_new,
-    &SignalingMessage::TokenAuth(TokenAuth { token }),
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    }),
   )
   .await;
 
@@ -1390,6 +1457,8 @@ async fn test_peer_reconnection_after_network_issue() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- 7001 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1_new, &SignalingMessage::SdpOffer(offer2)).await;
 
@@ -1447,6 +1516,7 @@ async fn test_session_persistence_on_reconnect() {
     &mut ws1,
     &SignalingMessage::TokenAuth(TokenAuth {
       token: token.clone(),
+      device: None,
     }),
   )
   .await;
@@ -1461,7 +1531,14 @@ async fn test_session_persistence_on_reconnect() {
 
   // Reconnect with same token
   let mut ws2 = connect_ws(addr).await;
-  send_signaling(&mut ws2, &SignalingMessage::TokenAuth(TokenAuth { token })).await;
+  send_signaling(
+    &mut ws2,
+    &SignalingMessage::TokenAuth(TokenAuth {
+      token,
+      device: None,
+    }),
+  )
+  .await;
 
   let auth_response = recv_signaling(&mut ws2).await;
   match auth_response {
@@ -1505,6 +1582,8 @@ async fn test_large_sdp_message_handling() {
     from: user1.clone(),
     to: user2.clone(),
     sdp,
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -1594,6 +1673,8 @@ async fn test_concurrent_multi_user_negotiations() {
         "v=0\r\no=- {} 2 IN IP4 127.0.0.1\r\ns=Session with {}\r\n",
         target_name, target_name
       ),
+      from_device: None,
+      to_device: None,
     };
     send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -1631,6 +1712,8 @@ async fn test_ring_topology_negotiation() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- ring12 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer12)).await;
   assert!(matches!(
@@ -1642,6 +1725,8 @@ async fn test_ring_topology_negotiation() {
     from: user2.clone(),
     to: user3.clone(),
     sdp: "v=0\r\no=- ring23 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpOffer(offer23)).await;
   assert!(matches!(
@@ -1653,6 +1738,8 @@ async fn test_ring_topology_negotiation() {
     from: user3.clone(),
     to: user4.clone(),
     sdp: "v=0\r\no=- ring34 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws3, &SignalingMessage::SdpOffer(offer34)).await;
   assert!(matches!(
@@ -1664,6 +1751,8 @@ async fn test_ring_topology_negotiation() {
     from: user4.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- ring41 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws4, &SignalingMessage::SdpOffer(offer41)).await;
   assert!(matches!(
@@ -1699,6 +1788,8 @@ async fn test_broadcast_to_all_peers() {
       from: broadcaster_id.clone(),
       to: target_id.clone(),
       sdp: broadcast_sdp.to_string(),
+      from_device: None,
+      to_device: None,
     };
     send_signaling(&mut ws_broadcaster, &SignalingMessage::SdpOffer(offer)).await;
   }
@@ -1742,6 +1833,8 @@ async fn test_malformed_sdp_recovery() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "not a valid SDP".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(malformed_offer)).await;
 
@@ -1754,6 +1847,8 @@ async fn test_malformed_sdp_recovery() {
     from: user2.clone(),
     to: user1.clone(),
     sdp: "v=0\r\no=- valid 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws2, &SignalingMessage::SdpAnswer(valid_answer)).await;
 
@@ -1785,6 +1880,8 @@ async fn test_sdp_to_disconnected_user() {
     from: user1.clone(),
     to: user2.clone(),
     sdp: "v=0\r\no=- late 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(offer)).await;
 
@@ -1801,6 +1898,8 @@ async fn test_sdp_to_disconnected_user() {
     from: user1.clone(),
     to: user3.clone(),
     sdp: "v=0\r\no=- new 2 IN IP4 127.0.0.1\r\n".to_string(),
+    from_device: None,
+    to_device: None,
   };
   send_signaling(&mut ws1, &SignalingMessage::SdpOffer(new_offer)).await;
 